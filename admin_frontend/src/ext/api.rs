@@ -1,5 +1,7 @@
 use database_entity::dto::{AFRole, AFWorkspace, AFWorkspaceInvitation};
+use shared_entity::dto::admin_dto::{AdminBulkUserParams, AdminBulkUserResult};
 use shared_entity::dto::{auth_dto::SignInTokenResponse, workspace_dto::WorkspaceMemberInvitation};
+use uuid::Uuid;
 
 use super::{
   check_response,
@@ -295,3 +297,41 @@ pub async fn delete_current_user(
   check_response(resp).await?;
   Ok(())
 }
+
+/// `action` is one of `deactivate`, `delete` or `password-reset`.
+pub async fn admin_bulk_user_action(
+  access_token: &str,
+  action: &str,
+  user_uuids: Vec<Uuid>,
+  appflowy_cloud_base_url: &str,
+) -> Result<AdminBulkUserResult, Error> {
+  let http_client = reqwest::Client::new();
+  let url = format!("{}/api/admin/users/{}", appflowy_cloud_base_url, action);
+  let resp = http_client
+    .post(url)
+    .header("Authorization", format!("Bearer {}", access_token))
+    .json(&AdminBulkUserParams { user_uuids })
+    .send()
+    .await?;
+  from_json_response(resp).await
+}
+
+pub async fn admin_export_users_csv(
+  access_token: &str,
+  appflowy_cloud_base_url: &str,
+) -> Result<String, Error> {
+  let http_client = reqwest::Client::new();
+  let url = format!("{}/api/admin/users/export", appflowy_cloud_base_url);
+  let resp = http_client
+    .get(url)
+    .header("Authorization", format!("Bearer {}", access_token))
+    .send()
+    .await?;
+
+  let status = resp.status();
+  let payload = resp.text().await?;
+  if !status.is_success() {
+    return Err(Error::NotOk(status.as_u16(), payload));
+  }
+  Ok(payload)
+}
@@ -41,6 +41,12 @@ pub struct WebApiAdminCreateUserRequest {
   pub require_email_verification: bool,
 }
 
+/// The uuids of the selected users, separated by commas.
+#[derive(Deserialize)]
+pub struct WebApiAdminBulkUserRequest {
+  pub user_uuids: String,
+}
+
 #[derive(Deserialize)]
 pub struct WebApiInviteUserRequest {
   pub email: String,
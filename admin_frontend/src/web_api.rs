@@ -1,13 +1,13 @@
 use crate::error::WebApiError;
 use crate::ext::api::{
-  accept_workspace_invitation, delete_current_user, invite_user_to_workspace, leave_workspace,
-  verify_token_cloud,
+  accept_workspace_invitation, admin_bulk_user_action, admin_export_users_csv, delete_current_user,
+  invite_user_to_workspace, leave_workspace, verify_token_cloud,
 };
 use crate::models::{AppState, WebApiLoginRequest};
 use crate::models::{
-  LoginParams, OAuthRedirect, OAuthRedirectToken, WebApiAdminCreateUserRequest,
-  WebApiChangePasswordRequest, WebApiCreateSSOProviderRequest, WebApiInviteUserRequest,
-  WebApiPutUserRequest,
+  LoginParams, OAuthRedirect, OAuthRedirectToken, WebApiAdminBulkUserRequest,
+  WebApiAdminCreateUserRequest, WebApiChangePasswordRequest, WebApiCreateSSOProviderRequest,
+  WebApiInviteUserRequest, WebApiPutUserRequest,
 };
 use crate::response::WebApiResponse;
 use crate::session::{self, new_session_cookie, CodeSession, UserSession};
@@ -60,6 +60,11 @@ pub fn router() -> Router<AppState> {
       "/admin/user/:email/generate-link",
       post(post_user_generate_link_handler),
     )
+    .route(
+      "/admin/users/bulk/:action",
+      post(admin_bulk_user_handler),
+    )
+    .route("/admin/users/export", get(admin_export_users_handler))
     .route("/admin/sso", post(admin_create_sso_handler))
     .route("/admin/sso/:provider_id", delete(admin_delete_sso_handler))
 }
@@ -303,6 +308,66 @@ async fn admin_delete_user_handler(
   Ok(().into())
 }
 
+async fn admin_bulk_user_handler(
+  State(state): State<AppState>,
+  session: UserSession,
+  Path(action): Path<String>,
+  Form(param): Form<WebApiAdminBulkUserRequest>,
+) -> Result<WebApiResponse<()>, WebApiError<'static>> {
+  let done = match action.as_str() {
+    "deactivate" => "deactivated",
+    "delete" => "deleted",
+    "password-reset" => "sent a password reset email",
+    _ => {
+      return Err(WebApiError::new(
+        StatusCode::NOT_FOUND,
+        format!("unknown bulk action: {}", action),
+      ))
+    },
+  };
+  let user_uuids = param
+    .user_uuids
+    .split(',')
+    .map(str::trim)
+    .filter(|user_uuid| !user_uuid.is_empty())
+    .map(uuid::Uuid::parse_str)
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|err| WebApiError::new(StatusCode::BAD_REQUEST, err.to_string()))?;
+
+  let result = admin_bulk_user_action(
+    &session.token.access_token,
+    &action,
+    user_uuids,
+    &state.appflowy_cloud_url,
+  )
+  .await?;
+
+  let mut message = format!("{} user(s) {}", result.succeeded.len(), done);
+  // The message is displayed as html
+  for failure in result.failed {
+    let error = failure.error.replace('&', "&amp;").replace('<', "&lt;");
+    message.push_str(&format!("<br>{}: {}", failure.user_uuid, error));
+  }
+  Ok(WebApiResponse::<()>::from_str(message.into()))
+}
+
+async fn admin_export_users_handler(
+  State(state): State<AppState>,
+  session: UserSession,
+) -> Result<impl IntoResponse, WebApiError<'static>> {
+  let csv = admin_export_users_csv(&session.token.access_token, &state.appflowy_cloud_url).await?;
+  Ok((
+    [
+      (axum::http::header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+      (
+        axum::http::header::CONTENT_DISPOSITION,
+        "attachment; filename=\"users.csv\"",
+      ),
+    ],
+    csv,
+  ))
+}
+
 async fn admin_add_user_handler(
   State(state): State<AppState>,
   session: UserSession,
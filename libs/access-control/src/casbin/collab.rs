@@ -2,11 +2,15 @@ use app_error::AppError;
 use async_trait::async_trait;
 use database_entity::dto::AFAccessLevel;
 use tracing::instrument;
+use uuid::Uuid;
 
 use crate::{
   act::Action,
   collab::{CollabAccessControl, RealtimeAccessControl},
+  collab_guest::CollabGuestCache,
   entity::ObjectType,
+  restricted_view::RestrictedViewCache,
+  workspace_read_only::WorkspaceReadOnlyCache,
 };
 
 use super::access::AccessControl;
@@ -14,11 +18,53 @@ use super::access::AccessControl;
 #[derive(Clone)]
 pub struct CollabAccessControlImpl {
   access_control: AccessControl,
+  collab_guest: CollabGuestCache,
+  restricted_view: RestrictedViewCache,
 }
 
 impl CollabAccessControlImpl {
-  pub fn new(access_control: AccessControl) -> Self {
-    Self { access_control }
+  pub fn new(
+    access_control: AccessControl,
+    collab_guest: CollabGuestCache,
+    restricted_view: RestrictedViewCache,
+  ) -> Self {
+    Self {
+      access_control,
+      collab_guest,
+      restricted_view,
+    }
+  }
+
+  /// The role of a member gives access to the whole workspace, except to the collabs hidden by
+  /// a restricted view.
+  async fn enforce_member_access(
+    &self,
+    workspace_id: &str,
+    uid: &i64,
+    oid: &str,
+  ) -> Result<(), AppError> {
+    if can_access_restricted(&self.restricted_view, workspace_id, uid, oid).await? {
+      Ok(())
+    } else {
+      Err(AppError::NotEnoughPermissions)
+    }
+  }
+}
+
+/// Returns false if the collab is hidden from the member by a restricted view.
+async fn can_access_restricted(
+  restricted_view: &RestrictedViewCache,
+  workspace_id: &str,
+  uid: &i64,
+  oid: &str,
+) -> Result<bool, AppError> {
+  match Uuid::parse_str(workspace_id) {
+    Ok(workspace_id) => {
+      restricted_view
+        .can_access_object(*uid, &workspace_id, oid)
+        .await
+    },
+    Err(_) => Ok(true),
   }
 }
 
@@ -28,11 +74,9 @@ impl CollabAccessControl for CollabAccessControlImpl {
     &self,
     workspace_id: &str,
     uid: &i64,
-    _oid: &str,
+    oid: &str,
     action: Action,
   ) -> Result<(), AppError> {
-    // TODO: allow non workspace member to read a collab.
-
     // Anyone who can write to a workspace, can also delete a collab.
     let workspace_action = match action {
       Action::Read => Action::Read,
@@ -49,8 +93,16 @@ impl CollabAccessControl for CollabAccessControlImpl {
       )
       .await;
     match result {
-      Ok(true) => Ok(()),
-      Ok(false) => Err(AppError::NotEnoughPermissions),
+      Ok(true) => self.enforce_member_access(workspace_id, uid, oid).await,
+      Ok(false) => {
+        // The collab may have been shared with a user who is not a member of the workspace.
+        let guest_access = self.collab_guest.get_by_str(*uid, workspace_id).await?;
+        if guest_access.can_perform_action(oid, &action) {
+          Ok(())
+        } else {
+          Err(AppError::NotEnoughPermissions)
+        }
+      },
       Err(e) => Err(e),
     }
   }
@@ -59,11 +111,9 @@ impl CollabAccessControl for CollabAccessControlImpl {
     &self,
     workspace_id: &str,
     uid: &i64,
-    _oid: &str,
+    oid: &str,
     access_level: AFAccessLevel,
   ) -> Result<(), AppError> {
-    // TODO: allow non workspace member to read a collab.
-
     // Anyone who can write to a workspace, also have full access to a collab.
     let workspace_action = match access_level {
       AFAccessLevel::ReadOnly => Action::Read,
@@ -81,8 +131,15 @@ impl CollabAccessControl for CollabAccessControlImpl {
       )
       .await;
     match result {
-      Ok(true) => Ok(()),
-      Ok(false) => Err(AppError::NotEnoughPermissions),
+      Ok(true) => self.enforce_member_access(workspace_id, uid, oid).await,
+      Ok(false) => {
+        let guest_access = self.collab_guest.get_by_str(*uid, workspace_id).await?;
+        if guest_access.has_access_level(oid, access_level) {
+          Ok(())
+        } else {
+          Err(AppError::NotEnoughPermissions)
+        }
+      },
       Err(e) => Err(e),
     }
   }
@@ -108,22 +165,32 @@ impl CollabAccessControl for CollabAccessControlImpl {
 #[derive(Clone)]
 pub struct RealtimeCollabAccessControlImpl {
   access_control: AccessControl,
+  workspace_read_only: WorkspaceReadOnlyCache,
+  collab_guest: CollabGuestCache,
+  restricted_view: RestrictedViewCache,
 }
 
 impl RealtimeCollabAccessControlImpl {
-  pub fn new(access_control: AccessControl) -> Self {
-    Self { access_control }
+  pub fn new(
+    access_control: AccessControl,
+    workspace_read_only: WorkspaceReadOnlyCache,
+    collab_guest: CollabGuestCache,
+    restricted_view: RestrictedViewCache,
+  ) -> Self {
+    Self {
+      access_control,
+      workspace_read_only,
+      collab_guest,
+      restricted_view,
+    }
   }
 
-  async fn can_perform_action(
+  async fn can_perform_workspace_action(
     &self,
     workspace_id: &str,
     uid: &i64,
-    _oid: &str,
     required_action: Action,
   ) -> Result<bool, AppError> {
-    // TODO: allow non workspace member to read a collab.
-
     // Anyone who can write to a workspace, can also delete a collab.
     let workspace_action = match required_action {
       Action::Read => Action::Read,
@@ -140,6 +207,24 @@ impl RealtimeCollabAccessControlImpl {
       )
       .await
   }
+
+  async fn can_perform_action(
+    &self,
+    workspace_id: &str,
+    uid: &i64,
+    oid: &str,
+    required_action: Action,
+  ) -> Result<bool, AppError> {
+    if self
+      .can_perform_workspace_action(workspace_id, uid, required_action.clone())
+      .await?
+    {
+      return can_access_restricted(&self.restricted_view, workspace_id, uid, oid).await;
+    }
+    // The collab may have been shared with a user who is not a member of the workspace.
+    let guest_access = self.collab_guest.get_by_str(*uid, workspace_id).await?;
+    Ok(guest_access.can_perform_action(oid, &required_action))
+  }
 }
 
 #[async_trait]
@@ -150,6 +235,11 @@ impl RealtimeAccessControl for RealtimeCollabAccessControlImpl {
     uid: &i64,
     oid: &str,
   ) -> Result<bool, AppError> {
+    // Nobody can write to a read-only workspace, regardless of the permission. The locks are
+    // checked by [crate::collab_lock::CollabLockRealtimeAccessControl].
+    if self.is_workspace_read_only(workspace_id).await? {
+      return Ok(false);
+    }
     self
       .can_perform_action(workspace_id, uid, oid, Action::Write)
       .await
@@ -165,17 +255,58 @@ impl RealtimeAccessControl for RealtimeCollabAccessControlImpl {
       .can_perform_action(workspace_id, uid, oid, Action::Read)
       .await
   }
+
+  async fn can_write_collabs(
+    &self,
+    workspace_id: &str,
+    uid: &i64,
+    oids: &[String],
+  ) -> Result<Vec<bool>, AppError> {
+    // The permission of the members is checked at the workspace level, so a single check covers
+    // all the collabs. Only the access of the guests, and the restricted views, are checked per
+    // collab.
+    if self.is_workspace_read_only(workspace_id).await? {
+      return Ok(vec![false; oids.len()]);
+    }
+    let is_member_writer = self
+      .can_perform_workspace_action(workspace_id, uid, Action::Write)
+      .await?;
+    let guest_access = if is_member_writer {
+      None
+    } else {
+      Some(self.collab_guest.get_by_str(*uid, workspace_id).await?)
+    };
+    let mut result = Vec::with_capacity(oids.len());
+    for oid in oids {
+      let can_write = match &guest_access {
+        None => can_access_restricted(&self.restricted_view, workspace_id, uid, oid).await?,
+        Some(guest_access) => guest_access.can_perform_action(oid, &Action::Write),
+      };
+      result.push(can_write);
+    }
+    Ok(result)
+  }
+
+  async fn is_workspace_read_only(&self, workspace_id: &str) -> Result<bool, AppError> {
+    match Uuid::parse_str(workspace_id) {
+      Ok(workspace_id) => self.workspace_read_only.is_read_only(&workspace_id).await,
+      Err(_) => Ok(false),
+    }
+  }
 }
 
 #[cfg(test)]
 mod tests {
   use database_entity::dto::AFRole;
+  use sqlx::PgPool;
 
   use crate::{
     act::Action,
     casbin::{access::AccessControl, enforcer::tests::test_enforcer},
     collab::CollabAccessControl,
+    collab_guest::CollabGuestCache,
     entity::{ObjectType, SubjectType},
+    restricted_view::RestrictedViewCache,
   };
 
   #[tokio::test]
@@ -193,7 +324,13 @@ mod tests {
       .await
       .unwrap();
     let access_control = AccessControl::with_enforcer(enforcer);
-    let collab_access_control = super::CollabAccessControlImpl::new(access_control);
+    // The guest access is only read for the users who are not members of the workspace.
+    let collab_guest = CollabGuestCache::new(PgPool::connect_lazy("postgres://localhost").unwrap());
+    // The restricted views are not read for a workspace id that is not a uuid.
+    let restricted_view =
+      RestrictedViewCache::new(PgPool::connect_lazy("postgres://localhost").unwrap());
+    let collab_access_control =
+      super::CollabAccessControlImpl::new(access_control, collab_guest, restricted_view);
     for action in [Action::Read, Action::Write, Action::Delete] {
       collab_access_control
         .enforce_action(workspace_id, &uid, oid, action.clone())
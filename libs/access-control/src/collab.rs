@@ -62,4 +62,25 @@ pub trait RealtimeAccessControl: Sync + Send + 'static {
     uid: &i64,
     oid: &str,
   ) -> Result<bool, AppError>;
+
+  /// Same as [RealtimeAccessControl::can_write_collab], but for multiple collabs of the same
+  /// workspace. The result contains one entry per given oid, in the same order.
+  async fn can_write_collabs(
+    &self,
+    workspace_id: &str,
+    uid: &i64,
+    oids: &[String],
+  ) -> Result<Vec<bool>, AppError> {
+    let mut result = Vec::with_capacity(oids.len());
+    for oid in oids {
+      result.push(self.can_write_collab(workspace_id, uid, oid).await?);
+    }
+    Ok(result)
+  }
+
+  /// Return true if the workspace is over the limits of its plan. The collabs of a read-only
+  /// workspace can be read but not written, whatever the permission of the user.
+  async fn is_workspace_read_only(&self, _workspace_id: &str) -> Result<bool, AppError> {
+    Ok(false)
+  }
 }
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use app_error::AppError;
+use dashmap::DashMap;
+use database::collab_guest::{
+  delete_collab_guest, select_collab_guest_access_levels, upsert_collab_guest,
+};
+use database_entity::dto::AFAccessLevel;
+use sqlx::PgPool;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::act::Action;
+use crate::PG_STATE_CACHE_TTL;
+
+/// The collabs of a workspace shared with a user who is not a member of it, with the access level
+/// of the user on each of them.
+#[derive(Debug, Default)]
+pub struct GuestAccess {
+  access_levels: HashMap<String, AFAccessLevel>,
+}
+
+impl GuestAccess {
+  pub fn new(access_levels: Vec<(String, AFAccessLevel)>) -> Self {
+    Self {
+      access_levels: access_levels.into_iter().collect(),
+    }
+  }
+
+  /// Returns true if no collab of the workspace is shared with the user.
+  pub fn is_empty(&self) -> bool {
+    self.access_levels.is_empty()
+  }
+
+  pub fn access_level(&self, oid: &str) -> Option<AFAccessLevel> {
+    self.access_levels.get(oid).copied()
+  }
+
+  pub fn object_ids(&self) -> impl Iterator<Item = &String> {
+    self.access_levels.keys()
+  }
+
+  pub fn can_perform_action(&self, oid: &str, action: &Action) -> bool {
+    match (self.access_level(oid), action) {
+      (None, _) => false,
+      (Some(_), Action::Read) => true,
+      (Some(level), Action::Write) => level.can_write(),
+      (Some(level), Action::Delete) => level.can_delete(),
+    }
+  }
+
+  pub fn has_access_level(&self, oid: &str, required: AFAccessLevel) -> bool {
+    self
+      .access_level(oid)
+      .is_some_and(|level| level as i32 >= required as i32)
+  }
+}
+
+/// Caches the collabs shared with the guests of the workspaces. Only consulted when the workspace
+/// role of a user doesn't allow an action, so the members of a workspace never read it.
+#[derive(Clone)]
+pub struct CollabGuestCache {
+  pg_pool: PgPool,
+  access: Arc<DashMap<(i64, Uuid), CachedGuestAccess>>,
+  ttl: Duration,
+}
+
+struct CachedGuestAccess {
+  access: Arc<GuestAccess>,
+  fetched_at: Instant,
+}
+
+impl CollabGuestCache {
+  pub fn new(pg_pool: PgPool) -> Self {
+    Self {
+      pg_pool,
+      access: Arc::new(DashMap::new()),
+      ttl: PG_STATE_CACHE_TTL,
+    }
+  }
+
+  pub async fn get(&self, uid: i64, workspace_id: &Uuid) -> Result<Arc<GuestAccess>, AppError> {
+    if let Some(cached) = self.access.get(&(uid, *workspace_id)) {
+      if cached.fetched_at.elapsed() < self.ttl {
+        return Ok(cached.access.clone());
+      }
+    }
+    self.refresh(uid, workspace_id).await
+  }
+
+  /// Same as [CollabGuestCache::get] for a workspace id that was not parsed yet. An invalid id
+  /// has no guests.
+  pub async fn get_by_str(
+    &self,
+    uid: i64,
+    workspace_id: &str,
+  ) -> Result<Arc<GuestAccess>, AppError> {
+    match Uuid::parse_str(workspace_id) {
+      Ok(workspace_id) => self.get(uid, &workspace_id).await,
+      Err(_) => Ok(Arc::new(GuestAccess::default())),
+    }
+  }
+
+  /// Shares the collab with the user, replacing the access level it already had.
+  pub async fn share(
+    &self,
+    workspace_id: &Uuid,
+    oid: &str,
+    uid: i64,
+    access_level: AFAccessLevel,
+  ) -> Result<(), AppError> {
+    upsert_collab_guest(&self.pg_pool, workspace_id, oid, uid, access_level).await?;
+    info!(
+      "collab {} of workspace {} shared with guest {}: {:?}",
+      oid, workspace_id, uid, access_level
+    );
+    self.refresh(uid, workspace_id).await?;
+    Ok(())
+  }
+
+  /// Returns false when the collab was not shared with the user.
+  pub async fn unshare(&self, workspace_id: &Uuid, oid: &str, uid: i64) -> Result<bool, AppError> {
+    let deleted = delete_collab_guest(&self.pg_pool, workspace_id, oid, uid).await?;
+    if deleted {
+      info!(
+        "collab {} of workspace {} no longer shared with guest {}",
+        oid, workspace_id, uid
+      );
+    }
+    self.refresh(uid, workspace_id).await?;
+    Ok(deleted)
+  }
+
+  /// Forgets the cached access of the user, e.g. once the guest rows were removed in a
+  /// transaction.
+  pub fn invalidate(&self, uid: i64, workspace_id: &Uuid) {
+    self.access.remove(&(uid, *workspace_id));
+  }
+
+  async fn refresh(&self, uid: i64, workspace_id: &Uuid) -> Result<Arc<GuestAccess>, AppError> {
+    let access_levels = select_collab_guest_access_levels(&self.pg_pool, uid, workspace_id).await?;
+    let access = Arc::new(GuestAccess::new(access_levels));
+    self.access.insert(
+      (uid, *workspace_id),
+      CachedGuestAccess {
+        access: access.clone(),
+        fetched_at: Instant::now(),
+      },
+    );
+    Ok(access)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn guest_actions_follow_the_access_level() {
+    let access = GuestAccess::new(vec![
+      ("read".to_string(), AFAccessLevel::ReadOnly),
+      ("write".to_string(), AFAccessLevel::ReadAndWrite),
+    ]);
+    assert!(access.can_perform_action("read", &Action::Read));
+    assert!(!access.can_perform_action("read", &Action::Write));
+    assert!(access.can_perform_action("write", &Action::Write));
+    assert!(!access.can_perform_action("write", &Action::Delete));
+    assert!(!access.can_perform_action("other", &Action::Read));
+    assert!(access.has_access_level("write", AFAccessLevel::ReadAndComment));
+    assert!(!access.has_access_level("read", AFAccessLevel::ReadAndComment));
+  }
+}
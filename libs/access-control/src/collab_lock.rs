@@ -0,0 +1,242 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use app_error::AppError;
+use async_trait::async_trait;
+use dashmap::DashMap;
+use database::collab::{delete_collab_lock, select_collab_lock, upsert_collab_lock};
+use database_entity::dto::AFCollabLock;
+use sqlx::PgPool;
+use tracing::trace;
+use uuid::Uuid;
+
+use crate::collab::RealtimeAccessControl;
+use crate::PG_STATE_CACHE_TTL;
+
+/// At most this many lock states are cached. Once reached, the expired entries are evicted.
+const MAX_CACHED_LOCKS: usize = 10_000;
+
+/// Caches the lock state of collabs, keyed by workspace and object id. [CollabLockCache::is_locked]
+/// is called for every realtime write and every write to the collab storage.
+#[derive(Clone)]
+pub struct CollabLockCache {
+  pg_pool: PgPool,
+  locks: Arc<DashMap<(Uuid, String), CachedLock>>,
+  ttl: Duration,
+}
+
+struct CachedLock {
+  lock: Option<AFCollabLock>,
+  fetched_at: Instant,
+}
+
+impl CollabLockCache {
+  pub fn new(pg_pool: PgPool) -> Self {
+    Self {
+      pg_pool,
+      locks: Arc::new(DashMap::new()),
+      ttl: PG_STATE_CACHE_TTL,
+    }
+  }
+
+  pub async fn get_lock(
+    &self,
+    workspace_id: &Uuid,
+    object_id: &str,
+  ) -> Result<Option<AFCollabLock>, AppError> {
+    let key = (*workspace_id, object_id.to_string());
+    if let Some(cached) = self.locks.get(&key) {
+      if cached.fetched_at.elapsed() < self.ttl {
+        return Ok(cached.lock.clone());
+      }
+    }
+
+    let lock = select_collab_lock(&self.pg_pool, workspace_id, object_id).await?;
+    self.cache(key, lock.clone());
+    Ok(lock)
+  }
+
+  pub async fn is_locked(&self, workspace_id: &Uuid, object_id: &str) -> Result<bool, AppError> {
+    Ok(self.get_lock(workspace_id, object_id).await?.is_some())
+  }
+
+  /// Returns [AppError::NotEnoughPermissions] if the collab is locked.
+  pub async fn enforce_unlocked(
+    &self,
+    workspace_id: &Uuid,
+    object_id: &str,
+  ) -> Result<(), AppError> {
+    if self.is_locked(workspace_id, object_id).await? {
+      return Err(AppError::NotEnoughPermissions);
+    }
+    Ok(())
+  }
+
+  pub async fn lock(
+    &self,
+    workspace_id: &Uuid,
+    object_id: &str,
+    uid: i64,
+    reason: Option<&str>,
+  ) -> Result<AFCollabLock, AppError> {
+    let lock = upsert_collab_lock(&self.pg_pool, workspace_id, object_id, uid, reason).await?;
+    trace!("collab {} locked by {}", object_id, uid);
+    self.cache((*workspace_id, object_id.to_string()), Some(lock.clone()));
+    Ok(lock)
+  }
+
+  pub async fn unlock(&self, workspace_id: &Uuid, object_id: &str) -> Result<(), AppError> {
+    delete_collab_lock(&self.pg_pool, workspace_id, object_id).await?;
+    trace!("collab {} unlocked", object_id);
+    self.cache((*workspace_id, object_id.to_string()), None);
+    Ok(())
+  }
+
+  fn cache(&self, key: (Uuid, String), lock: Option<AFCollabLock>) {
+    if self.locks.len() >= MAX_CACHED_LOCKS {
+      let ttl = self.ttl;
+      self
+        .locks
+        .retain(|_, cached| cached.fetched_at.elapsed() < ttl);
+    }
+    self.locks.insert(
+      key,
+      CachedLock {
+        lock,
+        fetched_at: Instant::now(),
+      },
+    );
+  }
+}
+
+/// Denies the writes to the locked collabs on top of another realtime access control. The locks
+/// are not a permission of the user, so they also hold when the access control is disabled.
+pub struct CollabLockRealtimeAccessControl {
+  inner: Arc<dyn RealtimeAccessControl>,
+  collab_lock: CollabLockCache,
+}
+
+impl CollabLockRealtimeAccessControl {
+  pub fn new(inner: Arc<dyn RealtimeAccessControl>, collab_lock: CollabLockCache) -> Self {
+    Self { inner, collab_lock }
+  }
+
+  async fn is_locked(&self, workspace_id: &str, oid: &str) -> Result<bool, AppError> {
+    match Uuid::parse_str(workspace_id) {
+      Ok(workspace_id) => self.collab_lock.is_locked(&workspace_id, oid).await,
+      Err(_) => Ok(false),
+    }
+  }
+}
+
+#[async_trait]
+impl RealtimeAccessControl for CollabLockRealtimeAccessControl {
+  async fn can_write_collab(
+    &self,
+    workspace_id: &str,
+    uid: &i64,
+    oid: &str,
+  ) -> Result<bool, AppError> {
+    if self.is_locked(workspace_id, oid).await? {
+      return Ok(false);
+    }
+    self.inner.can_write_collab(workspace_id, uid, oid).await
+  }
+
+  async fn can_read_collab(
+    &self,
+    workspace_id: &str,
+    uid: &i64,
+    oid: &str,
+  ) -> Result<bool, AppError> {
+    self.inner.can_read_collab(workspace_id, uid, oid).await
+  }
+
+  async fn can_write_collabs(
+    &self,
+    workspace_id: &str,
+    uid: &i64,
+    oids: &[String],
+  ) -> Result<Vec<bool>, AppError> {
+    let mut result = self
+      .inner
+      .can_write_collabs(workspace_id, uid, oids)
+      .await?;
+    for (can_write, oid) in result.iter_mut().zip(oids) {
+      if *can_write && self.is_locked(workspace_id, oid).await? {
+        *can_write = false;
+      }
+    }
+    Ok(result)
+  }
+
+  async fn is_workspace_read_only(&self, workspace_id: &str) -> Result<bool, AppError> {
+    self.inner.is_workspace_read_only(workspace_id).await
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::noops::collab::RealtimeCollabAccessControlImpl;
+
+  fn cache_with_lock(workspace_id: Uuid, object_id: &str) -> CollabLockCache {
+    // The cached states are fresh, Postgres is never queried.
+    let cache = CollabLockCache::new(PgPool::connect_lazy("postgres://localhost").unwrap());
+    cache.cache(
+      (workspace_id, object_id.to_string()),
+      Some(AFCollabLock {
+        object_id: object_id.to_string(),
+        locked_by: 1,
+        reason: None,
+        locked_at: Default::default(),
+      }),
+    );
+    cache.cache((workspace_id, "unlocked".to_string()), None);
+    cache
+  }
+
+  #[tokio::test]
+  async fn locked_collabs_are_not_writable() {
+    let workspace_id = Uuid::new_v4();
+    let cache = cache_with_lock(workspace_id, "locked");
+    assert!(cache.is_locked(&workspace_id, "locked").await.unwrap());
+    assert!(cache
+      .enforce_unlocked(&workspace_id, "unlocked")
+      .await
+      .is_ok());
+
+    // the realtime access control is disabled
+    let access_control =
+      CollabLockRealtimeAccessControl::new(Arc::new(RealtimeCollabAccessControlImpl::new()), cache);
+    let workspace_id = workspace_id.to_string();
+    assert!(!access_control
+      .can_write_collab(&workspace_id, &1, "locked")
+      .await
+      .unwrap());
+    assert!(access_control
+      .can_read_collab(&workspace_id, &1, "locked")
+      .await
+      .unwrap());
+    let oids = vec!["locked".to_string(), "unlocked".to_string()];
+    assert_eq!(
+      access_control
+        .can_write_collabs(&workspace_id, &1, &oids)
+        .await
+        .unwrap(),
+      vec![false, true]
+    );
+  }
+
+  #[tokio::test]
+  async fn expired_locks_are_evicted() {
+    let mut cache = CollabLockCache::new(PgPool::connect_lazy("postgres://localhost").unwrap());
+    cache.ttl = Duration::ZERO;
+    let workspace_id = Uuid::new_v4();
+    for i in 0..MAX_CACHED_LOCKS {
+      cache.cache((workspace_id, i.to_string()), None);
+    }
+    cache.cache((workspace_id, "new".to_string()), None);
+    assert_eq!(cache.locks.len(), 1);
+  }
+}
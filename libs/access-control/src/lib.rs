@@ -1,9 +1,21 @@
+use std::time::Duration;
+
 pub mod act;
 #[cfg(feature = "casbin")]
 pub mod casbin;
 pub mod collab;
+pub mod collab_guest;
+pub mod collab_lock;
 pub mod entity;
 pub mod metrics;
 pub mod noops;
 mod request;
+pub mod restricted_view;
+pub mod session_policy;
 pub mod workspace;
+pub mod workspace_read_only;
+
+/// How long the caches of this crate trust a state read from Postgres. The changes made through a
+/// cache are visible immediately on this server instance, the changes made by another instance
+/// after at most this duration.
+pub(crate) const PG_STATE_CACHE_TTL: Duration = Duration::from_secs(10);
@@ -0,0 +1,440 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use app_error::AppError;
+use async_trait::async_trait;
+use dashmap::DashMap;
+use database::restricted_view::{
+  delete_restricted_view, select_restricted_views, upsert_restricted_view, AFRestrictedViewRow,
+};
+use sqlx::PgPool;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::PG_STATE_CACHE_TTL;
+
+/// The views of a workspace that only their members can see, see [AFRestrictedViewRow]. A
+/// restriction applies to the view and to all the views below it in the folder, and to the
+/// databases and rows shown in these views. The folder itself holds the names of all the views,
+/// so it can't be read by the members a view is hidden from: they use the folder endpoints, which
+/// leave out the hidden views.
+#[derive(Debug, Default)]
+pub struct RestrictedViews {
+  members_by_view_id: HashMap<String, HashSet<i64>>,
+}
+
+impl RestrictedViews {
+  pub fn new(rows: Vec<AFRestrictedViewRow>) -> Self {
+    let members_by_view_id = rows
+      .into_iter()
+      .map(|row| (row.view_id, row.member_uids.into_iter().collect()))
+      .collect();
+    Self { members_by_view_id }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.members_by_view_id.is_empty()
+  }
+
+  /// The restricted views the user is not a member of. Their subtrees are hidden from the user.
+  pub fn hidden_view_ids(&self, uid: i64) -> HashSet<String> {
+    self
+      .members_by_view_id
+      .iter()
+      .filter(|(_, members)| !members.contains(&uid))
+      .map(|(view_id, _)| view_id.clone())
+      .collect()
+  }
+
+  /// Returns true if neither the view nor any of its ancestors is hidden from the user.
+  /// `parent_of` returns the parent of a view in the folder, None for a view that is not in the
+  /// folder or has no parent.
+  pub fn can_access_view(
+    &self,
+    uid: i64,
+    view_id: &str,
+    parent_of: impl Fn(&str) -> Option<String>,
+  ) -> bool {
+    if self.is_empty() {
+      return true;
+    }
+    let mut visited = HashSet::new();
+    let mut current = Some(view_id.to_string());
+    while let Some(view_id) = current {
+      if let Some(members) = self.members_by_view_id.get(&view_id) {
+        if !members.contains(&uid) {
+          return false;
+        }
+      }
+      // the parents of a corrupted folder may form a cycle
+      if !visited.insert(view_id.clone()) {
+        break;
+      }
+      current = parent_of(&view_id);
+    }
+    true
+  }
+
+  /// Returns true if the view, or one of its ancestors, is restricted to some members.
+  fn is_restricted(&self, view_id: &str, tree: &ViewTree) -> bool {
+    let mut visited = HashSet::new();
+    let mut current = Some(view_id.to_string());
+    while let Some(view_id) = current {
+      if self.members_by_view_id.contains_key(&view_id) {
+        return true;
+      }
+      if !visited.insert(view_id.clone()) {
+        break;
+      }
+      current = tree.parent_of(&view_id);
+    }
+    false
+  }
+
+  /// Returns true if the user can access the collab. A database is hidden when all the views
+  /// showing it are, and its rows and the documents of its rows with it. The collabs that are not
+  /// shown in the folder, like the collabs of the user, are not restricted.
+  pub fn can_access_object(
+    &self,
+    uid: i64,
+    workspace_id: &str,
+    object_id: &str,
+    tree: &ViewTree,
+  ) -> bool {
+    if self.is_empty() {
+      return true;
+    }
+    if object_id == workspace_id {
+      return self.hidden_view_ids(uid).is_empty();
+    }
+    let view_ids = if tree.parent_by_view_id.contains_key(object_id)
+      || self.members_by_view_id.contains_key(object_id)
+    {
+      vec![object_id]
+    } else {
+      tree.view_ids_of_database_object(object_id)
+    };
+    view_ids.is_empty()
+      || view_ids
+        .iter()
+        .any(|view_id| self.can_access_view(uid, view_id, |view_id| tree.parent_of(view_id)))
+  }
+}
+
+/// Reads the parts of the folder and of the databases of a workspace needed to find the views a
+/// collab is shown in. Implemented with the collab storage, which depends on this crate.
+#[async_trait]
+pub trait ViewTreeLoader: Send + Sync + 'static {
+  /// The parent of every view of the folder, keyed by view id.
+  async fn load_view_parents(
+    &self,
+    workspace_id: &Uuid,
+  ) -> Result<HashMap<String, String>, AppError>;
+
+  /// The views showing each database of the workspace, keyed by database id.
+  async fn load_database_views(
+    &self,
+    workspace_id: &Uuid,
+  ) -> Result<HashMap<String, Vec<String>>, AppError>;
+
+  /// The ids of the rows of the database and of the documents of its rows.
+  async fn load_database_row_objects(
+    &self,
+    workspace_id: &Uuid,
+    database_id: &str,
+  ) -> Result<Vec<String>, AppError>;
+}
+
+/// Where the collabs of a workspace are shown in the folder. Only the rows of the restricted
+/// databases are listed, the rows of the other databases are not restricted.
+#[derive(Debug, Default)]
+pub struct ViewTree {
+  parent_by_view_id: HashMap<String, String>,
+  view_ids_by_database_id: HashMap<String, Vec<String>>,
+  database_id_by_row_object_id: HashMap<String, String>,
+}
+
+impl ViewTree {
+  pub fn new(
+    parent_by_view_id: HashMap<String, String>,
+    view_ids_by_database_id: HashMap<String, Vec<String>>,
+  ) -> Self {
+    Self {
+      parent_by_view_id,
+      view_ids_by_database_id,
+      database_id_by_row_object_id: HashMap::new(),
+    }
+  }
+
+  pub fn insert_database_rows(&mut self, database_id: &str, row_object_ids: Vec<String>) {
+    for object_id in row_object_ids {
+      self
+        .database_id_by_row_object_id
+        .insert(object_id, database_id.to_string());
+    }
+  }
+
+  fn parent_of(&self, view_id: &str) -> Option<String> {
+    self.parent_by_view_id.get(view_id).cloned()
+  }
+
+  /// The views showing the database, or the database of the row.
+  fn view_ids_of_database_object(&self, object_id: &str) -> Vec<&str> {
+    let database_id = self
+      .database_id_by_row_object_id
+      .get(object_id)
+      .map(String::as_str)
+      .unwrap_or(object_id);
+    self
+      .view_ids_by_database_id
+      .get(database_id)
+      .map(|view_ids| view_ids.iter().map(String::as_str).collect())
+      .unwrap_or_default()
+  }
+}
+
+/// Caches the restricted views of the workspaces, and the view trees they are resolved against.
+/// They are checked for every folder read, search and realtime subscription.
+#[derive(Clone)]
+pub struct RestrictedViewCache {
+  pg_pool: PgPool,
+  views: Arc<DashMap<Uuid, CachedViews>>,
+  /// The view trees of the workspaces that have restricted views.
+  trees: Arc<DashMap<Uuid, CachedTree>>,
+  view_tree_loader: Option<Arc<dyn ViewTreeLoader>>,
+  ttl: Duration,
+}
+
+struct CachedViews {
+  views: Arc<RestrictedViews>,
+  fetched_at: Instant,
+}
+
+struct CachedTree {
+  tree: Arc<ViewTree>,
+  fetched_at: Instant,
+}
+
+impl RestrictedViewCache {
+  pub fn new(pg_pool: PgPool) -> Self {
+    Self {
+      pg_pool,
+      views: Arc::new(DashMap::new()),
+      trees: Arc::new(DashMap::new()),
+      view_tree_loader: None,
+      ttl: PG_STATE_CACHE_TTL,
+    }
+  }
+
+  /// Without a loader, only the restricted views themselves can be checked by
+  /// [RestrictedViewCache::can_access_object], not the views, databases and rows below them.
+  pub fn with_view_tree_loader(mut self, loader: Arc<dyn ViewTreeLoader>) -> Self {
+    self.view_tree_loader = Some(loader);
+    self
+  }
+
+  /// See [RestrictedViews::can_access_object]. The folder and the databases are only read for
+  /// the workspaces that have restricted views.
+  pub async fn can_access_object(
+    &self,
+    uid: i64,
+    workspace_id: &Uuid,
+    object_id: &str,
+  ) -> Result<bool, AppError> {
+    let views = self.get(workspace_id).await?;
+    if views.is_empty() {
+      return Ok(true);
+    }
+    let tree = self.get_tree(workspace_id, &views).await?;
+    Ok(views.can_access_object(uid, &workspace_id.to_string(), object_id, &tree))
+  }
+
+  /// Returns [AppError::NotEnoughPermissions] if the collab is hidden from the user.
+  pub async fn enforce_object_access(
+    &self,
+    uid: i64,
+    workspace_id: &Uuid,
+    object_id: &str,
+  ) -> Result<(), AppError> {
+    if !self.can_access_object(uid, workspace_id, object_id).await? {
+      return Err(AppError::NotEnoughPermissions);
+    }
+    Ok(())
+  }
+
+  async fn get_tree(
+    &self,
+    workspace_id: &Uuid,
+    views: &RestrictedViews,
+  ) -> Result<Arc<ViewTree>, AppError> {
+    if let Some(cached) = self.trees.get(workspace_id) {
+      if cached.fetched_at.elapsed() < self.ttl {
+        return Ok(cached.tree.clone());
+      }
+    }
+    let Some(loader) = &self.view_tree_loader else {
+      return Ok(Arc::new(ViewTree::default()));
+    };
+    let parents = loader.load_view_parents(workspace_id).await?;
+    let databases = loader.load_database_views(workspace_id).await?;
+    let mut tree = ViewTree::new(parents, databases);
+    // the rows are only needed for the databases that can be hidden
+    let restricted_database_ids: Vec<String> = tree
+      .view_ids_by_database_id
+      .iter()
+      .filter(|(_, view_ids)| {
+        !view_ids.is_empty()
+          && view_ids
+            .iter()
+            .all(|view_id| views.is_restricted(view_id, &tree))
+      })
+      .map(|(database_id, _)| database_id.clone())
+      .collect();
+    for database_id in restricted_database_ids {
+      let row_object_ids = loader
+        .load_database_row_objects(workspace_id, &database_id)
+        .await?;
+      tree.insert_database_rows(&database_id, row_object_ids);
+    }
+    let tree = Arc::new(tree);
+    self.trees.insert(
+      *workspace_id,
+      CachedTree {
+        tree: tree.clone(),
+        fetched_at: Instant::now(),
+      },
+    );
+    Ok(tree)
+  }
+
+  pub async fn get(&self, workspace_id: &Uuid) -> Result<Arc<RestrictedViews>, AppError> {
+    if let Some(cached) = self.views.get(workspace_id) {
+      if cached.fetched_at.elapsed() < self.ttl {
+        return Ok(cached.views.clone());
+      }
+    }
+    self.refresh(workspace_id).await
+  }
+
+  /// Restricts the view to the given members. The members of a view that was already restricted
+  /// are replaced.
+  pub async fn restrict(
+    &self,
+    workspace_id: &Uuid,
+    view_id: &str,
+    created_by: i64,
+    member_uids: &[i64],
+  ) -> Result<(), AppError> {
+    let mut txn = self.pg_pool.begin().await?;
+    upsert_restricted_view(&mut txn, workspace_id, view_id, created_by, member_uids).await?;
+    txn.commit().await?;
+    info!(
+      "view {} of workspace {} restricted to {} members",
+      view_id,
+      workspace_id,
+      member_uids.len()
+    );
+    self.refresh(workspace_id).await?;
+    Ok(())
+  }
+
+  /// Returns false when the view was not restricted.
+  pub async fn unrestrict(&self, workspace_id: &Uuid, view_id: &str) -> Result<bool, AppError> {
+    let deleted = delete_restricted_view(&self.pg_pool, workspace_id, view_id).await?;
+    if deleted {
+      info!(
+        "view {} of workspace {} is no longer restricted",
+        view_id, workspace_id
+      );
+    }
+    self.refresh(workspace_id).await?;
+    Ok(deleted)
+  }
+
+  async fn refresh(&self, workspace_id: &Uuid) -> Result<Arc<RestrictedViews>, AppError> {
+    // the restricted databases depend on the restricted views
+    self.trees.remove(workspace_id);
+    let rows = select_restricted_views(&self.pg_pool, workspace_id).await?;
+    let views = Arc::new(RestrictedViews::new(rows));
+    self.views.insert(
+      *workspace_id,
+      CachedViews {
+        views: views.clone(),
+        fetched_at: Instant::now(),
+      },
+    );
+    Ok(views)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn restricted_view(view_id: &str, member_uids: Vec<i64>) -> AFRestrictedViewRow {
+    AFRestrictedViewRow {
+      workspace_id: Uuid::nil(),
+      view_id: view_id.to_string(),
+      created_by: 1,
+      created_at: Default::default(),
+      member_uids,
+    }
+  }
+
+  #[test]
+  fn subtree_of_restricted_view_is_hidden() {
+    let views = RestrictedViews::new(vec![restricted_view("space", vec![1])]);
+    // workspace -> space -> page -> sub_page
+    let parent_of = |view_id: &str| match view_id {
+      "sub_page" => Some("page".to_string()),
+      "page" => Some("space".to_string()),
+      "space" => Some("workspace".to_string()),
+      _ => None,
+    };
+    assert!(views.can_access_view(1, "sub_page", parent_of));
+    assert!(!views.can_access_view(2, "sub_page", parent_of));
+    assert!(!views.can_access_view(2, "space", parent_of));
+    assert!(views.can_access_view(2, "workspace", parent_of));
+    assert_eq!(
+      views.hidden_view_ids(2),
+      HashSet::from(["space".to_string()])
+    );
+    assert!(views.hidden_view_ids(1).is_empty());
+  }
+
+  #[test]
+  fn databases_and_rows_of_restricted_view_are_hidden() {
+    let views = RestrictedViews::new(vec![restricted_view("space", vec![1])]);
+    // workspace -> space -> grid, workspace -> board
+    let mut tree = ViewTree::new(
+      HashMap::from([
+        ("space".to_string(), "workspace".to_string()),
+        ("grid".to_string(), "space".to_string()),
+        ("board".to_string(), "workspace".to_string()),
+      ]),
+      HashMap::from([
+        ("hidden_db".to_string(), vec!["grid".to_string()]),
+        (
+          "shared_db".to_string(),
+          vec!["grid".to_string(), "board".to_string()],
+        ),
+      ]),
+    );
+    assert!(views.is_restricted("grid", &tree));
+    assert!(!views.is_restricted("board", &tree));
+    tree.insert_database_rows("hidden_db", vec!["row".to_string(), "row_doc".to_string()]);
+
+    for object_id in ["grid", "hidden_db", "row", "row_doc"] {
+      assert!(views.can_access_object(1, "workspace", object_id, &tree));
+      assert!(!views.can_access_object(2, "workspace", object_id, &tree));
+    }
+    // shown in a view the user can see
+    assert!(views.can_access_object(2, "workspace", "shared_db", &tree));
+    // not in the folder
+    assert!(views.can_access_object(2, "workspace", "user_awareness", &tree));
+    // the folder has the names of the hidden views
+    assert!(views.can_access_object(1, "workspace", "workspace", &tree));
+    assert!(!views.can_access_object(2, "workspace", "workspace", &tree));
+  }
+}
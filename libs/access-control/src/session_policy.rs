@@ -0,0 +1,53 @@
+use database::organization::select_max_sessions_per_user;
+use database::user::delete_gotrue_session;
+use sqlx::PgPool;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Decides how many realtime sessions a user can keep open at the same time. Organizations can
+/// set a stricter limit for their users than the default of the server.
+#[derive(Clone)]
+pub struct SessionPolicy {
+  pg_pool: PgPool,
+  /// Limit of the users who don't belong to an organization setting one, 0 means unlimited.
+  default_max_sessions: usize,
+}
+
+impl SessionPolicy {
+  pub fn new(pg_pool: PgPool, default_max_sessions: usize) -> Self {
+    Self {
+      pg_pool,
+      default_max_sessions,
+    }
+  }
+
+  /// Returns the maximum number of sessions of the user, 0 if there is no limit. The default of
+  /// the server is used when the limit of the organizations can't be read, so a database error
+  /// never prevents the user from connecting.
+  pub async fn max_sessions(&self, uid: i64) -> usize {
+    match select_max_sessions_per_user(&self.pg_pool, uid).await {
+      Ok(Some(max_sessions)) => max_sessions.max(1) as usize,
+      Ok(None) => self.default_max_sessions,
+      Err(err) => {
+        warn!("failed to read the session limit of user {}: {}", uid, err);
+        self.default_max_sessions
+      },
+    }
+  }
+
+  /// Revokes the refresh tokens of the GoTrue session, so the device has to sign in again once
+  /// its access token expires.
+  pub async fn revoke_auth_session(&self, uid: i64, auth_session_id: &Uuid) {
+    match delete_gotrue_session(&self.pg_pool, auth_session_id).await {
+      Ok(true) => info!(
+        "revoked session {} of user {} after exceeding the session limit",
+        auth_session_id, uid
+      ),
+      Ok(false) => {},
+      Err(err) => warn!(
+        "failed to revoke session {} of user {}: {}",
+        auth_session_id, uid, err
+      ),
+    }
+  }
+}
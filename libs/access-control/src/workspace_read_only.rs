@@ -0,0 +1,97 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use app_error::AppError;
+use dashmap::DashMap;
+use database::billing::{
+  delete_workspace_read_only, select_workspace_read_only, upsert_workspace_read_only,
+};
+use database::pg_row::AFWorkspaceReadOnlyRow;
+use sqlx::PgPool;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::PG_STATE_CACHE_TTL;
+
+/// Caches whether workspaces are in read-only mode, see [AFWorkspaceReadOnlyRow]. The state is
+/// checked for every realtime write and every mutating request.
+#[derive(Clone)]
+pub struct WorkspaceReadOnlyCache {
+  pg_pool: PgPool,
+  states: Arc<DashMap<Uuid, CachedState>>,
+  ttl: Duration,
+}
+
+struct CachedState {
+  read_only: Option<AFWorkspaceReadOnlyRow>,
+  fetched_at: Instant,
+}
+
+impl WorkspaceReadOnlyCache {
+  pub fn new(pg_pool: PgPool) -> Self {
+    Self {
+      pg_pool,
+      states: Arc::new(DashMap::new()),
+      ttl: PG_STATE_CACHE_TTL,
+    }
+  }
+
+  pub async fn get(&self, workspace_id: &Uuid) -> Result<Option<AFWorkspaceReadOnlyRow>, AppError> {
+    if let Some(cached) = self.states.get(workspace_id) {
+      if cached.fetched_at.elapsed() < self.ttl {
+        return Ok(cached.read_only.clone());
+      }
+    }
+
+    let read_only = select_workspace_read_only(&self.pg_pool, workspace_id).await?;
+    self.cache(workspace_id, read_only.clone());
+    Ok(read_only)
+  }
+
+  pub async fn is_read_only(&self, workspace_id: &Uuid) -> Result<bool, AppError> {
+    Ok(self.get(workspace_id).await?.is_some())
+  }
+
+  /// Returns [AppError::WorkspaceReadOnly] if the workspace is in read-only mode.
+  pub async fn enforce_writable(&self, workspace_id: &Uuid) -> Result<(), AppError> {
+    match self.get(workspace_id).await? {
+      None => Ok(()),
+      Some(read_only) => Err(AppError::WorkspaceReadOnly {
+        workspace_id: *workspace_id,
+        reason: read_only.reason,
+      }),
+    }
+  }
+
+  /// Puts the workspace in read-only mode when a reason is given, restores it otherwise.
+  pub async fn set(&self, workspace_id: &Uuid, reason: Option<&str>) -> Result<(), AppError> {
+    let was_read_only = self.is_read_only(workspace_id).await?;
+    let read_only = match reason {
+      Some(reason) => Some(upsert_workspace_read_only(&self.pg_pool, workspace_id, reason).await?),
+      None => {
+        delete_workspace_read_only(&self.pg_pool, workspace_id).await?;
+        None
+      },
+    };
+    match (&read_only, was_read_only) {
+      (Some(read_only), false) => info!(
+        "workspace {} is now read-only: {}",
+        workspace_id, read_only.reason
+      ),
+      (None, true) => info!("workspace {} is writable again", workspace_id),
+      _ => {},
+    }
+    self.cache(workspace_id, read_only);
+    Ok(())
+  }
+
+  fn cache(&self, workspace_id: &Uuid, read_only: Option<AFWorkspaceReadOnlyRow>) {
+    self.states.insert(
+      *workspace_id,
+      CachedState {
+        read_only,
+        fetched_at: Instant::now(),
+      },
+    );
+  }
+}
@@ -9,7 +9,7 @@ use std::string::FromUtf8Error;
 #[cfg(feature = "appflowy_ai_error")]
 use appflowy_ai_client::error::AIError;
 use reqwest::StatusCode;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use uuid::Uuid;
 
@@ -191,6 +191,44 @@ pub enum AppError {
 
   #[error("{0}")]
   InvalidBlock(String),
+
+  #[error("Quota exceeded for {resource}: {usage}/{limit}")]
+  QuotaExceeded {
+    resource: String,
+    limit: i64,
+    usage: i64,
+  },
+
+  #[error("Conflict:{0}")]
+  Conflict(String),
+
+  #[error("{service} is unavailable: {reason}")]
+  DependencyUnavailable { service: String, reason: String },
+
+  #[error("{0} is disabled for this workspace")]
+  FeatureDisabled(String),
+
+  /// The object id is already used by a collab of another workspace. The owning workspace is only
+  /// disclosed to its members.
+  #[error("Object {object_id} already exists in another workspace")]
+  CollabObjectIdConflict {
+    object_id: String,
+    owner_workspace_id: Option<Uuid>,
+  },
+
+  /// The published content matched the moderation denylists of the instance.
+  #[error("Publishing was rejected: {0}")]
+  PublishContentRejected(String),
+
+  /// The workspace is over the limits of its plan. Its content can be read and exported, but not
+  /// changed, until the usage is back within the limits or the plan is upgraded.
+  #[error("Workspace {workspace_id} is read-only: {reason}")]
+  WorkspaceReadOnly { workspace_id: Uuid, reason: String },
+
+  /// The same request was made too recently, for example an email was already sent to the
+  /// address.
+  #[error("Too many requests: {0}")]
+  TooManyRequests(String),
 }
 
 impl AppError {
@@ -210,6 +248,89 @@ impl AppError {
     matches!(self, AppError::UserUnAuthorized(_))
   }
 
+  pub fn category(&self) -> ErrorCategory {
+    self.code().category()
+  }
+
+  /// Machine-readable information about the error. Clients should branch on the category and
+  /// the fields here rather than parsing the message.
+  pub fn details(&self) -> Option<ErrorDetails> {
+    let mut fields = serde_json::Map::new();
+    match self {
+      AppError::Ok => return None,
+      AppError::QuotaExceeded {
+        resource,
+        limit,
+        usage,
+      } => {
+        fields.insert("resource".to_string(), resource.as_str().into());
+        fields.insert("limit".to_string(), (*limit).into());
+        fields.insert("usage".to_string(), (*usage).into());
+      },
+      AppError::DependencyUnavailable { service, .. } => {
+        fields.insert("service".to_string(), service.as_str().into());
+      },
+      AppError::CollabObjectIdConflict {
+        object_id,
+        owner_workspace_id,
+      } => {
+        fields.insert("object_id".to_string(), object_id.as_str().into());
+        if let Some(owner_workspace_id) = owner_workspace_id {
+          fields.insert(
+            "workspace_id".to_string(),
+            owner_workspace_id.to_string().into(),
+          );
+        }
+      },
+      AppError::AccessRequestAlreadyExists {
+        workspace_id,
+        view_id,
+      } => {
+        fields.insert("workspace_id".to_string(), workspace_id.to_string().into());
+        fields.insert("view_id".to_string(), view_id.to_string().into());
+      },
+      AppError::PublishNameAlreadyExists {
+        workspace_id,
+        publish_name,
+      } => {
+        fields.insert("workspace_id".to_string(), workspace_id.to_string().into());
+        fields.insert("publish_name".to_string(), publish_name.as_str().into());
+      },
+      AppError::PublishNameInvalidCharacter { character }
+      | AppError::CustomNamespaceInvalidCharacter { character } => {
+        fields.insert("character".to_string(), character.to_string().into());
+      },
+      AppError::PublishNameTooLong {
+        given_length,
+        max_length,
+      } => {
+        fields.insert("given_length".to_string(), (*given_length).into());
+        fields.insert("max_length".to_string(), (*max_length).into());
+      },
+      AppError::WorkspaceReadOnly {
+        workspace_id,
+        reason,
+      } => {
+        fields.insert("workspace_id".to_string(), workspace_id.to_string().into());
+        fields.insert("reason".to_string(), reason.as_str().into());
+      },
+      #[cfg(feature = "validation_error")]
+      AppError::ValidatorError(errors) => {
+        let invalid_fields = errors
+          .field_errors()
+          .keys()
+          .map(|field| serde_json::Value::from(field.to_string()))
+          .collect::<Vec<_>>();
+        fields.insert("fields".to_string(), invalid_fields.into());
+      },
+      _ => {},
+    }
+    Some(ErrorDetails {
+      category: self.category(),
+      fields,
+    })
+  }
+
   pub fn code(&self) -> ErrorCode {
     match self {
       AppError::Ok => ErrorCode::Ok,
@@ -273,6 +394,14 @@ impl AppError {
       AppError::ApplyUpdateError(_) => ErrorCode::ApplyUpdateError,
       AppError::ActionTimeout(_) => ErrorCode::ActionTimeout,
       AppError::InvalidBlock(_) => ErrorCode::InvalidBlock,
+      AppError::QuotaExceeded { .. } => ErrorCode::QuotaExceeded,
+      AppError::Conflict(_) => ErrorCode::Conflict,
+      AppError::DependencyUnavailable { .. } => ErrorCode::DependencyUnavailable,
+      AppError::FeatureDisabled(_) => ErrorCode::FeatureDisabled,
+      AppError::CollabObjectIdConflict { .. } => ErrorCode::CollabObjectIdConflict,
+      AppError::PublishContentRejected(_) => ErrorCode::PublishContentRejected,
+      AppError::WorkspaceReadOnly { .. } => ErrorCode::WorkspaceReadOnly,
+      AppError::TooManyRequests(_) => ErrorCode::TooManyRequests,
     }
   }
 }
@@ -327,6 +456,17 @@ impl From<sqlx::Error> for AppError {
         AppError::RecordNotFound(format!("Record not exist in db. {})", msg))
       },
       sqlx::Error::PoolTimedOut => AppError::ActionTimeout(value.to_string()),
+      sqlx::Error::PoolClosed | sqlx::Error::Io(_) => AppError::DependencyUnavailable {
+        service: "postgres".to_string(),
+        reason: msg,
+      },
+      sqlx::Error::Database(err) => match err.kind() {
+        sqlx::error::ErrorKind::UniqueViolation => AppError::RecordAlreadyExists(msg),
+        sqlx::error::ErrorKind::ForeignKeyViolation
+        | sqlx::error::ErrorKind::NotNullViolation
+        | sqlx::error::ErrorKind::CheckViolation => AppError::InvalidRequest(msg),
+        _ => AppError::SqlxError(msg),
+      },
       _ => AppError::SqlxError(msg),
     }
   }
@@ -343,11 +483,18 @@ impl From<crate::gotrue::GoTrueError> for AppError {
       GoTrueError::Auth(err) => AppError::UserUnAuthorized(err),
       GoTrueError::Internal(err) => match (err.code, err.msg.as_str()) {
         (400, m) if m.starts_with("oauth error") => AppError::OAuthError(err.msg),
-        (400, m) if m.starts_with("User already registered") => {
+        (400 | 422, m)
+          if m.starts_with("User already registered") || m.contains("already been registered") =>
+        {
           AppError::UserAlreadyRegistered(err.msg)
         },
+        (400 | 422, m) if m.contains("validate email") || m.contains("invalid format") => {
+          AppError::InvalidEmail(err.msg)
+        },
+        (422, m) if m.starts_with("Password should") => AppError::InvalidPassword(err.msg),
         (401, _) => AppError::UserUnAuthorized(format!("{}:{}", err.code, err.msg)),
         (422, _) => AppError::InvalidRequest(err.msg),
+        (429, _) => AppError::TooManyRequests(err.msg),
         _ => AppError::OAuthError(err.msg),
       },
       GoTrueError::Unhandled(err) => AppError::Internal(err),
@@ -363,6 +510,7 @@ impl From<String> for AppError {
 }
 
 #[cfg_attr(target_arch = "wasm32", derive(tsify::Tsify))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(
   Eq,
   PartialEq,
@@ -444,18 +592,142 @@ pub enum ErrorCode {
   MemberNotFound = 1063,
   InvalidBlock = 1064,
   RequestTimeout = 1065,
+  QuotaExceeded = 1066,
+  Conflict = 1067,
+  DependencyUnavailable = 1068,
+  FeatureDisabled = 1069,
+  CollabObjectIdConflict = 1070,
+  PublishContentRejected = 1071,
+  WorkspaceReadOnly = 1072,
+  TooManyRequests = 1073,
 }
 
 impl ErrorCode {
   pub fn value(&self) -> i32 {
     *self as i32
   }
+
+  pub fn category(&self) -> ErrorCategory {
+    match self {
+      ErrorCode::Ok => ErrorCategory::Ok,
+      ErrorCode::RecordNotFound | ErrorCode::MemberNotFound | ErrorCode::MissingView => {
+        ErrorCategory::NotFound
+      },
+      ErrorCode::RecordAlreadyExists
+      | ErrorCode::OverrideWithIncorrectData
+      | ErrorCode::PublishNamespaceAlreadyTaken
+      | ErrorCode::AccessRequestAlreadyExists
+      | ErrorCode::PublishNameAlreadyExists
+      | ErrorCode::Conflict
+      | ErrorCode::CollabObjectIdConflict => ErrorCategory::Conflict,
+      ErrorCode::OAuthError
+      | ErrorCode::NotLoggedIn
+      | ErrorCode::NotEnoughPermissions
+      | ErrorCode::UserUnAuthorized
+      | ErrorCode::NotInviteeOfWorkspaceInvitation
+      | ErrorCode::CustomNamespaceDisallowed
+      | ErrorCode::LicenseError
+      | ErrorCode::FeatureDisabled
+      | ErrorCode::PublishContentRejected => ErrorCategory::Permission,
+      ErrorCode::StorageSpaceNotEnough
+      | ErrorCode::WorkspaceLimitExceeded
+      | ErrorCode::WorkspaceMemberLimitExceeded
+      | ErrorCode::FileStorageLimitExceeded
+      | ErrorCode::AIResponseLimitExceeded
+      | ErrorCode::AIImageResponseLimitExceeded
+      | ErrorCode::SingleUploadLimitExceeded
+      | ErrorCode::TooManyImportTask
+      | ErrorCode::AIMaxRequired
+      | ErrorCode::QuotaExceeded
+      | ErrorCode::WorkspaceReadOnly
+      | ErrorCode::TooManyRequests => ErrorCategory::Quota,
+      ErrorCode::InvalidEmail
+      | ErrorCode::InvalidPassword
+      | ErrorCode::MissingPayload
+      | ErrorCode::InvalidUrl
+      | ErrorCode::InvalidRequest
+      | ErrorCode::InvalidOAuthProvider
+      | ErrorCode::PayloadTooLarge
+      | ErrorCode::UuidError
+      | ErrorCode::SerdeError
+      | ErrorCode::NoRequiredData
+      | ErrorCode::PublishNamespaceNotSet
+      | ErrorCode::StringLengthLimitReached
+      | ErrorCode::InvalidContentType
+      | ErrorCode::InvalidPublishedOutline
+      | ErrorCode::InvalidFolderView
+      | ErrorCode::CustomNamespaceDisabled
+      | ErrorCode::CustomNamespaceTooShort
+      | ErrorCode::CustomNamespaceTooLong
+      | ErrorCode::CustomNamespaceReserved
+      | ErrorCode::PublishNameInvalidCharacter
+      | ErrorCode::PublishNameTooLong
+      | ErrorCode::CustomNamespaceInvalidCharacter
+      | ErrorCode::DecodeUpdateError
+      | ErrorCode::InvalidPageData
+      | ErrorCode::InvalidBlock => ErrorCategory::Validation,
+      ErrorCode::NetworkError
+      | ErrorCode::S3ResponseError
+      | ErrorCode::AIServiceUnavailable
+      | ErrorCode::ServiceTemporaryUnavailable
+      | ErrorCode::ActionTimeout
+      | ErrorCode::RequestTimeout
+      | ErrorCode::MailerError
+      | ErrorCode::DependencyUnavailable => ErrorCategory::DependencyUnavailable,
+      _ => ErrorCategory::Internal,
+    }
+  }
+
+  /// The HTTP status of the error responses. Errors are returned with a 200 status and the code in
+  /// the body, except the codes introduced with a dedicated status.
+  pub fn http_status(&self) -> u16 {
+    match self {
+      ErrorCode::CollabObjectIdConflict => 409,
+      _ => 200,
+    }
+  }
+
+  /// Whether retrying the same request later may succeed.
+  pub fn is_retryable(&self) -> bool {
+    matches!(self.category(), ErrorCategory::DependencyUnavailable)
+  }
+}
+
+/// Coarse grouping of [ErrorCode]s, so that clients can decide how to react to an error without
+/// knowing every individual code.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Eq, PartialEq, Copy, Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+  Ok,
+  Validation,
+  Permission,
+  Quota,
+  Conflict,
+  NotFound,
+  DependencyUnavailable,
+  #[default]
+  #[serde(other)]
+  Internal,
+}
+
+/// Structured payload attached to error responses.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ErrorDetails {
+  pub category: ErrorCategory,
+  /// Error specific fields, for example `limit` and `usage` for quota errors.
+  #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+  #[cfg_attr(feature = "openapi", schema(value_type = Object))]
+  pub fields: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Serialize)]
 struct AppErrorSerde {
   code: ErrorCode,
   message: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  details: Option<ErrorDetails>,
 }
 
 impl From<&AppError> for AppErrorSerde {
@@ -463,6 +735,7 @@ impl From<&AppError> for AppErrorSerde {
     Self {
       code: value.code(),
       message: value.to_string(),
+      details: value.details(),
     }
   }
 }
@@ -470,11 +743,12 @@ impl From<&AppError> for AppErrorSerde {
 #[cfg(feature = "actix_web_error")]
 impl actix_web::error::ResponseError for AppError {
   fn status_code(&self) -> actix_web::http::StatusCode {
-    actix_web::http::StatusCode::OK
+    actix_web::http::StatusCode::from_u16(self.code().http_status())
+      .unwrap_or(actix_web::http::StatusCode::OK)
   }
 
   fn error_response(&self) -> actix_web::HttpResponse {
-    actix_web::HttpResponse::Ok().json(AppErrorSerde::from(self))
+    actix_web::HttpResponse::build(self.status_code()).json(AppErrorSerde::from(self))
   }
 }
 
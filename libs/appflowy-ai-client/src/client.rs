@@ -1,8 +1,8 @@
 use crate::dto::{
   CalculateSimilarityParams, ChatAnswer, ChatQuestion, CompleteTextParams, CreateChatContext,
-  Document, LocalAIConfig, MessageData, ModelList, QuestionMetadata, RepeatedLocalAIPackage,
-  RepeatedRelatedQuestion, ResponseFormat, SearchDocumentsRequest, SimilarityResponse,
-  SummarizeRowResponse, TranslateRowData, TranslateRowResponse,
+  Document, LanguageCheckResponse, LocalAIConfig, MessageData, ModelList, QuestionMetadata,
+  RepeatedLocalAIPackage, RepeatedRelatedQuestion, ResponseFormat, SearchDocumentsRequest,
+  SimilarityResponse, SummarizeRowResponse, TranslateRowData, TranslateRowResponse,
 };
 use crate::error::AIError;
 
@@ -321,6 +321,30 @@ impl AppFlowyAIClient {
       .into_data()
   }
 
+  /// Checks the spelling and the grammar of the text. The offsets of the matches are relative to
+  /// the text.
+  pub async fn language_check(
+    &self,
+    text: &str,
+    language: Option<&str>,
+    model: &str,
+  ) -> Result<LanguageCheckResponse, AIError> {
+    if text.is_empty() {
+      return Err(AIError::InvalidRequest("Empty text".to_string()));
+    }
+
+    let url = format!("{}/language_check", self.url);
+    let resp = self
+      .async_http_client(Method::POST, &url)?
+      .header(AI_MODEL_HEADER_KEY, model)
+      .json(&serde_json::json!({ "text": text, "language": language }))
+      .send()
+      .await?;
+    AIResponse::<LanguageCheckResponse>::from_reqwest_response(resp)
+      .await?
+      .into_data()
+  }
+
   fn async_http_client(&self, method: Method, url: &str) -> Result<RequestBuilder, AIError> {
     let request_builder = self.async_client.request(method, url);
     Ok(request_builder)
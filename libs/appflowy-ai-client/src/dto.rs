@@ -448,6 +448,35 @@ pub struct SimilarityResponse {
   pub score: f64,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LanguageCheckParams {
+  pub workspace_id: String,
+  pub text: String,
+  /// Language code of the text, such as `en-US`. The language is detected when it's not set.
+  #[serde(default)]
+  pub language: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LanguageCheckResponse {
+  pub matches: Vec<LanguageCheckMatch>,
+}
+
+/// A spelling or grammar issue found in the checked text.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LanguageCheckMatch {
+  /// Offset of the issue in the text, in UTF-16 code units.
+  pub offset: usize,
+  /// Length of the issue, in UTF-16 code units.
+  pub length: usize,
+  pub message: String,
+  /// Suggested replacements, the most likely one first.
+  #[serde(default)]
+  pub replacements: Vec<String>,
+  #[serde(default)]
+  pub rule_id: Option<String>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CompletionMetadata {
   /// A unique identifier for the object.
@@ -91,7 +91,15 @@ pub struct Authorization {
   pub claims: GoTrueJWTClaims,
 }
 
+/// Role of the GoTrue admins in the JWT, set with `GOTRUE_JWT_ADMIN_GROUP_NAME`.
+const GOTRUE_ADMIN_ROLE: &str = "supabase_admin";
+
 impl Authorization {
+  /// Whether the token belongs to an administrator of the AppFlowy Cloud instance.
+  pub fn is_admin(&self) -> bool {
+    self.claims.role == GOTRUE_ADMIN_ROLE
+  }
+
   pub fn uuid(&self) -> Result<uuid::Uuid, actix_web::Error> {
     self
       .claims
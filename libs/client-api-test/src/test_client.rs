@@ -30,7 +30,7 @@ use tracing::trace;
 use uuid::Uuid;
 
 #[cfg(feature = "collab-sync")]
-use client_api::collab_sync::{SinkConfig, SyncObject, SyncPlugin};
+use client_api::collab_sync::{SinkConfig, SyncObject, SyncPlugin, SyncStateTracker};
 use client_api::entity::id::user_awareness_object_id;
 use client_api::entity::{
   PublishCollabItem, PublishCollabMetadata, QueryWorkspaceMember, QuestionStream,
@@ -63,6 +63,8 @@ pub struct TestClient {
   pub api_client: client_api::Client,
   pub collabs: HashMap<String, TestCollab>,
   pub device_id: String,
+  #[cfg(feature = "collab-sync")]
+  pub sync_state_tracker: SyncStateTracker,
 }
 pub struct TestCollab {
   #[allow(dead_code)]
@@ -112,6 +114,7 @@ impl TestClient {
         buffer_capacity: 100,
         ping_per_secs: 6,
         retry_connect_per_pings: 5,
+        batch_init_sync: true,
       },
       api_client.clone(),
       api_client.clone(),
@@ -125,6 +128,8 @@ impl TestClient {
       api_client,
       collabs: Default::default(),
       device_id,
+      #[cfg(feature = "collab-sync")]
+      sync_state_tracker: SyncStateTracker::new(),
     }
   }
 
@@ -896,7 +901,9 @@ impl TestClient {
         object,
         Arc::downgrade(&collab_ref),
         sink,
-        SinkConfig::default(),
+        SinkConfig::default()
+          .sync_state_tracker(self.sync_state_tracker.clone())
+          .realtime_protocol(self.ws_client.shared_protocol()),
         stream,
         Some(handler),
         ws_connect_state,
@@ -969,7 +976,9 @@ impl TestClient {
         object,
         Arc::downgrade(&collab_ref),
         sink,
-        SinkConfig::default(),
+        SinkConfig::default()
+          .sync_state_tracker(self.sync_state_tracker.clone())
+          .realtime_protocol(self.ws_client.shared_protocol()),
         stream,
         Some(handler),
         ws_connect_state,
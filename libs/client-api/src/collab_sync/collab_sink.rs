@@ -1,7 +1,7 @@
 use std::collections::BinaryHeap;
 use std::collections::{HashMap, HashSet};
 use std::ops::{Deref, DerefMut};
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Weak};
 use std::time::{Duration, Instant};
 
@@ -9,16 +9,24 @@ use anyhow::Error;
 use collab::core::origin::{CollabClient, CollabOrigin};
 use collab::lock::Mutex;
 use futures_util::SinkExt;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::{broadcast, watch};
 use tokio::time::{interval, sleep};
 use tracing::{error, trace, warn};
 
 use crate::collab_sync::collab_stream::SeqNumCounter;
-use crate::collab_sync::{SinkConfig, SyncError, SyncObject};
-use collab_rt_entity::{ClientCollabMessage, MsgId, ServerCollabMessage, SinkMessage};
+use crate::collab_sync::{
+  DefaultMsgIdCounter, MsgIdCounter, ObjectSyncState, PersistentMsgIdCounter, SinkConfig,
+  SyncError, SyncObject,
+};
+use collab_rt_entity::{
+  ClientCollabMessage, MsgId, RealtimeProtocol, ServerCollabMessage, SinkMessage,
+};
 
 pub(crate) const SEND_INTERVAL: Duration = Duration::from_secs(8);
 pub const COLLAB_SINK_DELAY_MILLIS: u64 = 500;
+/// Delay before checking again whether a large payload can be sent.
+const LARGE_PAYLOAD_RETRY_MILLIS: u64 = 10_000;
 
 pub struct CollabSink<Sink> {
   #[allow(dead_code)]
@@ -47,6 +55,9 @@ impl<Sink> Drop for CollabSink<Sink> {
 
     //
     let _ = self.notifier.send(SinkSignal::Stop);
+    if let Some(tracker) = &self.config.sync_state_tracker {
+      tracker.remove(&self.object.object_id);
+    }
   }
 }
 
@@ -67,7 +78,14 @@ where
     let sender = Arc::new(Mutex::from(sink));
     let message_queue = Arc::new(parking_lot::Mutex::new(SinkQueue::new()));
     let sending_messages = Arc::new(parking_lot::Mutex::new(HashSet::new()));
-    let state = Arc::new(CollabSinkState::new());
+    let id_counter: Arc<dyn MsgIdCounter> = match &config.msg_id_store {
+      Some(store) => Arc::new(PersistentMsgIdCounter::new(
+        store.clone(),
+        &object.object_id,
+      )),
+      None => Arc::new(DefaultMsgIdCounter::new()),
+    };
+    let state = Arc::new(CollabSinkState::new(id_counter));
     let mut interval = interval(SEND_INTERVAL);
     let weak_sending_messages = Arc::downgrade(&sending_messages);
 
@@ -107,7 +125,33 @@ where
       }
     });
 
-    Self {
+    if let Some(scheduler) = config.sync_scheduler.clone() {
+      let mut resumed_rx = scheduler.subscribe_resumed();
+      let weak_notifier = Arc::downgrade(&notifier);
+      let workspace_id = object.workspace_id.clone();
+      let object_id = object.object_id.clone();
+      tokio::spawn(async move {
+        loop {
+          match resumed_rx.recv().await {
+            Ok(_) | Err(RecvError::Lagged(_)) => {
+              let notifier = match weak_notifier.upgrade() {
+                Some(notifier) => notifier,
+                None => break,
+              };
+              // send the changes queued while the sync was deferred
+              if !scheduler.is_deferred(&workspace_id, &object_id)
+                && notifier.send(SinkSignal::Proceed).is_err()
+              {
+                break;
+              }
+            },
+            Err(RecvError::Closed) => break,
+          }
+        }
+      });
+    }
+
+    let collab_sink = Self {
       uid,
       object,
       sender,
@@ -117,7 +161,12 @@ where
       config,
       sending_messages,
       state,
+    };
+    // the ack of the last session, when the message ids are persisted
+    if let Some(last_ack) = collab_sink.state.id_counter.last_ack() {
+      collab_sink.update_sync_state(|state| state.last_ack_msg_id = Some(last_ack));
     }
+    collab_sink
   }
 
   /// Put the message into the queue and notify the sink to process the next message.
@@ -133,6 +182,11 @@ where
     msg_queue.push_msg(msg_id, new_msg);
     drop(msg_queue);
     self.merge();
+    let queued_messages = pending_message_count(&self.message_queue.lock());
+    self.update_sync_state(|state| {
+      state.queued_messages = queued_messages;
+      state.sink_state = CollabSyncState::Syncing;
+    });
 
     // Notify the sink to process the next message after 500ms.
     let _ = self
@@ -151,10 +205,37 @@ where
     let msg_id = self.state.id_counter.next();
     let init_sync = f(msg_id);
     msg_queue.push_msg(msg_id, init_sync);
+    let queued_messages = pending_message_count(&msg_queue);
+    drop(msg_queue);
     self.state.did_queue_int_sync.store(true, Ordering::SeqCst);
+    self.update_sync_state(|state| {
+      state.queued_messages = queued_messages;
+      state.sink_state = CollabSyncState::Syncing;
+    });
     let _ = self.notifier.send(SinkSignal::Proceed);
   }
 
+  /// Stores the resume token received from the server. It will be presented in the next
+  /// [ClientCollabMessage::ClientResumeSync] after reconnecting.
+  pub fn set_resume_token(&self, resume_token: String) {
+    *self.state.resume_token.lock() = Some(resume_token);
+  }
+
+  /// The protocol negotiated by the current connection, see [SinkConfig::realtime_protocol].
+  pub fn realtime_protocol(&self) -> RealtimeProtocol {
+    self
+      .config
+      .realtime_protocol
+      .as_ref()
+      .map(|protocol| protocol.read().clone())
+      .unwrap_or_else(RealtimeProtocol::legacy)
+  }
+
+  /// Takes the latest resume token received from the server, if any.
+  pub fn take_resume_token(&self) -> Option<String> {
+    self.state.resume_token.lock().take()
+  }
+
   pub fn did_queue_init_sync(&self) -> bool {
     self.state.did_queue_int_sync.load(Ordering::SeqCst)
   }
@@ -175,6 +256,7 @@ where
   pub fn clear(&self) {
     self.message_queue.lock().clear();
     self.sending_messages.lock().clear();
+    self.update_sync_state(|state| state.queued_messages = 0);
   }
 
   pub fn pause(&self) {
@@ -183,6 +265,7 @@ where
     }
 
     self.state.pause_ping.store(true, Ordering::SeqCst);
+    self.update_sync_state(|state| state.is_paused = true);
   }
 
   pub fn resume(&self) {
@@ -191,6 +274,18 @@ where
     }
 
     self.state.pause_ping.store(false, Ordering::SeqCst);
+    self.update_sync_state(|state| state.is_paused = false);
+  }
+
+  /// Records the sequence number of the last update broadcast by the server.
+  pub(crate) fn did_receive_broadcast(&self, seq_num: u32) {
+    self.update_sync_state(|state| state.last_broadcast_seq = Some(seq_num));
+  }
+
+  fn update_sync_state(&self, f: impl FnOnce(&mut ObjectSyncState)) {
+    if let Some(tracker) = &self.config.sync_state_tracker {
+      tracker.update(&self.object.object_id, f);
+    }
   }
 
   /// Notify the sink to process the next message and mark the current message as done.
@@ -233,6 +328,7 @@ where
       } else {
         is_valid = true;
         sending_messages.remove(&income_message_id);
+        self.state.id_counter.did_ack(income_message_id);
       }
     }
 
@@ -249,6 +345,17 @@ where
       .iter()
       .any(|item| !item.message().is_ping_sync());
 
+    let queued_messages = pending_message_count(&message_queue);
+    self.update_sync_state(|state| {
+      state.queued_messages = queued_messages;
+      if is_valid {
+        state.last_ack_msg_id = Some(income_message_id);
+      }
+      if all_non_ping_messages_sent {
+        state.sink_state = CollabSyncState::Finished;
+      }
+    });
+
     // If there are no non-ping messages left in the queue, it indicates all messages have been sent
     if all_non_ping_messages_sent {
       if let Err(err) = self.sync_state_tx.send(CollabSyncState::Finished) {
@@ -283,6 +390,18 @@ where
       return;
     }
 
+    if let Some(scheduler) = &self.config.sync_scheduler {
+      if scheduler.is_deferred(&self.object.workspace_id, &self.object.object_id) {
+        if cfg!(feature = "sync_verbose_log") {
+          trace!(
+            "{}: background sync paused, keep the messages queued",
+            self.object.object_id
+          );
+        }
+        return;
+      }
+    }
+
     let items = {
       let (mut msg_queue, mut sending_messages) = match (
         self.message_queue.try_lock(),
@@ -303,6 +422,38 @@ where
       };
       get_next_batch_item(&self.state, &mut sending_messages, &mut msg_queue)
     };
+    if items.is_empty() {
+      return;
+    }
+
+    let payload_size: usize = items.iter().map(|item| item.message().payload_size()).sum();
+    if payload_size > self.config.large_payload_threshold {
+      if let Some(policy) = &self.config.large_payload_policy {
+        if !policy.can_send_large_payload() {
+          if cfg!(feature = "sync_verbose_log") {
+            trace!(
+              "{}: defer {} bytes payload",
+              self.object.object_id,
+              payload_size
+            );
+          }
+          let message_ids = items.iter().map(|item| item.msg_id()).collect::<Vec<_>>();
+          self
+            .sending_messages
+            .lock()
+            .retain(|id| !message_ids.contains(id));
+          retry_after(Arc::downgrade(&self.notifier), LARGE_PAYLOAD_RETRY_MILLIS);
+          return;
+        }
+      }
+    }
+
+    if let Some(limiter) = &self.config.bandwidth_limiter {
+      let delay = limiter.reserve(payload_size);
+      if !delay.is_zero() {
+        sleep(delay).await;
+      }
+    }
     self.send_immediately(items).await;
   }
 
@@ -457,9 +608,21 @@ fn get_next_batch_item(
   next_sending_items
 }
 
+/// Returns the number of messages in the queue that carry local changes, the pings excluded.
+fn pending_message_count(msg_queue: &SinkQueue<ClientCollabMessage>) -> usize {
+  msg_queue
+    .iter()
+    .filter(|item| !item.message().is_ping_sync())
+    .count()
+}
+
 fn retry_later(weak_notifier: Weak<watch::Sender<SinkSignal>>) {
+  retry_after(weak_notifier, 200);
+}
+
+fn retry_after(weak_notifier: Weak<watch::Sender<SinkSignal>>, millis: u64) {
   if let Some(notifier) = weak_notifier.upgrade() {
-    let _ = notifier.send(SinkSignal::ProcessAfterMillis(200));
+    let _ = notifier.send(SinkSignal::ProcessAfterMillis(millis));
   }
 }
 
@@ -498,23 +661,6 @@ impl CollabSinkRunner {
   }
 }
 
-pub trait MsgIdCounter: Send + Sync + 'static {
-  /// Get the next message id. The message id should be unique.
-  fn next(&self) -> MsgId;
-}
-
-#[derive(Debug, Default)]
-pub struct DefaultMsgIdCounter(Arc<AtomicU64>);
-
-impl DefaultMsgIdCounter {
-  pub fn new() -> Self {
-    Self::default()
-  }
-  pub(crate) fn next(&self) -> MsgId {
-    self.0.fetch_add(1, Ordering::SeqCst)
-  }
-}
-
 pub(crate) struct SyncTimestamp {
   last_sync: Mutex<Instant>,
 }
@@ -542,23 +688,25 @@ impl SyncTimestamp {
 pub(crate) struct CollabSinkState {
   pub(crate) latest_sync: SyncTimestamp,
   pub(crate) pause_ping: AtomicBool,
-  pub(crate) id_counter: DefaultMsgIdCounter,
+  pub(crate) id_counter: Arc<dyn MsgIdCounter>,
   pub(crate) did_queue_int_sync: AtomicBool,
+  /// The latest resume cursor sent by the server for this collab.
+  pub(crate) resume_token: parking_lot::Mutex<Option<String>>,
 }
 
 impl CollabSinkState {
-  fn new() -> Self {
-    let msg_id_counter = DefaultMsgIdCounter::new();
+  fn new(id_counter: Arc<dyn MsgIdCounter>) -> Self {
     CollabSinkState {
       latest_sync: SyncTimestamp::new(),
       pause_ping: AtomicBool::new(false),
-      id_counter: msg_id_counter,
+      id_counter,
       did_queue_int_sync: Default::default(),
+      resume_token: Default::default(),
     }
   }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum CollabSyncState {
   /// The sink is syncing the messages to the remote.
   Syncing,
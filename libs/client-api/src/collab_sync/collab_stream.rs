@@ -11,7 +11,7 @@ use collab::preclude::Collab;
 use futures_util::{SinkExt, StreamExt};
 use tokio::select;
 use tokio_util::sync::CancellationToken;
-use tracing::{error, instrument, trace, warn};
+use tracing::{error, info, instrument, trace, warn};
 use yrs::encoding::read::Cursor;
 use yrs::updates::decoder::DecoderV1;
 use yrs::updates::encoder::Encode;
@@ -245,6 +245,25 @@ where
       trace!("handle server: {}", msg);
     }
 
+    if let ServerCollabMessage::ServerResumeCursor(cursor) = &msg {
+      sink.set_resume_token(cursor.resume_token.clone());
+      return Ok(());
+    }
+
+    if let ServerCollabMessage::ServerCollabLock(lock) = &msg {
+      // Updates sent while the collab is locked are rejected by the server, the application is
+      // expected to switch the editor to read-only mode.
+      info!("{}", lock);
+      return Ok(());
+    }
+
+    if let ServerCollabMessage::ServerCellLock(lock) = &msg {
+      // The advisory cell locks don't change the collab, the application shows who is editing the
+      // cell.
+      trace!("{}", lock);
+      return Ok(());
+    }
+
     if let ServerCollabMessage::ClientAck(ack) = &msg {
       let ack_code = ack.get_code();
       // if the server can not apply the update, we start the init sync.
@@ -263,6 +282,11 @@ where
           reason: MissUpdateReason::ServerMissUpdates,
         });
       }
+
+      if ack_code == AckCode::WorkspaceReadOnly {
+        // The server drops the updates until the workspace is back within the limits of its plan.
+        info!("{} is read-only, the update was rejected", object.object_id);
+      }
     }
 
     // msg_id will be None for [ServerBroadcast] or [ServerAwareness].
@@ -275,6 +299,7 @@ where
         if let ServerCollabMessage::ServerBroadcast(ref data) = msg {
           seq_num_counter.check_broadcast_contiguous(&object.object_id, data.seq_num)?;
           seq_num_counter.store_broadcast_seq_num(data.seq_num);
+          sink.did_receive_broadcast(data.seq_num);
         }
         Ok(())
       },
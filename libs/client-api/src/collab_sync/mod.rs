@@ -1,11 +1,17 @@
 mod collab_sink;
 mod collab_stream;
 mod error;
+mod msg_id;
 mod plugin;
 mod sync_control;
+mod sync_state;
+mod throttle;
 
 pub use collab_rt_entity::{MsgId, ServerCollabMessage};
 pub use collab_sink::*;
 pub use error::*;
+pub use msg_id::*;
 pub use plugin::*;
 pub use sync_control::*;
+pub use sync_state::*;
+pub use throttle::*;
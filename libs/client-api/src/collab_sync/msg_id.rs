@@ -0,0 +1,105 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use collab_rt_entity::MsgId;
+
+/// Number of message ids reserved in the [MsgIdStore] at once, the store is written once per
+/// block instead of once per message.
+const MSG_ID_BLOCK_SIZE: MsgId = 100;
+
+pub trait MsgIdCounter: Send + Sync + 'static {
+  /// Get the next message id. The message id should be unique.
+  fn next(&self) -> MsgId;
+
+  /// Called when the server acknowledged the message.
+  fn did_ack(&self, _msg_id: MsgId) {}
+
+  /// The last message acknowledged by the server, None when unknown.
+  fn last_ack(&self) -> Option<MsgId> {
+    None
+  }
+}
+
+/// Starts from 0 every time the process starts.
+#[derive(Debug, Default)]
+pub struct DefaultMsgIdCounter(Arc<AtomicU64>);
+
+impl DefaultMsgIdCounter {
+  pub fn new() -> Self {
+    Self::default()
+  }
+}
+
+impl MsgIdCounter for DefaultMsgIdCounter {
+  fn next(&self) -> MsgId {
+    self.0.fetch_add(1, Ordering::SeqCst)
+  }
+}
+
+/// Key-value storage of the application, used by [PersistentMsgIdCounter] to keep the message
+/// ids of the collabs across restarts. The writes don't need to be durable immediately, but they
+/// must not be reordered.
+pub trait MsgIdStore: Send + Sync + 'static {
+  fn get(&self, key: &str) -> Option<u64>;
+  fn set(&self, key: &str, value: u64);
+}
+
+/// A [MsgIdCounter] that never reuses the ids of the messages sent before the process restarted.
+/// The server acknowledges the messages by id, so an ack of a message sent before a crash could
+/// otherwise be matched with a new message that got the same id.
+///
+/// The ids are reserved by blocks: after a restart, the counter resumes at the end of the last
+/// reserved block, skipping the ids that were reserved but never used.
+pub struct PersistentMsgIdCounter {
+  store: Arc<dyn MsgIdStore>,
+  next_id_key: String,
+  last_ack_key: String,
+  ids: parking_lot::Mutex<ReservedIds>,
+  last_ack: parking_lot::Mutex<Option<MsgId>>,
+}
+
+struct ReservedIds {
+  next: MsgId,
+  end: MsgId,
+}
+
+impl PersistentMsgIdCounter {
+  pub fn new(store: Arc<dyn MsgIdStore>, object_id: &str) -> Self {
+    let next_id_key = format!("collab_msg_id:{}", object_id);
+    let last_ack_key = format!("collab_last_ack_msg_id:{}", object_id);
+    let next = store.get(&next_id_key).unwrap_or(0);
+    let last_ack = store.get(&last_ack_key);
+    Self {
+      store,
+      next_id_key,
+      last_ack_key,
+      ids: parking_lot::Mutex::new(ReservedIds { next, end: next }),
+      last_ack: parking_lot::Mutex::new(last_ack),
+    }
+  }
+}
+
+impl MsgIdCounter for PersistentMsgIdCounter {
+  fn next(&self) -> MsgId {
+    let mut ids = self.ids.lock();
+    if ids.next >= ids.end {
+      ids.end = ids.next + MSG_ID_BLOCK_SIZE;
+      self.store.set(&self.next_id_key, ids.end);
+    }
+    let msg_id = ids.next;
+    ids.next += 1;
+    msg_id
+  }
+
+  fn did_ack(&self, msg_id: MsgId) {
+    let mut last_ack = self.last_ack.lock();
+    if last_ack.map_or(true, |last_ack| msg_id > last_ack) {
+      *last_ack = Some(msg_id);
+      self.store.set(&self.last_ack_key, msg_id);
+    }
+  }
+
+  fn last_ack(&self) -> Option<MsgId> {
+    *self.last_ack.lock()
+  }
+}
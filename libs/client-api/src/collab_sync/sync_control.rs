@@ -7,20 +7,24 @@ use collab::core::awareness::Awareness;
 use collab::core::origin::CollabOrigin;
 use collab::preclude::Collab;
 use futures_util::{SinkExt, StreamExt};
+use parking_lot::RwLock;
 use tokio::sync::{broadcast, watch};
 use tracing::{error, instrument, trace};
 use yrs::updates::decoder::Decode;
 use yrs::updates::encoder::{Encode, Encoder, EncoderV1};
 use yrs::{ReadTxn, StateVector};
 
-use collab_rt_entity::{ClientCollabMessage, InitSync, ServerCollabMessage, UpdateSync};
+use collab_rt_entity::{
+  ClientCollabMessage, InitSync, RealtimeProtocol, ServerCollabMessage, UpdateSync,
+};
 use collab_rt_protocol::{ClientSyncProtocol, CollabSyncProtocol, Message, SyncMessage};
 
 use crate::collab_sync::collab_stream::{CollabRef, ObserveCollab};
 use crate::collab_sync::{
-  CollabSink, CollabSinkRunner, CollabSyncState, MissUpdateReason, SinkSignal, SyncError,
-  SyncObject,
+  BandwidthLimiter, CollabSink, CollabSinkRunner, CollabSyncState, LargePayloadPolicy,
+  MissUpdateReason, MsgIdStore, SinkSignal, SyncError, SyncObject, SyncStateTracker,
 };
+use crate::ws::SyncScheduler;
 
 pub const DEFAULT_SYNC_TIMEOUT: u64 = 10;
 
@@ -245,6 +249,14 @@ where
         &sync_object.object_id,
         reason
       );
+      // Only a network resume can continue from the last resume cursor. Otherwise, the token is
+      // dropped and the server performs a full init sync.
+      let resume_token = sink.take_resume_token();
+      let resume_token = match reason {
+        SyncReason::NetworkResume => resume_token,
+        _ => None,
+      };
+      let protocol = sink.realtime_protocol();
       let awareness = collab.get_awareness();
       let payload = gen_sync_state(awareness, &ClientSyncProtocol)?;
       sink.queue_init_sync(|msg_id| {
@@ -256,7 +268,7 @@ where
           msg_id,
           payload,
         );
-        ClientCollabMessage::new_init_sync(init_sync)
+        ClientCollabMessage::new_sync_start(&protocol, init_sync, resume_token)
       });
     },
   };
@@ -278,6 +290,23 @@ pub struct SinkConfig {
   pub send_timeout: Duration,
   /// `maximum_payload_size` is the maximum size of the messages to be merged.
   pub maximum_payload_size: usize,
+  /// Limits the bytes sent per second. Not limited when `None`.
+  pub bandwidth_limiter: Option<Arc<BandwidthLimiter>>,
+  /// Batches larger than `large_payload_threshold` bytes are only sent when the
+  /// `large_payload_policy` allows it. They stay queued otherwise.
+  pub large_payload_threshold: usize,
+  pub large_payload_policy: Option<Arc<dyn LargePayloadPolicy>>,
+  /// Defers the sync of the collabs whose workspace is paused.
+  pub sync_scheduler: Option<SyncScheduler>,
+  /// Reports the sync progress of the collab.
+  pub sync_state_tracker: Option<SyncStateTracker>,
+  /// Persists the message ids of the collab, see [PersistentMsgIdCounter]. The ids start from 0
+  /// in every process when `None`.
+  pub msg_id_store: Option<Arc<dyn MsgIdStore>>,
+  /// The protocol negotiated by the websocket connection, see [crate::ws::WSClient::shared_protocol].
+  /// The legacy protocol is assumed when `None`, so the collab is never resumed from the last
+  /// resume cursor.
+  pub realtime_protocol: Option<Arc<RwLock<RealtimeProtocol>>>,
 }
 
 impl SinkConfig {
@@ -288,6 +317,48 @@ impl SinkConfig {
     self.send_timeout = Duration::from_secs(secs);
     self
   }
+
+  /// Limits the bytes sent by this sink per second. Use [SinkConfig::bandwidth_limiter] to share
+  /// a single budget between several collabs.
+  pub fn max_bytes_per_sec(mut self, bytes_per_sec: u64) -> Self {
+    self.bandwidth_limiter = Some(Arc::new(BandwidthLimiter::new(bytes_per_sec)));
+    self
+  }
+
+  pub fn bandwidth_limiter(mut self, limiter: Arc<BandwidthLimiter>) -> Self {
+    self.bandwidth_limiter = Some(limiter);
+    self
+  }
+
+  pub fn large_payload_policy(
+    mut self,
+    threshold: usize,
+    policy: Arc<dyn LargePayloadPolicy>,
+  ) -> Self {
+    self.large_payload_threshold = threshold;
+    self.large_payload_policy = Some(policy);
+    self
+  }
+
+  pub fn sync_scheduler(mut self, scheduler: SyncScheduler) -> Self {
+    self.sync_scheduler = Some(scheduler);
+    self
+  }
+
+  pub fn sync_state_tracker(mut self, tracker: SyncStateTracker) -> Self {
+    self.sync_state_tracker = Some(tracker);
+    self
+  }
+
+  pub fn msg_id_store(mut self, store: Arc<dyn MsgIdStore>) -> Self {
+    self.msg_id_store = Some(store);
+    self
+  }
+
+  pub fn realtime_protocol(mut self, protocol: Arc<RwLock<RealtimeProtocol>>) -> Self {
+    self.realtime_protocol = Some(protocol);
+    self
+  }
 }
 
 impl Default for SinkConfig {
@@ -295,6 +366,13 @@ impl Default for SinkConfig {
     Self {
       send_timeout: Duration::from_secs(DEFAULT_SYNC_TIMEOUT),
       maximum_payload_size: 1024 * 10,
+      bandwidth_limiter: None,
+      large_payload_threshold: 1024 * 64,
+      large_payload_policy: None,
+      sync_scheduler: None,
+      sync_state_tracker: None,
+      msg_id_store: None,
+      realtime_protocol: None,
     }
   }
 }
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use collab_rt_entity::MsgId;
+use parking_lot::RwLock;
+use tokio::sync::broadcast;
+
+use crate::collab_sync::CollabSyncState;
+
+/// The sync progress of a collab, as seen by its sink.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ObjectSyncState {
+  /// Number of local changes that were not acknowledged by the server yet.
+  pub queued_messages: usize,
+  /// Id of the last message acknowledged by the server.
+  pub last_ack_msg_id: Option<MsgId>,
+  /// Sequence number of the last update broadcast by the server for this collab.
+  pub last_broadcast_seq: Option<u32>,
+  pub sink_state: CollabSyncState,
+  /// True while the sync is paused because the websocket is disconnected or unauthorized. The
+  /// local changes stay queued until the connection is back.
+  pub is_paused: bool,
+}
+
+impl Default for ObjectSyncState {
+  fn default() -> Self {
+    Self {
+      queued_messages: 0,
+      last_ack_msg_id: None,
+      last_broadcast_seq: None,
+      sink_state: CollabSyncState::Finished,
+      is_paused: false,
+    }
+  }
+}
+
+impl ObjectSyncState {
+  /// Returns true if all the local changes reached the server.
+  pub fn is_saved(&self) -> bool {
+    self.queued_messages == 0 && !self.sink_state.is_syncing()
+  }
+}
+
+#[derive(Clone, Debug)]
+pub enum SyncStateChange {
+  Updated {
+    object_id: String,
+    state: ObjectSyncState,
+  },
+  /// The collab was closed, its state is not tracked anymore.
+  Removed { object_id: String },
+}
+
+/// Keeps the [ObjectSyncState] of the collabs whose [crate::collab_sync::SinkConfig] was given
+/// this tracker, so applications can show whether the changes are saved, still being sent or
+/// waiting for the connection. The same tracker is meant to be shared by all the collabs.
+#[derive(Clone)]
+pub struct SyncStateTracker {
+  states: Arc<RwLock<HashMap<String, ObjectSyncState>>>,
+  change_tx: broadcast::Sender<SyncStateChange>,
+}
+
+impl Default for SyncStateTracker {
+  fn default() -> Self {
+    let (change_tx, _) = broadcast::channel(1000);
+    Self {
+      states: Default::default(),
+      change_tx,
+    }
+  }
+}
+
+impl SyncStateTracker {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn sync_state(&self, object_id: &str) -> Option<ObjectSyncState> {
+    self.states.read().get(object_id).cloned()
+  }
+
+  /// Notified every time the sync state of a collab changes.
+  pub fn subscribe(&self) -> broadcast::Receiver<SyncStateChange> {
+    self.change_tx.subscribe()
+  }
+
+  /// Applies `f` to the state of the collab and notifies the subscribers if the state changed.
+  pub(crate) fn update(&self, object_id: &str, f: impl FnOnce(&mut ObjectSyncState)) {
+    let state = {
+      let mut states = self.states.write();
+      let state = states.entry(object_id.to_string()).or_default();
+      let previous = state.clone();
+      f(state);
+      if *state == previous {
+        return;
+      }
+      state.clone()
+    };
+    let _ = self.change_tx.send(SyncStateChange::Updated {
+      object_id: object_id.to_string(),
+      state,
+    });
+  }
+
+  pub(crate) fn remove(&self, object_id: &str) {
+    if self.states.write().remove(object_id).is_some() {
+      let _ = self.change_tx.send(SyncStateChange::Removed {
+        object_id: object_id.to_string(),
+      });
+    }
+  }
+}
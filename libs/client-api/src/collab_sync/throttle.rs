@@ -0,0 +1,37 @@
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+/// Decides whether a payload larger than [crate::collab_sync::SinkConfig::large_payload_threshold]
+/// can be sent right now. Implemented by the application, e.g. to only send the large payloads
+/// over wifi on metered connections.
+pub trait LargePayloadPolicy: Send + Sync {
+  fn can_send_large_payload(&self) -> bool;
+}
+
+/// Limits the number of bytes sent per second. The same limiter can be shared by the sinks of
+/// several collabs to apply a single budget to all of them.
+pub struct BandwidthLimiter {
+  bytes_per_sec: u64,
+  next_free: Mutex<Option<Instant>>,
+}
+
+impl BandwidthLimiter {
+  pub fn new(bytes_per_sec: u64) -> Self {
+    Self {
+      bytes_per_sec: bytes_per_sec.max(1),
+      next_free: Mutex::new(None),
+    }
+  }
+
+  /// Reserves the bandwidth to send `bytes`. Returns how long the caller has to wait before
+  /// sending them.
+  pub fn reserve(&self, bytes: usize) -> Duration {
+    let now = Instant::now();
+    let cost = Duration::from_secs_f64(bytes as f64 / self.bytes_per_sec as f64);
+    let mut next_free = self.next_free.lock();
+    let start = next_free.map(|next| next.max(now)).unwrap_or(now);
+    *next_free = Some(start + cost);
+    start.duration_since(now)
+  }
+}
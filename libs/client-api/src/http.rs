@@ -14,7 +14,11 @@ use gotrue::grant::{Grant, RefreshTokenGrant};
 use gotrue::params::MagicLinkParams;
 use gotrue::params::{AdminUserParams, GenerateLinkParams};
 use reqwest::StatusCode;
-use shared_entity::dto::workspace_dto::{CreateWorkspaceParam, PatchWorkspaceParam};
+use shared_entity::dto::workspace_dto::{
+  CloneWorkspaceParams, CreateWorkspaceParam, PatchWorkspaceParam, RebuiltWorkspaceFolder,
+  WorkspaceCloneTask,
+};
+use std::collections::BTreeMap;
 use std::fmt::{Display, Formatter};
 #[cfg(feature = "enable_brotli")]
 use std::io::Read;
@@ -25,23 +29,31 @@ use reqwest::RequestBuilder;
 
 use anyhow::anyhow;
 use client_api_entity::{
-  AFSnapshotMeta, AFSnapshotMetas, AFUserProfile, AFUserWorkspaceInfo, AFWorkspace,
-  QuerySnapshotParams, SnapshotData,
+  AFCollabCheckpoint, AFCollabHistory, AFSnapshotMeta, AFSnapshotMetas, AFUserProfile,
+  AFUserWorkspaceInfo, AFWorkspace, CreateCollabCheckpointParams, QuerySnapshotParams,
+  RevertCollabCheckpointParams, SnapshotData,
 };
 use semver::Version;
 use shared_entity::dto::auth_dto::SignInTokenResponse;
 use shared_entity::dto::auth_dto::UpdateUserParams;
+use shared_entity::dto::auth_dto::{ChangeEmailParams, EmailLinkParams};
+use shared_entity::dto::notification_dto::{
+  NotificationPreferences, RegisterPushDeviceParams, UnregisterPushDeviceParams,
+  UpdateNotificationPreferencesParams,
+};
 use shared_entity::dto::workspace_dto::WorkspaceSpaceUsage;
 use shared_entity::response::{AppResponse, AppResponseError};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio_retry::strategy::FixedInterval;
 use tokio_retry::RetryIf;
 use tracing::{debug, error, event, info, instrument, trace, warn};
 use url::Url;
 
-use crate::retry::{RefreshTokenAction, RefreshTokenRetryCondition};
+use crate::retry::{
+  CircuitBreaker, RefreshTokenAction, RefreshTokenRetryCondition, RetryPolicy, SendWithRetry,
+};
 use crate::ws::ConnectInfo;
 use client_api_entity::SignUpResponse::{Authenticated, NotAuthenticated};
 use client_api_entity::{GotrueTokenResponse, UpdateGotrueUserParams, User};
@@ -59,6 +71,7 @@ pub struct ClientConfiguration {
   /// A larger buffer size means more data is compressed in a single operation, which can lead to better compression ratios
   /// since Brotli has more data to analyze for patterns and repetitions.
   pub(crate) compression_buffer_size: usize,
+  pub(crate) retry_policy: RetryPolicy,
 }
 
 impl ClientConfiguration {
@@ -76,6 +89,12 @@ impl ClientConfiguration {
     };
     self
   }
+
+  /// Retry policy of the http requests, see [RetryPolicy] for the requests that are retried.
+  pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+    self.retry_policy = retry_policy;
+    self
+  }
 }
 
 impl Default for ClientConfiguration {
@@ -83,6 +102,7 @@ impl Default for ClientConfiguration {
     Self {
       compression_quality: 8,
       compression_buffer_size: 10240,
+      retry_policy: RetryPolicy::default(),
     }
   }
 }
@@ -111,7 +131,11 @@ pub struct Client {
   pub(crate) is_refreshing_token: Arc<AtomicBool>,
   pub(crate) refresh_ret_txs: Arc<RwLock<Vec<RefreshTokenSender>>>,
   pub(crate) config: ClientConfiguration,
+  pub(crate) circuit_breaker: Arc<CircuitBreaker>,
   pub(crate) ai_model: Arc<RwLock<String>>,
+  /// Sent in the websocket handshake for the server to hint the region to connect to.
+  pub(crate) ws_workspace_id: Arc<RwLock<Option<String>>>,
+  pub(crate) region_latencies: Arc<RwLock<BTreeMap<String, u32>>>,
 }
 
 pub(crate) type RefreshTokenSender = tokio::sync::oneshot::Sender<Result<(), AppResponseError>>;
@@ -186,9 +210,12 @@ impl Client {
       is_refreshing_token: Default::default(),
       refresh_ret_txs: Default::default(),
       config,
+      circuit_breaker: Default::default(),
       device_id: device_id.to_string(),
       client_version,
       ai_model,
+      ws_workspace_id: Default::default(),
+      region_latencies: Default::default(),
     }
   }
 
@@ -256,17 +283,57 @@ impl Client {
     email: &str,
     redirect_to: Option<String>,
   ) -> Result<(), AppResponseError> {
-    self
-      .gotrue_client
-      .magic_link(
-        &MagicLinkParams {
-          email: email.to_owned(),
-          ..Default::default()
-        },
+    let url = format!("{}/api/user/magic-link", self.base_url);
+    let resp = self
+      .cloud_client
+      .post(&url)
+      .json(&EmailLinkParams {
+        email: email.to_owned(),
         redirect_to,
-      )
+      })
+      .send_with_retry(self)
       .await?;
-    Ok(())
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  /// Sends a link to reset the password to the email. The redirect_to parameter works like the one
+  /// of [Client::sign_in_with_magic_link].
+  #[instrument(level = "debug", skip_all, err)]
+  pub async fn send_password_recovery(
+    &self,
+    email: &str,
+    redirect_to: Option<String>,
+  ) -> Result<(), AppResponseError> {
+    let url = format!("{}/api/user/recover", self.base_url);
+    let resp = self
+      .cloud_client
+      .post(&url)
+      .json(&EmailLinkParams {
+        email: email.to_owned(),
+        redirect_to,
+      })
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  /// Changes the email of the current user. A confirmation link is sent to the new email, and the
+  /// email only changes once the link is opened.
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn change_email(&self, new_email: &str) -> Result<(), AppResponseError> {
+    let url = format!("{}/api/user/email", self.base_url);
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(&ChangeEmailParams {
+        new_email: new_email.to_owned(),
+      })
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
   }
 
   /// Attempts to sign in using a URL, extracting refresh_token from the URL.
@@ -450,7 +517,7 @@ impl Client {
   #[inline]
   async fn verify_token_cloud(&self, access_token: &str) -> Result<bool, AppResponseError> {
     let url = format!("{}/api/user/verify/{}", self.base_url, access_token);
-    let resp = self.cloud_client.get(&url).send().await?;
+    let resp = self.cloud_client.get(&url).send_with_retry(self).await?;
     let sign_in_resp: SignInTokenResponse = AppResponse::from_response(resp).await?.into_data()?;
     Ok(sign_in_resp.is_new)
   }
@@ -587,7 +654,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<AFUserProfile>::from_response(resp)
@@ -595,13 +662,144 @@ impl Client {
       .into_data()
   }
 
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn get_notification_preferences(
+    &self,
+  ) -> Result<NotificationPreferences, AppResponseError> {
+    let url = format!("{}/api/user/notification-preferences", self.base_url);
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<NotificationPreferences>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Updates the preferences of the user in all the workspaces, except the ones set for a
+  /// workspace with [Self::update_workspace_notification_preferences].
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn update_notification_preferences(
+    &self,
+    params: &UpdateNotificationPreferencesParams,
+  ) -> Result<NotificationPreferences, AppResponseError> {
+    let url = format!("{}/api/user/notification-preferences", self.base_url);
+    let resp = self
+      .http_client_with_auth(Method::PUT, &url)
+      .await?
+      .json(params)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<NotificationPreferences>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Returns the preferences that apply in the workspace.
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn get_workspace_notification_preferences(
+    &self,
+    workspace_id: &str,
+  ) -> Result<NotificationPreferences, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/notification-preferences",
+      self.base_url, workspace_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<NotificationPreferences>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn update_workspace_notification_preferences(
+    &self,
+    workspace_id: &str,
+    params: &UpdateNotificationPreferencesParams,
+  ) -> Result<NotificationPreferences, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/notification-preferences",
+      self.base_url, workspace_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::PUT, &url)
+      .await?
+      .json(params)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<NotificationPreferences>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Removes the preferences set for the workspace, the ones of the user apply to it again.
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn reset_workspace_notification_preferences(
+    &self,
+    workspace_id: &str,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/notification-preferences",
+      self.base_url, workspace_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::DELETE, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  /// Registers the push token of the device, the high priority notifications of the user are
+  /// pushed to it.
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn register_push_device(
+    &self,
+    params: &RegisterPushDeviceParams,
+  ) -> Result<(), AppResponseError> {
+    let url = format!("{}/api/user/push-device", self.base_url);
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(params)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn unregister_push_device(&self, token: &str) -> Result<(), AppResponseError> {
+    let url = format!("{}/api/user/push-device", self.base_url);
+    let resp = self
+      .http_client_with_auth(Method::DELETE, &url)
+      .await?
+      .json(&UnregisterPushDeviceParams {
+        token: token.to_string(),
+      })
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
   #[instrument(level = "info", skip_all, err)]
   pub async fn get_user_workspace_info(&self) -> Result<AFUserWorkspaceInfo, AppResponseError> {
     let url = format!("{}/api/user/workspace", self.base_url);
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<AFUserWorkspaceInfo>::from_response(resp)
@@ -615,7 +813,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::DELETE, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()?;
@@ -632,7 +830,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(&params)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<AFWorkspace>::from_response(resp)
@@ -640,6 +838,48 @@ impl Client {
       .into_data()
   }
 
+  /// Clones the workspace into a new workspace owned by the user. The collabs and the files are
+  /// copied in the background, the progress can be polled with [Client::get_workspace_clone_task].
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn clone_workspace(
+    &self,
+    workspace_id: &str,
+    params: CloneWorkspaceParams,
+  ) -> Result<WorkspaceCloneTask, AppResponseError> {
+    let url = format!("{}/api/workspace/{}/clone", self.base_url, workspace_id);
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(&params)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<WorkspaceCloneTask>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn get_workspace_clone_task(
+    &self,
+    workspace_id: &str,
+    task_id: &str,
+  ) -> Result<WorkspaceCloneTask, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/clone/{}",
+      self.base_url, workspace_id, task_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<WorkspaceCloneTask>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
   #[instrument(level = "info", skip_all, err)]
   pub async fn patch_workspace(&self, params: PatchWorkspaceParam) -> Result<(), AppResponseError> {
     let url = format!("{}/api/workspace", self.base_url);
@@ -647,7 +887,7 @@ impl Client {
       .http_client_with_auth(Method::PATCH, &url)
       .await?
       .json(&params)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()
@@ -669,7 +909,7 @@ impl Client {
       .http_client_with_auth(Method::GET, &url)
       .await?
       .query(&param)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<Vec<AFWorkspace>>::from_response(resp)
@@ -704,7 +944,7 @@ impl Client {
         depth,
         root_view_id,
       })
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<FolderView>::from_response(resp)
@@ -712,13 +952,35 @@ impl Client {
       .into_data()
   }
 
+  /// Rebuilds the folder of the workspace from the documents and databases of the workspace.
+  /// Only the owner of the workspace can rebuild its folder.
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn rebuild_workspace_folder(
+    &self,
+    workspace_id: &str,
+  ) -> Result<RebuiltWorkspaceFolder, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/folder/rebuild",
+      self.base_url, workspace_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<RebuiltWorkspaceFolder>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
   #[instrument(level = "info", skip_all, err)]
   pub async fn open_workspace(&self, workspace_id: &str) -> Result<AFWorkspace, AppResponseError> {
     let url = format!("{}/api/workspace/{}/open", self.base_url, workspace_id);
     let resp = self
       .http_client_with_auth(Method::PUT, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<AFWorkspace>::from_response(resp)
@@ -735,7 +997,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<FavoriteSectionItems>::from_response(resp)
@@ -752,7 +1014,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<RecentSectionItems>::from_response(resp)
@@ -769,7 +1031,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<TrashSectionItems>::from_response(resp)
@@ -838,7 +1100,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(&params)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()
@@ -868,7 +1130,7 @@ impl Client {
         provider_access_token,
         provider_refresh_token,
       })
-      .send()
+      .send_with_retry(self)
       .await?;
 
     log_request_id(&resp);
@@ -887,7 +1149,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<AFSnapshotMetas>::from_response(resp)
@@ -909,7 +1171,7 @@ impl Client {
       .http_client_with_auth(Method::GET, &url)
       .await?
       .json(&params)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<SnapshotData>::from_response(resp)
@@ -931,7 +1193,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(&collab_type)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<AFSnapshotMeta>::from_response(resp)
@@ -939,6 +1201,73 @@ impl Client {
       .into_data()
   }
 
+  pub async fn create_checkpoint(
+    &self,
+    workspace_id: &str,
+    object_id: &str,
+    params: CreateCollabCheckpointParams,
+  ) -> Result<AFCollabCheckpoint, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/{}/checkpoint",
+      self.base_url, workspace_id, object_id,
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(&params)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<AFCollabCheckpoint>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Reverts the object to the given checkpoint. Returns the checkpoint that was created for the
+  /// state of the object before the revert.
+  pub async fn revert_to_checkpoint(
+    &self,
+    workspace_id: &str,
+    object_id: &str,
+    checkpoint_id: i64,
+    collab_type: CollabType,
+  ) -> Result<AFCollabCheckpoint, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/{}/checkpoint/{}/revert",
+      self.base_url, workspace_id, object_id, checkpoint_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(&RevertCollabCheckpointParams { collab_type })
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<AFCollabCheckpoint>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn get_collab_history(
+    &self,
+    workspace_id: &str,
+    object_id: &str,
+  ) -> Result<AFCollabHistory, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/{}/history",
+      self.base_url, workspace_id, object_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<AFCollabHistory>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
   pub async fn ws_connect_info(&self, auto_refresh: bool) -> Result<ConnectInfo, AppResponseError> {
     if auto_refresh {
       self
@@ -953,9 +1282,51 @@ impl Client {
       access_token: self.access_token()?,
       client_version: self.client_version.clone(),
       device_id: self.device_id.clone(),
+      workspace_id: self.ws_workspace_id.read().clone(),
+      region_latencies: self.region_latencies.read().clone(),
     })
   }
 
+  /// Sets the workspace opened by the user. The server hints the websocket to the region the
+  /// workspace resides in on the next connection.
+  pub fn set_ws_workspace_id(&self, workspace_id: Option<String>) {
+    *self.ws_workspace_id.write() = workspace_id;
+  }
+
+  /// Sets the latency, in milliseconds, to the regions of the server sent in the websocket
+  /// handshake.
+  pub fn set_region_latencies(&self, latencies: BTreeMap<String, u32>) {
+    *self.region_latencies.write() = latencies;
+  }
+
+  /// Measures the latency to the regions of the server with a request to the server info of each
+  /// region, and sends them in the next websocket handshakes. The regions that can't be reached
+  /// are left out.
+  #[instrument(level = "info", skip_all)]
+  pub async fn measure_region_latencies(&self) -> Result<BTreeMap<String, u32>, AppResponseError> {
+    let server_info = self.get_server_info().await?;
+    let mut latencies = BTreeMap::new();
+    for region in server_info.regions {
+      let url = format!("{}/api/server", region.base_url);
+      let start = Instant::now();
+      match self
+        .cloud_client
+        .get(&url)
+        .timeout(Duration::from_secs(5))
+        .send()
+        .await
+      {
+        Ok(resp) if resp.status().is_success() => {
+          latencies.insert(region.name, start.elapsed().as_millis() as u32);
+        },
+        Ok(resp) => warn!("region {} answered {}", region.name, resp.status()),
+        Err(err) => warn!("failed to reach region {}: {}", region.name, err),
+      }
+    }
+    self.set_region_latencies(latencies.clone());
+    Ok(latencies)
+  }
+
   #[instrument(level = "info", skip_all)]
   pub async fn get_workspace_usage(
     &self,
@@ -965,7 +1336,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<WorkspaceSpaceUsage>::from_response(resp)
@@ -979,7 +1350,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     if resp.status() == StatusCode::NOT_FOUND {
       Err(AppResponseError::new(
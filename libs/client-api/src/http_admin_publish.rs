@@ -0,0 +1,85 @@
+use reqwest::Method;
+use tracing::instrument;
+use uuid::Uuid;
+
+use shared_entity::dto::admin_dto::{PublishReportStatus, PublishedViewReport};
+use shared_entity::response::{AppResponse, AppResponseError};
+
+use crate::http::log_request_id;
+use crate::retry::SendWithRetry;
+use crate::Client;
+
+impl Client {
+  /// Lists the abuse reports on published views with the given status, the open ones by default,
+  /// oldest first. Only for the administrators of the instance.
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn admin_list_publish_reports(
+    &self,
+    status: Option<PublishReportStatus>,
+    offset: Option<i64>,
+    limit: Option<i64>,
+  ) -> Result<Vec<PublishedViewReport>, AppResponseError> {
+    let url = format!("{}/api/admin/publish-reports", self.base_url);
+    let mut query = vec![];
+    if let Some(status) = status {
+      query.push(("status", status.as_str().to_string()));
+    }
+    if let Some(offset) = offset {
+      query.push(("offset", offset.to_string()));
+    }
+    if let Some(limit) = limit {
+      query.push(("limit", limit.to_string()));
+    }
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .query(&query)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<Vec<PublishedViewReport>>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Closes the report, the view stays published. Only for the administrators of the instance.
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn admin_dismiss_publish_report(
+    &self,
+    report_id: &Uuid,
+  ) -> Result<(), AppResponseError> {
+    self
+      .admin_publish_report_request(report_id, "dismiss")
+      .await
+  }
+
+  /// Unpublishes the reported view and closes its open reports. Only for the administrators of
+  /// the instance.
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn admin_unpublish_reported_view(
+    &self,
+    report_id: &Uuid,
+  ) -> Result<(), AppResponseError> {
+    self
+      .admin_publish_report_request(report_id, "unpublish")
+      .await
+  }
+
+  async fn admin_publish_report_request(
+    &self,
+    report_id: &Uuid,
+    action: &str,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/admin/publish-reports/{}/{}",
+      self.base_url, report_id, action
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+}
@@ -0,0 +1,106 @@
+use reqwest::Method;
+use tracing::instrument;
+use uuid::Uuid;
+
+use shared_entity::dto::admin_dto::{AdminAuditLog, AdminBulkUserParams, AdminBulkUserResult};
+use shared_entity::response::{AppResponse, AppResponseError};
+
+use crate::http::log_request_id;
+use crate::retry::SendWithRetry;
+use crate::Client;
+
+impl Client {
+  /// Deactivates the users, who can not sign in anymore. Only for the administrators of the
+  /// instance.
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn admin_deactivate_users(
+    &self,
+    user_uuids: Vec<Uuid>,
+  ) -> Result<AdminBulkUserResult, AppResponseError> {
+    self.admin_bulk_user_request("deactivate", user_uuids).await
+  }
+
+  /// Deletes the users and the workspaces they own. Only for the administrators of the instance.
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn admin_delete_users(
+    &self,
+    user_uuids: Vec<Uuid>,
+  ) -> Result<AdminBulkUserResult, AppResponseError> {
+    self.admin_bulk_user_request("delete", user_uuids).await
+  }
+
+  /// Sends the password reset email to the users. Only for the administrators of the instance.
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn admin_send_password_reset_emails(
+    &self,
+    user_uuids: Vec<Uuid>,
+  ) -> Result<AdminBulkUserResult, AppResponseError> {
+    self
+      .admin_bulk_user_request("password-reset", user_uuids)
+      .await
+  }
+
+  /// Returns all the users with their workspace counts, as CSV. Only for the administrators of the
+  /// instance.
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn admin_export_users_csv(&self) -> Result<String, AppResponseError> {
+    let url = format!("{}/api/admin/users/export", self.base_url);
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    if resp.status().is_success() {
+      Ok(resp.text().await?)
+    } else {
+      AppResponse::from_response(resp).await?.into_data()
+    }
+  }
+
+  /// Lists the audit log of the admin user operations, most recent first. Only for the
+  /// administrators of the instance.
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn admin_list_audit_logs(
+    &self,
+    offset: Option<i64>,
+    limit: Option<i64>,
+  ) -> Result<Vec<AdminAuditLog>, AppResponseError> {
+    let url = format!("{}/api/admin/users/audit-log", self.base_url);
+    let mut query = vec![];
+    if let Some(offset) = offset {
+      query.push(("offset", offset));
+    }
+    if let Some(limit) = limit {
+      query.push(("limit", limit));
+    }
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .query(&query)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<Vec<AdminAuditLog>>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  async fn admin_bulk_user_request(
+    &self,
+    action: &str,
+    user_uuids: Vec<Uuid>,
+  ) -> Result<AdminBulkUserResult, AppResponseError> {
+    let url = format!("{}/api/admin/users/{}", self.base_url, action);
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(&AdminBulkUserParams { user_uuids })
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<AdminBulkUserResult>::from_response(resp)
+      .await?
+      .into_data()
+  }
+}
@@ -1,11 +1,12 @@
 use crate::http::log_request_id;
+use crate::retry::SendWithRetry;
 use crate::Client;
 use bytes::Bytes;
 use futures_core::Stream;
 use reqwest::Method;
 use shared_entity::dto::ai_dto::{
-  CompleteTextParams, LocalAIConfig, ModelList, SummarizeRowParams, SummarizeRowResponse,
-  TranslateRowParams, TranslateRowResponse,
+  CompleteTextParams, LanguageCheckParams, LanguageCheckResponse, LocalAIConfig, ModelList,
+  SummarizeRowParams, SummarizeRowResponse, TranslateRowParams, TranslateRowResponse,
 };
 use shared_entity::response::{AppResponse, AppResponseError};
 use std::time::Duration;
@@ -22,7 +23,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(&params)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::answer_response_stream(resp).await
@@ -42,7 +43,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(&params)
-      .send()
+      .send_with_retry(self)
       .await?;
 
     log_request_id(&resp);
@@ -66,7 +67,7 @@ impl Client {
       .await?
       .json(&params)
       .timeout(Duration::from_secs(30))
-      .send()
+      .send_with_retry(self)
       .await?;
 
     log_request_id(&resp);
@@ -75,6 +76,30 @@ impl Client {
       .into_data()
   }
 
+  #[instrument(level = "info", skip_all)]
+  pub async fn language_check(
+    &self,
+    params: LanguageCheckParams,
+  ) -> Result<LanguageCheckResponse, AppResponseError> {
+    let url = format!(
+      "{}/api/ai/{}/language_check",
+      self.base_url, params.workspace_id
+    );
+
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(&params)
+      .timeout(Duration::from_secs(60))
+      .send_with_retry(self)
+      .await?;
+
+    log_request_id(&resp);
+    AppResponse::<LanguageCheckResponse>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
   #[instrument(level = "info", skip_all, err)]
   pub async fn get_local_ai_config(
     &self,
@@ -89,7 +114,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<LocalAIConfig>::from_response(resp)
@@ -103,7 +128,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<ModelList>::from_response(resp)
@@ -1,7 +1,8 @@
+use crate::retry::SendWithRetry;
 use crate::Client;
 use client_api_entity::billing_dto::{
   SetSubscriptionRecurringInterval, SubscriptionCancelRequest, SubscriptionLinkRequest,
-  SubscriptionPlanDetail, WorkspaceUsageAndLimit,
+  SubscriptionPlanDetail, WorkspaceBilling, WorkspaceUsageAndLimit,
 };
 use reqwest::Method;
 use shared_entity::dto::billing_dto::{
@@ -12,6 +13,7 @@ use shared_entity::{
   dto::billing_dto::{RecurringInterval, SubscriptionPlan, WorkspaceSubscriptionStatus},
   response::{AppResponse, AppResponseError},
 };
+use uuid::Uuid;
 
 lazy_static::lazy_static! {
   static ref BASE_BILLING_URL: Option<String> = match std::env::var("APPFLOWY_CLOUD_BASE_BILLING_URL") {
@@ -33,7 +35,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
 
     AppResponse::<String>::from_response(resp)
@@ -71,7 +73,7 @@ impl Client {
       .http_client_with_auth(Method::GET, &url)
       .await?
       .query(sub_link_req)
-      .send()
+      .send_with_retry(self)
       .await?;
 
     AppResponse::<String>::from_response(resp)
@@ -91,7 +93,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(req)
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
@@ -106,7 +108,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
 
     AppResponse::<Vec<WorkspaceSubscriptionStatus>>::from_response(resp)
@@ -122,7 +124,7 @@ impl Client {
     let portal_url = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?
       .error_for_status()?
       .json::<AppResponse<String>>()
@@ -142,7 +144,7 @@ impl Client {
     self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?
       .error_for_status()?
       .json::<AppResponse<WorkspaceUsageAndLimit>>()
@@ -150,6 +152,22 @@ impl Client {
       .into_data()
   }
 
+  /// Returns the plan, usage, limits and renewal date of the workspace, as enforced by the server.
+  pub async fn get_workspace_billing(
+    &self,
+    workspace_id: &Uuid,
+  ) -> Result<WorkspaceBilling, AppResponseError> {
+    let url = format!("{}/api/workspace/{}/billing", self.base_url, workspace_id);
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    AppResponse::<WorkspaceBilling>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
   /// Query all subscription status for a workspace
   pub async fn get_workspace_subscriptions(
     &self,
@@ -163,7 +181,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
 
     AppResponse::<Vec<WorkspaceSubscriptionStatus>>::from_response(resp)
@@ -184,7 +202,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
 
     AppResponse::<Vec<SubscriptionPlan>>::from_response(resp)
@@ -205,7 +223,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(set_sub_recur)
-      .send()
+      .send_with_retry(self)
       .await?;
 
     AppResponse::<()>::from_response(resp).await?.into_error()
@@ -219,7 +237,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
 
     AppResponse::<Vec<SubscriptionPlanDetail>>::from_response(resp)
@@ -238,7 +256,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
 
     AppResponse::<Vec<LicensedProductDetail>>::from_response(resp)
@@ -255,7 +273,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
 
     AppResponse::<UserSubscribeProduct>::from_response(resp)
@@ -275,7 +293,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
 
     AppResponse::<Vec<SubscribeProductLicense>>::from_response(resp)
@@ -292,7 +310,7 @@ impl Client {
     let resp = self
       .http_client_without_auth(Method::POST, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
 
     AppResponse::<()>::from_response(resp).await?.into_data()
@@ -311,7 +329,7 @@ impl Client {
       .http_client_without_auth(Method::GET, &url)
       .await?
       .query(&query)
-      .send()
+      .send_with_retry(self)
       .await?;
 
     AppResponse::<String>::from_response(resp)
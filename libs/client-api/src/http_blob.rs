@@ -1,4 +1,5 @@
 use crate::http::log_request_id;
+use crate::retry::SendWithRetry;
 use crate::Client;
 
 use app_error::AppError;
@@ -7,10 +8,16 @@ use futures_util::TryStreamExt;
 use mime::Mime;
 use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
 use reqwest::{header, Method, StatusCode};
-use shared_entity::dto::workspace_dto::{BlobMetadata, RepeatedBlobMetaData};
+use shared_entity::dto::admin_dto::{UpdateWorkspaceStorageParams, WorkspaceStorageInfo};
+use shared_entity::dto::workspace_dto::{
+  ApproveWorkspaceFilesParams, ApprovedWorkspaceFiles, BlobMetadata, DeleteWorkspaceFilesParams,
+  DeletedWorkspaceFiles, ListWorkspaceFilesQuery, RepeatedBlobMetaData, WorkspaceFiles,
+};
 use shared_entity::response::{AppResponse, AppResponseError};
 
-use shared_entity::dto::file_dto::PutFileResponse;
+use shared_entity::dto::file_dto::{
+  PutFileResponse, StoragePrefixAuditQuery, StoragePrefixAuditReport,
+};
 use tracing::instrument;
 use url::Url;
 
@@ -35,7 +42,7 @@ impl Client {
       .await?
       .header(header::CONTENT_TYPE, mime.to_string())
       .body(data)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     if resp.status() == StatusCode::PAYLOAD_TOO_LARGE {
@@ -64,7 +71,7 @@ impl Client {
       .await?
       .header(header::CONTENT_TYPE, mime.to_string())
       .body(data)
-      .send()
+      .send_with_retry(self)
       .await?;
 
     log_request_id(&resp);
@@ -94,7 +101,7 @@ impl Client {
       .header(header::CONTENT_TYPE, mime.to_string())
       .header(header::CONTENT_LENGTH, content_length)
       .body(data.into())
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<crate::entity::AFBlobRecord>::from_response(resp)
@@ -159,7 +166,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::DELETE, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()
@@ -180,7 +187,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
 
     log_request_id(&resp);
@@ -196,7 +203,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
 
@@ -240,7 +247,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
 
     log_request_id(&resp);
@@ -254,7 +261,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::DELETE, url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()
@@ -268,11 +275,138 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<RepeatedBlobMetaData>::from_response(resp)
       .await?
       .into_data()
   }
+
+  /// Lists the files uploaded to the workspace, ordered by file id.
+  pub async fn list_workspace_files(
+    &self,
+    workspace_id: &str,
+    query: &ListWorkspaceFilesQuery,
+  ) -> Result<WorkspaceFiles, AppResponseError> {
+    let url = format!("{}/api/workspace/{}/files", self.base_url, workspace_id);
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .query(query)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<WorkspaceFiles>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Deletes the files of the workspace with the given ids. Only for the owner of the workspace.
+  pub async fn delete_workspace_files(
+    &self,
+    workspace_id: &str,
+    file_ids: Vec<String>,
+  ) -> Result<DeletedWorkspaceFiles, AppResponseError> {
+    let url = format!("{}/api/workspace/{}/files", self.base_url, workspace_id);
+    let resp = self
+      .http_client_with_auth(Method::DELETE, &url)
+      .await?
+      .json(&DeleteWorkspaceFilesParams { file_ids })
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<DeletedWorkspaceFiles>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Approves the files uploaded by the guests of the workspace, so that they can be downloaded.
+  pub async fn approve_workspace_files(
+    &self,
+    workspace_id: &str,
+    file_ids: Vec<String>,
+  ) -> Result<ApprovedWorkspaceFiles, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/files/approve",
+      self.base_url, workspace_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(&ApproveWorkspaceFilesParams { file_ids })
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<ApprovedWorkspaceFiles>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Audits the storage of the workspace for blob keys outside of the workspace prefix and for
+  /// references to the blobs of other workspaces. Only for the administrators of the instance.
+  pub async fn audit_workspace_storage_prefix(
+    &self,
+    workspace_id: &str,
+    query: &StoragePrefixAuditQuery,
+  ) -> Result<StoragePrefixAuditReport, AppResponseError> {
+    let url = format!(
+      "{}/api/admin/file_storage/{}/prefix_audit",
+      self.base_url, workspace_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .query(query)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<StoragePrefixAuditReport>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Only for the administrators of the instance.
+  pub async fn get_workspace_storage(
+    &self,
+    workspace_id: &str,
+  ) -> Result<WorkspaceStorageInfo, AppResponseError> {
+    let url = format!(
+      "{}/api/admin/file_storage/{}/storage",
+      self.base_url, workspace_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<WorkspaceStorageInfo>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Moves the objects of the workspace to the given bucket, or back to the default storage when
+  /// the settings are None. The objects are moved in the background, [Self::get_workspace_storage]
+  /// returns the progress. Only for the administrators of the instance.
+  pub async fn relocate_workspace_storage(
+    &self,
+    workspace_id: &str,
+    params: &UpdateWorkspaceStorageParams,
+  ) -> Result<WorkspaceStorageInfo, AppResponseError> {
+    let url = format!(
+      "{}/api/admin/file_storage/{}/storage",
+      self.base_url, workspace_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::PUT, &url)
+      .await?
+      .json(params)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<WorkspaceStorageInfo>::from_response(resp)
+      .await?
+      .into_data()
+  }
 }
@@ -1,4 +1,5 @@
 use crate::http::log_request_id;
+use crate::retry::SendWithRetry;
 use crate::Client;
 
 use app_error::AppError;
@@ -8,13 +9,17 @@ use client_api_entity::chat_dto::{
 };
 use futures_core::{ready, Stream};
 use pin_project::pin_project;
-use reqwest::Method;
+use reqwest::{multipart, Method};
 use serde_json::Value;
 use shared_entity::dto::ai_dto::{
   CalculateSimilarityParams, ChatQuestionQuery, RepeatedRelatedQuestion, SimilarityResponse,
   STREAM_ANSWER_KEY, STREAM_IMAGE_KEY, STREAM_KEEP_ALIVE_KEY, STREAM_METADATA_KEY,
 };
-use shared_entity::dto::chat_dto::{ChatSettings, UpdateChatParams};
+use shared_entity::dto::chat_dto::{
+  ChatAttachment, ChatMessageFeedback, ChatMessageFeedbackParams, ChatMessageFeedbackQuery,
+  ChatMessageSearchResult, ChatSettings, GetChatThreadParams, RepeatedChatThread,
+  SearchChatMessageParams, UpdateChatParams,
+};
 use shared_entity::response::{AppResponse, AppResponseError};
 use std::pin::Pin;
 use std::task::{Context, Poll};
@@ -33,7 +38,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(&params)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()
@@ -53,7 +58,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(&params)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()
@@ -70,7 +75,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<ChatSettings>::from_response(resp)
@@ -88,7 +93,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::DELETE, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()
@@ -109,7 +114,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(&params)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<ChatMessage>::from_response(resp)
@@ -132,7 +137,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(&params)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<ChatMessage>::from_response(resp)
@@ -185,7 +190,7 @@ impl Client {
       .await?
       .timeout(Duration::from_secs(60))
       .json(&query)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     let stream = AppResponse::<serde_json::Value>::json_response_stream(resp).await?;
@@ -205,7 +210,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<ChatMessage>::from_response(resp)
@@ -229,7 +234,7 @@ impl Client {
       .http_client_with_auth(Method::PUT, &url)
       .await?
       .json(&params)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()
@@ -249,7 +254,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<RepeatedRelatedQuestion>::from_response(resp)
@@ -287,13 +292,54 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<RepeatedChatMessage>::from_response(resp)
       .await?
       .into_data()
   }
 
+  /// Search the messages of a chat, best match first.
+  pub async fn search_chat_messages(
+    &self,
+    workspace_id: &str,
+    chat_id: &str,
+    params: &SearchChatMessageParams,
+  ) -> Result<Vec<ChatMessageSearchResult>, AppResponseError> {
+    let url = format!("{}/api/chat/{workspace_id}/{chat_id}/search", self.base_url);
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .query(params)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<Vec<ChatMessageSearchResult>>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Return the threads of a chat, newest first. A thread groups a question, its answer and the
+  /// follow-up questions.
+  pub async fn get_chat_threads(
+    &self,
+    workspace_id: &str,
+    chat_id: &str,
+    params: &GetChatThreadParams,
+  ) -> Result<RepeatedChatThread, AppResponseError> {
+    let url = format!("{}/api/chat/{workspace_id}/{chat_id}/thread", self.base_url);
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .query(params)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<RepeatedChatThread>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
   /// Return list of chat messages for a chat. Each message will have author_uuid as
   /// as the author's uid, as author_uid will face precision issue in the browser environment.
   pub async fn get_chat_messages_with_author_uuid(
@@ -325,7 +371,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<RepeatedChatMessageWithAuthorUuid>::from_response(resp)
       .await?
@@ -347,7 +393,7 @@ impl Client {
       .http_client_with_auth(Method::GET, &url)
       .await?
       .query(&[("answer_message_id", answer_message_id)])
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<Option<ChatMessage>>::from_response(resp)
       .await?
@@ -366,13 +412,105 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(&params)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<SimilarityResponse>::from_response(resp)
       .await?
       .into_data()
   }
+
+  /// Rates an answer of the AI. The message_id should be the answer's id
+  pub async fn create_chat_message_feedback(
+    &self,
+    workspace_id: &str,
+    chat_id: &str,
+    message_id: i64,
+    params: ChatMessageFeedbackParams,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/chat/{workspace_id}/{chat_id}/{message_id}/feedback",
+      self.base_url
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(&params)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  /// Exports the feedback given to the answers of the AI. Only for the administrators of the
+  /// instance.
+  pub async fn export_chat_message_feedback(
+    &self,
+    query: &ChatMessageFeedbackQuery,
+  ) -> Result<Vec<ChatMessageFeedback>, AppResponseError> {
+    let url = format!("{}/api/admin/chat/feedback", self.base_url);
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .query(query)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<Vec<ChatMessageFeedback>>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Uploads a text, markdown, PDF or image file as the context of the chat. The text of PDF and
+  /// image files is extracted in the background, use [Self::list_chat_attachments] to check when
+  /// the attachment is ready.
+  pub async fn upload_chat_attachment(
+    &self,
+    workspace_id: &str,
+    chat_id: &str,
+    file_name: &str,
+    content_type: &str,
+    data: Vec<u8>,
+  ) -> Result<ChatAttachment, AppResponseError> {
+    let url = format!(
+      "{}/api/chat/{workspace_id}/{chat_id}/context/file",
+      self.base_url
+    );
+    let part = multipart::Part::bytes(data)
+      .file_name(file_name.to_string())
+      .mime_str(content_type)?;
+    let form = multipart::Form::new().part("file", part);
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .multipart(form)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<ChatAttachment>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn list_chat_attachments(
+    &self,
+    workspace_id: &str,
+    chat_id: &str,
+  ) -> Result<Vec<ChatAttachment>, AppResponseError> {
+    let url = format!(
+      "{}/api/chat/{workspace_id}/{chat_id}/context/file",
+      self.base_url
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<Vec<ChatAttachment>>::from_response(resp)
+      .await?
+      .into_data()
+  }
 }
 
 #[pin_project]
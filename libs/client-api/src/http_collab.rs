@@ -1,29 +1,41 @@
 use crate::entity::CollabType;
 use crate::http::log_request_id;
+use crate::retry::SendWithRetry;
 use crate::{blocking_brotli_compress, brotli_compress, Client};
 use anyhow::anyhow;
-use app_error::AppError;
+use app_error::{AppError, ErrorCode};
 use bytes::Bytes;
 use chrono::{DateTime, Utc};
 use client_api_entity::workspace_dto::{
-  AFDatabase, AFDatabaseField, AFDatabaseRow, AFDatabaseRowDetail, AFInsertDatabaseField,
-  AddDatatabaseRow, DatabaseRowUpdatedItem, ListDatabaseRowDetailParam,
-  ListDatabaseRowUpdatedParam, UpsertDatatabaseRow,
+  AFDatabase, AFDatabaseExternalIdField, AFDatabaseField, AFDatabaseFormToken, AFDatabaseRow,
+  AFDatabaseRowDetail, AFDatabaseRowDocument, AFDatabaseView, AFDatabaseViewSettings,
+  AFInsertDatabaseField, AFUpdateDatabaseField, AddDatatabaseRow, BulkUpsertDatabaseRow,
+  BulkUpsertDatabaseRows, BulkUpsertDatabaseRowsResponse, CreateDatabaseFormTokenParams,
+  DatabaseRowUpdatedItem, ListDatabaseRowDetailParam, ListDatabaseRowUpdatedParam,
+  SubmitDatabaseFormRow, SubmitDatabaseFormRowResponse, UpsertDatatabaseRow,
 };
 use client_api_entity::{
-  AFCollabEmbedInfo, BatchQueryCollabParams, BatchQueryCollabResult, CollabParams,
-  CreateCollabParams, DeleteCollabParams, PublishCollabItem, QueryCollab, QueryCollabParams,
-  RepeatedAFCollabEmbedInfo, UpdateCollabWebParams,
+  AFChangedCollabs, AFCollabArchiveStatus, AFCollabEditStats, AFCollabEmbedInfo, AFCollabLock,
+  AFDatabaseRowActivities, AFWorkspaceCollabs, BatchQueryCollabParams, BatchQueryCollabResult,
+  ChangedCollabQuery, CollabEditStatsQuery, CollabParams, CreateCollabParams,
+  CreateCollabUploadRequest, CreateCollabUploadResponse, CreateGlobalCommentParams,
+  DatabaseRowActivityQuery, DeleteCollabParams, DeleteGlobalCommentParams, GlobalComments,
+  LockCollabParams, PublishCollabItem, QueryCollab, QueryCollabParams, QueryCollabStreamItem,
+  RepeatedAFCollabEmbedInfo, UpdateCollabWebParams, WarmUpCollabParams, WarmUpCollabResult,
+  WorkspaceCollabsQuery,
 };
 use collab_rt_entity::collab_proto::{CollabDocStateParams, PayloadCompressionType};
 use collab_rt_entity::HttpRealtimeMessage;
-use futures::Stream;
+use futures::{future, Stream, TryStreamExt};
 use futures_util::stream;
 use prost::Message;
 use rayon::prelude::*;
 use reqwest::{Body, Method};
 use serde::Serialize;
-use shared_entity::dto::workspace_dto::{CollabResponse, CollabTypeParam, EmbeddedCollabQuery};
+use shared_entity::dto::export_dto::ExportTaskDetail;
+use shared_entity::dto::workspace_dto::{
+  CollabResponse, CollabTypeParam, EmbeddedCollabQuery, RenderCollabQuery, RenderFormat,
+};
 use shared_entity::response::{AppResponse, AppResponseError};
 use std::collections::HashMap;
 use std::future::Future;
@@ -35,6 +47,10 @@ use tokio_retry::strategy::ExponentialBackoff;
 use tokio_retry::{Action, Condition, RetryIf};
 use tracing::{event, instrument};
 
+/// Collabs whose compressed body is larger than this are uploaded to the blob storage instead of
+/// being sent to the create collab api. It matches the default payload limit of the server.
+const LARGE_COLLAB_THRESHOLD: usize = 5 * 1024 * 1024;
+
 impl Client {
   #[instrument(level = "info", skip_all, err)]
   pub async fn create_collab(&self, params: CreateCollabParams) -> Result<(), AppResponseError> {
@@ -53,6 +69,12 @@ impl Client {
     )
     .await?;
 
+    if compress_bytes.len() > LARGE_COLLAB_THRESHOLD {
+      return self
+        .upload_large_collab(&params.workspace_id, &params.object_id, compress_bytes)
+        .await;
+    }
+
     #[allow(unused_mut)]
     let mut builder = self
       .http_client_with_auth_compress(Method::POST, &url)
@@ -63,7 +85,70 @@ impl Client {
       builder = builder.timeout(std::time::Duration::from_secs(60));
     }
 
-    let resp = builder.body(compress_bytes).send().await?;
+    let resp = builder
+      .body(compress_bytes.clone())
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    match AppResponse::<()>::from_response(resp).await?.into_error() {
+      // The server may be configured with a lower limit than [LARGE_COLLAB_THRESHOLD]
+      Err(err) if err.code == ErrorCode::PayloadTooLarge => {
+        self
+          .upload_large_collab(&params.workspace_id, &params.object_id, compress_bytes)
+          .await
+      },
+      result => result,
+    }
+  }
+
+  /// Creates a collab whose body exceeds the payload limit of the create collab api: the body is
+  /// uploaded to the blob storage with a presigned url, then the server creates the collab from
+  /// the uploaded body.
+  async fn upload_large_collab(
+    &self,
+    workspace_id: &str,
+    object_id: &str,
+    compress_bytes: Vec<u8>,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/collab/{}/upload",
+      self.base_url, workspace_id, object_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(&CreateCollabUploadRequest {
+        content_length: compress_bytes.len() as u64,
+      })
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    let upload = AppResponse::<CreateCollabUploadResponse>::from_response(resp)
+      .await?
+      .into_data()?;
+
+    let upload_resp = reqwest::Client::new()
+      .put(&upload.presigned_url)
+      .header("Content-Length", compress_bytes.len())
+      .header("Content-Type", "application/octet-stream")
+      // Required by Azure Blob Storage SAS urls, ignored by S3 compatible storages.
+      .header("x-ms-blob-type", "BlockBlob")
+      .body(compress_bytes)
+      .send_with_retry(self)
+      .await?;
+    if !upload_resp.status().is_success() {
+      return Err(AppError::S3ResponseError("Cannot upload collab to S3".to_string()).into());
+    }
+
+    let url = format!(
+      "{}/api/workspace/{}/collab/{}/upload/{}/complete",
+      self.base_url, workspace_id, object_id, upload.upload_id
+    );
+    let resp = self
+      .http_client_with_auth_compress(Method::POST, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
@@ -78,7 +163,7 @@ impl Client {
       .http_client_with_auth(Method::PUT, &url)
       .await?
       .json(&params)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()
@@ -98,7 +183,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(&params)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()
@@ -127,6 +212,40 @@ impl Client {
       .await
   }
 
+  /// Streams the results of the given collabs one object at a time, as soon as the server has
+  /// fetched them. Unlike [Client::batch_get_collab], the whole batch is never held in memory, which
+  /// matters for big workspaces.
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn stream_batch_get_collab(
+    &self,
+    workspace_id: &str,
+    params: Vec<QueryCollab>,
+  ) -> Result<impl Stream<Item = Result<QueryCollabStreamItem, AppResponseError>>, AppResponseError>
+  {
+    let url = format!(
+      "{}/api/workspace/v1/{}/collab_list",
+      self.base_url, workspace_id
+    );
+    let params = BatchQueryCollabParams(params);
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(&params)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    let lines = AppResponse::<QueryCollabStreamItem>::new_line_response_stream(resp).await?;
+    Ok(
+      lines
+        .try_filter(|line| future::ready(!line.trim().is_empty()))
+        .and_then(|line| {
+          future::ready(
+            serde_json::from_str::<QueryCollabStreamItem>(&line).map_err(AppResponseError::from),
+          )
+        }),
+    )
+  }
+
   async fn send_batch_collab_request(
     &self,
     method: Method,
@@ -142,7 +261,7 @@ impl Client {
       .http_client_with_auth(method, &url)
       .await?
       .json(&params)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<BatchQueryCollabResult>::from_response(resp)
@@ -150,6 +269,31 @@ impl Client {
       .into_data()
   }
 
+  /// Ask the server to preload the given collabs into its memory cache, so that the first
+  /// editor opening them does not pay the cost of loading them from Postgres. Intended to be
+  /// called with the visible views when a folder is opened.
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn warm_up_collabs(
+    &self,
+    workspace_id: &str,
+    collabs: Vec<QueryCollab>,
+  ) -> Result<WarmUpCollabResult, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/collab_warm_up",
+      self.base_url, workspace_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(&WarmUpCollabParams { collabs })
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<WarmUpCollabResult>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
   #[instrument(level = "info", skip_all, err)]
   pub async fn delete_collab(&self, params: DeleteCollabParams) -> Result<(), AppResponseError> {
     let url = format!(
@@ -160,12 +304,248 @@ impl Client {
       .http_client_with_auth(Method::DELETE, &url)
       .await?
       .json(&params)
-      .send()
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  /// Freezes the collab. Only members with full access to the collab can lock it.
+  pub async fn lock_collab(
+    &self,
+    workspace_id: &str,
+    object_id: &str,
+    reason: Option<String>,
+  ) -> Result<AFCollabLock, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/collab/{}/lock",
+      self.base_url, workspace_id, object_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(&LockCollabParams { reason })
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<AFCollabLock>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn unlock_collab(
+    &self,
+    workspace_id: &str,
+    object_id: &str,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/collab/{}/lock",
+      self.base_url, workspace_id, object_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::DELETE, &url)
+      .await?
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
 
+  pub async fn get_collab_lock(
+    &self,
+    workspace_id: &str,
+    object_id: &str,
+  ) -> Result<Option<AFCollabLock>, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/collab/{}/lock",
+      self.base_url, workspace_id, object_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<Option<AFCollabLock>>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Returns the edit statistics of the collab, with the daily edit counts of the last `days` days.
+  pub async fn get_collab_edit_stats(
+    &self,
+    workspace_id: &str,
+    object_id: &str,
+    days: Option<u32>,
+  ) -> Result<Option<AFCollabEditStats>, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/collab/{}/stats",
+      self.base_url, workspace_id, object_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .query(&CollabEditStatsQuery { days })
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<Option<AFCollabEditStats>>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Returns the collabs of the workspace created, updated or deleted since `query.since`, oldest
+  /// change first. Use the `next_since` and `next_after` of the result to get the next changes.
+  pub async fn get_changed_collabs(
+    &self,
+    workspace_id: &str,
+    query: &ChangedCollabQuery,
+  ) -> Result<AFChangedCollabs, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/collab/changed",
+      self.base_url, workspace_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .query(query)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<AFChangedCollabs>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Lists the collabs of the workspace of `query.collab_type`, for instance `Database`, with the
+  /// names of their views. Use the `next_after` of the result to get the next collabs.
+  pub async fn list_workspace_collabs(
+    &self,
+    workspace_id: &str,
+    query: &WorkspaceCollabsQuery,
+  ) -> Result<AFWorkspaceCollabs, AppResponseError> {
+    let url = format!("{}/api/workspace/{}/collabs", self.base_url, workspace_id);
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .query(query)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<AFWorkspaceCollabs>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Returns whether the collab has been moved to the cold storage. Archived collabs are
+  /// restored the first time they are opened, which takes longer than usual.
+  pub async fn get_collab_archive_status(
+    &self,
+    workspace_id: &str,
+    object_id: &str,
+  ) -> Result<AFCollabArchiveStatus, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/collab/{}/archive",
+      self.base_url, workspace_id, object_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<AFCollabArchiveStatus>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Returns the content of a document as markdown or sanitized HTML.
+  pub async fn render_document(
+    &self,
+    workspace_id: &str,
+    object_id: &str,
+    format: RenderFormat,
+  ) -> Result<String, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/collab/{}/render",
+      self.base_url, workspace_id, object_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .query(&RenderCollabQuery { format })
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<String>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Starts exporting a document to PDF. The export runs in the background, use
+  /// [Client::get_export_task] to poll its status.
+  pub async fn export_pdf(
+    &self,
+    workspace_id: &str,
+    object_id: &str,
+  ) -> Result<ExportTaskDetail, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/collab/{}/export/pdf",
+      self.base_url, workspace_id, object_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<ExportTaskDetail>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Starts exporting the whole workspace to an archive, which can be imported with
+  /// [Client::create_appflowy_archive_import]. Only the owner of the workspace can export it. The
+  /// export runs in the background, use [Client::get_export_task] to poll its status.
+  pub async fn export_workspace_archive(
+    &self,
+    workspace_id: &str,
+  ) -> Result<ExportTaskDetail, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/export/archive",
+      self.base_url, workspace_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<ExportTaskDetail>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn get_export_task(
+    &self,
+    workspace_id: &str,
+    task_id: &str,
+  ) -> Result<ExportTaskDetail, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/export/{}",
+      self.base_url, workspace_id, task_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<ExportTaskDetail>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
   #[instrument(level = "info", skip_all, err)]
   pub async fn list_databases(
     &self,
@@ -175,7 +555,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::from_response(resp).await?.into_data()
@@ -193,7 +573,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::from_response(resp).await?.into_data()
@@ -211,7 +591,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::from_response(resp).await?.into_data()
@@ -233,12 +613,96 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(insert_field)
-      .send()
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::from_response(resp).await?.into_data()
+  }
+
+  pub async fn list_database_views(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+  ) -> Result<Vec<AFDatabaseView>, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/view",
+      self.base_url, workspace_id, database_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::from_response(resp).await?.into_data()
   }
 
+  // Replaces the group-by field, filters and/or sorts of a database view.
+  // Returns the view after the update.
+  pub async fn update_database_view_settings(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    view_id: &str,
+    settings: &AFDatabaseViewSettings,
+  ) -> Result<AFDatabaseView, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/view/{}",
+      self.base_url, workspace_id, database_id, view_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::PATCH, &url)
+      .await?
+      .json(settings)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::from_response(resp).await?.into_data()
+  }
+
+  // Updates the name, type and/or type option of a database field.
+  // Returns the field after the update.
+  pub async fn update_database_field(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    field_id: &str,
+    update_field: &AFUpdateDatabaseField,
+  ) -> Result<AFDatabaseField, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/fields/{}",
+      self.base_url, workspace_id, database_id, field_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::PUT, &url)
+      .await?
+      .json(update_field)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::from_response(resp).await?.into_data()
+  }
+
+  // Deletes a database field. The primary field can not be deleted.
+  pub async fn delete_database_field(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    field_id: &str,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/fields/{}",
+      self.base_url, workspace_id, database_id, field_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::DELETE, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::from_response(resp).await?.into_result()
+  }
+
   pub async fn list_database_row_ids_updated(
     &self,
     workspace_id: &str,
@@ -253,7 +717,7 @@ impl Client {
       .http_client_with_auth(Method::GET, &url)
       .await?
       .query(&ListDatabaseRowUpdatedParam { after })
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::from_response(resp).await?.into_data()
@@ -274,7 +738,29 @@ impl Client {
       .http_client_with_auth(Method::GET, &url)
       .await?
       .query(&ListDatabaseRowDetailParam::new(row_ids, with_doc))
-      .send()
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::from_response(resp).await?.into_data()
+  }
+
+  /// Same as [Client::list_database_row_details], with all the query options available.
+  /// Use [ListDatabaseRowDetailParam::with_expand_relations] to resolve relation cells.
+  pub async fn list_database_row_details_with_params(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    params: &ListDatabaseRowDetailParam,
+  ) -> Result<Vec<AFDatabaseRowDetail>, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/row/detail",
+      self.base_url, workspace_id, database_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .query(params)
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::from_response(resp).await?.into_data()
@@ -304,7 +790,7 @@ impl Client {
         cells: cells_by_id,
         document: row_doc_content,
       })
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::from_response(resp).await?.into_data()
@@ -333,12 +819,272 @@ impl Client {
         cells: cells_by_id,
         document: row_doc_content,
       })
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::from_response(resp).await?.into_data()
   }
 
+  pub async fn get_database_row_comments(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    row_id: &str,
+  ) -> Result<GlobalComments, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/row/{}/comment",
+      self.base_url, workspace_id, database_id, row_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<GlobalComments>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn create_database_row_comment(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    row_id: &str,
+    content: &str,
+    reply_comment_id: Option<uuid::Uuid>,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/row/{}/comment",
+      self.base_url, workspace_id, database_id, row_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(&CreateGlobalCommentParams {
+        content: content.to_string(),
+        reply_comment_id,
+      })
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  pub async fn delete_database_row_comment(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    row_id: &str,
+    comment_id: &uuid::Uuid,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/row/{}/comment",
+      self.base_url, workspace_id, database_id, row_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::DELETE, &url)
+      .await?
+      .json(&DeleteGlobalCommentParams {
+        comment_id: *comment_id,
+      })
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  /// Returns the cell changes of the row, most recent first.
+  pub async fn get_database_row_activity(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    row_id: &str,
+    query: &DatabaseRowActivityQuery,
+  ) -> Result<AFDatabaseRowActivities, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/row/{}/activity",
+      self.base_url, workspace_id, database_id, row_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .query(query)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<AFDatabaseRowActivities>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Returns the id of the document of the row, the server creates the document if the row
+  /// doesn't have one yet.
+  pub async fn open_database_row_document(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    row_id: &str,
+  ) -> Result<AFDatabaseRowDocument, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/row/{}/document",
+      self.base_url, workspace_id, database_id, row_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<AFDatabaseRowDocument>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn get_database_external_id_field(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+  ) -> Result<AFDatabaseExternalIdField, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/external-id-field",
+      self.base_url, workspace_id, database_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<AFDatabaseExternalIdField>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Sets the text field holding the ids of the rows in an external system, used by
+  /// [Self::bulk_upsert_database_rows] to find the rows to update.
+  pub async fn set_database_external_id_field(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    field_id: &str,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/external-id-field",
+      self.base_url, workspace_id, database_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::PUT, &url)
+      .await?
+      .json(&AFDatabaseExternalIdField {
+        field_id: field_id.to_string(),
+      })
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  /// Updates the rows matching the external ids and creates the others. Sending the same rows
+  /// again doesn't create new ones.
+  pub async fn bulk_upsert_database_rows(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    rows: Vec<BulkUpsertDatabaseRow>,
+  ) -> Result<BulkUpsertDatabaseRowsResponse, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/rows:bulk_upsert",
+      self.base_url, workspace_id, database_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::PUT, &url)
+      .await?
+      .json(&BulkUpsertDatabaseRows { rows })
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<BulkUpsertDatabaseRowsResponse>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// The token is only returned by this call, in [AFDatabaseFormToken::token].
+  pub async fn create_database_form_token(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    params: &CreateDatabaseFormTokenParams,
+  ) -> Result<AFDatabaseFormToken, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/form-token",
+      self.base_url, workspace_id, database_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(params)
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<AFDatabaseFormToken>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn list_database_form_tokens(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+  ) -> Result<Vec<AFDatabaseFormToken>, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/form-token",
+      self.base_url, workspace_id, database_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<Vec<AFDatabaseFormToken>>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn delete_database_form_token(
+    &self,
+    workspace_id: &str,
+    database_id: &str,
+    token_id: &str,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/database/{}/form-token/{}",
+      self.base_url, workspace_id, database_id, token_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::DELETE, &url)
+      .await?
+      .send()
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  /// Adds a row with a form token, without being signed in.
+  pub async fn submit_database_form_row(
+    &self,
+    token: &str,
+    params: &SubmitDatabaseFormRow,
+  ) -> Result<SubmitDatabaseFormRowResponse, AppResponseError> {
+    let url = format!("{}/api/public/database/{}/rows", self.base_url, token);
+    let resp = self.cloud_client.post(&url).json(params).send().await?;
+    log_request_id(&resp);
+    AppResponse::<SubmitDatabaseFormRowResponse>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
   #[instrument(level = "debug", skip_all, err)]
   pub async fn post_realtime_msg(
     &self,
@@ -356,7 +1102,7 @@ impl Client {
       .http_client_with_auth_compress(Method::POST, &url)
       .await?
       .body(body)
-      .send()
+      .send_with_retry(self)
       .await?;
     crate::http::log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()
@@ -407,7 +1153,7 @@ impl Client {
       .await?
       .timeout(Duration::from_secs(60))
       .body(body)
-      .send()
+      .send_with_retry(self)
       .await?;
 
     log_request_id(&resp);
@@ -440,7 +1186,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .body(Body::wrap_stream(publish_collab_stream))
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
@@ -460,7 +1206,7 @@ impl Client {
       .await?
       .header("Content-Type", "application/json")
       .query(&CollabTypeParam { collab_type })
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<AFCollabEmbedInfo>::from_response(resp)
@@ -481,7 +1227,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(&params)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     let data = AppResponse::<RepeatedAFCollabEmbedInfo>::from_response(resp)
@@ -527,7 +1273,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .body(Bytes::from(encoded_payload))
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     if resp.status().is_success() {
@@ -637,7 +1383,7 @@ impl Action for GetCollabAction {
         .http_client_with_auth(Method::GET, &url)
         .await?
         .query(&CollabTypeParam { collab_type })
-        .send()
+        .send_with_retry(&client)
         .await?;
       log_request_id(&resp);
       let resp = AppResponse::<CollabResponse>::from_response(resp).await?;
@@ -0,0 +1,59 @@
+use reqwest::Method;
+use tracing::instrument;
+
+use shared_entity::dto::feature_flag_dto::{FeatureFlag, UpsertFeatureFlagParams};
+use shared_entity::response::{AppResponse, AppResponseError};
+
+use crate::http::log_request_id;
+use crate::retry::SendWithRetry;
+use crate::Client;
+
+impl Client {
+  /// Lists the feature flags. Only for the administrators of the instance.
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn list_feature_flags(&self) -> Result<Vec<FeatureFlag>, AppResponseError> {
+    let url = format!("{}/api/admin/feature-flags", self.base_url);
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<Vec<FeatureFlag>>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Creates or updates a feature flag. Only for the administrators of the instance.
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn upsert_feature_flag(
+    &self,
+    name: &str,
+    params: &UpsertFeatureFlagParams,
+  ) -> Result<FeatureFlag, AppResponseError> {
+    let url = format!("{}/api/admin/feature-flags/{}", self.base_url, name);
+    let resp = self
+      .http_client_with_auth(Method::PUT, &url)
+      .await?
+      .json(params)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<FeatureFlag>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Deletes a feature flag, which turns it off. Only for the administrators of the instance.
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn delete_feature_flag(&self, name: &str) -> Result<(), AppResponseError> {
+    let url = format!("{}/api/admin/feature-flags/{}", self.base_url, name);
+    let resp = self
+      .http_client_with_auth(Method::DELETE, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+}
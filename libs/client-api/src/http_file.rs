@@ -1,4 +1,5 @@
 use crate::http::log_request_id;
+use crate::retry::SendWithRetry;
 use crate::ws::{ConnectInfo, WSClientConnectURLProvider, WSClientHttpSender, WSError};
 use crate::Client;
 
@@ -9,7 +10,10 @@ use std::fs::metadata;
 use client_api_entity::{
   CompleteUploadRequest, CreateUploadRequest, CreateUploadResponse, UploadPartResponse,
 };
-use client_api_entity::{CreateImportTask, CreateImportTaskResponse};
+use client_api_entity::{
+  CreateImportTask, CreateImportTaskResponse, ImportSource, UploadImportTask,
+  UploadImportTaskResponse,
+};
 
 use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use reqwest::{multipart, Body, Method};
@@ -18,7 +22,7 @@ use std::path::Path;
 
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
-use shared_entity::dto::import_dto::UserImportTask;
+use shared_entity::dto::import_dto::{ImportPageMapping, ImportTaskDetail, UserImportTask};
 use tokio::fs::File;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio_util::codec::{BytesCodec, FramedRead};
@@ -39,7 +43,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(&req)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<CreateUploadResponse>::from_response(resp)
@@ -76,7 +80,7 @@ impl Client {
       .http_client_with_auth(Method::PUT, &url)
       .await?
       .body(body)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<UploadPartResponse>::from_response(resp)
@@ -97,7 +101,7 @@ impl Client {
       .http_client_with_auth(Method::PUT, &url)
       .await?
       .json(&req)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()
@@ -160,7 +164,7 @@ impl Client {
       .header("X-Host", self.base_url.clone())
       .header("X-Content-MD5", md5_base64)
       .header("X-Content-Length", metadata.len());
-    let resp = builder.send().await?;
+    let resp = builder.send_with_retry(self).await?;
 
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
@@ -177,6 +181,52 @@ impl Client {
   pub async fn create_import(
     &self,
     file_path: &Path,
+  ) -> Result<CreateImportTaskResponse, AppResponseError> {
+    self.create_scheduled_import(file_path, None).await
+  }
+
+  /// Same as [Self::create_import], but the import is not started before `start_after`, a unix
+  /// timestamp in seconds. The file can be uploaded right away.
+  pub async fn create_scheduled_import(
+    &self,
+    file_path: &Path,
+    start_after: Option<i64>,
+  ) -> Result<CreateImportTaskResponse, AppResponseError> {
+    self
+      .create_import_task(file_path, &[], start_after, ImportSource::Notion)
+      .await
+  }
+
+  /// Same as [Self::create_import], for a Notion export split into several zips. `file_path` is
+  /// the first part, each of the `part_paths` is uploaded to the url of the same index in
+  /// [CreateImportTaskResponse::part_presigned_urls].
+  pub async fn create_split_import(
+    &self,
+    file_path: &Path,
+    part_paths: &[&Path],
+  ) -> Result<CreateImportTaskResponse, AppResponseError> {
+    self
+      .create_import_task(file_path, part_paths, None, ImportSource::Notion)
+      .await
+  }
+
+  /// Same as [Self::create_import], for a workspace archive exported by
+  /// [Self::export_workspace_archive], possibly from another instance.
+  pub async fn create_appflowy_archive_import(
+    &self,
+    file_path: &Path,
+  ) -> Result<CreateImportTaskResponse, AppResponseError> {
+    self
+      .create_import_task(file_path, &[], None, ImportSource::AppFlowy)
+      .await
+  }
+
+  async fn create_import_task(
+    &self,
+    file_path: &Path,
+    part_paths: &[&Path],
+    start_after: Option<i64>,
+    source: ImportSource,
   ) -> Result<CreateImportTaskResponse, AppResponseError> {
     let url = format!("{}/api/import/create", self.base_url);
     let file_name = file_path
@@ -185,16 +235,23 @@ impl Client {
       .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
 
     let content_length = tokio::fs::metadata(file_path).await?.len();
+    let mut part_content_lengths = Vec::with_capacity(part_paths.len());
+    for part_path in part_paths {
+      part_content_lengths.push(tokio::fs::metadata(part_path).await?.len());
+    }
     let params = CreateImportTask {
       workspace_name: file_name.clone(),
       content_length,
+      start_after,
+      source,
+      part_content_lengths,
     };
     let resp = self
       .http_client_with_auth(Method::POST, &url)
       .await?
       .header("X-Host", self.base_url.clone())
       .json(&params)
-      .send()
+      .send_with_retry(self)
       .await?;
 
     log_request_id(&resp);
@@ -228,8 +285,10 @@ impl Client {
       .put(url)
       .header("Content-Length", file_size)
       .header("Content-Type", "application/zip")
+      // Required by Azure Blob Storage SAS urls, ignored by S3 compatible storages.
+      .header("x-ms-blob-type", "BlockBlob")
       .body(stream_body)
-      .send()
+      .send_with_retry(self)
       .await?;
 
     if !upload_resp.status().is_success() {
@@ -240,18 +299,97 @@ impl Client {
     Ok(())
   }
 
+  /// Sends the file and creates its import task in a single request. The server streams the file
+  /// to the blob storage, there is no presigned url to upload to.
+  pub async fn upload_and_import(
+    &self,
+    file_path: &Path,
+    source: ImportSource,
+  ) -> Result<UploadImportTaskResponse, AppResponseError> {
+    let workspace_name = file_path
+      .file_stem()
+      .map(|s| s.to_string_lossy().to_string())
+      .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let file = File::open(&file_path).await?;
+    let stream = FramedRead::new(file, BytesCodec::new());
+    let file_part = multipart::Part::stream(reqwest::Body::wrap_stream(stream))
+      .file_name(workspace_name.clone())
+      .mime_str("application/zip")?;
+    let form = multipart::Form::new().part("file", file_part);
+
+    let url = format!("{}/api/import/upload", self.base_url);
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .header("X-Host", self.base_url.clone())
+      .query(&UploadImportTask {
+        workspace_name,
+        source,
+      })
+      .multipart(form)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<UploadImportTaskResponse>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
   pub async fn get_import_list(&self) -> Result<UserImportTask, AppResponseError> {
     let url = format!("{}/api/import", self.base_url);
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
 
     AppResponse::<UserImportTask>::from_response(resp)
       .await?
       .into_data()
   }
+
+  /// Returns the status of the import task, with its summary once the task is done.
+  pub async fn get_import_task(&self, task_id: &str) -> Result<ImportTaskDetail, AppResponseError> {
+    let url = format!("{}/api/import/{}", self.base_url, task_id);
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<ImportTaskDetail>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// The views created for the pages of the imported file, empty until the import is completed.
+  pub async fn get_import_page_mapping(
+    &self,
+    task_id: &str,
+  ) -> Result<Vec<ImportPageMapping>, AppResponseError> {
+    let url = format!("{}/api/import/{}/page-mapping", self.base_url, task_id);
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<Vec<ImportPageMapping>>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Cancels an import task that hasn't been started yet.
+  pub async fn cancel_import(&self, task_id: &str) -> Result<(), AppResponseError> {
+    let url = format!("{}/api/import/{}/cancel", self.base_url, task_id);
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
 }
 
 #[async_trait]
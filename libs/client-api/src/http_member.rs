@@ -1,4 +1,5 @@
 use crate::http::log_request_id;
+use crate::retry::SendWithRetry;
 use crate::Client;
 use client_api_entity::{
   AFWorkspaceInvitation, AFWorkspaceInvitationStatus, AFWorkspaceMember, QueryWorkspaceMember,
@@ -18,7 +19,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(&())
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()
@@ -37,7 +38,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<Vec<AFWorkspaceMember>>::from_response(resp)
@@ -56,7 +57,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(&invitations)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()?;
@@ -72,7 +73,7 @@ impl Client {
     if let Some(status) = status {
       builder = builder.query(&[("status", status)])
     }
-    let resp = builder.send().await?;
+    let resp = builder.send_with_retry(self).await?;
     log_request_id(&resp);
     let res = AppResponse::<Vec<AFWorkspaceInvitation>>::from_response(resp).await?;
     res.into_data()
@@ -86,7 +87,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     let res: AppResponse<AFWorkspaceInvitation> = AppResponse::from_response(resp).await?;
@@ -105,7 +106,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(&())
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()?;
@@ -129,7 +130,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(&members)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()?;
@@ -151,7 +152,7 @@ impl Client {
       .http_client_with_auth(Method::PUT, &url)
       .await?
       .json(&changeset)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()?;
@@ -174,7 +175,7 @@ impl Client {
       .http_client_with_auth(Method::DELETE, &url)
       .await?
       .json(&payload)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()?;
@@ -193,7 +194,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<AFWorkspaceMember>::from_response(resp)
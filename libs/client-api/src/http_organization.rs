@@ -0,0 +1,182 @@
+use client_api_entity::org_dto::{
+  AddOrganizationMembersParams, CreateOrganizationParams, Organization, OrganizationMember,
+  OrganizationUsage, RemoveOrganizationMembersParams, UpdateOrganizationSessionPolicyParams,
+};
+use reqwest::Method;
+use shared_entity::response::{AppResponse, AppResponseError};
+use uuid::Uuid;
+
+use crate::retry::SendWithRetry;
+use crate::{log_request_id, Client};
+
+impl Client {
+  /// Creates an organization, the user becomes its first admin.
+  pub async fn create_organization(
+    &self,
+    params: &CreateOrganizationParams,
+  ) -> Result<Organization, AppResponseError> {
+    let url = format!("{}/api/organization", self.base_url);
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(params)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<Organization>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Returns the organizations the user belongs to.
+  pub async fn list_organizations(&self) -> Result<Vec<Organization>, AppResponseError> {
+    let url = format!("{}/api/organization", self.base_url);
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<Vec<Organization>>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn get_organization(&self, org_id: &Uuid) -> Result<Organization, AppResponseError> {
+    let url = format!("{}/api/organization/{}", self.base_url, org_id);
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<Organization>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Moves a workspace owned by the user to the organization.
+  pub async fn attach_workspace_to_organization(
+    &self,
+    org_id: &Uuid,
+    workspace_id: &Uuid,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/organization/{}/workspace/{}",
+      self.base_url, org_id, workspace_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::PUT, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  pub async fn detach_workspace_from_organization(
+    &self,
+    org_id: &Uuid,
+    workspace_id: &Uuid,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/organization/{}/workspace/{}",
+      self.base_url, org_id, workspace_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::DELETE, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  /// Returns the members of the organization and of its workspaces.
+  pub async fn list_organization_members(
+    &self,
+    org_id: &Uuid,
+  ) -> Result<Vec<OrganizationMember>, AppResponseError> {
+    let url = format!("{}/api/organization/{}/member", self.base_url, org_id);
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<Vec<OrganizationMember>>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn add_organization_members(
+    &self,
+    org_id: &Uuid,
+    params: &AddOrganizationMembersParams,
+  ) -> Result<(), AppResponseError> {
+    let url = format!("{}/api/organization/{}/member", self.base_url, org_id);
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(params)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  pub async fn remove_organization_members(
+    &self,
+    org_id: &Uuid,
+    params: &RemoveOrganizationMembersParams,
+  ) -> Result<(), AppResponseError> {
+    let url = format!("{}/api/organization/{}/member", self.base_url, org_id);
+    let resp = self
+      .http_client_with_auth(Method::DELETE, &url)
+      .await?
+      .json(params)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  /// Returns the usage of all the workspaces of the organization.
+  pub async fn get_organization_usage(
+    &self,
+    org_id: &Uuid,
+  ) -> Result<OrganizationUsage, AppResponseError> {
+    let url = format!("{}/api/organization/{}/usage", self.base_url, org_id);
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<OrganizationUsage>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Sets how many realtime sessions each user of the organization can keep open.
+  pub async fn update_organization_session_policy(
+    &self,
+    org_id: &Uuid,
+    params: &UpdateOrganizationSessionPolicyParams,
+  ) -> Result<Organization, AppResponseError> {
+    let url = format!(
+      "{}/api/organization/{}/session-policy",
+      self.base_url, org_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::PUT, &url)
+      .await?
+      .json(params)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<Organization>::from_response(resp)
+      .await?
+      .into_data()
+  }
+}
@@ -1,7 +1,10 @@
 use bytes::Bytes;
-use client_api_entity::publish_dto::DuplicatePublishedPageResponse;
+use client_api_entity::publish_dto::{DuplicatePublishedPageResponse, ReportPublishedViewParams};
 use client_api_entity::workspace_dto::{PublishInfoView, PublishedView};
-use client_api_entity::{workspace_dto::PublishedDuplicate, PublishInfo, UpdatePublishNamespace};
+use client_api_entity::{
+  workspace_dto::PublishedDuplicate, AddPublishDomain, PublishAnalytics, PublishAnalyticsQuery,
+  PublishDomainInfo, PublishInfo, UpdatePublishNamespace,
+};
 use client_api_entity::{
   CreateGlobalCommentParams, CreateReactionParams, DeleteGlobalCommentParams, DeleteReactionParams,
   GetReactionQueryParams, GlobalComments, PatchPublishedCollab, PublishInfoMeta, Reactions,
@@ -11,6 +14,7 @@ use reqwest::Method;
 use shared_entity::response::{AppResponse, AppResponseError};
 use tracing::instrument;
 
+use crate::retry::SendWithRetry;
 use crate::{log_request_id, Client};
 
 // Publisher API
@@ -28,7 +32,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<Vec<PublishInfoView>>::from_response(resp)
@@ -57,12 +61,117 @@ impl Client {
         old_namespace,
         new_namespace,
       })
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
 
+  /// Attaches a custom domain to the publish namespace of the workspace. The domain is served
+  /// once the returned TXT record is added to its DNS zone and [Client::verify_publish_domain]
+  /// succeeds.
+  pub async fn add_publish_domain(
+    &self,
+    workspace_id: &str,
+    domain: &str,
+  ) -> Result<PublishDomainInfo, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/publish-domain",
+      self.base_url, workspace_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(&AddPublishDomain {
+        domain: domain.to_string(),
+      })
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<PublishDomainInfo>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn list_publish_domains(
+    &self,
+    workspace_id: &str,
+  ) -> Result<Vec<PublishDomainInfo>, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/publish-domain",
+      self.base_url, workspace_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<Vec<PublishDomainInfo>>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn verify_publish_domain(
+    &self,
+    workspace_id: &str,
+    domain: &str,
+  ) -> Result<PublishDomainInfo, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/publish-domain/{}/verify",
+      self.base_url, workspace_id, domain
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<PublishDomainInfo>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn remove_publish_domain(
+    &self,
+    workspace_id: &str,
+    domain: &str,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/publish-domain/{}",
+      self.base_url, workspace_id, domain
+    );
+    let resp = self
+      .http_client_with_auth(Method::DELETE, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  /// Returns the daily views of the published pages of the workspace.
+  pub async fn get_publish_analytics(
+    &self,
+    workspace_id: &str,
+    query: &PublishAnalyticsQuery,
+  ) -> Result<PublishAnalytics, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/publish/analytics",
+      self.base_url, workspace_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .query(query)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<PublishAnalytics>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
   pub async fn get_workspace_publish_namespace(
     &self,
     workspace_id: &str,
@@ -74,7 +183,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<String>::from_response(resp)
@@ -92,7 +201,7 @@ impl Client {
       .http_client_with_auth(Method::PATCH, &url)
       .await?
       .json(patches)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()
@@ -108,7 +217,7 @@ impl Client {
       .http_client_with_auth(Method::DELETE, &url)
       .await?
       .json(view_ids)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()
@@ -131,7 +240,7 @@ impl Client {
         content: comment_content.to_string(),
         reply_comment_id: *reply_comment_id,
       })
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()
@@ -152,7 +261,7 @@ impl Client {
       .json(&DeleteGlobalCommentParams {
         comment_id: *comment_id,
       })
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()
@@ -175,7 +284,7 @@ impl Client {
         reaction_type: reaction_type.to_string(),
         comment_id: *comment_id,
       })
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()
@@ -198,7 +307,7 @@ impl Client {
         reaction_type: reaction_type.to_string(),
         comment_id: *comment_id,
       })
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()
@@ -217,7 +326,7 @@ impl Client {
       .http_client_with_auth(Method::PUT, &url)
       .await?
       .json(&UpdateDefaultPublishView { view_id })
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()
@@ -234,7 +343,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::DELETE, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<()>::from_response(resp).await?.into_error()
@@ -251,7 +360,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<PublishInfo>::from_response(resp)
@@ -276,12 +385,33 @@ impl Client {
       self.http_client_without_auth(Method::GET, &url).await?
     };
 
-    let resp = client.send().await?;
+    let resp = client.send_with_retry(self).await?;
     log_request_id(&resp);
     AppResponse::<GlobalComments>::from_response(resp)
       .await?
       .into_data()
   }
+
+  /// Reports a published view to the administrators of the instance.
+  pub async fn report_published_view(
+    &self,
+    view_id: &uuid::Uuid,
+    params: &ReportPublishedViewParams,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/published-info/{}/report",
+      self.base_url, view_id
+    );
+    let client = if let Ok(client) = self.http_client_with_auth(Method::POST, &url).await {
+      client
+    } else {
+      self.http_client_without_auth(Method::POST, &url).await?
+    };
+
+    let resp = client.json(params).send_with_retry(self).await?;
+    log_request_id(&resp);
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
 }
 
 // Guest API (no login required)
@@ -296,7 +426,7 @@ impl Client {
       self.base_url, view_id
     );
 
-    let resp = self.cloud_client.get(&url).send().await?;
+    let resp = self.cloud_client.get(&url).send_with_retry(self).await?;
     AppResponse::<PublishInfo>::from_response(resp)
       .await?
       .into_data()
@@ -315,7 +445,7 @@ impl Client {
     let resp = self
       .cloud_client
       .get(&url)
-      .send()
+      .send_with_retry(self)
       .await?
       .error_for_status()?;
 
@@ -341,7 +471,7 @@ impl Client {
     let resp = self
       .cloud_client
       .get(&url)
-      .send()
+      .send_with_retry(self)
       .await?
       .error_for_status()?;
 
@@ -373,7 +503,7 @@ impl Client {
     let resp = self
       .cloud_client
       .get(&url)
-      .send()
+      .send_with_retry(self)
       .await?
       .error_for_status()?;
     log_request_id(&resp);
@@ -396,7 +526,7 @@ impl Client {
       "{}/api/workspace/published/{}/{}/blob",
       self.base_url, publish_namespace, publish_name
     );
-    let resp = self.cloud_client.get(&url).send().await?;
+    let resp = self.cloud_client.get(&url).send_with_retry(self).await?;
     log_request_id(&resp);
     let bytes = resp.error_for_status()?.bytes().await?;
 
@@ -420,7 +550,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(publish_duplicate)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<DuplicatePublishedPageResponse>::from_response(resp)
@@ -443,7 +573,7 @@ impl Client {
       .query(&GetReactionQueryParams {
         comment_id: *comment_id,
       })
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<Reactions>::from_response(resp)
@@ -5,6 +5,7 @@ use reqwest::Method;
 use shared_entity::response::{AppResponse, AppResponseError};
 use uuid::Uuid;
 
+use crate::retry::SendWithRetry;
 use crate::Client;
 
 fn quick_note_resources_url(base_url: &str, workspace_id: Uuid) -> String {
@@ -28,7 +29,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(&CreateQuickNoteParams { data })
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<QuickNote>::from_response(resp)
       .await?
@@ -51,7 +52,7 @@ impl Client {
         offset,
         limit,
       })
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<QuickNotes>::from_response(resp)
       .await?
@@ -69,7 +70,7 @@ impl Client {
       .http_client_with_auth(Method::PUT, &url)
       .await?
       .json(&UpdateQuickNoteParams { data })
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
@@ -83,7 +84,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::DELETE, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
@@ -4,6 +4,7 @@ use shared_entity::dto::search_dto::SearchDocumentResponseItem;
 use shared_entity::response::{AppResponse, AppResponseError};
 
 use crate::http::log_request_id;
+use crate::retry::SendWithRetry;
 use crate::Client;
 
 impl Client {
@@ -24,7 +25,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     AppResponse::<Vec<SearchDocumentResponseItem>>::from_response(resp)
@@ -2,10 +2,16 @@ use reqwest::Method;
 use tracing::{instrument, trace};
 
 use client_api_entity::AFWorkspaceSettings;
+use shared_entity::dto::workspace_dto::{
+  HistoryCompactionReport, RetentionDryRunQuery, RetentionDryRunReport,
+  UpdateWorkspaceHistoryCompactionParams, UpdateWorkspaceRetentionPolicyParams,
+  WorkspaceHistoryCompaction, WorkspaceRetentionPolicy,
+};
 use shared_entity::response::{AppResponse, AppResponseError};
 
 use crate::entity::AFWorkspaceSettingsChange;
 use crate::http::log_request_id;
+use crate::retry::SendWithRetry;
 use crate::Client;
 
 impl Client {
@@ -22,7 +28,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     let resp = AppResponse::<AFWorkspaceSettings>::from_response(resp).await?;
@@ -45,10 +51,144 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(&changes)
-      .send()
+      .send_with_retry(self)
       .await?;
     log_request_id(&resp);
     let resp = AppResponse::<AFWorkspaceSettings>::from_response(resp).await?;
     resp.into_data()
   }
+
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn get_workspace_retention_policy<T: AsRef<str>>(
+    &self,
+    workspace_id: T,
+  ) -> Result<WorkspaceRetentionPolicy, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/retention",
+      self.base_url,
+      workspace_id.as_ref()
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<WorkspaceRetentionPolicy>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn update_workspace_retention_policy<T: AsRef<str>>(
+    &self,
+    workspace_id: T,
+    params: &UpdateWorkspaceRetentionPolicyParams,
+  ) -> Result<WorkspaceRetentionPolicy, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/retention",
+      self.base_url,
+      workspace_id.as_ref()
+    );
+    let resp = self
+      .http_client_with_auth(Method::PUT, &url)
+      .await?
+      .json(params)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<WorkspaceRetentionPolicy>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Returns what the cleanup would delete with the policy, without deleting anything.
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn dry_run_workspace_retention_policy<T: AsRef<str>>(
+    &self,
+    workspace_id: T,
+    query: &RetentionDryRunQuery,
+  ) -> Result<RetentionDryRunReport, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/retention/dry-run",
+      self.base_url,
+      workspace_id.as_ref()
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .query(query)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<RetentionDryRunReport>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn get_workspace_history_compaction<T: AsRef<str>>(
+    &self,
+    workspace_id: T,
+  ) -> Result<WorkspaceHistoryCompaction, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/history-compaction",
+      self.base_url,
+      workspace_id.as_ref()
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<WorkspaceHistoryCompaction>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn update_workspace_history_compaction<T: AsRef<str>>(
+    &self,
+    workspace_id: T,
+    params: &UpdateWorkspaceHistoryCompactionParams,
+  ) -> Result<WorkspaceHistoryCompaction, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/history-compaction",
+      self.base_url,
+      workspace_id.as_ref()
+    );
+    let resp = self
+      .http_client_with_auth(Method::PUT, &url)
+      .await?
+      .json(params)
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<WorkspaceHistoryCompaction>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Compacts the history of the workspace right away, instead of waiting for the worker.
+  #[instrument(level = "info", skip_all, err)]
+  pub async fn compact_workspace_history<T: AsRef<str>>(
+    &self,
+    workspace_id: T,
+  ) -> Result<HistoryCompactionReport, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/history-compaction/run",
+      self.base_url,
+      workspace_id.as_ref()
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    log_request_id(&resp);
+    AppResponse::<HistoryCompactionReport>::from_response(resp)
+      .await?
+      .into_data()
+  }
 }
@@ -9,6 +9,7 @@ use reqwest::Method;
 use shared_entity::response::{AppResponse, AppResponseError};
 use uuid::Uuid;
 
+use crate::retry::SendWithRetry;
 use crate::Client;
 
 fn template_api_prefix(base_url: &str) -> String {
@@ -53,7 +54,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(params)
-      .send()
+      .send_with_retry(self)
       .await?;
 
     AppResponse::<TemplateCategory>::from_response(resp)
@@ -74,7 +75,7 @@ impl Client {
         name_contains: name_contains.map(|s| s.to_string()),
         category_type,
       })
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<TemplateCategories>::from_response(resp)
       .await?
@@ -89,7 +90,7 @@ impl Client {
     let resp = self
       .http_client_without_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<TemplateCategory>::from_response(resp)
       .await?
@@ -101,7 +102,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::DELETE, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
@@ -116,7 +117,7 @@ impl Client {
       .http_client_with_auth(Method::PUT, &url)
       .await?
       .json(params)
-      .send()
+      .send_with_retry(self)
       .await?;
 
     AppResponse::<TemplateCategory>::from_response(resp)
@@ -139,7 +140,7 @@ impl Client {
         avatar_url: avatar_url.to_string(),
         account_links,
       })
-      .send()
+      .send_with_retry(self)
       .await?;
 
     AppResponse::<TemplateCreator>::from_response(resp)
@@ -158,7 +159,7 @@ impl Client {
       .query(&GetTemplateCreatorsQueryParams {
         name_contains: name_contains.map(|s| s.to_string()),
       })
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<TemplateCreators>::from_response(resp)
       .await?
@@ -173,7 +174,7 @@ impl Client {
     let resp = self
       .http_client_without_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<TemplateCreator>::from_response(resp)
       .await?
@@ -185,7 +186,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::DELETE, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
@@ -206,7 +207,7 @@ impl Client {
         avatar_url: avatar_url.to_string(),
         account_links,
       })
-      .send()
+      .send_with_retry(self)
       .await?;
 
     AppResponse::<TemplateCreator>::from_response(resp)
@@ -223,7 +224,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(params)
-      .send()
+      .send_with_retry(self)
       .await?;
 
     AppResponse::<Template>::from_response(resp)
@@ -239,7 +240,7 @@ impl Client {
     let resp = self
       .http_client_without_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
 
     AppResponse::<TemplateWithPublishInfo>::from_response(resp)
@@ -264,7 +265,7 @@ impl Client {
         is_new_template,
         name_contains,
       })
-      .send()
+      .send_with_retry(self)
       .await?;
 
     AppResponse::<Templates>::from_response(resp)
@@ -282,7 +283,7 @@ impl Client {
       .http_client_with_auth(Method::PUT, &url)
       .await?
       .json(params)
-      .send()
+      .send_with_retry(self)
       .await?;
 
     AppResponse::<Template>::from_response(resp)
@@ -295,7 +296,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::DELETE, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
 
     AppResponse::<()>::from_response(resp).await?.into_error()
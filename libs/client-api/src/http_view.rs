@@ -1,13 +1,15 @@
 use client_api_entity::workspace_dto::{
-  AppendBlockToPageParams, CreatePageDatabaseViewParams, CreatePageParams, CreateSpaceParams,
-  DuplicatePageParams, MovePageParams, Page, PageCollab, PublishPageParams, Space,
-  UpdatePageParams, UpdateSpaceParams,
+  AFCollabGuest, AFCollabMemberInfo, AFRestrictedView, AppendBlockToPageParams,
+  CreatePageDatabaseViewParams, CreatePageParams, CreateSpaceParams, DuplicatePageParams,
+  ImageAsset, InviteCollabGuestParams, MovePageParams, Page, PageCollab, PublishPageParams,
+  RemoveCollabGuestParams, RestrictViewParams, Space, UpdatePageParams, UpdateSpaceParams,
 };
-use reqwest::Method;
+use reqwest::{multipart, Method};
 use serde_json::json;
 use shared_entity::response::{AppResponse, AppResponseError};
 use uuid::Uuid;
 
+use crate::retry::SendWithRetry;
 use crate::Client;
 
 impl Client {
@@ -21,7 +23,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(params)
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<Page>::from_response(resp).await?.into_data()
   }
@@ -40,7 +42,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(params)
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
@@ -58,7 +60,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(&json!({}))
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
@@ -76,7 +78,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(&json!({}))
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
@@ -93,7 +95,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(&json!({}))
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
@@ -110,7 +112,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::DELETE, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
@@ -127,7 +129,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(&json!({}))
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
@@ -146,7 +148,7 @@ impl Client {
       .http_client_with_auth(Method::PATCH, &url)
       .await?
       .json(params)
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
@@ -163,7 +165,7 @@ impl Client {
     let resp = self
       .http_client_with_auth(Method::GET, &url)
       .await?
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<PageCollab>::from_response(resp)
       .await?
@@ -184,7 +186,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(params)
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
@@ -202,7 +204,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(&json!({}))
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
@@ -217,7 +219,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(params)
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<Space>::from_response(resp).await?.into_data()
   }
@@ -236,7 +238,7 @@ impl Client {
       .http_client_with_auth(Method::PATCH, &url)
       .await?
       .json(params)
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
@@ -255,7 +257,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(params)
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
@@ -274,7 +276,7 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(params)
-      .send()
+      .send_with_retry(self)
       .await?;
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
@@ -293,7 +295,221 @@ impl Client {
       .http_client_with_auth(Method::POST, &url)
       .await?
       .json(params)
-      .send()
+      .send_with_retry(self)
+      .await?;
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  /// Uploads the icon of the workspace. The webp sizes are generated in the background, the
+  /// original can be read right away.
+  pub async fn upload_workspace_icon(
+    &self,
+    workspace_id: Uuid,
+    file_name: &str,
+    data: Vec<u8>,
+  ) -> Result<ImageAsset, AppResponseError> {
+    let url = format!("{}/api/workspace/{}/icon", self.base_url, workspace_id);
+    self.put_image_asset(&url, file_name, data).await
+  }
+
+  pub async fn upload_page_icon(
+    &self,
+    workspace_id: Uuid,
+    view_id: &str,
+    file_name: &str,
+    data: Vec<u8>,
+  ) -> Result<ImageAsset, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/page-view/{}/icon",
+      self.base_url, workspace_id, view_id
+    );
+    self.put_image_asset(&url, file_name, data).await
+  }
+
+  pub async fn upload_page_cover(
+    &self,
+    workspace_id: Uuid,
+    view_id: &str,
+    file_name: &str,
+    data: Vec<u8>,
+  ) -> Result<ImageAsset, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/page-view/{}/cover",
+      self.base_url, workspace_id, view_id
+    );
+    self.put_image_asset(&url, file_name, data).await
+  }
+
+  async fn put_image_asset(
+    &self,
+    url: &str,
+    file_name: &str,
+    data: Vec<u8>,
+  ) -> Result<ImageAsset, AppResponseError> {
+    let part = multipart::Part::bytes(data).file_name(file_name.to_string());
+    let form = multipart::Form::new().part("file", part);
+    let resp = self
+      .http_client_with_auth(Method::PUT, url)
+      .await?
+      .multipart(form)
+      .send_with_retry(self)
+      .await?;
+    AppResponse::<ImageAsset>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn list_restricted_views(
+    &self,
+    workspace_id: Uuid,
+  ) -> Result<Vec<AFRestrictedView>, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/restricted-view",
+      self.base_url, workspace_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    AppResponse::<Vec<AFRestrictedView>>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Only the members can see or sync the view and the views below it.
+  pub async fn restrict_view(
+    &self,
+    workspace_id: Uuid,
+    view_id: &str,
+    params: &RestrictViewParams,
+  ) -> Result<AFRestrictedView, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/restricted-view/{}",
+      self.base_url, workspace_id, view_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::PUT, &url)
+      .await?
+      .json(params)
+      .send_with_retry(self)
+      .await?;
+    AppResponse::<AFRestrictedView>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  pub async fn unrestrict_view(
+    &self,
+    workspace_id: Uuid,
+    view_id: &str,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/restricted-view/{}",
+      self.base_url, workspace_id, view_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::DELETE, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  /// The guests the page is shared with, including the emails that did not sign up yet.
+  pub async fn list_collab_guests(
+    &self,
+    workspace_id: Uuid,
+    object_id: &str,
+  ) -> Result<Vec<AFCollabGuest>, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/collab/{}/guest",
+      self.base_url, workspace_id, object_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    AppResponse::<Vec<AFCollabGuest>>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// The users who can access the page, with their access level and whether it comes from their
+  /// role in the workspace or from the page being shared with them.
+  pub async fn list_collab_members(
+    &self,
+    workspace_id: Uuid,
+    object_id: &str,
+  ) -> Result<Vec<AFCollabMemberInfo>, AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/collab/{}/members",
+      self.base_url, workspace_id, object_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::GET, &url)
+      .await?
+      .send_with_retry(self)
+      .await?;
+    AppResponse::<Vec<AFCollabMemberInfo>>::from_response(resp)
+      .await?
+      .into_data()
+  }
+
+  /// Shares the page with an email that is not a member of the workspace. The guest only sees
+  /// the pages shared with it.
+  pub async fn invite_collab_guest(
+    &self,
+    workspace_id: Uuid,
+    object_id: &str,
+    params: &InviteCollabGuestParams,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/collab/{}/guest",
+      self.base_url, workspace_id, object_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .json(params)
+      .send_with_retry(self)
+      .await?;
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  pub async fn remove_collab_guest(
+    &self,
+    workspace_id: Uuid,
+    object_id: &str,
+    params: &RemoveCollabGuestParams,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/collab/{}/guest",
+      self.base_url, workspace_id, object_id
+    );
+    let resp = self
+      .http_client_with_auth(Method::DELETE, &url)
+      .await?
+      .json(params)
+      .send_with_retry(self)
+      .await?;
+    AppResponse::<()>::from_response(resp).await?.into_error()
+  }
+
+  pub async fn convert_guest_to_member(
+    &self,
+    workspace_id: Uuid,
+    guest_uid: i64,
+  ) -> Result<(), AppResponseError> {
+    let url = format!(
+      "{}/api/workspace/{}/guest/{}/convert-to-member",
+      self.base_url, workspace_id, guest_uid
+    );
+    let resp = self
+      .http_client_with_auth(Method::POST, &url)
+      .await?
+      .send_with_retry(self)
       .await?;
     AppResponse::<()>::from_response(resp).await?.into_error()
   }
@@ -3,9 +3,13 @@ mod http_ai;
 mod http_billing;
 
 mod http_access_request;
+mod http_admin_publish;
+mod http_admin_user;
 mod http_blob;
 mod http_collab;
+mod http_feature_flag;
 mod http_member;
+mod http_organization;
 mod http_publish;
 mod http_quick_note;
 mod http_search;
@@ -22,6 +26,7 @@ mod http_settings;
 pub mod notify;
 mod ping;
 mod retry;
+pub use retry::RetryPolicy;
 pub mod ws;
 
 pub mod error {
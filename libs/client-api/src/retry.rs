@@ -2,19 +2,24 @@ use crate::notify::ClientToken;
 use crate::ws::{
   ConnectState, ConnectStateNotify, StateNotify, WSClientConnectURLProvider, WSError,
 };
+use crate::Client;
 
 use app_error::gotrue::GoTrueError;
 use client_websocket::{connect_async, WebSocketStream};
 use gotrue::grant::{Grant, RefreshTokenGrant};
 use parking_lot::RwLock;
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use shared_entity::response::{AppResponseError, ErrorCode};
+use std::collections::hash_map::RandomState;
 use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
 use std::pin::Pin;
 use std::sync::{Arc, Weak};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio_retry::strategy::FixedInterval;
 use tokio_retry::{Action, Condition, RetryIf};
 use tokio_tungstenite::tungstenite::http::HeaderMap;
-use tracing::{debug, info, trace};
+use tracing::{debug, info, trace, warn};
 
 pub(crate) struct RefreshTokenAction {
   token: Arc<RwLock<ClientToken>>,
@@ -76,13 +81,17 @@ impl Condition<GoTrueError> for RefreshTokenRetryCondition {
   }
 }
 
+/// Connects to the region endpoint when one is set, see [crate::ws::WSClient::region_endpoint].
+/// The region endpoint is dropped when it can't be reached, and the next attempt connects to the
+/// url of the provider.
 pub async fn retry_connect(
   connect_provider: Arc<dyn WSClientConnectURLProvider>,
   state_notify: Weak<StateNotify>,
+  region_endpoint: Arc<RwLock<Option<String>>>,
 ) -> Result<WebSocketStream, WSError> {
   let stream = RetryIf::spawn(
     FixedInterval::new(Duration::from_secs(15)),
-    ConnectAction::new(connect_provider),
+    ConnectAction::new(connect_provider, region_endpoint),
     RetryCondition { state_notify },
   )
   .await?;
@@ -91,11 +100,18 @@ pub async fn retry_connect(
 
 struct ConnectAction {
   connect_provider: Arc<dyn WSClientConnectURLProvider>,
+  region_endpoint: Arc<RwLock<Option<String>>>,
 }
 
 impl ConnectAction {
-  fn new(connect_provider: Arc<dyn WSClientConnectURLProvider>) -> Self {
-    Self { connect_provider }
+  fn new(
+    connect_provider: Arc<dyn WSClientConnectURLProvider>,
+    region_endpoint: Arc<RwLock<Option<String>>>,
+  ) -> Self {
+    Self {
+      connect_provider,
+      region_endpoint,
+    }
   }
 }
 
@@ -106,9 +122,13 @@ impl Action for ConnectAction {
 
   fn run(&mut self) -> Self::Future {
     let connect_provider = self.connect_provider.clone();
+    let region_endpoint = self.region_endpoint.clone();
     Box::pin(async move {
       info!("🔵websocket start connecting");
-      let url = connect_provider.connect_ws_url();
+      let region_url = region_endpoint.read().clone();
+      let url = region_url
+        .clone()
+        .unwrap_or_else(|| connect_provider.connect_ws_url());
       let headers: HeaderMap = connect_provider.connect_info().await?.into();
       trace!("websocket url:{}, headers: {:?}", url, headers);
       match connect_async(&url, headers).await {
@@ -116,7 +136,13 @@ impl Action for ConnectAction {
           info!("🟢websocket connect success");
           Ok(stream)
         },
-        Err(e) => Err(e.into()),
+        Err(e) => {
+          if region_url.is_some() {
+            warn!("failed to connect to region endpoint {}: {}", url, e);
+            region_endpoint.write().take();
+          }
+          Err(e.into())
+        },
       }
     })
   }
@@ -138,3 +164,201 @@ impl Condition<WSError> for RetryCondition {
     true
   }
 }
+
+/// Retry policy of the http requests sent by the [Client](crate::Client).
+///
+/// Only the requests with an idempotent method (GET, HEAD, PUT, DELETE, OPTIONS) are retried, when
+/// they fail to reach the server or when the server answers with 502, 503 or 504. The delay
+/// between two attempts doubles from `initial_backoff` up to `max_backoff`, and a random jitter
+/// spreads the retries of the clients that failed at the same time.
+///
+/// The circuit breaker opens after `circuit_breaker_threshold` consecutive failed attempts, of any
+/// method. While it is open the requests fail immediately, until `circuit_breaker_cooldown` has
+/// elapsed. A threshold of 0 disables the circuit breaker.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+  pub(crate) max_retries: u32,
+  pub(crate) initial_backoff: Duration,
+  pub(crate) max_backoff: Duration,
+  pub(crate) circuit_breaker_threshold: u32,
+  pub(crate) circuit_breaker_cooldown: Duration,
+}
+
+impl RetryPolicy {
+  /// Every request is sent once and the circuit breaker is disabled.
+  pub fn disabled() -> Self {
+    Self {
+      max_retries: 0,
+      circuit_breaker_threshold: 0,
+      ..Default::default()
+    }
+  }
+
+  pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+    self.max_retries = max_retries;
+    self
+  }
+
+  pub fn with_backoff(mut self, initial_backoff: Duration, max_backoff: Duration) -> Self {
+    self.initial_backoff = initial_backoff;
+    self.max_backoff = max_backoff.max(initial_backoff);
+    self
+  }
+
+  pub fn with_circuit_breaker(mut self, threshold: u32, cooldown: Duration) -> Self {
+    self.circuit_breaker_threshold = threshold;
+    self.circuit_breaker_cooldown = cooldown;
+    self
+  }
+
+  /// Delay before the retry following the given attempt, which starts at 0. Half of the delay is
+  /// random.
+  fn backoff(&self, attempt: u32) -> Duration {
+    let backoff = self
+      .initial_backoff
+      .saturating_mul(2u32.saturating_pow(attempt))
+      .min(self.max_backoff);
+    let half = backoff / 2;
+    let jitter = half.mul_f64(random_fraction());
+    half + jitter
+  }
+}
+
+impl Default for RetryPolicy {
+  fn default() -> Self {
+    Self {
+      max_retries: 2,
+      initial_backoff: Duration::from_millis(200),
+      max_backoff: Duration::from_secs(5),
+      circuit_breaker_threshold: 0,
+      circuit_breaker_cooldown: Duration::from_secs(30),
+    }
+  }
+}
+
+/// Returns a number in [0, 1). The keys of [RandomState] are random, which is enough for a jitter.
+fn random_fraction() -> f64 {
+  let value = RandomState::new().build_hasher().finish();
+  (value >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[derive(Default)]
+pub(crate) struct CircuitBreaker {
+  state: parking_lot::Mutex<CircuitState>,
+}
+
+#[derive(Default)]
+struct CircuitState {
+  consecutive_failures: u32,
+  open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+  fn is_open(&self) -> bool {
+    let state = self.state.lock();
+    state
+      .open_until
+      .is_some_and(|open_until| Instant::now() < open_until)
+  }
+
+  fn record_success(&self) {
+    let mut state = self.state.lock();
+    state.consecutive_failures = 0;
+    state.open_until = None;
+  }
+
+  /// Once the cooldown has elapsed the requests are let through again, and a single failure
+  /// opens the circuit again as the failures are still consecutive.
+  fn record_failure(&self, policy: &RetryPolicy) {
+    if policy.circuit_breaker_threshold == 0 {
+      return;
+    }
+    let mut state = self.state.lock();
+    state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+    if state.consecutive_failures >= policy.circuit_breaker_threshold {
+      if state.open_until.is_none() {
+        warn!(
+          "circuit breaker opened after {} consecutive failed requests",
+          state.consecutive_failures
+        );
+      }
+      state.open_until = Some(Instant::now() + policy.circuit_breaker_cooldown);
+    }
+  }
+}
+
+pub(crate) type SendFuture =
+  Pin<Box<dyn Future<Output = Result<Response, AppResponseError>> + Send + Sync>>;
+
+/// Sends the request with the retry policy and the circuit breaker of the client.
+pub(crate) trait SendWithRetry {
+  fn send_with_retry(self, client: &Client) -> SendFuture;
+}
+
+impl SendWithRetry for RequestBuilder {
+  fn send_with_retry(self, client: &Client) -> SendFuture {
+    let policy = client.config.retry_policy.clone();
+    let circuit_breaker = client.circuit_breaker.clone();
+    Box::pin(async move { send_with_retry(self, &policy, &circuit_breaker).await })
+  }
+}
+
+async fn send_with_retry(
+  builder: RequestBuilder,
+  policy: &RetryPolicy,
+  circuit_breaker: &CircuitBreaker,
+) -> Result<Response, AppResponseError> {
+  if circuit_breaker.is_open() {
+    return Err(AppResponseError::new(
+      ErrorCode::ServiceTemporaryUnavailable,
+      "The server is unreachable, requests are paused for a while",
+    ));
+  }
+
+  let (http_client, request) = builder.build_split();
+  let mut request = request?;
+  let idempotent = matches!(
+    *request.method(),
+    Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS
+  );
+  let mut attempt = 0;
+  loop {
+    // Requests with a streaming body can not be cloned and are sent once.
+    let next_request = if idempotent && attempt < policy.max_retries {
+      request.try_clone()
+    } else {
+      None
+    };
+    let url = request.url().clone();
+    let result = http_client.execute(request).await;
+    let failed = match &result {
+      Ok(resp) => matches!(
+        resp.status(),
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+      ),
+      Err(err) => err.is_connect() || err.is_timeout() || err.is_request(),
+    };
+    if !failed {
+      circuit_breaker.record_success();
+      return Ok(result?);
+    }
+
+    circuit_breaker.record_failure(policy);
+    match next_request {
+      Some(next_request) if !circuit_breaker.is_open() => {
+        drop(result);
+        let backoff = policy.backoff(attempt);
+        debug!(
+          "request to {} failed, retry {} in {:?}",
+          url,
+          attempt + 1,
+          backoff
+        );
+        tokio::time::sleep(backoff).await;
+        request = next_request;
+        attempt += 1;
+      },
+      _ => return Ok(result?),
+    }
+  }
+}
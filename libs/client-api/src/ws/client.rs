@@ -1,5 +1,5 @@
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Display;
 use std::sync::{Arc, Weak};
 use std::time::Duration;
@@ -19,12 +19,20 @@ use tracing::{error, info, trace, warn};
 use crate::ping::ServerFixIntervalPing;
 use crate::retry::retry_connect;
 use crate::ws::msg_queue::{AggregateMessageQueue, AggregateMessagesReceiver};
-use crate::ws::{ConnectState, ConnectStateNotify, WSError, WebSocketChannel};
+use crate::ws::{ConnectState, ConnectStateNotify, SyncScheduler, WSError, WebSocketChannel};
 use client_websocket::{CloseCode, CloseFrame, Message, WebSocketStream};
 use collab_rt_entity::user::UserMessage;
 use collab_rt_entity::ClientCollabMessage;
 use collab_rt_entity::ServerCollabMessage;
+use collab_rt_entity::{MsgId, ObserveCollab};
+use collab_rt_entity::{
+  RealtimeCapability, RealtimeProtocol, CAPABILITIES_HEADER, PROTOCOL_VERSION_HEADER,
+};
 use collab_rt_entity::{RealtimeMessage, SystemMessage};
+use shared_entity::dto::server_info_dto::{
+  encode_region_latencies, PREFERRED_REGION_ENDPOINT_HEADER, REGION_LATENCY_HEADER,
+  REGION_WORKSPACE_HEADER,
+};
 
 pub struct WSClientConfig {
   /// specifies the number of messages that the channel can hold at any given
@@ -34,6 +42,9 @@ pub struct WSClientConfig {
   pub ping_per_secs: u64,
   /// specifies the number of pings that the client will start reconnecting
   pub retry_connect_per_pings: u32,
+  /// when true, the init syncs of multiple objects are sent in a single batch, as long as the
+  /// server negotiated [RealtimeCapability::BatchInitSync] when connecting.
+  pub batch_init_sync: bool,
 }
 
 impl Default for WSClientConfig {
@@ -42,6 +53,7 @@ impl Default for WSClientConfig {
       buffer_capacity: 2000,
       ping_per_secs: 5,
       retry_connect_per_pings: 6,
+      batch_init_sync: false,
     }
   }
 }
@@ -80,10 +92,15 @@ pub struct WSClient {
   ping: Arc<Mutex<Option<ServerFixIntervalPing>>>,
   stop_ws_msg_loop_tx: Mutex<Option<oneshot::Sender<()>>>,
   aggregate_queue: Arc<AggregateMessageQueue>,
+  sync_scheduler: SyncScheduler,
 
   #[cfg(debug_assertions)]
   skip_realtime_message: Arc<std::sync::atomic::AtomicBool>,
   connect_provider: Arc<dyn WSClientConnectURLProvider>,
+  /// Websocket endpoint of the region the server hinted, used instead of the url of the
+  /// connect provider until it can't be reached.
+  region_endpoint: Arc<RwLock<Option<String>>>,
+  protocol: Arc<RwLock<RealtimeProtocol>>,
 }
 impl WSClient {
   pub fn new<H, C>(config: WSClientConfig, http_sender: H, connect_provider: C) -> Self
@@ -111,10 +128,13 @@ impl WSClient {
       ping,
       stop_ws_msg_loop_tx: Mutex::from(None),
       aggregate_queue,
+      sync_scheduler: SyncScheduler::new(),
 
       #[cfg(debug_assertions)]
       skip_realtime_message: Default::default(),
       connect_provider,
+      region_endpoint: Default::default(),
+      protocol: Arc::new(RwLock::new(RealtimeProtocol::legacy())),
     }
   }
 
@@ -137,8 +157,13 @@ impl WSClient {
     let conn_result = retry_connect(
       self.connect_provider.clone(),
       Arc::downgrade(&self.state_notify),
+      self.region_endpoint.clone(),
     )
     .await;
+    let conn_result = match conn_result {
+      Ok(stream) => Ok(self.follow_region_hint(stream).await),
+      Err(err) => Err(err),
+    };
 
     // 3. handle websocket error when connecting or sending message
     if let Err(err) = &conn_result {
@@ -153,7 +178,13 @@ impl WSClient {
 
     // 4. after the connection is established, the client will start sending ping messages to the server
     // at regular intervals to detect the connection status.
-    let (sink, stream) = conn_result?.split();
+    let stream = conn_result?;
+    let protocol = negotiated_protocol(&stream);
+    info!("websocket connected with protocol {}", protocol);
+    let batch_init_sync =
+      self.config.batch_init_sync && protocol.supports(RealtimeCapability::BatchInitSync);
+    *self.protocol.write() = protocol;
+    let (sink, stream) = stream.split();
     self.set_state(ConnectState::Connected).await;
 
     // 5. start pinging
@@ -168,7 +199,7 @@ impl WSClient {
     );
 
     let (tx, rx) = tokio::sync::mpsc::channel(100);
-    self.aggregate_queue.set_sender(tx).await;
+    self.aggregate_queue.set_sender(tx, batch_init_sync).await;
 
     // 7. spawn a task that continuously sending client message.
     self.spawn_send_client_message(sink, &device_id, stop_ws_msg_loop_rx, rx);
@@ -179,6 +210,59 @@ impl WSClient {
     Ok(())
   }
 
+  /// Reconnects to the region endpoint the server hinted in the handshake response, if any. The
+  /// current connection is kept when the region can't be reached.
+  async fn follow_region_hint(&self, stream: WebSocketStream) -> WebSocketStream {
+    let endpoint = match stream
+      .response_headers()
+      .get(PREFERRED_REGION_ENDPOINT_HEADER)
+      .and_then(|value| value.to_str().ok())
+    {
+      Some(endpoint) if !endpoint.is_empty() => endpoint.to_string(),
+      _ => return stream,
+    };
+    if self.region_endpoint.read().as_deref() == Some(endpoint.as_str()) {
+      return stream;
+    }
+
+    info!("server hinted region endpoint {}, reconnecting", endpoint);
+    let headers: HeaderMap = match self.connect_provider.connect_info().await {
+      Ok(info) => info.into(),
+      Err(err) => {
+        warn!("failed to get connect info for region endpoint: {}", err);
+        return stream;
+      },
+    };
+    match client_websocket::connect_async(&endpoint, headers).await {
+      Ok(regional_stream) => {
+        *self.region_endpoint.write() = Some(endpoint);
+        regional_stream
+      },
+      Err(err) => {
+        warn!("failed to connect to region endpoint {}: {}", endpoint, err);
+        stream
+      },
+    }
+  }
+
+  /// The websocket endpoint of the region the client connects to instead of the url of the
+  /// connect provider, as hinted by the server.
+  pub fn region_endpoint(&self) -> Option<String> {
+    self.region_endpoint.read().clone()
+  }
+
+  /// The protocol negotiated with the server by the last connection, the legacy protocol before
+  /// the first connection or with a server that doesn't negotiate.
+  pub fn protocol(&self) -> RealtimeProtocol {
+    self.protocol.read().clone()
+  }
+
+  /// The protocol negotiated by the current connection, updated on every reconnect. Pass it to
+  /// the sinks with [crate::collab_sync::SinkConfig::realtime_protocol].
+  pub fn shared_protocol(&self) -> Arc<RwLock<RealtimeProtocol>> {
+    self.protocol.clone()
+  }
+
   fn spawn_aggregate_message(&self) {
     let mut rx = self.rt_msg_sender.subscribe();
     let weak_aggregate_queue = Arc::downgrade(&self.aggregate_queue);
@@ -300,11 +384,21 @@ impl WSClient {
                     trace!("detect same ws connect from this device, closing the connection");
                     break;
                   },
+                  SystemMessage::WorkspaceAccessRevoked(workspace_id) => {
+                    warn!("access to workspace {} has been revoked", workspace_id);
+                  },
+                  SystemMessage::SessionLimitExceeded => {
+                    warn!("too many sessions are open for this user, closing the connection");
+                    break;
+                  },
                 },
                 RealtimeMessage::ServerCollabV1(collab_messages) => {
                   handle_collab_message(&weak_collab_channels, collab_messages);
                 },
-                RealtimeMessage::ClientCollabV2(_) | RealtimeMessage::ClientCollabV1(_) => {
+                RealtimeMessage::ClientCollabV2(_)
+                | RealtimeMessage::ClientCollabV1(_)
+                | RealtimeMessage::ClientBatchInitSync(_)
+                | RealtimeMessage::ClientObserve(_) => {
                   // The message from server should not be collab message.
                   error!(
                     "received unexpected collab message from websocket: {:?}",
@@ -366,6 +460,26 @@ impl WSClient {
     Ok(channel)
   }
 
+  /// Starts or stops observing the collab in read-only mode, without opening a sync session. The
+  /// updates of the collab and the ack of this request, carrying the given `msg_id`, are received
+  /// through the channel returned by [WSClient::subscribe_collab].
+  pub fn observe_collab(
+    &self,
+    workspace_id: &str,
+    object_id: &str,
+    msg_id: MsgId,
+    observe: bool,
+  ) -> Result<(), WSError> {
+    let message = RealtimeMessage::ClientObserve(ObserveCollab::new(
+      workspace_id.to_string(),
+      object_id.to_string(),
+      msg_id,
+      observe,
+    ));
+    let data = message.encode()?;
+    self.send(Message::Binary(data))
+  }
+
   pub fn subscribe_user_changed(&self) -> Receiver<UserMessage> {
     self.user_channel.subscribe()
   }
@@ -410,6 +524,28 @@ impl WSClient {
     self.state_notify.lock().state.clone()
   }
 
+  /// Returns the scheduler to pass to the [crate::collab_sync::SinkConfig] of the collabs, so
+  /// their sync follows [WSClient::pause_background_sync] and [WSClient::resume_background_sync].
+  pub fn sync_scheduler(&self) -> SyncScheduler {
+    self.sync_scheduler.clone()
+  }
+
+  /// Keeps the changes of the collabs of the workspace queued, except the active ones, until
+  /// [WSClient::resume_background_sync] is called.
+  pub fn pause_background_sync(&self, workspace_id: &str) {
+    self.sync_scheduler.pause_workspace(workspace_id);
+  }
+
+  pub fn resume_background_sync(&self, workspace_id: &str) {
+    self.sync_scheduler.resume_workspace(workspace_id);
+  }
+
+  /// Marks the collab, e.g. the document opened by the user, as active. Active collabs keep
+  /// syncing in realtime while their workspace is paused.
+  pub fn set_active_collab(&self, object_id: &str, active: bool) {
+    self.sync_scheduler.set_active(object_id, active);
+  }
+
   async fn set_state(&self, state: ConnectState) {
     self.state_notify.lock().set_state(state);
   }
@@ -478,11 +614,28 @@ async fn send_message(
   Ok(())
 }
 
+/// The protocol the server enabled for the connection, from the headers of its handshake response.
+fn negotiated_protocol(stream: &WebSocketStream) -> RealtimeProtocol {
+  let headers = stream.response_headers();
+  RealtimeProtocol::from_headers(
+    headers
+      .get(PROTOCOL_VERSION_HEADER)
+      .and_then(|value| value.to_str().ok()),
+    headers
+      .get(CAPABILITIES_HEADER)
+      .and_then(|value| value.to_str().ok()),
+  )
+}
+
 #[derive(Clone, Eq, PartialEq)]
 pub struct ConnectInfo {
   pub access_token: String,
   pub client_version: Version,
   pub device_id: String,
+  /// The workspace opened by the client, the server hints the region it resides in.
+  pub workspace_id: Option<String>,
+  /// Latency in milliseconds by region name, the server may hint a region with a lower latency.
+  pub region_latencies: BTreeMap<String, u32>,
 }
 
 impl Display for ConnectInfo {
@@ -514,6 +667,28 @@ impl From<ConnectInfo> for HeaderMap {
       "connect-at",
       HeaderValue::from(chrono::Utc::now().timestamp()),
     );
+    if let Some(value) = info
+      .workspace_id
+      .and_then(|workspace_id| HeaderValue::from_str(&workspace_id).ok())
+    {
+      headers.insert(REGION_WORKSPACE_HEADER, value);
+    }
+    let protocol = RealtimeProtocol::current();
+    headers.insert(PROTOCOL_VERSION_HEADER, HeaderValue::from(protocol.version));
+    if let Ok(value) = HeaderValue::from_str(&protocol.capabilities_header()) {
+      headers.insert(CAPABILITIES_HEADER, value);
+    }
+    if !info.region_latencies.is_empty() {
+      let latencies = encode_region_latencies(
+        info
+          .region_latencies
+          .iter()
+          .map(|(region, latency)| (region.as_str(), *latency)),
+      );
+      if let Ok(value) = HeaderValue::from_str(&latencies) {
+        headers.insert(REGION_LATENCY_HEADER, value);
+      }
+    }
     headers
   }
 }
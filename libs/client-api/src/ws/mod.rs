@@ -3,8 +3,10 @@ mod error;
 mod handler;
 mod msg_queue;
 mod state;
+mod sync_schedule;
 
 pub use client::*;
 pub use error::*;
 pub use handler::*;
 pub use state::*;
+pub use sync_schedule::*;
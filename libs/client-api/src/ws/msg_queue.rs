@@ -8,7 +8,7 @@ use tokio::time::{sleep_until, Instant};
 use tracing::{error, trace};
 
 use client_websocket::Message;
-use collab_rt_entity::{ClientCollabMessage, MsgId};
+use collab_rt_entity::{BatchInitSync, ClientCollabMessage, MsgId};
 use collab_rt_entity::{MessageByObjectId, RealtimeMessage};
 
 pub type AggregateMessagesSender = mpsc::Sender<Message>;
@@ -46,7 +46,9 @@ impl AggregateMessageQueue {
     self.seen_ids.lock().await.clear();
   }
 
-  pub async fn set_sender(&self, sender: AggregateMessagesSender) {
+  /// Starts sending the queued messages to the sender of a new connection. When `batch_init_sync`
+  /// is true, the init syncs of multiple objects are sent as a single [BatchInitSync].
+  pub async fn set_sender(&self, sender: AggregateMessagesSender, batch_init_sync: bool) {
     let (tx, mut rx) = mpsc::channel(1);
     if let Some(old_stop_tx) = self.stop_tx.lock().await.take() {
       let _ = old_stop_tx.send(()).await;
@@ -64,7 +66,7 @@ impl AggregateMessageQueue {
           _ = rx.recv() => break,
           _ = sleep_until(next_tick) => {
             if let Some(queue) = weak_queue.upgrade() {
-              let (num_init_sync, num_messages) = handle_tick(&sender, &queue, maximum_payload_size, weak_seen_ids.clone()).await;
+              let (num_init_sync, num_messages) = handle_tick(&sender, &queue, maximum_payload_size, batch_init_sync, weak_seen_ids.clone()).await;
               // To determine the next interval dynamically, consider factors such as the number of messages sent,
               // their total size, and the current network type. This approach allows for more nuanced interval
               // adjustments, optimizing for efficiency and responsiveness under varying conditions.
@@ -84,9 +86,11 @@ async fn handle_tick(
   sender: &AggregateMessagesSender,
   queue: &Arc<Mutex<BinaryHeap<ClientCollabMessage>>>,
   maximum_payload_size: usize,
+  batch_init_sync: bool,
   weak_seen_ids: Weak<Mutex<HashSet<SeenId>>>,
 ) -> (usize, usize) {
-  let (did_sent_seen_ids, messages_map) = next_batch_message(10, maximum_payload_size, queue).await;
+  let (did_sent_seen_ids, mut messages_map) =
+    next_batch_message(10, maximum_payload_size, queue).await;
   if messages_map.is_empty() {
     return (0, 0);
   }
@@ -94,7 +98,14 @@ async fn handle_tick(
   log_message_map(&messages_map);
 
   // Send messages to server
-  send_batch_message(sender, messages_map).await;
+  if batch_init_sync {
+    for batch in take_batch_init_syncs(&mut messages_map) {
+      send_realtime_message(sender, RealtimeMessage::ClientBatchInitSync(batch)).await;
+    }
+  }
+  if !messages_map.is_empty() {
+    send_batch_message(sender, messages_map).await;
+  }
 
   // after sending messages, remove seen_ids
   let num_init_sync = did_sent_seen_ids
@@ -115,7 +126,16 @@ async fn send_batch_message(
   sender: &AggregateMessagesSender,
   messages_map: HashMap<String, Vec<ClientCollabMessage>>,
 ) {
-  match RealtimeMessage::ClientCollabV2(MessageByObjectId(messages_map)).encode() {
+  send_realtime_message(
+    sender,
+    RealtimeMessage::ClientCollabV2(MessageByObjectId(messages_map)),
+  )
+  .await;
+}
+
+#[inline]
+async fn send_realtime_message(sender: &AggregateMessagesSender, message: RealtimeMessage) {
+  match message.encode() {
     Ok(data) => {
       if let Err(e) = sender.send(Message::Binary(data)).await {
         trace!("websocket channel close:{}, stop sending messages", e);
@@ -127,6 +147,41 @@ async fn send_batch_message(
   }
 }
 
+/// Removes the objects whose only pending message is an init sync from `messages_map` and groups
+/// them by workspace, so that reconnecting with many open objects doesn't issue one init sync per
+/// object. Nothing is taken when there are less than two such objects.
+fn take_batch_init_syncs(
+  messages_map: &mut HashMap<String, Vec<ClientCollabMessage>>,
+) -> Vec<BatchInitSync> {
+  let object_ids = messages_map
+    .iter()
+    .filter(|(_, messages)| messages.len() == 1 && messages[0].init_sync_target().is_some())
+    .map(|(object_id, _)| object_id.clone())
+    .collect::<Vec<_>>();
+  if object_ids.len() < 2 {
+    return vec![];
+  }
+
+  let mut messages_by_workspace: HashMap<String, Vec<ClientCollabMessage>> = HashMap::new();
+  for object_id in object_ids {
+    if let Some(message) = messages_map
+      .remove(&object_id)
+      .and_then(|mut msgs| msgs.pop())
+    {
+      if let Some((workspace_id, _)) = message.init_sync_target() {
+        messages_by_workspace
+          .entry(workspace_id.to_string())
+          .or_default()
+          .push(message);
+      }
+    }
+  }
+  messages_by_workspace
+    .into_iter()
+    .map(|(workspace_id, messages)| BatchInitSync::new(workspace_id, messages))
+    .collect()
+}
+
 /// Gathers a batch of messages up to certain limits.
 ///
 /// This function collects messages from a shared priority queue until reaching either the maximum number
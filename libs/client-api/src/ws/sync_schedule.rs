@@ -0,0 +1,85 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+use tokio::sync::broadcast;
+
+#[derive(Default)]
+struct ScheduleState {
+  paused_workspaces: HashSet<String>,
+  active_objects: HashSet<String>,
+}
+
+/// Pauses and resumes the background sync per workspace. While a workspace is paused, its collabs
+/// keep their changes queued instead of sending them, except the active ones, like the document
+/// currently opened by the user, which keep syncing in realtime.
+#[derive(Clone)]
+pub struct SyncScheduler {
+  state: Arc<RwLock<ScheduleState>>,
+  /// Notified when some collabs may no longer be deferred.
+  resumed_tx: broadcast::Sender<()>,
+}
+
+impl Default for SyncScheduler {
+  fn default() -> Self {
+    let (resumed_tx, _) = broadcast::channel(16);
+    Self {
+      state: Default::default(),
+      resumed_tx,
+    }
+  }
+}
+
+impl SyncScheduler {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn pause_workspace(&self, workspace_id: &str) {
+    self
+      .state
+      .write()
+      .paused_workspaces
+      .insert(workspace_id.to_string());
+  }
+
+  /// Resumes the background sync of the workspace. The queued changes are sent right away.
+  pub fn resume_workspace(&self, workspace_id: &str) {
+    let removed = self.state.write().paused_workspaces.remove(workspace_id);
+    if removed {
+      let _ = self.resumed_tx.send(());
+    }
+  }
+
+  pub fn is_workspace_paused(&self, workspace_id: &str) -> bool {
+    self.state.read().paused_workspaces.contains(workspace_id)
+  }
+
+  /// Marks the collab as active, so it keeps syncing while its workspace is paused.
+  pub fn set_active(&self, object_id: &str, active: bool) {
+    if active {
+      let inserted = self
+        .state
+        .write()
+        .active_objects
+        .insert(object_id.to_string());
+      if inserted {
+        let _ = self.resumed_tx.send(());
+      }
+    } else {
+      self.state.write().active_objects.remove(object_id);
+    }
+  }
+
+  /// Returns true if the changes of the collab must stay queued for now.
+  pub fn is_deferred(&self, workspace_id: &str, object_id: &str) -> bool {
+    let state = self.state.read();
+    state.paused_workspaces.contains(workspace_id) && !state.active_objects.contains(object_id)
+  }
+
+  /// Notified when a workspace is resumed or a collab becomes active. The sinks then check
+  /// whether they can send their queued changes.
+  pub fn subscribe_resumed(&self) -> broadcast::Receiver<()> {
+    self.resumed_tx.subscribe()
+  }
+}
@@ -16,9 +16,12 @@ pub async fn connect_async(url: &str, header_map: HeaderMap) -> crate::Result<We
   let mut request = url.into_client_request()?;
   request.headers_mut().extend(header_map);
 
-  let (inner, _response) = tokio_tungstenite::connect_async(request).await?;
+  let (inner, response) = tokio_tungstenite::connect_async(request).await?;
   let inner = inner.filter_map(to_fut_message as fn(_) -> _);
-  Ok(WebSocketStream { inner })
+  Ok(WebSocketStream {
+    inner,
+    response_headers: response.headers().clone(),
+  })
 }
 
 type TokioTungsteniteStream =
@@ -30,6 +33,14 @@ pub struct WebSocketStream {
     FutMessage,
     fn(Result<Message>) -> FutMessage,
   >,
+  response_headers: HeaderMap,
+}
+
+impl WebSocketStream {
+  /// Headers of the handshake response of the server.
+  pub fn response_headers(&self) -> &HeaderMap {
+    &self.response_headers
+  }
 }
 
 impl Stream for WebSocketStream {
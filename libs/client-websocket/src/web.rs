@@ -15,9 +15,15 @@ pub struct WebSocketStream {
   _on_message_callback: Closure<dyn FnMut(MessageEvent)>,
   _on_error_callback: Closure<dyn FnMut(ErrorEvent)>,
   _on_close_callback: Closure<dyn FnMut(CloseEvent)>,
+  /// The browsers don't expose the headers of the handshake response, this is always empty.
+  response_headers: HeaderMap,
 }
 
 impl WebSocketStream {
+  pub fn response_headers(&self) -> &HeaderMap {
+    &self.response_headers
+  }
+
   async fn new(url: &str, headers: HeaderMap) -> crate::Result<Self> {
     let query_string = header_map_to_query_string(&headers);
     // Construct the full WebSocket URL with query parameters
@@ -108,6 +114,7 @@ impl WebSocketStream {
           _on_message_callback: on_message_callback,
           _on_error_callback: on_error_callback,
           _on_close_callback: on_close_callback,
+          response_headers: HeaderMap::new(),
         })
       },
     }
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Field of the awareness state holding the database cell the client has the focus on. The
+/// awareness state is the JSON object that each client shares with the other clients of the
+/// collab, the other fields are left as they are.
+pub const CELL_FOCUS_AWARENESS_FIELD: &str = "cell_focus";
+
+/// Database cell a client has the focus on, so the other clients can show who is looking at or
+/// editing the cell. Unlike [crate::CellLockRequest], the focus is not tracked by the server.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize, Hash)]
+pub struct DatabaseCellFocus {
+  pub row_id: String,
+  pub field_id: String,
+  /// False when the cell is only selected.
+  #[serde(default)]
+  pub editing: bool,
+}
+
+impl DatabaseCellFocus {
+  /// Returns the cell focused in the awareness state, None when the client focuses no cell or the
+  /// state is not a JSON object.
+  pub fn from_awareness_state(state: &str) -> Option<Self> {
+    let mut state = serde_json::from_str::<Value>(state).ok()?;
+    let focus = state.get_mut(CELL_FOCUS_AWARENESS_FIELD)?.take();
+    serde_json::from_value(focus).ok()
+  }
+
+  /// Returns the awareness state with the focused cell set, or removed when `focus` is None.
+  pub fn set_in_awareness_state(state: &str, focus: Option<&Self>) -> String {
+    let mut state = match serde_json::from_str::<Value>(state) {
+      Ok(Value::Object(state)) => state,
+      _ => serde_json::Map::new(),
+    };
+    match focus.and_then(|focus| serde_json::to_value(focus).ok()) {
+      Some(focus) => {
+        state.insert(CELL_FOCUS_AWARENESS_FIELD.to_string(), focus);
+      },
+      None => {
+        state.remove(CELL_FOCUS_AWARENESS_FIELD);
+      },
+    }
+    Value::Object(state).to_string()
+  }
+}
@@ -1,6 +1,6 @@
 use crate::message::RealtimeMessage;
 use crate::server_message::ServerInit;
-use crate::{CollabMessage, MessageByObjectId, MsgId};
+use crate::{CollabMessage, MessageByObjectId, MsgId, RealtimeCapability, RealtimeProtocol};
 use anyhow::{anyhow, Error};
 use bytes::Bytes;
 use collab::core::origin::CollabOrigin;
@@ -32,6 +32,8 @@ pub enum ClientCollabMessage {
   ServerInitSync(ServerInit),
   ClientAwarenessSync(UpdateSync),
   ClientCollabStateCheck(CollabStateCheck),
+  ClientResumeSync { data: ResumeSync },
+  ClientCellLock { data: CellLockRequest },
 }
 
 impl ClientCollabMessage {
@@ -51,6 +53,29 @@ impl ClientCollabMessage {
     Self::ClientAwarenessSync(data)
   }
 
+  pub fn new_resume_sync(data: ResumeSync) -> Self {
+    Self::ClientResumeSync { data }
+  }
+
+  /// The message starting the sync of a collab on a connection with the given protocol. Only the
+  /// servers that negotiated [RealtimeCapability::ResumeSync] can decode a [ResumeSync], the
+  /// others get the [InitSync] and the resume token is dropped.
+  pub fn new_sync_start(
+    protocol: &RealtimeProtocol,
+    data: InitSync,
+    resume_token: Option<String>,
+  ) -> Self {
+    if protocol.supports(RealtimeCapability::ResumeSync) {
+      Self::new_resume_sync(ResumeSync::new(data, resume_token))
+    } else {
+      Self::new_init_sync(data)
+    }
+  }
+
+  pub fn new_cell_lock(data: CellLockRequest) -> Self {
+    Self::ClientCellLock { data }
+  }
+
   pub fn size(&self) -> usize {
     match self {
       ClientCollabMessage::ClientInitSync { data, .. } => data.payload.len(),
@@ -58,6 +83,8 @@ impl ClientCollabMessage {
       ClientCollabMessage::ServerInitSync(msg) => msg.payload.len(),
       ClientCollabMessage::ClientAwarenessSync(data) => data.payload.len(),
       ClientCollabMessage::ClientCollabStateCheck(_) => 0,
+      ClientCollabMessage::ClientResumeSync { data, .. } => data.payload.len(),
+      ClientCollabMessage::ClientCellLock { .. } => 0,
     }
   }
   pub fn object_id(&self) -> &str {
@@ -67,6 +94,8 @@ impl ClientCollabMessage {
       ClientCollabMessage::ServerInitSync(msg) => &msg.object_id,
       ClientCollabMessage::ClientAwarenessSync(data) => &data.object_id,
       ClientCollabMessage::ClientCollabStateCheck(data) => &data.object_id,
+      ClientCollabMessage::ClientResumeSync { data, .. } => &data.object_id,
+      ClientCollabMessage::ClientCellLock { data } => &data.object_id,
     }
   }
 
@@ -77,6 +106,8 @@ impl ClientCollabMessage {
       ClientCollabMessage::ServerInitSync(msg) => &msg.origin,
       ClientCollabMessage::ClientAwarenessSync(data) => &data.origin,
       ClientCollabMessage::ClientCollabStateCheck(data) => &data.origin,
+      ClientCollabMessage::ClientResumeSync { data, .. } => &data.origin,
+      ClientCollabMessage::ClientCellLock { data } => &data.origin,
     }
   }
   pub fn payload(&self) -> &Bytes {
@@ -87,6 +118,8 @@ impl ClientCollabMessage {
       ClientCollabMessage::ServerInitSync(msg) => &msg.payload,
       ClientCollabMessage::ClientAwarenessSync(data) => &data.payload,
       ClientCollabMessage::ClientCollabStateCheck(_data) => &EMPTY_BYTES,
+      ClientCollabMessage::ClientResumeSync { data, .. } => &data.payload,
+      ClientCollabMessage::ClientCellLock { .. } => &EMPTY_BYTES,
     }
   }
   pub fn device_id(&self) -> Option<String> {
@@ -103,11 +136,29 @@ impl ClientCollabMessage {
       ClientCollabMessage::ServerInitSync(value) => value.msg_id,
       ClientCollabMessage::ClientAwarenessSync(data) => data.msg_id,
       ClientCollabMessage::ClientCollabStateCheck(data) => data.msg_id,
+      ClientCollabMessage::ClientResumeSync { data, .. } => data.msg_id,
+      ClientCollabMessage::ClientCellLock { data } => data.msg_id,
     }
   }
 
+  /// Returns true for the messages that start a sync session, either a full init sync or a
+  /// resume sync that falls back to a full init sync when the resume token is stale.
   pub fn is_init_sync(&self) -> bool {
-    matches!(self, ClientCollabMessage::ClientInitSync { .. })
+    matches!(
+      self,
+      ClientCollabMessage::ClientInitSync { .. } | ClientCollabMessage::ClientResumeSync { .. }
+    )
+  }
+
+  /// Returns the workspace id and collab type carried by the messages that can create a group.
+  pub fn init_sync_target(&self) -> Option<(&str, &CollabType)> {
+    match self {
+      ClientCollabMessage::ClientInitSync { data } => Some((&data.workspace_id, &data.collab_type)),
+      ClientCollabMessage::ClientResumeSync { data } => {
+        Some((&data.workspace_id, &data.collab_type))
+      },
+      _ => None,
+    }
   }
 }
 
@@ -125,6 +176,8 @@ impl Display for ClientCollabMessage {
         data.payload.len(),
       )),
       ClientCollabMessage::ClientCollabStateCheck(data) => Display::fmt(data, f),
+      ClientCollabMessage::ClientResumeSync { data, .. } => Display::fmt(&data, f),
+      ClientCollabMessage::ClientCellLock { data } => Display::fmt(&data, f),
     }
   }
 }
@@ -181,7 +234,7 @@ impl SinkMessage for ClientCollabMessage {
   }
 
   fn is_client_init_sync(&self) -> bool {
-    matches!(self, ClientCollabMessage::ClientInitSync { .. })
+    self.is_init_sync()
   }
 
   fn is_server_init_sync(&self) -> bool {
@@ -228,11 +281,9 @@ impl PartialOrd for ClientCollabMessage {
 impl Ord for ClientCollabMessage {
   fn cmp(&self, other: &Self) -> Ordering {
     match (&self, &other) {
-      (ClientCollabMessage::ClientInitSync { .. }, ClientCollabMessage::ClientInitSync { .. }) => {
-        Ordering::Equal
-      },
-      (ClientCollabMessage::ClientInitSync { .. }, _) => Ordering::Greater,
-      (_, ClientCollabMessage::ClientInitSync { .. }) => Ordering::Less,
+      (left, right) if left.is_init_sync() && right.is_init_sync() => Ordering::Equal,
+      (left, _) if left.is_init_sync() => Ordering::Greater,
+      (_, right) if right.is_init_sync() => Ordering::Less,
       (ClientCollabMessage::ServerInitSync(_left), ClientCollabMessage::ServerInitSync(_right)) => {
         Ordering::Equal
       },
@@ -368,6 +419,133 @@ impl Display for UpdateSync {
   }
 }
 
+///  ⚠️ ⚠️ ⚠️Compatibility Warning:
+///
+/// The structure of this struct is integral to maintaining compatibility with existing messages.
+/// Therefore, adding or removing any properties (fields) from this struct could disrupt the
+/// compatibility. Such changes may lead to issues in processing existing messages that expect
+/// the struct to have a specific format. It's crucial to carefully consider the implications
+/// of modifying this struct's fields
+///
+/// Sent instead of [InitSync] by clients that support resumable sync. When `resume_token` is the
+/// last [ResumeCursor] received from the server, the server replays only the updates that were
+/// added to the collab update stream after it. When the token is missing or the stream was already
+/// pruned past it, the server falls back to a full init sync using `payload`.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize, Hash)]
+pub struct ResumeSync {
+  pub origin: CollabOrigin,
+  pub object_id: String,
+  pub collab_type: CollabType,
+  pub workspace_id: String,
+  pub msg_id: MsgId,
+  pub resume_token: Option<String>,
+  /// Same payload as [InitSync::payload], used when the server can't resume from the token.
+  pub payload: Bytes,
+}
+
+impl ResumeSync {
+  pub fn new(init_sync: InitSync, resume_token: Option<String>) -> Self {
+    Self {
+      origin: init_sync.origin,
+      object_id: init_sync.object_id,
+      collab_type: init_sync.collab_type,
+      workspace_id: init_sync.workspace_id,
+      msg_id: init_sync.msg_id,
+      resume_token,
+      payload: init_sync.payload,
+    }
+  }
+}
+
+impl Display for ResumeSync {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_fmt(format_args!(
+      "client resume: [uid:{}|oid:{}|msg_id:{}|token:{:?}|len:{}]",
+      self.origin.client_user_id().unwrap_or(0),
+      self.object_id,
+      self.msg_id,
+      self.resume_token,
+      self.payload.len(),
+    ))
+  }
+}
+
+/// Init syncs of multiple collab objects of the same workspace sent in a single realtime message.
+/// Clients use it when restoring a connection with many open documents, and the server answers
+/// with a single [crate::RealtimeMessage::ServerCollabV1] carrying the ack of each object.
+///
+/// Each message must be either a [ClientCollabMessage::ClientInitSync] or a
+/// [ClientCollabMessage::ClientResumeSync] of the given workspace.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BatchInitSync {
+  pub workspace_id: String,
+  pub messages: Vec<ClientCollabMessage>,
+}
+
+impl BatchInitSync {
+  pub fn new(workspace_id: String, messages: Vec<ClientCollabMessage>) -> Self {
+    Self {
+      workspace_id,
+      messages,
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.messages.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.messages.is_empty()
+  }
+}
+
+impl Display for BatchInitSync {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_fmt(format_args!(
+      "batch init sync: [workspace_id:{}|num of objects:{}]",
+      self.workspace_id,
+      self.messages.len(),
+    ))
+  }
+}
+
+/// Starts or stops observing a collab object in read-only mode.
+///
+/// Observers don't join the collab group: they never send updates, and they receive the updates
+/// of the object as [crate::ServerCollabMessage::ServerBroadcast] from a fan-out shared by all the
+/// observers of the same object. Observers that need the current state of the object should start
+/// observing first and then fetch the object over http, since updates can be applied in any order.
+///
+/// The server answers with a [crate::ServerCollabMessage::ClientAck] carrying the given `msg_id`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ObserveCollab {
+  pub workspace_id: String,
+  pub object_id: String,
+  pub msg_id: MsgId,
+  /// `true` to start observing the object, `false` to stop.
+  pub observe: bool,
+}
+
+impl ObserveCollab {
+  pub fn new(workspace_id: String, object_id: String, msg_id: MsgId, observe: bool) -> Self {
+    Self {
+      workspace_id,
+      object_id,
+      msg_id,
+      observe,
+    }
+  }
+}
+
+impl Display for ObserveCollab {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_fmt(format_args!(
+      "observe: [oid:{}|msg_id:{}|observe:{}]",
+      self.object_id, self.msg_id, self.observe,
+    ))
+  }
+}
+
 #[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize, Hash)]
 pub struct CollabStateCheck {
   pub origin: CollabOrigin,
@@ -385,3 +563,58 @@ impl Display for CollabStateCheck {
     ))
   }
 }
+
+/// How long a cell lock is held when it's not acquired again.
+pub const CELL_LOCK_TTL_SECS: i64 = 30;
+
+/// Advisory lock of a database cell, sent while the user edits the cell. The lock doesn't prevent
+/// the other clients from writing to the cell, they are expected to show who is editing it and
+/// may defer their own changes. The server answers with an ack and broadcasts a
+/// [crate::CellLockChanged] with the holder of the lock, which is the requester unless the cell
+/// was already locked by another client.
+///
+/// A lock expires after [CELL_LOCK_TTL_SECS], clients that keep editing the cell send the request
+/// again before. The lock is released by sending the request with `acquire` set to false.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize, Hash)]
+pub struct CellLockRequest {
+  pub origin: CollabOrigin,
+  pub object_id: String,
+  pub msg_id: MsgId,
+  pub row_id: String,
+  pub field_id: String,
+  pub acquire: bool,
+}
+
+impl CellLockRequest {
+  pub fn new(
+    origin: CollabOrigin,
+    object_id: String,
+    msg_id: MsgId,
+    row_id: String,
+    field_id: String,
+    acquire: bool,
+  ) -> Self {
+    Self {
+      origin,
+      object_id,
+      msg_id,
+      row_id,
+      field_id,
+      acquire,
+    }
+  }
+}
+
+impl Display for CellLockRequest {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_fmt(format_args!(
+      "cell lock: [uid:{}|oid:{}|msg_id:{}|row:{}|field:{}|acquire:{}]",
+      self.origin.client_user_id().unwrap_or(0),
+      self.object_id,
+      self.msg_id,
+      self.row_id,
+      self.field_id,
+      self.acquire,
+    ))
+  }
+}
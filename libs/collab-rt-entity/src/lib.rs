@@ -1,6 +1,8 @@
 mod message;
 pub mod user;
 
+mod awareness;
+
 mod client_message;
 // If the realtime_proto not exist, the following code will be generated:
 // ```shell
@@ -9,10 +11,13 @@ mod client_message;
 //  cargo build
 // ```
 pub mod collab_proto;
+mod protocol;
 pub mod realtime_proto;
 mod server_message;
 
+pub use awareness::*;
 pub use client_message::*;
 pub use message::*;
+pub use protocol::*;
 pub use realtime_proto::*;
 pub use server_message::*;
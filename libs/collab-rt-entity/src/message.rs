@@ -1,18 +1,20 @@
 use anyhow::{anyhow, Error};
 use bincode::{DefaultOptions, Options};
+use std::borrow::Cow;
 use std::collections::HashMap;
 
-use crate::client_message::ClientCollabMessage;
+use crate::client_message::{BatchInitSync, ClientCollabMessage, ObserveCollab};
 use crate::server_message::ServerCollabMessage;
 use crate::user::UserMessage;
-use crate::{AwarenessSync, BroadcastSync, CollabAck, InitSync, ServerInit, UpdateSync};
-#[cfg(feature = "rt_compress")]
+use crate::{
+  AwarenessSync, BroadcastSync, CellLockChanged, CollabAck, CollabLockChanged, InitSync,
+  ResumeCursor, ServerInit, UpdateSync,
+};
 use brotli::{CompressorReader, Decompressor};
 use bytes::Bytes;
 use collab::core::origin::CollabOrigin;
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
-#[cfg(feature = "rt_compress")]
 use std::io::Read;
 use std::ops::{Deref, DerefMut};
 
@@ -25,7 +27,6 @@ use std::ops::{Deref, DerefMut};
 pub const MAXIMUM_REALTIME_MESSAGE_SIZE: u64 = 10 * 1024 * 1024; // 10 MB
 
 /// 1 for using brotli compression
-#[cfg(feature = "rt_compress")]
 const COMPRESSED_PREFIX: &[u8] = b"COMPRESSED:1";
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
@@ -68,6 +69,8 @@ pub enum RealtimeMessage {
   ClientCollabV1(Vec<ClientCollabMessage>), // Deprecated
   ClientCollabV2(MessageByObjectId),
   ServerCollabV1(Vec<ServerCollabMessage>),
+  ClientBatchInitSync(BatchInitSync),
+  ClientObserve(ObserveCollab),
 }
 
 impl RealtimeMessage {
@@ -105,12 +108,23 @@ impl RealtimeMessage {
           None
         }
       },
+      RealtimeMessage::ClientBatchInitSync(batch) => batch
+        .messages
+        .first()
+        .map(|msg| msg.object_id().to_string()),
+      RealtimeMessage::ClientObserve(observe) => Some(observe.object_id.clone()),
       _ => None,
     }
   }
 
-  #[cfg(feature = "rt_compress")]
+  /// Encodes the message, compressed when the crate is built with the `rt_compress` feature.
   pub fn encode(&self) -> Result<Vec<u8>, Error> {
+    self.encode_with(cfg!(feature = "rt_compress"))
+  }
+
+  /// Encodes the message, compressed with brotli when `compress` is true. Only the peers that
+  /// negotiated [crate::RealtimeCapability::Compression] can decode compressed messages.
+  pub fn encode_with(&self, compress: bool) -> Result<Vec<u8>, Error> {
     let data = DefaultOptions::new()
       .with_fixint_encoding()
       .allow_trailing_bytes()
@@ -123,6 +137,9 @@ impl RealtimeMessage {
           self.object_id()
         )
       })?;
+    if !compress {
+      return Ok(data);
+    }
 
     let mut compressor = CompressorReader::new(&*data, 4096, 4, 22);
     let mut compressed_data = Vec::new();
@@ -133,34 +150,17 @@ impl RealtimeMessage {
     Ok(data)
   }
 
-  #[cfg(not(feature = "rt_compress"))]
-  pub fn encode(&self) -> Result<Vec<u8>, Error> {
-    let data = DefaultOptions::new()
-      .with_fixint_encoding()
-      .allow_trailing_bytes()
-      .with_limit(MAXIMUM_REALTIME_MESSAGE_SIZE)
-      .serialize(self)
-      .map_err(|e| {
-        anyhow!(
-          "Failed to encode realtime message: {}, object_id:{:?}",
-          e,
-          self.object_id()
-        )
-      })?;
-    Ok(data)
-  }
-
-  #[cfg(feature = "rt_compress")]
+  /// Decodes a message, compressed or not.
   pub fn decode(data: &[u8]) -> Result<Self, Error> {
-    if data.starts_with(COMPRESSED_PREFIX) {
-      let data_without_prefix = &data[COMPRESSED_PREFIX.len()..];
-      let mut decompressor = Decompressor::new(data_without_prefix, 4096);
-      let mut decompressed_data = Vec::new();
-      decompressor.read_to_end(&mut decompressed_data)?;
-      decompressed_data
-    } else {
-      data.to_vec()
-    }
+    let data = match data.strip_prefix(COMPRESSED_PREFIX) {
+      Some(compressed_data) => {
+        let mut decompressor = Decompressor::new(compressed_data, 4096);
+        let mut decompressed_data = Vec::new();
+        decompressor.read_to_end(&mut decompressed_data)?;
+        Cow::Owned(decompressed_data)
+      },
+      None => Cow::Borrowed(data),
+    };
 
     let message = DefaultOptions::new()
       .with_fixint_encoding()
@@ -169,16 +169,6 @@ impl RealtimeMessage {
       .deserialize(&data)?;
     Ok(message)
   }
-
-  #[cfg(not(feature = "rt_compress"))]
-  pub fn decode(data: &[u8]) -> Result<Self, Error> {
-    let message = DefaultOptions::new()
-      .with_fixint_encoding()
-      .allow_trailing_bytes()
-      .with_limit(MAXIMUM_REALTIME_MESSAGE_SIZE)
-      .deserialize(data)?;
-    Ok(message)
-  }
 }
 
 impl Display for RealtimeMessage {
@@ -190,6 +180,8 @@ impl Display for RealtimeMessage {
       RealtimeMessage::ClientCollabV1(_) => f.write_fmt(format_args!("ClientCollabV1")),
       RealtimeMessage::ClientCollabV2(_) => f.write_fmt(format_args!("ClientCollabV2")),
       RealtimeMessage::ServerCollabV1(_) => f.write_fmt(format_args!("ServerCollabV1")),
+      RealtimeMessage::ClientBatchInitSync(batch) => Display::fmt(batch, f),
+      RealtimeMessage::ClientObserve(observe) => Display::fmt(observe, f),
     }
   }
 }
@@ -199,6 +191,12 @@ pub enum SystemMessage {
   RateLimit(u32),
   KickOff,
   DuplicateConnection,
+  /// The user has been removed from the workspace with the given id, the server stopped sending
+  /// the updates of its collabs.
+  WorkspaceAccessRevoked(String),
+  /// The user opened more sessions than allowed, this one was the oldest. The server closes the
+  /// connection and revokes the refresh token of the session, the user has to sign in again.
+  SessionLimitExceeded,
 }
 
 pub type MsgId = u64;
@@ -210,6 +208,9 @@ pub enum CollabMessage {
   ServerInitSync(ServerInit),
   AwarenessSync(AwarenessSync),
   ServerBroadcast(BroadcastSync),
+  ServerResumeCursor(ResumeCursor),
+  ServerCollabLock(CollabLockChanged),
+  ServerCellLock(CellLockChanged),
 }
 
 impl CollabMessage {
@@ -221,6 +222,9 @@ impl CollabMessage {
       CollabMessage::ServerInitSync(value) => Some(value.msg_id),
       CollabMessage::ServerBroadcast(_) => None,
       CollabMessage::AwarenessSync(_) => None,
+      CollabMessage::ServerResumeCursor(_) => None,
+      CollabMessage::ServerCollabLock(_) => None,
+      CollabMessage::ServerCellLock(_) => None,
     }
   }
 
@@ -228,6 +232,7 @@ impl CollabMessage {
     self.payload().len()
   }
   pub fn payload(&self) -> &Bytes {
+    static EMPTY_BYTES: Bytes = Bytes::from_static(b"");
     match self {
       CollabMessage::ClientInitSync(value) => &value.payload,
       CollabMessage::ClientUpdateSync(value) => &value.payload,
@@ -235,6 +240,9 @@ impl CollabMessage {
       CollabMessage::ServerInitSync(value) => &value.payload,
       CollabMessage::ServerBroadcast(value) => &value.payload,
       CollabMessage::AwarenessSync(value) => &value.payload,
+      CollabMessage::ServerResumeCursor(_) => &EMPTY_BYTES,
+      CollabMessage::ServerCollabLock(_) => &EMPTY_BYTES,
+      CollabMessage::ServerCellLock(_) => &EMPTY_BYTES,
     }
   }
   pub fn is_empty(&self) -> bool {
@@ -248,6 +256,9 @@ impl CollabMessage {
       CollabMessage::ServerInitSync(value) => &value.origin,
       CollabMessage::ServerBroadcast(value) => &value.origin,
       CollabMessage::AwarenessSync(value) => &value.origin,
+      CollabMessage::ServerResumeCursor(value) => &value.origin,
+      CollabMessage::ServerCollabLock(value) => &value.origin,
+      CollabMessage::ServerCellLock(value) => &value.origin,
     }
   }
 
@@ -263,6 +274,9 @@ impl CollabMessage {
       CollabMessage::ServerInitSync(value) => &value.object_id,
       CollabMessage::ServerBroadcast(value) => &value.object_id,
       CollabMessage::AwarenessSync(value) => &value.object_id,
+      CollabMessage::ServerResumeCursor(value) => &value.object_id,
+      CollabMessage::ServerCollabLock(value) => &value.object_id,
+      CollabMessage::ServerCellLock(value) => &value.object_id,
     }
   }
 }
@@ -276,6 +290,9 @@ impl Display for CollabMessage {
       CollabMessage::ServerInitSync(value) => Display::fmt(&value, f),
       CollabMessage::ServerBroadcast(value) => Display::fmt(&value, f),
       CollabMessage::AwarenessSync(value) => Display::fmt(&value, f),
+      CollabMessage::ServerResumeCursor(value) => Display::fmt(&value, f),
+      CollabMessage::ServerCollabLock(value) => Display::fmt(&value, f),
+      CollabMessage::ServerCellLock(value) => Display::fmt(&value, f),
     }
   }
 }
@@ -310,6 +327,24 @@ impl From<AwarenessSync> for CollabMessage {
   }
 }
 
+impl From<ResumeCursor> for CollabMessage {
+  fn from(value: ResumeCursor) -> Self {
+    CollabMessage::ServerResumeCursor(value)
+  }
+}
+
+impl From<CollabLockChanged> for CollabMessage {
+  fn from(value: CollabLockChanged) -> Self {
+    CollabMessage::ServerCollabLock(value)
+  }
+}
+
+impl From<CellLockChanged> for CollabMessage {
+  fn from(value: CellLockChanged) -> Self {
+    CollabMessage::ServerCellLock(value)
+  }
+}
+
 impl From<ServerInit> for CollabMessage {
   fn from(value: ServerInit) -> Self {
     CollabMessage::ServerInitSync(value)
@@ -0,0 +1,129 @@
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+
+/// Header, or query parameter, with the version of the realtime protocol of the client. The server
+/// answers with the negotiated version in the header of the same name.
+pub const PROTOCOL_VERSION_HEADER: &str = "protocol-version";
+/// Header, or query parameter, with the comma separated capabilities of the client. The server
+/// answers with the capabilities enabled for the connection in the header of the same name.
+pub const CAPABILITIES_HEADER: &str = "capabilities";
+
+/// Version of the realtime protocol implemented by this crate. Bumped when the meaning of existing
+/// messages changes, the optional behaviors are negotiated as [RealtimeCapability] instead.
+pub const REALTIME_PROTOCOL_VERSION: u32 = 1;
+
+/// A behavior of the realtime connection that both sides have to support to be enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RealtimeCapability {
+  /// The messages may be compressed with brotli, see [crate::RealtimeMessage::encode_with].
+  Compression,
+  /// The init syncs of multiple collabs may be sent as a single [crate::BatchInitSync].
+  BatchInitSync,
+  /// The system messages added after the first clients were released can be decoded:
+  /// [crate::SystemMessage::WorkspaceAccessRevoked] and
+  /// [crate::SystemMessage::SessionLimitExceeded].
+  ExtendedSystemMessages,
+  /// The sync of a collab may continue from the last resume cursor with a [crate::ResumeSync]
+  /// instead of a full [crate::InitSync].
+  ResumeSync,
+}
+
+impl RealtimeCapability {
+  const ALL: [RealtimeCapability; 4] = [
+    RealtimeCapability::Compression,
+    RealtimeCapability::BatchInitSync,
+    RealtimeCapability::ExtendedSystemMessages,
+    RealtimeCapability::ResumeSync,
+  ];
+
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      RealtimeCapability::Compression => "compression",
+      RealtimeCapability::BatchInitSync => "batch-init-sync",
+      RealtimeCapability::ExtendedSystemMessages => "extended-system-messages",
+      RealtimeCapability::ResumeSync => "resume-sync",
+    }
+  }
+
+  /// None for the capabilities of newer peers, which are ignored.
+  pub fn from_name(name: &str) -> Option<Self> {
+    Self::ALL
+      .into_iter()
+      .find(|capability| capability.as_str() == name)
+  }
+}
+
+/// The protocol version and the capabilities of one side of a realtime connection, or the ones
+/// both sides agreed on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RealtimeProtocol {
+  pub version: u32,
+  pub capabilities: BTreeSet<RealtimeCapability>,
+}
+
+impl RealtimeProtocol {
+  /// The protocol implemented by this crate, with all the capabilities.
+  pub fn current() -> Self {
+    Self {
+      version: REALTIME_PROTOCOL_VERSION,
+      capabilities: RealtimeCapability::ALL.into_iter().collect(),
+    }
+  }
+
+  /// The protocol of the peers released before the negotiation, which send no protocol version.
+  /// None of the capabilities is enabled for them.
+  pub fn legacy() -> Self {
+    Self {
+      version: 0,
+      capabilities: BTreeSet::new(),
+    }
+  }
+
+  /// Parses the headers of the peer, the legacy protocol when the version is missing or invalid.
+  pub fn from_headers(version: Option<&str>, capabilities: Option<&str>) -> Self {
+    let Some(version) = version.and_then(|version| version.trim().parse::<u32>().ok()) else {
+      return Self::legacy();
+    };
+    let capabilities = capabilities
+      .unwrap_or_default()
+      .split(',')
+      .filter_map(|name| RealtimeCapability::from_name(name.trim()))
+      .collect();
+    Self {
+      version,
+      capabilities,
+    }
+  }
+
+  /// The lowest version of the two sides and the capabilities both support.
+  pub fn negotiate(&self, peer: &RealtimeProtocol) -> Self {
+    Self {
+      version: self.version.min(peer.version),
+      capabilities: self
+        .capabilities
+        .intersection(&peer.capabilities)
+        .copied()
+        .collect(),
+    }
+  }
+
+  pub fn supports(&self, capability: RealtimeCapability) -> bool {
+    self.capabilities.contains(&capability)
+  }
+
+  /// The value of the [CAPABILITIES_HEADER].
+  pub fn capabilities_header(&self) -> String {
+    self
+      .capabilities
+      .iter()
+      .map(RealtimeCapability::as_str)
+      .collect::<Vec<_>>()
+      .join(",")
+  }
+}
+
+impl Display for RealtimeProtocol {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "v{} [{}]", self.version, self.capabilities_header())
+  }
+}
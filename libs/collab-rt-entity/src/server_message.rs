@@ -13,6 +13,9 @@ pub enum ServerCollabMessage {
   ServerInitSync(ServerInit),
   AwarenessSync(AwarenessSync),
   ServerBroadcast(BroadcastSync),
+  ServerResumeCursor(ResumeCursor),
+  ServerCollabLock(CollabLockChanged),
+  ServerCellLock(CellLockChanged),
 }
 
 impl ServerCollabMessage {
@@ -22,6 +25,9 @@ impl ServerCollabMessage {
       ServerCollabMessage::ServerInitSync(value) => &value.object_id,
       ServerCollabMessage::AwarenessSync(value) => &value.object_id,
       ServerCollabMessage::ServerBroadcast(value) => &value.object_id,
+      ServerCollabMessage::ServerResumeCursor(value) => &value.object_id,
+      ServerCollabMessage::ServerCollabLock(value) => &value.object_id,
+      ServerCollabMessage::ServerCellLock(value) => &value.object_id,
     }
   }
 
@@ -31,15 +37,22 @@ impl ServerCollabMessage {
       ServerCollabMessage::ServerInitSync(value) => Some(value.msg_id),
       ServerCollabMessage::AwarenessSync(_) => None,
       ServerCollabMessage::ServerBroadcast(_) => None,
+      ServerCollabMessage::ServerResumeCursor(_) => None,
+      ServerCollabMessage::ServerCollabLock(_) => None,
+      ServerCollabMessage::ServerCellLock(_) => None,
     }
   }
 
   pub fn payload(&self) -> &Bytes {
+    static EMPTY_BYTES: Bytes = Bytes::from_static(b"");
     match self {
       ServerCollabMessage::ClientAck(value) => &value.payload,
       ServerCollabMessage::ServerInitSync(value) => &value.payload,
       ServerCollabMessage::AwarenessSync(value) => &value.payload,
       ServerCollabMessage::ServerBroadcast(value) => &value.payload,
+      ServerCollabMessage::ServerResumeCursor(_) => &EMPTY_BYTES,
+      ServerCollabMessage::ServerCollabLock(_) => &EMPTY_BYTES,
+      ServerCollabMessage::ServerCellLock(_) => &EMPTY_BYTES,
     }
   }
 
@@ -49,6 +62,9 @@ impl ServerCollabMessage {
       ServerCollabMessage::ServerInitSync(msg) => msg.payload.len(),
       ServerCollabMessage::AwarenessSync(msg) => msg.payload.len(),
       ServerCollabMessage::ServerBroadcast(msg) => msg.payload.len(),
+      ServerCollabMessage::ServerResumeCursor(_) => 0,
+      ServerCollabMessage::ServerCollabLock(_) => 0,
+      ServerCollabMessage::ServerCellLock(_) => 0,
     }
   }
 
@@ -58,6 +74,9 @@ impl ServerCollabMessage {
       ServerCollabMessage::ServerInitSync(value) => &value.origin,
       ServerCollabMessage::AwarenessSync(value) => &value.origin,
       ServerCollabMessage::ServerBroadcast(value) => &value.origin,
+      ServerCollabMessage::ServerResumeCursor(value) => &value.origin,
+      ServerCollabMessage::ServerCollabLock(value) => &value.origin,
+      ServerCollabMessage::ServerCellLock(value) => &value.origin,
     }
   }
 }
@@ -69,6 +88,9 @@ impl Display for ServerCollabMessage {
       ServerCollabMessage::ServerInitSync(value) => Display::fmt(&value, f),
       ServerCollabMessage::AwarenessSync(value) => Display::fmt(&value, f),
       ServerCollabMessage::ServerBroadcast(value) => Display::fmt(&value, f),
+      ServerCollabMessage::ServerResumeCursor(value) => Display::fmt(&value, f),
+      ServerCollabMessage::ServerCollabLock(value) => Display::fmt(&value, f),
+      ServerCollabMessage::ServerCellLock(value) => Display::fmt(&value, f),
     }
   }
 }
@@ -82,6 +104,9 @@ impl TryFrom<CollabMessage> for ServerCollabMessage {
       CollabMessage::ServerInitSync(msg) => Ok(ServerCollabMessage::ServerInitSync(msg)),
       CollabMessage::AwarenessSync(msg) => Ok(ServerCollabMessage::AwarenessSync(msg)),
       CollabMessage::ServerBroadcast(msg) => Ok(ServerCollabMessage::ServerBroadcast(msg)),
+      CollabMessage::ServerResumeCursor(msg) => Ok(ServerCollabMessage::ServerResumeCursor(msg)),
+      CollabMessage::ServerCollabLock(msg) => Ok(ServerCollabMessage::ServerCollabLock(msg)),
+      CollabMessage::ServerCellLock(msg) => Ok(ServerCollabMessage::ServerCellLock(msg)),
       _ => Err(anyhow!("Invalid collab message type.")),
     }
   }
@@ -211,6 +236,11 @@ pub enum AckCode {
   Internal = 3,
   EncodeStateAsUpdateFail = 4,
   MissUpdate = 5,
+  PermissionDenied = 6,
+  /// The collab reached the maximum number of read-only observers.
+  ObserverLimitReached = 7,
+  /// The workspace is over the limits of its plan, its collabs can not be changed.
+  WorkspaceReadOnly = 8,
 }
 
 impl From<u8> for AckCode {
@@ -222,6 +252,9 @@ impl From<u8> for AckCode {
       3 => AckCode::Internal,
       4 => AckCode::EncodeStateAsUpdateFail,
       5 => AckCode::MissUpdate,
+      6 => AckCode::PermissionDenied,
+      7 => AckCode::ObserverLimitReached,
+      8 => AckCode::WorkspaceReadOnly,
       _ => AckCode::Internal,
     }
   }
@@ -328,3 +361,115 @@ impl Display for AwarenessSync {
     ))
   }
 }
+
+/// Sent to clients that joined a group with [crate::ResumeSync]. The `resume_token` is the id of
+/// the last collab update stream entry that the server already delivered to the client. Clients
+/// keep the latest token and present it in the next [crate::ResumeSync] after reconnecting.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize, Hash)]
+pub struct ResumeCursor {
+  pub origin: CollabOrigin,
+  pub object_id: String,
+  pub resume_token: String,
+}
+
+impl ResumeCursor {
+  pub fn new(object_id: String, resume_token: String) -> Self {
+    Self {
+      origin: CollabOrigin::Server,
+      object_id,
+      resume_token,
+    }
+  }
+}
+
+impl Display for ResumeCursor {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_fmt(format_args!(
+      "resume cursor: [oid:{}|token:{}]",
+      self.object_id, self.resume_token,
+    ))
+  }
+}
+
+/// Broadcast to every client that has the collab open when the collab is locked or unlocked.
+/// While a collab is locked, updates sent by clients are rejected with
+/// [AckCode::PermissionDenied], so editors are expected to switch to read-only mode.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize, Hash)]
+pub struct CollabLockChanged {
+  pub origin: CollabOrigin,
+  pub object_id: String,
+  pub locked: bool,
+  /// The user that locked or unlocked the collab.
+  pub uid: i64,
+  pub reason: Option<String>,
+}
+
+impl CollabLockChanged {
+  pub fn new(object_id: String, locked: bool, uid: i64, reason: Option<String>) -> Self {
+    Self {
+      origin: CollabOrigin::Server,
+      object_id,
+      locked,
+      uid,
+      reason,
+    }
+  }
+}
+
+impl Display for CollabLockChanged {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_fmt(format_args!(
+      "collab lock: [oid:{}|locked:{}|uid:{}]",
+      self.object_id, self.locked, self.uid,
+    ))
+  }
+}
+
+/// Client holding the advisory lock of a database cell, see [crate::CellLockRequest].
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize, Hash)]
+pub struct CellLockHolder {
+  pub uid: i64,
+  pub device_id: String,
+  /// Unix timestamp in seconds after which the lock is released if not acquired again.
+  pub expires_at: i64,
+}
+
+/// Broadcast to every client that has the collab open when the advisory lock of a cell is
+/// acquired, renewed or released. `holder` is None once the cell is released.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize, Hash)]
+pub struct CellLockChanged {
+  pub origin: CollabOrigin,
+  pub object_id: String,
+  pub row_id: String,
+  pub field_id: String,
+  pub holder: Option<CellLockHolder>,
+}
+
+impl CellLockChanged {
+  pub fn new(
+    object_id: String,
+    row_id: String,
+    field_id: String,
+    holder: Option<CellLockHolder>,
+  ) -> Self {
+    Self {
+      origin: CollabOrigin::Server,
+      object_id,
+      row_id,
+      field_id,
+      holder,
+    }
+  }
+}
+
+impl Display for CellLockChanged {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_fmt(format_args!(
+      "cell lock: [oid:{}|row:{}|field:{}|holder:{:?}]",
+      self.object_id,
+      self.row_id,
+      self.field_id,
+      self.holder.as_ref().map(|holder| holder.uid),
+    ))
+  }
+}
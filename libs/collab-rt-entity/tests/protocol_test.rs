@@ -0,0 +1,70 @@
+use collab::core::origin::CollabOrigin;
+use collab_entity::CollabType;
+use collab_rt_entity::{
+  ClientCollabMessage, InitSync, RealtimeCapability, RealtimeProtocol, REALTIME_PROTOCOL_VERSION,
+};
+
+#[test]
+fn negotiate_with_newer_and_legacy_peers() {
+  let newer_peer =
+    RealtimeProtocol::from_headers(Some("3"), Some("batch-init-sync, delta-sync,compression"));
+  assert_eq!(newer_peer.version, 3);
+  let protocol = RealtimeProtocol::current().negotiate(&newer_peer);
+  assert_eq!(protocol.version, REALTIME_PROTOCOL_VERSION);
+  assert!(protocol.supports(RealtimeCapability::Compression));
+  assert!(protocol.supports(RealtimeCapability::BatchInitSync));
+  assert!(!protocol.supports(RealtimeCapability::ExtendedSystemMessages));
+  assert_eq!(
+    protocol.capabilities_header(),
+    "compression,batch-init-sync"
+  );
+
+  let legacy_peer = RealtimeProtocol::from_headers(None, Some("compression"));
+  assert_eq!(
+    RealtimeProtocol::current().negotiate(&legacy_peer),
+    RealtimeProtocol::legacy()
+  );
+  assert_eq!(
+    RealtimeProtocol::from_headers(Some("next"), None),
+    RealtimeProtocol::legacy()
+  );
+}
+
+#[test]
+fn resume_sync_only_when_negotiated() {
+  let init_sync = InitSync::new(
+    CollabOrigin::Empty,
+    "object_id".to_string(),
+    CollabType::Document,
+    "workspace_id".to_string(),
+    1,
+    vec![1, 2, 3],
+  );
+
+  let protocol = RealtimeProtocol::current().negotiate(&RealtimeProtocol::current());
+  assert!(protocol.supports(RealtimeCapability::ResumeSync));
+  let message =
+    ClientCollabMessage::new_sync_start(&protocol, init_sync.clone(), Some("token".to_string()));
+  match message {
+    ClientCollabMessage::ClientResumeSync { data } => {
+      assert_eq!(data.resume_token.as_deref(), Some("token"));
+      assert_eq!(data.payload, init_sync.payload);
+    },
+    _ => panic!("expected a resume sync, got {}", message),
+  }
+
+  // the servers released before the resume sync get a full init sync
+  let older_server = RealtimeProtocol::from_headers(Some("1"), Some("compression,batch-init-sync"));
+  let protocol = RealtimeProtocol::current().negotiate(&older_server);
+  let message =
+    ClientCollabMessage::new_sync_start(&protocol, init_sync.clone(), Some("token".to_string()));
+  assert!(matches!(
+    message,
+    ClientCollabMessage::ClientInitSync { .. }
+  ));
+  let message = ClientCollabMessage::new_sync_start(&RealtimeProtocol::legacy(), init_sync, None);
+  assert!(matches!(
+    message,
+    ClientCollabMessage::ClientInitSync { .. }
+  ));
+}
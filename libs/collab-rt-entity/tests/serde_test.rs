@@ -2,7 +2,9 @@ use bytes::Bytes;
 use collab::core::origin::CollabOrigin;
 use collab_entity::CollabType;
 use collab_rt_entity::user::UserMessage;
-use collab_rt_entity::{CollabMessage, InitSync, MsgId};
+use collab_rt_entity::{
+  CellLockRequest, ClientCollabMessage, CollabMessage, DatabaseCellFocus, InitSync, MsgId,
+};
 use collab_rt_entity::{RealtimeMessage, SystemMessage};
 use serde::{Deserialize, Serialize};
 use std::fs::File;
@@ -101,6 +103,44 @@ fn decode_version_2_collab_message_with_version_1_test_1() {
   }
 }
 
+#[test]
+fn encode_cell_lock_request_test() {
+  let request = CellLockRequest::new(
+    CollabOrigin::Empty,
+    "row 1".to_string(),
+    2,
+    "row 1".to_string(),
+    "field 1".to_string(),
+    true,
+  );
+  let message = RealtimeMessage::from(ClientCollabMessage::new_cell_lock(request.clone()));
+  let decoded = RealtimeMessage::decode(&message.encode().unwrap())
+    .unwrap()
+    .split_messages_by_object_id()
+    .unwrap();
+  match decoded.get("row 1").and_then(|messages| messages.first()) {
+    Some(ClientCollabMessage::ClientCellLock { data }) => assert_eq!(data, &request),
+    _ => panic!("Failed to decode the cell lock request"),
+  }
+}
+
+#[test]
+fn cell_focus_in_awareness_state_test() {
+  let focus = DatabaseCellFocus {
+    row_id: "row 1".to_string(),
+    field_id: "field 1".to_string(),
+    editing: true,
+  };
+  let state = DatabaseCellFocus::set_in_awareness_state(r#"{"user":{"name":"a"}}"#, Some(&focus));
+  assert_eq!(DatabaseCellFocus::from_awareness_state(&state), Some(focus));
+  assert!(state.contains(r#""user":{"name":"a"}"#));
+
+  let state = DatabaseCellFocus::set_in_awareness_state(&state, None);
+  assert_eq!(DatabaseCellFocus::from_awareness_state(&state), None);
+  assert!(state.contains(r#""user":{"name":"a"}"#));
+  assert_eq!(DatabaseCellFocus::from_awareness_state("not json"), None);
+}
+
 #[allow(dead_code)]
 fn write_message_to_file(
   message: &RealtimeMessage,
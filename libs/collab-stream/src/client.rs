@@ -2,21 +2,24 @@ use crate::collab_update_sink::{AwarenessUpdateSink, CollabUpdateSink};
 use crate::error::{internal, StreamError};
 use crate::lease::{Lease, LeaseAcquisition};
 use crate::metrics::CollabStreamMetrics;
-use crate::model::{AwarenessStreamUpdate, CollabStreamUpdate, MessageId};
+use crate::model::{AwarenessStreamConfig, AwarenessStreamUpdate, CollabStreamUpdate, MessageId};
+use crate::pubsub::{collab_observe_channel, ObservedCollabUpdate};
 use crate::stream_group::{StreamConfig, StreamGroup};
 use crate::stream_router::{StreamRouter, StreamRouterOptions};
 use futures::Stream;
 use redis::aio::ConnectionManager;
-use redis::streams::StreamReadReply;
+use redis::streams::{StreamRangeReply, StreamReadReply};
 use redis::{AsyncCommands, FromRedisValue};
+use std::collections::HashSet;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tracing::error;
 
 #[derive(Clone)]
 pub struct CollabRedisStream {
   connection_manager: ConnectionManager,
   stream_router: Arc<StreamRouter>,
+  awareness_config: AwarenessStreamConfig,
 }
 
 impl CollabRedisStream {
@@ -51,9 +54,19 @@ impl CollabRedisStream {
     Self {
       connection_manager,
       stream_router,
+      awareness_config: AwarenessStreamConfig::default(),
     }
   }
 
+  pub fn with_awareness_config(mut self, awareness_config: AwarenessStreamConfig) -> Self {
+    self.awareness_config = awareness_config;
+    self
+  }
+
+  pub fn awareness_config(&self) -> &AwarenessStreamConfig {
+    &self.awareness_config
+  }
+
   pub async fn lease(
     &self,
     workspace_id: &str,
@@ -112,9 +125,69 @@ impl CollabRedisStream {
     CollabUpdateSink::new(self.connection_manager.clone(), stream_key)
   }
 
+  /// Publishes a collab update to the read-only observers of the collab. Publishing is cheap when
+  /// nobody observes the collab, since Redis drops messages of channels without subscribers.
+  pub async fn publish_observed_update(
+    &self,
+    update: &ObservedCollabUpdate,
+  ) -> Result<(), StreamError> {
+    let payload = update.encode()?;
+    let mut conn = self.connection_manager.clone();
+    let () = conn
+      .publish(collab_observe_channel(&update.object_id), payload)
+      .await?;
+    Ok(())
+  }
+
   pub fn awareness_update_sink(&self, workspace_id: &str, object_id: &str) -> AwarenessUpdateSink {
     let stream_key = AwarenessStreamUpdate::stream_key(workspace_id, object_id);
-    AwarenessUpdateSink::new(self.connection_manager.clone(), stream_key)
+    AwarenessUpdateSink::new(
+      self.connection_manager.clone(),
+      stream_key,
+      self.awareness_config,
+    )
+  }
+
+  /// Returns the most recent awareness update of at most `limit` senders within the replay window,
+  /// oldest first. An awareness update carries the whole state of its sender, so only the latest
+  /// update of every sender is returned.
+  pub async fn recent_awareness_updates(
+    &self,
+    workspace_id: &str,
+    object_id: &str,
+    limit: usize,
+  ) -> Result<Vec<(MessageId, AwarenessStreamUpdate)>, StreamError> {
+    let stream_key = AwarenessStreamUpdate::stream_key(workspace_id, object_id);
+    let now = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .unwrap_or_default();
+    let min_id = now
+      .saturating_sub(self.awareness_config.replay_window)
+      .as_millis();
+    let mut conn = self.connection_manager.clone();
+    let reply: StreamRangeReply = conn
+      .xrevrange_count(
+        &stream_key,
+        "+",
+        min_id.to_string(),
+        self.awareness_config.max_len,
+      )
+      .await?;
+
+    let mut senders = HashSet::new();
+    let mut result = Vec::new();
+    for stream_id in reply.ids {
+      if result.len() >= limit {
+        break;
+      }
+      let message_id = MessageId::try_from(stream_id.id.as_str())?;
+      let update = AwarenessStreamUpdate::try_from(stream_id.map)?;
+      if senders.insert(update.sender.to_string()) {
+        result.push((message_id, update));
+      }
+    }
+    result.reverse();
+    Ok(result)
   }
 
   /// Reads all collab updates for a given `workspace_id`:`object_id` entry, starting
@@ -142,6 +215,22 @@ impl CollabRedisStream {
     Ok(result)
   }
 
+  /// Returns the id of the oldest collab update still kept in the `workspace_id`:`object_id`
+  /// stream, or `None` if the stream is empty. Entries older than this id have been pruned.
+  pub async fn first_collab_update_id(
+    &self,
+    workspace_id: &str,
+    object_id: &str,
+  ) -> Result<Option<MessageId>, StreamError> {
+    let stream_key = CollabStreamUpdate::stream_key(workspace_id, object_id);
+    let mut conn = self.connection_manager.clone();
+    let reply: StreamRangeReply = conn.xrange_count(&stream_key, "-", "+", 1).await?;
+    match reply.ids.first() {
+      None => Ok(None),
+      Some(stream_id) => Ok(Some(MessageId::try_from(stream_id.id.as_str())?)),
+    }
+  }
+
   /// Reads all collab updates for a given `workspace_id`:`object_id` entry, starting
   /// from a given message id. This stream will be kept alive and pass over all future messages
   /// coming from corresponding Redis stream until explicitly closed.
@@ -1,7 +1,7 @@
 use crate::error::StreamError;
-use crate::model::{AwarenessStreamUpdate, CollabStreamUpdate, MessageId};
+use crate::model::{AwarenessStreamConfig, AwarenessStreamUpdate, CollabStreamUpdate, MessageId};
 use redis::aio::ConnectionManager;
-use redis::cmd;
+use redis::{cmd, pipe};
 use tokio::sync::Mutex;
 
 pub struct CollabUpdateSink {
@@ -37,28 +37,37 @@ impl CollabUpdateSink {
 pub struct AwarenessUpdateSink {
   conn: Mutex<ConnectionManager>,
   stream_key: String,
+  config: AwarenessStreamConfig,
 }
 
 impl AwarenessUpdateSink {
-  pub fn new(conn: ConnectionManager, stream_key: String) -> Self {
+  pub fn new(conn: ConnectionManager, stream_key: String, config: AwarenessStreamConfig) -> Self {
     AwarenessUpdateSink {
       conn: conn.into(),
       stream_key,
+      config,
     }
   }
 
   pub async fn send(&self, msg: &AwarenessStreamUpdate) -> Result<MessageId, StreamError> {
     let mut lock = self.conn.lock().await;
-    let msg_id: MessageId = cmd("XADD")
+    // The stream is capped to the replay window, and removed once nobody has updated the
+    // awareness for the duration of the window.
+    let (msg_id, _): (MessageId, i64) = pipe()
+      .atomic()
+      .cmd("XADD")
       .arg(&self.stream_key)
       .arg("MAXLEN")
       .arg("~")
-      .arg(100) // we cap awareness stream to at most 20 awareness updates
+      .arg(self.config.max_len)
       .arg("*")
       .arg("sender")
       .arg(msg.sender.to_string())
       .arg("data")
       .arg(&*msg.data)
+      .cmd("PEXPIRE")
+      .arg(&self.stream_key)
+      .arg(self.config.replay_window.as_millis() as u64)
       .query_async(&mut *lock)
       .await?;
     Ok(msg_id)
@@ -12,6 +12,14 @@ else
 end
 "#;
 
+const RENEW_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+  return redis.call("PEXPIRE", KEYS[1], ARGV[2])
+else
+  return 0
+end
+"#;
+
 pub struct LeaseAcquisition {
   conn: Option<ConnectionManager>,
   stream_key: String,
@@ -27,6 +35,24 @@ impl LeaseAcquisition {
     }
   }
 
+  /// Extends the time-to-live of the lease. Returns `false` if the lease has already expired or
+  /// was acquired by someone else in the meantime.
+  pub async fn renew(&mut self, ttl: Duration) -> Result<bool, StreamError> {
+    match self.conn.as_mut() {
+      None => Ok(false),
+      Some(conn) => {
+        let script = redis::Script::new(RENEW_SCRIPT);
+        let result: i32 = script
+          .key(&self.stream_key)
+          .arg(self.token.to_le_bytes().as_slice())
+          .arg(ttl.as_millis() as u64)
+          .invoke_async(conn)
+          .await?;
+        Ok(result == 1)
+      },
+    }
+  }
+
   async fn release_internal<S: AsRef<str>>(
     mut conn: ConnectionManager,
     stream_key: S,
@@ -130,7 +156,16 @@ mod test {
 
     assert!(l2.is_none(), "should fail to acquire lease");
 
-    l1.unwrap().release().await.unwrap();
+    let mut l1 = l1.unwrap();
+    assert!(
+      l1.renew(std::time::Duration::from_secs(2)).await.unwrap(),
+      "should renew the lease it holds"
+    );
+    l1.release().await.unwrap();
+    assert!(
+      !l1.renew(std::time::Duration::from_secs(2)).await.unwrap(),
+      "should not renew a released lease"
+    );
 
     let l3 = conn
       .lease("stream1".into(), std::time::Duration::from_secs(1))
@@ -12,6 +12,7 @@ use std::collections::{BTreeMap, HashMap};
 use std::fmt::{Display, Formatter};
 use std::ops::Deref;
 use std::str::FromStr;
+use std::time::Duration;
 
 /// The [MessageId] generated by XADD has two parts: a timestamp and a sequence number, separated by
 /// a hyphen (-). The timestamp is based on the server's time when the message is added, and the
@@ -433,6 +434,28 @@ impl TryFrom<HashMap<String, redis::Value>> for CollabStreamUpdate {
   }
 }
 
+/// Bounds of the awareness stream of a collab, which is kept as a replay window so that a newly
+/// joined client can render the cursors of the other participants right away.
+#[derive(Debug, Clone, Copy)]
+pub struct AwarenessStreamConfig {
+  /// Approximate maximum number of awareness updates kept in the stream.
+  pub max_len: usize,
+  /// Awareness updates older than the window are not replayed.
+  pub replay_window: Duration,
+  /// Maximum number of participants whose awareness is replayed to a newly joined client.
+  pub replay_count: usize,
+}
+
+impl Default for AwarenessStreamConfig {
+  fn default() -> Self {
+    Self {
+      max_len: 100,
+      replay_window: Duration::from_secs(5 * 60),
+      replay_count: 20,
+    }
+  }
+}
+
 pub struct AwarenessStreamUpdate {
   pub data: Vec<u8>, // AwarenessUpdate::encode_v1
   pub sender: CollabOrigin,
@@ -1,4 +1,5 @@
 use crate::error::StreamError;
+use collab::core::origin::CollabOrigin;
 use collab_entity::proto;
 use futures::stream::BoxStream;
 use futures::StreamExt;
@@ -10,6 +11,12 @@ use serde::{Deserialize, Serialize};
 use tracing::instrument;
 
 const ACTIVE_COLLAB_CHANNEL: &str = "active_collab_channel";
+const COLLAB_OBSERVE_CHANNEL_PREFIX: &str = "af:collab_observe:";
+
+/// Name of the channel the updates of given collab are published on for read-only observers.
+pub fn collab_observe_channel(object_id: &str) -> String {
+  format!("{}{}", COLLAB_OBSERVE_CHANNEL_PREFIX, object_id)
+}
 
 pub struct CollabStreamSub {
   #[allow(deprecated)]
@@ -55,6 +62,52 @@ impl CollabStreamPub {
   }
 }
 
+/// Subscription to the updates of a single collab, used to fan out the updates to all the read-only
+/// observers of that collab connected to the same instance.
+pub struct CollabObserveSub {
+  #[allow(deprecated)]
+  conn: Connection,
+}
+
+impl CollabObserveSub {
+  #[allow(deprecated)]
+  pub fn new(conn: Connection) -> Self {
+    Self { conn }
+  }
+
+  pub async fn subscribe(
+    self,
+    object_id: &str,
+  ) -> Result<BoxStream<'static, Result<ObservedCollabUpdate, StreamError>>, StreamError> {
+    let mut pubsub = self.conn.into_pubsub();
+    pubsub.subscribe(collab_observe_channel(object_id)).await?;
+
+    let message_stream = pubsub
+      .into_on_message()
+      .map(|msg| ObservedCollabUpdate::from_vec(msg.get_payload_bytes()))
+      .boxed();
+    Ok(message_stream)
+  }
+}
+
+/// Collab update published for the read-only observers of a collab.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ObservedCollabUpdate {
+  pub object_id: String,
+  pub sender: CollabOrigin,
+  pub data: Vec<u8>,
+}
+
+impl ObservedCollabUpdate {
+  pub fn from_vec(vec: &[u8]) -> Result<Self, StreamError> {
+    bincode::deserialize(vec).map_err(StreamError::BinCodeSerde)
+  }
+
+  pub fn encode(&self) -> Result<Vec<u8>, StreamError> {
+    bincode::serialize(self).map_err(StreamError::BinCodeSerde)
+  }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct PubSubMessage {
   pub workspace_id: String,
@@ -115,4 +168,19 @@ mod test {
     assert_eq!(message, decoded_from_bincode);
     assert_eq!(message, decoded_from_protobuf);
   }
+
+  #[test]
+  fn test_observed_collab_update_encoding() {
+    let update = super::ObservedCollabUpdate {
+      object_id: "o1".to_string(),
+      sender: collab::core::origin::CollabOrigin::Server,
+      data: vec![1, 2, 3],
+    };
+    let encoded = update.encode().unwrap();
+    assert_eq!(
+      super::ObservedCollabUpdate::from_vec(&encoded).unwrap(),
+      update
+    );
+    assert_eq!(super::collab_observe_channel("o1"), "af:collab_observe:o1");
+  }
 }
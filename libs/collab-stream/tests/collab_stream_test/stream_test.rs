@@ -1 +1,41 @@
+use crate::collab_stream_test::test_util::{random_i64, stream_client};
+use collab::core::origin::{CollabClient, CollabOrigin};
+use collab_stream::model::AwarenessStreamUpdate;
 
+#[tokio::test]
+async fn replay_latest_awareness_of_each_sender_test() {
+  let workspace_id = "w1";
+  let oid = format!("o{}", random_i64());
+  let client = stream_client().await;
+  let sink = client.awareness_update_sink(workspace_id, &oid);
+
+  let alice = CollabOrigin::Client(CollabClient::new(1, "device_a"));
+  let bob = CollabOrigin::Client(CollabClient::new(2, "device_b"));
+  for (sender, data) in [
+    (alice.clone(), vec![1]),
+    (bob.clone(), vec![2]),
+    (alice.clone(), vec![3]),
+  ] {
+    sink
+      .send(&AwarenessStreamUpdate { data, sender })
+      .await
+      .unwrap();
+  }
+
+  let updates = client
+    .recent_awareness_updates(workspace_id, &oid, 10)
+    .await
+    .unwrap();
+  let updates: Vec<_> = updates
+    .into_iter()
+    .map(|(_, update)| (update.sender, update.data))
+    .collect();
+  assert_eq!(updates, vec![(bob, vec![2]), (alice.clone(), vec![3])]);
+
+  let updates = client
+    .recent_awareness_updates(workspace_id, &oid, 1)
+    .await
+    .unwrap();
+  assert_eq!(updates.len(), 1);
+  assert_eq!(updates[0].1.sender, alice);
+}
@@ -2,7 +2,7 @@ use crate::error::EntityError;
 use crate::error::EntityError::{DeserializationError, InvalidData};
 
 use bytes::Bytes;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use collab_entity::proto;
 use collab_entity::CollabType;
 use infra::validate::{validate_not_empty_payload, validate_not_empty_str};
@@ -22,6 +22,20 @@ use validator::Validate;
 /// The default compression level of ZSTD-compressed collabs.
 pub const ZSTD_COMPRESSION_LEVEL: i32 = 3;
 
+/// Requests a presigned url to upload a collab that is too large to be sent to the create collab
+/// api directly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateCollabUploadRequest {
+  /// Size of the uploaded body, which is the body of the create collab request.
+  pub content_length: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateCollabUploadResponse {
+  pub upload_id: String,
+  pub presigned_url: String,
+}
+
 #[derive(Debug, Clone, Validate, Serialize, Deserialize)]
 pub struct CreateCollabParams {
   #[validate(custom(function = "validate_not_empty_str"))]
@@ -323,6 +337,246 @@ pub struct AFSnapshotMeta {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AFSnapshotMetas(pub Vec<AFSnapshotMeta>);
 
+#[derive(Debug, Clone, Validate)]
+pub struct InsertCheckpointParams {
+  #[validate(custom(function = "validate_not_empty_str"))]
+  pub object_id: String,
+  #[validate(custom(function = "validate_not_empty_str"))]
+  pub workspace_id: String,
+  #[validate(custom(function = "validate_not_empty_str"))]
+  pub label: String,
+  pub created_by: Option<i64>,
+  #[validate(custom(function = "validate_not_empty_payload"))]
+  pub doc_state: Bytes,
+  pub collab_type: CollabType,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateCollabCheckpointParams {
+  pub label: String,
+  pub collab_type: CollabType,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevertCollabCheckpointParams {
+  pub collab_type: CollabType,
+}
+
+/// A named, user created snapshot of a collab. Unlike automatic snapshots, checkpoints are never
+/// trimmed and can be used to revert a collab to a known state.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFCollabCheckpoint {
+  pub checkpoint_id: i64,
+  pub object_id: String,
+  pub label: String,
+  pub created_by: Option<i64>,
+  pub created_at: DateTime<Utc>,
+}
+
+/// The history of a collab: named checkpoints and automatic snapshots, both in descending order
+/// of creation time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFCollabHistory {
+  pub checkpoints: Vec<AFCollabCheckpoint>,
+  pub snapshots: Vec<AFSnapshotMeta>,
+}
+
+/// A collab that has been frozen. Writes to the collab are rejected until it is unlocked.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AFCollabLock {
+  pub object_id: String,
+  pub locked_by: i64,
+  pub reason: Option<String>,
+  pub locked_at: DateTime<Utc>,
+}
+
+/// Edits of a collab received by a collaboration server since they were last recorded.
+#[derive(Debug, Clone)]
+pub struct CollabEditCounts {
+  pub total_updates: i64,
+  pub last_edited_by: i64,
+  pub last_edited_at: DateTime<Utc>,
+  /// Number of updates per day (UTC).
+  pub daily: Vec<(NaiveDate, i64)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFCollabEditStats {
+  pub object_id: String,
+  pub total_updates: i64,
+  pub last_edited_by: Option<i64>,
+  pub last_edited_at: DateTime<Utc>,
+  /// Days without any edit are omitted, the most recent day first.
+  pub daily: Vec<AFCollabDailyEdits>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFCollabDailyEdits {
+  pub date: NaiveDate,
+  pub edit_count: i64,
+}
+
+/// Number of days of the daily edit counts returned with the stats. Defaults to 30.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CollabEditStatsQuery {
+  pub days: Option<u32>,
+}
+
+/// A change of a cell of a database row. The values are the json representation of the cell, and
+/// are None when the cell didn't exist before or was removed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatabaseRowCellChange {
+  pub field_id: String,
+  pub old_value: Option<serde_json::Value>,
+  pub new_value: Option<serde_json::Value>,
+  /// Uid of the user who made the change, None for the changes made by the server.
+  pub changed_by: Option<i64>,
+  pub changed_at: DateTime<Utc>,
+}
+
+impl DatabaseRowCellChange {
+  /// Compares the cells of a row, keyed by field id, before and after an edit.
+  pub fn diff(
+    before: &HashMap<String, serde_json::Value>,
+    after: &HashMap<String, serde_json::Value>,
+    changed_by: Option<i64>,
+    changed_at: DateTime<Utc>,
+  ) -> Vec<Self> {
+    let mut changes = after
+      .iter()
+      .filter(|(field_id, value)| before.get(*field_id) != Some(*value))
+      .map(|(field_id, value)| Self {
+        field_id: field_id.clone(),
+        old_value: before.get(field_id).cloned(),
+        new_value: Some(value.clone()),
+        changed_by,
+        changed_at,
+      })
+      .chain(
+        before
+          .iter()
+          .filter(|(field_id, _)| !after.contains_key(*field_id))
+          .map(|(field_id, value)| Self {
+            field_id: field_id.clone(),
+            old_value: Some(value.clone()),
+            new_value: None,
+            changed_by,
+            changed_at,
+          }),
+      )
+      .collect::<Vec<_>>();
+    changes.sort_by(|a, b| a.field_id.cmp(&b.field_id));
+    changes
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFDatabaseRowActivity {
+  pub activity_id: i64,
+  pub field_id: String,
+  pub old_value: Option<serde_json::Value>,
+  pub new_value: Option<serde_json::Value>,
+  /// Uuid of the user who made the change, None for the changes made by the server.
+  pub changed_by: Option<Uuid>,
+  pub changed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFDatabaseRowActivities {
+  /// Most recent first.
+  pub activities: Vec<AFDatabaseRowActivity>,
+  pub has_more: bool,
+}
+
+/// Returns the activities older than `before`, an activity id. Defaults to the most recent 50.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DatabaseRowActivityQuery {
+  pub before: Option<i64>,
+  pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollabChangeKind {
+  Created,
+  Updated,
+  Deleted,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFChangedCollab {
+  pub object_id: String,
+  pub collab_type: CollabType,
+  pub change: CollabChangeKind,
+  pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFChangedCollabs {
+  /// Oldest change first.
+  pub changes: Vec<AFChangedCollab>,
+  /// Pass `next_since` and `next_after` as `since` and `after` to get the next changes. They
+  /// stay the same when there is no new change.
+  pub next_since: DateTime<Utc>,
+  pub next_after: Option<String>,
+  pub has_more: bool,
+}
+
+/// Returns the collabs changed after `since`. `after` is the object id of the last collab
+/// returned by the previous call, it's used to continue with the collabs updated at the same time.
+/// Defaults to 500 collabs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangedCollabQuery {
+  pub since: DateTime<Utc>,
+  pub after: Option<String>,
+  pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFWorkspaceCollab {
+  pub object_id: String,
+  /// Name of the view of the collab in the folder, of the first view for a database. None for
+  /// the collabs that have no view, like the rows and their documents.
+  pub name: Option<String>,
+  /// Size of the encoded collab in bytes.
+  pub size: i64,
+  pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFWorkspaceCollabs {
+  pub collabs: Vec<AFWorkspaceCollab>,
+  /// Pass `next_after` as `after` to get the next page.
+  pub next_after: Option<String>,
+  pub has_more: bool,
+}
+
+/// Lists the collabs of the workspace of the given type, ordered by object id. `collab_type` is
+/// the name of the type, e.g. `Document` or `Database`. `after` is the object id of the last collab
+/// returned by the previous call. Defaults to 500 collabs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceCollabsQuery {
+  #[serde(rename = "type")]
+  pub collab_type: String,
+  pub after: Option<String>,
+  pub limit: Option<i64>,
+}
+
+/// Whether the collab has been moved to the cold storage because it hasn't been edited for a
+/// long time. Opening an archived collab takes longer, it's restored on first access.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFCollabArchiveStatus {
+  pub object_id: String,
+  pub archived: bool,
+  pub storage_class: Option<String>,
+  pub archived_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LockCollabParams {
+  pub reason: Option<String>,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct QueryObjectSnapshotParams {
   pub object_id: String,
@@ -348,6 +602,32 @@ pub enum QueryCollabResult {
 #[derive(Serialize, Deserialize)]
 pub struct BatchQueryCollabResult(pub HashMap<String, QueryCollabResult>);
 
+/// A line of the newline delimited JSON returned by the streaming batch collab query: the result of
+/// a single object.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct QueryCollabStreamItem {
+  pub object_id: String,
+  pub result: QueryCollabResult,
+}
+
+/// Collabs to load into the collab memory cache ahead of editing.
+#[derive(Debug, Clone, Validate, Serialize, Deserialize)]
+pub struct WarmUpCollabParams {
+  #[validate(length(min = 1, max = 100))]
+  pub collabs: Vec<QueryCollab>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WarmUpCollabResult {
+  /// Collabs that were loaded from the disk into the memory cache.
+  pub warmed: Vec<String>,
+  /// Collabs that were already in the memory cache.
+  pub cached: Vec<String>,
+  /// Collabs that were not loaded, either because they don't exist or because the memory budget
+  /// of the request was exhausted.
+  pub skipped: Vec<String>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct WorkspaceUsage {
   pub total_document_size: i64,
@@ -380,6 +660,62 @@ pub struct UpdatePublishNamespace {
   pub new_namespace: String,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct AddPublishDomain {
+  pub domain: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PublishDomainQuery {
+  pub domain: String,
+}
+
+/// Date range of the publish analytics, inclusive. Defaults to the last 30 days.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct PublishAnalyticsQuery {
+  pub from: Option<NaiveDate>,
+  pub to: Option<NaiveDate>,
+  pub view_id: Option<Uuid>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PublishAnalytics {
+  pub from: NaiveDate,
+  pub to: NaiveDate,
+  pub daily_views: Vec<PublishDailyViews>,
+  /// Hosts of the referrers, with the most views first.
+  pub referrers: Vec<PublishViewsBucket>,
+  /// ISO 3166 country codes, with the most views first.
+  pub countries: Vec<PublishViewsBucket>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PublishDailyViews {
+  pub date: NaiveDate,
+  pub view_id: Uuid,
+  pub views: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PublishViewsBucket {
+  pub name: String,
+  pub views: i64,
+}
+
+/// A custom domain attached to the publish namespace of a workspace. The domain is served once the
+/// TXT record below is found in its DNS zone.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PublishDomainInfo {
+  pub domain: String,
+  pub verified: bool,
+  pub txt_record_name: String,
+  pub txt_record_value: String,
+  /// Host the domain should point to with a CNAME record, when configured on the server.
+  pub cname_target: Option<String>,
+  pub created_at: DateTime<Utc>,
+  pub verified_at: Option<DateTime<Utc>>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct UpdateDefaultPublishView {
   pub view_id: Uuid,
@@ -450,6 +786,7 @@ pub struct PublishInfoMeta<Meta> {
   pub meta: Meta,
 }
 
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone, Hash)]
 #[repr(i32)]
 pub enum AFRole {
@@ -616,6 +953,7 @@ impl Ord for AFAccessLevel {
 
 pub type RawData = Vec<u8>;
 
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Serialize, Deserialize)]
 pub struct AFUserProfile {
   pub uid: i64,
@@ -629,6 +967,7 @@ pub struct AFUserProfile {
   pub updated_at: i64,
 }
 
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AFWorkspace {
   pub workspace_id: Uuid,
@@ -643,7 +982,11 @@ pub struct AFWorkspace {
   pub icon: String,
   pub member_count: Option<i64>,
   #[serde(default)]
+  #[cfg_attr(feature = "openapi", schema(value_type = Option<String>, example = "Owner"))]
   pub role: Option<AFRole>, // role of the user requesting the workspace
+  /// The user is not a member of the workspace, only some of its pages are shared with the user.
+  #[serde(default)]
+  pub is_collab_guest: bool,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -653,6 +996,19 @@ pub struct AFWorkspaceSettings {
 
   #[serde(default)]
   pub ai_model: String,
+
+  #[serde(default)]
+  pub ai_features: AFWorkspaceAIFeatures,
+
+  /// IANA name of the time zone the dates without an offset are given in, for the date fields
+  /// that have no time zone of their own. Empty for UTC.
+  #[serde(default)]
+  pub default_timezone: String,
+
+  /// Name of the region the data of the workspace resides in, the realtime connections of the
+  /// clients opening the workspace are hinted to it. Empty when the workspace has no region.
+  #[serde(default)]
+  pub region: String,
 }
 
 impl Default for AFWorkspaceSettings {
@@ -660,16 +1016,104 @@ impl Default for AFWorkspaceSettings {
     Self {
       disable_search_indexing: false,
       ai_model: "".to_string(),
+      ai_features: AFWorkspaceAIFeatures::default(),
+      default_timezone: "".to_string(),
+      region: "".to_string(),
+    }
+  }
+}
+
+/// The AI features that the members of the workspace can use. All the features are enabled
+/// unless the owner of the workspace turns them off.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AFWorkspaceAIFeatures {
+  #[serde(default = "default_true")]
+  pub ai_chat: bool,
+  #[serde(default = "default_true")]
+  pub ai_complete: bool,
+  #[serde(default = "default_true")]
+  pub ai_summarize_row: bool,
+  #[serde(default = "default_true")]
+  pub ai_translate: bool,
+  #[serde(default = "default_true")]
+  pub ai_language_check: bool,
+}
+
+impl Default for AFWorkspaceAIFeatures {
+  fn default() -> Self {
+    Self {
+      ai_chat: true,
+      ai_complete: true,
+      ai_summarize_row: true,
+      ai_translate: true,
+      ai_language_check: true,
+    }
+  }
+}
+
+fn default_true() -> bool {
+  true
+}
+
+impl AFWorkspaceAIFeatures {
+  pub fn is_enabled(&self, feature: AIFeature) -> bool {
+    match feature {
+      AIFeature::Chat => self.ai_chat,
+      AIFeature::Complete => self.ai_complete,
+      AIFeature::SummarizeRow => self.ai_summarize_row,
+      AIFeature::Translate => self.ai_translate,
+      AIFeature::LanguageCheck => self.ai_language_check,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AIFeature {
+  Chat,
+  Complete,
+  SummarizeRow,
+  Translate,
+  LanguageCheck,
+}
+
+impl Display for AIFeature {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      AIFeature::Chat => f.write_str("ai_chat"),
+      AIFeature::Complete => f.write_str("ai_complete"),
+      AIFeature::SummarizeRow => f.write_str("ai_summarize_row"),
+      AIFeature::Translate => f.write_str("ai_translate"),
+      AIFeature::LanguageCheck => f.write_str("ai_language_check"),
     }
   }
 }
 
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct AFWorkspaceAIFeaturesChange {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub ai_chat: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub ai_complete: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub ai_summarize_row: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub ai_translate: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub ai_language_check: Option<bool>,
+}
+
 #[derive(Default, Serialize, Deserialize, Debug)]
 pub struct AFWorkspaceSettingsChange {
   #[serde(skip_serializing_if = "Option::is_none")]
   pub disable_search_indexing: Option<bool>,
   #[serde(skip_serializing_if = "Option::is_none")]
   pub ai_model: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub ai_features: Option<AFWorkspaceAIFeaturesChange>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub default_timezone: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub region: Option<String>,
 }
 
 impl AFWorkspaceSettingsChange {
@@ -677,6 +1121,9 @@ impl AFWorkspaceSettingsChange {
     Self {
       disable_search_indexing: None,
       ai_model: None,
+      ai_features: None,
+      default_timezone: None,
+      region: None,
     }
   }
   pub fn disable_search_indexing(mut self, disable_search_indexing: bool) -> Self {
@@ -687,8 +1134,21 @@ impl AFWorkspaceSettingsChange {
     self.ai_model = Some(ai_model);
     self
   }
+  pub fn ai_features(mut self, ai_features: AFWorkspaceAIFeaturesChange) -> Self {
+    self.ai_features = Some(ai_features);
+    self
+  }
+  pub fn default_timezone(mut self, default_timezone: String) -> Self {
+    self.default_timezone = Some(default_timezone);
+    self
+  }
+  pub fn region(mut self, region: String) -> Self {
+    self.region = Some(region);
+    self
+  }
 }
 
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Serialize, Deserialize)]
 pub struct AFUserWorkspaceInfo {
   pub user_profile: AFUserProfile,
@@ -1106,6 +1566,7 @@ pub struct AvatarImageSource {
   pub file_id: String,
 }
 
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Serialize_repr, Deserialize_repr, PartialEq, Debug, Copy, Clone)]
 #[repr(i32)]
 pub enum AccessRequestStatus {
@@ -1124,6 +1585,7 @@ pub struct AccessRequestWithViewId {
   pub created_at: DateTime<Utc>,
 }
 
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Serialize, Deserialize, Debug)]
 pub struct AccessRequesterInfo {
   pub uid: i64,
@@ -1133,6 +1595,7 @@ pub struct AccessRequesterInfo {
   pub avatar_url: Option<String>,
 }
 
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Serialize, Deserialize, Debug)]
 pub struct AccessRequestMinimal {
   pub request_id: Uuid,
@@ -1141,12 +1604,14 @@ pub struct AccessRequestMinimal {
   pub view_id: Uuid,
 }
 
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CreateAccessRequestParams {
   pub workspace_id: Uuid,
   pub view_id: Uuid,
 }
 
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ApproveAccessRequestParams {
   pub is_approved: bool,
@@ -1157,6 +1622,39 @@ pub struct CreateImportTask {
   #[validate(custom(function = "validate_not_empty_str"))]
   pub workspace_name: String,
   pub content_length: u64,
+  /// Unix timestamp in seconds before which the import is not started. The file can be uploaded
+  /// in the meantime.
+  #[serde(default)]
+  pub start_after: Option<i64>,
+  #[serde(default)]
+  pub source: ImportSource,
+  /// Sizes of the other zips of an export that Notion split into several parts, in order.
+  /// [Self::content_length] is the size of the first part.
+  #[serde(default)]
+  #[validate(length(max = 20))]
+  pub part_content_lengths: Vec<u64>,
+}
+
+/// Format of the file uploaded for an import.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ImportSource {
+  /// A zip file exported from Notion.
+  #[default]
+  #[serde(rename = "notion")]
+  Notion,
+  /// A workspace archive exported from AppFlowy Cloud, which keeps the collabs and the files of
+  /// the workspace as they are.
+  #[serde(rename = "appflowy")]
+  AppFlowy,
+}
+
+impl ImportSource {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ImportSource::Notion => "notion",
+      ImportSource::AppFlowy => "appflowy",
+    }
+  }
 }
 
 /// Create a import task
@@ -1165,6 +1663,28 @@ pub struct CreateImportTask {
 pub struct CreateImportTaskResponse {
   pub task_id: String,
   pub presigned_url: String,
+  /// Upload urls of the other parts, in the order of [CreateImportTask::part_content_lengths].
+  /// The import starts once all the parts are uploaded.
+  #[serde(default)]
+  pub part_presigned_urls: Vec<String>,
+}
+
+/// Query of an import whose file is sent in the same request, as the first field of a multipart
+/// form.
+#[derive(Debug, Clone, Validate, Serialize, Deserialize)]
+pub struct UploadImportTask {
+  #[validate(custom(function = "validate_not_empty_str"))]
+  pub workspace_name: String,
+  #[serde(default)]
+  pub source: ImportSource,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadImportTaskResponse {
+  pub task_id: String,
+  /// The workspace the file is imported into, it is created empty.
+  pub workspace_id: String,
+  pub file_size: u64,
 }
 
 #[derive(Debug)]
@@ -1205,6 +1725,59 @@ pub struct ListQuickNotesQueryParams {
   pub limit: Option<i32>,
 }
 
+/// What an import added to the workspace, stored in the metadata of the import task under
+/// [IMPORT_SUMMARY_KEY].
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ImportSummary {
+  pub documents: usize,
+  pub databases: usize,
+  pub rows: usize,
+  pub uploaded_files: usize,
+  /// Relations to imported rows that were written with another form of the row id.
+  #[serde(default)]
+  pub repaired_relations: usize,
+  /// Relations to rows that were not imported, which were removed from the relation cells.
+  #[serde(default)]
+  pub removed_relations: usize,
+  /// Pages of the export whose view was recorded, see the page mapping of the import task.
+  #[serde(default)]
+  pub mapped_pages: usize,
+  /// Links to pages of the export that were turned into mentions of the imported views.
+  #[serde(default)]
+  pub rewritten_links: usize,
+  /// Items of the imported file that are not in the workspace.
+  #[serde(default)]
+  pub skipped_items: Vec<ImportSkippedItem>,
+  #[serde(default)]
+  pub durations: ImportDurations,
+  /// Why the import failed, none when it succeeded.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+}
+
+/// Time spent in each stage of an import, in milliseconds. The stages that didn't run are zero.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct ImportDurations {
+  /// Downloading and unzipping the imported file.
+  pub download_ms: u64,
+  pub collab_build_ms: u64,
+  pub db_insert_ms: u64,
+  pub upload_ms: u64,
+  pub total_ms: u64,
+}
+
+pub const IMPORT_SUMMARY_KEY: &str = "summary";
+/// Key of the start time of a scheduled import in the metadata of the import task.
+pub const IMPORT_START_AFTER_KEY: &str = "start_after";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ImportSkippedItem {
+  /// The path of the file in the imported archive or the url of the remote resource.
+  pub name: String,
+  pub reason: String,
+}
+
 #[cfg(test)]
 mod test {
   use crate::dto::{CollabParams, CollabParamsV0};
@@ -1307,4 +1880,32 @@ mod test {
     let collab_params_decoded = CollabParams::from_protobuf_bytes(&protobuf_encoded).unwrap();
     assert_eq!(collab_params, collab_params_decoded);
   }
+
+  #[test]
+  fn database_row_cell_diff() {
+    use crate::dto::DatabaseRowCellChange;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    let before = HashMap::from([
+      ("name".to_string(), json!({"data": "apple"})),
+      ("price".to_string(), json!({"data": "1"})),
+      ("tag".to_string(), json!({"data": "fruit"})),
+    ]);
+    let after = HashMap::from([
+      ("name".to_string(), json!({"data": "apple"})),
+      ("price".to_string(), json!({"data": "2"})),
+      ("note".to_string(), json!({"data": "fresh"})),
+    ]);
+    let changes = DatabaseRowCellChange::diff(&before, &after, Some(1), chrono::Utc::now());
+    let fields = changes
+      .iter()
+      .map(|c| c.field_id.as_str())
+      .collect::<Vec<_>>();
+    assert_eq!(fields, vec!["note", "price", "tag"]);
+    assert_eq!(changes[0].old_value, None);
+    assert_eq!(changes[1].old_value, Some(json!({"data": "1"})));
+    assert_eq!(changes[1].new_value, Some(json!({"data": "2"})));
+    assert_eq!(changes[2].new_value, None);
+  }
 }
@@ -0,0 +1,66 @@
+use app_error::AppError;
+use shared_entity::dto::admin_dto::AdminAuditLog;
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::pg_row::AFAdminAuditLogRow;
+
+pub async fn insert_admin_audit_log<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  actor_uuid: &Uuid,
+  action: &str,
+  target: &str,
+  succeeded: bool,
+  detail: Option<&str>,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      INSERT INTO af_admin_audit_log (actor_uuid, action, target, succeeded, detail)
+      VALUES ($1, $2, $3, $4, $5)
+    "#,
+  )
+  .bind(actor_uuid)
+  .bind(action)
+  .bind(target)
+  .bind(succeeded)
+  .bind(detail)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+/// Returns the audit logs, most recent first.
+pub async fn select_admin_audit_logs<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  offset: i64,
+  limit: i64,
+) -> Result<Vec<AFAdminAuditLogRow>, AppError> {
+  let rows = sqlx::query_as::<_, AFAdminAuditLogRow>(
+    r#"
+      SELECT id, actor_uuid, action, target, succeeded, detail, created_at
+      FROM af_admin_audit_log
+      ORDER BY created_at DESC, id DESC
+      OFFSET $1
+      LIMIT $2
+    "#,
+  )
+  .bind(offset)
+  .bind(limit)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows)
+}
+
+impl From<AFAdminAuditLogRow> for AdminAuditLog {
+  fn from(row: AFAdminAuditLogRow) -> Self {
+    AdminAuditLog {
+      id: row.id,
+      actor_uuid: row.actor_uuid,
+      action: row.action,
+      target: row.target,
+      succeeded: row.succeeded,
+      detail: row.detail,
+      created_at: row.created_at,
+    }
+  }
+}
@@ -0,0 +1,50 @@
+use app_error::AppError;
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+pub async fn insert_user_auth_audit_log<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  user_uuid: Option<&Uuid>,
+  email: &str,
+  action: &str,
+  succeeded: bool,
+  detail: Option<&str>,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      INSERT INTO af_user_auth_audit_log (user_uuid, email, action, succeeded, detail)
+      VALUES ($1, $2, $3, $4, $5)
+    "#,
+  )
+  .bind(user_uuid)
+  .bind(email)
+  .bind(action)
+  .bind(succeeded)
+  .bind(detail)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+/// Returns when the action last succeeded for the email, None if it never did.
+pub async fn select_last_user_auth_action_at<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  email: &str,
+  action: &str,
+) -> Result<Option<DateTime<Utc>>, AppError> {
+  let created_at = sqlx::query_scalar::<_, DateTime<Utc>>(
+    r#"
+      SELECT created_at
+      FROM af_user_auth_audit_log
+      WHERE email = $1 AND action = $2 AND succeeded
+      ORDER BY created_at DESC
+      LIMIT 1
+    "#,
+  )
+  .bind(email)
+  .bind(action)
+  .fetch_optional(executor)
+  .await?;
+  Ok(created_at)
+}
@@ -0,0 +1,199 @@
+use app_error::AppError;
+use chrono::NaiveDate;
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::pg_row::{AFSubscriptionRow, AFWorkspaceReadOnlyRow};
+
+/// Records the event as processed. Returns false if it was already processed.
+pub async fn insert_billing_event<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  event_id: &str,
+  event_type: &str,
+) -> Result<bool, AppError> {
+  let result = sqlx::query(
+    r#"
+      INSERT INTO af_billing_event (event_id, event_type)
+      VALUES ($1, $2)
+      ON CONFLICT (event_id) DO NOTHING
+    "#,
+  )
+  .bind(event_id)
+  .bind(event_type)
+  .execute(executor)
+  .await?;
+  Ok(result.rows_affected() == 1)
+}
+
+/// Inserts or updates the subscription. The update is skipped when the subscription was last
+/// updated by a more recent event. Returns false if the update was skipped.
+pub async fn upsert_subscription<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  subscription: &AFSubscriptionRow,
+) -> Result<bool, AppError> {
+  let result = sqlx::query(
+    r#"
+      INSERT INTO af_subscription (subscription_id, customer_id, workspace_id, org_id, plan,
+        status, recurring_interval, quantity, current_period_end, cancel_at, event_created_at)
+      VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+      ON CONFLICT (subscription_id) DO UPDATE
+      SET customer_id = EXCLUDED.customer_id,
+          workspace_id = EXCLUDED.workspace_id,
+          org_id = EXCLUDED.org_id,
+          plan = EXCLUDED.plan,
+          status = EXCLUDED.status,
+          recurring_interval = EXCLUDED.recurring_interval,
+          quantity = EXCLUDED.quantity,
+          current_period_end = EXCLUDED.current_period_end,
+          cancel_at = EXCLUDED.cancel_at,
+          event_created_at = EXCLUDED.event_created_at,
+          updated_at = CURRENT_TIMESTAMP
+      WHERE af_subscription.event_created_at <= EXCLUDED.event_created_at
+    "#,
+  )
+  .bind(&subscription.subscription_id)
+  .bind(&subscription.customer_id)
+  .bind(subscription.workspace_id)
+  .bind(subscription.org_id)
+  .bind(subscription.plan)
+  .bind(&subscription.status)
+  .bind(subscription.recurring_interval)
+  .bind(subscription.quantity)
+  .bind(subscription.current_period_end)
+  .bind(subscription.cancel_at)
+  .bind(subscription.event_created_at)
+  .execute(executor)
+  .await?;
+  Ok(result.rows_affected() == 1)
+}
+
+/// Returns the subscriptions of the workspace and of its organization, whatever their status.
+pub async fn select_workspace_subscriptions<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+) -> Result<Vec<AFSubscriptionRow>, AppError> {
+  let rows = sqlx::query_as::<_, AFSubscriptionRow>(
+    r#"
+      SELECT s.subscription_id, s.customer_id, s.workspace_id, s.org_id, s.plan, s.status,
+        s.recurring_interval, s.quantity, s.current_period_end, s.cancel_at, s.event_created_at
+      FROM af_subscription s
+      WHERE s.workspace_id = $1
+        OR s.org_id = (SELECT w.org_id FROM af_workspace w WHERE w.workspace_id = $1)
+      ORDER BY s.current_period_end DESC
+    "#,
+  )
+  .bind(workspace_id)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows)
+}
+
+pub async fn increment_workspace_ai_responses<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      INSERT INTO af_workspace_ai_usage (created_at, workspace_id, ai_responses)
+      VALUES (now()::date, $1, 1)
+      ON CONFLICT (created_at, workspace_id) DO UPDATE
+      SET ai_responses = af_workspace_ai_usage.ai_responses + 1
+    "#,
+  )
+  .bind(workspace_id)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+/// Returns the number of AI responses generated for the workspace since the given day, included.
+pub async fn select_workspace_ai_responses_since<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  since: NaiveDate,
+) -> Result<i64, AppError> {
+  let count = sqlx::query_scalar::<_, i64>(
+    r#"
+      SELECT COALESCE(SUM(ai_responses), 0)::BIGINT
+      FROM af_workspace_ai_usage
+      WHERE workspace_id = $1 AND created_at >= $2
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(since)
+  .fetch_one(executor)
+  .await?;
+  Ok(count)
+}
+
+pub async fn select_workspace_read_only<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+) -> Result<Option<AFWorkspaceReadOnlyRow>, AppError> {
+  let row = sqlx::query_as::<_, AFWorkspaceReadOnlyRow>(
+    r#"
+      SELECT workspace_id, reason, since
+      FROM af_workspace_read_only
+      WHERE workspace_id = $1
+    "#,
+  )
+  .bind(workspace_id)
+  .fetch_optional(executor)
+  .await?;
+  Ok(row)
+}
+
+/// Puts the workspace in read-only mode. When the workspace already is, only the reason is
+/// updated, so `since` keeps the time the workspace first went over its limits.
+pub async fn upsert_workspace_read_only<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  reason: &str,
+) -> Result<AFWorkspaceReadOnlyRow, AppError> {
+  let row = sqlx::query_as::<_, AFWorkspaceReadOnlyRow>(
+    r#"
+      INSERT INTO af_workspace_read_only (workspace_id, reason)
+      VALUES ($1, $2)
+      ON CONFLICT (workspace_id) DO UPDATE SET reason = EXCLUDED.reason
+      RETURNING workspace_id, reason, since
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(reason)
+  .fetch_one(executor)
+  .await?;
+  Ok(row)
+}
+
+pub async fn delete_workspace_read_only<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+) -> Result<(), AppError> {
+  sqlx::query("DELETE FROM af_workspace_read_only WHERE workspace_id = $1")
+    .bind(workspace_id)
+    .execute(executor)
+    .await?;
+  Ok(())
+}
+
+/// Returns the workspaces whose read-only state can change without any action of their members:
+/// the read-only workspaces, and the workspaces with a subscription, directly or through their
+/// organization, since a subscription lapses or renews on its own.
+pub async fn select_workspace_ids_with_plan_state<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+) -> Result<Vec<Uuid>, AppError> {
+  let workspace_ids = sqlx::query_scalar::<_, Uuid>(
+    r#"
+      SELECT workspace_id FROM af_workspace_read_only
+      UNION
+      SELECT workspace_id FROM af_subscription WHERE workspace_id IS NOT NULL
+      UNION
+      SELECT w.workspace_id
+      FROM af_workspace w
+      JOIN af_subscription s ON s.org_id = w.org_id
+    "#,
+  )
+  .fetch_all(executor)
+  .await?;
+  Ok(workspace_ids)
+}
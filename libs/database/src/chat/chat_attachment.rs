@@ -0,0 +1,110 @@
+use app_error::AppError;
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::pg_row::AFChatAttachmentRow;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChatAttachmentState {
+  Processing = 0,
+  Ready = 1,
+  Failed = 2,
+}
+
+impl From<i16> for ChatAttachmentState {
+  fn from(val: i16) -> Self {
+    match val {
+      1 => ChatAttachmentState::Ready,
+      2 => ChatAttachmentState::Failed,
+      _ => ChatAttachmentState::Processing,
+    }
+  }
+}
+
+/// Object key of the file uploaded as the context of a chat.
+pub fn chat_attachment_object_key(
+  workspace_id: &Uuid,
+  chat_id: &Uuid,
+  attachment_id: &Uuid,
+) -> String {
+  format!(
+    "chat_attachment/{}/{}/{}",
+    workspace_id, chat_id, attachment_id
+  )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn insert_chat_attachment<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  attachment_id: &Uuid,
+  chat_id: &Uuid,
+  workspace_id: &Uuid,
+  uid: i64,
+  file_name: &str,
+  content_type: &str,
+  file_size: i64,
+  file_key: &str,
+) -> Result<AFChatAttachmentRow, AppError> {
+  let row = sqlx::query_as::<_, AFChatAttachmentRow>(
+    r#"
+      INSERT INTO af_chat_attachment
+        (attachment_id, chat_id, workspace_id, uid, file_name, content_type, file_size, file_key, status)
+      VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+      RETURNING attachment_id, chat_id, workspace_id, uid, file_name, content_type, file_size,
+        file_key, status, error, created_at
+    "#,
+  )
+  .bind(attachment_id)
+  .bind(chat_id)
+  .bind(workspace_id)
+  .bind(uid)
+  .bind(file_name)
+  .bind(content_type)
+  .bind(file_size)
+  .bind(file_key)
+  .bind(ChatAttachmentState::Processing as i16)
+  .fetch_one(executor)
+  .await?;
+  Ok(row)
+}
+
+pub async fn select_chat_attachments<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  chat_id: &Uuid,
+) -> Result<Vec<AFChatAttachmentRow>, AppError> {
+  let rows = sqlx::query_as::<_, AFChatAttachmentRow>(
+    r#"
+      SELECT attachment_id, chat_id, workspace_id, uid, file_name, content_type, file_size,
+        file_key, status, error, created_at
+      FROM af_chat_attachment
+      WHERE chat_id = $1
+      ORDER BY created_at ASC
+    "#,
+  )
+  .bind(chat_id)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows)
+}
+
+/// Marks the attachment as ready once its text is embedded, or as failed with the reason.
+pub async fn update_chat_attachment_status<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  attachment_id: &Uuid,
+  state: ChatAttachmentState,
+  error: Option<&str>,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      UPDATE af_chat_attachment
+      SET status = $2, error = $3, updated_at = CURRENT_TIMESTAMP
+      WHERE attachment_id = $1
+    "#,
+  )
+  .bind(attachment_id)
+  .bind(state as i16)
+  .bind(error)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
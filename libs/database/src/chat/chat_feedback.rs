@@ -0,0 +1,93 @@
+use app_error::AppError;
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::pg_row::AFChatMessageFeedbackRow;
+
+/// Returns the author of the message, or `None` if the message doesn't belong to the chat.
+pub async fn select_chat_message_author<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  chat_id: &Uuid,
+  message_id: i64,
+) -> Result<Option<serde_json::Value>, AppError> {
+  let author = sqlx::query_scalar::<_, serde_json::Value>(
+    r#"
+      SELECT author
+      FROM af_chat_messages
+      WHERE message_id = $1 AND chat_id = $2 AND deleted_at IS NULL
+    "#,
+  )
+  .bind(message_id)
+  .bind(chat_id)
+  .fetch_optional(executor)
+  .await?;
+  Ok(author)
+}
+
+/// Inserts the feedback of the user, or replaces the previous one. Returns the previous rating.
+pub async fn upsert_chat_message_feedback<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  message_id: i64,
+  uid: i64,
+  chat_id: &Uuid,
+  workspace_id: &Uuid,
+  rating: i16,
+  comment: Option<&str>,
+) -> Result<Option<i16>, AppError> {
+  let previous = sqlx::query_scalar::<_, Option<i16>>(
+    r#"
+      WITH previous AS (
+        SELECT rating FROM af_chat_message_feedback WHERE message_id = $1 AND uid = $2
+      ), upserted AS (
+        INSERT INTO af_chat_message_feedback (message_id, uid, chat_id, workspace_id, rating, comment)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (message_id, uid) DO UPDATE
+        SET rating = EXCLUDED.rating, comment = EXCLUDED.comment, updated_at = CURRENT_TIMESTAMP
+      )
+      SELECT (SELECT rating FROM previous)
+    "#,
+  )
+  .bind(message_id)
+  .bind(uid)
+  .bind(chat_id)
+  .bind(workspace_id)
+  .bind(rating)
+  .bind(comment)
+  .fetch_one(executor)
+  .await?;
+  Ok(previous)
+}
+
+/// Returns the feedback updated in the range, oldest first.
+pub async fn select_chat_message_feedback<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  from: Option<DateTime<Utc>>,
+  to: Option<DateTime<Utc>>,
+  workspace_id: Option<&Uuid>,
+  offset: i64,
+  limit: i64,
+) -> Result<Vec<AFChatMessageFeedbackRow>, AppError> {
+  let rows = sqlx::query_as::<_, AFChatMessageFeedbackRow>(
+    r#"
+      SELECT f.message_id, f.uid, f.chat_id, f.workspace_id, f.rating, f.comment,
+        m.content AS answer, f.created_at, f.updated_at
+      FROM af_chat_message_feedback f
+      JOIN af_chat_messages m ON m.message_id = f.message_id
+      WHERE ($1::TIMESTAMPTZ IS NULL OR f.updated_at >= $1)
+        AND ($2::TIMESTAMPTZ IS NULL OR f.updated_at < $2)
+        AND ($3::UUID IS NULL OR f.workspace_id = $3)
+      ORDER BY f.updated_at ASC, f.message_id ASC, f.uid ASC
+      OFFSET $4
+      LIMIT $5
+    "#,
+  )
+  .bind(from)
+  .bind(to)
+  .bind(workspace_id)
+  .bind(offset)
+  .bind(limit)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows)
+}
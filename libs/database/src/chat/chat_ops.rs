@@ -894,3 +894,74 @@ pub async fn select_chat_message_matching_reply_message_id(
 
   Ok(message)
 }
+
+/// Returns the messages of the chat that match the query, best match first, along with the part
+/// of their content that matches. The query accepts the web search syntax, for example quoted
+/// phrases and `-word`.
+pub async fn select_chat_messages_matching_query<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  chat_id: &str,
+  query: &str,
+  limit: i64,
+) -> Result<Vec<(ChatMessage, String)>, AppError> {
+  let chat_id = Uuid::from_str(chat_id)?;
+  #[allow(clippy::type_complexity)]
+  let rows: Vec<(
+    i64,
+    String,
+    DateTime<Utc>,
+    serde_json::Value,
+    serde_json::Value,
+    Option<i64>,
+    String,
+  )> = sqlx::query_as(
+    r#"
+      SELECT
+        cm.message_id,
+        cm.content,
+        cm.created_at,
+        cm.author,
+        cm.meta_data,
+        cm.reply_message_id,
+        ts_headline('simple', cm.content, q.query, 'MaxFragments=2, MaxWords=20, MinWords=5')
+      FROM af_chat_messages AS cm,
+        websearch_to_tsquery('simple', $2) AS q(query)
+      WHERE cm.chat_id = $1
+        AND cm.deleted_at IS NULL
+        AND cm.content_tsv @@ q.query
+      ORDER BY ts_rank(cm.content_tsv, q.query) DESC, cm.message_id DESC
+      LIMIT $3
+    "#,
+  )
+  .bind(chat_id)
+  .bind(query)
+  .bind(limit)
+  .fetch_all(executor)
+  .await?;
+
+  let messages = rows
+    .into_iter()
+    .flat_map(
+      |(message_id, content, created_at, author, meta_data, reply_message_id, snippet)| {
+        match serde_json::from_value::<ChatAuthor>(author) {
+          Ok(author) => Some((
+            ChatMessage {
+              author,
+              message_id,
+              content,
+              created_at,
+              meta_data,
+              reply_message_id,
+            },
+            snippet,
+          )),
+          Err(err) => {
+            warn!("Failed to deserialize author: {}", err);
+            None
+          },
+        }
+      },
+    )
+    .collect();
+  Ok(messages)
+}
@@ -1 +1,3 @@
+pub mod chat_attachment;
+pub mod chat_feedback;
 pub mod chat_ops;
@@ -0,0 +1,208 @@
+use app_error::AppError;
+use chrono::{DateTime, Utc};
+use collab_entity::CollabType;
+use sqlx::{Executor, FromRow, PgPool, Postgres, Transaction};
+use std::ops::DerefMut;
+use uuid::Uuid;
+
+use crate::collab::{compress_collab_blob, partition_key_from_collab_type};
+use crate::pg_row::AFCollabArchiveRow;
+
+/// Returns the key of the archived collab in the object storage. The archive has the same format
+/// as the collabs stored in the object storage: the zstd compressed doc state.
+pub fn collab_archive_key(workspace_id: &Uuid, object_id: &str) -> String {
+  format!(
+    "collab_archive/{}/{}/encoded_collab.v1.zstd",
+    workspace_id, object_id
+  )
+}
+
+/// A document whose last edit is older than the inactivity threshold.
+#[derive(Debug, FromRow)]
+pub struct CollabArchiveCandidate {
+  pub oid: String,
+  pub partition_key: i32,
+  pub workspace_id: Uuid,
+  pub len: Option<i32>,
+  pub updated_at: DateTime<Utc>,
+}
+
+/// Returns the documents that haven't been edited since `inactive_since`, least recently edited
+/// first. Only the collabs with edit statistics are considered.
+pub async fn select_collabs_to_archive(
+  pg_pool: &PgPool,
+  inactive_since: DateTime<Utc>,
+  limit: i64,
+) -> Result<Vec<CollabArchiveCandidate>, AppError> {
+  let partition_key = partition_key_from_collab_type(&CollabType::Document);
+  let candidates = sqlx::query_as::<_, CollabArchiveCandidate>(
+    r#"
+      SELECT c.oid, c.partition_key, c.workspace_id, c.len, c.updated_at
+      FROM af_collab c
+      JOIN af_collab_edit_stats s ON s.oid = c.oid
+      WHERE c.partition_key = $1
+        AND c.deleted_at IS NULL
+        AND s.last_edited_at < $2
+        AND NOT EXISTS (
+          SELECT 1 FROM af_collab_archive a
+          WHERE a.oid = c.oid AND a.partition_key = c.partition_key
+        )
+      ORDER BY s.last_edited_at
+      LIMIT $3
+    "#,
+  )
+  .bind(partition_key)
+  .bind(inactive_since)
+  .bind(limit)
+  .fetch_all(pg_pool)
+  .await?;
+  Ok(candidates)
+}
+
+/// Empties the blob of the collab once it has been copied to the archive. Returns false if the
+/// collab has been written since `updated_at`, in which case it must not be archived.
+pub async fn clear_archived_collab_blob(
+  tx: &mut Transaction<'_, Postgres>,
+  oid: &str,
+  partition_key: i32,
+  updated_at: &DateTime<Utc>,
+) -> Result<bool, AppError> {
+  let result = sqlx::query(
+    r#"
+      UPDATE af_collab
+      SET blob = ''::bytea, len = 0, compressed = FALSE
+      WHERE oid = $1 AND partition_key = $2 AND updated_at = $3
+    "#,
+  )
+  .bind(oid)
+  .bind(partition_key)
+  .bind(updated_at)
+  .execute(tx.deref_mut())
+  .await?;
+  Ok(result.rows_affected() > 0)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn insert_collab_archive<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  oid: &str,
+  partition_key: i32,
+  workspace_id: &Uuid,
+  archive_key: &str,
+  in_place: bool,
+  storage_class: &str,
+  archived_len: i64,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      INSERT INTO af_collab_archive
+        (oid, partition_key, workspace_id, archive_key, in_place, storage_class, archived_len)
+      VALUES ($1, $2, $3, $4, $5, $6, $7)
+      ON CONFLICT (oid, partition_key) DO UPDATE
+      SET archive_key = EXCLUDED.archive_key,
+          in_place = EXCLUDED.in_place,
+          storage_class = EXCLUDED.storage_class,
+          archived_len = EXCLUDED.archived_len,
+          archived_at = CURRENT_TIMESTAMP
+    "#,
+  )
+  .bind(oid)
+  .bind(partition_key)
+  .bind(workspace_id)
+  .bind(archive_key)
+  .bind(in_place)
+  .bind(storage_class)
+  .bind(archived_len)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+pub async fn select_collab_archive<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  oid: &str,
+  collab_type: &CollabType,
+) -> Result<Option<AFCollabArchiveRow>, AppError> {
+  let partition_key = partition_key_from_collab_type(collab_type);
+  let row = sqlx::query_as::<_, AFCollabArchiveRow>(
+    r#"
+      SELECT oid, partition_key, workspace_id, archive_key, in_place, storage_class,
+        archived_len, archived_at
+      FROM af_collab_archive
+      WHERE oid = $1 AND partition_key = $2
+    "#,
+  )
+  .bind(oid)
+  .bind(partition_key)
+  .fetch_optional(executor)
+  .await?;
+  Ok(row)
+}
+
+/// Writes the rehydrated blob back to the collab and removes the archive record. The blob is only
+/// restored if the collab hasn't been written since it was archived.
+pub async fn restore_archived_collab(
+  pg_pool: &PgPool,
+  oid: &str,
+  partition_key: i32,
+  blob: &[u8],
+) -> Result<(), AppError> {
+  let (stored_blob, compressed) = compress_collab_blob(blob)?;
+  let mut tx = pg_pool.begin().await?;
+  sqlx::query(
+    r#"
+      UPDATE af_collab
+      SET blob = $3, len = $4, compressed = $5
+      WHERE oid = $1 AND partition_key = $2 AND len = 0
+    "#,
+  )
+  .bind(oid)
+  .bind(partition_key)
+  .bind(stored_blob.as_ref())
+  .bind(blob.len() as i32)
+  .bind(compressed)
+  .execute(tx.deref_mut())
+  .await?;
+  delete_collab_archive(tx.deref_mut(), oid, partition_key).await?;
+  tx.commit().await?;
+  Ok(())
+}
+
+pub async fn delete_collab_archive<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  oid: &str,
+  partition_key: i32,
+) -> Result<(), AppError> {
+  sqlx::query("DELETE FROM af_collab_archive WHERE oid = $1 AND partition_key = $2")
+    .bind(oid)
+    .bind(partition_key)
+    .execute(executor)
+    .await?;
+  Ok(())
+}
+
+/// Returns the archives that are no longer needed because the collab has been written since it
+/// was archived.
+pub async fn select_outdated_collab_archives(
+  pg_pool: &PgPool,
+  limit: i64,
+) -> Result<Vec<AFCollabArchiveRow>, AppError> {
+  let rows = sqlx::query_as::<_, AFCollabArchiveRow>(
+    r#"
+      SELECT a.oid, a.partition_key, a.workspace_id, a.archive_key, a.in_place, a.storage_class,
+        a.archived_len, a.archived_at
+      FROM af_collab_archive a
+      LEFT JOIN af_collab c ON c.oid = a.oid AND c.partition_key = a.partition_key
+      LEFT JOIN af_collab_edit_stats s ON s.oid = a.oid
+      WHERE c.oid IS NULL
+        OR c.deleted_at IS NOT NULL
+        OR (NOT a.in_place AND c.len > 0)
+        OR (a.in_place AND s.last_edited_at > a.archived_at)
+      LIMIT $1
+    "#,
+  )
+  .bind(limit)
+  .fetch_all(pg_pool)
+  .await?;
+  Ok(rows)
+}
@@ -0,0 +1,69 @@
+use app_error::AppError;
+use chrono::{DateTime, Utc};
+use collab_entity::CollabType;
+use database_entity::dto::{AFChangedCollab, CollabChangeKind};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+#[derive(Debug, FromRow)]
+struct AFChangedCollabRow {
+  oid: String,
+  partition_key: i32,
+  created_at: DateTime<Utc>,
+  updated_at: DateTime<Utc>,
+  deleted_at: Option<DateTime<Utc>>,
+}
+
+/// Returns the collabs of the workspace changed after the (`since`, `after_oid`) position, ordered
+/// by update time then object id, so the next page starts after the last returned collab.
+///
+/// The update time is set when the transaction starts, so a transaction committing late can write
+/// an update time older than changes already returned. The changes of the last `settle_secs`
+/// seconds are left out so such transactions have committed before their changes are listed.
+pub async fn select_changed_collabs(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  since: &DateTime<Utc>,
+  after_oid: &str,
+  limit: i64,
+  settle_secs: f64,
+) -> Result<Vec<AFChangedCollab>, AppError> {
+  let rows = sqlx::query_as::<_, AFChangedCollabRow>(
+    r#"
+      SELECT oid, partition_key, created_at, updated_at, deleted_at
+      FROM af_collab
+      WHERE workspace_id = $1
+        AND (updated_at, oid) > ($2, $3)
+        AND updated_at < NOW() - make_interval(secs => $5)
+      ORDER BY updated_at, oid
+      LIMIT $4
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(since)
+  .bind(after_oid)
+  .bind(limit)
+  .bind(settle_secs)
+  .fetch_all(pg_pool)
+  .await?;
+
+  let changes = rows
+    .into_iter()
+    .map(|row| {
+      let change = if row.deleted_at.is_some() {
+        CollabChangeKind::Deleted
+      } else if &row.created_at >= since {
+        CollabChangeKind::Created
+      } else {
+        CollabChangeKind::Updated
+      };
+      AFChangedCollab {
+        object_id: row.oid,
+        collab_type: CollabType::from(row.partition_key),
+        change,
+        updated_at: row.updated_at,
+      }
+    })
+    .collect();
+  Ok(changes)
+}
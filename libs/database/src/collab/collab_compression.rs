@@ -0,0 +1,136 @@
+use std::borrow::Cow;
+use std::io;
+use std::ops::DerefMut;
+
+use app_error::AppError;
+use database_entity::dto::ZSTD_COMPRESSION_LEVEL;
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+/// Blobs smaller than this are stored uncompressed, compressing them saves little space and
+/// costs a decompression on every read.
+pub const COLLAB_COMPRESSION_MIN_LEN: usize = 1024;
+
+/// Returns the bytes stored in the `blob` column of `af_collab` for the encoded collab, and
+/// whether they are zstd compressed. The blob is kept as is when it's small or when compressing
+/// it doesn't make it smaller.
+pub fn compress_collab_blob(blob: &[u8]) -> io::Result<(Cow<'_, [u8]>, bool)> {
+  if blob.len() < COLLAB_COMPRESSION_MIN_LEN {
+    return Ok((Cow::Borrowed(blob), false));
+  }
+  let compressed = zstd::encode_all(blob, ZSTD_COMPRESSION_LEVEL)?;
+  if compressed.len() >= blob.len() {
+    return Ok((Cow::Borrowed(blob), false));
+  }
+  Ok((Cow::Owned(compressed), true))
+}
+
+/// Returns the encoded collab stored in the `blob` column of `af_collab`.
+pub fn decompress_collab_blob(blob: Vec<u8>, compressed: bool) -> io::Result<Vec<u8>> {
+  if compressed {
+    zstd::decode_all(blob.as_slice())
+  } else {
+    Ok(blob)
+  }
+}
+
+/// A collab stored before the blobs were compressed.
+#[derive(Debug, FromRow)]
+pub struct UncompressedCollab {
+  pub oid: String,
+  pub partition_key: i32,
+  pub workspace_id: Uuid,
+  pub blob: Vec<u8>,
+}
+
+/// Returns the uncompressed collabs big enough to be compressed, ordered by object id and
+/// starting after `after_oid`.
+pub async fn select_uncompressed_collabs(
+  pg_pool: &PgPool,
+  after_oid: Option<&str>,
+  limit: i64,
+) -> Result<Vec<UncompressedCollab>, AppError> {
+  let rows = sqlx::query_as::<_, UncompressedCollab>(
+    r#"
+      SELECT oid, partition_key, workspace_id, blob
+      FROM af_collab
+      WHERE NOT compressed AND len >= $1 AND deleted_at IS NULL
+        AND ($2::TEXT IS NULL OR oid > $2)
+      ORDER BY oid
+      LIMIT $3
+    "#,
+  )
+  .bind(COLLAB_COMPRESSION_MIN_LEN as i32)
+  .bind(after_oid)
+  .bind(limit)
+  .fetch_all(pg_pool)
+  .await?;
+  Ok(rows)
+}
+
+/// Replaces the blob of the collab with its compressed version. Returns false if the collab has
+/// been written since it was selected, or if compressing the blob doesn't make it smaller.
+///
+/// The content of the collab doesn't change, so its update time is kept and the collab isn't
+/// listed as changed to the clients syncing the workspace.
+pub async fn compress_stored_collab(
+  pg_pool: &PgPool,
+  collab: &UncompressedCollab,
+) -> Result<bool, AppError> {
+  let (blob, compressed) = compress_collab_blob(&collab.blob)?;
+  if !compressed {
+    return Ok(false);
+  }
+  let mut tx = pg_pool.begin().await?;
+  sqlx::query("SET LOCAL af.keep_updated_at = 'on'")
+    .execute(tx.deref_mut())
+    .await?;
+  let result = sqlx::query(
+    r#"
+      UPDATE af_collab
+      SET blob = $4, compressed = TRUE
+      WHERE oid = $1 AND partition_key = $2 AND workspace_id = $3
+        AND NOT compressed AND blob = $5
+    "#,
+  )
+  .bind(&collab.oid)
+  .bind(collab.partition_key)
+  .bind(collab.workspace_id)
+  .bind(blob.as_ref())
+  .bind(&collab.blob)
+  .execute(tx.deref_mut())
+  .await?;
+  tx.commit().await?;
+  Ok(result.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn compress_collab_blob_round_trip() {
+    let small = vec![1u8; COLLAB_COMPRESSION_MIN_LEN - 1];
+    let (stored, compressed) = compress_collab_blob(&small).unwrap();
+    assert!(!compressed);
+    assert_eq!(stored.as_ref(), small.as_slice());
+
+    let large = b"hello appflowy ".repeat(1000);
+    let (stored, compressed) = compress_collab_blob(&large).unwrap();
+    assert!(compressed);
+    assert!(stored.len() < large.len());
+    assert_eq!(
+      decompress_collab_blob(stored.into_owned(), compressed).unwrap(),
+      large
+    );
+
+    // random bytes don't compress, they are stored as is
+    let random = (0..4096u32)
+      .map(|i| (i.wrapping_mul(2654435761) >> 13) as u8)
+      .collect::<Vec<_>>();
+    let (stored, compressed) = compress_collab_blob(&random).unwrap();
+    if !compressed {
+      assert_eq!(stored.as_ref(), random.as_slice());
+    }
+  }
+}
@@ -1,14 +1,16 @@
 use anyhow::{anyhow, Context};
 use collab_entity::CollabType;
 use database_entity::dto::{
-  AFCollabEmbedInfo, AFSnapshotMeta, AFSnapshotMetas, CollabParams, QueryCollab, QueryCollabResult,
-  RawData, RepeatedAFCollabEmbedInfo,
+  AFCollabCheckpoint, AFCollabEmbedInfo, AFCollabLock, AFSnapshotMeta, AFSnapshotMetas,
+  CollabParams, QueryCollab, QueryCollabResult, RawData, RepeatedAFCollabEmbedInfo,
 };
 use shared_entity::dto::workspace_dto::{DatabaseRowUpdatedItem, EmbeddedCollabQuery};
 
-use crate::collab::{partition_key_from_collab_type, SNAPSHOT_PER_HOUR};
-use crate::pg_row::AFCollabRowMeta;
+use crate::collab::{
+  compress_collab_blob, decompress_collab_blob, partition_key_from_collab_type, SNAPSHOT_PER_HOUR,
+};
 use crate::pg_row::AFSnapshotRow;
+use crate::pg_row::{AFCollabCheckpointRow, AFCollabLockRow, AFCollabRowMeta};
 use app_error::AppError;
 use chrono::{DateTime, Duration, Utc};
 
@@ -57,32 +59,79 @@ pub async fn insert_into_af_collab(
     params.object_id,
     params.encoded_collab_v1.len(),
   );
+  // `len` is the size of the encoded collab, whether the stored blob is compressed or not
+  let (blob, compressed) = compress_collab_blob(&params.encoded_collab_v1)?;
 
-  sqlx::query!(
-    r#"
-      INSERT INTO af_collab (oid, blob, len, partition_key, encrypt, owner_uid, workspace_id)
-      VALUES ($1, $2, $3, $4, $5, $6, $7) ON CONFLICT (oid, partition_key)
-      DO UPDATE SET blob = $2, len = $3, encrypt = $5, owner_uid = $6 WHERE excluded.workspace_id = af_collab.workspace_id;
+  // The workspace id is part of the primary key since af_collab is partitioned by workspace, so
+  // the collab of another workspace with the same object id is excluded explicitly.
+  let result =
+    sqlx::query(
+      r#"
+      INSERT INTO af_collab
+        (oid, blob, len, partition_key, encrypt, owner_uid, workspace_id, compressed)
+      SELECT $1, $2, $3, $4, $5, $6, $7, $8
+      WHERE NOT EXISTS (
+        SELECT 1 FROM af_collab
+        WHERE oid = $1 AND partition_key = $4 AND workspace_id <> $7
+      )
+      ON CONFLICT (oid, partition_key, workspace_id)
+      DO UPDATE SET blob = $2, len = $3, encrypt = $5, owner_uid = $6, compressed = $8;
     "#,
-    params.object_id,
-    params.encoded_collab_v1.as_ref(),
-    params.encoded_collab_v1.len() as i32,
-    partition_key,
-    encrypt,
-    uid,
-    workspace_id,
-  )
-  .execute(tx.deref_mut())
-  .await.map_err(|err| {
-    AppError::Internal(anyhow!(
+    )
+    .bind(&params.object_id)
+    .bind(blob.as_ref())
+    .bind(params.encoded_collab_v1.len() as i32)
+    .bind(partition_key)
+    .bind(encrypt)
+    .bind(uid)
+    .bind(workspace_id)
+    .bind(compressed)
+    .execute(tx.deref_mut())
+    .await
+    .map_err(|err| {
+      AppError::Internal(anyhow!(
       "Update af_collab failed: workspace_id:{}, uid:{}, object_id:{}, collab_type:{}. error: {:?}",
       workspace_id, uid, params.object_id, params.collab_type, err,
     ))
-  })?;
+    })?;
+
+  // nothing is written when the object belongs to another workspace
+  if result.rows_affected() == 0 {
+    let owner_workspace_id =
+      select_conflicting_collab_workspace(tx.deref_mut(), uid, &params.object_id, partition_key)
+        .await?;
+    return Err(AppError::CollabObjectIdConflict {
+      object_id: params.object_id.clone(),
+      owner_workspace_id,
+    });
+  }
 
   Ok(())
 }
 
+/// Returns the workspace owning the collab if the user is a member of it.
+async fn select_conflicting_collab_workspace<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  uid: &i64,
+  object_id: &str,
+  partition_key: i32,
+) -> Result<Option<Uuid>, AppError> {
+  let workspace_id = sqlx::query_scalar::<_, Uuid>(
+    r#"
+      SELECT c.workspace_id
+      FROM af_collab c
+      JOIN af_workspace_member m ON m.workspace_id = c.workspace_id
+      WHERE c.oid = $1 AND c.partition_key = $2 AND m.uid = $3
+    "#,
+  )
+  .bind(object_id)
+  .bind(partition_key)
+  .bind(uid)
+  .fetch_optional(executor)
+  .await?;
+  Ok(workspace_id)
+}
+
 /// Inserts or updates multiple collaboration records for a specific user in bulk. It assumes you are the
 /// owner of the workspace.
 ///
@@ -157,6 +206,7 @@ pub async fn insert_into_af_collab_bulk_for_user(
   let mut object_ids: Vec<Uuid> = Vec::with_capacity(len);
   let mut blobs: Vec<Vec<u8>> = Vec::with_capacity(len);
   let mut lengths: Vec<i32> = Vec::with_capacity(len);
+  let mut compressed_flags: Vec<bool> = Vec::with_capacity(len);
   let mut partition_keys: Vec<i32> = Vec::with_capacity(len);
   let mut visited = HashSet::with_capacity(collab_params_list.len());
   for params in collab_params_list {
@@ -164,8 +214,10 @@ pub async fn insert_into_af_collab_bulk_for_user(
     if visited.insert(oid) {
       let partition_key = partition_key_from_collab_type(&params.collab_type);
       object_ids.push(oid);
-      blobs.push(params.encoded_collab_v1.to_vec());
+      let (blob, compressed) = compress_collab_blob(&params.encoded_collab_v1)?;
+      blobs.push(blob.into_owned());
       lengths.push(params.encoded_collab_v1.len() as i32);
+      compressed_flags.push(compressed);
       partition_keys.push(partition_key);
     }
   }
@@ -173,21 +225,31 @@ pub async fn insert_into_af_collab_bulk_for_user(
   let uids: Vec<i64> = vec![*uid; object_ids.len()];
   let workspace_ids: Vec<Uuid> = vec![workspace_uuid; object_ids.len()];
   // Bulk insert into `af_collab` for the provided collab params
-  sqlx::query!(
+  sqlx::query(
       r#"
-        INSERT INTO af_collab (oid, blob, len, partition_key, encrypt, owner_uid, workspace_id)
-        SELECT * FROM UNNEST($1::uuid[], $2::bytea[], $3::int[], $4::int[], $5::int[], $6::bigint[], $7::uuid[])
-        ON CONFLICT (oid, partition_key)
-        DO UPDATE SET blob = excluded.blob, len = excluded.len, encrypt = excluded.encrypt where af_collab.workspace_id = excluded.workspace_id
+        INSERT INTO af_collab
+          (oid, blob, len, partition_key, encrypt, owner_uid, workspace_id, compressed)
+        SELECT t.oid::text, t.blob, t.len, t.partition_key, t.encrypt, t.owner_uid, t.workspace_id,
+          t.compressed
+        FROM UNNEST($1::uuid[], $2::bytea[], $3::int[], $4::int[], $5::int[], $6::bigint[], $7::uuid[], $8::bool[])
+          AS t(oid, blob, len, partition_key, encrypt, owner_uid, workspace_id, compressed)
+        WHERE NOT EXISTS (
+          SELECT 1 FROM af_collab c
+          WHERE c.oid = t.oid::text AND c.partition_key = t.partition_key AND c.workspace_id <> t.workspace_id
+        )
+        ON CONFLICT (oid, partition_key, workspace_id)
+        DO UPDATE SET blob = excluded.blob, len = excluded.len, encrypt = excluded.encrypt,
+          compressed = excluded.compressed
       "#,
-      &object_ids,
-      &blobs,
-      &lengths,
-      &partition_keys,
-      &vec![encrypt; object_ids.len()],
-      &uids,
-      &workspace_ids
     )
+      .bind(&object_ids)
+      .bind(&blobs)
+      .bind(&lengths)
+      .bind(&partition_keys)
+      .bind(vec![encrypt; object_ids.len()])
+      .bind(&uids)
+      .bind(&workspace_ids)
+      .bind(&compressed_flags)
       .execute(tx.deref_mut())
       .await
       .map_err(|err| {
@@ -211,17 +273,44 @@ where
   E: Executor<'a, Database = Postgres>,
 {
   let partition_key = partition_key_from_collab_type(collab_type);
-  sqlx::query_scalar!(
+  let (blob, compressed) = sqlx::query_as::<_, (Vec<u8>, bool)>(
     r#"
-        SELECT blob
+        SELECT blob, compressed
         FROM af_collab
         WHERE oid = $1 AND partition_key = $2 AND deleted_at IS NULL;
         "#,
-    object_id,
-    partition_key,
   )
+  .bind(object_id)
+  .bind(partition_key)
   .fetch_one(conn)
-  .await
+  .await?;
+  decompress_collab_blob(blob, compressed).map_err(|err| sqlx::Error::Decode(err.into()))
+}
+
+/// Same as [select_blob_from_af_collab], but only looks into the partition of the workspace.
+pub async fn select_blob_from_af_collab_in_workspace<'a, E>(
+  conn: E,
+  workspace_id: &Uuid,
+  collab_type: &CollabType,
+  object_id: &str,
+) -> Result<Vec<u8>, sqlx::Error>
+where
+  E: Executor<'a, Database = Postgres>,
+{
+  let partition_key = partition_key_from_collab_type(collab_type);
+  let (blob, compressed) = sqlx::query_as::<_, (Vec<u8>, bool)>(
+    r#"
+      SELECT blob, compressed
+      FROM af_collab
+      WHERE workspace_id = $1 AND oid = $2 AND partition_key = $3 AND deleted_at IS NULL
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(object_id)
+  .bind(partition_key)
+  .fetch_one(conn)
+  .await?;
+  decompress_collab_blob(blob, compressed).map_err(|err| sqlx::Error::Decode(err.into()))
 }
 
 #[inline]
@@ -248,9 +337,12 @@ where
   .await
 }
 
+/// Reads the blobs of the collabs of the workspace. Filtering on the workspace id lets Postgres
+/// scan the hash partition of the workspace only.
 #[inline]
 pub async fn batch_select_collab_blob(
   pg_pool: &PgPool,
+  workspace_id: &Uuid,
   queries: Vec<QueryCollab>,
   results: &mut HashMap<String, QueryCollabResult>,
 ) {
@@ -264,30 +356,32 @@ pub async fn batch_select_collab_blob(
 
   for (collab_type, mut object_ids) in object_ids_by_collab_type.into_iter() {
     let partition_key = partition_key_from_collab_type(&collab_type);
-    let par_results: Result<Vec<QueryCollabData>, sqlx::Error> = sqlx::query_as!(
-      QueryCollabData,
-      r#"
-       SELECT oid, blob
+    let par_results: Result<Vec<QueryCollabData>, sqlx::Error> =
+      sqlx::query_as::<_, QueryCollabData>(
+        r#"
+       SELECT oid, blob, compressed
        FROM af_collab
-       WHERE oid = ANY($1) AND partition_key = $2 AND deleted_at IS NULL;
+       WHERE workspace_id = $3 AND oid = ANY($1) AND partition_key = $2 AND deleted_at IS NULL;
     "#,
-      &object_ids,
-      partition_key,
-    )
-    .fetch_all(pg_pool)
-    .await;
+      )
+      .bind(&object_ids)
+      .bind(partition_key)
+      .bind(workspace_id)
+      .fetch_all(pg_pool)
+      .await;
 
     match par_results {
       Ok(par_results) => {
         object_ids.retain(|oid| !par_results.iter().any(|par_result| par_result.oid == *oid));
 
         results.extend(par_results.into_iter().map(|par_result| {
-          (
-            par_result.oid,
-            QueryCollabResult::Success {
-              encode_collab_v1: par_result.blob,
+          let result = match decompress_collab_blob(par_result.blob, par_result.compressed) {
+            Ok(encode_collab_v1) => QueryCollabResult::Success { encode_collab_v1 },
+            Err(err) => QueryCollabResult::Failed {
+              error: format!("Failed to decompress collab: {}", err),
             },
-          )
+          };
+          (par_result.oid, result)
         }));
 
         results.extend(object_ids.into_iter().map(|oid| {
@@ -308,6 +402,7 @@ pub async fn batch_select_collab_blob(
 struct QueryCollabData {
   oid: String,
   blob: RawData,
+  compressed: bool,
 }
 
 pub async fn create_snapshot(
@@ -429,16 +524,16 @@ pub async fn select_snapshot(
   snapshot_id: &i64,
 ) -> Result<Option<AFSnapshotRow>, Error> {
   let workspace_id = Uuid::from_str(workspace_id).map_err(|err| Error::Decode(err.into()))?;
-  let row = sqlx::query_as!(
-    AFSnapshotRow,
+  let row = sqlx::query_as::<_, AFSnapshotRow>(
     r#"
-      SELECT * FROM af_collab_snapshot
+      SELECT sid, oid, blob, len, encrypt, deleted_at, created_at, workspace_id, storage_key
+      FROM af_collab_snapshot
       WHERE sid = $1 AND oid = $2 AND workspace_id = $3 AND deleted_at IS NULL;
     "#,
-    snapshot_id,
-    object_id,
-    workspace_id
   )
+  .bind(snapshot_id)
+  .bind(object_id)
+  .bind(workspace_id)
   .fetch_optional(pg_pool)
   .await?;
   Ok(row)
@@ -450,17 +545,17 @@ pub async fn select_latest_snapshot(
   workspace_id: &Uuid,
   object_id: &str,
 ) -> Result<Option<AFSnapshotRow>, Error> {
-  let row = sqlx::query_as!(
-    AFSnapshotRow,
+  let row = sqlx::query_as::<_, AFSnapshotRow>(
     r#"
-      SELECT * FROM af_collab_snapshot
+      SELECT sid, oid, blob, len, encrypt, deleted_at, created_at, workspace_id, storage_key
+      FROM af_collab_snapshot
       WHERE workspace_id = $1 AND oid = $2 AND deleted_at IS NULL
       ORDER BY created_at DESC
       LIMIT 1;
     "#,
-    workspace_id,
-    object_id
   )
+  .bind(workspace_id)
+  .bind(object_id)
   .fetch_optional(pg_pool)
   .await?;
   Ok(row)
@@ -486,6 +581,139 @@ pub async fn get_all_collab_snapshot_meta(
   Ok(AFSnapshotMetas(snapshots))
 }
 
+pub async fn insert_collab_checkpoint(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  object_id: &str,
+  label: &str,
+  created_by: Option<i64>,
+) -> Result<AFCollabCheckpoint, Error> {
+  let row = sqlx::query_as::<_, AFCollabCheckpointRow>(
+    r#"
+      INSERT INTO af_collab_checkpoint (workspace_id, oid, label, created_by)
+      VALUES ($1, $2, $3, $4)
+      RETURNING checkpoint_id, oid, label, created_by, created_at
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(object_id)
+  .bind(label)
+  .bind(created_by)
+  .fetch_one(pg_pool)
+  .await?;
+  Ok(row.into())
+}
+
+pub async fn delete_collab_checkpoint(pg_pool: &PgPool, checkpoint_id: i64) -> Result<(), Error> {
+  sqlx::query("DELETE FROM af_collab_checkpoint WHERE checkpoint_id = $1")
+    .bind(checkpoint_id)
+    .execute(pg_pool)
+    .await?;
+  Ok(())
+}
+
+pub async fn select_collab_checkpoint(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  object_id: &str,
+  checkpoint_id: i64,
+) -> Result<Option<AFCollabCheckpoint>, Error> {
+  let row = sqlx::query_as::<_, AFCollabCheckpointRow>(
+    r#"
+      SELECT checkpoint_id, oid, label, created_by, created_at
+      FROM af_collab_checkpoint
+      WHERE workspace_id = $1 AND oid = $2 AND checkpoint_id = $3
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(object_id)
+  .bind(checkpoint_id)
+  .fetch_optional(pg_pool)
+  .await?;
+  Ok(row.map(Into::into))
+}
+
+/// Returns the checkpoints of the given object in descending order of creation time.
+pub async fn select_collab_checkpoints(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  object_id: &str,
+) -> Result<Vec<AFCollabCheckpoint>, Error> {
+  let rows = sqlx::query_as::<_, AFCollabCheckpointRow>(
+    r#"
+      SELECT checkpoint_id, oid, label, created_by, created_at
+      FROM af_collab_checkpoint
+      WHERE workspace_id = $1 AND oid = $2
+      ORDER BY created_at DESC
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(object_id)
+  .fetch_all(pg_pool)
+  .await?;
+  Ok(rows.into_iter().map(Into::into).collect())
+}
+
+/// Locks the collab. Locking an already locked collab updates the owner and the reason. Returns
+/// [Error::RowNotFound] if the collab is locked in another workspace.
+pub async fn upsert_collab_lock(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  object_id: &str,
+  locked_by: i64,
+  reason: Option<&str>,
+) -> Result<AFCollabLock, Error> {
+  let row = sqlx::query_as::<_, AFCollabLockRow>(
+    r#"
+      INSERT INTO af_collab_lock (oid, workspace_id, locked_by, reason)
+      VALUES ($1, $2, $3, $4)
+      ON CONFLICT (oid) DO UPDATE
+      SET locked_by = EXCLUDED.locked_by, reason = EXCLUDED.reason, locked_at = NOW()
+      WHERE af_collab_lock.workspace_id = EXCLUDED.workspace_id
+      RETURNING oid, locked_by, reason, locked_at
+    "#,
+  )
+  .bind(object_id)
+  .bind(workspace_id)
+  .bind(locked_by)
+  .bind(reason)
+  .fetch_one(pg_pool)
+  .await?;
+  Ok(row.into())
+}
+
+pub async fn delete_collab_lock(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  object_id: &str,
+) -> Result<(), Error> {
+  sqlx::query("DELETE FROM af_collab_lock WHERE workspace_id = $1 AND oid = $2")
+    .bind(workspace_id)
+    .bind(object_id)
+    .execute(pg_pool)
+    .await?;
+  Ok(())
+}
+
+pub async fn select_collab_lock(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  object_id: &str,
+) -> Result<Option<AFCollabLock>, Error> {
+  let row = sqlx::query_as::<_, AFCollabLockRow>(
+    r#"
+      SELECT oid, locked_by, reason, locked_at
+      FROM af_collab_lock
+      WHERE workspace_id = $1 AND oid = $2
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(object_id)
+  .fetch_optional(pg_pool)
+  .await?;
+  Ok(row.map(Into::into))
+}
+
 #[inline]
 fn transform_record_not_found_error(
   result: Result<Option<bool>, sqlx::Error>,
@@ -502,6 +730,81 @@ fn transform_record_not_found_error(
   }
 }
 
+/// Returns the object ids and blobs of the collabs of the workspace, ordered by object id and
+/// starting after `after_oid`.
+pub async fn select_workspace_collab_blobs<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  after_oid: Option<&str>,
+  limit: i64,
+) -> Result<Vec<(String, Vec<u8>)>, AppError> {
+  let rows = sqlx::query_as::<_, (String, Vec<u8>, bool)>(
+    r#"
+      SELECT oid, blob, compressed FROM af_collab
+      WHERE workspace_id = $1 AND deleted_at IS NULL AND ($2::TEXT IS NULL OR oid > $2)
+      ORDER BY oid
+      LIMIT $3
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(after_oid)
+  .bind(limit)
+  .fetch_all(executor)
+  .await?;
+  rows
+    .into_iter()
+    .map(|(oid, blob, compressed)| Ok((oid, decompress_collab_blob(blob, compressed)?)))
+    .collect()
+}
+
+/// Returns the object ids of the collabs of the given type that belong to the workspace.
+pub async fn select_workspace_collab_oids_by_type<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  collab_type: &CollabType,
+) -> Result<Vec<String>, AppError> {
+  let partition_key = partition_key_from_collab_type(collab_type);
+  let oids = sqlx::query_scalar::<_, String>(
+    r#"
+      SELECT oid FROM af_collab
+      WHERE workspace_id = $1 AND partition_key = $2 AND deleted_at IS NULL
+      ORDER BY created_at
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(partition_key)
+  .fetch_all(executor)
+  .await?;
+  Ok(oids)
+}
+
+/// Returns the object id, size and update time of the collabs of the given type that belong to the
+/// workspace, ordered by object id and starting after `after_oid`.
+pub async fn select_workspace_collabs_by_type<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  collab_type: &CollabType,
+  after_oid: &str,
+  limit: i64,
+) -> Result<Vec<(String, i64, DateTime<Utc>)>, AppError> {
+  let partition_key = partition_key_from_collab_type(collab_type);
+  let rows = sqlx::query_as::<_, (String, i64, DateTime<Utc>)>(
+    r#"
+      SELECT oid, COALESCE(len, 0)::BIGINT, updated_at FROM af_collab
+      WHERE workspace_id = $1 AND partition_key = $2 AND deleted_at IS NULL AND oid > $3
+      ORDER BY oid
+      LIMIT $4
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(partition_key)
+  .bind(after_oid)
+  .bind(limit)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows)
+}
+
 /// Checks for the existence of a collaboration entry in the `af_collab` table using a specified `oid`.
 /// Use this method to verify if a specific collaboration object is already registered in the database.
 /// For a more efficient lookup, especially in frequent checks, consider using the cached method [CollabCache::is_exist].
@@ -662,3 +965,24 @@ where
   }
   Ok(RepeatedAFCollabEmbedInfo(items))
 }
+
+/// Tables partitioned by collab type, then by the hash of the workspace id.
+pub const WORKSPACE_HASH_PARTITIONED_TABLES: [&str; 3] =
+  ["af_collab", "af_snapshot_meta", "af_snapshot_state"];
+
+/// Creates the partition of `table` holding the collabs of the given type, split into `modulus`
+/// partitions by the hash of the workspace id. Does nothing if the partition already exists.
+pub async fn create_workspace_hash_partition<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  table: &str,
+  collab_type: &CollabType,
+  modulus: i32,
+) -> Result<(), AppError> {
+  sqlx::query("SELECT af_create_workspace_hash_partition($1, $2, $3)")
+    .bind(table)
+    .bind(partition_key_from_collab_type(collab_type))
+    .bind(modulus)
+    .execute(executor)
+    .await?;
+  Ok(())
+}
@@ -0,0 +1,157 @@
+use app_error::AppError;
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, FromRow, PgPool, Postgres};
+use uuid::Uuid;
+
+/// Returns the key of an offloaded snapshot in the object storage. The object holds the zstd
+/// compressed encoded collab, the same content as the `blob` column of an inline snapshot.
+///
+/// The key must not start with the `snapshot_` prefix used by the snapshots written to the object
+/// storage without a Postgres record, otherwise they would be listed twice.
+pub fn collab_snapshot_storage_key(workspace_id: &Uuid, object_id: &str, sid: i64) -> String {
+  format!(
+    "collabs/{}/{}/snapshots/{}.v1.zstd",
+    workspace_id, object_id, sid
+  )
+}
+
+/// Reserves the id of a new snapshot, so the key of its offloaded blob can be derived before the
+/// record is inserted.
+pub async fn next_collab_snapshot_id<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+) -> Result<i64, AppError> {
+  let sid = sqlx::query_scalar::<_, i64>(
+    "SELECT nextval(pg_get_serial_sequence('af_collab_snapshot', 'sid'))",
+  )
+  .fetch_one(executor)
+  .await?;
+  Ok(sid)
+}
+
+/// Inserts the record of a snapshot. `blob` is empty when the snapshot has been offloaded to
+/// `storage_key`, `len` is always the size of the encoded collab.
+pub async fn insert_collab_snapshot_record<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  sid: i64,
+  workspace_id: &Uuid,
+  object_id: &str,
+  blob: &[u8],
+  len: i32,
+  storage_key: Option<&str>,
+) -> Result<DateTime<Utc>, AppError> {
+  let created_at = sqlx::query_scalar::<_, DateTime<Utc>>(
+    r#"
+      INSERT INTO af_collab_snapshot (sid, oid, blob, len, encrypt, workspace_id, storage_key)
+      VALUES ($1, $2, $3, $4, 0, $5, $6)
+      RETURNING created_at
+    "#,
+  )
+  .bind(sid)
+  .bind(object_id)
+  .bind(blob)
+  .bind(len)
+  .bind(workspace_id)
+  .bind(storage_key)
+  .fetch_one(executor)
+  .await?;
+  Ok(created_at)
+}
+
+/// Deletes the oldest snapshots of the object beyond `limit`. Returns the keys of the offloaded
+/// blobs of the deleted snapshots, which have to be removed from the object storage.
+pub async fn delete_collab_snapshots_over_limit<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  object_id: &str,
+  limit: i64,
+) -> Result<Vec<String>, AppError> {
+  let keys = sqlx::query_scalar::<_, Option<String>>(
+    r#"
+      DELETE FROM af_collab_snapshot
+      WHERE workspace_id = $1 AND oid = $2 AND sid NOT IN (
+        SELECT sid FROM af_collab_snapshot
+        WHERE workspace_id = $1 AND oid = $2
+        ORDER BY created_at DESC
+        LIMIT $3
+      )
+      RETURNING storage_key
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(object_id)
+  .bind(limit)
+  .fetch_all(executor)
+  .await?;
+  Ok(keys.into_iter().flatten().collect())
+}
+
+/// A snapshot whose blob is still stored in Postgres.
+#[derive(Debug, FromRow)]
+pub struct CollabSnapshotOffloadCandidate {
+  pub sid: i64,
+  pub oid: String,
+  pub workspace_id: Uuid,
+  pub blob: Vec<u8>,
+}
+
+/// Returns the snapshots stored in Postgres whose blob is larger than `threshold` bytes.
+pub async fn select_collab_snapshots_to_offload(
+  pg_pool: &PgPool,
+  threshold: i64,
+  limit: i64,
+) -> Result<Vec<CollabSnapshotOffloadCandidate>, AppError> {
+  let rows = sqlx::query_as::<_, CollabSnapshotOffloadCandidate>(
+    r#"
+      SELECT sid, oid, workspace_id, blob
+      FROM af_collab_snapshot
+      WHERE storage_key IS NULL
+        AND deleted_at IS NULL
+        AND octet_length(blob) > $1
+      ORDER BY sid
+      LIMIT $2
+    "#,
+  )
+  .bind(threshold)
+  .bind(limit)
+  .fetch_all(pg_pool)
+  .await?;
+  Ok(rows)
+}
+
+/// Empties the blob of the snapshot once it has been copied to `storage_key`. Returns false if
+/// the snapshot has been deleted or offloaded in the meantime.
+pub async fn offload_collab_snapshot_blob<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  sid: i64,
+  storage_key: &str,
+) -> Result<bool, AppError> {
+  let result = sqlx::query(
+    r#"
+      UPDATE af_collab_snapshot
+      SET blob = ''::bytea, storage_key = $2
+      WHERE sid = $1 AND storage_key IS NULL
+    "#,
+  )
+  .bind(sid)
+  .bind(storage_key)
+  .execute(executor)
+  .await?;
+  Ok(result.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn snapshot_storage_key_is_not_listed_with_legacy_snapshots() {
+    let workspace_id = Uuid::new_v4();
+    let key = collab_snapshot_storage_key(&workspace_id, "object", 42);
+    assert_eq!(
+      key,
+      format!("collabs/{}/object/snapshots/42.v1.zstd", workspace_id)
+    );
+    let legacy_prefix = format!("collabs/{}/object/snapshot_", workspace_id);
+    assert!(!key.starts_with(&legacy_prefix));
+  }
+}
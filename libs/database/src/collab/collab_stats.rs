@@ -0,0 +1,120 @@
+use app_error::AppError;
+use chrono::{DateTime, NaiveDate, Utc};
+use database_entity::dto::{AFCollabDailyEdits, AFCollabEditStats, CollabEditCounts};
+use sqlx::{FromRow, PgPool};
+use std::ops::DerefMut;
+use uuid::Uuid;
+
+#[derive(Debug, FromRow)]
+struct AFCollabEditStatsRow {
+  oid: String,
+  total_updates: i64,
+  last_edited_by: Option<i64>,
+  last_edited_at: DateTime<Utc>,
+}
+
+#[derive(Debug, FromRow)]
+struct AFCollabDailyEditsRow {
+  day: NaiveDate,
+  edit_count: i64,
+}
+
+/// Adds the edits to the statistics of the collab. Several collaboration servers may record edits
+/// of the same collab, so the counters are incremented instead of being replaced.
+pub async fn upsert_collab_edit_counts(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  oid: &str,
+  counts: &CollabEditCounts,
+) -> Result<(), AppError> {
+  let mut tx = pg_pool.begin().await?;
+  sqlx::query(
+    r#"
+      INSERT INTO af_collab_edit_stats (oid, workspace_id, total_updates, last_edited_by, last_edited_at)
+      VALUES ($1, $2, $3, $4, $5)
+      ON CONFLICT (oid) DO UPDATE
+      SET total_updates = af_collab_edit_stats.total_updates + EXCLUDED.total_updates,
+          last_edited_by = CASE
+            WHEN EXCLUDED.last_edited_at >= af_collab_edit_stats.last_edited_at
+            THEN EXCLUDED.last_edited_by
+            ELSE af_collab_edit_stats.last_edited_by
+          END,
+          last_edited_at = GREATEST(af_collab_edit_stats.last_edited_at, EXCLUDED.last_edited_at)
+    "#,
+  )
+  .bind(oid)
+  .bind(workspace_id)
+  .bind(counts.total_updates)
+  .bind(counts.last_edited_by)
+  .bind(counts.last_edited_at)
+  .execute(tx.deref_mut())
+  .await?;
+
+  let (days, edit_counts): (Vec<NaiveDate>, Vec<i64>) = counts.daily.iter().cloned().unzip();
+  sqlx::query(
+    r#"
+      INSERT INTO af_collab_edit_daily (oid, day, edit_count)
+      SELECT $1, day, edit_count FROM UNNEST($2::date[], $3::bigint[]) AS t(day, edit_count)
+      ON CONFLICT (oid, day) DO UPDATE
+      SET edit_count = af_collab_edit_daily.edit_count + EXCLUDED.edit_count
+    "#,
+  )
+  .bind(oid)
+  .bind(days)
+  .bind(edit_counts)
+  .execute(tx.deref_mut())
+  .await?;
+  tx.commit().await?;
+  Ok(())
+}
+
+/// Returns the edit statistics of the collab with the daily counts since `since`, or `None` if the
+/// collab has never been edited through the collaboration server.
+pub async fn select_collab_edit_stats(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  oid: &str,
+  since: NaiveDate,
+) -> Result<Option<AFCollabEditStats>, AppError> {
+  let row = sqlx::query_as::<_, AFCollabEditStatsRow>(
+    r#"
+      SELECT oid, total_updates, last_edited_by, last_edited_at
+      FROM af_collab_edit_stats
+      WHERE oid = $1 AND workspace_id = $2
+    "#,
+  )
+  .bind(oid)
+  .bind(workspace_id)
+  .fetch_optional(pg_pool)
+  .await?;
+  let row = match row {
+    Some(row) => row,
+    None => return Ok(None),
+  };
+
+  let daily = sqlx::query_as::<_, AFCollabDailyEditsRow>(
+    r#"
+      SELECT day, edit_count
+      FROM af_collab_edit_daily
+      WHERE oid = $1 AND day >= $2
+      ORDER BY day DESC
+    "#,
+  )
+  .bind(oid)
+  .bind(since)
+  .fetch_all(pg_pool)
+  .await?;
+  Ok(Some(AFCollabEditStats {
+    object_id: row.oid,
+    total_updates: row.total_updates,
+    last_edited_by: row.last_edited_by,
+    last_edited_at: row.last_edited_at,
+    daily: daily
+      .into_iter()
+      .map(|row| AFCollabDailyEdits {
+        date: row.day,
+        edit_count: row.edit_count,
+      })
+      .collect(),
+  }))
+}
@@ -2,8 +2,9 @@ use app_error::AppError;
 use async_trait::async_trait;
 
 use database_entity::dto::{
-  AFAccessLevel, AFSnapshotMeta, AFSnapshotMetas, CollabParams, InsertSnapshotParams, QueryCollab,
-  QueryCollabParams, QueryCollabResult, SnapshotData,
+  AFAccessLevel, AFCollabCheckpoint, AFCollabHistory, AFSnapshotMeta, AFSnapshotMetas,
+  CollabEditCounts, CollabParams, DatabaseRowCellChange, InsertCheckpointParams,
+  InsertSnapshotParams, QueryCollab, QueryCollabParams, QueryCollabResult, SnapshotData,
 };
 
 use crate::collab::CollabType;
@@ -161,6 +162,38 @@ pub trait CollabStorage: Send + Sync + 'static {
     workspace_id: &str,
     oid: &str,
   ) -> AppResult<AFSnapshotMetas>;
+
+  async fn create_checkpoint(
+    &self,
+    params: InsertCheckpointParams,
+  ) -> AppResult<AFCollabCheckpoint>;
+
+  /// Returns the checkpoint along with the doc state of the collab at the time it was created.
+  async fn get_collab_checkpoint(
+    &self,
+    workspace_id: &str,
+    object_id: &str,
+    checkpoint_id: i64,
+  ) -> AppResult<(AFCollabCheckpoint, Vec<u8>)>;
+
+  /// Returns the named checkpoints and automatic snapshots of the given object.
+  async fn get_collab_history(&self, workspace_id: &str, oid: &str) -> AppResult<AFCollabHistory>;
+
+  /// Adds the edits received by the collaboration server to the edit statistics of the object.
+  async fn record_collab_edits(
+    &self,
+    workspace_id: &str,
+    oid: &str,
+    counts: &CollabEditCounts,
+  ) -> AppResult<()>;
+
+  /// Appends the cell changes of a database row to its activity log.
+  async fn record_database_row_activity(
+    &self,
+    workspace_id: &str,
+    row_id: &str,
+    changes: &[DatabaseRowCellChange],
+  ) -> AppResult<()>;
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -0,0 +1,48 @@
+use app_error::AppError;
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+/// Returns the field holding the external ids of the rows of the database, None if the database
+/// has none.
+pub async fn select_database_external_id_field<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  database_id: &str,
+) -> Result<Option<String>, AppError> {
+  let field_id = sqlx::query_scalar::<_, String>(
+    r#"
+      SELECT field_id FROM af_database_external_id_field
+      WHERE workspace_id = $1 AND database_id = $2
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(database_id)
+  .fetch_optional(executor)
+  .await?;
+  Ok(field_id)
+}
+
+pub async fn upsert_database_external_id_field<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  database_id: &str,
+  field_id: &str,
+  uid: i64,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      INSERT INTO af_database_external_id_field (workspace_id, database_id, field_id, updated_by)
+      VALUES ($1, $2, $3, $4)
+      ON CONFLICT (workspace_id, database_id)
+      DO UPDATE SET field_id = EXCLUDED.field_id, updated_by = EXCLUDED.updated_by,
+        updated_at = CURRENT_TIMESTAMP
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(database_id)
+  .bind(field_id)
+  .bind(uid)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
@@ -1,9 +1,23 @@
+mod collab_archive;
+mod collab_changes;
+mod collab_compression;
 mod collab_db_ops;
+mod collab_snapshot_storage;
+mod collab_stats;
 mod collab_storage;
+mod external_id;
+mod row_activity;
 
+pub use collab_archive::*;
+pub use collab_changes::*;
+pub use collab_compression::*;
 pub use collab_db_ops::*;
 use collab_entity::CollabType;
+pub use collab_snapshot_storage::*;
+pub use collab_stats::*;
 pub use collab_storage::*;
+pub use external_id::*;
+pub use row_activity::*;
 
 pub(crate) fn partition_key_from_collab_type(collab_type: &CollabType) -> i32 {
   match collab_type {
@@ -0,0 +1,137 @@
+use app_error::AppError;
+use chrono::{DateTime, Utc};
+use collab::preclude::{Collab, Map, MapRef, ReadTxn};
+use collab_database::rows::ROW_CELLS;
+use database_entity::dto::{AFDatabaseRowActivity, DatabaseRowCellChange};
+use sqlx::{Executor, FromRow, Postgres};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Key of the root map that holds the data of a database row, see `DatabaseRowBody`.
+const DATABASE_ROW_DATA: &str = "data";
+
+#[derive(Debug, FromRow)]
+struct AFDatabaseRowActivityRow {
+  activity_id: i64,
+  field_id: String,
+  old_value: Option<serde_json::Value>,
+  new_value: Option<serde_json::Value>,
+  changed_by: Option<Uuid>,
+  changed_at: DateTime<Utc>,
+}
+
+/// Returns the map holding the data of a database row collab, or `None` if the row has not been
+/// initialized yet.
+pub fn database_row_data<T: ReadTxn>(collab: &Collab, txn: &T) -> Option<MapRef> {
+  collab
+    .data
+    .get(txn, DATABASE_ROW_DATA)
+    .and_then(|out| out.cast::<MapRef>().ok())
+}
+
+/// Returns the json representation of the cells of a database row, keyed by field id.
+pub fn database_row_cells<T: ReadTxn>(
+  row_data: &MapRef,
+  txn: &T,
+) -> HashMap<String, serde_json::Value> {
+  let cells = match row_data
+    .get(txn, ROW_CELLS)
+    .and_then(|out| out.cast::<MapRef>().ok())
+  {
+    Some(cells) => cells,
+    None => return HashMap::new(),
+  };
+  cells
+    .iter(txn)
+    .filter_map(|(field_id, cell)| {
+      let value = serde_json::to_value(cell.to_json(txn)).ok()?;
+      Some((field_id.to_string(), value))
+    })
+    .collect()
+}
+
+pub async fn insert_database_row_activity<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  row_id: &str,
+  changes: &[DatabaseRowCellChange],
+) -> Result<(), AppError> {
+  if changes.is_empty() {
+    return Ok(());
+  }
+
+  let mut field_ids = Vec::with_capacity(changes.len());
+  let mut old_values = Vec::with_capacity(changes.len());
+  let mut new_values = Vec::with_capacity(changes.len());
+  let mut changed_by = Vec::with_capacity(changes.len());
+  let mut changed_at = Vec::with_capacity(changes.len());
+  for change in changes {
+    field_ids.push(change.field_id.clone());
+    old_values.push(change.old_value.clone());
+    new_values.push(change.new_value.clone());
+    changed_by.push(change.changed_by);
+    changed_at.push(change.changed_at);
+  }
+
+  sqlx::query(
+    r#"
+      INSERT INTO af_database_row_activity
+        (workspace_id, row_id, field_id, old_value, new_value, changed_by, changed_at)
+      SELECT $1, $2, field_id, old_value, new_value, changed_by, changed_at
+      FROM UNNEST($3::text[], $4::jsonb[], $5::jsonb[], $6::bigint[], $7::timestamptz[])
+        AS t(field_id, old_value, new_value, changed_by, changed_at)
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(row_id)
+  .bind(field_ids)
+  .bind(old_values)
+  .bind(new_values)
+  .bind(changed_by)
+  .bind(changed_at)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+/// Returns the cell changes of a database row, most recent first. Only the changes with an id
+/// lower than `before` are returned when it is set.
+pub async fn select_database_row_activity<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  row_id: &str,
+  before: Option<i64>,
+  limit: i64,
+) -> Result<Vec<AFDatabaseRowActivity>, AppError> {
+  let rows = sqlx::query_as::<_, AFDatabaseRowActivityRow>(
+    r#"
+      SELECT a.activity_id, a.field_id, a.old_value, a.new_value, u.uuid AS changed_by, a.changed_at
+      FROM af_database_row_activity a
+      LEFT JOIN af_user u ON u.uid = a.changed_by
+      WHERE a.workspace_id = $1
+        AND a.row_id = $2
+        AND ($3::bigint IS NULL OR a.activity_id < $3)
+      ORDER BY a.activity_id DESC
+      LIMIT $4
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(row_id)
+  .bind(before)
+  .bind(limit)
+  .fetch_all(executor)
+  .await?;
+  Ok(
+    rows
+      .into_iter()
+      .map(|row| AFDatabaseRowActivity {
+        activity_id: row.activity_id,
+        field_id: row.field_id,
+        old_value: row.old_value,
+        new_value: row.new_value,
+        changed_by: row.changed_by,
+        changed_at: row.changed_at,
+      })
+      .collect(),
+  )
+}
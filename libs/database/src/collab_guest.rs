@@ -0,0 +1,296 @@
+use app_error::AppError;
+use chrono::{DateTime, Utc};
+use database_entity::dto::{AFAccessLevel, AFRole};
+use sqlx::{Executor, FromRow, Postgres, Transaction};
+use uuid::Uuid;
+
+/// A guest of a collab: either a user with an account, or an email invited before it had one.
+#[derive(Debug, Clone, FromRow)]
+pub struct AFCollabGuestRow {
+  /// None while the invitation of the email is pending.
+  pub uid: Option<i64>,
+  pub name: Option<String>,
+  pub email: String,
+  pub access_level: i32,
+  pub created_at: DateTime<Utc>,
+}
+
+/// A user who can access a collab, either as a member of the workspace or because the collab was
+/// shared with the user, or both.
+#[derive(Debug, Clone, FromRow)]
+pub struct AFCollabMemberRow {
+  pub uid: i64,
+  pub uuid: Uuid,
+  pub name: String,
+  pub email: String,
+  pub avatar_url: Option<String>,
+  /// The role id of the user in the workspace, None when the user is not a member of it.
+  pub role: Option<i32>,
+  /// The access level the collab was shared with, None when it was not shared with the user.
+  pub access_level: Option<i32>,
+}
+
+/// Returns the collabs of the workspace shared with the user, with the access level of the user
+/// on each of them. Empty when the user is not a guest of the workspace.
+pub async fn select_collab_guest_access_levels<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  uid: i64,
+  workspace_id: &Uuid,
+) -> Result<Vec<(String, AFAccessLevel)>, AppError> {
+  let rows = sqlx::query_as::<_, (String, i32)>(
+    r#"
+      SELECT m.oid, p.access_level
+      FROM af_collab_member m
+      JOIN af_permissions p ON p.id = m.permission_id
+      WHERE m.workspace_id = $1 AND m.uid = $2
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(uid)
+  .fetch_all(executor)
+  .await?;
+  Ok(
+    rows
+      .into_iter()
+      .map(|(oid, access_level)| (oid, AFAccessLevel::from(access_level)))
+      .collect(),
+  )
+}
+
+/// Gives the user the access level on the collab, replacing the previous one.
+pub async fn upsert_collab_guest<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  oid: &str,
+  uid: i64,
+  access_level: AFAccessLevel,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      INSERT INTO af_collab_member (uid, oid, permission_id, workspace_id)
+      SELECT $1, $2, id, $4 FROM af_permissions WHERE access_level = $3
+      ON CONFLICT (uid, oid)
+      DO UPDATE SET permission_id = EXCLUDED.permission_id, workspace_id = EXCLUDED.workspace_id
+    "#,
+  )
+  .bind(uid)
+  .bind(oid)
+  .bind(access_level as i32)
+  .bind(workspace_id)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+/// Returns false when the user was not a guest of the collab.
+pub async fn delete_collab_guest<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  oid: &str,
+  uid: i64,
+) -> Result<bool, AppError> {
+  let result =
+    sqlx::query("DELETE FROM af_collab_member WHERE workspace_id = $1 AND oid = $2 AND uid = $3")
+      .bind(workspace_id)
+      .bind(oid)
+      .bind(uid)
+      .execute(executor)
+      .await?;
+  Ok(result.rows_affected() > 0)
+}
+
+/// Makes the guest a member of the workspace with the role, and removes the collabs of the
+/// workspace shared with the user since the role gives access to them. Returns false when the
+/// user was not a guest of the workspace.
+pub async fn convert_collab_guest_to_member(
+  txn: &mut Transaction<'_, Postgres>,
+  workspace_id: &Uuid,
+  uid: i64,
+  role: AFRole,
+) -> Result<bool, AppError> {
+  let result = sqlx::query("DELETE FROM af_collab_member WHERE workspace_id = $1 AND uid = $2")
+    .bind(workspace_id)
+    .bind(uid)
+    .execute(txn.as_mut())
+    .await?;
+  if result.rows_affected() == 0 {
+    return Ok(false);
+  }
+  let role_id: i32 = role.into();
+  sqlx::query(
+    r#"
+      INSERT INTO af_workspace_member (workspace_id, uid, role_id)
+      VALUES ($1, $2, $3)
+      ON CONFLICT (workspace_id, uid) DO NOTHING
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(uid)
+  .bind(role_id)
+  .execute(txn.as_mut())
+  .await?;
+  Ok(true)
+}
+
+/// The guests of the collab, including the pending invitations.
+pub async fn select_collab_guests<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  oid: &str,
+) -> Result<Vec<AFCollabGuestRow>, AppError> {
+  let rows = sqlx::query_as::<_, AFCollabGuestRow>(
+    r#"
+      SELECT u.uid, u.name, u.email, p.access_level,
+        m.created_at AT TIME ZONE 'UTC' AS created_at
+      FROM af_collab_member m
+      JOIN af_user u ON u.uid = m.uid
+      JOIN af_permissions p ON p.id = m.permission_id
+      WHERE m.workspace_id = $1 AND m.oid = $2
+      UNION ALL
+      SELECT NULL, NULL, i.invitee_email, p.access_level, i.created_at
+      FROM af_collab_guest_invitation i
+      JOIN af_permissions p ON p.id = i.permission_id
+      WHERE i.workspace_id = $1 AND i.oid = $2
+      ORDER BY created_at
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(oid)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows)
+}
+
+/// The members of the workspace and the users the collab was shared with, in the order they got
+/// access. The pending invitations are not included, see [select_collab_guests].
+pub async fn select_collab_members<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  oid: &str,
+) -> Result<Vec<AFCollabMemberRow>, AppError> {
+  let rows = sqlx::query_as::<_, AFCollabMemberRow>(
+    r#"
+      WITH members AS (
+        SELECT uid, role_id, created_at
+        FROM af_workspace_member
+        WHERE workspace_id = $1
+      ),
+      guests AS (
+        SELECT m.uid, p.access_level, m.created_at AT TIME ZONE 'UTC' AS created_at
+        FROM af_collab_member m
+        JOIN af_permissions p ON p.id = m.permission_id
+        WHERE m.workspace_id = $1 AND m.oid = $2
+      )
+      SELECT u.uid, u.uuid, u.name, u.email, u.metadata ->> 'icon_url' AS avatar_url,
+        members.role_id AS role, guests.access_level
+      FROM members
+      FULL OUTER JOIN guests ON guests.uid = members.uid
+      JOIN af_user u ON u.uid = COALESCE(members.uid, guests.uid)
+      ORDER BY COALESCE(members.created_at, guests.created_at), u.uid
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(oid)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows)
+}
+
+/// The workspaces in which the user is a guest of some collabs but not a member.
+pub async fn select_guest_workspace_ids<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  uid: i64,
+) -> Result<Vec<Uuid>, AppError> {
+  let workspace_ids = sqlx::query_scalar::<_, Uuid>(
+    r#"
+      SELECT DISTINCT m.workspace_id
+      FROM af_collab_member m
+      JOIN af_workspace w ON w.workspace_id = m.workspace_id
+      WHERE m.uid = $1
+        AND COALESCE(w.is_initialized, true) = true
+        AND NOT EXISTS (
+          SELECT 1 FROM af_workspace_member wm
+          WHERE wm.workspace_id = m.workspace_id AND wm.uid = m.uid
+        )
+    "#,
+  )
+  .bind(uid)
+  .fetch_all(executor)
+  .await?;
+  Ok(workspace_ids)
+}
+
+/// Invites an email that has no account yet, replacing the access level of a pending invitation.
+pub async fn upsert_collab_guest_invitation<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  oid: &str,
+  invitee_email: &str,
+  access_level: AFAccessLevel,
+  invited_by: i64,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      INSERT INTO af_collab_guest_invitation (workspace_id, oid, invitee_email, permission_id, invited_by)
+      SELECT $1, $2, $3, id, $5 FROM af_permissions WHERE access_level = $4
+      ON CONFLICT (workspace_id, oid, invitee_email)
+      DO UPDATE SET permission_id = EXCLUDED.permission_id, invited_by = EXCLUDED.invited_by
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(oid)
+  .bind(invitee_email)
+  .bind(access_level as i32)
+  .bind(invited_by)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+/// Returns false when the email had no pending invitation to the collab.
+pub async fn delete_collab_guest_invitation<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  oid: &str,
+  invitee_email: &str,
+) -> Result<bool, AppError> {
+  let result = sqlx::query(
+    r#"
+      DELETE FROM af_collab_guest_invitation
+      WHERE workspace_id = $1 AND oid = $2 AND LOWER(invitee_email) = LOWER($3)
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(oid)
+  .bind(invitee_email)
+  .execute(executor)
+  .await?;
+  Ok(result.rows_affected() > 0)
+}
+
+/// Turns the pending invitations of the email into guest access for the user who signed up with
+/// it. Returns the number of collabs shared with the user.
+pub async fn accept_collab_guest_invitations<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  uid: i64,
+  email: &str,
+) -> Result<u64, AppError> {
+  let result = sqlx::query(
+    r#"
+      WITH accepted AS (
+        DELETE FROM af_collab_guest_invitation
+        WHERE LOWER(invitee_email) = LOWER($2)
+        RETURNING workspace_id, oid, permission_id
+      )
+      INSERT INTO af_collab_member (uid, oid, permission_id, workspace_id)
+      SELECT $1, oid, permission_id, workspace_id FROM accepted
+      ON CONFLICT (uid, oid)
+      DO UPDATE SET permission_id = EXCLUDED.permission_id, workspace_id = EXCLUDED.workspace_id
+    "#,
+  )
+  .bind(uid)
+  .bind(email)
+  .execute(executor)
+  .await?;
+  Ok(result.rows_affected())
+}
@@ -0,0 +1,118 @@
+use app_error::AppError;
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, FromRow, Postgres};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, FromRow)]
+pub struct AFDatabaseFormTokenRow {
+  pub token_id: Uuid,
+  pub workspace_id: Uuid,
+  pub database_id: String,
+  pub view_id: Option<String>,
+  pub name: String,
+  pub created_by: i64,
+  pub created_at: DateTime<Utc>,
+  pub last_used_at: Option<DateTime<Utc>>,
+}
+
+pub async fn insert_database_form_token<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  database_id: &str,
+  view_id: Option<&str>,
+  name: &str,
+  token_hash: &str,
+  created_by: i64,
+) -> Result<AFDatabaseFormTokenRow, AppError> {
+  let row = sqlx::query_as::<_, AFDatabaseFormTokenRow>(
+    r#"
+      INSERT INTO af_database_form_token
+        (token_hash, workspace_id, database_id, view_id, name, created_by)
+      VALUES ($1, $2, $3, $4, $5, $6)
+      RETURNING token_id, workspace_id, database_id, view_id, name, created_by, created_at,
+        last_used_at
+    "#,
+  )
+  .bind(token_hash)
+  .bind(workspace_id)
+  .bind(database_id)
+  .bind(view_id)
+  .bind(name)
+  .bind(created_by)
+  .fetch_one(executor)
+  .await?;
+  Ok(row)
+}
+
+pub async fn select_database_form_tokens<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  database_id: &str,
+) -> Result<Vec<AFDatabaseFormTokenRow>, AppError> {
+  let rows = sqlx::query_as::<_, AFDatabaseFormTokenRow>(
+    r#"
+      SELECT token_id, workspace_id, database_id, view_id, name, created_by, created_at,
+        last_used_at
+      FROM af_database_form_token
+      WHERE workspace_id = $1 AND database_id = $2
+      ORDER BY created_at
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(database_id)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows)
+}
+
+pub async fn select_database_form_token_by_hash<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  token_hash: &str,
+) -> Result<Option<AFDatabaseFormTokenRow>, AppError> {
+  let row = sqlx::query_as::<_, AFDatabaseFormTokenRow>(
+    r#"
+      SELECT token_id, workspace_id, database_id, view_id, name, created_by, created_at,
+        last_used_at
+      FROM af_database_form_token
+      WHERE token_hash = $1
+    "#,
+  )
+  .bind(token_hash)
+  .fetch_optional(executor)
+  .await?;
+  Ok(row)
+}
+
+/// Returns false when the database has no such token.
+pub async fn delete_database_form_token<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  database_id: &str,
+  token_id: &Uuid,
+) -> Result<bool, AppError> {
+  let result = sqlx::query(
+    r#"
+      DELETE FROM af_database_form_token
+      WHERE workspace_id = $1 AND database_id = $2 AND token_id = $3
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(database_id)
+  .bind(token_id)
+  .execute(executor)
+  .await?;
+  Ok(result.rows_affected() > 0)
+}
+
+pub async fn update_database_form_token_last_used<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  token_id: &Uuid,
+) -> Result<(), AppError> {
+  sqlx::query(
+    "UPDATE af_database_form_token SET last_used_at = CURRENT_TIMESTAMP WHERE token_id = $1",
+  )
+  .bind(token_id)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
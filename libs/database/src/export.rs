@@ -0,0 +1,94 @@
+use app_error::AppError;
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::pg_row::AFExportTaskRow;
+
+/// Format of an exported document.
+pub const EXPORT_FORMAT_PDF: &str = "pdf";
+/// Format of an exported workspace, a zip archive that can be imported into another workspace.
+pub const EXPORT_FORMAT_APPFLOWY: &str = "appflowy";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportTaskState {
+  Pending = 0,
+  Completed = 1,
+  Failed = 2,
+}
+
+impl From<i16> for ExportTaskState {
+  fn from(val: i16) -> Self {
+    match val {
+      1 => ExportTaskState::Completed,
+      2 => ExportTaskState::Failed,
+      _ => ExportTaskState::Pending,
+    }
+  }
+}
+
+pub async fn insert_export_task<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  task_id: &Uuid,
+  workspace_id: &Uuid,
+  oid: &str,
+  uid: i64,
+  format: &str,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      INSERT INTO af_export_task (task_id, workspace_id, oid, uid, format, status)
+      VALUES ($1, $2, $3, $4, $5, $6)
+    "#,
+  )
+  .bind(task_id)
+  .bind(workspace_id)
+  .bind(oid)
+  .bind(uid)
+  .bind(format)
+  .bind(ExportTaskState::Pending as i16)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+pub async fn select_export_task<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  task_id: &Uuid,
+) -> Result<AFExportTaskRow, AppError> {
+  let row = sqlx::query_as::<_, AFExportTaskRow>(
+    r#"
+      SELECT task_id, workspace_id, oid, uid, format, status, file_key, error, created_at
+      FROM af_export_task
+      WHERE task_id = $1
+    "#,
+  )
+  .bind(task_id)
+  .fetch_optional(executor)
+  .await?
+  .ok_or_else(|| AppError::RecordNotFound(format!("export task {} not found", task_id)))?;
+  Ok(row)
+}
+
+/// Marks the export task as completed or failed. `file_key` is the S3 key of the exported file.
+pub async fn update_export_task_status<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  task_id: &Uuid,
+  state: ExportTaskState,
+  file_key: Option<&str>,
+  error: Option<&str>,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      UPDATE af_export_task
+      SET status = $2, file_key = $3, error = $4, updated_at = CURRENT_TIMESTAMP
+      WHERE task_id = $1
+    "#,
+  )
+  .bind(task_id)
+  .bind(state as i16)
+  .bind(file_key)
+  .bind(error)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use app_error::AppError;
+use sha2::{Digest, Sha256};
+use shared_entity::dto::feature_flag_dto::{FeatureFlag, UpsertFeatureFlagParams};
+use sqlx::{Executor, PgPool, Postgres};
+use tokio::sync::RwLock;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::pg_row::AFFeatureFlagRow;
+
+pub async fn select_feature_flags<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+) -> Result<Vec<AFFeatureFlagRow>, AppError> {
+  let rows = sqlx::query_as::<_, AFFeatureFlagRow>(
+    r#"
+      SELECT name, enabled, rollout_percentage, description, updated_at
+      FROM af_feature_flag
+      ORDER BY name
+    "#,
+  )
+  .fetch_all(executor)
+  .await?;
+  Ok(rows)
+}
+
+pub async fn upsert_feature_flag<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  name: &str,
+  enabled: bool,
+  rollout_percentage: i16,
+  description: &str,
+) -> Result<AFFeatureFlagRow, AppError> {
+  let row = sqlx::query_as::<_, AFFeatureFlagRow>(
+    r#"
+      INSERT INTO af_feature_flag (name, enabled, rollout_percentage, description)
+      VALUES ($1, $2, $3, $4)
+      ON CONFLICT (name) DO UPDATE
+      SET enabled = EXCLUDED.enabled,
+          rollout_percentage = EXCLUDED.rollout_percentage,
+          description = EXCLUDED.description,
+          updated_at = CURRENT_TIMESTAMP
+      RETURNING name, enabled, rollout_percentage, description, updated_at
+    "#,
+  )
+  .bind(name)
+  .bind(enabled)
+  .bind(rollout_percentage)
+  .bind(description)
+  .fetch_one(executor)
+  .await?;
+  Ok(row)
+}
+
+pub async fn delete_feature_flag<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  name: &str,
+) -> Result<bool, AppError> {
+  let result = sqlx::query("DELETE FROM af_feature_flag WHERE name = $1")
+    .bind(name)
+    .execute(executor)
+    .await?;
+  Ok(result.rows_affected() > 0)
+}
+
+impl From<AFFeatureFlagRow> for FeatureFlag {
+  fn from(row: AFFeatureFlagRow) -> Self {
+    FeatureFlag {
+      name: row.name,
+      enabled: row.enabled,
+      rollout_percentage: row.rollout_percentage,
+      description: row.description,
+      updated_at: row.updated_at,
+    }
+  }
+}
+
+/// How long the flags are cached before being read again from Postgres. A flag changed through
+/// another instance is picked up after at most this duration.
+const FEATURE_FLAG_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Evaluates the feature flags stored in Postgres, shared by the server, the collaboration server
+/// and the worker.
+///
+/// A flag can be overridden per process with the `APPFLOWY_FEATURE_FLAG_<NAME>` environment
+/// variable, `<NAME>` being the upper case name of the flag. The value is either `true`, `false`
+/// or a rollout percentage.
+#[derive(Clone)]
+pub struct FeatureFlags {
+  pg_pool: PgPool,
+  cache: Arc<RwLock<Option<(Instant, HashMap<String, AFFeatureFlagRow>)>>>,
+}
+
+impl FeatureFlags {
+  pub fn new(pg_pool: PgPool) -> Self {
+    Self {
+      pg_pool,
+      cache: Default::default(),
+    }
+  }
+
+  /// Returns true if the flag is on for the given workspace. Unknown flags are off.
+  pub async fn is_enabled(&self, name: &str, workspace_id: &Uuid) -> bool {
+    let percentage = match env_override(name) {
+      Some(percentage) => percentage,
+      None => match self.get(name).await {
+        Some(flag) if flag.enabled => flag.rollout_percentage,
+        _ => 0,
+      },
+    };
+    rollout_bucket(name, workspace_id) < percentage
+  }
+
+  pub async fn list(&self) -> Result<Vec<FeatureFlag>, AppError> {
+    let rows = select_feature_flags(&self.pg_pool).await?;
+    Ok(rows.into_iter().map(FeatureFlag::from).collect())
+  }
+
+  pub async fn upsert(
+    &self,
+    name: &str,
+    params: UpsertFeatureFlagParams,
+  ) -> Result<FeatureFlag, AppError> {
+    if !is_valid_flag_name(name) {
+      return Err(AppError::InvalidRequest(format!(
+        "invalid feature flag name: {}, only lower case letters, digits and underscores are allowed",
+        name
+      )));
+    }
+    if !(0..=100).contains(&params.rollout_percentage) {
+      return Err(AppError::InvalidRequest(
+        "rollout percentage must be between 0 and 100".to_string(),
+      ));
+    }
+    let row = upsert_feature_flag(
+      &self.pg_pool,
+      name,
+      params.enabled,
+      params.rollout_percentage,
+      &params.description,
+    )
+    .await?;
+    self.invalidate().await;
+    Ok(row.into())
+  }
+
+  pub async fn delete(&self, name: &str) -> Result<(), AppError> {
+    if !delete_feature_flag(&self.pg_pool, name).await? {
+      return Err(AppError::RecordNotFound(format!(
+        "feature flag {} not found",
+        name
+      )));
+    }
+    self.invalidate().await;
+    Ok(())
+  }
+
+  async fn get(&self, name: &str) -> Option<AFFeatureFlagRow> {
+    if let Some((loaded_at, flags)) = self.cache.read().await.as_ref() {
+      if loaded_at.elapsed() < FEATURE_FLAG_CACHE_TTL {
+        return flags.get(name).cloned();
+      }
+    }
+
+    let mut cache = self.cache.write().await;
+    match select_feature_flags(&self.pg_pool).await {
+      Ok(rows) => {
+        let flags = rows
+          .into_iter()
+          .map(|row| (row.name.clone(), row))
+          .collect::<HashMap<_, _>>();
+        let flag = flags.get(name).cloned();
+        *cache = Some((Instant::now(), flags));
+        flag
+      },
+      Err(err) => {
+        // keep using the stale flags rather than turning everything off
+        warn!("Failed to load the feature flags: {}", err);
+        cache
+          .as_ref()
+          .and_then(|(_, flags)| flags.get(name).cloned())
+      },
+    }
+  }
+
+  async fn invalidate(&self) {
+    *self.cache.write().await = None;
+  }
+}
+
+fn is_valid_flag_name(name: &str) -> bool {
+  !name.is_empty()
+    && name.len() <= 64
+    && name
+      .chars()
+      .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+fn env_override(name: &str) -> Option<i16> {
+  let key = format!("APPFLOWY_FEATURE_FLAG_{}", name.to_uppercase());
+  let value = std::env::var(key).ok()?;
+  match value.trim() {
+    "true" => Some(100),
+    "false" => Some(0),
+    value => value.parse::<i16>().ok().map(|p| p.clamp(0, 100)),
+  }
+}
+
+/// Returns the bucket, between 0 and 99, of the workspace for the given flag. Salting the hash
+/// with the flag name spreads the rollouts of the different flags over different workspaces.
+fn rollout_bucket(name: &str, workspace_id: &Uuid) -> i16 {
+  let mut hasher = Sha256::new();
+  hasher.update(name.as_bytes());
+  hasher.update(workspace_id.as_bytes());
+  let hash = hasher.finalize();
+  let value = u64::from_be_bytes(hash[..8].try_into().unwrap());
+  (value % 100) as i16
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn rollout_bucket_is_stable_and_spread() {
+    let workspace_id = Uuid::new_v4();
+    assert_eq!(
+      rollout_bucket("new_sync", &workspace_id),
+      rollout_bucket("new_sync", &workspace_id)
+    );
+
+    let enabled = (0..10_000)
+      .filter(|_| rollout_bucket("new_sync", &Uuid::new_v4()) < 20)
+      .count();
+    assert!((1_500..2_500).contains(&enabled), "{}", enabled);
+  }
+
+  #[test]
+  fn flag_name_validation() {
+    assert!(is_valid_flag_name("notion_importer_v2"));
+    assert!(!is_valid_flag_name(""));
+    assert!(!is_valid_flag_name("New-Sync"));
+  }
+}
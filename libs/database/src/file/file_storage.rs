@@ -1,6 +1,8 @@
-use crate::pg_row::AFBlobMetadataRow;
+use crate::file::{object_key_from_blob_metadata_key, verify_workspace_object_key};
+use crate::pg_row::{AFBlobMetadataRow, AFBlobStatus};
 use crate::resource_usage::{
-  delete_blob_metadata, get_blob_metadata, insert_blob_metadata, is_blob_metadata_exists,
+  delete_blob_metadata, delete_blob_metadata_bulk, get_blob_metadata, insert_blob_metadata,
+  is_blob_metadata_exists,
 };
 use app_error::AppError;
 use async_trait::async_trait;
@@ -62,6 +64,22 @@ pub trait BucketClient {
   async fn remove_dir(&self, dir: &str) -> Result<(), AppError>;
 
   async fn list_dir(&self, dir: &str, limit: usize) -> Result<Vec<String>, AppError>;
+
+  /// Generates a url that allows uploading the object without credentials until it expires.
+  async fn gen_presigned_put_url(
+    &self,
+    object_key: &str,
+    content_type: &str,
+    content_length: u64,
+    expires_in_secs: u64,
+  ) -> Result<String, AppError>;
+
+  /// Generates a url that allows downloading the object without credentials until it expires.
+  async fn gen_presigned_get_url(
+    &self,
+    object_key: &str,
+    expires_in_secs: u64,
+  ) -> Result<String, AppError>;
 }
 
 pub trait BlobKey: Send + Sync {
@@ -69,6 +87,14 @@ pub trait BlobKey: Send + Sync {
   fn object_key(&self) -> String;
   fn blob_metadata_key(&self) -> String;
   fn e_tag(&self) -> &str;
+
+  /// Returns the [BlobKey::object_key] after verifying that it stays within the prefix of
+  /// [BlobKey::workspace_id].
+  fn scoped_object_key(&self) -> Result<String, AppError> {
+    let object_key = self.object_key();
+    verify_workspace_object_key(self.workspace_id(), &object_key)?;
+    Ok(object_key)
+  }
 }
 
 pub struct BucketStorage<C> {
@@ -98,7 +124,10 @@ where
     file_stream: ByteStream,
     file_type: String,
     file_size: usize,
+    uid: i64,
+    status: AFBlobStatus,
   ) -> Result<(), AppError> {
+    let object_key = key.scoped_object_key()?;
     if is_blob_metadata_exists(&self.pg_pool, key.workspace_id(), &key.blob_metadata_key()).await? {
       warn!(
         "file already exists, workspace_id: {}, blob_metadata_key: {}",
@@ -110,7 +139,7 @@ where
 
     self
       .client
-      .put_blob(&key.object_key(), file_stream, Some(&file_type))
+      .put_blob(&object_key, file_stream, Some(&file_type))
       .await?;
     insert_blob_metadata(
       &self.pg_pool,
@@ -118,13 +147,15 @@ where
       key.workspace_id(),
       &file_type,
       file_size,
+      Some(uid),
+      status,
     )
     .await?;
     Ok(())
   }
 
   pub async fn delete_blob(&self, key: impl BlobKey) -> Result<(), AppError> {
-    self.client.delete_blob(&key.object_key()).await?;
+    self.client.delete_blob(&key.scoped_object_key()?).await?;
 
     let mut tx = self.pg_pool.begin().await?;
     delete_blob_metadata(&mut tx, key.workspace_id(), &key.blob_metadata_key()).await?;
@@ -132,6 +163,35 @@ where
     Ok(())
   }
 
+  /// Deletes the blobs of the workspace with the given metadata keys. Returns the metadata keys
+  /// and sizes of the blobs that existed.
+  pub async fn delete_blobs(
+    &self,
+    workspace_id: &Uuid,
+    blob_metadata_keys: &[String],
+  ) -> Result<Vec<(String, i64)>, AppError> {
+    let mut tx = self.pg_pool.begin().await?;
+    let deleted = delete_blob_metadata_bulk(&mut tx, workspace_id, blob_metadata_keys).await?;
+    let object_keys = deleted
+      .iter()
+      .map(|(key, _)| object_key_from_blob_metadata_key(workspace_id, key))
+      .filter(
+        |object_key| match verify_workspace_object_key(workspace_id, object_key) {
+          Ok(_) => true,
+          Err(_) => {
+            warn!("skip deleting blob with invalid object key: {}", object_key);
+            false
+          },
+        },
+      )
+      .collect::<Vec<_>>();
+    if !object_keys.is_empty() {
+      self.client.delete_blobs(object_keys).await?;
+    }
+    tx.commit().await?;
+    Ok(deleted)
+  }
+
   pub async fn get_blob_metadata(
     &self,
     workspace_id: &Uuid,
@@ -142,7 +202,11 @@ where
   }
 
   pub async fn get_blob(&self, key: &impl BlobKey) -> Result<Vec<u8>, AppError> {
-    let blob = self.client.get_blob(&key.object_key()).await?.to_blob();
+    let blob = self
+      .client
+      .get_blob(&key.scoped_object_key()?)
+      .await?
+      .to_blob();
     Ok(blob)
   }
 
@@ -151,7 +215,10 @@ where
     key: impl BlobKey,
     req: CreateUploadRequest,
   ) -> Result<CreateUploadResponse, AppError> {
-    self.client.create_upload(&key.object_key(), req).await
+    self
+      .client
+      .create_upload(&key.scoped_object_key()?, req)
+      .await
   }
 
   pub async fn upload_part(
@@ -159,33 +226,41 @@ where
     key: impl BlobKey,
     req: UploadPartData,
   ) -> Result<UploadPartResponse, AppError> {
-    self.client.upload_part(&key.object_key(), req).await
+    self
+      .client
+      .upload_part(&key.scoped_object_key()?, req)
+      .await
   }
 
+  /// Returns the size of the uploaded file, or None if the file had already been uploaded.
   pub async fn complete_upload(
     &self,
     key: impl BlobKey,
     req: CompleteUploadRequest,
-  ) -> Result<(), AppError> {
-    if is_blob_metadata_exists(&self.pg_pool, key.workspace_id(), &key.object_key()).await? {
+    uid: i64,
+    status: AFBlobStatus,
+  ) -> Result<Option<usize>, AppError> {
+    let object_key = key.scoped_object_key()?;
+    if is_blob_metadata_exists(&self.pg_pool, key.workspace_id(), &object_key).await? {
       warn!(
         "file already exists, workspace_id: {}, request: {}",
         key.workspace_id(),
         req
       );
-      return Ok(());
+      return Ok(None);
     }
 
-    let (content_length, content_type) =
-      self.client.complete_upload(&key.object_key(), req).await?;
+    let (content_length, content_type) = self.client.complete_upload(&object_key, req).await?;
     insert_blob_metadata(
       &self.pg_pool,
       &key.blob_metadata_key(),
       key.workspace_id(),
       &content_type,
       content_length,
+      Some(uid),
+      status,
     )
     .await?;
-    Ok(())
+    Ok(Some(content_length))
   }
 }
@@ -0,0 +1,633 @@
+use crate::file::s3_client_impl::S3ResponseData;
+use crate::file::BucketClient;
+use anyhow::anyhow;
+use app_error::AppError;
+use async_trait::async_trait;
+use aws_sdk_s3::primitives::ByteStream;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use database_entity::file_dto::{
+  CompleteUploadRequest, CreateUploadRequest, CreateUploadResponse, UploadPartData,
+  UploadPartResponse,
+};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use tokio::fs;
+use tokio::io::{AsyncRead, AsyncWriteExt};
+use tracing::{trace, warn};
+use uuid::Uuid;
+
+/// Path of the api serving the presigned urls of the [LocalFsBucketClientImpl].
+pub const LOCAL_FS_PRESIGNED_PATH: &str = "/api/file_storage/local";
+
+const BLOBS_DIR: &str = "blobs";
+const META_DIR: &str = "meta";
+const UPLOADS_DIR: &str = "uploads";
+const TMP_DIR: &str = "tmp";
+const UPLOAD_INFO_FILE: &str = "upload.json";
+
+/// Blob storage backed by a directory on the local disk, for self-hosted setups that don't want
+/// to run an object store. The appflowy_cloud, appflowy_collaborate and appflowy_worker services
+/// must share the same root directory.
+///
+/// The root directory is laid out as:
+/// - `blobs/{object_key}`: the content of the blobs
+/// - `meta/{object_key}`: the content type of the blobs
+/// - `uploads/{upload_id}/`: the parts of the pending multipart uploads
+/// - `tmp/`: files being written, moved into `blobs` once complete
+///
+/// Presigned urls point to [LOCAL_FS_PRESIGNED_PATH] with a token signed by the configured key,
+/// the server verifies the token with [LocalFsBucketClientImpl::verify_presigned_token] before
+/// reading or writing the blob.
+#[derive(Clone)]
+pub struct LocalFsBucketClientImpl {
+  root: Arc<PathBuf>,
+  signing_key: Arc<Vec<u8>>,
+  /// Base url of the appflowy_cloud server, e.g. `https://appflowy.example.com`.
+  public_url: String,
+}
+
+/// The operation granted by a presigned url.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PresignedMethod {
+  Get,
+  Put,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalFsPresignedToken {
+  pub object_key: String,
+  pub method: PresignedMethod,
+  /// Unix timestamp in seconds.
+  pub expires_at: i64,
+  pub content_type: Option<String>,
+  pub content_length: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct UploadInfo {
+  object_key: String,
+  content_type: String,
+}
+
+impl LocalFsBucketClientImpl {
+  pub fn new(root: PathBuf, signing_key: &str, public_url: String) -> Result<Self, AppError> {
+    if signing_key.is_empty() {
+      return Err(AppError::Internal(anyhow!(
+        "The signing key of the local file storage is empty"
+      )));
+    }
+    std::fs::create_dir_all(&root).map_err(|err| {
+      AppError::Internal(anyhow!(
+        "Failed to create local file storage directory {}: {}",
+        root.display(),
+        err
+      ))
+    })?;
+    Ok(Self {
+      root: Arc::new(root),
+      signing_key: Arc::new(signing_key.as_bytes().to_vec()),
+      public_url: public_url.trim_end_matches('/').to_string(),
+    })
+  }
+
+  pub fn root(&self) -> &Path {
+    &self.root
+  }
+
+  /// Returns the file holding the blob, its length and its content type.
+  pub async fn open_blob(
+    &self,
+    object_key: &str,
+  ) -> Result<(fs::File, u64, Option<String>), AppError> {
+    let path = self.blob_path(object_key)?;
+    let file = fs::File::open(&path)
+      .await
+      .map_err(|err| not_found_or_io(err, object_key))?;
+    let len = file.metadata().await?.len();
+    let content_type = self.read_content_type(object_key).await?;
+    Ok((file, len, content_type))
+  }
+
+  /// Writes the blob from a reader, used by the presigned url api to stream the upload to disk.
+  pub async fn put_blob_from_reader<R>(
+    &self,
+    object_key: &str,
+    mut reader: R,
+    content_type: &str,
+  ) -> Result<u64, AppError>
+  where
+    R: AsyncRead + Unpin + Send,
+  {
+    let (tmp_path, mut file) = self.tmp_file().await?;
+    let result = async {
+      let len = tokio::io::copy(&mut reader, &mut file).await?;
+      file.flush().await?;
+      Ok::<_, AppError>(len)
+    }
+    .await;
+    let len = match result {
+      Ok(len) => len,
+      Err(err) => {
+        let _ = fs::remove_file(&tmp_path).await;
+        return Err(err);
+      },
+    };
+    self
+      .commit_blob(&tmp_path, object_key, content_type)
+      .await?;
+    trace!(
+      "put object to local file storage: {} ({} bytes)",
+      object_key,
+      len
+    );
+    Ok(len)
+  }
+
+  /// Verifies the signature and expiration of a token generated by one of the presign methods.
+  pub fn verify_presigned_token(
+    &self,
+    token: &str,
+    method: PresignedMethod,
+  ) -> Result<LocalFsPresignedToken, AppError> {
+    let (payload, signature) = token
+      .split_once('.')
+      .ok_or_else(|| AppError::InvalidRequest("Malformed presigned token".to_string()))?;
+    let signature = URL_SAFE_NO_PAD
+      .decode(signature)
+      .map_err(|_| AppError::InvalidRequest("Malformed presigned token".to_string()))?;
+    let mut mac = self.mac();
+    mac.update(payload.as_bytes());
+    mac
+      .verify_slice(&signature)
+      .map_err(|_| AppError::NotEnoughPermissions)?;
+
+    let payload = URL_SAFE_NO_PAD
+      .decode(payload)
+      .map_err(|_| AppError::InvalidRequest("Malformed presigned token".to_string()))?;
+    let token: LocalFsPresignedToken = serde_json::from_slice(&payload)?;
+    if token.method != method {
+      return Err(AppError::NotEnoughPermissions);
+    }
+    if token.expires_at < chrono::Utc::now().timestamp() {
+      return Err(AppError::UserUnAuthorized(
+        "Presigned url has expired".to_string(),
+      ));
+    }
+    Ok(token)
+  }
+
+  fn sign_token(&self, token: &LocalFsPresignedToken) -> Result<String, AppError> {
+    let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(token)?);
+    let mut mac = self.mac();
+    mac.update(payload.as_bytes());
+    let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+    Ok(format!("{}.{}", payload, signature))
+  }
+
+  fn presigned_url(&self, token: &LocalFsPresignedToken) -> Result<String, AppError> {
+    Ok(format!(
+      "{}{}/{}",
+      self.public_url,
+      LOCAL_FS_PRESIGNED_PATH,
+      self.sign_token(token)?
+    ))
+  }
+
+  fn mac(&self) -> Hmac<Sha256> {
+    Hmac::<Sha256>::new_from_slice(&self.signing_key).expect("HMAC can take key of any size")
+  }
+
+  fn blob_path(&self, object_key: &str) -> Result<PathBuf, AppError> {
+    Ok(
+      self
+        .root
+        .join(BLOBS_DIR)
+        .join(relative_key_path(object_key)?),
+    )
+  }
+
+  fn meta_path(&self, object_key: &str) -> Result<PathBuf, AppError> {
+    Ok(
+      self
+        .root
+        .join(META_DIR)
+        .join(relative_key_path(object_key)?),
+    )
+  }
+
+  fn upload_dir(&self, upload_id: &str) -> Result<PathBuf, AppError> {
+    let upload_id = Uuid::parse_str(upload_id)
+      .map_err(|_| AppError::InvalidRequest(format!("Invalid upload id: {}", upload_id)))?;
+    Ok(self.root.join(UPLOADS_DIR).join(upload_id.to_string()))
+  }
+
+  async fn tmp_file(&self) -> Result<(PathBuf, fs::File), AppError> {
+    let dir = self.root.join(TMP_DIR);
+    fs::create_dir_all(&dir).await?;
+    let path = dir.join(Uuid::new_v4().to_string());
+    let file = fs::File::create(&path).await?;
+    Ok((path, file))
+  }
+
+  /// Moves a fully written temporary file to the blob location, so readers never see a partially
+  /// written blob.
+  async fn commit_blob(
+    &self,
+    tmp_path: &Path,
+    object_key: &str,
+    content_type: &str,
+  ) -> Result<(), AppError> {
+    let blob_path = self.blob_path(object_key)?;
+    let meta_path = self.meta_path(object_key)?;
+    for path in [&blob_path, &meta_path] {
+      if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+      }
+    }
+    fs::write(&meta_path, content_type).await?;
+    if let Err(err) = fs::rename(tmp_path, &blob_path).await {
+      let _ = fs::remove_file(tmp_path).await;
+      return Err(err.into());
+    }
+    Ok(())
+  }
+
+  async fn read_content_type(&self, object_key: &str) -> Result<Option<String>, AppError> {
+    match fs::read_to_string(self.meta_path(object_key)?).await {
+      Ok(content_type) => Ok(Some(content_type)),
+      Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+      Err(err) => Err(err.into()),
+    }
+  }
+
+  /// Returns the keys starting with the given prefix, like the S3 ListObjects api.
+  async fn list_keys(&self, prefix: &str, limit: usize) -> Result<Vec<String>, AppError> {
+    let blobs_root = self.root.join(BLOBS_DIR);
+    // Only walk the deepest directory that contains every key matching the prefix.
+    let start_dir = match prefix.rfind('/') {
+      Some(pos) => blobs_root.join(relative_key_path(&prefix[..pos])?),
+      None => blobs_root.clone(),
+    };
+
+    let mut keys = vec![];
+    let mut pending = vec![start_dir];
+    while let Some(dir) = pending.pop() {
+      let mut entries = match fs::read_dir(&dir).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == ErrorKind::NotFound => continue,
+        Err(err) => return Err(err.into()),
+      };
+      while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if entry.file_type().await?.is_dir() {
+          pending.push(path);
+          continue;
+        }
+        let key = path
+          .strip_prefix(&blobs_root)
+          .map_err(|err| AppError::Internal(err.into()))?
+          .components()
+          .map(|c| c.as_os_str().to_string_lossy())
+          .collect::<Vec<_>>()
+          .join("/");
+        if key.starts_with(prefix) {
+          keys.push(key);
+        }
+      }
+    }
+    keys.sort();
+    keys.truncate(limit);
+    Ok(keys)
+  }
+
+  async fn remove_key(&self, object_key: &str) -> Result<(), AppError> {
+    for path in [self.blob_path(object_key)?, self.meta_path(object_key)?] {
+      match fs::remove_file(&path).await {
+        Ok(_) => {},
+        Err(err) if err.kind() == ErrorKind::NotFound => {},
+        Err(err) => return Err(err.into()),
+      }
+    }
+    Ok(())
+  }
+}
+
+#[async_trait]
+impl BucketClient for LocalFsBucketClientImpl {
+  type ResponseData = S3ResponseData;
+
+  async fn put_blob(
+    &self,
+    object_key: &str,
+    content: ByteStream,
+    content_type: Option<&str>,
+  ) -> Result<(), AppError> {
+    self
+      .put_blob_from_reader(
+        object_key,
+        content.into_async_read(),
+        content_type.unwrap_or("application/octet-stream"),
+      )
+      .await?;
+    Ok(())
+  }
+
+  async fn put_blob_with_content_type(
+    &self,
+    object_key: &str,
+    stream: ByteStream,
+    content_type: &str,
+  ) -> Result<(), AppError> {
+    self.put_blob(object_key, stream, Some(content_type)).await
+  }
+
+  async fn delete_blob(&self, object_key: &str) -> Result<Self::ResponseData, AppError> {
+    self.remove_key(object_key).await?;
+    Ok(S3ResponseData::new_with_data(vec![], None))
+  }
+
+  async fn delete_blobs(&self, object_keys: Vec<String>) -> Result<(), AppError> {
+    for key in object_keys {
+      if let Err(err) = self.remove_key(&key).await {
+        warn!("failed to delete {} from local file storage: {}", key, err);
+      }
+    }
+    Ok(())
+  }
+
+  async fn get_blob(&self, object_key: &str) -> Result<Self::ResponseData, AppError> {
+    let data = fs::read(self.blob_path(object_key)?)
+      .await
+      .map_err(|err| not_found_or_io(err, object_key))?;
+    let content_type = self.read_content_type(object_key).await?;
+    trace!(
+      "get object from local file storage: {} ({} bytes)",
+      object_key,
+      data.len()
+    );
+    Ok(S3ResponseData::new_with_data(data, content_type))
+  }
+
+  async fn create_upload(
+    &self,
+    object_key: &str,
+    req: CreateUploadRequest,
+  ) -> Result<CreateUploadResponse, AppError> {
+    // Validate the key before accepting any part.
+    relative_key_path(object_key)?;
+    let upload_id = Uuid::new_v4().to_string();
+    let dir = self.upload_dir(&upload_id)?;
+    fs::create_dir_all(&dir).await?;
+    let info = UploadInfo {
+      object_key: object_key.to_string(),
+      content_type: req.content_type,
+    };
+    fs::write(dir.join(UPLOAD_INFO_FILE), serde_json::to_vec(&info)?).await?;
+    Ok(CreateUploadResponse {
+      file_id: req.file_id,
+      upload_id,
+    })
+  }
+
+  async fn upload_part(
+    &self,
+    object_key: &str,
+    req: UploadPartData,
+  ) -> Result<UploadPartResponse, AppError> {
+    if req.body.is_empty() {
+      return Err(AppError::InvalidRequest("body is empty".to_string()));
+    }
+    let dir = self.upload_dir(&req.upload_id)?;
+    let info = read_upload_info(&dir).await?;
+    if info.object_key != object_key {
+      return Err(AppError::InvalidRequest(format!(
+        "upload {} does not belong to {}",
+        req.upload_id, object_key
+      )));
+    }
+    let e_tag = hex_digest(&req.body);
+    fs::write(dir.join(part_file_name(req.part_number)), &req.body).await?;
+    Ok(UploadPartResponse {
+      part_num: req.part_number,
+      e_tag,
+    })
+  }
+
+  async fn complete_upload(
+    &self,
+    object_key: &str,
+    req: CompleteUploadRequest,
+  ) -> Result<(usize, String), AppError> {
+    let dir = self.upload_dir(&req.upload_id)?;
+    let info = read_upload_info(&dir).await?;
+    if info.object_key != object_key {
+      return Err(AppError::InvalidRequest(format!(
+        "upload {} does not belong to {}",
+        req.upload_id, object_key
+      )));
+    }
+
+    let mut parts = req.parts;
+    parts.sort_by_key(|part| part.part_number);
+    let (tmp_path, mut file) = self.tmp_file().await?;
+    let result = async {
+      let mut len = 0;
+      for part in parts {
+        let data = fs::read(dir.join(part_file_name(part.part_number)))
+          .await
+          .map_err(|_| {
+            AppError::InvalidRequest(format!("part {} was not uploaded", part.part_number))
+          })?;
+        if hex_digest(&data) != part.e_tag {
+          return Err(AppError::InvalidRequest(format!(
+            "e_tag of part {} does not match",
+            part.part_number
+          )));
+        }
+        file.write_all(&data).await?;
+        len += data.len();
+      }
+      file.flush().await?;
+      Ok(len)
+    }
+    .await;
+    let len = match result {
+      Ok(len) => len,
+      Err(err) => {
+        let _ = fs::remove_file(&tmp_path).await;
+        return Err(err);
+      },
+    };
+
+    self
+      .commit_blob(&tmp_path, object_key, &info.content_type)
+      .await?;
+    if let Err(err) = fs::remove_dir_all(&dir).await {
+      warn!(
+        "failed to remove upload directory {}: {}",
+        dir.display(),
+        err
+      );
+    }
+    Ok((len, info.content_type))
+  }
+
+  async fn remove_dir(&self, dir: &str) -> Result<(), AppError> {
+    let keys = self.list_keys(dir, usize::MAX).await?;
+    for key in keys {
+      self.remove_key(&key).await?;
+    }
+    Ok(())
+  }
+
+  async fn list_dir(&self, dir: &str, limit: usize) -> Result<Vec<String>, AppError> {
+    self.list_keys(dir, limit).await
+  }
+
+  async fn gen_presigned_put_url(
+    &self,
+    object_key: &str,
+    content_type: &str,
+    content_length: u64,
+    expires_in_secs: u64,
+  ) -> Result<String, AppError> {
+    relative_key_path(object_key)?;
+    self.presigned_url(&LocalFsPresignedToken {
+      object_key: object_key.to_string(),
+      method: PresignedMethod::Put,
+      expires_at: chrono::Utc::now().timestamp() + expires_in_secs as i64,
+      content_type: Some(content_type.to_string()),
+      content_length: Some(content_length),
+    })
+  }
+
+  async fn gen_presigned_get_url(
+    &self,
+    object_key: &str,
+    expires_in_secs: u64,
+  ) -> Result<String, AppError> {
+    relative_key_path(object_key)?;
+    self.presigned_url(&LocalFsPresignedToken {
+      object_key: object_key.to_string(),
+      method: PresignedMethod::Get,
+      expires_at: chrono::Utc::now().timestamp() + expires_in_secs as i64,
+      content_type: None,
+      content_length: None,
+    })
+  }
+}
+
+/// Converts an object key to a path relative to the storage root, rejecting keys that would
+/// escape it.
+fn relative_key_path(object_key: &str) -> Result<PathBuf, AppError> {
+  let path = Path::new(object_key);
+  let is_valid = !object_key.is_empty()
+    && !object_key.contains('\\')
+    && path
+      .components()
+      .all(|component| matches!(component, Component::Normal(_)));
+  if !is_valid {
+    return Err(AppError::InvalidRequest(format!(
+      "Invalid object key: {}",
+      object_key
+    )));
+  }
+  Ok(path.to_path_buf())
+}
+
+async fn read_upload_info(dir: &Path) -> Result<UploadInfo, AppError> {
+  let data = fs::read(dir.join(UPLOAD_INFO_FILE))
+    .await
+    .map_err(|_| AppError::RecordNotFound("upload not found".to_string()))?;
+  Ok(serde_json::from_slice(&data)?)
+}
+
+fn part_file_name(part_number: i32) -> String {
+  format!("part_{:05}", part_number)
+}
+
+fn hex_digest(data: &[u8]) -> String {
+  Sha256::digest(data)
+    .iter()
+    .map(|b| format!("{:02x}", b))
+    .collect()
+}
+
+fn not_found_or_io(err: std::io::Error, object_key: &str) -> AppError {
+  if err.kind() == ErrorKind::NotFound {
+    AppError::RecordNotFound(format!("blob not found for key:{object_key}"))
+  } else {
+    err.into()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn client() -> LocalFsBucketClientImpl {
+    LocalFsBucketClientImpl {
+      root: Arc::new(std::env::temp_dir().join("appflowy_local_fs_test")),
+      signing_key: Arc::new(b"secret".to_vec()),
+      public_url: "http://localhost:8000".to_string(),
+    }
+  }
+
+  #[test]
+  fn local_fs_rejects_keys_escaping_root() {
+    assert!(relative_key_path("workspace/parent/file").is_ok());
+    assert!(relative_key_path("").is_err());
+    assert!(relative_key_path("/etc/passwd").is_err());
+    assert!(relative_key_path("workspace/../../etc/passwd").is_err());
+    assert!(relative_key_path("workspace/./file").is_err());
+    assert!(relative_key_path("workspace\\..\\file").is_err());
+  }
+
+  #[test]
+  fn local_fs_presigned_token_round_trip() {
+    let client = client();
+    let token = LocalFsPresignedToken {
+      object_key: "workspace/parent/file".to_string(),
+      method: PresignedMethod::Put,
+      expires_at: chrono::Utc::now().timestamp() + 60,
+      content_type: Some("application/zip".to_string()),
+      content_length: Some(10),
+    };
+    let signed = client.sign_token(&token).unwrap();
+    let verified = client
+      .verify_presigned_token(&signed, PresignedMethod::Put)
+      .unwrap();
+    assert_eq!(verified.object_key, token.object_key);
+    assert_eq!(verified.content_length, Some(10));
+
+    assert!(client
+      .verify_presigned_token(&signed, PresignedMethod::Get)
+      .is_err());
+    let mut tampered = signed.clone();
+    tampered.insert(0, 'a');
+    assert!(client
+      .verify_presigned_token(&tampered, PresignedMethod::Put)
+      .is_err());
+  }
+
+  #[test]
+  fn local_fs_presigned_token_expired() {
+    let client = client();
+    let token = LocalFsPresignedToken {
+      object_key: "workspace/parent/file".to_string(),
+      method: PresignedMethod::Get,
+      expires_at: chrono::Utc::now().timestamp() - 1,
+      content_type: None,
+      content_length: None,
+    };
+    let signed = client.sign_token(&token).unwrap();
+    assert!(client
+      .verify_presigned_token(&signed, PresignedMethod::Get)
+      .is_err());
+  }
+}
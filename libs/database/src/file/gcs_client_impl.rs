@@ -0,0 +1,157 @@
+use crate::file::s3_client_impl::{AwsS3BucketClientImpl, S3ResponseData};
+use crate::file::BucketClient;
+use app_error::AppError;
+use async_trait::async_trait;
+use aws_sdk_s3::primitives::ByteStream;
+use database_entity::file_dto::{
+  CompleteUploadRequest, CreateUploadRequest, CreateUploadResponse, UploadPartData,
+  UploadPartResponse,
+};
+use futures_util::{stream, StreamExt};
+use tracing::{trace, warn};
+
+/// Endpoint of the Cloud Storage XML API.
+pub const GCS_XML_API_ENDPOINT: &str = "https://storage.googleapis.com";
+const DELETE_CONCURRENCY: usize = 16;
+
+/// Google Cloud Storage client. Cloud Storage implements the S3 API through its XML API when
+/// authenticating with HMAC keys, so the requests go through the aws sdk configured with
+/// [GCS_XML_API_ENDPOINT]: https://cloud.google.com/storage/docs/interoperability
+///
+/// The only missing operation is the multi-object delete, which is replaced by concurrent single
+/// object deletes.
+#[derive(Clone)]
+pub struct GcsBucketClientImpl {
+  inner: AwsS3BucketClientImpl,
+}
+
+impl GcsBucketClientImpl {
+  pub fn new(inner: AwsS3BucketClientImpl) -> Self {
+    Self { inner }
+  }
+
+  /// Returns the number of deleted objects.
+  async fn delete_each(&self, object_keys: Vec<String>) -> usize {
+    stream::iter(object_keys)
+      .map(|key| async move {
+        let result = self.inner.delete_blob(&key).await;
+        if let Err(err) = &result {
+          warn!("failed to delete object {} from GCS: {}", key, err);
+        }
+        result.is_ok()
+      })
+      .buffer_unordered(DELETE_CONCURRENCY)
+      .filter(|deleted| futures_util::future::ready(*deleted))
+      .count()
+      .await
+  }
+}
+
+#[async_trait]
+impl BucketClient for GcsBucketClientImpl {
+  type ResponseData = S3ResponseData;
+
+  async fn put_blob(
+    &self,
+    object_key: &str,
+    content: ByteStream,
+    content_type: Option<&str>,
+  ) -> Result<(), AppError> {
+    self.inner.put_blob(object_key, content, content_type).await
+  }
+
+  async fn put_blob_with_content_type(
+    &self,
+    object_key: &str,
+    stream: ByteStream,
+    content_type: &str,
+  ) -> Result<(), AppError> {
+    self
+      .inner
+      .put_blob_with_content_type(object_key, stream, content_type)
+      .await
+  }
+
+  async fn delete_blob(&self, object_key: &str) -> Result<Self::ResponseData, AppError> {
+    self.inner.delete_blob(object_key).await
+  }
+
+  async fn delete_blobs(&self, object_keys: Vec<String>) -> Result<(), AppError> {
+    let deleted = self.delete_each(object_keys).await;
+    trace!("deleted {} objects from GCS", deleted);
+    Ok(())
+  }
+
+  async fn get_blob(&self, object_key: &str) -> Result<Self::ResponseData, AppError> {
+    self.inner.get_blob(object_key).await
+  }
+
+  async fn create_upload(
+    &self,
+    object_key: &str,
+    req: CreateUploadRequest,
+  ) -> Result<CreateUploadResponse, AppError> {
+    self.inner.create_upload(object_key, req).await
+  }
+
+  async fn upload_part(
+    &self,
+    object_key: &str,
+    req: UploadPartData,
+  ) -> Result<UploadPartResponse, AppError> {
+    self.inner.upload_part(object_key, req).await
+  }
+
+  async fn complete_upload(
+    &self,
+    object_key: &str,
+    req: CompleteUploadRequest,
+  ) -> Result<(usize, String), AppError> {
+    self.inner.complete_upload(object_key, req).await
+  }
+
+  async fn remove_dir(&self, dir: &str) -> Result<(), AppError> {
+    loop {
+      let keys = self.inner.list_dir(dir, 1000).await?;
+      if keys.is_empty() {
+        break;
+      }
+      // Stop instead of listing the same objects over and over when the deletes fail.
+      if self.delete_each(keys).await == 0 {
+        return Err(AppError::Internal(anyhow::anyhow!(
+          "Failed to delete objects at directory: {}",
+          dir
+        )));
+      }
+    }
+    Ok(())
+  }
+
+  async fn list_dir(&self, dir: &str, limit: usize) -> Result<Vec<String>, AppError> {
+    self.inner.list_dir(dir, limit).await
+  }
+
+  async fn gen_presigned_put_url(
+    &self,
+    object_key: &str,
+    content_type: &str,
+    content_length: u64,
+    expires_in_secs: u64,
+  ) -> Result<String, AppError> {
+    self
+      .inner
+      .gen_presigned_put_url(object_key, content_type, content_length, expires_in_secs)
+      .await
+  }
+
+  async fn gen_presigned_get_url(
+    &self,
+    object_key: &str,
+    expires_in_secs: u64,
+  ) -> Result<String, AppError> {
+    self
+      .inner
+      .gen_presigned_get_url(object_key, expires_in_secs)
+      .await
+  }
+}
@@ -0,0 +1,335 @@
+use crate::file::s3_client_impl::S3ResponseData;
+use crate::file::BucketClient;
+use app_error::AppError;
+use async_trait::async_trait;
+use aws_sdk_s3::primitives::ByteStream;
+use database_entity::file_dto::{
+  CompleteUploadRequest, CreateUploadRequest, CreateUploadResponse, UploadPartData,
+  UploadPartResponse,
+};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard};
+use uuid::Uuid;
+
+/// Blob storage kept in the memory of the process, used by the test harness so the tests don't
+/// need an object store. The clones of a client share the same blobs.
+///
+/// Presigned urls use the `memory://` scheme, they can't be fetched but are enough to check
+/// which object a url points to.
+#[derive(Clone, Default)]
+pub struct InMemoryBucketClientImpl {
+  inner: Arc<Mutex<InMemoryBucket>>,
+}
+
+#[derive(Default)]
+struct InMemoryBucket {
+  /// Sorted by key, so listing a directory returns the keys in the same order as S3.
+  blobs: BTreeMap<String, InMemoryBlob>,
+  uploads: HashMap<String, InMemoryUpload>,
+}
+
+#[derive(Clone)]
+struct InMemoryBlob {
+  data: Vec<u8>,
+  content_type: Option<String>,
+}
+
+struct InMemoryUpload {
+  object_key: String,
+  content_type: String,
+  parts: HashMap<i32, Vec<u8>>,
+}
+
+impl InMemoryBucketClientImpl {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Returns true if a blob is stored under the key.
+  pub fn contains(&self, object_key: &str) -> bool {
+    self.bucket().blobs.contains_key(object_key)
+  }
+
+  /// Number of blobs stored, the pending multipart uploads are not counted.
+  pub fn len(&self) -> usize {
+    self.bucket().blobs.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  fn bucket(&self) -> MutexGuard<'_, InMemoryBucket> {
+    // A panicking test must not fail the other tests sharing the client.
+    self
+      .inner
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+}
+
+#[async_trait]
+impl BucketClient for InMemoryBucketClientImpl {
+  type ResponseData = S3ResponseData;
+
+  async fn put_blob(
+    &self,
+    object_key: &str,
+    content: ByteStream,
+    content_type: Option<&str>,
+  ) -> Result<(), AppError> {
+    let data = content
+      .collect()
+      .await
+      .map_err(|err| AppError::Internal(anyhow::anyhow!("Failed to collect body: {}", err)))?
+      .into_bytes()
+      .to_vec();
+    self.bucket().blobs.insert(
+      object_key.to_string(),
+      InMemoryBlob {
+        data,
+        content_type: content_type.map(|s| s.to_string()),
+      },
+    );
+    Ok(())
+  }
+
+  async fn put_blob_with_content_type(
+    &self,
+    object_key: &str,
+    stream: ByteStream,
+    content_type: &str,
+  ) -> Result<(), AppError> {
+    self.put_blob(object_key, stream, Some(content_type)).await
+  }
+
+  async fn delete_blob(&self, object_key: &str) -> Result<Self::ResponseData, AppError> {
+    self.bucket().blobs.remove(object_key);
+    Ok(S3ResponseData::new_with_data(vec![], None))
+  }
+
+  async fn delete_blobs(&self, object_keys: Vec<String>) -> Result<(), AppError> {
+    let mut bucket = self.bucket();
+    for key in object_keys {
+      bucket.blobs.remove(&key);
+    }
+    Ok(())
+  }
+
+  async fn get_blob(&self, object_key: &str) -> Result<Self::ResponseData, AppError> {
+    let blob = self
+      .bucket()
+      .blobs
+      .get(object_key)
+      .cloned()
+      .ok_or_else(|| AppError::RecordNotFound(format!("blob not found for key:{object_key}")))?;
+    Ok(S3ResponseData::new_with_data(blob.data, blob.content_type))
+  }
+
+  async fn create_upload(
+    &self,
+    object_key: &str,
+    req: CreateUploadRequest,
+  ) -> Result<CreateUploadResponse, AppError> {
+    let upload_id = Uuid::new_v4().to_string();
+    self.bucket().uploads.insert(
+      upload_id.clone(),
+      InMemoryUpload {
+        object_key: object_key.to_string(),
+        content_type: req.content_type,
+        parts: HashMap::new(),
+      },
+    );
+    Ok(CreateUploadResponse {
+      file_id: req.file_id,
+      upload_id,
+    })
+  }
+
+  async fn upload_part(
+    &self,
+    object_key: &str,
+    req: UploadPartData,
+  ) -> Result<UploadPartResponse, AppError> {
+    if req.body.is_empty() {
+      return Err(AppError::InvalidRequest("body is empty".to_string()));
+    }
+    let mut bucket = self.bucket();
+    let upload = upload_of_key(&mut bucket, &req.upload_id, object_key)?;
+    let e_tag = hex_digest(&req.body);
+    upload.parts.insert(req.part_number, req.body);
+    Ok(UploadPartResponse {
+      part_num: req.part_number,
+      e_tag,
+    })
+  }
+
+  async fn complete_upload(
+    &self,
+    object_key: &str,
+    req: CompleteUploadRequest,
+  ) -> Result<(usize, String), AppError> {
+    let mut bucket = self.bucket();
+    let upload = upload_of_key(&mut bucket, &req.upload_id, object_key)?;
+    let mut parts = req.parts;
+    parts.sort_by_key(|part| part.part_number);
+    let mut data = Vec::new();
+    for part in parts {
+      let part_data = upload.parts.get(&part.part_number).ok_or_else(|| {
+        AppError::InvalidRequest(format!("part {} was not uploaded", part.part_number))
+      })?;
+      if hex_digest(part_data) != part.e_tag {
+        return Err(AppError::InvalidRequest(format!(
+          "e_tag of part {} does not match",
+          part.part_number
+        )));
+      }
+      data.extend_from_slice(part_data);
+    }
+
+    let content_type = upload.content_type.clone();
+    bucket.uploads.remove(&req.upload_id);
+    let len = data.len();
+    bucket.blobs.insert(
+      object_key.to_string(),
+      InMemoryBlob {
+        data,
+        content_type: Some(content_type.clone()),
+      },
+    );
+    Ok((len, content_type))
+  }
+
+  async fn remove_dir(&self, dir: &str) -> Result<(), AppError> {
+    self.bucket().blobs.retain(|key, _| !key.starts_with(dir));
+    Ok(())
+  }
+
+  async fn list_dir(&self, dir: &str, limit: usize) -> Result<Vec<String>, AppError> {
+    let keys = self
+      .bucket()
+      .blobs
+      .keys()
+      .filter(|key| key.starts_with(dir))
+      .take(limit)
+      .cloned()
+      .collect();
+    Ok(keys)
+  }
+
+  async fn gen_presigned_put_url(
+    &self,
+    object_key: &str,
+    content_type: &str,
+    content_length: u64,
+    expires_in_secs: u64,
+  ) -> Result<String, AppError> {
+    Ok(format!(
+      "memory://{}?method=put&content_type={}&content_length={}&expires_in={}",
+      object_key, content_type, content_length, expires_in_secs
+    ))
+  }
+
+  async fn gen_presigned_get_url(
+    &self,
+    object_key: &str,
+    expires_in_secs: u64,
+  ) -> Result<String, AppError> {
+    Ok(format!(
+      "memory://{}?method=get&expires_in={}",
+      object_key, expires_in_secs
+    ))
+  }
+}
+
+fn upload_of_key<'a>(
+  bucket: &'a mut InMemoryBucket,
+  upload_id: &str,
+  object_key: &str,
+) -> Result<&'a mut InMemoryUpload, AppError> {
+  let upload = bucket
+    .uploads
+    .get_mut(upload_id)
+    .ok_or_else(|| AppError::RecordNotFound(format!("upload {} not found", upload_id)))?;
+  if upload.object_key != object_key {
+    return Err(AppError::InvalidRequest(format!(
+      "upload {} does not belong to {}",
+      upload_id, object_key
+    )));
+  }
+  Ok(upload)
+}
+
+fn hex_digest(data: &[u8]) -> String {
+  Sha256::digest(data)
+    .iter()
+    .map(|b| format!("{:02x}", b))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::file::ResponseBlob;
+  use database_entity::file_dto::CompletedPartRequest;
+
+  #[tokio::test]
+  async fn in_memory_multipart_upload() {
+    let client = InMemoryBucketClientImpl::new();
+    let upload = client
+      .create_upload(
+        "workspace/parent/file",
+        CreateUploadRequest {
+          file_id: "file".to_string(),
+          parent_dir: "parent".to_string(),
+          content_type: "text/plain".to_string(),
+          file_size: None,
+        },
+      )
+      .await
+      .unwrap();
+    let mut parts = vec![];
+    for (part_number, body) in [(2, b"world".to_vec()), (1, b"hello ".to_vec())] {
+      let resp = client
+        .upload_part(
+          "workspace/parent/file",
+          UploadPartData {
+            file_id: "file".to_string(),
+            upload_id: upload.upload_id.clone(),
+            part_number,
+            body,
+          },
+        )
+        .await
+        .unwrap();
+      parts.push(CompletedPartRequest {
+        e_tag: resp.e_tag,
+        part_number,
+      });
+    }
+    let (len, content_type) = client
+      .complete_upload(
+        "workspace/parent/file",
+        CompleteUploadRequest {
+          file_id: "file".to_string(),
+          parent_dir: "parent".to_string(),
+          upload_id: upload.upload_id,
+          parts,
+        },
+      )
+      .await
+      .unwrap();
+    assert_eq!(len, 11);
+    assert_eq!(content_type, "text/plain");
+
+    let blob = client.get_blob("workspace/parent/file").await.unwrap();
+    assert_eq!(blob.to_blob(), b"hello world");
+    assert_eq!(
+      client.list_dir("workspace/", 10).await.unwrap(),
+      vec!["workspace/parent/file".to_string()]
+    );
+    client.remove_dir("workspace/parent").await.unwrap();
+    assert!(client.is_empty());
+  }
+}
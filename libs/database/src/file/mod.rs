@@ -1,5 +1,15 @@
+pub mod azure_client_impl;
 mod file_storage;
+pub mod fs_client_impl;
+pub mod gcs_client_impl;
+pub mod memory_client_impl;
+mod object_key;
 pub mod s3_client_impl;
+mod storage_backend;
 mod utils;
+mod workspace_storage_router;
 
 pub use file_storage::*;
+pub use object_key::*;
+pub use storage_backend::*;
+pub use workspace_storage_router::*;
@@ -0,0 +1,241 @@
+use app_error::AppError;
+use uuid::Uuid;
+
+/// The path of the file storage API that is embedded in the blob urls stored in the documents.
+const FILE_STORAGE_URL_MARKER: &[u8] = b"/api/file_storage/";
+const UUID_STR_LEN: usize = 36;
+/// Directories whose objects are stored under `{dir}/{workspace_id}/`, along with the uploaded
+/// files stored under `{workspace_id}/`.
+const WORKSPACE_SCOPED_DIRS: [&str; 8] = [
+  "collabs",
+  "collab_archive",
+  "collab_upload",
+  "export",
+  "published-collab",
+  "published_cache",
+  "published_feed",
+  "chat_attachment",
+];
+
+/// Returns the prefix that every object key of the workspace starts with.
+pub fn workspace_object_key_prefix(workspace_id: &Uuid) -> String {
+  format!("{}/", workspace_id)
+}
+
+/// Returns the prefixes of all the objects of the workspace.
+pub fn workspace_object_key_prefixes(workspace_id: &Uuid) -> Vec<String> {
+  std::iter::once(workspace_object_key_prefix(workspace_id))
+    .chain(
+      WORKSPACE_SCOPED_DIRS
+        .iter()
+        .map(|dir| format!("{}/{}/", dir, workspace_id)),
+    )
+    .collect()
+}
+
+/// Returns the workspace the object belongs to, None for the objects that are not stored under
+/// one of the [workspace_object_key_prefixes], like the uploaded imports.
+pub fn workspace_id_of_object_key(object_key: &str) -> Option<Uuid> {
+  let mut segments = object_key.split('/');
+  let first = segments.next()?;
+  if let Ok(workspace_id) = Uuid::parse_str(first) {
+    return Some(workspace_id);
+  }
+  if !WORKSPACE_SCOPED_DIRS.contains(&first) {
+    return None;
+  }
+  segments.next().and_then(|id| Uuid::parse_str(id).ok())
+}
+
+/// Validates a single segment of an object key, such as the `parent_dir` or the `file_id` of a
+/// blob. A segment must not be able to change the directory the object key points to.
+pub fn validate_object_key_segment(segment: &str) -> Result<(), AppError> {
+  let is_valid = !segment.is_empty()
+    && segment != "."
+    && segment != ".."
+    && !segment.contains(['/', '\\'])
+    && !segment.chars().any(char::is_control);
+  if !is_valid {
+    return Err(AppError::InvalidRequest(format!(
+      "Invalid object key segment: {}",
+      segment
+    )));
+  }
+  Ok(())
+}
+
+/// Verifies that the object key lives under the prefix of the authorized workspace, so a request
+/// authorized for one workspace can never read or write the objects of another one.
+pub fn verify_workspace_object_key(workspace_id: &Uuid, object_key: &str) -> Result<(), AppError> {
+  let relative_key = object_key
+    .strip_prefix(&workspace_object_key_prefix(workspace_id))
+    .ok_or(AppError::NotEnoughPermissions)?;
+  relative_key
+    .split('/')
+    .try_for_each(validate_object_key_segment)
+}
+
+/// Returns the id of the object a blob was uploaded for, which is the `parent_dir` of the blobs
+/// uploaded with the v1 API. Their metadata key is `{parent_dir}_{file_id}`, where `parent_dir` is
+/// the id of the object. The blobs uploaded with the v0 API don't belong to an object.
+pub fn blob_parent_dir(blob_metadata_key: &str) -> Option<&str> {
+  let parent_dir = blob_metadata_key.get(..UUID_STR_LEN)?;
+  let file_id = blob_metadata_key.get(UUID_STR_LEN..)?.strip_prefix('_')?;
+  if file_id.is_empty() || Uuid::parse_str(parent_dir).is_err() {
+    return None;
+  }
+  Some(parent_dir)
+}
+
+/// Returns the object key of the blob with the given metadata key.
+pub fn object_key_from_blob_metadata_key(workspace_id: &Uuid, blob_metadata_key: &str) -> String {
+  match blob_parent_dir(blob_metadata_key) {
+    Some(parent_dir) => format!(
+      "{}{}/{}",
+      workspace_object_key_prefix(workspace_id),
+      parent_dir,
+      &blob_metadata_key[UUID_STR_LEN + 1..]
+    ),
+    None => format!(
+      "{}{}",
+      workspace_object_key_prefix(workspace_id),
+      blob_metadata_key
+    ),
+  }
+}
+
+/// Returns the ids of the other workspaces whose file storage urls are referenced in the given
+/// data, along with the referenced url path. Used to audit the documents for blobs that live
+/// outside of the workspace prefix.
+pub fn find_cross_workspace_blob_references(
+  workspace_id: &Uuid,
+  data: &[u8],
+) -> Vec<(Uuid, String)> {
+  let mut references = vec![];
+  let mut start = 0;
+  while let Some(pos) = find_subslice(&data[start..], FILE_STORAGE_URL_MARKER) {
+    let id_start = start + pos + FILE_STORAGE_URL_MARKER.len();
+    start = id_start;
+    let Some(id_bytes) = data.get(id_start..id_start + UUID_STR_LEN) else {
+      break;
+    };
+    let Some(referenced_workspace_id) = std::str::from_utf8(id_bytes)
+      .ok()
+      .and_then(|id| Uuid::parse_str(id).ok())
+    else {
+      continue;
+    };
+    if referenced_workspace_id == *workspace_id {
+      continue;
+    }
+    let path_end = data[id_start..]
+      .iter()
+      .position(|b| !is_url_path_byte(*b))
+      .map(|len| id_start + len)
+      .unwrap_or(data.len());
+    let url_path = format!(
+      "{}{}",
+      String::from_utf8_lossy(FILE_STORAGE_URL_MARKER),
+      String::from_utf8_lossy(&data[id_start..path_end])
+    );
+    references.push((referenced_workspace_id, url_path));
+  }
+  references
+}
+
+fn find_subslice(data: &[u8], needle: &[u8]) -> Option<usize> {
+  data
+    .windows(needle.len())
+    .position(|window| window == needle)
+}
+
+fn is_url_path_byte(b: u8) -> bool {
+  b.is_ascii_alphanumeric() || matches!(b, b'/' | b'-' | b'_' | b'.' | b'~' | b'%' | b'=')
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn object_key_must_stay_in_workspace_prefix() {
+    let workspace_id = Uuid::new_v4();
+    let other_workspace_id = Uuid::new_v4();
+    assert!(
+      verify_workspace_object_key(&workspace_id, &format!("{}/dir/file", workspace_id)).is_ok()
+    );
+    assert!(verify_workspace_object_key(&workspace_id, &format!("{}/file", workspace_id)).is_ok());
+    assert!(verify_workspace_object_key(
+      &workspace_id,
+      &format!("{}/dir/file", other_workspace_id)
+    )
+    .is_err());
+    assert!(verify_workspace_object_key(
+      &workspace_id,
+      &format!("{}/../{}/file", workspace_id, other_workspace_id)
+    )
+    .is_err());
+    assert!(
+      verify_workspace_object_key(&workspace_id, &format!("{}//file", workspace_id)).is_err()
+    );
+    assert!(verify_workspace_object_key(&workspace_id, &format!("{}/", workspace_id)).is_err());
+    assert!(verify_workspace_object_key(&workspace_id, &workspace_id.to_string()).is_err());
+  }
+
+  #[test]
+  fn workspace_of_object_key() {
+    let workspace_id = Uuid::new_v4();
+    for prefix in workspace_object_key_prefixes(&workspace_id) {
+      assert_eq!(
+        workspace_id_of_object_key(&format!("{}dir/file", prefix)),
+        Some(workspace_id)
+      );
+    }
+    assert_eq!(
+      workspace_id_of_object_key(&workspace_id.to_string()),
+      Some(workspace_id)
+    );
+    assert_eq!(
+      workspace_id_of_object_key(&format!("import/{}/file", workspace_id)),
+      None
+    );
+    assert_eq!(workspace_id_of_object_key("import_presigned_url_abc"), None);
+    assert_eq!(workspace_id_of_object_key("collabs/abc/file"), None);
+  }
+
+  #[test]
+  fn find_blob_references_of_other_workspaces() {
+    let workspace_id = Uuid::new_v4();
+    let other_workspace_id = Uuid::new_v4();
+    let data = format!(
+      "\u{1}https://host/api/file_storage/{}/v1/blob/dir/a.png\u{2}https://host/api/file_storage/{}/v1/blob/dir/b.png\u{3}",
+      workspace_id, other_workspace_id
+    );
+    let references = find_cross_workspace_blob_references(&workspace_id, data.as_bytes());
+    assert_eq!(
+      references,
+      vec![(
+        other_workspace_id,
+        format!("/api/file_storage/{}/v1/blob/dir/b.png", other_workspace_id)
+      )]
+    );
+  }
+
+  #[test]
+  fn object_key_of_blob_metadata_key() {
+    let workspace_id = Uuid::new_v4();
+    let view_id = Uuid::new_v4();
+    let v1_key = format!("{}_a_b.png", view_id);
+    assert_eq!(blob_parent_dir(&v1_key), Some(view_id.to_string().as_str()));
+    assert_eq!(
+      object_key_from_blob_metadata_key(&workspace_id, &v1_key),
+      format!("{}/{}/a_b.png", workspace_id, view_id)
+    );
+    assert_eq!(blob_parent_dir("a_b.png"), None);
+    assert_eq!(blob_parent_dir(&format!("{}_", view_id)), None);
+    assert_eq!(
+      object_key_from_blob_metadata_key(&workspace_id, "a_b.png"),
+      format!("{}/a_b.png", workspace_id)
+    );
+  }
+}
@@ -54,52 +54,6 @@ impl AwsS3BucketClientImpl {
     }
   }
 
-  pub async fn gen_presigned_url(
-    &self,
-    s3_key: &str,
-    content_length: u64,
-    expires_in_secs: u64,
-  ) -> Result<String, AppError> {
-    let expires_in = Duration::from_secs(expires_in_secs);
-    let config = PresigningConfig::builder()
-      .start_time(SystemTime::now())
-      .expires_in(expires_in)
-      .build()
-      .map_err(|e| AppError::S3ResponseError(e.to_string()))?;
-
-    // There is no easy way to restrict file size of the upload (default limit max 5GB using PUT or other upload methods)
-    // https://github.com/aws/aws-sdk-net/issues/424
-    //
-    // consider using POST:
-    // https://docs.aws.amazon.com/AmazonS3/latest/API/sigv4-HTTPPOSTConstructPolicy.html
-    let put_object_req = self
-      .client
-      .put_object()
-      .bucket(&self.bucket)
-      .key(s3_key)
-      .content_type("application/zip")
-      .content_length(content_length as i64)
-      .presigned(config)
-      .await
-      .map_err(|err| AppError::Internal(anyhow!("Generate presigned url failed: {:?}", err)))?;
-    let url = put_object_req.uri().to_string();
-
-    let public_url = self
-      .presigned_url_endpoint
-      .as_ref()
-      .map_or(url.clone(), |presigned| {
-        url.replace(&self.endpoint, presigned)
-      });
-    trace!(
-      "generated presigned url: {}, public presigned url:{}, endpoint:{}, presigned_url_endpoint:{:?}",
-      url,
-      public_url,
-      self.endpoint,
-      self.presigned_url_endpoint
-    );
-    Ok(public_url)
-  }
-
   async fn complete_upload_and_get_metadata(
     &self,
     object_key: &str,
@@ -489,6 +443,81 @@ impl BucketClient for AwsS3BucketClientImpl {
         .collect(),
     )
   }
+  async fn gen_presigned_put_url(
+    &self,
+    s3_key: &str,
+    content_type: &str,
+    content_length: u64,
+    expires_in_secs: u64,
+  ) -> Result<String, AppError> {
+    let expires_in = Duration::from_secs(expires_in_secs);
+    let config = PresigningConfig::builder()
+      .start_time(SystemTime::now())
+      .expires_in(expires_in)
+      .build()
+      .map_err(|e| AppError::S3ResponseError(e.to_string()))?;
+
+    // There is no easy way to restrict file size of the upload (default limit max 5GB using PUT or other upload methods)
+    // https://github.com/aws/aws-sdk-net/issues/424
+    //
+    // consider using POST:
+    // https://docs.aws.amazon.com/AmazonS3/latest/API/sigv4-HTTPPOSTConstructPolicy.html
+    let put_object_req = self
+      .client
+      .put_object()
+      .bucket(&self.bucket)
+      .key(s3_key)
+      .content_type(content_type)
+      .content_length(content_length as i64)
+      .presigned(config)
+      .await
+      .map_err(|err| AppError::Internal(anyhow!("Generate presigned url failed: {:?}", err)))?;
+    let url = put_object_req.uri().to_string();
+
+    let public_url = self
+      .presigned_url_endpoint
+      .as_ref()
+      .map_or(url.clone(), |presigned| {
+        url.replace(&self.endpoint, presigned)
+      });
+    trace!(
+      "generated presigned url: {}, public presigned url:{}, endpoint:{}, presigned_url_endpoint:{:?}",
+      url,
+      public_url,
+      self.endpoint,
+      self.presigned_url_endpoint
+    );
+    Ok(public_url)
+  }
+
+  async fn gen_presigned_get_url(
+    &self,
+    s3_key: &str,
+    expires_in_secs: u64,
+  ) -> Result<String, AppError> {
+    let config = PresigningConfig::builder()
+      .start_time(SystemTime::now())
+      .expires_in(Duration::from_secs(expires_in_secs))
+      .build()
+      .map_err(|e| AppError::S3ResponseError(e.to_string()))?;
+    let get_object_req = self
+      .client
+      .get_object()
+      .bucket(&self.bucket)
+      .key(s3_key)
+      .presigned(config)
+      .await
+      .map_err(|err| AppError::Internal(anyhow!("Generate presigned url failed: {:?}", err)))?;
+    let url = get_object_req.uri().to_string();
+    Ok(
+      self
+        .presigned_url_endpoint
+        .as_ref()
+        .map_or(url.clone(), |presigned| {
+          url.replace(&self.endpoint, presigned)
+        }),
+    )
+  }
 }
 
 #[derive(Debug)]
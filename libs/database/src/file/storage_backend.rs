@@ -0,0 +1,236 @@
+use crate::file::azure_client_impl::AzureBlobClientImpl;
+use crate::file::fs_client_impl::LocalFsBucketClientImpl;
+use crate::file::gcs_client_impl::GcsBucketClientImpl;
+use crate::file::memory_client_impl::InMemoryBucketClientImpl;
+use crate::file::s3_client_impl::{AwsS3BucketClientImpl, S3ResponseData};
+use crate::file::{
+  workspace_id_of_object_key, BucketClient, BucketStorage, WorkspaceStorageRouter,
+};
+use app_error::AppError;
+use async_trait::async_trait;
+use aws_sdk_s3::primitives::ByteStream;
+use database_entity::file_dto::{
+  CompleteUploadRequest, CreateUploadRequest, CreateUploadResponse, UploadPartData,
+  UploadPartResponse,
+};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub type BlobBucketStorage = BucketStorage<BlobStorageClient>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BlobStorageBackend {
+  /// AWS S3 or any S3 compatible storage, like MinIO.
+  S3,
+  Gcs,
+  Azure,
+  /// Directory on the local disk, for small self-hosted setups.
+  LocalFs,
+  /// Kept in the memory of the process, only used by the tests. It can't be configured since the
+  /// services would not share the blobs.
+  Memory,
+}
+
+impl TryFrom<&str> for BlobStorageBackend {
+  type Error = anyhow::Error;
+
+  fn try_from(value: &str) -> Result<Self, Self::Error> {
+    match value {
+      "s3" | "minio" => Ok(BlobStorageBackend::S3),
+      "gcs" => Ok(BlobStorageBackend::Gcs),
+      "azure" => Ok(BlobStorageBackend::Azure),
+      "fs" | "local" => Ok(BlobStorageBackend::LocalFs),
+      _ => Err(anyhow::anyhow!("Invalid BlobStorageBackend: {}", value)),
+    }
+  }
+}
+
+/// The blob storage selected by the configuration.
+#[derive(Clone)]
+pub enum BlobStorageClient {
+  S3(AwsS3BucketClientImpl),
+  Gcs(GcsBucketClientImpl),
+  Azure(AzureBlobClientImpl),
+  LocalFs(LocalFsBucketClientImpl),
+  Memory(InMemoryBucketClientImpl),
+  /// Sends the objects of the workspaces configured with their own storage to their bucket.
+  Routed(WorkspaceRoutedClient),
+}
+
+impl BlobStorageClient {
+  pub fn backend(&self) -> BlobStorageBackend {
+    match self {
+      BlobStorageClient::S3(_) => BlobStorageBackend::S3,
+      BlobStorageClient::Gcs(_) => BlobStorageBackend::Gcs,
+      BlobStorageClient::Azure(_) => BlobStorageBackend::Azure,
+      BlobStorageClient::LocalFs(_) => BlobStorageBackend::LocalFs,
+      BlobStorageClient::Memory(_) => BlobStorageBackend::Memory,
+      BlobStorageClient::Routed(routed) => routed.default.backend(),
+    }
+  }
+
+  /// Routes the objects of the workspaces configured with their own storage through the router,
+  /// the other objects keep using this client.
+  pub fn with_workspace_storage(self, router: Arc<WorkspaceStorageRouter>) -> Self {
+    BlobStorageClient::Routed(WorkspaceRoutedClient {
+      default: Box::new(self),
+      router,
+    })
+  }
+
+  /// The client of the default storage of the instance.
+  pub fn default_client(&self) -> &BlobStorageClient {
+    match self {
+      BlobStorageClient::Routed(routed) => &routed.default,
+      client => client,
+    }
+  }
+}
+
+#[derive(Clone)]
+pub struct WorkspaceRoutedClient {
+  default: Box<BlobStorageClient>,
+  router: Arc<WorkspaceStorageRouter>,
+}
+
+impl WorkspaceRoutedClient {
+  async fn client_for(&self, object_key: &str) -> Result<Cow<'_, BlobStorageClient>, AppError> {
+    let client = match self.router.bucket_for_key(object_key).await? {
+      Some(bucket) => Cow::Owned(BlobStorageClient::S3(AwsS3BucketClientImpl::new(
+        bucket.client,
+        bucket.bucket,
+        bucket.endpoint,
+        None,
+      ))),
+      None => Cow::Borrowed(self.default.as_ref()),
+    };
+    Ok(client)
+  }
+
+  async fn delete_blobs(&self, object_keys: Vec<String>) -> Result<(), AppError> {
+    let mut keys_by_workspace = HashMap::<_, Vec<String>>::new();
+    for object_key in object_keys {
+      keys_by_workspace
+        .entry(workspace_id_of_object_key(&object_key))
+        .or_default()
+        .push(object_key);
+    }
+    for object_keys in keys_by_workspace.into_values() {
+      let client = self.client_for(&object_keys[0]).await?;
+      client.delete_blobs(object_keys).await?;
+    }
+    Ok(())
+  }
+}
+
+macro_rules! dispatch {
+  ($self:ident, $key:expr, $client:ident => $call:expr) => {
+    match $self {
+      BlobStorageClient::S3($client) => $call,
+      BlobStorageClient::Gcs($client) => $call,
+      BlobStorageClient::Azure($client) => $call,
+      BlobStorageClient::LocalFs($client) => $call,
+      BlobStorageClient::Memory($client) => $call,
+      BlobStorageClient::Routed(routed) => {
+        let $client = routed.client_for($key).await?;
+        $call
+      },
+    }
+  };
+}
+
+#[async_trait]
+impl BucketClient for BlobStorageClient {
+  type ResponseData = S3ResponseData;
+
+  async fn put_blob(
+    &self,
+    object_key: &str,
+    content: ByteStream,
+    content_type: Option<&str>,
+  ) -> Result<(), AppError> {
+    dispatch!(self, object_key, client => client.put_blob(object_key, content, content_type).await)
+  }
+
+  async fn put_blob_with_content_type(
+    &self,
+    object_key: &str,
+    stream: ByteStream,
+    content_type: &str,
+  ) -> Result<(), AppError> {
+    dispatch!(self, object_key, client => client.put_blob_with_content_type(object_key, stream, content_type).await)
+  }
+
+  async fn delete_blob(&self, object_key: &str) -> Result<Self::ResponseData, AppError> {
+    dispatch!(self, object_key, client => client.delete_blob(object_key).await)
+  }
+
+  async fn delete_blobs(&self, object_keys: Vec<String>) -> Result<(), AppError> {
+    // The keys may belong to different workspaces, so they are grouped by storage first.
+    match self {
+      BlobStorageClient::S3(client) => client.delete_blobs(object_keys).await,
+      BlobStorageClient::Gcs(client) => client.delete_blobs(object_keys).await,
+      BlobStorageClient::Azure(client) => client.delete_blobs(object_keys).await,
+      BlobStorageClient::LocalFs(client) => client.delete_blobs(object_keys).await,
+      BlobStorageClient::Memory(client) => client.delete_blobs(object_keys).await,
+      BlobStorageClient::Routed(routed) => routed.delete_blobs(object_keys).await,
+    }
+  }
+
+  async fn get_blob(&self, object_key: &str) -> Result<Self::ResponseData, AppError> {
+    dispatch!(self, object_key, client => client.get_blob(object_key).await)
+  }
+
+  async fn create_upload(
+    &self,
+    object_key: &str,
+    req: CreateUploadRequest,
+  ) -> Result<CreateUploadResponse, AppError> {
+    dispatch!(self, object_key, client => client.create_upload(object_key, req).await)
+  }
+
+  async fn upload_part(
+    &self,
+    object_key: &str,
+    req: UploadPartData,
+  ) -> Result<UploadPartResponse, AppError> {
+    dispatch!(self, object_key, client => client.upload_part(object_key, req).await)
+  }
+
+  async fn complete_upload(
+    &self,
+    object_key: &str,
+    req: CompleteUploadRequest,
+  ) -> Result<(usize, String), AppError> {
+    dispatch!(self, object_key, client => client.complete_upload(object_key, req).await)
+  }
+
+  async fn remove_dir(&self, dir: &str) -> Result<(), AppError> {
+    dispatch!(self, dir, client => client.remove_dir(dir).await)
+  }
+
+  async fn list_dir(&self, dir: &str, limit: usize) -> Result<Vec<String>, AppError> {
+    dispatch!(self, dir, client => client.list_dir(dir, limit).await)
+  }
+
+  async fn gen_presigned_put_url(
+    &self,
+    object_key: &str,
+    content_type: &str,
+    content_length: u64,
+    expires_in_secs: u64,
+  ) -> Result<String, AppError> {
+    dispatch!(self, object_key, client => client
+      .gen_presigned_put_url(object_key, content_type, content_length, expires_in_secs)
+      .await)
+  }
+
+  async fn gen_presigned_get_url(
+    &self,
+    object_key: &str,
+    expires_in_secs: u64,
+  ) -> Result<String, AppError> {
+    dispatch!(self, object_key, client => client.gen_presigned_get_url(object_key, expires_in_secs).await)
+  }
+}
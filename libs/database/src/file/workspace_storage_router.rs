@@ -0,0 +1,220 @@
+use crate::file::workspace_id_of_object_key;
+use crate::workspace_storage::select_workspace_storage_settings;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::anyhow;
+use app_error::AppError;
+use aws_sdk_s3::config::{Credentials, Region, SharedCredentialsProvider};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use shared_entity::dto::admin_dto::WorkspaceStorageSettings;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+const NONCE_LEN: usize = 12;
+/// The expired entries are only dropped once the cache holds that many workspaces.
+const MAX_CACHED_WORKSPACES: usize = 10_000;
+
+/// Encrypts the storage settings of the workspaces, which contain the keys of their buckets, with
+/// AES-256-GCM. The nonce is stored in front of the ciphertext.
+#[derive(Clone)]
+pub struct WorkspaceStorageCipher {
+  cipher: Aes256Gcm,
+}
+
+impl WorkspaceStorageCipher {
+  /// `key` is the base64 encoding of a 32 bytes key.
+  pub fn from_base64(key: &str) -> Result<Self, AppError> {
+    let key = STANDARD
+      .decode(key.trim())
+      .map_err(|err| AppError::Internal(anyhow!("Invalid workspace storage key: {}", err)))?;
+    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|_| {
+      AppError::Internal(anyhow!(
+        "The workspace storage key must be 32 bytes long, got {} bytes",
+        key.len()
+      ))
+    })?;
+    Ok(Self { cipher })
+  }
+
+  pub fn encrypt(&self, settings: &WorkspaceStorageSettings) -> Result<Vec<u8>, AppError> {
+    let plaintext = serde_json::to_vec(settings)?;
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = self
+      .cipher
+      .encrypt(&nonce, plaintext.as_slice())
+      .map_err(|_| AppError::Internal(anyhow!("Failed to encrypt the workspace storage")))?;
+    let mut data = nonce.to_vec();
+    data.extend(ciphertext);
+    Ok(data)
+  }
+
+  pub fn decrypt(&self, data: &[u8]) -> Result<WorkspaceStorageSettings, AppError> {
+    if data.len() < NONCE_LEN {
+      return Err(AppError::Internal(anyhow!(
+        "The encrypted workspace storage is too short"
+      )));
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    let plaintext = self
+      .cipher
+      .decrypt(Nonce::from_slice(nonce), ciphertext)
+      .map_err(|_| AppError::Internal(anyhow!("Failed to decrypt the workspace storage")))?;
+    Ok(serde_json::from_slice(&plaintext)?)
+  }
+}
+
+/// The bucket holding the objects of a workspace that does not use the default storage.
+#[derive(Clone)]
+pub struct WorkspaceBucket {
+  pub client: aws_sdk_s3::Client,
+  pub bucket: String,
+  /// Empty when the AWS endpoint of the region is used.
+  pub endpoint: String,
+}
+
+struct CachedBucket {
+  bucket: Option<WorkspaceBucket>,
+  cached_at: Instant,
+}
+
+/// Resolves the bucket of the workspaces configured with their own storage, from the workspace id
+/// found in the object keys. The settings are cached, so a change reaches all the servers within
+/// the cache ttl.
+///
+/// Lookup failures are returned as errors instead of falling back to the default storage, so the
+/// objects of a workspace never end up outside of its storage.
+pub struct WorkspaceStorageRouter {
+  pg_pool: PgPool,
+  cipher: WorkspaceStorageCipher,
+  /// Used for the buckets configured without keys.
+  default_credentials: Credentials,
+  cache_ttl: Duration,
+  cache: RwLock<HashMap<Uuid, CachedBucket>>,
+}
+
+impl WorkspaceStorageRouter {
+  pub fn new(
+    pg_pool: PgPool,
+    cipher: WorkspaceStorageCipher,
+    default_credentials: Credentials,
+    cache_ttl: Duration,
+  ) -> Self {
+    Self {
+      pg_pool,
+      cipher,
+      default_credentials,
+      cache_ttl,
+      cache: RwLock::new(HashMap::new()),
+    }
+  }
+
+  pub fn cipher(&self) -> &WorkspaceStorageCipher {
+    &self.cipher
+  }
+
+  pub fn cache_ttl(&self) -> Duration {
+    self.cache_ttl
+  }
+
+  /// Returns the bucket of the workspace the object belongs to, None for the default storage.
+  pub async fn bucket_for_key(
+    &self,
+    object_key: &str,
+  ) -> Result<Option<WorkspaceBucket>, AppError> {
+    match workspace_id_of_object_key(object_key) {
+      Some(workspace_id) => self.bucket_for_workspace(&workspace_id).await,
+      None => Ok(None),
+    }
+  }
+
+  pub async fn bucket_for_workspace(
+    &self,
+    workspace_id: &Uuid,
+  ) -> Result<Option<WorkspaceBucket>, AppError> {
+    if let Some(cached) = self.cache.read().await.get(workspace_id) {
+      if cached.cached_at.elapsed() < self.cache_ttl {
+        return Ok(cached.bucket.clone());
+      }
+    }
+
+    let bucket = match select_workspace_storage_settings(&self.pg_pool, workspace_id).await? {
+      Some(settings) => Some(self.bucket(&self.cipher.decrypt(&settings)?)),
+      None => None,
+    };
+    let mut cache = self.cache.write().await;
+    if cache.len() >= MAX_CACHED_WORKSPACES {
+      let cache_ttl = self.cache_ttl;
+      cache.retain(|_, cached| cached.cached_at.elapsed() < cache_ttl);
+    }
+    cache.insert(
+      *workspace_id,
+      CachedBucket {
+        bucket: bucket.clone(),
+        cached_at: Instant::now(),
+      },
+    );
+    Ok(bucket)
+  }
+
+  /// Builds the client of the bucket described by the settings.
+  pub fn bucket(&self, settings: &WorkspaceStorageSettings) -> WorkspaceBucket {
+    let credentials = match (&settings.access_key, &settings.secret_key) {
+      (Some(access_key), Some(secret_key)) => Credentials::new(
+        access_key.clone(),
+        secret_key.clone(),
+        None,
+        None,
+        "workspace",
+      ),
+      _ => self.default_credentials.clone(),
+    };
+    let config_builder = aws_sdk_s3::Config::builder()
+      .credentials_provider(SharedCredentialsProvider::new(credentials))
+      .region(Region::new(settings.region.clone()));
+    let config = match &settings.endpoint {
+      Some(endpoint) => config_builder
+        .endpoint_url(endpoint)
+        .force_path_style(true)
+        .build(),
+      None => config_builder.build(),
+    };
+    WorkspaceBucket {
+      client: aws_sdk_s3::Client::from_conf(config),
+      bucket: settings.bucket.clone(),
+      endpoint: settings.endpoint.clone().unwrap_or_default(),
+    }
+  }
+
+  /// Drops the cached settings of the workspace on this server.
+  pub async fn invalidate(&self, workspace_id: &Uuid) {
+    self.cache.write().await.remove(workspace_id);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn encrypted_settings_roundtrip() {
+    let cipher = WorkspaceStorageCipher::from_base64(&STANDARD.encode([7u8; 32])).unwrap();
+    let settings = WorkspaceStorageSettings {
+      bucket: "appflowy-eu".to_string(),
+      region: "eu-central-1".to_string(),
+      endpoint: None,
+      access_key: Some("access".to_string()),
+      secret_key: Some("secret".to_string()),
+    };
+    let encrypted = cipher.encrypt(&settings).unwrap();
+    assert!(!encrypted.windows(6).any(|window| window == b"secret"));
+    assert_eq!(cipher.decrypt(&encrypted).unwrap(), settings);
+
+    let other = WorkspaceStorageCipher::from_base64(&STANDARD.encode([8u8; 32])).unwrap();
+    assert!(other.decrypt(&encrypted).is_err());
+    assert!(WorkspaceStorageCipher::from_base64(&STANDARD.encode([7u8; 16])).is_err());
+  }
+}
@@ -0,0 +1,213 @@
+use app_error::AppError;
+use sqlx::{Executor, FromRow, PgPool, Postgres};
+use std::ops::DerefMut;
+use uuid::Uuid;
+
+use crate::pg_row::AFWorkspaceHistoryCompactionRow;
+
+const SECONDS_PER_HOUR: i64 = 3600;
+const SECONDS_PER_DAY: i64 = 24 * SECONDS_PER_HOUR;
+const SECONDS_PER_WEEK: i64 = 7 * SECONDS_PER_DAY;
+
+/// Workspace whose history is compacted by the worker, with the tiers to apply.
+#[derive(Debug, Clone, FromRow)]
+pub struct HistoryCompactionTarget {
+  pub workspace_id: Uuid,
+  pub hourly_days: i32,
+  pub daily_days: i32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HistoryCompactionResult {
+  /// Number of deleted versions (rows of `af_snapshot_meta`).
+  pub deleted_versions: i64,
+  /// Number of deleted document states (rows of `af_snapshot_state`).
+  pub deleted_states: i64,
+  /// Size of the deleted versions and document states, in bytes.
+  pub reclaimed_bytes: i64,
+}
+
+pub async fn select_workspace_history_compaction<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+) -> Result<Option<AFWorkspaceHistoryCompactionRow>, AppError> {
+  let row = sqlx::query_as::<_, AFWorkspaceHistoryCompactionRow>(
+    r#"
+      SELECT workspace_id, enabled, hourly_days, daily_days, last_compacted_at, updated_at
+      FROM af_workspace_history_compaction
+      WHERE workspace_id = $1
+    "#,
+  )
+  .bind(workspace_id)
+  .fetch_optional(executor)
+  .await?;
+  Ok(row)
+}
+
+pub async fn upsert_workspace_history_compaction<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  enabled: bool,
+  hourly_days: i32,
+  daily_days: i32,
+) -> Result<AFWorkspaceHistoryCompactionRow, AppError> {
+  let row = sqlx::query_as::<_, AFWorkspaceHistoryCompactionRow>(
+    r#"
+      INSERT INTO af_workspace_history_compaction (workspace_id, enabled, hourly_days, daily_days)
+      VALUES ($1, $2, $3, $4)
+      ON CONFLICT (workspace_id) DO UPDATE
+      SET enabled = EXCLUDED.enabled,
+          hourly_days = EXCLUDED.hourly_days,
+          daily_days = EXCLUDED.daily_days,
+          updated_at = CURRENT_TIMESTAMP
+      RETURNING workspace_id, enabled, hourly_days, daily_days, last_compacted_at, updated_at
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(enabled)
+  .bind(hourly_days)
+  .bind(daily_days)
+  .fetch_one(executor)
+  .await?;
+  Ok(row)
+}
+
+/// Returns the workspaces with compaction enabled, ordered by id and starting after `after`. The
+/// workspaces without settings use the default tiers of the table.
+pub async fn select_history_compaction_targets<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  after: Option<Uuid>,
+  limit: i64,
+) -> Result<Vec<HistoryCompactionTarget>, AppError> {
+  let targets = sqlx::query_as::<_, HistoryCompactionTarget>(
+    r#"
+      SELECT w.workspace_id,
+             COALESCE(c.hourly_days, 7) AS hourly_days,
+             COALESCE(c.daily_days, 30) AS daily_days
+      FROM af_workspace w
+      LEFT JOIN af_workspace_history_compaction c ON c.workspace_id = w.workspace_id
+      WHERE COALESCE(c.enabled, TRUE)
+        AND ($1::UUID IS NULL OR w.workspace_id > $1)
+      ORDER BY w.workspace_id
+      LIMIT $2
+    "#,
+  )
+  .bind(after)
+  .bind(limit)
+  .fetch_all(executor)
+  .await?;
+  Ok(targets)
+}
+
+/// Thins out the history of the collabs of the workspace. For each collab, the most recent version
+/// of every hour is kept for the last `hourly_days`, of every day until `daily_days`, and of every
+/// week beyond. The document states that are no longer needed to restore a remaining version are
+/// deleted as well, the latest state of each collab is always kept.
+///
+/// `now` is the unix timestamp, in seconds, the tiers are computed from.
+pub async fn compact_workspace_history(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  hourly_days: i32,
+  daily_days: i32,
+  now: i64,
+) -> Result<HistoryCompactionResult, AppError> {
+  let hourly_since = now - hourly_days as i64 * SECONDS_PER_DAY;
+  let daily_since = now - daily_days as i64 * SECONDS_PER_DAY;
+  let mut txn = pg_pool.begin().await?;
+
+  let (deleted_versions, version_bytes) = sqlx::query_as::<_, (i64, i64)>(
+    r#"
+      WITH ranked AS (
+        SELECT oid, partition_key, created_at,
+               ROW_NUMBER() OVER (
+                 PARTITION BY oid, partition_key, tier, bucket
+                 ORDER BY created_at DESC
+               ) AS rank
+        FROM (
+          SELECT oid, partition_key, created_at,
+                 CASE WHEN created_at >= $2 THEN 0 WHEN created_at >= $3 THEN 1 ELSE 2 END AS tier,
+                 CASE WHEN created_at >= $2 THEN created_at / $4
+                      WHEN created_at >= $3 THEN created_at / $5
+                      ELSE created_at / $6 END AS bucket
+          FROM af_snapshot_meta
+          WHERE workspace_id = $1
+        ) versions
+      ), deleted AS (
+        DELETE FROM af_snapshot_meta m
+        USING ranked r
+        WHERE m.workspace_id = $1
+          AND m.oid = r.oid
+          AND m.partition_key = r.partition_key
+          AND m.created_at = r.created_at
+          AND r.rank > 1
+        RETURNING octet_length(m.snapshot) AS size
+      )
+      SELECT COUNT(*), COALESCE(SUM(size), 0)::BIGINT FROM deleted
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(hourly_since)
+  .bind(daily_since)
+  .bind(SECONDS_PER_HOUR)
+  .bind(SECONDS_PER_DAY)
+  .bind(SECONDS_PER_WEEK)
+  .fetch_one(txn.deref_mut())
+  .await?;
+
+  // A version is restored from the first document state created at or after it, so a state is
+  // only needed when a version was created between the previous state and itself.
+  let (deleted_states, state_bytes) = sqlx::query_as::<_, (i64, i64)>(
+    r#"
+      WITH states AS (
+        SELECT snapshot_id, partition_key, oid, created_at,
+               LAG(created_at) OVER w AS prev_created_at,
+               LEAD(created_at) OVER w AS next_created_at
+        FROM af_snapshot_state
+        WHERE workspace_id = $1
+        WINDOW w AS (PARTITION BY oid, partition_key ORDER BY created_at)
+      ), deleted AS (
+        DELETE FROM af_snapshot_state t
+        USING states s
+        WHERE t.snapshot_id = s.snapshot_id
+          AND t.partition_key = s.partition_key
+          AND s.next_created_at IS NOT NULL
+          AND NOT EXISTS (
+            SELECT 1 FROM af_snapshot_meta m
+            WHERE m.oid = s.oid
+              AND m.partition_key = s.partition_key
+              AND m.created_at <= s.created_at
+              AND (s.prev_created_at IS NULL OR m.created_at > s.prev_created_at)
+          )
+          AND NOT EXISTS (
+            SELECT 1 FROM af_snapshot_state d
+            WHERE d.workspace_id = $1 AND d.deps_snapshot_id = s.snapshot_id
+          )
+        RETURNING octet_length(t.doc_state) AS size
+      )
+      SELECT COUNT(*), COALESCE(SUM(size), 0)::BIGINT FROM deleted
+    "#,
+  )
+  .bind(workspace_id)
+  .fetch_one(txn.deref_mut())
+  .await?;
+
+  sqlx::query(
+    r#"
+      INSERT INTO af_workspace_history_compaction (workspace_id, last_compacted_at)
+      VALUES ($1, CURRENT_TIMESTAMP)
+      ON CONFLICT (workspace_id) DO UPDATE
+      SET last_compacted_at = EXCLUDED.last_compacted_at
+    "#,
+  )
+  .bind(workspace_id)
+  .execute(txn.deref_mut())
+  .await?;
+  txn.commit().await?;
+
+  Ok(HistoryCompactionResult {
+    deleted_versions,
+    deleted_states,
+    reclaimed_bytes: version_bytes + state_bytes,
+  })
+}
@@ -1 +1,2 @@
+pub mod compaction;
 pub mod ops;
@@ -8,7 +8,7 @@ use pgvector::Vector;
 use sqlx::pool::PoolConnection;
 use sqlx::postgres::{PgHasArrayType, PgTypeInfo};
 use sqlx::{Error, Executor, Postgres, Transaction};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::DerefMut;
 use uuid::Uuid;
 
@@ -118,6 +118,28 @@ pub async fn upsert_collab_embeddings(
   Ok(())
 }
 
+/// Returns the ids of the fragments of the collabs that have an embedding. Fragment ids are
+/// derived from the content of the fragment, see `af_collab_embeddings_upsert`.
+pub async fn select_embedded_fragment_ids<'a, E>(
+  executor: E,
+  object_ids: &[String],
+) -> Result<HashSet<String>, Error>
+where
+  E: Executor<'a, Database = Postgres>,
+{
+  let fragment_ids = sqlx::query_scalar::<_, String>(
+    r#"
+      SELECT fragment_id
+      FROM af_collab_embeddings
+      WHERE oid = ANY($1) AND embedding IS NOT NULL
+    "#,
+  )
+  .bind(object_ids)
+  .fetch_all(executor)
+  .await?;
+  Ok(fragment_ids.into_iter().collect())
+}
+
 pub async fn stream_collabs_without_embeddings(
   conn: &mut PoolConnection<Postgres>,
   workspace_id: Uuid,
@@ -0,0 +1,105 @@
+use app_error::AppError;
+use chrono::{DateTime, Utc};
+use collab_entity::CollabType;
+use database_entity::dto::AFRole;
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::collab::partition_key_from_collab_type;
+use crate::pg_row::AFBlobStatus;
+use crate::workspace::ImportTaskState;
+
+/// The initialized workspaces that have no folder, which can't be opened.
+pub async fn select_workspaces_without_folder<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  limit: i64,
+) -> Result<Vec<Uuid>, AppError> {
+  let workspace_ids = sqlx::query_scalar::<_, Uuid>(
+    r#"
+      SELECT w.workspace_id FROM af_workspace w
+      WHERE COALESCE(w.is_initialized, true) = true
+        AND w.deleted_at IS NULL
+        AND NOT EXISTS (
+          SELECT 1 FROM af_collab c
+          WHERE c.oid = w.workspace_id::TEXT AND c.partition_key = $1 AND c.deleted_at IS NULL
+        )
+      ORDER BY w.created_at
+      LIMIT $2
+    "#,
+  )
+  .bind(partition_key_from_collab_type(&CollabType::Folder))
+  .bind(limit)
+  .fetch_all(executor)
+  .await?;
+  Ok(workspace_ids)
+}
+
+/// The workspaces whose owner is not one of their members with the owner role.
+pub async fn select_workspaces_without_owner_member<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  limit: i64,
+) -> Result<Vec<Uuid>, AppError> {
+  let workspace_ids = sqlx::query_scalar::<_, Uuid>(
+    r#"
+      SELECT w.workspace_id FROM af_workspace w
+      WHERE COALESCE(w.is_initialized, true) = true
+        AND w.deleted_at IS NULL
+        AND NOT EXISTS (
+          SELECT 1 FROM af_workspace_member m
+          WHERE m.workspace_id = w.workspace_id AND m.uid = w.owner_uid AND m.role_id = $1
+        )
+      ORDER BY w.created_at
+      LIMIT $2
+    "#,
+  )
+  .bind(i32::from(AFRole::Owner))
+  .bind(limit)
+  .fetch_all(executor)
+  .await?;
+  Ok(workspace_ids)
+}
+
+/// The import tasks still pending although they were created before `created_before`, which the
+/// worker lost track of.
+pub async fn select_stale_import_tasks<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  created_before: DateTime<Utc>,
+  limit: i64,
+) -> Result<Vec<Uuid>, AppError> {
+  let task_ids = sqlx::query_scalar::<_, Uuid>(
+    r#"
+      SELECT task_id FROM af_import_task
+      WHERE status = $1 AND created_at < $2
+      ORDER BY created_at
+      LIMIT $3
+    "#,
+  )
+  .bind(ImportTaskState::Pending as i16)
+  .bind(created_before)
+  .bind(limit)
+  .fetch_all(executor)
+  .await?;
+  Ok(task_ids)
+}
+
+/// The blobs still pending since before `created_before`, whose upload never completed.
+pub async fn select_incomplete_blobs<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  created_before: DateTime<Utc>,
+  limit: i64,
+) -> Result<Vec<(Uuid, String)>, AppError> {
+  let blobs = sqlx::query_as::<_, (Uuid, String)>(
+    r#"
+      SELECT workspace_id, file_id FROM af_blob_metadata
+      WHERE status = $1 AND modified_at < $2
+      ORDER BY modified_at
+      LIMIT $3
+    "#,
+  )
+  .bind(AFBlobStatus::Pending as i16)
+  .bind(created_before)
+  .bind(limit)
+  .fetch_all(executor)
+  .await?;
+  Ok(blobs)
+}
@@ -1,14 +1,32 @@
 pub mod access_request;
+pub mod admin_audit;
+pub mod auth_audit;
+pub mod billing;
 pub mod chat;
 pub mod collab;
+pub mod collab_guest;
+pub mod database_form;
+pub mod export;
+pub mod feature_flag;
 pub mod file;
 pub mod history;
 pub mod index;
+pub mod integrity;
 pub mod listener;
+pub mod notification_preferences;
+pub mod organization;
 pub mod pg_row;
 pub mod publish;
+pub mod publish_analytics;
+pub mod publish_report;
+pub mod push_device;
 pub mod quick_note;
 pub mod resource_usage;
+pub mod restricted_view;
+pub mod retention;
 pub mod template;
 pub mod user;
 pub mod workspace;
+pub mod workspace_clone;
+pub mod workspace_delete;
+pub mod workspace_storage;
@@ -0,0 +1,110 @@
+use app_error::AppError;
+use shared_entity::dto::notification_dto::{
+  NotificationPreferences, UpdateNotificationPreferencesParams,
+};
+use sqlx::{Executor, FromRow, Postgres};
+use uuid::Uuid;
+
+#[derive(Debug, FromRow)]
+struct AFNotificationPreferencesRow {
+  workspace_id: Option<Uuid>,
+  email_on_mention: Option<bool>,
+  email_on_comment: Option<bool>,
+  email_on_import_completion: Option<bool>,
+  digest_frequency: Option<i16>,
+}
+
+impl AFNotificationPreferencesRow {
+  fn apply_to(self, preferences: &mut NotificationPreferences) {
+    if let Some(value) = self.email_on_mention {
+      preferences.email_on_mention = value;
+    }
+    if let Some(value) = self.email_on_comment {
+      preferences.email_on_comment = value;
+    }
+    if let Some(value) = self.email_on_import_completion {
+      preferences.email_on_import_completion = value;
+    }
+    if let Some(value) = self.digest_frequency {
+      preferences.digest_frequency = value.into();
+    }
+  }
+}
+
+/// Returns the preferences of the user, with the ones set for the workspace applied when a
+/// workspace is given.
+pub async fn select_notification_preferences<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  uid: i64,
+  workspace_id: Option<&Uuid>,
+) -> Result<NotificationPreferences, AppError> {
+  let mut rows = sqlx::query_as::<_, AFNotificationPreferencesRow>(
+    r#"
+      SELECT workspace_id, email_on_mention, email_on_comment, email_on_import_completion,
+        digest_frequency
+      FROM af_notification_preferences
+      WHERE uid = $1 AND (workspace_id IS NULL OR workspace_id = $2)
+    "#,
+  )
+  .bind(uid)
+  .bind(workspace_id)
+  .fetch_all(executor)
+  .await?;
+  // the preferences of the user first, the ones of the workspace override them
+  rows.sort_by_key(|row| row.workspace_id.is_some());
+
+  let mut preferences = NotificationPreferences::default();
+  for row in rows {
+    row.apply_to(&mut preferences);
+  }
+  Ok(preferences)
+}
+
+/// Sets the given preferences of the user, or of the user in the workspace when a workspace is
+/// given.
+pub async fn upsert_notification_preferences<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  uid: i64,
+  workspace_id: Option<&Uuid>,
+  params: &UpdateNotificationPreferencesParams,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      INSERT INTO af_notification_preferences
+        (uid, workspace_id, email_on_mention, email_on_comment, email_on_import_completion,
+          digest_frequency)
+      VALUES ($1, $2, $3, $4, $5, $6)
+      ON CONFLICT (uid, COALESCE(workspace_id, '00000000-0000-0000-0000-000000000000'::uuid))
+      DO UPDATE SET
+        email_on_mention = COALESCE(EXCLUDED.email_on_mention, af_notification_preferences.email_on_mention),
+        email_on_comment = COALESCE(EXCLUDED.email_on_comment, af_notification_preferences.email_on_comment),
+        email_on_import_completion = COALESCE(EXCLUDED.email_on_import_completion,
+          af_notification_preferences.email_on_import_completion),
+        digest_frequency = COALESCE(EXCLUDED.digest_frequency, af_notification_preferences.digest_frequency),
+        updated_at = CURRENT_TIMESTAMP
+    "#,
+  )
+  .bind(uid)
+  .bind(workspace_id)
+  .bind(params.email_on_mention)
+  .bind(params.email_on_comment)
+  .bind(params.email_on_import_completion)
+  .bind(params.digest_frequency.map(|frequency| frequency.as_i16()))
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+/// Removes the preferences set for the workspace, the ones of the user apply again.
+pub async fn delete_workspace_notification_preferences<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  uid: i64,
+  workspace_id: &Uuid,
+) -> Result<(), AppError> {
+  sqlx::query("DELETE FROM af_notification_preferences WHERE uid = $1 AND workspace_id = $2")
+    .bind(uid)
+    .bind(workspace_id)
+    .execute(executor)
+    .await?;
+  Ok(())
+}
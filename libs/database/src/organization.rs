@@ -0,0 +1,335 @@
+use app_error::AppError;
+use shared_entity::dto::org_dto::{OrgRole, Organization};
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::pg_row::{AFOrganizationMemberRow, AFOrganizationRow, AFOrganizationWorkspaceUsageRow};
+
+pub async fn insert_organization<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  name: &str,
+  billing_email: Option<&str>,
+  owner_uid: i64,
+) -> Result<Uuid, AppError> {
+  let org_id = sqlx::query_scalar::<_, Uuid>(
+    r#"
+      INSERT INTO af_organization (name, billing_email, owner_uid)
+      VALUES ($1, $2, $3)
+      RETURNING org_id
+    "#,
+  )
+  .bind(name)
+  .bind(billing_email)
+  .bind(owner_uid)
+  .fetch_one(executor)
+  .await?;
+  Ok(org_id)
+}
+
+/// Adds the user to the organization, or changes their role if they already belong to it.
+pub async fn upsert_organization_member<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  org_id: &Uuid,
+  uid: i64,
+  role: OrgRole,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      INSERT INTO af_organization_member (org_id, uid, role)
+      VALUES ($1, $2, $3)
+      ON CONFLICT (org_id, uid) DO UPDATE SET role = EXCLUDED.role
+    "#,
+  )
+  .bind(org_id)
+  .bind(uid)
+  .bind(role as i16)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+/// Removes the users from the organization, except its owner. Returns the number of removed users.
+pub async fn delete_organization_members<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  org_id: &Uuid,
+  emails: &[String],
+) -> Result<u64, AppError> {
+  let res = sqlx::query(
+    r#"
+      DELETE FROM af_organization_member om
+      USING af_user u
+      WHERE om.org_id = $1
+        AND om.uid = u.uid
+        AND u.email = ANY($2)
+        AND om.uid <> (SELECT owner_uid FROM af_organization WHERE org_id = $1)
+    "#,
+  )
+  .bind(org_id)
+  .bind(emails)
+  .execute(executor)
+  .await?;
+  Ok(res.rows_affected())
+}
+
+/// Returns the role of the user in the organization, None if they don't belong to it.
+pub async fn select_organization_role<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  org_id: &Uuid,
+  uid: i64,
+) -> Result<Option<OrgRole>, AppError> {
+  let role = sqlx::query_scalar::<_, i16>(
+    r#"
+      SELECT role FROM af_organization_member
+      WHERE org_id = $1 AND uid = $2
+    "#,
+  )
+  .bind(org_id)
+  .bind(uid)
+  .fetch_optional(executor)
+  .await?;
+  Ok(role.map(OrgRole::from))
+}
+
+/// Returns the organizations the user belongs to, with the role of the user in each.
+pub async fn select_organizations_of_user<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  uid: i64,
+) -> Result<Vec<AFOrganizationRow>, AppError> {
+  let rows = sqlx::query_as::<_, AFOrganizationRow>(
+    r#"
+      SELECT o.org_id, o.name, o.billing_email, owner.uuid AS owner_uuid, om.role,
+        (SELECT COUNT(*) FROM af_workspace w WHERE w.org_id = o.org_id) AS workspace_count,
+        o.max_sessions_per_user, o.created_at
+      FROM af_organization_member om
+      JOIN af_organization o ON o.org_id = om.org_id
+      JOIN af_user owner ON owner.uid = o.owner_uid
+      WHERE om.uid = $1
+      ORDER BY o.created_at
+    "#,
+  )
+  .bind(uid)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows)
+}
+
+pub async fn select_organization_of_user<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  org_id: &Uuid,
+  uid: i64,
+) -> Result<Option<AFOrganizationRow>, AppError> {
+  let row = sqlx::query_as::<_, AFOrganizationRow>(
+    r#"
+      SELECT o.org_id, o.name, o.billing_email, owner.uuid AS owner_uuid, om.role,
+        (SELECT COUNT(*) FROM af_workspace w WHERE w.org_id = o.org_id) AS workspace_count,
+        o.max_sessions_per_user, o.created_at
+      FROM af_organization_member om
+      JOIN af_organization o ON o.org_id = om.org_id
+      JOIN af_user owner ON owner.uid = o.owner_uid
+      WHERE om.org_id = $1 AND om.uid = $2
+    "#,
+  )
+  .bind(org_id)
+  .bind(uid)
+  .fetch_optional(executor)
+  .await?;
+  Ok(row)
+}
+
+/// Moves the workspace to the organization. Returns false if the workspace belongs to another
+/// organization.
+pub async fn update_workspace_organization<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  org_id: &Uuid,
+) -> Result<bool, AppError> {
+  let res = sqlx::query(
+    r#"
+      UPDATE af_workspace
+      SET org_id = $2
+      WHERE workspace_id = $1 AND (org_id IS NULL OR org_id = $2)
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(org_id)
+  .execute(executor)
+  .await?;
+  Ok(res.rows_affected() > 0)
+}
+
+/// Returns false if the workspace doesn't belong to the organization.
+pub async fn delete_workspace_organization<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  org_id: &Uuid,
+) -> Result<bool, AppError> {
+  let res = sqlx::query(
+    r#"
+      UPDATE af_workspace
+      SET org_id = NULL
+      WHERE workspace_id = $1 AND org_id = $2
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(org_id)
+  .execute(executor)
+  .await?;
+  Ok(res.rows_affected() > 0)
+}
+
+pub async fn select_organization_workspace_ids<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  org_id: &Uuid,
+) -> Result<Vec<Uuid>, AppError> {
+  let workspace_ids = sqlx::query_scalar::<_, Uuid>(
+    r#"
+      SELECT workspace_id FROM af_workspace
+      WHERE org_id = $1
+    "#,
+  )
+  .bind(org_id)
+  .fetch_all(executor)
+  .await?;
+  Ok(workspace_ids)
+}
+
+/// Returns the members of the organization and the members of its workspaces, one row for each
+/// workspace of the organization the user belongs to, ordered by email.
+pub async fn select_organization_members<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  org_id: &Uuid,
+) -> Result<Vec<AFOrganizationMemberRow>, AppError> {
+  let rows = sqlx::query_as::<_, AFOrganizationMemberRow>(
+    r#"
+      WITH org_workspace AS (
+        SELECT workspace_id FROM af_workspace WHERE org_id = $1
+      ), org_user AS (
+        SELECT uid FROM af_organization_member WHERE org_id = $1
+        UNION
+        SELECT wm.uid FROM af_workspace_member wm JOIN org_workspace USING (workspace_id)
+      )
+      SELECT u.uuid, u.email, u.name, om.role AS org_role, wm.workspace_id,
+        wm.role_id AS workspace_role
+      FROM org_user
+      JOIN af_user u ON u.uid = org_user.uid
+      LEFT JOIN af_organization_member om ON om.org_id = $1 AND om.uid = u.uid
+      LEFT JOIN af_workspace_member wm ON wm.uid = u.uid
+        AND wm.workspace_id IN (SELECT workspace_id FROM org_workspace)
+      ORDER BY u.email, wm.workspace_id
+    "#,
+  )
+  .bind(org_id)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows)
+}
+
+pub async fn select_organization_workspace_usage<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  org_id: &Uuid,
+) -> Result<Vec<AFOrganizationWorkspaceUsageRow>, AppError> {
+  let rows = sqlx::query_as::<_, AFOrganizationWorkspaceUsageRow>(
+    r#"
+      SELECT w.workspace_id, COALESCE(w.workspace_name, '') AS workspace_name,
+        (SELECT COUNT(*) FROM af_workspace_member wm
+          WHERE wm.workspace_id = w.workspace_id) AS member_count,
+        (SELECT COALESCE(SUM(len), 0)::BIGINT FROM af_collab c
+          WHERE c.workspace_id = w.workspace_id) AS total_document_size,
+        (SELECT COALESCE(SUM(file_size), 0)::BIGINT FROM af_blob_metadata b
+          WHERE b.workspace_id = w.workspace_id) AS total_file_size
+      FROM af_workspace w
+      WHERE w.org_id = $1
+      ORDER BY w.workspace_id
+    "#,
+  )
+  .bind(org_id)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows)
+}
+
+/// Number of distinct users who are members of at least one workspace of the organization.
+pub async fn select_organization_workspace_member_count<
+  'a,
+  E: Executor<'a, Database = Postgres>,
+>(
+  executor: E,
+  org_id: &Uuid,
+) -> Result<i64, AppError> {
+  let count = sqlx::query_scalar::<_, i64>(
+    r#"
+      SELECT COUNT(DISTINCT wm.uid)
+      FROM af_workspace_member wm
+      JOIN af_workspace w ON w.workspace_id = wm.workspace_id
+      WHERE w.org_id = $1
+    "#,
+  )
+  .bind(org_id)
+  .fetch_one(executor)
+  .await?;
+  Ok(count)
+}
+
+pub async fn update_organization_max_sessions_per_user<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  org_id: &Uuid,
+  max_sessions_per_user: Option<i32>,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      UPDATE af_organization
+      SET max_sessions_per_user = $2
+      WHERE org_id = $1
+    "#,
+  )
+  .bind(org_id)
+  .bind(max_sessions_per_user)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+/// Returns the strictest session limit of the organizations the user belongs to, directly or
+/// through one of their workspaces. None if none of them sets a limit.
+pub async fn select_max_sessions_per_user<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  uid: i64,
+) -> Result<Option<i32>, AppError> {
+  let max_sessions = sqlx::query_scalar::<_, Option<i32>>(
+    r#"
+      SELECT MIN(o.max_sessions_per_user)
+      FROM af_organization o
+      WHERE o.max_sessions_per_user IS NOT NULL
+        AND (
+          EXISTS (
+            SELECT 1 FROM af_organization_member om
+            WHERE om.org_id = o.org_id AND om.uid = $1
+          )
+          OR EXISTS (
+            SELECT 1 FROM af_workspace w
+            JOIN af_workspace_member wm ON wm.workspace_id = w.workspace_id
+            WHERE w.org_id = o.org_id AND wm.uid = $1
+          )
+        )
+    "#,
+  )
+  .bind(uid)
+  .fetch_one(executor)
+  .await?;
+  Ok(max_sessions)
+}
+
+impl From<AFOrganizationRow> for Organization {
+  fn from(row: AFOrganizationRow) -> Self {
+    Organization {
+      org_id: row.org_id,
+      name: row.name,
+      billing_email: row.billing_email,
+      owner_uuid: row.owner_uuid,
+      role: row.role.into(),
+      workspace_count: row.workspace_count,
+      max_sessions_per_user: row.max_sessions_per_user,
+      created_at: row.created_at,
+    }
+  }
+}
@@ -3,9 +3,9 @@ use app_error::AppError;
 use chrono::{DateTime, Utc};
 
 use database_entity::dto::{
-  AFAccessLevel, AFRole, AFUserProfile, AFWebUser, AFWorkspace, AFWorkspaceInvitationStatus,
-  AccessRequestMinimal, AccessRequestStatus, AccessRequestWithViewId, AccessRequesterInfo,
-  AccountLink, GlobalComment, QuickNote, Reaction, Template, TemplateCategory,
+  AFAccessLevel, AFCollabCheckpoint, AFCollabLock, AFRole, AFUserProfile, AFWebUser, AFWorkspace,
+  AFWorkspaceInvitationStatus, AccessRequestMinimal, AccessRequestStatus, AccessRequestWithViewId,
+  AccessRequesterInfo, AccountLink, GlobalComment, QuickNote, Reaction, Template, TemplateCategory,
   TemplateCategoryMinimal, TemplateCategoryType, TemplateCreator, TemplateCreatorMinimal,
   TemplateGroup, TemplateMinimal,
 };
@@ -55,6 +55,7 @@ impl TryFrom<AFWorkspaceRow> for AFWorkspace {
       icon,
       member_count: None,
       role: None,
+      is_collab_guest: false,
     })
   }
 }
@@ -101,6 +102,7 @@ impl TryFrom<AFWorkspaceWithMemberCountRow> for AFWorkspace {
       icon,
       member_count: Some(value.member_count),
       role: None,
+      is_collab_guest: false,
     })
   }
 }
@@ -205,6 +207,9 @@ pub enum AFBlobStatus {
   PolicyViolation = 1,
   Failed = 2,
   Pending = 3,
+  /// Uploaded by a user that is not a member of the workspace, can't be downloaded until a member
+  /// approves it.
+  Quarantined = 4,
 }
 
 impl From<i16> for AFBlobStatus {
@@ -214,6 +219,7 @@ impl From<i16> for AFBlobStatus {
       1 => AFBlobStatus::PolicyViolation,
       2 => AFBlobStatus::Failed,
       3 => AFBlobStatus::Pending,
+      4 => AFBlobStatus::Quarantined,
       _ => AFBlobStatus::Ok,
     }
   }
@@ -249,6 +255,19 @@ pub struct AFBlobMetadataRow {
   pub source: i16,
   #[serde(default)]
   pub source_metadata: serde_json::Value,
+  #[serde(default)]
+  pub uploaded_by: Option<i64>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct AFWorkspaceFileRow {
+  pub file_id: String,
+  pub file_type: String,
+  pub file_size: i64,
+  pub modified_at: DateTime<Utc>,
+  pub status: i16,
+  pub uploaded_by: Option<Uuid>,
+  pub uploader_name: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -256,6 +275,32 @@ pub struct AFUserNotification {
   pub payload: Option<AFUserRow>,
 }
 
+/// Payload sent by the af_workspace_member_change_trigger.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AFWorkspaceMemberNotification {
+  pub old: Option<AFWorkspaceMemberChangeRow>,
+  pub new: Option<AFWorkspaceMemberChangeRow>,
+  pub action_type: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AFWorkspaceMemberChangeRow {
+  pub uid: i64,
+  pub role_id: i32,
+  pub workspace_id: Uuid,
+}
+
+impl AFWorkspaceMemberNotification {
+  /// Returns the member removed from the workspace, if the notification is about a removal.
+  pub fn removed_member(&self) -> Option<&AFWorkspaceMemberChangeRow> {
+    if self.action_type == "DELETE" {
+      self.old.as_ref()
+    } else {
+      None
+    }
+  }
+}
+
 #[derive(FromRow, Debug, Clone)]
 pub struct AFPermissionRow {
   pub id: i32,
@@ -274,6 +319,174 @@ pub struct AFSnapshotRow {
   pub deleted_at: Option<DateTime<Utc>>,
   pub created_at: DateTime<Utc>,
   pub workspace_id: Uuid,
+  /// Key of the object holding the blob when it has been offloaded to the object storage, in
+  /// which case `blob` is empty.
+  pub storage_key: Option<String>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct AFCollabCheckpointRow {
+  pub checkpoint_id: i64,
+  pub oid: String,
+  pub label: String,
+  pub created_by: Option<i64>,
+  pub created_at: DateTime<Utc>,
+}
+
+impl From<AFCollabCheckpointRow> for AFCollabCheckpoint {
+  fn from(value: AFCollabCheckpointRow) -> Self {
+    Self {
+      checkpoint_id: value.checkpoint_id,
+      object_id: value.oid,
+      label: value.label,
+      created_by: value.created_by,
+      created_at: value.created_at,
+    }
+  }
+}
+
+#[derive(Debug, FromRow)]
+pub struct AFExportTaskRow {
+  pub task_id: Uuid,
+  pub workspace_id: Uuid,
+  pub oid: String,
+  pub uid: i64,
+  pub format: String,
+  pub status: i16,
+  pub file_key: Option<String>,
+  pub error: Option<String>,
+  pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct AFChatAttachmentRow {
+  pub attachment_id: Uuid,
+  pub chat_id: Uuid,
+  pub workspace_id: Uuid,
+  pub uid: i64,
+  pub file_name: String,
+  pub content_type: String,
+  pub file_size: i64,
+  pub file_key: String,
+  pub status: i16,
+  pub error: Option<String>,
+  pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct AFWorkspaceRetentionPolicyRow {
+  pub workspace_id: Uuid,
+  pub chat_history_days: Option<i32>,
+  pub snapshot_days: Option<i32>,
+  pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct AFWorkspaceHistoryCompactionRow {
+  pub workspace_id: Uuid,
+  pub enabled: bool,
+  pub hourly_days: i32,
+  pub daily_days: i32,
+  pub last_compacted_at: Option<DateTime<Utc>>,
+  pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct AFAdminAuditLogRow {
+  pub id: i64,
+  pub actor_uuid: Uuid,
+  pub action: String,
+  pub target: String,
+  pub succeeded: bool,
+  pub detail: Option<String>,
+  pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct AFPublishedViewReportRow {
+  pub report_id: Uuid,
+  pub view_id: Uuid,
+  pub workspace_id: Uuid,
+  pub reporter_uuid: Option<Uuid>,
+  pub reason: String,
+  pub details: Option<String>,
+  pub status: i16,
+  pub reviewed_by: Option<Uuid>,
+  pub reviewed_at: Option<DateTime<Utc>>,
+  pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct AFUserWithWorkspaceCountRow {
+  pub uid: i64,
+  pub uuid: Uuid,
+  pub email: String,
+  pub name: String,
+  pub created_at: Option<DateTime<Utc>>,
+  pub owned_workspace_count: i64,
+  pub member_workspace_count: i64,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct AFFeatureFlagRow {
+  pub name: String,
+  pub enabled: bool,
+  pub rollout_percentage: i16,
+  pub description: String,
+  pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct AFChatMessageFeedbackRow {
+  pub message_id: i64,
+  pub uid: i64,
+  pub chat_id: Uuid,
+  pub workspace_id: Uuid,
+  pub rating: i16,
+  pub comment: Option<String>,
+  /// Content of the rated answer.
+  pub answer: String,
+  pub created_at: DateTime<Utc>,
+  pub updated_at: DateTime<Utc>,
+}
+
+/// A published page listed in the sitemap and the feeds of the publish namespace.
+#[derive(Debug, FromRow)]
+pub struct AFPublishedFeedItemRow {
+  pub view_id: Uuid,
+  pub publish_name: String,
+  pub title: Option<String>,
+  pub created_at: DateTime<Utc>,
+  pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct AFWorkspaceCustomDomainRow {
+  pub domain: String,
+  pub workspace_id: Uuid,
+  pub verification_token: String,
+  pub verified_at: Option<DateTime<Utc>>,
+  pub checked_at: Option<DateTime<Utc>>,
+  pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct AFCollabLockRow {
+  pub oid: String,
+  pub locked_by: i64,
+  pub reason: Option<String>,
+  pub locked_at: DateTime<Utc>,
+}
+
+impl From<AFCollabLockRow> for AFCollabLock {
+  fn from(value: AFCollabLockRow) -> Self {
+    Self {
+      object_id: value.oid,
+      locked_by: value.locked_by,
+      reason: value.reason,
+      locked_at: value.locked_at,
+    }
+  }
 }
 
 #[derive(Debug, FromRow, Deserialize, Serialize)]
@@ -720,3 +933,113 @@ pub struct AFPublishViewWithPublishInfo {
   pub comments_enabled: bool,
   pub duplicate_enabled: bool,
 }
+
+/// A collab moved to a cheaper storage class after a long inactivity.
+#[derive(Debug, FromRow)]
+pub struct AFCollabArchiveRow {
+  pub oid: String,
+  pub partition_key: i32,
+  pub workspace_id: Uuid,
+  pub archive_key: String,
+  pub in_place: bool,
+  pub storage_class: String,
+  pub archived_len: i64,
+  pub archived_at: DateTime<Utc>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct AFWorkspaceCloneTaskRow {
+  pub task_id: Uuid,
+  pub source_workspace_id: Uuid,
+  pub target_workspace_id: Uuid,
+  pub uid: i64,
+  pub include_members: bool,
+  pub status: i16,
+  pub total_collabs: i32,
+  pub cloned_collabs: i32,
+  pub total_blobs: i32,
+  pub cloned_blobs: i32,
+  pub error: Option<String>,
+  pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct AFWorkspaceDeleteTaskRow {
+  pub task_id: Uuid,
+  pub workspace_id: Uuid,
+  pub uid: i64,
+  pub status: i16,
+  pub deleted_collabs: i32,
+  pub deleted_snapshots: i32,
+  pub error: Option<String>,
+  pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct AFWorkspaceStorageRow {
+  pub workspace_id: Uuid,
+  pub settings: Option<Vec<u8>>,
+  pub target_settings: Option<Vec<u8>>,
+  pub previous_settings: Option<Vec<u8>>,
+  pub relocation_status: i16,
+  pub relocation_error: Option<String>,
+  pub relocated_objects: i64,
+  pub switched_at: Option<DateTime<Utc>>,
+  pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct AFOrganizationRow {
+  pub org_id: Uuid,
+  pub name: String,
+  pub billing_email: Option<String>,
+  pub owner_uuid: Uuid,
+  pub role: i16,
+  pub workspace_count: i64,
+  pub max_sessions_per_user: Option<i32>,
+  pub created_at: DateTime<Utc>,
+}
+
+/// A user of the organization with one of the workspaces of the organization they belong to.
+#[derive(Debug, Clone, FromRow)]
+pub struct AFOrganizationMemberRow {
+  pub uuid: Uuid,
+  pub email: String,
+  pub name: String,
+  pub org_role: Option<i16>,
+  pub workspace_id: Option<Uuid>,
+  pub workspace_role: Option<i32>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct AFOrganizationWorkspaceUsageRow {
+  pub workspace_id: Uuid,
+  pub workspace_name: String,
+  pub member_count: i64,
+  pub total_document_size: i64,
+  pub total_file_size: i64,
+}
+
+/// A subscription held by a workspace or by an organization, see [crate::billing].
+#[derive(Debug, Clone, FromRow)]
+pub struct AFSubscriptionRow {
+  pub subscription_id: String,
+  pub customer_id: String,
+  pub workspace_id: Option<Uuid>,
+  pub org_id: Option<Uuid>,
+  pub plan: i16,
+  pub status: String,
+  pub recurring_interval: i16,
+  pub quantity: i32,
+  pub current_period_end: DateTime<Utc>,
+  pub cancel_at: Option<DateTime<Utc>>,
+  pub event_created_at: DateTime<Utc>,
+}
+
+/// A workspace over the limits of its plan, see [crate::billing].
+#[derive(Debug, Clone, FromRow)]
+pub struct AFWorkspaceReadOnlyRow {
+  pub workspace_id: Uuid,
+  pub reason: String,
+  pub since: DateTime<Utc>,
+}
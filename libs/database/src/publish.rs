@@ -1,11 +1,14 @@
 use app_error::AppError;
+use chrono::{DateTime, Utc};
 use database_entity::dto::{
   PatchPublishedCollab, PublishCollabItem, PublishCollabKey, PublishInfo, WorkspaceNamespace,
 };
 use sqlx::{Executor, PgPool, Postgres, QueryBuilder};
 use uuid::Uuid;
 
-use crate::pg_row::AFPublishViewWithPublishInfo;
+use crate::pg_row::{
+  AFPublishViewWithPublishInfo, AFPublishedFeedItemRow, AFWorkspaceCustomDomainRow,
+};
 
 pub async fn select_user_is_collab_publisher_for_all_views(
   pg_pool: &PgPool,
@@ -734,3 +737,235 @@ pub async fn select_published_view_ids_with_publish_info_for_workspace<
 
   Ok(res)
 }
+
+pub async fn insert_workspace_custom_domain<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  domain: &str,
+  verification_token: &str,
+) -> Result<AFWorkspaceCustomDomainRow, AppError> {
+  let row = sqlx::query_as::<_, AFWorkspaceCustomDomainRow>(
+    r#"
+      INSERT INTO af_workspace_custom_domain (domain, workspace_id, verification_token)
+      VALUES ($1, $2, $3)
+      RETURNING domain, workspace_id, verification_token, verified_at, checked_at, created_at
+    "#,
+  )
+  .bind(domain)
+  .bind(workspace_id)
+  .bind(verification_token)
+  .fetch_one(executor)
+  .await?;
+  Ok(row)
+}
+
+pub async fn select_workspace_custom_domains<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+) -> Result<Vec<AFWorkspaceCustomDomainRow>, AppError> {
+  let rows = sqlx::query_as::<_, AFWorkspaceCustomDomainRow>(
+    r#"
+      SELECT domain, workspace_id, verification_token, verified_at, checked_at, created_at
+      FROM af_workspace_custom_domain
+      WHERE workspace_id = $1
+      ORDER BY created_at
+    "#,
+  )
+  .bind(workspace_id)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows)
+}
+
+/// Returns the claim of the workspace on the domain, verified or not.
+pub async fn select_workspace_custom_domain<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  domain: &str,
+) -> Result<Option<AFWorkspaceCustomDomainRow>, AppError> {
+  let row = sqlx::query_as::<_, AFWorkspaceCustomDomainRow>(
+    r#"
+      SELECT domain, workspace_id, verification_token, verified_at, checked_at, created_at
+      FROM af_workspace_custom_domain
+      WHERE workspace_id = $1 AND domain = $2
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(domain)
+  .fetch_optional(executor)
+  .await?;
+  Ok(row)
+}
+
+/// Marks the claim of the workspace as verified. Fails when another workspace has the domain
+/// verified already.
+pub async fn update_custom_domain_verified<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  domain: &str,
+) -> Result<AFWorkspaceCustomDomainRow, AppError> {
+  let row = sqlx::query_as::<_, AFWorkspaceCustomDomainRow>(
+    r#"
+      UPDATE af_workspace_custom_domain
+      SET verified_at = COALESCE(verified_at, NOW()), checked_at = NOW()
+      WHERE workspace_id = $1 AND domain = $2
+      RETURNING domain, workspace_id, verification_token, verified_at, checked_at, created_at
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(domain)
+  .fetch_one(executor)
+  .await?;
+  Ok(row)
+}
+
+/// Removes the claims of the other workspaces on a domain that has just been verified.
+pub async fn delete_pending_custom_domain_claims<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  domain: &str,
+) -> Result<u64, AppError> {
+  let res = sqlx::query(
+    r#"
+      DELETE FROM af_workspace_custom_domain
+      WHERE domain = $1 AND verified_at IS NULL
+    "#,
+  )
+  .bind(domain)
+  .execute(executor)
+  .await?;
+  Ok(res.rows_affected())
+}
+
+/// Removes the claims that haven't been verified since `created_before`.
+pub async fn delete_expired_custom_domain_claims<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  created_before: DateTime<Utc>,
+) -> Result<u64, AppError> {
+  let res = sqlx::query(
+    r#"
+      DELETE FROM af_workspace_custom_domain
+      WHERE verified_at IS NULL AND created_at < $1
+    "#,
+  )
+  .bind(created_before)
+  .execute(executor)
+  .await?;
+  Ok(res.rows_affected())
+}
+
+/// Returns the verified domains whose TXT record hasn't been checked since `checked_before`.
+pub async fn select_custom_domains_to_recheck<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  checked_before: DateTime<Utc>,
+  limit: i64,
+) -> Result<Vec<AFWorkspaceCustomDomainRow>, AppError> {
+  let rows = sqlx::query_as::<_, AFWorkspaceCustomDomainRow>(
+    r#"
+      SELECT domain, workspace_id, verification_token, verified_at, checked_at, created_at
+      FROM af_workspace_custom_domain
+      WHERE verified_at IS NOT NULL AND COALESCE(checked_at, verified_at) < $1
+      ORDER BY COALESCE(checked_at, verified_at)
+      LIMIT $2
+    "#,
+  )
+  .bind(checked_before)
+  .bind(limit)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows)
+}
+
+/// Records the result of a check of the TXT record of a verified domain. The domain is no longer
+/// verified, and no longer served, when the record is gone. It becomes a pending claim again,
+/// which expires unless the record is restored and the domain verified again.
+pub async fn update_custom_domain_checked<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  domain: &str,
+  record_found: bool,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      UPDATE af_workspace_custom_domain
+      SET checked_at = NOW(),
+          verified_at = CASE WHEN $3 THEN verified_at ELSE NULL END,
+          created_at = CASE WHEN $3 THEN created_at ELSE NOW() END
+      WHERE workspace_id = $1 AND domain = $2
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(domain)
+  .bind(record_found)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+pub async fn delete_workspace_custom_domain<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  domain: &str,
+) -> Result<bool, AppError> {
+  let res = sqlx::query(
+    r#"
+      DELETE FROM af_workspace_custom_domain
+      WHERE workspace_id = $1 AND domain = $2
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(domain)
+  .execute(executor)
+  .await?;
+  Ok(res.rows_affected() > 0)
+}
+
+/// Returns the workspace of a verified custom domain.
+pub async fn select_workspace_id_for_verified_domain<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  domain: &str,
+) -> Result<Option<Uuid>, AppError> {
+  let workspace_id = sqlx::query_scalar::<_, Uuid>(
+    r#"
+      SELECT workspace_id
+      FROM af_workspace_custom_domain
+      WHERE domain = $1 AND verified_at IS NOT NULL
+    "#,
+  )
+  .bind(domain)
+  .fetch_optional(executor)
+  .await?;
+  Ok(workspace_id)
+}
+
+/// Object key of the sitemap and feeds generated by the worker for the published pages of a
+/// workspace, `file_name` being one of `sitemap.xml`, `rss.xml` or `atom.xml`.
+pub fn published_feed_object_key(workspace_id: &Uuid, file_name: &str) -> String {
+  format!("{}{}", published_feed_dir(workspace_id), file_name)
+}
+
+/// Prefix of the object keys of the sitemap and feeds of a workspace.
+pub fn published_feed_dir(workspace_id: &Uuid) -> String {
+  format!("published_feed/{}/", workspace_id)
+}
+
+/// Returns the published pages of the workspace, most recently updated first.
+pub async fn select_published_feed_items<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  limit: i64,
+) -> Result<Vec<AFPublishedFeedItemRow>, AppError> {
+  let rows = sqlx::query_as::<_, AFPublishedFeedItemRow>(
+    r#"
+      SELECT view_id, publish_name, metadata->'view'->>'name' AS title, created_at, updated_at
+      FROM af_published_collab
+      WHERE workspace_id = $1 AND unpublished_at IS NULL
+      ORDER BY updated_at DESC
+      LIMIT $2
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(limit)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows)
+}
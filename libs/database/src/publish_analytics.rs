@@ -0,0 +1,184 @@
+use app_error::AppError;
+use chrono::{DateTime, NaiveDate, Utc};
+use database_entity::dto::{PublishDailyViews, PublishViewsBucket};
+use sqlx::{Executor, FromRow, Postgres};
+use uuid::Uuid;
+
+/// A view of a published page, identified by its publish namespace and name.
+#[derive(Debug, Clone)]
+pub struct PublishedViewEvent {
+  pub publish_namespace: String,
+  pub publish_name: String,
+  pub referrer: Option<String>,
+  pub country: Option<String>,
+  pub created_at: DateTime<Utc>,
+}
+
+/// Creates the monthly partition of the view events containing `month`, if it doesn't exist yet.
+pub async fn create_published_view_event_partition<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  month: NaiveDate,
+) -> Result<(), AppError> {
+  sqlx::query("SELECT af_create_published_view_event_partition($1)")
+    .bind(month)
+    .execute(executor)
+    .await?;
+  Ok(())
+}
+
+/// Inserts the events of the pages that are still published, the others are dropped.
+pub async fn insert_published_view_events<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  events: &[PublishedViewEvent],
+) -> Result<u64, AppError> {
+  let mut namespaces = Vec::with_capacity(events.len());
+  let mut names = Vec::with_capacity(events.len());
+  let mut referrers = Vec::with_capacity(events.len());
+  let mut countries = Vec::with_capacity(events.len());
+  let mut created_ats = Vec::with_capacity(events.len());
+  for event in events {
+    namespaces.push(event.publish_namespace.as_str());
+    names.push(event.publish_name.as_str());
+    referrers.push(event.referrer.as_deref());
+    countries.push(event.country.as_deref());
+    created_ats.push(event.created_at);
+  }
+
+  let res = sqlx::query(
+    r#"
+      INSERT INTO af_published_view_event (workspace_id, view_id, referrer, country, created_at)
+      SELECT apc.workspace_id, apc.view_id, e.referrer, e.country, e.created_at
+      FROM UNNEST($1::TEXT[], $2::TEXT[], $3::TEXT[], $4::TEXT[], $5::TIMESTAMPTZ[])
+        AS e(namespace, publish_name, referrer, country, created_at)
+      JOIN af_workspace_namespace awn ON awn.namespace = e.namespace
+      JOIN af_published_collab apc
+        ON apc.workspace_id = awn.workspace_id
+        AND apc.publish_name = e.publish_name
+        AND apc.unpublished_at IS NULL
+    "#,
+  )
+  .bind(&namespaces)
+  .bind(&names)
+  .bind(&referrers)
+  .bind(&countries)
+  .bind(&created_ats)
+  .execute(executor)
+  .await?;
+  Ok(res.rows_affected())
+}
+
+#[derive(FromRow)]
+struct DailyViewsRow {
+  date: NaiveDate,
+  view_id: Uuid,
+  views: i64,
+}
+
+/// Number of views per day and page between `from` and `to`, both inclusive.
+pub async fn select_published_view_daily_counts<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  from: NaiveDate,
+  to: NaiveDate,
+  view_id: Option<&Uuid>,
+) -> Result<Vec<PublishDailyViews>, AppError> {
+  let rows = sqlx::query_as::<_, DailyViewsRow>(
+    r#"
+      SELECT (created_at AT TIME ZONE 'UTC')::DATE AS date, view_id, COUNT(*) AS views
+      FROM af_published_view_event
+      WHERE workspace_id = $1
+        AND created_at >= $2::DATE
+        AND created_at < $3::DATE + 1
+        AND ($4::UUID IS NULL OR view_id = $4)
+      GROUP BY date, view_id
+      ORDER BY date, view_id
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(from)
+  .bind(to)
+  .bind(view_id)
+  .fetch_all(executor)
+  .await?;
+  Ok(
+    rows
+      .into_iter()
+      .map(|row| PublishDailyViews {
+        date: row.date,
+        view_id: row.view_id,
+        views: row.views,
+      })
+      .collect(),
+  )
+}
+
+#[derive(FromRow)]
+struct BucketRow {
+  name: String,
+  views: i64,
+}
+
+/// Most frequent values of `column`, which is either `referrer` or `country`.
+async fn select_published_view_buckets<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  column: &str,
+  workspace_id: &Uuid,
+  from: NaiveDate,
+  to: NaiveDate,
+  view_id: Option<&Uuid>,
+  limit: i64,
+) -> Result<Vec<PublishViewsBucket>, AppError> {
+  let query = format!(
+    r#"
+      SELECT {column} AS name, COUNT(*) AS views
+      FROM af_published_view_event
+      WHERE workspace_id = $1
+        AND created_at >= $2::DATE
+        AND created_at < $3::DATE + 1
+        AND ($4::UUID IS NULL OR view_id = $4)
+        AND {column} IS NOT NULL
+      GROUP BY {column}
+      ORDER BY views DESC
+      LIMIT $5
+    "#
+  );
+  let rows = sqlx::query_as::<_, BucketRow>(&query)
+    .bind(workspace_id)
+    .bind(from)
+    .bind(to)
+    .bind(view_id)
+    .bind(limit)
+    .fetch_all(executor)
+    .await?;
+  Ok(
+    rows
+      .into_iter()
+      .map(|row| PublishViewsBucket {
+        name: row.name,
+        views: row.views,
+      })
+      .collect(),
+  )
+}
+
+pub async fn select_published_view_referrers<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  from: NaiveDate,
+  to: NaiveDate,
+  view_id: Option<&Uuid>,
+  limit: i64,
+) -> Result<Vec<PublishViewsBucket>, AppError> {
+  select_published_view_buckets(executor, "referrer", workspace_id, from, to, view_id, limit).await
+}
+
+pub async fn select_published_view_countries<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  from: NaiveDate,
+  to: NaiveDate,
+  view_id: Option<&Uuid>,
+  limit: i64,
+) -> Result<Vec<PublishViewsBucket>, AppError> {
+  select_published_view_buckets(executor, "country", workspace_id, from, to, view_id, limit).await
+}
@@ -0,0 +1,159 @@
+use app_error::AppError;
+use shared_entity::dto::admin_dto::{PublishReportStatus, PublishedViewReport};
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::pg_row::AFPublishedViewReportRow;
+
+/// Returns the workspace of the view if the view is currently published.
+pub async fn select_workspace_id_of_published_view<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  view_id: &Uuid,
+) -> Result<Option<Uuid>, AppError> {
+  let workspace_id = sqlx::query_scalar::<_, Uuid>(
+    r#"
+      SELECT workspace_id
+      FROM af_published_collab
+      WHERE view_id = $1 AND unpublished_at IS NULL
+      LIMIT 1
+    "#,
+  )
+  .bind(view_id)
+  .fetch_optional(executor)
+  .await?;
+  Ok(workspace_id)
+}
+
+pub async fn insert_published_view_report<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  view_id: &Uuid,
+  workspace_id: &Uuid,
+  reporter_uuid: Option<&Uuid>,
+  reason: &str,
+  details: Option<&str>,
+) -> Result<Uuid, AppError> {
+  let report_id = sqlx::query_scalar::<_, Uuid>(
+    r#"
+      INSERT INTO af_published_view_report (view_id, workspace_id, reporter_uuid, reason, details)
+      VALUES ($1, $2, $3, $4, $5)
+      RETURNING report_id
+    "#,
+  )
+  .bind(view_id)
+  .bind(workspace_id)
+  .bind(reporter_uuid)
+  .bind(reason)
+  .bind(details)
+  .fetch_one(executor)
+  .await?;
+  Ok(report_id)
+}
+
+/// Returns the reports with the given status, oldest first so the queue is reviewed in order.
+pub async fn select_published_view_reports<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  status: PublishReportStatus,
+  offset: i64,
+  limit: i64,
+) -> Result<Vec<AFPublishedViewReportRow>, AppError> {
+  let rows = sqlx::query_as::<_, AFPublishedViewReportRow>(
+    r#"
+      SELECT report_id, view_id, workspace_id, reporter_uuid, reason, details, status,
+        reviewed_by, reviewed_at, created_at
+      FROM af_published_view_report
+      WHERE status = $1
+      ORDER BY created_at ASC, report_id ASC
+      OFFSET $2
+      LIMIT $3
+    "#,
+  )
+  .bind(status as i16)
+  .bind(offset)
+  .bind(limit)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows)
+}
+
+pub async fn select_published_view_report<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  report_id: &Uuid,
+) -> Result<AFPublishedViewReportRow, AppError> {
+  let row = sqlx::query_as::<_, AFPublishedViewReportRow>(
+    r#"
+      SELECT report_id, view_id, workspace_id, reporter_uuid, reason, details, status,
+        reviewed_by, reviewed_at, created_at
+      FROM af_published_view_report
+      WHERE report_id = $1
+    "#,
+  )
+  .bind(report_id)
+  .fetch_optional(executor)
+  .await?
+  .ok_or_else(|| AppError::RecordNotFound(format!("publish report {} not found", report_id)))?;
+  Ok(row)
+}
+
+/// Closes the open report. Returns false if the report was already reviewed.
+pub async fn update_published_view_report_status<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  report_id: &Uuid,
+  status: PublishReportStatus,
+  reviewed_by: &Uuid,
+) -> Result<bool, AppError> {
+  let res = sqlx::query(
+    r#"
+      UPDATE af_published_view_report
+      SET status = $2, reviewed_by = $3, reviewed_at = CURRENT_TIMESTAMP
+      WHERE report_id = $1 AND status = $4
+    "#,
+  )
+  .bind(report_id)
+  .bind(status as i16)
+  .bind(reviewed_by)
+  .bind(PublishReportStatus::Open as i16)
+  .execute(executor)
+  .await?;
+  Ok(res.rows_affected() > 0)
+}
+
+/// Closes all the open reports of the view, once it has been unpublished. Returns the number of
+/// closed reports.
+pub async fn update_open_published_view_reports_status<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  view_id: &Uuid,
+  status: PublishReportStatus,
+  reviewed_by: &Uuid,
+) -> Result<u64, AppError> {
+  let res = sqlx::query(
+    r#"
+      UPDATE af_published_view_report
+      SET status = $2, reviewed_by = $3, reviewed_at = CURRENT_TIMESTAMP
+      WHERE view_id = $1 AND status = $4
+    "#,
+  )
+  .bind(view_id)
+  .bind(status as i16)
+  .bind(reviewed_by)
+  .bind(PublishReportStatus::Open as i16)
+  .execute(executor)
+  .await?;
+  Ok(res.rows_affected())
+}
+
+impl From<AFPublishedViewReportRow> for PublishedViewReport {
+  fn from(row: AFPublishedViewReportRow) -> Self {
+    PublishedViewReport {
+      report_id: row.report_id,
+      view_id: row.view_id,
+      workspace_id: row.workspace_id,
+      reporter_uuid: row.reporter_uuid,
+      reason: row.reason.as_str().into(),
+      details: row.details,
+      status: row.status.into(),
+      reviewed_by: row.reviewed_by,
+      reviewed_at: row.reviewed_at,
+      created_at: row.created_at,
+    }
+  }
+}
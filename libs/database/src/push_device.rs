@@ -0,0 +1,83 @@
+use app_error::AppError;
+use shared_entity::dto::notification_dto::PushPlatform;
+use sqlx::{Executor, Postgres};
+
+#[derive(Debug, Clone)]
+pub struct AFPushDevice {
+  pub token: String,
+  pub platform: PushPlatform,
+}
+
+/// Registers the token for the user. A token registered by another user before is moved to this
+/// user, the app was signed in with another account on the device.
+pub async fn upsert_push_device<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  uid: i64,
+  token: &str,
+  platform: PushPlatform,
+  device_id: Option<&str>,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      INSERT INTO af_push_device (token, uid, platform, device_id)
+      VALUES ($1, $2, $3, $4)
+      ON CONFLICT (token) DO UPDATE SET
+        uid = EXCLUDED.uid,
+        platform = EXCLUDED.platform,
+        device_id = EXCLUDED.device_id,
+        updated_at = CURRENT_TIMESTAMP
+    "#,
+  )
+  .bind(token)
+  .bind(uid)
+  .bind(platform.as_i16())
+  .bind(device_id)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+pub async fn delete_push_device<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  uid: i64,
+  token: &str,
+) -> Result<(), AppError> {
+  sqlx::query("DELETE FROM af_push_device WHERE uid = $1 AND token = $2")
+    .bind(uid)
+    .bind(token)
+    .execute(executor)
+    .await?;
+  Ok(())
+}
+
+/// Removes the tokens the push services reported as no longer valid.
+pub async fn delete_push_devices_by_token<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  tokens: &[String],
+) -> Result<u64, AppError> {
+  let result = sqlx::query("DELETE FROM af_push_device WHERE token = ANY($1)")
+    .bind(tokens)
+    .execute(executor)
+    .await?;
+  Ok(result.rows_affected())
+}
+
+pub async fn select_push_devices<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  uid: i64,
+) -> Result<Vec<AFPushDevice>, AppError> {
+  let rows: Vec<(String, i16)> =
+    sqlx::query_as("SELECT token, platform FROM af_push_device WHERE uid = $1")
+      .bind(uid)
+      .fetch_all(executor)
+      .await?;
+  Ok(
+    rows
+      .into_iter()
+      .filter_map(|(token, platform)| {
+        let platform = PushPlatform::try_from(platform).ok()?;
+        Some(AFPushDevice { token, platform })
+      })
+      .collect(),
+  )
+}
@@ -1,4 +1,4 @@
-use crate::pg_row::AFBlobMetadataRow;
+use crate::pg_row::{AFBlobMetadataRow, AFBlobStatus, AFWorkspaceFileRow};
 use app_error::AppError;
 use rust_decimal::prelude::ToPrimitive;
 use sqlx::types::Decimal;
@@ -39,21 +39,27 @@ pub async fn insert_blob_metadata(
   workspace_id: &Uuid,
   file_type: &str,
   file_size: usize,
+  uploaded_by: Option<i64>,
+  status: AFBlobStatus,
 ) -> Result<(), AppError> {
-  let res = sqlx::query!(
+  let res = sqlx::query(
     r#"
         INSERT INTO af_blob_metadata
-        (workspace_id, file_id, file_type, file_size)
-        VALUES ($1, $2, $3, $4)
+        (workspace_id, file_id, file_type, file_size, uploaded_by, status)
+        VALUES ($1, $2, $3, $4, $5, $6)
         ON CONFLICT (workspace_id, file_id) DO UPDATE SET
             file_type = $3,
-            file_size = $4
+            file_size = $4,
+            uploaded_by = COALESCE($5, af_blob_metadata.uploaded_by),
+            status = $6
         "#,
-    workspace_id,
-    file_id,
-    file_type,
-    file_size as i64,
   )
+  .bind(workspace_id)
+  .bind(file_id)
+  .bind(file_type)
+  .bind(file_size as i64)
+  .bind(uploaded_by)
+  .bind(status as i16)
   .execute(pg_pool)
   .await?;
   let n = res.rows_affected();
@@ -93,6 +99,19 @@ pub async fn insert_blob_metadata_bulk<'a, E: Executor<'a, Database = Postgres>>
     file_types.push(file_type);
     file_sizes.push(file_size);
   }
+  insert_blob_metadata_rows(executor, workspace_id, file_ids, file_types, file_sizes).await
+}
+
+/// Same as [insert_blob_metadata_bulk], for file ids that already include the parent directory,
+/// as returned by [get_all_workspace_blob_metadata].
+#[instrument(level = "trace", skip_all, err)]
+pub async fn insert_blob_metadata_rows<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  file_ids: Vec<String>,
+  file_types: Vec<String>,
+  file_sizes: Vec<i64>,
+) -> Result<u64, sqlx::Error> {
   let query = r#"
         INSERT INTO af_blob_metadata (workspace_id, file_id, file_type, file_size)
         SELECT $1, unnest($2::text[]), unnest($3::text[]), unnest($4::int8[])
@@ -213,3 +232,90 @@ pub async fn get_workspace_usage_size(pool: &PgPool, workspace_id: &Uuid) -> Res
     None => Ok(0),
   }
 }
+
+/// Returns a page of the blob metadata of a workspace whose file id starts with `prefix`, ordered
+/// by file id, along with the user who uploaded each blob. Only the blobs with a file id greater
+/// than `cursor`, and with the given status, are returned when they are set.
+#[instrument(level = "trace", skip_all, err)]
+pub async fn select_workspace_files(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  prefix: &str,
+  cursor: Option<&str>,
+  status: Option<AFBlobStatus>,
+  limit: i64,
+) -> Result<Vec<AFWorkspaceFileRow>, AppError> {
+  let rows = sqlx::query_as::<_, AFWorkspaceFileRow>(
+    r#"
+      SELECT
+        b.file_id,
+        b.file_type,
+        b.file_size,
+        b.modified_at,
+        b.status,
+        u.uuid AS uploaded_by,
+        u.name AS uploader_name
+      FROM af_blob_metadata b
+      LEFT JOIN af_user u ON u.uid = b.uploaded_by
+      WHERE b.workspace_id = $1
+        AND starts_with(b.file_id, $2)
+        AND ($3::text IS NULL OR b.file_id > $3)
+        AND ($4::smallint IS NULL OR b.status = $4)
+      ORDER BY b.file_id
+      LIMIT $5
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(prefix)
+  .bind(cursor)
+  .bind(status.map(|status| status as i16))
+  .bind(limit)
+  .fetch_all(pg_pool)
+  .await?;
+  Ok(rows)
+}
+
+/// Deletes the metadata of the given blobs, returning the file id and size of the deleted ones.
+#[instrument(level = "trace", skip_all, err)]
+pub async fn delete_blob_metadata_bulk(
+  tx: &mut Transaction<'_, sqlx::Postgres>,
+  workspace_id: &Uuid,
+  file_ids: &[String],
+) -> Result<Vec<(String, i64)>, AppError> {
+  let deleted = sqlx::query_as::<_, (String, i64)>(
+    r#"
+      DELETE FROM af_blob_metadata
+      WHERE workspace_id = $1 AND file_id = ANY($2)
+      RETURNING file_id, file_size
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(file_ids)
+  .fetch_all(tx.deref_mut())
+  .await?;
+  Ok(deleted)
+}
+
+/// Releases the given blobs from the quarantine, returning the file ids of the released ones.
+#[instrument(level = "trace", skip_all, err)]
+pub async fn update_quarantined_blobs_to_ok(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  file_ids: &[String],
+) -> Result<Vec<String>, AppError> {
+  let file_ids = sqlx::query_scalar::<_, String>(
+    r#"
+      UPDATE af_blob_metadata
+      SET status = $3
+      WHERE workspace_id = $1 AND file_id = ANY($2) AND status = $4
+      RETURNING file_id
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(file_ids)
+  .bind(AFBlobStatus::Ok as i16)
+  .bind(AFBlobStatus::Quarantined as i16)
+  .fetch_all(pg_pool)
+  .await?;
+  Ok(file_ids)
+}
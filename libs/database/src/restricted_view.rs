@@ -0,0 +1,95 @@
+use app_error::AppError;
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, FromRow, Postgres, Transaction};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, FromRow)]
+pub struct AFRestrictedViewRow {
+  pub workspace_id: Uuid,
+  pub view_id: String,
+  pub created_by: i64,
+  pub created_at: DateTime<Utc>,
+  pub member_uids: Vec<i64>,
+}
+
+pub async fn select_restricted_views<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+) -> Result<Vec<AFRestrictedViewRow>, AppError> {
+  let rows = sqlx::query_as::<_, AFRestrictedViewRow>(
+    r#"
+      SELECT v.workspace_id, v.view_id, v.created_by, v.created_at,
+        COALESCE(
+          ARRAY_AGG(m.uid ORDER BY m.uid) FILTER (WHERE m.uid IS NOT NULL),
+          '{}'
+        ) AS member_uids
+      FROM af_restricted_view v
+      LEFT JOIN af_restricted_view_member m
+        ON m.workspace_id = v.workspace_id AND m.view_id = v.view_id
+      WHERE v.workspace_id = $1
+      GROUP BY v.workspace_id, v.view_id
+      ORDER BY v.created_at
+    "#,
+  )
+  .bind(workspace_id)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows)
+}
+
+/// Restricts the view to the given members, replacing the members of a view that was already
+/// restricted.
+pub async fn upsert_restricted_view(
+  txn: &mut Transaction<'_, Postgres>,
+  workspace_id: &Uuid,
+  view_id: &str,
+  created_by: i64,
+  member_uids: &[i64],
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      INSERT INTO af_restricted_view (workspace_id, view_id, created_by)
+      VALUES ($1, $2, $3)
+      ON CONFLICT (workspace_id, view_id) DO NOTHING
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(view_id)
+  .bind(created_by)
+  .execute(txn.as_mut())
+  .await?;
+
+  sqlx::query("DELETE FROM af_restricted_view_member WHERE workspace_id = $1 AND view_id = $2")
+    .bind(workspace_id)
+    .bind(view_id)
+    .execute(txn.as_mut())
+    .await?;
+  sqlx::query(
+    r#"
+      INSERT INTO af_restricted_view_member (workspace_id, view_id, uid)
+      SELECT $1, $2, UNNEST($3::BIGINT[])
+      ON CONFLICT DO NOTHING
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(view_id)
+  .bind(member_uids)
+  .execute(txn.as_mut())
+  .await?;
+  Ok(())
+}
+
+/// Returns false when the view was not restricted.
+pub async fn delete_restricted_view<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  view_id: &str,
+) -> Result<bool, AppError> {
+  let result =
+    sqlx::query("DELETE FROM af_restricted_view WHERE workspace_id = $1 AND view_id = $2")
+      .bind(workspace_id)
+      .bind(view_id)
+      .execute(executor)
+      .await?;
+  Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,143 @@
+use app_error::AppError;
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::pg_row::AFWorkspaceRetentionPolicyRow;
+
+pub async fn select_workspace_retention_policy<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+) -> Result<Option<AFWorkspaceRetentionPolicyRow>, AppError> {
+  let row = sqlx::query_as::<_, AFWorkspaceRetentionPolicyRow>(
+    r#"
+      SELECT workspace_id, chat_history_days, snapshot_days, updated_at
+      FROM af_workspace_retention_policy
+      WHERE workspace_id = $1
+    "#,
+  )
+  .bind(workspace_id)
+  .fetch_optional(executor)
+  .await?;
+  Ok(row)
+}
+
+pub async fn upsert_workspace_retention_policy<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  chat_history_days: Option<i32>,
+  snapshot_days: Option<i32>,
+) -> Result<AFWorkspaceRetentionPolicyRow, AppError> {
+  let row = sqlx::query_as::<_, AFWorkspaceRetentionPolicyRow>(
+    r#"
+      INSERT INTO af_workspace_retention_policy (workspace_id, chat_history_days, snapshot_days)
+      VALUES ($1, $2, $3)
+      ON CONFLICT (workspace_id) DO UPDATE
+      SET chat_history_days = EXCLUDED.chat_history_days,
+          snapshot_days = EXCLUDED.snapshot_days,
+          updated_at = CURRENT_TIMESTAMP
+      RETURNING workspace_id, chat_history_days, snapshot_days, updated_at
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(chat_history_days)
+  .bind(snapshot_days)
+  .fetch_one(executor)
+  .await?;
+  Ok(row)
+}
+
+/// Returns the number of chat messages of the workspace older than `days`.
+pub async fn count_expired_chat_messages<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  days: i32,
+) -> Result<i64, AppError> {
+  let count = sqlx::query_scalar::<_, i64>(
+    r#"
+      SELECT COUNT(*)
+      FROM af_chat_messages m
+      JOIN af_chat c ON c.chat_id = m.chat_id
+      WHERE c.workspace_id = $1
+        AND m.created_at < NOW() - make_interval(days => $2)
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(days)
+  .fetch_one(executor)
+  .await?;
+  Ok(count)
+}
+
+/// Returns the number of collab snapshots of the workspace older than `days`.
+pub async fn count_expired_collab_snapshots<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  days: i32,
+) -> Result<i64, AppError> {
+  let count = sqlx::query_scalar::<_, i64>(
+    r#"
+      SELECT COUNT(*)
+      FROM af_collab_snapshot
+      WHERE workspace_id = $1
+        AND created_at < NOW() - make_interval(days => $2)
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(days)
+  .fetch_one(executor)
+  .await?;
+  Ok(count)
+}
+
+/// Deletes at most `limit` chat messages that are older than the retention of their workspace.
+/// Returns the number of deleted messages.
+pub async fn delete_expired_chat_messages<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  limit: i64,
+) -> Result<u64, AppError> {
+  let result = sqlx::query(
+    r#"
+      DELETE FROM af_chat_messages
+      WHERE message_id IN (
+        SELECT m.message_id
+        FROM af_workspace_retention_policy p
+        JOIN af_chat c ON c.workspace_id = p.workspace_id
+        JOIN af_chat_messages m ON m.chat_id = c.chat_id
+        WHERE p.chat_history_days IS NOT NULL
+          AND m.created_at < NOW() - make_interval(days => p.chat_history_days)
+        LIMIT $1
+      )
+    "#,
+  )
+  .bind(limit)
+  .execute(executor)
+  .await?;
+  Ok(result.rows_affected())
+}
+
+/// Deletes at most `limit` collab snapshots that are older than the retention of their
+/// workspace. Returns the storage key of each deleted snapshot, `None` for the snapshots whose
+/// blob was stored in Postgres.
+pub async fn delete_expired_collab_snapshots<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  limit: i64,
+) -> Result<Vec<Option<String>>, AppError> {
+  let keys = sqlx::query_scalar::<_, Option<String>>(
+    r#"
+      DELETE FROM af_collab_snapshot
+      WHERE sid IN (
+        SELECT s.sid
+        FROM af_workspace_retention_policy p
+        JOIN af_collab_snapshot s ON s.workspace_id = p.workspace_id
+        WHERE p.snapshot_days IS NOT NULL
+          AND s.created_at < NOW() - make_interval(days => p.snapshot_days)
+        LIMIT $1
+      )
+      RETURNING storage_key
+    "#,
+  )
+  .bind(limit)
+  .fetch_all(executor)
+  .await?;
+  Ok(keys)
+}
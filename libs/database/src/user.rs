@@ -8,7 +8,7 @@ use uuid::Uuid;
 
 use app_error::AppError;
 
-use crate::pg_row::AFUserIdRow;
+use crate::pg_row::{AFUserIdRow, AFUserWithWorkspaceCountRow};
 
 /// Updates the user's details in the `af_user` table.
 ///
@@ -185,6 +185,23 @@ pub async fn select_uid_from_email<'a, E: Executor<'a, Database = Postgres>>(
   Ok(uid)
 }
 
+/// Returns the uuid of the user with the email, None if no user has it. Emails are compared
+/// case-insensitively, like GoTrue does.
+pub async fn select_uuid_from_email<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  email: &str,
+) -> Result<Option<Uuid>, AppError> {
+  let uuid = sqlx::query_scalar::<_, Uuid>(
+    r#"
+      SELECT uuid FROM af_user WHERE LOWER(email) = LOWER($1) LIMIT 1
+    "#,
+  )
+  .bind(email)
+  .fetch_optional(executor)
+  .await?;
+  Ok(uuid)
+}
+
 #[inline]
 pub async fn is_user_exist<'a, E: Executor<'a, Database = Postgres>>(
   executor: E,
@@ -273,3 +290,50 @@ pub async fn select_web_user_from_uid(
 
   Ok(row)
 }
+
+/// Returns the users whose uid is greater than `after_uid`, ordered by uid, with the number of
+/// workspaces they own and the number of workspaces they are a member of, owned ones included.
+pub async fn select_users_with_workspace_count<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  after_uid: i64,
+  limit: i64,
+) -> Result<Vec<AFUserWithWorkspaceCountRow>, AppError> {
+  let rows = sqlx::query_as::<_, AFUserWithWorkspaceCountRow>(
+    r#"
+      SELECT
+        u.uid,
+        u.uuid,
+        u.email,
+        u.name,
+        u.created_at,
+        (SELECT COUNT(*) FROM af_workspace w WHERE w.owner_uid = u.uid) AS owned_workspace_count,
+        (SELECT COUNT(*) FROM af_workspace_member m WHERE m.uid = u.uid) AS member_workspace_count
+      FROM af_user u
+      WHERE u.uid > $1 AND u.deleted_at IS NULL
+      ORDER BY u.uid
+      LIMIT $2
+    "#,
+  )
+  .bind(after_uid)
+  .bind(limit)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows)
+}
+
+/// Deletes a GoTrue session, which revokes its refresh tokens. The access tokens already issued
+/// for the session stay valid until they expire. Returns false if the session doesn't exist.
+pub async fn delete_gotrue_session<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  session_id: &Uuid,
+) -> Result<bool, AppError> {
+  let res = sqlx::query(
+    r#"
+      DELETE FROM auth.sessions WHERE id = $1
+    "#,
+  )
+  .bind(session_id)
+  .execute(executor)
+  .await?;
+  Ok(res.rows_affected() > 0)
+}
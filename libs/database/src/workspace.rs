@@ -650,6 +650,43 @@ pub async fn select_workspace<'a, E: Executor<'a, Database = Postgres>>(
   Ok(workspace)
 }
 
+/// The initialized workspaces of the instance, oldest first. Filtered by owner when `owner_uid` is
+/// given.
+pub async fn select_all_workspaces<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  owner_uid: Option<i64>,
+  limit: i64,
+  offset: i64,
+) -> Result<Vec<AFWorkspaceRow>, AppError> {
+  let workspaces = sqlx::query_as::<_, AFWorkspaceRow>(
+    r#"
+      SELECT
+        workspace_id,
+        database_storage_id,
+        owner_uid,
+        owner_profile.name as owner_name,
+        owner_profile.email as owner_email,
+        af_workspace.created_at,
+        workspace_type,
+        af_workspace.deleted_at,
+        workspace_name,
+        icon
+      FROM public.af_workspace
+      JOIN public.af_user owner_profile ON af_workspace.owner_uid = owner_profile.uid
+      WHERE COALESCE(af_workspace.is_initialized, true) = true
+        AND ($1::BIGINT IS NULL OR af_workspace.owner_uid = $1)
+      ORDER BY af_workspace.created_at
+      LIMIT $2 OFFSET $3
+    "#,
+  )
+  .bind(owner_uid)
+  .bind(limit)
+  .bind(offset)
+  .fetch_all(executor)
+  .await?;
+  Ok(workspaces)
+}
+
 #[inline]
 pub async fn select_workspace_database_storage_id<'a, E: Executor<'a, Database = Postgres>>(
   executor: E,
@@ -781,7 +818,7 @@ where
     return Ok(());
   }
 
-  let query = "INSERT INTO public.af_workspace_deleted (workspace_id) SELECT unnest($1::uuid[])";
+  let query = "INSERT INTO public.af_workspace_deleted (workspace_id) SELECT unnest($1::uuid[]) ON CONFLICT DO NOTHING";
   sqlx::query(query)
     .bind(workspace_ids)
     .execute(executor)
@@ -1359,6 +1396,8 @@ pub enum ImportTaskState {
   Failed = 2,
   Expire = 3,
   Cancel = 4,
+  /// Waiting for the start time chosen by the user, becomes pending once it's due.
+  Scheduled = 5,
 }
 
 impl From<i16> for ImportTaskState {
@@ -1368,6 +1407,7 @@ impl From<i16> for ImportTaskState {
       1 => ImportTaskState::Completed,
       2 => ImportTaskState::Failed,
       4 => ImportTaskState::Cancel,
+      5 => ImportTaskState::Scheduled,
       _ => ImportTaskState::Pending,
     }
   }
@@ -1400,6 +1440,48 @@ pub async fn update_import_task_status<'a, E: Executor<'a, Database = Postgres>>
   Ok(())
 }
 
+/// Cancels the import task of the user if it hasn't been started yet. Returns false if the task
+/// doesn't exist, belongs to another user or is not waiting in the queue anymore.
+pub async fn cancel_import_task(
+  pg_pool: &PgPool,
+  task_id: &Uuid,
+  uid: i64,
+) -> Result<bool, AppError> {
+  let result = sqlx::query(
+    r#"
+      UPDATE af_import_task
+      SET status = $3
+      WHERE task_id = $1 AND created_by = $2 AND status IN ($4, $5)
+    "#,
+  )
+  .bind(task_id)
+  .bind(uid)
+  .bind(ImportTaskState::Cancel as i16)
+  .bind(ImportTaskState::Pending as i16)
+  .bind(ImportTaskState::Scheduled as i16)
+  .execute(pg_pool)
+  .await?;
+  Ok(result.rows_affected() > 0)
+}
+
+/// Moves the import task from `from` to `to`. Returns false if the task was not in the `from`
+/// state, for instance because the user cancelled it in the meantime.
+pub async fn transition_import_task_status<'a, E: Executor<'a, Database = Postgres>>(
+  task_id: &Uuid,
+  from: ImportTaskState,
+  to: ImportTaskState,
+  executor: E,
+) -> Result<bool, AppError> {
+  let result =
+    sqlx::query("UPDATE af_import_task SET status = $1 WHERE task_id = $2 AND status = $3")
+      .bind(to as i16)
+      .bind(task_id)
+      .bind(from as i16)
+      .execute(executor)
+      .await?;
+  Ok(result.rows_affected() > 0)
+}
+
 #[allow(clippy::too_many_arguments)]
 pub async fn insert_import_task(
   uid: i64,
@@ -1407,6 +1489,7 @@ pub async fn insert_import_task(
   file_size: i64,
   workspace_id: String,
   created_by: i64,
+  status: ImportTaskState,
   metadata: Option<serde_json::Value>,
   presigned_url: Option<String>,
   pg_pool: &PgPool,
@@ -1421,7 +1504,7 @@ pub async fn insert_import_task(
     .bind(file_size)
     .bind(workspace_id)
     .bind(created_by)
-    .bind(ImportTaskState::Pending as i32)
+    .bind(status as i32)
     .bind(metadata)
     .bind(uid)
     .bind(presigned_url)
@@ -1437,10 +1520,19 @@ pub async fn insert_import_task(
   Ok(())
 }
 
-pub async fn update_import_task_metadata(
+/// Removes a task that could not be queued.
+pub async fn delete_import_task(pg_pool: &PgPool, task_id: &Uuid) -> Result<(), AppError> {
+  sqlx::query("DELETE FROM af_import_task WHERE task_id = $1")
+    .bind(task_id)
+    .execute(pg_pool)
+    .await?;
+  Ok(())
+}
+
+pub async fn update_import_task_metadata<'a, E: Executor<'a, Database = Postgres>>(
   task_id: Uuid,
   new_metadata: serde_json::Value,
-  pg_pool: &PgPool,
+  executor: E,
 ) -> Result<(), AppError> {
   let query = r#"
         UPDATE af_import_task
@@ -1451,7 +1543,7 @@ pub async fn update_import_task_metadata(
   sqlx::query(query)
     .bind(new_metadata)
     .bind(task_id)
-    .execute(pg_pool)
+    .execute(executor)
     .await
     .map_err(|err| {
       AppError::Internal(anyhow::anyhow!(
@@ -1464,6 +1556,49 @@ pub async fn update_import_task_metadata(
   Ok(())
 }
 
+/// Records the view created for each page of the imported export, as (source id, view id) pairs.
+pub async fn insert_import_page_mapping<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  task_id: &Uuid,
+  workspace_id: &Uuid,
+  entries: Vec<(String, String)>,
+) -> Result<u64, AppError> {
+  let (source_ids, view_ids): (Vec<String>, Vec<String>) = entries.into_iter().unzip();
+  let result = sqlx::query(
+    r#"
+      INSERT INTO af_import_page_mapping (task_id, workspace_id, source_id, view_id)
+      SELECT $1, $2, source_id, view_id
+      FROM UNNEST($3::text[], $4::text[]) AS t(source_id, view_id)
+      ON CONFLICT (task_id, source_id) DO UPDATE SET view_id = EXCLUDED.view_id
+    "#,
+  )
+  .bind(task_id)
+  .bind(workspace_id)
+  .bind(source_ids)
+  .bind(view_ids)
+  .execute(executor)
+  .await?;
+  Ok(result.rows_affected())
+}
+
+/// The (source id, view id) pairs recorded by the import task.
+pub async fn select_import_page_mapping<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  task_id: &Uuid,
+) -> Result<Vec<(String, String)>, AppError> {
+  let rows = sqlx::query_as::<_, (String, String)>(
+    r#"
+      SELECT source_id, view_id FROM af_import_page_mapping
+      WHERE task_id = $1
+      ORDER BY source_id
+    "#,
+  )
+  .bind(task_id)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows)
+}
+
 #[inline]
 pub async fn select_publish_name_exists(
   pg_pool: &PgPool,
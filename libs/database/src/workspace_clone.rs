@@ -0,0 +1,175 @@
+use app_error::AppError;
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::pg_row::AFWorkspaceCloneTaskRow;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WorkspaceCloneTaskState {
+  Pending = 0,
+  Completed = 1,
+  Failed = 2,
+}
+
+impl From<i16> for WorkspaceCloneTaskState {
+  fn from(val: i16) -> Self {
+    match val {
+      1 => WorkspaceCloneTaskState::Completed,
+      2 => WorkspaceCloneTaskState::Failed,
+      _ => WorkspaceCloneTaskState::Pending,
+    }
+  }
+}
+
+pub async fn insert_workspace_clone_task<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  task_id: &Uuid,
+  source_workspace_id: &Uuid,
+  target_workspace_id: &Uuid,
+  uid: i64,
+  include_members: bool,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      INSERT INTO af_workspace_clone_task
+        (task_id, source_workspace_id, target_workspace_id, uid, include_members, status)
+      VALUES ($1, $2, $3, $4, $5, $6)
+    "#,
+  )
+  .bind(task_id)
+  .bind(source_workspace_id)
+  .bind(target_workspace_id)
+  .bind(uid)
+  .bind(include_members)
+  .bind(WorkspaceCloneTaskState::Pending as i16)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+pub async fn select_workspace_clone_task<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  task_id: &Uuid,
+) -> Result<AFWorkspaceCloneTaskRow, AppError> {
+  let row = sqlx::query_as::<_, AFWorkspaceCloneTaskRow>(
+    r#"
+      SELECT task_id, source_workspace_id, target_workspace_id, uid, include_members, status,
+        total_collabs, cloned_collabs, total_blobs, cloned_blobs, error, created_at
+      FROM af_workspace_clone_task
+      WHERE task_id = $1
+    "#,
+  )
+  .bind(task_id)
+  .fetch_optional(executor)
+  .await?
+  .ok_or_else(|| AppError::RecordNotFound(format!("workspace clone task {} not found", task_id)))?;
+  Ok(row)
+}
+
+/// Returns true if a clone of the workspace is still in progress.
+pub async fn is_workspace_clone_pending<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  source_workspace_id: &Uuid,
+) -> Result<bool, AppError> {
+  let pending = sqlx::query_scalar::<_, bool>(
+    r#"
+      SELECT EXISTS (
+        SELECT 1 FROM af_workspace_clone_task
+        WHERE source_workspace_id = $1 AND status = $2
+      )
+    "#,
+  )
+  .bind(source_workspace_id)
+  .bind(WorkspaceCloneTaskState::Pending as i16)
+  .fetch_one(executor)
+  .await?;
+  Ok(pending)
+}
+
+/// Records the number of collabs and files that are going to be copied.
+pub async fn update_workspace_clone_task_total<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  task_id: &Uuid,
+  total_collabs: i32,
+  total_blobs: i32,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      UPDATE af_workspace_clone_task
+      SET total_collabs = $2, total_blobs = $3, updated_at = CURRENT_TIMESTAMP
+      WHERE task_id = $1
+    "#,
+  )
+  .bind(task_id)
+  .bind(total_collabs)
+  .bind(total_blobs)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+pub async fn update_workspace_clone_task_progress<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  task_id: &Uuid,
+  cloned_collabs: i32,
+  cloned_blobs: i32,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      UPDATE af_workspace_clone_task
+      SET cloned_collabs = $2, cloned_blobs = $3, updated_at = CURRENT_TIMESTAMP
+      WHERE task_id = $1
+    "#,
+  )
+  .bind(task_id)
+  .bind(cloned_collabs)
+  .bind(cloned_blobs)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+pub async fn update_workspace_clone_task_status<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  task_id: &Uuid,
+  state: WorkspaceCloneTaskState,
+  error: Option<&str>,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      UPDATE af_workspace_clone_task
+      SET status = $2, error = $3, updated_at = CURRENT_TIMESTAMP
+      WHERE task_id = $1
+    "#,
+  )
+  .bind(task_id)
+  .bind(state as i16)
+  .bind(error)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+/// Copies the metadata of the files of the source workspace to the target workspace. The files
+/// keep their id, only the workspace part of their key changes.
+pub async fn copy_workspace_blob_metadata<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  source_workspace_id: &Uuid,
+  target_workspace_id: &Uuid,
+) -> Result<u64, AppError> {
+  let result = sqlx::query(
+    r#"
+      INSERT INTO af_blob_metadata
+        (workspace_id, file_id, file_type, file_size, status, source, source_metadata)
+      SELECT $2, file_id, file_type, file_size, status, source, source_metadata
+      FROM af_blob_metadata
+      WHERE workspace_id = $1
+      ON CONFLICT (workspace_id, file_id) DO NOTHING
+    "#,
+  )
+  .bind(source_workspace_id)
+  .bind(target_workspace_id)
+  .execute(executor)
+  .await?;
+  Ok(result.rows_affected())
+}
@@ -0,0 +1,201 @@
+use app_error::AppError;
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::pg_row::AFWorkspaceDeleteTaskRow;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WorkspaceDeleteTaskState {
+  Pending = 0,
+  Completed = 1,
+  Failed = 2,
+}
+
+impl From<i16> for WorkspaceDeleteTaskState {
+  fn from(val: i16) -> Self {
+    match val {
+      1 => WorkspaceDeleteTaskState::Completed,
+      2 => WorkspaceDeleteTaskState::Failed,
+      _ => WorkspaceDeleteTaskState::Pending,
+    }
+  }
+}
+
+pub async fn insert_workspace_delete_task<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  task_id: &Uuid,
+  workspace_id: &Uuid,
+  uid: i64,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      INSERT INTO af_workspace_delete_task (task_id, workspace_id, uid, status)
+      VALUES ($1, $2, $3, $4)
+    "#,
+  )
+  .bind(task_id)
+  .bind(workspace_id)
+  .bind(uid)
+  .bind(WorkspaceDeleteTaskState::Pending as i16)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+pub async fn select_workspace_delete_task<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  task_id: &Uuid,
+) -> Result<AFWorkspaceDeleteTaskRow, AppError> {
+  let row = sqlx::query_as::<_, AFWorkspaceDeleteTaskRow>(
+    r#"
+      SELECT task_id, workspace_id, uid, status, deleted_collabs, deleted_snapshots, error,
+        created_at
+      FROM af_workspace_delete_task
+      WHERE task_id = $1
+    "#,
+  )
+  .bind(task_id)
+  .fetch_optional(executor)
+  .await?
+  .ok_or_else(|| {
+    AppError::RecordNotFound(format!("workspace delete task {} not found", task_id))
+  })?;
+  Ok(row)
+}
+
+/// Returns true if the deletion of the workspace has been queued and is not done yet.
+pub async fn is_workspace_delete_pending<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+) -> Result<bool, AppError> {
+  let pending = sqlx::query_scalar::<_, bool>(
+    r#"
+      SELECT EXISTS (
+        SELECT 1 FROM af_workspace_delete_task
+        WHERE workspace_id = $1 AND status = $2
+      )
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(WorkspaceDeleteTaskState::Pending as i16)
+  .fetch_one(executor)
+  .await?;
+  Ok(pending)
+}
+
+pub async fn update_workspace_delete_task_progress<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  task_id: &Uuid,
+  deleted_collabs: i32,
+  deleted_snapshots: i32,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      UPDATE af_workspace_delete_task
+      SET deleted_collabs = $2, deleted_snapshots = $3, updated_at = CURRENT_TIMESTAMP
+      WHERE task_id = $1
+    "#,
+  )
+  .bind(task_id)
+  .bind(deleted_collabs)
+  .bind(deleted_snapshots)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+pub async fn update_workspace_delete_task_status<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  task_id: &Uuid,
+  state: WorkspaceDeleteTaskState,
+  error: Option<&str>,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      UPDATE af_workspace_delete_task
+      SET status = $2, error = $3, updated_at = CURRENT_TIMESTAMP
+      WHERE task_id = $1
+    "#,
+  )
+  .bind(task_id)
+  .bind(state as i16)
+  .bind(error)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+/// Deletes at most `limit` collabs of the workspace and returns their object ids. The embeddings
+/// of the collabs are deleted by the `af_collab_delete_embeddings_trigger` trigger.
+pub async fn delete_workspace_collab_batch<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  limit: i64,
+) -> Result<Vec<String>, AppError> {
+  let oids = sqlx::query_scalar::<_, String>(
+    r#"
+      DELETE FROM af_collab
+      WHERE workspace_id = $1
+        AND (oid, partition_key) IN (
+          SELECT oid, partition_key FROM af_collab
+          WHERE workspace_id = $1
+          LIMIT $2
+        )
+      RETURNING oid
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(limit)
+  .fetch_all(executor)
+  .await?;
+  Ok(oids)
+}
+
+/// Deletes at most `limit` rows of each snapshot table of the workspace: the collab snapshots and
+/// the versions of the history. Returns the number of deleted rows. The snapshot blobs offloaded
+/// to the object storage are under the `collabs/{workspace_id}/` prefix and are removed with it.
+pub async fn delete_workspace_snapshot_batch<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  limit: i64,
+) -> Result<i64, AppError> {
+  let deleted = sqlx::query_scalar::<_, i64>(
+    r#"
+      WITH snapshots AS (
+        DELETE FROM af_collab_snapshot
+        WHERE sid IN (
+          SELECT sid FROM af_collab_snapshot
+          WHERE workspace_id = $1
+          LIMIT $2
+        )
+        RETURNING 1
+      ), metas AS (
+        DELETE FROM af_snapshot_meta
+        WHERE workspace_id = $1
+          AND (oid, partition_key, created_at) IN (
+            SELECT oid, partition_key, created_at FROM af_snapshot_meta
+            WHERE workspace_id = $1
+            LIMIT $2
+          )
+        RETURNING 1
+      ), states AS (
+        DELETE FROM af_snapshot_state
+        WHERE workspace_id = $1
+          AND (snapshot_id, partition_key) IN (
+            SELECT snapshot_id, partition_key FROM af_snapshot_state
+            WHERE workspace_id = $1
+            LIMIT $2
+          )
+        RETURNING 1
+      )
+      SELECT (SELECT COUNT(*) FROM snapshots)
+        + (SELECT COUNT(*) FROM metas)
+        + (SELECT COUNT(*) FROM states)
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(limit)
+  .fetch_one(executor)
+  .await?;
+  Ok(deleted)
+}
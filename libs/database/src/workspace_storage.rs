@@ -0,0 +1,198 @@
+use app_error::AppError;
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, Postgres};
+use uuid::Uuid;
+
+use crate::pg_row::AFWorkspaceStorageRow;
+
+pub use shared_entity::dto::admin_dto::WorkspaceStorageRelocationStatus;
+
+const WORKSPACE_STORAGE_COLUMNS: &str = r#"
+  workspace_id, settings, target_settings, previous_settings, relocation_status,
+  relocation_error, relocated_objects, switched_at, updated_at
+"#;
+
+pub async fn select_workspace_storage<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+) -> Result<Option<AFWorkspaceStorageRow>, AppError> {
+  let row = sqlx::query_as::<_, AFWorkspaceStorageRow>(&format!(
+    "SELECT {} FROM af_workspace_storage WHERE workspace_id = $1",
+    WORKSPACE_STORAGE_COLUMNS
+  ))
+  .bind(workspace_id)
+  .fetch_optional(executor)
+  .await?;
+  Ok(row)
+}
+
+/// Returns the encrypted settings of the storage the workspace currently uses, None for the
+/// default storage.
+pub async fn select_workspace_storage_settings<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+) -> Result<Option<Vec<u8>>, AppError> {
+  let settings = sqlx::query_scalar::<_, Option<Vec<u8>>>(
+    r#"
+      SELECT settings FROM af_workspace_storage WHERE workspace_id = $1
+    "#,
+  )
+  .bind(workspace_id)
+  .fetch_optional(executor)
+  .await?;
+  Ok(settings.flatten())
+}
+
+/// Queues the move of the objects of the workspace to the storage with the given encrypted
+/// settings, None for the default storage. Returns false if a relocation of the workspace is
+/// already running.
+pub async fn queue_workspace_storage_relocation<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  target_settings: Option<&[u8]>,
+) -> Result<bool, AppError> {
+  let queued = sqlx::query_scalar::<_, bool>(
+    r#"
+      INSERT INTO af_workspace_storage (workspace_id, target_settings, relocation_status)
+      VALUES ($1, $2, $3)
+      ON CONFLICT (workspace_id) DO UPDATE
+      SET target_settings = EXCLUDED.target_settings,
+          relocation_status = EXCLUDED.relocation_status,
+          relocation_error = NULL,
+          relocated_objects = 0,
+          switched_at = NULL,
+          updated_at = CURRENT_TIMESTAMP
+      WHERE af_workspace_storage.relocation_status NOT IN ($3, $4)
+      RETURNING TRUE
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(target_settings)
+  .bind(WorkspaceStorageRelocationStatus::Copying as i16)
+  .bind(WorkspaceStorageRelocationStatus::Switched as i16)
+  .fetch_optional(executor)
+  .await?;
+  Ok(queued.is_some())
+}
+
+/// Returns the relocations to process: the ones copying, and the switched ones whose workspace
+/// switched to the new storage before `switched_before`.
+pub async fn select_workspace_storage_relocations<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  switched_before: DateTime<Utc>,
+  limit: i64,
+) -> Result<Vec<AFWorkspaceStorageRow>, AppError> {
+  let rows = sqlx::query_as::<_, AFWorkspaceStorageRow>(&format!(
+    r#"
+      SELECT {} FROM af_workspace_storage
+      WHERE relocation_status = $1
+        OR (relocation_status = $2 AND switched_at < $3)
+      ORDER BY updated_at
+      LIMIT $4
+    "#,
+    WORKSPACE_STORAGE_COLUMNS
+  ))
+  .bind(WorkspaceStorageRelocationStatus::Copying as i16)
+  .bind(WorkspaceStorageRelocationStatus::Switched as i16)
+  .bind(switched_before)
+  .bind(limit)
+  .fetch_all(executor)
+  .await?;
+  Ok(rows)
+}
+
+pub async fn update_workspace_storage_relocated_objects<
+  'a,
+  E: Executor<'a, Database = Postgres>,
+>(
+  executor: E,
+  workspace_id: &Uuid,
+  relocated_objects: i64,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      UPDATE af_workspace_storage
+      SET relocated_objects = $2, updated_at = CURRENT_TIMESTAMP
+      WHERE workspace_id = $1
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(relocated_objects)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+/// Makes the workspace use the storage its objects were copied to. The previous settings are kept
+/// until the previous storage has been emptied.
+pub async fn switch_workspace_storage<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      UPDATE af_workspace_storage
+      SET previous_settings = settings,
+          settings = target_settings,
+          target_settings = NULL,
+          relocation_status = $3,
+          switched_at = CURRENT_TIMESTAMP,
+          updated_at = CURRENT_TIMESTAMP
+      WHERE workspace_id = $1 AND relocation_status = $2
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(WorkspaceStorageRelocationStatus::Copying as i16)
+  .bind(WorkspaceStorageRelocationStatus::Switched as i16)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+pub async fn complete_workspace_storage_relocation<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      UPDATE af_workspace_storage
+      SET previous_settings = NULL,
+          relocation_status = $2,
+          relocation_error = NULL,
+          updated_at = CURRENT_TIMESTAMP
+      WHERE workspace_id = $1
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(WorkspaceStorageRelocationStatus::Idle as i16)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
+
+/// Records the error of the relocation. A relocation that failed while copying is abandoned, the
+/// workspace keeps its storage. Once switched, the relocation is retried until the previous
+/// storage has been emptied.
+pub async fn update_workspace_storage_relocation_error<'a, E: Executor<'a, Database = Postgres>>(
+  executor: E,
+  workspace_id: &Uuid,
+  error: &str,
+) -> Result<(), AppError> {
+  sqlx::query(
+    r#"
+      UPDATE af_workspace_storage
+      SET relocation_error = $2,
+          relocation_status = CASE WHEN relocation_status = $3 THEN $4 ELSE relocation_status END,
+          target_settings = CASE WHEN relocation_status = $3 THEN NULL ELSE target_settings END,
+          updated_at = CURRENT_TIMESTAMP
+      WHERE workspace_id = $1
+    "#,
+  )
+  .bind(workspace_id)
+  .bind(error)
+  .bind(WorkspaceStorageRelocationStatus::Copying as i16)
+  .bind(WorkspaceStorageRelocationStatus::Failed as i16)
+  .execute(executor)
+  .await?;
+  Ok(())
+}
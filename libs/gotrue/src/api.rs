@@ -247,6 +247,20 @@ impl Client {
     check_gotrue_result(resp).await
   }
 
+  /// Sends the password recovery email to the user.
+  pub async fn recover(&self, email: &str, redirect_to: Option<String>) -> Result<(), GoTrueError> {
+    let url = format!("{}/recover", self.base_url);
+    let mut req_builder = self.client.request(Method::POST, &url);
+    if let Some(redirect_to) = redirect_to {
+      req_builder = req_builder.header("redirect_to", redirect_to);
+    }
+    let resp = req_builder
+      .json(&serde_json::json!({ "email": email }))
+      .send()
+      .await?;
+    check_gotrue_result(resp).await
+  }
+
   pub async fn admin_list_sso_providers(
     &self,
     access_token: &str,
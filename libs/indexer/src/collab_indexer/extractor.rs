@@ -0,0 +1,141 @@
+use anyhow::anyhow;
+use app_error::AppError;
+use collab::preclude::{Any, Collab, Map, MapRef, Out, ReadTxn};
+use collab_database::database::DatabaseBody;
+use collab_database::entity::FieldType;
+use collab_database::rows::{CELL_FIELD_TYPE, ROW_CELLS};
+use collab_database::template::entity::CELL_DATA;
+use collab_database::workspace_database::NoPersistenceDatabaseCollabService;
+use collab_document::document::DocumentBody;
+use collab_document::error::DocumentError;
+use database::collab::database_row_data;
+use std::sync::Arc;
+
+/// Keys of the folder collab: the views are stored by id in the `views` map of the `folder` root
+/// map, see `FolderBody`.
+const FOLDER: &str = "folder";
+const FOLDER_VIEWS: &str = "views";
+const VIEW_NAME: &str = "name";
+
+/// Extracts the text indexed for a type of collab. An empty text means there is nothing to index.
+pub trait TextExtractor: Send + Sync {
+  fn extract_text(&self, collab: &Collab) -> Result<String, AppError>;
+}
+
+pub struct DocumentTextExtractor;
+
+impl TextExtractor for DocumentTextExtractor {
+  fn extract_text(&self, collab: &Collab) -> Result<String, AppError> {
+    let document = DocumentBody::from_collab(collab).ok_or_else(|| {
+      anyhow!(
+        "Failed to get document body from collab `{}`: schema is missing required fields",
+        collab.object_id()
+      )
+    })?;
+    match document.to_plain_text(collab.transact(), false, true) {
+      Ok(text) => Ok(text),
+      Err(DocumentError::NoRequiredData) => Ok(String::new()),
+      Err(err) => Err(AppError::Internal(err.into())),
+    }
+  }
+}
+
+/// The names of the views and of the fields of the database. The rows are collabs of their own,
+/// see [DatabaseRowTextExtractor].
+pub struct DatabaseTextExtractor;
+
+impl TextExtractor for DatabaseTextExtractor {
+  fn extract_text(&self, collab: &Collab) -> Result<String, AppError> {
+    let body =
+      DatabaseBody::from_collab(collab, Arc::new(NoPersistenceDatabaseCollabService), None)
+        .ok_or_else(|| {
+          anyhow!(
+            "Failed to get database body from collab `{}`",
+            collab.object_id()
+          )
+        })?;
+    let txn = collab.transact();
+    let view_names = body
+      .views
+      .get_all_views(&txn)
+      .into_iter()
+      .map(|view| view.name);
+    let field_names = body
+      .fields
+      .get_all_fields(&txn)
+      .into_iter()
+      .map(|field| field.name);
+    Ok(join_lines(view_names.chain(field_names)))
+  }
+}
+
+/// The text cells of the row. The primary field of a database is always a text field, so they
+/// include the primary cell that names the row.
+pub struct DatabaseRowTextExtractor;
+
+impl TextExtractor for DatabaseRowTextExtractor {
+  fn extract_text(&self, collab: &Collab) -> Result<String, AppError> {
+    let txn = collab.transact();
+    let cells = database_row_data(collab, &txn)
+      .and_then(|data| data.get(&txn, ROW_CELLS))
+      .and_then(|cells| cells.cast::<MapRef>().ok());
+    let Some(cells) = cells else {
+      return Ok(String::new());
+    };
+    let texts = cells
+      .iter(&txn)
+      .filter_map(|(_, cell)| cell.cast::<MapRef>().ok())
+      .filter(|cell| {
+        matches!(
+          cell.get(&txn, CELL_FIELD_TYPE),
+          Some(Out::Any(Any::BigInt(field_type))) if field_type == FieldType::RichText as i64
+        )
+      })
+      .filter_map(|cell| match cell.get(&txn, CELL_DATA) {
+        Some(Out::Any(Any::String(text))) => Some(text.to_string()),
+        _ => None,
+      })
+      .collect::<Vec<_>>();
+    Ok(join_lines(texts))
+  }
+}
+
+/// The names of the views of the workspace.
+pub struct FolderTextExtractor;
+
+impl TextExtractor for FolderTextExtractor {
+  fn extract_text(&self, collab: &Collab) -> Result<String, AppError> {
+    let txn = collab.transact();
+    let views = collab
+      .data
+      .get(&txn, FOLDER)
+      .and_then(|folder| folder.cast::<MapRef>().ok())
+      .and_then(|folder| folder.get(&txn, FOLDER_VIEWS))
+      .and_then(|views| views.cast::<MapRef>().ok());
+    let Some(views) = views else {
+      return Ok(String::new());
+    };
+    let names = views
+      .iter(&txn)
+      .filter_map(|(_, view)| view.cast::<MapRef>().ok())
+      .filter_map(|view| view_name(&view, &txn))
+      .collect::<Vec<_>>();
+    Ok(join_lines(names))
+  }
+}
+
+fn view_name<T: ReadTxn>(view: &MapRef, txn: &T) -> Option<String> {
+  match view.get(txn, VIEW_NAME) {
+    Some(Out::Any(Any::String(name))) => Some(name.to_string()),
+    _ => None,
+  }
+}
+
+fn join_lines(texts: impl IntoIterator<Item = String>) -> String {
+  texts
+    .into_iter()
+    .map(|text| text.trim().to_string())
+    .filter(|text| !text.is_empty())
+    .collect::<Vec<_>>()
+    .join("\n")
+}
@@ -1,5 +1,7 @@
-mod document_indexer;
+mod extractor;
 mod provider;
+mod text_indexer;
 
-pub use document_indexer::*;
+pub use extractor::*;
 pub use provider::*;
+pub use text_indexer::*;
@@ -1,4 +1,7 @@
-use crate::collab_indexer::DocumentIndexer;
+use crate::collab_indexer::{
+  DatabaseRowTextExtractor, DatabaseTextExtractor, DocumentTextExtractor, FolderTextExtractor,
+  TextExtractor, TextIndexer,
+};
 use crate::vector::embedder::Embedder;
 use app_error::AppError;
 use appflowy_ai_client::dto::EmbeddingModel;
@@ -6,11 +9,14 @@ use collab::preclude::Collab;
 use collab_entity::CollabType;
 use database_entity::dto::{AFCollabEmbeddedChunk, AFCollabEmbeddings};
 use infra::env_util::get_env_var;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tracing::info;
 
 pub trait Indexer: Send + Sync {
+  /// Returns the text of the collab that is indexed, empty if there is nothing to index.
+  fn extract_text(&self, collab: &Collab) -> Result<String, AppError>;
+
   fn create_embedded_chunks_from_collab(
     &self,
     collab: &Collab,
@@ -29,6 +35,30 @@ pub trait Indexer: Send + Sync {
     embedder: &Embedder,
     content: Vec<AFCollabEmbeddedChunk>,
   ) -> Result<Option<AFCollabEmbeddings>, AppError>;
+
+  /// Embeds the chunks whose fragment has no embedding yet. The other chunks are returned without
+  /// embedding, their fragment keeps the embedding it has.
+  fn embed_changed(
+    &self,
+    embedder: &Embedder,
+    content: Vec<AFCollabEmbeddedChunk>,
+    embedded_fragment_ids: &HashSet<String>,
+  ) -> Result<Option<AFCollabEmbeddings>, AppError> {
+    let (unchanged, changed): (Vec<_>, Vec<_>) = content
+      .into_iter()
+      .partition(|chunk| embedded_fragment_ids.contains(&chunk.fragment_id));
+    let mut embeddings = self
+      .embed(embedder, changed)?
+      .unwrap_or_else(|| AFCollabEmbeddings {
+        tokens_consumed: 0,
+        params: vec![],
+      });
+    embeddings.params.extend(unchanged);
+    if embeddings.params.is_empty() {
+      return Ok(None);
+    }
+    Ok(Some(embeddings))
+  }
 }
 
 /// A structure responsible for resolving different [Indexer] types for different [CollabType]s,
@@ -39,18 +69,29 @@ pub struct IndexerProvider {
 
 impl IndexerProvider {
   pub fn new() -> Arc<Self> {
-    let mut cache: HashMap<CollabType, Arc<dyn Indexer>> = HashMap::new();
+    let cache: HashMap<CollabType, Arc<dyn Indexer>> = HashMap::new();
     let enabled = get_env_var("APPFLOWY_INDEXER_ENABLED", "true")
       .parse::<bool>()
       .unwrap_or(true);
 
     info!("Indexer is enabled: {}", enabled);
+    let mut provider = Self {
+      indexer_cache: cache,
+    };
     if enabled {
-      cache.insert(CollabType::Document, Arc::new(DocumentIndexer));
+      provider.register(CollabType::Document, Arc::new(DocumentTextExtractor));
+      provider.register(CollabType::Database, Arc::new(DatabaseTextExtractor));
+      provider.register(CollabType::DatabaseRow, Arc::new(DatabaseRowTextExtractor));
+      provider.register(CollabType::Folder, Arc::new(FolderTextExtractor));
     }
-    Arc::new(Self {
-      indexer_cache: cache,
-    })
+    Arc::new(provider)
+  }
+
+  /// Indexes the collabs of the given type with the text the extractor returns. Replaces the
+  /// extractor previously registered for the type.
+  pub fn register(&mut self, collab_type: CollabType, extractor: Arc<dyn TextExtractor>) {
+    let indexer = TextIndexer::new(collab_type.clone(), extractor);
+    self.indexer_cache.insert(collab_type, Arc::new(indexer));
   }
 
   /// Returns indexer for a specific type of [Collab] object.
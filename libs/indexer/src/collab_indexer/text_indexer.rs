@@ -0,0 +1,251 @@
+use crate::collab_indexer::{Indexer, TextExtractor};
+use crate::vector::embedder::Embedder;
+use crate::vector::open_ai::split_text_by_max_content_len;
+use app_error::AppError;
+use appflowy_ai_client::dto::{
+  EmbeddingEncodingFormat, EmbeddingInput, EmbeddingModel, EmbeddingOutput, EmbeddingRequest,
+};
+use async_trait::async_trait;
+use collab::preclude::Collab;
+use collab_entity::CollabType;
+use database_entity::dto::{AFCollabEmbeddedChunk, AFCollabEmbeddings, EmbeddingContentType};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::trace;
+
+/// We assume that every token is ~4 bytes. The chunks are at most ~2000 tokens each.
+const MAX_CHUNK_LEN: usize = 8000;
+/// A chunk longer than this is closed after the next line whose hash is a multiple of
+/// [CHUNK_BOUNDARY_MODULO]. The boundaries depend on the lines around them only, so an edit changes
+/// the chunk it is made in and leaves the other chunks, and the embeddings of their fragments, as
+/// they are.
+const MIN_CHUNK_LEN: usize = 2000;
+const CHUNK_BOUNDARY_MODULO: u8 = 4;
+
+/// Indexes the text the [TextExtractor] of the collab type extracts from the collabs.
+pub struct TextIndexer {
+  collab_type: CollabType,
+  extractor: Arc<dyn TextExtractor>,
+}
+
+impl TextIndexer {
+  pub fn new(collab_type: CollabType, extractor: Arc<dyn TextExtractor>) -> Self {
+    Self {
+      collab_type,
+      extractor,
+    }
+  }
+}
+
+#[async_trait]
+impl Indexer for TextIndexer {
+  fn extract_text(&self, collab: &Collab) -> Result<String, AppError> {
+    self.extractor.extract_text(collab)
+  }
+
+  fn create_embedded_chunks_from_collab(
+    &self,
+    collab: &Collab,
+    embedding_model: EmbeddingModel,
+  ) -> Result<Vec<AFCollabEmbeddedChunk>, AppError> {
+    let object_id = collab.object_id().to_string();
+    let content = self.extract_text(collab)?;
+    self.create_embedded_chunks_from_text(object_id, content, embedding_model)
+  }
+
+  fn create_embedded_chunks_from_text(
+    &self,
+    object_id: String,
+    text: String,
+    model: EmbeddingModel,
+  ) -> Result<Vec<AFCollabEmbeddedChunk>, AppError> {
+    split_text_into_chunks(object_id, text, self.collab_type.clone(), &model)
+  }
+
+  fn embed(
+    &self,
+    embedder: &Embedder,
+    mut content: Vec<AFCollabEmbeddedChunk>,
+  ) -> Result<Option<AFCollabEmbeddings>, AppError> {
+    if content.is_empty() {
+      return Ok(None);
+    }
+
+    let contents: Vec<_> = content
+      .iter()
+      .map(|fragment| fragment.content.clone())
+      .collect();
+    let resp = embedder.embed(EmbeddingRequest {
+      input: EmbeddingInput::StringArray(contents),
+      model: embedder.model().name().to_string(),
+      encoding_format: EmbeddingEncodingFormat::Float,
+      dimensions: EmbeddingModel::TextEmbedding3Small.default_dimensions(),
+    })?;
+
+    trace!(
+      "[Embedding] request {} embeddings, received {} embeddings",
+      content.len(),
+      resp.data.len()
+    );
+
+    for embedding in resp.data {
+      let param = &mut content[embedding.index as usize];
+      let embedding: Vec<f32> = match embedding.embedding {
+        EmbeddingOutput::Float(embedding) => embedding.into_iter().map(|f| f as f32).collect(),
+        EmbeddingOutput::Base64(_) => {
+          return Err(AppError::OpenError(
+            "Unexpected base64 encoding".to_string(),
+          ))
+        },
+      };
+      param.embedding = Some(embedding);
+    }
+
+    Ok(Some(AFCollabEmbeddings {
+      tokens_consumed: resp.usage.total_tokens as u32,
+      params: content,
+    }))
+  }
+}
+
+fn split_text_into_chunks(
+  object_id: String,
+  content: String,
+  collab_type: CollabType,
+  embedding_model: &EmbeddingModel,
+) -> Result<Vec<AFCollabEmbeddedChunk>, AppError> {
+  debug_assert!(matches!(
+    embedding_model,
+    EmbeddingModel::TextEmbedding3Small
+  ));
+
+  if content.is_empty() {
+    return Ok(vec![]);
+  }
+  let split_contents = split_text_into_line_chunks(&content)?;
+  let name = match collab_type {
+    CollabType::Document => "document",
+    CollabType::Database => "database",
+    CollabType::DatabaseRow => "database_row",
+    CollabType::Folder => "folder",
+    _ => "collab",
+  };
+  let metadata =
+    json!({"id": object_id, "source": "appflowy", "name": name, "collab_type": collab_type });
+  // chunks with the same content would have the same fragment, they are indexed once
+  let mut seen = HashSet::new();
+  Ok(
+    split_contents
+      .into_iter()
+      .map(|content| (fragment_id(&object_id, &content), content))
+      .filter(|(fragment_id, _)| seen.insert(fragment_id.clone()))
+      .enumerate()
+      .map(|(index, (fragment_id, content))| AFCollabEmbeddedChunk {
+        fragment_id,
+        object_id: object_id.clone(),
+        content_type: EmbeddingContentType::PlainText,
+        content,
+        embedding: None,
+        metadata: metadata.clone(),
+        fragment_index: index as i32,
+        embedded_type: 0,
+      })
+      .collect(),
+  )
+}
+
+/// The id of a fragment is derived from its content, a fragment that already has an embedding
+/// doesn't need to be embedded again when the collab is reindexed.
+fn fragment_id(object_id: &str, content: &str) -> String {
+  let digest = Sha256::new()
+    .chain_update(object_id.as_bytes())
+    .chain_update([0u8])
+    .chain_update(content.as_bytes())
+    .finalize();
+  format!("{:x}", digest)
+}
+
+fn split_text_into_line_chunks(content: &str) -> Result<Vec<String>, AppError> {
+  let mut chunks = vec![];
+  let mut chunk = String::new();
+  for line in content.lines() {
+    if !chunk.is_empty() && chunk.len() + line.len() + 1 > MAX_CHUNK_LEN {
+      chunks.push(std::mem::take(&mut chunk));
+    }
+    if line.len() > MAX_CHUNK_LEN {
+      chunks.extend(split_text_by_max_content_len(
+        line.to_string(),
+        MAX_CHUNK_LEN,
+      )?);
+      continue;
+    }
+    if !chunk.is_empty() {
+      chunk.push('\n');
+    }
+    chunk.push_str(line);
+    if chunk.len() >= MIN_CHUNK_LEN && is_chunk_boundary(line) {
+      chunks.push(std::mem::take(&mut chunk));
+    }
+  }
+  if !chunk.is_empty() {
+    chunks.push(chunk);
+  }
+  Ok(chunks)
+}
+
+fn is_chunk_boundary(line: &str) -> bool {
+  Sha256::digest(line.as_bytes())[0] % CHUNK_BOUNDARY_MODULO == 0
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn paragraphs(count: usize) -> Vec<String> {
+    (0..count)
+      .map(|i| {
+        format!(
+          "paragraph {} {}",
+          i,
+          "lorem ipsum dolor sit amet ".repeat(8)
+        )
+      })
+      .collect()
+  }
+
+  fn fragment_ids(lines: &[String]) -> Vec<String> {
+    split_text_into_chunks(
+      "object".to_string(),
+      lines.join("\n"),
+      CollabType::Document,
+      &EmbeddingModel::TextEmbedding3Small,
+    )
+    .unwrap()
+    .into_iter()
+    .map(|chunk| chunk.fragment_id)
+    .collect()
+  }
+
+  #[test]
+  fn chunks_are_cut_at_lines() {
+    let lines = paragraphs(200);
+    let chunks = split_text_into_line_chunks(&lines.join("\n")).unwrap();
+    assert!(chunks.len() > 1);
+    assert!(chunks.iter().all(|chunk| chunk.len() <= MAX_CHUNK_LEN));
+    assert_eq!(chunks.join("\n"), lines.join("\n"));
+  }
+
+  #[test]
+  fn edit_only_changes_the_fragments_around_it() {
+    let mut lines = paragraphs(200);
+    let before = fragment_ids(&lines);
+    lines[100].push_str(" edited");
+    let after = fragment_ids(&lines);
+    // the edited line may become or stop being a boundary, merging or splitting its chunk
+    let changed = after.iter().filter(|id| !before.contains(id)).count();
+    assert!((1..=2).contains(&changed));
+    assert!(before.len() > 4);
+  }
+}
@@ -9,7 +9,7 @@ use serde_json::from_str;
 use tracing::error;
 
 pub const INDEX_TASK_STREAM_NAME: &str = "index_collab_task_stream";
-const INDEXER_WORKER_GROUP_NAME: &str = "indexer_worker_group";
+pub const INDEXER_WORKER_GROUP_NAME: &str = "indexer_worker_group";
 const INDEXER_CONSUMER_NAME: &str = "appflowy_worker";
 
 impl TryFrom<&StreamId> for UnindexedCollabTask {
@@ -9,10 +9,11 @@ use crate::vector::open_ai;
 use app_error::AppError;
 use appflowy_ai_client::dto::{EmbeddingRequest, OpenAIEmbeddingResponse};
 use collab::preclude::Collab;
-use collab_document::document::DocumentBody;
 use collab_entity::CollabType;
 use database::collab::CollabStorage;
-use database::index::{update_collab_indexed_at, upsert_collab_embeddings};
+use database::index::{
+  select_embedded_fragment_ids, update_collab_indexed_at, upsert_collab_embeddings,
+};
 use database::workspace::select_workspace_settings;
 use database_entity::dto::AFCollabEmbeddedChunk;
 use infra::env_util::get_env_var;
@@ -254,32 +255,38 @@ impl IndexerScheduler {
       return Ok(());
     }
 
-    match collab_type {
-      CollabType::Document => {
-        let txn = collab.transact();
-        let text = DocumentBody::from_collab(collab)
-          .and_then(|body| body.to_plain_text(txn, false, true).ok());
-
-        if let Some(text) = text {
-          if !text.is_empty() {
-            let pending = UnindexedCollabTask::new(
-              Uuid::parse_str(workspace_id)?,
-              object_id.to_string(),
-              collab_type.clone(),
-              UnindexedData::Text(text),
-            );
-            self.embed_immediately(pending)?;
-          }
-        }
-      },
-      _ => {
-        // TODO(nathan): support other collab types
-      },
+    if let Some(text) = self.extract_text(collab_type, collab) {
+      let pending = UnindexedCollabTask::new(
+        Uuid::parse_str(workspace_id)?,
+        object_id.to_string(),
+        collab_type.clone(),
+        UnindexedData::Text(text),
+      );
+      self.embed_immediately(pending)?;
     }
 
     Ok(())
   }
 
+  /// Returns the text indexed for the collab, or `None` if the collab type is not indexed or
+  /// there is nothing to index.
+  pub fn extract_text(&self, collab_type: &CollabType, collab: &Collab) -> Option<String> {
+    let indexer = self.indexer_provider.indexer_for(collab_type)?;
+    match indexer.extract_text(collab) {
+      Ok(text) if !text.is_empty() => Some(text),
+      Ok(_) => None,
+      Err(err) => {
+        warn!(
+          "failed to extract the text of {} collab {}: {}",
+          collab_type,
+          collab.object_id(),
+          err
+        );
+        None
+      },
+    }
+  }
+
   pub async fn can_index_workspace(&self, workspace_id: &str) -> Result<bool, AppError> {
     if !self.index_enabled() {
       return Ok(false);
@@ -332,6 +339,7 @@ async fn spawn_rayon_generate_embeddings(
       "[Embedding] received {} embeddings to generate",
       records.len()
     );
+    let embedded_fragment_ids = load_embedded_fragment_ids(&scheduler.pg_pool, &records).await;
     let metrics = scheduler.metrics.clone();
     let threads = scheduler.threads.clone();
     let indexer_provider = scheduler.indexer_provider.clone();
@@ -343,7 +351,14 @@ async fn spawn_rayon_generate_embeddings(
           records.into_par_iter().for_each(|record| {
             let result = threads.install(|| {
               let indexer = indexer_provider.indexer_for(&record.collab_type);
-              match process_collab(&embedder, indexer, &record.object_id, record.data, &metrics) {
+              match process_collab(
+                &embedder,
+                indexer,
+                &record.object_id,
+                record.data,
+                &embedded_fragment_ids,
+                &metrics,
+              ) {
                 Ok(Some((tokens_used, contents))) => {
                   if let Err(err) = write_embedding_tx.send(EmbeddingRecord {
                     workspace_id: record.workspace_id,
@@ -479,12 +494,31 @@ pub(crate) async fn batch_insert_records(
   Ok(())
 }
 
+/// Returns the fragments of the collabs that already have an embedding. When they can't be
+/// loaded, all the fragments are embedded again.
+pub async fn load_embedded_fragment_ids(
+  pg_pool: &PgPool,
+  tasks: &[UnindexedCollabTask],
+) -> HashSet<String> {
+  let object_ids = tasks
+    .iter()
+    .map(|task| task.object_id.clone())
+    .collect::<Vec<_>>();
+  select_embedded_fragment_ids(pg_pool, &object_ids)
+    .await
+    .unwrap_or_else(|err| {
+      warn!("[Embedding] failed to load the embedded fragments: {}", err);
+      HashSet::new()
+    })
+}
+
 /// This function must be called within the rayon thread pool.
 fn process_collab(
   embedder: &Embedder,
   indexer: Option<Arc<dyn Indexer>>,
   object_id: &str,
   data: UnindexedData,
+  embedded_fragment_ids: &HashSet<String>,
   metrics: &EmbeddingMetrics,
 ) -> Result<Option<(u32, Vec<AFCollabEmbeddedChunk>)>, AppError> {
   if let Some(indexer) = indexer {
@@ -499,7 +533,7 @@ fn process_collab(
     }
 
     metrics.record_embed_count(1);
-    let result = indexer.embed(embedder, chunks);
+    let result = indexer.embed_changed(embedder, chunks, embedded_fragment_ids);
     match result {
       Ok(Some(embeddings)) => Ok(Some((embeddings.tokens_consumed, embeddings.params))),
       Ok(None) => Ok(None),
@@ -188,18 +188,20 @@ where
     let mut this = self.project();
 
     loop {
+      // A chunk can contain several lines, return the buffered ones before polling for more data
+      if let Some(pos) = this.buffer.iter().position(|&b| b == b'\n') {
+        let line = this.buffer.split_to(pos + 1);
+        let line = &line[..line.len() - 1]; // Remove the newline character
+
+        match String::from_utf8(line.to_vec()) {
+          Ok(value) => return Poll::Ready(Some(Ok(value))),
+          Err(err) => return Poll::Ready(Some(Err(E::from(err)))),
+        }
+      }
+
       match ready!(this.stream.as_mut().poll_next(cx)) {
         Some(Ok(bytes)) => {
           this.buffer.extend_from_slice(&bytes);
-          if let Some(pos) = this.buffer.iter().position(|&b| b == b'\n') {
-            let line = this.buffer.split_to(pos + 1);
-            let line = &line[..line.len() - 1]; // Remove the newline character
-
-            match String::from_utf8(line.to_vec()) {
-              Ok(value) => return Poll::Ready(Some(Ok(value))),
-              Err(err) => return Poll::Ready(Some(Err(E::from(err)))),
-            }
-          }
         },
         Some(Err(err)) => return Poll::Ready(Some(Err(err))),
         None => {
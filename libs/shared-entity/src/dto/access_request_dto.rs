@@ -4,6 +4,7 @@ use uuid::Uuid;
 
 use super::workspace_dto::{ViewIcon, ViewLayout};
 
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Serialize, Deserialize, Debug)]
 pub struct AccessRequestView {
   pub view_id: String,
@@ -12,6 +13,7 @@ pub struct AccessRequestView {
   pub layout: ViewLayout,
 }
 
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Serialize, Deserialize, Debug)]
 pub struct AccessRequest {
   pub request_id: Uuid,
@@ -0,0 +1,239 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::publish_dto::PublishReportReason;
+
+/// Maximum number of users a single bulk request can act on.
+pub const MAX_ADMIN_BULK_USERS: usize = 100;
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AdminBulkUserParams {
+  pub user_uuids: Vec<Uuid>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AdminBulkUserResult {
+  pub succeeded: Vec<Uuid>,
+  pub failed: Vec<AdminBulkUserFailure>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AdminBulkUserFailure {
+  pub user_uuid: Uuid,
+  pub error: String,
+}
+
+/// Stored in the `action` column of the audit log.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AdminAuditAction {
+  DeactivateUser,
+  DeleteUser,
+  SendPasswordReset,
+  ExportUsers,
+  DismissPublishReport,
+  UnpublishReportedView,
+  RelocateWorkspaceStorage,
+}
+
+impl AdminAuditAction {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      AdminAuditAction::DeactivateUser => "deactivate_user",
+      AdminAuditAction::DeleteUser => "delete_user",
+      AdminAuditAction::SendPasswordReset => "send_password_reset",
+      AdminAuditAction::ExportUsers => "export_users",
+      AdminAuditAction::DismissPublishReport => "dismiss_publish_report",
+      AdminAuditAction::UnpublishReportedView => "unpublish_reported_view",
+      AdminAuditAction::RelocateWorkspaceStorage => "relocate_workspace_storage",
+    }
+  }
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AdminAuditLog {
+  pub id: i64,
+  /// The administrator who performed the action.
+  pub actor_uuid: Uuid,
+  pub action: String,
+  /// Uuid of the user, abuse report or workspace the action was applied to, `*` when it targets
+  /// all the users.
+  pub target: String,
+  pub succeeded: bool,
+  pub detail: Option<String>,
+  pub created_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AdminAuditLogQuery {
+  #[serde(default)]
+  pub offset: Option<i64>,
+  #[serde(default)]
+  pub limit: Option<i64>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PublishReportStatus {
+  Open = 0,
+  Dismissed = 1,
+  /// The reported view was unpublished by an administrator.
+  Unpublished = 2,
+}
+
+impl PublishReportStatus {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      PublishReportStatus::Open => "open",
+      PublishReportStatus::Dismissed => "dismissed",
+      PublishReportStatus::Unpublished => "unpublished",
+    }
+  }
+}
+
+impl From<i16> for PublishReportStatus {
+  fn from(val: i16) -> Self {
+    match val {
+      1 => PublishReportStatus::Dismissed,
+      2 => PublishReportStatus::Unpublished,
+      _ => PublishReportStatus::Open,
+    }
+  }
+}
+
+/// An abuse report on a published view, in the review queue of the administrators.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PublishedViewReport {
+  pub report_id: Uuid,
+  pub view_id: Uuid,
+  pub workspace_id: Uuid,
+  /// None when the view was reported by a visitor who is not signed in.
+  pub reporter_uuid: Option<Uuid>,
+  pub reason: PublishReportReason,
+  pub details: Option<String>,
+  pub status: PublishReportStatus,
+  /// The administrator who reviewed the report.
+  pub reviewed_by: Option<Uuid>,
+  pub reviewed_at: Option<DateTime<Utc>>,
+  pub created_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PublishReportQuery {
+  #[serde(default)]
+  pub offset: Option<i64>,
+  #[serde(default)]
+  pub limit: Option<i64>,
+  /// Only the open reports are returned by default.
+  #[serde(default)]
+  pub status: Option<PublishReportStatus>,
+}
+
+/// Bucket holding the objects of a workspace instead of the default storage of the instance, e.g.
+/// to keep the data of the workspace in a given region. Stored encrypted, the keys are never
+/// returned by the API.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WorkspaceStorageSettings {
+  pub bucket: String,
+  pub region: String,
+  /// Endpoint of an S3 compatible storage. The AWS endpoint of the region is used when not set.
+  #[serde(default)]
+  pub endpoint: Option<String>,
+  /// The keys of the default S3 storage of the instance are used when not set.
+  #[serde(default)]
+  pub access_key: Option<String>,
+  #[serde(default)]
+  pub secret_key: Option<String>,
+}
+
+impl WorkspaceStorageSettings {
+  /// Whether both settings point to the same bucket, whatever the keys used to access it.
+  pub fn is_same_bucket(&self, other: &Self) -> bool {
+    self.bucket == other.bucket && self.endpoint == other.endpoint
+  }
+
+  pub fn location(&self) -> WorkspaceStorageLocation {
+    WorkspaceStorageLocation {
+      bucket: self.bucket.clone(),
+      region: self.region.clone(),
+      endpoint: self.endpoint.clone(),
+    }
+  }
+}
+
+impl std::fmt::Debug for WorkspaceStorageSettings {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("WorkspaceStorageSettings")
+      .field("bucket", &self.bucket)
+      .field("region", &self.region)
+      .field("endpoint", &self.endpoint)
+      .field("access_key", &self.access_key)
+      .field("secret_key", &self.secret_key.as_ref().map(|_| "***"))
+      .finish()
+  }
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UpdateWorkspaceStorageParams {
+  /// The storage the objects of the workspace are moved to, None to move them back to the default
+  /// storage of the instance.
+  #[serde(default)]
+  pub settings: Option<WorkspaceStorageSettings>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkspaceStorageRelocationStatus {
+  Idle = 0,
+  /// The objects are being copied to the target storage, the workspace still uses its current
+  /// storage.
+  Copying = 1,
+  /// The workspace uses its new storage. The objects written to the previous storage while they
+  /// were being copied are copied over, then the previous storage is emptied.
+  Switched = 2,
+  /// The copy failed, the workspace kept its storage.
+  Failed = 3,
+}
+
+impl From<i16> for WorkspaceStorageRelocationStatus {
+  fn from(val: i16) -> Self {
+    match val {
+      1 => WorkspaceStorageRelocationStatus::Copying,
+      2 => WorkspaceStorageRelocationStatus::Switched,
+      3 => WorkspaceStorageRelocationStatus::Failed,
+      _ => WorkspaceStorageRelocationStatus::Idle,
+    }
+  }
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WorkspaceStorageLocation {
+  pub bucket: String,
+  pub region: String,
+  pub endpoint: Option<String>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WorkspaceStorageInfo {
+  pub workspace_id: Uuid,
+  /// None when the workspace uses the default storage of the instance.
+  pub location: Option<WorkspaceStorageLocation>,
+  /// The storage the objects are being copied to while the relocation is
+  /// [WorkspaceStorageRelocationStatus::Copying], None for the default storage.
+  pub target: Option<WorkspaceStorageLocation>,
+  pub relocation_status: WorkspaceStorageRelocationStatus,
+  pub relocated_objects: i64,
+  pub relocation_error: Option<String>,
+  pub updated_at: Option<DateTime<Utc>>,
+}
@@ -10,6 +10,7 @@ pub struct SignInParams {
   pub password: String,
 }
 
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Default, Deserialize, Serialize, Clone)]
 pub struct UserMetaData(HashMap<String, serde_json::Value>);
 impl UserMetaData {
@@ -25,6 +26,7 @@ impl UserMetaData {
   }
 }
 
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(serde::Deserialize, serde::Serialize, Default)]
 pub struct UpdateUserParams {
   pub name: Option<String>,
@@ -61,13 +63,51 @@ pub struct SignInPasswordResponse {
   pub is_new: bool,
 }
 
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(serde::Deserialize, serde::Serialize)]
 pub struct SignInTokenResponse {
   pub is_new: bool,
 }
 
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams), into_params(parameter_in = Query))]
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
 pub struct DeleteUserQuery {
   pub provider_access_token: Option<String>,
   pub provider_refresh_token: Option<String>,
 }
+
+/// Changes the email of the current user. GoTrue sends a confirmation link to the new address and
+/// the email only changes once the link is opened.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeEmailParams {
+  pub new_email: String,
+}
+
+/// Sends a password recovery link, or a magic link to sign in, to the email.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailLinkParams {
+  pub email: String,
+  /// Where the link redirects to once opened, `appflowy-flutter://` by default.
+  #[serde(default)]
+  pub redirect_to: Option<String>,
+}
+
+/// Actions recorded in the audit log of the authentication flows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UserAuthAction {
+  ChangeEmail,
+  RecoverPassword,
+  MagicLink,
+}
+
+impl UserAuthAction {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      UserAuthAction::ChangeEmail => "change_email",
+      UserAuthAction::RecoverPassword => "recover_password",
+      UserAuthAction::MagicLink => "magic_link",
+    }
+  }
+}
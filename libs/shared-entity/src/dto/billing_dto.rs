@@ -1,7 +1,9 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
+use uuid::Uuid;
 
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum RecurringInterval {
@@ -30,6 +32,7 @@ impl TryFrom<i16> for RecurringInterval {
   }
 }
 
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "snake_case")]
 #[repr(i16)]
@@ -84,6 +87,7 @@ impl TryFrom<&str> for SubscriptionPlan {
   }
 }
 
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Copy, Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum SubscriptionStatus {
@@ -97,6 +101,47 @@ pub enum SubscriptionStatus {
   Unpaid,
 }
 
+impl SubscriptionStatus {
+  pub fn as_str(&self) -> &str {
+    match self {
+      SubscriptionStatus::Active => "active",
+      SubscriptionStatus::Canceled => "canceled",
+      SubscriptionStatus::Incomplete => "incomplete",
+      SubscriptionStatus::IncompleteExpired => "incomplete_expired",
+      SubscriptionStatus::PastDue => "past_due",
+      SubscriptionStatus::Paused => "paused",
+      SubscriptionStatus::Trialing => "trialing",
+      SubscriptionStatus::Unpaid => "unpaid",
+    }
+  }
+
+  /// The subscriptions past due keep their plan while the payment is retried.
+  pub fn grants_plan(&self) -> bool {
+    matches!(
+      self,
+      SubscriptionStatus::Active | SubscriptionStatus::Trialing | SubscriptionStatus::PastDue
+    )
+  }
+}
+
+impl TryFrom<&str> for SubscriptionStatus {
+  type Error = String;
+
+  fn try_from(value: &str) -> Result<Self, Self::Error> {
+    match value {
+      "active" => Ok(SubscriptionStatus::Active),
+      "canceled" => Ok(SubscriptionStatus::Canceled),
+      "incomplete" => Ok(SubscriptionStatus::Incomplete),
+      "incomplete_expired" => Ok(SubscriptionStatus::IncompleteExpired),
+      "past_due" => Ok(SubscriptionStatus::PastDue),
+      "paused" => Ok(SubscriptionStatus::Paused),
+      "trialing" => Ok(SubscriptionStatus::Trialing),
+      "unpaid" => Ok(SubscriptionStatus::Unpaid),
+      _ => Err(format!("Invalid SubscriptionStatus value: {}", value)),
+    }
+  }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct WorkspaceSubscriptionStatus {
   pub workspace_id: String,
@@ -128,6 +173,46 @@ pub struct WorkspaceUsageAndLimit {
   pub ai_responses_unlimited: bool,
 }
 
+/// Plan, usage and renewal date of a workspace, as enforced by the server.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WorkspaceBilling {
+  pub workspace_id: Uuid,
+  pub plan: SubscriptionPlan,
+  /// Active add-ons, like AI Max.
+  pub add_ons: Vec<SubscriptionPlan>,
+  /// None on the free plan.
+  pub status: Option<SubscriptionStatus>,
+  pub recurring_interval: Option<RecurringInterval>,
+  /// Set when the plan comes from the subscription of the organization of the workspace.
+  pub org_id: Option<Uuid>,
+  pub renewal_date: Option<DateTime<Utc>>,
+  pub cancel_at: Option<DateTime<Utc>>,
+  pub usage: WorkspacePlanUsage,
+  pub limits: WorkspacePlanLimits,
+  /// Set when the workspace is over the limits of its plan. Its content can be read and exported,
+  /// but not changed, until the usage is back within the limits.
+  #[serde(default)]
+  pub read_only_reason: Option<String>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WorkspacePlanUsage {
+  pub member_count: i64,
+  pub storage_bytes: i64,
+  pub ai_responses_this_month: i64,
+}
+
+/// None means unlimited. All limits are None when the server does not enforce billing.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct WorkspacePlanLimits {
+  pub member_count: Option<i64>,
+  pub storage_bytes: Option<i64>,
+  pub ai_responses_per_month: Option<i64>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SubscriptionCancelRequest {
   pub workspace_id: String,
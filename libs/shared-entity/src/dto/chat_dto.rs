@@ -186,6 +186,70 @@ impl Display for ContextLoader {
   }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum ChatAttachmentStatus {
+  /// The text of the file is being extracted and embedded.
+  Processing = 0,
+  /// The file can be used when answering the questions of the chat.
+  Ready = 1,
+  Failed = 2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatMessageFeedbackRating {
+  ThumbsUp,
+  ThumbsDown,
+}
+
+#[derive(Debug, Clone, Validate, Serialize, Deserialize)]
+pub struct ChatMessageFeedbackParams {
+  pub rating: ChatMessageFeedbackRating,
+  #[validate(length(max = 2000))]
+  #[serde(default)]
+  pub comment: Option<String>,
+}
+
+/// Feedback given by a user to an answer of the AI, as exported to the admins.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessageFeedback {
+  pub message_id: i64,
+  pub uid: i64,
+  pub chat_id: Uuid,
+  pub workspace_id: Uuid,
+  pub rating: ChatMessageFeedbackRating,
+  pub comment: Option<String>,
+  /// Content of the rated answer.
+  pub answer: String,
+  pub created_at: DateTime<Utc>,
+  pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChatMessageFeedbackQuery {
+  /// Only the feedback updated at or after this time.
+  pub from: Option<DateTime<Utc>>,
+  /// Only the feedback updated before this time.
+  pub to: Option<DateTime<Utc>>,
+  pub workspace_id: Option<Uuid>,
+  pub offset: Option<i64>,
+  pub limit: Option<i64>,
+}
+
+/// A file uploaded as the context of a chat.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatAttachment {
+  pub attachment_id: Uuid,
+  pub chat_id: Uuid,
+  pub file_name: String,
+  pub content_type: String,
+  pub file_size: i64,
+  pub status: ChatAttachmentStatus,
+  pub error: Option<String>,
+  pub created_at: DateTime<Utc>,
+}
+
 impl ChatRAGData {
   pub fn new_text(content: String) -> Self {
     let size = content.len();
@@ -325,6 +389,54 @@ pub struct RepeatedChatMessageWithAuthorUuid {
   pub total: i64,
 }
 
+/// A question with its answer, followed by the questions asked shortly after the previous message,
+/// which are treated as follow-ups of the same conversation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatThread {
+  /// Id of the first message of the thread.
+  pub thread_id: i64,
+  /// Messages of the thread, oldest first.
+  pub messages: Vec<ChatMessage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepeatedChatThread {
+  /// Threads, newest first.
+  pub threads: Vec<ChatThread>,
+  pub has_more: bool,
+  /// Number of threads in the chat.
+  pub total: i64,
+}
+
+#[derive(Debug, Clone, Validate, Serialize, Deserialize)]
+pub struct GetChatThreadParams {
+  /// Only return the threads that started before this thread.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub before: Option<i64>,
+  #[validate(range(min = 1, max = 100))]
+  pub limit: u64,
+}
+
+#[derive(Debug, Clone, Validate, Serialize, Deserialize)]
+pub struct SearchChatMessageParams {
+  #[validate(custom(function = "validate_not_empty_str"))]
+  pub q: String,
+  #[serde(default = "default_search_chat_message_limit")]
+  #[validate(range(min = 1, max = 100))]
+  pub limit: u64,
+}
+
+fn default_search_chat_message_limit() -> u64 {
+  20
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessageSearchResult {
+  pub message: ChatMessage,
+  /// Part of the content that matches the query, with the matching words wrapped in `<b>`.
+  pub snippet: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatSettings {
   // Currently we have not used the `name` field in the ChatSettings
@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportTaskDetail {
+  pub task_id: String,
+  pub object_id: String,
+  /// Format of the exported file, e.g. "pdf"
+  pub format: String,
+  /// 0: pending, 1: completed, 2: failed
+  pub status: i16,
+  /// Presigned url of the exported file, only available once the task is completed.
+  pub download_url: Option<String>,
+  pub error: Option<String>,
+  pub created_at: i64,
+}
@@ -0,0 +1,27 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct FeatureFlag {
+  pub name: String,
+  pub enabled: bool,
+  /// Percentage of the workspaces, between 0 and 100, for which the flag is on when enabled.
+  pub rollout_percentage: i16,
+  pub description: String,
+  pub updated_at: DateTime<Utc>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UpsertFeatureFlagParams {
+  pub enabled: bool,
+  #[serde(default = "default_rollout_percentage")]
+  pub rollout_percentage: i16,
+  #[serde(default)]
+  pub description: String,
+}
+
+fn default_rollout_percentage() -> i16 {
+  100
+}
@@ -4,3 +4,30 @@ use serde::{Deserialize, Serialize};
 pub struct PutFileResponse {
   pub file_id: String,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct StoragePrefixAuditQuery {
+  /// The object id to continue the scan of the collabs from, as returned in
+  /// [StoragePrefixAuditReport::next_cursor].
+  pub cursor: Option<String>,
+  pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoragePrefixAuditReport {
+  pub workspace_id: String,
+  /// Blob metadata keys that can not be turned into an object key under the workspace prefix.
+  /// Only reported in the first page of the scan.
+  pub invalid_blob_keys: Vec<String>,
+  pub scanned_collabs: usize,
+  pub cross_workspace_references: Vec<CrossWorkspaceBlobReference>,
+  /// Set when there are more collabs to scan.
+  pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CrossWorkspaceBlobReference {
+  pub object_id: String,
+  pub referenced_workspace_id: String,
+  pub url_path: String,
+}
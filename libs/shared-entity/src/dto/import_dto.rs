@@ -1,3 +1,4 @@
+use database_entity::dto::ImportSummary;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -12,4 +13,18 @@ pub struct ImportTaskDetail {
   pub file_size: u64,
   pub created_at: i64,
   pub status: i16,
+  /// Set once the import is completed.
+  #[serde(default)]
+  pub summary: Option<ImportSummary>,
+  /// Unix timestamp in seconds before which a scheduled import is not started.
+  #[serde(default)]
+  pub start_after: Option<i64>,
+}
+
+/// The view created for a page of the imported file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ImportPageMapping {
+  /// The id of the page in the imported file, e.g. the id of a Notion page without hyphens.
+  pub source_id: String,
+  pub view_id: String,
 }
@@ -1,11 +1,16 @@
 pub mod access_request_dto;
+pub mod admin_dto;
 pub mod ai_dto;
 pub mod auth_dto;
 pub mod billing_dto;
 pub mod chat_dto;
+pub mod export_dto;
+pub mod feature_flag_dto;
 pub mod file_dto;
 pub mod history_dto;
 pub mod import_dto;
+pub mod notification_dto;
+pub mod org_dto;
 pub mod publish_dto;
 pub mod search_dto;
 pub mod server_info_dto;
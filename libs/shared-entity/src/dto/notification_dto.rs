@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+
+/// How often the user receives a summary of the activity of the workspaces.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestFrequency {
+  #[default]
+  Never,
+  Daily,
+  Weekly,
+}
+
+impl DigestFrequency {
+  pub fn as_i16(&self) -> i16 {
+    match self {
+      DigestFrequency::Never => 0,
+      DigestFrequency::Daily => 1,
+      DigestFrequency::Weekly => 2,
+    }
+  }
+}
+
+impl From<i16> for DigestFrequency {
+  fn from(value: i16) -> Self {
+    match value {
+      1 => DigestFrequency::Daily,
+      2 => DigestFrequency::Weekly,
+      _ => DigestFrequency::Never,
+    }
+  }
+}
+
+/// The notifications the user receives. The preferences of a workspace override the ones of the
+/// user, which override the defaults. The defaults send every email and no digest.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationPreferences {
+  pub email_on_mention: bool,
+  pub email_on_comment: bool,
+  pub email_on_import_completion: bool,
+  pub digest_frequency: DigestFrequency,
+}
+
+impl Default for NotificationPreferences {
+  fn default() -> Self {
+    Self {
+      email_on_mention: true,
+      email_on_comment: true,
+      email_on_import_completion: true,
+      digest_frequency: DigestFrequency::Never,
+    }
+  }
+}
+
+/// The preferences left to None are not changed.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateNotificationPreferencesParams {
+  #[serde(default)]
+  pub email_on_mention: Option<bool>,
+  #[serde(default)]
+  pub email_on_comment: Option<bool>,
+  #[serde(default)]
+  pub email_on_import_completion: Option<bool>,
+  #[serde(default)]
+  pub digest_frequency: Option<DigestFrequency>,
+}
+
+/// The service delivering the push notifications to the device.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PushPlatform {
+  Apns,
+  Fcm,
+}
+
+impl PushPlatform {
+  pub fn as_i16(&self) -> i16 {
+    match self {
+      PushPlatform::Apns => 0,
+      PushPlatform::Fcm => 1,
+    }
+  }
+}
+
+impl TryFrom<i16> for PushPlatform {
+  type Error = String;
+
+  fn try_from(value: i16) -> Result<Self, Self::Error> {
+    match value {
+      0 => Ok(PushPlatform::Apns),
+      1 => Ok(PushPlatform::Fcm),
+      _ => Err(format!("invalid push platform {}", value)),
+    }
+  }
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegisterPushDeviceParams {
+  pub token: String,
+  pub platform: PushPlatform,
+  #[serde(default)]
+  pub device_id: Option<String>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnregisterPushDeviceParams {
+  pub token: String,
+}
@@ -0,0 +1,122 @@
+use chrono::{DateTime, Utc};
+use database_entity::dto::AFRole;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Maximum number of users a single request can add to or remove from an organization.
+pub const MAX_ORG_MEMBERS_PER_REQUEST: usize = 100;
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OrgRole {
+  /// Manages the workspaces, the members and the billing of the organization.
+  Admin = 1,
+  Member = 2,
+}
+
+impl From<i16> for OrgRole {
+  fn from(val: i16) -> Self {
+    match val {
+      1 => OrgRole::Admin,
+      _ => OrgRole::Member,
+    }
+  }
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateOrganizationParams {
+  pub name: String,
+  /// Where the invoices of the organization are sent.
+  pub billing_email: Option<String>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Organization {
+  pub org_id: Uuid,
+  pub name: String,
+  pub billing_email: Option<String>,
+  pub owner_uuid: Uuid,
+  /// The role of the requesting user in the organization.
+  pub role: OrgRole,
+  pub workspace_count: i64,
+  /// Maximum number of realtime sessions each user of the organization can keep open, None when
+  /// the default of the server applies.
+  #[serde(default)]
+  pub max_sessions_per_user: Option<i32>,
+  pub created_at: DateTime<Utc>,
+}
+
+/// Replaces the session policy of the organization. Setting `max_sessions_per_user` to None
+/// restores the default of the server.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UpdateOrganizationSessionPolicyParams {
+  pub max_sessions_per_user: Option<i32>,
+}
+
+/// Adds existing users to the organization. Unless `add_to_workspaces` is false, they also become
+/// members of every workspace of the organization.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AddOrganizationMembersParams {
+  pub emails: Vec<String>,
+  pub role: OrgRole,
+  #[serde(default = "default_add_to_workspaces")]
+  pub add_to_workspaces: bool,
+}
+
+fn default_add_to_workspaces() -> bool {
+  true
+}
+
+/// Removes users from the organization. They keep their workspace memberships.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RemoveOrganizationMembersParams {
+  pub emails: Vec<String>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OrganizationMemberWorkspace {
+  pub workspace_id: Uuid,
+  pub role: AFRole,
+}
+
+/// A user who belongs to the organization, or to at least one of its workspaces.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OrganizationMember {
+  pub uuid: Uuid,
+  pub email: String,
+  pub name: String,
+  /// None for the users who are only members of workspaces of the organization.
+  pub org_role: Option<OrgRole>,
+  pub workspaces: Vec<OrganizationMemberWorkspace>,
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OrganizationWorkspaceUsage {
+  pub workspace_id: Uuid,
+  pub workspace_name: String,
+  pub member_count: i64,
+  pub total_document_size: i64,
+  pub total_file_size: i64,
+}
+
+/// The usage of all the workspaces of the organization, the base of its billing.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OrganizationUsage {
+  pub org_id: Uuid,
+  pub workspace_count: i64,
+  /// Users who are members of several workspaces are counted once.
+  pub member_count: i64,
+  pub total_document_size: i64,
+  pub total_file_size: i64,
+  pub workspaces: Vec<OrganizationWorkspaceUsage>,
+}
@@ -64,3 +64,46 @@ pub struct PublishDatabaseData {
 pub struct DuplicatePublishedPageResponse {
   pub view_id: String,
 }
+
+/// Maximum length of the details of an abuse report on a published view.
+pub const MAX_PUBLISH_REPORT_DETAILS_LEN: usize = 2000;
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PublishReportReason {
+  Spam,
+  Phishing,
+  Malware,
+  Other,
+}
+
+impl PublishReportReason {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      PublishReportReason::Spam => "spam",
+      PublishReportReason::Phishing => "phishing",
+      PublishReportReason::Malware => "malware",
+      PublishReportReason::Other => "other",
+    }
+  }
+}
+
+impl From<&str> for PublishReportReason {
+  fn from(value: &str) -> Self {
+    match value {
+      "spam" => PublishReportReason::Spam,
+      "phishing" => PublishReportReason::Phishing,
+      "malware" => PublishReportReason::Malware,
+      _ => PublishReportReason::Other,
+    }
+  }
+}
+
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReportPublishedViewParams {
+  pub reason: PublishReportReason,
+  #[serde(default)]
+  pub details: Option<String>,
+}
@@ -4,6 +4,7 @@ use serde_repr::{Deserialize_repr, Serialize_repr};
 
 /// Parameters used to customize the collab vector search query.
 /// In response, a list of [SearchDocumentResponseItem] is returned.
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams), into_params(parameter_in = Query))]
 #[derive(Clone, Debug, Deserialize)]
 pub struct SearchDocumentRequest {
   /// Query statement to search for.
@@ -18,6 +19,7 @@ pub struct SearchDocumentRequest {
 
 /// Response array element for the collab vector search query.
 /// See: [SearchDocumentRequest].
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SearchDocumentResponseItem {
   /// Unique object identifier.
@@ -42,6 +44,7 @@ pub struct SearchDocumentResponseItem {
 /// Type of the document content to be presented in the search results.
 /// See: [SearchDocumentResponseItem].
 #[repr(i32)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Clone, Copy, Debug, Serialize_repr, Deserialize_repr)]
 pub enum SearchContentType {
   /// Document block contents displayed as plain text.
@@ -1,14 +1,67 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub enum SupportedClientFeatures {
   // Supports Collab Params serialization using Protobuf
   CollabParamsProtobuf,
 }
 
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ServerInfoResponseItem {
   pub supported_client_features: Vec<SupportedClientFeatures>,
   pub minimum_supported_client_version: Option<String>,
   pub appflowy_web_url: Option<String>,
+  /// Region of the server that answered, empty when the server is deployed in a single region.
+  #[serde(default)]
+  pub region: String,
+  /// Every region of the server, including the one that answered.
+  #[serde(default)]
+  pub regions: Vec<RegionInfo>,
+}
+
+/// Header of the websocket handshake with the latency, in milliseconds, measured by the client to
+/// the regions of the server, e.g. `us-east=120,eu-west=35`.
+pub const REGION_LATENCY_HEADER: &str = "region-latency";
+/// Header of the websocket handshake with the workspace the client opens. The server prefers the
+/// region the data of the workspace resides in.
+pub const REGION_WORKSPACE_HEADER: &str = "workspace-id";
+/// Headers of the handshake response naming the region the client should connect to instead, and
+/// the websocket endpoint of that region. The connection is accepted either way.
+pub const PREFERRED_REGION_HEADER: &str = "x-appflowy-preferred-region";
+pub const PREFERRED_REGION_ENDPOINT_HEADER: &str = "x-appflowy-preferred-region-endpoint";
+
+/// A region the server is deployed in.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RegionInfo {
+  pub name: String,
+  /// Base url of the http api of the region.
+  pub base_url: String,
+  /// Url of the websocket endpoint of the region.
+  pub ws_url: String,
+}
+
+/// Formats latencies, in milliseconds by region name, as the value of [REGION_LATENCY_HEADER].
+pub fn encode_region_latencies<'a>(latencies: impl IntoIterator<Item = (&'a str, u32)>) -> String {
+  latencies
+    .into_iter()
+    .map(|(region, latency)| format!("{}={}", region, latency))
+    .collect::<Vec<_>>()
+    .join(",")
+}
+
+/// Parses the value of [REGION_LATENCY_HEADER]. Malformed entries are skipped.
+pub fn parse_region_latencies(value: &str) -> HashMap<String, u32> {
+  value
+    .split(',')
+    .filter_map(|entry| {
+      let (region, latency) = entry.split_once('=')?;
+      let latency = latency.trim().parse::<u32>().ok()?;
+      Some((region.trim().to_string(), latency))
+    })
+    .filter(|(region, _)| !region.is_empty())
+    .collect()
 }
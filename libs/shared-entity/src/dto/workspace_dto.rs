@@ -1,6 +1,8 @@
 use chrono::{DateTime, Utc};
 use collab_entity::{CollabType, EncodedCollab};
-use database_entity::dto::{AFRole, AFWebUser, AFWorkspaceInvitationStatus, PublishInfo};
+use database_entity::dto::{
+  AFAccessLevel, AFRole, AFWebUser, AFWorkspaceInvitationStatus, PublishInfo,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use serde_repr::{Deserialize_repr, Serialize_repr};
@@ -108,11 +110,178 @@ pub struct BlobMetadata {
   pub modified_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListWorkspaceFilesQuery {
+  /// Only the files whose id starts with the prefix are listed. The files uploaded for an object
+  /// have an id starting with the id of the object.
+  pub prefix: Option<String>,
+  /// The file id to continue the listing from, as returned in [WorkspaceFiles::next_cursor].
+  pub cursor: Option<String>,
+  pub limit: Option<u32>,
+  /// Only list the files that are, or are not, waiting for the approval of a member.
+  pub quarantined: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceFile {
+  pub file_id: String,
+  pub file_type: String,
+  pub file_size: i64,
+  pub modified_at: DateTime<Utc>,
+  /// None for the files uploaded before the uploader was recorded.
+  pub uploaded_by: Option<Uuid>,
+  pub uploader_name: Option<String>,
+  /// The object the file was uploaded for, usually a document or a database row.
+  pub object_id: Option<String>,
+  /// Set for the files uploaded by users outside the workspace. They can't be downloaded until a
+  /// member approves them.
+  #[serde(default)]
+  pub quarantined: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceFiles {
+  pub files: Vec<WorkspaceFile>,
+  /// Set when there are more files to list.
+  pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteWorkspaceFilesParams {
+  pub file_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeletedWorkspaceFiles {
+  /// The ids of the files that have been deleted, unknown ids are ignored.
+  pub file_ids: Vec<String>,
+  pub freed_bytes: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApproveWorkspaceFilesParams {
+  pub file_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovedWorkspaceFiles {
+  /// The ids of the files that have been released from the quarantine. The ids of unknown files,
+  /// or files that were not quarantined, are ignored.
+  pub file_ids: Vec<String>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct CreateWorkspaceParam {
   pub workspace_name: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CloneWorkspaceParams {
+  /// Defaults to the name of the source workspace with a "(Copy)" suffix.
+  pub workspace_name: Option<String>,
+  /// Adds the members of the source workspace, with the same role, to the clone.
+  #[serde(default)]
+  pub include_members: bool,
+}
+
+/// Progress of the clone of a workspace. The clone is only listed in the workspaces of its
+/// members once the task is completed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceCloneTask {
+  pub task_id: Uuid,
+  pub source_workspace_id: Uuid,
+  pub target_workspace_id: Uuid,
+  /// 0: pending, 1: completed, 2: failed
+  pub status: i16,
+  pub total_collabs: i32,
+  pub cloned_collabs: i32,
+  pub total_blobs: i32,
+  pub cloned_blobs: i32,
+  pub error: Option<String>,
+  pub created_at: DateTime<Utc>,
+}
+
+/// How long the data of the workspace is kept. `None` keeps the data forever.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkspaceRetentionPolicy {
+  /// Chat messages older than that many days are deleted.
+  pub chat_history_days: Option<i32>,
+  /// Snapshots of the document history older than that many days are deleted.
+  pub snapshot_days: Option<i32>,
+  pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateWorkspaceRetentionPolicyParams {
+  pub chat_history_days: Option<i32>,
+  pub snapshot_days: Option<i32>,
+}
+
+/// Previews a retention policy. The fields that are not set fall back to the current policy of
+/// the workspace.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetentionDryRunQuery {
+  pub chat_history_days: Option<i32>,
+  pub snapshot_days: Option<i32>,
+}
+
+/// What would be deleted if the cleanup ran now with the given policy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionDryRunReport {
+  pub chat_history_days: Option<i32>,
+  pub snapshot_days: Option<i32>,
+  pub chat_messages: i64,
+  pub snapshots: i64,
+}
+
+/// How the history of the collabs of a workspace is thinned out: the most recent version of every
+/// hour is kept for `hourly_days`, of every day until `daily_days`, and of every week beyond.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceHistoryCompaction {
+  pub enabled: bool,
+  pub hourly_days: i32,
+  pub daily_days: i32,
+  pub last_compacted_at: Option<DateTime<Utc>>,
+}
+
+impl Default for WorkspaceHistoryCompaction {
+  fn default() -> Self {
+    Self {
+      enabled: true,
+      hourly_days: 7,
+      daily_days: 30,
+      last_compacted_at: None,
+    }
+  }
+}
+
+/// The fields that are not set keep their current value.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateWorkspaceHistoryCompactionParams {
+  pub enabled: Option<bool>,
+  pub hourly_days: Option<i32>,
+  pub daily_days: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryCompactionReport {
+  pub deleted_versions: i64,
+  pub deleted_states: i64,
+  pub reclaimed_bytes: i64,
+}
+
+/// The outcome of rebuilding the folder of a workspace from its collabs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RebuiltWorkspaceFolder {
+  /// Number of views in the rebuilt folder.
+  pub views: usize,
+  /// Number of views that were missing from the previous folder and were added back.
+  pub recovered_views: usize,
+  /// Checkpoint of the folder before the rebuild, to revert to if needed. `None` when the previous
+  /// folder couldn't be read.
+  pub backup_checkpoint_id: Option<i64>,
+}
+
 #[derive(Serialize, Deserialize, Default)]
 pub struct PatchWorkspaceParam {
   pub workspace_id: Uuid,
@@ -215,6 +384,9 @@ pub struct DuplicatePageParams {
 pub struct CreatePageDatabaseViewParams {
   pub layout: ViewLayout,
   pub name: Option<String>,
+  /// Group-by field, filters and sorts to apply to the new view.
+  #[serde(default)]
+  pub settings: Option<AFDatabaseViewSettings>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -313,6 +485,7 @@ pub struct PublishPageParams {
   pub duplicate_enabled: Option<bool>,
 }
 
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, Serialize_repr, Deserialize_repr)]
 #[repr(u8)]
 pub enum IconType {
@@ -321,12 +494,14 @@ pub enum IconType {
   Icon = 2,
 }
 
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
 pub struct ViewIcon {
   pub ty: IconType,
   pub value: String,
 }
 
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, Serialize_repr, Deserialize_repr)]
 #[repr(u8)]
 pub enum ViewLayout {
@@ -377,6 +552,10 @@ pub struct ListDatabaseRowDetailParam {
   // if set to true, document data will be fetched (if exist)
   // as markdown
   pub with_doc: Option<bool>,
+  // Comma separated list of data to expand in the response
+  // e.g. "relations" replaces the row ids of relation cells with
+  // the id and primary field value of the related rows
+  pub expand: Option<String>,
 }
 
 #[derive(Default, Debug, Deserialize, Serialize)]
@@ -395,11 +574,37 @@ impl ListDatabaseRowDetailParam {
     Self {
       ids: ids.join(","),
       with_doc: Some(with_doc),
+      expand: None,
     }
   }
+  pub fn with_expand_relations(mut self) -> Self {
+    self.expand = Some("relations".to_string());
+    self
+  }
   pub fn into_ids(&self) -> Vec<&str> {
     self.ids.split(',').collect()
   }
+  pub fn expand_relations(&self) -> bool {
+    self
+      .expand
+      .as_deref()
+      .map(|expand| expand.split(',').any(|s| s.trim() == "relations"))
+      .unwrap_or(false)
+  }
+}
+
+#[derive(Default, Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderFormat {
+  #[default]
+  Markdown,
+  Html,
+}
+
+#[derive(Default, Debug, Deserialize, Serialize)]
+pub struct RenderCollabQuery {
+  #[serde(default)]
+  pub format: RenderFormat,
 }
 
 #[derive(Default, Debug, Deserialize, Serialize)]
@@ -452,6 +657,14 @@ pub struct AFDatabaseRowDetail {
   pub doc: Option<String>,
 }
 
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AFDatabaseRowDocument {
+  /// id of the document collab, also the id of its orphan view in the folder
+  pub document_id: String,
+  /// true if the document was created by this request
+  pub created: bool,
+}
+
 #[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct AFDatabaseField {
   pub id: String,
@@ -468,6 +681,61 @@ pub struct AFInsertDatabaseField {
   pub type_option_data: Option<serde_json::Value>, // TypeOptionData
 }
 
+/// Partial update of a database field. Fields that are `None` are left unchanged. When the field
+/// type changes, `type_option_data` is applied to the new field type.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct AFUpdateDatabaseField {
+  pub name: Option<String>,
+  pub field_type: Option<i64>,                     // FieldType ID
+  pub type_option_data: Option<serde_json::Value>, // TypeOptionData
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AFDatabaseView {
+  pub id: String,
+  pub name: String,
+  /// Grid, Board or Calendar
+  pub layout: String,
+  pub is_inline: bool,
+  /// Field the rows are grouped by, only set for board views.
+  pub group_field_id: Option<String>,
+  pub filters: Vec<AFDatabaseViewFilter>,
+  pub sorts: Vec<AFDatabaseViewSort>,
+}
+
+/// Filter on a single field. The meaning of `condition` and `content` depends on the field type,
+/// and follows the filter definitions of the AppFlowy client (e.g. for a text field, condition 0
+/// is "is" and `content` is the text to compare with).
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AFDatabaseViewFilter {
+  /// Generated by the server when empty.
+  #[serde(default)]
+  pub id: String,
+  pub field_id: String,
+  pub condition: i64,
+  #[serde(default)]
+  pub content: String,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AFDatabaseViewSort {
+  /// Generated by the server when empty.
+  #[serde(default)]
+  pub id: String,
+  pub field_id: String,
+  #[serde(default)]
+  pub descending: bool,
+}
+
+/// Settings of a database view. Settings that are `None` are left unchanged, an empty list
+/// removes all the filters or sorts of the view.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct AFDatabaseViewSettings {
+  pub group_field_id: Option<String>,
+  pub filters: Option<Vec<AFDatabaseViewFilter>>,
+  pub sorts: Option<Vec<AFDatabaseViewSort>>,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct AddDatatabaseRow {
   pub cells: HashMap<String, serde_json::Value>,
@@ -480,3 +748,159 @@ pub struct UpsertDatatabaseRow {
   pub cells: HashMap<String, serde_json::Value>,
   pub document: Option<String>,
 }
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AFDatabaseExternalIdField {
+  /// id of a text field of the database, holding the id of each row in the external system
+  pub field_id: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BulkUpsertDatabaseRows {
+  pub rows: Vec<BulkUpsertDatabaseRow>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BulkUpsertDatabaseRow {
+  /// matched against the external id field of the database, the row is created when no row
+  /// has this id
+  pub external_id: String,
+  /// cells by field id or field name, the other cells of an existing row are kept
+  pub cells: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateDatabaseFormTokenParams {
+  /// shown to the owners of the workspace to tell the tokens apart
+  #[serde(default)]
+  pub name: String,
+  /// the rows submitted with the token can only fill the fields shown in this view
+  #[serde(default)]
+  pub view_id: Option<String>,
+}
+
+/// Write-only token that lets anyone holding it add rows to a single database, without being a
+/// member of the workspace.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFDatabaseFormToken {
+  pub token_id: Uuid,
+  pub name: String,
+  pub database_id: String,
+  pub view_id: Option<String>,
+  pub created_at: DateTime<Utc>,
+  pub last_used_at: Option<DateTime<Utc>>,
+  /// only returned when the token is created, it can't be retrieved afterwards
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub token: Option<String>,
+}
+
+/// A view of the folder that only its members can see or sync, along with the views below it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFRestrictedView {
+  pub view_id: String,
+  pub member_uids: Vec<i64>,
+  pub created_by: i64,
+  pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestrictViewParams {
+  /// the user restricting the view is always a member
+  pub member_uids: Vec<i64>,
+}
+
+/// A user who is not a member of the workspace but can access one of its pages.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFCollabGuest {
+  /// None until the invited email signs up
+  pub uid: Option<i64>,
+  pub name: Option<String>,
+  pub email: String,
+  pub access_level: AFAccessLevel,
+  pub created_at: DateTime<Utc>,
+}
+
+/// Where the access of a user to a collab comes from.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AFCollabMemberSource {
+  /// The collab was shared with the user.
+  Direct,
+  /// The role of the user in the workspace.
+  Workspace,
+  /// A page containing the collab was shared with the user. Not returned yet: the pages shared
+  /// with a guest don't give access to their children.
+  Folder,
+}
+
+/// A user who can access a collab, with the highest access level the user has on it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AFCollabMemberInfo {
+  pub uid: i64,
+  pub uuid: Uuid,
+  pub name: String,
+  pub email: String,
+  pub avatar_url: Option<String>,
+  pub access_level: AFAccessLevel,
+  pub source: AFCollabMemberSource,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InviteCollabGuestParams {
+  pub email: String,
+  pub access_level: AFAccessLevel,
+  #[serde(default)]
+  pub skip_email_send: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoveCollabGuestParams {
+  pub email: String,
+}
+
+/// An icon or cover image uploaded to a workspace or a page. The keys are the object keys of the
+/// blobs, `{workspace_id}/{asset_id}/{file_id}`, readable with the v1 file storage API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageAsset {
+  pub asset_id: Uuid,
+  /// The uploaded image, readable right away.
+  pub original_key: String,
+  /// The webp sizes, largest first, generated in the background. The first one is the key stored
+  /// in the workspace or page metadata.
+  pub sizes: Vec<ImageAssetSize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageAssetSize {
+  pub key: String,
+  pub max_width: u32,
+  pub max_height: u32,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SubmitDatabaseFormRow {
+  /// cells by field id or field name
+  pub cells: HashMap<String, serde_json::Value>,
+  /// response of the captcha widget, required when the server verifies captchas
+  #[serde(default)]
+  pub captcha_response: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmitDatabaseFormRowResponse {
+  pub row_id: String,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BulkUpsertDatabaseRowsResponse {
+  /// in the order of the request
+  pub rows: Vec<AFUpsertedDatabaseRow>,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AFUpsertedDatabaseRow {
+  pub external_id: String,
+  pub row_id: String,
+  /// true if the row was created by this request, false if an existing row was updated
+  pub created: bool,
+}
@@ -2,7 +2,7 @@ use serde::{Deserialize, Deserializer, Serialize};
 use std::borrow::Cow;
 
 use app_error::AppError;
-pub use app_error::ErrorCode;
+pub use app_error::{ErrorCategory, ErrorCode, ErrorDetails};
 use serde::de::DeserializeOwned;
 use std::fmt::{Debug, Display};
 
@@ -38,6 +38,9 @@ pub struct AppResponse<T> {
 
   #[serde(default)]
   pub message: Cow<'static, str>,
+
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub details: Option<ErrorDetails>,
 }
 
 impl<T> AppResponse<T> {
@@ -46,16 +49,18 @@ impl<T> AppResponse<T> {
       data: None,
       code,
       message: message.into(),
+      details: None,
     }
   }
 
   static_app_response!(Ok, AppError::Ok);
 
   pub fn split(self) -> (Option<T>, AppResponseError) {
-    if self.is_ok() {
-      (self.data, AppResponseError::new(self.code, self.message))
+    let err = AppResponseError::new(self.code, self.message).with_details(self.details);
+    if err.code == ErrorCode::Ok {
+      (self.data, err)
     } else {
-      (None, AppResponseError::new(self.code, self.message))
+      (None, err)
     }
   }
 
@@ -68,7 +73,7 @@ impl<T> AppResponse<T> {
         Some(data) => Ok(data),
       }
     } else {
-      Err(AppResponseError::new(self.code, self.message))
+      Err(AppResponseError::new(self.code, self.message).with_details(self.details))
     }
   }
 
@@ -76,7 +81,7 @@ impl<T> AppResponse<T> {
     if matches!(self.code, ErrorCode::Ok) {
       Ok(())
     } else {
-      Err(AppResponseError::new(self.code, self.message))
+      Err(AppResponseError::new(self.code, self.message).with_details(self.details))
     }
   }
 
@@ -123,7 +128,9 @@ where
 {
   fn from(value: T1) -> Self {
     let err: AppResponseError = value.into();
-    AppResponse::new(err.code, err.message)
+    let mut resp = AppResponse::new(err.code, err.message);
+    resp.details = err.details;
+    resp
   }
 }
 
@@ -133,6 +140,20 @@ where
 {
   pub async fn from_response(resp: reqwest::Response) -> Result<Self, anyhow::Error> {
     let status_code = resp.status();
+    if status_code == reqwest::StatusCode::PAYLOAD_TOO_LARGE {
+      // Rejected by the payload limits before reaching the handler.
+      let body = resp.text().await?;
+      return Ok(
+        serde_json::from_str(&body).unwrap_or_else(|_| Self::new(ErrorCode::PayloadTooLarge, body)),
+      );
+    }
+    if status_code == reqwest::StatusCode::CONFLICT {
+      // Typed conflicts carry the error in the body.
+      let body = resp.text().await?;
+      return Ok(
+        serde_json::from_str(&body).unwrap_or_else(|_| Self::new(ErrorCode::Conflict, body)),
+      );
+    }
     if !status_code.is_success() {
       let body = resp.text().await?;
       anyhow::bail!("got error code: {}, body: {}", status_code, body)
@@ -144,11 +165,15 @@ where
   }
 }
 
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[derive(Clone, Debug, Serialize, Deserialize, thiserror::Error)]
 pub struct AppResponseError {
   #[serde(deserialize_with = "default_error_code")]
   pub code: ErrorCode,
+  #[cfg_attr(feature = "openapi", schema(value_type = String))]
   pub message: Cow<'static, str>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub details: Option<ErrorDetails>,
 }
 
 impl AppResponseError {
@@ -156,12 +181,47 @@ impl AppResponseError {
     Self {
       code,
       message: message.into(),
+      details: None,
     }
   }
 
+  pub fn with_details(mut self, details: Option<ErrorDetails>) -> Self {
+    self.details = details;
+    self
+  }
+
   pub fn is_record_not_found(&self) -> bool {
     matches!(self.code, ErrorCode::RecordNotFound)
   }
+
+  /// Prefer the category sent by the server, and fall back to the one derived from the code for
+  /// servers that do not send details.
+  pub fn category(&self) -> ErrorCategory {
+    self
+      .details
+      .as_ref()
+      .map(|details| details.category)
+      .unwrap_or_else(|| self.code.category())
+  }
+
+  /// Returns an error specific field from the details, e.g. `limit` for quota errors.
+  pub fn detail(&self, key: &str) -> Option<&serde_json::Value> {
+    self.details.as_ref()?.fields.get(key)
+  }
+
+  /// Whether the object id of a new collab is already used in another workspace. Retrying won't
+  /// help, the caller should use another object id or ask the user what to do.
+  pub fn is_collab_object_id_conflict(&self) -> bool {
+    matches!(self.code, ErrorCode::CollabObjectIdConflict)
+  }
+
+  /// The workspace owning the conflicting object, only sent when the user is a member of it.
+  pub fn conflicting_workspace_id(&self) -> Option<&str> {
+    if !self.is_collab_object_id_conflict() {
+      return None;
+    }
+    self.detail("workspace_id")?.as_str()
+  }
 }
 
 impl<T> From<T> for AppResponseError
@@ -173,6 +233,7 @@ where
     Self {
       code: err.code(),
       message: Cow::Owned(err.to_string()),
+      details: err.details(),
     }
   }
 }
@@ -186,11 +247,12 @@ impl Display for AppResponseError {
 #[cfg(feature = "cloud")]
 impl actix_web::error::ResponseError for AppResponseError {
   fn status_code(&self) -> actix_web::http::StatusCode {
-    actix_web::http::StatusCode::OK
+    actix_web::http::StatusCode::from_u16(self.code.http_status())
+      .unwrap_or(actix_web::http::StatusCode::OK)
   }
 
   fn error_response(&self) -> actix_web::HttpResponse {
-    actix_web::HttpResponse::Ok().json(self)
+    actix_web::HttpResponse::build(self.status_code()).json(self)
   }
 }
 
@@ -17,10 +17,11 @@ where
   T: Debug + Display + Clone + Serialize,
 {
   fn status_code(&self) -> actix_web::http::StatusCode {
-    actix_web::http::StatusCode::OK
+    actix_web::http::StatusCode::from_u16(self.code.http_status())
+      .unwrap_or(actix_web::http::StatusCode::OK)
   }
 
   fn error_response(&self) -> actix_web::HttpResponse {
-    actix_web::HttpResponse::Ok().json(self)
+    actix_web::HttpResponse::build(self.status_code()).json(self)
   }
 }
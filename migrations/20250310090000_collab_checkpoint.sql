@@ -0,0 +1,13 @@
+-- Named checkpoints of a collab. The encoded collab itself is stored in S3, next to the automatic
+-- snapshots, under `collabs/{workspace_id}/{oid}/checkpoint_{checkpoint_id}.v1.zstd`.
+CREATE TABLE IF NOT EXISTS af_collab_checkpoint (
+  checkpoint_id BIGSERIAL PRIMARY KEY,
+  workspace_id UUID NOT NULL REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+  oid TEXT NOT NULL,
+  label TEXT NOT NULL,
+  created_by BIGINT,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_collab_checkpoint_oid_created_at
+  ON af_collab_checkpoint (workspace_id, oid, created_at DESC);
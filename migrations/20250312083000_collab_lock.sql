@@ -0,0 +1,9 @@
+-- Collabs that are frozen by a member with full access. While a row exists, writes to the collab
+-- are rejected.
+CREATE TABLE IF NOT EXISTS af_collab_lock (
+  oid TEXT PRIMARY KEY,
+  workspace_id UUID NOT NULL REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+  locked_by BIGINT NOT NULL,
+  reason TEXT,
+  locked_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
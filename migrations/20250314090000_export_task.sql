@@ -0,0 +1,17 @@
+-- Export tasks processed by the worker. The rendered file is stored in S3 under `file_key` once
+-- the task is completed.
+CREATE TABLE IF NOT EXISTS af_export_task (
+  task_id UUID PRIMARY KEY,
+  workspace_id UUID NOT NULL REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+  oid TEXT NOT NULL,
+  uid BIGINT NOT NULL,
+  format TEXT NOT NULL,
+  -- 0: pending, 1: completed, 2: failed
+  status SMALLINT NOT NULL DEFAULT 0,
+  file_key TEXT,
+  error TEXT,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_export_task_uid ON af_export_task (uid, created_at DESC);
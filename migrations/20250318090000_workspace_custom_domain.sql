@@ -0,0 +1,12 @@
+-- Custom domains attached to the publish namespace of a workspace. A domain is only served once
+-- the owner has proven the control of the domain with the DNS TXT challenge.
+CREATE TABLE IF NOT EXISTS af_workspace_custom_domain (
+  domain TEXT PRIMARY KEY,
+  workspace_id UUID NOT NULL REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+  verification_token TEXT NOT NULL,
+  verified_at TIMESTAMP WITH TIME ZONE,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_workspace_custom_domain_workspace_id
+  ON af_workspace_custom_domain (workspace_id);
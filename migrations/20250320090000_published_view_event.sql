@@ -0,0 +1,34 @@
+-- Anonymized views of the published pages. No ip address or user identifier is stored, the
+-- referrer is reduced to its host and the country comes from the reverse proxy.
+-- The table is partitioned by month, the partitions are created ahead of time by the server with
+-- af_create_published_view_event_partition.
+CREATE TABLE IF NOT EXISTS af_published_view_event (
+  workspace_id UUID NOT NULL,
+  view_id UUID NOT NULL,
+  referrer TEXT,
+  country TEXT,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+) PARTITION BY RANGE (created_at);
+
+CREATE TABLE IF NOT EXISTS af_published_view_event_default
+  PARTITION OF af_published_view_event DEFAULT;
+
+CREATE INDEX IF NOT EXISTS idx_af_published_view_event_workspace_id
+  ON af_published_view_event (workspace_id, created_at);
+
+CREATE OR REPLACE FUNCTION af_create_published_view_event_partition(month DATE)
+RETURNS VOID AS $$
+DECLARE
+  start_date DATE := date_trunc('month', month)::DATE;
+  end_date DATE := (date_trunc('month', month) + INTERVAL '1 month')::DATE;
+  partition_name TEXT := 'af_published_view_event_' || to_char(start_date, 'YYYYMM');
+BEGIN
+  EXECUTE format(
+    'CREATE TABLE IF NOT EXISTS %I PARTITION OF af_published_view_event FOR VALUES FROM (%L) TO (%L)',
+    partition_name, start_date, end_date
+  );
+END;
+$$ LANGUAGE plpgsql;
+
+SELECT af_create_published_view_event_partition(CURRENT_DATE);
+SELECT af_create_published_view_event_partition((CURRENT_DATE + INTERVAL '1 month')::DATE);
@@ -0,0 +1,20 @@
+-- Files uploaded as the context of a chat. The file is stored under `file_key`, its text is
+-- extracted by the server or by the worker and sent to the AI service, which chunks and embeds it
+-- for the retrieval of the answers of the chat.
+CREATE TABLE IF NOT EXISTS af_chat_attachment (
+  attachment_id UUID PRIMARY KEY,
+  chat_id UUID NOT NULL REFERENCES af_chat(chat_id) ON DELETE CASCADE,
+  workspace_id UUID NOT NULL REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+  uid BIGINT NOT NULL,
+  file_name TEXT NOT NULL,
+  content_type TEXT NOT NULL,
+  file_size BIGINT NOT NULL,
+  file_key TEXT NOT NULL,
+  -- 0: processing, 1: ready, 2: failed
+  status SMALLINT NOT NULL DEFAULT 0,
+  error TEXT,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_chat_attachment_chat_id ON af_chat_attachment (chat_id, created_at);
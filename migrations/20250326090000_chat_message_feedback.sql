@@ -0,0 +1,16 @@
+-- Rating given by the users to the answers of the AI, used to monitor the quality of the answers.
+-- A user has one rating per message, submitting the feedback again replaces it.
+CREATE TABLE IF NOT EXISTS af_chat_message_feedback (
+  message_id BIGINT NOT NULL REFERENCES af_chat_messages(message_id) ON DELETE CASCADE,
+  uid BIGINT NOT NULL,
+  chat_id UUID NOT NULL,
+  workspace_id UUID NOT NULL REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+  -- 1: thumbs up, -1: thumbs down
+  rating SMALLINT NOT NULL,
+  comment TEXT,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (message_id, uid)
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_chat_message_feedback_updated_at ON af_chat_message_feedback (updated_at);
@@ -0,0 +1,19 @@
+-- Edit statistics of the collab objects, updated by the collaboration server when it persists the
+-- updates sent by the clients. Used for the "last edited" information and inactivity policies.
+CREATE TABLE IF NOT EXISTS af_collab_edit_stats (
+  oid TEXT PRIMARY KEY,
+  workspace_id UUID NOT NULL REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+  total_updates BIGINT NOT NULL DEFAULT 0,
+  last_edited_by BIGINT,
+  last_edited_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_collab_edit_stats_workspace_last_edited_at ON af_collab_edit_stats (workspace_id, last_edited_at);
+
+-- Number of updates of a collab object per day (UTC).
+CREATE TABLE IF NOT EXISTS af_collab_edit_daily (
+  oid TEXT NOT NULL REFERENCES af_collab_edit_stats(oid) ON DELETE CASCADE,
+  day DATE NOT NULL,
+  edit_count BIGINT NOT NULL DEFAULT 0,
+  PRIMARY KEY (oid, day)
+);
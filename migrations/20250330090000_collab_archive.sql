@@ -0,0 +1,18 @@
+-- Collabs moved to a cheaper storage class of the object storage after a long inactivity. The
+-- af_collab row is kept, its blob is emptied until the collab is rehydrated on first access.
+CREATE TABLE IF NOT EXISTS af_collab_archive (
+  oid TEXT NOT NULL,
+  partition_key INTEGER NOT NULL,
+  workspace_id UUID NOT NULL REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+  -- key of the archived collab in the object storage
+  archive_key TEXT NOT NULL,
+  -- true when the collab was already stored in the object storage and only its storage class
+  -- changed, such collabs stay readable without rehydration
+  in_place BOOLEAN NOT NULL DEFAULT FALSE,
+  storage_class TEXT NOT NULL,
+  archived_len BIGINT NOT NULL,
+  archived_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (oid, partition_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_collab_archive_workspace_id ON af_collab_archive (workspace_id);
@@ -0,0 +1,21 @@
+-- Workspace clones processed by the worker. The target workspace is created by the server and
+-- stays hidden (is_initialized = false) until the worker has copied the collabs and the files.
+CREATE TABLE IF NOT EXISTS af_workspace_clone_task (
+  task_id UUID PRIMARY KEY,
+  source_workspace_id UUID NOT NULL REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+  target_workspace_id UUID NOT NULL,
+  uid BIGINT NOT NULL,
+  include_members BOOLEAN NOT NULL DEFAULT FALSE,
+  -- 0: pending, 1: completed, 2: failed
+  status SMALLINT NOT NULL DEFAULT 0,
+  total_collabs INTEGER NOT NULL DEFAULT 0,
+  cloned_collabs INTEGER NOT NULL DEFAULT 0,
+  total_blobs INTEGER NOT NULL DEFAULT 0,
+  cloned_blobs INTEGER NOT NULL DEFAULT 0,
+  error TEXT,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_workspace_clone_task_source
+  ON af_workspace_clone_task (source_workspace_id, created_at DESC);
@@ -0,0 +1,11 @@
+-- Retention policy of a workspace. The chat messages and the collab snapshots older than the
+-- configured number of days are deleted by the worker. NULL keeps the data forever.
+CREATE TABLE IF NOT EXISTS af_workspace_retention_policy (
+  workspace_id UUID PRIMARY KEY REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+  chat_history_days INTEGER,
+  snapshot_days INTEGER,
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_collab_snapshot_workspace_id_created_at ON af_collab_snapshot (workspace_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_af_chat_messages_chat_id_created_at ON af_chat_messages (chat_id, created_at);
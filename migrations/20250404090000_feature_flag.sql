@@ -0,0 +1,10 @@
+-- Feature flags used to gate new subsystems. A flag is on for a workspace when it's enabled and
+-- the bucket of the workspace, derived from a hash of the flag name and the workspace id, is
+-- lower than the rollout percentage.
+CREATE TABLE IF NOT EXISTS af_feature_flag (
+  name TEXT PRIMARY KEY,
+  enabled BOOLEAN NOT NULL DEFAULT FALSE,
+  rollout_percentage SMALLINT NOT NULL DEFAULT 100 CHECK (rollout_percentage BETWEEN 0 AND 100),
+  description TEXT NOT NULL DEFAULT '',
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
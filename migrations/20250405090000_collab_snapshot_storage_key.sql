@@ -0,0 +1,7 @@
+-- Snapshots whose blob has been offloaded to the object storage keep an empty blob and the key of
+-- the object holding the zstd compressed encoded collab.
+ALTER TABLE af_collab_snapshot ADD COLUMN IF NOT EXISTS storage_key TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_af_collab_snapshot_not_offloaded
+  ON af_collab_snapshot (sid)
+  WHERE storage_key IS NULL;
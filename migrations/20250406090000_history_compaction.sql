@@ -0,0 +1,13 @@
+-- History compaction settings of a workspace. The versions of the collab history are thinned
+-- out by the worker: one version per hour is kept for `hourly_days`, one per day until
+-- `daily_days`, and one per week beyond. The workspaces without a row use the defaults.
+CREATE TABLE IF NOT EXISTS af_workspace_history_compaction (
+  workspace_id UUID PRIMARY KEY REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+  enabled BOOLEAN NOT NULL DEFAULT TRUE,
+  hourly_days INTEGER NOT NULL DEFAULT 7,
+  daily_days INTEGER NOT NULL DEFAULT 30,
+  last_compacted_at TIMESTAMP WITH TIME ZONE,
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_snapshot_meta_oid_created_at ON af_snapshot_meta (oid, partition_key, created_at);
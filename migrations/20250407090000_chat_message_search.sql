@@ -0,0 +1,8 @@
+-- Full text search within a chat. The 'simple' configuration is used since chats are written in
+-- any language.
+ALTER TABLE af_chat_messages
+  ADD COLUMN IF NOT EXISTS content_tsv TSVECTOR
+    GENERATED ALWAYS AS (to_tsvector('simple'::regconfig, content)) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_af_chat_messages_content_tsv
+  ON af_chat_messages USING GIN (content_tsv);
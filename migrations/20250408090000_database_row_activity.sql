@@ -0,0 +1,16 @@
+-- Changes of the cells of a database row, derived from the updates of the row collab.
+CREATE TABLE IF NOT EXISTS af_database_row_activity (
+  activity_id   BIGSERIAL PRIMARY KEY,
+  workspace_id  UUID NOT NULL REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+  row_id        TEXT NOT NULL,
+  field_id      TEXT NOT NULL,
+  -- NULL when the cell was added or removed
+  old_value     JSONB,
+  new_value     JSONB,
+  -- NULL when the change was made by the server
+  changed_by    BIGINT REFERENCES af_user(uid) ON DELETE SET NULL,
+  changed_at    TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_database_row_activity_row_id
+  ON af_database_row_activity (row_id, activity_id DESC);
@@ -0,0 +1,3 @@
+-- The user who uploaded the blob, NULL for the blobs uploaded before this column was added.
+ALTER TABLE af_blob_metadata
+ADD COLUMN uploaded_by BIGINT REFERENCES af_user(uid) ON DELETE SET NULL;
@@ -0,0 +1,5 @@
+-- Uploads of the users that are not members of the workspace are quarantined (status = 4) until
+-- a member approves them.
+CREATE INDEX IF NOT EXISTS idx_af_blob_metadata_quarantined
+  ON af_blob_metadata (workspace_id)
+  WHERE status = 4;
@@ -0,0 +1,13 @@
+-- Actions performed by the administrators of the instance on the users.
+CREATE TABLE IF NOT EXISTS af_admin_audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    actor_uuid UUID NOT NULL,
+    action TEXT NOT NULL,
+    -- uuid of the user the action was applied to, '*' when the action targets all the users
+    target TEXT NOT NULL,
+    succeeded BOOLEAN NOT NULL,
+    detail TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_af_admin_audit_log_created_at
+  ON af_admin_audit_log (created_at DESC);
@@ -0,0 +1,120 @@
+-- Sub-partitions the collab type partitions of af_collab, af_snapshot_meta and af_snapshot_state
+-- by the hash of the workspace id, so vacuum and the indexes work on smaller tables.
+--
+-- The workspace id becomes part of the primary keys because a unique constraint of a partitioned
+-- table must contain the partition columns. The existing rows are copied into the new partitions,
+-- which rewrites the tables: on large deployments, run this migration during a maintenance window.
+-- The partitions of collab types added later are created by the appflowy worker with
+-- af_create_workspace_hash_partition.
+
+-- Creates the partition of `p_parent` holding `p_partition_key`, split into `p_modulus` partitions
+-- by the hash of the workspace id. Does nothing if the partition already exists.
+CREATE OR REPLACE FUNCTION af_create_workspace_hash_partition(
+  p_parent TEXT,
+  p_partition_key INT,
+  p_modulus INT,
+  p_partition_name TEXT DEFAULT NULL
+)
+RETURNS VOID AS $$
+DECLARE
+  partition_name TEXT := COALESCE(p_partition_name, p_parent || '_' || p_partition_key);
+  remainder INT;
+BEGIN
+  IF EXISTS (
+    SELECT 1
+    FROM pg_inherits i
+    JOIN pg_class c ON c.oid = i.inhrelid
+    WHERE i.inhparent = p_parent::regclass
+      AND pg_get_expr(c.relpartbound, c.oid) = format('FOR VALUES IN (%s)', p_partition_key)
+  ) THEN
+    RETURN;
+  END IF;
+
+  EXECUTE format(
+    'CREATE TABLE %I PARTITION OF %I FOR VALUES IN (%s) PARTITION BY HASH (workspace_id)',
+    partition_name, p_parent, p_partition_key
+  );
+  FOR remainder IN 0..p_modulus - 1 LOOP
+    EXECUTE format(
+      'CREATE TABLE %I PARTITION OF %I FOR VALUES WITH (MODULUS %s, REMAINDER %s)',
+      partition_name || '_h' || remainder, partition_name, p_modulus, remainder
+    );
+  END LOOP;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Replaces the plain partitions of `p_parent` with hash sub-partitioned ones holding the same rows,
+-- and changes the primary key of `p_parent` to `p_primary_key`.
+CREATE OR REPLACE FUNCTION af_repartition_by_workspace_hash(
+  p_parent TEXT,
+  p_primary_key TEXT,
+  p_modulus INT
+)
+RETURNS VOID AS $$
+DECLARE
+  column_list TEXT;
+  leaf RECORD;
+  names TEXT[] := '{}';
+  partition_keys INT[] := '{}';
+  i INT;
+BEGIN
+  SELECT string_agg(quote_ident(column_name), ', ' ORDER BY ordinal_position)
+  INTO column_list
+  FROM information_schema.columns
+  WHERE table_schema = current_schema() AND table_name = p_parent;
+
+  FOR leaf IN
+    SELECT
+      c.relname AS name,
+      (regexp_match(pg_get_expr(c.relpartbound, c.oid), '\((\d+)\)'))[1]::INT AS partition_key
+    FROM pg_inherits i
+    JOIN pg_class c ON c.oid = i.inhrelid
+    WHERE i.inhparent = p_parent::regclass AND c.relkind = 'r'
+  LOOP
+    EXECUTE format('ALTER TABLE %I DETACH PARTITION %I', p_parent, leaf.name);
+    EXECUTE format('ALTER TABLE %I RENAME TO %I', leaf.name, leaf.name || '_legacy');
+    names := names || leaf.name::TEXT;
+    partition_keys := partition_keys || leaf.partition_key;
+  END LOOP;
+
+  EXECUTE format('ALTER TABLE %I DROP CONSTRAINT IF EXISTS %I', p_parent, p_parent || '_pkey');
+  EXECUTE format('ALTER TABLE %I ADD PRIMARY KEY (%s)', p_parent, p_primary_key);
+
+  FOR i IN 1..COALESCE(array_length(names, 1), 0) LOOP
+    PERFORM af_create_workspace_hash_partition(p_parent, partition_keys[i], p_modulus, names[i]);
+    EXECUTE format(
+      'INSERT INTO %I (%s) SELECT %s FROM %I',
+      p_parent, column_list, column_list, names[i] || '_legacy'
+    );
+    EXECUTE format('DROP TABLE %I', names[i] || '_legacy');
+  END LOOP;
+END;
+$$ LANGUAGE plpgsql;
+
+-- The foreign key of the embeddings references the former primary key of af_collab. The cascade
+-- delete is done by a trigger instead. The embeddings table only exists when pgvector is installed.
+DO $$
+BEGIN
+  IF to_regclass('af_collab_embeddings') IS NOT NULL THEN
+    ALTER TABLE af_collab_embeddings DROP CONSTRAINT IF EXISTS af_collab_embeddings_oid_partition_key_fkey;
+
+    CREATE OR REPLACE FUNCTION af_collab_delete_embeddings() RETURNS TRIGGER AS $fn$
+    BEGIN
+      DELETE FROM af_collab_embeddings
+      WHERE oid = OLD.oid AND partition_key = OLD.partition_key;
+      RETURN OLD;
+    END;
+    $fn$ LANGUAGE plpgsql;
+
+    CREATE OR REPLACE TRIGGER af_collab_delete_embeddings_trigger
+      AFTER DELETE ON af_collab
+      FOR EACH ROW EXECUTE FUNCTION af_collab_delete_embeddings();
+  END IF;
+END;
+$$;
+
+SELECT af_repartition_by_workspace_hash('af_collab', 'oid, partition_key, workspace_id', 16);
+SELECT af_repartition_by_workspace_hash('af_snapshot_meta', 'oid, created_at, partition_key, workspace_id', 16);
+SELECT af_repartition_by_workspace_hash('af_snapshot_state', 'snapshot_id, partition_key, workspace_id', 16);
+
+DROP FUNCTION af_repartition_by_workspace_hash(TEXT, TEXT, INT);
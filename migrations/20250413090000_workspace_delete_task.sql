@@ -0,0 +1,18 @@
+-- Workspace deletions processed by the worker. The workspace is hidden (is_initialized = false)
+-- when the task is created, and its row is only deleted once the collabs, the snapshots and the
+-- files have been removed. There is no foreign key to af_workspace so the task outlives it.
+CREATE TABLE IF NOT EXISTS af_workspace_delete_task (
+  task_id UUID PRIMARY KEY,
+  workspace_id UUID NOT NULL,
+  uid BIGINT NOT NULL,
+  -- 0: pending, 1: completed, 2: failed
+  status SMALLINT NOT NULL DEFAULT 0,
+  deleted_collabs INTEGER NOT NULL DEFAULT 0,
+  deleted_snapshots INTEGER NOT NULL DEFAULT 0,
+  error TEXT,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_workspace_delete_task_workspace
+  ON af_workspace_delete_task (workspace_id, created_at DESC);
@@ -0,0 +1,19 @@
+-- Abuse reports on the published views, reviewed by the administrators of the instance.
+CREATE TABLE IF NOT EXISTS af_published_view_report (
+    report_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    view_id UUID NOT NULL,
+    workspace_id UUID NOT NULL REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+    -- null when the page was reported by a visitor who is not signed in
+    reporter_uuid UUID,
+    reason TEXT NOT NULL,
+    details TEXT,
+    -- 0: open, 1: dismissed, 2: the view was unpublished
+    status SMALLINT NOT NULL DEFAULT 0,
+    reviewed_by UUID,
+    reviewed_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_af_published_view_report_status_created_at
+  ON af_published_view_report (status, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_af_published_view_report_view_id
+  ON af_published_view_report (view_id);
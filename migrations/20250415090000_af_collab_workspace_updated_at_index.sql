@@ -0,0 +1,3 @@
+-- Lists the collabs of a workspace changed since a given time, for the incremental backups.
+CREATE INDEX IF NOT EXISTS idx_af_collab_workspace_id_updated_at
+  ON af_collab (workspace_id, updated_at, oid);
@@ -0,0 +1,23 @@
+-- Organizations group the workspaces of a company so they are administered and billed together.
+CREATE TABLE IF NOT EXISTS af_organization (
+  org_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  name TEXT NOT NULL,
+  billing_email TEXT,
+  owner_uid BIGINT NOT NULL REFERENCES af_user(uid) ON DELETE CASCADE,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- role: 1 admin, 2 member
+CREATE TABLE IF NOT EXISTS af_organization_member (
+  org_id UUID NOT NULL REFERENCES af_organization(org_id) ON DELETE CASCADE,
+  uid BIGINT NOT NULL REFERENCES af_user(uid) ON DELETE CASCADE,
+  role SMALLINT NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (org_id, uid)
+);
+CREATE INDEX IF NOT EXISTS idx_af_organization_member_uid ON af_organization_member (uid);
+
+-- A workspace belongs to at most one organization.
+ALTER TABLE af_workspace
+  ADD COLUMN IF NOT EXISTS org_id UUID REFERENCES af_organization(org_id) ON DELETE SET NULL;
+CREATE INDEX IF NOT EXISTS idx_af_workspace_org_id ON af_workspace (org_id) WHERE org_id IS NOT NULL;
@@ -0,0 +1,34 @@
+-- Subscriptions received from the webhooks of the payment provider. A subscription is held by a
+-- workspace, or by an organization and then applies to all its workspaces.
+CREATE TABLE IF NOT EXISTS af_subscription (
+  subscription_id TEXT PRIMARY KEY,
+  customer_id TEXT NOT NULL,
+  workspace_id UUID REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+  org_id UUID REFERENCES af_organization(org_id) ON DELETE CASCADE,
+  -- 0: free, 1: pro, 2: team, 3: ai max, 4: ai local
+  plan SMALLINT NOT NULL,
+  -- the status of the provider, e.g. active, past_due, canceled
+  status TEXT NOT NULL,
+  -- 0: month, 1: year
+  recurring_interval SMALLINT NOT NULL,
+  quantity INT NOT NULL DEFAULT 1,
+  current_period_end TIMESTAMPTZ NOT NULL,
+  cancel_at TIMESTAMPTZ,
+  -- creation time of the last applied event, the events may be delivered out of order
+  event_created_at TIMESTAMPTZ NOT NULL,
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  CHECK ((workspace_id IS NULL) <> (org_id IS NULL))
+);
+CREATE INDEX IF NOT EXISTS idx_af_subscription_workspace_id
+  ON af_subscription (workspace_id) WHERE workspace_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_af_subscription_org_id
+  ON af_subscription (org_id) WHERE org_id IS NOT NULL;
+
+-- Webhook events already processed, the provider delivers an event at least once.
+CREATE TABLE IF NOT EXISTS af_billing_event (
+  event_id TEXT PRIMARY KEY,
+  event_type TEXT NOT NULL,
+  received_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+ALTER TABLE af_workspace_ai_usage ADD COLUMN IF NOT EXISTS ai_responses INT NOT NULL DEFAULT 0;
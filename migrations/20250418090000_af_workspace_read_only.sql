@@ -0,0 +1,7 @@
+-- Workspaces that are over the limits of their plan. Their content can still be read and exported,
+-- but not changed, until the usage is back within the limits or the plan is upgraded.
+CREATE TABLE IF NOT EXISTS af_workspace_read_only (
+  workspace_id UUID PRIMARY KEY REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+  reason TEXT NOT NULL,
+  since TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
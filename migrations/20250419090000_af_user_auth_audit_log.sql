@@ -0,0 +1,13 @@
+-- Email change, password recovery and magic link requests made through the server.
+CREATE TABLE IF NOT EXISTS af_user_auth_audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    -- null when the email does not belong to a user of the instance yet
+    user_uuid UUID,
+    email TEXT NOT NULL,
+    action TEXT NOT NULL,
+    succeeded BOOLEAN NOT NULL,
+    detail TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_af_user_auth_audit_log_email_created_at
+  ON af_user_auth_audit_log (email, created_at DESC);
@@ -0,0 +1,4 @@
+-- Maximum number of realtime sessions a user of the organization can keep open at the same time.
+-- NULL uses the default of the server.
+ALTER TABLE af_organization
+  ADD COLUMN IF NOT EXISTS max_sessions_per_user INT CHECK (max_sessions_per_user > 0);
@@ -0,0 +1,24 @@
+-- Whether the blob of the collab is zstd compressed. The blobs written before are read as is and
+-- compressed in the background by the worker.
+ALTER TABLE af_collab
+  ADD COLUMN IF NOT EXISTS compressed BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Compressing a blob in the background doesn't change the collab, so it must not move its
+-- `updated_at`, which is used to list the changed collabs and to find the inactive ones. The
+-- writes that set `af.keep_updated_at` in their transaction keep the previous value.
+CREATE OR REPLACE FUNCTION af_collab_update_updated_at()
+RETURNS TRIGGER AS $$
+BEGIN
+  IF TG_OP = 'UPDATE' AND current_setting('af.keep_updated_at', true) = 'on' THEN
+    NEW.updated_at = OLD.updated_at;
+  ELSE
+    NEW.updated_at = CURRENT_TIMESTAMP;
+  END IF;
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER set_updated_at
+BEFORE INSERT OR UPDATE ON af_collab
+FOR EACH ROW
+EXECUTE FUNCTION af_collab_update_updated_at();
@@ -0,0 +1,14 @@
+-- Indexes for the lookups done on every request of the published pages and by the archive worker.
+
+-- The published view is looked up by its id alone, the primary key starts with the workspace id.
+CREATE INDEX IF NOT EXISTS idx_af_published_collab_view_id
+  ON af_published_collab (view_id);
+
+-- The published pages are looked up by the workspace of the namespace and the publish name.
+CREATE INDEX IF NOT EXISTS idx_af_published_collab_workspace_id_publish_name
+  ON af_published_collab (workspace_id, publish_name)
+  WHERE unpublished_at IS NULL;
+
+-- The documents to archive are the least recently edited ones across all the workspaces.
+CREATE INDEX IF NOT EXISTS idx_af_collab_edit_stats_last_edited_at
+  ON af_collab_edit_stats (last_edited_at);
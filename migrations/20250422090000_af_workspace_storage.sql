@@ -0,0 +1,21 @@
+-- Storage of the workspaces whose objects are kept in their own bucket instead of the default
+-- storage of the instance. The settings are encrypted by the server, they contain the keys of the
+-- bucket. NULL settings stand for the default storage.
+CREATE TABLE IF NOT EXISTS af_workspace_storage (
+  workspace_id UUID PRIMARY KEY REFERENCES af_workspace (workspace_id) ON DELETE CASCADE,
+  settings BYTEA,
+  -- The storage the objects are being copied to, while the relocation is copying.
+  target_settings BYTEA,
+  -- The storage the objects were copied from, until it has been emptied.
+  previous_settings BYTEA,
+  -- 0: idle, 1: copying, 2: switched, 3: failed
+  relocation_status SMALLINT NOT NULL DEFAULT 0,
+  relocation_error TEXT,
+  relocated_objects BIGINT NOT NULL DEFAULT 0,
+  switched_at TIMESTAMP WITH TIME ZONE,
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_workspace_storage_relocation
+  ON af_workspace_storage (updated_at)
+  WHERE relocation_status IN (1, 2);
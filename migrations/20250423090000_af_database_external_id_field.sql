@@ -0,0 +1,10 @@
+-- Field of a database holding the ids of the rows in an external system, the bulk upserts match
+-- the rows by the value of this field.
+CREATE TABLE IF NOT EXISTS af_database_external_id_field (
+  workspace_id  UUID NOT NULL REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+  database_id   TEXT NOT NULL,
+  field_id      TEXT NOT NULL,
+  updated_by    BIGINT REFERENCES af_user(uid) ON DELETE SET NULL,
+  updated_at    TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (workspace_id, database_id)
+);
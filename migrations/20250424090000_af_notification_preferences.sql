@@ -0,0 +1,15 @@
+-- Notification preferences of the users. The row without workspace holds the preferences of the
+-- user, the rows of a workspace override them. NULL columns fall back to the next level.
+CREATE TABLE IF NOT EXISTS af_notification_preferences (
+  uid                         BIGINT NOT NULL REFERENCES af_user(uid) ON DELETE CASCADE,
+  workspace_id                UUID REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+  email_on_mention            BOOLEAN,
+  email_on_comment            BOOLEAN,
+  email_on_import_completion  BOOLEAN,
+  -- 0: never, 1: daily, 2: weekly
+  digest_frequency            SMALLINT,
+  updated_at                  TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_af_notification_preferences_uid_workspace_id
+  ON af_notification_preferences (uid, COALESCE(workspace_id, '00000000-0000-0000-0000-000000000000'::uuid));
@@ -0,0 +1,13 @@
+-- Push tokens of the mobile devices. A token identifies an install of the app, so it belongs to
+-- the last user who registered it.
+CREATE TABLE IF NOT EXISTS af_push_device (
+  token       TEXT PRIMARY KEY,
+  uid         BIGINT NOT NULL REFERENCES af_user(uid) ON DELETE CASCADE,
+  -- 0: APNs, 1: FCM
+  platform    SMALLINT NOT NULL,
+  device_id   TEXT,
+  created_at  TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at  TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_push_device_uid ON af_push_device (uid);
@@ -0,0 +1,18 @@
+-- Write-only tokens that let anyone holding them add rows to one database, e.g. from a public
+-- form. Only the sha256 of the token is stored, the token is shown once when it's created.
+CREATE TABLE IF NOT EXISTS af_database_form_token (
+  token_id      UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  token_hash    TEXT NOT NULL UNIQUE,
+  workspace_id  UUID NOT NULL REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+  database_id   TEXT NOT NULL,
+  -- the rows can only fill the fields shown in this view when set
+  view_id       TEXT,
+  name          TEXT NOT NULL DEFAULT '',
+  -- the rows are created on behalf of the user who created the token
+  created_by    BIGINT NOT NULL REFERENCES af_user(uid) ON DELETE CASCADE,
+  created_at    TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  last_used_at  TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_af_database_form_token_database
+  ON af_database_form_token (workspace_id, database_id);
@@ -0,0 +1,50 @@
+-- Fragment ids are derived from the content of the fragment. Reindexing a collab only deletes
+-- the fragments it no longer has, and the fragments it still has keep their embedding: they are
+-- given without embedding by the indexer, which only embeds the new fragments.
+CREATE OR REPLACE PROCEDURE af_collab_embeddings_upsert(
+    IN p_workspace_id UUID,
+    IN p_oid TEXT,
+    IN p_partition_key INT,
+    IN p_tokens_used INT,
+    IN p_fragments af_fragment_v3[]
+)
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    DELETE FROM af_collab_embeddings
+    WHERE oid = p_oid
+      AND fragment_id NOT IN (SELECT f.fragment_id FROM UNNEST(p_fragments) AS f);
+
+    INSERT INTO af_collab_embeddings (fragment_id, oid, partition_key, content_type, content, embedding, indexed_at, metadata, fragment_index, embedder_type)
+    SELECT
+        f.fragment_id,
+        p_oid,
+        p_partition_key,
+        f.content_type,
+        f.contents,
+        f.embedding,
+        NOW(),
+        f.metadata,
+        f.fragment_index,
+        f.embedder_type
+    FROM UNNEST(p_fragments) as f
+    ON CONFLICT (fragment_id) DO UPDATE SET
+        metadata = EXCLUDED.metadata,
+        fragment_index = EXCLUDED.fragment_index,
+        embedding = COALESCE(EXCLUDED.embedding, af_collab_embeddings.embedding),
+        embedder_type = CASE
+            WHEN EXCLUDED.embedding IS NULL THEN af_collab_embeddings.embedder_type
+            ELSE EXCLUDED.embedder_type
+        END,
+        indexed_at = CASE
+            WHEN EXCLUDED.embedding IS NULL THEN af_collab_embeddings.indexed_at
+            ELSE NOW()
+        END;
+
+    -- Update the usage tracking table
+    INSERT INTO af_workspace_ai_usage(created_at, workspace_id, search_requests, search_tokens_consumed, index_tokens_consumed)
+    VALUES (now()::date, p_workspace_id, 0, 0, p_tokens_used)
+    ON CONFLICT (created_at, workspace_id)
+    DO UPDATE SET index_tokens_consumed = af_workspace_ai_usage.index_tokens_consumed + p_tokens_used;
+END
+$$;
@@ -0,0 +1,18 @@
+-- Folder subtrees of a workspace that only their members can see or sync. The restriction applies
+-- to the view and to all the views below it in the folder.
+CREATE TABLE IF NOT EXISTS af_restricted_view (
+  workspace_id  UUID NOT NULL REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+  view_id       TEXT NOT NULL,
+  created_by    BIGINT NOT NULL REFERENCES af_user(uid) ON DELETE CASCADE,
+  created_at    TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (workspace_id, view_id)
+);
+
+CREATE TABLE IF NOT EXISTS af_restricted_view_member (
+  workspace_id  UUID NOT NULL,
+  view_id       TEXT NOT NULL,
+  uid           BIGINT NOT NULL REFERENCES af_user(uid) ON DELETE CASCADE,
+  PRIMARY KEY (workspace_id, view_id, uid),
+  FOREIGN KEY (workspace_id, view_id)
+    REFERENCES af_restricted_view(workspace_id, view_id) ON DELETE CASCADE
+);
@@ -0,0 +1,21 @@
+-- Document-level guests: users who are not members of a workspace but were given access to some
+-- of its collabs. Their access is stored in af_collab_member, which is no longer written for the
+-- workspace members, so only the rows with a workspace_id are guest rows.
+ALTER TABLE af_collab_member
+  ADD COLUMN IF NOT EXISTS workspace_id UUID REFERENCES af_workspace(workspace_id) ON DELETE CASCADE;
+CREATE INDEX IF NOT EXISTS idx_af_collab_member_workspace_uid
+  ON af_collab_member (workspace_id, uid) WHERE workspace_id IS NOT NULL;
+
+-- Guests invited by an email that has no account yet. The invitations become af_collab_member
+-- rows once the user signs up.
+CREATE TABLE IF NOT EXISTS af_collab_guest_invitation (
+  workspace_id   UUID NOT NULL REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+  oid            TEXT NOT NULL,
+  invitee_email  TEXT NOT NULL,
+  permission_id  INTEGER NOT NULL REFERENCES af_permissions(id),
+  invited_by     BIGINT NOT NULL REFERENCES af_user(uid) ON DELETE CASCADE,
+  created_at     TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (workspace_id, oid, invitee_email)
+);
+CREATE INDEX IF NOT EXISTS idx_af_collab_guest_invitation_email
+  ON af_collab_guest_invitation (LOWER(invitee_email));
@@ -0,0 +1,13 @@
+-- The views created for the pages of an imported Notion export, keyed by the id of the Notion
+-- page. Kept so that the links to the Notion pages that were not rewritten during the import can
+-- be pointed at the imported views later.
+CREATE TABLE IF NOT EXISTS af_import_page_mapping (
+  task_id       UUID NOT NULL REFERENCES af_import_task(task_id) ON DELETE CASCADE,
+  workspace_id  UUID NOT NULL REFERENCES af_workspace(workspace_id) ON DELETE CASCADE,
+  source_id     TEXT NOT NULL,
+  view_id       TEXT NOT NULL,
+  created_at    TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (task_id, source_id)
+);
+CREATE INDEX IF NOT EXISTS idx_af_import_page_mapping_workspace_source
+  ON af_import_page_mapping (workspace_id, source_id);
@@ -0,0 +1,10 @@
+-- A custom domain may be claimed by several workspaces until one of them passes the DNS TXT
+-- challenge, so that a workspace can't hold a domain it doesn't control. Only one workspace can
+-- have the domain verified.
+ALTER TABLE af_workspace_custom_domain DROP CONSTRAINT IF EXISTS af_workspace_custom_domain_pkey;
+ALTER TABLE af_workspace_custom_domain ADD PRIMARY KEY (domain, workspace_id);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_af_workspace_custom_domain_verified
+  ON af_workspace_custom_domain (domain) WHERE verified_at IS NOT NULL;
+
+-- Last time the TXT record of a verified domain was found again.
+ALTER TABLE af_workspace_custom_domain ADD COLUMN IF NOT EXISTS checked_at TIMESTAMP WITH TIME ZONE;
@@ -0,0 +1,242 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Error};
+use uuid::Uuid;
+
+pub const USAGE: &str = r#"Usage: appflowy-admin <command> [options]
+
+Reads the configuration of appflowy_cloud from the environment or from the .env file.
+
+Commands:
+  create-user --email <email> --password <password>
+      Creates a user whose email is already confirmed.
+  reset-password --email <email> --password <password>
+      Sets the password of the user.
+  list-workspaces [--owner <email>] [--limit <n>] [--offset <n>]
+      Lists the workspaces, optionally only those owned by the user.
+  check-integrity [--limit <n>]
+      Reports the workspaces, import tasks and files left in an inconsistent state.
+  export-workspace --workspace <id>
+      Queues an export of the workspace to an AppFlowy archive, the owner is sent the download
+      link once it's ready.
+  requeue-imports [--min-idle-mins <n>] [--dry-run]
+      Puts back in the queue the import tasks a worker read but never finished.
+  flush-cache (--workspace <id> | --object <id>)
+      Removes the collabs of the workspace, or the collab, from the Redis cache.
+  help
+      Prints this message."#;
+
+const DEFAULT_LIST_LIMIT: i64 = 100;
+const DEFAULT_INTEGRITY_LIMIT: i64 = 20;
+const DEFAULT_MIN_IDLE_MINS: u64 = 30;
+/// The options that take no value.
+const FLAGS: [&str; 1] = ["dry-run"];
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+  CreateUser {
+    email: String,
+    password: String,
+  },
+  ResetPassword {
+    email: String,
+    password: String,
+  },
+  ListWorkspaces {
+    owner_email: Option<String>,
+    limit: i64,
+    offset: i64,
+  },
+  CheckIntegrity {
+    limit: i64,
+  },
+  ExportWorkspace {
+    workspace_id: Uuid,
+  },
+  RequeueImports {
+    min_idle: Duration,
+    dry_run: bool,
+  },
+  FlushCache(CacheTarget),
+  Help,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CacheTarget {
+  Workspace(Uuid),
+  Object(String),
+}
+
+impl Command {
+  pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, Error> {
+    let mut args = args.into_iter();
+    let name = args.next().unwrap_or_else(|| "help".to_string());
+    let mut options = Options::parse(args)?;
+    let command = match name.as_str() {
+      "create-user" => Command::CreateUser {
+        email: options.required("email")?,
+        password: options.required("password")?,
+      },
+      "reset-password" => Command::ResetPassword {
+        email: options.required("email")?,
+        password: options.required("password")?,
+      },
+      "list-workspaces" => Command::ListWorkspaces {
+        owner_email: options.optional("owner"),
+        limit: options.parsed("limit", DEFAULT_LIST_LIMIT)?,
+        offset: options.parsed("offset", 0)?,
+      },
+      "check-integrity" => Command::CheckIntegrity {
+        limit: options.parsed("limit", DEFAULT_INTEGRITY_LIMIT)?,
+      },
+      "export-workspace" => Command::ExportWorkspace {
+        workspace_id: options.required_parsed("workspace")?,
+      },
+      "requeue-imports" => Command::RequeueImports {
+        min_idle: Duration::from_secs(options.parsed("min-idle-mins", DEFAULT_MIN_IDLE_MINS)? * 60),
+        dry_run: options.flag("dry-run"),
+      },
+      "flush-cache" => {
+        let workspace_id = options.optional("workspace");
+        let object_id = options.optional("object");
+        let target = match (workspace_id, object_id) {
+          (Some(workspace_id), None) => {
+            CacheTarget::Workspace(Uuid::parse_str(&workspace_id).context("invalid --workspace")?)
+          },
+          (None, Some(object_id)) => CacheTarget::Object(object_id),
+          _ => return Err(anyhow!("flush-cache needs either --workspace or --object")),
+        };
+        Command::FlushCache(target)
+      },
+      "help" | "--help" | "-h" => Command::Help,
+      other => return Err(anyhow!("unknown command {}", other)),
+    };
+    options.finish()?;
+    Ok(command)
+  }
+}
+
+/// The `--name value` options and the `--flag` flags following the command.
+struct Options {
+  values: HashMap<String, String>,
+  flags: HashSet<String>,
+}
+
+impl Options {
+  fn parse(args: impl Iterator<Item = String>) -> Result<Self, Error> {
+    let mut values = HashMap::new();
+    let mut flags = HashSet::new();
+    let mut args = args.peekable();
+    while let Some(arg) = args.next() {
+      let name = arg
+        .strip_prefix("--")
+        .ok_or_else(|| anyhow!("unexpected argument {}", arg))?;
+      if FLAGS.contains(&name) {
+        flags.insert(name.to_string());
+        continue;
+      }
+      let value = args
+        .next_if(|value| !value.starts_with("--"))
+        .ok_or_else(|| anyhow!("missing value of --{}", name))?;
+      values.insert(name.to_string(), value);
+    }
+    Ok(Self { values, flags })
+  }
+
+  fn optional(&mut self, name: &str) -> Option<String> {
+    self.values.remove(name)
+  }
+
+  fn required(&mut self, name: &str) -> Result<String, Error> {
+    self
+      .optional(name)
+      .ok_or_else(|| anyhow!("missing --{}", name))
+  }
+
+  fn required_parsed<T>(&mut self, name: &str) -> Result<T, Error>
+  where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+  {
+    let value = self.required(name)?;
+    value
+      .parse()
+      .with_context(|| format!("invalid --{}: {}", name, value))
+  }
+
+  fn parsed<T>(&mut self, name: &str, default: T) -> Result<T, Error>
+  where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+  {
+    match self.values.contains_key(name) {
+      true => self.required_parsed(name),
+      false => Ok(default),
+    }
+  }
+
+  fn flag(&mut self, name: &str) -> bool {
+    self.flags.remove(name)
+  }
+
+  /// Fails on the options the command doesn't take.
+  fn finish(self) -> Result<(), Error> {
+    match self.values.keys().chain(self.flags.iter()).next() {
+      Some(name) => Err(anyhow!("unexpected option --{}", name)),
+      None => Ok(()),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(args: &str) -> Result<Command, Error> {
+    Command::parse(args.split_whitespace().map(String::from))
+  }
+
+  #[test]
+  fn parse_commands() {
+    assert_eq!(
+      parse("create-user --email a@appflowy.io --password secret").unwrap(),
+      Command::CreateUser {
+        email: "a@appflowy.io".to_string(),
+        password: "secret".to_string(),
+      }
+    );
+    assert_eq!(
+      parse("list-workspaces --limit 5").unwrap(),
+      Command::ListWorkspaces {
+        owner_email: None,
+        limit: 5,
+        offset: 0,
+      }
+    );
+    assert_eq!(
+      parse("requeue-imports --dry-run --min-idle-mins 10").unwrap(),
+      Command::RequeueImports {
+        min_idle: Duration::from_secs(600),
+        dry_run: true,
+      }
+    );
+    assert_eq!(
+      parse("flush-cache --object abc").unwrap(),
+      Command::FlushCache(CacheTarget::Object("abc".to_string()))
+    );
+    assert_eq!(parse("").unwrap(), Command::Help);
+  }
+
+  #[test]
+  fn reject_invalid_arguments() {
+    assert!(parse("create-user --email a@appflowy.io").is_err());
+    assert!(parse("create-user --email --password secret").is_err());
+    assert!(parse("list-workspaces --limit many").is_err());
+    assert!(parse("check-integrity --owner a@appflowy.io").is_err());
+    assert!(parse("flush-cache --workspace not-a-uuid").is_err());
+    assert!(parse("flush-cache").is_err());
+    assert!(parse("drop-database").is_err());
+  }
+}
@@ -0,0 +1,50 @@
+use std::sync::Arc;
+
+use anyhow::Error;
+use appflowy_collaborate::collab::cache::mem_cache::CollabMemCache;
+use appflowy_collaborate::CollabMetrics;
+use collab_entity::CollabType;
+use database::collab::select_workspace_collab_oids_by_type;
+use redis::aio::ConnectionManager;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const COLLAB_TYPES: [CollabType; 6] = [
+  CollabType::Document,
+  CollabType::Database,
+  CollabType::WorkspaceDatabase,
+  CollabType::Folder,
+  CollabType::DatabaseRow,
+  CollabType::UserAwareness,
+];
+
+/// Removes the collabs of the workspace from the Redis cache, the next reads load them from
+/// Postgres or S3.
+pub async fn flush_workspace(
+  pg_pool: &PgPool,
+  redis_client: ConnectionManager,
+  workspace_id: &Uuid,
+) -> Result<(), Error> {
+  let mem_cache = CollabMemCache::new(redis_client, Arc::new(CollabMetrics::default()));
+  let mut flushed = 0;
+  for collab_type in COLLAB_TYPES.iter() {
+    let object_ids =
+      select_workspace_collab_oids_by_type(pg_pool, workspace_id, collab_type).await?;
+    for object_id in object_ids {
+      mem_cache.remove_encode_collab(&object_id).await?;
+      flushed += 1;
+    }
+  }
+  println!(
+    "flushed {} collabs of workspace {} from the cache",
+    flushed, workspace_id
+  );
+  Ok(())
+}
+
+pub async fn flush_object(redis_client: ConnectionManager, object_id: &str) -> Result<(), Error> {
+  let mem_cache = CollabMemCache::new(redis_client, Arc::new(CollabMetrics::default()));
+  mem_cache.remove_encode_collab(object_id).await?;
+  println!("flushed {} from the cache", object_id);
+  Ok(())
+}
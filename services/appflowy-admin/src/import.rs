@@ -0,0 +1,112 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Error};
+use database::workspace::{select_import_task, ImportTaskState};
+use redis::aio::ConnectionManager;
+use redis::streams::{StreamPendingCountReply, StreamRangeReply};
+use redis::AsyncCommands;
+use serde_json::Value;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const IMPORT_TASK_STREAM: &str = "import_task_stream";
+const IMPORT_TASK_GROUP: &str = "import_task_group";
+/// The pending entries read at once.
+const PENDING_BATCH_SIZE: usize = 100;
+
+/// The worker only claims the entries it read but never acknowledged when it restarts. Adds them
+/// back to the end of the stream, so that a running worker picks them up, as long as their
+/// import task is still pending. The entries of the finished tasks are only acknowledged.
+pub async fn requeue_imports(
+  pg_pool: &PgPool,
+  mut redis_client: ConnectionManager,
+  min_idle: Duration,
+  dry_run: bool,
+) -> Result<(), Error> {
+  let reply: StreamPendingCountReply = redis_client
+    .xpending_count(
+      IMPORT_TASK_STREAM,
+      IMPORT_TASK_GROUP,
+      "-",
+      "+",
+      PENDING_BATCH_SIZE,
+    )
+    .await?;
+  let min_idle_ms = min_idle.as_millis() as usize;
+  let (mut requeued, mut acknowledged) = (0, 0);
+  for pending in reply.ids {
+    if pending.last_delivered_ms < min_idle_ms {
+      continue;
+    }
+    let range: StreamRangeReply = redis_client
+      .xrange(IMPORT_TASK_STREAM, &pending.id, &pending.id)
+      .await?;
+    // the entry was deleted while it was pending, only its id is left in the group
+    let task = range
+      .ids
+      .into_iter()
+      .next()
+      .and_then(|entry| entry.get::<String>("task"));
+    let task_id = task.as_deref().map(task_id_of).transpose()?;
+    let still_pending = match task_id {
+      Some(task_id) => {
+        let status = select_import_task(pg_pool, &task_id).await?.status;
+        status == ImportTaskState::Pending as i16
+      },
+      None => false,
+    };
+
+    let action = if still_pending { "requeue" } else { "ack" };
+    println!(
+      "{} {} (task {}, idle {}s, delivered {} times)",
+      action,
+      pending.id,
+      task_id.map(|id| id.to_string()).unwrap_or_default(),
+      pending.last_delivered_ms / 1000,
+      pending.times_delivered
+    );
+    if dry_run {
+      continue;
+    }
+    let mut pipe = redis::pipe();
+    pipe
+      .atomic()
+      .xack(IMPORT_TASK_STREAM, IMPORT_TASK_GROUP, &[&pending.id])
+      .ignore()
+      .xdel(IMPORT_TASK_STREAM, &[&pending.id])
+      .ignore();
+    match (still_pending, task) {
+      (true, Some(task)) => {
+        pipe
+          .xadd(IMPORT_TASK_STREAM, "*", &[("task", task)])
+          .ignore();
+        requeued += 1;
+      },
+      _ => acknowledged += 1,
+    }
+    let () = pipe.query_async(&mut redis_client).await?;
+  }
+
+  if dry_run {
+    println!("dry run, nothing changed");
+  } else {
+    println!(
+      "{} import tasks requeued, {} entries acknowledged",
+      requeued, acknowledged
+    );
+  }
+  Ok(())
+}
+
+/// The task of an entry is an object with a single field named after the kind of import, like
+/// `{"notion": {"task_id": ..}}`.
+fn task_id_of(task: &str) -> Result<Uuid, Error> {
+  let value = serde_json::from_str::<Value>(task)?;
+  let task_id = value
+    .as_object()
+    .and_then(|task| task.values().next())
+    .and_then(|task| task.get("task_id"))
+    .and_then(Value::as_str)
+    .ok_or_else(|| anyhow!("import task without task_id: {}", task))?;
+  Ok(Uuid::parse_str(task_id)?)
+}
@@ -0,0 +1,57 @@
+use std::fmt::Display;
+
+use anyhow::Error;
+use chrono::{Duration, Utc};
+use database::integrity::{
+  select_incomplete_blobs, select_stale_import_tasks, select_workspaces_without_folder,
+  select_workspaces_without_owner_member,
+};
+use sqlx::PgPool;
+
+/// The import tasks and the uploads older than this are no longer expected to complete.
+const STALE_AFTER_HOURS: i64 = 24;
+
+/// Prints the records left in an inconsistent state, up to `limit` of each kind. Nothing is
+/// repaired, the fix depends on how the records got there.
+pub async fn check_integrity(pg_pool: &PgPool, limit: i64) -> Result<(), Error> {
+  let stale_before = Utc::now() - Duration::hours(STALE_AFTER_HOURS);
+  let mut issues = 0;
+  issues += report(
+    "workspaces without folder",
+    select_workspaces_without_folder(pg_pool, limit).await?,
+  );
+  issues += report(
+    "workspaces whose owner is not an owner member",
+    select_workspaces_without_owner_member(pg_pool, limit).await?,
+  );
+  issues += report(
+    "import tasks pending for more than a day",
+    select_stale_import_tasks(pg_pool, stale_before, limit).await?,
+  );
+  issues += report(
+    "uploads pending for more than a day",
+    select_incomplete_blobs(pg_pool, stale_before, limit)
+      .await?
+      .into_iter()
+      .map(|(workspace_id, file_id)| format!("{}/{}", workspace_id, file_id))
+      .collect(),
+  );
+  match issues {
+    0 => println!("no issue found"),
+    _ => println!(
+      "{} issues found, at most {} of each kind are listed",
+      issues, limit
+    ),
+  }
+  Ok(())
+}
+
+fn report<T: Display>(title: &str, records: Vec<T>) -> usize {
+  if !records.is_empty() {
+    println!("{} ({}):", title, records.len());
+    for record in records.iter() {
+      println!("  {}", record);
+    }
+  }
+  records.len()
+}
@@ -0,0 +1,89 @@
+mod args;
+mod cache;
+mod import;
+mod integrity;
+mod user;
+mod workspace;
+
+use anyhow::Error;
+use appflowy_cloud::application::{get_admin_client, get_connection_pool, get_gotrue_client};
+use appflowy_cloud::config::config::{get_configuration, Config};
+use secrecy::ExposeSecret;
+use sqlx::PgPool;
+
+use crate::args::{CacheTarget, Command, USAGE};
+
+#[tokio::main]
+async fn main() {
+  dotenvy::dotenv().ok();
+  let command = match Command::parse(std::env::args().skip(1)) {
+    Ok(command) => command,
+    Err(err) => {
+      eprintln!("{}\n\n{}", err, USAGE);
+      std::process::exit(2);
+    },
+  };
+  if let Err(err) = run(command).await {
+    eprintln!("error: {:?}", err);
+    std::process::exit(1);
+  }
+}
+
+async fn run(command: Command) -> Result<(), Error> {
+  if command == Command::Help {
+    println!("{}", USAGE);
+    return Ok(());
+  }
+  let config = get_configuration()?;
+  match command {
+    Command::CreateUser { email, password } => {
+      let gotrue_admin = get_admin_client(get_gotrue_client(&config.gotrue).await?, &config.gotrue);
+      user::create_user(&gotrue_admin, &email, &password).await
+    },
+    Command::ResetPassword { email, password } => {
+      let gotrue_admin = get_admin_client(get_gotrue_client(&config.gotrue).await?, &config.gotrue);
+      user::reset_password(&gotrue_admin, &email, &password).await
+    },
+    Command::ListWorkspaces {
+      owner_email,
+      limit,
+      offset,
+    } => {
+      let pg_pool = connect_postgres(&config).await?;
+      workspace::list_workspaces(&pg_pool, owner_email.as_deref(), limit, offset).await
+    },
+    Command::CheckIntegrity { limit } => {
+      let pg_pool = connect_postgres(&config).await?;
+      integrity::check_integrity(&pg_pool, limit).await
+    },
+    Command::ExportWorkspace { workspace_id } => {
+      let pg_pool = connect_postgres(&config).await?;
+      let redis_client = connect_redis(&config).await?;
+      workspace::export_workspace(&config, &pg_pool, &redis_client, workspace_id).await
+    },
+    Command::RequeueImports { min_idle, dry_run } => {
+      let pg_pool = connect_postgres(&config).await?;
+      let redis_client = connect_redis(&config).await?;
+      import::requeue_imports(&pg_pool, redis_client, min_idle, dry_run).await
+    },
+    Command::FlushCache(CacheTarget::Workspace(workspace_id)) => {
+      let pg_pool = connect_postgres(&config).await?;
+      let redis_client = connect_redis(&config).await?;
+      cache::flush_workspace(&pg_pool, redis_client, &workspace_id).await
+    },
+    Command::FlushCache(CacheTarget::Object(object_id)) => {
+      let redis_client = connect_redis(&config).await?;
+      cache::flush_object(redis_client, &object_id).await
+    },
+    Command::Help => Ok(()),
+  }
+}
+
+async fn connect_postgres(config: &Config) -> Result<PgPool, Error> {
+  get_connection_pool(&config.db_settings).await
+}
+
+async fn connect_redis(config: &Config) -> Result<redis::aio::ConnectionManager, Error> {
+  let client = redis::Client::open(config.redis_uri.expose_secret().as_str())?;
+  Ok(client.get_connection_manager().await?)
+}
@@ -0,0 +1,60 @@
+use anyhow::{anyhow, Error};
+use app_error::AppError;
+use appflowy_cloud::state::GoTrueAdmin;
+use gotrue::params::AdminUserParams;
+
+/// Creates a user in GoTrue with a confirmed email, its AppFlowy profile and workspace are
+/// created when it signs in for the first time.
+pub async fn create_user(
+  gotrue_admin: &GoTrueAdmin,
+  email: &str,
+  password: &str,
+) -> Result<(), Error> {
+  let admin_token = gotrue_admin.token().await?;
+  let user = gotrue_admin
+    .gotrue_client
+    .admin_add_user(
+      &admin_token,
+      &AdminUserParams {
+        email: email.to_string(),
+        password: Some(password.to_string()),
+        email_confirm: true,
+        ..Default::default()
+      },
+    )
+    .await
+    .map_err(AppError::from)?;
+  println!("created user {} with id {}", user.email, user.id);
+  Ok(())
+}
+
+pub async fn reset_password(
+  gotrue_admin: &GoTrueAdmin,
+  email: &str,
+  password: &str,
+) -> Result<(), Error> {
+  let admin_token = gotrue_admin.token().await?;
+  let user = gotrue_admin
+    .gotrue_client
+    .admin_list_user(&admin_token, Some(email))
+    .await
+    .map_err(AppError::from)?
+    .users
+    .into_iter()
+    .find(|user| user.email.eq_ignore_ascii_case(email))
+    .ok_or_else(|| anyhow!("no user with email {}", email))?;
+  gotrue_admin
+    .gotrue_client
+    .admin_update_user(
+      &admin_token,
+      &user.id,
+      &AdminUserParams {
+        password: Some(password.to_string()),
+        ..Default::default()
+      },
+    )
+    .await
+    .map_err(AppError::from)?;
+  println!("password of {} reset", user.email);
+  Ok(())
+}
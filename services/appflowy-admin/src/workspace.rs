@@ -0,0 +1,80 @@
+use anyhow::{anyhow, Error};
+use appflowy_cloud::application::{get_blob_storage_client, get_workspace_storage_router};
+use appflowy_cloud::biz::collab::export::create_workspace_export_task;
+use appflowy_cloud::config::config::Config;
+use database::user::{select_uid_from_email, select_uuid_from_email};
+use database::workspace::{select_all_workspaces, select_workspace};
+use redis::aio::ConnectionManager;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub async fn list_workspaces(
+  pg_pool: &PgPool,
+  owner_email: Option<&str>,
+  limit: i64,
+  offset: i64,
+) -> Result<(), Error> {
+  let owner_uid = match owner_email {
+    Some(email) => Some(select_uid_from_email(pg_pool, email).await?),
+    None => None,
+  };
+  let workspaces = select_all_workspaces(pg_pool, owner_uid, limit, offset).await?;
+  for workspace in workspaces.iter() {
+    println!(
+      "{}\t{}\t{}\t{}",
+      workspace.workspace_id,
+      workspace.workspace_name.as_deref().unwrap_or_default(),
+      workspace.owner_email.as_deref().unwrap_or_default(),
+      workspace
+        .created_at
+        .map(|created_at| created_at.to_rfc3339())
+        .unwrap_or_default(),
+    );
+  }
+  println!("{} workspaces", workspaces.len());
+  Ok(())
+}
+
+/// Queues the export of the workspace on behalf of its owner, who receives the download link.
+pub async fn export_workspace(
+  config: &Config,
+  pg_pool: &PgPool,
+  redis_client: &ConnectionManager,
+  workspace_id: Uuid,
+) -> Result<(), Error> {
+  let workspace = select_workspace(pg_pool, &workspace_id).await?;
+  let (owner_uid, owner_email) = workspace
+    .owner_uid
+    .zip(workspace.owner_email)
+    .ok_or_else(|| anyhow!("workspace {} has no owner", workspace_id))?;
+  let owner_uuid = select_uuid_from_email(pg_pool, &owner_email)
+    .await?
+    .ok_or_else(|| {
+      anyhow!(
+        "owner {} of workspace {} not found",
+        owner_email,
+        workspace_id
+      )
+    })?;
+
+  let bucket_client = match get_workspace_storage_router(config, pg_pool)? {
+    Some(router) => get_blob_storage_client(config)
+      .await?
+      .with_workspace_storage(router),
+    None => get_blob_storage_client(config).await?,
+  };
+  let task = create_workspace_export_task(
+    &bucket_client,
+    redis_client,
+    pg_pool,
+    owner_uid,
+    &owner_uuid,
+    workspace_id,
+  )
+  .await?;
+  println!(
+    "export task {} queued, the download link is sent to {}",
+    task.task_id, owner_email
+  );
+  Ok(())
+}
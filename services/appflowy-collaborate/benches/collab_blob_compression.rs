@@ -0,0 +1,79 @@
+//! Compares the cost of reading a collab from the blob stored in `af_collab`, when the blob is
+//! stored as is and when it's zstd compressed, for documents of different sizes. The time spent
+//! in Postgres isn't included, only the work done by the server once the blob is fetched.
+//!
+//! Run with `cargo bench -p appflowy-collaborate --bench collab_blob_compression`.
+
+use collab::entity::EncodedCollab;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use database::collab::{compress_collab_blob, decompress_collab_blob};
+use yrs::updates::encoder::Encode;
+use yrs::{Doc, ReadTxn, StateVector, Text, Transact};
+
+/// Number of paragraphs typed into the document.
+const PARAGRAPHS: [usize; 3] = [10, 100, 1000];
+
+fn encoded_collab_blob(paragraphs: usize) -> Vec<u8> {
+  let doc = Doc::with_client_id(1);
+  let text = doc.get_or_insert_text("text");
+  for i in 0..paragraphs {
+    let mut txn = doc.transact_mut();
+    let len = text.len(&txn);
+    text.insert(
+      &mut txn,
+      len,
+      &format!(
+        "Paragraph {} of the document, typed one edit at a time.\n",
+        i
+      ),
+    );
+  }
+  let txn = doc.transact();
+  EncodedCollab::new_v1(
+    txn.state_vector().encode_v1(),
+    txn.encode_state_as_update_v1(&StateVector::default()),
+  )
+  .encode_to_bytes()
+  .unwrap()
+}
+
+fn bench_read_collab_blob(c: &mut Criterion) {
+  let mut group = c.benchmark_group("read_collab_blob");
+  for paragraphs in PARAGRAPHS {
+    let blob = encoded_collab_blob(paragraphs);
+    let (stored, compressed) = compress_collab_blob(&blob).unwrap();
+    let stored = stored.into_owned();
+    println!(
+      "{} paragraphs: {} bytes, {} bytes stored, compressed: {}",
+      paragraphs,
+      blob.len(),
+      stored.len(),
+      compressed
+    );
+
+    group.bench_with_input(
+      BenchmarkId::new("uncompressed", paragraphs),
+      &blob,
+      |b, blob| {
+        b.iter(|| {
+          let blob = decompress_collab_blob(blob.clone(), false).unwrap();
+          EncodedCollab::decode_from_bytes(&blob).unwrap()
+        })
+      },
+    );
+    group.bench_with_input(
+      BenchmarkId::new("compressed", paragraphs),
+      &stored,
+      |b, stored| {
+        b.iter(|| {
+          let blob = decompress_collab_blob(stored.clone(), compressed).unwrap();
+          EncodedCollab::decode_from_bytes(&blob).unwrap()
+        })
+      },
+    );
+  }
+  group.finish();
+}
+
+criterion_group!(benches, bench_read_collab_blob);
+criterion_main!(benches);
@@ -0,0 +1,117 @@
+//! Compares the cost of reading the state vector of a collab group while its inbound task keeps
+//! merging updates, when the state vector is behind an async lock and when it's published as a
+//! lock-free snapshot by a single writer.
+//!
+//! Run with `cargo bench -p appflowy-collaborate --bench group_state_vector`.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use arc_swap::ArcSwap;
+use collab::lock::RwLock;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use tokio::runtime::Runtime;
+use tokio_util::sync::CancellationToken;
+use yrs::{Doc, ReadTxn, StateVector, Text, Transact};
+
+/// Number of concurrent readers, standing for the subscribers syncing with the group.
+const READERS: [usize; 3] = [1, 16, 128];
+
+fn edited_state_vectors(count: usize) -> Vec<StateVector> {
+  let doc = Doc::with_client_id(1);
+  let text = doc.get_or_insert_text("text");
+  (0..count)
+    .map(|i| {
+      let mut txn = doc.transact_mut();
+      text.insert(&mut txn, 0, &i.to_string());
+      txn.state_vector()
+    })
+    .collect()
+}
+
+fn bench_locked_state_vector(c: &mut Criterion) {
+  let rt = Runtime::new().unwrap();
+  let mut group = c.benchmark_group("locked_state_vector");
+  group.measurement_time(Duration::from_secs(5));
+  for readers in READERS {
+    group.bench_with_input(
+      BenchmarkId::from_parameter(readers),
+      &readers,
+      |b, &readers| {
+        let state_vector = Arc::new(RwLock::new(StateVector::default()));
+        let stop = CancellationToken::new();
+        let writer_sv = state_vector.clone();
+        let writer_stop = stop.clone();
+        rt.spawn(async move {
+          let updates = edited_state_vectors(1000);
+          while !writer_stop.is_cancelled() {
+            for update in updates.iter() {
+              writer_sv.write().await.merge(update.clone());
+            }
+            tokio::task::yield_now().await;
+          }
+        });
+        b.to_async(&rt).iter(|| {
+          let state_vector = state_vector.clone();
+          async move {
+            let tasks = (0..readers).map(|_| {
+              let state_vector = state_vector.clone();
+              tokio::spawn(async move { state_vector.read().await.clone() })
+            });
+            futures::future::join_all(tasks).await
+          }
+        });
+        stop.cancel();
+      },
+    );
+  }
+  group.finish();
+}
+
+fn bench_single_writer_state_vector(c: &mut Criterion) {
+  let rt = Runtime::new().unwrap();
+  let mut group = c.benchmark_group("single_writer_state_vector");
+  group.measurement_time(Duration::from_secs(5));
+  for readers in READERS {
+    group.bench_with_input(
+      BenchmarkId::from_parameter(readers),
+      &readers,
+      |b, &readers| {
+        let state_vector = Arc::new(ArcSwap::from_pointee(StateVector::default()));
+        let stop = CancellationToken::new();
+        let writer_sv = state_vector.clone();
+        let writer_stop = stop.clone();
+        rt.spawn(async move {
+          let updates = edited_state_vectors(1000);
+          while !writer_stop.is_cancelled() {
+            for update in updates.iter() {
+              let mut merged = StateVector::clone(&writer_sv.load());
+              merged.merge(update.clone());
+              writer_sv.store(Arc::new(merged));
+            }
+            tokio::task::yield_now().await;
+          }
+        });
+        b.to_async(&rt).iter(|| {
+          let state_vector = state_vector.clone();
+          async move {
+            let tasks = (0..readers).map(|_| {
+              let state_vector = state_vector.clone();
+              tokio::spawn(async move { StateVector::clone(&state_vector.load()) })
+            });
+            futures::future::join_all(tasks).await
+          }
+        });
+        stop.cancel();
+      },
+    );
+  }
+  group.finish();
+}
+
+criterion_group!(
+  benches,
+  bench_locked_state_vector,
+  bench_single_writer_state_vector
+);
+criterion_main!(benches);
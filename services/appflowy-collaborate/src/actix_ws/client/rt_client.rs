@@ -1,6 +1,8 @@
 use crate::actix_ws::entities::{ClientWebSocketMessage, Connect, Disconnect, RealtimeMessage};
+use crate::connect_state::ConnectionHeartbeat;
 use crate::error::RealtimeError;
 use crate::RealtimeClientWebsocketSink;
+use access_control::session_policy::SessionPolicy;
 use actix::{
   fut, Actor, ActorContext, ActorFutureExt, Addr, AsyncContext, Context, ContextFutureSpawner,
   Handler, MailboxError, Recipient, Running, StreamHandler, WrapFuture,
@@ -11,18 +13,20 @@ use anyhow::anyhow;
 use async_trait::async_trait;
 use bytes::Bytes;
 use collab_rt_entity::user::RealtimeUser;
-use collab_rt_entity::SystemMessage;
+use collab_rt_entity::{RealtimeCapability, RealtimeProtocol, SystemMessage};
 use governor::clock::DefaultClock;
 use governor::middleware::NoOpMiddleware;
 use governor::state::{InMemoryState, NotKeyed};
 use governor::{Quota, RateLimiter};
 use semver::Version;
+use std::borrow::Cow;
 use std::num::NonZeroU32;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use tokio::time::sleep;
 use tracing::{debug, error, trace, warn};
+use uuid::Uuid;
 
 pub type HandlerResult = anyhow::Result<(), RealtimeError>;
 pub trait RealtimeServer:
@@ -37,6 +41,8 @@ type BinaryRateLimiter = RateLimiter<NotKeyed, InMemoryState, DefaultClock, NoOp
 pub struct RealtimeClient<S: RealtimeServer> {
   user: RealtimeUser,
   hb: Instant,
+  /// Shared with the server so that it can find idle connections and ask this actor to close them.
+  heartbeat: ConnectionHeartbeat,
   pub server: Addr<S>,
   heartbeat_interval: Duration,
   client_timeout: Duration,
@@ -48,6 +54,20 @@ pub struct RealtimeClient<S: RealtimeServer> {
   /// mechanism. This limits the number of messages a client can send per second, ensuring the server's
   /// mailbox does not get full from receiving too many messages at the same time.
   binary_rate_limiter: Arc<BinaryRateLimiter>,
+  session_limit: Option<SessionLimit>,
+  /// The protocol negotiated with the client when it connected, the legacy protocol by default.
+  protocol: RealtimeProtocol,
+}
+
+/// Limits the number of sessions the user of the connection can keep open at the same time.
+#[derive(Clone)]
+pub struct SessionLimit {
+  /// Maximum number of sessions of the user, 0 means unlimited.
+  pub max_sessions: usize,
+  pub policy: SessionPolicy,
+  /// The GoTrue session the connection was authenticated with, revoked when the connection is
+  /// evicted because the user opened too many sessions.
+  pub auth_session_id: Option<Uuid>,
 }
 
 impl<S> RealtimeClient<S>
@@ -67,17 +87,65 @@ where
     Self {
       user,
       hb: Instant::now(),
+      heartbeat: ConnectionHeartbeat::new(),
       server,
       heartbeat_interval,
       client_timeout,
       external_source: Some(external_source),
       client_version,
       binary_rate_limiter: Arc::new(rate_limiter),
+      session_limit: None,
+      protocol: RealtimeProtocol::legacy(),
+    }
+  }
+
+  pub fn with_session_limit(mut self, session_limit: SessionLimit) -> Self {
+    self.session_limit = Some(session_limit);
+    self
+  }
+
+  pub fn with_protocol(mut self, protocol: RealtimeProtocol) -> Self {
+    self.protocol = protocol;
+    self
+  }
+
+  /// The message as the client can decode it, None when the client has no equivalent. The clients
+  /// released before a system message was added fail to decode it.
+  fn compatible_message<'a>(
+    &self,
+    message: &'a RealtimeMessage,
+  ) -> Option<Cow<'a, RealtimeMessage>> {
+    if self
+      .protocol
+      .supports(RealtimeCapability::ExtendedSystemMessages)
+    {
+      return Some(Cow::Borrowed(message));
+    }
+    match message {
+      RealtimeMessage::System(SystemMessage::SessionLimitExceeded) => {
+        Some(Cow::Owned(RealtimeMessage::System(SystemMessage::KickOff)))
+      },
+      RealtimeMessage::System(SystemMessage::WorkspaceAccessRevoked(_)) => None,
+      _ => Some(Cow::Borrowed(message)),
     }
   }
 
   fn hb(&self, ctx: &mut ws::WebsocketContext<Self>) {
     ctx.run_interval(self.heartbeat_interval, move |act, ctx| {
+      if act.heartbeat.is_closed() {
+        warn!(
+          "User {} connection was reaped by the server after being idle for {} secs",
+          act.user,
+          act.heartbeat.idle_duration().as_secs()
+        );
+        ctx.close(Some(CloseReason {
+          code: CloseCode::Away,
+          description: Some("Idle connection".to_string()),
+        }));
+        ctx.stop();
+        return;
+      }
+
       if Instant::now().duration_since(act.hb) > act.client_timeout {
         let user = act.user.clone();
         warn!(
@@ -171,10 +239,15 @@ where
   }
 
   fn handle_ping(&mut self, ctx: &mut WebsocketContext<RealtimeClient<S>>, msg: &Bytes) {
-    self.hb = Instant::now();
+    self.touch_heartbeat();
     ctx.pong(msg);
   }
 
+  fn touch_heartbeat(&mut self) {
+    self.hb = Instant::now();
+    self.heartbeat.touch();
+  }
+
   fn handle_close(
     &mut self,
     ctx: &mut WebsocketContext<RealtimeClient<S>>,
@@ -226,6 +299,12 @@ where
         .send(Connect {
           socket: ctx.address().recipient(),
           user: self.user.clone(),
+          heartbeat: self.heartbeat.clone(),
+          max_sessions: self
+            .session_limit
+            .as_ref()
+            .map(|limit| limit.max_sessions)
+            .unwrap_or(0),
         })
         // Converts the future into an actor future, allowing it to be handled within the actor context.
         .into_actor(self)
@@ -272,9 +351,15 @@ where
   type Result = ();
 
   fn handle(&mut self, message: RealtimeMessage, ctx: &mut Self::Context) {
-    match message.encode() {
-      Ok(data) => ctx.binary(Bytes::from(data)),
-      Err(err) => error!("Error encoding message: {}", err),
+    match self.compatible_message(&message) {
+      Some(compatible_message) => {
+        let compress = self.protocol.supports(RealtimeCapability::Compression);
+        match compatible_message.encode_with(compress) {
+          Ok(data) => ctx.binary(Bytes::from(data)),
+          Err(err) => error!("Error encoding message: {}", err),
+        }
+      },
+      None => trace!("{} not supported by the client of {}", message, self.user),
     }
 
     if let RealtimeMessage::System(SystemMessage::DuplicateConnection) = &message {
@@ -284,6 +369,25 @@ where
       };
       ctx.close(Some(reason));
     }
+
+    if let RealtimeMessage::System(SystemMessage::SessionLimitExceeded) = &message {
+      if let Some(SessionLimit {
+        policy,
+        auth_session_id: Some(auth_session_id),
+        ..
+      }) = self.session_limit.clone()
+      {
+        let uid = self.user.uid;
+        actix::spawn(async move {
+          policy.revoke_auth_session(uid, &auth_session_id).await;
+        });
+      }
+      let reason = CloseReason {
+        code: CloseCode::Policy,
+        description: Some("Too many sessions".to_string()),
+      };
+      ctx.close(Some(reason));
+    }
   }
 }
 
@@ -302,7 +406,7 @@ where
 
     match msg.unwrap() {
       ws::Message::Ping(msg) => self.handle_ping(ctx, &msg),
-      ws::Message::Pong(_) => self.hb = Instant::now(),
+      ws::Message::Pong(_) => self.touch_heartbeat(),
       ws::Message::Text(_) => {},
       ws::Message::Binary(bytes) => self.handle_binary(ctx, bytes),
       ws::Message::Close(reason) => self.handle_close(ctx, reason),
@@ -1,3 +1,4 @@
+use crate::connect_state::ConnectionHeartbeat;
 use crate::error::RealtimeError;
 use actix::{Message, Recipient};
 use app_error::AppError;
@@ -5,6 +6,7 @@ use app_error::AppError;
 use bytes::Bytes;
 use collab_entity::CollabType;
 use collab_rt_entity::user::RealtimeUser;
+use collab_rt_entity::CollabLockChanged;
 pub use collab_rt_entity::RealtimeMessage;
 use serde_repr::{Deserialize_repr, Serialize_repr};
 use std::fmt::Debug;
@@ -13,6 +15,9 @@ use std::fmt::Debug;
 pub struct Connect {
   pub socket: Recipient<RealtimeMessage>,
   pub user: RealtimeUser,
+  pub heartbeat: ConnectionHeartbeat,
+  /// Maximum number of sessions the user can keep open, 0 means unlimited.
+  pub max_sessions: usize,
 }
 
 #[derive(Debug, Message, Clone)]
@@ -69,3 +74,10 @@ pub struct ClientGenerateEmbeddingMessage {
   pub object_id: String,
   pub return_tx: Option<tokio::sync::oneshot::Sender<Result<(), AppError>>>,
 }
+
+/// Informs the clients that have the collab open that it was locked or unlocked.
+#[derive(Message)]
+#[rtype(result = "Result<(), AppError>")]
+pub struct CollabLockChangedMessage {
+  pub message: CollabLockChanged,
+}
@@ -6,13 +6,14 @@ use actix::{Actor, Context, Handler};
 use anyhow::anyhow;
 use app_error::AppError;
 use collab_rt_entity::user::UserDevice;
+use collab_rt_entity::RealtimeMessage;
 use database::collab::CollabStorage;
 use tracing::{error, info, trace, warn};
 
 use crate::actix_ws::client::rt_client::{RealtimeClientWebsocketSinkImpl, RealtimeServer};
 use crate::actix_ws::entities::{
   ClientGenerateEmbeddingMessage, ClientHttpStreamMessage, ClientHttpUpdateMessage,
-  ClientWebSocketMessage, Connect, Disconnect,
+  ClientWebSocketMessage, CollabLockChangedMessage, Connect, Disconnect,
 };
 
 #[derive(Clone)]
@@ -71,7 +72,12 @@ where
 
   fn handle(&mut self, new_conn: Connect, _ctx: &mut Context<Self>) -> Self::Result {
     let conn_sink = RealtimeClientWebsocketSinkImpl(new_conn.socket);
-    self.handle_new_connection(new_conn.user, conn_sink)
+    self.handle_new_connection(
+      new_conn.user,
+      conn_sink,
+      new_conn.heartbeat,
+      new_conn.max_sessions,
+    )
   }
 }
 
@@ -97,6 +103,12 @@ where
     _ctx: &mut Context<Self>,
   ) -> Self::Result {
     let ClientWebSocketMessage { user, message } = client_msg;
+    if let RealtimeMessage::ClientBatchInitSync(batch) = message {
+      return self.handle_client_batch_init_sync(user, batch);
+    }
+    if let RealtimeMessage::ClientObserve(observe) = message {
+      return self.handle_client_observe(user, observe);
+    }
     match message.split_messages_by_object_id() {
       Ok(message_by_object_id) => self.handle_client_message(user, message_by_object_id),
       Err(err) => {
@@ -160,6 +172,18 @@ where
   }
 }
 
+impl<S> Handler<CollabLockChangedMessage> for RealtimeServerActor<S>
+where
+  S: CollabStorage + Unpin,
+{
+  type Result = Result<(), AppError>;
+
+  fn handle(&mut self, msg: CollabLockChangedMessage, _ctx: &mut Self::Context) -> Self::Result {
+    self.handle_collab_lock_changed(msg);
+    Ok(())
+  }
+}
+
 impl<S> Handler<ClientGenerateEmbeddingMessage> for RealtimeServerActor<S>
 where
   S: CollabStorage + Unpin,
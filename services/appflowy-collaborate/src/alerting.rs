@@ -0,0 +1,295 @@
+//! Evaluates a few realtime metrics against configurable thresholds and posts a message to a
+//! webhook when one of them is breached, so that operators get notified without having to scrape
+//! Prometheus. The payload carries a `text` field, which makes it usable with Slack-compatible
+//! incoming webhooks as is.
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Display, Formatter};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde_json::json;
+use tokio::time::{interval, Instant, MissedTickBehavior};
+use tracing::{error, info, warn};
+
+use crate::config::AlertingSetting;
+use crate::metrics::{CollabMetrics, CollabRealtimeMetrics};
+
+/// Maximum number of samples kept between two evaluations. Older samples are dropped first.
+const MAX_LATENCY_SAMPLES: usize = 10_000;
+
+/// Bounded buffer of latency samples collected between two alert evaluations. Our prometheus
+/// client doesn't expose quantiles, so the percentiles are computed from these samples instead.
+#[derive(Default)]
+pub struct LatencySamples {
+  samples: Mutex<VecDeque<f64>>,
+}
+
+impl LatencySamples {
+  pub fn push(&self, value: f64) {
+    let mut samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+    if samples.len() >= MAX_LATENCY_SAMPLES {
+      samples.pop_front();
+    }
+    samples.push_back(value);
+  }
+
+  pub fn drain(&self) -> Vec<f64> {
+    let mut samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+    samples.drain(..).collect()
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlertKind {
+  ActiveGroups,
+  BroadcastDelayP99,
+  MemCacheMissRate,
+}
+
+impl Display for AlertKind {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      AlertKind::ActiveGroups => f.write_str("active collab groups"),
+      AlertKind::BroadcastDelayP99 => f.write_str("broadcast delay p99 (ms)"),
+      AlertKind::MemCacheMissRate => f.write_str("collab memory cache miss rate"),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alert {
+  pub kind: AlertKind,
+  pub value: f64,
+  pub threshold: f64,
+}
+
+impl Alert {
+  pub fn message(&self) -> String {
+    format!(
+      "[appflowy-collaborate] {} is {:.2}, above the threshold of {:.2}",
+      self.kind, self.value, self.threshold
+    )
+  }
+}
+
+/// Metric values sampled at each evaluation. Counters are cumulative, the evaluator computes the
+/// difference with the previous sample itself.
+#[derive(Debug, Default)]
+pub struct MetricsSample {
+  pub active_groups: i64,
+  pub broadcast_delays: Vec<f64>,
+  pub mem_cache_hits: u64,
+  pub mem_cache_misses: u64,
+}
+
+pub struct AlertEvaluator {
+  setting: AlertingSetting,
+  last_fired: HashMap<AlertKind, Instant>,
+  last_hits: u64,
+  last_misses: u64,
+}
+
+impl AlertEvaluator {
+  pub fn new(setting: AlertingSetting) -> Self {
+    Self {
+      setting,
+      last_fired: HashMap::new(),
+      last_hits: 0,
+      last_misses: 0,
+    }
+  }
+
+  /// Returns the alerts breached by the given sample, leaving out the ones that already fired
+  /// within the cooldown period.
+  pub fn evaluate(&mut self, sample: MetricsSample, now: Instant) -> Vec<Alert> {
+    let mut breached = vec![];
+    if let Some(threshold) = self.setting.max_active_groups {
+      if sample.active_groups > threshold {
+        breached.push(Alert {
+          kind: AlertKind::ActiveGroups,
+          value: sample.active_groups as f64,
+          threshold: threshold as f64,
+        });
+      }
+    }
+
+    if let Some(threshold) = self.setting.max_broadcast_delay_p99_ms {
+      if let Some(p99) = percentile(sample.broadcast_delays, 0.99) {
+        if p99 > threshold {
+          breached.push(Alert {
+            kind: AlertKind::BroadcastDelayP99,
+            value: p99,
+            threshold,
+          });
+        }
+      }
+    }
+
+    let hits = sample.mem_cache_hits.saturating_sub(self.last_hits);
+    let misses = sample.mem_cache_misses.saturating_sub(self.last_misses);
+    self.last_hits = sample.mem_cache_hits;
+    self.last_misses = sample.mem_cache_misses;
+    if let Some(threshold) = self.setting.max_mem_cache_miss_rate {
+      let reads = hits + misses;
+      if reads > 0 && reads >= self.setting.min_mem_cache_reads {
+        let miss_rate = misses as f64 / reads as f64;
+        if miss_rate > threshold {
+          breached.push(Alert {
+            kind: AlertKind::MemCacheMissRate,
+            value: miss_rate,
+            threshold,
+          });
+        }
+      }
+    }
+
+    let cooldown = Duration::from_secs(self.setting.cooldown_secs);
+    breached
+      .into_iter()
+      .filter(|alert| match self.last_fired.get(&alert.kind) {
+        Some(fired_at) if now.duration_since(*fired_at) < cooldown => false,
+        _ => {
+          self.last_fired.insert(alert.kind, now);
+          true
+        },
+      })
+      .collect()
+  }
+}
+
+/// Returns the value below which `p` of the samples fall, `None` if there are no samples.
+fn percentile(mut samples: Vec<f64>, p: f64) -> Option<f64> {
+  if samples.is_empty() {
+    return None;
+  }
+  samples.sort_by(|a, b| a.total_cmp(b));
+  let rank = (p * samples.len() as f64).ceil() as usize;
+  Some(samples[rank.clamp(1, samples.len()) - 1])
+}
+
+/// Spawns the task evaluating the alert thresholds. Does nothing if no webhook is configured.
+pub fn spawn_alerting(
+  setting: AlertingSetting,
+  realtime_metrics: Arc<CollabRealtimeMetrics>,
+  collab_metrics: Arc<CollabMetrics>,
+) {
+  if setting.webhook_url.is_empty() {
+    info!("Realtime alerting is disabled, no webhook configured");
+    return;
+  }
+
+  let webhook_url = setting.webhook_url.clone();
+  let mut ticker = interval(Duration::from_secs(setting.interval_secs.max(1)));
+  ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+  let mut evaluator = AlertEvaluator::new(setting);
+  let client = reqwest::Client::builder()
+    .timeout(Duration::from_secs(10))
+    .build()
+    .unwrap_or_default();
+
+  tokio::spawn(async move {
+    loop {
+      ticker.tick().await;
+      let sample = MetricsSample {
+        active_groups: realtime_metrics.opening_collab_count.get(),
+        broadcast_delays: realtime_metrics.broadcast_delay_samples.drain(),
+        mem_cache_hits: collab_metrics.redis_read_collab_count.get(),
+        mem_cache_misses: collab_metrics.redis_miss_collab_count.get(),
+      };
+      for alert in evaluator.evaluate(sample, Instant::now()) {
+        let message = alert.message();
+        warn!("{}", message);
+        let result = client
+          .post(&webhook_url)
+          .json(&json!({ "text": message }))
+          .send()
+          .await
+          .and_then(|resp| resp.error_for_status());
+        if let Err(err) = result {
+          error!("Failed to send alert to the webhook: {}", err);
+        }
+      }
+    }
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn setting() -> AlertingSetting {
+    AlertingSetting {
+      webhook_url: "http://localhost".to_string(),
+      interval_secs: 60,
+      cooldown_secs: 600,
+      max_active_groups: Some(100),
+      max_broadcast_delay_p99_ms: Some(50.0),
+      max_mem_cache_miss_rate: Some(0.5),
+      min_mem_cache_reads: 10,
+    }
+  }
+
+  #[test]
+  fn percentile_test() {
+    assert_eq!(percentile(vec![], 0.99), None);
+    assert_eq!(percentile(vec![3.0], 0.99), Some(3.0));
+    let samples = (1..=100).map(|i| i as f64).rev().collect();
+    assert_eq!(percentile(samples, 0.99), Some(99.0));
+  }
+
+  #[tokio::test]
+  async fn alert_fires_once_within_cooldown() {
+    let mut evaluator = AlertEvaluator::new(setting());
+    let now = Instant::now();
+    let sample = || MetricsSample {
+      active_groups: 101,
+      ..Default::default()
+    };
+
+    let alerts = evaluator.evaluate(sample(), now);
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].kind, AlertKind::ActiveGroups);
+    assert!(evaluator
+      .evaluate(sample(), now + Duration::from_secs(60))
+      .is_empty());
+    assert_eq!(
+      evaluator
+        .evaluate(sample(), now + Duration::from_secs(601))
+        .len(),
+      1
+    );
+  }
+
+  #[tokio::test]
+  async fn mem_cache_miss_rate_uses_counter_deltas() {
+    let mut evaluator = AlertEvaluator::new(setting());
+    let now = Instant::now();
+    // 90 hits / 10 misses: below the threshold
+    let alerts = evaluator.evaluate(
+      MetricsSample {
+        mem_cache_hits: 90,
+        mem_cache_misses: 10,
+        ..Default::default()
+      },
+      now,
+    );
+    assert!(alerts.is_empty());
+
+    // 5 new hits / 15 new misses since the previous sample: 75% miss rate
+    let alerts = evaluator.evaluate(
+      MetricsSample {
+        mem_cache_hits: 95,
+        mem_cache_misses: 25,
+        broadcast_delays: vec![10.0, 20.0, 80.0],
+        ..Default::default()
+      },
+      now,
+    );
+    let kinds = alerts.iter().map(|alert| alert.kind).collect::<Vec<_>>();
+    assert_eq!(
+      kinds,
+      vec![AlertKind::BroadcastDelayP99, AlertKind::MemCacheMissRate]
+    );
+    assert_eq!(alerts[1].value, 0.75);
+  }
+}
@@ -23,7 +23,7 @@ use collab_rt_entity::user::{AFUserChange, RealtimeUser, UserMessage};
 use collab_rt_entity::{HttpRealtimeMessage, RealtimeMessage};
 use shared_entity::response::{AppResponse, AppResponseError};
 
-use crate::actix_ws::client::RealtimeClient;
+use crate::actix_ws::client::{RealtimeClient, SessionLimit};
 use crate::actix_ws::entities::ClientHttpStreamMessage;
 use crate::actix_ws::server::RealtimeServerActor;
 use crate::collab::storage::CollabAccessControlStorage;
@@ -248,6 +248,11 @@ async fn start_connect(
   connect_at: i64,
 ) -> Result<HttpResponse> {
   let auth = authorization_from_token(access_token.as_str(), jwt_secret)?;
+  let auth_session_id = auth
+    .claims
+    .session_id
+    .as_deref()
+    .and_then(|session_id| uuid::Uuid::parse_str(session_id).ok());
   let user_uuid = UserUuid::from_auth(auth)?;
   let result = state.user_cache.get_user_uid(&user_uuid).await;
 
@@ -266,6 +271,11 @@ async fn start_connect(
         connect_at,
         client_app_version.to_string(),
       );
+      let session_limit = SessionLimit {
+        max_sessions: state.session_policy.max_sessions(uid).await,
+        policy: state.session_policy.clone(),
+        auth_session_id,
+      };
       let (tx, external_source) = mpsc::channel(100);
       let client = RealtimeClient::new(
         realtime_user,
@@ -274,8 +284,12 @@ async fn start_connect(
         Duration::from_secs(state.config.websocket.client_timeout as u64),
         client_app_version,
         external_source,
-        10,
-      );
+        state
+          .reloadable_setting
+          .borrow()
+          .websocket_rate_limit_per_sec,
+      )
+      .with_session_limit(session_limit);
 
       // Receive user change notifications and send them to the client.
       listen_on_user_change(state, uid, tx);
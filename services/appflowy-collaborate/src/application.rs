@@ -3,6 +3,11 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use access_control::casbin::collab::{CollabAccessControlImpl, RealtimeCollabAccessControlImpl};
+use access_control::collab_guest::CollabGuestCache;
+use access_control::collab_lock::{CollabLockCache, CollabLockRealtimeAccessControl};
+use access_control::restricted_view::RestrictedViewCache;
+use access_control::session_policy::SessionPolicy;
+use access_control::workspace_read_only::WorkspaceReadOnlyCache;
 
 use access_control::casbin::workspace::WorkspaceAccessControlImpl;
 use actix::Supervisor;
@@ -18,20 +23,31 @@ use aws_sdk_s3::types::{
 use secrecy::{ExposeSecret, Secret};
 use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
+use tokio::sync::watch;
 use tracing::info;
 
 use crate::actix_ws::server::RealtimeServerActor;
+use crate::alerting::spawn_alerting;
 use crate::api::{collab_scope, ws_scope};
 use crate::collab::access_control::CollabStorageAccessControlImpl;
 use access_control::casbin::access::AccessControl;
 use collab_stream::metrics::CollabStreamMetrics;
 use collab_stream::stream_router::{StreamRouter, StreamRouterOptions};
+use database::file::azure_client_impl::AzureBlobClientImpl;
+use database::file::fs_client_impl::LocalFsBucketClientImpl;
+use database::file::gcs_client_impl::GcsBucketClientImpl;
+use database::file::memory_client_impl::InMemoryBucketClientImpl;
 use database::file::s3_client_impl::AwsS3BucketClientImpl;
+use database::file::{
+  BlobStorageBackend, BlobStorageClient, WorkspaceStorageCipher, WorkspaceStorageRouter,
+};
 
 use crate::collab::cache::CollabCache;
 use crate::collab::storage::CollabStorageImpl;
-use crate::command::{CLCommandReceiver, CLCommandSender};
-use crate::config::{get_env_var, Config, DatabaseSetting, S3Setting};
+use crate::collab::view_tree::CollabCacheViewTreeLoader;
+use crate::command::{spawn_workspace_member_revocation, CLCommandReceiver, CLCommandSender};
+use crate::config::{get_env_var, Config, DatabaseSetting, ReloadableSetting, S3Setting};
+use crate::config_reload::spawn_config_reload;
 use crate::pg_listener::PgListeners;
 use crate::snapshot::SnapshotControl;
 use crate::state::{AppMetrics, AppState, UserCache};
@@ -76,20 +92,32 @@ pub async fn run_actix_server(
   // Initialize metrics that which are registered in the registry.
   let realtime_server = CollaborationServer::<_>::new(
     storage.clone(),
-    Arc::new(RealtimeCollabAccessControlImpl::new(
-      state.access_control.clone(),
+    Arc::new(CollabLockRealtimeAccessControl::new(
+      Arc::new(RealtimeCollabAccessControlImpl::new(
+        state.access_control.clone(),
+        state.workspace_read_only_cache.clone(),
+        state.collab_guest_cache.clone(),
+        state.restricted_view_cache.clone(),
+      )),
+      state.collab_lock_cache.clone(),
     )),
+    state.restricted_view_cache.clone(),
     state.metrics.realtime_metrics.clone(),
     rt_cmd_recv,
+    state.redis_client.clone(),
     state.redis_stream_router.clone(),
     state.redis_connection_manager.clone(),
-    Duration::from_secs(config.collab.group_persistence_interval_secs),
-    Duration::from_secs(config.collab.group_prune_grace_period_secs),
+    state.reloadable_setting.clone(),
     state.indexer_scheduler.clone(),
   )
   .await
   .unwrap();
   let realtime_server_actor = Supervisor::start(|_| RealtimeServerActor(realtime_server));
+  spawn_alerting(
+    config.alerting.clone(),
+    state.metrics.realtime_metrics.clone(),
+    state.metrics.collab_metrics.clone(),
+  );
   let mut server = HttpServer::new(move || {
     App::new()
       .app_data(Data::new(state.clone()))
@@ -111,7 +139,7 @@ pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<A
   let user_cache = UserCache::new(pg_pool.clone()).await;
 
   info!("Connecting to Redis...");
-  let (redis_conn_manager, redis_stream_router) = get_redis_client(
+  let (redis_client, redis_conn_manager, redis_stream_router) = get_redis_client(
     config.redis_uri.expose_secret(),
     config.redis_worker_count,
     metrics.collab_stream_metrics.clone(),
@@ -121,19 +149,22 @@ pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<A
   // Pg listeners
   info!("Setting up Pg listeners...");
   let pg_listeners = Arc::new(PgListeners::new(&pg_pool).await?);
+  spawn_workspace_member_revocation(
+    pg_listeners.subscribe_workspace_member_change(),
+    rt_cmd_tx.clone(),
+  );
   let access_control =
     AccessControl::new(pg_pool.clone(), metrics.access_control_metrics.clone()).await?;
 
   info!("Setting up S3 bucket...");
-  let s3_client = AwsS3BucketClientImpl::new(
-    get_aws_s3_client(&config.s3).await?,
-    config.s3.bucket.clone(),
-    config.s3.minio_url.clone(),
-    config.s3.presigned_url_endpoint.clone(),
-  );
+  let s3_client = match get_workspace_storage_router(config, &pg_pool)? {
+    Some(router) => get_blob_storage_client(config)
+      .await?
+      .with_workspace_storage(router),
+    None => get_blob_storage_client(config).await?,
+  };
 
-  let collab_access_control = CollabAccessControlImpl::new(access_control.clone());
-  let workspace_access_control = WorkspaceAccessControlImpl::new(access_control.clone());
+  let collab_guest_cache = CollabGuestCache::new(pg_pool.clone());
   let collab_cache = CollabCache::new(
     redis_conn_manager.clone(),
     pg_pool.clone(),
@@ -141,16 +172,29 @@ pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<A
     metrics.collab_metrics.clone(),
     config.collab.s3_collab_threshold as usize,
   );
+  let restricted_view_cache =
+    RestrictedViewCache::new(pg_pool.clone()).with_view_tree_loader(Arc::new(
+      CollabCacheViewTreeLoader::new(collab_cache.clone(), pg_pool.clone()),
+    ));
+  let collab_access_control = CollabAccessControlImpl::new(
+    access_control.clone(),
+    collab_guest_cache.clone(),
+    restricted_view_cache.clone(),
+  );
+  let workspace_access_control = WorkspaceAccessControlImpl::new(access_control.clone());
 
+  let collab_lock_cache = CollabLockCache::new(pg_pool.clone());
   let collab_storage_access_control = CollabStorageAccessControlImpl {
     collab_access_control: Arc::new(collab_access_control.clone()),
     workspace_access_control: Arc::new(workspace_access_control.clone()),
+    collab_lock: collab_lock_cache.clone(),
     cache: collab_cache.clone(),
   };
   let snapshot_control = SnapshotControl::new(
     pg_pool.clone(),
     s3_client.clone(),
     metrics.collab_metrics.clone(),
+    config.collab.snapshot_offload_threshold,
   )
   .await;
   let collab_storage = Arc::new(CollabStorageImpl::new(
@@ -179,16 +223,27 @@ pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<A
     redis_conn_manager.clone(),
   );
 
+  let (reloadable_setting_tx, reloadable_setting) =
+    watch::channel(ReloadableSetting::from_config(config));
+  spawn_config_reload(config.clone(), reloadable_setting_tx);
+
   let app_state = AppState {
     config: Arc::new(config.clone()),
     pg_listeners,
     user_cache,
+    redis_client,
     redis_stream_router,
     redis_connection_manager: redis_conn_manager,
     access_control,
+    collab_lock_cache,
+    workspace_read_only_cache: WorkspaceReadOnlyCache::new(pg_pool.clone()),
+    restricted_view_cache,
+    collab_guest_cache,
+    session_policy: SessionPolicy::new(pg_pool.clone(), config.websocket.max_sessions_per_user),
     collab_access_control_storage: collab_storage,
     metrics,
     indexer_scheduler,
+    reloadable_setting,
   };
   Ok(app_state)
 }
@@ -197,7 +252,14 @@ async fn get_redis_client(
   redis_uri: &str,
   worker_count: usize,
   metrics: Arc<CollabStreamMetrics>,
-) -> Result<(redis::aio::ConnectionManager, Arc<StreamRouter>), Error> {
+) -> Result<
+  (
+    redis::Client,
+    redis::aio::ConnectionManager,
+    Arc<StreamRouter>,
+  ),
+  Error,
+> {
   info!("Connecting to redis with uri: {}", redis_uri);
   let client = redis::Client::open(redis_uri).context("failed to connect to redis")?;
 
@@ -216,7 +278,7 @@ async fn get_redis_client(
     .get_connection_manager()
     .await
     .context("failed to get the connection manager")?;
-  Ok((manager, router.into()))
+  Ok((client, manager, router.into()))
 }
 
 async fn get_connection_pool(setting: &DatabaseSetting) -> Result<PgPool, Error> {
@@ -231,6 +293,82 @@ async fn get_connection_pool(setting: &DatabaseSetting) -> Result<PgPool, Error>
     .map_err(|e| anyhow::anyhow!("Failed to connect to postgres database: {}", e))
 }
 
+/// Returns the router of the workspaces that use their own bucket, None when the per-workspace
+/// storage is not configured.
+pub fn get_workspace_storage_router(
+  config: &Config,
+  pg_pool: &PgPool,
+) -> Result<Option<Arc<WorkspaceStorageRouter>>, Error> {
+  let Some(key) = &config.blob_storage.workspace_storage_key else {
+    return Ok(None);
+  };
+  let cipher = WorkspaceStorageCipher::from_base64(key.expose_secret())?;
+  let credentials = Credentials::new(
+    config.s3.access_key.clone(),
+    config.s3.secret_key.expose_secret().clone(),
+    None,
+    None,
+    "custom",
+  );
+  Ok(Some(Arc::new(WorkspaceStorageRouter::new(
+    pg_pool.clone(),
+    cipher,
+    credentials,
+    Duration::from_secs(config.blob_storage.workspace_storage_cache_ttl_secs),
+  ))))
+}
+
+pub async fn get_blob_storage_client(config: &Config) -> Result<BlobStorageClient, Error> {
+  let s3_setting = &config.s3;
+  let client = match config.blob_storage.backend {
+    BlobStorageBackend::S3 => BlobStorageClient::S3(AwsS3BucketClientImpl::new(
+      get_aws_s3_client(s3_setting).await?,
+      s3_setting.bucket.clone(),
+      s3_setting.minio_url.clone(),
+      s3_setting.presigned_url_endpoint.clone(),
+    )),
+    BlobStorageBackend::Gcs => {
+      let gcs_setting = S3Setting {
+        // Buckets are expected to be created in the Google Cloud console.
+        create_bucket: false,
+        use_minio: true,
+        minio_url: config.blob_storage.gcs_endpoint.clone(),
+        region: if s3_setting.region.is_empty() {
+          "auto".to_string()
+        } else {
+          s3_setting.region.clone()
+        },
+        ..s3_setting.clone()
+      };
+      BlobStorageClient::Gcs(GcsBucketClientImpl::new(AwsS3BucketClientImpl::new(
+        get_aws_s3_client(&gcs_setting).await?,
+        gcs_setting.bucket.clone(),
+        gcs_setting.minio_url.clone(),
+        gcs_setting.presigned_url_endpoint.clone(),
+      )))
+    },
+    BlobStorageBackend::Azure => {
+      let azure = &config.blob_storage.azure;
+      BlobStorageClient::Azure(AzureBlobClientImpl::new(
+        azure.account_name.clone(),
+        azure.account_key.expose_secret(),
+        azure.container.clone(),
+        azure.endpoint.clone(),
+      )?)
+    },
+    BlobStorageBackend::LocalFs => {
+      let local = &config.blob_storage.local;
+      BlobStorageClient::LocalFs(LocalFsBucketClientImpl::new(
+        local.root.clone().into(),
+        local.signing_key.expose_secret(),
+        local.public_url.clone(),
+      )?)
+    },
+    BlobStorageBackend::Memory => BlobStorageClient::Memory(InMemoryBucketClientImpl::new()),
+  };
+  Ok(client)
+}
+
 pub async fn get_aws_s3_client(s3_setting: &S3Setting) -> Result<aws_sdk_s3::Client, Error> {
   let credentials = Credentials::new(
     s3_setting.access_key.clone(),
@@ -8,7 +8,7 @@ use tracing::{error, trace};
 
 use access_control::collab::RealtimeAccessControl;
 use collab_rt_entity::user::RealtimeUser;
-use collab_rt_entity::ClientCollabMessage;
+use collab_rt_entity::{AckCode, ClientCollabMessage, CollabAck, ServerCollabMessage};
 use collab_rt_entity::{MessageByObjectId, RealtimeMessage};
 
 use crate::util::channel_ext::UnboundedSenderSink;
@@ -103,6 +103,7 @@ impl ClientMessageRouter {
     // forward the message to the subscriber which is the broadcast channel [CollabBroadcast].
     let (client_msg_rx, rx) = tokio::sync::mpsc::channel(100);
     let client_stream = ReceiverStream::new(rx);
+    let reject_sink = self.sink.clone();
     tokio::spawn(async move {
       while let Some(Ok(messages_by_oid)) = stream_rx.next().await {
         for (message_object_id, original_messages) in messages_by_oid.into_inner() {
@@ -132,6 +133,30 @@ impl ClientMessageRouter {
             invalid_message.len()
           );
 
+          // The messages are dropped silently when the user has no permission, but the client is
+          // told when the workspace is read-only, so it can stop sending the updates.
+          if !invalid_message.is_empty()
+            && access_control
+              .is_workspace_read_only(&stream_workspace_id)
+              .await
+              .unwrap_or(false)
+          {
+            let acks = invalid_message
+              .iter()
+              .map(|message| {
+                let ack = CollabAck::new(
+                  message.origin().clone(),
+                  message.object_id().to_string(),
+                  message.msg_id(),
+                  0,
+                )
+                .with_code(AckCode::WorkspaceReadOnly);
+                ServerCollabMessage::ClientAck(ack)
+              })
+              .collect();
+            reject_sink.do_send(RealtimeMessage::ServerCollabV1(acks));
+          }
+
           if valid_messages.is_empty() {
             continue;
           }
@@ -4,18 +4,31 @@ use std::sync::Arc;
 use crate::collab::cache::CollabCache;
 use access_control::act::Action;
 use access_control::collab::CollabAccessControl;
+use access_control::collab_lock::CollabLockCache;
 use access_control::workspace::WorkspaceAccessControl;
 use app_error::AppError;
 use database::collab::CollabStorageAccessControl;
 use database_entity::dto::AFAccessLevel;
+use uuid::Uuid;
 
 #[derive(Clone)]
 pub struct CollabStorageAccessControlImpl {
   pub collab_access_control: Arc<dyn CollabAccessControl>,
   pub workspace_access_control: Arc<dyn WorkspaceAccessControl>,
+  /// Checked for every write, whatever access control is enabled.
+  pub collab_lock: CollabLockCache,
   pub cache: CollabCache,
 }
 
+impl CollabStorageAccessControlImpl {
+  async fn enforce_unlocked(&self, workspace_id: &str, oid: &str) -> Result<(), AppError> {
+    match Uuid::parse_str(workspace_id) {
+      Ok(workspace_id) => self.collab_lock.enforce_unlocked(&workspace_id, oid).await,
+      Err(_) => Ok(()),
+    }
+  }
+}
+
 #[async_trait]
 impl CollabStorageAccessControl for CollabStorageAccessControlImpl {
   async fn update_policy(
@@ -63,7 +76,8 @@ impl CollabStorageAccessControl for CollabStorageAccessControlImpl {
     self
       .collab_access_control
       .enforce_action(workspace_id, uid, oid, Action::Write)
-      .await
+      .await?;
+    self.enforce_unlocked(workspace_id, oid).await
   }
 
   async fn enforce_write_workspace(&self, uid: &i64, workspace_id: &str) -> Result<(), AppError> {
@@ -77,6 +91,7 @@ impl CollabStorageAccessControl for CollabStorageAccessControlImpl {
     self
       .collab_access_control
       .enforce_access_level(workspace_id, uid, oid, AFAccessLevel::FullAccess)
-      .await
+      .await?;
+    self.enforce_unlocked(workspace_id, oid).await
   }
 }
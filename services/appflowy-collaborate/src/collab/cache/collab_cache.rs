@@ -6,14 +6,17 @@ use itertools::{Either, Itertools};
 use sqlx::{PgPool, Transaction};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tracing::{error, event, Level};
+use tracing::{error, event, trace, Level};
 
 use super::disk_cache::CollabDiskCache;
 use super::mem_cache::{cache_exp_secs_from_collab_type, CollabMemCache};
 use crate::CollabMetrics;
 use app_error::AppError;
-use database::file::s3_client_impl::AwsS3BucketClientImpl;
-use database_entity::dto::{CollabParams, PendingCollabWrite, QueryCollab, QueryCollabResult};
+use database::file::BlobStorageClient;
+use database_entity::dto::{
+  CollabEditCounts, CollabParams, DatabaseRowCellChange, PendingCollabWrite, QueryCollab,
+  QueryCollabResult, WarmUpCollabResult,
+};
 
 #[derive(Clone)]
 pub struct CollabCache {
@@ -27,7 +30,7 @@ impl CollabCache {
   pub fn new(
     redis_conn_manager: redis::aio::ConnectionManager,
     pg_pool: PgPool,
-    s3: AwsS3BucketClientImpl,
+    s3: BlobStorageClient,
     metrics: Arc<CollabMetrics>,
     s3_collab_threshold: usize,
   ) -> Self {
@@ -119,6 +122,63 @@ impl CollabCache {
     Ok(encode_collab)
   }
 
+  /// Loads the given collabs from the disk cache into the memory cache, so that the first editor
+  /// doesn't pay the cost of reading them from Postgres/S3. Collabs are loaded in the given order
+  /// until their total encoded size reaches `budget_bytes`, the remaining ones are skipped.
+  pub async fn warm_up(
+    &self,
+    workspace_id: &str,
+    queries: Vec<QueryCollab>,
+    budget_bytes: usize,
+  ) -> WarmUpCollabResult {
+    let mut result = WarmUpCollabResult::default();
+    let mut used_bytes = 0;
+    for query in queries {
+      if used_bytes >= budget_bytes {
+        result.skipped.push(query.object_id);
+        continue;
+      }
+      if let Ok(true) = self.mem_cache.is_exist(&query.object_id).await {
+        result.cached.push(query.object_id);
+        continue;
+      }
+
+      let object_id = query.object_id.clone();
+      let expiration_secs = cache_exp_secs_from_collab_type(&query.collab_type);
+      let data = match self
+        .disk_cache
+        .get_collab_encoded_from_disk(workspace_id, query)
+        .await
+        .and_then(|encoded_collab| {
+          encoded_collab
+            .encode_to_bytes()
+            .map_err(|err| AppError::Internal(err.into()))
+        }) {
+        Ok(data) => data,
+        Err(err) => {
+          trace!("skip warming up collab {}: {}", object_id, err);
+          result.skipped.push(object_id);
+          continue;
+        },
+      };
+
+      used_bytes += data.len();
+      let timestamp = chrono::Utc::now().timestamp();
+      match self
+        .mem_cache
+        .insert_encode_collab_data(&object_id, &data, timestamp, Some(expiration_secs))
+        .await
+      {
+        Ok(_) => result.warmed.push(object_id),
+        Err(err) => {
+          error!("Failed to warm up collab {}: {}", object_id, err);
+          result.skipped.push(object_id);
+        },
+      }
+    }
+    result
+  }
+
   /// Batch get the encoded collab data from the cache.
   /// returns a hashmap of the object_id to the encoded collab data.
   pub async fn batch_get_encode_collab<T: Into<QueryCollab>>(
@@ -236,6 +296,30 @@ impl CollabCache {
     Ok(())
   }
 
+  pub async fn record_edit_counts(
+    &self,
+    workspace_id: &str,
+    oid: &str,
+    counts: &CollabEditCounts,
+  ) -> Result<(), AppError> {
+    self
+      .disk_cache
+      .record_edit_counts(workspace_id, oid, counts)
+      .await
+  }
+
+  pub async fn record_database_row_activity(
+    &self,
+    workspace_id: &str,
+    row_id: &str,
+    changes: &[DatabaseRowCellChange],
+  ) -> Result<(), AppError> {
+    self
+      .disk_cache
+      .record_database_row_activity(workspace_id, row_id, changes)
+      .await
+  }
+
   pub async fn is_exist(&self, workspace_id: &str, oid: &str) -> Result<bool, AppError> {
     if let Ok(value) = self.mem_cache.is_exist(oid).await {
       if value {
@@ -9,24 +9,28 @@ use std::time::{Duration, Instant};
 use tokio::task::JoinSet;
 use tokio::time::sleep;
 use tracing::{error, instrument};
+use uuid::Uuid;
 
 use crate::collab::cache::encode_collab_from_bytes;
 use crate::CollabMetrics;
 use app_error::AppError;
 use database::collab::{
-  batch_select_collab_blob, insert_into_af_collab, insert_into_af_collab_bulk_for_user,
-  is_collab_exists, select_blob_from_af_collab, AppResult,
+  batch_select_collab_blob, insert_database_row_activity, insert_into_af_collab,
+  insert_into_af_collab_bulk_for_user, is_collab_exists, restore_archived_collab,
+  select_blob_from_af_collab_in_workspace, select_collab_archive, upsert_collab_edit_counts,
+  AppResult,
 };
-use database::file::s3_client_impl::AwsS3BucketClientImpl;
+use database::file::BlobStorageClient;
 use database::file::{BucketClient, ResponseBlob};
 use database_entity::dto::{
-  CollabParams, PendingCollabWrite, QueryCollab, QueryCollabResult, ZSTD_COMPRESSION_LEVEL,
+  CollabEditCounts, CollabParams, DatabaseRowCellChange, PendingCollabWrite, QueryCollab,
+  QueryCollabResult, ZSTD_COMPRESSION_LEVEL,
 };
 
 #[derive(Clone)]
 pub struct CollabDiskCache {
   pg_pool: PgPool,
-  s3: AwsS3BucketClientImpl,
+  s3: BlobStorageClient,
   s3_collab_threshold: usize,
   metrics: Arc<CollabMetrics>,
 }
@@ -34,7 +38,7 @@ pub struct CollabDiskCache {
 impl CollabDiskCache {
   pub fn new(
     pg_pool: PgPool,
-    s3: AwsS3BucketClientImpl,
+    s3: BlobStorageClient,
     s3_collab_threshold: usize,
     metrics: Arc<CollabMetrics>,
   ) -> Self {
@@ -57,6 +61,26 @@ impl CollabDiskCache {
     }
   }
 
+  pub async fn record_edit_counts(
+    &self,
+    workspace_id: &str,
+    object_id: &str,
+    counts: &CollabEditCounts,
+  ) -> AppResult<()> {
+    let workspace_id = Uuid::parse_str(workspace_id)?;
+    upsert_collab_edit_counts(&self.pg_pool, &workspace_id, object_id, counts).await
+  }
+
+  pub async fn record_database_row_activity(
+    &self,
+    workspace_id: &str,
+    row_id: &str,
+    changes: &[DatabaseRowCellChange],
+  ) -> AppResult<()> {
+    let workspace_id = Uuid::parse_str(workspace_id)?;
+    insert_database_row_activity(&self.pg_pool, &workspace_id, row_id, changes).await
+  }
+
   pub async fn upsert_collab(
     &self,
     workspace_id: &str,
@@ -95,7 +119,7 @@ impl CollabDiskCache {
     Ok(())
   }
 
-  pub fn s3_client(&self) -> AwsS3BucketClientImpl {
+  pub fn s3_client(&self) -> BlobStorageClient {
     self.s3.clone()
   }
 
@@ -104,7 +128,7 @@ impl CollabDiskCache {
     uid: &i64,
     mut params: CollabParams,
     transaction: &mut Transaction<'_, sqlx::Postgres>,
-    s3: AwsS3BucketClientImpl,
+    s3: BlobStorageClient,
     s3_collab_threshold: usize,
     metrics: &CollabMetrics,
   ) -> AppResult<()> {
@@ -168,16 +192,27 @@ impl CollabDiskCache {
       },
     }
 
+    let workspace_uuid = Uuid::parse_str(workspace_id)?;
     const MAX_ATTEMPTS: usize = 3;
     let mut attempts = 0;
 
     loop {
-      let result =
-        select_blob_from_af_collab(&self.pg_pool, &query.collab_type, &query.object_id).await;
+      let result = select_blob_from_af_collab_in_workspace(
+        &self.pg_pool,
+        &workspace_uuid,
+        &query.collab_type,
+        &query.object_id,
+      )
+      .await;
 
       match result {
         Ok(data) => {
           self.metrics.pg_read_collab_count.inc();
+          if data.is_empty() {
+            if let Some(encoded_collab) = self.rehydrate_collab(&query).await? {
+              return Ok(encoded_collab);
+            }
+          }
           return encode_collab_from_bytes(data).await;
         },
         Err(e) => {
@@ -319,25 +354,113 @@ impl CollabDiskCache {
     queries: Vec<QueryCollab>,
   ) -> HashMap<String, QueryCollabResult> {
     let mut results = HashMap::new();
+    let workspace_uuid = match Uuid::parse_str(workspace_id) {
+      Ok(workspace_uuid) => workspace_uuid,
+      Err(err) => {
+        results.extend(queries.into_iter().map(|query| {
+          (
+            query.object_id,
+            QueryCollabResult::Failed {
+              error: format!("invalid workspace id {}: {}", workspace_id, err),
+            },
+          )
+        }));
+        return results;
+      },
+    };
     let not_found = batch_get_collab_from_s3(&self.s3, workspace_id, queries, &mut results).await;
     let s3_fetch = results.len() as u64;
-    batch_select_collab_blob(&self.pg_pool, not_found, &mut results).await;
+    batch_select_collab_blob(
+      &self.pg_pool,
+      &workspace_uuid,
+      not_found.clone(),
+      &mut results,
+    )
+    .await;
     let pg_fetch = results.len() as u64 - s3_fetch;
     self.metrics.s3_read_collab_count.inc_by(s3_fetch);
     self.metrics.pg_read_collab_count.inc_by(pg_fetch);
+
+    // archived collabs have an empty blob until they are rehydrated
+    for query in not_found {
+      let is_archived = matches!(
+        results.get(&query.object_id),
+        Some(QueryCollabResult::Success { encode_collab_v1 }) if encode_collab_v1.is_empty()
+      );
+      if !is_archived {
+        continue;
+      }
+      let result = match self.rehydrate_collab(&query).await {
+        Ok(Some(encoded_collab)) => match encoded_collab.encode_to_bytes() {
+          Ok(encode_collab_v1) => QueryCollabResult::Success { encode_collab_v1 },
+          Err(err) => QueryCollabResult::Failed {
+            error: err.to_string(),
+          },
+        },
+        Ok(None) => continue,
+        Err(err) => QueryCollabResult::Failed {
+          error: err.to_string(),
+        },
+      };
+      results.insert(query.object_id, result);
+    }
     results
   }
 
+  /// Restores a collab archived because of its inactivity: the archive is read from the object
+  /// storage and written back to Postgres. Returns `None` if the collab is not archived.
+  async fn rehydrate_collab(&self, query: &QueryCollab) -> AppResult<Option<EncodedCollab>> {
+    let archive =
+      match select_collab_archive(&self.pg_pool, &query.object_id, &query.collab_type).await? {
+        Some(archive) if !archive.in_place => archive,
+        _ => return Ok(None),
+      };
+
+    let start = Instant::now();
+    let compressed = self.s3.get_blob(&archive.archive_key).await?.to_blob();
+    let doc_state = zstd::decode_all(&*compressed)?;
+    let encoded_collab = EncodedCollab {
+      state_vector: Default::default(),
+      doc_state: doc_state.into(),
+      version: EncoderVersion::V1,
+    };
+    let blob = encoded_collab
+      .encode_to_bytes()
+      .map_err(|err| AppError::Internal(err.into()))?;
+    restore_archived_collab(&self.pg_pool, &archive.oid, archive.partition_key, &blob).await?;
+    self.metrics.rehydrate_collab_count.inc();
+    tracing::info!(
+      "rehydrated archived collab {} ({} storage) in {:?}",
+      archive.oid,
+      archive.storage_class,
+      start.elapsed()
+    );
+
+    let s3 = self.s3.clone();
+    tokio::spawn(async move {
+      if let Err(err) = s3.delete_blob(&archive.archive_key).await {
+        tracing::warn!(
+          "failed to delete collab archive {}: {}",
+          archive.archive_key,
+          err
+        );
+      }
+    });
+    Ok(Some(encoded_collab))
+  }
+
   pub async fn delete_collab(&self, workspace_id: &str, object_id: &str) -> AppResult<()> {
-    sqlx::query!(
+    let workspace_uuid = Uuid::parse_str(workspace_id)?;
+    sqlx::query(
       r#"
         UPDATE af_collab
-        SET deleted_at = $2
-        WHERE oid = $1;
+        SET deleted_at = $3
+        WHERE workspace_id = $1 AND oid = $2;
         "#,
-      object_id,
-      chrono::Utc::now()
     )
+    .bind(workspace_uuid)
+    .bind(object_id)
+    .bind(chrono::Utc::now())
     .execute(&self.pg_pool)
     .await?;
     let key = collab_key(workspace_id, object_id);
@@ -348,7 +471,7 @@ impl CollabDiskCache {
   }
 
   async fn insert_blob_with_retries(
-    s3: AwsS3BucketClientImpl,
+    s3: BlobStorageClient,
     key: String,
     blob: Bytes,
     mut retries: usize,
@@ -391,7 +514,7 @@ impl CollabDiskCache {
 }
 
 async fn batch_put_collab_to_s3(
-  s3: &AwsS3BucketClientImpl,
+  s3: &BlobStorageClient,
   collabs: HashMap<String, Bytes>,
 ) -> Result<(), AppError> {
   let mut join_set = JoinSet::<Result<(), AppError>>::new();
@@ -419,7 +542,7 @@ async fn batch_put_collab_to_s3(
 }
 
 async fn batch_get_collab_from_s3(
-  s3: &AwsS3BucketClientImpl,
+  s3: &BlobStorageClient,
   workspace_id: &str,
   params: Vec<QueryCollab>,
   results: &mut HashMap<String, QueryCollabResult>,
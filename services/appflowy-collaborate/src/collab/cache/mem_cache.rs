@@ -286,6 +286,7 @@ impl CollabMemCache {
       }
     } else {
       // No data found for the provided object_id
+      self.metrics.redis_miss_collab_count.inc();
       Ok(None)
     }
   }
@@ -2,3 +2,4 @@ pub mod access_control;
 pub mod cache;
 pub mod storage;
 pub mod validator;
+pub mod view_tree;
@@ -12,8 +12,10 @@ use database::collab::{
   CollabStorageAccessControl, GetCollabOrigin,
 };
 use database_entity::dto::{
-  AFAccessLevel, AFSnapshotMeta, AFSnapshotMetas, CollabParams, InsertSnapshotParams,
-  PendingCollabWrite, QueryCollab, QueryCollabParams, QueryCollabResult, SnapshotData,
+  AFAccessLevel, AFCollabCheckpoint, AFCollabHistory, AFSnapshotMeta, AFSnapshotMetas,
+  CollabEditCounts, CollabParams, DatabaseRowCellChange, InsertCheckpointParams,
+  InsertSnapshotParams, PendingCollabWrite, QueryCollab, QueryCollabParams, QueryCollabResult,
+  SnapshotData,
 };
 use itertools::{Either, Itertools};
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
@@ -567,4 +569,54 @@ where
       .get_collab_snapshot_list(workspace_id, oid)
       .await
   }
+
+  async fn create_checkpoint(
+    &self,
+    params: InsertCheckpointParams,
+  ) -> AppResult<AFCollabCheckpoint> {
+    self.snapshot_control.create_checkpoint(params).await
+  }
+
+  async fn get_collab_checkpoint(
+    &self,
+    workspace_id: &str,
+    object_id: &str,
+    checkpoint_id: i64,
+  ) -> AppResult<(AFCollabCheckpoint, Vec<u8>)> {
+    self
+      .snapshot_control
+      .get_checkpoint(workspace_id, object_id, checkpoint_id)
+      .await
+  }
+
+  async fn get_collab_history(&self, workspace_id: &str, oid: &str) -> AppResult<AFCollabHistory> {
+    self
+      .snapshot_control
+      .get_collab_history(workspace_id, oid)
+      .await
+  }
+
+  async fn record_collab_edits(
+    &self,
+    workspace_id: &str,
+    oid: &str,
+    counts: &CollabEditCounts,
+  ) -> AppResult<()> {
+    self
+      .cache
+      .record_edit_counts(workspace_id, oid, counts)
+      .await
+  }
+
+  async fn record_database_row_activity(
+    &self,
+    workspace_id: &str,
+    row_id: &str,
+    changes: &[DatabaseRowCellChange],
+  ) -> AppResult<()> {
+    self
+      .cache
+      .record_database_row_activity(workspace_id, row_id, changes)
+      .await
+  }
 }
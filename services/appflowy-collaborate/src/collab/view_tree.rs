@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use access_control::restricted_view::ViewTreeLoader;
+use anyhow::anyhow;
+use app_error::AppError;
+use async_trait::async_trait;
+use collab::core::origin::CollabOrigin;
+use collab::preclude::Collab;
+use collab_database::database::DatabaseBody;
+use collab_database::rows::{meta_id_from_row_id, RowMetaKey};
+use collab_database::workspace_database::{NoPersistenceDatabaseCollabService, WorkspaceDatabase};
+use collab_entity::CollabType;
+use collab_folder::Folder;
+use database::collab::select_workspace_database_oid;
+use database_entity::dto::QueryCollab;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::collab::cache::CollabCache;
+
+/// Reads the folder and the databases of a workspace from the collab cache, to find the views a
+/// collab is shown in.
+pub struct CollabCacheViewTreeLoader {
+  cache: CollabCache,
+  pg_pool: PgPool,
+}
+
+impl CollabCacheViewTreeLoader {
+  pub fn new(cache: CollabCache, pg_pool: PgPool) -> Self {
+    Self { cache, pg_pool }
+  }
+
+  async fn get_collab(
+    &self,
+    workspace_id: &str,
+    object_id: &str,
+    collab_type: CollabType,
+  ) -> Result<Collab, AppError> {
+    let encoded_collab = self
+      .cache
+      .get_encode_collab(workspace_id, QueryCollab::new(object_id, collab_type))
+      .await?;
+    Collab::new_with_source(
+      CollabOrigin::Server,
+      object_id,
+      encoded_collab.into(),
+      vec![],
+      false,
+    )
+    .map_err(|err| AppError::Internal(anyhow!("failed to open collab {}: {}", object_id, err)))
+  }
+}
+
+#[async_trait]
+impl ViewTreeLoader for CollabCacheViewTreeLoader {
+  async fn load_view_parents(
+    &self,
+    workspace_id: &Uuid,
+  ) -> Result<HashMap<String, String>, AppError> {
+    let workspace_id = workspace_id.to_string();
+    let encoded_folder = self
+      .cache
+      .get_encode_collab(
+        &workspace_id,
+        QueryCollab::new(&workspace_id, CollabType::Folder),
+      )
+      .await?;
+    let folder = Folder::from_collab_doc_state(
+      0,
+      CollabOrigin::Server,
+      encoded_folder.into(),
+      &workspace_id,
+      vec![],
+    )
+    .map_err(|err| {
+      AppError::Internal(anyhow!("failed to open folder {}: {}", workspace_id, err))
+    })?;
+    let parents = folder
+      .get_folder_data(&workspace_id)
+      .map(|folder_data| {
+        folder_data
+          .views
+          .into_iter()
+          .map(|view| (view.id, view.parent_view_id))
+          .collect()
+      })
+      .unwrap_or_default();
+    Ok(parents)
+  }
+
+  async fn load_database_views(
+    &self,
+    workspace_id: &Uuid,
+  ) -> Result<HashMap<String, Vec<String>>, AppError> {
+    let oid = match select_workspace_database_oid(&self.pg_pool, workspace_id).await {
+      Ok(oid) => oid,
+      // a workspace without any database
+      Err(sqlx::Error::RowNotFound) => return Ok(HashMap::new()),
+      Err(err) => return Err(err.into()),
+    };
+    let encoded_collab = self
+      .cache
+      .get_encode_collab(
+        &workspace_id.to_string(),
+        QueryCollab::new(&oid, CollabType::WorkspaceDatabase),
+      )
+      .await?;
+    let workspace_database =
+      WorkspaceDatabase::from_collab_doc_state(&oid, CollabOrigin::Server, encoded_collab.into())
+        .map_err(|err| {
+        AppError::Internal(anyhow!(
+          "failed to open workspace database {}: {}",
+          oid,
+          err
+        ))
+      })?;
+    Ok(
+      workspace_database
+        .get_all_database_meta()
+        .into_iter()
+        .map(|meta| (meta.database_id, meta.linked_views))
+        .collect(),
+    )
+  }
+
+  async fn load_database_row_objects(
+    &self,
+    workspace_id: &Uuid,
+    database_id: &str,
+  ) -> Result<Vec<String>, AppError> {
+    let collab = self
+      .get_collab(&workspace_id.to_string(), database_id, CollabType::Database)
+      .await?;
+    let body =
+      DatabaseBody::from_collab(&collab, Arc::new(NoPersistenceDatabaseCollabService), None)
+        .ok_or_else(|| AppError::Internal(anyhow!("failed to open database {}", database_id)))?;
+    let mut object_ids = vec![];
+    let txn = collab.transact();
+    for view in body.views.get_all_views(&txn) {
+      for row_order in view.row_orders {
+        if let Ok(row_uuid) = Uuid::parse_str(row_order.id.as_str()) {
+          object_ids.push(meta_id_from_row_id(&row_uuid, RowMetaKey::DocumentId));
+        }
+        object_ids.push(row_order.id.to_string());
+      }
+    }
+    object_ids.sort();
+    object_ids.dedup();
+    Ok(object_ids)
+  }
+}
@@ -1,20 +1,24 @@
 use crate::{
+  connect_state::ConnectState,
   error::RealtimeError,
   group::{
     cmd::{GroupCommand, GroupCommandSender},
     manager::GroupManager,
+    observer::ObserverHub,
   },
 };
 use collab::entity::EncodedCollab;
-use collab_rt_entity::ClientCollabMessage;
+use collab_rt_entity::{ClientCollabMessage, SystemMessage};
 use dashmap::DashMap;
 use database::collab::CollabStorage;
+use database::pg_row::AFWorkspaceMemberNotification;
 use futures::StreamExt;
 use std::{
   collections::HashMap,
   sync::{Arc, Weak},
 };
-use tracing::error;
+use tokio::sync::broadcast;
+use tracing::{error, info};
 pub type CLCommandSender = tokio::sync::mpsc::Sender<CollaborationCommand>;
 pub type CLCommandReceiver = tokio::sync::mpsc::Receiver<CollaborationCommand>;
 
@@ -34,6 +38,8 @@ pub enum CollaborationCommand {
     collab_messages: Vec<ClientCollabMessage>,
     ret: tokio::sync::oneshot::Sender<Result<(), RealtimeError>>,
   },
+  /// Unsubscribes the user from all the groups of the workspace and notifies its connections.
+  RevokeWorkspaceAccess { workspace_id: String, uid: i64 },
 }
 
 const BATCH_GET_ENCODE_COLLAB_CONCURRENCY: usize = 10;
@@ -42,6 +48,8 @@ pub(crate) fn spawn_collaboration_command<S>(
   mut command_recv: CLCommandReceiver,
   group_sender_by_object_id: &Arc<DashMap<String, GroupCommandSender>>,
   weak_groups: Weak<GroupManager<S>>,
+  observer_hub: Arc<ObserverHub>,
+  connect_state: ConnectState,
 ) where
   S: CollabStorage,
 {
@@ -114,6 +122,53 @@ pub(crate) fn spawn_collaboration_command<S>(
             };
           }
         },
+        CollaborationCommand::RevokeWorkspaceAccess { workspace_id, uid } => {
+          if let Some(group_manager) = weak_groups.upgrade() {
+            let removed = group_manager.remove_user_from_workspace(&workspace_id, uid);
+            info!(
+              "user {} removed from workspace {}, unsubscribed from {} groups",
+              uid, workspace_id, removed
+            );
+          }
+          let removed = observer_hub.remove_user_from_workspace(&workspace_id, uid);
+          if removed > 0 {
+            info!(
+              "user {} removed from workspace {}, stopped observing {} collabs",
+              uid, workspace_id, removed
+            );
+          }
+          connect_state
+            .send_system_message(uid, SystemMessage::WorkspaceAccessRevoked(workspace_id));
+        },
+      }
+    }
+  });
+}
+
+/// Forwards the removals of workspace members, notified by Postgres, to the collaboration server
+/// so that removed members stop receiving the updates of the collabs they have already opened.
+pub fn spawn_workspace_member_revocation(
+  mut member_change: broadcast::Receiver<AFWorkspaceMemberNotification>,
+  command_sender: CLCommandSender,
+) {
+  tokio::spawn(async move {
+    loop {
+      let notification = match member_change.recv().await {
+        Ok(notification) => notification,
+        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+          error!("Skipped {} workspace member notifications", skipped);
+          continue;
+        },
+        Err(broadcast::error::RecvError::Closed) => break,
+      };
+      if let Some(member) = notification.removed_member() {
+        let command = CollaborationCommand::RevokeWorkspaceAccess {
+          workspace_id: member.workspace_id.to_string(),
+          uid: member.uid,
+        };
+        if command_sender.send(command).await.is_err() {
+          break;
+        }
       }
     }
   });
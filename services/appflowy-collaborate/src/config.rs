@@ -1,4 +1,6 @@
 use anyhow::Context;
+use database::file::gcs_client_impl::GCS_XML_API_ENDPOINT;
+use database::file::BlobStorageBackend;
 use secrecy::Secret;
 use semver::Version;
 use serde::Deserialize;
@@ -6,6 +8,7 @@ use sqlx::postgres::{PgConnectOptions, PgSslMode};
 use std::env::VarError;
 use std::fmt::Display;
 use std::str::FromStr;
+use std::time::Duration;
 
 #[derive(Clone, Debug)]
 pub struct Config {
@@ -19,6 +22,8 @@ pub struct Config {
   pub redis_worker_count: usize,
   pub ai: AISettings,
   pub s3: S3Setting,
+  pub blob_storage: BlobStorageSetting,
+  pub alerting: AlertingSetting,
 }
 
 #[derive(serde::Deserialize, Clone, Debug)]
@@ -33,6 +38,37 @@ pub struct S3Setting {
   pub presigned_url_endpoint: Option<String>,
 }
 
+/// Selects where blobs are stored. The [S3Setting] bucket and keys are used by the S3 and GCS
+/// backends, GCS expecting HMAC keys.
+#[derive(Clone, Debug)]
+pub struct BlobStorageSetting {
+  pub backend: BlobStorageBackend,
+  pub gcs_endpoint: String,
+  pub azure: AzureBlobSetting,
+  pub local: LocalFsBlobSetting,
+  /// Base64 encoded 32 bytes key encrypting the storage settings of the workspaces that use their
+  /// own bucket. Per-workspace storage is disabled when not set.
+  pub workspace_storage_key: Option<Secret<String>>,
+  pub workspace_storage_cache_ttl_secs: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct AzureBlobSetting {
+  pub account_name: String,
+  pub account_key: Secret<String>,
+  pub container: String,
+  /// Overrides the account endpoint, e.g. to use the Azurite emulator.
+  pub endpoint: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LocalFsBlobSetting {
+  /// Directory holding the blobs, shared with the appflowy_cloud server.
+  pub root: String,
+  pub signing_key: Secret<String>,
+  pub public_url: String,
+}
+
 #[derive(Clone, Debug)]
 pub struct ApplicationSetting {
   pub port: u16,
@@ -86,6 +122,11 @@ pub struct WebsocketSetting {
   pub heartbeat_interval: u8,
   pub client_timeout: u8,
   pub min_client_version: Version,
+  /// Maximum number of binary messages a client can send per second.
+  pub rate_limit_per_sec: u32,
+  /// Maximum number of realtime sessions a user can keep open, 0 means unlimited. Organizations
+  /// can set a stricter limit for their users.
+  pub max_sessions_per_user: usize,
 }
 
 #[derive(Clone, Debug)]
@@ -97,14 +138,17 @@ pub struct DatabaseSetting {
   /// connections are reserved for system applications.
   /// When we exceed the limit of the database connection, then it shows an error message.
   pub max_connections: u32,
+  /// Number of prepared statements kept by each connection. The statements evicted from the cache
+  /// are parsed and planned again on their next execution.
+  pub statement_cache_capacity: usize,
 }
 
 impl Display for DatabaseSetting {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     write!(
       f,
-      "DatabaseSetting {{ pg_conn_opts: {:?}, require_ssl: {}, max_connections: {} }}",
-      self.pg_conn_opts, self.require_ssl, self.max_connections
+      "DatabaseSetting {{ pg_conn_opts: {:?}, require_ssl: {}, max_connections: {}, statement_cache_capacity: {} }}",
+      self.pg_conn_opts, self.require_ssl, self.max_connections, self.statement_cache_capacity
     )
   }
 }
@@ -117,7 +161,9 @@ impl DatabaseSetting {
       PgSslMode::Prefer
     };
     let options = self.pg_conn_opts.clone();
-    options.ssl_mode(ssl_mode)
+    options
+      .ssl_mode(ssl_mode)
+      .statement_cache_capacity(self.statement_cache_capacity)
   }
 }
 
@@ -133,6 +179,63 @@ pub struct CollabSetting {
   pub edit_state_max_count: u32,
   pub edit_state_max_secs: i64,
   pub s3_collab_threshold: u64,
+  /// When set, the snapshots are recorded in Postgres and only the ones larger than that many
+  /// bytes are written to the object storage. When not set, all snapshots are written to the
+  /// object storage.
+  pub snapshot_offload_threshold: Option<usize>,
+}
+
+/// Settings that can be changed without restarting the server, by sending it a SIGHUP. See
+/// [crate::config_reload].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReloadableSetting {
+  pub group_persistence_interval: Duration,
+  pub group_prune_grace_period: Duration,
+  /// Only applies to the websocket connections established after the reload.
+  pub websocket_rate_limit_per_sec: u32,
+}
+
+impl ReloadableSetting {
+  pub fn from_config(config: &Config) -> Self {
+    Self {
+      group_persistence_interval: Duration::from_secs(
+        config.collab.group_persistence_interval_secs,
+      ),
+      group_prune_grace_period: Duration::from_secs(config.collab.group_prune_grace_period_secs),
+      websocket_rate_limit_per_sec: config.websocket.rate_limit_per_sec,
+    }
+  }
+}
+
+/// Thresholds evaluated by the realtime alerting. A `None` threshold is not evaluated.
+#[derive(Clone, Debug)]
+pub struct AlertingSetting {
+  /// Webhook receiving the alerts, alerting is disabled when empty.
+  pub webhook_url: String,
+  pub interval_secs: u64,
+  /// Minimum time between two alerts of the same kind.
+  pub cooldown_secs: u64,
+  pub max_active_groups: Option<i64>,
+  pub max_broadcast_delay_p99_ms: Option<f64>,
+  /// Ratio of collab reads that missed the Redis cache, between 0 and 1.
+  pub max_mem_cache_miss_rate: Option<f64>,
+  /// Minimum number of collab reads within an interval for the miss rate to be evaluated.
+  pub min_mem_cache_reads: u64,
+}
+
+fn get_optional_env_var<T>(key: &str) -> Result<Option<T>, anyhow::Error>
+where
+  T: FromStr,
+  T::Err: std::error::Error + Send + Sync + 'static,
+{
+  let value = get_env_var(key, "");
+  if value.is_empty() {
+    return Ok(None);
+  }
+  let value = value
+    .parse()
+    .with_context(|| format!("fail to get {}", key))?;
+  Ok(Some(value))
 }
 
 pub fn get_env_var(key: &str, default: &str) -> String {
@@ -166,6 +269,9 @@ pub fn get_configuration() -> Result<Config, anyhow::Error> {
       heartbeat_interval: get_env_var("APPFLOWY_WEBSOCKET_HEARTBEAT_INTERVAL", "6").parse()?,
       client_timeout: get_env_var("APPFLOWY_WEBSOCKET_CLIENT_TIMEOUT", "60").parse()?,
       min_client_version: get_env_var("APPFLOWY_WEBSOCKET_CLIENT_MIN_VERSION", "0.5.0").parse()?,
+      rate_limit_per_sec: get_env_var("APPFLOWY_WEBSOCKET_RATE_LIMIT_PER_SEC", "10").parse()?,
+      max_sessions_per_user: get_env_var("APPFLOWY_WEBSOCKET_MAX_SESSIONS_PER_USER", "10")
+        .parse()?,
     },
     db_settings: DatabaseSetting {
       pg_conn_opts: PgConnectOptions::from_str(&get_env_var(
@@ -178,6 +284,9 @@ pub fn get_configuration() -> Result<Config, anyhow::Error> {
       max_connections: get_env_var("APPFLOWY_DATABASE_MAX_CONNECTIONS", "40")
         .parse()
         .context("fail to get APPFLOWY_DATABASE_MAX_CONNECTIONS")?,
+      statement_cache_capacity: get_env_var("APPFLOWY_DATABASE_STATEMENT_CACHE_CAPACITY", "500")
+        .parse()
+        .context("fail to get APPFLOWY_DATABASE_STATEMENT_CACHE_CAPACITY")?,
     },
     s3: S3Setting {
       create_bucket: get_env_var("APPFLOWY_S3_CREATE_BUCKET", "true")
@@ -193,6 +302,37 @@ pub fn get_configuration() -> Result<Config, anyhow::Error> {
       region: get_env_var("APPFLOWY_S3_REGION", ""),
       presigned_url_endpoint: None,
     },
+    blob_storage: BlobStorageSetting {
+      backend: get_env_var("APPFLOWY_BLOB_STORAGE_BACKEND", "s3")
+        .as_str()
+        .try_into()
+        .context("fail to get APPFLOWY_BLOB_STORAGE_BACKEND")?,
+      gcs_endpoint: get_env_var("APPFLOWY_GCS_ENDPOINT", GCS_XML_API_ENDPOINT),
+      azure: AzureBlobSetting {
+        account_name: get_env_var("APPFLOWY_AZURE_STORAGE_ACCOUNT", ""),
+        account_key: get_env_var("APPFLOWY_AZURE_STORAGE_KEY", "").into(),
+        container: get_env_var("APPFLOWY_AZURE_STORAGE_CONTAINER", "appflowy"),
+        endpoint: Some(get_env_var("APPFLOWY_AZURE_STORAGE_ENDPOINT", ""))
+          .filter(|endpoint| !endpoint.is_empty()),
+      },
+      local: LocalFsBlobSetting {
+        root: get_env_var("APPFLOWY_LOCAL_STORAGE_ROOT", "./data/blobs"),
+        signing_key: Some(get_env_var("APPFLOWY_LOCAL_STORAGE_SIGNING_KEY", ""))
+          .filter(|key| !key.is_empty())
+          .unwrap_or_else(|| get_env_var("APPFLOWY_GOTRUE_JWT_SECRET", "hello456"))
+          .into(),
+        public_url: get_env_var("APPFLOWY_LOCAL_STORAGE_PUBLIC_URL", "http://localhost:8000"),
+      },
+      workspace_storage_key: Some(get_env_var("APPFLOWY_WORKSPACE_STORAGE_KEY", ""))
+        .filter(|key| !key.is_empty())
+        .map(Secret::new),
+      workspace_storage_cache_ttl_secs: get_env_var(
+        "APPFLOWY_WORKSPACE_STORAGE_CACHE_TTL_SECS",
+        "60",
+      )
+      .parse()
+      .context("fail to get APPFLOWY_WORKSPACE_STORAGE_CACHE_TTL_SECS")?,
+    },
     gotrue: GoTrueSetting {
       jwt_secret: get_env_var("APPFLOWY_GOTRUE_JWT_SECRET", "hello456").into(),
     },
@@ -207,6 +347,9 @@ pub fn get_configuration() -> Result<Config, anyhow::Error> {
       edit_state_max_count: get_env_var("APPFLOWY_COLLAB_EDIT_STATE_MAX_COUNT", "100").parse()?,
       edit_state_max_secs: get_env_var("APPFLOWY_COLLAB_EDIT_STATE_MAX_SECS", "60").parse()?,
       s3_collab_threshold: get_env_var("APPFLOWY_COLLAB_S3_THRESHOLD", "8000").parse()?,
+      snapshot_offload_threshold: get_optional_env_var(
+        "APPFLOWY_COLLAB_SNAPSHOT_OFFLOAD_THRESHOLD",
+      )?,
     },
     redis_uri: get_env_var("APPFLOWY_REDIS_URI", "redis://localhost:6379").into(),
     redis_worker_count: get_env_var("APPFLOWY_REDIS_WORKERS", "60").parse()?,
@@ -214,6 +357,20 @@ pub fn get_configuration() -> Result<Config, anyhow::Error> {
       port: get_env_var("AI_SERVER_PORT", "5001").parse()?,
       host: get_env_var("AI_SERVER_HOST", "localhost"),
     },
+    alerting: AlertingSetting {
+      webhook_url: get_env_var("APPFLOWY_COLLAB_ALERT_WEBHOOK_URL", ""),
+      interval_secs: get_env_var("APPFLOWY_COLLAB_ALERT_INTERVAL_SECS", "60").parse()?,
+      cooldown_secs: get_env_var("APPFLOWY_COLLAB_ALERT_COOLDOWN_SECS", "900").parse()?,
+      max_active_groups: get_optional_env_var("APPFLOWY_COLLAB_ALERT_MAX_ACTIVE_GROUPS")?,
+      max_broadcast_delay_p99_ms: get_optional_env_var(
+        "APPFLOWY_COLLAB_ALERT_MAX_BROADCAST_DELAY_P99_MS",
+      )?,
+      max_mem_cache_miss_rate: get_optional_env_var(
+        "APPFLOWY_COLLAB_ALERT_MAX_MEMCACHE_MISS_RATE",
+      )?,
+      min_mem_cache_reads: get_env_var("APPFLOWY_COLLAB_ALERT_MIN_MEMCACHE_READS", "100")
+        .parse()?,
+    },
   };
   Ok(config)
 }
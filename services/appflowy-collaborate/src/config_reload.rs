@@ -0,0 +1,209 @@
+//! Re-reads the configuration when the server receives a SIGHUP, and publishes the
+//! [ReloadableSetting] to the running subsystems. The other settings are only read at startup,
+//! so a reload that changes any of them is rejected as a whole.
+use secrecy::ExposeSecret;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+use crate::config::{get_configuration, Config, ReloadableSetting};
+
+pub fn spawn_config_reload(config: Config, sender: watch::Sender<ReloadableSetting>) {
+  let mut hangup = match signal(SignalKind::hangup()) {
+    Ok(hangup) => hangup,
+    Err(err) => {
+      error!(
+        "Failed to listen to SIGHUP, config reload is disabled: {}",
+        err
+      );
+      return;
+    },
+  };
+
+  tokio::spawn(async move {
+    while hangup.recv().await.is_some() {
+      info!("Received SIGHUP, reloading the configuration");
+      // pick up the changes made to the .env file
+      dotenvy::dotenv_override().ok();
+      let new_config = match get_configuration() {
+        Ok(new_config) => new_config,
+        Err(err) => {
+          error!("Failed to reload the configuration: {}", err);
+          continue;
+        },
+      };
+
+      match check_reload(&config, &new_config) {
+        Ok(setting) => {
+          sender.send_if_modified(|current| {
+            if *current == setting {
+              return false;
+            }
+            info!("Applying reloaded settings: {:?}", setting);
+            *current = setting;
+            true
+          });
+        },
+        Err(err) => warn!("Configuration reload rejected: {}", err),
+      }
+    }
+  });
+}
+
+/// Returns the settings to apply if only hot-reloadable settings changed between `current` and
+/// `new`.
+pub fn check_reload(current: &Config, new: &Config) -> Result<ReloadableSetting, String> {
+  let new_fields = fixed_fields(new);
+  let changed = fixed_fields(current)
+    .into_iter()
+    .zip(new_fields)
+    .filter(|(current, new)| current.1 != new.1)
+    .map(|(current, _)| current.0)
+    .collect::<Vec<_>>();
+  if !changed.is_empty() {
+    return Err(format!(
+      "the following settings require a restart: {}",
+      changed.join(", ")
+    ));
+  }
+
+  let setting = ReloadableSetting::from_config(new);
+  if setting.group_persistence_interval.is_zero() {
+    return Err("APPFLOWY_COLLAB_GROUP_PERSISTENCE_INTERVAL must be greater than 0".to_string());
+  }
+  Ok(setting)
+}
+
+/// Settings that can't be changed by a reload. The values are only compared, never logged.
+fn fixed_fields(config: &Config) -> Vec<(&'static str, String)> {
+  vec![
+    ("APPFLOWY_ENVIRONMENT", config.app_env.as_str().to_string()),
+    (
+      "APPFLOWY_COLLAB_SERVICE_HOST",
+      config.application.host.clone(),
+    ),
+    (
+      "APPFLOWY_COLLAB_SERVICE_PORT",
+      config.application.port.to_string(),
+    ),
+    (
+      "APPFLOWY_WEBSOCKET_HEARTBEAT_INTERVAL",
+      config.websocket.heartbeat_interval.to_string(),
+    ),
+    (
+      "APPFLOWY_WEBSOCKET_CLIENT_TIMEOUT",
+      config.websocket.client_timeout.to_string(),
+    ),
+    (
+      "APPFLOWY_WEBSOCKET_CLIENT_MIN_VERSION",
+      config.websocket.min_client_version.to_string(),
+    ),
+    (
+      "APPFLOWY_DATABASE_URL",
+      format!("{:?}", config.db_settings.pg_conn_opts),
+    ),
+    (
+      "APPFLOWY_DATABASE_REQUIRE_SSL",
+      config.db_settings.require_ssl.to_string(),
+    ),
+    (
+      "APPFLOWY_DATABASE_MAX_CONNECTIONS",
+      config.db_settings.max_connections.to_string(),
+    ),
+    (
+      "APPFLOWY_DATABASE_STATEMENT_CACHE_CAPACITY",
+      config.db_settings.statement_cache_capacity.to_string(),
+    ),
+    (
+      "APPFLOWY_GOTRUE_JWT_SECRET",
+      config.gotrue.jwt_secret.expose_secret().clone(),
+    ),
+    (
+      "APPFLOWY_COLLAB_EDIT_STATE_MAX_COUNT",
+      config.collab.edit_state_max_count.to_string(),
+    ),
+    (
+      "APPFLOWY_COLLAB_EDIT_STATE_MAX_SECS",
+      config.collab.edit_state_max_secs.to_string(),
+    ),
+    (
+      "APPFLOWY_COLLAB_S3_THRESHOLD",
+      config.collab.s3_collab_threshold.to_string(),
+    ),
+    (
+      "APPFLOWY_REDIS_URI",
+      config.redis_uri.expose_secret().clone(),
+    ),
+    (
+      "APPFLOWY_REDIS_WORKERS",
+      config.redis_worker_count.to_string(),
+    ),
+    ("AI_SERVER", config.ai.url()),
+    (
+      "APPFLOWY_S3_*",
+      format!(
+        "{}:{}:{}:{}:{}:{}:{}",
+        config.s3.create_bucket,
+        config.s3.use_minio,
+        config.s3.minio_url,
+        config.s3.access_key,
+        config.s3.secret_key.expose_secret(),
+        config.s3.bucket,
+        config.s3.region
+      ),
+    ),
+    (
+      "APPFLOWY_BLOB_STORAGE_*",
+      format!(
+        "{:?}:{}:{}:{}:{}:{:?}:{}:{}:{}",
+        config.blob_storage.backend,
+        config.blob_storage.gcs_endpoint,
+        config.blob_storage.azure.account_name,
+        config.blob_storage.azure.account_key.expose_secret(),
+        config.blob_storage.azure.container,
+        config.blob_storage.azure.endpoint,
+        config.blob_storage.local.root,
+        config.blob_storage.local.signing_key.expose_secret(),
+        config.blob_storage.local.public_url
+      ),
+    ),
+    ("APPFLOWY_COLLAB_ALERT_*", format!("{:?}", config.alerting)),
+  ]
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::time::Duration;
+
+  #[test]
+  fn reload_reloadable_settings() {
+    let current = get_configuration().unwrap();
+    let mut new = current.clone();
+    new.collab.group_persistence_interval_secs = current.collab.group_persistence_interval_secs + 1;
+    new.websocket.rate_limit_per_sec = 20;
+
+    let setting = check_reload(&current, &new).unwrap();
+    assert_eq!(
+      setting.group_persistence_interval,
+      Duration::from_secs(current.collab.group_persistence_interval_secs + 1)
+    );
+    assert_eq!(setting.websocket_rate_limit_per_sec, 20);
+  }
+
+  #[test]
+  fn reject_non_reloadable_settings() {
+    let current = get_configuration().unwrap();
+    let mut new = current.clone();
+    new.application.port += 1;
+    new.redis_worker_count += 1;
+
+    let err = check_reload(&current, &new).unwrap_err();
+    assert!(err.contains("APPFLOWY_COLLAB_SERVICE_PORT"));
+    assert!(err.contains("APPFLOWY_REDIS_WORKERS"));
+
+    let mut new = current.clone();
+    new.collab.group_persistence_interval_secs = 0;
+    assert!(check_reload(&current, &new).is_err());
+  }
+}
@@ -4,9 +4,77 @@ use dashmap::DashMap;
 
 use crate::client::client_msg_router::ClientMessageRouter;
 use dashmap::mapref::entry::Entry;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tracing::{info, trace};
 
+/// Tracks the liveness of a single websocket connection.
+///
+/// The websocket actor refreshes the heartbeat whenever it receives a ping or pong frame, and the
+/// server periodically inspects it to find connections that stopped answering. When the server
+/// reaps a connection it marks the heartbeat as closed, which tells the actor to close the socket.
+#[derive(Clone, Debug)]
+pub struct ConnectionHeartbeat {
+  inner: Arc<ConnectionHeartbeatInner>,
+}
+
+#[derive(Debug)]
+struct ConnectionHeartbeatInner {
+  connected_at: Instant,
+  /// Milliseconds elapsed between `connected_at` and the last received ping/pong.
+  last_pong_millis: AtomicU64,
+  closed: AtomicBool,
+}
+
+impl Default for ConnectionHeartbeat {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl ConnectionHeartbeat {
+  pub fn new() -> Self {
+    Self {
+      inner: Arc::new(ConnectionHeartbeatInner {
+        connected_at: Instant::now(),
+        last_pong_millis: AtomicU64::new(0),
+        closed: AtomicBool::new(false),
+      }),
+    }
+  }
+
+  /// Records that the client answered a ping or sent one of its own.
+  pub fn touch(&self) {
+    let elapsed = self.inner.connected_at.elapsed().as_millis() as u64;
+    self
+      .inner
+      .last_pong_millis
+      .store(elapsed, Ordering::Relaxed);
+  }
+
+  /// How long the connection has been established.
+  pub fn age(&self) -> Duration {
+    self.inner.connected_at.elapsed()
+  }
+
+  /// How long it has been since the last ping/pong was received.
+  pub fn idle_duration(&self) -> Duration {
+    let last_pong = Duration::from_millis(self.inner.last_pong_millis.load(Ordering::Relaxed));
+    self.age().saturating_sub(last_pong)
+  }
+
+  /// Marks the connection as closed by the server. The websocket actor checks this flag on every
+  /// heartbeat tick and closes the socket once it is set.
+  pub fn close(&self) {
+    self.inner.closed.store(true, Ordering::Relaxed);
+  }
+
+  pub fn is_closed(&self) -> bool {
+    self.inner.closed.load(Ordering::Relaxed)
+  }
+}
+
 #[derive(Clone, Default)]
 pub struct ConnectState {
   pub(crate) user_by_device: Arc<DashMap<UserDevice, RealtimeUser>>,
@@ -14,6 +82,8 @@ pub struct ConnectState {
   /// 1. User disconnection.
   /// 2. Server closes the connection due to a ping/pong timeout.
   pub(crate) client_message_routers: Arc<DashMap<RealtimeUser, ClientMessageRouter>>,
+  /// Heartbeat of each websocket connection, used to find and reap idle connections.
+  pub(crate) heartbeat_by_user: Arc<DashMap<RealtimeUser, ConnectionHeartbeat>>,
 }
 
 impl ConnectState {
@@ -42,6 +112,7 @@ impl ConnectState {
         if e.get().connect_at <= new_user.connect_at {
           let old_user = e.insert(new_user.clone());
           trace!("[realtime]: new connection replaces old => {}", new_user);
+          self.heartbeat_by_user.remove(&old_user);
           if let Some((_, old_stream)) = self.client_message_routers.remove(&old_user) {
             info!("Removing old stream for same user and device: {}", old_user);
 
@@ -68,6 +139,66 @@ impl ConnectState {
     }
   }
 
+  /// Disconnects the oldest sessions of the user until at most `max_sessions` remain, never the
+  /// `current` one. Each evicted session receives a `SessionLimitExceeded` system message, which
+  /// tells its websocket actor to close the socket. A `max_sessions` of 0 means unlimited.
+  pub fn evict_oldest_sessions(
+    &self,
+    current: &RealtimeUser,
+    max_sessions: usize,
+  ) -> Vec<RealtimeUser> {
+    if max_sessions == 0 {
+      return vec![];
+    }
+    let mut others = self
+      .user_by_device
+      .iter()
+      .filter(|entry| entry.value().uid == current.uid && entry.value() != current)
+      .map(|entry| entry.value().clone())
+      .collect::<Vec<_>>();
+    let excess = (others.len() + 1).saturating_sub(max_sessions);
+    if excess == 0 {
+      return vec![];
+    }
+
+    others.sort_by_key(|user| user.connect_at);
+    let mut evicted = Vec::with_capacity(excess);
+    for user in others.into_iter().take(excess) {
+      let removed = self
+        .user_by_device
+        .remove_if(&UserDevice::from(&user), |_, existing_user| {
+          existing_user.session_id == user.session_id
+        });
+      if removed.is_none() {
+        continue;
+      }
+      self.heartbeat_by_user.remove(&user);
+      if let Some((_, router)) = self.client_message_routers.remove(&user) {
+        router
+          .sink
+          .do_send(RealtimeMessage::System(SystemMessage::SessionLimitExceeded));
+      }
+      info!(
+        "[realtime]: session limit of {} exceeded, disconnect {}",
+        max_sessions, user
+      );
+      evicted.push(user);
+    }
+    evicted
+  }
+
+  /// Sends the message to all the connections of the user.
+  pub fn send_system_message(&self, uid: i64, message: SystemMessage) {
+    for entry in self.client_message_routers.iter() {
+      if entry.key().uid == uid {
+        entry
+          .value()
+          .sink
+          .do_send(RealtimeMessage::System(message.clone()));
+      }
+    }
+  }
+
   /// Handles the disconnection of a user from the system.
   ///
   /// remove a user based on their device and session ID. If the session ID of the disconnecting user matches
@@ -97,6 +228,34 @@ impl ConnectState {
     was_removed
   }
 
+  /// Starts tracking the heartbeat of the given connection. The heartbeat is only recorded when the
+  /// user is still the active connection of its device.
+  pub fn track_heartbeat(&self, user: &RealtimeUser, heartbeat: ConnectionHeartbeat) {
+    if self.client_message_routers.contains_key(user) {
+      self.heartbeat_by_user.insert(user.clone(), heartbeat);
+    }
+  }
+
+  pub fn remove_heartbeat(&self, user: &RealtimeUser) -> Option<ConnectionHeartbeat> {
+    self
+      .heartbeat_by_user
+      .remove(user)
+      .map(|(_, heartbeat)| heartbeat)
+  }
+
+  /// Returns the connections that haven't received a ping/pong within `idle_timeout`.
+  pub fn idle_connections(
+    &self,
+    idle_timeout: Duration,
+  ) -> Vec<(RealtimeUser, ConnectionHeartbeat)> {
+    self
+      .heartbeat_by_user
+      .iter()
+      .filter(|entry| entry.value().idle_duration() > idle_timeout)
+      .map(|entry| (entry.key().clone(), entry.value().clone()))
+      .collect()
+  }
+
   pub fn number_of_connected_users(&self) -> usize {
     self.user_by_device.len()
   }
@@ -110,7 +269,7 @@ impl ConnectState {
 #[cfg(test)]
 mod tests {
   use crate::client::client_msg_router::ClientMessageRouter;
-  use crate::connect_state::ConnectState;
+  use crate::connect_state::{ConnectState, ConnectionHeartbeat};
   use crate::RealtimeClientWebsocketSink;
   use collab_rt_entity::user::{RealtimeUser, UserDevice};
   use collab_rt_entity::RealtimeMessage;
@@ -265,4 +424,58 @@ mod tests {
       .unwrap();
     assert_eq!(user.connect_at, 2);
   }
+
+  #[tokio::test]
+  async fn idle_connection_test() {
+    let connect_state = ConnectState::new();
+    let idle_user = mock_user(1, "device_a", 1);
+    let active_user = mock_user(2, "device_a", 1);
+    let idle_heartbeat = ConnectionHeartbeat::new();
+    let active_heartbeat = ConnectionHeartbeat::new();
+    connect_state.handle_user_connect(idle_user.clone(), mock_stream());
+    connect_state.handle_user_connect(active_user.clone(), mock_stream());
+    connect_state.track_heartbeat(&idle_user, idle_heartbeat);
+    connect_state.track_heartbeat(&active_user, active_heartbeat.clone());
+
+    sleep(Duration::from_millis(300)).await;
+    active_heartbeat.touch();
+
+    let idle = connect_state.idle_connections(Duration::from_millis(200));
+    assert_eq!(idle.len(), 1);
+    assert_eq!(idle[0].0, idle_user);
+
+    // A replaced connection must not be reported as idle anymore.
+    connect_state.handle_user_connect(mock_user(1, "device_a", 2), mock_stream());
+    assert!(connect_state
+      .idle_connections(Duration::from_millis(200))
+      .is_empty());
+  }
+
+  #[tokio::test]
+  async fn evict_oldest_sessions_test() {
+    let connect_state = ConnectState::new();
+    let users = (0..4)
+      .map(|i| mock_user(1, &format!("device_{}", i), i))
+      .collect::<Vec<_>>();
+    for user in &users {
+      connect_state.handle_user_connect(user.clone(), mock_stream());
+    }
+    connect_state.handle_user_connect(mock_user(2, "device_0", 0), mock_stream());
+
+    // The sessions of other users are not counted, and 0 means unlimited.
+    assert!(connect_state.evict_oldest_sessions(&users[3], 4).is_empty());
+    assert!(connect_state.evict_oldest_sessions(&users[3], 0).is_empty());
+
+    let evicted = connect_state.evict_oldest_sessions(&users[3], 2);
+    assert_eq!(evicted, vec![users[0].clone(), users[1].clone()]);
+    assert_eq!(connect_state.user_by_device.len(), 3);
+    assert_eq!(connect_state.client_message_routers.len(), 3);
+
+    // The current session is kept even when it is the oldest one.
+    let evicted = connect_state.evict_oldest_sessions(&users[2], 1);
+    assert_eq!(evicted, vec![users[3].clone()]);
+    assert!(connect_state
+      .get_user_by_device(&UserDevice::from(&users[2]))
+      .is_some());
+  }
 }
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use collab::core::origin::CollabOrigin;
+use yrs::sync::awareness::{AwarenessUpdate, AwarenessUpdateEntry};
+use yrs::updates::decoder::Decode;
+use yrs::updates::encoder::Encode;
+
+/// The json of an awareness state that was removed.
+const REMOVED_AWARENESS_STATE: &str = "null";
+
+struct AwarenessClient {
+  origin: CollabOrigin,
+  clock: u32,
+}
+
+/// Awareness states sent by the subscribers of a group, keyed by awareness client id, so that
+/// the states of a subscriber whose connection closed without removing them can be removed on
+/// its behalf.
+#[derive(Default)]
+pub(crate) struct AwarenessClients {
+  inner: Mutex<HashMap<u64, AwarenessClient>>,
+}
+
+impl AwarenessClients {
+  /// Keeps the clients set by an awareness update received from `origin`, and forgets the ones it
+  /// removes. Malformed updates are ignored, they are rejected by the other clients anyway.
+  pub fn observe(&self, origin: &CollabOrigin, update: &[u8]) {
+    let update = match AwarenessUpdate::decode_v1(update) {
+      Ok(update) => update,
+      Err(_) => return,
+    };
+    let mut inner = self.inner.lock().unwrap();
+    for (client_id, entry) in update.clients {
+      if &*entry.json == REMOVED_AWARENESS_STATE {
+        inner.remove(&client_id);
+      } else {
+        inner.insert(
+          client_id,
+          AwarenessClient {
+            origin: origin.clone(),
+            clock: entry.clock,
+          },
+        );
+      }
+    }
+  }
+
+  /// Forgets the clients of `origin` and returns the awareness update removing all of them at
+  /// once, None when the origin has no state left.
+  pub fn remove_origin(&self, origin: &CollabOrigin) -> Option<Vec<u8>> {
+    let mut clients = HashMap::new();
+    self.inner.lock().unwrap().retain(|client_id, client| {
+      if &client.origin != origin {
+        return true;
+      }
+      // the other clients only apply a state with a newer clock
+      clients.insert(
+        *client_id,
+        AwarenessUpdateEntry {
+          clock: client.clock + 1,
+          json: Arc::from(REMOVED_AWARENESS_STATE),
+        },
+      );
+      false
+    });
+    if clients.is_empty() {
+      return None;
+    }
+    Some(AwarenessUpdate { clients }.encode_v1())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use collab::core::origin::CollabClient;
+
+  fn client(uid: i64, device_id: &str) -> CollabOrigin {
+    CollabOrigin::Client(CollabClient {
+      uid,
+      device_id: device_id.to_string(),
+    })
+  }
+
+  fn awareness_update(states: &[(u64, u32, &str)]) -> Vec<u8> {
+    let clients = states
+      .iter()
+      .map(|(client_id, clock, json)| {
+        (
+          *client_id,
+          AwarenessUpdateEntry {
+            clock: *clock,
+            json: Arc::from(*json),
+          },
+        )
+      })
+      .collect();
+    AwarenessUpdate { clients }.encode_v1()
+  }
+
+  #[test]
+  fn removes_the_states_of_an_origin() {
+    let clients = AwarenessClients::default();
+    let alice = client(1, "alice");
+    let bob = client(2, "bob");
+    clients.observe(&alice, &awareness_update(&[(10, 3, r#"{"user":"alice"}"#)]));
+    clients.observe(&alice, &awareness_update(&[(11, 1, r#"{"user":"alice"}"#)]));
+    clients.observe(&bob, &awareness_update(&[(20, 1, r#"{"user":"bob"}"#)]));
+    // removed by the client itself
+    clients.observe(
+      &alice,
+      &awareness_update(&[(11, 2, REMOVED_AWARENESS_STATE)]),
+    );
+
+    let update = clients.remove_origin(&alice).unwrap();
+    let update = AwarenessUpdate::decode_v1(&update).unwrap();
+    assert_eq!(update.clients.len(), 1);
+    assert_eq!(update.clients[&10].clock, 4);
+    assert_eq!(&*update.clients[&10].json, REMOVED_AWARENESS_STATE);
+
+    assert!(clients.remove_origin(&alice).is_none());
+    assert!(clients.remove_origin(&bob).is_some());
+  }
+}
@@ -0,0 +1,128 @@
+use collab::core::origin::CollabOrigin;
+use collab_rt_entity::{CellLockHolder, CellLockRequest, CELL_LOCK_TTL_SECS};
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+
+type CellKey = (String, String);
+
+/// Advisory locks of the database cells of a group, keyed by (row_id, field_id). The locks only
+/// tell the clients who is editing a cell, the updates to a locked cell are still applied.
+#[derive(Default)]
+pub(crate) struct CellLocks {
+  locks: DashMap<CellKey, CellLockHolder>,
+}
+
+impl CellLocks {
+  /// Applies the request and returns the holder of the lock after it, None when the cell is not
+  /// locked. A cell locked by another client stays locked by it until the lock expires.
+  pub fn apply(&self, request: &CellLockRequest, now: i64) -> Option<CellLockHolder> {
+    let (uid, device_id) = match &request.origin {
+      CollabOrigin::Client(client) => (client.uid, client.device_id.clone()),
+      _ => return None,
+    };
+    let key = (request.row_id.clone(), request.field_id.clone());
+    let requested = CellLockHolder {
+      uid,
+      device_id,
+      expires_at: now + CELL_LOCK_TTL_SECS,
+    };
+    match self.locks.entry(key) {
+      Entry::Occupied(mut entry) => {
+        let holder = entry.get();
+        let is_available = holder.expires_at <= now
+          || (holder.uid == requested.uid && holder.device_id == requested.device_id);
+        if !is_available {
+          Some(holder.clone())
+        } else if request.acquire {
+          entry.insert(requested.clone());
+          Some(requested)
+        } else {
+          entry.remove();
+          None
+        }
+      },
+      Entry::Vacant(entry) => {
+        if request.acquire {
+          entry.insert(requested.clone());
+          Some(requested)
+        } else {
+          None
+        }
+      },
+    }
+  }
+
+  /// Releases the locks held by the device, returns the (row_id, field_id) of the released cells.
+  pub fn release_all(&self, uid: i64, device_id: &str) -> Vec<CellKey> {
+    let mut released = vec![];
+    self.locks.retain(|key, holder| {
+      let is_held = holder.uid == uid && holder.device_id == device_id;
+      if is_held {
+        released.push(key.clone());
+      }
+      !is_held
+    });
+    released
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use collab::core::origin::CollabClient;
+
+  fn request(uid: i64, acquire: bool) -> CellLockRequest {
+    let origin = CollabOrigin::Client(CollabClient {
+      uid,
+      device_id: format!("device {}", uid),
+    });
+    CellLockRequest::new(
+      origin,
+      "row".to_string(),
+      1,
+      "row".to_string(),
+      "field".to_string(),
+      acquire,
+    )
+  }
+
+  #[test]
+  fn cell_locked_by_another_client_is_not_taken() {
+    let locks = CellLocks::default();
+    let holder = locks.apply(&request(1, true), 100).unwrap();
+    assert_eq!(holder.uid, 1);
+    assert_eq!(holder.expires_at, 100 + CELL_LOCK_TTL_SECS);
+
+    // another client can neither take nor release the lock
+    assert_eq!(locks.apply(&request(2, true), 110).unwrap().uid, 1);
+    assert_eq!(locks.apply(&request(2, false), 110).unwrap().uid, 1);
+
+    // the holder renews the lock, then releases it
+    let holder = locks.apply(&request(1, true), 120).unwrap();
+    assert_eq!(holder.expires_at, 120 + CELL_LOCK_TTL_SECS);
+    assert!(locks.apply(&request(1, false), 130).is_none());
+    assert_eq!(locks.apply(&request(2, true), 130).unwrap().uid, 2);
+  }
+
+  #[test]
+  fn expired_cell_lock_is_taken() {
+    let locks = CellLocks::default();
+    locks.apply(&request(1, true), 100);
+    let holder = locks
+      .apply(&request(2, true), 100 + CELL_LOCK_TTL_SECS)
+      .unwrap();
+    assert_eq!(holder.uid, 2);
+  }
+
+  #[test]
+  fn locks_of_a_device_are_released() {
+    let locks = CellLocks::default();
+    locks.apply(&request(1, true), 100);
+    assert!(locks.release_all(2, "device 2").is_empty());
+    assert_eq!(
+      locks.release_all(1, "device 1"),
+      vec![("row".to_string(), "field".to_string())]
+    );
+    assert_eq!(locks.apply(&request(2, true), 110).unwrap().uid, 2);
+  }
+}
@@ -15,7 +15,8 @@ use collab_entity::CollabType;
 use collab_rt_entity::user::RealtimeUser;
 use collab_rt_entity::CollabAck;
 use collab_rt_entity::{
-  AckCode, ClientCollabMessage, MessageByObjectId, ServerCollabMessage, SinkMessage, UpdateSync,
+  AckCode, ClientCollabMessage, CollabLockChanged, MessageByObjectId, ServerCollabMessage,
+  SinkMessage, UpdateSync,
 };
 use collab_rt_protocol::{Message, SyncMessage};
 use database::collab::CollabStorage;
@@ -25,8 +26,10 @@ use yrs::StateVector;
 
 /// Using [GroupCommand] to interact with the group
 /// - HandleClientCollabMessage: Handle the client message
+/// - HandleClientInitSync: Handle an init sync that is part of a batch and return its responses
 /// - EncodeCollab: Encode the collab
 /// - HandleServerCollabMessage: Handle the server message
+/// - BroadcastCollabLock: Inform the subscribers that the collab was locked or unlocked
 pub enum GroupCommand {
   HandleClientCollabMessage {
     user: RealtimeUser,
@@ -34,6 +37,11 @@ pub enum GroupCommand {
     collab_messages: Vec<ClientCollabMessage>,
     ret: tokio::sync::oneshot::Sender<Result<(), RealtimeError>>,
   },
+  HandleClientInitSync {
+    user: RealtimeUser,
+    message: ClientCollabMessage,
+    ret: tokio::sync::oneshot::Sender<Result<Vec<ServerCollabMessage>, RealtimeError>>,
+  },
   HandleClientHttpUpdate {
     user: RealtimeUser,
     workspace_id: String,
@@ -59,6 +67,9 @@ pub enum GroupCommand {
     state_vector: StateVector,
     ret: tokio::sync::oneshot::Sender<Result<Vec<u8>, RealtimeError>>,
   },
+  BroadcastCollabLock {
+    message: CollabLockChanged,
+  },
 }
 
 pub type GroupCommandSender = tokio::sync::mpsc::Sender<GroupCommand>;
@@ -105,6 +116,12 @@ where
               warn!("Send handle client collab message result fail: {:?}", err);
             }
           },
+          GroupCommand::HandleClientInitSync { user, message, ret } => {
+            let result = self.handle_client_init_sync(&user, message).await;
+            if let Err(err) = ret.send(result) {
+              warn!("Send handle client init sync result fail: {:?}", err);
+            }
+          },
           GroupCommand::EncodeCollab { object_id, ret } => {
             let group = self.group_manager.get_group(&object_id).await;
             if let Err(_err) = match group {
@@ -155,6 +172,11 @@ where
               }
             }
           },
+          GroupCommand::BroadcastCollabLock { message } => {
+            if let Some(group) = self.group_manager.get_group(&message.object_id).await {
+              group.broadcast_collab_lock(message).await;
+            }
+          },
           GroupCommand::CalculateMissingUpdate {
             object_id,
             state_vector,
@@ -255,6 +277,35 @@ where
     Ok(())
   }
 
+  /// Handles an init sync of a [collab_rt_entity::BatchInitSync]. Unlike
+  /// `handle_client_collab_message`, the responses are returned instead of being sent to the
+  /// client, so that the caller can answer the whole batch with a single message.
+  #[instrument(level = "trace", skip_all)]
+  async fn handle_client_init_sync(
+    &self,
+    user: &RealtimeUser,
+    message: ClientCollabMessage,
+  ) -> Result<Vec<ServerCollabMessage>, RealtimeError> {
+    if self.msg_router_by_user.get(user).is_none() {
+      return Err(RealtimeError::UserNotFound(user.to_string()));
+    }
+
+    let object_id = message.object_id().to_string();
+    if !self.group_manager.contains_group(&object_id) {
+      self.create_group_with_message(user, &message).await?;
+    }
+    if !self.group_manager.contains_user(&object_id, user) {
+      self.subscribe_group_with_message(user, &message).await?;
+    }
+
+    let group = self
+      .group_manager
+      .get_group(&object_id)
+      .await
+      .ok_or_else(|| RealtimeError::GroupNotFound(object_id.clone()))?;
+    group.handle_init_sync(user, message).await
+  }
+
   /// This functions will be called when client post update via http requset
   #[instrument(level = "trace", skip_all)]
   async fn handle_client_posted_http_update(
@@ -400,19 +451,14 @@ where
     collab_message: &ClientCollabMessage,
   ) -> Result<(), RealtimeError> {
     let object_id = collab_message.object_id();
-    match collab_message {
-      ClientCollabMessage::ClientInitSync { data, .. } => {
+    match collab_message.init_sync_target() {
+      Some((workspace_id, collab_type)) => {
         self
-          .create_group(
-            user,
-            &data.workspace_id,
-            object_id,
-            data.collab_type.clone(),
-          )
+          .create_group(user, workspace_id, object_id, collab_type.clone())
           .await?;
         Ok(())
       },
-      _ => Err(RealtimeError::ExpectInitSync(collab_message.to_string())),
+      None => Err(RealtimeError::ExpectInitSync(collab_message.to_string())),
     }
   }
 
@@ -0,0 +1,151 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use collab::core::origin::CollabOrigin;
+use collab_rt_entity::MsgId;
+
+/// How long a message is remembered. Clients resend a message that hasn't been acked after a few
+/// seconds, so the retransmissions arrive well within that window.
+const DEDUP_TTL: Duration = Duration::from_secs(60);
+/// Maximum number of messages remembered by a group.
+const DEDUP_CAPACITY: usize = 1024;
+
+type MessageKey = (CollabOrigin, MsgId);
+
+struct RecentMessage {
+  /// A client reusing a message id after a reconnect sends a different payload, which must not be
+  /// taken for a retransmission.
+  payload_hash: u64,
+  ack_payload: Bytes,
+  received_at: Instant,
+}
+
+#[derive(Default)]
+struct RecentMessagesInner {
+  by_key: HashMap<MessageKey, RecentMessage>,
+  /// Keys in the order they were inserted, used to expire the oldest messages first.
+  order: VecDeque<(MessageKey, Instant)>,
+}
+
+/// Messages recently applied to a group, keyed by (origin, msg_id), with the payload of the ack
+/// that was sent for them. A retransmitted message is acked again without being applied a second
+/// time.
+pub(crate) struct RecentMessages {
+  ttl: Duration,
+  capacity: usize,
+  inner: Mutex<RecentMessagesInner>,
+}
+
+impl Default for RecentMessages {
+  fn default() -> Self {
+    Self::new(DEDUP_TTL, DEDUP_CAPACITY)
+  }
+}
+
+impl RecentMessages {
+  pub fn new(ttl: Duration, capacity: usize) -> Self {
+    Self {
+      ttl,
+      capacity,
+      inner: Mutex::new(RecentMessagesInner::default()),
+    }
+  }
+
+  /// Returns the payload of the ack of the message if the same message has already been applied.
+  pub fn get(&self, origin: &CollabOrigin, msg_id: MsgId, payload: &[u8]) -> Option<Bytes> {
+    let inner = self.inner.lock().unwrap();
+    let message = inner.by_key.get(&(origin.clone(), msg_id))?;
+    if message.received_at.elapsed() > self.ttl || message.payload_hash != payload_hash(payload) {
+      return None;
+    }
+    Some(message.ack_payload.clone())
+  }
+
+  pub fn insert(&self, origin: CollabOrigin, msg_id: MsgId, payload: &[u8], ack_payload: Bytes) {
+    let now = Instant::now();
+    let mut inner = self.inner.lock().unwrap();
+    let key = (origin, msg_id);
+    inner.order.push_back((key.clone(), now));
+    inner.by_key.insert(
+      key,
+      RecentMessage {
+        payload_hash: payload_hash(payload),
+        ack_payload,
+        received_at: now,
+      },
+    );
+
+    while let Some((_, received_at)) = inner.order.front() {
+      if inner.order.len() <= self.capacity && received_at.elapsed() <= self.ttl {
+        break;
+      }
+      let (key, received_at) = inner.order.pop_front().unwrap();
+      // the key is still in the map if the message hasn't been inserted again since
+      if inner
+        .by_key
+        .get(&key)
+        .is_some_and(|message| message.received_at == received_at)
+      {
+        inner.by_key.remove(&key);
+      }
+    }
+  }
+}
+
+fn payload_hash(payload: &[u8]) -> u64 {
+  let mut hasher = DefaultHasher::new();
+  payload.hash(&mut hasher);
+  hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use collab::core::origin::CollabClient;
+
+  fn client(device_id: &str) -> CollabOrigin {
+    CollabOrigin::Client(CollabClient {
+      uid: 1,
+      device_id: device_id.to_string(),
+    })
+  }
+
+  fn ack(msg_id: MsgId) -> Bytes {
+    Bytes::from(msg_id.to_le_bytes().to_vec())
+  }
+
+  #[test]
+  fn retransmitted_message_is_found() {
+    let messages = RecentMessages::default();
+    messages.insert(client("a"), 1, b"update", ack(1));
+
+    assert_eq!(messages.get(&client("a"), 1, b"update"), Some(ack(1)));
+    // another device, another message or a reused message id
+    assert!(messages.get(&client("b"), 1, b"update").is_none());
+    assert!(messages.get(&client("a"), 2, b"update").is_none());
+    assert!(messages.get(&client("a"), 1, b"other update").is_none());
+  }
+
+  #[test]
+  fn oldest_messages_are_evicted() {
+    let messages = RecentMessages::new(DEDUP_TTL, 2);
+    for msg_id in 1..=3 {
+      messages.insert(client("a"), msg_id, b"update", ack(msg_id));
+    }
+    assert!(messages.get(&client("a"), 1, b"update").is_none());
+    assert!(messages.get(&client("a"), 2, b"update").is_some());
+    assert!(messages.get(&client("a"), 3, b"update").is_some());
+  }
+
+  #[test]
+  fn expired_messages_are_not_found() {
+    let messages = RecentMessages::new(Duration::ZERO, DEDUP_CAPACITY);
+    messages.insert(client("a"), 1, b"update", ack(1));
+    std::thread::sleep(Duration::from_millis(1));
+    assert!(messages.get(&client("a"), 1, b"update").is_none());
+  }
+}
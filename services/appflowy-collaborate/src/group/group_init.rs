@@ -1,16 +1,20 @@
+use crate::config::ReloadableSetting;
 use crate::error::RealtimeError;
+use crate::group::awareness_gc::AwarenessClients;
+use crate::group::cell_lock::CellLocks;
+use crate::group::dedup::RecentMessages;
 use anyhow::anyhow;
 use app_error::AppError;
-use arc_swap::ArcSwap;
+use arc_swap::{ArcSwap, ArcSwapOption};
 use collab::core::collab::DataSource;
 use collab::core::origin::CollabOrigin;
 use collab::entity::EncodedCollab;
-use collab::lock::RwLock;
 use collab::preclude::Collab;
 use collab_entity::CollabType;
 use collab_rt_entity::user::RealtimeUser;
 use collab_rt_entity::{
-  AckCode, AwarenessSync, BroadcastSync, CollabAck, MessageByObjectId, MsgId,
+  AckCode, AwarenessSync, BroadcastSync, CellLockChanged, CellLockRequest, CollabAck,
+  CollabLockChanged, MessageByObjectId, MsgId, ResumeCursor, ResumeSync, ServerCollabMessage,
 };
 use collab_rt_entity::{ClientCollabMessage, CollabMessage};
 use collab_rt_protocol::{Message, MessageReader, RTProtocolError, SyncMessage};
@@ -19,25 +23,32 @@ use collab_stream::collab_update_sink::{AwarenessUpdateSink, CollabUpdateSink};
 
 use crate::metrics::CollabRealtimeMetrics;
 use bytes::Bytes;
-use collab_document::document::DocumentBody;
+use chrono::{DateTime, NaiveDate, Utc};
 use collab_stream::error::StreamError;
 use collab_stream::model::{AwarenessStreamUpdate, CollabStreamUpdate, MessageId, UpdateFlags};
+use collab_stream::pubsub::ObservedCollabUpdate;
 use dashmap::DashMap;
-use database::collab::{CollabStorage, GetCollabOrigin};
-use database_entity::dto::{CollabParams, QueryCollabParams};
+use database::collab::{database_row_cells, database_row_data, CollabStorage, GetCollabOrigin};
+use database_entity::dto::{
+  CollabEditCounts, CollabParams, DatabaseRowCellChange, QueryCollabParams,
+};
 use futures::{pin_mut, Sink, Stream};
 use futures_util::{SinkExt, StreamExt};
 use indexer::scheduler::{IndexerScheduler, UnindexedCollabTask, UnindexedData};
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::watch;
 use tokio::time::MissedTickBehavior;
 use tokio_util::sync::CancellationToken;
 use tracing::{error, info, trace, warn};
 use uuid::Uuid;
 use yrs::updates::decoder::{Decode, DecoderV1};
 use yrs::updates::encoder::{Encode, Encoder, EncoderV1};
-use yrs::{ReadTxn, StateVector, Update};
+use yrs::{merge_updates_v1, ReadTxn, StateVector, Update};
 
 /// A group used to manage a single [Collab] object
 pub struct CollabGroup {
@@ -46,6 +57,11 @@ pub struct CollabGroup {
 
 /// Inner state of [CollabGroup] that's private and hidden behind Arc, so that it can be moved into
 /// tasks.
+///
+/// The updates and awareness of the collab are applied by a single writer: the inbound task reads
+/// them from the Redis streams, which act as the group's queue, and is the only one that mutates
+/// the state vector and broadcasts to the subscribers. The other tasks only read lock-free
+/// snapshots of that state, so a slow subscriber or a client sync never blocks the broadcast.
 struct CollabGroupState {
   workspace_id: String,
   object_id: String,
@@ -60,8 +76,33 @@ struct CollabGroupState {
   shutdown: CancellationToken,
   last_activity: ArcSwap<Instant>,
   seq_no: AtomicU32,
-  /// The most recent state vector from a redis update.
-  state_vector: RwLock<StateVector>,
+  /// The most recent state vector from a redis update. Only written by the inbound task.
+  state_vector: ArcSwap<StateVector>,
+  /// Id of the most recent update from the Redis stream that has been broadcast to subscribers.
+  /// Used as a resume token by the clients that reconnect with [ResumeSync].
+  last_message_id: ArcSwapOption<MessageId>,
+  /// Consecutive updates from the same sender received within this window are merged into a
+  /// single broadcast. Zero broadcasts every update right away.
+  broadcast_batch_window: Duration,
+  /// Updates and awareness recently applied, so the ones resent by the clients after a timeout
+  /// are acked without being applied and broadcast again.
+  recent_messages: RecentMessages,
+  /// Advisory locks of the database cells edited by the subscribers.
+  cell_locks: CellLocks,
+  /// Awareness states of the subscribers, removed when their connection closes.
+  awareness_clients: AwarenessClients,
+}
+
+/// Upper bound of the updates merged into a single broadcast, so that a long typing burst is still
+/// broadcast in pieces.
+const MAX_BROADCAST_BATCH_LEN: usize = 100;
+
+/// Consecutive collab updates from the same sender waiting for the end of the batching window.
+struct PendingBroadcast {
+  sender: CollabOrigin,
+  updates: Vec<Vec<u8>>,
+  last_message_id: MessageId,
+  started_at: Instant,
 }
 
 impl Drop for CollabGroup {
@@ -81,8 +122,8 @@ impl CollabGroup {
     metrics: Arc<CollabRealtimeMetrics>,
     storage: Arc<S>,
     collab_redis_stream: Arc<CollabRedisStream>,
-    persistence_interval: Duration,
-    prune_grace_period: Duration,
+    settings: watch::Receiver<ReloadableSetting>,
+    broadcast_batch_window: Duration,
     state_vector: StateVector,
     indexer_scheduler: Arc<IndexerScheduler>,
   ) -> Result<Self, StreamError>
@@ -99,7 +140,7 @@ impl CollabGroup {
       collab_redis_stream,
       indexer_scheduler,
       metrics.clone(),
-      prune_grace_period,
+      settings.clone(),
     );
 
     let state = Arc::new(CollabGroupState {
@@ -112,7 +153,12 @@ impl CollabGroup {
       persister,
       last_activity: ArcSwap::new(Instant::now().into()),
       seq_no: AtomicU32::new(0),
-      state_vector: state_vector.into(),
+      state_vector: ArcSwap::from_pointee(state_vector),
+      last_message_id: ArcSwapOption::empty(),
+      broadcast_batch_window,
+      recent_messages: RecentMessages::default(),
+      cell_locks: CellLocks::default(),
+      awareness_clients: AwarenessClients::default(),
     });
 
     /*
@@ -124,7 +170,7 @@ impl CollabGroup {
      tasks and triggered when this `CollabGroup` is dropped.
     */
 
-    // setup task used to receive collab updates from Redis
+    // setup the single writer task used to receive collab and awareness updates from Redis
     {
       let state = state.clone();
       tokio::spawn(async move {
@@ -134,23 +180,9 @@ impl CollabGroup {
       });
     }
 
-    // setup task used to receive awareness updates from Redis
-    {
-      let state = state.clone();
-      tokio::spawn(async move {
-        if let Err(err) = Self::inbound_awareness_task(state).await {
-          tracing::warn!("failed to receive awareness update: {}", err);
-        }
-      });
-    }
-
     // setup periodic snapshot
     {
-      tokio::spawn(Self::snapshot_task(
-        state.clone(),
-        persistence_interval,
-        is_new_collab,
-      ));
+      tokio::spawn(Self::snapshot_task(state.clone(), settings, is_new_collab));
     }
 
     Ok(Self { state })
@@ -171,7 +203,9 @@ impl CollabGroup {
     self.state.shutdown.is_cancelled()
   }
 
-  /// Task used to receive collab updates from Redis.
+  /// Task used to receive collab and awareness updates from Redis. Both are handled by this single
+  /// loop, so that they are broadcast in a deterministic order and a pending batch is always
+  /// flushed before the next update is applied.
   async fn inbound_task(state: Arc<CollabGroupState>) -> Result<(), RealtimeError> {
     let updates = state.persister.collab_redis_stream.live_collab_updates(
       &state.workspace_id,
@@ -179,20 +213,84 @@ impl CollabGroup {
       None,
     );
     pin_mut!(updates);
+    let awareness_updates = state.persister.collab_redis_stream.awareness_updates(
+      &state.workspace_id,
+      &state.object_id,
+      None,
+    );
+    pin_mut!(awareness_updates);
+    let mut awareness_closed = false;
+    let mut pending: Option<PendingBroadcast> = None;
     loop {
+      let batch_deadline = pending
+        .as_ref()
+        .map(|pending| pending.started_at + state.broadcast_batch_window);
       tokio::select! {
         _ = state.shutdown.cancelled() => {
+          if let Some(pending) = pending.take() {
+            Self::flush_broadcast(&state, pending).await;
+          }
           match state.persister.trim_awareness().await {
             Ok(_) => (),
             Err(err) => warn!("unable to trim awareness due to {}", err),
           };
           break;
         }
+        _ = Self::wait_for_batch_deadline(batch_deadline) => {
+          if let Some(pending) = pending.take() {
+            Self::flush_broadcast(&state, pending).await;
+          }
+        }
+        res = awareness_updates.next(), if !awareness_closed => {
+          match res {
+            Some(Ok(awareness_update)) => {
+              Self::handle_inbound_awareness(&state, awareness_update).await;
+            },
+            Some(Err(err)) => {
+              tracing::warn!("failed to handle incoming awareness for collab `{}`: {}", state.object_id, err);
+              awareness_closed = true;
+            },
+            None => {
+              awareness_closed = true;
+            }
+          }
+        }
         res = updates.next() => {
           match res {
             Some(Ok((message_id, update))) => {
               state.metrics.observe_collab_stream_latency(message_id.timestamp_ms);
-              Self::handle_inbound_update(&state, update).await;
+              if state.broadcast_batch_window.is_zero() {
+                state.metrics.broadcast_batch_size.observe(1.0);
+                state.metrics.broadcast_batch_bytes.observe(update.data.len() as f64);
+                Self::handle_inbound_update(&state, update).await;
+                state.last_message_id.store(Some(Arc::new(message_id)));
+                continue;
+              }
+
+              match pending.as_mut() {
+                Some(batch) if batch.sender == update.sender => {
+                  batch.updates.push(update.data);
+                  batch.last_message_id = message_id;
+                },
+                _ => {
+                  // updates of different senders are never merged, so that the broadcast keeps
+                  // the origin of every update
+                  if let Some(batch) = pending.take() {
+                    Self::flush_broadcast(&state, batch).await;
+                  }
+                  pending = Some(PendingBroadcast {
+                    sender: update.sender,
+                    updates: vec![update.data],
+                    last_message_id: message_id,
+                    started_at: Instant::now(),
+                  });
+                },
+              }
+              if pending.as_ref().is_some_and(|batch| batch.updates.len() >= MAX_BROADCAST_BATCH_LEN) {
+                if let Some(batch) = pending.take() {
+                  Self::flush_broadcast(&state, batch).await;
+                }
+              }
             },
             Some(Err(err)) => {
               tracing::warn!("failed to handle incoming update for collab `{}`: {}", state.object_id, err);
@@ -205,17 +303,69 @@ impl CollabGroup {
         }
       }
     }
+    if let Some(pending) = pending.take() {
+      Self::flush_broadcast(&state, pending).await;
+    }
     Ok(())
   }
 
+  async fn wait_for_batch_deadline(deadline: Option<Instant>) {
+    match deadline {
+      Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+      None => std::future::pending().await,
+    }
+  }
+
+  /// Merges the pending updates into a single update and broadcasts it.
+  async fn flush_broadcast(state: &CollabGroupState, mut pending: PendingBroadcast) {
+    let count = pending.updates.len();
+    let data = if count == 1 {
+      pending.updates.pop().unwrap_or_default()
+    } else {
+      match merge_updates_v1(&pending.updates) {
+        Ok(merged) => merged,
+        Err(err) => {
+          warn!(
+            "{}: failed to merge {} updates before broadcast, sending them one by one: {}",
+            state.object_id, count, err
+          );
+          for data in pending.updates {
+            let update =
+              CollabStreamUpdate::new(data, pending.sender.clone(), UpdateFlags::default());
+            Self::handle_inbound_update(state, update).await;
+          }
+          state
+            .last_message_id
+            .store(Some(Arc::new(pending.last_message_id)));
+          return;
+        },
+      }
+    };
+
+    state.metrics.broadcast_batch_size.observe(count as f64);
+    state
+      .metrics
+      .broadcast_batch_bytes
+      .observe(data.len() as f64);
+    state
+      .metrics
+      .observe_broadcast_batch_delay(pending.started_at.elapsed().as_millis() as f64);
+    let update = CollabStreamUpdate::new(data, pending.sender, UpdateFlags::default());
+    Self::handle_inbound_update(state, update).await;
+    state
+      .last_message_id
+      .store(Some(Arc::new(pending.last_message_id)));
+  }
+
   async fn handle_inbound_update(state: &CollabGroupState, update: CollabStreamUpdate) {
     // update state vector based on incoming message
     match Update::decode_v1(&update.data) {
-      Ok(update) => state
-        .state_vector
-        .write()
-        .await
-        .merge(update.state_vector()),
+      Ok(update) => {
+        // the inbound task is the only writer, so there is no concurrent store to lose
+        let mut state_vector = StateVector::clone(&state.state_vector.load());
+        state_vector.merge(update.state_vector());
+        state.state_vector.store(Arc::new(state_vector));
+      },
       Err(err) => {
         tracing::error!(
           "received malformed update for collab `{}`: {}",
@@ -254,38 +404,6 @@ impl CollabGroup {
     }
   }
 
-  /// Task used to receive awareness updates from Redis.
-  async fn inbound_awareness_task(state: Arc<CollabGroupState>) -> Result<(), RealtimeError> {
-    let updates = state.persister.collab_redis_stream.awareness_updates(
-      &state.workspace_id,
-      &state.object_id,
-      None,
-    );
-    pin_mut!(updates);
-    loop {
-      tokio::select! {
-        _ = state.shutdown.cancelled() => {
-          break;
-        }
-        res = updates.next() => {
-          match res {
-            Some(Ok(awareness_update)) => {
-              Self::handle_inbound_awareness(&state, awareness_update).await;
-            },
-            Some(Err(err)) => {
-              tracing::warn!("failed to handle incoming update for collab `{}`: {}", state.object_id, err);
-              break;
-            },
-            None => {
-              break;
-            }
-          }
-        }
-      }
-    }
-    Ok(())
-  }
-
   async fn handle_inbound_awareness(state: &CollabGroupState, update: AwarenessStreamUpdate) {
     tracing::trace!(
       "broadcasting awareness update from {} ({} bytes)",
@@ -317,7 +435,64 @@ impl CollabGroup {
     }
   }
 
-  async fn snapshot_task(state: Arc<CollabGroupState>, interval: Duration, is_new_collab: bool) {
+  /// Sends the recent awareness states of the other participants to a newly joined subscriber,
+  /// so that it can render their cursors without waiting for them to move.
+  async fn replay_awareness_task<Sink>(
+    state: Arc<CollabGroupState>,
+    mut sink: Sink,
+    origin: CollabOrigin,
+    shutdown: CancellationToken,
+  ) where
+    Sink: SubscriptionSink + 'static,
+  {
+    let redis_stream = &state.persister.collab_redis_stream;
+    let replay_count = redis_stream.awareness_config().replay_count;
+    if replay_count == 0 {
+      return;
+    }
+    let updates = match redis_stream
+      .recent_awareness_updates(&state.workspace_id, &state.object_id, replay_count)
+      .await
+    {
+      Ok(updates) => updates,
+      Err(err) => {
+        warn!(
+          "failed to read recent awareness of {}: {}",
+          state.object_id, err
+        );
+        return;
+      },
+    };
+
+    for (_, update) in updates {
+      if shutdown.is_cancelled() {
+        break;
+      }
+      if update.sender == origin {
+        continue;
+      }
+      let message = AwarenessSync::new(
+        state.object_id.clone(),
+        Message::Awareness(update.data).encode_v1(),
+        CollabOrigin::Empty,
+      );
+      if let Err(err) = sink.send(message.into()).await {
+        tracing::debug!(
+          "failed to replay awareness `{}` to `{}`: {}",
+          state.object_id,
+          origin,
+          err
+        );
+        break;
+      }
+    }
+  }
+
+  async fn snapshot_task(
+    state: Arc<CollabGroupState>,
+    mut settings: watch::Receiver<ReloadableSetting>,
+    is_new_collab: bool,
+  ) {
     if is_new_collab {
       tracing::trace!("persisting new collab for {}", state.object_id);
       if let Err(err) = state.persister.save().await {
@@ -329,6 +504,7 @@ impl CollabGroup {
       }
     }
 
+    let mut interval = settings.borrow_and_update().group_persistence_interval;
     let mut snapshot_tick = tokio::time::interval(interval);
     // if saving took longer than snapshot_tick, just skip it over and try in the next round
     snapshot_tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
@@ -339,11 +515,22 @@ impl CollabGroup {
           if let Err(err) = state.persister.save().await {
             tracing::warn!("failed to persist collab `{}/{}`: {}", state.workspace_id, state.object_id, err);
           }
+          state.persister.record_edits().await;
+        },
+        Ok(()) = settings.changed() => {
+          // the persistence interval has been changed by a config reload
+          let new_interval = settings.borrow_and_update().group_persistence_interval;
+          if new_interval != interval {
+            interval = new_interval;
+            snapshot_tick = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+            snapshot_tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+          }
         },
         _ = state.shutdown.cancelled() => {
           if let Err(err) = state.persister.save().await {
             tracing::warn!("failed to persist collab on shutdown `{}/{}`: {}", state.workspace_id, state.object_id, err);
           }
+          state.persister.record_edits().await;
           break;
         }
       }
@@ -382,8 +569,8 @@ impl CollabGroup {
   ) -> Result<Vec<u8>, RealtimeError> {
     {
       // first check if we need to send any updates
-      let collab_sv = self.state.state_vector.read().await;
-      if *collab_sv <= state_vector {
+      let collab_sv = self.state.state_vector.load();
+      if **collab_sv <= state_vector {
         return Ok(vec![]);
       }
     }
@@ -412,6 +599,26 @@ impl CollabGroup {
     Ok(encode_collab)
   }
 
+  /// Sends the lock state of the collab to every subscriber, including the one that changed it.
+  pub async fn broadcast_collab_lock(&self, message: CollabLockChanged) {
+    Self::send_to_subscribers(&self.state, message.into()).await;
+  }
+
+  async fn send_to_subscribers(state: &CollabGroupState, message: CollabMessage) {
+    for mut e in state.subscribers.iter_mut() {
+      let subscription = e.value_mut();
+      if let Err(err) = subscription.sink.send(message.clone()).await {
+        tracing::debug!(
+          "failed to send collab `{}` {} to `{}`: {}",
+          state.object_id,
+          message,
+          subscription.collab_origin,
+          err
+        );
+      }
+    }
+  }
+
   pub fn contains_user(&self, user: &RealtimeUser) -> bool {
     self.state.subscribers.contains_key(user)
   }
@@ -423,6 +630,17 @@ impl CollabGroup {
         self.state.object_id,
         user
       );
+      // The cells edited by the user are released right away instead of when their lock expires.
+      let released = self.state.cell_locks.release_all(user.uid, &user.device_id);
+      if !released.is_empty() {
+        let state = self.state.clone();
+        tokio::spawn(async move {
+          for (row_id, field_id) in released {
+            let message = CellLockChanged::new(state.object_id.clone(), row_id, field_id, None);
+            Self::send_to_subscribers(&state, message.into()).await;
+          }
+        });
+      }
     }
   }
 
@@ -448,15 +666,25 @@ impl CollabGroup {
   {
     // create new subscription for new subscriber
     let subscriber_shutdown = self.state.shutdown.child_token();
+    let resumable = Arc::new(AtomicBool::new(false));
 
     tokio::spawn(Self::receive_from_client_task(
       self.state.clone(),
       sink.clone(),
       stream,
       subscriber_origin.clone(),
+      resumable.clone(),
+      subscriber_shutdown.clone(),
+    ));
+
+    tokio::spawn(Self::replay_awareness_task(
+      self.state.clone(),
+      sink.clone(),
+      subscriber_origin.clone(),
+      subscriber_shutdown.clone(),
     ));
 
-    let sub = Subscription::new(sink, subscriber_origin, subscriber_shutdown);
+    let sub = Subscription::new(sink, subscriber_origin, subscriber_shutdown, resumable);
     if self
       .state
       .subscribers
@@ -488,41 +716,165 @@ impl CollabGroup {
     mut sink: Sink,
     mut stream: Stream,
     origin: CollabOrigin,
+    resumable: Arc<AtomicBool>,
+    subscriber_shutdown: CancellationToken,
   ) where
     Sink: SubscriptionSink + 'static,
     Stream: SubscriptionStream + 'static,
   {
+    // Subscribers that connected with a resume sync receive a resume cursor after each batch of
+    // acks, so that they can present it when reconnecting.
+    let mut sent_cursor: Option<MessageId> = None;
     loop {
       tokio::select! {
         _ = state.shutdown.cancelled() => {
           break;
         }
+        // the subscription was dropped, the connection of the subscriber is closed
+        _ = subscriber_shutdown.cancelled() => {
+          break;
+        }
         msg = stream.next() => {
           match msg {
             None => break,
-            Some(msg) => if let Err(err) =  Self::handle_messages(&state, &mut sink, msg).await {
-              tracing::warn!(
-                "collab `{}` failed to handle message from `{}`: {}",
-                state.object_id,
-                origin,
-                err
-              );
-
-            }
+            Some(msg) => {
+              if msg
+                .0
+                .values()
+                .flatten()
+                .any(|msg| matches!(msg, ClientCollabMessage::ClientResumeSync { .. }))
+              {
+                resumable.store(true, Ordering::Relaxed);
+              }
+              match Self::handle_messages(&state, &mut sink, msg).await {
+                Ok(replayed) => if resumable.load(Ordering::Relaxed) {
+                  Self::send_resume_cursor(&state, &mut sink, &mut sent_cursor, replayed).await;
+                },
+                Err(err) => tracing::warn!(
+                  "collab `{}` failed to handle message from `{}`: {}",
+                  state.object_id,
+                  origin,
+                  err
+                ),
+              }
+            },
           }
         }
       }
     }
+    Self::remove_stale_awareness(&state, &origin).await;
   }
 
+  /// Removes the awareness states left by a subscriber whose connection closed, so that the other
+  /// clients stop showing its cursor right away. A client that was killed never removes them
+  /// itself.
+  async fn remove_stale_awareness(state: &CollabGroupState, origin: &CollabOrigin) {
+    if state.shutdown.is_cancelled() {
+      return;
+    }
+    // the subscriber reconnected, its states are still in use
+    if state
+      .subscribers
+      .iter()
+      .any(|e| &e.value().collab_origin == origin)
+    {
+      return;
+    }
+    if let Some(update) = state.awareness_clients.remove_origin(origin) {
+      trace!(
+        "{}: remove awareness of disconnected subscriber {}",
+        state.object_id,
+        origin
+      );
+      if let Err(err) = state.persister.send_awareness(origin, update).await {
+        warn!(
+          "failed to remove awareness of {} from collab `{}`: {}",
+          origin, state.object_id, err
+        );
+      }
+    }
+  }
+
+  async fn send_resume_cursor<Sink>(
+    state: &CollabGroupState,
+    sink: &mut Sink,
+    sent_cursor: &mut Option<MessageId>,
+    replayed: Option<MessageId>,
+  ) where
+    Sink: SubscriptionSink + 'static,
+  {
+    let latest = state
+      .last_message_id
+      .load()
+      .as_deref()
+      .copied()
+      .max(replayed);
+    if latest <= *sent_cursor {
+      return;
+    }
+    if let Some(cursor) = Self::resume_cursor(state, replayed) {
+      match sink.send(cursor.into()).await {
+        Ok(()) => *sent_cursor = latest,
+        Err(err) => trace!("[realtime]: send resume cursor failed: {}", err),
+      }
+    }
+  }
+
+  fn resume_cursor(state: &CollabGroupState, replayed: Option<MessageId>) -> Option<ResumeCursor> {
+    let broadcast = state.last_message_id.load().as_deref().copied();
+    let message_id = broadcast.max(replayed)?;
+    Some(ResumeCursor::new(
+      state.object_id.clone(),
+      message_id.to_string(),
+    ))
+  }
+
+  /// Handles an init sync that was sent as part of a [collab_rt_entity::BatchInitSync]. Instead of
+  /// being sent through the subscriber sink, the responses are returned to the caller, which
+  /// answers all the objects of the batch at once.
+  pub async fn handle_init_sync(
+    &self,
+    user: &RealtimeUser,
+    message: ClientCollabMessage,
+  ) -> Result<Vec<ServerCollabMessage>, RealtimeError> {
+    let state = &self.state;
+    let mut replayed = None;
+    let mut resumed = None;
+    if let ClientCollabMessage::ClientResumeSync { data } = &message {
+      if let Some(subscription) = state.subscribers.get(user) {
+        subscription.resumable.store(true, Ordering::Relaxed);
+      }
+      if let Some((ack, last_message_id)) = Self::handle_resume_sync(state, data).await {
+        replayed = last_message_id;
+        resumed = Some(ack);
+      }
+    }
+
+    let is_resumable = matches!(message, ClientCollabMessage::ClientResumeSync { .. });
+    let ack = match resumed {
+      Some(ack) => ack,
+      None => Self::handle_client_message(state, message).await?,
+    };
+    let mut responses = vec![ServerCollabMessage::ClientAck(ack)];
+    if is_resumable {
+      if let Some(cursor) = Self::resume_cursor(state, replayed) {
+        responses.push(ServerCollabMessage::ServerResumeCursor(cursor));
+      }
+    }
+    Ok(responses)
+  }
+
+  /// Handles the messages sent by a subscriber and acks each of them. Returns the id of the last
+  /// update replayed from the Redis stream if one of the messages was a resumed sync.
   async fn handle_messages<Sink>(
     state: &CollabGroupState,
     sink: &mut Sink,
     msg: MessageByObjectId,
-  ) -> Result<(), RealtimeError>
+  ) -> Result<Option<MessageId>, RealtimeError>
   where
     Sink: SubscriptionSink + 'static,
   {
+    let mut replayed = None;
     for (message_object_id, messages) in msg.0 {
       if state.object_id != message_object_id {
         error!(
@@ -532,7 +884,24 @@ impl CollabGroup {
         continue;
       }
       for message in messages {
-        match Self::handle_client_message(state, message).await {
+        // The messages that don't carry a collab update are acked without being applied.
+        let handled = match &message {
+          ClientCollabMessage::ClientResumeSync { data } => {
+            Self::handle_resume_sync(state, data).await
+          },
+          ClientCollabMessage::ClientCellLock { data } => {
+            Some((Self::handle_cell_lock(state, data).await, None))
+          },
+          _ => None,
+        };
+        let result = match handled {
+          Some((ack, last_message_id)) => {
+            replayed = replayed.max(last_message_id);
+            Ok(ack)
+          },
+          None => Self::handle_client_message(state, message).await,
+        };
+        match result {
           Ok(response) => {
             trace!("[realtime]: sending response: {}", response);
             match sink.send(response.into()).await {
@@ -553,7 +922,119 @@ impl CollabGroup {
         }
       }
     }
-    Ok(())
+    Ok(replayed)
+  }
+
+  /// Replays the updates that were added to the Redis update stream after the resume token
+  /// presented by the client. Returns `None` when the token is missing or the stream has been
+  /// pruned past it, in which case the message is handled as a regular init sync.
+  async fn handle_resume_sync(
+    state: &CollabGroupState,
+    resume_sync: &ResumeSync,
+  ) -> Option<(CollabAck, Option<MessageId>)> {
+    let token = resume_sync.resume_token.as_deref()?;
+    let since = match MessageId::try_from(token) {
+      Ok(message_id) => message_id,
+      Err(err) => {
+        warn!(
+          "{}: invalid resume token `{}`: {}",
+          state.object_id, token, err
+        );
+        return None;
+      },
+    };
+
+    let stream = &state.persister.collab_redis_stream;
+    match stream
+      .first_collab_update_id(&state.workspace_id, &state.object_id)
+      .await
+    {
+      Ok(Some(first)) if first <= since => {},
+      Ok(_) => {
+        trace!(
+          "{}: resume token {} is no longer in the update stream",
+          state.object_id,
+          since
+        );
+        state.metrics.resume_sync_fallback_count.inc();
+        return None;
+      },
+      Err(err) => {
+        warn!("{}: failed to check resume token: {}", state.object_id, err);
+        state.metrics.resume_sync_fallback_count.inc();
+        return None;
+      },
+    }
+
+    let updates = match stream
+      .current_collab_updates(&state.workspace_id, &state.object_id, Some(since))
+      .await
+    {
+      Ok(updates) => updates,
+      Err(err) => {
+        warn!(
+          "{}: failed to read missed updates: {}",
+          state.object_id, err
+        );
+        state.metrics.resume_sync_fallback_count.inc();
+        return None;
+      },
+    };
+
+    let last_message_id = updates.last().map(|(message_id, _)| *message_id);
+    let mut encoder = EncoderV1::new();
+    if !updates.is_empty() {
+      let merged = match merge_updates_v1(updates.iter().map(|(_, update)| &update.data)) {
+        Ok(merged) => merged,
+        Err(err) => {
+          warn!(
+            "{}: failed to merge missed updates: {}",
+            state.object_id, err
+          );
+          state.metrics.resume_sync_fallback_count.inc();
+          return None;
+        },
+      };
+      Message::Sync(SyncMessage::Update(merged)).encode(&mut encoder);
+    }
+    // Ask the client for the changes it made while it was offline.
+    let server_sv = StateVector::clone(&state.state_vector.load());
+    Message::Sync(SyncMessage::SyncStep1(server_sv)).encode(&mut encoder);
+
+    trace!(
+      "{}: resumed sync from {} with {} missed updates",
+      state.object_id,
+      since,
+      updates.len()
+    );
+    state.metrics.resume_sync_count.inc();
+    let ack = CollabAck::new(
+      CollabOrigin::Server,
+      state.object_id.to_string(),
+      resume_sync.msg_id,
+      state.seq_no.load(Ordering::SeqCst),
+    )
+    .with_payload(encoder.to_vec());
+    Some((ack, last_message_id))
+  }
+
+  /// Applies the advisory lock request and broadcasts the resulting holder of the cell to every
+  /// subscriber, including the requester.
+  async fn handle_cell_lock(state: &CollabGroupState, request: &CellLockRequest) -> CollabAck {
+    let holder = state.cell_locks.apply(request, Utc::now().timestamp());
+    let message = CellLockChanged::new(
+      state.object_id.clone(),
+      request.row_id.clone(),
+      request.field_id.clone(),
+      holder,
+    );
+    Self::send_to_subscribers(state, message.into()).await;
+    CollabAck::new(
+      CollabOrigin::Server,
+      state.object_id.to_string(),
+      request.msg_id,
+      state.seq_no.load(Ordering::SeqCst),
+    )
   }
 
   /// Handle the message sent from the client
@@ -578,20 +1059,53 @@ impl CollabGroup {
       ));
     }
 
+    let payload = collab_msg.payload();
+    let deduplicated = matches!(
+      collab_msg,
+      ClientCollabMessage::ClientUpdateSync { .. } | ClientCollabMessage::ClientAwarenessSync(_)
+    );
+    if deduplicated {
+      state.metrics.dedup_checked_count.inc();
+      if let Some(ack_payload) = state.recent_messages.get(&message_origin, msg_id, payload) {
+        trace!(
+          "{}: message {} from {} already applied",
+          state.object_id,
+          msg_id,
+          message_origin
+        );
+        state.metrics.duplicate_message_count.inc();
+        return Ok(
+          CollabAck::new(
+            CollabOrigin::Server,
+            state.object_id.to_string(),
+            msg_id,
+            state.seq_no.load(Ordering::SeqCst),
+          )
+          .with_payload(ack_payload),
+        );
+      }
+    }
+
     trace!(
       "Applying client updates: {}, origin:{}",
       collab_msg,
       message_origin
     );
 
-    let payload = collab_msg.payload();
-
     // Spawn a blocking task to handle the message
     let result = Self::handle_message(state, payload, &message_origin, msg_id).await;
 
     match result {
       Ok(inner_result) => match inner_result {
-        Some(response) => Ok(response),
+        Some(response) => {
+          // the failed messages are applied again when they are resent
+          if deduplicated && response.code == AckCode::Success as u8 {
+            state
+              .recent_messages
+              .insert(message_origin, msg_id, payload, response.payload.clone());
+          }
+          Ok(response)
+        },
         None => Err(RealtimeError::UnexpectedData("No ack response")),
       },
       Err(err) => Err(RealtimeError::Internal(anyhow!(
@@ -688,14 +1202,14 @@ impl CollabGroup {
     state: &CollabGroupState,
     remote_sv: &StateVector,
   ) -> Result<Option<Vec<u8>>, RTProtocolError> {
-    if let Ok(sv) = state.state_vector.try_read() {
-      // we optimistically try to obtain state vector lock for a fast track:
-      // if we remote sv is up-to-date with current one, we don't need to do anything
-      match sv.partial_cmp(remote_sv) {
+    {
+      // fast track: if the remote sv is up-to-date with current one, we don't need to do anything
+      let sv = state.state_vector.load();
+      match sv.as_ref().partial_cmp(remote_sv) {
         Some(std::cmp::Ordering::Equal) => return Ok(None), // client and server are in sync
         Some(std::cmp::Ordering::Less) => {
           // server is behind client
-          let msg = Message::Sync(SyncMessage::SyncStep1(sv.clone()));
+          let msg = Message::Sync(SyncMessage::SyncStep1(StateVector::clone(&sv)));
           return Ok(Some(msg.encode_v1()));
         },
         Some(std::cmp::Ordering::Greater) | None => { /* server has some new updates */ },
@@ -747,9 +1261,12 @@ impl CollabGroup {
       .map_err(|err| RTProtocolError::Internal(err.into()))??
     };
     let missing_updates = {
-      let state_vector = state.state_vector.read().await;
-      match state_vector.partial_cmp(&decoded_update.state_vector_lower()) {
-        None | Some(std::cmp::Ordering::Less) => Some(state_vector.clone()),
+      let state_vector = state.state_vector.load();
+      match state_vector
+        .as_ref()
+        .partial_cmp(&decoded_update.state_vector_lower())
+      {
+        None | Some(std::cmp::Ordering::Less) => Some(StateVector::clone(&state_vector)),
         _ => None,
       }
     };
@@ -788,6 +1305,7 @@ impl CollabGroup {
     origin: &CollabOrigin,
     update: Vec<u8>,
   ) -> Result<Option<Vec<u8>>, RTProtocolError> {
+    state.awareness_clients.observe(origin, &update);
     state
       .persister
       .send_awareness(origin, update)
@@ -848,10 +1366,17 @@ struct Subscription {
   collab_origin: CollabOrigin,
   sink: Box<dyn SubscriptionSink>,
   shutdown: CancellationToken,
+  /// Set when the subscriber synced with a [ResumeSync], so that it receives resume cursors.
+  resumable: Arc<AtomicBool>,
 }
 
 impl Subscription {
-  fn new<S>(sink: S, collab_origin: CollabOrigin, shutdown: CancellationToken) -> Self
+  fn new<S>(
+    sink: S,
+    collab_origin: CollabOrigin,
+    shutdown: CancellationToken,
+    resumable: Arc<AtomicBool>,
+  ) -> Self
   where
     S: SubscriptionSink + 'static,
   {
@@ -859,6 +1384,7 @@ impl Subscription {
       sink: Box::new(sink),
       collab_origin,
       shutdown,
+      resumable,
     }
   }
 }
@@ -870,6 +1396,34 @@ impl Drop for Subscription {
   }
 }
 
+/// Edits received from the clients of a collab since they were last recorded.
+#[derive(Default)]
+struct PendingEdits {
+  total_updates: i64,
+  last_edit: Option<(i64, DateTime<Utc>)>,
+  daily: HashMap<NaiveDate, i64>,
+}
+
+impl PendingEdits {
+  fn record(&mut self, uid: i64, edited_at: DateTime<Utc>) {
+    self.total_updates += 1;
+    self.last_edit = Some((uid, edited_at));
+    *self.daily.entry(edited_at.date_naive()).or_default() += 1;
+  }
+
+  fn take(&mut self) -> Option<CollabEditCounts> {
+    let (last_edited_by, last_edited_at) = self.last_edit.take()?;
+    let counts = CollabEditCounts {
+      total_updates: self.total_updates,
+      last_edited_by,
+      last_edited_at,
+      daily: self.daily.drain().collect(),
+    };
+    self.total_updates = 0;
+    Some(counts)
+  }
+}
+
 struct CollabPersister {
   uid: i64,
   workspace_id: String,
@@ -881,9 +1435,14 @@ struct CollabPersister {
   metrics: Arc<CollabRealtimeMetrics>,
   update_sink: CollabUpdateSink,
   awareness_sink: AwarenessUpdateSink,
-  /// A grace period for prunning Redis collab updates. Instead of deleting all messages we
-  /// read right away, we give 1min for other potential client to catch up.
-  prune_grace_period: Duration,
+  /// Edits of the clients that haven't been added to the edit statistics yet.
+  pending_edits: std::sync::Mutex<PendingEdits>,
+  /// Hash of the text last sent to the indexer. Updates from the stream that don't change the
+  /// indexed text, like formatting or moving blocks around, don't reindex the collab.
+  last_indexed_text: std::sync::Mutex<Option<u64>>,
+  /// Holds the grace period for prunning Redis collab updates. Instead of deleting all messages
+  /// we read right away, we give 1min for other potential client to catch up.
+  settings: watch::Receiver<ReloadableSetting>,
 }
 
 impl CollabPersister {
@@ -897,7 +1456,7 @@ impl CollabPersister {
     collab_redis_stream: Arc<CollabRedisStream>,
     indexer_scheduler: Arc<IndexerScheduler>,
     metrics: Arc<CollabRealtimeMetrics>,
-    prune_grace_period: Duration,
+    settings: watch::Receiver<ReloadableSetting>,
   ) -> Self {
     let update_sink = collab_redis_stream.collab_update_sink(&workspace_id, &object_id);
     let awareness_sink = collab_redis_stream.awareness_update_sink(&workspace_id, &object_id);
@@ -912,7 +1471,9 @@ impl CollabPersister {
       metrics,
       update_sink,
       awareness_sink,
-      prune_grace_period,
+      pending_edits: Default::default(),
+      last_indexed_text: Default::default(),
+      settings,
     }
   }
 
@@ -925,12 +1486,34 @@ impl CollabPersister {
     // send updates to redis queue
     let update = CollabStreamUpdate::new(update, sender, UpdateFlags::default());
     let msg_id = self.update_sink.send(&update).await?;
+    if let CollabOrigin::Client(client) = &update.sender {
+      if let Ok(mut pending_edits) = self.pending_edits.lock() {
+        pending_edits.record(client.uid, Utc::now());
+      }
+    }
     tracing::trace!(
       "persisted update from {} ({} bytes) - msg id: {}",
       update.sender,
       len,
       msg_id
     );
+    let observed = ObservedCollabUpdate {
+      object_id: self.object_id.clone(),
+      sender: update.sender,
+      data: update.data,
+    };
+    if let Err(err) = self
+      .collab_redis_stream
+      .publish_observed_update(&observed)
+      .await
+    {
+      // observers are best effort, the update has already been persisted in the stream
+      tracing::warn!(
+        "failed to publish update of {} to observers: {}",
+        self.object_id,
+        err
+      );
+    }
     Ok(msg_id)
   }
 
@@ -1002,6 +1585,7 @@ impl CollabPersister {
     let snapshot = CollabSnapshot {
       collab,
       last_message_id,
+      row_changes: Vec::new(),
     };
     Ok(snapshot)
   }
@@ -1019,8 +1603,13 @@ impl CollabPersister {
     let mut i = 0;
     let mut collab = None;
     let mut last_message_id = None;
+    // cells of the database row before the update being applied, used to build the row activity
+    let track_row_changes = matches!(self.collab_type, CollabType::DatabaseRow);
+    let mut row_cells = None;
+    let mut row_changes = Vec::new();
     for (message_id, update) in updates {
       i += 1;
+      let sender = update.sender.clone();
       let update: Update = update.into_update()?;
       if collab.is_none() {
         collab = Some(match self.load_collab_full().await? {
@@ -1031,10 +1620,29 @@ impl CollabPersister {
         })
       };
       let collab = collab.as_mut().unwrap();
+      if track_row_changes && row_cells.is_none() {
+        row_cells = Some(row_cells_of(collab));
+      }
       collab
         .transact_mut()
         .apply_update(update)
         .map_err(|err| RTProtocolError::YrsApplyUpdate(err.to_string()))?;
+      if track_row_changes {
+        let cells = row_cells_of(collab);
+        // the changes made through the REST API are recorded by the server along with the user
+        // who made them, so only the changes from clients are recorded here
+        if let (CollabOrigin::Client(client), Some(before)) = (&sender, &row_cells) {
+          let changed_at = DateTime::from_timestamp_millis(message_id.timestamp_ms as i64)
+            .unwrap_or_else(Utc::now);
+          row_changes.extend(DatabaseRowCellChange::diff(
+            before,
+            &cells,
+            Some(client.uid),
+            changed_at,
+          ));
+        }
+        row_cells = Some(cells);
+      }
       last_message_id = Some(message_id); //TODO: shouldn't this happen before decoding?
       self.metrics.apply_update_count.inc();
     }
@@ -1066,6 +1674,7 @@ impl CollabPersister {
         Ok(Some(CollabSnapshot {
           collab,
           last_message_id,
+          row_changes,
         }))
       },
       None => Ok(None),
@@ -1080,7 +1689,9 @@ impl CollabPersister {
         // non-nil message_id means that we had to update the most recent collab state snapshot
         // with new updates from Redis. This means that our snapshot state is newer than the last
         // persisted one in the database
-        self.save_attempt(&mut snapshot.collab, message_id).await?;
+        self
+          .save_attempt(&mut snapshot.collab, message_id, &snapshot.row_changes)
+          .await?;
       }
     } else {
       tracing::trace!("collab {} state has not changed", self.object_id);
@@ -1096,6 +1707,7 @@ impl CollabPersister {
     &self,
     collab: &mut Collab,
     message_id: MessageId,
+    row_changes: &[DatabaseRowCellChange],
   ) -> Result<(), RealtimeError> {
     // try to acquire snapshot lease - it's possible that multiple web services will try to
     // perform snapshot at the same time, so we'll use lease to let only one of them atm.
@@ -1110,18 +1722,28 @@ impl CollabPersister {
       let light_len = doc_state_light.len();
       self.write_collab(doc_state_light).await?;
 
-      match self.collab_type {
-        CollabType::Document => {
-          let txn = collab.transact();
-          if let Some(text) = DocumentBody::from_collab(collab)
-            .and_then(|body| body.to_plain_text(txn, false, true).ok())
-          {
-            self.index_collab_content(text);
-          }
-        },
-        _ => {
-          // TODO(nathan): support other collab type
-        },
+      // only the server holding the lease records the row activity, the others would replay the
+      // same updates
+      if !row_changes.is_empty() {
+        if let Err(err) = self
+          .storage
+          .record_database_row_activity(&self.workspace_id, &self.object_id, row_changes)
+          .await
+        {
+          warn!(
+            "failed to record {} cell changes of database row {}: {}",
+            row_changes.len(),
+            self.object_id,
+            err
+          );
+        }
+      }
+
+      if let Some(text) = self
+        .indexer_scheduler
+        .extract_text(&self.collab_type, collab)
+      {
+        self.index_collab_content(text);
       }
 
       tracing::debug!(
@@ -1133,8 +1755,9 @@ impl CollabPersister {
 
       // 3. finally we can drop Redis messages
       let now = SystemTime::UNIX_EPOCH.elapsed().unwrap().as_millis();
+      let prune_grace_period = self.settings.borrow().group_prune_grace_period;
       let msg_id = MessageId {
-        timestamp_ms: (now - self.prune_grace_period.as_millis()) as u64,
+        timestamp_ms: (now - prune_grace_period.as_millis()) as u64,
         sequence_number: 0,
       };
       let stream_key = CollabStreamUpdate::stream_key(&self.workspace_id, &self.object_id);
@@ -1149,6 +1772,27 @@ impl CollabPersister {
     Ok(())
   }
 
+  /// Adds the edits received since the last call to the edit statistics of the collab. Every
+  /// collaboration server records the edits it received itself, so they are never counted twice.
+  async fn record_edits(&self) {
+    let counts = match self.pending_edits.lock() {
+      Ok(mut pending_edits) => pending_edits.take(),
+      Err(_) => None,
+    };
+    if let Some(counts) = counts {
+      if let Err(err) = self
+        .storage
+        .record_collab_edits(&self.workspace_id, &self.object_id, &counts)
+        .await
+      {
+        warn!(
+          "failed to record {} edits of collab {}: {}",
+          counts.total_updates, self.object_id, err
+        );
+      }
+    }
+  }
+
   async fn trim_awareness(&self) -> Result<(), RealtimeError> {
     let stream_key = AwarenessStreamUpdate::stream_key(&self.workspace_id, &self.object_id);
     self
@@ -1177,6 +1821,17 @@ impl CollabPersister {
   }
 
   fn index_collab_content(&self, text: String) {
+    let text_hash = {
+      let mut hasher = DefaultHasher::new();
+      text.hash(&mut hasher);
+      hasher.finish()
+    };
+    if let Ok(mut last_indexed_text) = self.last_indexed_text.lock() {
+      if last_indexed_text.replace(text_hash) == Some(text_hash) {
+        trace!("indexed text of collab {} has not changed", self.object_id);
+        return;
+      }
+    }
     if let Ok(workspace_id) = Uuid::parse_str(&self.workspace_id) {
       let indexed_collab = UnindexedCollabTask::new(
         workspace_id,
@@ -1229,4 +1884,13 @@ impl CollabPersister {
 pub struct CollabSnapshot {
   pub collab: Collab,
   pub last_message_id: Option<MessageId>,
+  /// Cell changes of a database row made by the replayed updates.
+  pub row_changes: Vec<DatabaseRowCellChange>,
+}
+
+fn row_cells_of(collab: &Collab) -> HashMap<String, serde_json::Value> {
+  let txn = collab.transact();
+  database_row_data(collab, &txn)
+    .map(|row_data| database_row_cells(&row_data, &txn))
+    .unwrap_or_default()
 }
@@ -2,6 +2,7 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use access_control::collab::RealtimeAccessControl;
+use access_control::restricted_view::RestrictedViewCache;
 use app_error::AppError;
 use collab::core::collab::DataSource;
 use collab::core::origin::CollabOrigin;
@@ -13,10 +14,13 @@ use collab_rt_entity::CollabMessage;
 use collab_stream::client::CollabRedisStream;
 use database::collab::{CollabStorage, GetCollabOrigin};
 use database_entity::dto::QueryCollabParams;
+use tokio::sync::watch;
 use tracing::{instrument, trace};
+use uuid::Uuid;
 use yrs::{ReadTxn, StateVector};
 
 use crate::client::client_msg_router::ClientMessageRouter;
+use crate::config::ReloadableSetting;
 use crate::error::RealtimeError;
 use crate::group::group_init::CollabGroup;
 use crate::group::state::GroupManagementState;
@@ -27,10 +31,11 @@ pub struct GroupManager<S> {
   state: GroupManagementState,
   storage: Arc<S>,
   access_control: Arc<dyn RealtimeAccessControl>,
+  restricted_view_cache: RestrictedViewCache,
   metrics_calculate: Arc<CollabRealtimeMetrics>,
   collab_redis_stream: Arc<CollabRedisStream>,
-  persistence_interval: Duration,
-  prune_grace_period: Duration,
+  settings: watch::Receiver<ReloadableSetting>,
+  broadcast_batch_window: Duration,
   indexer_scheduler: Arc<IndexerScheduler>,
 }
 
@@ -42,10 +47,11 @@ where
   pub async fn new(
     storage: Arc<S>,
     access_control: Arc<dyn RealtimeAccessControl>,
+    restricted_view_cache: RestrictedViewCache,
     metrics_calculate: Arc<CollabRealtimeMetrics>,
     collab_stream: CollabRedisStream,
-    persistence_interval: Duration,
-    prune_grace_period: Duration,
+    settings: watch::Receiver<ReloadableSetting>,
+    broadcast_batch_window: Duration,
     indexer_scheduler: Arc<IndexerScheduler>,
   ) -> Result<Self, RealtimeError> {
     let collab_stream = Arc::new(collab_stream);
@@ -53,10 +59,11 @@ where
       state: GroupManagementState::new(metrics_calculate.clone()),
       storage,
       access_control,
+      restricted_view_cache,
       metrics_calculate,
       collab_redis_stream: collab_stream,
-      persistence_interval,
-      prune_grace_period,
+      settings,
+      broadcast_batch_window,
       indexer_scheduler,
     })
   }
@@ -69,6 +76,12 @@ where
     self.state.contains_user(object_id, user)
   }
 
+  /// Unsubscribes all the connections of the user from the groups of the workspace. Returns the
+  /// number of groups the user was removed from.
+  pub fn remove_user_from_workspace(&self, workspace_id: &str, uid: i64) -> usize {
+    self.state.remove_user_from_workspace(workspace_id, uid)
+  }
+
   pub fn remove_user(&self, user: &RealtimeUser) {
     self.state.remove_user(user);
   }
@@ -88,6 +101,12 @@ where
     message_origin: &CollabOrigin,
     client_msg_router: &mut ClientMessageRouter,
   ) -> Result<(), RealtimeError> {
+    if let Some(group) = self.state.get_group(object_id).await {
+      self
+        .enforce_view_access(user.uid, group.workspace_id(), object_id)
+        .await?;
+    }
+
     // Lock the group and subscribe the user to the group.
     if let Some(mut e) = self.state.get_mut_group(object_id).await {
       let group = e.value_mut();
@@ -118,6 +137,9 @@ where
     object_id: &str,
     collab_type: CollabType,
   ) -> Result<(), RealtimeError> {
+    self
+      .enforce_view_access(user.uid, workspace_id, object_id)
+      .await?;
     let params = QueryCollabParams::new(object_id, collab_type.clone(), workspace_id);
     let res = self
       .storage
@@ -153,14 +175,35 @@ where
       self.metrics_calculate.clone(),
       self.storage.clone(),
       self.collab_redis_stream.clone(),
-      self.persistence_interval,
-      self.prune_grace_period,
+      self.settings.clone(),
+      self.broadcast_batch_window,
       state_vector,
       self.indexer_scheduler.clone(),
     )?;
     self.state.insert_group(object_id, group);
     Ok(())
   }
+
+  /// Returns [RealtimeError::NotEnoughPermissionToRead] if the collab is hidden from the user by
+  /// a view of the folder that is restricted to other members of the workspace.
+  async fn enforce_view_access(
+    &self,
+    uid: i64,
+    workspace_id: &str,
+    object_id: &str,
+  ) -> Result<(), RealtimeError> {
+    let workspace_uuid =
+      Uuid::parse_str(workspace_id).map_err(|err| RealtimeError::Internal(err.into()))?;
+    let can_access = self
+      .restricted_view_cache
+      .can_access_object(uid, &workspace_uuid, object_id)
+      .await
+      .map_err(|err| RealtimeError::Internal(err.into()))?;
+    if !can_access {
+      return Err(RealtimeError::NotEnoughPermissionToRead(uid));
+    }
+    Ok(())
+  }
 }
 
 #[allow(dead_code)]
@@ -1,6 +1,10 @@
+mod awareness_gc;
+mod cell_lock;
 pub(crate) mod cmd;
+mod dedup;
 pub(crate) mod group_init;
 pub(crate) mod manager;
 mod null_sender;
+pub(crate) mod observer;
 mod plugin;
 mod state;
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+
+use collab_rt_entity::user::RealtimeUser;
+use collab_rt_entity::{AckCode, BroadcastSync, RealtimeMessage, ServerCollabMessage};
+use collab_rt_protocol::{Message, SyncMessage};
+use collab_stream::pubsub::CollabObserveSub;
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use futures_util::StreamExt;
+use tokio::task::JoinHandle;
+use tracing::{error, trace, warn};
+
+use crate::{CollabRealtimeMetrics, RealtimeClientWebsocketSink};
+
+/// Keeps track of the read-only observers of the collabs of this instance.
+///
+/// Unlike the subscribers of a [crate::group::group_init::CollabGroup], observers don't go through
+/// a permission filtered sink per user: their read permission is checked once when they start
+/// observing, and the updates of a collab are received from a single Redis pub/sub subscription
+/// that is shared by all the observers of that collab on this instance.
+pub(crate) struct ObserverHub {
+  redis_client: redis::Client,
+  collabs: Arc<DashMap<String, ObservedCollab>>,
+  max_observers_per_group: usize,
+  metrics: Arc<CollabRealtimeMetrics>,
+}
+
+struct ObservedCollab {
+  workspace_id: String,
+  observers: HashMap<RealtimeUser, Arc<dyn RealtimeClientWebsocketSink>>,
+  fan_out: JoinHandle<()>,
+}
+
+impl Drop for ObservedCollab {
+  fn drop(&mut self) {
+    self.fan_out.abort();
+  }
+}
+
+impl ObserverHub {
+  pub fn new(
+    redis_client: redis::Client,
+    max_observers_per_group: usize,
+    metrics: Arc<CollabRealtimeMetrics>,
+  ) -> Self {
+    Self {
+      redis_client,
+      collabs: Arc::new(DashMap::new()),
+      max_observers_per_group,
+      metrics,
+    }
+  }
+
+  /// Starts forwarding the updates of the given collab to the user. Returns the [AckCode] to send
+  /// back to the user when the collab already reached the maximum number of observers.
+  pub fn add_observer(
+    &self,
+    workspace_id: &str,
+    object_id: &str,
+    user: RealtimeUser,
+    sink: Arc<dyn RealtimeClientWebsocketSink>,
+  ) -> Result<(), AckCode> {
+    match self.collabs.entry(object_id.to_string()) {
+      Entry::Occupied(mut entry) => {
+        let collab = entry.get_mut();
+        if !collab.observers.contains_key(&user)
+          && collab.observers.len() >= self.max_observers_per_group
+        {
+          self.metrics.observer_rejected_count.inc();
+          return Err(AckCode::ObserverLimitReached);
+        }
+        if collab.observers.insert(user, sink).is_none() {
+          self.metrics.observer_count.inc();
+        }
+      },
+      Entry::Vacant(entry) => {
+        if self.max_observers_per_group == 0 {
+          self.metrics.observer_rejected_count.inc();
+          return Err(AckCode::ObserverLimitReached);
+        }
+        let fan_out = spawn_fan_out(
+          self.redis_client.clone(),
+          object_id.to_string(),
+          Arc::downgrade(&self.collabs),
+        );
+        entry.insert(ObservedCollab {
+          workspace_id: workspace_id.to_string(),
+          observers: HashMap::from([(user, sink)]),
+          fan_out,
+        });
+        self.metrics.observer_count.inc();
+      },
+    }
+    Ok(())
+  }
+
+  pub fn remove_observer(&self, object_id: &str, user: &RealtimeUser) {
+    if let Entry::Occupied(mut entry) = self.collabs.entry(object_id.to_string()) {
+      if entry.get_mut().observers.remove(user).is_some() {
+        self.metrics.observer_count.dec();
+      }
+      if entry.get().observers.is_empty() {
+        entry.remove();
+      }
+    }
+  }
+
+  /// Removes the user from all the collabs it observes, when its connection is closed.
+  pub fn remove_user(&self, user: &RealtimeUser) {
+    self.collabs.retain(|_, collab| {
+      if collab.observers.remove(user).is_some() {
+        self.metrics.observer_count.dec();
+      }
+      !collab.observers.is_empty()
+    });
+  }
+
+  /// Removes all the connections of the user from the collabs it observes in the given workspace.
+  /// Returns the number of collabs the user stopped observing.
+  pub fn remove_user_from_workspace(&self, workspace_id: &str, uid: i64) -> usize {
+    let mut removed = 0;
+    self.collabs.retain(|_, collab| {
+      if collab.workspace_id == workspace_id {
+        let before = collab.observers.len();
+        collab.observers.retain(|user, _| user.uid != uid);
+        let count = before - collab.observers.len();
+        if count > 0 {
+          removed += 1;
+          self.metrics.observer_count.dec_by(count as i64);
+        }
+      }
+      !collab.observers.is_empty()
+    });
+    removed
+  }
+}
+
+/// Subscribes to the updates of the collab and broadcasts them to its observers, until the collab
+/// has no observers anymore.
+fn spawn_fan_out(
+  redis_client: redis::Client,
+  object_id: String,
+  collabs: Weak<DashMap<String, ObservedCollab>>,
+) -> JoinHandle<()> {
+  tokio::spawn(async move {
+    #[allow(deprecated)]
+    let conn = match redis_client.get_async_connection().await {
+      Ok(conn) => conn,
+      Err(err) => {
+        error!(
+          "failed to connect observers of {} to redis: {}",
+          object_id, err
+        );
+        return;
+      },
+    };
+    let mut updates = match CollabObserveSub::new(conn).subscribe(&object_id).await {
+      Ok(updates) => updates,
+      Err(err) => {
+        error!("failed to subscribe to updates of {}: {}", object_id, err);
+        return;
+      },
+    };
+
+    let mut seq_num = 0;
+    while let Some(result) = updates.next().await {
+      let update = match result {
+        Ok(update) => update,
+        Err(err) => {
+          warn!("failed to decode observed update of {}: {}", object_id, err);
+          continue;
+        },
+      };
+      let sinks = match collabs.upgrade() {
+        Some(collabs) => match collabs.get(&object_id) {
+          Some(collab) => collab.observers.values().cloned().collect::<Vec<_>>(),
+          None => break,
+        },
+        None => break,
+      };
+
+      seq_num += 1;
+      let payload = Message::Sync(SyncMessage::Update(update.data)).encode_v1();
+      let message = ServerCollabMessage::ServerBroadcast(BroadcastSync::new(
+        update.sender,
+        object_id.clone(),
+        payload,
+        seq_num,
+      ));
+      trace!(
+        "broadcasting observed update of {} to {} observers",
+        object_id,
+        sinks.len()
+      );
+      for sink in sinks {
+        sink.do_send(RealtimeMessage::ServerCollabV1(vec![message.clone()]));
+      }
+    }
+  })
+}
@@ -189,6 +189,34 @@ impl GroupManagementState {
     }
   }
 
+  pub(crate) fn remove_user_from_workspace(&self, workspace_id: &str, uid: i64) -> usize {
+    let mut removed = 0;
+    for mut entry in self.editing_by_user.iter_mut() {
+      if entry.key().uid != uid {
+        continue;
+      }
+      let user = entry.key().clone();
+      entry.value_mut().retain(|editing| {
+        match self.group_by_object_id.try_get(&editing.object_id) {
+          TryResult::Present(group) if group.workspace_id() == workspace_id => {
+            group.remove_user(&user);
+            removed += 1;
+            false
+          },
+          TryResult::Locked => {
+            error!(
+              "Failed to get the group:{}. cause by lock issue",
+              editing.object_id
+            );
+            true
+          },
+          _ => true,
+        }
+      });
+    }
+    removed
+  }
+
   pub fn contains_user(&self, object_id: &str, user: &RealtimeUser) -> bool {
     match self.group_by_object_id.try_get(object_id) {
       TryResult::Present(entry) => entry.value().contains_user(user),
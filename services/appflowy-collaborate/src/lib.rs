@@ -1,4 +1,5 @@
 pub mod actix_ws;
+pub mod alerting;
 pub mod api;
 pub mod application;
 mod client;
@@ -6,6 +7,7 @@ pub mod collab;
 pub mod command;
 pub mod compression;
 pub mod config;
+pub mod config_reload;
 pub mod connect_state;
 pub mod error;
 pub mod group;
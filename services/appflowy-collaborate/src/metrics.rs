@@ -1,9 +1,13 @@
+use std::sync::Arc;
+
 use chrono::Utc;
 use prometheus_client::metrics::counter::Counter;
 use prometheus_client::metrics::gauge::Gauge;
 use prometheus_client::metrics::histogram::Histogram;
 use prometheus_client::registry::Registry;
 
+use crate::alerting::LatencySamples;
+
 #[derive(Clone)]
 pub struct CollabRealtimeMetrics {
   pub(crate) connected_users: Gauge,
@@ -25,6 +29,36 @@ pub struct CollabRealtimeMetrics {
   pub(crate) full_collab_size: Histogram,
   /// How long does it take since collab update is send to a stream to be read from it.
   pub(crate) collab_stream_latency: Histogram,
+  /// How long a websocket connection lived before it was closed, in seconds.
+  pub(crate) connection_age: Histogram,
+  /// Number of websocket connections closed by the server because they stopped answering pings.
+  pub(crate) reaped_connection_count: Counter,
+  /// Number of websocket connections closed by the server because the user opened too many.
+  pub(crate) evicted_session_count: Counter,
+  /// Number of websocket connections that haven't answered a ping within the idle timeout.
+  pub(crate) idle_connection_count: Gauge,
+  /// Number of collabs carried by each batch init sync.
+  pub(crate) batch_init_sync_size: Histogram,
+  /// Number of sync sessions resumed from a client resume token.
+  pub(crate) resume_sync_count: Counter,
+  /// Number of resume syncs that fell back to a full init sync.
+  pub(crate) resume_sync_fallback_count: Counter,
+  /// Number of collab updates merged into a single broadcast.
+  pub(crate) broadcast_batch_size: Histogram,
+  /// Size in bytes of the merged update of a broadcast.
+  pub(crate) broadcast_batch_bytes: Histogram,
+  /// How long the first update of a broadcast batch waited before being broadcast, in milliseconds.
+  pub(crate) broadcast_batch_delay: Histogram,
+  /// Recent broadcast batching delays, used by the alerting to compute percentiles.
+  pub(crate) broadcast_delay_samples: Arc<LatencySamples>,
+  /// Number of read-only observers currently attached to collabs of this instance.
+  pub(crate) observer_count: Gauge,
+  /// Number of observe requests rejected because the collab reached its observer limit.
+  pub(crate) observer_rejected_count: Counter,
+  /// Number of client updates and awareness messages checked against the recently applied ones.
+  pub(crate) dedup_checked_count: Counter,
+  /// Number of client messages acked without being applied because they were retransmissions.
+  pub(crate) duplicate_message_count: Counter,
 }
 
 impl CollabRealtimeMetrics {
@@ -64,6 +98,33 @@ impl CollabRealtimeMetrics {
         ]
         .into_iter(),
       ),
+      // websocket connection age in seconds: 10s, 1m, 5m, 30m, 1h, 6h, 12h, 24h
+      connection_age: Histogram::new(
+        [10.0, 60.0, 300.0, 1800.0, 3600.0, 21600.0, 43200.0, 86400.0].into_iter(),
+      ),
+      reaped_connection_count: Default::default(),
+      evicted_session_count: Default::default(),
+      idle_connection_count: Default::default(),
+      // number of collabs per batch init sync: 1, 5, 10, 20, 50, 100
+      batch_init_sync_size: Histogram::new([1.0, 5.0, 10.0, 20.0, 50.0, 100.0].into_iter()),
+      resume_sync_count: Default::default(),
+      resume_sync_fallback_count: Default::default(),
+      // number of updates per broadcast: 1, 2, 5, 10, 20, 50, 100
+      broadcast_batch_size: Histogram::new([1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0].into_iter()),
+      // merged update size in bytes: 64B, 256B, 1KB, 4KB, 16KB, 64KB, 256KB, 1MB
+      broadcast_batch_bytes: Histogram::new(
+        [
+          64.0, 256.0, 1024.0, 4096.0, 16384.0, 65536.0, 262144.0, 1048576.0,
+        ]
+        .into_iter(),
+      ),
+      // batching delay in milliseconds: 1ms, 5ms, 10ms, 20ms, 50ms, 100ms, 500ms
+      broadcast_batch_delay: Histogram::new([1.0, 5.0, 10.0, 20.0, 50.0, 100.0, 500.0].into_iter()),
+      broadcast_delay_samples: Arc::new(LatencySamples::default()),
+      observer_count: Default::default(),
+      observer_rejected_count: Default::default(),
+      dedup_checked_count: Default::default(),
+      duplicate_message_count: Default::default(),
       load_collab_count: Default::default(),
       load_full_collab_count: Default::default(),
     }
@@ -127,9 +188,84 @@ impl CollabRealtimeMetrics {
       "latency since collab update is send to a stream to be read from it",
       metrics.collab_stream_latency.clone(),
     );
+    realtime_registry.register(
+      "connection_age",
+      "lifetime of websocket connections in seconds",
+      metrics.connection_age.clone(),
+    );
+    realtime_registry.register(
+      "reaped_connection_count",
+      "number of idle websocket connections closed by the server",
+      metrics.reaped_connection_count.clone(),
+    );
+    realtime_registry.register(
+      "evicted_session_count",
+      "number of websocket connections closed because the user exceeded the session limit",
+      metrics.evicted_session_count.clone(),
+    );
+    realtime_registry.register(
+      "idle_connection_count",
+      "number of websocket connections exceeding the idle timeout",
+      metrics.idle_connection_count.clone(),
+    );
+    realtime_registry.register(
+      "batch_init_sync_size",
+      "number of collabs carried by a batch init sync",
+      metrics.batch_init_sync_size.clone(),
+    );
+    realtime_registry.register(
+      "resume_sync_count",
+      "number of sync sessions resumed from a resume token",
+      metrics.resume_sync_count.clone(),
+    );
+    realtime_registry.register(
+      "resume_sync_fallback_count",
+      "number of resume syncs that fell back to a full init sync",
+      metrics.resume_sync_fallback_count.clone(),
+    );
+    realtime_registry.register(
+      "broadcast_batch_size",
+      "number of collab updates merged into a single broadcast",
+      metrics.broadcast_batch_size.clone(),
+    );
+    realtime_registry.register(
+      "broadcast_batch_bytes",
+      "size of the merged update of a broadcast in bytes",
+      metrics.broadcast_batch_bytes.clone(),
+    );
+    realtime_registry.register(
+      "broadcast_batch_delay",
+      "time spent by an update in the broadcast batching window in milliseconds",
+      metrics.broadcast_batch_delay.clone(),
+    );
+    realtime_registry.register(
+      "observer_count",
+      "number of read-only observers attached to collabs",
+      metrics.observer_count.clone(),
+    );
+    realtime_registry.register(
+      "observer_rejected_count",
+      "number of observe requests rejected by the per collab observer limit",
+      metrics.observer_rejected_count.clone(),
+    );
+    realtime_registry.register(
+      "dedup_checked_count",
+      "number of client updates and awareness messages checked for retransmissions",
+      metrics.dedup_checked_count.clone(),
+    );
+    realtime_registry.register(
+      "duplicate_message_count",
+      "number of retransmitted client messages acked without being applied",
+      metrics.duplicate_message_count.clone(),
+    );
     metrics
   }
 
+  pub fn observe_broadcast_batch_delay(&self, millis: f64) {
+    self.broadcast_batch_delay.observe(millis);
+    self.broadcast_delay_samples.push(millis);
+  }
+
   pub fn observe_collab_stream_latency(&self, message_id_timestamp: u64) {
     let now = Utc::now().timestamp_millis() as u64;
     if now > message_id_timestamp {
@@ -151,7 +287,11 @@ pub struct CollabMetrics {
   pub pg_read_collab_count: Counter,
   pub s3_read_collab_count: Counter,
   pub redis_read_collab_count: Counter,
+  /// Number of collab reads that weren't found in Redis and fell back to the disk cache.
+  pub redis_miss_collab_count: Counter,
   pub success_queue_collab_count: Counter,
+  /// Number of archived collabs restored from the object storage on first access.
+  pub rehydrate_collab_count: Counter,
   pg_tx_collab_millis: Histogram,
 }
 
@@ -204,11 +344,21 @@ impl CollabMetrics {
       "success read collabs from Redis",
       metrics.redis_read_collab_count.clone(),
     );
+    realtime_registry.register(
+      "redis_miss_collab_count",
+      "collab reads not found in Redis",
+      metrics.redis_miss_collab_count.clone(),
+    );
     realtime_registry.register(
       "success_queue_collab_count",
       "success queue collab",
       metrics.success_queue_collab_count.clone(),
     );
+    realtime_registry.register(
+      "rehydrate_collab_count",
+      "archived collabs restored on first access",
+      metrics.rehydrate_collab_count.clone(),
+    );
     realtime_registry.register(
       "pg_tx_collab_millis",
       "total time (in milliseconds) spend in transaction writing collab to postgres",
@@ -237,7 +387,9 @@ impl Default for CollabMetrics {
       pg_read_collab_count: Default::default(),
       s3_read_collab_count: Default::default(),
       redis_read_collab_count: Default::default(),
+      redis_miss_collab_count: Default::default(),
       success_queue_collab_count: Default::default(),
+      rehydrate_collab_count: Default::default(),
       pg_tx_collab_millis: Histogram::new(
         [
           100.0, 300.0, 500.0, 1000.0, 2000.0, 5000.0, 10000.0, 30000.0, 60000.0,
@@ -1,16 +1,28 @@
 use anyhow::Error;
 use database::listener::PostgresDBListener;
-use database::pg_row::AFUserNotification;
+use database::pg_row::{AFUserNotification, AFWorkspaceMemberNotification};
 use sqlx::PgPool;
 
 pub struct PgListeners {
   user_listener: UserListener,
+  workspace_member_listener: WorkspaceMemberListener,
 }
 
 impl PgListeners {
   pub async fn new(pg_pool: &PgPool) -> Result<Self, Error> {
     let user_listener = UserListener::new(pg_pool, "af_user_channel").await?;
-    Ok(Self { user_listener })
+    let workspace_member_listener =
+      WorkspaceMemberListener::new(pg_pool, "af_workspace_member_channel").await?;
+    Ok(Self {
+      user_listener,
+      workspace_member_listener,
+    })
+  }
+
+  pub fn subscribe_workspace_member_change(
+    &self,
+  ) -> tokio::sync::broadcast::Receiver<AFWorkspaceMemberNotification> {
+    self.workspace_member_listener.notify.subscribe()
   }
 
   pub fn subscribe_user_change(&self, uid: i64) -> tokio::sync::mpsc::Receiver<AFUserNotification> {
@@ -30,5 +42,5 @@ impl PgListeners {
 }
 
 // pub type CollabMemberListener = PostgresDBListener<CollabMemberNotification>;
-// pub type WorkspaceMemberListener = PostgresDBListener<WorkspaceMemberNotification>;
 pub type UserListener = PostgresDBListener<AFUserNotification>;
+pub type WorkspaceMemberListener = PostgresDBListener<AFWorkspaceMemberNotification>;
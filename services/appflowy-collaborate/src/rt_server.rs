@@ -2,16 +2,24 @@ use std::sync::{Arc, Weak};
 use std::time::Duration;
 
 use access_control::collab::RealtimeAccessControl;
+use access_control::restricted_view::RestrictedViewCache;
 use anyhow::{anyhow, Result};
 use app_error::AppError;
+use collab::core::origin::{CollabClient, CollabOrigin};
 use collab_rt_entity::user::{RealtimeUser, UserDevice};
-use collab_rt_entity::MessageByObjectId;
+use collab_rt_entity::{
+  AckCode, BatchInitSync, CollabAck, MessageByObjectId, ObserveCollab, RealtimeMessage,
+  ServerCollabMessage,
+};
 use collab_stream::client::CollabRedisStream;
+use collab_stream::model::AwarenessStreamConfig;
 use collab_stream::stream_router::StreamRouter;
 use dashmap::mapref::entry::Entry;
 use dashmap::DashMap;
+use futures_util::future::join_all;
 use redis::aio::ConnectionManager;
 use tokio::sync::mpsc::Sender;
+use tokio::sync::watch;
 use tokio::task::yield_now;
 use tokio::time::interval;
 use tracing::{error, info, trace, warn};
@@ -20,16 +28,19 @@ use yrs::StateVector;
 
 use crate::client::client_msg_router::ClientMessageRouter;
 use crate::command::{spawn_collaboration_command, CLCommandReceiver};
-use crate::config::get_env_var;
-use crate::connect_state::ConnectState;
+use crate::config::{get_env_var, ReloadableSetting};
+use crate::connect_state::{ConnectState, ConnectionHeartbeat};
 use crate::error::{CreateGroupFailedReason, RealtimeError};
 use crate::group::cmd::{GroupCommand, GroupCommandRunner, GroupCommandSender};
 use crate::group::manager::GroupManager;
+use crate::group::observer::ObserverHub;
 use crate::rt_server::collaboration_runtime::COLLAB_RUNTIME;
 use database::collab::CollabStorage;
 use indexer::scheduler::IndexerScheduler;
 
-use crate::actix_ws::entities::{ClientGenerateEmbeddingMessage, ClientHttpUpdateMessage};
+use crate::actix_ws::entities::{
+  ClientGenerateEmbeddingMessage, ClientHttpUpdateMessage, CollabLockChangedMessage,
+};
 use crate::{CollabRealtimeMetrics, RealtimeClientWebsocketSink};
 
 #[derive(Clone)]
@@ -38,7 +49,9 @@ pub struct CollaborationServer<S> {
   group_manager: Arc<GroupManager<S>>,
   connect_state: ConnectState,
   group_sender_by_object_id: Arc<DashMap<String, GroupCommandSender>>,
-  #[allow(dead_code)]
+  /// Read-only observers, which don't join the collab groups.
+  observer_hub: Arc<ObserverHub>,
+  access_control: Arc<dyn RealtimeAccessControl>,
   metrics: Arc<CollabRealtimeMetrics>,
   enable_custom_runtime: bool,
 }
@@ -51,12 +64,13 @@ where
   pub async fn new(
     storage: Arc<S>,
     access_control: Arc<dyn RealtimeAccessControl>,
+    restricted_view_cache: RestrictedViewCache,
     metrics: Arc<CollabRealtimeMetrics>,
     command_recv: CLCommandReceiver,
+    redis_client: redis::Client,
     redis_stream_router: Arc<StreamRouter>,
     redis_connection_manager: ConnectionManager,
-    group_persistence_interval: Duration,
-    prune_grace_period: Duration,
+    settings: watch::Receiver<ReloadableSetting>,
     indexer_scheduler: Arc<IndexerScheduler>,
   ) -> Result<Self, RealtimeError> {
     let enable_custom_runtime = get_env_var("APPFLOWY_COLLABORATE_MULTI_THREAD", "false")
@@ -71,15 +85,17 @@ where
 
     let connect_state = ConnectState::new();
     let collab_stream =
-      CollabRedisStream::new_with_connection_manager(redis_connection_manager, redis_stream_router);
+      CollabRedisStream::new_with_connection_manager(redis_connection_manager, redis_stream_router)
+        .with_awareness_config(awareness_stream_config());
     let group_manager = Arc::new(
       GroupManager::new(
         storage.clone(),
         access_control.clone(),
+        restricted_view_cache,
         metrics.clone(),
         collab_stream,
-        group_persistence_interval,
-        prune_grace_period,
+        settings,
+        broadcast_batch_window(),
         indexer_scheduler.clone(),
       )
       .await?,
@@ -87,18 +103,39 @@ where
     let group_sender_by_object_id: Arc<DashMap<String, GroupCommandSender>> =
       Arc::new(Default::default());
 
+    let observer_hub = Arc::new(ObserverHub::new(
+      redis_client,
+      max_observers_per_group(),
+      metrics.clone(),
+    ));
+
     spawn_period_check_inactive_group(Arc::downgrade(&group_manager), &group_sender_by_object_id);
 
+    let idle_timeout = get_env_var("APPFLOWY_WEBSOCKET_IDLE_TIMEOUT", "120")
+      .parse::<u64>()
+      .map(Duration::from_secs)
+      .unwrap_or(Duration::from_secs(120));
+    spawn_period_reap_idle_connections(
+      Arc::downgrade(&group_manager),
+      connect_state.clone(),
+      metrics.clone(),
+      idle_timeout,
+    );
+
     spawn_collaboration_command(
       command_recv,
       &group_sender_by_object_id,
       Arc::downgrade(&group_manager),
+      observer_hub.clone(),
+      connect_state.clone(),
     );
 
     Ok(Self {
       group_manager,
       connect_state,
       group_sender_by_object_id,
+      observer_hub,
+      access_control,
       metrics,
       enable_custom_runtime,
     })
@@ -110,19 +147,34 @@ where
   /// - Replaces any existing user connection with the new one, signaling the old connection
   ///   if it's replaced.
   /// - Removes the old user connection from all collaboration groups.
+  /// - Disconnects the oldest sessions of the user when it has more than `max_sessions`.
   ///
   pub fn handle_new_connection(
     &self,
     connected_user: RealtimeUser,
     conn_sink: impl RealtimeClientWebsocketSink,
+    heartbeat: ConnectionHeartbeat,
+    max_sessions: usize,
   ) -> Result<(), RealtimeError> {
     let new_client_router = ClientMessageRouter::new(conn_sink);
     if let Some(old_user) = self
       .connect_state
-      .handle_user_connect(connected_user, new_client_router)
+      .handle_user_connect(connected_user.clone(), new_client_router)
     {
       // Remove the old user from all collaboration groups.
       self.group_manager.remove_user(&old_user);
+      self.observer_hub.remove_user(&old_user);
+    }
+    self
+      .connect_state
+      .track_heartbeat(&connected_user, heartbeat);
+    for evicted_user in self
+      .connect_state
+      .evict_oldest_sessions(&connected_user, max_sessions)
+    {
+      self.group_manager.remove_user(&evicted_user);
+      self.observer_hub.remove_user(&evicted_user);
+      self.metrics.evicted_session_count.inc();
     }
     self
       .metrics
@@ -142,12 +194,19 @@ where
     trace!("[realtime]: disconnect => {}", disconnect_user);
     let was_removed = self.connect_state.handle_user_disconnect(&disconnect_user);
     if was_removed.is_some() {
+      if let Some(heartbeat) = self.connect_state.remove_heartbeat(&disconnect_user) {
+        self
+          .metrics
+          .connection_age
+          .observe(heartbeat.age().as_secs_f64());
+      }
       self
         .metrics
         .connected_users
         .set(self.connect_state.number_of_connected_users() as i64);
 
       self.group_manager.remove_user(&disconnect_user);
+      self.observer_hub.remove_user(&disconnect_user);
     }
 
     Ok(())
@@ -201,6 +260,180 @@ where
     Ok(())
   }
 
+  /// Handles the init syncs of multiple collabs sent in a single [BatchInitSync]. The write
+  /// permission is checked once for the whole batch, each collab is initialized by its own group
+  /// command runner concurrently, and all the responses are sent back in a single message.
+  pub fn handle_client_batch_init_sync(
+    &self,
+    user: RealtimeUser,
+    batch: BatchInitSync,
+  ) -> Result<(), RealtimeError> {
+    if batch.is_empty() {
+      return Ok(());
+    }
+    let sink = match self.connect_state.client_message_routers.get(&user) {
+      Some(router) => router.sink.clone(),
+      None => {
+        trace!(
+          "The client stream: {} is not found, skip batch init sync",
+          user
+        );
+        return Ok(());
+      },
+    };
+    self
+      .metrics
+      .batch_init_sync_size
+      .observe(batch.len() as f64);
+
+    let BatchInitSync {
+      workspace_id,
+      messages,
+    } = batch;
+    let (messages, invalid_messages): (Vec<_>, Vec<_>) = messages.into_iter().partition(|msg| {
+      msg
+        .init_sync_target()
+        .is_some_and(|(msg_workspace_id, _)| msg_workspace_id == workspace_id)
+    });
+    if !invalid_messages.is_empty() {
+      warn!(
+        "{} sent {} messages that are not init syncs of workspace {} in a batch init sync",
+        user,
+        invalid_messages.len(),
+        workspace_id
+      );
+    }
+    let mut responses = invalid_messages
+      .iter()
+      .map(|msg| reject_init_sync(msg, AckCode::Internal))
+      .collect::<Vec<_>>();
+
+    let senders = messages
+      .iter()
+      .map(|msg| self.create_group_if_not_exist(msg.object_id()))
+      .collect::<Vec<_>>();
+    let access_control = self.access_control.clone();
+    tokio::spawn(async move {
+      let object_ids = messages
+        .iter()
+        .map(|msg| msg.object_id().to_string())
+        .collect::<Vec<_>>();
+      let denied_code = if access_control
+        .is_workspace_read_only(&workspace_id)
+        .await
+        .unwrap_or(false)
+      {
+        AckCode::WorkspaceReadOnly
+      } else {
+        AckCode::PermissionDenied
+      };
+      let permissions = access_control
+        .can_write_collabs(&workspace_id, &user.uid, &object_ids)
+        .await
+        .unwrap_or_else(|err| {
+          error!("failed to check batch init sync permission: {}", err);
+          vec![false; object_ids.len()]
+        });
+
+      let tasks =
+        messages
+          .into_iter()
+          .zip(senders)
+          .zip(permissions)
+          .map(|((message, sender), can_write)| {
+            let user = user.clone();
+            let denied_code = denied_code.clone();
+            async move {
+              if !can_write {
+                return vec![reject_init_sync(&message, denied_code)];
+              }
+              let rejected = reject_init_sync(&message, AckCode::Internal);
+              let (tx, rx) = tokio::sync::oneshot::channel();
+              let command = GroupCommand::HandleClientInitSync {
+                user,
+                message,
+                ret: tx,
+              };
+              if let Err(err) = sender.send(command).await {
+                error!("Send batch init sync to group fail: {}", err);
+                return vec![rejected];
+              }
+              match rx.await {
+                Ok(Ok(responses)) => responses,
+                Ok(Err(err)) => {
+                  trace!(
+                    "batch init sync of {} failed: {}",
+                    rejected.object_id(),
+                    err
+                  );
+                  vec![rejected]
+                },
+                Err(_) => vec![rejected],
+              }
+            }
+          });
+      responses.extend(join_all(tasks).await.into_iter().flatten());
+      sink.do_send(RealtimeMessage::ServerCollabV1(responses));
+    });
+    Ok(())
+  }
+
+  /// Starts or stops forwarding the updates of a collab to a read-only observer. The read
+  /// permission is only checked when the observer starts observing the collab.
+  pub fn handle_client_observe(
+    &self,
+    user: RealtimeUser,
+    observe: ObserveCollab,
+  ) -> Result<(), RealtimeError> {
+    let sink = match self.connect_state.client_message_routers.get(&user) {
+      Some(router) => router.sink.clone(),
+      None => {
+        trace!("The client stream: {} is not found, skip observe", user);
+        return Ok(());
+      },
+    };
+    let ObserveCollab {
+      workspace_id,
+      object_id,
+      msg_id,
+      observe,
+    } = observe;
+    let origin = CollabOrigin::Client(CollabClient::new(user.uid, user.device_id.clone()));
+    if !observe {
+      self.observer_hub.remove_observer(&object_id, &user);
+      let ack = CollabAck::new(origin, object_id, msg_id, 0);
+      sink.do_send(RealtimeMessage::ServerCollabV1(vec![
+        ServerCollabMessage::ClientAck(ack),
+      ]));
+      return Ok(());
+    }
+
+    let access_control = self.access_control.clone();
+    let observer_hub = self.observer_hub.clone();
+    tokio::spawn(async move {
+      let can_read = access_control
+        .can_read_collab(&workspace_id, &user.uid, &object_id)
+        .await
+        .unwrap_or_else(|err| {
+          error!("failed to check observe permission: {}", err);
+          false
+        });
+      let code = if can_read {
+        match observer_hub.add_observer(&workspace_id, &object_id, user, sink.clone()) {
+          Ok(()) => AckCode::Success,
+          Err(code) => code,
+        }
+      } else {
+        AckCode::PermissionDenied
+      };
+      let ack = CollabAck::new(origin, object_id, msg_id, 0).with_code(code);
+      sink.do_send(RealtimeMessage::ServerCollabV1(vec![
+        ServerCollabMessage::ClientAck(ack),
+      ]));
+    });
+    Ok(())
+  }
+
   #[inline]
   pub fn handle_client_http_update(
     &self,
@@ -372,6 +605,24 @@ where
     Ok(())
   }
 
+  /// Forwards the lock state to the group of the collab. Nothing needs to be sent when the
+  /// collab is not open, clients will be rejected when they try to write to it.
+  pub fn handle_collab_lock_changed(&self, message: CollabLockChangedMessage) {
+    let message = message.message;
+    let group_cmd_sender = match self.group_sender_by_object_id.get(&message.object_id) {
+      Some(sender) => sender.clone(),
+      None => return,
+    };
+    tokio::spawn(async move {
+      if let Err(err) = group_cmd_sender
+        .send(GroupCommand::BroadcastCollabLock { message })
+        .await
+      {
+        error!("send collab lock to group fail: {}", err);
+      }
+    });
+  }
+
   pub fn get_user_by_device(&self, user_device: &UserDevice) -> Option<RealtimeUser> {
     self
       .connect_state
@@ -381,6 +632,63 @@ where
   }
 }
 
+/// Ack for an init sync of a [BatchInitSync] that couldn't be handled.
+fn reject_init_sync(
+  message: &collab_rt_entity::ClientCollabMessage,
+  code: AckCode,
+) -> ServerCollabMessage {
+  let ack = CollabAck::new(
+    message.origin().clone(),
+    message.object_id().to_string(),
+    message.msg_id(),
+    0,
+  )
+  .with_code(code);
+  ServerCollabMessage::ClientAck(ack)
+}
+
+/// Maximum number of read-only observers of a single collab on this instance.
+fn max_observers_per_group() -> usize {
+  get_env_var("APPFLOWY_COLLABORATE_MAX_OBSERVERS_PER_GROUP", "500")
+    .parse::<usize>()
+    .unwrap_or(500)
+}
+
+/// Window during which consecutive collab updates from the same sender are merged before being
+/// broadcast. Zero disables the batching.
+fn broadcast_batch_window() -> Duration {
+  get_env_var("APPFLOWY_COLLABORATE_BROADCAST_BATCH_WINDOW_MS", "30")
+    .parse::<u64>()
+    .map(Duration::from_millis)
+    .unwrap_or(Duration::from_millis(30))
+}
+
+/// Bounds of the awareness replay window, see [AwarenessStreamConfig].
+fn awareness_stream_config() -> AwarenessStreamConfig {
+  let default = AwarenessStreamConfig::default();
+  AwarenessStreamConfig {
+    max_len: get_env_var(
+      "APPFLOWY_COLLABORATE_AWARENESS_STREAM_MAX_LEN",
+      &default.max_len.to_string(),
+    )
+    .parse::<usize>()
+    .unwrap_or(default.max_len),
+    replay_window: get_env_var(
+      "APPFLOWY_COLLABORATE_AWARENESS_REPLAY_WINDOW_SECS",
+      &default.replay_window.as_secs().to_string(),
+    )
+    .parse::<u64>()
+    .map(Duration::from_secs)
+    .unwrap_or(default.replay_window),
+    replay_count: get_env_var(
+      "APPFLOWY_COLLABORATE_AWARENESS_REPLAY_COUNT",
+      &default.replay_count.to_string(),
+    )
+    .parse::<usize>()
+    .unwrap_or(default.replay_count),
+  }
+}
+
 fn spawn_period_check_inactive_group<S>(
   weak_groups: Weak<GroupManager<S>>,
   group_sender_by_object_id: &Arc<DashMap<String, GroupCommandSender>>,
@@ -408,6 +716,57 @@ fn spawn_period_check_inactive_group<S>(
   });
 }
 
+/// Periodically closes websocket connections that stopped answering pings within `idle_timeout`.
+///
+/// The websocket actor normally disconnects itself when the client times out, but an actor whose
+/// socket is stuck never runs that check. Reaping the connection on the server side removes the
+/// user from every collab group it subscribed to and signals the actor to close the socket.
+fn spawn_period_reap_idle_connections<S>(
+  weak_groups: Weak<GroupManager<S>>,
+  connect_state: ConnectState,
+  metrics: Arc<CollabRealtimeMetrics>,
+  idle_timeout: Duration,
+) where
+  S: CollabStorage,
+{
+  let mut interval = interval(Duration::from_secs(30));
+  tokio::spawn(async move {
+    loop {
+      interval.tick().await;
+      let groups = match weak_groups.upgrade() {
+        Some(groups) => groups,
+        None => break,
+      };
+
+      let idle_connections = connect_state.idle_connections(idle_timeout);
+      metrics
+        .idle_connection_count
+        .set(idle_connections.len() as i64);
+
+      for (user, heartbeat) in idle_connections {
+        warn!(
+          "[realtime]: reap idle connection {}, idle for {} secs",
+          user,
+          heartbeat.idle_duration().as_secs()
+        );
+        heartbeat.close();
+        if connect_state.handle_user_disconnect(&user).is_some() {
+          groups.remove_user(&user);
+        }
+        connect_state.remove_heartbeat(&user);
+        metrics.reaped_connection_count.inc();
+        metrics
+          .connection_age
+          .observe(heartbeat.age().as_secs_f64());
+      }
+
+      metrics
+        .connected_users
+        .set(connect_state.number_of_connected_users() as i64);
+    }
+  });
+}
+
 /// When the CollaborationServer operates within an actix-web actor, utilizing tokio::spawn for
 /// task execution confines all tasks to the same thread, attributable to the actor's reliance on a
 /// single-threaded Tokio runtime. To circumvent this limitation and enable task execution across
@@ -6,18 +6,24 @@ use collab::entity::{EncodedCollab, EncoderVersion};
 use collab_entity::CollabType;
 use sqlx::PgPool;
 use tracing::{debug, error, trace, warn};
+use uuid::Uuid;
 use validator::Validate;
 
 use app_error::AppError;
 use database::collab::{
-  get_all_collab_snapshot_meta, latest_snapshot_time, select_snapshot, AppResult,
+  collab_snapshot_storage_key, delete_collab_checkpoint, delete_collab_snapshots_over_limit,
+  get_all_collab_snapshot_meta, insert_collab_checkpoint, insert_collab_snapshot_record,
+  latest_snapshot_time, next_collab_snapshot_id, select_collab_checkpoint,
+  select_collab_checkpoints, select_latest_snapshot, select_snapshot, AppResult,
   COLLAB_SNAPSHOT_LIMIT, SNAPSHOT_PER_HOUR,
 };
-use database::file::s3_client_impl::AwsS3BucketClientImpl;
+use database::file::BlobStorageClient;
 use database::file::{BucketClient, ResponseBlob};
 use database::history::ops::get_latest_snapshot;
+use database::pg_row::AFSnapshotRow;
 use database_entity::dto::{
-  AFSnapshotMeta, AFSnapshotMetas, InsertSnapshotParams, SnapshotData, ZSTD_COMPRESSION_LEVEL,
+  AFCollabCheckpoint, AFCollabHistory, AFSnapshotMeta, AFSnapshotMetas, InsertCheckpointParams,
+  InsertSnapshotParams, SnapshotData, ZSTD_COMPRESSION_LEVEL,
 };
 
 use crate::metrics::CollabMetrics;
@@ -36,6 +42,13 @@ fn collab_snapshot_prefix(workspace_id: &str, object_id: &str) -> String {
   format!("collabs/{}/{}/snapshot_", workspace_id, object_id)
 }
 
+fn collab_checkpoint_key(workspace_id: &str, object_id: &str, checkpoint_id: i64) -> String {
+  format!(
+    "collabs/{}/{}/checkpoint_{}.v1.zstd",
+    workspace_id, object_id, checkpoint_id
+  )
+}
+
 fn get_timestamp(object_key: &str) -> Option<DateTime<Utc>> {
   let (_, right) = object_key.rsplit_once('/')?;
   let trimmed = right
@@ -61,23 +74,46 @@ fn get_meta(objct_key: String) -> Option<AFSnapshotMeta> {
   })
 }
 
+fn snapshot_data_from_doc_state(
+  workspace_id: &str,
+  object_id: &str,
+  doc_state: Vec<u8>,
+) -> AppResult<SnapshotData> {
+  let encoded_collab = EncodedCollab {
+    state_vector: Default::default(),
+    doc_state: doc_state.into(),
+    version: EncoderVersion::V1,
+  };
+  Ok(SnapshotData {
+    object_id: object_id.to_string(),
+    encoded_collab_v1: encoded_collab.encode_to_bytes()?,
+    workspace_id: workspace_id.to_string(),
+  })
+}
+
+/// Snapshots are either written to the object storage only, or recorded in Postgres with the blobs
+/// larger than `offload_threshold` offloaded to the object storage. The snapshots written in the
+/// other mode remain readable.
 #[derive(Clone)]
 pub struct SnapshotControl {
   pg_pool: PgPool,
-  s3: AwsS3BucketClientImpl,
+  s3: BlobStorageClient,
   collab_metrics: Arc<CollabMetrics>,
+  offload_threshold: Option<usize>,
 }
 
 impl SnapshotControl {
   pub async fn new(
     pg_pool: PgPool,
-    s3: AwsS3BucketClientImpl,
+    s3: BlobStorageClient,
     collab_metrics: Arc<CollabMetrics>,
+    offload_threshold: Option<usize>,
   ) -> Self {
     Self {
       pg_pool,
       s3,
       collab_metrics,
+      offload_threshold,
     }
   }
 
@@ -117,6 +153,92 @@ impl SnapshotControl {
     debug!("create snapshot for object:{}", params.object_id);
     self.collab_metrics.write_snapshot.inc();
 
+    match self.offload_threshold {
+      Some(threshold) => self.create_snapshot_record(params, threshold).await,
+      None => self.create_snapshot_object(params).await,
+    }
+  }
+
+  /// Records the snapshot in Postgres. Its blob is stored inline unless the encoded collab is
+  /// larger than `threshold`, in which case it's written to the object storage.
+  async fn create_snapshot_record(
+    &self,
+    params: InsertSnapshotParams,
+    threshold: usize,
+  ) -> AppResult<AFSnapshotMeta> {
+    let workspace_id = Uuid::parse_str(&params.workspace_id)?;
+    let encoded_collab_v1 = EncodedCollab {
+      state_vector: Default::default(),
+      doc_state: params.doc_state,
+      version: EncoderVersion::V1,
+    }
+    .encode_to_bytes()?;
+    let sid = next_collab_snapshot_id(&self.pg_pool).await?;
+
+    let storage_key = if encoded_collab_v1.len() > threshold {
+      let key = collab_snapshot_storage_key(&workspace_id, &params.object_id, sid);
+      let compressed = zstd::encode_all(encoded_collab_v1.as_slice(), ZSTD_COMPRESSION_LEVEL)?;
+      if let Err(err) = self.s3.put_blob(&key, compressed.into(), None).await {
+        self.collab_metrics.write_snapshot_failures.inc();
+        return Err(err);
+      }
+      Some(key)
+    } else {
+      None
+    };
+    let blob: &[u8] = if storage_key.is_some() {
+      &[]
+    } else {
+      &encoded_collab_v1
+    };
+
+    let created_at = match insert_collab_snapshot_record(
+      &self.pg_pool,
+      sid,
+      &workspace_id,
+      &params.object_id,
+      blob,
+      encoded_collab_v1.len() as i32,
+      storage_key.as_deref(),
+    )
+    .await
+    {
+      Ok(created_at) => created_at,
+      Err(err) => {
+        self.collab_metrics.write_snapshot_failures.inc();
+        if let Some(key) = storage_key {
+          if let Err(err) = self.s3.delete_blob(&key).await {
+            error!("Failed to delete dangling snapshot blob {}: {}", key, err);
+          }
+        }
+        return Err(err);
+      },
+    };
+
+    // drop old snapshots if exceeds limit
+    let outdated_keys = delete_collab_snapshots_over_limit(
+      &self.pg_pool,
+      &workspace_id,
+      &params.object_id,
+      COLLAB_SNAPSHOT_LIMIT,
+    )
+    .await?;
+    if !outdated_keys.is_empty() {
+      self.s3.delete_blobs(outdated_keys).await?;
+    }
+
+    Ok(AFSnapshotMeta {
+      snapshot_id: sid,
+      object_id: params.object_id,
+      created_at,
+    })
+  }
+
+  /// Writes the snapshot to the object storage, keyed by its creation time.
+  async fn create_snapshot_object(
+    &self,
+    params: InsertSnapshotParams,
+  ) -> AppResult<AFSnapshotMeta> {
     let timestamp = Utc::now();
     let snapshot_id = timestamp.timestamp_millis();
     let key = collab_snapshot_key(&params.workspace_id, &params.object_id, snapshot_id);
@@ -156,45 +278,48 @@ impl SnapshotControl {
     })
   }
 
+  /// Returns the encoded collab of a snapshot recorded in Postgres, reading it from the object
+  /// storage if it has been offloaded.
+  async fn read_snapshot_row(&self, row: AFSnapshotRow) -> AppResult<Vec<u8>> {
+    match row.storage_key {
+      Some(key) => {
+        let resp = self.s3.get_blob(&key).await?;
+        Ok(zstd::decode_all(&*resp.to_blob())?)
+      },
+      None => Ok(row.blob),
+    }
+  }
+
   pub async fn get_collab_snapshot(
     &self,
     workspace_id: &str,
     object_id: &str,
     snapshot_id: &i64,
   ) -> AppResult<SnapshotData> {
+    if let Some(row) = select_snapshot(&self.pg_pool, workspace_id, object_id, snapshot_id).await? {
+      self.collab_metrics.read_snapshot.inc();
+      return Ok(SnapshotData {
+        object_id: object_id.to_string(),
+        encoded_collab_v1: self.read_snapshot_row(row).await?,
+        workspace_id: workspace_id.to_string(),
+      });
+    }
+
+    debug!(
+      "snapshot {} for `{}` not found in postgres: fallback to s3",
+      snapshot_id, object_id
+    );
     let key = collab_snapshot_key(workspace_id, object_id, *snapshot_id);
     match self.s3.get_blob(&key).await {
       Ok(resp) => {
         self.collab_metrics.read_snapshot.inc();
         let decompressed = zstd::decode_all(&*resp.to_blob())?;
-        let encoded_collab = EncodedCollab {
-          state_vector: Default::default(),
-          doc_state: decompressed.into(),
-          version: EncoderVersion::V1,
-        };
-        Ok(SnapshotData {
-          object_id: object_id.to_string(),
-          encoded_collab_v1: encoded_collab.encode_to_bytes()?,
-          workspace_id: workspace_id.to_string(),
-        })
-      },
-      Err(AppError::RecordNotFound(_)) => {
-        debug!(
-          "snapshot {} for `{}` not found in s3: fallback to postgres",
-          snapshot_id, object_id
-        );
-        match select_snapshot(&self.pg_pool, workspace_id, object_id, snapshot_id).await? {
-          None => Err(AppError::RecordNotFound(format!(
-            "Can't find the snapshot with id:{}",
-            snapshot_id
-          ))),
-          Some(row) => Ok(SnapshotData {
-            object_id: object_id.to_string(),
-            encoded_collab_v1: row.blob,
-            workspace_id: workspace_id.to_string(),
-          }),
-        }
+        snapshot_data_from_doc_state(workspace_id, object_id, decompressed)
       },
+      Err(AppError::RecordNotFound(_)) => Err(AppError::RecordNotFound(format!(
+        "Can't find the snapshot with id:{}",
+        snapshot_id
+      ))),
       Err(err) => Err(err),
     }
   }
@@ -210,13 +335,11 @@ impl SnapshotControl {
       .s3
       .list_dir(&snapshot_prefix, COLLAB_SNAPSHOT_LIMIT as usize)
       .await?;
-    if resp.is_empty() {
-      let metas = get_all_collab_snapshot_meta(&self.pg_pool, oid).await?;
-      Ok(metas)
-    } else {
-      let metas: Vec<_> = resp.into_iter().filter_map(get_meta).collect();
-      Ok(AFSnapshotMetas(metas))
-    }
+    let mut metas = get_all_collab_snapshot_meta(&self.pg_pool, oid).await?.0;
+    metas.extend(resp.into_iter().filter_map(get_meta));
+    metas.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    metas.truncate(COLLAB_SNAPSHOT_LIMIT as usize);
+    Ok(AFSnapshotMetas(metas))
   }
 
   pub async fn queue_snapshot(&self, params: InsertSnapshotParams) -> Result<(), AppError> {
@@ -248,19 +371,30 @@ impl SnapshotControl {
     oid: &str,
     collab_type: CollabType,
   ) -> Result<Option<SnapshotData>, AppError> {
+    let workspace_uuid = Uuid::parse_str(workspace_id)?;
+    let row = select_latest_snapshot(&self.pg_pool, &workspace_uuid, oid).await?;
     let snapshot_prefix = collab_snapshot_prefix(workspace_id, oid);
-    let mut resp = self.s3.list_dir(&snapshot_prefix, 1).await?;
-    if let Some(key) = resp.pop() {
+    let object_key = self.s3.list_dir(&snapshot_prefix, 1).await?.pop();
+    // prefer the most recent of the snapshot recorded in postgres and the one in the object storage
+    let object_key = match (&row, object_key) {
+      (Some(row), Some(key)) => get_timestamp(&key)
+        .filter(|time| *time > row.created_at)
+        .map(|_| key),
+      (_, key) => key,
+    };
+
+    if let Some(key) = object_key {
       let resp = self.s3.get_blob(&key).await?;
       let decompressed = zstd::decode_all(&*resp.to_blob())?;
-      let encoded_collab = EncodedCollab {
-        state_vector: Default::default(),
-        doc_state: decompressed.into(),
-        version: EncoderVersion::V1,
-      };
+      Ok(Some(snapshot_data_from_doc_state(
+        workspace_id,
+        oid,
+        decompressed,
+      )?))
+    } else if let Some(row) = row {
       Ok(Some(SnapshotData {
         object_id: oid.to_string(),
-        encoded_collab_v1: encoded_collab.encode_to_bytes()?,
+        encoded_collab_v1: self.read_snapshot_row(row).await?,
         workspace_id: workspace_id.to_string(),
       }))
     } else {
@@ -277,6 +411,81 @@ impl SnapshotControl {
     }
   }
 
+  /// Creates a named checkpoint. Checkpoints are stored next to the snapshots but under their own
+  /// prefix, so they are not affected by [COLLAB_SNAPSHOT_LIMIT].
+  pub async fn create_checkpoint(
+    &self,
+    params: InsertCheckpointParams,
+  ) -> AppResult<AFCollabCheckpoint> {
+    params.validate()?;
+    let workspace_id = Uuid::parse_str(&params.workspace_id)?;
+    debug!(
+      "create checkpoint `{}` for object:{}",
+      params.label, params.object_id
+    );
+
+    let checkpoint = insert_collab_checkpoint(
+      &self.pg_pool,
+      &workspace_id,
+      &params.object_id,
+      &params.label,
+      params.created_by,
+    )
+    .await?;
+    let key = collab_checkpoint_key(
+      &params.workspace_id,
+      &params.object_id,
+      checkpoint.checkpoint_id,
+    );
+    let compressed = zstd::encode_all(params.doc_state.as_ref(), ZSTD_COMPRESSION_LEVEL)?;
+    if let Err(err) = self.s3.put_blob(&key, compressed.into(), None).await {
+      // don't keep a checkpoint that can't be reverted to
+      if let Err(err) = delete_collab_checkpoint(&self.pg_pool, checkpoint.checkpoint_id).await {
+        error!("Failed to delete dangling checkpoint: {}", err);
+      }
+      return Err(err);
+    }
+    Ok(checkpoint)
+  }
+
+  /// Returns the checkpoint metadata along with its doc state.
+  pub async fn get_checkpoint(
+    &self,
+    workspace_id: &str,
+    object_id: &str,
+    checkpoint_id: i64,
+  ) -> AppResult<(AFCollabCheckpoint, Vec<u8>)> {
+    let workspace_uuid = Uuid::parse_str(workspace_id)?;
+    let checkpoint =
+      select_collab_checkpoint(&self.pg_pool, &workspace_uuid, object_id, checkpoint_id)
+        .await?
+        .ok_or_else(|| {
+          AppError::RecordNotFound(format!(
+            "Can't find the checkpoint with id:{}",
+            checkpoint_id
+          ))
+        })?;
+    let key = collab_checkpoint_key(workspace_id, object_id, checkpoint_id);
+    let resp = self.s3.get_blob(&key).await?;
+    let doc_state = zstd::decode_all(&*resp.to_blob())?;
+    Ok((checkpoint, doc_state))
+  }
+
+  /// Returns the named checkpoints and the automatic snapshots of the given object.
+  pub async fn get_collab_history(
+    &self,
+    workspace_id: &str,
+    oid: &str,
+  ) -> AppResult<AFCollabHistory> {
+    let workspace_uuid = Uuid::parse_str(workspace_id)?;
+    let checkpoints = select_collab_checkpoints(&self.pg_pool, &workspace_uuid, oid).await?;
+    let snapshots = self.get_collab_snapshot_list(workspace_id, oid).await?.0;
+    Ok(AFCollabHistory {
+      checkpoints,
+      snapshots,
+    })
+  }
+
   async fn latest_snapshot_time(
     &self,
     workspace_id: &str,
@@ -284,10 +493,8 @@ impl SnapshotControl {
   ) -> Result<Option<DateTime<Utc>>, AppError> {
     let snapshot_prefix = collab_snapshot_prefix(workspace_id, oid);
     let mut resp = self.s3.list_dir(&snapshot_prefix, 1).await?;
-    if let Some(key) = resp.pop() {
-      Ok(get_timestamp(&key))
-    } else {
-      Ok(latest_snapshot_time(oid, &self.pg_pool).await?)
-    }
+    let object_time = resp.pop().and_then(|key| get_timestamp(&key));
+    let record_time = latest_snapshot_time(oid, &self.pg_pool).await?;
+    Ok(object_time.max(record_time))
   }
 }
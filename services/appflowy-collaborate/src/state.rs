@@ -1,13 +1,19 @@
 use std::sync::Arc;
 
 use access_control::casbin::access::AccessControl;
+use access_control::collab_guest::CollabGuestCache;
+use access_control::collab_lock::CollabLockCache;
+use access_control::restricted_view::RestrictedViewCache;
+use access_control::session_policy::SessionPolicy;
+use access_control::workspace_read_only::WorkspaceReadOnlyCache;
 use dashmap::DashMap;
 use futures_util::StreamExt;
 use sqlx::PgPool;
+use tokio::sync::watch;
 use uuid::Uuid;
 
 use crate::collab::storage::CollabAccessControlStorage;
-use crate::config::Config;
+use crate::config::{Config, ReloadableSetting};
 use crate::metrics::CollabMetrics;
 use crate::pg_listener::PgListeners;
 use crate::CollabRealtimeMetrics;
@@ -26,12 +32,20 @@ pub struct AppState {
   pub config: Arc<Config>,
   pub pg_listeners: Arc<PgListeners>,
   pub user_cache: UserCache,
+  pub redis_client: redis::Client,
   pub redis_stream_router: Arc<StreamRouter>,
   pub redis_connection_manager: RedisConnectionManager,
   pub access_control: AccessControl,
+  pub collab_lock_cache: CollabLockCache,
+  pub workspace_read_only_cache: WorkspaceReadOnlyCache,
+  pub restricted_view_cache: RestrictedViewCache,
+  pub collab_guest_cache: CollabGuestCache,
+  pub session_policy: SessionPolicy,
   pub collab_access_control_storage: Arc<CollabAccessControlStorage>,
   pub metrics: AppMetrics,
   pub indexer_scheduler: Arc<IndexerScheduler>,
+  /// Settings updated by a config reload. [AppState::config] keeps the values read at startup.
+  pub reloadable_setting: watch::Receiver<ReloadableSetting>,
 }
 
 #[derive(Clone)]
@@ -1,9 +1,14 @@
+use collab::core::collab::DataSource;
 use collab::core::origin::CollabOrigin;
 use collab::preclude::Collab;
 use collab_document::document::Document;
+use collab_entity::CollabType;
+use indexer::collab_indexer::{DatabaseRowTextExtractor, DatabaseTextExtractor, TextExtractor};
 use workspace_template::document::getting_started::{
-  get_initial_document_data, getting_started_document_data,
+  get_initial_document_data, getting_started_document_data, GettingStartedTemplate,
 };
+use workspace_template::hierarchy_builder::WorkspaceViewBuilder;
+use workspace_template::WorkspaceTemplate;
 
 #[test]
 fn document_plain_text() {
@@ -24,3 +29,39 @@ fn document_plain_text_with_nested_blocks() {
   let expected = "Welcome to AppFlowy! Here are the basics Here is H3 Click anywhere and just start typing. Click Enter to create a new line. Highlight any text, and use the editing menu to style your writing however you like. As soon as you type / a menu will pop up. Select different types of content blocks you can add. Type / followed by /bullet or /num to create a list. Click + New Page button at the bottom of your sidebar to add a new page. Click + next to any page title in the sidebar to quickly add a new subpage, Document , Grid , or Kanban Board . Keyboard shortcuts, markdown, and code block Keyboard shortcuts guide Markdown reference Type /code to insert a code block // This is the main function.\nfn main() {\n    // Print text to the console.\n    println!(\"Hello World!\");\n} This is a paragraph This is a paragraph Have a question❓ Click ? at the bottom right for help and support. This is a paragraph This is a paragraph Click ? at the bottom right for help and support. Like AppFlowy? Follow us: GitHub Twitter : @appflowy Newsletter ";
   assert_eq!(&text, expected);
 }
+
+#[tokio::test]
+async fn database_and_row_text() {
+  let mut builder = WorkspaceViewBuilder::new(uuid::Uuid::new_v4().to_string(), 1);
+  let template_data = GettingStartedTemplate
+    .create_workspace_view(1, &mut builder)
+    .await
+    .unwrap();
+
+  let mut database_text = String::new();
+  let mut row_texts = vec![];
+  for data in template_data {
+    let collab = Collab::new_with_source(
+      CollabOrigin::Server,
+      "1",
+      DataSource::DocStateV1(data.encoded_collab.doc_state.to_vec()),
+      vec![],
+      false,
+    )
+    .unwrap();
+    match data.collab_type {
+      CollabType::Database => database_text = DatabaseTextExtractor.extract_text(&collab).unwrap(),
+      CollabType::DatabaseRow => {
+        row_texts.push(DatabaseRowTextExtractor.extract_text(&collab).unwrap())
+      },
+      _ => {},
+    }
+  }
+
+  // the field names, the status field is a select field
+  assert!(database_text.contains("Description"));
+  assert!(database_text.contains("Status"));
+  // the primary cells
+  assert_eq!(row_texts.len(), 5);
+  assert!(row_texts.contains(&"Follow us on Twitter @appflowy".to_string()));
+}
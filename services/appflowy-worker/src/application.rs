@@ -7,19 +7,49 @@ use sqlx::PgPool;
 use crate::import_worker::worker::run_import_worker;
 use aws_sdk_s3::config::{Credentials, Region, SharedCredentialsProvider};
 
+use crate::chat_attachment_worker::extractor::TextExtractor;
+use crate::chat_attachment_worker::worker::run_chat_attachment_worker;
+use crate::collab_archive_worker::worker::{run_collab_archive_worker, CollabArchiveSetting};
+use crate::collab_compression_worker::worker::{
+  run_collab_compression_worker, CollabCompressionSetting,
+};
+use crate::collab_partition_worker::worker::{run_collab_partition_worker, CollabPartitionSetting};
+use crate::export_worker::email_notifier::ExportEmailNotifier;
+use crate::export_worker::renderer::PdfRenderer;
+use crate::export_worker::worker::run_export_worker;
+use crate::history_compaction_worker::worker::{
+  run_history_compaction_worker, HistoryCompactionSetting,
+};
+use crate::image_asset_worker::worker::run_image_asset_worker;
 use crate::import_worker::email_notifier::EmailNotifier;
-use crate::s3_client::S3ClientImpl;
+use crate::publish_feed_worker::worker::{run_publish_feed_worker, PublishFeedSetting};
+use crate::retention_worker::worker::{run_retention_worker, RetentionSetting};
+use crate::s3_client::{
+  AzureBlobClient, LocalFsBlobClient, S3Client, S3ClientImpl, WorkspaceRoutedS3Client,
+};
+use crate::snapshot_offload_worker::worker::{run_snapshot_offload_worker, SnapshotOffloadSetting};
+use crate::stream_lag_worker::worker::{run_stream_lag_worker, ConsumerStream, StreamLagSetting};
+use crate::workspace_clone_worker::worker::run_workspace_clone_worker;
+use crate::workspace_delete_worker::worker::{run_workspace_delete_worker, WorkspaceDeleteSetting};
+use crate::workspace_storage_worker::worker::{
+  run_workspace_storage_worker, WorkspaceStorageRelocationSetting,
+};
+use database::file::azure_client_impl::AzureBlobClientImpl;
+use database::file::fs_client_impl::LocalFsBucketClientImpl;
+use database::file::{BlobStorageBackend, WorkspaceStorageCipher, WorkspaceStorageRouter};
 
+use appflowy_ai_client::client::AppFlowyAIClient;
 use axum::Router;
 
+use crate::indexer_worker::{run_background_indexer, BackgroundIndexerConfig};
 use crate::mailer::AFWorkerMailer;
-use crate::metric::ImportMetrics;
-use appflowy_worker::indexer_worker::{run_background_indexer, BackgroundIndexerConfig};
+use crate::metric::{HistoryCompactionMetrics, ImportMetrics, StreamLagMetrics};
 use axum::extract::State;
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::routing::get;
 use indexer::metrics::EmbeddingMetrics;
+use indexer::queue::{INDEXER_WORKER_GROUP_NAME, INDEX_TASK_STREAM_NAME};
 use indexer::thread_pool::ThreadPoolNoAbortBuilder;
 use infra::env_util::get_env_var;
 use mailer::sender::Mailer;
@@ -33,6 +63,14 @@ use tracing::subscriber::set_global_default;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::EnvFilter;
 
+const EXPORT_TASK_STREAM: &str = "export_task_stream";
+const PUBLISH_FEED_TASK_STREAM: &str = "publish_feed_task_stream";
+const CHAT_ATTACHMENT_TASK_STREAM: &str = "chat_attachment_task_stream";
+const IMAGE_ASSET_TASK_STREAM: &str = "image_asset_task_stream";
+const WORKSPACE_CLONE_TASK_STREAM: &str = "workspace_clone_task_stream";
+const WORKSPACE_DELETE_TASK_STREAM: &str = "workspace_delete_task_stream";
+const IMPORT_TASK_STREAM: &str = "import_task_stream";
+
 pub async fn run_server(
   listener: TcpListener,
   config: Config,
@@ -92,8 +130,28 @@ pub async fn create_app(listener: TcpListener, config: Config) -> Result<(), Err
     .await
     .expect("failed to get redis connection manager");
 
-  let mailer = get_worker_mailer(&config).await?;
-  let s3_client = get_aws_s3_client(&config.s3_setting).await?;
+  run_workers(&config, pg_pool, redis_client, Some(listener)).await
+}
+
+/// Runs the worker loops until the Notion importer or the metrics server stops. The pools are
+/// those of the appflowy_cloud process when the workers are embedded in it, in which case no
+/// metrics listener is given and the worker metrics are not served.
+pub async fn run_workers(
+  config: &Config,
+  pg_pool: PgPool,
+  redis_client: ConnectionManager,
+  metrics_listener: Option<TcpListener>,
+) -> Result<(), Error> {
+  let mailer = get_worker_mailer(config).await?;
+  let default_s3_client = get_s3_client(config).await?;
+  let workspace_storage_router = get_workspace_storage_router(config, &pg_pool)?;
+  let s3_client: Arc<dyn S3Client> = match &workspace_storage_router {
+    Some(router) => Arc::new(WorkspaceRoutedS3Client {
+      default: default_s3_client.clone(),
+      router: router.clone(),
+    }),
+    None => default_s3_client.clone(),
+  };
   let metrics = AppMetrics::new();
 
   let state = AppState {
@@ -116,13 +174,154 @@ pub async fn create_app(listener: TcpListener, config: Config) -> Result<(), Err
       .parse::<u64>()
       .unwrap_or(1_000_000_000);
 
+  tokio::spawn(run_export_worker(
+    state.pg_pool.clone(),
+    state.redis_client.clone(),
+    state.s3_client.clone(),
+    Arc::new(ExportEmailNotifier::new(state.mailer.clone())),
+    PdfRenderer::from_env(),
+    EXPORT_TASK_STREAM,
+    tick_interval,
+  ));
+
+  tokio::spawn(run_publish_feed_worker(
+    state.pg_pool.clone(),
+    state.redis_client.clone(),
+    state.s3_client.clone(),
+    PublishFeedSetting {
+      web_url: get_env_var("APPFLOWY_WEB_URL", "http://localhost:3000"),
+      api_url: get_env_var("APPFLOWY_WORKER_PUBLIC_API_URL", "http://localhost:8000"),
+    },
+    PUBLISH_FEED_TASK_STREAM,
+    tick_interval,
+  ));
+
+  let ai_url = format!(
+    "http://{}:{}",
+    get_env_var("AI_SERVER_HOST", "localhost"),
+    get_env_var("AI_SERVER_PORT", "5001")
+  );
+  tokio::spawn(run_chat_attachment_worker(
+    state.pg_pool.clone(),
+    state.redis_client.clone(),
+    state.s3_client.clone(),
+    AppFlowyAIClient::new(&ai_url),
+    TextExtractor::from_env(),
+    CHAT_ATTACHMENT_TASK_STREAM,
+    tick_interval,
+  ));
+
+  tokio::spawn(run_image_asset_worker(
+    state.pg_pool.clone(),
+    state.redis_client.clone(),
+    state.s3_client.clone(),
+    IMAGE_ASSET_TASK_STREAM,
+    tick_interval,
+  ));
+
+  tokio::spawn(run_collab_archive_worker(
+    state.pg_pool.clone(),
+    state.redis_client.clone(),
+    state.s3_client.clone(),
+    CollabArchiveSetting::from_env(),
+  ));
+
+  tokio::spawn(run_collab_compression_worker(
+    state.pg_pool.clone(),
+    state.redis_client.clone(),
+    CollabCompressionSetting::from_env(),
+  ));
+
+  if let Some(router) = workspace_storage_router {
+    tokio::spawn(run_workspace_storage_worker(
+      state.pg_pool.clone(),
+      state.redis_client.clone(),
+      default_s3_client,
+      router,
+      WorkspaceStorageRelocationSetting::from_env(),
+    ));
+  }
+
+  tokio::spawn(run_collab_partition_worker(
+    state.pg_pool.clone(),
+    CollabPartitionSetting::from_env(),
+  ));
+
+  tokio::spawn(run_snapshot_offload_worker(
+    state.pg_pool.clone(),
+    state.redis_client.clone(),
+    state.s3_client.clone(),
+    SnapshotOffloadSetting::from_env(),
+  ));
+
+  tokio::spawn(run_retention_worker(
+    state.pg_pool.clone(),
+    state.redis_client.clone(),
+    state.s3_client.clone(),
+    RetentionSetting::from_env(),
+  ));
+
+  tokio::spawn(run_history_compaction_worker(
+    state.pg_pool.clone(),
+    state.redis_client.clone(),
+    state.metrics.history_compaction_metrics.clone(),
+    HistoryCompactionSetting::from_env(),
+  ));
+
+  tokio::spawn(run_stream_lag_worker(
+    state.redis_client.clone(),
+    state.metrics.stream_lag_metrics.clone(),
+    vec![
+      ConsumerStream::new(IMPORT_TASK_STREAM, crate::import_worker::worker::GROUP_NAME),
+      ConsumerStream::new(EXPORT_TASK_STREAM, crate::export_worker::worker::GROUP_NAME),
+      ConsumerStream::new(
+        PUBLISH_FEED_TASK_STREAM,
+        crate::publish_feed_worker::worker::GROUP_NAME,
+      ),
+      ConsumerStream::new(
+        CHAT_ATTACHMENT_TASK_STREAM,
+        crate::chat_attachment_worker::worker::GROUP_NAME,
+      ),
+      ConsumerStream::new(
+        IMAGE_ASSET_TASK_STREAM,
+        crate::image_asset_worker::worker::GROUP_NAME,
+      ),
+      ConsumerStream::new(
+        WORKSPACE_CLONE_TASK_STREAM,
+        crate::workspace_clone_worker::worker::GROUP_NAME,
+      ),
+      ConsumerStream::new(
+        WORKSPACE_DELETE_TASK_STREAM,
+        crate::workspace_delete_worker::worker::GROUP_NAME,
+      ),
+      ConsumerStream::new(INDEX_TASK_STREAM_NAME, INDEXER_WORKER_GROUP_NAME),
+    ],
+    StreamLagSetting::from_env(),
+  ));
+
+  tokio::spawn(run_workspace_clone_worker(
+    state.pg_pool.clone(),
+    state.redis_client.clone(),
+    state.s3_client.clone(),
+    WORKSPACE_CLONE_TASK_STREAM,
+    tick_interval,
+  ));
+
+  tokio::spawn(run_workspace_delete_worker(
+    state.pg_pool.clone(),
+    state.redis_client.clone(),
+    state.s3_client.clone(),
+    WORKSPACE_DELETE_TASK_STREAM,
+    WorkspaceDeleteSetting::from_env(),
+  ));
+
   let import_worker_fut = local_set.run_until(run_import_worker(
     state.pg_pool.clone(),
     state.redis_client.clone(),
     Some(state.metrics.import_metrics.clone()),
-    Arc::new(state.s3_client.clone()),
+    state.s3_client.clone(),
     Arc::new(email_notifier),
-    "import_task_stream",
+    IMPORT_TASK_STREAM,
     tick_interval,
     maximum_import_file_size,
   ));
@@ -156,11 +355,17 @@ pub async fn create_app(listener: TcpListener, config: Config) -> Result<(), Err
     .route("/metrics", get(metrics_handler))
     .with_state(Arc::new(state));
 
+  let metrics_server = async move {
+    match metrics_listener {
+      Some(listener) => axum::serve(listener, app).await,
+      None => std::future::pending().await,
+    }
+  };
   tokio::select! {
     _ = import_worker_fut => {
       info!("Notion importer stopped");
     },
-    _ = axum::serve(listener, app) => {
+    _ = metrics_server => {
       info!("worker stopped");
     },
   }
@@ -172,8 +377,7 @@ pub async fn create_app(listener: TcpListener, config: Config) -> Result<(), Err
 pub struct AppState {
   pub redis_client: ConnectionManager,
   pub pg_pool: PgPool,
-  pub s3_client: S3ClientImpl,
-  #[allow(dead_code)]
+  pub s3_client: Arc<dyn S3Client>,
   pub mailer: AFWorkerMailer,
   pub metrics: AppMetrics,
 }
@@ -207,6 +411,74 @@ async fn get_connection_pool(setting: &DatabaseSetting) -> Result<PgPool, Error>
     .map_err(|e| anyhow::anyhow!("Failed to connect to postgres database: {}", e))
 }
 
+async fn get_s3_client(config: &Config) -> Result<Arc<dyn S3Client>, Error> {
+  let client: Arc<dyn S3Client> = match config.blob_storage.backend {
+    BlobStorageBackend::S3 => Arc::new(get_aws_s3_client(&config.s3_setting).await?),
+    BlobStorageBackend::Gcs => {
+      // Cloud Storage is accessed through its S3 compatible XML API.
+      let gcs_setting = S3Setting {
+        use_minio: true,
+        minio_url: config.blob_storage.gcs_endpoint.clone(),
+        region: if config.s3_setting.region.is_empty() {
+          "auto".to_string()
+        } else {
+          config.s3_setting.region.clone()
+        },
+        ..config.s3_setting.clone()
+      };
+      Arc::new(get_aws_s3_client(&gcs_setting).await?)
+    },
+    BlobStorageBackend::Azure => {
+      let azure = &config.blob_storage.azure;
+      Arc::new(AzureBlobClient(AzureBlobClientImpl::new(
+        azure.account_name.clone(),
+        azure.account_key.expose_secret(),
+        azure.container.clone(),
+        azure.endpoint.clone(),
+      )?))
+    },
+    BlobStorageBackend::LocalFs => {
+      let local = &config.blob_storage.local;
+      Arc::new(LocalFsBlobClient(LocalFsBucketClientImpl::new(
+        local.root.clone().into(),
+        local.signing_key.expose_secret(),
+        local.public_url.clone(),
+      )?))
+    },
+    BlobStorageBackend::Memory => {
+      return Err(anyhow::anyhow!(
+        "The in-memory blob storage can't be shared with the appflowy_worker"
+      ))
+    },
+  };
+  Ok(client)
+}
+
+/// Returns the router of the workspaces that use their own bucket, None when the per-workspace
+/// storage is not configured.
+fn get_workspace_storage_router(
+  config: &Config,
+  pg_pool: &PgPool,
+) -> Result<Option<Arc<WorkspaceStorageRouter>>, Error> {
+  let Some(key) = &config.blob_storage.workspace_storage_key else {
+    return Ok(None);
+  };
+  let cipher = WorkspaceStorageCipher::from_base64(key.expose_secret())?;
+  let credentials = Credentials::new(
+    config.s3_setting.access_key.clone(),
+    config.s3_setting.secret_key.expose_secret().clone(),
+    None,
+    None,
+    "appflowy-worker",
+  );
+  Ok(Some(Arc::new(WorkspaceStorageRouter::new(
+    pg_pool.clone(),
+    cipher,
+    credentials,
+    Duration::from_secs(config.blob_storage.workspace_storage_cache_ttl_secs),
+  ))))
+}
+
 pub async fn get_aws_s3_client(s3_setting: &S3Setting) -> Result<S3ClientImpl, Error> {
   let credentials = Credentials::new(
     s3_setting.access_key.clone(),
@@ -241,6 +513,8 @@ pub struct AppMetrics {
   registry: Arc<prometheus_client::registry::Registry>,
   import_metrics: Arc<ImportMetrics>,
   embedder_metrics: Arc<EmbeddingMetrics>,
+  history_compaction_metrics: Arc<HistoryCompactionMetrics>,
+  stream_lag_metrics: Arc<StreamLagMetrics>,
 }
 
 impl AppMetrics {
@@ -248,10 +522,14 @@ impl AppMetrics {
     let mut registry = prometheus_client::registry::Registry::default();
     let import_metrics = Arc::new(ImportMetrics::register(&mut registry));
     let embedder_metrics = Arc::new(EmbeddingMetrics::register(&mut registry));
+    let history_compaction_metrics = Arc::new(HistoryCompactionMetrics::register(&mut registry));
+    let stream_lag_metrics = Arc::new(StreamLagMetrics::register(&mut registry));
     Self {
       registry: Arc::new(registry),
       import_metrics,
       embedder_metrics,
+      history_compaction_metrics,
+      stream_lag_metrics,
     }
   }
 }
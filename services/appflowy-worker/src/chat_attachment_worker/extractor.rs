@@ -0,0 +1,89 @@
+use crate::error::WorkerError;
+use anyhow::anyhow;
+use infra::env_util::get_env_var;
+use std::path::Path;
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::trace;
+
+const DEFAULT_PDF_TEXT_COMMAND: &str = "pdftotext -layout -enc UTF-8 {input} -";
+const DEFAULT_IMAGE_TEXT_COMMAND: &str = "tesseract {input} stdout";
+
+/// Extracts the text of the chat attachments that can't be read as is, by running an external
+/// command that prints the text to stdout. The commands are configured with
+/// `APPFLOWY_WORKER_PDF_TEXT_COMMAND` and `APPFLOWY_WORKER_IMAGE_TEXT_COMMAND`, where `{input}` is
+/// replaced with the path of the file.
+#[derive(Debug, Clone)]
+pub struct TextExtractor {
+  pdf_command: String,
+  image_command: String,
+  timeout: Duration,
+}
+
+impl TextExtractor {
+  pub fn from_env() -> Self {
+    let timeout_secs = get_env_var("APPFLOWY_WORKER_TEXT_EXTRACT_TIMEOUT_SECS", "120")
+      .parse::<u64>()
+      .unwrap_or(120);
+    Self {
+      pdf_command: get_env_var("APPFLOWY_WORKER_PDF_TEXT_COMMAND", DEFAULT_PDF_TEXT_COMMAND),
+      image_command: get_env_var(
+        "APPFLOWY_WORKER_IMAGE_TEXT_COMMAND",
+        DEFAULT_IMAGE_TEXT_COMMAND,
+      ),
+      timeout: Duration::from_secs(timeout_secs),
+    }
+  }
+
+  pub async fn extract_pdf(&self, input: &Path) -> Result<String, WorkerError> {
+    self.run(&self.pdf_command, input).await
+  }
+
+  /// Recognizes the text of the image with OCR.
+  pub async fn extract_image(&self, input: &Path) -> Result<String, WorkerError> {
+    self.run(&self.image_command, input).await
+  }
+
+  async fn run(&self, command: &str, input: &Path) -> Result<String, WorkerError> {
+    let args = command_args(command, input);
+    let (program, args) = args
+      .split_first()
+      .ok_or_else(|| anyhow!("text extraction command is empty"))?;
+    trace!("[Chat Attachment] extracting text: {} {:?}", program, args);
+
+    let result = tokio::time::timeout(
+      self.timeout,
+      Command::new(program).args(args).kill_on_drop(true).output(),
+    )
+    .await
+    .map_err(|_| anyhow!("text extraction timed out after {:?}", self.timeout))??;
+    if !result.status.success() {
+      return Err(WorkerError::Internal(anyhow!(
+        "{} exited with {}: {}",
+        program,
+        result.status,
+        String::from_utf8_lossy(&result.stderr)
+      )));
+    }
+    Ok(String::from_utf8_lossy(&result.stdout).trim().to_string())
+  }
+}
+
+fn command_args(command: &str, input: &Path) -> Vec<String> {
+  let input = input.to_string_lossy();
+  command
+    .split_whitespace()
+    .map(|arg| arg.replace("{input}", &input))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn substitute_input_in_command() {
+    let args = command_args(DEFAULT_IMAGE_TEXT_COMMAND, Path::new("/tmp/scan.png"));
+    assert_eq!(args, vec!["tesseract", "/tmp/scan.png", "stdout"]);
+  }
+}
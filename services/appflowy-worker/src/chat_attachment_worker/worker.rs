@@ -0,0 +1,211 @@
+use crate::chat_attachment_worker::extractor::TextExtractor;
+use crate::error::WorkerError;
+use crate::import_worker::worker::ensure_consumer_group;
+use crate::s3_client::S3Client;
+use anyhow::anyhow;
+use appflowy_ai_client::client::AppFlowyAIClient;
+use appflowy_ai_client::dto::CreateChatContext;
+use database::chat::chat_attachment::{update_chat_attachment_status, ChatAttachmentState};
+use futures::AsyncReadExt;
+use redis::aio::ConnectionManager;
+use redis::streams::{StreamId, StreamReadOptions, StreamReadReply};
+use redis::{AsyncCommands, Value};
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::PgPool;
+use std::env::temp_dir;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::fs;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{error, info, trace};
+use uuid::Uuid;
+
+pub(crate) const GROUP_NAME: &str = "chat_attachment_task_group";
+const CONSUMER_NAME: &str = "appflowy_worker";
+/// Must match the source used by the server for the attachments it embeds itself.
+const ATTACHMENT_SOURCE: &str = "appflowy_chat_attachment";
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum AttachmentKind {
+  Text,
+  Markdown,
+  Pdf,
+  Image,
+}
+
+/// Task pushed by the server for the attachments whose text can't be read as is. The file is
+/// stored under `file_key`.
+#[derive(Debug, Clone, Deserialize)]
+struct ChatAttachmentTask {
+  attachment_id: Uuid,
+  workspace_id: Uuid,
+  chat_id: Uuid,
+  file_name: String,
+  file_key: String,
+  kind: AttachmentKind,
+}
+
+impl TryFrom<&StreamId> for ChatAttachmentTask {
+  type Error = WorkerError;
+
+  fn try_from(stream_id: &StreamId) -> Result<Self, Self::Error> {
+    let task_str = match stream_id.map.get("task") {
+      Some(Value::Data(data)) => String::from_utf8_lossy(data).to_string(),
+      _ => {
+        return Err(WorkerError::Internal(anyhow!(
+          "missing task in stream entry {}",
+          stream_id.id
+        )))
+      },
+    };
+    serde_json::from_str(&task_str).map_err(|err| WorkerError::Internal(err.into()))
+  }
+}
+
+/// Extracts the text of the files uploaded as chat context and sends it to the AI service, which
+/// chunks and embeds it for the chat.
+pub async fn run_chat_attachment_worker(
+  pg_pool: PgPool,
+  mut redis_client: ConnectionManager,
+  s3_client: Arc<dyn S3Client>,
+  ai_client: AppFlowyAIClient,
+  extractor: TextExtractor,
+  stream_name: &str,
+  tick_interval_secs: u64,
+) -> Result<(), WorkerError> {
+  info!("Starting chat attachment worker");
+  if let Err(err) = ensure_consumer_group(stream_name, GROUP_NAME, &mut redis_client).await {
+    error!("Failed to ensure consumer group: {:?}", err);
+  }
+
+  let mut pending_id = Some("0");
+  let options = StreamReadOptions::default()
+    .group(GROUP_NAME, CONSUMER_NAME)
+    .count(5);
+  let mut interval = interval(Duration::from_secs(tick_interval_secs));
+  interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+  loop {
+    interval.tick().await;
+    let id = pending_id.take().unwrap_or(">");
+    let reply: StreamReadReply = match redis_client
+      .xread_options(&[stream_name], &[id], &options)
+      .await
+    {
+      Ok(reply) => reply,
+      Err(err) => {
+        error!(
+          "Failed to read chat attachment tasks from Redis stream: {:?}",
+          err
+        );
+        if err.code() == Some("NOGROUP") {
+          if let Err(err) = ensure_consumer_group(stream_name, GROUP_NAME, &mut redis_client).await
+          {
+            error!("Failed to ensure consumer group: {:?}", err);
+          }
+        }
+        continue;
+      },
+    };
+
+    for stream_key in reply.keys {
+      for stream_id in stream_key.ids {
+        match ChatAttachmentTask::try_from(&stream_id) {
+          Ok(task) => {
+            process_attachment(&pg_pool, &s3_client, &ai_client, &extractor, task).await;
+          },
+          Err(err) => error!("Failed to deserialize chat attachment task: {:?}", err),
+        }
+        // The result is recorded on the attachment, the user uploads the file again to retry.
+        let _: Result<(), _> = redis_client
+          .xack(stream_name, GROUP_NAME, &[&stream_id.id])
+          .await
+          .map_err(|err| error!("Failed to ack chat attachment task: {:?}", err));
+      }
+    }
+  }
+}
+
+async fn process_attachment(
+  pg_pool: &PgPool,
+  s3_client: &Arc<dyn S3Client>,
+  ai_client: &AppFlowyAIClient,
+  extractor: &TextExtractor,
+  task: ChatAttachmentTask,
+) {
+  trace!("[Chat Attachment] processing task: {:?}", task);
+  let work_dir = temp_dir().join(format!("chat_attachment_{}", task.attachment_id));
+  let result = embed_attachment(s3_client, ai_client, extractor, &task, &work_dir).await;
+  let _ = fs::remove_dir_all(&work_dir).await;
+
+  let (state, error) = match &result {
+    Ok(_) => (ChatAttachmentState::Ready, None),
+    Err(err) => {
+      error!(
+        "[Chat Attachment] attachment {} of workspace {} failed: {:?}",
+        task.attachment_id, task.workspace_id, err
+      );
+      (ChatAttachmentState::Failed, Some(err.to_string()))
+    },
+  };
+  if let Err(err) =
+    update_chat_attachment_status(pg_pool, &task.attachment_id, state, error.as_deref()).await
+  {
+    error!(
+      "Failed to update chat attachment {}: {:?}",
+      task.attachment_id, err
+    );
+  }
+}
+
+async fn embed_attachment(
+  s3_client: &Arc<dyn S3Client>,
+  ai_client: &AppFlowyAIClient,
+  extractor: &TextExtractor,
+  task: &ChatAttachmentTask,
+  work_dir: &Path,
+) -> Result<(), WorkerError> {
+  fs::create_dir_all(work_dir).await?;
+  let mut data = Vec::new();
+  s3_client
+    .get_blob_stream(&task.file_key)
+    .await?
+    .stream
+    .read_to_end(&mut data)
+    .await?;
+
+  let (loader, text) = match task.kind {
+    AttachmentKind::Text => ("text", String::from_utf8_lossy(&data).to_string()),
+    AttachmentKind::Markdown => ("markdown", String::from_utf8_lossy(&data).to_string()),
+    AttachmentKind::Pdf | AttachmentKind::Image => {
+      let path = work_dir.join("attachment");
+      fs::write(&path, data).await?;
+      let text = match task.kind {
+        AttachmentKind::Pdf => extractor.extract_pdf(&path).await?,
+        _ => extractor.extract_image(&path).await?,
+      };
+      ("text", text)
+    },
+  };
+  if text.trim().is_empty() {
+    return Err(WorkerError::Internal(anyhow!(
+      "no text found in {}",
+      task.file_name
+    )));
+  }
+
+  let context = CreateChatContext::new(task.chat_id.to_string(), loader.to_string(), text)
+    .with_metadata(json!({
+      "id": task.attachment_id.to_string(),
+      "name": task.file_name,
+      "source": ATTACHMENT_SOURCE,
+    }));
+  ai_client
+    .create_chat_text_context(context)
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+  Ok(())
+}
@@ -0,0 +1,266 @@
+use crate::error::WorkerError;
+use crate::s3_client::S3Client;
+use anyhow::anyhow;
+use aws_sdk_s3::primitives::ByteStream;
+use chrono::Utc;
+use collab::entity::EncodedCollab;
+use collab_entity::CollabType;
+use database::collab::{
+  clear_archived_collab_blob, collab_archive_key, delete_collab_archive, insert_collab_archive,
+  select_blob_from_af_collab_in_workspace, select_collabs_to_archive,
+  select_outdated_collab_archives, CollabArchiveCandidate,
+};
+use database_entity::dto::ZSTD_COMPRESSION_LEVEL;
+use infra::env_util::get_env_var;
+use redis::aio::ConnectionManager;
+use sqlx::PgPool;
+use std::ops::DerefMut;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{error, info, trace, warn};
+
+/// Only one worker archives collabs at a time.
+const ARCHIVE_LOCK_KEY: &str = "af:collab_archive:lock";
+
+#[derive(Debug, Clone)]
+pub struct CollabArchiveSetting {
+  pub enabled: bool,
+  /// Documents that haven't been edited for that many days are archived.
+  pub inactive_days: u64,
+  /// Storage class of the archived collabs, e.g. `GLACIER_IR`. Only the storage classes that can
+  /// be read without a restore request should be used, the archives are read on first access.
+  pub storage_class: String,
+  /// Maximum number of collabs archived in each run.
+  pub batch_size: i64,
+  pub interval: Duration,
+}
+
+impl CollabArchiveSetting {
+  pub fn from_env() -> Self {
+    Self {
+      enabled: get_env_var("APPFLOWY_WORKER_COLLAB_ARCHIVE_ENABLED", "false")
+        .parse()
+        .unwrap_or(false),
+      inactive_days: get_env_var("APPFLOWY_WORKER_COLLAB_ARCHIVE_INACTIVE_DAYS", "180")
+        .parse()
+        .unwrap_or(180),
+      storage_class: get_env_var("APPFLOWY_WORKER_COLLAB_ARCHIVE_STORAGE_CLASS", "GLACIER_IR"),
+      batch_size: get_env_var("APPFLOWY_WORKER_COLLAB_ARCHIVE_BATCH_SIZE", "100")
+        .parse()
+        .unwrap_or(100),
+      interval: Duration::from_secs(
+        get_env_var("APPFLOWY_WORKER_COLLAB_ARCHIVE_INTERVAL_SECS", "3600")
+          .parse()
+          .unwrap_or(3600),
+      ),
+    }
+  }
+}
+
+/// Periodically moves the documents that haven't been edited for a long time to a cheaper storage
+/// class. Their metadata stays in Postgres and they are rehydrated by the collaboration server
+/// when they are accessed again.
+pub async fn run_collab_archive_worker(
+  pg_pool: PgPool,
+  mut redis_client: ConnectionManager,
+  s3_client: Arc<dyn S3Client>,
+  setting: CollabArchiveSetting,
+) -> Result<(), WorkerError> {
+  if !setting.enabled {
+    return Ok(());
+  }
+  info!(
+    "Starting collab archive worker: archiving documents inactive for {} days to {}",
+    setting.inactive_days, setting.storage_class
+  );
+  let mut interval = interval(setting.interval);
+  interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+  loop {
+    interval.tick().await;
+    let locked: Option<String> = redis::cmd("SET")
+      .arg(ARCHIVE_LOCK_KEY)
+      .arg(1)
+      .arg("NX")
+      .arg("EX")
+      .arg(setting.interval.as_secs().max(1))
+      .query_async(&mut redis_client)
+      .await
+      .unwrap_or_else(|err| {
+        error!("Failed to acquire collab archive lock: {:?}", err);
+        None
+      });
+    if locked.is_none() {
+      trace!("[Collab Archive] another worker is archiving collabs");
+      continue;
+    }
+
+    if let Err(err) = remove_outdated_archives(&pg_pool, &s3_client, setting.batch_size).await {
+      error!(
+        "[Collab Archive] failed to remove outdated archives: {:?}",
+        err
+      );
+    }
+    match archive_inactive_collabs(&pg_pool, &s3_client, &setting).await {
+      Ok(count) if count > 0 => info!("[Collab Archive] archived {} collabs", count),
+      Ok(_) => {},
+      Err(err) => error!("[Collab Archive] failed to archive collabs: {:?}", err),
+    }
+  }
+}
+
+async fn archive_inactive_collabs(
+  pg_pool: &PgPool,
+  s3_client: &Arc<dyn S3Client>,
+  setting: &CollabArchiveSetting,
+) -> Result<usize, WorkerError> {
+  let inactive_since = Utc::now() - chrono::Duration::days(setting.inactive_days as i64);
+  let candidates = select_collabs_to_archive(pg_pool, inactive_since, setting.batch_size)
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+  let mut count = 0;
+  for candidate in candidates {
+    match archive_collab(pg_pool, s3_client, &setting.storage_class, &candidate).await {
+      Ok(true) => count += 1,
+      Ok(false) => {},
+      Err(err) => warn!(
+        "[Collab Archive] failed to archive collab {}: {:?}",
+        candidate.oid, err
+      ),
+    }
+  }
+  Ok(count)
+}
+
+/// Archives a single collab. Returns false if the collab has been skipped.
+async fn archive_collab(
+  pg_pool: &PgPool,
+  s3_client: &Arc<dyn S3Client>,
+  storage_class: &str,
+  candidate: &CollabArchiveCandidate,
+) -> Result<bool, WorkerError> {
+  if candidate.len.unwrap_or(0) == 0 {
+    // big collabs are already stored in the object storage, only their storage class changes
+    if !s3_client.supports_storage_class() {
+      return Ok(false);
+    }
+    let key = collab_key(candidate);
+    let meta = match s3_client.get_blob_meta(&key).await {
+      Ok(meta) => meta,
+      Err(WorkerError::RecordNotFound(_)) => return Ok(false),
+      Err(err) => return Err(err),
+    };
+    s3_client.set_storage_class(&key, storage_class).await?;
+    insert_collab_archive(
+      pg_pool,
+      &candidate.oid,
+      candidate.partition_key,
+      &candidate.workspace_id,
+      &key,
+      true,
+      storage_class,
+      meta.content_length,
+    )
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+    return Ok(true);
+  }
+
+  let blob = select_blob_from_af_collab_in_workspace(
+    pg_pool,
+    &candidate.workspace_id,
+    &CollabType::Document,
+    &candidate.oid,
+  )
+  .await
+  .map_err(|err| WorkerError::Internal(err.into()))?;
+  let encoded_collab = EncodedCollab::decode_from_bytes(&blob)
+    .map_err(|err| WorkerError::Internal(anyhow!("invalid collab {}: {}", candidate.oid, err)))?;
+  let compressed = zstd::encode_all(&*encoded_collab.doc_state, ZSTD_COMPRESSION_LEVEL)?;
+  let archived_len = compressed.len() as i64;
+  let archive_key = collab_archive_key(&candidate.workspace_id, &candidate.oid);
+  s3_client
+    .put_blob_with_storage_class(&archive_key, ByteStream::from(compressed), storage_class)
+    .await?;
+
+  let mut tx = pg_pool
+    .begin()
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+  let cleared = clear_archived_collab_blob(
+    &mut tx,
+    &candidate.oid,
+    candidate.partition_key,
+    &candidate.updated_at,
+  )
+  .await
+  .map_err(|err| WorkerError::Internal(err.into()))?;
+  if !cleared {
+    // written since it was selected, it's not inactive anymore
+    tx.rollback()
+      .await
+      .map_err(|err| WorkerError::Internal(err.into()))?;
+    s3_client.delete_blob(&archive_key).await?;
+    return Ok(false);
+  }
+  insert_collab_archive(
+    tx.deref_mut(),
+    &candidate.oid,
+    candidate.partition_key,
+    &candidate.workspace_id,
+    &archive_key,
+    false,
+    storage_class,
+    archived_len,
+  )
+  .await
+  .map_err(|err| WorkerError::Internal(err.into()))?;
+  tx.commit()
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+  trace!(
+    "[Collab Archive] archived collab {}: {} bytes",
+    candidate.oid,
+    archived_len
+  );
+  Ok(true)
+}
+
+/// Removes the archives of the collabs that have been written or deleted since they were
+/// archived.
+async fn remove_outdated_archives(
+  pg_pool: &PgPool,
+  s3_client: &Arc<dyn S3Client>,
+  limit: i64,
+) -> Result<(), WorkerError> {
+  let archives = select_outdated_collab_archives(pg_pool, limit)
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+  for archive in archives {
+    if !archive.in_place {
+      match s3_client.delete_blob(&archive.archive_key).await {
+        Ok(_) | Err(WorkerError::RecordNotFound(_)) => {},
+        Err(err) => {
+          warn!(
+            "[Collab Archive] failed to delete archive {}: {:?}",
+            archive.archive_key, err
+          );
+          continue;
+        },
+      }
+    }
+    delete_collab_archive(pg_pool, &archive.oid, archive.partition_key)
+      .await
+      .map_err(|err| WorkerError::Internal(err.into()))?;
+  }
+  Ok(())
+}
+
+/// Must match the key used by the collaboration server for the collabs stored in the object
+/// storage.
+fn collab_key(candidate: &CollabArchiveCandidate) -> String {
+  format!(
+    "collabs/{}/{}/encoded_collab.v1.zstd",
+    candidate.workspace_id, candidate.oid
+  )
+}
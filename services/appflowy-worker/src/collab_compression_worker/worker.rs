@@ -0,0 +1,114 @@
+use crate::error::WorkerError;
+use database::collab::{compress_stored_collab, select_uncompressed_collabs};
+use infra::env_util::get_env_var;
+use redis::aio::ConnectionManager;
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{error, info, trace, warn};
+
+/// Only one worker compresses collabs at a time.
+const COMPRESSION_LOCK_KEY: &str = "af:collab_compression:lock";
+
+#[derive(Debug, Clone)]
+pub struct CollabCompressionSetting {
+  pub enabled: bool,
+  /// Maximum number of collabs compressed in each run.
+  pub batch_size: i64,
+  pub interval: Duration,
+}
+
+impl CollabCompressionSetting {
+  pub fn from_env() -> Self {
+    Self {
+      enabled: get_env_var("APPFLOWY_WORKER_COLLAB_COMPRESSION_ENABLED", "false")
+        .parse()
+        .unwrap_or(false),
+      batch_size: get_env_var("APPFLOWY_WORKER_COLLAB_COMPRESSION_BATCH_SIZE", "500")
+        .parse()
+        .unwrap_or(500)
+        .max(1),
+      interval: Duration::from_secs(
+        get_env_var("APPFLOWY_WORKER_COLLAB_COMPRESSION_INTERVAL_SECS", "10")
+          .parse()
+          .unwrap_or(10),
+      ),
+    }
+  }
+}
+
+/// Compresses the blobs of the collabs written before the blobs were compressed on write. The
+/// collabs are walked in object id order, once all of them are compressed the walk starts over
+/// to pick up the collabs restored from an archive in the meantime.
+pub async fn run_collab_compression_worker(
+  pg_pool: PgPool,
+  mut redis_client: ConnectionManager,
+  setting: CollabCompressionSetting,
+) -> Result<(), WorkerError> {
+  if !setting.enabled {
+    return Ok(());
+  }
+  info!("Starting collab compression worker");
+  let mut after_oid: Option<String> = None;
+  let mut interval = interval(setting.interval);
+  interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+  loop {
+    interval.tick().await;
+    let locked: Option<String> = redis::cmd("SET")
+      .arg(COMPRESSION_LOCK_KEY)
+      .arg(1)
+      .arg("NX")
+      .arg("EX")
+      .arg(setting.interval.as_secs().max(1))
+      .query_async(&mut redis_client)
+      .await
+      .unwrap_or_else(|err| {
+        error!("Failed to acquire collab compression lock: {:?}", err);
+        None
+      });
+    if locked.is_none() {
+      trace!("[Collab Compression] another worker is compressing collabs");
+      continue;
+    }
+
+    match compress_collabs(&pg_pool, after_oid.as_deref(), setting.batch_size).await {
+      Ok((last_oid, count)) => {
+        if count > 0 {
+          info!("[Collab Compression] compressed {} collabs", count);
+        }
+        after_oid = last_oid;
+      },
+      Err(err) => error!("[Collab Compression] failed to compress collabs: {:?}", err),
+    }
+  }
+}
+
+/// Compresses a batch of collabs. Returns the object id to continue from, None when the end of
+/// the table has been reached, and the number of compressed collabs.
+async fn compress_collabs(
+  pg_pool: &PgPool,
+  after_oid: Option<&str>,
+  batch_size: i64,
+) -> Result<(Option<String>, usize), WorkerError> {
+  let collabs = select_uncompressed_collabs(pg_pool, after_oid, batch_size)
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+  let last_oid = if collabs.len() as i64 == batch_size {
+    collabs.last().map(|collab| collab.oid.clone())
+  } else {
+    None
+  };
+
+  let mut count = 0;
+  for collab in &collabs {
+    match compress_stored_collab(pg_pool, collab).await {
+      Ok(true) => count += 1,
+      Ok(false) => {},
+      Err(err) => warn!(
+        "[Collab Compression] failed to compress collab {}: {:?}",
+        collab.oid, err
+      ),
+    }
+  }
+  Ok((last_oid, count))
+}
@@ -0,0 +1,75 @@
+use crate::error::WorkerError;
+use collab_entity::CollabType;
+use database::collab::{create_workspace_hash_partition, WORKSPACE_HASH_PARTITIONED_TABLES};
+use infra::env_util::get_env_var;
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{error, info};
+
+const PARTITIONED_COLLAB_TYPES: [CollabType; 6] = [
+  CollabType::Document,
+  CollabType::Database,
+  CollabType::WorkspaceDatabase,
+  CollabType::Folder,
+  CollabType::DatabaseRow,
+  CollabType::UserAwareness,
+];
+
+#[derive(Debug, Clone)]
+pub struct CollabPartitionSetting {
+  pub enabled: bool,
+  /// Number of workspace hash partitions of the partitions created by the worker. The existing
+  /// partitions keep the number they were created with.
+  pub hash_partitions: i32,
+  pub interval: Duration,
+}
+
+impl CollabPartitionSetting {
+  pub fn from_env() -> Self {
+    Self {
+      enabled: get_env_var("APPFLOWY_WORKER_COLLAB_PARTITION_ENABLED", "true")
+        .parse()
+        .unwrap_or(true),
+      hash_partitions: get_env_var("APPFLOWY_WORKER_COLLAB_PARTITION_HASH_PARTITIONS", "16")
+        .parse()
+        .unwrap_or(16)
+        .max(1),
+      interval: Duration::from_secs(
+        get_env_var("APPFLOWY_WORKER_COLLAB_PARTITION_INTERVAL_SECS", "86400")
+          .parse()
+          .unwrap_or(86400),
+      ),
+    }
+  }
+}
+
+/// Makes sure every collab type has its partition, split by workspace hash, in the tables
+/// partitioned by collab type. Writes of a collab type without partition would fail.
+pub async fn run_collab_partition_worker(
+  pg_pool: PgPool,
+  setting: CollabPartitionSetting,
+) -> Result<(), WorkerError> {
+  if !setting.enabled {
+    return Ok(());
+  }
+  info!("Starting collab partition worker");
+  let mut interval = interval(setting.interval);
+  interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+  loop {
+    interval.tick().await;
+    for table in WORKSPACE_HASH_PARTITIONED_TABLES {
+      for collab_type in PARTITIONED_COLLAB_TYPES.iter() {
+        if let Err(err) =
+          create_workspace_hash_partition(&pg_pool, table, collab_type, setting.hash_partitions)
+            .await
+        {
+          error!(
+            "[Collab Partition] failed to create the {} partition of {}: {:?}",
+            collab_type, table, err
+          );
+        }
+      }
+    }
+  }
+}
@@ -1,5 +1,7 @@
 use anyhow::{Context, Error};
-use infra::env_util::get_env_var;
+use database::file::gcs_client_impl::GCS_XML_API_ENDPOINT;
+use database::file::BlobStorageBackend;
+use infra::env_util::{get_env_var, get_env_var_opt};
 use mailer::config::MailerSetting;
 use secrecy::Secret;
 use serde::Deserialize;
@@ -12,6 +14,7 @@ pub struct Config {
   pub redis_url: String,
   pub db_settings: DatabaseSetting,
   pub s3_setting: S3Setting,
+  pub blob_storage: BlobStorageSetting,
   pub mailer: MailerSetting,
 }
 
@@ -45,6 +48,34 @@ impl Config {
         bucket: get_env_var("APPFLOWY_S3_BUCKET", "appflowy"),
         region: get_env_var("APPFLOWY_S3_REGION", ""),
       },
+      blob_storage: BlobStorageSetting {
+        backend: get_env_var("APPFLOWY_BLOB_STORAGE_BACKEND", "s3")
+          .as_str()
+          .try_into()
+          .context("fail to get APPFLOWY_BLOB_STORAGE_BACKEND")?,
+        gcs_endpoint: get_env_var("APPFLOWY_GCS_ENDPOINT", GCS_XML_API_ENDPOINT),
+        azure: AzureBlobSetting {
+          account_name: get_env_var("APPFLOWY_AZURE_STORAGE_ACCOUNT", ""),
+          account_key: get_env_var("APPFLOWY_AZURE_STORAGE_KEY", "").into(),
+          container: get_env_var("APPFLOWY_AZURE_STORAGE_CONTAINER", "appflowy"),
+          endpoint: Some(get_env_var("APPFLOWY_AZURE_STORAGE_ENDPOINT", ""))
+            .filter(|endpoint| !endpoint.is_empty()),
+        },
+        local: LocalFsBlobSetting {
+          root: get_env_var("APPFLOWY_LOCAL_STORAGE_ROOT", "./data/blobs"),
+          signing_key: get_env_var_opt("APPFLOWY_LOCAL_STORAGE_SIGNING_KEY")
+            .unwrap_or_else(|| get_env_var("APPFLOWY_GOTRUE_JWT_SECRET", "hello456"))
+            .into(),
+          public_url: get_env_var("APPFLOWY_LOCAL_STORAGE_PUBLIC_URL", "http://localhost:8000"),
+        },
+        workspace_storage_key: get_env_var_opt("APPFLOWY_WORKSPACE_STORAGE_KEY").map(Secret::new),
+        workspace_storage_cache_ttl_secs: get_env_var(
+          "APPFLOWY_WORKSPACE_STORAGE_CACHE_TTL_SECS",
+          "60",
+        )
+        .parse()
+        .context("fail to get APPFLOWY_WORKSPACE_STORAGE_CACHE_TTL_SECS")?,
+      },
       mailer: MailerSetting {
         smtp_host: get_env_var("APPFLOWY_MAILER_SMTP_HOST", "smtp.gmail.com"),
         smtp_port: get_env_var("APPFLOWY_MAILER_SMTP_PORT", "465").parse()?,
@@ -134,3 +165,34 @@ pub struct S3Setting {
   pub bucket: String,
   pub region: String,
 }
+
+/// Selects where blobs are stored. The [S3Setting] bucket and keys are used by the S3 and GCS
+/// backends, GCS expecting HMAC keys.
+#[derive(Clone, Debug)]
+pub struct BlobStorageSetting {
+  pub backend: BlobStorageBackend,
+  pub gcs_endpoint: String,
+  pub azure: AzureBlobSetting,
+  pub local: LocalFsBlobSetting,
+  /// Base64 encoded 32 bytes key encrypting the storage settings of the workspaces that use their
+  /// own bucket. Per-workspace storage is disabled when not set.
+  pub workspace_storage_key: Option<Secret<String>>,
+  pub workspace_storage_cache_ttl_secs: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct AzureBlobSetting {
+  pub account_name: String,
+  pub account_key: Secret<String>,
+  pub container: String,
+  /// Overrides the account endpoint, e.g. to use the Azurite emulator.
+  pub endpoint: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LocalFsBlobSetting {
+  /// Directory holding the blobs, shared with the appflowy_cloud server.
+  pub root: String,
+  pub signing_key: Secret<String>,
+  pub public_url: String,
+}
@@ -1,3 +1,4 @@
+use app_error::AppError;
 pub use collab_importer::error::ImporterError as CollabImporterError;
 #[derive(thiserror::Error, Debug)]
 pub enum WorkerError {
@@ -49,10 +50,66 @@ pub enum ImportError {
     max_size_in_mb: f64,
   },
 
+  #[error("Import archive exceeds limit: {0}")]
+  ArchiveLimitExceeded(String),
+
+  #[error("Import task was cancelled")]
+  Cancelled,
+
+  #[error("Invalid AppFlowy archive: {0}")]
+  UnsupportedArchive(String),
+
   #[error(transparent)]
   Internal(#[from] anyhow::Error),
 }
 
+impl From<WorkerError> for AppError {
+  fn from(err: WorkerError) -> AppError {
+    match err {
+      WorkerError::ZipError(err) => AppError::InvalidRequest(err.to_string()),
+      WorkerError::RecordNotFound(msg) => AppError::RecordNotFound(msg),
+      WorkerError::IOError(err) => AppError::IOError(err),
+      WorkerError::ImportError(err) => err.into(),
+      WorkerError::S3ServiceUnavailable(reason) => AppError::DependencyUnavailable {
+        service: "s3".to_string(),
+        reason,
+      },
+      WorkerError::StreamGroupNotExist(reason) => AppError::DependencyUnavailable {
+        service: "redis".to_string(),
+        reason,
+      },
+      WorkerError::Internal(err) => AppError::Internal(err),
+    }
+  }
+}
+
+impl From<ImportError> for AppError {
+  fn from(err: ImportError) -> AppError {
+    match err {
+      ImportError::ImportCollabError(CollabImporterError::Internal(err)) => AppError::Internal(err),
+      ImportError::ImportCollabError(CollabImporterError::FileNotFound)
+      | ImportError::UploadFileNotFound => AppError::RecordNotFound(err.to_string()),
+      ImportError::ImportCollabError(_)
+      | ImportError::CannotOpenWorkspace(_)
+      | ImportError::UnZipFileError(_)
+      | ImportError::UploadFileExpire
+      | ImportError::UpgradeToLatestVersion(_)
+      | ImportError::UnsupportedArchive(_) => AppError::InvalidRequest(err.to_string()),
+      ImportError::UploadFileTooLarge {
+        file_size_in_mb,
+        max_size_in_mb,
+      } => AppError::QuotaExceeded {
+        resource: "import_file_size_mb".to_string(),
+        limit: max_size_in_mb.ceil() as i64,
+        usage: file_size_in_mb.ceil() as i64,
+      },
+      ImportError::ArchiveLimitExceeded(reason) => AppError::PayloadTooLarge(reason),
+      ImportError::Cancelled => AppError::InvalidRequest(err.to_string()),
+      ImportError::Internal(err) => AppError::Internal(err),
+    }
+  }
+}
+
 impl From<WorkerError> for ImportError {
   fn from(err: WorkerError) -> ImportError {
     match err {
@@ -71,6 +128,14 @@ impl ImportError {
       _ => false,
     }
   }
+  /// Errors that will not go away by downloading and unzipping the file again.
+  pub fn is_limit_exceeded(&self) -> bool {
+    matches!(
+      self,
+      ImportError::UploadFileTooLarge { .. } | ImportError::ArchiveLimitExceeded(_)
+    )
+  }
+
   pub fn report(&self, task_id: &str) -> (String, String) {
     match self {
       ImportError::ImportCollabError(error) => match error {
@@ -214,6 +279,31 @@ impl ImportError {
           format!("Task ID: {} - Upload file too large: {} MB", task_id, file_size_in_mb),
         )
       }
+      ImportError::ArchiveLimitExceeded(reason) => {
+        (
+          format!(
+            "Task ID: {} - The file can not be imported because {}. Please split the export into smaller files and try again.",
+            task_id,
+            reason,
+          ),
+          format!("Task ID: {} - Archive limit exceeded: {}", task_id, reason),
+        )
+      }
+      ImportError::Cancelled => {
+        (
+          format!("Task ID: {} - The import was cancelled.", task_id),
+          format!("Task ID: {} - Cancelled", task_id),
+        )
+      }
+      ImportError::UnsupportedArchive(reason) => {
+        (
+          format!(
+            "Task ID: {} - The file is not a valid AppFlowy export. Please export the workspace again and retry.",
+            task_id
+          ),
+          format!("Task ID: {} - Invalid AppFlowy archive: {}", task_id, reason),
+        )
+      }
     }
   }
 }
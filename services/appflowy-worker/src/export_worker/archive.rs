@@ -0,0 +1,244 @@
+use crate::error::WorkerError;
+use crate::s3_client::S3Client;
+use crate::workspace_clone_worker::cloner::{CollabSource, WorkspaceCollabSource};
+use crate::workspace_clone_worker::worker::blob_object_key;
+use async_zip::base::write::ZipFileWriter;
+use async_zip::{Compression, ZipEntryBuilder};
+use collab_entity::CollabType;
+use database::collab::select_workspace_collab_oids_by_type;
+use database::resource_usage::get_all_workspace_blob_metadata;
+use database::workspace::select_workspace_name_from_workspace_id;
+use futures::AsyncWriteExt;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::fs::File;
+use tokio_util::compat::{Compat, TokioAsyncWriteCompatExt};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Version of the archive format written by this worker. Archives with a greater version are
+/// rejected by the import.
+pub const ARCHIVE_VERSION: u32 = 1;
+/// Directory holding the content of the archive, the import considers the first directory of an
+/// archive as its root.
+pub const ARCHIVE_ROOT_DIR: &str = "appflowy_workspace";
+pub const ARCHIVE_MANIFEST_FILE: &str = "manifest.json";
+const COLLABS_DIR: &str = "collabs";
+const BLOBS_DIR: &str = "blobs";
+
+/// Describes the content of an archive. The collabs are stored under `collabs/{object_id}`,
+/// encoded with [collab::entity::EncodedCollab::encode_to_bytes], and the files under
+/// `blobs/{file_id}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+  pub version: u32,
+  /// Version of the worker that wrote the archive.
+  pub exported_by: String,
+  pub exported_at: i64,
+  pub workspace_id: Uuid,
+  pub workspace_name: String,
+  pub workspace_database_id: Option<String>,
+  pub collabs: Vec<ArchiveCollab>,
+  pub blobs: Vec<ArchiveBlob>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveCollab {
+  pub object_id: String,
+  pub collab_type: CollabType,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveBlob {
+  /// Id of the file in the blob metadata, `{parent_dir}_{file_id}` for the files uploaded to a
+  /// parent directory.
+  pub file_id: String,
+  pub file_type: String,
+  pub file_size: i64,
+}
+
+pub fn collab_entry_name(object_id: &str) -> String {
+  format!("{}/{}", COLLABS_DIR, object_id)
+}
+
+pub fn blob_entry_name(file_id: &str) -> String {
+  format!("{}/{}", BLOBS_DIR, file_id)
+}
+
+/// The ids are used as file names in the archive, the ones that could escape their directory
+/// are rejected.
+pub fn is_valid_entry_id(id: &str) -> bool {
+  !id.is_empty() && id != "." && id != ".." && !id.contains(['/', '\\', '\0'])
+}
+
+/// Writes the collabs and the files of the workspace to a zip archive at `path`.
+pub async fn write_workspace_archive(
+  pg_pool: &PgPool,
+  s3_client: &Arc<dyn S3Client>,
+  workspace_id: &Uuid,
+  path: &Path,
+) -> Result<ArchiveManifest, WorkerError> {
+  let workspace_name = select_workspace_name_from_workspace_id(pg_pool, workspace_id)
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?
+    .unwrap_or_default();
+  let source = WorkspaceCollabSource::new(pg_pool.clone(), s3_client.clone(), *workspace_id);
+  let mut writer = ArchiveWriter::create(path).await?;
+
+  let mut collabs = vec![(workspace_id.to_string(), CollabType::Folder)];
+  let workspace_database_id = source.workspace_database_id().await?;
+  if let Some(w_database_id) = &workspace_database_id {
+    collabs.push((w_database_id.clone(), CollabType::WorkspaceDatabase));
+  }
+  for collab_type in [
+    CollabType::Document,
+    CollabType::Database,
+    CollabType::DatabaseRow,
+  ] {
+    let oids = select_workspace_collab_oids_by_type(pg_pool, workspace_id, &collab_type)
+      .await
+      .map_err(|err| WorkerError::Internal(err.into()))?;
+    collabs.extend(oids.into_iter().map(|oid| (oid, collab_type.clone())));
+  }
+
+  let mut manifest = ArchiveManifest {
+    version: ARCHIVE_VERSION,
+    exported_by: env!("CARGO_PKG_VERSION").to_string(),
+    exported_at: chrono::Utc::now().timestamp(),
+    workspace_id: *workspace_id,
+    workspace_name,
+    workspace_database_id: None,
+    collabs: vec![],
+    blobs: vec![],
+  };
+  for (object_id, collab_type) in collabs {
+    if !is_valid_entry_id(&object_id) {
+      warn!("[Export] skip collab with invalid id {:?}", object_id);
+      continue;
+    }
+    let encoded_collab = match source.read_collab(&object_id, &collab_type).await {
+      Ok(encoded_collab) => encoded_collab,
+      Err(WorkerError::RecordNotFound(_)) if collab_type != CollabType::Folder => {
+        warn!("[Export] {:?} {} not found", collab_type, object_id);
+        continue;
+      },
+      Err(err) => return Err(err),
+    };
+    let bytes = encoded_collab
+      .encode_to_bytes()
+      .map_err(|err| WorkerError::Internal(err.into()))?;
+    writer
+      .write_entry(&collab_entry_name(&object_id), &bytes)
+      .await?;
+    if collab_type == CollabType::WorkspaceDatabase {
+      manifest.workspace_database_id = Some(object_id.clone());
+    }
+    manifest.collabs.push(ArchiveCollab {
+      object_id,
+      collab_type,
+    });
+  }
+
+  let blobs = get_all_workspace_blob_metadata(pg_pool, workspace_id)
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+  for blob in blobs {
+    if !is_valid_entry_id(&blob.file_id) {
+      warn!("[Export] skip file with invalid id {:?}", blob.file_id);
+      continue;
+    }
+    let key = blob_object_key(workspace_id, &blob.file_id);
+    let mut resp = match s3_client.get_blob_stream(&key).await {
+      Ok(resp) => resp,
+      Err(WorkerError::RecordNotFound(_)) => {
+        warn!("[Export] file {} not found", key);
+        continue;
+      },
+      Err(err) => return Err(err),
+    };
+    writer
+      .copy_entry(&blob_entry_name(&blob.file_id), &mut resp.stream)
+      .await?;
+    manifest.blobs.push(ArchiveBlob {
+      file_id: blob.file_id,
+      file_type: blob.file_type,
+      file_size: blob.file_size,
+    });
+  }
+
+  let manifest_bytes =
+    serde_json::to_vec_pretty(&manifest).map_err(|err| WorkerError::Internal(err.into()))?;
+  writer
+    .write_entry(ARCHIVE_MANIFEST_FILE, &manifest_bytes)
+    .await?;
+  writer.close().await?;
+  info!(
+    "[Export] archived workspace {}: {} collabs, {} files",
+    workspace_id,
+    manifest.collabs.len(),
+    manifest.blobs.len()
+  );
+  Ok(manifest)
+}
+
+struct ArchiveWriter {
+  inner: ZipFileWriter<Compat<File>>,
+}
+
+impl ArchiveWriter {
+  async fn create(path: &Path) -> Result<Self, WorkerError> {
+    let mut inner = ZipFileWriter::new(File::create(path).await?.compat_write());
+    let root = ZipEntryBuilder::new(format!("{}/", ARCHIVE_ROOT_DIR).into(), Compression::Stored);
+    inner.write_entry_whole(root, &[]).await?;
+    Ok(Self { inner })
+  }
+
+  async fn write_entry(&mut self, name: &str, data: &[u8]) -> Result<(), WorkerError> {
+    let entry = ZipEntryBuilder::new(
+      format!("{}/{}", ARCHIVE_ROOT_DIR, name).into(),
+      Compression::Deflate,
+    );
+    self.inner.write_entry_whole(entry, data).await?;
+    Ok(())
+  }
+
+  async fn copy_entry<R>(&mut self, name: &str, reader: R) -> Result<(), WorkerError>
+  where
+    R: futures::AsyncRead + Unpin,
+  {
+    let entry = ZipEntryBuilder::new(
+      format!("{}/{}", ARCHIVE_ROOT_DIR, name).into(),
+      Compression::Deflate,
+    );
+    let mut entry_writer = self.inner.write_entry_stream(entry).await?;
+    futures::io::copy(reader, &mut entry_writer).await?;
+    entry_writer.close().await?;
+    Ok(())
+  }
+
+  async fn close(self) -> Result<(), WorkerError> {
+    let mut file = self.inner.close().await?;
+    file.close().await?;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn entry_ids_stay_in_their_directory() {
+    assert!(is_valid_entry_id("3f1a2b6c-1d2e-4f5a-9b8c-7d6e5f4a3b2c"));
+    assert!(is_valid_entry_id(
+      "3f1a2b6c-1d2e-4f5a-9b8c-7d6e5f4a3b2c_image.png"
+    ));
+    for id in ["", ".", "..", "../folder", "a/b", "a\\b", "a\0b"] {
+      assert!(!is_valid_entry_id(id), "{:?} should be rejected", id);
+    }
+    assert_eq!(collab_entry_name("abc"), "collabs/abc");
+    assert_eq!(blob_entry_name("abc"), "blobs/abc");
+  }
+}
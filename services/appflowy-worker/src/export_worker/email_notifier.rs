@@ -0,0 +1,40 @@
+use crate::export_worker::worker::{ExportNotifier, ExportResult};
+use crate::mailer::{AFWorkerMailer, EXPORT_FAIL_TEMPLATE, EXPORT_SUCCESS_TEMPLATE};
+use axum::async_trait;
+use tracing::{error, trace};
+
+pub struct ExportEmailNotifier(AFWorkerMailer);
+impl ExportEmailNotifier {
+  pub fn new(mailer: AFWorkerMailer) -> Self {
+    Self(mailer)
+  }
+}
+
+#[async_trait]
+impl ExportNotifier for ExportEmailNotifier {
+  async fn notify_finished(&self, result: ExportResult) {
+    trace!(
+      "[Export]: sending export report email to {}, params: {:?}",
+      result.user_email,
+      result,
+    );
+    let template_name = if result.is_success {
+      EXPORT_SUCCESS_TEMPLATE
+    } else {
+      EXPORT_FAIL_TEMPLATE
+    };
+    if let Err(err) = self
+      .0
+      .send_email_template(
+        Some(result.user_name),
+        &result.user_email,
+        template_name,
+        result.value,
+        "Notification: Export Report",
+      )
+      .await
+    {
+      error!("Failed to send export report email: {}", err);
+    }
+  }
+}
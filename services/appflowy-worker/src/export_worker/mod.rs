@@ -0,0 +1,4 @@
+pub mod archive;
+pub mod email_notifier;
+pub mod renderer;
+pub mod worker;
@@ -0,0 +1,91 @@
+use crate::error::WorkerError;
+use anyhow::anyhow;
+use infra::env_util::get_env_var;
+use std::path::Path;
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::trace;
+
+/// The page is written by the user, so the browser keeps its sandbox. The page itself forbids
+/// any network access with its content security policy.
+const DEFAULT_PDF_RENDER_COMMAND: &str =
+  "chromium --headless --disable-gpu --no-pdf-header-footer --print-to-pdf={output} {input}";
+
+/// Converts the HTML rendition of a document into a PDF by running an external renderer, so that
+/// the worker doesn't have to embed a layout engine. The command is configured with
+/// `APPFLOWY_WORKER_PDF_RENDER_COMMAND`, where `{input}` and `{output}` are replaced with the
+/// path of the HTML file and the path of the PDF file to create.
+#[derive(Debug, Clone)]
+pub struct PdfRenderer {
+  command: String,
+  timeout: Duration,
+}
+
+impl PdfRenderer {
+  pub fn from_env() -> Self {
+    let timeout_secs = get_env_var("APPFLOWY_WORKER_PDF_RENDER_TIMEOUT_SECS", "120")
+      .parse::<u64>()
+      .unwrap_or(120);
+    Self {
+      command: get_env_var(
+        "APPFLOWY_WORKER_PDF_RENDER_COMMAND",
+        DEFAULT_PDF_RENDER_COMMAND,
+      ),
+      timeout: Duration::from_secs(timeout_secs),
+    }
+  }
+
+  pub fn command_args(&self, input: &Path, output: &Path) -> Vec<String> {
+    let input = input.to_string_lossy();
+    let output = output.to_string_lossy();
+    self
+      .command
+      .split_whitespace()
+      .map(|arg| arg.replace("{input}", &input).replace("{output}", &output))
+      .collect()
+  }
+
+  pub async fn render(&self, input: &Path, output: &Path) -> Result<(), WorkerError> {
+    let args = self.command_args(input, output);
+    let (program, args) = args
+      .split_first()
+      .ok_or_else(|| anyhow!("APPFLOWY_WORKER_PDF_RENDER_COMMAND is empty"))?;
+    trace!("[Export] rendering pdf: {} {:?}", program, args);
+
+    let result = tokio::time::timeout(
+      self.timeout,
+      Command::new(program).args(args).kill_on_drop(true).output(),
+    )
+    .await
+    .map_err(|_| anyhow!("pdf rendering timed out after {:?}", self.timeout))??;
+    if !result.status.success() {
+      return Err(WorkerError::Internal(anyhow!(
+        "pdf renderer exited with {}: {}",
+        result.status,
+        String::from_utf8_lossy(&result.stderr)
+      )));
+    }
+    if !tokio::fs::try_exists(output).await? {
+      return Err(WorkerError::Internal(anyhow!(
+        "pdf renderer did not create {:?}",
+        output
+      )));
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn substitute_paths_in_command() {
+    let renderer = PdfRenderer {
+      command: "typst-html {input} --out={output}".to_string(),
+      timeout: Duration::from_secs(1),
+    };
+    let args = renderer.command_args(Path::new("/tmp/a.html"), Path::new("/tmp/a.pdf"));
+    assert_eq!(args, vec!["typst-html", "/tmp/a.html", "--out=/tmp/a.pdf"]);
+  }
+}
@@ -0,0 +1,267 @@
+use crate::error::WorkerError;
+use crate::export_worker::archive::write_workspace_archive;
+use crate::export_worker::renderer::PdfRenderer;
+use crate::import_worker::worker::ensure_consumer_group;
+use crate::s3_client::S3Client;
+use anyhow::anyhow;
+use aws_sdk_s3::primitives::ByteStream;
+use axum::async_trait;
+use database::export::{
+  update_export_task_status, ExportTaskState, EXPORT_FORMAT_APPFLOWY, EXPORT_FORMAT_PDF,
+};
+use futures::AsyncReadExt;
+use redis::aio::ConnectionManager;
+use redis::streams::{StreamId, StreamReadOptions, StreamReadReply};
+use redis::{AsyncCommands, Value};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::env::temp_dir;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::fs;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{error, info, trace};
+use uuid::Uuid;
+
+pub(crate) const GROUP_NAME: &str = "export_task_group";
+const CONSUMER_NAME: &str = "appflowy_worker";
+
+/// Task pushed by the server to the export stream. For a PDF, the server renders the document to
+/// HTML and uploads it to `html_key`, the worker turns it into a PDF stored at `file_key`. For an
+/// AppFlowy archive, the worker writes the whole workspace to `file_key`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportTask {
+  pub task_id: Uuid,
+  pub uid: i64,
+  pub user_name: String,
+  pub user_email: String,
+  pub workspace_id: String,
+  pub object_id: String,
+  pub file_name: String,
+  /// Tasks pushed by older servers have no format, they are PDF exports.
+  #[serde(default = "default_export_format")]
+  pub format: String,
+  #[serde(default)]
+  pub html_key: Option<String>,
+  #[serde(alias = "pdf_key")]
+  pub file_key: String,
+  /// Presigned url of `file_key`, included in the notification.
+  pub download_url: String,
+}
+
+fn default_export_format() -> String {
+  EXPORT_FORMAT_PDF.to_string()
+}
+
+impl TryFrom<&StreamId> for ExportTask {
+  type Error = WorkerError;
+
+  fn try_from(stream_id: &StreamId) -> Result<Self, Self::Error> {
+    let task_str = match stream_id.map.get("task") {
+      Some(Value::Data(data)) => String::from_utf8_lossy(data).to_string(),
+      _ => {
+        return Err(WorkerError::Internal(anyhow!(
+          "missing task in stream entry {}",
+          stream_id.id
+        )))
+      },
+    };
+    serde_json::from_str(&task_str).map_err(|err| WorkerError::Internal(err.into()))
+  }
+}
+
+#[async_trait]
+pub trait ExportNotifier: Send + Sync + 'static {
+  async fn notify_finished(&self, result: ExportResult);
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportResult {
+  pub user_name: String,
+  pub user_email: String,
+  pub is_success: bool,
+  pub value: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportMailerParam {
+  pub task_id: String,
+  pub user_name: String,
+  pub file_name: String,
+  pub download_url: Option<String>,
+  pub error: Option<String>,
+}
+
+pub async fn run_export_worker(
+  pg_pool: PgPool,
+  mut redis_client: ConnectionManager,
+  s3_client: Arc<dyn S3Client>,
+  notifier: Arc<dyn ExportNotifier>,
+  renderer: PdfRenderer,
+  stream_name: &str,
+  tick_interval_secs: u64,
+) -> Result<(), WorkerError> {
+  info!("Starting export worker");
+  if let Err(err) = ensure_consumer_group(stream_name, GROUP_NAME, &mut redis_client).await {
+    error!("Failed to ensure consumer group: {:?}", err);
+  }
+
+  // Entries that were delivered to this consumer before a restart but never acknowledged are
+  // read with the id "0", new entries with ">".
+  let mut pending_id = Some("0");
+  let options = StreamReadOptions::default()
+    .group(GROUP_NAME, CONSUMER_NAME)
+    .count(5);
+  let mut interval = interval(Duration::from_secs(tick_interval_secs));
+  interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+  loop {
+    interval.tick().await;
+    let id = pending_id.take().unwrap_or(">");
+    let reply: StreamReadReply = match redis_client
+      .xread_options(&[stream_name], &[id], &options)
+      .await
+    {
+      Ok(reply) => reply,
+      Err(err) => {
+        error!("Failed to read export tasks from Redis stream: {:?}", err);
+        if err.code() == Some("NOGROUP") {
+          if let Err(err) = ensure_consumer_group(stream_name, GROUP_NAME, &mut redis_client).await
+          {
+            error!("Failed to ensure consumer group: {:?}", err);
+          }
+        }
+        continue;
+      },
+    };
+
+    for stream_key in reply.keys {
+      for stream_id in stream_key.ids {
+        match ExportTask::try_from(&stream_id) {
+          Ok(task) => {
+            process_task(&pg_pool, &s3_client, notifier.as_ref(), &renderer, task).await;
+          },
+          Err(err) => error!("Failed to deserialize export task: {:?}", err),
+        }
+        // Failed tasks are recorded in the database and reported to the user, retrying them
+        // wouldn't produce a different result.
+        let _: Result<(), _> = redis_client
+          .xack(stream_name, GROUP_NAME, &[&stream_id.id])
+          .await
+          .map_err(|err| error!("Failed to ack export task {}: {:?}", stream_id.id, err));
+      }
+    }
+  }
+}
+
+async fn process_task(
+  pg_pool: &PgPool,
+  s3_client: &Arc<dyn S3Client>,
+  notifier: &dyn ExportNotifier,
+  renderer: &PdfRenderer,
+  task: ExportTask,
+) {
+  trace!("[Export] processing task: {:?}", task);
+  let work_dir = temp_dir().join(format!("export_{}", task.task_id));
+  let result = match task.format.as_str() {
+    EXPORT_FORMAT_PDF => export_pdf(s3_client, renderer, &task, &work_dir).await,
+    EXPORT_FORMAT_APPFLOWY => export_archive(pg_pool, s3_client, &task, &work_dir).await,
+    format => Err(WorkerError::Internal(anyhow!(
+      "unsupported export format {}",
+      format
+    ))),
+  };
+  let _ = fs::remove_dir_all(&work_dir).await;
+
+  let (state, error) = match &result {
+    Ok(_) => (ExportTaskState::Completed, None),
+    Err(err) => {
+      error!("[Export] task {} failed: {:?}", task.task_id, err);
+      (ExportTaskState::Failed, Some(err.to_string()))
+    },
+  };
+  let file_key = result.as_ref().ok().map(|_| task.file_key.as_str());
+  if let Err(err) =
+    update_export_task_status(pg_pool, &task.task_id, state, file_key, error.as_deref()).await
+  {
+    error!("Failed to update export task {}: {:?}", task.task_id, err);
+  }
+  if let Some(html_key) = &task.html_key {
+    if let Err(err) = s3_client.delete_blob(html_key).await {
+      error!("Failed to delete {}: {:?}", html_key, err);
+    }
+  }
+
+  let param = ExportMailerParam {
+    task_id: task.task_id.to_string(),
+    user_name: task.user_name.clone(),
+    file_name: task.file_name.clone(),
+    download_url: result.is_ok().then(|| task.download_url.clone()),
+    error,
+  };
+  notifier
+    .notify_finished(ExportResult {
+      user_name: task.user_name,
+      user_email: task.user_email,
+      is_success: result.is_ok(),
+      value: serde_json::to_value(param).unwrap_or_default(),
+    })
+    .await;
+}
+
+async fn export_pdf(
+  s3_client: &Arc<dyn S3Client>,
+  renderer: &PdfRenderer,
+  task: &ExportTask,
+  work_dir: &Path,
+) -> Result<(), WorkerError> {
+  let html_key = task
+    .html_key
+    .as_deref()
+    .ok_or_else(|| WorkerError::Internal(anyhow!("missing html_key in pdf export task")))?;
+  fs::create_dir_all(work_dir).await?;
+  let mut html = Vec::new();
+  s3_client
+    .get_blob_stream(html_key)
+    .await?
+    .stream
+    .read_to_end(&mut html)
+    .await?;
+
+  let html_path = work_dir.join("document.html");
+  let pdf_path = work_dir.join("document.pdf");
+  fs::write(&html_path, html).await?;
+  renderer.render(&html_path, &pdf_path).await?;
+
+  let pdf = fs::read(&pdf_path).await?;
+  s3_client
+    .put_blob(
+      &task.file_key,
+      ByteStream::from(pdf),
+      Some("application/pdf"),
+    )
+    .await?;
+  Ok(())
+}
+
+async fn export_archive(
+  pg_pool: &PgPool,
+  s3_client: &Arc<dyn S3Client>,
+  task: &ExportTask,
+  work_dir: &Path,
+) -> Result<(), WorkerError> {
+  let workspace_id = Uuid::parse_str(&task.workspace_id)
+    .map_err(|err| WorkerError::Internal(anyhow!("invalid workspace id: {}", err)))?;
+  fs::create_dir_all(work_dir).await?;
+  let archive_path = work_dir.join("workspace.zip");
+  write_workspace_archive(pg_pool, s3_client, &workspace_id, &archive_path).await?;
+
+  let stream = ByteStream::from_path(&archive_path)
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+  s3_client
+    .put_blob(&task.file_key, stream, Some("application/zip"))
+    .await?;
+  Ok(())
+}
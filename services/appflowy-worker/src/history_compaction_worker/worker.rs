@@ -0,0 +1,128 @@
+use crate::error::WorkerError;
+use crate::metric::HistoryCompactionMetrics;
+use chrono::Utc;
+use database::history::compaction::{compact_workspace_history, select_history_compaction_targets};
+use infra::env_util::get_env_var;
+use redis::aio::ConnectionManager;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{error, info, trace};
+
+/// Only one worker runs the compaction at a time.
+const HISTORY_COMPACTION_LOCK_KEY: &str = "af:history_compaction:lock";
+
+#[derive(Debug, Clone)]
+pub struct HistoryCompactionSetting {
+  pub enabled: bool,
+  /// Number of workspaces loaded at once.
+  pub batch_size: i64,
+  pub interval: Duration,
+}
+
+impl HistoryCompactionSetting {
+  pub fn from_env() -> Self {
+    Self {
+      enabled: get_env_var("APPFLOWY_WORKER_HISTORY_COMPACTION_ENABLED", "true")
+        .parse()
+        .unwrap_or(true),
+      batch_size: get_env_var("APPFLOWY_WORKER_HISTORY_COMPACTION_BATCH_SIZE", "100")
+        .parse()
+        .unwrap_or(100),
+      interval: Duration::from_secs(
+        get_env_var("APPFLOWY_WORKER_HISTORY_COMPACTION_INTERVAL_SECS", "86400")
+          .parse()
+          .unwrap_or(86400),
+      ),
+    }
+  }
+}
+
+/// Periodically thins out the collab history of the workspaces that have the compaction enabled,
+/// which is the default. See [compact_workspace_history] for the versions that are kept.
+pub async fn run_history_compaction_worker(
+  pg_pool: PgPool,
+  mut redis_client: ConnectionManager,
+  metrics: Arc<HistoryCompactionMetrics>,
+  setting: HistoryCompactionSetting,
+) -> Result<(), WorkerError> {
+  if !setting.enabled {
+    return Ok(());
+  }
+  info!("Starting history compaction worker");
+  let mut interval = interval(setting.interval);
+  interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+  loop {
+    interval.tick().await;
+    let locked: Option<String> = redis::cmd("SET")
+      .arg(HISTORY_COMPACTION_LOCK_KEY)
+      .arg(1)
+      .arg("NX")
+      .arg("EX")
+      .arg(setting.interval.as_secs().max(1))
+      .query_async(&mut redis_client)
+      .await
+      .unwrap_or_else(|err| {
+        error!("Failed to acquire history compaction lock: {:?}", err);
+        None
+      });
+    if locked.is_none() {
+      trace!("[History Compaction] another worker is running the compaction");
+      continue;
+    }
+
+    match compact_all_workspaces(&pg_pool, &metrics, setting.batch_size).await {
+      Ok(reclaimed_bytes) => info!(
+        "[History Compaction] reclaimed {} bytes of collab history",
+        reclaimed_bytes
+      ),
+      Err(err) => error!("[History Compaction] failed to compact history: {:?}", err),
+    }
+  }
+}
+
+async fn compact_all_workspaces(
+  pg_pool: &PgPool,
+  metrics: &HistoryCompactionMetrics,
+  batch_size: i64,
+) -> Result<i64, WorkerError> {
+  let mut reclaimed_bytes = 0;
+  let mut after = None;
+  loop {
+    let targets = select_history_compaction_targets(pg_pool, after, batch_size)
+      .await
+      .map_err(|err| WorkerError::Internal(err.into()))?;
+    let is_last_batch = targets.len() < batch_size as usize;
+    after = targets.last().map(|target| target.workspace_id);
+
+    for target in targets {
+      // a failing workspace doesn't prevent the other ones from being compacted
+      match compact_workspace_history(
+        pg_pool,
+        &target.workspace_id,
+        target.hourly_days,
+        target.daily_days,
+        Utc::now().timestamp(),
+      )
+      .await
+      {
+        Ok(result) => {
+          metrics.record_compaction(&result);
+          reclaimed_bytes += result.reclaimed_bytes;
+        },
+        Err(err) => {
+          metrics.compaction_fail_count.inc();
+          error!(
+            "[History Compaction] failed to compact workspace {}: {:?}",
+            target.workspace_id, err
+          );
+        },
+      }
+    }
+
+    if is_last_batch {
+      return Ok(reclaimed_bytes);
+    }
+  }
+}
@@ -0,0 +1,85 @@
+use std::io::Cursor;
+
+use anyhow::anyhow;
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageReader, Limits};
+use serde::Deserialize;
+
+use crate::error::WorkerError;
+
+/// The server validates the dimensions before queuing the task, this only guards the worker
+/// against an original that was replaced in the bucket.
+const MAX_SOURCE_DIMENSION: u32 = 8192;
+
+/// A size generated from the original image, stored next to it under `file_id`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImageVariant {
+  pub file_id: String,
+  pub max_width: u32,
+  pub max_height: u32,
+}
+
+pub fn decode_image(data: &[u8]) -> Result<DynamicImage, WorkerError> {
+  let mut reader = ImageReader::new(Cursor::new(data)).with_guessed_format()?;
+  let mut limits = Limits::default();
+  limits.max_image_width = Some(MAX_SOURCE_DIMENSION);
+  limits.max_image_height = Some(MAX_SOURCE_DIMENSION);
+  reader.limits(limits);
+  reader
+    .decode()
+    .map_err(|err| WorkerError::Internal(anyhow!("failed to decode image: {}", err)))
+}
+
+/// Scales the image down to fit in the bounds of the variant, keeping its aspect ratio, and
+/// encodes it as webp. Images that already fit are never scaled up.
+pub fn encode_webp_variant(
+  image: &DynamicImage,
+  variant: &ImageVariant,
+) -> Result<Vec<u8>, WorkerError> {
+  let resized = if image.width() > variant.max_width || image.height() > variant.max_height {
+    image.resize(variant.max_width, variant.max_height, FilterType::Lanczos3)
+  } else {
+    image.clone()
+  };
+  // the webp encoder only supports 8 bit colors
+  let rgba = DynamicImage::ImageRgba8(resized.to_rgba8());
+  let mut data = Vec::new();
+  rgba
+    .write_with_encoder(WebPEncoder::new_lossless(&mut data))
+    .map_err(|err| WorkerError::Internal(anyhow!("failed to encode webp: {}", err)))?;
+  Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use image::{ImageFormat, RgbImage};
+
+  #[test]
+  fn variant_fits_in_bounds_and_keeps_aspect_ratio() {
+    let mut png = Vec::new();
+    DynamicImage::ImageRgb8(RgbImage::new(400, 200))
+      .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+      .unwrap();
+    let image = decode_image(&png).unwrap();
+
+    let variant = ImageVariant {
+      file_id: "100.webp".to_string(),
+      max_width: 100,
+      max_height: 100,
+    };
+    let webp = encode_webp_variant(&image, &variant).unwrap();
+    let resized = decode_image(&webp).unwrap();
+    assert_eq!((resized.width(), resized.height()), (100, 50));
+
+    let variant = ImageVariant {
+      file_id: "1000.webp".to_string(),
+      max_width: 1000,
+      max_height: 1000,
+    };
+    let webp = encode_webp_variant(&image, &variant).unwrap();
+    let not_upscaled = decode_image(&webp).unwrap();
+    assert_eq!((not_upscaled.width(), not_upscaled.height()), (400, 200));
+  }
+}
@@ -0,0 +1,172 @@
+use crate::error::WorkerError;
+use crate::image_asset_worker::processor::{decode_image, encode_webp_variant, ImageVariant};
+use crate::import_worker::worker::ensure_consumer_group;
+use crate::s3_client::S3Client;
+use anyhow::anyhow;
+use aws_sdk_s3::primitives::ByteStream;
+use database::pg_row::AFBlobStatus;
+use database::resource_usage::insert_blob_metadata;
+use futures::AsyncReadExt;
+use redis::aio::ConnectionManager;
+use redis::streams::{StreamId, StreamReadOptions, StreamReadReply};
+use redis::{AsyncCommands, Value};
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{error, info, trace};
+use uuid::Uuid;
+
+pub(crate) const GROUP_NAME: &str = "image_asset_task_group";
+const CONSUMER_NAME: &str = "appflowy_worker";
+const WEBP_CONTENT_TYPE: &str = "image/webp";
+
+/// Task pushed by the server once it stored the original of an uploaded icon or cover under
+/// `original_key`. The variants are stored next to it, as the `{asset_id}/{file_id}` blobs of the
+/// workspace.
+#[derive(Debug, Clone, Deserialize)]
+struct ImageAssetTask {
+  asset_id: Uuid,
+  workspace_id: Uuid,
+  uid: i64,
+  original_key: String,
+  variants: Vec<ImageVariant>,
+}
+
+impl TryFrom<&StreamId> for ImageAssetTask {
+  type Error = WorkerError;
+
+  fn try_from(stream_id: &StreamId) -> Result<Self, Self::Error> {
+    let task_str = match stream_id.map.get("task") {
+      Some(Value::Data(data)) => String::from_utf8_lossy(data).to_string(),
+      _ => {
+        return Err(WorkerError::Internal(anyhow!(
+          "missing task in stream entry {}",
+          stream_id.id
+        )))
+      },
+    };
+    serde_json::from_str(&task_str).map_err(|err| WorkerError::Internal(err.into()))
+  }
+}
+
+/// Generates the webp sizes of the icons and cover images uploaded to the workspaces and pages.
+pub async fn run_image_asset_worker(
+  pg_pool: PgPool,
+  mut redis_client: ConnectionManager,
+  s3_client: Arc<dyn S3Client>,
+  stream_name: &str,
+  tick_interval_secs: u64,
+) -> Result<(), WorkerError> {
+  info!("Starting image asset worker");
+  if let Err(err) = ensure_consumer_group(stream_name, GROUP_NAME, &mut redis_client).await {
+    error!("Failed to ensure consumer group: {:?}", err);
+  }
+
+  let mut pending_id = Some("0");
+  let options = StreamReadOptions::default()
+    .group(GROUP_NAME, CONSUMER_NAME)
+    .count(5);
+  let mut interval = interval(Duration::from_secs(tick_interval_secs));
+  interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+  loop {
+    interval.tick().await;
+    let id = pending_id.take().unwrap_or(">");
+    let reply: StreamReadReply = match redis_client
+      .xread_options(&[stream_name], &[id], &options)
+      .await
+    {
+      Ok(reply) => reply,
+      Err(err) => {
+        error!(
+          "Failed to read image asset tasks from Redis stream: {:?}",
+          err
+        );
+        if err.code() == Some("NOGROUP") {
+          if let Err(err) = ensure_consumer_group(stream_name, GROUP_NAME, &mut redis_client).await
+          {
+            error!("Failed to ensure consumer group: {:?}", err);
+          }
+        }
+        continue;
+      },
+    };
+
+    for stream_key in reply.keys {
+      for stream_id in stream_key.ids {
+        match ImageAssetTask::try_from(&stream_id) {
+          Ok(task) => {
+            if let Err(err) = process_image_asset(&pg_pool, &s3_client, &task).await {
+              error!(
+                "[Image Asset] asset {} of workspace {} failed: {:?}",
+                task.asset_id, task.workspace_id, err
+              );
+            }
+          },
+          Err(err) => error!("Failed to deserialize image asset task: {:?}", err),
+        }
+        // The variants that failed stay missing, the user uploads the image again to retry.
+        let _: Result<(), _> = redis_client
+          .xack(stream_name, GROUP_NAME, &[&stream_id.id])
+          .await
+          .map_err(|err| error!("Failed to ack image asset task: {:?}", err));
+      }
+    }
+  }
+}
+
+async fn process_image_asset(
+  pg_pool: &PgPool,
+  s3_client: &Arc<dyn S3Client>,
+  task: &ImageAssetTask,
+) -> Result<(), WorkerError> {
+  trace!("[Image Asset] processing task: {:?}", task);
+  let mut original = Vec::new();
+  s3_client
+    .get_blob_stream(&task.original_key)
+    .await?
+    .stream
+    .read_to_end(&mut original)
+    .await?;
+
+  // decoding and encoding are CPU bound
+  let variants = task.variants.clone();
+  let encoded = tokio::task::spawn_blocking(move || {
+    let image = decode_image(&original)?;
+    variants
+      .into_iter()
+      .map(|variant| encode_webp_variant(&image, &variant).map(|data| (variant.file_id, data)))
+      .collect::<Result<Vec<_>, WorkerError>>()
+  })
+  .await
+  .map_err(|err| WorkerError::Internal(err.into()))??;
+
+  for (file_id, data) in encoded {
+    let object_key = format!("{}/{}/{}", task.workspace_id, task.asset_id, file_id);
+    let file_size = data.len();
+    s3_client
+      .put_blob(&object_key, ByteStream::from(data), Some(WEBP_CONTENT_TYPE))
+      .await?;
+    // the blob metadata makes the variant readable with the file storage API
+    insert_blob_metadata(
+      pg_pool,
+      &format!("{}_{}", task.asset_id, file_id),
+      &task.workspace_id,
+      WEBP_CONTENT_TYPE,
+      file_size,
+      Some(task.uid),
+      AFBlobStatus::Ok,
+    )
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+  }
+  info!(
+    "[Image Asset] generated {} sizes of asset {} of workspace {}",
+    task.variants.len(),
+    task.asset_id,
+    task.workspace_id
+  );
+  Ok(())
+}
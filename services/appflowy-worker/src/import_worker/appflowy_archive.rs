@@ -0,0 +1,475 @@
+use crate::error::{ImportError, WorkerError};
+use crate::export_worker::archive::{
+  blob_entry_name, collab_entry_name, is_valid_entry_id, ArchiveBlob, ArchiveManifest,
+  ARCHIVE_MANIFEST_FILE, ARCHIVE_ROOT_DIR, ARCHIVE_VERSION,
+};
+use crate::import_worker::task_queue::EncodedCollabCache;
+use crate::import_worker::worker::{record_import_stage, NotionImportTask};
+use crate::metric::{ImportMetrics, ImportStage};
+use crate::s3_client::S3Client;
+use crate::workspace_clone_worker::cloner::{CollabSource, WorkspaceCloner};
+use crate::workspace_clone_worker::worker::blob_object_key;
+use anyhow::anyhow;
+use aws_sdk_s3::primitives::ByteStream;
+use axum::async_trait;
+use collab::entity::EncodedCollab;
+use collab_entity::CollabType;
+use database::billing::select_workspace_subscriptions;
+use database::collab::insert_into_af_collab_bulk_for_user;
+use database::resource_usage::{get_workspace_usage_size, insert_blob_metadata_rows};
+use database::workspace::{
+  transition_import_task_status, update_import_task_metadata,
+  update_updated_at_of_workspace_with_uid, update_workspace_status, ImportTaskState,
+};
+use database_entity::dto::{ImportSkippedItem, ImportSummary, IMPORT_SUMMARY_KEY};
+use futures::{stream, StreamExt};
+use infra::env_util::get_env_var;
+use serde_json::json;
+use shared_entity::dto::billing_dto::{SubscriptionPlan, SubscriptionStatus};
+use sqlx::types::chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::io::ErrorKind;
+use std::ops::DerefMut;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::fs;
+use tracing::{error, info, trace, warn};
+use uuid::Uuid;
+
+/// The types of the files of an archive that are served as they are. The manifest of an archive
+/// can't be trusted, the other files are stored as binary files so that, for example, an html file
+/// is never rendered by a browser.
+const ALLOWED_BLOB_FILE_TYPES: [&str; 16] = [
+  "image/png",
+  "image/jpeg",
+  "image/gif",
+  "image/webp",
+  "image/bmp",
+  "video/mp4",
+  "video/webm",
+  "video/quicktime",
+  "audio/mpeg",
+  "audio/wav",
+  "audio/ogg",
+  "application/pdf",
+  "application/zip",
+  "application/json",
+  "text/plain",
+  "text/csv",
+];
+const DEFAULT_BLOB_FILE_TYPE: &str = "application/octet-stream";
+/// Same default as the server, see `APPFLOWY_BILLING_FREE_STORAGE_LIMIT`.
+const DEFAULT_FREE_STORAGE_LIMIT: u64 = 5 * 1024 * 1024 * 1024;
+
+/// Reads the collabs of a workspace from an unzipped archive written by the export worker.
+pub struct ArchiveCollabSource {
+  dir: PathBuf,
+  manifest: ArchiveManifest,
+}
+
+impl ArchiveCollabSource {
+  pub async fn open(unzip_dir: &Path) -> Result<Self, ImportError> {
+    // The unzipped directory is the root of the archive, unless the archive was zipped again
+    // with its root directory.
+    let mut dir = unzip_dir.to_path_buf();
+    let has_manifest = fs::try_exists(dir.join(ARCHIVE_MANIFEST_FILE))
+      .await
+      .map_err(|err| ImportError::Internal(err.into()))?;
+    if !has_manifest {
+      dir = unzip_dir.join(ARCHIVE_ROOT_DIR);
+    }
+    let manifest = match fs::read(dir.join(ARCHIVE_MANIFEST_FILE)).await {
+      Ok(bytes) => serde_json::from_slice::<ArchiveManifest>(&bytes)
+        .map_err(|err| ImportError::UnsupportedArchive(format!("invalid manifest: {}", err)))?,
+      Err(err) if err.kind() == ErrorKind::NotFound => {
+        return Err(ImportError::UnsupportedArchive(
+          "the manifest is missing".to_string(),
+        ))
+      },
+      Err(err) => return Err(ImportError::Internal(err.into())),
+    };
+    if manifest.version > ARCHIVE_VERSION {
+      return Err(ImportError::UpgradeToLatestVersion(format!(
+        "The archive was exported by a newer version ({})",
+        manifest.exported_by
+      )));
+    }
+    Ok(Self { dir, manifest })
+  }
+
+  pub fn manifest(&self) -> &ArchiveManifest {
+    &self.manifest
+  }
+
+  fn blob_path(&self, file_id: &str) -> Option<PathBuf> {
+    is_valid_entry_id(file_id).then(|| self.dir.join(blob_entry_name(file_id)))
+  }
+}
+
+#[async_trait]
+impl CollabSource for ArchiveCollabSource {
+  async fn read_collab(
+    &self,
+    object_id: &str,
+    _collab_type: &CollabType,
+  ) -> Result<EncodedCollab, WorkerError> {
+    if !is_valid_entry_id(object_id) {
+      return Err(WorkerError::RecordNotFound(object_id.to_string()));
+    }
+    let bytes = match fs::read(self.dir.join(collab_entry_name(object_id))).await {
+      Ok(bytes) => bytes,
+      Err(err) if err.kind() == ErrorKind::NotFound => {
+        return Err(WorkerError::RecordNotFound(object_id.to_string()))
+      },
+      Err(err) => return Err(err.into()),
+    };
+    EncodedCollab::decode_from_bytes(&bytes)
+      .map_err(|err| WorkerError::Internal(anyhow!("invalid collab {}: {}", object_id, err)))
+  }
+
+  async fn workspace_database_id(&self) -> Result<Option<String>, WorkerError> {
+    Ok(self.manifest.workspace_database_id.clone())
+  }
+}
+
+/// Imports an AppFlowy archive into the workspace created for the import task. The collabs get
+/// new object ids, the same way a workspace is cloned, so an archive can be imported more than
+/// once, including into the instance it was exported from.
+pub async fn import_appflowy_archive(
+  import_task: &NotionImportTask,
+  unzip_dir_path: &Path,
+  pg_pool: &PgPool,
+  collab_cache: &dyn EncodedCollabCache,
+  s3_client: &Arc<dyn S3Client>,
+  metrics: &Option<Arc<ImportMetrics>>,
+) -> Result<ImportSummary, ImportError> {
+  let collab_build_started_at = Instant::now();
+  let workspace_id =
+    Uuid::parse_str(&import_task.workspace_id).map_err(|err| ImportError::Internal(err.into()))?;
+  let source = ArchiveCollabSource::open(unzip_dir_path).await?;
+  let manifest = source.manifest();
+  trace!(
+    "[Import]: {} import archive of workspace {}, exported by {}",
+    import_task.workspace_id,
+    manifest.workspace_id,
+    manifest.exported_by
+  );
+
+  let cloned = WorkspaceCloner::new(import_task.uid, manifest.workspace_id, workspace_id)
+    .clone_collabs(
+      &source,
+      pg_pool,
+      s3_client,
+      import_task.workspace_name.clone(),
+    )
+    .await
+    .map_err(|err| match err {
+      WorkerError::RecordNotFound(msg) => {
+        ImportError::UnsupportedArchive(format!("{} is missing", msg))
+      },
+      err => ImportError::Internal(err.into()),
+    })?;
+
+  let mut summary = ImportSummary::default();
+  for params in &cloned.collabs {
+    match params.collab_type {
+      CollabType::Document => summary.documents += 1,
+      CollabType::Database => summary.databases += 1,
+      CollabType::DatabaseRow => summary.rows += 1,
+      _ => {},
+    }
+  }
+
+  let mut blobs = vec![];
+  for blob in &manifest.blobs {
+    let path = match source.blob_path(&blob.file_id) {
+      Some(path) => fs::try_exists(&path)
+        .await
+        .map_err(|err| ImportError::Internal(err.into()))?
+        .then_some(path),
+      None => None,
+    };
+    match path {
+      Some(path) => {
+        // the size and the type of the manifest are only hints
+        let file_size = fs::metadata(&path)
+          .await
+          .map_err(|err| ImportError::Internal(err.into()))?
+          .len();
+        let blob = ArchiveBlob {
+          file_id: blob.file_id.clone(),
+          file_type: allowed_blob_file_type(&blob.file_type),
+          file_size: file_size as i64,
+        };
+        blobs.push((blob, path))
+      },
+      None => summary.skipped_items.push(ImportSkippedItem {
+        name: blob_entry_name(&blob.file_id),
+        reason: "the file is missing from the archive".to_string(),
+      }),
+    }
+  }
+  summary.uploaded_files = blobs.len();
+  let blob_bytes = blobs
+    .iter()
+    .map(|(blob, _)| blob.file_size as u64)
+    .sum::<u64>();
+  check_storage_limit(pg_pool, &workspace_id, blob_bytes).await?;
+
+  let w_database_id = cloned
+    .workspace_database
+    .as_ref()
+    .map(|params| params.object_id.clone());
+  let mut collab_params_list = cloned.collabs;
+  collab_params_list.push(cloned.folder);
+  collab_params_list.extend(cloned.workspace_database);
+  summary.durations.collab_build_ms = record_import_stage(
+    metrics,
+    import_task.source,
+    ImportStage::CollabBuild,
+    collab_build_started_at,
+  );
+
+  let db_insert_started_at = Instant::now();
+  let mut transaction = pg_pool.begin().await.map_err(|err| {
+    ImportError::Internal(anyhow!(
+      "Failed to start transaction when importing archive: {:?}",
+      err
+    ))
+  })?;
+  insert_into_af_collab_bulk_for_user(
+    &mut transaction,
+    &import_task.uid,
+    &import_task.workspace_id,
+    &collab_params_list,
+  )
+  .await
+  .map_err(|err| {
+    ImportError::Internal(anyhow!(
+      "Failed to insert collabs into database when importing archive: {:?}",
+      err
+    ))
+  })?;
+
+  let completed = transition_import_task_status(
+    &import_task.task_id,
+    ImportTaskState::Pending,
+    ImportTaskState::Completed,
+    transaction.deref_mut(),
+  )
+  .await
+  .map_err(|err| {
+    ImportError::Internal(anyhow!(
+      "Failed to update import task status when importing archive: {:?}",
+      err
+    ))
+  })?;
+  if !completed {
+    return Err(ImportError::Cancelled);
+  }
+  update_workspace_status(transaction.deref_mut(), &workspace_id, true)
+    .await
+    .map_err(|err| {
+      ImportError::Internal(anyhow!(
+        "Failed to update workspace status when importing archive: {:?}",
+        err
+      ))
+    })?;
+  // Same as the Notion import, the imported workspace is not the most recently visited one.
+  let updated_at = DateTime::from_timestamp(0, 0).unwrap_or_else(Utc::now);
+  update_updated_at_of_workspace_with_uid(
+    transaction.deref_mut(),
+    import_task.uid,
+    &workspace_id,
+    updated_at,
+  )
+  .await
+  .map_err(|err| {
+    ImportError::Internal(anyhow!(
+      "Failed to update workspace updated_at when importing archive: {:?}",
+      err
+    ))
+  })?;
+
+  let (file_ids, file_types, file_sizes) = blobs.iter().fold(
+    (vec![], vec![], vec![]),
+    |(mut file_ids, mut file_types, mut file_sizes), (blob, _)| {
+      file_ids.push(blob.file_id.clone());
+      file_types.push(blob.file_type.clone());
+      file_sizes.push(blob.file_size);
+      (file_ids, file_types, file_sizes)
+    },
+  );
+  insert_blob_metadata_rows(
+    transaction.deref_mut(),
+    &workspace_id,
+    file_ids,
+    file_types,
+    file_sizes,
+  )
+  .await
+  .map_err(|err| {
+    ImportError::Internal(anyhow!(
+      "Failed to insert blob metadata into database when importing archive: {:?}",
+      err
+    ))
+  })?;
+
+  let summary_value =
+    serde_json::to_value(&summary).map_err(|err| ImportError::Internal(err.into()))?;
+  update_import_task_metadata(
+    import_task.task_id,
+    json!({ IMPORT_SUMMARY_KEY: summary_value }),
+    transaction.deref_mut(),
+  )
+  .await
+  .map_err(|err| {
+    ImportError::Internal(anyhow!(
+      "Failed to save the import summary when importing archive: {:?}",
+      err
+    ))
+  })?;
+  transaction.commit().await.map_err(|err| {
+    ImportError::Internal(anyhow!(
+      "Failed to commit transaction when importing archive: {:?}",
+      err
+    ))
+  })?;
+  summary.durations.db_insert_ms = record_import_stage(
+    metrics,
+    import_task.source,
+    ImportStage::DbInsert,
+    db_insert_started_at,
+  );
+
+  // The folder and the workspace database of the empty workspace may have been cached when the
+  // workspace was created.
+  let mut object_ids = vec![import_task.workspace_id.clone()];
+  object_ids.extend(w_database_id);
+  if let Err(err) = collab_cache.remove_encoded_collabs(object_ids).await {
+    warn!(
+      "[Import]: failed to remove cached collabs of {}: {}",
+      import_task.workspace_id, err
+    );
+  }
+
+  trace!("[Import]: {} upload files to s3", import_task.workspace_id);
+  let s3_upload_started_at = Instant::now();
+  upload_archive_blobs(s3_client, &workspace_id, blobs).await;
+  summary.durations.upload_ms = record_import_stage(
+    metrics,
+    import_task.source,
+    ImportStage::S3Upload,
+    s3_upload_started_at,
+  );
+  info!(
+    "[Import]: {} imported archive of workspace {}: {} collabs, {} files",
+    import_task.workspace_id,
+    manifest.workspace_id,
+    collab_params_list.len(),
+    summary.uploaded_files
+  );
+  Ok(summary)
+}
+
+fn allowed_blob_file_type(file_type: &str) -> String {
+  let file_type = file_type.trim().to_ascii_lowercase();
+  if ALLOWED_BLOB_FILE_TYPES.contains(&file_type.as_str()) {
+    file_type
+  } else {
+    DEFAULT_BLOB_FILE_TYPE.to_string()
+  }
+}
+
+/// Checks that the files of the archive fit in the storage of the free plan, the same limit the
+/// server checks when a file is uploaded. It is only enforced when billing is, and the workspaces
+/// with a paid plan have unlimited storage.
+async fn check_storage_limit(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  new_bytes: u64,
+) -> Result<(), ImportError> {
+  let billing_enabled = get_env_var("APPFLOWY_BILLING_ENABLED", "false")
+    .parse::<bool>()
+    .unwrap_or(false);
+  if !billing_enabled || new_bytes == 0 {
+    return Ok(());
+  }
+  let subscriptions = select_workspace_subscriptions(pg_pool, workspace_id)
+    .await
+    .map_err(|err| ImportError::Internal(err.into()))?;
+  let has_paid_plan = subscriptions.iter().any(|subscription| {
+    let grants_plan = SubscriptionStatus::try_from(subscription.status.as_str())
+      .map(|status| status.grants_plan())
+      .unwrap_or(false);
+    grants_plan
+      && matches!(
+        SubscriptionPlan::try_from(subscription.plan),
+        Ok(SubscriptionPlan::Pro | SubscriptionPlan::Team)
+      )
+  });
+  if has_paid_plan {
+    return Ok(());
+  }
+  let storage_limit = get_env_var(
+    "APPFLOWY_BILLING_FREE_STORAGE_LIMIT",
+    &DEFAULT_FREE_STORAGE_LIMIT.to_string(),
+  )
+  .parse::<u64>()
+  .unwrap_or(DEFAULT_FREE_STORAGE_LIMIT);
+  let storage_bytes = get_workspace_usage_size(pg_pool, workspace_id)
+    .await
+    .map_err(|err| ImportError::Internal(err.into()))?
+    + new_bytes;
+  if storage_bytes > storage_limit {
+    return Err(ImportError::ArchiveLimitExceeded(format!(
+      "the files of the archive need {} bytes, the storage of the workspace is limited to {} bytes",
+      storage_bytes, storage_limit
+    )));
+  }
+  Ok(())
+}
+
+/// The metadata of the files is already committed, a file that fails to upload is only logged,
+/// the same as the files of a Notion import.
+async fn upload_archive_blobs(
+  s3_client: &Arc<dyn S3Client>,
+  workspace_id: &Uuid,
+  blobs: Vec<(ArchiveBlob, PathBuf)>,
+) {
+  let errors = stream::iter(blobs.into_iter().map(|(blob, path)| async move {
+    let key = blob_object_key(workspace_id, &blob.file_id);
+    let stream = ByteStream::from_path(&path)
+      .await
+      .map_err(|err| anyhow!("failed to read {:?}: {}", path, err))?;
+    s3_client
+      .put_blob(&key, stream, Some(&blob.file_type))
+      .await
+      .map_err(|err| anyhow!("failed to upload {}: {}", key, err))
+  }))
+  .buffer_unordered(5)
+  .filter_map(|result| async move { result.err() })
+  .collect::<Vec<_>>()
+  .await;
+  if !errors.is_empty() {
+    error!("Some uploads failed: {:?}", errors);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn file_types_outside_the_allowlist_are_binary() {
+    assert_eq!(allowed_blob_file_type("image/png"), "image/png");
+    assert_eq!(allowed_blob_file_type(" Image/JPEG "), "image/jpeg");
+    assert_eq!(allowed_blob_file_type("text/html"), DEFAULT_BLOB_FILE_TYPE);
+    assert_eq!(
+      allowed_blob_file_type("image/svg+xml"),
+      DEFAULT_BLOB_FILE_TYPE
+    );
+    assert_eq!(allowed_blob_file_type(""), DEFAULT_BLOB_FILE_TYPE);
+  }
+}
@@ -0,0 +1,304 @@
+use crate::error::ImportError;
+use crate::import_worker::unzip::sanitize_entry_path;
+use futures::{AsyncBufRead, AsyncRead};
+use infra::env_util::get_env_var;
+use std::io;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tracing::trace;
+
+const DEFAULT_MAX_ARCHIVE_BYTES: u64 = 3 * 1024 * 1024 * 1024;
+const DEFAULT_MAX_FILE_COUNT: usize = 100_000;
+const DEFAULT_MAX_ENTRY_BYTES: u64 = 1024 * 1024 * 1024;
+const DEFAULT_MAX_EXTRACTED_BYTES: u64 = 10 * 1024 * 1024 * 1024;
+const DEFAULT_MAX_COMPRESSION_RATIO: u64 = 100;
+/// Small files compress extremely well (e.g. an empty csv), so the ratio is only checked for
+/// entries bigger than this.
+const MIN_RATIO_CHECK_BYTES: u64 = 1024 * 1024;
+
+/// Limits applied to an import archive before and while it is being unzipped, so that an
+/// oversized upload fails fast instead of midway through the import.
+#[derive(Debug, Clone)]
+pub struct ImportLimits {
+  /// Maximum size of the zip file itself.
+  pub max_archive_bytes: u64,
+  /// Maximum number of files in the archive.
+  pub max_files: usize,
+  /// Maximum uncompressed size of a single file in the archive.
+  pub max_entry_bytes: u64,
+  /// Maximum number of bytes written to disk when extracting the archive.
+  pub max_extracted_bytes: u64,
+  /// Maximum ratio between the uncompressed and compressed size of an entry.
+  pub max_compression_ratio: u64,
+}
+
+impl Default for ImportLimits {
+  fn default() -> Self {
+    Self {
+      max_archive_bytes: DEFAULT_MAX_ARCHIVE_BYTES,
+      max_files: DEFAULT_MAX_FILE_COUNT,
+      max_entry_bytes: DEFAULT_MAX_ENTRY_BYTES,
+      max_extracted_bytes: DEFAULT_MAX_EXTRACTED_BYTES,
+      max_compression_ratio: DEFAULT_MAX_COMPRESSION_RATIO,
+    }
+  }
+}
+
+impl ImportLimits {
+  pub fn from_env() -> Self {
+    let default = Self::default();
+    Self {
+      max_archive_bytes: get_env_var(
+        "APPFLOWY_WORKER_IMPORT_TASK_MAX_FILE_SIZE_BYTES",
+        &default.max_archive_bytes.to_string(),
+      )
+      .parse()
+      .unwrap_or(default.max_archive_bytes),
+      max_files: get_env_var(
+        "APPFLOWY_WORKER_IMPORT_TASK_MAX_FILE_COUNT",
+        &default.max_files.to_string(),
+      )
+      .parse()
+      .unwrap_or(default.max_files),
+      max_entry_bytes: get_env_var(
+        "APPFLOWY_WORKER_IMPORT_TASK_MAX_ENTRY_SIZE_BYTES",
+        &default.max_entry_bytes.to_string(),
+      )
+      .parse()
+      .unwrap_or(default.max_entry_bytes),
+      max_extracted_bytes: get_env_var(
+        "APPFLOWY_WORKER_IMPORT_TASK_MAX_EXTRACTED_BYTES",
+        &default.max_extracted_bytes.to_string(),
+      )
+      .parse()
+      .unwrap_or(default.max_extracted_bytes),
+      max_compression_ratio: get_env_var(
+        "APPFLOWY_WORKER_IMPORT_TASK_MAX_COMPRESSION_RATIO",
+        &default.max_compression_ratio.to_string(),
+      )
+      .parse()
+      .unwrap_or(default.max_compression_ratio),
+    }
+  }
+
+  /// Validates the size reported by the S3 head_object call.
+  pub fn check_archive_size(&self, content_length: i64) -> Result<(), ImportError> {
+    if content_length > 0 && content_length as u64 > self.max_archive_bytes {
+      return Err(self.archive_too_large(content_length as u64));
+    }
+    Ok(())
+  }
+
+  pub fn archive_too_large(&self, size: u64) -> ImportError {
+    ImportError::UploadFileTooLarge {
+      file_size_in_mb: size as f64 / 1_048_576.0,
+      max_size_in_mb: (self.max_archive_bytes as f64 / 1_048_576.0).ceil(),
+    }
+  }
+
+  /// Validates the entries listed in the central directory of a downloaded zip file, before
+  /// anything gets extracted.
+  pub async fn check_zip_entries(&self, zip_file_path: &Path) -> Result<(), ImportError> {
+    let reader = async_zip::tokio::read::fs::ZipFileReader::new(zip_file_path)
+      .await
+      .map_err(|err| ImportError::UnZipFileError(err.to_string()))?;
+    let entries = reader.file().entries();
+    let mut file_count = 0;
+    let mut extracted_bytes = 0;
+    for entry in entries {
+      let file_name = entry
+        .filename()
+        .as_str()
+        .map_err(|err| ImportError::UnZipFileError(err.to_string()))?;
+      sanitize_entry_path(file_name)?;
+      if file_name.ends_with('/') {
+        continue;
+      }
+
+      file_count += 1;
+      extracted_bytes += entry.uncompressed_size();
+      self.check_file_count(file_count)?;
+      self.check_entry_size(file_name, entry.uncompressed_size())?;
+      self.check_compression_ratio(
+        file_name,
+        entry.compressed_size(),
+        entry.uncompressed_size(),
+      )?;
+      self.check_extracted_bytes(extracted_bytes)?;
+    }
+
+    trace!(
+      "[Import] zip file {:?} contains {} files",
+      zip_file_path,
+      file_count
+    );
+    Ok(())
+  }
+
+  pub fn check_file_count(&self, file_count: usize) -> Result<(), ImportError> {
+    if file_count > self.max_files {
+      return Err(ImportError::ArchiveLimitExceeded(format!(
+        "the archive contains more than {} files",
+        self.max_files
+      )));
+    }
+    Ok(())
+  }
+
+  pub fn check_extracted_bytes(&self, extracted_bytes: u64) -> Result<(), ImportError> {
+    if extracted_bytes > self.max_extracted_bytes {
+      return Err(ImportError::ArchiveLimitExceeded(format!(
+        "the extracted files are larger than {} MB",
+        self.max_extracted_bytes / 1_048_576
+      )));
+    }
+    Ok(())
+  }
+
+  /// Rejects entries that expand far more than regular documents do, which is the signature of a
+  /// zip bomb.
+  pub fn check_compression_ratio(
+    &self,
+    file_name: &str,
+    compressed_size: u64,
+    uncompressed_size: u64,
+  ) -> Result<(), ImportError> {
+    if uncompressed_size < MIN_RATIO_CHECK_BYTES {
+      return Ok(());
+    }
+    if uncompressed_size / compressed_size.max(1) > self.max_compression_ratio {
+      return Err(ImportError::ArchiveLimitExceeded(format!(
+        "{} has a suspicious compression ratio",
+        file_name
+      )));
+    }
+    Ok(())
+  }
+
+  pub fn check_entry_size(&self, file_name: &str, size: u64) -> Result<(), ImportError> {
+    if size > self.max_entry_bytes {
+      return Err(ImportError::ArchiveLimitExceeded(format!(
+        "{} is larger than {} MB",
+        file_name,
+        self.max_entry_bytes / 1_048_576
+      )));
+    }
+    Ok(())
+  }
+}
+
+/// Wraps the zip stream downloaded from S3 and stops reading once more than `limit` bytes went
+/// through it. The content length reported by S3 is checked up front, but when streaming we
+/// never hold the whole file, so the stream itself has to be bounded as well.
+pub struct LimitedStream<R> {
+  inner: R,
+  read: u64,
+  limit: u64,
+  exceeded: Arc<AtomicBool>,
+}
+
+impl<R> LimitedStream<R> {
+  pub fn new(inner: R, limit: u64) -> Self {
+    Self {
+      inner,
+      read: 0,
+      limit,
+      exceeded: Arc::new(AtomicBool::new(false)),
+    }
+  }
+
+  /// The unzip error loses the reason why the stream was cut. Keep a handle to this flag to tell
+  /// whether the limit was the cause.
+  pub fn exceeded_flag(&self) -> Arc<AtomicBool> {
+    self.exceeded.clone()
+  }
+
+  fn advance(&mut self, n: usize) -> io::Result<()> {
+    self.read += n as u64;
+    if self.read > self.limit {
+      self.exceeded.store(true, Ordering::Relaxed);
+      return Err(io::Error::new(
+        io::ErrorKind::Other,
+        format!("zip stream exceeds the limit of {} bytes", self.limit),
+      ));
+    }
+    Ok(())
+  }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for LimitedStream<R> {
+  fn poll_read(
+    mut self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+    buf: &mut [u8],
+  ) -> Poll<io::Result<usize>> {
+    let this = &mut *self;
+    match Pin::new(&mut this.inner).poll_read(cx, buf) {
+      Poll::Ready(Ok(n)) => Poll::Ready(this.advance(n).map(|_| n)),
+      other => other,
+    }
+  }
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncBufRead for LimitedStream<R> {
+  fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+    let this = self.get_mut();
+    if this.exceeded.load(Ordering::Relaxed) {
+      return Poll::Ready(Err(io::Error::new(
+        io::ErrorKind::Other,
+        format!("zip stream exceeds the limit of {} bytes", this.limit),
+      )));
+    }
+    Pin::new(&mut this.inner).poll_fill_buf(cx)
+  }
+
+  fn consume(self: Pin<&mut Self>, amt: usize) {
+    let this = self.get_mut();
+    Pin::new(&mut this.inner).consume(amt);
+    // The error surfaces on the next poll_fill_buf call.
+    let _ = this.advance(amt);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use futures::AsyncReadExt;
+
+  #[tokio::test]
+  async fn limited_stream_stops_after_limit() {
+    let data = vec![0u8; 1024];
+    let mut stream = LimitedStream::new(futures::io::Cursor::new(data.clone()), 512);
+    let exceeded = stream.exceeded_flag();
+    let mut buf = Vec::new();
+    assert!(stream.read_to_end(&mut buf).await.is_err());
+    assert!(exceeded.load(Ordering::Relaxed));
+
+    let mut stream = LimitedStream::new(futures::io::Cursor::new(data), 1024);
+    let mut buf = Vec::new();
+    assert_eq!(stream.read_to_end(&mut buf).await.unwrap(), 1024);
+    assert!(!stream.exceeded_flag().load(Ordering::Relaxed));
+  }
+
+  #[test]
+  fn entry_limits() {
+    let limits = ImportLimits {
+      max_archive_bytes: 10,
+      max_files: 2,
+      max_entry_bytes: 5,
+      ..Default::default()
+    };
+    assert!(limits.check_archive_size(10).is_ok());
+    assert!(matches!(
+      limits.check_archive_size(11),
+      Err(ImportError::UploadFileTooLarge { .. })
+    ));
+    assert!(limits.check_file_count(2).is_ok());
+    assert!(limits.check_file_count(3).is_err());
+    assert!(limits.check_entry_size("a.md", 5).is_ok());
+    assert!(limits.check_entry_size("a.md", 6).is_err());
+  }
+}
@@ -0,0 +1,233 @@
+use crate::error::ImportError;
+use collab::core::origin::CollabOrigin;
+use collab::entity::EncodedCollab;
+use collab::preclude::Collab;
+use collab_document::document::Document;
+use collab_entity::CollabType;
+use collab_importer::notion::page::NotionPage;
+use reqwest::Url;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tracing::trace;
+
+const NOTION_ID_LEN: usize = 32;
+
+/// The views created for the pages of a Notion export, keyed by the id of the Notion page. Notion
+/// writes the links between the pages of a workspace as absolute urls, which point back to Notion
+/// once imported.
+#[derive(Debug, Default)]
+pub struct NotionPageMapping {
+  view_ids: HashMap<String, String>,
+}
+
+impl NotionPageMapping {
+  pub fn from_pages(pages: &[NotionPage]) -> Self {
+    let mut mapping = Self::default();
+    let mut stack = pages.iter().collect::<Vec<_>>();
+    while let Some(page) = stack.pop() {
+      if let Some(notion_id) = page.notion_id.as_deref().and_then(normalize_notion_id) {
+        mapping.view_ids.insert(notion_id, page.view_id.clone());
+      }
+      stack.extend(page.children.iter());
+    }
+    mapping
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.view_ids.is_empty()
+  }
+
+  pub fn page_count(&self) -> usize {
+    self.view_ids.len()
+  }
+
+  /// The Notion page ids and the ids of the views they were imported as.
+  pub fn entries(&self) -> impl Iterator<Item = (&String, &String)> {
+    self.view_ids.iter()
+  }
+
+  /// Returns the view imported from the Notion page the url points to, None for the urls that
+  /// don't point to a page of the export.
+  pub fn view_id_for_url(&self, url: &str) -> Option<&str> {
+    let notion_id = notion_page_id_from_url(url)?;
+    self.view_ids.get(&notion_id).map(String::as_str)
+  }
+
+  /// Replaces the links to the pages of the export in the text of the document with mentions of
+  /// the imported views. Returns None when the document has no such link.
+  pub fn rewrite_document_links(
+    &self,
+    object_id: &str,
+    encoded_collab: &EncodedCollab,
+  ) -> Result<Option<(EncodedCollab, usize)>, ImportError> {
+    let collab = Collab::new_with_source(
+      CollabOrigin::Server,
+      object_id,
+      encoded_collab.clone().into(),
+      vec![],
+      false,
+    )
+    .map_err(|err| ImportError::Internal(err.into()))?;
+    let mut data = Document::open(collab)
+      .and_then(|document| document.get_document_data())
+      .map_err(|err| ImportError::Internal(err.into()))?;
+
+    let mut rewritten = 0;
+    for delta in data
+      .meta
+      .text_map
+      .iter_mut()
+      .flat_map(|map| map.values_mut())
+    {
+      if let Some((new_delta, count)) = self.rewrite_delta(delta) {
+        *delta = new_delta;
+        rewritten += count;
+      }
+    }
+    if rewritten == 0 {
+      return Ok(None);
+    }
+
+    let encoded_collab = Document::create(object_id, data)
+      .map_err(|err| ImportError::Internal(err.into()))?
+      .encode_collab_v1(|collab| CollabType::Document.validate_require_data(collab))
+      .map_err(|err| ImportError::Internal(err.into()))?;
+    trace!(
+      "[Import]: {} rewrote {} links to imported pages",
+      object_id,
+      rewritten
+    );
+    Ok(Some((encoded_collab, rewritten)))
+  }
+
+  /// The delta is the json array of the text operations of a block. A text linking to an imported
+  /// page becomes a mention of the page, which shows the name of the page.
+  fn rewrite_delta(&self, delta: &str) -> Option<(String, usize)> {
+    let mut ops = serde_json::from_str::<Vec<Value>>(delta).ok()?;
+    let mut rewritten = 0;
+    for op in ops.iter_mut() {
+      let Some(href) = op
+        .get("attributes")
+        .and_then(|attributes| attributes.get("href"))
+        .and_then(Value::as_str)
+      else {
+        continue;
+      };
+      let Some(view_id) = self.view_id_for_url(href) else {
+        continue;
+      };
+      *op = json!({
+        "insert": "$",
+        "attributes": {
+          "mention": { "type": "page", "page_id": view_id },
+        },
+      });
+      rewritten += 1;
+    }
+    if rewritten == 0 {
+      return None;
+    }
+    Some((Value::Array(ops).to_string(), rewritten))
+  }
+}
+
+/// Notion ids are written with or without hyphens, and in any case.
+fn normalize_notion_id(id: &str) -> Option<String> {
+  let id = id.replace('-', "").to_ascii_lowercase();
+  if id.len() == NOTION_ID_LEN && id.chars().all(|c| c.is_ascii_hexdigit()) {
+    Some(id)
+  } else {
+    None
+  }
+}
+
+/// The id of the page a Notion url points to, for urls like
+/// `https://www.notion.so/workspace/Page-Title-<id>`, `https://<domain>.notion.site/<id>` or the
+/// urls of a page opened as a peek, `https://www.notion.so/<database id>?p=<page id>`.
+fn notion_page_id_from_url(url: &str) -> Option<String> {
+  let url = Url::parse(url).ok()?;
+  let host = url.host_str()?.to_ascii_lowercase();
+  let is_notion = ["notion.so", "notion.site"]
+    .iter()
+    .any(|domain| host == *domain || host.ends_with(&format!(".{}", domain)));
+  if !is_notion {
+    return None;
+  }
+  if let Some((_, page_id)) = url.query_pairs().find(|(key, _)| key == "p") {
+    return normalize_notion_id(&page_id);
+  }
+  let segment = url
+    .path_segments()?
+    .rfind(|segment| !segment.is_empty())?
+    .replace('-', "");
+  let id = segment.get(segment.len().checked_sub(NOTION_ID_LEN)?..)?;
+  normalize_notion_id(id)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const PAGE_ID: &str = "1a2b3c4d5e6f708192a3b4c5d6e7f809";
+
+  fn mapping() -> NotionPageMapping {
+    NotionPageMapping {
+      view_ids: HashMap::from([(PAGE_ID.to_string(), "view".to_string())]),
+    }
+  }
+
+  #[test]
+  fn notion_page_id_from_urls() {
+    let id = Some(PAGE_ID.to_string());
+    assert_eq!(
+      notion_page_id_from_url(&format!("https://www.notion.so/acme/Road-map-{}", PAGE_ID)),
+      id
+    );
+    assert_eq!(
+      notion_page_id_from_url(&format!(
+        "https://www.notion.so/{}?pvs=21#7f0e",
+        PAGE_ID.to_uppercase()
+      )),
+      id
+    );
+    assert_eq!(
+      notion_page_id_from_url("https://acme.notion.site/1a2b3c4d-5e6f-7081-92a3-b4c5d6e7f809"),
+      id
+    );
+    assert_eq!(
+      notion_page_id_from_url(&format!(
+        "https://www.notion.so/acme/ffffffffffffffffffffffffffffffff?v=1&p={}",
+        PAGE_ID
+      )),
+      id
+    );
+    assert_eq!(
+      notion_page_id_from_url(&format!("https://example.com/Road-map-{}", PAGE_ID)),
+      None
+    );
+    assert_eq!(notion_page_id_from_url("https://www.notion.so/acme"), None);
+  }
+
+  #[test]
+  fn links_to_imported_pages_become_mentions() {
+    let delta = json!([
+      { "insert": "see " },
+      { "insert": "the road map", "attributes": { "href": format!("https://www.notion.so/Road-map-{}", PAGE_ID) } },
+      { "insert": "other", "attributes": { "href": "https://www.notion.so/Other-ffffffffffffffffffffffffffffffff" } },
+    ])
+    .to_string();
+    let (rewritten, count) = mapping().rewrite_delta(&delta).unwrap();
+    assert_eq!(count, 1);
+    let ops: Vec<Value> = serde_json::from_str(&rewritten).unwrap();
+    assert_eq!(ops[0], json!({ "insert": "see " }));
+    assert_eq!(
+      ops[1],
+      json!({ "insert": "$", "attributes": { "mention": { "type": "page", "page_id": "view" } } })
+    );
+    assert_eq!(ops[2]["insert"], "other");
+
+    assert!(mapping()
+      .rewrite_delta(r#"[{"insert":"plain text"}]"#)
+      .is_none());
+  }
+}
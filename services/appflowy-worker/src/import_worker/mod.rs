@@ -1,3 +1,10 @@
+pub mod appflowy_archive;
 pub mod email_notifier;
+pub mod limits;
+pub mod links;
+pub mod relations;
+pub mod remote_resource;
 pub mod report;
+pub mod task_queue;
+pub mod unzip;
 pub mod worker;
@@ -0,0 +1,289 @@
+use anyhow::anyhow;
+use bytes::Bytes;
+use collab::core::origin::CollabOrigin;
+use collab::entity::EncodedCollab;
+use collab::preclude::{Any, Array, ArrayRef, Collab, Map, MapRef, Out};
+use collab_database::database::DatabaseBody;
+use collab_database::entity::FieldType;
+use collab_database::rows::{DatabaseRowBody, CELL_FIELD_TYPE, ROW_CELLS};
+use collab_database::template::entity::CELL_DATA;
+use collab_database::workspace_database::NoPersistenceDatabaseCollabService;
+use collab_entity::CollabType;
+use database_entity::dto::CollabParams;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tracing::{trace, warn};
+use uuid::Uuid;
+
+/// The changes made to the relation cells of the imported rows.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RelationFixes {
+  /// References that were written in another form of the id of an imported row, for example
+  /// without hyphens, and now use the id of the row.
+  pub repaired: usize,
+  /// References to rows that were not imported, or that don't belong to the related database.
+  pub removed: usize,
+}
+
+#[derive(Default)]
+struct ImportedDatabase {
+  /// Normalized row id to the row id used by the database views.
+  row_ids: HashMap<String, String>,
+  /// Relation field id to the normalized id of the related database.
+  relation_fields: HashMap<String, String>,
+}
+
+/// Notion exports keep relations to pages that are not part of the export, and the rows that
+/// fail to import are dropped. Validates the relation cells of the imported rows against the
+/// rows of the related database and rewrites the cells that point elsewhere.
+///
+/// The pass is best effort: a collab that can't be opened is left untouched.
+pub fn fix_imported_relations(collab_params_list: &mut [CollabParams]) -> RelationFixes {
+  let mut databases: HashMap<String, ImportedDatabase> = HashMap::new();
+  let mut database_of_row: HashMap<String, String> = HashMap::new();
+  for params in collab_params_list
+    .iter()
+    .filter(|params| params.collab_type == CollabType::Database)
+  {
+    let database_id = normalize_id(&params.object_id);
+    let database = match read_database(params) {
+      Ok(database) => database,
+      Err(err) => {
+        warn!(
+          "[Import] skip relations of database {}: {}",
+          database_id, err
+        );
+        continue;
+      },
+    };
+    for row_id in database.row_ids.values() {
+      database_of_row.insert(row_id.clone(), database_id.clone());
+    }
+    databases.insert(database_id, database);
+  }
+
+  let mut fixes = RelationFixes::default();
+  if databases
+    .values()
+    .all(|database| database.relation_fields.is_empty())
+  {
+    return fixes;
+  }
+
+  for params in collab_params_list
+    .iter_mut()
+    .filter(|params| params.collab_type == CollabType::DatabaseRow)
+  {
+    let Some(database) = database_of_row
+      .get(&params.object_id)
+      .and_then(|database_id| databases.get(database_id))
+    else {
+      continue;
+    };
+    if database.relation_fields.is_empty() {
+      continue;
+    }
+    match fix_row_relations(params, &database.relation_fields, &databases) {
+      Ok(Some((encoded_collab_v1, row_fixes))) => {
+        trace!(
+          "[Import] fixed relations of row {}: {:?}",
+          params.object_id,
+          row_fixes
+        );
+        params.encoded_collab_v1 = encoded_collab_v1;
+        fixes.repaired += row_fixes.repaired;
+        fixes.removed += row_fixes.removed;
+      },
+      Ok(None) => {},
+      Err(err) => warn!(
+        "[Import] skip relations of row {}: {}",
+        params.object_id, err
+      ),
+    }
+  }
+  fixes
+}
+
+fn read_database(params: &CollabParams) -> Result<ImportedDatabase, anyhow::Error> {
+  let collab = open_collab(params)?;
+  let body = DatabaseBody::from_collab(&collab, Arc::new(NoPersistenceDatabaseCollabService), None)
+    .ok_or_else(|| anyhow!("no database body"))?;
+  let txn = collab.transact();
+  let mut database = ImportedDatabase::default();
+  for field in body.fields.get_all_fields(&txn) {
+    if FieldType::from(field.field_type) != FieldType::Relation {
+      continue;
+    }
+    if let Some(Any::String(related_database_id)) = field
+      .type_options
+      .get(&FieldType::Relation.type_id())
+      .and_then(|type_option| type_option.get("database_id"))
+    {
+      database
+        .relation_fields
+        .insert(field.id.clone(), normalize_id(related_database_id));
+    }
+  }
+  for view in body.views.get_all_views(&txn) {
+    for row_order in view.row_orders {
+      let row_id = row_order.id.to_string();
+      database.row_ids.insert(normalize_id(&row_id), row_id);
+    }
+  }
+  Ok(database)
+}
+
+/// Returns the encoded row when any of its relation cells changed.
+fn fix_row_relations(
+  params: &CollabParams,
+  relation_fields: &HashMap<String, String>,
+  databases: &HashMap<String, ImportedDatabase>,
+) -> Result<Option<(Bytes, RelationFixes)>, anyhow::Error> {
+  let mut collab = open_collab(params)?;
+  let body = DatabaseRowBody::open(params.object_id.clone().into(), &mut collab)
+    .map_err(|err| anyhow!("failed to open row: {}", err))?;
+  let mut fixes = RelationFixes::default();
+  {
+    let mut txn = collab.context.transact_mut();
+    let Some(cells) = body.get_data().get(&txn, ROW_CELLS) else {
+      return Ok(None);
+    };
+    let cells: MapRef = cells
+      .cast()
+      .map_err(|err| anyhow!("cells is not a map: {:?}", err))?;
+    let mut relation_cells = vec![];
+    for (field_id, out) in cells.iter(&txn) {
+      let Some(related_database_id) = relation_fields.get(field_id) else {
+        continue;
+      };
+      let Ok(cell) = out.cast::<MapRef>() else {
+        continue;
+      };
+      if !matches!(
+        cell.get(&txn, CELL_FIELD_TYPE),
+        Some(Out::Any(Any::BigInt(field_type))) if field_type == FieldType::Relation as i64
+      ) {
+        continue;
+      }
+      if let Some(Ok(row_ids)) = cell
+        .get(&txn, CELL_DATA)
+        .map(|data| data.cast::<ArrayRef>())
+      {
+        relation_cells.push((related_database_id, row_ids));
+      }
+    }
+
+    let empty = HashMap::new();
+    for (related_database_id, related_row_ids) in relation_cells {
+      let old_row_ids = related_row_ids
+        .iter(&txn)
+        .filter_map(|out| match out {
+          Out::Any(Any::String(id)) => Some(id.to_string()),
+          _ => None,
+        })
+        .collect::<Vec<_>>();
+      let imported_row_ids = databases
+        .get(related_database_id)
+        .map(|database| &database.row_ids)
+        .unwrap_or(&empty);
+      let (new_row_ids, cell_fixes) = fix_related_row_ids(&old_row_ids, imported_row_ids);
+      if cell_fixes == RelationFixes::default() {
+        continue;
+      }
+      let len = related_row_ids.len(&txn);
+      related_row_ids.remove_range(&mut txn, 0, len);
+      for row_id in new_row_ids {
+        related_row_ids.push_back(&mut txn, row_id);
+      }
+      fixes.repaired += cell_fixes.repaired;
+      fixes.removed += cell_fixes.removed;
+    }
+  }
+  if fixes == RelationFixes::default() {
+    return Ok(None);
+  }
+
+  let encoded_collab = collab
+    .encode_collab_v1(|collab| CollabType::DatabaseRow.validate_require_data(collab))
+    .map_err(|err| anyhow!("failed to encode row: {}", err))?;
+  Ok(Some((
+    Bytes::from(encoded_collab.encode_to_bytes()?),
+    fixes,
+  )))
+}
+
+/// Keeps the references to the rows of the related database, rewritten with the row id used by
+/// the database when they are written differently, and drops the others and the duplicates.
+fn fix_related_row_ids(
+  row_ids: &[String],
+  imported_row_ids: &HashMap<String, String>,
+) -> (Vec<String>, RelationFixes) {
+  let mut fixes = RelationFixes::default();
+  let mut seen = HashSet::new();
+  let mut fixed = Vec::with_capacity(row_ids.len());
+  for row_id in row_ids {
+    match imported_row_ids.get(&normalize_id(row_id)) {
+      Some(imported_row_id) if seen.insert(imported_row_id.as_str()) => {
+        if imported_row_id != row_id {
+          fixes.repaired += 1;
+        }
+        fixed.push(imported_row_id.clone());
+      },
+      _ => fixes.removed += 1,
+    }
+  }
+  (fixed, fixes)
+}
+
+fn open_collab(params: &CollabParams) -> Result<Collab, anyhow::Error> {
+  let encoded_collab = EncodedCollab::decode_from_bytes(&params.encoded_collab_v1)?;
+  Collab::new_with_source(
+    CollabOrigin::Server,
+    &params.object_id,
+    encoded_collab.into(),
+    vec![],
+    false,
+  )
+  .map_err(|err| anyhow!("failed to open collab {}: {}", params.object_id, err))
+}
+
+/// Notion ids are uuids that are written with or without hyphens.
+fn normalize_id(id: &str) -> String {
+  let id = id.trim();
+  Uuid::parse_str(id)
+    .map(|id| id.to_string())
+    .unwrap_or_else(|_| id.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn keep_only_references_to_imported_rows() {
+    let row_a = Uuid::new_v4().to_string();
+    let row_b = Uuid::new_v4().to_string();
+    let imported_row_ids = HashMap::from([
+      (normalize_id(&row_a), row_a.clone()),
+      (normalize_id(&row_b), row_b.clone()),
+    ]);
+    let missing_row = Uuid::new_v4().to_string();
+    let row_b_simple = row_b.replace('-', "").to_uppercase();
+    let (row_ids, fixes) = fix_related_row_ids(
+      &[row_a.clone(), missing_row, row_b_simple, row_a.clone()],
+      &imported_row_ids,
+    );
+    assert_eq!(row_ids, vec![row_a, row_b]);
+    assert_eq!(
+      fixes,
+      RelationFixes {
+        repaired: 1,
+        removed: 2,
+      }
+    );
+
+    let (row_ids, fixes) = fix_related_row_ids(&row_ids, &imported_row_ids);
+    assert_eq!(row_ids.len(), 2);
+    assert_eq!(fixes, RelationFixes::default());
+  }
+}
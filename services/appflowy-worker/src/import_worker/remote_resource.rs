@@ -0,0 +1,442 @@
+use crate::error::ImportError;
+use anyhow::anyhow;
+use collab::core::origin::CollabOrigin;
+use collab::entity::EncodedCollab;
+use collab::preclude::Collab;
+use collab_document::document::Document;
+use collab_entity::CollabType;
+use collab_importer::notion::page::CollabResource;
+use collab_importer::util::FileId;
+use database_entity::dto::ImportSkippedItem;
+use futures::StreamExt;
+use infra::env_util::get_env_var;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::Url;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tracing::{trace, warn};
+use uuid::Uuid;
+
+const DEFAULT_MAX_RESOURCE_BYTES: u64 = 20 * 1024 * 1024;
+const DEFAULT_FETCH_TIMEOUT_SECS: u64 = 15;
+const DEFAULT_MAX_RESOURCES_PER_IMPORT: usize = 500;
+/// Block types whose `url` points to a file that can be stored in AppFlowy.
+const FILE_BLOCK_TYPES: [&str; 2] = ["image", "file"];
+
+/// Controls the download of remote resources referenced by imported documents. Notion exports
+/// bundle uploaded files, but images embedded by URL are kept as links to the original host, which
+/// may disappear or require authentication later on.
+#[derive(Debug, Clone)]
+pub struct RemoteResourceConfig {
+  pub max_resource_bytes: u64,
+  pub timeout: Duration,
+  /// Maximum number of resources downloaded for a single import.
+  pub max_resources: usize,
+  /// Hosts that resources can be downloaded from. Subdomains of a listed host are allowed too.
+  /// An empty list allows every public host.
+  pub allowed_hosts: Vec<String>,
+}
+
+impl RemoteResourceConfig {
+  /// Returns None unless the download is enabled with `APPFLOWY_WORKER_IMPORT_FETCH_REMOTE_RESOURCES`.
+  pub fn from_env() -> Option<Self> {
+    let enabled = get_env_var("APPFLOWY_WORKER_IMPORT_FETCH_REMOTE_RESOURCES", "false")
+      .parse::<bool>()
+      .unwrap_or(false);
+    if !enabled {
+      return None;
+    }
+    let allowed_hosts = get_env_var("APPFLOWY_WORKER_IMPORT_REMOTE_RESOURCE_ALLOWED_HOSTS", "")
+      .split(',')
+      .map(|host| host.trim().to_lowercase())
+      .filter(|host| !host.is_empty())
+      .collect();
+    Some(Self {
+      max_resource_bytes: get_env_var(
+        "APPFLOWY_WORKER_IMPORT_REMOTE_RESOURCE_MAX_BYTES",
+        &DEFAULT_MAX_RESOURCE_BYTES.to_string(),
+      )
+      .parse()
+      .unwrap_or(DEFAULT_MAX_RESOURCE_BYTES),
+      timeout: Duration::from_secs(
+        get_env_var(
+          "APPFLOWY_WORKER_IMPORT_REMOTE_RESOURCE_TIMEOUT_SECS",
+          &DEFAULT_FETCH_TIMEOUT_SECS.to_string(),
+        )
+        .parse()
+        .unwrap_or(DEFAULT_FETCH_TIMEOUT_SECS),
+      ),
+      max_resources: get_env_var(
+        "APPFLOWY_WORKER_IMPORT_REMOTE_RESOURCE_MAX_COUNT",
+        &DEFAULT_MAX_RESOURCES_PER_IMPORT.to_string(),
+      )
+      .parse()
+      .unwrap_or(DEFAULT_MAX_RESOURCES_PER_IMPORT),
+      allowed_hosts,
+    })
+  }
+
+  /// Only http(s) urls pointing to an allowed, non internal host are downloaded. The addresses
+  /// the host names resolve to are checked when downloading, by [PublicDnsResolver].
+  pub fn is_allowed(&self, url: &Url) -> bool {
+    if !matches!(url.scheme(), "http" | "https") {
+      return false;
+    }
+    let Some(host) = url.host_str().map(|host| host.to_lowercase()) else {
+      return false;
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if host == "localhost" || host.ends_with(".localhost") || host.ends_with(".internal") {
+      return false;
+    }
+    if let Ok(ip) = host.parse::<IpAddr>() {
+      if !is_public_ip(&ip) {
+        return false;
+      }
+    }
+    self.allowed_hosts.is_empty()
+      || self
+        .allowed_hosts
+        .iter()
+        .any(|allowed| host == allowed || host.ends_with(&format!(".{}", allowed)))
+  }
+}
+
+fn is_public_ip(ip: &IpAddr) -> bool {
+  match ip {
+    IpAddr::V4(ip) => {
+      let [first, second, ..] = ip.octets();
+      // 0.0.0.0/8 and the carrier grade NAT range 100.64.0.0/10
+      let is_reserved = first == 0 || (first == 100 && (second & 0xc0) == 64);
+      !(is_reserved
+        || ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_documentation())
+    },
+    IpAddr::V6(ip) => {
+      if let Some(ip) = ip.to_ipv4_mapped() {
+        return is_public_ip(&IpAddr::V4(ip));
+      }
+      let is_unique_local = (ip.segments()[0] & 0xfe00) == 0xfc00;
+      let is_link_local = (ip.segments()[0] & 0xffc0) == 0xfe80;
+      !(ip.is_loopback() || ip.is_unspecified() || is_unique_local || is_link_local)
+    },
+  }
+}
+
+/// Resolves the hosts of the remote resources, failing when one of the addresses of a host is
+/// internal: a public name can point to an internal address.
+struct PublicDnsResolver;
+
+impl Resolve for PublicDnsResolver {
+  fn resolve(&self, name: Name) -> Resolving {
+    Box::pin(async move {
+      let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+      if let Some(addr) = addrs.iter().find(|addr| !is_public_ip(&addr.ip())) {
+        return Err(
+          format!(
+            "{} resolves to the internal address {}",
+            name.as_str(),
+            addr.ip()
+          )
+          .into(),
+        );
+      }
+      Ok(Box::new(addrs.into_iter()) as Addrs)
+    })
+  }
+}
+
+/// Downloads the remote files referenced by imported documents and rewrites the links to the
+/// files stored in AppFlowy.
+pub struct RemoteResourceFetcher {
+  config: RemoteResourceConfig,
+  client: reqwest::Client,
+  download_dir: PathBuf,
+  /// Downloaded urls, so that a resource referenced several times is fetched once.
+  downloaded: HashMap<String, Option<PathBuf>>,
+  /// Resources that could not be downloaded and keep their remote url.
+  skipped: Vec<ImportSkippedItem>,
+}
+
+impl RemoteResourceFetcher {
+  pub fn new(config: RemoteResourceConfig, download_dir: PathBuf) -> Result<Self, ImportError> {
+    let client = reqwest::Client::builder()
+      .timeout(config.timeout)
+      // Redirects could point to an internal host that is not checked by the allowlist.
+      .redirect(reqwest::redirect::Policy::none())
+      .dns_resolver(Arc::new(PublicDnsResolver))
+      .build()
+      .map_err(|err| ImportError::Internal(err.into()))?;
+    Ok(Self {
+      config,
+      client,
+      download_dir,
+      downloaded: HashMap::new(),
+      skipped: vec![],
+    })
+  }
+
+  /// Replaces the remote urls of the image and file blocks of the document with the url of the
+  /// downloaded copy. Returns the re-encoded document and the downloaded files, which are
+  /// uploaded alongside the files bundled in the export. Resources that can't be downloaded keep
+  /// their original url, and a document that can't be rewritten is imported as is.
+  pub async fn localize_document(
+    &mut self,
+    host: &str,
+    workspace_id: &str,
+    object_id: &str,
+    encoded_collab: EncodedCollab,
+  ) -> (EncodedCollab, Option<CollabResource>) {
+    match self
+      .try_localize_document(host, workspace_id, object_id, &encoded_collab)
+      .await
+    {
+      Ok(Some((localized, resource))) => (localized, Some(resource)),
+      Ok(None) => (encoded_collab, None),
+      Err(err) => {
+        warn!(
+          "[Import]: failed to download the remote files of {}: {}",
+          object_id, err
+        );
+        self.skipped.push(ImportSkippedItem {
+          name: object_id.to_string(),
+          reason: format!("failed to download the remote files of the page: {}", err),
+        });
+        (encoded_collab, None)
+      },
+    }
+  }
+
+  async fn try_localize_document(
+    &mut self,
+    host: &str,
+    workspace_id: &str,
+    object_id: &str,
+    encoded_collab: &EncodedCollab,
+  ) -> Result<Option<(EncodedCollab, CollabResource)>, ImportError> {
+    let collab = Collab::new_with_source(
+      CollabOrigin::Server,
+      object_id,
+      encoded_collab.clone().into(),
+      vec![],
+      false,
+    )
+    .map_err(|err| ImportError::Internal(err.into()))?;
+    let mut data = Document::open(collab)
+      .and_then(|document| document.get_document_data())
+      .map_err(|err| ImportError::Internal(err.into()))?;
+
+    let mut files = vec![];
+    for block in data.blocks.values_mut() {
+      if !FILE_BLOCK_TYPES.contains(&block.ty.as_str()) {
+        continue;
+      }
+      let Some(Value::String(url)) = block.data.get("url") else {
+        continue;
+      };
+      if url.starts_with(host) {
+        continue;
+      }
+      let Some(path) = self.fetch(url).await else {
+        continue;
+      };
+      let file_id = FileId::from_path(&path)
+        .await
+        .map_err(|err| ImportError::Internal(err.into()))?;
+      let new_url = format!(
+        "{}/api/file_storage/{}/v1/blob/{}/{}",
+        host, workspace_id, object_id, file_id
+      );
+      block.data.insert("url".to_string(), Value::String(new_url));
+      // Image blocks distinguish internal files (1) from external urls (2).
+      if block.ty == "image" {
+        block
+          .data
+          .insert("image_type".to_string(), Value::Number(1.into()));
+      }
+      let path = path.to_string_lossy().to_string();
+      if !files.contains(&path) {
+        files.push(path);
+      }
+    }
+    if files.is_empty() {
+      return Ok(None);
+    }
+
+    let encoded_collab = Document::create(object_id, data)
+      .map_err(|err| ImportError::Internal(err.into()))?
+      .encode_collab_v1(|collab| CollabType::Document.validate_require_data(collab))
+      .map_err(|err| ImportError::Internal(err.into()))?;
+    trace!(
+      "[Import]: {} downloaded {} remote resources",
+      object_id,
+      files.len()
+    );
+    Ok(Some((
+      encoded_collab,
+      CollabResource {
+        object_id: object_id.to_string(),
+        files,
+      },
+    )))
+  }
+
+  async fn fetch(&mut self, url: &str) -> Option<PathBuf> {
+    if let Some(path) = self.downloaded.get(url) {
+      return path.clone();
+    }
+    let parsed = Url::parse(url)
+      .ok()
+      .filter(|url| self.config.is_allowed(url));
+    let path = match parsed {
+      Some(url) if self.downloaded.len() < self.config.max_resources => {
+        match self.download(url).await {
+          Ok(path) => Some(path),
+          Err(err) => {
+            warn!("[Import]: failed to download remote resource: {}", err);
+            self.skipped.push(ImportSkippedItem {
+              name: url.to_string(),
+              reason: format!("failed to download the remote file: {}", err),
+            });
+            None
+          },
+        }
+      },
+      Some(url) => {
+        self.skipped.push(ImportSkippedItem {
+          name: url.to_string(),
+          reason: format!(
+            "more than {} remote files are referenced, kept the link",
+            self.config.max_resources
+          ),
+        });
+        None
+      },
+      None => None,
+    };
+    self.downloaded.insert(url.to_string(), path.clone());
+    path
+  }
+
+  /// Returns the resources that could not be downloaded since the last call.
+  pub fn take_skipped_items(&mut self) -> Vec<ImportSkippedItem> {
+    std::mem::take(&mut self.skipped)
+  }
+
+  async fn download(&self, url: Url) -> Result<PathBuf, anyhow::Error> {
+    let resp = self.client.get(url.clone()).send().await?;
+    if !resp.status().is_success() {
+      return Err(anyhow!("{} responded with {}", url, resp.status()));
+    }
+    if let Some(len) = resp.content_length() {
+      if len > self.config.max_resource_bytes {
+        return Err(anyhow!("{} is larger than {} bytes", url, len));
+      }
+    }
+
+    fs::create_dir_all(&self.download_dir).await?;
+    let path = self
+      .download_dir
+      .join(file_name(&url, Uuid::new_v4().to_string()));
+    let result = write_limited(resp, &path, self.config.max_resource_bytes).await;
+    if result.is_err() {
+      let _ = fs::remove_file(&path).await;
+    }
+    result.map(|_| path)
+  }
+}
+
+/// The extension is kept so that the mime type of the stored file can be guessed from the path.
+fn file_name(url: &Url, stem: String) -> String {
+  let extension = url
+    .path_segments()
+    .and_then(|mut segments| segments.next_back())
+    .and_then(|name| Path::new(name).extension())
+    .and_then(|ext| ext.to_str())
+    .filter(|ext| ext.len() <= 8 && ext.chars().all(|c| c.is_ascii_alphanumeric()));
+  match extension {
+    Some(ext) => format!("{}.{}", stem, ext),
+    None => stem,
+  }
+}
+
+async fn write_limited(
+  resp: reqwest::Response,
+  path: &Path,
+  max_bytes: u64,
+) -> Result<(), anyhow::Error> {
+  let mut file = fs::File::create(path).await?;
+  let mut written = 0;
+  let mut stream = resp.bytes_stream();
+  while let Some(chunk) = stream.next().await {
+    let chunk = chunk?;
+    written += chunk.len() as u64;
+    if written > max_bytes {
+      return Err(anyhow!("resource is larger than {} bytes", max_bytes));
+    }
+    file.write_all(&chunk).await?;
+  }
+  file.flush().await?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn config(allowed_hosts: Vec<&str>) -> RemoteResourceConfig {
+    RemoteResourceConfig {
+      max_resource_bytes: DEFAULT_MAX_RESOURCE_BYTES,
+      timeout: Duration::from_secs(DEFAULT_FETCH_TIMEOUT_SECS),
+      max_resources: DEFAULT_MAX_RESOURCES_PER_IMPORT,
+      allowed_hosts: allowed_hosts.into_iter().map(String::from).collect(),
+    }
+  }
+
+  #[test]
+  fn remote_resource_allowlist() {
+    let any = config(vec![]);
+    let check =
+      |config: &RemoteResourceConfig, url: &str| config.is_allowed(&Url::parse(url).unwrap());
+    assert!(check(&any, "https://images.unsplash.com/photo.png"));
+    assert!(!check(&any, "file:///etc/passwd"));
+    assert!(!check(&any, "http://localhost:8000/a.png"));
+    assert!(!check(&any, "http://127.0.0.1/a.png"));
+    assert!(!check(&any, "http://10.0.0.3/a.png"));
+    assert!(!check(&any, "http://169.254.169.254/latest/meta-data"));
+    assert!(!check(&any, "http://[::1]/a.png"));
+    assert!(!check(&any, "http://[::ffff:10.0.0.1]/a.png"));
+    assert!(!check(&any, "http://100.64.0.1/a.png"));
+    assert!(check(&any, "http://100.128.0.1/a.png"));
+
+    let restricted = config(vec!["unsplash.com"]);
+    assert!(check(&restricted, "https://unsplash.com/a.png"));
+    assert!(check(&restricted, "https://images.unsplash.com/a.png"));
+    assert!(!check(&restricted, "https://evilunsplash.com/a.png"));
+    assert!(!check(&restricted, "https://example.com/a.png"));
+  }
+
+  #[tokio::test]
+  async fn hosts_resolving_to_internal_addresses_are_rejected() {
+    let name = "localhost".parse::<Name>().unwrap();
+    assert!(PublicDnsResolver.resolve(name).await.is_err());
+  }
+
+  #[test]
+  fn remote_resource_file_name_keeps_extension() {
+    let url = Url::parse("https://example.com/images/cover.JPG?w=100").unwrap();
+    assert_eq!(file_name(&url, "id".to_string()), "id.JPG");
+    let url = Url::parse("https://example.com/images/cover").unwrap();
+    assert_eq!(file_name(&url, "id".to_string()), "id");
+  }
+}
@@ -0,0 +1,384 @@
+use crate::error::ImportError;
+use crate::import_worker::worker::{encode_collab_key, ImportTask};
+use axum::async_trait;
+use collab_stream::lease::Lease;
+use infra::env_util::get_env_var;
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::time::interval;
+use tracing::{error, trace, warn};
+
+/// The stream the import tasks are consumed from. The tasks read from the stream stay in it until
+/// they are acknowledged or put back at the end of the queue.
+#[async_trait]
+pub trait ImportTaskQueue: Send + Sync {
+  /// Replaces the entry with the task at the end of the queue.
+  async fn requeue_task(&self, entry_id: &str, task: &ImportTask) -> Result<(), ImportError>;
+
+  /// Removes the entry once the task was handled.
+  async fn ack_task(&self, entry_id: &str) -> Result<(), ImportError>;
+
+  /// Acquires the import lease of the workspace, only one import may write to a workspace at a
+  /// time. Returns `None` if another import holds the lease.
+  async fn acquire_workspace_lease(
+    &self,
+    workspace_id: &str,
+  ) -> Result<Option<WorkspaceImportLease>, ImportError>;
+}
+
+/// Cache of the encoded collabs read by the realtime server. The import writes the folder and the
+/// workspace database it rebuilds, failures are only logged.
+#[async_trait]
+pub trait EncodedCollabCache: Send + Sync {
+  async fn insert_encoded_collab(
+    &self,
+    object_id: &str,
+    encoded_collab: Vec<u8>,
+    expire_secs: u64,
+  ) -> Result<(), anyhow::Error>;
+
+  async fn remove_encoded_collabs(&self, object_ids: Vec<String>) -> Result<(), anyhow::Error>;
+}
+
+/// Keeps the per workspace import lease until dropped.
+pub struct WorkspaceImportLease {
+  _guard: Box<dyn Send + Sync>,
+}
+
+impl WorkspaceImportLease {
+  pub fn new(guard: impl Send + Sync + 'static) -> Self {
+    Self {
+      _guard: Box::new(guard),
+    }
+  }
+}
+
+/// [ImportTaskQueue] reading the Redis stream of the import worker.
+pub struct RedisImportTaskQueue {
+  redis_client: ConnectionManager,
+  stream_name: String,
+}
+
+impl RedisImportTaskQueue {
+  pub fn new(redis_client: ConnectionManager, stream_name: &str) -> Self {
+    Self {
+      redis_client,
+      stream_name: stream_name.to_string(),
+    }
+  }
+}
+
+#[async_trait]
+impl ImportTaskQueue for RedisImportTaskQueue {
+  async fn requeue_task(&self, entry_id: &str, task: &ImportTask) -> Result<(), ImportError> {
+    let task_str = serde_json::to_string(task).map_err(|e| {
+      error!("Failed to serialize task: {:?}", e);
+      ImportError::Internal(e.into())
+    })?;
+
+    let mut pipeline = redis::pipe();
+    pipeline
+      .atomic() // Ensures the commands are executed atomically
+      .cmd("XDEL") // delete the task
+      .arg(&self.stream_name)
+      .arg(entry_id)
+      .ignore() // Ignore the result of XDEL
+      .cmd("XADD") // Re-add the task to the stream
+      .arg(&self.stream_name)
+      .arg("*")
+      .arg("task")
+      .arg(task_str);
+
+    let mut redis_client = self.redis_client.clone();
+    let result: Result<(), redis::RedisError> = pipeline.query_async(&mut redis_client).await;
+    match result {
+      Ok(_) => Ok(()),
+      Err(err) => {
+        error!(
+          "Failed to execute transaction for re-adding task: {:?}",
+          err
+        );
+        Err(ImportError::Internal(err.into()))
+      },
+    }
+  }
+
+  async fn ack_task(&self, entry_id: &str) -> Result<(), ImportError> {
+    let mut redis_client = self.redis_client.clone();
+    let _: () = redis_client
+      .xdel(&self.stream_name, &[entry_id])
+      .await
+      .map_err(|e| {
+        error!("Failed to delete import task: {:?}", e);
+        ImportError::Internal(e.into())
+      })?;
+    Ok(())
+  }
+
+  /// Spawns a task that renews the lease until the returned guard is dropped, so that long
+  /// running imports keep the lease.
+  async fn acquire_workspace_lease(
+    &self,
+    workspace_id: &str,
+  ) -> Result<Option<WorkspaceImportLease>, ImportError> {
+    let ttl = Duration::from_secs(
+      get_env_var("APPFLOWY_WORKER_IMPORT_LEASE_TTL_SECS", "60")
+        .parse::<u64>()
+        .unwrap_or(60),
+    );
+    let lease_key = format!("af:import:{}:lease", workspace_id);
+    let lease = self
+      .redis_client
+      .lease(lease_key, ttl)
+      .await
+      .map_err(|err| ImportError::Internal(err.into()))?;
+    let Some(mut lease) = lease else {
+      return Ok(None);
+    };
+
+    let (stop_tx, mut stop_rx) = oneshot::channel::<()>();
+    let workspace_id = workspace_id.to_string();
+    tokio::spawn(async move {
+      let mut interval = interval(ttl / 3);
+      interval.tick().await;
+      loop {
+        tokio::select! {
+          _ = &mut stop_rx => break,
+          _ = interval.tick() => match lease.renew(ttl).await {
+            Ok(true) => trace!("[Import] {} renewed import lease", workspace_id),
+            Ok(false) => {
+              warn!("[Import] {} lost import lease", workspace_id);
+              return;
+            },
+            Err(err) => error!("[Import] {} failed to renew import lease: {}", workspace_id, err),
+          },
+        }
+      }
+      if let Err(err) = lease.release().await {
+        error!(
+          "[Import] {} failed to release import lease: {}",
+          workspace_id, err
+        );
+      }
+    });
+    Ok(Some(WorkspaceImportLease::new(stop_tx)))
+  }
+}
+
+#[async_trait]
+impl EncodedCollabCache for ConnectionManager {
+  async fn insert_encoded_collab(
+    &self,
+    object_id: &str,
+    encoded_collab: Vec<u8>,
+    expire_secs: u64,
+  ) -> Result<(), anyhow::Error> {
+    let mut redis_client = self.clone();
+    redis_client
+      .set_ex::<_, _, ()>(encode_collab_key(object_id), encoded_collab, expire_secs)
+      .await?;
+    Ok(())
+  }
+
+  async fn remove_encoded_collabs(&self, object_ids: Vec<String>) -> Result<(), anyhow::Error> {
+    let keys = object_ids
+      .iter()
+      .map(|object_id| encode_collab_key(object_id))
+      .collect::<Vec<_>>();
+    let mut redis_client = self.clone();
+    redis_client.del::<_, ()>(keys).await?;
+    Ok(())
+  }
+}
+
+/// [ImportTaskQueue] kept in memory, to test the import without Redis. The tasks are delivered
+/// in the order they were pushed.
+#[derive(Default)]
+pub struct InMemoryImportTaskQueue {
+  state: Mutex<InMemoryQueueState>,
+  leased_workspaces: Arc<Mutex<HashSet<String>>>,
+}
+
+#[derive(Default)]
+struct InMemoryQueueState {
+  next_entry_id: u64,
+  queued: VecDeque<(String, ImportTask)>,
+  /// Delivered by [InMemoryImportTaskQueue::next_task] and not acknowledged yet.
+  pending: HashMap<String, ImportTask>,
+}
+
+impl InMemoryQueueState {
+  fn push(&mut self, task: ImportTask) -> String {
+    self.next_entry_id += 1;
+    let entry_id = format!("{}-0", self.next_entry_id);
+    self.queued.push_back((entry_id.clone(), task));
+    entry_id
+  }
+}
+
+impl InMemoryImportTaskQueue {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Adds the task at the end of the queue, returns its entry id.
+  pub fn push_task(&self, task: ImportTask) -> String {
+    self.state.lock().unwrap().push(task)
+  }
+
+  /// Delivers the first queued task, it stays pending until acknowledged or requeued.
+  pub fn next_task(&self) -> Option<(String, ImportTask)> {
+    let mut state = self.state.lock().unwrap();
+    let (entry_id, task) = state.queued.pop_front()?;
+    state.pending.insert(entry_id.clone(), task.clone());
+    Some((entry_id, task))
+  }
+
+  /// Number of queued and pending tasks.
+  pub fn len(&self) -> usize {
+    let state = self.state.lock().unwrap();
+    state.queued.len() + state.pending.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  pub fn is_workspace_leased(&self, workspace_id: &str) -> bool {
+    self
+      .leased_workspaces
+      .lock()
+      .unwrap()
+      .contains(workspace_id)
+  }
+}
+
+#[async_trait]
+impl ImportTaskQueue for InMemoryImportTaskQueue {
+  async fn requeue_task(&self, entry_id: &str, task: &ImportTask) -> Result<(), ImportError> {
+    let mut state = self.state.lock().unwrap();
+    state.pending.remove(entry_id);
+    state.queued.retain(|(id, _)| id != entry_id);
+    state.push(task.clone());
+    Ok(())
+  }
+
+  async fn ack_task(&self, entry_id: &str) -> Result<(), ImportError> {
+    let mut state = self.state.lock().unwrap();
+    state.pending.remove(entry_id);
+    state.queued.retain(|(id, _)| id != entry_id);
+    Ok(())
+  }
+
+  async fn acquire_workspace_lease(
+    &self,
+    workspace_id: &str,
+  ) -> Result<Option<WorkspaceImportLease>, ImportError> {
+    if !self
+      .leased_workspaces
+      .lock()
+      .unwrap()
+      .insert(workspace_id.to_string())
+    {
+      return Ok(None);
+    }
+    Ok(Some(WorkspaceImportLease::new(InMemoryLease {
+      workspace_id: workspace_id.to_string(),
+      leased_workspaces: self.leased_workspaces.clone(),
+    })))
+  }
+}
+
+struct InMemoryLease {
+  workspace_id: String,
+  leased_workspaces: Arc<Mutex<HashSet<String>>>,
+}
+
+impl Drop for InMemoryLease {
+  fn drop(&mut self) {
+    if let Ok(mut leased_workspaces) = self.leased_workspaces.lock() {
+      leased_workspaces.remove(&self.workspace_id);
+    }
+  }
+}
+
+/// [EncodedCollabCache] kept in memory, to test the import without Redis.
+#[derive(Default)]
+pub struct InMemoryEncodedCollabCache {
+  encoded_collabs: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+impl InMemoryEncodedCollabCache {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn get(&self, object_id: &str) -> Option<Vec<u8>> {
+    self.encoded_collabs.lock().unwrap().get(object_id).cloned()
+  }
+}
+
+#[async_trait]
+impl EncodedCollabCache for InMemoryEncodedCollabCache {
+  async fn insert_encoded_collab(
+    &self,
+    object_id: &str,
+    encoded_collab: Vec<u8>,
+    _expire_secs: u64,
+  ) -> Result<(), anyhow::Error> {
+    self
+      .encoded_collabs
+      .lock()
+      .unwrap()
+      .insert(object_id.to_string(), encoded_collab);
+    Ok(())
+  }
+
+  async fn remove_encoded_collabs(&self, object_ids: Vec<String>) -> Result<(), anyhow::Error> {
+    let mut encoded_collabs = self.encoded_collabs.lock().unwrap();
+    for object_id in object_ids {
+      encoded_collabs.remove(&object_id);
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  #[tokio::test]
+  async fn in_memory_queue_requeues_at_the_end() {
+    let queue = InMemoryImportTaskQueue::new();
+    queue.push_task(ImportTask::Custom(json!({"n": 1})));
+    queue.push_task(ImportTask::Custom(json!({"n": 2})));
+
+    let (entry_id, task) = queue.next_task().unwrap();
+    queue.requeue_task(&entry_id, &task).await.unwrap();
+    assert_eq!(queue.len(), 2);
+
+    let (entry_id, task) = queue.next_task().unwrap();
+    assert!(matches!(task, ImportTask::Custom(value) if value["n"] == 2));
+    queue.ack_task(&entry_id).await.unwrap();
+    let (_, task) = queue.next_task().unwrap();
+    assert!(matches!(task, ImportTask::Custom(value) if value["n"] == 1));
+    assert!(queue.next_task().is_none());
+  }
+
+  #[tokio::test]
+  async fn in_memory_lease_is_released_on_drop() {
+    let queue = InMemoryImportTaskQueue::new();
+    let lease = queue.acquire_workspace_lease("w1").await.unwrap();
+    assert!(lease.is_some());
+    assert!(queue.acquire_workspace_lease("w1").await.unwrap().is_none());
+    assert!(queue.is_workspace_leased("w1"));
+    drop(lease);
+    assert!(!queue.is_workspace_leased("w1"));
+    assert!(queue.acquire_workspace_lease("w1").await.unwrap().is_some());
+  }
+}
@@ -0,0 +1,282 @@
+use crate::error::ImportError;
+use crate::import_worker::limits::ImportLimits;
+use async_zip::base::read::stream::{Ready, ZipFileReader};
+use futures::{AsyncBufRead, AsyncReadExt};
+use std::path::{Component, Path, PathBuf};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tracing::trace;
+
+/// Turns the file name stored in a zip entry into a relative path that is guaranteed to stay
+/// inside the extraction directory. Absolute paths, `..` components and windows drive prefixes
+/// are rejected instead of being silently stripped, since a legitimate export never contains them.
+pub fn sanitize_entry_path(file_name: &str) -> Result<PathBuf, ImportError> {
+  let invalid =
+    || ImportError::UnZipFileError(format!("invalid file path in archive: {file_name}"));
+  if file_name.is_empty() || file_name.contains('\0') {
+    return Err(invalid());
+  }
+
+  // Zip files created on windows may use backslashes as separators.
+  let normalized = file_name.replace('\\', "/");
+  let mut path = PathBuf::new();
+  for component in Path::new(&normalized).components() {
+    match component {
+      Component::Normal(part) => path.push(part),
+      Component::CurDir => {},
+      Component::ParentDir | Component::RootDir | Component::Prefix(_) => return Err(invalid()),
+    }
+  }
+
+  let has_drive_prefix = path
+    .components()
+    .next()
+    .and_then(|c| c.as_os_str().to_str())
+    .map(|first| first.len() == 2 && first.ends_with(':'))
+    .unwrap_or(false);
+  if path.as_os_str().is_empty() || has_drive_prefix {
+    return Err(invalid());
+  }
+  Ok(path)
+}
+
+/// Streaming counterpart of the collab importer's `async_unzip` that applies the [ImportLimits]
+/// while extracting: entry paths are sanitized, and the number of files, the size of each file and
+/// the total number of extracted bytes are counted from the decompressed data rather than trusted
+/// from the local headers.
+///
+/// Returns the directory that contains the extracted files. As with `async_unzip`, the first
+/// directory entry of the archive is considered its root. Archives without a directory entry are
+/// extracted into `default_dir_name`.
+pub async fn guarded_async_unzip<R>(
+  mut zip_reader: ZipFileReader<Ready<R>>,
+  out_dir: PathBuf,
+  default_dir_name: Option<String>,
+  limits: &ImportLimits,
+) -> Result<PathBuf, ImportError>
+where
+  R: AsyncBufRead + Unpin,
+{
+  let mut root_dir: Option<PathBuf> = None;
+  let mut file_count = 0;
+  let mut extracted_bytes = 0;
+  let mut buffer = vec![0u8; 64 * 1024];
+
+  while let Some(mut next_reader) = zip_reader
+    .next_with_entry()
+    .await
+    .map_err(|err| ImportError::UnZipFileError(err.to_string()))?
+  {
+    let entry_reader = next_reader.reader_mut();
+    let entry = entry_reader.entry();
+    let file_name = entry
+      .filename()
+      .as_str()
+      .map_err(|err| ImportError::UnZipFileError(err.to_string()))?
+      .to_string();
+    let mut relative_path = sanitize_entry_path(&file_name)?;
+    let is_dir = file_name.ends_with('/');
+
+    if root_dir.is_none() {
+      root_dir = if is_dir {
+        Some(relative_path.clone())
+      } else {
+        Some(PathBuf::from(
+          default_dir_name
+            .clone()
+            .unwrap_or_else(|| "import".to_string()),
+        ))
+      };
+    }
+    if let Some(root_dir) = &root_dir {
+      if !relative_path.starts_with(root_dir) {
+        relative_path = root_dir.join(relative_path);
+      }
+    }
+
+    let output_path = out_dir.join(&relative_path);
+    if is_dir {
+      fs::create_dir_all(&output_path).await.map_err(io_error)?;
+    } else {
+      file_count += 1;
+      limits.check_file_count(file_count)?;
+      limits.check_entry_size(&file_name, entry.uncompressed_size())?;
+      limits.check_compression_ratio(
+        &file_name,
+        entry.compressed_size(),
+        entry.uncompressed_size(),
+      )?;
+
+      if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent).await.map_err(io_error)?;
+      }
+      let mut file = fs::File::create(&output_path).await.map_err(io_error)?;
+      let mut entry_bytes = 0;
+      loop {
+        let read = entry_reader.read(&mut buffer).await.map_err(io_error)?;
+        if read == 0 {
+          break;
+        }
+        entry_bytes += read as u64;
+        extracted_bytes += read as u64;
+        limits.check_entry_size(&file_name, entry_bytes)?;
+        limits.check_extracted_bytes(extracted_bytes)?;
+        file.write_all(&buffer[..read]).await.map_err(io_error)?;
+      }
+      file.flush().await.map_err(io_error)?;
+    }
+
+    zip_reader = next_reader
+      .done()
+      .await
+      .map_err(|err| ImportError::UnZipFileError(err.to_string()))?;
+  }
+
+  let root_dir =
+    root_dir.ok_or_else(|| ImportError::UnZipFileError("empty archive".to_string()))?;
+  trace!(
+    "[Import] extracted {} files ({} bytes) to {:?}",
+    file_count,
+    extracted_bytes,
+    out_dir.join(&root_dir)
+  );
+  Ok(out_dir.join(root_dir))
+}
+
+fn io_error(err: std::io::Error) -> ImportError {
+  ImportError::Internal(err.into())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use async_zip::base::write::ZipFileWriter;
+  use async_zip::{Compression, ZipEntryBuilder};
+  use futures::io::Cursor;
+  use uuid::Uuid;
+
+  async fn build_zip(entries: Vec<(&str, Vec<u8>)>) -> Vec<u8> {
+    let mut writer = ZipFileWriter::new(Cursor::new(Vec::new()));
+    for (name, data) in entries {
+      let entry = ZipEntryBuilder::new(name.to_string().into(), Compression::Deflate);
+      writer.write_entry_whole(entry, &data).await.unwrap();
+    }
+    writer.close().await.unwrap().into_inner()
+  }
+
+  async fn unzip(data: Vec<u8>, limits: &ImportLimits) -> (PathBuf, Result<PathBuf, ImportError>) {
+    let out_dir = std::env::temp_dir().join(Uuid::new_v4().to_string());
+    let reader = ZipFileReader::new(Cursor::new(data));
+    let result = guarded_async_unzip(reader, out_dir.clone(), Some("ws".to_string()), limits).await;
+    (out_dir, result)
+  }
+
+  async fn check_entries(data: Vec<u8>, limits: &ImportLimits) -> Result<(), ImportError> {
+    let path = std::env::temp_dir().join(format!("{}.zip", Uuid::new_v4()));
+    fs::write(&path, data).await.unwrap();
+    let result = limits.check_zip_entries(&path).await;
+    fs::remove_file(&path).await.unwrap();
+    result
+  }
+
+  #[test]
+  fn sanitize_path_test() {
+    assert_eq!(
+      sanitize_entry_path("Export/page.md").unwrap(),
+      PathBuf::from("Export/page.md")
+    );
+    assert_eq!(
+      sanitize_entry_path("./Export\\page.md").unwrap(),
+      PathBuf::from("Export/page.md")
+    );
+    for name in [
+      "../evil.md",
+      "Export/../../evil.md",
+      "/etc/passwd",
+      "..\\..\\evil.md",
+      "C:/evil.md",
+      "",
+      "./",
+    ] {
+      assert!(
+        sanitize_entry_path(name).is_err(),
+        "{name} should be rejected"
+      );
+    }
+  }
+
+  #[tokio::test]
+  async fn reject_path_traversal_archive() {
+    let data = build_zip(vec![
+      ("Export/", vec![]),
+      ("Export/page.md", b"hello".to_vec()),
+      ("../evil.md", b"evil".to_vec()),
+    ])
+    .await;
+
+    let limits = ImportLimits::default();
+    assert!(check_entries(data.clone(), &limits).await.is_err());
+
+    let (out_dir, result) = unzip(data, &limits).await;
+    assert!(result.is_err());
+    assert!(!out_dir.parent().unwrap().join("evil.md").exists());
+    let _ = fs::remove_dir_all(out_dir).await;
+  }
+
+  #[tokio::test]
+  async fn reject_zip_bomb_archive() {
+    // 16MB of zeros compresses to a few KB.
+    let data = build_zip(vec![
+      ("Export/", vec![]),
+      ("Export/bomb.csv", vec![0u8; 16 * 1024 * 1024]),
+    ])
+    .await;
+
+    let limits = ImportLimits::default();
+    assert!(matches!(
+      check_entries(data.clone(), &limits).await,
+      Err(ImportError::ArchiveLimitExceeded(_))
+    ));
+
+    let (out_dir, result) = unzip(data, &limits).await;
+    assert!(matches!(result, Err(ImportError::ArchiveLimitExceeded(_))));
+    let _ = fs::remove_dir_all(out_dir).await;
+  }
+
+  #[tokio::test]
+  async fn reject_archive_exceeding_extracted_budget() {
+    let data = build_zip(vec![
+      ("Export/", vec![]),
+      ("Export/a.md", vec![1u8; 1024]),
+      ("Export/b.md", vec![2u8; 1024]),
+    ])
+    .await;
+    let limits = ImportLimits {
+      max_extracted_bytes: 1500,
+      ..Default::default()
+    };
+    assert!(check_entries(data.clone(), &limits).await.is_err());
+    let (out_dir, result) = unzip(data, &limits).await;
+    assert!(matches!(result, Err(ImportError::ArchiveLimitExceeded(_))));
+    let _ = fs::remove_dir_all(out_dir).await;
+  }
+
+  #[tokio::test]
+  async fn extract_regular_archive() {
+    let data = build_zip(vec![
+      ("Export/", vec![]),
+      ("Export/page.md", b"hello".to_vec()),
+      ("Export/sub/table.csv", b"a,b".to_vec()),
+    ])
+    .await;
+    let limits = ImportLimits::default();
+    assert!(check_entries(data.clone(), &limits).await.is_ok());
+
+    let (out_dir, result) = unzip(data, &limits).await;
+    let root = result.unwrap();
+    assert_eq!(root, out_dir.join("Export"));
+    assert_eq!(fs::read(root.join("page.md")).await.unwrap(), b"hello");
+    assert_eq!(fs::read(root.join("sub/table.csv")).await.unwrap(), b"a,b");
+    let _ = fs::remove_dir_all(out_dir).await;
+  }
+}
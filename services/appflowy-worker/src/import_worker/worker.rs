@@ -1,4 +1,11 @@
+use crate::import_worker::appflowy_archive::import_appflowy_archive;
+use crate::import_worker::limits::{ImportLimits, LimitedStream};
+use crate::import_worker::links::NotionPageMapping;
+use crate::import_worker::relations::fix_imported_relations;
+use crate::import_worker::remote_resource::{RemoteResourceConfig, RemoteResourceFetcher};
 use crate::import_worker::report::{ImportNotifier, ImportProgress, ImportResult};
+use crate::import_worker::task_queue::{EncodedCollabCache, ImportTaskQueue, RedisImportTaskQueue};
+use crate::import_worker::unzip::guarded_async_unzip;
 use crate::s3_client::{download_file, AutoRemoveDownloadedFile, S3StreamResponse};
 use anyhow::anyhow;
 use aws_sdk_s3::primitives::ByteStream;
@@ -17,19 +24,23 @@ use collab_importer::imported_collab::ImportType;
 use collab_importer::notion::page::CollabResource;
 use collab_importer::notion::NotionImporter;
 use collab_importer::util::FileId;
-use database::collab::{insert_into_af_collab_bulk_for_user, select_blob_from_af_collab};
+use database::collab::{
+  insert_into_af_collab_bulk_for_user, select_blob_from_af_collab_in_workspace,
+};
+use database::notification_preferences::select_notification_preferences;
 use database::resource_usage::{insert_blob_metadata_bulk, BulkInsertMeta};
 use database::workspace::{
-  delete_from_workspace, select_import_task, select_workspace_database_storage_id,
+  delete_from_workspace, insert_import_page_mapping, select_import_task,
+  select_workspace_database_storage_id, transition_import_task_status, update_import_task_metadata,
   update_import_task_status, update_updated_at_of_workspace_with_uid, update_workspace_status,
   ImportTaskState,
 };
-use database_entity::dto::CollabParams;
+use database_entity::dto::{
+  CollabParams, ImportDurations, ImportSkippedItem, ImportSource, ImportSummary, IMPORT_SUMMARY_KEY,
+};
 
-use crate::metric::ImportMetrics;
+use crate::metric::{ImportMetrics, ImportStage};
 use async_zip::base::read::stream::{Ready, ZipFileReader};
-use collab_importer::zip_tool::async_zip::async_unzip;
-use collab_importer::zip_tool::sync_zip::sync_unzip;
 
 use futures::stream::FuturesUnordered;
 use futures::{stream, AsyncBufRead, AsyncReadExt, StreamExt};
@@ -43,7 +54,7 @@ use redis::{AsyncCommands, RedisResult, Value};
 
 use database::pg_row::AFImportTask;
 use serde::{Deserialize, Serialize};
-use serde_json::from_str;
+use serde_json::{from_str, json};
 use sqlx::types::chrono::{DateTime, TimeZone, Utc};
 use sqlx::PgPool;
 use std::collections::{HashMap, HashSet};
@@ -56,8 +67,9 @@ use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::str::FromStr;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::fs;
 use tokio::task::spawn_local;
 use tokio::time::{interval, MissedTickBehavior};
@@ -65,9 +77,8 @@ use tokio_util::compat::TokioAsyncReadCompatExt;
 use tracing::{error, info, trace, warn};
 use uuid::Uuid;
 
-const GROUP_NAME: &str = "import_task_group";
+pub(crate) const GROUP_NAME: &str = "import_task_group";
 const CONSUMER_NAME: &str = "appflowy_worker";
-const MAXIMUM_CONTENT_LENGTH: &str = "3221225472";
 
 #[allow(clippy::too_many_arguments)]
 pub async fn run_import_worker(
@@ -85,94 +96,65 @@ pub async fn run_import_worker(
     error!("Failed to ensure consumer group: {:?}", err);
   }
 
-  let storage_dir = temp_dir();
+  let context = TaskContext {
+    storage_dir: temp_dir(),
+    task_queue: Arc::new(RedisImportTaskQueue::new(redis_client.clone(), stream_name)),
+    collab_cache: Arc::new(redis_client.clone()),
+    s3_client,
+    pg_pool,
+    notifier,
+    metrics,
+    maximum_import_file_size: max_import_file_size,
+  };
   process_un_acked_tasks(
-    &storage_dir,
+    &context,
     &mut redis_client,
-    &s3_client,
-    &pg_pool,
     stream_name,
     GROUP_NAME,
     CONSUMER_NAME,
-    notifier.clone(),
-    &metrics,
-    max_import_file_size,
   )
   .await;
 
   process_upcoming_tasks(
-    &storage_dir,
+    &context,
     &mut redis_client,
-    &s3_client,
-    pg_pool,
     stream_name,
     GROUP_NAME,
     CONSUMER_NAME,
-    notifier.clone(),
     tick_interval_secs,
-    &metrics,
-    max_import_file_size,
   )
   .await?;
 
   Ok(())
 }
 
-#[allow(clippy::too_many_arguments)]
 async fn process_un_acked_tasks(
-  storage_dir: &Path,
+  context: &TaskContext,
   redis_client: &mut ConnectionManager,
-  s3_client: &Arc<dyn S3Client>,
-  pg_pool: &PgPool,
   stream_name: &str,
   group_name: &str,
   consumer_name: &str,
-  notifier: Arc<dyn ImportNotifier>,
-  metrics: &Option<Arc<ImportMetrics>>,
-  maximum_import_file_size: u64,
 ) {
   // when server restarts, we need to check if there are any unacknowledged tasks
   match get_un_ack_tasks(stream_name, group_name, consumer_name, redis_client).await {
     Ok(un_ack_tasks) => {
       info!("Found {} unacknowledged tasks", un_ack_tasks.len());
       for un_ack_task in un_ack_tasks {
-        let context = TaskContext {
-          storage_dir: storage_dir.to_path_buf(),
-          redis_client: redis_client.clone(),
-          s3_client: s3_client.clone(),
-          pg_pool: pg_pool.clone(),
-          notifier: notifier.clone(),
-          metrics: metrics.clone(),
-          maximum_import_file_size,
-        };
         // Ignore the error here since the consume task will handle the error
-        let _ = consume_task(
-          context,
-          un_ack_task.task,
-          stream_name,
-          group_name,
-          un_ack_task.stream_id.id,
-        )
-        .await;
+        let _ = consume_task(context.clone(), un_ack_task.task, un_ack_task.stream_id.id).await;
       }
     },
     Err(err) => error!("Failed to get unacknowledged tasks: {:?}", err),
   }
 }
 
-#[allow(clippy::too_many_arguments)]
 async fn process_upcoming_tasks(
-  storage_dir: &Path,
+  context: &TaskContext,
   redis_client: &mut ConnectionManager,
-  s3_client: &Arc<dyn S3Client>,
-  pg_pool: PgPool,
   stream_name: &str,
   group_name: &str,
   consumer_name: &str,
-  notifier: Arc<dyn ImportNotifier>,
   interval_secs: u64,
-  metrics: &Option<Arc<ImportMetrics>>,
-  maximum_import_file_size: u64,
 ) -> Result<(), ImportError> {
   let options = StreamReadOptions::default()
     .group(group_name, consumer_name)
@@ -212,28 +194,9 @@ async fn process_upcoming_tasks(
       for stream_id in stream_key.ids {
         match ImportTask::try_from(&stream_id) {
           Ok(import_task) => {
-            let stream_name = stream_name.to_string();
-            let group_name = group_name.to_string();
-
-            let context = TaskContext {
-              storage_dir: storage_dir.to_path_buf(),
-              redis_client: redis_client.clone(),
-              s3_client: s3_client.clone(),
-              pg_pool: pg_pool.clone(),
-              notifier: notifier.clone(),
-              metrics: metrics.clone(),
-              maximum_import_file_size,
-            };
-
+            let context = context.clone();
             let handle = spawn_local(async move {
-              consume_task(
-                context,
-                import_task,
-                &stream_name,
-                &group_name,
-                stream_id.id,
-              )
-              .await?;
+              consume_task(context, import_task, stream_id.id).await?;
               Ok::<(), ImportError>(())
             });
             task_handlers.push(handle);
@@ -254,29 +217,67 @@ async fn process_upcoming_tasks(
     }
   }
 }
+
+/// What a task needs to be processed. The queue, the cache and the blob storage can be replaced
+/// by their in-memory counterparts to test the import without Redis or S3.
 #[derive(Clone)]
-struct TaskContext {
-  storage_dir: PathBuf,
-  redis_client: ConnectionManager,
-  s3_client: Arc<dyn S3Client>,
-  pg_pool: PgPool,
-  notifier: Arc<dyn ImportNotifier>,
-  metrics: Option<Arc<ImportMetrics>>,
-  maximum_import_file_size: u64,
+pub struct TaskContext {
+  pub storage_dir: PathBuf,
+  pub task_queue: Arc<dyn ImportTaskQueue>,
+  pub collab_cache: Arc<dyn EncodedCollabCache>,
+  pub s3_client: Arc<dyn S3Client>,
+  pub pg_pool: PgPool,
+  pub notifier: Arc<dyn ImportNotifier>,
+  pub metrics: Option<Arc<ImportMetrics>>,
+  pub maximum_import_file_size: u64,
 }
 
-#[allow(clippy::too_many_arguments)]
-async fn consume_task(
-  mut context: TaskContext,
+/// Processes the task read from the queue with the given entry id. The entry is acknowledged once
+/// the task is done, or put back at the end of the queue when the task can't run yet.
+pub async fn consume_task(
+  context: TaskContext,
   mut import_task: ImportTask,
-  stream_name: &str,
-  group_name: &str,
   entry_id: String,
 ) -> Result<(), ImportError> {
   if let ImportTask::Notion(task) = &mut import_task {
     // If no created_at timestamp, proceed directly to processing
     if task.created_at.is_none() {
-      return process_and_ack_task(context, import_task, stream_name, group_name, &entry_id).await;
+      let Some(_lease) = context
+        .task_queue
+        .acquire_workspace_lease(&task.workspace_id)
+        .await?
+      else {
+        return context
+          .task_queue
+          .requeue_task(&entry_id, &import_task)
+          .await;
+      };
+      return process_and_ack_task(context, import_task, &entry_id).await;
+    }
+
+    // The user may have cancelled the task while it was waiting in the queue
+    if let Ok(import_record) = select_import_task(&context.pg_pool, &task.task_id).await {
+      if matches!(
+        ImportTaskState::from(import_record.status),
+        ImportTaskState::Cancel
+      ) {
+        info!("[Import] {} task was cancelled", task.workspace_id);
+        discard_task(&context, &import_record, task, &entry_id).await;
+        return Ok(());
+      }
+    }
+
+    // Scheduled tasks go back to the end of the queue until they are due
+    if task
+      .start_after
+      .is_some_and(|start_after| start_after > Utc::now().timestamp())
+    {
+      trace!("[Import] {} task is not due yet", task.workspace_id);
+      context
+        .task_queue
+        .requeue_task(&entry_id, &import_task)
+        .await?;
+      return Ok(());
     }
 
     match task.file_size {
@@ -292,11 +293,9 @@ async fn consume_task(
           let max_size_in_mb = (context.maximum_import_file_size as f64 / 1_048_576.0).ceil();
           if let Ok(import_record) = select_import_task(&context.pg_pool, &task.task_id).await {
             handle_failed_task(
-              &mut context,
+              &context,
               &import_record,
               task,
-              stream_name,
-              group_name,
               &entry_id,
               ImportError::UploadFileTooLarge {
                 file_size_in_mb,
@@ -315,16 +314,18 @@ async fn consume_task(
       },
     }
 
-    // Check if the task is expired
-    if let Err(reason) = is_task_expired(task.created_at.unwrap(), task.last_process_at) {
+    // Check if the task is expired. Scheduled tasks expire relative to their start time.
+    let created_at = task.created_at.unwrap();
+    let started_at = task
+      .start_after
+      .map_or(created_at, |start_after| start_after.max(created_at));
+    if let Err(reason) = is_task_expired(started_at, task.last_process_at) {
       if let Ok(import_record) = select_import_task(&context.pg_pool, &task.task_id).await {
         error!("[Import] {} task is expired: {}", task.workspace_id, reason);
         handle_failed_task(
-          &mut context,
+          &context,
           &import_record,
           task,
-          stream_name,
-          group_name,
           &entry_id,
           ImportError::UploadFileExpire,
           ImportTaskState::Expire,
@@ -334,40 +335,69 @@ async fn consume_task(
       return Ok(());
     }
 
-    // Check if the blob exists
-    if check_blob_existence(&context.s3_client, &task.s3_key).await? {
+    // Check if the blobs exist, the parts of an import are only processed once all of them are
+    // uploaded
+    let mut is_uploaded = true;
+    for s3_key in task.s3_keys() {
+      if !check_blob_existence(&context.s3_client, s3_key).await? {
+        is_uploaded = false;
+        break;
+      }
+    }
+    if is_uploaded {
+      // Only one import may write to a workspace at a time. If another worker is still importing
+      // into the same workspace, put the task back to the end of the queue.
+      let Some(_lease) = context
+        .task_queue
+        .acquire_workspace_lease(&task.workspace_id)
+        .await?
+      else {
+        info!(
+          "[Import] {} another import is in progress, queue task",
+          task.workspace_id
+        );
+        context
+          .task_queue
+          .requeue_task(&entry_id, &import_task)
+          .await?;
+        return Ok(());
+      };
+
+      if task.start_after.is_some() {
+        transition_import_task_status(
+          &task.task_id,
+          ImportTaskState::Scheduled,
+          ImportTaskState::Pending,
+          &context.pg_pool,
+        )
+        .await
+        .map_err(|err| ImportError::Internal(err.into()))?;
+      }
       if task.last_process_at.is_none() {
         task.last_process_at = Some(Utc::now().timestamp());
       }
-      process_and_ack_task(context, import_task, stream_name, group_name, &entry_id).await
+      process_and_ack_task(context, import_task, &entry_id).await
     } else {
       info!(
         "[Import] {} zip file not found, queue task",
         task.workspace_id
       );
-      push_task(
-        &mut context.redis_client,
-        stream_name,
-        group_name,
-        import_task,
-        &entry_id,
-      )
-      .await?;
+      context
+        .task_queue
+        .requeue_task(&entry_id, &import_task)
+        .await?;
       Ok(())
     }
   } else {
     // If the task is not a notion task, proceed directly to processing
-    process_and_ack_task(context, import_task, stream_name, group_name, &entry_id).await
+    process_and_ack_task(context, import_task, &entry_id).await
   }
 }
 
-#[allow(clippy::too_many_arguments)]
 async fn handle_failed_task(
-  context: &mut TaskContext,
+  context: &TaskContext,
   import_record: &AFImportTask,
   task: &NotionImportTask,
-  stream_name: &str,
-  group_name: &str,
   entry_id: &str,
   error: ImportError,
   task_state: ImportTaskState,
@@ -383,24 +413,38 @@ async fn handle_failed_task(
       error!("Failed to update import task status: {:?}", e);
       ImportError::Internal(e.into())
     })?;
+  discard_task(context, import_record, task, entry_id).await;
+  let result = Err(error);
+  save_import_summary(task, &result, ImportDurations::default(), &context.pg_pool).await;
+  notify_user(
+    task,
+    result,
+    &context.pg_pool,
+    context.notifier.clone(),
+    &context.metrics,
+  )
+  .await?;
+  Ok(())
+}
+
+/// Removes the workspace created for the task, the uploaded file and the task itself from the
+/// queue.
+async fn discard_task(
+  context: &TaskContext,
+  import_record: &AFImportTask,
+  task: &NotionImportTask,
+  entry_id: &str,
+) {
   remove_workspace(&import_record.workspace_id, &context.pg_pool).await;
   info!("[Import]: deleted workspace {}", task.workspace_id);
 
-  if let Err(err) = context.s3_client.delete_blob(task.s3_key.as_str()).await {
-    error!(
-      "[Import]: {} failed to delete zip file from S3: {:?}",
-      task.workspace_id, err
-    );
-  }
-  if let Err(err) = delete_task(&mut context.redis_client, stream_name, group_name, entry_id).await
-  {
+  clean_up(&context.s3_client, task).await;
+  if let Err(err) = context.task_queue.ack_task(entry_id).await {
     error!(
       "[Import] failed to acknowledge task:{} error:{:?}",
       task.workspace_id, err
     );
   }
-  notify_user(task, Err(error), context.notifier.clone(), &context.metrics).await?;
-  Ok(())
 }
 
 async fn check_blob_existence(
@@ -414,16 +458,12 @@ async fn check_blob_existence(
 }
 
 async fn process_and_ack_task(
-  mut context: TaskContext,
+  context: TaskContext,
   import_task: ImportTask,
-  stream_name: &str,
-  group_name: &str,
   entry_id: &str,
 ) -> Result<(), ImportError> {
   let result = process_task(context.clone(), import_task).await;
-  delete_task(&mut context.redis_client, stream_name, group_name, entry_id)
-    .await
-    .ok();
+  context.task_queue.ack_task(entry_id).await.ok();
   result
 }
 
@@ -480,64 +520,7 @@ fn is_task_expired(created_timestamp: i64, last_process_at: Option<i64>) -> Resu
   }
 }
 
-async fn push_task(
-  redis_client: &mut ConnectionManager,
-  stream_name: &str,
-  _group_name: &str,
-  task: ImportTask,
-  entry_id: &str,
-) -> Result<(), ImportError> {
-  let task_str = serde_json::to_string(&task).map_err(|e| {
-    error!("Failed to serialize task: {:?}", e);
-    ImportError::Internal(e.into())
-  })?;
-
-  let mut pipeline = redis::pipe();
-  pipeline
-      .atomic() // Ensures the commands are executed atomically
-      .cmd("XDEL") // delete the task
-      .arg(stream_name)
-      .arg(entry_id)
-      .ignore() // Ignore the result of XDEL
-      .cmd("XADD") // Re-add the task to the stream
-      .arg(stream_name)
-      .arg("*")
-      .arg("task")
-      .arg(task_str);
-
-  let result: Result<(), redis::RedisError> = pipeline.query_async(redis_client).await;
-  match result {
-    Ok(_) => Ok(()),
-    Err(err) => {
-      error!(
-        "Failed to execute transaction for re-adding task: {:?}",
-        err
-      );
-      Err(ImportError::Internal(err.into()))
-    },
-  }
-}
-
-async fn delete_task(
-  redis_client: &mut ConnectionManager,
-  stream_name: &str,
-  _group_name: &str,
-  entry_id: &str,
-) -> Result<(), ImportError> {
-  let _: () = redis_client
-    .xdel(stream_name, &[entry_id])
-    .await
-    .map_err(|e| {
-      error!("Failed to delete import task: {:?}", e);
-      ImportError::Internal(e.into())
-    })?;
-  Ok(())
-}
-
-async fn process_task(
-  mut context: TaskContext,
-  import_task: ImportTask,
-) -> Result<(), ImportError> {
+async fn process_task(context: TaskContext, import_task: ImportTask) -> Result<(), ImportError> {
   let retry_interval: u64 = get_env_var("APPFLOWY_WORKER_IMPORT_TASK_RETRY_INTERVAL", "10")
     .parse()
     .unwrap_or(10);
@@ -550,6 +533,11 @@ async fn process_task(
 
   match import_task {
     ImportTask::Notion(task) => {
+      let _active_task = context
+        .metrics
+        .as_ref()
+        .map(|metrics| metrics.start_task(task.source));
+      let started_at = Instant::now();
       // 1. download zip file
       let unzip_result = download_and_unzip_file_retry(
         &context.storage_dir,
@@ -562,6 +550,10 @@ async fn process_task(
       )
       .await;
 
+      let mut durations = ImportDurations {
+        download_ms: started_at.elapsed().as_millis() as u64,
+        ..Default::default()
+      };
       trace!(
         "[Import]: {} download and unzip file result: {:?}",
         task.workspace_id,
@@ -570,14 +562,30 @@ async fn process_task(
       match unzip_result {
         Ok(unzip_dir_path) => {
           // 2. process unzip file
-          let result = process_unzip_file(
-            &task,
-            &unzip_dir_path,
-            &context.pg_pool,
-            &mut context.redis_client,
-            &context.s3_client,
-          )
-          .await;
+          let result = match task.source {
+            ImportSource::Notion => {
+              process_unzip_file(
+                &task,
+                &unzip_dir_path,
+                &context.pg_pool,
+                context.collab_cache.as_ref(),
+                &context.s3_client,
+                &context.metrics,
+              )
+              .await
+            },
+            ImportSource::AppFlowy => {
+              import_appflowy_archive(
+                &task,
+                &unzip_dir_path,
+                &context.pg_pool,
+                context.collab_cache.as_ref(),
+                &context.s3_client,
+                &context.metrics,
+              )
+              .await
+            },
+          };
 
           // If there is any errors when processing the unzip file, we will remove the workspace and notify the user.
           if result.is_err() {
@@ -589,7 +597,16 @@ async fn process_task(
           }
 
           clean_up(&context.s3_client, &task).await;
-          notify_user(&task, result, context.notifier, &context.metrics).await?;
+          durations.total_ms = started_at.elapsed().as_millis() as u64;
+          save_import_summary(&task, &result, durations, &context.pg_pool).await;
+          notify_user(
+            &task,
+            result,
+            &context.pg_pool,
+            context.notifier,
+            &context.metrics,
+          )
+          .await?;
 
           tokio::spawn(async move {
             match fs::remove_dir_all(&unzip_dir_path).await {
@@ -607,12 +624,19 @@ async fn process_task(
         },
         Err(err) => {
           // If there is any errors when download or unzip the file, we will remove the file from S3 and notify the user.
-          if let Err(err) = &context.s3_client.delete_blob(task.s3_key.as_str()).await {
-            error!("Failed to delete zip file from S3: {:?}", err);
-          }
           remove_workspace(&task.workspace_id, &context.pg_pool).await;
           clean_up(&context.s3_client, &task).await;
-          notify_user(&task, Err(err), context.notifier, &context.metrics).await?;
+          let result = Err(err);
+          durations.total_ms = started_at.elapsed().as_millis() as u64;
+          save_import_summary(&task, &result, durations, &context.pg_pool).await;
+          notify_user(
+            &task,
+            result,
+            &context.pg_pool,
+            context.notifier,
+            &context.metrics,
+          )
+          .await?;
         },
       }
 
@@ -637,6 +661,8 @@ async fn process_task(
 /// Retries the download and unzipping of a file from an S3 source.
 ///
 /// This function attempts to download a zip file from an S3 bucket and unzip it to a local directory.
+/// An export split into several zips is downloaded and unzipped as a whole, a failure of any of
+/// its parts fails the attempt.
 /// If the operation fails, it will retry up to `max_retries` times, waiting for `interval` between each attempt.
 ///
 pub async fn download_and_unzip_file_retry(
@@ -651,11 +677,12 @@ pub async fn download_and_unzip_file_retry(
   let mut attempt = 0;
   loop {
     attempt += 1;
-    match download_and_unzip_file(storage_dir, import_task, s3_client, streaming, metrics).await {
+    match download_and_unzip_parts(storage_dir, import_task, s3_client, streaming, metrics).await {
       Ok(result) => return Ok(result),
       Err(err) => {
-        // If the Upload file not found error occurs, we will not retry.
-        if matches!(err, ImportError::UploadFileNotFound) {
+        // If the Upload file not found error occurs, or the file exceeds the import limits, we
+        // will not retry.
+        if matches!(err, ImportError::UploadFileNotFound) || err.is_limit_exceeded() {
           return Err(err);
         }
 
@@ -676,6 +703,103 @@ pub async fn download_and_unzip_file_retry(
     }
   }
 }
+/// Downloads and unzips all the zips of the task. The other parts are unzipped next to the first
+/// one and their directory trees are merged into the one of the first part, which is returned.
+async fn download_and_unzip_parts(
+  storage_dir: &Path,
+  import_task: &NotionImportTask,
+  s3_client: &Arc<dyn S3Client>,
+  streaming: bool,
+  metrics: &Option<Arc<ImportMetrics>>,
+) -> Result<PathBuf, ImportError> {
+  let unzip_dir_path = download_and_unzip_file(
+    storage_dir,
+    import_task,
+    &import_task.s3_key,
+    &import_task.md5_base64,
+    s3_client,
+    streaming,
+    metrics,
+  )
+  .await?;
+
+  for s3_key in &import_task.part_s3_keys {
+    let result = async {
+      let part_dir = storage_dir.join(Uuid::new_v4().to_string());
+      fs::create_dir_all(&part_dir)
+        .await
+        .map_err(|err| ImportError::Internal(err.into()))?;
+      let result = async {
+        let part_unzip_dir = download_and_unzip_file(
+          &part_dir,
+          import_task,
+          s3_key,
+          &None,
+          s3_client,
+          streaming,
+          metrics,
+        )
+        .await?;
+        merge_dir_into(&part_unzip_dir, &unzip_dir_path).await
+      }
+      .await;
+      if let Err(err) = fs::remove_dir_all(&part_dir).await {
+        error!("Failed to delete unzip dir of part {}: {:?}", s3_key, err);
+      }
+      result
+    }
+    .await;
+
+    if let Err(err) = result {
+      if let Err(err) = fs::remove_dir_all(&unzip_dir_path).await {
+        error!("Failed to delete unzip file: {:?}", err);
+      }
+      return Err(err);
+    }
+    info!(
+      "[Import] {} merged part {} into {:?}",
+      import_task.workspace_id, s3_key, unzip_dir_path
+    );
+  }
+  Ok(unzip_dir_path)
+}
+
+/// Moves the content of `src` into `dest`. Notion splits an export by files, so the parts share
+/// directories but not files: the directories are merged, and a file that is already in `dest` is
+/// kept.
+async fn merge_dir_into(src: &Path, dest: &Path) -> Result<(), ImportError> {
+  let mut dirs = vec![(src.to_path_buf(), dest.to_path_buf())];
+  while let Some((src_dir, dest_dir)) = dirs.pop() {
+    let mut entries = fs::read_dir(&src_dir)
+      .await
+      .map_err(|err| ImportError::Internal(err.into()))?;
+    while let Some(entry) = entries
+      .next_entry()
+      .await
+      .map_err(|err| ImportError::Internal(err.into()))?
+    {
+      let src_path = entry.path();
+      let dest_path = dest_dir.join(entry.file_name());
+      let is_dir = entry
+        .file_type()
+        .await
+        .map_err(|err| ImportError::Internal(err.into()))?
+        .is_dir();
+      match fs::metadata(&dest_path).await {
+        Ok(dest_meta) if is_dir && dest_meta.is_dir() => dirs.push((src_path, dest_path)),
+        Ok(_) => warn!(
+          "[Import] {:?} is in more than one part of the export, keep the first one",
+          dest_path
+        ),
+        Err(_) => fs::rename(&src_path, &dest_path)
+          .await
+          .map_err(|err| ImportError::Internal(err.into()))?,
+      }
+    }
+  }
+  Ok(())
+}
+
 /// Downloads a zip file from S3 and unzips it to the local directory.
 ///
 /// This function fetches a zip file from an S3 source using the provided S3 client,
@@ -684,16 +808,18 @@ pub async fn download_and_unzip_file_retry(
 async fn download_and_unzip_file(
   storage_dir: &Path,
   import_task: &NotionImportTask,
+  s3_key: &str,
+  md5_base64: &Option<String>,
   s3_client: &Arc<dyn S3Client>,
   streaming: bool,
   metrics: &Option<Arc<ImportMetrics>>,
 ) -> Result<PathBuf, ImportError> {
-  let blob_meta = s3_client.get_blob_meta(import_task.s3_key.as_str()).await?;
+  let blob_meta = s3_client.get_blob_meta(s3_key).await?;
   match blob_meta.content_type {
     None => {
       error!(
         "[Import] {} failed to get content type for file: {:?}",
-        import_task.workspace_id, import_task.s3_key
+        import_task.workspace_id, s3_key
       );
     },
     Some(content_type) => {
@@ -713,24 +839,13 @@ async fn download_and_unzip_file(
     },
   }
 
-  let max_content_length = get_env_var(
-    "APPFLOWY_WORKER_IMPORT_TASK_MAX_FILE_SIZE_BYTES",
-    MAXIMUM_CONTENT_LENGTH,
-  )
-  .parse::<i64>()
-  .unwrap();
-  if blob_meta.content_length > max_content_length {
-    return Err(ImportError::Internal(anyhow!(
-      "File size is too large: {} bytes, max allowed: {} bytes",
-      blob_meta.content_length,
-      max_content_length
-    )));
-  }
+  let limits = ImportLimits::from_env();
+  limits.check_archive_size(blob_meta.content_length)?;
 
   trace!(
     "[Import] {} start download file: {:?}, size: {}",
     import_task.workspace_id,
-    import_task.s3_key,
+    s3_key,
     blob_meta.content_length
   );
 
@@ -738,70 +853,117 @@ async fn download_and_unzip_file(
     stream,
     content_type: _,
     content_length,
-  } = s3_client
-    .get_blob_stream(import_task.s3_key.as_str())
-    .await?;
+  } = s3_client.get_blob_stream(s3_key).await?;
 
   let buffer_size = buffer_size_from_content_length(content_length);
   if let Some(metrics) = metrics {
     metrics.record_import_size_bytes(buffer_size);
   }
   if streaming {
-    let zip_reader = get_zip_reader(buffer_size, StreamOrFile::Stream(stream)).await?;
-    let unique_file_name = Uuid::new_v4().to_string();
-    let output_file_path = storage_dir.join(unique_file_name);
-    fs::create_dir_all(&output_file_path)
-      .await
-      .map_err(|err| ImportError::Internal(err.into()))?;
-    fs::set_permissions(&output_file_path, Permissions::from_mode(0o777))
-      .await
-      .map_err(|err| {
-        ImportError::Internal(anyhow!("Failed to set permissions for temp dir: {:?}", err))
-      })?;
-    let unzip_file = async_unzip(
+    let unzip_started_at = Instant::now();
+    let stream = LimitedStream::new(stream, limits.max_archive_bytes);
+    let exceeded = stream.exceeded_flag();
+    let zip_reader = get_zip_reader(buffer_size, StreamOrFile::Stream(Box::new(stream))).await?;
+    let output_file_path = create_unzip_dir(storage_dir).await?;
+    let unzip_dir_path = guarded_async_unzip(
       zip_reader.inner,
       output_file_path,
       Some(import_task.workspace_name.clone()),
+      &limits,
     )
-    .await?;
-    Ok(unzip_file.unzip_dir_path)
+    .await
+    .map_err(|err| {
+      if exceeded.load(Ordering::Relaxed) {
+        limits.archive_too_large(limits.max_archive_bytes + 1)
+      } else {
+        err
+      }
+    })?;
+    record_import_stage(
+      metrics,
+      import_task.source,
+      ImportStage::Unzip,
+      unzip_started_at,
+    );
+    Ok(unzip_dir_path)
   } else {
+    let download_started_at = Instant::now();
+    let stream = LimitedStream::new(stream, limits.max_archive_bytes);
+    let exceeded = stream.exceeded_flag();
     let file = download_file(
       &import_task.workspace_id,
       storage_dir,
-      stream,
-      &import_task.md5_base64,
+      Box::new(stream),
+      md5_base64,
     )
-    .await?;
+    .await
+    .map_err(|err| {
+      if exceeded.load(Ordering::Relaxed) {
+        limits.archive_too_large(limits.max_archive_bytes + 1)
+      } else {
+        ImportError::from(err)
+      }
+    })?;
+    record_import_stage(
+      metrics,
+      import_task.source,
+      ImportStage::Download,
+      download_started_at,
+    );
+    let unzip_started_at = Instant::now();
+    // Fail fast on the sizes listed in the central directory. They can't be trusted, the limits
+    // are applied again to the bytes actually extracted.
+    limits.check_zip_entries(file.path_buf()).await?;
     trace!(
       "[Import] {} start unzip file: {:?}",
       import_task.workspace_id,
       file.path_buf()
     );
 
-    let file_path = file.path_buf().clone();
-    let storage_dir = storage_dir.to_path_buf();
-    let workspace_name = import_task.workspace_name.clone();
-    let unzip_file =
-      tokio::task::spawn_blocking(move || sync_unzip(file_path, storage_dir, Some(workspace_name)))
-        .await
-        .map_err(|err| ImportError::Internal(err.into()))??;
+    let zip_reader = get_zip_reader(buffer_size, StreamOrFile::File(file)).await?;
+    let output_file_path = create_unzip_dir(storage_dir).await?;
+    let unzip_dir_path = guarded_async_unzip(
+      zip_reader.inner,
+      output_file_path,
+      Some(import_task.workspace_name.clone()),
+      &limits,
+    )
+    .await?;
 
     info!(
-      "[Import] {} finish unzip file to dir:{}, file:{:?}",
-      import_task.workspace_id, unzip_file.dir_name, unzip_file.unzip_dir
+      "[Import] {} finish unzip file to dir:{:?}",
+      import_task.workspace_id, unzip_dir_path
+    );
+    record_import_stage(
+      metrics,
+      import_task.source,
+      ImportStage::Unzip,
+      unzip_started_at,
     );
-    Ok(unzip_file.unzip_dir)
+    Ok(unzip_dir_path)
   }
 }
 
+/// Creates a unique directory to extract an archive to.
+async fn create_unzip_dir(storage_dir: &Path) -> Result<PathBuf, ImportError> {
+  let output_file_path = storage_dir.join(Uuid::new_v4().to_string());
+  fs::create_dir_all(&output_file_path)
+    .await
+    .map_err(|err| ImportError::Internal(err.into()))?;
+  fs::set_permissions(&output_file_path, Permissions::from_mode(0o777))
+    .await
+    .map_err(|err| {
+      ImportError::Internal(anyhow!("Failed to set permissions for temp dir: {:?}", err))
+    })?;
+  Ok(output_file_path)
+}
+
 struct ZipReader {
   inner: ZipFileReader<Ready<Pin<Box<dyn AsyncBufRead + Unpin + Send>>>>,
   #[allow(dead_code)]
   file: Option<AutoRemoveDownloadedFile>,
 }
 
-#[allow(dead_code)]
 enum StreamOrFile {
   Stream(Box<dyn AsyncBufRead + Unpin + Send>),
   File(AutoRemoveDownloadedFile),
@@ -869,9 +1031,11 @@ async fn process_unzip_file(
   import_task: &NotionImportTask,
   unzip_dir_path: &PathBuf,
   pg_pool: &PgPool,
-  redis_client: &mut ConnectionManager,
+  collab_cache: &dyn EncodedCollabCache,
   s3_client: &Arc<dyn S3Client>,
-) -> Result<(), ImportError> {
+  metrics: &Option<Arc<ImportMetrics>>,
+) -> Result<ImportSummary, ImportError> {
+  let collab_build_started_at = Instant::now();
   let workspace_id =
     Uuid::parse_str(&import_task.workspace_id).map_err(|err| ImportError::Internal(err.into()))?;
   let notion_importer = NotionImporter::new(
@@ -891,6 +1055,7 @@ async fn process_unzip_file(
     .await
     .map_err(ImportError::ImportCollabError)?;
   let nested_views = imported.build_nested_views().await;
+  let page_mapping = NotionPageMapping::from_pages(imported.views());
   trace!(
     "[Import]: {} imported nested views:{}",
     import_task.workspace_id,
@@ -925,8 +1090,15 @@ async fn process_unzip_file(
 
   let mut resources = vec![];
   let mut collab_params_list = vec![];
+  let mut remote_resource_fetcher = RemoteResourceConfig::from_env()
+    .map(|config| RemoteResourceFetcher::new(config, unzip_dir_path.join("remote_resources")))
+    .transpose()?;
   let mut database_view_ids_by_database_id: HashMap<String, Vec<String>> = HashMap::new();
   let mut orphan_view_ids = HashSet::new();
+  let mut summary = ImportSummary {
+    mapped_pages: page_mapping.page_count(),
+    ..Default::default()
+  };
 
   // 3. Collect all collabs and resources
   let mut stream = imported.into_collab_stream().await;
@@ -937,17 +1109,48 @@ async fn process_unzip_file(
       imported_collab_info
     );
     resources.extend(imported_collab_info.resources);
-    collab_params_list.extend(
-      imported_collab_info
-        .imported_collabs
-        .into_iter()
-        .map(|imported_collab| CollabParams {
-          object_id: imported_collab.object_id,
-          collab_type: imported_collab.collab_type,
-          encoded_collab_v1: Bytes::from(imported_collab.encoded_collab.encode_to_bytes().unwrap()),
-        })
-        .collect::<Vec<_>>(),
+    let is_database = matches!(
+      imported_collab_info.import_type,
+      ImportType::Database { .. }
     );
+    for imported_collab in imported_collab_info.imported_collabs {
+      match (&imported_collab.collab_type, is_database) {
+        (CollabType::Document, false) => summary.documents += 1,
+        (CollabType::Database, _) => summary.databases += 1,
+        (CollabType::DatabaseRow, _) => summary.rows += 1,
+        _ => {},
+      }
+      let mut encoded_collab = imported_collab.encoded_collab;
+      // The links to the other pages of the export would point back to Notion
+      if imported_collab.collab_type == CollabType::Document && !page_mapping.is_empty() {
+        if let Some((rewritten, count)) =
+          page_mapping.rewrite_document_links(&imported_collab.object_id, &encoded_collab)?
+        {
+          encoded_collab = rewritten;
+          summary.rewritten_links += count;
+        }
+      }
+      if let (Some(fetcher), CollabType::Document) = (
+        remote_resource_fetcher.as_mut(),
+        &imported_collab.collab_type,
+      ) {
+        let (localized, remote_resources) = fetcher
+          .localize_document(
+            &import_task.host,
+            &import_task.workspace_id,
+            &imported_collab.object_id,
+            encoded_collab,
+          )
+          .await;
+        encoded_collab = localized;
+        resources.extend(remote_resources);
+      }
+      collab_params_list.push(CollabParams {
+        object_id: imported_collab.object_id,
+        collab_type: imported_collab.collab_type,
+        encoded_collab_v1: Bytes::from(encoded_collab.encode_to_bytes().unwrap()),
+      });
+    }
 
     match imported_collab_info.import_type {
       ImportType::Database {
@@ -964,6 +1167,11 @@ async fn process_unzip_file(
     }
   }
 
+  // 4. Point the relation cells at the imported rows
+  let relation_fixes = fix_imported_relations(&mut collab_params_list);
+  summary.repaired_relations = relation_fixes.repaired;
+  summary.removed_relations = relation_fixes.removed;
+
   let w_database_id = select_workspace_database_storage_id(pg_pool, &import_task.workspace_id)
     .await
     .map_err(|err| {
@@ -974,7 +1182,7 @@ async fn process_unzip_file(
     })
     .map(|id| id.to_string())?;
 
-  // 4. Edit workspace database collab and then encode workspace database collab
+  // 5. Edit workspace database collab and then encode workspace database collab
   if !database_view_ids_by_database_id.is_empty() {
     let w_db_collab = get_encode_collab_from_bytes(
       &import_task.workspace_id,
@@ -1001,9 +1209,9 @@ async fn process_unzip_file(
 
     match w_database_collab.encode_to_bytes() {
       Ok(bytes) => {
-        if let Err(err) = redis_client
-          .set_ex::<String, Vec<u8>, Value>(
-            encode_collab_key(&w_database_id),
+        if let Err(err) = collab_cache
+          .insert_encoded_collab(
+            &w_database_id,
             bytes,
             2592000, // WorkspaceDatabase => 1 month
           )
@@ -1033,7 +1241,7 @@ async fn process_unzip_file(
     collab_params_list.push(w_database_collab_params);
   }
 
-  // 5. Insert orphan view to folder
+  // 6. Insert orphan view to folder
   let orphan_views = orphan_view_ids
     .into_iter()
     .map(|orphan_view_id| {
@@ -1044,16 +1252,16 @@ async fn process_unzip_file(
     folder.insert_views(orphan_views);
   }
 
-  // 6. Encode Folder
+  // 7. Encode Folder
   let folder_collab = folder
     .encode_collab_v1(|collab| CollabType::Folder.validate_require_data(collab))
     .map_err(|err| ImportError::Internal(err.into()))?;
 
   match folder_collab.encode_to_bytes() {
     Ok(bytes) => {
-      if let Err(err) = redis_client
-        .set_ex::<String, Vec<u8>, Value>(
-          encode_collab_key(&import_task.workspace_id),
+      if let Err(err) = collab_cache
+        .insert_encoded_collab(
+          &import_task.workspace_id,
           bytes,
           604800, // Folder => 1 week
         )
@@ -1076,9 +1284,21 @@ async fn process_unzip_file(
   );
   collab_params_list.push(folder_collab_params);
 
-  let upload_resources = process_resources(resources).await;
+  if let Some(fetcher) = remote_resource_fetcher.as_mut() {
+    summary.skipped_items.extend(fetcher.take_skipped_items());
+  }
+  let (upload_resources, skipped_resources) = process_resources(resources).await;
+  summary.skipped_items.extend(skipped_resources);
+  summary.uploaded_files = upload_resources.len();
+  summary.durations.collab_build_ms = record_import_stage(
+    metrics,
+    import_task.source,
+    ImportStage::CollabBuild,
+    collab_build_started_at,
+  );
 
-  // 7. Start a transaction to insert all collabs
+  // 8. Start a transaction to insert all collabs
+  let db_insert_started_at = Instant::now();
   let mut transaction = pg_pool.begin().await.map_err(|err| {
     ImportError::Internal(anyhow!(
       "Failed to start transaction when importing data: {:?}",
@@ -1091,7 +1311,7 @@ async fn process_unzip_file(
     import_task.workspace_id
   );
 
-  // 8. write all collab to disk
+  // 9. write all collab to disk
   insert_into_af_collab_bulk_for_user(
     &mut transaction,
     &import_task.uid,
@@ -1111,8 +1331,11 @@ async fn process_unzip_file(
     import_task.workspace_id,
     import_task.task_id,
   );
-  update_import_task_status(
+  // The task may have been cancelled while it was being imported, in which case the transaction
+  // is rolled back and the workspace removed.
+  let completed = transition_import_task_status(
     &import_task.task_id,
+    ImportTaskState::Pending,
     ImportTaskState::Completed,
     transaction.deref_mut(),
   )
@@ -1123,6 +1346,9 @@ async fn process_unzip_file(
       err
     ))
   })?;
+  if !completed {
+    return Err(ImportError::Cancelled);
+  }
 
   trace!(
     "[Import]: {} set is_initialized to true",
@@ -1178,6 +1404,40 @@ async fn process_unzip_file(
     );
   }
 
+  // keep the view of each page, for the links to the pages that are outside of the documents
+  insert_import_page_mapping(
+    transaction.deref_mut(),
+    &import_task.task_id,
+    &workspace_id,
+    page_mapping
+      .entries()
+      .map(|(source_id, view_id)| (source_id.clone(), view_id.clone()))
+      .collect(),
+  )
+  .await
+  .map_err(|err| {
+    ImportError::Internal(anyhow!(
+      "Failed to save the page mapping when importing data: {:?}",
+      err
+    ))
+  })?;
+
+  // keep the summary, so that it can be retrieved with the import task
+  let summary_value =
+    serde_json::to_value(&summary).map_err(|err| ImportError::Internal(err.into()))?;
+  update_import_task_metadata(
+    import_task.task_id,
+    json!({ IMPORT_SUMMARY_KEY: summary_value }),
+    transaction.deref_mut(),
+  )
+  .await
+  .map_err(|err| {
+    ImportError::Internal(anyhow!(
+      "Failed to save the import summary when importing data: {:?}",
+      err
+    ))
+  })?;
+
   let result = transaction.commit().await.map_err(|err| {
     ImportError::Internal(anyhow!(
       "Failed to commit transaction when importing data: {:?}",
@@ -1185,26 +1445,55 @@ async fn process_unzip_file(
     ))
   });
 
-  if result.is_err() {
-    let _: RedisResult<Value> = redis_client.del(encode_collab_key(&w_database_id)).await;
-    let _: RedisResult<Value> = redis_client
-      .del(encode_collab_key(&import_task.workspace_id))
+  if let Err(err) = result {
+    let _ = collab_cache
+      .remove_encoded_collabs(vec![w_database_id, import_task.workspace_id.clone()])
       .await;
 
-    return result;
+    return Err(err);
   }
+  summary.durations.db_insert_ms = record_import_stage(
+    metrics,
+    import_task.source,
+    ImportStage::DbInsert,
+    db_insert_started_at,
+  );
 
-  // 9. after inserting all collabs, upload all files to S3
+  // 10. after inserting all collabs, upload all files to S3
   trace!("[Import]: {} upload files to s3", import_task.workspace_id,);
+  let s3_upload_started_at = Instant::now();
   batch_upload_files_to_s3(&import_task.workspace_id, s3_client, upload_resources)
     .await
     .map_err(|err| ImportError::Internal(anyhow!("Failed to upload files to S3: {:?}", err)))?;
-  Ok(())
+  summary.durations.upload_ms = record_import_stage(
+    metrics,
+    import_task.source,
+    ImportStage::S3Upload,
+    s3_upload_started_at,
+  );
+  Ok(summary)
 }
 
+/// Returns the duration of the stage in milliseconds, to be kept in the summary of the import.
+pub(crate) fn record_import_stage(
+  metrics: &Option<Arc<ImportMetrics>>,
+  source: ImportSource,
+  stage: ImportStage,
+  started_at: Instant,
+) -> u64 {
+  let elapsed = started_at.elapsed();
+  if let Some(metrics) = metrics {
+    metrics.record_stage_duration(source, stage, elapsed);
+  }
+  elapsed.as_millis() as u64
+}
+
+/// Deletes the zips of all the parts of the task from S3.
 async fn clean_up(s3_client: &Arc<dyn S3Client>, task: &NotionImportTask) {
-  if let Err(err) = s3_client.delete_blob(task.s3_key.as_str()).await {
-    error!("Failed to delete zip file from S3: {:?}", err);
+  for s3_key in task.s3_keys() {
+    if let Err(err) = s3_client.delete_blob(s3_key).await {
+      error!("Failed to delete zip file {} from S3: {:?}", s3_key, err);
+    }
   }
 }
 
@@ -1219,20 +1508,87 @@ async fn remove_workspace(workspace_id: &str, pg_pool: &PgPool) {
   }
 }
 
+/// Keeps the outcome of the task in its metadata, so that it can be retrieved once the report
+/// email is gone. A task that failed while it was pending is marked as failed.
+async fn save_import_summary(
+  import_task: &NotionImportTask,
+  result: &Result<ImportSummary, ImportError>,
+  durations: ImportDurations,
+  pg_pool: &PgPool,
+) {
+  let summary = match result {
+    Ok(summary) => ImportSummary {
+      durations: ImportDurations {
+        download_ms: durations.download_ms,
+        total_ms: durations.total_ms,
+        ..summary.durations.clone()
+      },
+      ..summary.clone()
+    },
+    Err(err) => {
+      if !matches!(err, ImportError::Cancelled) {
+        if let Err(err) = transition_import_task_status(
+          &import_task.task_id,
+          ImportTaskState::Pending,
+          ImportTaskState::Failed,
+          pg_pool,
+        )
+        .await
+        {
+          error!(
+            "[Import]: failed to mark task:{} as failed: {:?}",
+            import_task.task_id, err
+          );
+        }
+      }
+      let (error, _) = err.report(&import_task.task_id.to_string());
+      ImportSummary {
+        durations,
+        error: Some(error),
+        ..Default::default()
+      }
+    },
+  };
+
+  let summary_value = match serde_json::to_value(&summary) {
+    Ok(value) => value,
+    Err(err) => {
+      error!(
+        "[Import]: failed to serialize the import summary: {:?}",
+        err
+      );
+      return;
+    },
+  };
+  if let Err(err) = update_import_task_metadata(
+    import_task.task_id,
+    json!({ IMPORT_SUMMARY_KEY: summary_value }),
+    pg_pool,
+  )
+  .await
+  {
+    error!(
+      "[Import]: failed to save the summary of task:{}: {:?}",
+      import_task.task_id, err
+    );
+  }
+}
+
 async fn notify_user(
   import_task: &NotionImportTask,
-  result: Result<(), ImportError>,
+  result: Result<ImportSummary, ImportError>,
+  pg_pool: &PgPool,
   notifier: Arc<dyn ImportNotifier>,
   metrics: &Option<Arc<ImportMetrics>>,
 ) -> Result<(), ImportError> {
   let task_id = import_task.task_id.to_string();
-  let (error, error_detail) = match result {
-    Ok(_) => {
+  let (error, error_detail, summary) = match result {
+    Ok(summary) => {
       info!("[Import]: successfully imported:{}", import_task);
       if let Some(metrics) = metrics {
         metrics.incr_import_success_count(1);
       }
-      (None, None)
+      (None, None, Some(summary))
     },
     Err(err) => {
       error!(
@@ -1243,11 +1599,18 @@ async fn notify_user(
         metrics.incr_import_fail_count(1);
       }
       let (error, error_detail) = err.report(&task_id);
-      (Some(error), Some(error_detail))
+      (Some(error), Some(error_detail), None)
     },
   };
 
   let is_success = error.is_none();
+  if !is_import_email_enabled(pg_pool, import_task).await {
+    info!(
+      "[Import]: skip the report email of task:{}, disabled by the user",
+      task_id
+    );
+    return Ok(());
+  }
 
   let value = serde_json::to_value(ImportNotionMailerParam {
     import_task_id: task_id,
@@ -1258,6 +1621,7 @@ async fn notify_user(
     open_workspace: false,
     error,
     error_detail,
+    summary,
   })
   .unwrap();
 
@@ -1272,6 +1636,21 @@ async fn notify_user(
   Ok(())
 }
 
+/// The email is sent when the preferences can't be read, as it was before they existed.
+async fn is_import_email_enabled(pg_pool: &PgPool, import_task: &NotionImportTask) -> bool {
+  let workspace_id = Uuid::parse_str(&import_task.workspace_id).ok();
+  match select_notification_preferences(pg_pool, import_task.uid, workspace_id.as_ref()).await {
+    Ok(preferences) => preferences.email_on_import_completion,
+    Err(err) => {
+      warn!(
+        "[Import]: failed to read the notification preferences of user:{}: {}",
+        import_task.uid, err
+      );
+      true
+    },
+  }
+}
+
 async fn batch_upload_files_to_s3(
   workspace_id: &str,
   client: &Arc<dyn S3Client>,
@@ -1373,9 +1752,12 @@ async fn get_encode_collab_from_bytes(
     },
     Err(WorkerError::RecordNotFound(_)) => {
       // fallback to postgres
-      let bytes = select_blob_from_af_collab(pg_pool, collab_type, object_id)
-        .await
-        .map_err(|err| ImportError::Internal(err.into()))?;
+      let workspace_uuid =
+        Uuid::parse_str(workspace_id).map_err(|err| ImportError::Internal(err.into()))?;
+      let bytes =
+        select_blob_from_af_collab_in_workspace(pg_pool, &workspace_uuid, collab_type, object_id)
+          .await
+          .map_err(|err| ImportError::Internal(err.into()))?;
 
       Ok(
         EncodedCollab::decode_from_bytes(&bytes)
@@ -1387,7 +1769,7 @@ async fn get_encode_collab_from_bytes(
 }
 
 /// Ensure the consumer group exists, if not, create it.
-async fn ensure_consumer_group(
+pub(crate) async fn ensure_consumer_group(
   stream_key: &str,
   group_name: &str,
   redis_client: &mut ConnectionManager,
@@ -1469,6 +1851,10 @@ pub struct NotionImportTask {
   pub workspace_id: String,
   pub workspace_name: String,
   pub s3_key: String,
+  /// Keys of the other zips of an export that Notion split into several parts, in order. They
+  /// are imported together with the zip of [Self::s3_key] into a single workspace.
+  #[serde(default)]
+  pub part_s3_keys: Vec<String>,
   pub host: String,
   #[serde(default)]
   pub created_at: Option<i64>,
@@ -1478,6 +1864,19 @@ pub struct NotionImportTask {
   pub last_process_at: Option<i64>,
   #[serde(default)]
   pub file_size: Option<i64>,
+  /// Unix timestamp in seconds before which the task is not processed.
+  #[serde(default)]
+  pub start_after: Option<i64>,
+  /// Tasks pushed by older servers have no source, they import a Notion export.
+  #[serde(default)]
+  pub source: ImportSource,
+}
+
+impl NotionImportTask {
+  /// Keys of all the zips of the import, the first one is [Self::s3_key].
+  pub fn s3_keys(&self) -> impl Iterator<Item = &str> {
+    std::iter::once(self.s3_key.as_str()).chain(self.part_s3_keys.iter().map(String::as_str))
+  }
 }
 
 impl Display for NotionImportTask {
@@ -1539,8 +1938,11 @@ impl TryFrom<&StreamId> for ImportTask {
   }
 }
 
-async fn process_resources(resources: Vec<CollabResource>) -> Vec<UploadCollabResource> {
-  let upload_resources_stream = stream::iter(resources)
+/// Returns the files to upload and the files that can't be read from the unzipped archive.
+async fn process_resources(
+  resources: Vec<CollabResource>,
+) -> (Vec<UploadCollabResource>, Vec<ImportSkippedItem>) {
+  let mut upload_resources_stream = stream::iter(resources)
     .flat_map(|resource| {
       let object_id = resource.object_id.clone();
       stream::iter(resource.files.into_iter().map(move |file_path| {
@@ -1548,12 +1950,15 @@ async fn process_resources(resources: Vec<CollabResource>) -> Vec<UploadCollabRe
         let path = PathBuf::from(file_path.clone());
         async move {
           match insert_meta_from_path(&object_id, &path).await {
-            Ok(meta) => Some(UploadCollabResource {
+            Ok(meta) => Ok(UploadCollabResource {
               object_id,
               file_path,
               meta,
             }),
-            Err(_) => None,
+            Err(err) => Err(ImportSkippedItem {
+              name: file_path,
+              reason: format!("failed to read the file: {}", err),
+            }),
           }
         }
       }))
@@ -1561,10 +1966,15 @@ async fn process_resources(resources: Vec<CollabResource>) -> Vec<UploadCollabRe
     // buffer_unordered method limits how many futures (tasks) are run concurrently.
     .buffer_unordered(20);
 
-  upload_resources_stream
-    .filter_map(|result| async { result })
-    .collect::<Vec<UploadCollabResource>>()
-    .await
+  let mut upload_resources = vec![];
+  let mut skipped_items = vec![];
+  while let Some(result) = upload_resources_stream.next().await {
+    match result {
+      Ok(resource) => upload_resources.push(resource),
+      Err(skipped) => skipped_items.push(skipped),
+    }
+  }
+  (upload_resources, skipped_items)
 }
 
 struct UploadCollabResource {
@@ -1605,13 +2015,13 @@ async fn insert_meta_from_path(
   })
 }
 
-fn collab_key(workspace_id: &str, object_id: &str) -> String {
+pub(crate) fn collab_key(workspace_id: &str, object_id: &str) -> String {
   format!(
     "collabs/{}/{}/encoded_collab.v1.zstd",
     workspace_id, object_id
   )
 }
 
-fn encode_collab_key(object_id: &str) -> String {
+pub(crate) fn encode_collab_key(object_id: &str) -> String {
   format!("encode_collab_v0:{}", object_id)
 }
@@ -9,7 +9,9 @@ use indexer::queue::{
   ack_task, default_indexer_group_option, ensure_indexer_consumer_group,
   read_background_embed_tasks,
 };
-use indexer::scheduler::{spawn_pg_write_embeddings, UnindexedCollabTask, UnindexedData};
+use indexer::scheduler::{
+  load_embedded_fragment_ids, spawn_pg_write_embeddings, UnindexedCollabTask, UnindexedData,
+};
 use indexer::thread_pool::ThreadPoolNoAbort;
 use indexer::vector::embedder::Embedder;
 use indexer::vector::open_ai;
@@ -17,6 +19,7 @@ use rayon::prelude::*;
 use redis::aio::ConnectionManager;
 use secrecy::{ExposeSecret, Secret};
 use sqlx::PgPool;
+use std::collections::HashSet;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
@@ -156,13 +159,14 @@ async fn process_upcoming_tasks(
             info!("[Background Embedding] filter out {} tasks where `created_at` is less than `indexed_at`", all_tasks_len - tasks.len());
           }
 
+          let embedded_fragment_ids = load_embedded_fragment_ids(&pg_pool, &tasks).await;
           let start = Instant::now();
           let num_tasks = tasks.len();
           tasks.into_par_iter().for_each(|task| {
             let result = threads.install(|| {
               if let Some(indexer) = indexer_provider.indexer_for(&task.collab_type) {
                 let embedder = create_embedder(&config);
-                let result = handle_task(embedder, indexer, task);
+                let result = handle_task(embedder, indexer, task, &embedded_fragment_ids);
                 match result {
                   None => metrics.record_failed_embed_count(1),
                   Some(record) => {
@@ -220,6 +224,7 @@ fn handle_task(
   embedder: Embedder,
   indexer: Arc<dyn Indexer>,
   task: UnindexedCollabTask,
+  embedded_fragment_ids: &HashSet<String>,
 ) -> Option<EmbeddingRecord> {
   trace!(
     "[Background Embedding] processing task: {}, content:{:?}, collab_type: {}",
@@ -232,7 +237,9 @@ fn handle_task(
       .create_embedded_chunks_from_text(task.object_id.clone(), text, embedder.model())
       .ok()?,
   };
-  let embeddings = indexer.embed(&embedder, chunks).ok()?;
+  let embeddings = indexer
+    .embed_changed(&embedder, chunks, embedded_fragment_ids)
+    .ok()?;
   embeddings.map(|embeddings| EmbeddingRecord {
     workspace_id: task.workspace_id,
     object_id: task.object_id,
@@ -1,6 +1,22 @@
+pub mod application;
+pub mod chat_attachment_worker;
+pub mod collab_archive_worker;
+pub mod collab_compression_worker;
+pub mod collab_partition_worker;
+pub mod config;
 pub mod error;
+pub mod export_worker;
+pub mod history_compaction_worker;
+pub mod image_asset_worker;
 pub mod import_worker;
 pub mod indexer_worker;
 mod mailer;
 pub mod metric;
+pub mod publish_feed_worker;
+pub mod retention_worker;
 pub mod s3_client;
+pub mod snapshot_offload_worker;
+pub mod stream_lag_worker;
+pub mod workspace_clone_worker;
+pub mod workspace_delete_worker;
+pub mod workspace_storage_worker;
@@ -1,8 +1,11 @@
+use database_entity::dto::ImportSummary;
 use mailer::sender::Mailer;
 use std::ops::Deref;
 
 pub const IMPORT_SUCCESS_TEMPLATE: &str = "import_notion_success";
 pub const IMPORT_FAIL_TEMPLATE: &str = "import_notion_fail";
+pub const EXPORT_SUCCESS_TEMPLATE: &str = "export_pdf_success";
+pub const EXPORT_FAIL_TEMPLATE: &str = "export_pdf_fail";
 #[derive(Clone)]
 pub struct AFWorkerMailer(Mailer);
 
@@ -22,9 +25,16 @@ impl AFWorkerMailer {
     let import_data_fail =
       include_str!("../../../assets/mailer_templates/build_production/import_data_fail.html");
 
+    let export_pdf_success =
+      include_str!("../../../assets/mailer_templates/build_production/export_pdf_success.html");
+    let export_pdf_fail =
+      include_str!("../../../assets/mailer_templates/build_production/export_pdf_fail.html");
+
     for (name, template) in [
       (IMPORT_SUCCESS_TEMPLATE, import_data_success),
       (IMPORT_FAIL_TEMPLATE, import_data_fail),
+      (EXPORT_SUCCESS_TEMPLATE, export_pdf_success),
+      (EXPORT_FAIL_TEMPLATE, export_pdf_fail),
     ] {
       mailer
         .register_template(name, template)
@@ -48,11 +58,15 @@ pub struct ImportNotionMailerParam {
   pub open_workspace: bool,
   pub error: Option<String>,
   pub error_detail: Option<String>,
+  /// What was imported and skipped, only set when the import succeeded.
+  #[serde(default)]
+  pub summary: Option<ImportSummary>,
 }
 
 #[cfg(test)]
 mod tests {
   use crate::mailer::{AFWorkerMailer, ImportNotionMailerParam, IMPORT_SUCCESS_TEMPLATE};
+  use database_entity::dto::{ImportSkippedItem, ImportSummary};
   use mailer::sender::Mailer;
 
   #[tokio::test]
@@ -77,6 +91,19 @@ mod tests {
       open_workspace: true,
       error: None,
       error_detail: None,
+      summary: Some(ImportSummary {
+        documents: 12,
+        databases: 3,
+        rows: 42,
+        uploaded_files: 7,
+        repaired_relations: 0,
+        removed_relations: 5,
+        skipped_items: vec![ImportSkippedItem {
+          name: "https://example.com/image.png".to_string(),
+          reason: "failed to download the remote file".to_string(),
+        }],
+        ..Default::default()
+      }),
     })
     .unwrap();
     let s = worker_mailer
@@ -84,5 +111,9 @@ mod tests {
       .unwrap();
 
     println!("{}", s);
+    assert!(s.contains("12 documents"));
+    assert!(s.contains("42 rows"));
+    assert!(s.contains("https://example.com/image.png"));
+    assert!(s.contains("5 relations"));
   }
 }
@@ -1,14 +1,5 @@
-mod application;
-mod config;
-pub mod error;
-pub mod import_worker;
-pub(crate) mod s3_client;
-
-mod metric;
-
-mod mailer;
-use crate::application::run_server;
-use crate::config::Config;
+use appflowy_worker::application::run_server;
+use appflowy_worker::config::Config;
 use tokio::net::TcpListener;
 
 #[tokio::main]
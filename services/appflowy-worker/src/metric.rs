@@ -1,11 +1,50 @@
+use database::history::compaction::HistoryCompactionResult;
+use database_entity::dto::ImportSource;
+use prometheus_client::encoding::EncodeLabelSet;
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::family::Family;
 use prometheus_client::metrics::gauge::Gauge;
 use prometheus_client::metrics::histogram::{exponential_buckets, Histogram};
 use prometheus_client::registry::Registry;
+use std::time::Duration;
+
+type StageHistogram = Family<ImportTypeLabel, Histogram, fn() -> Histogram>;
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct ImportTypeLabel {
+  pub import_type: String,
+}
+
+impl From<ImportSource> for ImportTypeLabel {
+  fn from(source: ImportSource) -> Self {
+    Self {
+      import_type: source.as_str().to_string(),
+    }
+  }
+}
+
+/// Stages of an import task. With the streaming import, the file is downloaded while it's
+/// unzipped and both are recorded as [ImportStage::Unzip].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportStage {
+  Download,
+  Unzip,
+  /// Building the collabs, the folder and the workspace database from the unzipped files.
+  CollabBuild,
+  DbInsert,
+  S3Upload,
+}
 
 pub struct ImportMetrics {
   pub update_size_bytes: Histogram,
   pub import_success_count: Gauge,
   pub import_fail_count: Gauge,
+  pub download_seconds: StageHistogram,
+  pub unzip_seconds: StageHistogram,
+  pub collab_build_seconds: StageHistogram,
+  pub db_insert_seconds: StageHistogram,
+  pub s3_upload_seconds: StageHistogram,
+  pub active_tasks: Family<ImportTypeLabel, Gauge>,
 }
 
 impl ImportMetrics {
@@ -15,6 +54,12 @@ impl ImportMetrics {
       update_size_bytes: Histogram::new(update_size_buckets),
       import_success_count: Default::default(),
       import_fail_count: Default::default(),
+      download_seconds: stage_histogram(),
+      unzip_seconds: stage_histogram(),
+      collab_build_seconds: stage_histogram(),
+      db_insert_seconds: stage_histogram(),
+      s3_upload_seconds: stage_histogram(),
+      active_tasks: Default::default(),
     }
   }
 
@@ -36,6 +81,36 @@ impl ImportMetrics {
       "import fail count",
       metrics.import_fail_count.clone(),
     );
+    web_update_registry.register(
+      "import_download_seconds",
+      "time spent downloading the imported file",
+      metrics.download_seconds.clone(),
+    );
+    web_update_registry.register(
+      "import_unzip_seconds",
+      "time spent unzipping the imported file",
+      metrics.unzip_seconds.clone(),
+    );
+    web_update_registry.register(
+      "import_collab_build_seconds",
+      "time spent building the collabs of the imported file",
+      metrics.collab_build_seconds.clone(),
+    );
+    web_update_registry.register(
+      "import_db_insert_seconds",
+      "time spent writing the imported collabs to the database",
+      metrics.db_insert_seconds.clone(),
+    );
+    web_update_registry.register(
+      "import_s3_upload_seconds",
+      "time spent uploading the imported files to the object storage",
+      metrics.s3_upload_seconds.clone(),
+    );
+    web_update_registry.register(
+      "import_active_tasks",
+      "number of import tasks being processed",
+      metrics.active_tasks.clone(),
+    );
     metrics
   }
 
@@ -50,4 +125,130 @@ impl ImportMetrics {
   pub fn incr_import_fail_count(&self, count: i64) {
     self.import_fail_count.inc_by(count);
   }
+
+  pub fn record_stage_duration(
+    &self,
+    source: ImportSource,
+    stage: ImportStage,
+    duration: Duration,
+  ) {
+    let histogram = match stage {
+      ImportStage::Download => &self.download_seconds,
+      ImportStage::Unzip => &self.unzip_seconds,
+      ImportStage::CollabBuild => &self.collab_build_seconds,
+      ImportStage::DbInsert => &self.db_insert_seconds,
+      ImportStage::S3Upload => &self.s3_upload_seconds,
+    };
+    histogram
+      .get_or_create(&ImportTypeLabel::from(source))
+      .observe(duration.as_secs_f64());
+  }
+
+  /// Counts the task as active until the returned guard is dropped.
+  pub fn start_task(&self, source: ImportSource) -> ActiveImportTask {
+    let gauge = self
+      .active_tasks
+      .get_or_create(&ImportTypeLabel::from(source))
+      .clone();
+    gauge.inc();
+    ActiveImportTask(gauge)
+  }
+}
+
+pub struct ActiveImportTask(Gauge);
+
+impl Drop for ActiveImportTask {
+  fn drop(&mut self) {
+    self.0.dec();
+  }
+}
+
+/// From 100ms to about 27 minutes.
+fn stage_histogram() -> StageHistogram {
+  Family::new_with_constructor(|| Histogram::new(exponential_buckets(0.1, 2.0, 15)))
+}
+
+#[derive(Default)]
+pub struct HistoryCompactionMetrics {
+  pub reclaimed_bytes: Counter,
+  pub deleted_versions: Counter,
+  pub deleted_states: Counter,
+  pub compaction_fail_count: Counter,
+}
+
+impl HistoryCompactionMetrics {
+  pub fn register(registry: &mut Registry) -> Self {
+    let metrics = Self::default();
+    let compaction_registry = registry.sub_registry_with_prefix("history_compaction");
+    compaction_registry.register(
+      "reclaimed_bytes",
+      "size of the collab history deleted by the compaction in bytes",
+      metrics.reclaimed_bytes.clone(),
+    );
+    compaction_registry.register(
+      "deleted_versions",
+      "number of history versions deleted by the compaction",
+      metrics.deleted_versions.clone(),
+    );
+    compaction_registry.register(
+      "deleted_states",
+      "number of history document states deleted by the compaction",
+      metrics.deleted_states.clone(),
+    );
+    compaction_registry.register(
+      "fail_count",
+      "number of workspaces whose compaction failed",
+      metrics.compaction_fail_count.clone(),
+    );
+    metrics
+  }
+
+  pub fn record_compaction(&self, result: &HistoryCompactionResult) {
+    self.reclaimed_bytes.inc_by(result.reclaimed_bytes as u64);
+    self.deleted_versions.inc_by(result.deleted_versions as u64);
+    self.deleted_states.inc_by(result.deleted_states as u64);
+  }
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct StreamGroupLabel {
+  pub stream: String,
+  pub group: String,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct StreamConsumerLabel {
+  pub stream: String,
+  pub group: String,
+  pub consumer: String,
+}
+
+#[derive(Default)]
+pub struct StreamLagMetrics {
+  pub pending_messages: Family<StreamGroupLabel, Gauge>,
+  pub oldest_pending_age_ms: Family<StreamGroupLabel, Gauge>,
+  pub consumer_idle_ms: Family<StreamConsumerLabel, Gauge>,
+}
+
+impl StreamLagMetrics {
+  pub fn register(registry: &mut Registry) -> Self {
+    let metrics = Self::default();
+    let stream_registry = registry.sub_registry_with_prefix("redis_stream");
+    stream_registry.register(
+      "pending_messages",
+      "number of messages delivered to the consumer group but not acknowledged yet",
+      metrics.pending_messages.clone(),
+    );
+    stream_registry.register(
+      "oldest_pending_age_ms",
+      "age of the oldest message not acknowledged by the consumer group in milliseconds",
+      metrics.oldest_pending_age_ms.clone(),
+    );
+    stream_registry.register(
+      "consumer_idle_ms",
+      "time since the consumer last read from the stream in milliseconds",
+      metrics.consumer_idle_ms.clone(),
+    );
+    metrics
+  }
 }
@@ -0,0 +1,173 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Number of pages listed in the RSS and Atom feeds, the sitemap lists every published page.
+pub const FEED_PAGE_LIMIT: usize = 50;
+
+#[derive(Debug, Clone)]
+pub struct FeedPage {
+  pub view_id: Uuid,
+  pub title: String,
+  pub url: String,
+  pub created_at: DateTime<Utc>,
+  pub updated_at: DateTime<Utc>,
+}
+
+/// The publish namespace the feeds are generated for.
+#[derive(Debug, Clone)]
+pub struct FeedChannel {
+  pub title: String,
+  pub url: String,
+  pub feed_url: String,
+}
+
+pub fn render_sitemap(pages: &[FeedPage]) -> String {
+  let mut xml = String::from(
+    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+     <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+  );
+  for page in pages {
+    xml.push_str(&format!(
+      "  <url>\n    <loc>{}</loc>\n    <lastmod>{}</lastmod>\n  </url>\n",
+      escape_xml(&page.url),
+      page.updated_at.format("%Y-%m-%d"),
+    ));
+  }
+  xml.push_str("</urlset>\n");
+  xml
+}
+
+pub fn render_rss(channel: &FeedChannel, pages: &[FeedPage]) -> String {
+  let mut xml = String::from(
+    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+     <rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\">\n  <channel>\n",
+  );
+  xml.push_str(&format!(
+    "    <title>{}</title>\n    <link>{}</link>\n    <description>{}</description>\n    \
+     <atom:link href=\"{}\" rel=\"self\" type=\"application/rss+xml\"/>\n",
+    escape_xml(&channel.title),
+    escape_xml(&channel.url),
+    escape_xml(&channel.title),
+    escape_xml(&channel.feed_url),
+  ));
+  if let Some(last_updated) = pages.iter().map(|page| page.updated_at).max() {
+    xml.push_str(&format!(
+      "    <lastBuildDate>{}</lastBuildDate>\n",
+      last_updated.to_rfc2822()
+    ));
+  }
+  for page in pages.iter().take(FEED_PAGE_LIMIT) {
+    xml.push_str(&format!(
+      "    <item>\n      <title>{}</title>\n      <link>{}</link>\n      \
+       <guid isPermaLink=\"false\">{}</guid>\n      <pubDate>{}</pubDate>\n    </item>\n",
+      escape_xml(&page.title),
+      escape_xml(&page.url),
+      page.view_id,
+      page.created_at.to_rfc2822(),
+    ));
+  }
+  xml.push_str("  </channel>\n</rss>\n");
+  xml
+}
+
+pub fn render_atom(channel: &FeedChannel, pages: &[FeedPage]) -> String {
+  let updated = pages
+    .iter()
+    .map(|page| page.updated_at)
+    .max()
+    .unwrap_or_else(Utc::now);
+  let mut xml = String::from(
+    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">\n",
+  );
+  xml.push_str(&format!(
+    "  <title>{}</title>\n  <id>{}</id>\n  <link href=\"{}\"/>\n  \
+     <link href=\"{}\" rel=\"self\"/>\n  <updated>{}</updated>\n",
+    escape_xml(&channel.title),
+    escape_xml(&channel.url),
+    escape_xml(&channel.url),
+    escape_xml(&channel.feed_url),
+    updated.to_rfc3339(),
+  ));
+  for page in pages.iter().take(FEED_PAGE_LIMIT) {
+    xml.push_str(&format!(
+      "  <entry>\n    <title>{}</title>\n    <id>urn:uuid:{}</id>\n    <link href=\"{}\"/>\n    \
+       <published>{}</published>\n    <updated>{}</updated>\n  </entry>\n",
+      escape_xml(&page.title),
+      page.view_id,
+      escape_xml(&page.url),
+      page.created_at.to_rfc3339(),
+      page.updated_at.to_rfc3339(),
+    ));
+  }
+  xml.push_str("</feed>\n");
+  xml
+}
+
+fn escape_xml(value: &str) -> String {
+  let mut escaped = String::with_capacity(value.len());
+  for c in value.chars() {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      '\'' => escaped.push_str("&apos;"),
+      _ => escaped.push(c),
+    }
+  }
+  escaped
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn page(title: &str) -> FeedPage {
+    FeedPage {
+      view_id: Uuid::new_v4(),
+      title: title.to_string(),
+      url: "https://appflowy.com/ns/page-1?a=1&b=2".to_string(),
+      created_at: Utc::now(),
+      updated_at: Utc::now(),
+    }
+  }
+
+  #[test]
+  fn feeds_are_escaped() {
+    let channel = FeedChannel {
+      title: "ns".to_string(),
+      url: "https://appflowy.com/ns".to_string(),
+      feed_url: "https://appflowy.com/api/workspace/published/ns/rss.xml".to_string(),
+    };
+    let pages = vec![page("Tom & <Jerry>")];
+
+    let sitemap = render_sitemap(&pages);
+    assert!(sitemap.contains("<loc>https://appflowy.com/ns/page-1?a=1&amp;b=2</loc>"));
+
+    let rss = render_rss(&channel, &pages);
+    assert!(rss.contains("<title>Tom &amp; &lt;Jerry&gt;</title>"));
+
+    let atom = render_atom(&channel, &pages);
+    assert!(atom.contains(&format!("<id>urn:uuid:{}</id>", pages[0].view_id)));
+  }
+
+  #[test]
+  fn feeds_are_limited() {
+    let channel = FeedChannel {
+      title: "ns".to_string(),
+      url: "https://appflowy.com/ns".to_string(),
+      feed_url: "https://appflowy.com/api/workspace/published/ns/atom.xml".to_string(),
+    };
+    let pages: Vec<_> = (0..FEED_PAGE_LIMIT + 10)
+      .map(|i| page(&format!("page {}", i)))
+      .collect();
+    assert_eq!(
+      render_atom(&channel, &pages).matches("<entry>").count(),
+      FEED_PAGE_LIMIT
+    );
+    assert_eq!(
+      render_sitemap(&pages).matches("<url>").count(),
+      FEED_PAGE_LIMIT + 10
+    );
+  }
+}
@@ -0,0 +1,248 @@
+use crate::error::WorkerError;
+use crate::import_worker::worker::ensure_consumer_group;
+use crate::publish_feed_worker::feed::{
+  render_atom, render_rss, render_sitemap, FeedChannel, FeedPage,
+};
+use crate::s3_client::S3Client;
+use anyhow::anyhow;
+use aws_sdk_s3::primitives::ByteStream;
+use database::publish::{
+  published_feed_dir, published_feed_object_key, select_published_feed_items,
+  select_workspace_publish_namespaces,
+};
+use database::workspace_delete::is_workspace_delete_pending;
+use redis::aio::ConnectionManager;
+use redis::streams::{StreamId, StreamReadOptions, StreamReadReply};
+use redis::{AsyncCommands, Value};
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{error, info, trace};
+use uuid::Uuid;
+
+pub(crate) const GROUP_NAME: &str = "publish_feed_task_group";
+const CONSUMER_NAME: &str = "appflowy_worker";
+/// Maximum number of urls in a sitemap file.
+const SITEMAP_URL_LIMIT: i64 = 50_000;
+
+#[derive(Debug, Clone)]
+pub struct PublishFeedSetting {
+  /// Base url of AppFlowy Web, where the published pages are served.
+  pub web_url: String,
+  /// Base url of the AppFlowy Cloud API, where the feeds are served.
+  pub api_url: String,
+}
+
+/// Task pushed by the server whenever the published pages of a workspace change.
+#[derive(Debug, Clone, Deserialize)]
+struct PublishFeedTask {
+  workspace_id: Uuid,
+}
+
+impl TryFrom<&StreamId> for PublishFeedTask {
+  type Error = WorkerError;
+
+  fn try_from(stream_id: &StreamId) -> Result<Self, Self::Error> {
+    let task_str = match stream_id.map.get("task") {
+      Some(Value::Data(data)) => String::from_utf8_lossy(data).to_string(),
+      _ => {
+        return Err(WorkerError::Internal(anyhow!(
+          "missing task in stream entry {}",
+          stream_id.id
+        )))
+      },
+    };
+    serde_json::from_str(&task_str).map_err(|err| WorkerError::Internal(err.into()))
+  }
+}
+
+/// Regenerates the sitemap, RSS and Atom feeds of the publish namespaces. Several publish events
+/// of the same workspace read in one batch only trigger one regeneration.
+pub async fn run_publish_feed_worker(
+  pg_pool: PgPool,
+  mut redis_client: ConnectionManager,
+  s3_client: Arc<dyn S3Client>,
+  setting: PublishFeedSetting,
+  stream_name: &str,
+  tick_interval_secs: u64,
+) -> Result<(), WorkerError> {
+  info!("Starting publish feed worker");
+  if let Err(err) = ensure_consumer_group(stream_name, GROUP_NAME, &mut redis_client).await {
+    error!("Failed to ensure consumer group: {:?}", err);
+  }
+
+  let mut pending_id = Some("0");
+  let options = StreamReadOptions::default()
+    .group(GROUP_NAME, CONSUMER_NAME)
+    .count(50);
+  let mut interval = interval(Duration::from_secs(tick_interval_secs));
+  interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+  loop {
+    interval.tick().await;
+    let id = pending_id.take().unwrap_or(">");
+    let reply: StreamReadReply = match redis_client
+      .xread_options(&[stream_name], &[id], &options)
+      .await
+    {
+      Ok(reply) => reply,
+      Err(err) => {
+        error!(
+          "Failed to read publish feed tasks from Redis stream: {:?}",
+          err
+        );
+        if err.code() == Some("NOGROUP") {
+          if let Err(err) = ensure_consumer_group(stream_name, GROUP_NAME, &mut redis_client).await
+          {
+            error!("Failed to ensure consumer group: {:?}", err);
+          }
+        }
+        continue;
+      },
+    };
+
+    let mut workspace_ids = HashSet::new();
+    let mut stream_ids = vec![];
+    for stream_key in reply.keys {
+      for stream_id in stream_key.ids {
+        match PublishFeedTask::try_from(&stream_id) {
+          Ok(task) => {
+            workspace_ids.insert(task.workspace_id);
+          },
+          Err(err) => error!("Failed to deserialize publish feed task: {:?}", err),
+        }
+        stream_ids.push(stream_id.id);
+      }
+    }
+
+    for workspace_id in workspace_ids {
+      if let Err(err) = generate_publish_feeds(&pg_pool, &s3_client, &setting, &workspace_id).await
+      {
+        error!(
+          "Failed to generate publish feeds of workspace {}: {:?}",
+          workspace_id, err
+        );
+      }
+    }
+    // The feeds are regenerated on the next publish event, retrying a failed task isn't needed.
+    if !stream_ids.is_empty() {
+      let _: Result<(), _> = redis_client
+        .xack(stream_name, GROUP_NAME, &stream_ids)
+        .await
+        .map_err(|err| error!("Failed to ack publish feed tasks: {:?}", err));
+    }
+  }
+}
+
+async fn generate_publish_feeds(
+  pg_pool: &PgPool,
+  s3_client: &Arc<dyn S3Client>,
+  setting: &PublishFeedSetting,
+  workspace_id: &Uuid,
+) -> Result<(), WorkerError> {
+  trace!(
+    "[Publish Feed] generating feeds of workspace {}",
+    workspace_id
+  );
+  // The feeds of a workspace being deleted would keep listing its pages until the workspace row
+  // is gone, they are removed right away instead.
+  if is_workspace_delete_pending(pg_pool, workspace_id)
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?
+  {
+    return delete_publish_feeds(s3_client, workspace_id).await;
+  }
+  let mut namespaces = select_workspace_publish_namespaces(pg_pool, workspace_id)
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+  // The namespace set by the owner takes precedence over the original one
+  namespaces.sort_by_key(|namespace| namespace.is_original);
+  let namespace = match namespaces.into_iter().next() {
+    Some(namespace) => namespace.namespace,
+    None => return delete_publish_feeds(s3_client, workspace_id).await,
+  };
+
+  let web_url = setting.web_url.trim_end_matches('/');
+  let pages: Vec<FeedPage> = select_published_feed_items(pg_pool, workspace_id, SITEMAP_URL_LIMIT)
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?
+    .into_iter()
+    .map(|item| FeedPage {
+      view_id: item.view_id,
+      title: item
+        .title
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| "Untitled".to_string()),
+      url: format!("{}/{}/{}", web_url, namespace, item.publish_name),
+      created_at: item.created_at,
+      updated_at: item.updated_at,
+    })
+    .collect();
+
+  let feed_url = |file_name: &str| {
+    format!(
+      "{}/api/workspace/published/{}/{}",
+      setting.api_url.trim_end_matches('/'),
+      namespace,
+      file_name
+    )
+  };
+  let channel_url = format!("{}/{}", web_url, namespace);
+  let files = [
+    (
+      "sitemap.xml",
+      "application/xml; charset=utf-8",
+      render_sitemap(&pages),
+    ),
+    (
+      "rss.xml",
+      "application/rss+xml; charset=utf-8",
+      render_rss(
+        &FeedChannel {
+          title: namespace.clone(),
+          url: channel_url.clone(),
+          feed_url: feed_url("rss.xml"),
+        },
+        &pages,
+      ),
+    ),
+    (
+      "atom.xml",
+      "application/atom+xml; charset=utf-8",
+      render_atom(
+        &FeedChannel {
+          title: namespace.clone(),
+          url: channel_url.clone(),
+          feed_url: feed_url("atom.xml"),
+        },
+        &pages,
+      ),
+    ),
+  ];
+  for (file_name, content_type, content) in files {
+    s3_client
+      .put_blob(
+        &published_feed_object_key(workspace_id, file_name),
+        ByteStream::from(content.into_bytes()),
+        Some(content_type),
+      )
+      .await?;
+  }
+  Ok(())
+}
+
+async fn delete_publish_feeds(
+  s3_client: &Arc<dyn S3Client>,
+  workspace_id: &Uuid,
+) -> Result<(), WorkerError> {
+  trace!(
+    "[Publish Feed] deleting feeds of workspace {}",
+    workspace_id
+  );
+  s3_client
+    .remove_dir(&published_feed_dir(workspace_id))
+    .await
+}
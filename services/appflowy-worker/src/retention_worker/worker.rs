@@ -0,0 +1,145 @@
+use crate::error::WorkerError;
+use crate::s3_client::S3Client;
+use database::retention::{delete_expired_chat_messages, delete_expired_collab_snapshots};
+use infra::env_util::get_env_var;
+use redis::aio::ConnectionManager;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{error, info, trace, warn};
+
+/// Only one worker runs the cleanup at a time.
+const RETENTION_LOCK_KEY: &str = "af:retention:lock";
+/// Upper bound of the batches deleted in a single run, so a run never holds the lock for long.
+const MAX_BATCHES_PER_RUN: usize = 100;
+
+#[derive(Debug, Clone)]
+pub struct RetentionSetting {
+  pub enabled: bool,
+  /// Maximum number of rows deleted by each statement.
+  pub batch_size: i64,
+  pub interval: Duration,
+}
+
+impl RetentionSetting {
+  pub fn from_env() -> Self {
+    Self {
+      enabled: get_env_var("APPFLOWY_WORKER_RETENTION_ENABLED", "true")
+        .parse()
+        .unwrap_or(true),
+      batch_size: get_env_var("APPFLOWY_WORKER_RETENTION_BATCH_SIZE", "1000")
+        .parse()
+        .unwrap_or(1000),
+      interval: Duration::from_secs(
+        get_env_var("APPFLOWY_WORKER_RETENTION_INTERVAL_SECS", "3600")
+          .parse()
+          .unwrap_or(3600),
+      ),
+    }
+  }
+}
+
+/// Periodically deletes the chat messages and the collab snapshots that are older than the
+/// retention policy of their workspace. The workspaces without a policy keep their data.
+pub async fn run_retention_worker(
+  pg_pool: PgPool,
+  mut redis_client: ConnectionManager,
+  s3_client: Arc<dyn S3Client>,
+  setting: RetentionSetting,
+) -> Result<(), WorkerError> {
+  if !setting.enabled {
+    return Ok(());
+  }
+  info!("Starting retention worker");
+  let mut interval = interval(setting.interval);
+  interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+  loop {
+    interval.tick().await;
+    let locked: Option<String> = redis::cmd("SET")
+      .arg(RETENTION_LOCK_KEY)
+      .arg(1)
+      .arg("NX")
+      .arg("EX")
+      .arg(setting.interval.as_secs().max(1))
+      .query_async(&mut redis_client)
+      .await
+      .unwrap_or_else(|err| {
+        error!("Failed to acquire retention lock: {:?}", err);
+        None
+      });
+    if locked.is_none() {
+      trace!("[Retention] another worker is running the cleanup");
+      continue;
+    }
+
+    match delete_in_batches(
+      &pg_pool,
+      &s3_client,
+      setting.batch_size,
+      DataKind::ChatMessages,
+    )
+    .await
+    {
+      Ok(count) if count > 0 => info!("[Retention] deleted {} chat messages", count),
+      Ok(_) => {},
+      Err(err) => error!("[Retention] failed to delete chat messages: {:?}", err),
+    }
+    match delete_in_batches(
+      &pg_pool,
+      &s3_client,
+      setting.batch_size,
+      DataKind::Snapshots,
+    )
+    .await
+    {
+      Ok(count) if count > 0 => info!("[Retention] deleted {} collab snapshots", count),
+      Ok(_) => {},
+      Err(err) => error!("[Retention] failed to delete collab snapshots: {:?}", err),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum DataKind {
+  ChatMessages,
+  Snapshots,
+}
+
+async fn delete_in_batches(
+  pg_pool: &PgPool,
+  s3_client: &Arc<dyn S3Client>,
+  batch_size: i64,
+  kind: DataKind,
+) -> Result<u64, WorkerError> {
+  let mut total = 0;
+  for _ in 0..MAX_BATCHES_PER_RUN {
+    let deleted = match kind {
+      DataKind::ChatMessages => delete_expired_chat_messages(pg_pool, batch_size)
+        .await
+        .map_err(|err| WorkerError::Internal(err.into()))?,
+      DataKind::Snapshots => {
+        let keys = delete_expired_collab_snapshots(pg_pool, batch_size)
+          .await
+          .map_err(|err| WorkerError::Internal(err.into()))?;
+        let deleted = keys.len() as u64;
+        // the offloaded blobs are removed from the object storage as well
+        for key in keys.into_iter().flatten() {
+          match s3_client.delete_blob(&key).await {
+            Ok(_) | Err(WorkerError::RecordNotFound(_)) => {},
+            Err(err) => warn!(
+              "[Retention] failed to delete snapshot blob {}: {:?}",
+              key, err
+            ),
+          }
+        }
+        deleted
+      },
+    };
+    total += deleted;
+    if deleted < batch_size as u64 {
+      break;
+    }
+  }
+  Ok(total)
+}
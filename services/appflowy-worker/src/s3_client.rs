@@ -1,19 +1,27 @@
 use crate::error::WorkerError;
 use anyhow::{anyhow, Context};
+use app_error::AppError;
 use aws_sdk_s3::error::SdkError;
+use database::file::azure_client_impl::AzureBlobClientImpl;
+use database::file::fs_client_impl::LocalFsBucketClientImpl;
+use database::file::memory_client_impl::InMemoryBucketClientImpl;
+use database::file::s3_client_impl::AwsS3BucketClientImpl;
+use database::file::{BucketClient, ResponseBlob, WorkspaceBucket, WorkspaceStorageRouter};
 use std::fs::Permissions;
 
 use anyhow::Result;
 use aws_sdk_s3::operation::get_object::GetObjectError;
 use aws_sdk_s3::operation::head_object::{HeadObjectError, HeadObjectOutput};
 use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::{MetadataDirective, StorageClass};
 use axum::async_trait;
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
-use futures::AsyncReadExt;
+use futures::{AsyncReadExt, TryStreamExt};
 use std::ops::Deref;
 use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tokio::fs;
 use tokio::fs::OpenOptions;
 use tokio::io::AsyncWriteExt;
@@ -32,8 +40,46 @@ pub trait S3Client: Send + Sync {
   ) -> Result<(), WorkerError>;
   async fn delete_blob(&self, object_key: &str) -> Result<(), WorkerError>;
 
+  /// Deletes all the blobs whose key starts with `dir`.
+  async fn remove_dir(&self, dir: &str) -> Result<(), WorkerError>;
+
+  /// Lists the keys of the blobs starting with `prefix`, one page at a time. Returns the keys and
+  /// the continuation of the next page, None on the last page.
+  async fn list_blobs(
+    &self,
+    prefix: &str,
+    continuation: Option<String>,
+  ) -> Result<(Vec<String>, Option<String>), WorkerError>;
+
   async fn is_blob_exist(&self, object_key: &str) -> Result<bool, WorkerError>;
   async fn get_blob_meta(&self, object_key: &str) -> Result<BlobMeta, WorkerError>;
+
+  /// Whether the backend stores blobs in storage classes with different costs, like the S3
+  /// Glacier tiers.
+  fn supports_storage_class(&self) -> bool {
+    false
+  }
+
+  /// Uploads the blob in the given storage class. Backends without storage classes store it like
+  /// any other blob.
+  async fn put_blob_with_storage_class(
+    &self,
+    object_key: &str,
+    content: ByteStream,
+    _storage_class: &str,
+  ) -> Result<(), WorkerError> {
+    self.put_blob(object_key, content, None).await
+  }
+
+  /// Moves an existing blob to the given storage class. Does nothing for the backends without
+  /// storage classes.
+  async fn set_storage_class(
+    &self,
+    _object_key: &str,
+    _storage_class: &str,
+  ) -> Result<(), WorkerError> {
+    Ok(())
+  }
 }
 
 pub struct BlobMeta {
@@ -178,6 +224,39 @@ impl S3Client for S3ClientImpl {
     }
   }
 
+  async fn remove_dir(&self, dir: &str) -> Result<(), WorkerError> {
+    // The endpoints are only used to build the presigned urls.
+    AwsS3BucketClientImpl::new(self.inner.clone(), self.bucket.clone(), String::new(), None)
+      .remove_dir(dir)
+      .await
+      .map_err(worker_error)
+  }
+
+  async fn list_blobs(
+    &self,
+    prefix: &str,
+    continuation: Option<String>,
+  ) -> Result<(Vec<String>, Option<String>), WorkerError> {
+    let output = self
+      .inner
+      .list_objects_v2()
+      .bucket(&self.bucket)
+      .prefix(prefix)
+      .set_continuation_token(continuation)
+      .send()
+      .await
+      .map_err(|err| {
+        WorkerError::S3ServiceUnavailable(format!("Failed to list objects from S3: {}", err))
+      })?;
+    let keys = output
+      .contents
+      .unwrap_or_default()
+      .into_iter()
+      .filter_map(|object| object.key)
+      .collect();
+    Ok((keys, output.next_continuation_token))
+  }
+
   async fn is_blob_exist(&self, object_key: &str) -> Result<bool, WorkerError> {
     let result = self.get_head_object(object_key).await;
     match result {
@@ -198,6 +277,435 @@ impl S3Client for S3ClientImpl {
       content_type,
     })
   }
+
+  fn supports_storage_class(&self) -> bool {
+    true
+  }
+
+  async fn put_blob_with_storage_class(
+    &self,
+    object_key: &str,
+    content: ByteStream,
+    storage_class: &str,
+  ) -> Result<(), WorkerError> {
+    self
+      .inner
+      .put_object()
+      .bucket(&self.bucket)
+      .key(object_key)
+      .body(content)
+      .content_type("application/octet-stream")
+      .storage_class(StorageClass::from(storage_class))
+      .send()
+      .await
+      .map_err(|err| {
+        WorkerError::S3ServiceUnavailable(format!("Failed to upload object to S3: {}", err))
+      })?;
+    trace!("put object to S3 in {}: {}", storage_class, object_key);
+    Ok(())
+  }
+
+  async fn set_storage_class(
+    &self,
+    object_key: &str,
+    storage_class: &str,
+  ) -> Result<(), WorkerError> {
+    // copying the object onto itself is how S3 changes the storage class of an object
+    self
+      .inner
+      .copy_object()
+      .bucket(&self.bucket)
+      .key(object_key)
+      .copy_source(format!("{}/{}", self.bucket, object_key))
+      .storage_class(StorageClass::from(storage_class))
+      .metadata_directive(MetadataDirective::Copy)
+      .send()
+      .await
+      .map_err(|err| {
+        WorkerError::S3ServiceUnavailable(format!(
+          "Failed to change the storage class of {}: {}",
+          object_key, err
+        ))
+      })?;
+    trace!("moved S3 object {} to {}", object_key, storage_class);
+    Ok(())
+  }
+}
+
+const AZURE_LIST_PAGE_SIZE: usize = 1000;
+
+/// Azure Blob Storage counterpart of [S3ClientImpl].
+#[derive(Clone)]
+pub struct AzureBlobClient(pub AzureBlobClientImpl);
+
+fn worker_error(err: AppError) -> WorkerError {
+  match err {
+    AppError::RecordNotFound(msg) => WorkerError::RecordNotFound(msg),
+    AppError::ServiceTemporaryUnavailable(msg) => WorkerError::S3ServiceUnavailable(msg),
+    err => WorkerError::Internal(err.into()),
+  }
+}
+
+#[async_trait]
+impl S3Client for AzureBlobClient {
+  async fn get_blob_stream(&self, object_key: &str) -> Result<S3StreamResponse, WorkerError> {
+    let resp = self
+      .0
+      .get_blob_response(object_key)
+      .await
+      .map_err(worker_error)?;
+    let content_type = resp
+      .headers()
+      .get(reqwest::header::CONTENT_TYPE)
+      .and_then(|value| value.to_str().ok())
+      .map(|value| value.to_string());
+    let content_length = resp.content_length().map(|len| len as i64);
+    trace!(
+      "get blob from Azure: {} ({:?} bytes)",
+      object_key,
+      content_length
+    );
+    let stream = Box::pin(resp.bytes_stream())
+      .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))
+      .into_async_read();
+    Ok(S3StreamResponse {
+      stream: Box::new(stream),
+      content_type,
+      content_length,
+    })
+  }
+
+  async fn put_blob(
+    &self,
+    object_key: &str,
+    content: ByteStream,
+    content_type: Option<&str>,
+  ) -> Result<(), WorkerError> {
+    self
+      .0
+      .put_blob(object_key, content, content_type)
+      .await
+      .map_err(worker_error)
+  }
+
+  async fn delete_blob(&self, object_key: &str) -> Result<(), WorkerError> {
+    self
+      .0
+      .delete_blob(object_key)
+      .await
+      .map(|_| ())
+      .map_err(worker_error)
+  }
+
+  async fn remove_dir(&self, dir: &str) -> Result<(), WorkerError> {
+    self.0.remove_dir(dir).await.map_err(worker_error)
+  }
+
+  async fn list_blobs(
+    &self,
+    prefix: &str,
+    continuation: Option<String>,
+  ) -> Result<(Vec<String>, Option<String>), WorkerError> {
+    self
+      .0
+      .list_page(prefix, AZURE_LIST_PAGE_SIZE, continuation.as_deref())
+      .await
+      .map_err(worker_error)
+  }
+
+  async fn is_blob_exist(&self, object_key: &str) -> Result<bool, WorkerError> {
+    match self.0.get_blob_properties(object_key).await {
+      Ok(_) => Ok(true),
+      Err(AppError::RecordNotFound(_)) => Ok(false),
+      Err(err) => Err(worker_error(err)),
+    }
+  }
+
+  async fn get_blob_meta(&self, object_key: &str) -> Result<BlobMeta, WorkerError> {
+    let properties = self
+      .0
+      .get_blob_properties(object_key)
+      .await
+      .map_err(worker_error)?;
+    Ok(BlobMeta {
+      content_length: properties.content_length,
+      content_type: properties.content_type,
+    })
+  }
+}
+
+/// Local file storage counterpart of [S3ClientImpl].
+#[derive(Clone)]
+pub struct LocalFsBlobClient(pub LocalFsBucketClientImpl);
+
+#[async_trait]
+impl S3Client for LocalFsBlobClient {
+  async fn get_blob_stream(&self, object_key: &str) -> Result<S3StreamResponse, WorkerError> {
+    let (file, len, content_type) = self.0.open_blob(object_key).await.map_err(worker_error)?;
+    trace!(
+      "get blob from local file storage: {} ({} bytes)",
+      object_key,
+      len
+    );
+    Ok(S3StreamResponse {
+      stream: Box::new(tokio::io::BufReader::new(file).compat()),
+      content_type,
+      content_length: Some(len as i64),
+    })
+  }
+
+  async fn put_blob(
+    &self,
+    object_key: &str,
+    content: ByteStream,
+    content_type: Option<&str>,
+  ) -> Result<(), WorkerError> {
+    self
+      .0
+      .put_blob(object_key, content, content_type)
+      .await
+      .map_err(worker_error)
+  }
+
+  async fn delete_blob(&self, object_key: &str) -> Result<(), WorkerError> {
+    self
+      .0
+      .delete_blob(object_key)
+      .await
+      .map(|_| ())
+      .map_err(worker_error)
+  }
+
+  async fn remove_dir(&self, dir: &str) -> Result<(), WorkerError> {
+    self.0.remove_dir(dir).await.map_err(worker_error)
+  }
+
+  /// The keys are listed from the disk in a single page.
+  async fn list_blobs(
+    &self,
+    prefix: &str,
+    _continuation: Option<String>,
+  ) -> Result<(Vec<String>, Option<String>), WorkerError> {
+    let keys = self
+      .0
+      .list_dir(prefix, usize::MAX)
+      .await
+      .map_err(worker_error)?;
+    Ok((keys, None))
+  }
+
+  async fn is_blob_exist(&self, object_key: &str) -> Result<bool, WorkerError> {
+    match self.0.open_blob(object_key).await {
+      Ok(_) => Ok(true),
+      Err(AppError::RecordNotFound(_)) => Ok(false),
+      Err(err) => Err(worker_error(err)),
+    }
+  }
+
+  async fn get_blob_meta(&self, object_key: &str) -> Result<BlobMeta, WorkerError> {
+    let (_, len, content_type) = self.0.open_blob(object_key).await.map_err(worker_error)?;
+    Ok(BlobMeta {
+      content_length: len as i64,
+      content_type,
+    })
+  }
+}
+
+/// In-memory counterpart of [S3ClientImpl], to test the workers without an object store.
+#[derive(Clone, Default)]
+pub struct InMemoryBlobClient(pub InMemoryBucketClientImpl);
+
+#[async_trait]
+impl S3Client for InMemoryBlobClient {
+  async fn get_blob_stream(&self, object_key: &str) -> Result<S3StreamResponse, WorkerError> {
+    let blob = self.0.get_blob(object_key).await.map_err(worker_error)?;
+    let content_type = blob.content_type();
+    let data = blob.to_blob();
+    let content_length = Some(data.len() as i64);
+    Ok(S3StreamResponse {
+      stream: Box::new(futures::io::Cursor::new(data)),
+      content_type,
+      content_length,
+    })
+  }
+
+  async fn put_blob(
+    &self,
+    object_key: &str,
+    content: ByteStream,
+    content_type: Option<&str>,
+  ) -> Result<(), WorkerError> {
+    self
+      .0
+      .put_blob(object_key, content, content_type)
+      .await
+      .map_err(worker_error)
+  }
+
+  async fn delete_blob(&self, object_key: &str) -> Result<(), WorkerError> {
+    self
+      .0
+      .delete_blob(object_key)
+      .await
+      .map(|_| ())
+      .map_err(worker_error)
+  }
+
+  async fn remove_dir(&self, dir: &str) -> Result<(), WorkerError> {
+    self.0.remove_dir(dir).await.map_err(worker_error)
+  }
+
+  /// The keys are listed in a single page.
+  async fn list_blobs(
+    &self,
+    prefix: &str,
+    _continuation: Option<String>,
+  ) -> Result<(Vec<String>, Option<String>), WorkerError> {
+    let keys = self
+      .0
+      .list_dir(prefix, usize::MAX)
+      .await
+      .map_err(worker_error)?;
+    Ok((keys, None))
+  }
+
+  async fn is_blob_exist(&self, object_key: &str) -> Result<bool, WorkerError> {
+    Ok(self.0.contains(object_key))
+  }
+
+  async fn get_blob_meta(&self, object_key: &str) -> Result<BlobMeta, WorkerError> {
+    let blob = self.0.get_blob(object_key).await.map_err(worker_error)?;
+    Ok(BlobMeta {
+      content_type: blob.content_type(),
+      content_length: blob.to_blob().len() as i64,
+    })
+  }
+}
+
+/// Sends the blobs of the workspaces configured with their own storage to their bucket, and the
+/// other blobs to the default storage.
+pub struct WorkspaceRoutedS3Client {
+  pub default: Arc<dyn S3Client>,
+  pub router: Arc<WorkspaceStorageRouter>,
+}
+
+impl WorkspaceRoutedS3Client {
+  async fn client_for(&self, object_key: &str) -> Result<Arc<dyn S3Client>, WorkerError> {
+    let client: Arc<dyn S3Client> = match self
+      .router
+      .bucket_for_key(object_key)
+      .await
+      .map_err(worker_error)?
+    {
+      Some(bucket) => Arc::new(S3ClientImpl::from(bucket)),
+      None => self.default.clone(),
+    };
+    Ok(client)
+  }
+}
+
+impl From<WorkspaceBucket> for S3ClientImpl {
+  fn from(bucket: WorkspaceBucket) -> Self {
+    Self {
+      inner: bucket.client,
+      bucket: bucket.bucket,
+    }
+  }
+}
+
+#[async_trait]
+impl S3Client for WorkspaceRoutedS3Client {
+  async fn get_blob_stream(&self, object_key: &str) -> Result<S3StreamResponse, WorkerError> {
+    self
+      .client_for(object_key)
+      .await?
+      .get_blob_stream(object_key)
+      .await
+  }
+
+  async fn put_blob(
+    &self,
+    object_key: &str,
+    content: ByteStream,
+    content_type: Option<&str>,
+  ) -> Result<(), WorkerError> {
+    self
+      .client_for(object_key)
+      .await?
+      .put_blob(object_key, content, content_type)
+      .await
+  }
+
+  async fn delete_blob(&self, object_key: &str) -> Result<(), WorkerError> {
+    self
+      .client_for(object_key)
+      .await?
+      .delete_blob(object_key)
+      .await
+  }
+
+  async fn remove_dir(&self, dir: &str) -> Result<(), WorkerError> {
+    self.client_for(dir).await?.remove_dir(dir).await
+  }
+
+  async fn list_blobs(
+    &self,
+    prefix: &str,
+    continuation: Option<String>,
+  ) -> Result<(Vec<String>, Option<String>), WorkerError> {
+    self
+      .client_for(prefix)
+      .await?
+      .list_blobs(prefix, continuation)
+      .await
+  }
+
+  async fn is_blob_exist(&self, object_key: &str) -> Result<bool, WorkerError> {
+    self
+      .client_for(object_key)
+      .await?
+      .is_blob_exist(object_key)
+      .await
+  }
+
+  async fn get_blob_meta(&self, object_key: &str) -> Result<BlobMeta, WorkerError> {
+    self
+      .client_for(object_key)
+      .await?
+      .get_blob_meta(object_key)
+      .await
+  }
+
+  /// The buckets of the workspaces are S3 buckets, the storage class is only used when the
+  /// default storage supports it too.
+  fn supports_storage_class(&self) -> bool {
+    self.default.supports_storage_class()
+  }
+
+  async fn put_blob_with_storage_class(
+    &self,
+    object_key: &str,
+    content: ByteStream,
+    storage_class: &str,
+  ) -> Result<(), WorkerError> {
+    self
+      .client_for(object_key)
+      .await?
+      .put_blob_with_storage_class(object_key, content, storage_class)
+      .await
+  }
+
+  async fn set_storage_class(
+    &self,
+    object_key: &str,
+    storage_class: &str,
+  ) -> Result<(), WorkerError> {
+    self
+      .client_for(object_key)
+      .await?
+      .set_storage_class(object_key, storage_class)
+      .await
+  }
 }
 
 pub struct S3StreamResponse {
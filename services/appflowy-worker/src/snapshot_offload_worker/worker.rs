@@ -0,0 +1,150 @@
+use crate::error::WorkerError;
+use crate::s3_client::S3Client;
+use aws_sdk_s3::primitives::ByteStream;
+use database::collab::{
+  collab_snapshot_storage_key, offload_collab_snapshot_blob, select_collab_snapshots_to_offload,
+  CollabSnapshotOffloadCandidate,
+};
+use database_entity::dto::ZSTD_COMPRESSION_LEVEL;
+use infra::env_util::get_env_var;
+use redis::aio::ConnectionManager;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{error, info, trace, warn};
+
+/// Only one worker offloads snapshots at a time.
+const SNAPSHOT_OFFLOAD_LOCK_KEY: &str = "af:snapshot_offload:lock";
+/// Upper bound of the batches offloaded in a single run, so a run never holds the lock for long.
+const MAX_BATCHES_PER_RUN: usize = 10;
+
+#[derive(Debug, Clone)]
+pub struct SnapshotOffloadSetting {
+  pub enabled: bool,
+  /// The snapshots stored in Postgres that are larger than that many bytes are moved to the
+  /// object storage. Should match `APPFLOWY_COLLAB_SNAPSHOT_OFFLOAD_THRESHOLD`.
+  pub threshold: i64,
+  /// Maximum number of snapshots offloaded in each batch.
+  pub batch_size: i64,
+  pub interval: Duration,
+}
+
+impl SnapshotOffloadSetting {
+  pub fn from_env() -> Self {
+    Self {
+      enabled: get_env_var("APPFLOWY_WORKER_SNAPSHOT_OFFLOAD_ENABLED", "false")
+        .parse()
+        .unwrap_or(false),
+      threshold: get_env_var("APPFLOWY_COLLAB_SNAPSHOT_OFFLOAD_THRESHOLD", "8000")
+        .parse()
+        .unwrap_or(8000),
+      batch_size: get_env_var("APPFLOWY_WORKER_SNAPSHOT_OFFLOAD_BATCH_SIZE", "100")
+        .parse()
+        .unwrap_or(100),
+      interval: Duration::from_secs(
+        get_env_var("APPFLOWY_WORKER_SNAPSHOT_OFFLOAD_INTERVAL_SECS", "600")
+          .parse()
+          .unwrap_or(600),
+      ),
+    }
+  }
+}
+
+/// Periodically moves the blobs of the large collab snapshots still stored in Postgres to the
+/// object storage, leaving only their metadata and storage key in the `af_collab_snapshot` table.
+pub async fn run_snapshot_offload_worker(
+  pg_pool: PgPool,
+  mut redis_client: ConnectionManager,
+  s3_client: Arc<dyn S3Client>,
+  setting: SnapshotOffloadSetting,
+) -> Result<(), WorkerError> {
+  if !setting.enabled {
+    return Ok(());
+  }
+  info!(
+    "Starting snapshot offload worker: offloading snapshots larger than {} bytes",
+    setting.threshold
+  );
+  let mut interval = interval(setting.interval);
+  interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+  loop {
+    interval.tick().await;
+    let locked: Option<String> = redis::cmd("SET")
+      .arg(SNAPSHOT_OFFLOAD_LOCK_KEY)
+      .arg(1)
+      .arg("NX")
+      .arg("EX")
+      .arg(setting.interval.as_secs().max(1))
+      .query_async(&mut redis_client)
+      .await
+      .unwrap_or_else(|err| {
+        error!("Failed to acquire snapshot offload lock: {:?}", err);
+        None
+      });
+    if locked.is_none() {
+      trace!("[Snapshot Offload] another worker is offloading snapshots");
+      continue;
+    }
+
+    match offload_snapshots(&pg_pool, &s3_client, &setting).await {
+      Ok(count) if count > 0 => info!("[Snapshot Offload] offloaded {} snapshots", count),
+      Ok(_) => {},
+      Err(err) => error!("[Snapshot Offload] failed to offload snapshots: {:?}", err),
+    }
+  }
+}
+
+async fn offload_snapshots(
+  pg_pool: &PgPool,
+  s3_client: &Arc<dyn S3Client>,
+  setting: &SnapshotOffloadSetting,
+) -> Result<usize, WorkerError> {
+  let mut count = 0;
+  for _ in 0..MAX_BATCHES_PER_RUN {
+    let candidates =
+      select_collab_snapshots_to_offload(pg_pool, setting.threshold, setting.batch_size)
+        .await
+        .map_err(|err| WorkerError::Internal(err.into()))?;
+    let selected = candidates.len();
+    let mut offloaded = 0;
+    for candidate in candidates {
+      match offload_snapshot(pg_pool, s3_client, &candidate).await {
+        Ok(true) => offloaded += 1,
+        Ok(false) => {},
+        Err(err) => warn!(
+          "[Snapshot Offload] failed to offload snapshot {} of {}: {:?}",
+          candidate.sid, candidate.oid, err
+        ),
+      }
+    }
+    count += offloaded;
+    // stop when there is nothing left, or when the failing snapshots would be selected again
+    if selected < setting.batch_size as usize || offloaded == 0 {
+      break;
+    }
+  }
+  Ok(count)
+}
+
+/// Offloads a single snapshot. Returns false if the snapshot has been deleted or offloaded since
+/// it was selected.
+async fn offload_snapshot(
+  pg_pool: &PgPool,
+  s3_client: &Arc<dyn S3Client>,
+  candidate: &CollabSnapshotOffloadCandidate,
+) -> Result<bool, WorkerError> {
+  let key = collab_snapshot_storage_key(&candidate.workspace_id, &candidate.oid, candidate.sid);
+  let compressed = zstd::encode_all(candidate.blob.as_slice(), ZSTD_COMPRESSION_LEVEL)?;
+  s3_client
+    .put_blob(&key, ByteStream::from(compressed), None)
+    .await?;
+
+  let offloaded = offload_collab_snapshot_blob(pg_pool, candidate.sid, &key)
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+  if !offloaded {
+    s3_client.delete_blob(&key).await?;
+  }
+  Ok(offloaded)
+}
@@ -0,0 +1,148 @@
+use crate::metric::{StreamConsumerLabel, StreamGroupLabel, StreamLagMetrics};
+use chrono::Utc;
+use infra::env_util::get_env_var;
+use redis::aio::ConnectionManager;
+use redis::streams::{StreamInfoConsumersReply, StreamPendingReply};
+use redis::{AsyncCommands, RedisResult};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{info, trace, warn};
+
+#[derive(Debug, Clone)]
+pub struct StreamLagSetting {
+  pub enabled: bool,
+  pub interval: Duration,
+}
+
+impl StreamLagSetting {
+  pub fn from_env() -> Self {
+    Self {
+      enabled: get_env_var("APPFLOWY_WORKER_STREAM_LAG_ENABLED", "true")
+        .parse()
+        .unwrap_or(true),
+      interval: Duration::from_secs(
+        get_env_var("APPFLOWY_WORKER_STREAM_LAG_INTERVAL_SECS", "30")
+          .parse()
+          .unwrap_or(30),
+      ),
+    }
+  }
+}
+
+/// A redis stream read by a consumer group.
+#[derive(Debug, Clone)]
+pub struct ConsumerStream {
+  pub stream_key: &'static str,
+  pub group_name: &'static str,
+}
+
+impl ConsumerStream {
+  pub fn new(stream_key: &'static str, group_name: &'static str) -> Self {
+    Self {
+      stream_key,
+      group_name,
+    }
+  }
+}
+
+/// Periodically samples the pending messages and the consumers of the given streams, so that the
+/// backlog of the consumer groups can be alerted on.
+pub async fn run_stream_lag_worker(
+  mut redis_client: ConnectionManager,
+  metrics: Arc<StreamLagMetrics>,
+  streams: Vec<ConsumerStream>,
+  setting: StreamLagSetting,
+) {
+  if !setting.enabled {
+    return;
+  }
+  info!("Starting stream lag worker");
+  let mut interval = interval(setting.interval);
+  interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+  loop {
+    interval.tick().await;
+    // the consumers that have been removed since the last sample are not reported anymore
+    metrics.consumer_idle_ms.clear();
+    for stream in &streams {
+      if let Err(err) = sample_stream_lag(&mut redis_client, &metrics, stream).await {
+        if err.code() == Some("NOGROUP") {
+          trace!(
+            "[Stream Lag] group {} of stream {} has not been created yet",
+            stream.group_name,
+            stream.stream_key
+          );
+        } else {
+          warn!(
+            "[Stream Lag] failed to sample stream {}: {:?}",
+            stream.stream_key, err
+          );
+        }
+      }
+    }
+  }
+}
+
+async fn sample_stream_lag(
+  redis_client: &mut ConnectionManager,
+  metrics: &StreamLagMetrics,
+  stream: &ConsumerStream,
+) -> RedisResult<()> {
+  let group_label = StreamGroupLabel {
+    stream: stream.stream_key.to_string(),
+    group: stream.group_name.to_string(),
+  };
+  let pending: StreamPendingReply = redis_client
+    .xpending(stream.stream_key, stream.group_name)
+    .await?;
+  let (pending_messages, oldest_pending_age_ms) = match pending {
+    StreamPendingReply::Empty => (0, 0),
+    StreamPendingReply::Data(data) => {
+      let age = stream_id_millis(&data.start_id)
+        .map(|millis| (Utc::now().timestamp_millis() - millis).max(0))
+        .unwrap_or_default();
+      (data.count as i64, age)
+    },
+  };
+  metrics
+    .pending_messages
+    .get_or_create(&group_label)
+    .set(pending_messages);
+  metrics
+    .oldest_pending_age_ms
+    .get_or_create(&group_label)
+    .set(oldest_pending_age_ms);
+
+  let reply: StreamInfoConsumersReply = redis_client
+    .xinfo_consumers(stream.stream_key, stream.group_name)
+    .await?;
+  for consumer in reply.consumers {
+    metrics
+      .consumer_idle_ms
+      .get_or_create(&StreamConsumerLabel {
+        stream: stream.stream_key.to_string(),
+        group: stream.group_name.to_string(),
+        consumer: consumer.name,
+      })
+      .set(consumer.idle as i64);
+  }
+  Ok(())
+}
+
+/// Returns the time the message was added to the stream at, in milliseconds since the epoch. The
+/// ids generated by redis are `<millis>-<sequence>`.
+fn stream_id_millis(stream_id: &str) -> Option<i64> {
+  stream_id.split('-').next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn stream_id_millis_test() {
+    assert_eq!(stream_id_millis("1712345678901-0"), Some(1712345678901));
+    assert_eq!(stream_id_millis("1712345678901-12"), Some(1712345678901));
+    assert_eq!(stream_id_millis("not-an-id"), None);
+  }
+}
@@ -0,0 +1,743 @@
+use crate::error::WorkerError;
+use crate::import_worker::worker::collab_key;
+use crate::s3_client::S3Client;
+use anyhow::anyhow;
+use axum::async_trait;
+use bytes::Bytes;
+use collab::core::origin::CollabOrigin;
+use collab::entity::{EncodedCollab, EncoderVersion};
+use collab::preclude::{Any, Array, ArrayRef, Collab, Map, MapRef, Out};
+use collab_database::database::{gen_row_id, DatabaseBody};
+use collab_database::entity::FieldType;
+use collab_database::rows::{
+  meta_id_from_row_id, DatabaseRowBody, RowMetaKey, CELL_FIELD_TYPE, ROW_CELLS,
+};
+use collab_database::template::entity::CELL_DATA;
+use collab_database::workspace_database::{NoPersistenceDatabaseCollabService, WorkspaceDatabase};
+use collab_document::blocks::DocumentData;
+use collab_document::document::Document;
+use collab_entity::CollabType;
+use collab_folder::{Folder, FolderData, SectionItem};
+use database::collab::collab_archive_key;
+use database::collab::select_blob_from_af_collab_in_workspace;
+use database::workspace::select_workspace_database_storage_id;
+use database_entity::dto::CollabParams;
+use futures::AsyncReadExt;
+use serde_json::Value;
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tracing::{trace, warn};
+use uuid::Uuid;
+
+/// The collabs of the cloned workspace.
+pub struct ClonedWorkspace {
+  /// Documents, databases and database rows.
+  pub collabs: Vec<CollabParams>,
+  /// The folder of the target workspace. It's written last since it makes the other collabs
+  /// reachable.
+  pub folder: CollabParams,
+  /// The workspace database of the target workspace, when the source workspace has databases.
+  pub workspace_database: Option<CollabParams>,
+}
+
+/// Copies the collabs of a workspace, read from a [CollabSource]. The object id of a collab is
+/// unique across workspaces, so every view, database and row gets a new id and the references
+/// between them are rewritten.
+pub struct WorkspaceCloner {
+  uid: i64,
+  source_workspace_id: Uuid,
+  target_workspace_id: Uuid,
+  view_ids: HashMap<String, String>,
+  database_ids: HashMap<String, String>,
+  row_ids: HashMap<String, String>,
+  /// Object ids of the source collabs that have been cloned.
+  cloned: HashSet<String>,
+  collabs: Vec<CollabParams>,
+}
+
+impl WorkspaceCloner {
+  pub fn new(uid: i64, source_workspace_id: Uuid, target_workspace_id: Uuid) -> Self {
+    Self {
+      uid,
+      source_workspace_id,
+      target_workspace_id,
+      view_ids: HashMap::new(),
+      database_ids: HashMap::new(),
+      row_ids: HashMap::new(),
+      cloned: HashSet::new(),
+      collabs: vec![],
+    }
+  }
+
+  pub async fn clone_collabs(
+    mut self,
+    source: &dyn CollabSource,
+    pg_pool: &PgPool,
+    s3_client: &Arc<dyn S3Client>,
+    workspace_name: String,
+  ) -> Result<ClonedWorkspace, WorkerError> {
+    let source_id = self.source_workspace_id.to_string();
+    let folder_collab = source.read_collab(&source_id, &CollabType::Folder).await?;
+    let folder = Folder::from_collab_doc_state(
+      self.uid,
+      CollabOrigin::Server,
+      folder_collab.into(),
+      &source_id,
+      vec![],
+    )
+    .map_err(|err| {
+      WorkerError::Internal(anyhow!("failed to open folder {}: {}", source_id, err))
+    })?;
+    let folder_data = folder
+      .get_folder_data(&source_id)
+      .ok_or_else(|| WorkerError::RecordNotFound(format!("folder of workspace {}", source_id)))?;
+    for view in &folder_data.views {
+      self.view_ids.insert(view.id.clone(), gen_id());
+    }
+
+    // All the ids are assigned before cloning, the relations of a database may point to any
+    // other database of the workspace.
+    let databases = self.load_databases(source).await?;
+    let mut linked_views = HashMap::new();
+    for (database_id, collab) in databases {
+      let (new_database_id, view_ids) = self.clone_database(source, &database_id, collab).await?;
+      linked_views.insert(new_database_id, view_ids);
+    }
+    for view in &folder_data.views {
+      if view.layout.is_document() && !self.cloned.contains(&view.id) {
+        let new_view_id = self.new_view_id(&view.id);
+        self.clone_document(source, &view.id, &new_view_id).await?;
+      }
+    }
+
+    let folder = self.clone_folder(folder_data, workspace_name)?;
+    let workspace_database = if linked_views.is_empty() {
+      None
+    } else {
+      Some(
+        self
+          .target_workspace_database(pg_pool, s3_client, linked_views)
+          .await?,
+      )
+    };
+    trace!(
+      "[Workspace Clone] {} cloned {} collabs to {}",
+      self.source_workspace_id,
+      self.collabs.len(),
+      self.target_workspace_id
+    );
+    Ok(ClonedWorkspace {
+      collabs: self.collabs,
+      folder,
+      workspace_database,
+    })
+  }
+
+  /// Loads the databases of the source workspace and assigns the new ids of their views and rows.
+  async fn load_databases(
+    &mut self,
+    source: &dyn CollabSource,
+  ) -> Result<Vec<(String, Collab)>, WorkerError> {
+    let Some(w_database_id) = source.workspace_database_id().await? else {
+      return Ok(vec![]);
+    };
+    let encoded_collab = match source
+      .read_collab(&w_database_id, &CollabType::WorkspaceDatabase)
+      .await
+    {
+      Ok(encoded_collab) => encoded_collab,
+      Err(WorkerError::RecordNotFound(_)) => return Ok(vec![]),
+      Err(err) => return Err(err),
+    };
+    let w_database = WorkspaceDatabase::from_collab_doc_state(
+      &w_database_id,
+      CollabOrigin::Server,
+      encoded_collab.into(),
+    )
+    .map_err(|err| WorkerError::Internal(anyhow!("failed to open workspace database: {}", err)))?;
+
+    let mut databases = vec![];
+    for meta in w_database.get_all_database_meta() {
+      let encoded_collab = match source
+        .read_collab(&meta.database_id, &CollabType::Database)
+        .await
+      {
+        Ok(encoded_collab) => encoded_collab,
+        Err(WorkerError::RecordNotFound(_)) => {
+          warn!("[Workspace Clone] database {} not found", meta.database_id);
+          continue;
+        },
+        Err(err) => return Err(err),
+      };
+      let collab = collab_from_encoded(&meta.database_id, encoded_collab)?;
+      let body = open_database_body(&meta.database_id, &collab)?;
+      self.database_ids.insert(meta.database_id.clone(), gen_id());
+      for view_id in meta.linked_views {
+        self.view_ids.entry(view_id).or_insert_with(gen_id);
+      }
+      {
+        let txn = collab.context.transact();
+        for view in body.views.get_all_views(&txn) {
+          self.view_ids.entry(view.id).or_insert_with(gen_id);
+          for row_order in view.row_orders {
+            if self.row_ids.contains_key(row_order.id.as_str()) {
+              continue;
+            }
+            let new_row_id = gen_row_id().to_string();
+            // the document of a row is an orphan view, its id is derived from the row id
+            if let (Ok(row_id), Ok(new_row_uuid)) = (
+              Uuid::parse_str(row_order.id.as_str()),
+              Uuid::parse_str(&new_row_id),
+            ) {
+              self.view_ids.insert(
+                meta_id_from_row_id(&row_id, RowMetaKey::DocumentId),
+                meta_id_from_row_id(&new_row_uuid, RowMetaKey::DocumentId),
+              );
+            }
+            self.row_ids.insert(row_order.id.to_string(), new_row_id);
+          }
+        }
+      }
+      databases.push((meta.database_id, collab));
+    }
+    Ok(databases)
+  }
+
+  /// Clones the database and its rows. Returns the new database id and its view ids.
+  async fn clone_database(
+    &mut self,
+    source: &dyn CollabSource,
+    database_id: &str,
+    collab: Collab,
+  ) -> Result<(String, Vec<String>), WorkerError> {
+    let new_database_id = self
+      .database_ids
+      .get(database_id)
+      .cloned()
+      .ok_or_else(|| WorkerError::RecordNotFound(format!("database {}", database_id)))?;
+    let mut collab = collab;
+    let body = open_database_body(database_id, &collab)?;
+    let mut row_ids = vec![];
+    let mut new_view_ids = vec![];
+    {
+      let mut txn = collab.context.transact_mut();
+      for mut field in body.fields.get_all_fields(&txn) {
+        for (key, type_option) in field.type_options.iter_mut() {
+          if *key != FieldType::Relation.type_id() {
+            continue;
+          }
+          let related_database_id = match type_option.get("database_id") {
+            Some(Any::String(id)) => id.to_string(),
+            _ => continue,
+          };
+          if let Some(new_related_id) = self.database_ids.get(&related_database_id) {
+            type_option.insert(
+              "database_id".to_string(),
+              Any::String(new_related_id.as_str().into()),
+            );
+            body.fields.update_field(&mut txn, &field.id, |f| {
+              f.set_type_option(FieldType::Relation.into(), Some(type_option.clone()));
+            });
+          }
+        }
+      }
+
+      let inline_view_id = body.get_inline_view_id(&txn);
+      let mut views = body.views.get_all_views(&txn);
+      for view in views.iter_mut() {
+        view.id = self.new_view_id(&view.id);
+        view.database_id.clone_from(&new_database_id);
+        for row_order in view.row_orders.iter_mut() {
+          if !row_ids.contains(&row_order.id.to_string()) {
+            row_ids.push(row_order.id.to_string());
+          }
+          if let Some(new_row_id) = self.row_ids.get(row_order.id.as_str()) {
+            row_order.id = new_row_id.clone().into();
+          }
+        }
+        new_view_ids.push(view.id.clone());
+      }
+      body.root.insert(&mut txn, "id", new_database_id.clone());
+      body
+        .metas
+        .insert(&mut txn, "iid", self.new_view_id(&inline_view_id));
+      body.views.clear(&mut txn);
+      for view in views {
+        body.views.insert_view(&mut txn, view);
+      }
+    }
+    self.push_collab(new_database_id.clone(), CollabType::Database, &collab)?;
+    self.cloned.insert(database_id.to_string());
+
+    for row_id in row_ids {
+      self.clone_row(source, &row_id, &new_database_id).await?;
+    }
+    Ok((new_database_id, new_view_ids))
+  }
+
+  async fn clone_row(
+    &mut self,
+    source: &dyn CollabSource,
+    row_id: &str,
+    new_database_id: &str,
+  ) -> Result<(), WorkerError> {
+    let new_row_id = match self.row_ids.get(row_id) {
+      Some(new_row_id) => new_row_id.clone(),
+      None => return Ok(()),
+    };
+    if self.cloned.contains(row_id) {
+      return Ok(());
+    }
+    let encoded_collab = match source.read_collab(row_id, &CollabType::DatabaseRow).await {
+      Ok(encoded_collab) => encoded_collab,
+      Err(WorkerError::RecordNotFound(_)) => {
+        warn!("[Workspace Clone] database row {} not found", row_id);
+        return Ok(());
+      },
+      Err(err) => return Err(err),
+    };
+    let mut collab = collab_from_encoded(row_id, encoded_collab)?;
+    let mut body = DatabaseRowBody::open(row_id.to_string().into(), &mut collab)
+      .map_err(|err| WorkerError::Internal(anyhow!("failed to open row {}: {}", row_id, err)))?;
+    let document_id = {
+      let mut txn = collab.context.transact_mut();
+      body.update(&mut txn, |update| {
+        update.set_database_id(new_database_id.to_string());
+      });
+      // the id of the row document changes along with the row id
+      let document_id = body
+        .document_id(&txn)
+        .map_err(|err| WorkerError::Internal(err.into()))?;
+      body
+        .update_id(&mut txn, new_row_id.clone().into())
+        .map_err(|err| WorkerError::Internal(anyhow!("failed to update row id: {:?}", err)))?;
+
+      // the relation cells hold the ids of the related rows
+      if let Some(cells) = body.get_data().get(&txn, ROW_CELLS) {
+        let cells: MapRef = cells
+          .cast()
+          .map_err(|err| WorkerError::Internal(anyhow!("cells is not a map: {:?}", err)))?;
+        let mut relation_cells = vec![];
+        for (_, out) in cells.iter(&txn) {
+          let Ok(cell) = out.cast::<MapRef>() else {
+            continue;
+          };
+          if let Some(Out::Any(Any::BigInt(field_type))) = cell.get(&txn, CELL_FIELD_TYPE) {
+            if field_type == FieldType::Relation as i64 {
+              if let Some(Ok(row_ids)) = cell
+                .get(&txn, CELL_DATA)
+                .map(|data| data.cast::<ArrayRef>())
+              {
+                relation_cells.push(row_ids);
+              }
+            }
+          }
+        }
+        for related_row_ids in relation_cells {
+          let len = related_row_ids.len(&txn);
+          let old_row_ids = related_row_ids
+            .iter(&txn)
+            .filter_map(|out| match out {
+              Out::Any(Any::String(id)) => Some(id.to_string()),
+              _ => None,
+            })
+            .collect::<Vec<_>>();
+          related_row_ids.remove_range(&mut txn, 0, len);
+          for old_row_id in old_row_ids {
+            let new_related_id = self.row_ids.get(&old_row_id).cloned().unwrap_or(old_row_id);
+            related_row_ids.push_back(&mut txn, new_related_id);
+          }
+        }
+      }
+      document_id
+    };
+    self.push_collab(new_row_id, CollabType::DatabaseRow, &collab)?;
+    self.cloned.insert(row_id.to_string());
+
+    if let Some(document_id) = document_id {
+      if let Some(new_document_id) = self.view_ids.get(&document_id).cloned() {
+        self
+          .clone_document(source, &document_id, &new_document_id)
+          .await?;
+      }
+    }
+    Ok(())
+  }
+
+  async fn clone_document(
+    &mut self,
+    source: &dyn CollabSource,
+    document_id: &str,
+    new_document_id: &str,
+  ) -> Result<(), WorkerError> {
+    if self.cloned.contains(document_id) {
+      return Ok(());
+    }
+    let encoded_collab = match source.read_collab(document_id, &CollabType::Document).await {
+      Ok(encoded_collab) => encoded_collab,
+      Err(WorkerError::RecordNotFound(_)) => {
+        warn!("[Workspace Clone] document {} not found", document_id);
+        return Ok(());
+      },
+      Err(err) => return Err(err),
+    };
+    let collab = collab_from_encoded(document_id, encoded_collab)?;
+    let mut data = Document::open(collab)
+      .and_then(|document| document.get_document_data())
+      .map_err(|err| WorkerError::Internal(err.into()))?;
+    self.remap_document_data(&mut data);
+    let encoded_collab = Document::create(new_document_id, data)
+      .map_err(|err| WorkerError::Internal(err.into()))?
+      .encode_collab_v1(|collab| CollabType::Document.validate_require_data(collab))
+      .map_err(|err| WorkerError::Internal(err.into()))?;
+    self.collabs.push(CollabParams {
+      object_id: new_document_id.to_string(),
+      collab_type: CollabType::Document,
+      encoded_collab_v1: Bytes::from(
+        encoded_collab
+          .encode_to_bytes()
+          .map_err(|err| WorkerError::Internal(err.into()))?,
+      ),
+    });
+    self.cloned.insert(document_id.to_string());
+    Ok(())
+  }
+
+  /// Points the blocks, the page mentions and the file urls of the document to the cloned
+  /// workspace.
+  fn remap_document_data(&self, data: &mut DocumentData) {
+    for block in data.blocks.values_mut() {
+      for key in ["view_id", "parent_id"] {
+        if let Some(Value::String(view_id)) = block.data.get_mut(key) {
+          if let Some(new_view_id) = self.view_ids.get(view_id.as_str()) {
+            *view_id = new_view_id.clone();
+          }
+        }
+      }
+      for value in block.data.values_mut() {
+        if let Value::String(text) = value {
+          *text = self.rewrite_file_url(text);
+        }
+      }
+    }
+    if let Some(text_map) = data.meta.text_map.as_mut() {
+      for delta in text_map.values_mut() {
+        if let Some(new_delta) = remap_page_mentions(delta, &self.view_ids) {
+          *delta = new_delta;
+        }
+      }
+    }
+  }
+
+  fn clone_folder(
+    &self,
+    mut data: FolderData,
+    workspace_name: String,
+  ) -> Result<CollabParams, WorkerError> {
+    let source = self.source_workspace_id.to_string();
+    let target = self.target_workspace_id.to_string();
+    data.workspace.id.clone_from(&target);
+    data.workspace.name = workspace_name;
+    for child in data.workspace.child_views.items.iter_mut() {
+      child.id = self.new_view_id(&child.id);
+    }
+    data.current_view = self.new_view_id(&data.current_view);
+    for view in data.views.iter_mut() {
+      view.id = self.new_view_id(&view.id);
+      view.parent_view_id = if view.parent_view_id == source {
+        target.clone()
+      } else {
+        self.new_view_id(&view.parent_view_id)
+      };
+      for child in view.children.items.iter_mut() {
+        child.id = self.new_view_id(&child.id);
+      }
+      if let Some(icon) = view.icon.as_mut() {
+        icon.value = self.rewrite_file_url(&icon.value);
+      }
+    }
+    self.remap_section_items(data.favorites.values_mut());
+    self.remap_section_items(data.recent.values_mut());
+    self.remap_section_items(data.trash.values_mut());
+    self.remap_section_items(data.private.values_mut());
+
+    let collab = Collab::new_with_origin(CollabOrigin::Empty, &target, vec![], false);
+    let folder = Folder::create(self.uid, collab, None, data);
+    let encoded_collab = folder
+      .encode_collab_v1(|collab| CollabType::Folder.validate_require_data(collab))
+      .map_err(|err| WorkerError::Internal(err.into()))?;
+    Ok(CollabParams {
+      object_id: target,
+      collab_type: CollabType::Folder,
+      encoded_collab_v1: Bytes::from(
+        encoded_collab
+          .encode_to_bytes()
+          .map_err(|err| WorkerError::Internal(err.into()))?,
+      ),
+    })
+  }
+
+  fn remap_section_items<'a>(&self, sections: impl Iterator<Item = &'a mut Vec<SectionItem>>) {
+    for items in sections {
+      for item in items.iter_mut() {
+        item.id = self.new_view_id(&item.id);
+      }
+    }
+  }
+
+  /// Adds the cloned databases to the workspace database created along with the target
+  /// workspace.
+  async fn target_workspace_database(
+    &self,
+    pg_pool: &PgPool,
+    s3_client: &Arc<dyn S3Client>,
+    linked_views: HashMap<String, Vec<String>>,
+  ) -> Result<CollabParams, WorkerError> {
+    let w_database_id =
+      select_workspace_database_storage_id(pg_pool, &self.target_workspace_id.to_string())
+        .await
+        .map_err(|err| WorkerError::Internal(err.into()))?
+        .to_string();
+    let encoded_collab = read_collab(
+      pg_pool,
+      s3_client,
+      &self.target_workspace_id,
+      &w_database_id,
+      &CollabType::WorkspaceDatabase,
+    )
+    .await?;
+    let mut w_database = WorkspaceDatabase::from_collab_doc_state(
+      &w_database_id,
+      CollabOrigin::Server,
+      encoded_collab.into(),
+    )
+    .map_err(|err| WorkerError::Internal(anyhow!("failed to open workspace database: {}", err)))?;
+    w_database.batch_add_database(linked_views);
+    let encoded_collab = w_database
+      .encode_collab_v1()
+      .map_err(|err| WorkerError::Internal(err.into()))?;
+    Ok(CollabParams {
+      object_id: w_database_id,
+      collab_type: CollabType::WorkspaceDatabase,
+      encoded_collab_v1: Bytes::from(
+        encoded_collab
+          .encode_to_bytes()
+          .map_err(|err| WorkerError::Internal(err.into()))?,
+      ),
+    })
+  }
+
+  fn push_collab(
+    &mut self,
+    object_id: String,
+    collab_type: CollabType,
+    collab: &Collab,
+  ) -> Result<(), WorkerError> {
+    let encoded_collab = collab
+      .encode_collab_v1(|collab| collab_type.validate_require_data(collab))
+      .map_err(|err| WorkerError::Internal(err.into()))?;
+    self.collabs.push(CollabParams {
+      object_id,
+      collab_type,
+      encoded_collab_v1: Bytes::from(
+        encoded_collab
+          .encode_to_bytes()
+          .map_err(|err| WorkerError::Internal(err.into()))?,
+      ),
+    });
+    Ok(())
+  }
+
+  fn new_view_id(&self, view_id: &str) -> String {
+    self
+      .view_ids
+      .get(view_id)
+      .cloned()
+      .unwrap_or_else(|| view_id.to_string())
+  }
+
+  fn rewrite_file_url(&self, value: &str) -> String {
+    rewrite_file_url(value, &self.source_workspace_id, &self.target_workspace_id)
+  }
+}
+
+/// Where the collabs of the source workspace are read from.
+#[async_trait]
+pub trait CollabSource: Send + Sync {
+  /// Returns [WorkerError::RecordNotFound] when the collab does not exist.
+  async fn read_collab(
+    &self,
+    object_id: &str,
+    collab_type: &CollabType,
+  ) -> Result<EncodedCollab, WorkerError>;
+
+  /// Object id of the workspace database, None when the workspace has none.
+  async fn workspace_database_id(&self) -> Result<Option<String>, WorkerError>;
+}
+
+/// Reads the collabs of a workspace of this instance.
+pub struct WorkspaceCollabSource {
+  pg_pool: PgPool,
+  s3_client: Arc<dyn S3Client>,
+  workspace_id: Uuid,
+}
+
+impl WorkspaceCollabSource {
+  pub fn new(pg_pool: PgPool, s3_client: Arc<dyn S3Client>, workspace_id: Uuid) -> Self {
+    Self {
+      pg_pool,
+      s3_client,
+      workspace_id,
+    }
+  }
+}
+
+#[async_trait]
+impl CollabSource for WorkspaceCollabSource {
+  async fn read_collab(
+    &self,
+    object_id: &str,
+    collab_type: &CollabType,
+  ) -> Result<EncodedCollab, WorkerError> {
+    read_collab(
+      &self.pg_pool,
+      &self.s3_client,
+      &self.workspace_id,
+      object_id,
+      collab_type,
+    )
+    .await
+  }
+
+  async fn workspace_database_id(&self) -> Result<Option<String>, WorkerError> {
+    let w_database_id =
+      select_workspace_database_storage_id(&self.pg_pool, &self.workspace_id.to_string())
+        .await
+        .map_err(|err| WorkerError::Internal(err.into()))?;
+    Ok(Some(w_database_id.to_string()))
+  }
+}
+
+/// Reads the collab from the object storage, the database or the archive, where the inactive
+/// documents are moved to.
+pub(crate) async fn read_collab(
+  pg_pool: &PgPool,
+  s3_client: &Arc<dyn S3Client>,
+  workspace_id: &Uuid,
+  object_id: &str,
+  collab_type: &CollabType,
+) -> Result<EncodedCollab, WorkerError> {
+  let key = collab_key(&workspace_id.to_string(), object_id);
+  match read_compressed_collab(s3_client, &key).await {
+    Err(WorkerError::RecordNotFound(_)) => {},
+    result => return result,
+  }
+  let blob = select_blob_from_af_collab_in_workspace(pg_pool, workspace_id, collab_type, object_id)
+    .await
+    .map_err(|err| match err {
+      sqlx::Error::RowNotFound => WorkerError::RecordNotFound(object_id.to_string()),
+      err => WorkerError::Internal(err.into()),
+    })?;
+  if blob.is_empty() {
+    return read_compressed_collab(s3_client, &collab_archive_key(workspace_id, object_id)).await;
+  }
+  EncodedCollab::decode_from_bytes(&blob)
+    .map_err(|err| WorkerError::Internal(anyhow!("invalid collab {}: {}", object_id, err)))
+}
+
+async fn read_compressed_collab(
+  s3_client: &Arc<dyn S3Client>,
+  key: &str,
+) -> Result<EncodedCollab, WorkerError> {
+  let mut resp = s3_client.get_blob_stream(key).await?;
+  let mut buf = Vec::with_capacity(resp.content_length.unwrap_or(1024).max(0) as usize);
+  resp.stream.read_to_end(&mut buf).await?;
+  let doc_state = zstd::decode_all(&*buf)?;
+  Ok(EncodedCollab {
+    state_vector: Default::default(),
+    doc_state: doc_state.into(),
+    version: EncoderVersion::V1,
+  })
+}
+
+fn collab_from_encoded(
+  object_id: &str,
+  encoded_collab: EncodedCollab,
+) -> Result<Collab, WorkerError> {
+  Collab::new_with_source(
+    CollabOrigin::Server,
+    object_id,
+    encoded_collab.into(),
+    vec![],
+    false,
+  )
+  .map_err(|err| WorkerError::Internal(anyhow!("failed to open collab {}: {}", object_id, err)))
+}
+
+fn open_database_body(database_id: &str, collab: &Collab) -> Result<DatabaseBody, WorkerError> {
+  DatabaseBody::from_collab(collab, Arc::new(NoPersistenceDatabaseCollabService), None)
+    .ok_or_else(|| WorkerError::Internal(anyhow!("no database body found in {}", database_id)))
+}
+
+fn gen_id() -> String {
+  Uuid::new_v4().to_string()
+}
+
+/// Replaces the page ids of the page mentions in the delta. Returns None if the delta has no
+/// mention to replace.
+fn remap_page_mentions(delta: &str, view_ids: &HashMap<String, String>) -> Option<String> {
+  let mut value = serde_json::from_str::<Value>(delta).ok()?;
+  let mut changed = false;
+  let page_ids = value
+    .as_array_mut()?
+    .iter_mut()
+    .flat_map(|op| op.get_mut("attributes"))
+    .flat_map(|attributes| attributes.get_mut("mention"))
+    .filter(|mention| mention.get("type").and_then(Value::as_str) == Some("page"))
+    .flat_map(|mention| mention.get_mut("page_id"));
+  for page_id in page_ids {
+    if let Some(new_page_id) = page_id.as_str().and_then(|id| view_ids.get(id)) {
+      *page_id = Value::String(new_page_id.clone());
+      changed = true;
+    }
+  }
+  changed.then(|| value.to_string())
+}
+
+/// The files uploaded to the workspace are copied with the same file id, only the workspace id of
+/// their url changes.
+fn rewrite_file_url(value: &str, source_workspace_id: &Uuid, target_workspace_id: &Uuid) -> String {
+  value.replace(
+    &format!("/api/file_storage/{}/", source_workspace_id),
+    &format!("/api/file_storage/{}/", target_workspace_id),
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn remap_references_to_cloned_workspace() {
+    let view_ids = HashMap::from([("old_page".to_string(), "new_page".to_string())]);
+    let delta = r#"[{"insert":"$","attributes":{"mention":{"type":"page","page_id":"old_page"}}}]"#;
+    let remapped = remap_page_mentions(delta, &view_ids).unwrap();
+    assert!(remapped.contains("new_page"));
+    assert!(remap_page_mentions(r#"[{"insert":"hello"}]"#, &view_ids).is_none());
+
+    let source = Uuid::new_v4();
+    let target = Uuid::new_v4();
+    let url = format!(
+      "https://appflowy.cloud/api/file_storage/{}/v1/blob/parent/file.png",
+      source
+    );
+    assert_eq!(
+      rewrite_file_url(&url, &source, &target),
+      format!(
+        "https://appflowy.cloud/api/file_storage/{}/v1/blob/parent/file.png",
+        target
+      )
+    );
+  }
+}
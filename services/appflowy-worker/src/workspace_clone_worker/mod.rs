@@ -0,0 +1,2 @@
+pub mod cloner;
+pub mod worker;
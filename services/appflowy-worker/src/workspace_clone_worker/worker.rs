@@ -0,0 +1,343 @@
+use crate::error::WorkerError;
+use crate::import_worker::worker::{encode_collab_key, ensure_consumer_group};
+use crate::s3_client::S3Client;
+use crate::workspace_clone_worker::cloner::{WorkspaceCloner, WorkspaceCollabSource};
+use anyhow::anyhow;
+use aws_sdk_s3::primitives::ByteStream;
+use database::collab::insert_into_af_collab_bulk_for_user;
+use database::resource_usage::get_all_workspace_blob_metadata;
+use database::workspace::{
+  delete_from_workspace, select_workspace_database_storage_id,
+  select_workspace_name_from_workspace_id, update_workspace_status,
+};
+use database::workspace_clone::{
+  copy_workspace_blob_metadata, select_workspace_clone_task, update_workspace_clone_task_progress,
+  update_workspace_clone_task_status, update_workspace_clone_task_total, WorkspaceCloneTaskState,
+};
+use futures::AsyncReadExt;
+use redis::aio::ConnectionManager;
+use redis::streams::{StreamId, StreamReadOptions, StreamReadReply};
+use redis::{AsyncCommands, Value};
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::ops::DerefMut;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{error, info, trace, warn};
+use uuid::Uuid;
+
+pub(crate) const GROUP_NAME: &str = "workspace_clone_task_group";
+const CONSUMER_NAME: &str = "appflowy_worker";
+const COLLAB_BATCH_SIZE: usize = 100;
+const BLOB_PROGRESS_INTERVAL: usize = 20;
+
+/// Task pushed by the server once the empty target workspace has been created.
+#[derive(Debug, Clone, Deserialize)]
+struct WorkspaceCloneTask {
+  task_id: Uuid,
+  uid: i64,
+  source_workspace_id: Uuid,
+  target_workspace_id: Uuid,
+}
+
+impl TryFrom<&StreamId> for WorkspaceCloneTask {
+  type Error = WorkerError;
+
+  fn try_from(stream_id: &StreamId) -> Result<Self, Self::Error> {
+    let task_str = match stream_id.map.get("task") {
+      Some(Value::Data(data)) => String::from_utf8_lossy(data).to_string(),
+      _ => {
+        return Err(WorkerError::Internal(anyhow!(
+          "missing task in stream entry {}",
+          stream_id.id
+        )))
+      },
+    };
+    serde_json::from_str(&task_str).map_err(|err| WorkerError::Internal(err.into()))
+  }
+}
+
+/// Copies the collabs and the files of a workspace into the workspace created by the server. The
+/// target workspace stays uninitialized until everything has been copied, and is deleted if the
+/// clone fails.
+pub async fn run_workspace_clone_worker(
+  pg_pool: PgPool,
+  mut redis_client: ConnectionManager,
+  s3_client: Arc<dyn S3Client>,
+  stream_name: &str,
+  tick_interval_secs: u64,
+) -> Result<(), WorkerError> {
+  info!("Starting workspace clone worker");
+  if let Err(err) = ensure_consumer_group(stream_name, GROUP_NAME, &mut redis_client).await {
+    error!("Failed to ensure consumer group: {:?}", err);
+  }
+
+  let mut pending_id = Some("0");
+  let options = StreamReadOptions::default()
+    .group(GROUP_NAME, CONSUMER_NAME)
+    .count(1);
+  let mut interval = interval(Duration::from_secs(tick_interval_secs));
+  interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+  loop {
+    interval.tick().await;
+    let id = pending_id.take().unwrap_or(">");
+    let reply: StreamReadReply = match redis_client
+      .xread_options(&[stream_name], &[id], &options)
+      .await
+    {
+      Ok(reply) => reply,
+      Err(err) => {
+        error!(
+          "Failed to read workspace clone tasks from Redis stream: {:?}",
+          err
+        );
+        if err.code() == Some("NOGROUP") {
+          if let Err(err) = ensure_consumer_group(stream_name, GROUP_NAME, &mut redis_client).await
+          {
+            error!("Failed to ensure consumer group: {:?}", err);
+          }
+        }
+        continue;
+      },
+    };
+
+    for stream_key in reply.keys {
+      for stream_id in stream_key.ids {
+        match WorkspaceCloneTask::try_from(&stream_id) {
+          Ok(task) => process_task(&pg_pool, &mut redis_client, &s3_client, task).await,
+          Err(err) => error!("Failed to deserialize workspace clone task: {:?}", err),
+        }
+        // The result is recorded on the task, the user starts a new clone to retry.
+        let _: Result<(), _> = redis_client
+          .xack(stream_name, GROUP_NAME, &[&stream_id.id])
+          .await
+          .map_err(|err| error!("Failed to ack workspace clone task: {:?}", err));
+      }
+    }
+  }
+}
+
+async fn process_task(
+  pg_pool: &PgPool,
+  redis_client: &mut ConnectionManager,
+  s3_client: &Arc<dyn S3Client>,
+  task: WorkspaceCloneTask,
+) {
+  trace!("[Workspace Clone] processing task: {:?}", task);
+  match select_workspace_clone_task(pg_pool, &task.task_id).await {
+    Ok(row) if WorkspaceCloneTaskState::from(row.status) == WorkspaceCloneTaskState::Pending => {},
+    Ok(_) => {
+      trace!("[Workspace Clone] task {} is already done", task.task_id);
+      return;
+    },
+    Err(err) => {
+      error!(
+        "[Workspace Clone] failed to select task {}: {:?}",
+        task.task_id, err
+      );
+      return;
+    },
+  }
+
+  if let Err(err) = clone_workspace(pg_pool, redis_client, s3_client, &task).await {
+    error!(
+      "[Workspace Clone] failed to clone workspace {} to {}: {:?}",
+      task.source_workspace_id, task.target_workspace_id, err
+    );
+    if let Err(err) = update_workspace_clone_task_status(
+      pg_pool,
+      &task.task_id,
+      WorkspaceCloneTaskState::Failed,
+      Some(&err.to_string()),
+    )
+    .await
+    {
+      error!(
+        "Failed to update workspace clone task {}: {:?}",
+        task.task_id, err
+      );
+    }
+    if let Err(err) = delete_from_workspace(pg_pool, &task.target_workspace_id).await {
+      error!(
+        "[Workspace Clone] failed to delete workspace {}: {:?}",
+        task.target_workspace_id, err
+      );
+    }
+  }
+}
+
+async fn clone_workspace(
+  pg_pool: &PgPool,
+  redis_client: &mut ConnectionManager,
+  s3_client: &Arc<dyn S3Client>,
+  task: &WorkspaceCloneTask,
+) -> Result<(), WorkerError> {
+  let target = task.target_workspace_id.to_string();
+  let workspace_name = select_workspace_name_from_workspace_id(pg_pool, &task.target_workspace_id)
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?
+    .unwrap_or_default();
+  let source =
+    WorkspaceCollabSource::new(pg_pool.clone(), s3_client.clone(), task.source_workspace_id);
+  let cloned = WorkspaceCloner::new(task.uid, task.source_workspace_id, task.target_workspace_id)
+    .clone_collabs(&source, pg_pool, s3_client, workspace_name)
+    .await?;
+  let blobs = get_all_workspace_blob_metadata(pg_pool, &task.source_workspace_id)
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+
+  let mut last_collabs = vec![cloned.folder];
+  last_collabs.extend(cloned.workspace_database);
+  let total_collabs = (cloned.collabs.len() + last_collabs.len()) as i32;
+  let total_blobs = blobs.len() as i32;
+  update_workspace_clone_task_total(pg_pool, &task.task_id, total_collabs, total_blobs)
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+
+  let mut cloned_collabs = 0;
+  for chunk in cloned.collabs.chunks(COLLAB_BATCH_SIZE) {
+    let mut tx = pg_pool
+      .begin()
+      .await
+      .map_err(|err| WorkerError::Internal(err.into()))?;
+    insert_into_af_collab_bulk_for_user(&mut tx, &task.uid, &target, chunk)
+      .await
+      .map_err(|err| WorkerError::Internal(err.into()))?;
+    tx.commit()
+      .await
+      .map_err(|err| WorkerError::Internal(err.into()))?;
+    cloned_collabs += chunk.len() as i32;
+    update_workspace_clone_task_progress(pg_pool, &task.task_id, cloned_collabs, 0)
+      .await
+      .map_err(|err| WorkerError::Internal(err.into()))?;
+  }
+
+  for (index, blob) in blobs.iter().enumerate() {
+    copy_blob(
+      s3_client,
+      &task.source_workspace_id,
+      &task.target_workspace_id,
+      &blob.file_id,
+    )
+    .await?;
+    if (index + 1) % BLOB_PROGRESS_INTERVAL == 0 {
+      update_workspace_clone_task_progress(
+        pg_pool,
+        &task.task_id,
+        cloned_collabs,
+        (index + 1) as i32,
+      )
+      .await
+      .map_err(|err| WorkerError::Internal(err.into()))?;
+    }
+  }
+
+  let mut tx = pg_pool
+    .begin()
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+  insert_into_af_collab_bulk_for_user(&mut tx, &task.uid, &target, &last_collabs)
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+  copy_workspace_blob_metadata(
+    tx.deref_mut(),
+    &task.source_workspace_id,
+    &task.target_workspace_id,
+  )
+  .await
+  .map_err(|err| WorkerError::Internal(err.into()))?;
+  update_workspace_clone_task_progress(tx.deref_mut(), &task.task_id, total_collabs, total_blobs)
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+  update_workspace_clone_task_status(
+    tx.deref_mut(),
+    &task.task_id,
+    WorkspaceCloneTaskState::Completed,
+    None,
+  )
+  .await
+  .map_err(|err| WorkerError::Internal(err.into()))?;
+  update_workspace_status(tx.deref_mut(), &task.target_workspace_id, true)
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+  tx.commit()
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+
+  // The folder and the workspace database of the empty workspace may have been cached when the
+  // workspace was created.
+  let mut keys = vec![encode_collab_key(&target)];
+  if let Ok(w_database_id) = select_workspace_database_storage_id(pg_pool, &target).await {
+    keys.push(encode_collab_key(&w_database_id.to_string()));
+  }
+  if let Err(err) = redis_client.del::<_, ()>(keys).await {
+    warn!(
+      "[Workspace Clone] failed to remove cached collabs of {}: {}",
+      target, err
+    );
+  }
+  info!(
+    "[Workspace Clone] cloned workspace {} to {}: {} collabs, {} files",
+    task.source_workspace_id, task.target_workspace_id, total_collabs, total_blobs
+  );
+  Ok(())
+}
+
+async fn copy_blob(
+  s3_client: &Arc<dyn S3Client>,
+  source_workspace_id: &Uuid,
+  target_workspace_id: &Uuid,
+  file_id: &str,
+) -> Result<(), WorkerError> {
+  let source_key = blob_object_key(source_workspace_id, file_id);
+  let mut resp = match s3_client.get_blob_stream(&source_key).await {
+    Ok(resp) => resp,
+    Err(WorkerError::RecordNotFound(_)) => {
+      warn!("[Workspace Clone] file {} not found", source_key);
+      return Ok(());
+    },
+    Err(err) => return Err(err),
+  };
+  let mut data = Vec::with_capacity(resp.content_length.unwrap_or(0).max(0) as usize);
+  resp.stream.read_to_end(&mut data).await?;
+  s3_client
+    .put_blob(
+      &blob_object_key(target_workspace_id, file_id),
+      ByteStream::from(data),
+      resp.content_type.as_deref(),
+    )
+    .await
+}
+
+/// The files uploaded to a parent directory are stored under `{workspace_id}/{parent_dir}/{file_id}`
+/// and their metadata under `{parent_dir}_{file_id}`. The older files are stored directly under
+/// `{workspace_id}/{file_id}`.
+pub(crate) fn blob_object_key(workspace_id: &Uuid, file_id: &str) -> String {
+  match file_id.split_once('_') {
+    Some((parent_dir, file_id)) if Uuid::parse_str(parent_dir).is_ok() => {
+      format!("{}/{}/{}", workspace_id, parent_dir, file_id)
+    },
+    _ => format!("{}/{}", workspace_id, file_id),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn blob_object_key_from_metadata() {
+    let workspace_id = Uuid::new_v4();
+    let parent_dir = Uuid::new_v4();
+    assert_eq!(
+      blob_object_key(&workspace_id, &format!("{}_image.png", parent_dir)),
+      format!("{}/{}/image.png", workspace_id, parent_dir)
+    );
+    assert_eq!(
+      blob_object_key(&workspace_id, "legacy_file.png"),
+      format!("{}/legacy_file.png", workspace_id)
+    );
+  }
+}
@@ -0,0 +1,261 @@
+use crate::error::WorkerError;
+use crate::import_worker::worker::{encode_collab_key, ensure_consumer_group};
+use crate::s3_client::S3Client;
+use anyhow::anyhow;
+use database::publish::published_feed_dir;
+use database::workspace::delete_from_workspace;
+use database::workspace_delete::{
+  delete_workspace_collab_batch, delete_workspace_snapshot_batch, select_workspace_delete_task,
+  update_workspace_delete_task_progress, update_workspace_delete_task_status,
+  WorkspaceDeleteTaskState,
+};
+use infra::env_util::get_env_var;
+use redis::aio::ConnectionManager;
+use redis::streams::{StreamId, StreamReadOptions, StreamReadReply};
+use redis::{AsyncCommands, Value};
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{error, info, trace, warn};
+use uuid::Uuid;
+
+pub(crate) const GROUP_NAME: &str = "workspace_delete_task_group";
+const CONSUMER_NAME: &str = "appflowy_worker";
+
+#[derive(Debug, Clone)]
+pub struct WorkspaceDeleteSetting {
+  /// Maximum number of collabs, and of rows of each snapshot table, deleted by each statement.
+  pub batch_size: i64,
+  pub tick_interval: Duration,
+}
+
+impl WorkspaceDeleteSetting {
+  pub fn from_env() -> Self {
+    Self {
+      batch_size: get_env_var("APPFLOWY_WORKER_WORKSPACE_DELETE_BATCH_SIZE", "500")
+        .parse()
+        .unwrap_or(500)
+        .max(1),
+      tick_interval: Duration::from_secs(
+        get_env_var("APPFLOWY_WORKER_WORKSPACE_DELETE_INTERVAL_SECS", "5")
+          .parse()
+          .unwrap_or(5),
+      ),
+    }
+  }
+}
+
+/// Task pushed by the server once the workspace has been hidden from its members.
+#[derive(Debug, Clone, Deserialize)]
+struct WorkspaceDeleteTask {
+  task_id: Uuid,
+  workspace_id: Uuid,
+}
+
+impl TryFrom<&StreamId> for WorkspaceDeleteTask {
+  type Error = WorkerError;
+
+  fn try_from(stream_id: &StreamId) -> Result<Self, Self::Error> {
+    let task_str = match stream_id.map.get("task") {
+      Some(Value::Data(data)) => String::from_utf8_lossy(data).to_string(),
+      _ => {
+        return Err(WorkerError::Internal(anyhow!(
+          "missing task in stream entry {}",
+          stream_id.id
+        )))
+      },
+    };
+    serde_json::from_str(&task_str).map_err(|err| WorkerError::Internal(err.into()))
+  }
+}
+
+/// Deletes the workspaces queued by the server. The collabs and the snapshots are deleted in
+/// batches so a large workspace never holds long locks, then the files and the cached collabs
+/// are removed, and the workspace row is deleted last.
+pub async fn run_workspace_delete_worker(
+  pg_pool: PgPool,
+  mut redis_client: ConnectionManager,
+  s3_client: Arc<dyn S3Client>,
+  stream_name: &str,
+  setting: WorkspaceDeleteSetting,
+) -> Result<(), WorkerError> {
+  info!("Starting workspace delete worker");
+  if let Err(err) = ensure_consumer_group(stream_name, GROUP_NAME, &mut redis_client).await {
+    error!("Failed to ensure consumer group: {:?}", err);
+  }
+
+  let mut pending_id = Some("0");
+  let options = StreamReadOptions::default()
+    .group(GROUP_NAME, CONSUMER_NAME)
+    .count(1);
+  let mut interval = interval(setting.tick_interval);
+  interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+  loop {
+    interval.tick().await;
+    let id = pending_id.take().unwrap_or(">");
+    let reply: StreamReadReply = match redis_client
+      .xread_options(&[stream_name], &[id], &options)
+      .await
+    {
+      Ok(reply) => reply,
+      Err(err) => {
+        error!(
+          "Failed to read workspace delete tasks from Redis stream: {:?}",
+          err
+        );
+        if err.code() == Some("NOGROUP") {
+          if let Err(err) = ensure_consumer_group(stream_name, GROUP_NAME, &mut redis_client).await
+          {
+            error!("Failed to ensure consumer group: {:?}", err);
+          }
+        }
+        continue;
+      },
+    };
+
+    for stream_key in reply.keys {
+      for stream_id in stream_key.ids {
+        match WorkspaceDeleteTask::try_from(&stream_id) {
+          Ok(task) => {
+            process_task(
+              &pg_pool,
+              &mut redis_client,
+              &s3_client,
+              setting.batch_size,
+              task,
+            )
+            .await
+          },
+          Err(err) => error!("Failed to deserialize workspace delete task: {:?}", err),
+        }
+        // The result is recorded on the task, deleting the workspace again queues a new task.
+        let _: Result<(), _> = redis_client
+          .xack(stream_name, GROUP_NAME, &[&stream_id.id])
+          .await
+          .map_err(|err| error!("Failed to ack workspace delete task: {:?}", err));
+      }
+    }
+  }
+}
+
+async fn process_task(
+  pg_pool: &PgPool,
+  redis_client: &mut ConnectionManager,
+  s3_client: &Arc<dyn S3Client>,
+  batch_size: i64,
+  task: WorkspaceDeleteTask,
+) {
+  trace!("[Workspace Delete] processing task: {:?}", task);
+  match select_workspace_delete_task(pg_pool, &task.task_id).await {
+    Ok(row) if WorkspaceDeleteTaskState::from(row.status) == WorkspaceDeleteTaskState::Pending => {
+    },
+    Ok(_) => {
+      trace!("[Workspace Delete] task {} is already done", task.task_id);
+      return;
+    },
+    Err(err) => {
+      error!(
+        "[Workspace Delete] failed to select task {}: {:?}",
+        task.task_id, err
+      );
+      return;
+    },
+  }
+
+  let (state, error) =
+    match delete_workspace(pg_pool, redis_client, s3_client, batch_size, &task).await {
+      Ok(()) => (WorkspaceDeleteTaskState::Completed, None),
+      Err(err) => {
+        error!(
+          "[Workspace Delete] failed to delete workspace {}: {:?}",
+          task.workspace_id, err
+        );
+        (WorkspaceDeleteTaskState::Failed, Some(err.to_string()))
+      },
+    };
+  if let Err(err) =
+    update_workspace_delete_task_status(pg_pool, &task.task_id, state, error.as_deref()).await
+  {
+    error!(
+      "Failed to update workspace delete task {}: {:?}",
+      task.task_id, err
+    );
+  }
+}
+
+async fn delete_workspace(
+  pg_pool: &PgPool,
+  redis_client: &mut ConnectionManager,
+  s3_client: &Arc<dyn S3Client>,
+  batch_size: i64,
+  task: &WorkspaceDeleteTask,
+) -> Result<(), WorkerError> {
+  let workspace_id = task.workspace_id;
+  let mut deleted_collabs = 0;
+  loop {
+    let oids = delete_workspace_collab_batch(pg_pool, &workspace_id, batch_size)
+      .await
+      .map_err(|err| WorkerError::Internal(err.into()))?;
+    if oids.is_empty() {
+      break;
+    }
+    deleted_collabs += oids.len() as i32;
+    let keys = oids
+      .iter()
+      .map(|oid| encode_collab_key(oid))
+      .collect::<Vec<_>>();
+    if let Err(err) = redis_client.del::<_, ()>(keys).await {
+      warn!(
+        "[Workspace Delete] failed to remove cached collabs of {}: {}",
+        workspace_id, err
+      );
+    }
+    update_workspace_delete_task_progress(pg_pool, &task.task_id, deleted_collabs, 0)
+      .await
+      .map_err(|err| WorkerError::Internal(err.into()))?;
+  }
+
+  let mut deleted_snapshots = 0;
+  loop {
+    let deleted = delete_workspace_snapshot_batch(pg_pool, &workspace_id, batch_size)
+      .await
+      .map_err(|err| WorkerError::Internal(err.into()))?;
+    if deleted == 0 {
+      break;
+    }
+    deleted_snapshots += deleted as i32;
+    update_workspace_delete_task_progress(
+      pg_pool,
+      &task.task_id,
+      deleted_collabs,
+      deleted_snapshots,
+    )
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+  }
+
+  // The uploaded files are stored under `{workspace_id}/`, the collabs and the snapshots that
+  // were offloaded to the object storage under `collabs/{workspace_id}/`, the sitemap and feeds
+  // of the published pages under `published_feed/{workspace_id}/`.
+  s3_client.remove_dir(&workspace_id.to_string()).await?;
+  s3_client
+    .remove_dir(&format!("collabs/{}/", workspace_id))
+    .await?;
+  s3_client
+    .remove_dir(&published_feed_dir(&workspace_id))
+    .await?;
+
+  // Deleting the workspace row cascades to the remaining rows, like the members and the file
+  // metadata, and notifies the listeners of `af_workspace_deleted`.
+  delete_from_workspace(pg_pool, &workspace_id)
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+  info!(
+    "[Workspace Delete] deleted workspace {}: {} collabs, {} snapshots",
+    workspace_id, deleted_collabs, deleted_snapshots
+  );
+  Ok(())
+}
@@ -0,0 +1,259 @@
+use crate::error::WorkerError;
+use crate::s3_client::{S3Client, S3ClientImpl};
+use aws_sdk_s3::primitives::ByteStream;
+use chrono::Utc;
+use database::file::{workspace_object_key_prefixes, WorkspaceStorageRouter};
+use database::pg_row::AFWorkspaceStorageRow;
+use database::workspace_storage::{
+  complete_workspace_storage_relocation, select_workspace_storage_relocations,
+  switch_workspace_storage, update_workspace_storage_relocated_objects,
+  update_workspace_storage_relocation_error, WorkspaceStorageRelocationStatus,
+};
+use futures::AsyncReadExt;
+use infra::env_util::get_env_var;
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{error, info, trace};
+use uuid::Uuid;
+
+/// Only one worker relocates workspaces at a time. The lock is released after each run, it only
+/// expires on its own if the worker stops in the middle of a run.
+const RELOCATION_LOCK_KEY: &str = "af:workspace_storage_relocation:lock";
+const RELOCATION_LOCK_SECS: u64 = 60 * 60;
+/// Maximum number of workspaces relocated in each run.
+const RELOCATION_BATCH_SIZE: i64 = 10;
+
+#[derive(Debug, Clone)]
+pub struct WorkspaceStorageRelocationSetting {
+  pub interval: Duration,
+}
+
+impl WorkspaceStorageRelocationSetting {
+  pub fn from_env() -> Self {
+    Self {
+      interval: Duration::from_secs(
+        get_env_var("APPFLOWY_WORKER_WORKSPACE_STORAGE_INTERVAL_SECS", "30")
+          .parse()
+          .unwrap_or(30),
+      ),
+    }
+  }
+}
+
+/// Moves the objects of the workspaces whose storage was changed by an administrator:
+/// 1. the objects are copied to the new storage while the workspace keeps using its current one,
+/// 2. the workspace is switched to the new storage,
+/// 3. once every server has seen the switch, the objects written to the previous storage during
+///    the copy are copied over, and the previous storage is emptied.
+///
+/// `s3_client` is the default storage of the instance, not the one routing the workspaces.
+pub async fn run_workspace_storage_worker(
+  pg_pool: PgPool,
+  mut redis_client: ConnectionManager,
+  s3_client: Arc<dyn S3Client>,
+  router: Arc<WorkspaceStorageRouter>,
+  setting: WorkspaceStorageRelocationSetting,
+) -> Result<(), WorkerError> {
+  info!("Starting workspace storage relocation worker");
+  let mut interval = interval(setting.interval);
+  interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+  loop {
+    interval.tick().await;
+    let locked: Option<String> = redis::cmd("SET")
+      .arg(RELOCATION_LOCK_KEY)
+      .arg(1)
+      .arg("NX")
+      .arg("EX")
+      .arg(RELOCATION_LOCK_SECS)
+      .query_async(&mut redis_client)
+      .await
+      .unwrap_or_else(|err| {
+        error!(
+          "Failed to acquire workspace storage relocation lock: {:?}",
+          err
+        );
+        None
+      });
+    if locked.is_none() {
+      trace!("[Workspace Storage] another worker is relocating workspaces");
+      continue;
+    }
+
+    if let Err(err) = relocate_workspaces(&pg_pool, &s3_client, &router).await {
+      error!(
+        "[Workspace Storage] failed to relocate workspaces: {:?}",
+        err
+      );
+    }
+    if let Err(err) = redis_client.del::<_, ()>(RELOCATION_LOCK_KEY).await {
+      error!(
+        "Failed to release workspace storage relocation lock: {:?}",
+        err
+      );
+    }
+  }
+}
+
+async fn relocate_workspaces(
+  pg_pool: &PgPool,
+  s3_client: &Arc<dyn S3Client>,
+  router: &WorkspaceStorageRouter,
+) -> Result<(), WorkerError> {
+  // The servers cache the storage of the workspaces, a switched workspace may still be written to
+  // its previous storage until their cache expires.
+  let switched_before = Utc::now()
+    - chrono::Duration::from_std(router.cache_ttl() * 2)
+      .map_err(|err| WorkerError::Internal(err.into()))?;
+  let rows = select_workspace_storage_relocations(pg_pool, switched_before, RELOCATION_BATCH_SIZE)
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+
+  for row in rows {
+    let workspace_id = row.workspace_id;
+    let result = match WorkspaceStorageRelocationStatus::from(row.relocation_status) {
+      WorkspaceStorageRelocationStatus::Copying => {
+        copy_to_target(pg_pool, s3_client, router, &row).await
+      },
+      WorkspaceStorageRelocationStatus::Switched => {
+        empty_previous_storage(pg_pool, s3_client, router, &row).await
+      },
+      _ => continue,
+    };
+    if let Err(err) = result {
+      error!(
+        "[Workspace Storage] failed to relocate workspace {}: {:?}",
+        workspace_id, err
+      );
+      update_workspace_storage_relocation_error(pg_pool, &workspace_id, &err.to_string())
+        .await
+        .map_err(|err| WorkerError::Internal(err.into()))?;
+    }
+  }
+  Ok(())
+}
+
+async fn copy_to_target(
+  pg_pool: &PgPool,
+  s3_client: &Arc<dyn S3Client>,
+  router: &WorkspaceStorageRouter,
+  row: &AFWorkspaceStorageRow,
+) -> Result<(), WorkerError> {
+  let source = storage_client(s3_client, router, row.settings.as_deref())?;
+  let target = storage_client(s3_client, router, row.target_settings.as_deref())?;
+  let copied = copy_objects(pg_pool, &source, &target, &row.workspace_id, 0, false).await?;
+
+  switch_workspace_storage(pg_pool, &row.workspace_id)
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+  router.invalidate(&row.workspace_id).await;
+  info!(
+    "[Workspace Storage] copied {} objects of workspace {}, switched to the new storage",
+    copied, row.workspace_id
+  );
+  Ok(())
+}
+
+async fn empty_previous_storage(
+  pg_pool: &PgPool,
+  s3_client: &Arc<dyn S3Client>,
+  router: &WorkspaceStorageRouter,
+  row: &AFWorkspaceStorageRow,
+) -> Result<(), WorkerError> {
+  let previous = storage_client(s3_client, router, row.previous_settings.as_deref())?;
+  let current = storage_client(s3_client, router, row.settings.as_deref())?;
+  let copied = copy_objects(
+    pg_pool,
+    &previous,
+    &current,
+    &row.workspace_id,
+    row.relocated_objects,
+    true,
+  )
+  .await?;
+  for prefix in workspace_object_key_prefixes(&row.workspace_id) {
+    previous.remove_dir(&prefix).await?;
+  }
+
+  complete_workspace_storage_relocation(pg_pool, &row.workspace_id)
+    .await
+    .map_err(|err| WorkerError::Internal(err.into()))?;
+  info!(
+    "[Workspace Storage] relocated {} objects of workspace {}, the previous storage is emptied",
+    copied, row.workspace_id
+  );
+  Ok(())
+}
+
+/// Copies the objects of the workspace, only the ones missing from the target when
+/// `missing_only` is set. Returns the number of objects copied so far.
+async fn copy_objects(
+  pg_pool: &PgPool,
+  source: &Arc<dyn S3Client>,
+  target: &Arc<dyn S3Client>,
+  workspace_id: &Uuid,
+  mut copied: i64,
+  missing_only: bool,
+) -> Result<i64, WorkerError> {
+  for prefix in workspace_object_key_prefixes(workspace_id) {
+    let mut continuation = None;
+    loop {
+      let (keys, next) = source.list_blobs(&prefix, continuation).await?;
+      for key in keys {
+        if missing_only && target.is_blob_exist(&key).await? {
+          continue;
+        }
+        copy_object(source, target, &key).await?;
+        copied += 1;
+      }
+      update_workspace_storage_relocated_objects(pg_pool, workspace_id, copied)
+        .await
+        .map_err(|err| WorkerError::Internal(err.into()))?;
+      match next {
+        Some(next) => continuation = Some(next),
+        None => break,
+      }
+    }
+  }
+  Ok(copied)
+}
+
+async fn copy_object(
+  source: &Arc<dyn S3Client>,
+  target: &Arc<dyn S3Client>,
+  key: &str,
+) -> Result<(), WorkerError> {
+  let mut resp = match source.get_blob_stream(key).await {
+    Ok(resp) => resp,
+    // deleted since it was listed
+    Err(WorkerError::RecordNotFound(_)) => return Ok(()),
+    Err(err) => return Err(err),
+  };
+  let mut data = Vec::with_capacity(resp.content_length.unwrap_or(0).max(0) as usize);
+  resp.stream.read_to_end(&mut data).await?;
+  target
+    .put_blob(key, ByteStream::from(data), resp.content_type.as_deref())
+    .await
+}
+
+/// Returns the client of the storage with the given encrypted settings, the default storage when
+/// there are none.
+fn storage_client(
+  s3_client: &Arc<dyn S3Client>,
+  router: &WorkspaceStorageRouter,
+  settings: Option<&[u8]>,
+) -> Result<Arc<dyn S3Client>, WorkerError> {
+  match settings {
+    None => Ok(s3_client.clone()),
+    Some(settings) => {
+      let settings = router
+        .cipher()
+        .decrypt(settings)
+        .map_err(|err| WorkerError::Internal(err.into()))?;
+      Ok(Arc::new(S3ClientImpl::from(router.bucket(&settings))))
+    },
+  }
+}
@@ -1,8 +1,13 @@
 use anyhow::Result;
 use appflowy_worker::error::WorkerError;
 use appflowy_worker::import_worker::report::{ImportNotifier, ImportProgress};
-use appflowy_worker::import_worker::worker::{run_import_worker, ImportTask};
-use appflowy_worker::s3_client::{BlobMeta, S3Client, S3StreamResponse};
+use appflowy_worker::import_worker::task_queue::{
+  InMemoryEncodedCollabCache, InMemoryImportTaskQueue,
+};
+use appflowy_worker::import_worker::worker::{
+  consume_task, run_import_worker, ImportTask, TaskContext,
+};
+use appflowy_worker::s3_client::{BlobMeta, InMemoryBlobClient, S3Client, S3StreamResponse};
 use aws_sdk_s3::primitives::ByteStream;
 use axum::async_trait;
 
@@ -10,8 +15,8 @@ use redis::aio::ConnectionManager;
 use redis::AsyncCommands;
 use redis::RedisResult;
 use serde_json::json;
-use sqlx::PgPool;
 use sqlx::__rt::timeout;
+use sqlx::PgPool;
 use std::sync::{Arc, Once};
 use std::time::Duration;
 use tokio::runtime::Builder;
@@ -64,6 +69,30 @@ async fn create_custom_task_test(pg_pool: PgPool) {
   .unwrap();
 }
 
+#[sqlx::test(migrations = false)]
+async fn consume_task_with_in_memory_queue_test(pg_pool: PgPool) {
+  let queue = Arc::new(InMemoryImportTaskQueue::new());
+  let notifier = Arc::new(MockNotifier::new());
+  let mut rx = notifier.subscribe();
+  let context = TaskContext {
+    storage_dir: std::env::temp_dir(),
+    task_queue: queue.clone(),
+    collab_cache: Arc::new(InMemoryEncodedCollabCache::new()),
+    s3_client: Arc::new(InMemoryBlobClient::default()),
+    pg_pool,
+    notifier,
+    metrics: None,
+    maximum_import_file_size: 1_000_000_000,
+  };
+
+  queue.push_task(ImportTask::Custom(json!({"workspace_id": "w1"})));
+  let (entry_id, task) = queue.next_task().unwrap();
+  consume_task(context, task, entry_id).await.unwrap();
+
+  assert!(matches!(rx.try_recv(), Ok(ImportProgress::Finished(_))));
+  assert!(queue.is_empty());
+}
+
 // #[tokio::test]
 // async fn consume_group_task_test() {
 //   let mut redis_client = redis_client().await;
@@ -221,6 +250,18 @@ impl S3Client for MockS3Client {
     Ok(())
   }
 
+  async fn remove_dir(&self, _dir: &str) -> Result<(), WorkerError> {
+    Ok(())
+  }
+
+  async fn list_blobs(
+    &self,
+    _prefix: &str,
+    _continuation: Option<String>,
+  ) -> Result<(Vec<String>, Option<String>), WorkerError> {
+    Ok((vec![], None))
+  }
+
   async fn is_blob_exist(&self, _object_key: &str) -> Result<bool, WorkerError> {
     Ok(false)
   }
@@ -10,7 +10,7 @@ use database_entity::dto::{
 };
 use shared_entity::{
   dto::access_request_dto::AccessRequest,
-  response::{AppResponse, JsonAppResponse},
+  response::{AppResponse, AppResponseError, JsonAppResponse},
 };
 use uuid::Uuid;
 
@@ -31,6 +31,17 @@ pub fn access_request_scope() -> Scope {
     )
 }
 
+#[utoipa::path(
+  get,
+  path = "/api/access-request/{request_id}",
+  tag = "access request",
+  params(("request_id" = Uuid, Path, description = "Id of the access request")),
+  responses(
+    (status = 200, description = "The access request", body = AccessRequest),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
 async fn get_access_request_handler(
   uuid: UserUuid,
   access_request_id: web::Path<Uuid>,
@@ -48,6 +59,17 @@ async fn get_access_request_handler(
   Ok(Json(AppResponse::Ok().with_data(access_request)))
 }
 
+#[utoipa::path(
+  post,
+  path = "/api/access-request",
+  tag = "access request",
+  request_body = CreateAccessRequestParams,
+  responses(
+    (status = 200, description = "The created access request", body = AccessRequestMinimal),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
 async fn post_access_request_handler(
   uuid: UserUuid,
   create_access_request_params: Json<CreateAccessRequestParams>,
@@ -81,6 +103,18 @@ async fn post_access_request_handler(
   Ok(Json(AppResponse::Ok().with_data(access_request)))
 }
 
+#[utoipa::path(
+  post,
+  path = "/api/access-request/{request_id}/approve",
+  tag = "access request",
+  params(("request_id" = Uuid, Path, description = "Id of the access request")),
+  request_body = ApproveAccessRequestParams,
+  responses(
+    (status = 200, description = "Access request approved or rejected"),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
 async fn post_approve_access_request_handler(
   uuid: UserUuid,
   access_request_id: web::Path<Uuid>,
@@ -99,8 +133,10 @@ async fn post_approve_access_request_handler(
     )))?;
   approve_or_reject_access_request(
     &state.pg_pool,
+    &state.config.billing,
     state.workspace_access_control.clone(),
     state.mailer.clone(),
+    &state.notification_center,
     &appflowy_web_url,
     access_request_id,
     uid,
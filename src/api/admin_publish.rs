@@ -0,0 +1,110 @@
+use actix_web::web::{Data, Json};
+use actix_web::{web, Scope};
+use app_error::AppError;
+use authentication::jwt::Authorization;
+use shared_entity::dto::admin_dto::{PublishReportQuery, PublishReportStatus, PublishedViewReport};
+use shared_entity::response::{AppResponse, AppResponseError, JsonAppResponse};
+use uuid::Uuid;
+
+use crate::biz::workspace::publish_moderation;
+use crate::state::AppState;
+
+pub fn admin_publish_report_scope() -> Scope {
+  web::scope("/api/admin/publish-reports")
+    .service(web::resource("").route(web::get().to(list_publish_reports_handler)))
+    .service(
+      web::resource("/{report_id}/dismiss").route(web::post().to(dismiss_publish_report_handler)),
+    )
+    .service(
+      web::resource("/{report_id}/unpublish")
+        .route(web::post().to(unpublish_reported_view_handler)),
+    )
+}
+
+#[utoipa::path(
+  get,
+  path = "/api/admin/publish-reports",
+  tag = "admin",
+  params(
+    ("offset" = Option<i64>, Query, description = "Number of reports to skip"),
+    ("limit" = Option<i64>, Query, description = "Maximum number of reports to return, 100 by default"),
+    ("status" = Option<PublishReportStatus>, Query, description = "Status of the reports to return, open by default"),
+  ),
+  responses(
+    (status = 200, description = "The abuse reports on published views, oldest first", body = Vec<PublishedViewReport>),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+async fn list_publish_reports_handler(
+  auth: Authorization,
+  query: web::Query<PublishReportQuery>,
+  state: Data<AppState>,
+) -> actix_web::Result<JsonAppResponse<Vec<PublishedViewReport>>> {
+  if !auth.is_admin() {
+    return Err(AppError::NotEnoughPermissions.into());
+  }
+  let query = query.into_inner();
+  let reports = publish_moderation::list_publish_reports(
+    &state.pg_pool,
+    query.status,
+    query.offset,
+    query.limit,
+  )
+  .await?;
+  Ok(AppResponse::Ok().with_data(reports).into())
+}
+
+#[utoipa::path(
+  post,
+  path = "/api/admin/publish-reports/{report_id}/dismiss",
+  tag = "admin",
+  params(("report_id" = Uuid, Path, description = "Id of the report")),
+  responses(
+    (status = 200, description = "The report was dismissed, the view stays published"),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+async fn dismiss_publish_report_handler(
+  auth: Authorization,
+  report_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> actix_web::Result<JsonAppResponse<()>> {
+  if !auth.is_admin() {
+    return Err(AppError::NotEnoughPermissions.into());
+  }
+  publish_moderation::dismiss_publish_report(&state.pg_pool, auth.uuid()?, report_id.into_inner())
+    .await?;
+  Ok(Json(AppResponse::Ok()))
+}
+
+#[utoipa::path(
+  post,
+  path = "/api/admin/publish-reports/{report_id}/unpublish",
+  tag = "admin",
+  params(("report_id" = Uuid, Path, description = "Id of the report")),
+  responses(
+    (status = 200, description = "The reported view was unpublished and its open reports closed"),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+async fn unpublish_reported_view_handler(
+  auth: Authorization,
+  report_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> actix_web::Result<JsonAppResponse<()>> {
+  if !auth.is_admin() {
+    return Err(AppError::NotEnoughPermissions.into());
+  }
+  publish_moderation::unpublish_reported_view(
+    &state.pg_pool,
+    &state.published_collab_store,
+    &state.metrics.published_collab_metrics,
+    auth.uuid()?,
+    report_id.into_inner(),
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok()))
+}
@@ -0,0 +1,166 @@
+use actix_web::web::{Data, Json};
+use actix_web::{web, HttpResponse, Scope};
+use app_error::AppError;
+use authentication::jwt::Authorization;
+use shared_entity::dto::admin_dto::{
+  AdminAuditLog, AdminAuditLogQuery, AdminBulkUserParams, AdminBulkUserResult,
+};
+use shared_entity::response::{AppResponse, AppResponseError, JsonAppResponse};
+
+use crate::biz::user::user_admin;
+use crate::state::AppState;
+
+pub fn admin_user_scope() -> Scope {
+  web::scope("/api/admin/users")
+    .service(web::resource("/deactivate").route(web::post().to(deactivate_users_handler)))
+    .service(web::resource("/delete").route(web::post().to(delete_users_handler)))
+    .service(web::resource("/password-reset").route(web::post().to(password_reset_handler)))
+    .service(web::resource("/export").route(web::get().to(export_users_handler)))
+    .service(web::resource("/audit-log").route(web::get().to(list_audit_logs_handler)))
+}
+
+#[utoipa::path(
+  post,
+  path = "/api/admin/users/deactivate",
+  tag = "admin",
+  request_body = AdminBulkUserParams,
+  responses(
+    (status = 200, description = "The users that were deactivated and the ones that failed", body = AdminBulkUserResult),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+async fn deactivate_users_handler(
+  auth: Authorization,
+  payload: Json<AdminBulkUserParams>,
+  state: Data<AppState>,
+) -> actix_web::Result<JsonAppResponse<AdminBulkUserResult>> {
+  if !auth.is_admin() {
+    return Err(AppError::NotEnoughPermissions.into());
+  }
+  let result = user_admin::deactivate_users(
+    &state.pg_pool,
+    &state.gotrue_client,
+    &state.gotrue_admin,
+    auth.uuid()?,
+    payload.into_inner().user_uuids,
+  )
+  .await?;
+  Ok(AppResponse::Ok().with_data(result).into())
+}
+
+#[utoipa::path(
+  post,
+  path = "/api/admin/users/delete",
+  tag = "admin",
+  request_body = AdminBulkUserParams,
+  responses(
+    (status = 200, description = "The users that were deleted and the ones that failed", body = AdminBulkUserResult),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+async fn delete_users_handler(
+  auth: Authorization,
+  payload: Json<AdminBulkUserParams>,
+  state: Data<AppState>,
+) -> actix_web::Result<JsonAppResponse<AdminBulkUserResult>> {
+  if !auth.is_admin() {
+    return Err(AppError::NotEnoughPermissions.into());
+  }
+  let result = user_admin::delete_users(
+    &state.pg_pool,
+    &state.redis_connection_manager,
+    &state.gotrue_client,
+    &state.gotrue_admin,
+    auth.uuid()?,
+    payload.into_inner().user_uuids,
+  )
+  .await?;
+  Ok(AppResponse::Ok().with_data(result).into())
+}
+
+#[utoipa::path(
+  post,
+  path = "/api/admin/users/password-reset",
+  tag = "admin",
+  request_body = AdminBulkUserParams,
+  responses(
+    (status = 200, description = "The users the password reset email was sent to and the ones that failed", body = AdminBulkUserResult),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+async fn password_reset_handler(
+  auth: Authorization,
+  payload: Json<AdminBulkUserParams>,
+  state: Data<AppState>,
+) -> actix_web::Result<JsonAppResponse<AdminBulkUserResult>> {
+  if !auth.is_admin() {
+    return Err(AppError::NotEnoughPermissions.into());
+  }
+  let result = user_admin::send_password_reset_emails(
+    &state.pg_pool,
+    &state.gotrue_client,
+    auth.uuid()?,
+    payload.into_inner().user_uuids,
+  )
+  .await?;
+  Ok(AppResponse::Ok().with_data(result).into())
+}
+
+#[utoipa::path(
+  get,
+  path = "/api/admin/users/export",
+  tag = "admin",
+  responses(
+    (status = 200, description = "All the users with their workspace counts, as CSV", content_type = "text/csv", body = String),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+async fn export_users_handler(
+  auth: Authorization,
+  state: Data<AppState>,
+) -> actix_web::Result<HttpResponse> {
+  if !auth.is_admin() {
+    return Err(AppError::NotEnoughPermissions.into());
+  }
+  let csv = user_admin::export_users_csv(&state.pg_pool, auth.uuid()?).await?;
+  Ok(
+    HttpResponse::Ok()
+      .content_type("text/csv; charset=utf-8")
+      .insert_header((
+        actix_web::http::header::CONTENT_DISPOSITION,
+        "attachment; filename=\"users.csv\"",
+      ))
+      .body(csv),
+  )
+}
+
+#[utoipa::path(
+  get,
+  path = "/api/admin/users/audit-log",
+  tag = "admin",
+  params(
+    ("offset" = Option<i64>, Query, description = "Number of entries to skip"),
+    ("limit" = Option<i64>, Query, description = "Maximum number of entries to return, 100 by default"),
+  ),
+  responses(
+    (status = 200, description = "The audit log of the admin user operations, most recent first", body = Vec<AdminAuditLog>),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+async fn list_audit_logs_handler(
+  auth: Authorization,
+  query: web::Query<AdminAuditLogQuery>,
+  state: Data<AppState>,
+) -> actix_web::Result<JsonAppResponse<Vec<AdminAuditLog>>> {
+  if !auth.is_admin() {
+    return Err(AppError::NotEnoughPermissions.into());
+  }
+  let query = query.into_inner();
+  let logs = user_admin::list_audit_logs(&state.pg_pool, query.offset, query.limit).await?;
+  Ok(AppResponse::Ok().with_data(logs).into())
+}
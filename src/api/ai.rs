@@ -1,16 +1,19 @@
 use crate::api::util::ai_model_from_header;
+use crate::biz::billing::ops::consume_ai_response;
+use crate::biz::workspace::ops::ensure_ai_feature_enabled;
 use crate::state::AppState;
 
 use actix_web::web::{Data, Json};
 use actix_web::{web, HttpRequest, HttpResponse, Scope};
 use app_error::AppError;
 use appflowy_ai_client::dto::{
-  CalculateSimilarityParams, LocalAIConfig, ModelList, SimilarityResponse, TranslateRowParams,
-  TranslateRowResponse,
+  CalculateSimilarityParams, LanguageCheckParams, LanguageCheckResponse, LocalAIConfig, ModelList,
+  SimilarityResponse, TranslateRowParams, TranslateRowResponse,
 };
 
 use futures_util::{stream, TryStreamExt};
 
+use database_entity::dto::AIFeature;
 use serde::Deserialize;
 use shared_entity::dto::ai_dto::{
   CompleteTextParams, SummarizeRowData, SummarizeRowParams, SummarizeRowResponse,
@@ -18,6 +21,7 @@ use shared_entity::dto::ai_dto::{
 use shared_entity::response::AppResponse;
 
 use tracing::{error, instrument, trace};
+use uuid::Uuid;
 
 pub fn ai_completion_scope() -> Scope {
   web::scope("/api/ai/{workspace_id}")
@@ -29,13 +33,17 @@ pub fn ai_completion_scope() -> Scope {
       web::resource("/calculate_similarity").route(web::post().to(calculate_similarity_handler)),
     )
     .service(web::resource("/model/list").route(web::get().to(model_list_handler)))
+    .service(web::resource("/language_check").route(web::post().to(language_check_handler)))
 }
 
 async fn stream_complete_text_handler(
+  workspace_id: web::Path<Uuid>,
   state: Data<AppState>,
   payload: Json<CompleteTextParams>,
   req: HttpRequest,
 ) -> actix_web::Result<HttpResponse> {
+  ensure_ai_feature_enabled(&state.pg_pool, &workspace_id, AIFeature::Complete).await?;
+  consume_ai_response(&state.pg_pool, &state.config.billing, &workspace_id).await?;
   let ai_model = ai_model_from_header(&req);
   let params = payload.into_inner();
   state.metrics.ai_metrics.record_total_completion_count(1);
@@ -62,10 +70,12 @@ async fn stream_complete_text_handler(
 
 #[instrument(level = "debug", skip(state, payload), err)]
 async fn summarize_row_handler(
+  workspace_id: web::Path<Uuid>,
   state: Data<AppState>,
   payload: Json<SummarizeRowParams>,
   req: HttpRequest,
 ) -> actix_web::Result<Json<AppResponse<SummarizeRowResponse>>> {
+  ensure_ai_feature_enabled(&state.pg_pool, &workspace_id, AIFeature::SummarizeRow).await?;
   let params = payload.into_inner();
   match params.data {
     SummarizeRowData::Identity { .. } => {
@@ -82,6 +92,7 @@ async fn summarize_row_handler(
         );
       }
 
+      consume_ai_response(&state.pg_pool, &state.config.billing, &workspace_id).await?;
       state.metrics.ai_metrics.record_total_summary_row_count(1);
       let ai_model = ai_model_from_header(&req);
       let result = state.ai_client.summarize_row(&content, ai_model).await;
@@ -102,10 +113,13 @@ async fn summarize_row_handler(
 
 #[instrument(level = "debug", skip(state, payload), err)]
 async fn translate_row_handler(
+  workspace_id: web::Path<Uuid>,
   state: web::Data<AppState>,
   payload: web::Json<TranslateRowParams>,
   req: HttpRequest,
 ) -> actix_web::Result<Json<AppResponse<TranslateRowResponse>>> {
+  ensure_ai_feature_enabled(&state.pg_pool, &workspace_id, AIFeature::Translate).await?;
+  consume_ai_response(&state.pg_pool, &state.config.billing, &workspace_id).await?;
   let params = payload.into_inner();
   let ai_model = ai_model_from_header(&req);
   state.metrics.ai_metrics.record_total_translate_row_count(1);
@@ -122,6 +136,30 @@ async fn translate_row_handler(
   }
 }
 
+/// Checks the spelling and the grammar of the text. Long texts are checked in chunks, and the
+/// chunks that were checked recently are answered from the cache.
+#[instrument(level = "debug", skip(state, payload), err)]
+async fn language_check_handler(
+  workspace_id: web::Path<Uuid>,
+  state: web::Data<AppState>,
+  payload: web::Json<LanguageCheckParams>,
+  req: HttpRequest,
+) -> actix_web::Result<Json<AppResponse<LanguageCheckResponse>>> {
+  ensure_ai_feature_enabled(&state.pg_pool, &workspace_id, AIFeature::LanguageCheck).await?;
+  let params = payload.into_inner();
+  let ai_model = ai_model_from_header(&req);
+  let resp = state
+    .language_checker
+    .check(
+      &state.metrics.ai_metrics,
+      &params.text,
+      params.language.as_deref(),
+      ai_model,
+    )
+    .await?;
+  Ok(AppResponse::Ok().with_data(resp).into())
+}
+
 #[derive(Deserialize, Debug)]
 struct ConfigQuery {
   platform: String,
@@ -0,0 +1,41 @@
+use actix_web::web::{Bytes, Data, Json};
+use actix_web::{web, HttpRequest, Result, Scope};
+use shared_entity::response::{AppResponse, AppResponseError, JsonAppResponse};
+
+use crate::biz::billing::webhook::{handle_billing_webhook, SIGNATURE_HEADER};
+use crate::state::AppState;
+
+pub fn billing_scope() -> Scope {
+  web::scope("/api/billing")
+    .service(web::resource("/webhook").route(web::post().to(billing_webhook_handler)))
+}
+
+#[utoipa::path(
+  post,
+  path = "/api/billing/webhook",
+  tag = "billing",
+  request_body(content = String, description = "Subscription event of the payment provider, signed in the `Stripe-Signature` header", content_type = "application/json"),
+  responses(
+    (status = 200, description = "The event was applied, or ignored if it is not a subscription event"),
+    (status = "default", description = "Error response", body = AppResponseError),
+  )
+)]
+async fn billing_webhook_handler(
+  req: HttpRequest,
+  payload: Bytes,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<()>> {
+  let signature = req
+    .headers()
+    .get(SIGNATURE_HEADER)
+    .and_then(|value| value.to_str().ok());
+  handle_billing_webhook(
+    &state.pg_pool,
+    &state.config.billing,
+    &state.workspace_read_only_cache,
+    signature,
+    &payload,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok()))
+}
@@ -1,30 +1,40 @@
+use crate::biz::billing::ops::consume_ai_response;
+use crate::biz::chat::attachment::{list_chat_attachments, upload_chat_attachment};
+use crate::biz::chat::feedback::{create_chat_message_feedback, export_chat_message_feedback};
 use crate::biz::chat::ops::{
   create_chat, create_chat_message, delete_chat, generate_chat_message_answer,
-  get_chat_messages_with_author_uuid, get_question_message, update_chat_message,
+  get_chat_messages_with_author_uuid, get_question_message, search_chat_messages,
+  update_chat_message,
 };
+use crate::biz::chat::thread::get_chat_threads;
+use crate::biz::workspace::ops::ensure_ai_feature_enabled;
 use crate::state::AppState;
+use actix_multipart::form::{bytes::Bytes as MPBytes, MultipartForm, MultipartFormConfig};
 use actix_web::web::{Data, Json};
 use actix_web::{web, HttpRequest, HttpResponse, Scope};
 use serde::Deserialize;
 
 use crate::api::util::ai_model_from_header;
+use crate::config::config::PayloadLimitSetting;
 use app_error::AppError;
 use appflowy_ai_client::dto::{
   ChatQuestion, ChatQuestionQuery, CreateChatContext, MessageData, QuestionMetadata,
   RepeatedRelatedQuestion,
 };
-use authentication::jwt::UserUuid;
+use authentication::jwt::{Authorization, UserUuid};
 use bytes::Bytes;
 use database::chat;
+use database_entity::dto::AIFeature;
 use futures::Stream;
 use futures_util::stream;
 use futures_util::{FutureExt, TryStreamExt};
 use pin_project::pin_project;
 use shared_entity::dto::chat_dto::{
-  ChatAuthor, ChatMessage, ChatMessageWithAuthorUuid, ChatSettings, CreateAnswerMessageParams,
-  CreateChatMessageParams, CreateChatMessageParamsV2, CreateChatParams, GetChatMessageParams,
-  MessageCursor, RepeatedChatMessageWithAuthorUuid, UpdateChatMessageContentParams,
-  UpdateChatParams,
+  ChatAttachment, ChatAuthor, ChatMessage, ChatMessageFeedback, ChatMessageFeedbackParams,
+  ChatMessageFeedbackQuery, ChatMessageSearchResult, ChatMessageWithAuthorUuid, ChatSettings,
+  CreateAnswerMessageParams, CreateChatMessageParams, CreateChatMessageParamsV2, CreateChatParams,
+  GetChatMessageParams, GetChatThreadParams, MessageCursor, RepeatedChatMessageWithAuthorUuid,
+  RepeatedChatThread, SearchChatMessageParams, UpdateChatMessageContentParams, UpdateChatParams,
 };
 use shared_entity::response::{AppResponse, JsonAppResponse};
 use std::collections::HashMap;
@@ -35,7 +45,7 @@ use tokio::task;
 use tracing::{error, instrument, trace};
 use uuid::Uuid;
 use validator::Validate;
-pub fn chat_scope() -> Scope {
+pub fn chat_scope(payload_limits: &PayloadLimitSetting) -> Scope {
   web::scope("/api/chat/{workspace_id}")
       // Chat CRUD
       .service(
@@ -78,6 +88,14 @@ pub fn chat_scope() -> Scope {
         web::resource("/{chat_id}/message/find_question")
             .route(web::get().to(get_chat_question_message_handler))
       )
+      .service(
+        web::resource("/{chat_id}/search")
+            .route(web::get().to(search_chat_messages_handler))
+      )
+      .service(
+        web::resource("/{chat_id}/thread")
+            .route(web::get().to(get_chat_threads_handler))
+      )
 
       // AI response generation
       .service(
@@ -102,17 +120,38 @@ pub fn chat_scope() -> Scope {
         web::resource("/{chat_id}/{message_id}/related_question")
             .route(web::get().to(get_related_message_handler))
       )
+      .service(
+        web::resource("/{chat_id}/{message_id}/feedback")
+            .route(web::post().to(create_chat_message_feedback_handler))
+      )
       .service(
         web::resource("/{chat_id}/context/text")
             .route(web::post().to(create_chat_context_handler))
       )
+      .service(
+        web::resource("/{chat_id}/context/file")
+            .app_data(
+              MultipartFormConfig::default()
+                  .total_limit(payload_limits.chat_attachment)
+                  .memory_limit(payload_limits.chat_attachment),
+            )
+            .route(web::post().to(upload_chat_attachment_handler))
+            .route(web::get().to(list_chat_attachments_handler))
+      )
 }
+/// Endpoints reserved to the administrators of the instance.
+pub fn chat_admin_scope() -> Scope {
+  web::scope("/api/admin/chat")
+    .service(web::resource("/feedback").route(web::get().to(export_chat_message_feedback_handler)))
+}
+
 async fn create_chat_handler(
   path: web::Path<String>,
   state: Data<AppState>,
   payload: Json<CreateChatParams>,
 ) -> actix_web::Result<JsonAppResponse<()>> {
   let workspace_id = path.into_inner();
+  ensure_chat_enabled(&state, &workspace_id).await?;
   let params = payload.into_inner();
   create_chat(&state.pg_pool, params, &workspace_id).await?;
   Ok(AppResponse::Ok().into())
@@ -129,9 +168,12 @@ async fn delete_chat_handler(
 
 #[instrument(level = "debug", skip_all, err)]
 async fn create_chat_context_handler(
+  path: web::Path<(String, String)>,
   state: Data<AppState>,
   payload: Json<CreateChatContext>,
 ) -> actix_web::Result<JsonAppResponse<()>> {
+  let (workspace_id, _chat_id) = path.into_inner();
+  ensure_chat_enabled(&state, &workspace_id).await?;
   let params = payload.into_inner();
   state
     .ai_client
@@ -141,6 +183,49 @@ async fn create_chat_context_handler(
   Ok(AppResponse::Ok().into())
 }
 
+#[derive(MultipartForm)]
+#[multipart(duplicate_field = "deny")]
+struct ChatAttachmentForm {
+  file: MPBytes,
+}
+
+#[instrument(level = "debug", skip_all, err)]
+async fn upload_chat_attachment_handler(
+  uuid: UserUuid,
+  path: web::Path<(Uuid, Uuid)>,
+  MultipartForm(form): MultipartForm<ChatAttachmentForm>,
+  state: Data<AppState>,
+) -> actix_web::Result<JsonAppResponse<ChatAttachment>> {
+  let (workspace_id, chat_id) = path.into_inner();
+  ensure_ai_feature_enabled(&state.pg_pool, &workspace_id, AIFeature::Chat).await?;
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  let file_name = form.file.file_name.clone().unwrap_or_default();
+  let content_type = form.file.content_type.as_ref().map(|mime| mime.to_string());
+  let attachment = upload_chat_attachment(
+    &state.pg_pool,
+    &state.bucket_client,
+    &state.redis_connection_manager,
+    &state.ai_client,
+    uid,
+    &workspace_id,
+    &chat_id,
+    &file_name,
+    content_type.as_deref(),
+    form.file.data.to_vec(),
+  )
+  .await?;
+  Ok(AppResponse::Ok().with_data(attachment).into())
+}
+
+async fn list_chat_attachments_handler(
+  path: web::Path<(Uuid, Uuid)>,
+  state: Data<AppState>,
+) -> actix_web::Result<JsonAppResponse<Vec<ChatAttachment>>> {
+  let (workspace_id, chat_id) = path.into_inner();
+  let attachments = list_chat_attachments(&state.pg_pool, &workspace_id, &chat_id).await?;
+  Ok(AppResponse::Ok().with_data(attachments).into())
+}
+
 async fn update_question_handler(
   path: web::Path<(String, String)>,
   state: Data<AppState>,
@@ -148,6 +233,7 @@ async fn update_question_handler(
   req: HttpRequest,
 ) -> actix_web::Result<JsonAppResponse<()>> {
   let (workspace_id, _chat_id) = path.into_inner();
+  ensure_chat_enabled(&state, &workspace_id).await?;
   let params = payload.into_inner();
   let ai_model = ai_model_from_header(&req);
   update_chat_message(
@@ -166,7 +252,8 @@ async fn get_related_message_handler(
   state: Data<AppState>,
   req: HttpRequest,
 ) -> actix_web::Result<JsonAppResponse<RepeatedRelatedQuestion>> {
-  let (_workspace_id, chat_id, message_id) = path.into_inner();
+  let (workspace_id, chat_id, message_id) = path.into_inner();
+  ensure_chat_enabled(&state, &workspace_id).await?;
   let ai_model = ai_model_from_header(&req);
   let resp = state
     .ai_client
@@ -176,6 +263,39 @@ async fn get_related_message_handler(
   Ok(AppResponse::Ok().with_data(resp).into())
 }
 
+async fn create_chat_message_feedback_handler(
+  uuid: UserUuid,
+  path: web::Path<(Uuid, Uuid, i64)>,
+  payload: Json<ChatMessageFeedbackParams>,
+  state: Data<AppState>,
+) -> actix_web::Result<JsonAppResponse<()>> {
+  let (workspace_id, chat_id, message_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  create_chat_message_feedback(
+    &state.pg_pool,
+    &state.ai_metrics,
+    uid,
+    &workspace_id,
+    &chat_id,
+    message_id,
+    payload.into_inner(),
+  )
+  .await?;
+  Ok(AppResponse::Ok().into())
+}
+
+async fn export_chat_message_feedback_handler(
+  auth: Authorization,
+  query: web::Query<ChatMessageFeedbackQuery>,
+  state: Data<AppState>,
+) -> actix_web::Result<JsonAppResponse<Vec<ChatMessageFeedback>>> {
+  if !auth.is_admin() {
+    return Err(AppError::NotEnoughPermissions.into());
+  }
+  let feedback = export_chat_message_feedback(&state.pg_pool, query.into_inner()).await?;
+  Ok(AppResponse::Ok().with_data(feedback).into())
+}
+
 #[instrument(level = "debug", skip_all, err)]
 async fn create_question_handler(
   state: Data<AppState>,
@@ -183,7 +303,8 @@ async fn create_question_handler(
   payload: Json<CreateChatMessageParams>,
   uuid: UserUuid,
 ) -> actix_web::Result<JsonAppResponse<ChatMessageWithAuthorUuid>> {
-  let (_workspace_id, chat_id) = path.into_inner();
+  let (workspace_id, chat_id) = path.into_inner();
+  ensure_chat_enabled(&state, &workspace_id).await?;
   let params = payload.into_inner();
 
   // When create a question, we will extract the metadata from the question content.
@@ -248,6 +369,8 @@ async fn answer_handler(
   req: HttpRequest,
 ) -> actix_web::Result<JsonAppResponse<ChatMessage>> {
   let (workspace_id, chat_id, message_id) = path.into_inner();
+  ensure_chat_enabled(&state, &workspace_id).await?;
+  consume_chat_answer(&state, &workspace_id).await?;
   let ai_model = ai_model_from_header(&req);
   let message = generate_chat_message_answer(
     workspace_id,
@@ -268,6 +391,8 @@ async fn answer_stream_handler(
   req: HttpRequest,
 ) -> actix_web::Result<HttpResponse> {
   let (workspace_id, chat_id, question_id) = path.into_inner();
+  ensure_chat_enabled(&state, &workspace_id).await?;
+  consume_chat_answer(&state, &workspace_id).await?;
   let (content, metadata) =
     chat::chat_ops::select_chat_message_content(&state.pg_pool, question_id).await?;
   let rag_ids = chat::chat_ops::select_chat_rag_ids(&state.pg_pool, &chat_id).await?;
@@ -313,6 +438,8 @@ async fn answer_stream_v2_handler(
   req: HttpRequest,
 ) -> actix_web::Result<HttpResponse> {
   let (workspace_id, chat_id, question_id) = path.into_inner();
+  ensure_chat_enabled(&state, &workspace_id).await?;
+  consume_chat_answer(&state, &workspace_id).await?;
   let (content, metadata) =
     chat::chat_ops::select_chat_message_content(&state.pg_pool, question_id).await?;
   let rag_ids = chat::chat_ops::select_chat_rag_ids(&state.pg_pool, &chat_id).await?;
@@ -367,6 +494,8 @@ async fn answer_stream_v3_handler(
   req: HttpRequest,
 ) -> actix_web::Result<HttpResponse> {
   let (workspace_id, _) = path.into_inner();
+  ensure_chat_enabled(&state, &workspace_id).await?;
+  consume_chat_answer(&state, &workspace_id).await?;
   let payload = payload.into_inner();
   let (content, metadata) =
     chat::chat_ops::select_chat_message_content(&state.pg_pool, payload.question_id).await?;
@@ -447,6 +576,28 @@ async fn get_chat_message_handler(
   Ok(AppResponse::Ok().with_data(messages).into())
 }
 
+#[instrument(level = "debug", skip_all, err)]
+async fn search_chat_messages_handler(
+  path: web::Path<(String, String)>,
+  query: web::Query<SearchChatMessageParams>,
+  state: Data<AppState>,
+) -> actix_web::Result<JsonAppResponse<Vec<ChatMessageSearchResult>>> {
+  let (_workspace_id, chat_id) = path.into_inner();
+  let results = search_chat_messages(&state.pg_pool, &chat_id, query.into_inner()).await?;
+  Ok(AppResponse::Ok().with_data(results).into())
+}
+
+#[instrument(level = "debug", skip_all, err)]
+async fn get_chat_threads_handler(
+  path: web::Path<(String, String)>,
+  query: web::Query<GetChatThreadParams>,
+  state: Data<AppState>,
+) -> actix_web::Result<JsonAppResponse<RepeatedChatThread>> {
+  let (_workspace_id, chat_id) = path.into_inner();
+  let threads = get_chat_threads(&state.pg_pool, &chat_id, query.into_inner()).await?;
+  Ok(AppResponse::Ok().with_data(threads).into())
+}
+
 #[instrument(level = "debug", skip_all, err)]
 async fn get_chat_question_message_handler(
   path: web::Path<(String, String)>,
@@ -615,3 +766,14 @@ where
 struct FindQuestionParams {
   answer_message_id: i64,
 }
+
+async fn ensure_chat_enabled(state: &AppState, workspace_id: &str) -> Result<(), AppError> {
+  let workspace_id = Uuid::parse_str(workspace_id)?;
+  ensure_ai_feature_enabled(&state.pg_pool, &workspace_id, AIFeature::Chat).await
+}
+
+/// Counts the answer against the AI responses of the plan of the workspace.
+async fn consume_chat_answer(state: &AppState, workspace_id: &str) -> Result<(), AppError> {
+  let workspace_id = Uuid::parse_str(workspace_id)?;
+  consume_ai_response(&state.pg_pool, &state.config.billing, &workspace_id).await
+}
@@ -11,13 +11,22 @@ use database::file::BucketClient;
 use crate::biz::workspace::ops::{create_empty_workspace, create_upload_task, num_pending_task};
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
+use database::pg_row::AFImportTask;
 use database::user::select_name_and_email_from_uuid;
-use database::workspace::select_import_task_by_state;
-use database_entity::dto::{CreateImportTask, CreateImportTaskResponse};
+use database::workspace::{
+  cancel_import_task, select_import_page_mapping, select_import_task, select_import_task_by_state,
+};
+use database_entity::dto::{
+  CreateImportTask, CreateImportTaskResponse, UploadImportTask, UploadImportTaskResponse,
+  IMPORT_START_AFTER_KEY, IMPORT_SUMMARY_KEY,
+};
+use database_entity::file_dto::{
+  CompleteUploadRequest, CompletedPartRequest, CreateUploadRequest, UploadPartData,
+};
 use futures_util::StreamExt;
 use infra::env_util::get_env_var;
 use serde_json::json;
-use shared_entity::dto::import_dto::{ImportTaskDetail, UserImportTask};
+use shared_entity::dto::import_dto::{ImportPageMapping, ImportTaskDetail, UserImportTask};
 use shared_entity::response::{AppResponse, JsonAppResponse};
 use std::env::temp_dir;
 use std::path::PathBuf;
@@ -35,6 +44,13 @@ pub fn data_import_scope() -> Scope {
         .route(web::get().to(get_import_detail_handler)),
     )
     .service(web::resource("/create").route(web::post().to(create_import_handler)))
+    .service(web::resource("/upload").route(web::post().to(upload_import_handler)))
+    .service(web::resource("/{task_id}").route(web::get().to(get_import_task_handler)))
+    .service(web::resource("/{task_id}/cancel").route(web::post().to(cancel_import_handler)))
+    .service(
+      web::resource("/{task_id}/page-mapping")
+        .route(web::get().to(get_import_page_mapping_handler)),
+    )
 }
 
 #[instrument(level = "debug", skip_all)]
@@ -53,10 +69,24 @@ async fn create_import_handler(
   // Generate presigned url with 10 minutes expiration
   let presigned_url = state
     .bucket_client
-    .gen_presigned_url(&s3_key, params.content_length, 600)
+    .gen_presigned_put_url(&s3_key, "application/zip", params.content_length, 600)
     .await?;
   trace!("[Import] Presigned url: {}", presigned_url);
 
+  // The other parts of a split export are uploaded under keys derived from the first one
+  let mut part_s3_keys = Vec::with_capacity(params.part_content_lengths.len());
+  let mut part_presigned_urls = Vec::with_capacity(params.part_content_lengths.len());
+  for (i, content_length) in params.part_content_lengths.iter().enumerate() {
+    let part_s3_key = format!("{}_part{}", s3_key, i + 2);
+    let url = state
+      .bucket_client
+      .gen_presigned_put_url(&part_s3_key, "application/zip", *content_length, 600)
+      .await?;
+    part_s3_keys.push(part_s3_key);
+    part_presigned_urls.push(url);
+  }
+  let file_size = params.content_length + params.part_content_lengths.iter().sum::<u64>();
+
   let (user_name, user_email) = select_name_and_email_from_uuid(&state.pg_pool, &user_uuid).await?;
   let host = get_host_from_request(&req);
   let workspace = create_empty_workspace(
@@ -83,17 +113,21 @@ async fn create_import_handler(
          "user_email": user_email,
          "task_id": task_id.to_string(),
          "workspace_id": workspace_id,
-         "file_size":params.content_length,
+         "file_size": file_size,
          "created_at": timestamp,
          "s3_key": s3_key,
+         "part_s3_keys": part_s3_keys,
          "host": host,
          "workspace_name": &params.workspace_name,
+         "start_after": params.start_after,
+         "source": params.source,
       }
   });
 
   let data = CreateImportTaskResponse {
     task_id: task_id.to_string(),
     presigned_url: presigned_url.clone(),
+    part_presigned_urls,
   };
 
   create_upload_task(
@@ -104,6 +138,7 @@ async fn create_import_handler(
     &workspace_id,
     0,
     Some(presigned_url),
+    params.start_after,
     &state.redis_connection_manager,
     &state.pg_pool,
   )
@@ -112,6 +147,198 @@ async fn create_import_handler(
   Ok(AppResponse::Ok().with_data(data).into())
 }
 
+/// Size of the parts sent to the storage, S3 rejects the parts smaller than 5MB but the last one.
+const IMPORT_UPLOAD_PART_SIZE: usize = 8 * 1024 * 1024;
+
+/// Imports the file sent as the first field of the multipart form. The file is streamed to the
+/// storage under a key of the task, the workspace and the task are only created once the upload
+/// is complete.
+#[instrument(level = "debug", skip_all)]
+async fn upload_import_handler(
+  user_uuid: UserUuid,
+  state: Data<AppState>,
+  query: web::Query<UploadImportTask>,
+  mut payload: Multipart,
+  req: HttpRequest,
+) -> actix_web::Result<JsonAppResponse<UploadImportTaskResponse>> {
+  let params = query.into_inner();
+  params.validate().map_err(AppError::from)?;
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  check_maximum_task(&state, uid).await?;
+
+  let task_id = Uuid::new_v4();
+  let s3_key = format!("import_upload_{}", task_id);
+  let max_file_size = get_env_var("APPFLOWY_IMPORT_UPLOAD_MAX_FILE_SIZE_BYTES", "3221225472")
+    .parse::<usize>()
+    .unwrap_or(3 * 1024 * 1024 * 1024);
+  let file_size = stream_upload_to_storage(&state, &mut payload, &s3_key, max_file_size).await?;
+
+  let result = async {
+    let (user_name, user_email) =
+      select_name_and_email_from_uuid(&state.pg_pool, &user_uuid).await?;
+    let host = get_host_from_request(&req);
+    let workspace = create_empty_workspace(
+      &state.pg_pool,
+      state.workspace_access_control.clone(),
+      &state.collab_access_control_storage,
+      &user_uuid,
+      uid,
+      &params.workspace_name,
+    )
+    .await?;
+    let workspace_id = workspace.workspace_id.to_string();
+    info!(
+      "User:{} uploaded import data:{} to new workspace:{}, name:{}",
+      uid, file_size, workspace_id, params.workspace_name,
+    );
+
+    let task = json!({
+        "notion": {
+           "uid": uid,
+           "user_name": user_name,
+           "user_email": user_email,
+           "task_id": task_id.to_string(),
+           "workspace_id": workspace_id,
+           "file_size": file_size,
+           "created_at": chrono::Utc::now().timestamp(),
+           "s3_key": s3_key,
+           "host": host,
+           "workspace_name": &params.workspace_name,
+           "source": params.source,
+        }
+    });
+    create_upload_task(
+      uid,
+      task_id,
+      task,
+      &host,
+      &workspace_id,
+      file_size,
+      None,
+      None,
+      &state.redis_connection_manager,
+      &state.pg_pool,
+    )
+    .await?;
+    Ok::<_, AppError>(workspace_id)
+  }
+  .await;
+
+  match result {
+    Ok(workspace_id) => Ok(
+      AppResponse::Ok()
+        .with_data(UploadImportTaskResponse {
+          task_id: task_id.to_string(),
+          workspace_id,
+          file_size: file_size as u64,
+        })
+        .into(),
+    ),
+    Err(err) => {
+      if let Err(delete_err) = state.bucket_client.delete_blob(&s3_key).await {
+        error!(
+          "Failed to delete the uploaded import file:{}, error: {}",
+          s3_key, delete_err
+        );
+      }
+      Err(err.into())
+    },
+  }
+}
+
+/// Streams the first field of the form to the storage with a multipart upload, returns the size
+/// of the file. An upload that is not completed is left to the lifecycle rules of the storage.
+async fn stream_upload_to_storage(
+  state: &AppState,
+  payload: &mut Multipart,
+  object_key: &str,
+  max_file_size: usize,
+) -> Result<usize, AppError> {
+  let mut field = payload
+    .next()
+    .await
+    .ok_or_else(|| AppError::InvalidRequest("The file to import is missing".to_string()))?
+    .map_err(|err| AppError::InvalidRequest(format!("Invalid multipart form: {}", err)))?;
+
+  let upload = state
+    .bucket_client
+    .create_upload(
+      object_key,
+      CreateUploadRequest {
+        file_id: object_key.to_string(),
+        parent_dir: "".to_string(),
+        content_type: "application/zip".to_string(),
+        file_size: None,
+      },
+    )
+    .await?;
+
+  let mut parts = vec![];
+  let mut buf = Vec::with_capacity(IMPORT_UPLOAD_PART_SIZE);
+  let mut file_size = 0;
+  loop {
+    let chunk = field
+      .next()
+      .await
+      .transpose()
+      .map_err(|err| AppError::InvalidRequest(format!("Failed to read the file: {}", err)))?;
+    let is_end = chunk.is_none();
+    if let Some(chunk) = chunk {
+      file_size += chunk.len();
+      if file_size > max_file_size {
+        return Err(AppError::PayloadTooLarge(format!(
+          "The file to import exceeds {} bytes",
+          max_file_size
+        )));
+      }
+      buf.extend_from_slice(&chunk);
+    }
+
+    if buf.len() >= IMPORT_UPLOAD_PART_SIZE || (is_end && !buf.is_empty()) {
+      let part_number = parts.len() as i32 + 1;
+      let resp = state
+        .bucket_client
+        .upload_part(
+          object_key,
+          UploadPartData {
+            file_id: object_key.to_string(),
+            upload_id: upload.upload_id.clone(),
+            part_number,
+            body: std::mem::replace(&mut buf, Vec::with_capacity(IMPORT_UPLOAD_PART_SIZE)),
+          },
+        )
+        .await?;
+      parts.push(CompletedPartRequest {
+        e_tag: resp.e_tag,
+        part_number: resp.part_num,
+      });
+    }
+    if is_end {
+      break;
+    }
+  }
+
+  if parts.is_empty() {
+    return Err(AppError::InvalidRequest(
+      "The file to import is empty".to_string(),
+    ));
+  }
+  state
+    .bucket_client
+    .complete_upload(
+      object_key,
+      CompleteUploadRequest {
+        file_id: object_key.to_string(),
+        parent_dir: "".to_string(),
+        upload_id: upload.upload_id,
+        parts,
+      },
+    )
+    .await?;
+  trace!("[Import] uploaded {} bytes to {}", file_size, object_key);
+  Ok(file_size)
+}
+
 async fn get_import_detail_handler(
   user_uuid: UserUuid,
   state: Data<AppState>,
@@ -122,12 +349,7 @@ async fn get_import_detail_handler(
     .map(|tasks| {
       tasks
         .into_iter()
-        .map(|task| ImportTaskDetail {
-          task_id: task.task_id.to_string(),
-          file_size: task.file_size as u64,
-          created_at: task.created_at.timestamp(),
-          status: task.status,
-        })
+        .map(import_task_detail)
         .collect::<Vec<_>>()
     })?;
 
@@ -141,6 +363,84 @@ async fn get_import_detail_handler(
   )
 }
 
+/// Returns the status of an import task of the user, with the summary of the import once the task
+/// is done.
+async fn get_import_task_handler(
+  user_uuid: UserUuid,
+  task_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> actix_web::Result<JsonAppResponse<ImportTaskDetail>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let task_id = task_id.into_inner();
+  let task = select_import_task(&state.pg_pool, &task_id).await?;
+  // Don't tell the other users whether the task exists
+  if task.created_by != uid {
+    return Err(AppError::RecordNotFound(format!("import task {} not found", task_id)).into());
+  }
+  Ok(AppResponse::Ok().with_data(import_task_detail(task)).into())
+}
+
+/// The views created for the pages of the imported file, to fix the links to the pages that were
+/// not rewritten during the import.
+async fn get_import_page_mapping_handler(
+  user_uuid: UserUuid,
+  task_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> actix_web::Result<JsonAppResponse<Vec<ImportPageMapping>>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let task_id = task_id.into_inner();
+  let task = select_import_task(&state.pg_pool, &task_id).await?;
+  if task.created_by != uid {
+    return Err(AppError::RecordNotFound(format!("import task {} not found", task_id)).into());
+  }
+  let mapping = select_import_page_mapping(&state.pg_pool, &task_id)
+    .await?
+    .into_iter()
+    .map(|(source_id, view_id)| ImportPageMapping { source_id, view_id })
+    .collect();
+  Ok(AppResponse::Ok().with_data(mapping).into())
+}
+
+fn import_task_detail(task: AFImportTask) -> ImportTaskDetail {
+  ImportTaskDetail {
+    task_id: task.task_id.to_string(),
+    file_size: task.file_size as u64,
+    created_at: task.created_at.timestamp(),
+    status: task.status,
+    summary: task
+      .metadata
+      .get(IMPORT_SUMMARY_KEY)
+      .and_then(|summary| serde_json::from_value(summary.clone()).ok()),
+    start_after: task
+      .metadata
+      .get(IMPORT_START_AFTER_KEY)
+      .and_then(|start_after| start_after.as_i64()),
+  }
+}
+
+/// Cancels an import task that is waiting in the queue, either for its start time or for the
+/// file to be uploaded. The worker removes the workspace created for the import when it picks the
+/// task up.
+async fn cancel_import_handler(
+  user_uuid: UserUuid,
+  task_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> actix_web::Result<JsonAppResponse<()>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let task_id = task_id.into_inner();
+  if !cancel_import_task(&state.pg_pool, &task_id, uid).await? {
+    return Err(
+      AppError::InvalidRequest(format!(
+        "import task {} can't be cancelled, it is not waiting to be imported",
+        task_id
+      ))
+      .into(),
+    );
+  }
+  info!("User:{} cancelled import task:{}", uid, task_id);
+  Ok(AppResponse::Ok().into())
+}
+
 async fn import_data_handler(
   user_uuid: UserUuid,
   state: Data<AppState>,
@@ -247,6 +547,7 @@ async fn import_data_handler(
     &workspace_id,
     file.size,
     None,
+    None,
     &state.redis_connection_manager,
     &state.pg_pool,
   )
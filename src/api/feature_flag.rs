@@ -0,0 +1,90 @@
+use actix_web::web::{Data, Json};
+use actix_web::{web, Scope};
+use app_error::AppError;
+use authentication::jwt::Authorization;
+use shared_entity::dto::feature_flag_dto::{FeatureFlag, UpsertFeatureFlagParams};
+use shared_entity::response::{AppResponse, AppResponseError, JsonAppResponse};
+
+use crate::state::AppState;
+
+pub fn feature_flag_scope() -> Scope {
+  web::scope("/api/admin/feature-flags")
+    .service(web::resource("").route(web::get().to(list_feature_flags_handler)))
+    .service(
+      web::resource("/{name}")
+        .route(web::put().to(upsert_feature_flag_handler))
+        .route(web::delete().to(delete_feature_flag_handler)),
+    )
+}
+
+#[utoipa::path(
+  get,
+  path = "/api/admin/feature-flags",
+  tag = "admin",
+  responses(
+    (status = 200, description = "All feature flags", body = Vec<FeatureFlag>),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+async fn list_feature_flags_handler(
+  auth: Authorization,
+  state: Data<AppState>,
+) -> actix_web::Result<JsonAppResponse<Vec<FeatureFlag>>> {
+  if !auth.is_admin() {
+    return Err(AppError::NotEnoughPermissions.into());
+  }
+  let flags = state.feature_flags.list().await?;
+  Ok(AppResponse::Ok().with_data(flags).into())
+}
+
+#[utoipa::path(
+  put,
+  path = "/api/admin/feature-flags/{name}",
+  tag = "admin",
+  params(("name" = String, Path, description = "Name of the feature flag")),
+  request_body = UpsertFeatureFlagParams,
+  responses(
+    (status = 200, description = "The created or updated feature flag", body = FeatureFlag),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+async fn upsert_feature_flag_handler(
+  auth: Authorization,
+  path: web::Path<String>,
+  payload: Json<UpsertFeatureFlagParams>,
+  state: Data<AppState>,
+) -> actix_web::Result<JsonAppResponse<FeatureFlag>> {
+  if !auth.is_admin() {
+    return Err(AppError::NotEnoughPermissions.into());
+  }
+  let flag = state
+    .feature_flags
+    .upsert(&path.into_inner(), payload.into_inner())
+    .await?;
+  Ok(AppResponse::Ok().with_data(flag).into())
+}
+
+#[utoipa::path(
+  delete,
+  path = "/api/admin/feature-flags/{name}",
+  tag = "admin",
+  params(("name" = String, Path, description = "Name of the feature flag")),
+  responses(
+    (status = 200, description = "Feature flag deleted"),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+async fn delete_feature_flag_handler(
+  auth: Authorization,
+  path: web::Path<String>,
+  state: Data<AppState>,
+) -> actix_web::Result<JsonAppResponse<()>> {
+  if !auth.is_admin() {
+    return Err(AppError::NotEnoughPermissions.into());
+  }
+  state.feature_flags.delete(&path.into_inner()).await?;
+  Ok(AppResponse::Ok().into())
+}
@@ -11,16 +11,21 @@ use actix_web::{
 };
 use actix_web::{HttpResponse, Result};
 use app_error::AppError;
-use authentication::jwt::UserUuid;
+use authentication::jwt::{Authorization, UserUuid};
 use chrono::DateTime;
-use database::file::BlobKey;
+use database::file::fs_client_impl::{LocalFsBucketClientImpl, PresignedMethod};
+use database::file::{BlobKey, BlobStorageClient};
 use database::resource_usage::{get_all_workspace_blob_metadata, get_workspace_usage_size};
 use database_entity::file_dto::{
   CompleteUploadRequest, CreateUploadRequest, CreateUploadResponse, UploadPartData,
   UploadPartResponse,
 };
 
+use crate::biz::billing::ops::{check_storage_limit, refresh_workspace_read_only_after_change};
 use crate::biz::data_import::LimitedPayload;
+use crate::biz::workspace::files::{notify_upload_quarantined, upload_blob_status};
+use crate::biz::workspace::storage_audit::audit_workspace_storage_prefix;
+use crate::biz::workspace::storage_location::{get_workspace_storage, relocate_workspace_storage};
 use crate::state::AppState;
 use anyhow::anyhow;
 use appflowy_ai_client::client::AppFlowyAIClient;
@@ -28,18 +33,28 @@ use aws_sdk_s3::primitives::ByteStream;
 use collab_importer::util::FileId;
 use database::pg_row::{AFBlobSource, AFBlobStatus};
 use serde::Deserialize;
-use shared_entity::dto::file_dto::PutFileResponse;
+use shared_entity::dto::admin_dto::{UpdateWorkspaceStorageParams, WorkspaceStorageInfo};
+use shared_entity::dto::file_dto::{
+  PutFileResponse, StoragePrefixAuditQuery, StoragePrefixAuditReport,
+};
 use shared_entity::dto::workspace_dto::{BlobMetadata, RepeatedBlobMetaData, WorkspaceSpaceUsage};
 use shared_entity::response::{AppResponse, AppResponseError, JsonAppResponse};
 use sqlx::types::Uuid;
 use std::pin::Pin;
 use tokio::io::{AsyncRead, AsyncReadExt};
 use tokio_stream::StreamExt;
-use tokio_util::io::StreamReader;
+use tokio_util::io::{ReaderStream, StreamReader};
 use tracing::{error, event, info, instrument, trace};
 
 pub fn file_storage_scope() -> Scope {
   web::scope("/api/file_storage")
+    .service(
+      // Presigned urls of the local file storage backend. The signed token grants the access, so
+      // the requests are not authenticated.
+      web::resource("/local/{token}")
+        .route(web::get().to(get_local_presigned_blob_handler))
+        .route(web::put().to(put_local_presigned_blob_handler)),
+    )
     .service(
       // Deprecated, use put_blob_handler_v1 instead
       web::resource("/{workspace_id}/blob/{file_id}")
@@ -86,6 +101,19 @@ pub fn file_storage_scope() -> Scope {
     )
 }
 
+pub fn file_storage_admin_scope() -> Scope {
+  web::scope("/api/admin/file_storage")
+    .service(
+      web::resource("/{workspace_id}/prefix_audit")
+        .route(web::get().to(audit_storage_prefix_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/storage")
+        .route(web::get().to(get_workspace_storage_handler))
+        .route(web::put().to(relocate_workspace_storage_handler)),
+    )
+}
+
 #[instrument(skip_all, err)]
 async fn create_upload(
   user_uuid: UserUuid,
@@ -103,10 +131,14 @@ async fn create_upload(
   }
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   let workspace_id = workspace_id.into_inner();
-  state
-    .workspace_access_control
-    .enforce_action(&uid, &workspace_id.to_string(), Action::Write)
-    .await?;
+  upload_blob_status(state.workspace_access_control.as_ref(), &uid, &workspace_id).await?;
+  check_storage_limit(
+    &state.pg_pool,
+    &state.config.billing,
+    &workspace_id,
+    req.file_size.unwrap_or(0),
+  )
+  .await?;
 
   let key = BlobPathV1 {
     workspace_id,
@@ -150,10 +182,7 @@ async fn upload_part_handler(
   );
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   let workspace_id = path_params.workspace_id;
-  state
-    .workspace_access_control
-    .enforce_action(&uid, &workspace_id.to_string(), Action::Write)
-    .await?;
+  upload_blob_status(state.workspace_access_control.as_ref(), &uid, &workspace_id).await?;
 
   let content_length = content_length.into_inner().into_inner();
   let mut content = Vec::with_capacity(content_length);
@@ -201,21 +230,31 @@ async fn complete_upload_handler(
   let req = req.into_inner();
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   let workspace_id = workspace_id.into_inner();
-  state
-    .workspace_access_control
-    .enforce_action(&uid, &workspace_id.to_string(), Action::Write)
-    .await?;
+  let status =
+    upload_blob_status(state.workspace_access_control.as_ref(), &uid, &workspace_id).await?;
 
   let key = BlobPathV1 {
     workspace_id,
     parent_dir: req.parent_dir.clone(),
     file_id: req.file_id.clone(),
   };
-  state
+  let blob_metadata_key = key.blob_metadata_key();
+  let file_size = state
     .bucket_storage
-    .complete_upload(key, req)
+    .complete_upload(key, req, uid, status.clone())
     .await
     .map_err(AppResponseError::from)?;
+  if let (AFBlobStatus::Quarantined, Some(file_size)) = (status, file_size) {
+    notify_upload_quarantined(
+      state.pg_pool.clone(),
+      state.mailer.clone(),
+      state.config.appflowy_web_url.clone(),
+      workspace_id,
+      *user_uuid,
+      blob_metadata_key,
+      file_size,
+    );
+  }
 
   Ok(AppResponse::Ok().into())
 }
@@ -232,12 +271,17 @@ async fn put_blob_handler(
   let path = path.into_inner();
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   let workspace_id = path.workspace_id;
-  state
-    .workspace_access_control
-    .enforce_action(&uid, &workspace_id.to_string(), Action::Write)
-    .await?;
+  let status =
+    upload_blob_status(state.workspace_access_control.as_ref(), &uid, &workspace_id).await?;
 
   let content_length = content_length.into_inner().into_inner();
+  check_storage_limit(
+    &state.pg_pool,
+    &state.config.billing,
+    &workspace_id,
+    content_length as u64,
+  )
+  .await?;
   let content_type = content_type.into_inner().to_string();
   let content = {
     let mut payload_reader = payload_to_async_read(payload);
@@ -288,11 +332,30 @@ async fn put_blob_handler(
 
   let file_size = content.len();
   let file_stream = ByteStream::from(content);
+  let blob_metadata_key = path.blob_metadata_key();
   state
     .bucket_storage
-    .put_blob_with_content_type(path, file_stream, content_type, file_size)
+    .put_blob_with_content_type(
+      path,
+      file_stream,
+      content_type,
+      file_size,
+      uid,
+      status.clone(),
+    )
     .await
     .map_err(AppResponseError::from)?;
+  if status == AFBlobStatus::Quarantined {
+    notify_upload_quarantined(
+      state.pg_pool.clone(),
+      state.mailer.clone(),
+      state.config.appflowy_web_url.clone(),
+      workspace_id,
+      *user_uuid,
+      blob_metadata_key,
+      file_size,
+    );
+  }
 
   Ok(AppResponse::Ok().into())
 }
@@ -315,6 +378,13 @@ async fn delete_blob_handler(
     .delete_blob(path)
     .await
     .map_err(AppResponseError::from)?;
+  refresh_workspace_read_only_after_change(
+    &state.pg_pool,
+    &state.config.billing,
+    &state.workspace_read_only_cache,
+    &workspace_id,
+  )
+  .await;
 
   Ok(AppResponse::Ok().into())
 }
@@ -347,6 +417,13 @@ async fn delete_blob_v1_handler(
     .delete_blob(path)
     .await
     .map_err(AppResponseError::from)?;
+  refresh_workspace_read_only_after_change(
+    &state.pg_pool,
+    &state.config.billing,
+    &state.workspace_read_only_cache,
+    &workspace_id,
+  )
+  .await;
 
   Ok(AppResponse::Ok().into())
 }
@@ -356,6 +433,8 @@ async fn get_blob_by_object_key(
   key: &impl BlobKey,
   req: HttpRequest,
 ) -> Result<HttpResponse<BoxBody>> {
+  key.scoped_object_key()?;
+
   // Get the metadata
   let result = state
     .bucket_storage
@@ -371,6 +450,10 @@ async fn get_blob_by_object_key(
   }
 
   let metadata = result.unwrap();
+  // Quarantined blobs can't be downloaded until a member of the workspace approves them.
+  if AFBlobStatus::from(metadata.status) == AFBlobStatus::Quarantined {
+    return Ok(HttpResponse::Forbidden().finish());
+  }
   let source = AFBlobSource::from(metadata.source);
   trace!("blob metadata: {:?}", metadata);
   match source {
@@ -555,12 +638,21 @@ async fn put_blob_handler_v1(
 ) -> Result<JsonAppResponse<PutFileResponse>> {
   let path = path.into_inner();
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
-  state
-    .workspace_access_control
-    .enforce_action(&uid, &path.workspace_id.to_string(), Action::Write)
-    .await?;
+  let status = upload_blob_status(
+    state.workspace_access_control.as_ref(),
+    &uid,
+    &path.workspace_id,
+  )
+  .await?;
 
   let content_length = content_length.into_inner().into_inner();
+  check_storage_limit(
+    &state.pg_pool,
+    &state.config.billing,
+    &path.workspace_id,
+    content_length as u64,
+  )
+  .await?;
   let content_type = content_type.into_inner().to_string();
 
   let mut content = Vec::with_capacity(content_length);
@@ -597,16 +689,32 @@ async fn put_blob_handler_v1(
   );
 
   let file_stream = ByteStream::from(content);
+  let workspace_id = path.workspace_id;
+  let key = BlobPathV1::from((path, file_id));
+  let blob_metadata_key = key.blob_metadata_key();
   state
     .bucket_storage
     .put_blob_with_content_type(
-      BlobPathV1::from((path, file_id)),
+      key,
       file_stream,
       content_type,
       content_length,
+      uid,
+      status.clone(),
     )
     .await
     .map_err(AppResponseError::from)?;
+  if status == AFBlobStatus::Quarantined {
+    notify_upload_quarantined(
+      state.pg_pool.clone(),
+      state.mailer.clone(),
+      state.config.appflowy_web_url.clone(),
+      workspace_id,
+      *user_uuid,
+      blob_metadata_key,
+      content_length,
+    );
+  }
   Ok(AppResponse::Ok().with_data(resp_data).into())
 }
 
@@ -635,6 +743,143 @@ impl BlobKey for BlobPathV0 {
   }
 }
 
+/// Reports the blobs of the workspace whose keys escape the workspace prefix and the documents
+/// that reference the blobs of other workspaces. Only for the administrators of the instance.
+async fn audit_storage_prefix_handler(
+  auth: Authorization,
+  workspace_id: web::Path<Uuid>,
+  query: web::Query<StoragePrefixAuditQuery>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<StoragePrefixAuditReport>> {
+  if !auth.is_admin() {
+    return Err(AppError::NotEnoughPermissions.into());
+  }
+  let report =
+    audit_workspace_storage_prefix(&state.pg_pool, &workspace_id, query.into_inner()).await?;
+  Ok(AppResponse::Ok().with_data(report).into())
+}
+
+#[utoipa::path(
+  get,
+  path = "/api/admin/file_storage/{workspace_id}/storage",
+  tag = "admin",
+  params(("workspace_id" = Uuid, Path, description = "Id of the workspace")),
+  responses(
+    (status = 200, description = "The storage of the workspace and the state of its relocation", body = WorkspaceStorageInfo),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+async fn get_workspace_storage_handler(
+  auth: Authorization,
+  workspace_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<WorkspaceStorageInfo>> {
+  if !auth.is_admin() {
+    return Err(AppError::NotEnoughPermissions.into());
+  }
+  let info = get_workspace_storage(
+    &state.pg_pool,
+    state.workspace_storage_router.as_deref(),
+    &workspace_id,
+  )
+  .await?;
+  Ok(AppResponse::Ok().with_data(info).into())
+}
+
+#[utoipa::path(
+  put,
+  path = "/api/admin/file_storage/{workspace_id}/storage",
+  tag = "admin",
+  params(("workspace_id" = Uuid, Path, description = "Id of the workspace")),
+  request_body = UpdateWorkspaceStorageParams,
+  responses(
+    (status = 200, description = "The relocation was queued, the objects are moved in the background", body = WorkspaceStorageInfo),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+async fn relocate_workspace_storage_handler(
+  auth: Authorization,
+  workspace_id: web::Path<Uuid>,
+  params: Json<UpdateWorkspaceStorageParams>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<WorkspaceStorageInfo>> {
+  if !auth.is_admin() {
+    return Err(AppError::NotEnoughPermissions.into());
+  }
+  let info = relocate_workspace_storage(
+    &state.pg_pool,
+    &state.config,
+    state.workspace_storage_router.as_deref(),
+    auth.uuid()?,
+    &workspace_id,
+    params.into_inner(),
+  )
+  .await?;
+  Ok(AppResponse::Ok().with_data(info).into())
+}
+
+fn local_fs_client(state: &AppState) -> Result<&LocalFsBucketClientImpl, AppError> {
+  match state.bucket_client.default_client() {
+    BlobStorageClient::LocalFs(client) => Ok(client),
+    _ => Err(AppError::RecordNotFound(
+      "local file storage is not enabled".to_string(),
+    )),
+  }
+}
+
+async fn get_local_presigned_blob_handler(
+  state: Data<AppState>,
+  token: web::Path<String>,
+) -> Result<HttpResponse<BoxBody>> {
+  let client = local_fs_client(&state)?;
+  let token = client.verify_presigned_token(&token, PresignedMethod::Get)?;
+  let (file, len, content_type) = client.open_blob(&token.object_key).await?;
+  Ok(
+    HttpResponse::Ok()
+      .append_header((CONTENT_LENGTH, len))
+      .append_header((
+        CONTENT_TYPE,
+        content_type.unwrap_or_else(|| "application/octet-stream".to_string()),
+      ))
+      .streaming(ReaderStream::new(file)),
+  )
+}
+
+async fn put_local_presigned_blob_handler(
+  state: Data<AppState>,
+  token: web::Path<String>,
+  content_length: web::Header<ContentLength>,
+  payload: Payload,
+) -> Result<HttpResponse<BoxBody>> {
+  let client = local_fs_client(&state)?;
+  let token = client.verify_presigned_token(&token, PresignedMethod::Put)?;
+  let content_length = content_length.into_inner().into_inner();
+  if let Some(expected) = token.content_length {
+    if content_length as u64 != expected {
+      return Err(
+        AppError::InvalidRequest(format!(
+          "content length {} does not match the presigned length {}",
+          content_length, expected
+        ))
+        .into(),
+      );
+    }
+  }
+
+  let stream = LimitedPayload::new(payload, content_length)
+    .map(|chunk| chunk.map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err)));
+  let content_type = token
+    .content_type
+    .as_deref()
+    .unwrap_or("application/octet-stream");
+  client
+    .put_blob_from_reader(&token.object_key, StreamReader::new(stream), content_type)
+    .await?;
+  Ok(HttpResponse::Ok().finish())
+}
+
 /// Use [BlobPathV1] when put/get object by multiple upload parts
 #[derive(Deserialize, Debug)]
 pub struct BlobPathV1 {
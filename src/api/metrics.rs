@@ -153,6 +153,9 @@ pub struct PublishedCollabMetrics {
   success_read_published_collab_count: Gauge,
   fallback_read_published_collab_count: Gauge,
   failure_read_published_collab_count: Gauge,
+  abuse_report_count: Gauge,
+  moderation_rejected_count: Gauge,
+  moderation_unpublished_count: Gauge,
 }
 
 impl PublishedCollabMetrics {
@@ -164,6 +167,9 @@ impl PublishedCollabMetrics {
       success_read_published_collab_count: Default::default(),
       fallback_read_published_collab_count: Default::default(),
       failure_read_published_collab_count: Default::default(),
+      abuse_report_count: Default::default(),
+      moderation_rejected_count: Default::default(),
+      moderation_unpublished_count: Default::default(),
     }
   }
 
@@ -200,6 +206,21 @@ impl PublishedCollabMetrics {
       "failed to read published collab from primary store",
       metrics.fallback_read_published_collab_count.clone(),
     );
+    published_collab_registry.register(
+      "abuse_report_count",
+      "abuse reports received on published views",
+      metrics.abuse_report_count.clone(),
+    );
+    published_collab_registry.register(
+      "moderation_rejected_count",
+      "published collabs rejected by the moderation denylists",
+      metrics.moderation_rejected_count.clone(),
+    );
+    published_collab_registry.register(
+      "moderation_unpublished_count",
+      "published views unpublished by an administrator after a report",
+      metrics.moderation_unpublished_count.clone(),
+    );
 
     metrics
   }
@@ -227,6 +248,18 @@ impl PublishedCollabMetrics {
   pub fn incr_failure_read_count(&self, count: i64) {
     self.failure_read_published_collab_count.inc_by(count);
   }
+
+  pub fn incr_abuse_report_count(&self, count: i64) {
+    self.abuse_report_count.inc_by(count);
+  }
+
+  pub fn incr_moderation_rejected_count(&self, count: i64) {
+    self.moderation_rejected_count.inc_by(count);
+  }
+
+  pub fn incr_moderation_unpublished_count(&self, count: i64) {
+    self.moderation_unpublished_count.inc_by(count);
+  }
 }
 
 pub struct AppFlowyWebMetrics {
@@ -1,9 +1,16 @@
 pub mod access_request;
+pub mod admin_publish;
+pub mod admin_user;
 pub mod ai;
+pub mod billing;
 pub mod chat;
 pub mod data_import;
+pub mod feature_flag;
 pub mod file_storage;
 pub mod metrics;
+pub mod openapi;
+pub mod organization;
+pub mod public;
 pub mod search;
 pub mod server_info;
 pub mod template;
@@ -0,0 +1,199 @@
+use std::sync::LazyLock;
+
+use actix_web::{web, HttpResponse, Scope};
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+use crate::api::{
+  access_request, admin_publish, admin_user, billing, feature_flag, file_storage, organization,
+  search, server_info, user, workspace,
+};
+
+/// OpenAPI description of the endpoints annotated with `#[utoipa::path]`. Endpoints are added to
+/// `paths` as their handlers are annotated.
+///
+/// Successful responses are wrapped in the standard envelope, `{"code": 0, "message": "", "data":
+/// ...}`, where `data` holds the documented body. Errors are returned as [AppResponseError].
+///
+/// [AppResponseError]: shared_entity::response::AppResponseError
+#[derive(OpenApi)]
+#[openapi(
+  info(
+    title = "AppFlowy Cloud",
+    description = "Successful responses are wrapped in `{\"code\": 0, \"message\": \"\", \"data\": ...}` where `data` holds the documented body. Failed requests return an `AppResponseError`."
+  ),
+  paths(
+    server_info::server_info_handler,
+    user::verify_user_handler,
+    user::get_user_profile_handler,
+    user::get_user_workspace_info_handler,
+    user::get_notification_preferences_handler,
+    user::put_notification_preferences_handler,
+    user::register_push_device_handler,
+    user::unregister_push_device_handler,
+    user::update_user_handler,
+    user::change_email_handler,
+    user::recover_password_handler,
+    user::magic_link_handler,
+    user::delete_user_handler,
+    search::document_search,
+    feature_flag::list_feature_flags_handler,
+    feature_flag::upsert_feature_flag_handler,
+    feature_flag::delete_feature_flag_handler,
+    admin_user::deactivate_users_handler,
+    admin_user::delete_users_handler,
+    admin_user::password_reset_handler,
+    admin_user::export_users_handler,
+    admin_user::list_audit_logs_handler,
+    admin_publish::list_publish_reports_handler,
+    admin_publish::dismiss_publish_report_handler,
+    admin_publish::unpublish_reported_view_handler,
+    file_storage::get_workspace_storage_handler,
+    file_storage::relocate_workspace_storage_handler,
+    access_request::get_access_request_handler,
+    access_request::post_access_request_handler,
+    access_request::post_approve_access_request_handler,
+    organization::create_organization_handler,
+    organization::list_organizations_handler,
+    organization::get_organization_handler,
+    organization::attach_workspace_handler,
+    organization::detach_workspace_handler,
+    organization::list_organization_members_handler,
+    organization::add_organization_members_handler,
+    organization::remove_organization_members_handler,
+    organization::get_organization_usage_handler,
+    organization::update_organization_session_policy_handler,
+    billing::billing_webhook_handler,
+    workspace::get_workspace_billing_handler,
+  ),
+  components(schemas(
+    shared_entity::response::AppResponseError,
+    app_error::ErrorCode,
+    app_error::ErrorCategory,
+    app_error::ErrorDetails,
+    shared_entity::dto::server_info_dto::ServerInfoResponseItem,
+    shared_entity::dto::server_info_dto::SupportedClientFeatures,
+    shared_entity::dto::auth_dto::SignInTokenResponse,
+    shared_entity::dto::auth_dto::UpdateUserParams,
+    shared_entity::dto::auth_dto::ChangeEmailParams,
+    shared_entity::dto::auth_dto::EmailLinkParams,
+    shared_entity::dto::auth_dto::UserMetaData,
+    shared_entity::dto::notification_dto::NotificationPreferences,
+    shared_entity::dto::notification_dto::UpdateNotificationPreferencesParams,
+    shared_entity::dto::notification_dto::DigestFrequency,
+    shared_entity::dto::notification_dto::PushPlatform,
+    shared_entity::dto::notification_dto::RegisterPushDeviceParams,
+    shared_entity::dto::notification_dto::UnregisterPushDeviceParams,
+    database_entity::dto::AFUserProfile,
+    database_entity::dto::AFUserWorkspaceInfo,
+    database_entity::dto::AFWorkspace,
+    shared_entity::dto::search_dto::SearchDocumentResponseItem,
+    shared_entity::dto::search_dto::SearchContentType,
+    shared_entity::dto::feature_flag_dto::FeatureFlag,
+    shared_entity::dto::feature_flag_dto::UpsertFeatureFlagParams,
+    shared_entity::dto::admin_dto::AdminBulkUserParams,
+    shared_entity::dto::admin_dto::AdminBulkUserResult,
+    shared_entity::dto::admin_dto::AdminBulkUserFailure,
+    shared_entity::dto::admin_dto::AdminAuditLog,
+    shared_entity::dto::admin_dto::PublishedViewReport,
+    shared_entity::dto::admin_dto::PublishReportStatus,
+    shared_entity::dto::admin_dto::WorkspaceStorageInfo,
+    shared_entity::dto::admin_dto::WorkspaceStorageLocation,
+    shared_entity::dto::admin_dto::WorkspaceStorageRelocationStatus,
+    shared_entity::dto::admin_dto::WorkspaceStorageSettings,
+    shared_entity::dto::admin_dto::UpdateWorkspaceStorageParams,
+    shared_entity::dto::publish_dto::PublishReportReason,
+    shared_entity::dto::access_request_dto::AccessRequest,
+    shared_entity::dto::access_request_dto::AccessRequestView,
+    shared_entity::dto::workspace_dto::ViewIcon,
+    shared_entity::dto::workspace_dto::IconType,
+    shared_entity::dto::workspace_dto::ViewLayout,
+    database_entity::dto::AccessRequesterInfo,
+    database_entity::dto::AccessRequestStatus,
+    database_entity::dto::AccessRequestMinimal,
+    database_entity::dto::CreateAccessRequestParams,
+    database_entity::dto::ApproveAccessRequestParams,
+    database_entity::dto::AFRole,
+    shared_entity::dto::org_dto::OrgRole,
+    shared_entity::dto::org_dto::CreateOrganizationParams,
+    shared_entity::dto::org_dto::Organization,
+    shared_entity::dto::org_dto::AddOrganizationMembersParams,
+    shared_entity::dto::org_dto::RemoveOrganizationMembersParams,
+    shared_entity::dto::org_dto::OrganizationMember,
+    shared_entity::dto::org_dto::OrganizationMemberWorkspace,
+    shared_entity::dto::org_dto::OrganizationUsage,
+    shared_entity::dto::org_dto::OrganizationWorkspaceUsage,
+    shared_entity::dto::org_dto::UpdateOrganizationSessionPolicyParams,
+    shared_entity::dto::billing_dto::WorkspaceBilling,
+    shared_entity::dto::billing_dto::WorkspacePlanUsage,
+    shared_entity::dto::billing_dto::WorkspacePlanLimits,
+    shared_entity::dto::billing_dto::SubscriptionPlan,
+    shared_entity::dto::billing_dto::SubscriptionStatus,
+    shared_entity::dto::billing_dto::RecurringInterval,
+  )),
+  modifiers(&BearerAuth)
+)]
+pub struct ApiDoc;
+
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+  fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+    let components = openapi.components.get_or_insert_with(Default::default);
+    components.add_security_scheme(
+      "bearer_auth",
+      SecurityScheme::Http(
+        HttpBuilder::new()
+          .scheme(HttpAuthScheme::Bearer)
+          .bearer_format("JWT")
+          .build(),
+      ),
+    );
+  }
+}
+
+static OPENAPI_JSON: LazyLock<String> = LazyLock::new(|| {
+  ApiDoc::openapi()
+    .to_json()
+    .expect("OpenAPI document should serialize to json")
+});
+
+pub fn openapi_scope() -> Scope {
+  web::scope("/api/openapi.json").service(web::resource("").route(web::get().to(openapi_handler)))
+}
+
+async fn openapi_handler() -> HttpResponse {
+  HttpResponse::Ok()
+    .content_type("application/json")
+    .body(OPENAPI_JSON.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::PathBuf;
+
+  use utoipa::OpenApi;
+
+  use super::ApiDoc;
+
+  /// Compares the generated document with the snapshot at `doc/openapi.json`, so that changes to
+  /// the annotated handlers or their DTOs show up in review. Run with `UPDATE_OPENAPI_SNAPSHOT=1`
+  /// to accept the changes. A missing snapshot is written on the first run.
+  #[test]
+  fn openapi_matches_snapshot() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("doc/openapi.json");
+    let generated = ApiDoc::openapi().to_pretty_json().unwrap();
+    if std::env::var("UPDATE_OPENAPI_SNAPSHOT").is_ok() || !path.exists() {
+      std::fs::write(&path, format!("{}\n", generated)).unwrap();
+      return;
+    }
+
+    let snapshot: serde_json::Value =
+      serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    let generated: serde_json::Value = serde_json::from_str(&generated).unwrap();
+    assert!(
+      snapshot == generated,
+      "doc/openapi.json is out of date, run the test with UPDATE_OPENAPI_SNAPSHOT=1 to update it"
+    );
+  }
+}
@@ -0,0 +1,277 @@
+use actix_web::{
+  web::{self, Data, Json},
+  Result, Scope,
+};
+use authentication::jwt::UserUuid;
+use shared_entity::dto::org_dto::{
+  AddOrganizationMembersParams, CreateOrganizationParams, Organization, OrganizationMember,
+  OrganizationUsage, RemoveOrganizationMembersParams, UpdateOrganizationSessionPolicyParams,
+};
+use shared_entity::response::{AppResponse, AppResponseError, JsonAppResponse};
+use uuid::Uuid;
+
+use crate::biz::organization::ops;
+use crate::state::AppState;
+
+pub fn organization_scope() -> Scope {
+  web::scope("/api/organization")
+    .service(
+      web::resource("")
+        .route(web::post().to(create_organization_handler))
+        .route(web::get().to(list_organizations_handler)),
+    )
+    .service(web::resource("/{org_id}").route(web::get().to(get_organization_handler)))
+    .service(
+      web::resource("/{org_id}/workspace/{workspace_id}")
+        .route(web::put().to(attach_workspace_handler))
+        .route(web::delete().to(detach_workspace_handler)),
+    )
+    .service(
+      web::resource("/{org_id}/member")
+        .route(web::get().to(list_organization_members_handler))
+        .route(web::post().to(add_organization_members_handler))
+        .route(web::delete().to(remove_organization_members_handler)),
+    )
+    .service(web::resource("/{org_id}/usage").route(web::get().to(get_organization_usage_handler)))
+    .service(
+      web::resource("/{org_id}/session-policy")
+        .route(web::put().to(update_organization_session_policy_handler)),
+    )
+}
+
+#[utoipa::path(
+  post,
+  path = "/api/organization",
+  tag = "organization",
+  request_body = CreateOrganizationParams,
+  responses(
+    (status = 200, description = "The created organization, the user is its first admin", body = Organization),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+async fn create_organization_handler(
+  uuid: UserUuid,
+  payload: Json<CreateOrganizationParams>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<Organization>> {
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  let org = ops::create_organization(&state.pg_pool, uid, payload.into_inner()).await?;
+  Ok(Json(AppResponse::Ok().with_data(org)))
+}
+
+#[utoipa::path(
+  get,
+  path = "/api/organization",
+  tag = "organization",
+  responses(
+    (status = 200, description = "The organizations the user belongs to", body = Vec<Organization>),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+async fn list_organizations_handler(
+  uuid: UserUuid,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<Vec<Organization>>> {
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  let orgs = ops::list_organizations(&state.pg_pool, uid).await?;
+  Ok(Json(AppResponse::Ok().with_data(orgs)))
+}
+
+#[utoipa::path(
+  get,
+  path = "/api/organization/{org_id}",
+  tag = "organization",
+  params(("org_id" = Uuid, Path, description = "Id of the organization")),
+  responses(
+    (status = 200, description = "The organization", body = Organization),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+async fn get_organization_handler(
+  uuid: UserUuid,
+  org_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<Organization>> {
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  let org = ops::get_organization(&state.pg_pool, uid, &org_id).await?;
+  Ok(Json(AppResponse::Ok().with_data(org)))
+}
+
+#[utoipa::path(
+  put,
+  path = "/api/organization/{org_id}/workspace/{workspace_id}",
+  tag = "organization",
+  params(
+    ("org_id" = Uuid, Path, description = "Id of the organization"),
+    ("workspace_id" = Uuid, Path, description = "Id of a workspace owned by the user"),
+  ),
+  responses(
+    (status = 200, description = "The workspace belongs to the organization"),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+async fn attach_workspace_handler(
+  uuid: UserUuid,
+  path: web::Path<(Uuid, Uuid)>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<()>> {
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  let (org_id, workspace_id) = path.into_inner();
+  ops::attach_workspace(
+    &state.pg_pool,
+    &state.workspace_access_control,
+    uid,
+    &org_id,
+    &workspace_id,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok()))
+}
+
+#[utoipa::path(
+  delete,
+  path = "/api/organization/{org_id}/workspace/{workspace_id}",
+  tag = "organization",
+  params(
+    ("org_id" = Uuid, Path, description = "Id of the organization"),
+    ("workspace_id" = Uuid, Path, description = "Id of a workspace of the organization"),
+  ),
+  responses(
+    (status = 200, description = "The workspace no longer belongs to the organization"),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+async fn detach_workspace_handler(
+  uuid: UserUuid,
+  path: web::Path<(Uuid, Uuid)>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<()>> {
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  let (org_id, workspace_id) = path.into_inner();
+  ops::detach_workspace(&state.pg_pool, uid, &org_id, &workspace_id).await?;
+  Ok(Json(AppResponse::Ok()))
+}
+
+#[utoipa::path(
+  get,
+  path = "/api/organization/{org_id}/member",
+  tag = "organization",
+  params(("org_id" = Uuid, Path, description = "Id of the organization")),
+  responses(
+    (status = 200, description = "The members of the organization and of its workspaces", body = Vec<OrganizationMember>),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+async fn list_organization_members_handler(
+  uuid: UserUuid,
+  org_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<Vec<OrganizationMember>>> {
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  let members = ops::list_organization_members(&state.pg_pool, uid, &org_id).await?;
+  Ok(Json(AppResponse::Ok().with_data(members)))
+}
+
+#[utoipa::path(
+  post,
+  path = "/api/organization/{org_id}/member",
+  tag = "organization",
+  params(("org_id" = Uuid, Path, description = "Id of the organization")),
+  request_body = AddOrganizationMembersParams,
+  responses(
+    (status = 200, description = "The users were added to the organization"),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+async fn add_organization_members_handler(
+  uuid: UserUuid,
+  org_id: web::Path<Uuid>,
+  payload: Json<AddOrganizationMembersParams>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<()>> {
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  ops::add_organization_members(
+    &state.pg_pool,
+    &state.config.billing,
+    &state.workspace_access_control,
+    uid,
+    &org_id,
+    payload.into_inner(),
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok()))
+}
+
+#[utoipa::path(
+  delete,
+  path = "/api/organization/{org_id}/member",
+  tag = "organization",
+  params(("org_id" = Uuid, Path, description = "Id of the organization")),
+  request_body = RemoveOrganizationMembersParams,
+  responses(
+    (status = 200, description = "The users were removed from the organization"),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+async fn remove_organization_members_handler(
+  uuid: UserUuid,
+  org_id: web::Path<Uuid>,
+  payload: Json<RemoveOrganizationMembersParams>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<()>> {
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  ops::remove_organization_members(&state.pg_pool, uid, &org_id, payload.into_inner()).await?;
+  Ok(Json(AppResponse::Ok()))
+}
+
+#[utoipa::path(
+  get,
+  path = "/api/organization/{org_id}/usage",
+  tag = "organization",
+  params(("org_id" = Uuid, Path, description = "Id of the organization")),
+  responses(
+    (status = 200, description = "The usage of the workspaces of the organization", body = OrganizationUsage),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+async fn get_organization_usage_handler(
+  uuid: UserUuid,
+  org_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<OrganizationUsage>> {
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  let usage = ops::get_organization_usage(&state.pg_pool, uid, &org_id).await?;
+  Ok(Json(AppResponse::Ok().with_data(usage)))
+}
+
+#[utoipa::path(
+  put,
+  path = "/api/organization/{org_id}/session-policy",
+  tag = "organization",
+  params(("org_id" = Uuid, Path, description = "Id of the organization")),
+  request_body = UpdateOrganizationSessionPolicyParams,
+  responses(
+    (status = 200, description = "The organization with its new session policy", body = Organization),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+async fn update_organization_session_policy_handler(
+  uuid: UserUuid,
+  org_id: web::Path<Uuid>,
+  payload: Json<UpdateOrganizationSessionPolicyParams>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<Organization>> {
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  let org = ops::update_session_policy(&state.pg_pool, uid, &org_id, payload.into_inner()).await?;
+  Ok(Json(AppResponse::Ok().with_data(org)))
+}
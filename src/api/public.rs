@@ -0,0 +1,100 @@
+use std::net::IpAddr;
+
+use actix_web::web::{Data, Json};
+use actix_web::{web, HttpRequest, Result, Scope};
+use ipnet::IpNet;
+use shared_entity::dto::workspace_dto::{SubmitDatabaseFormRow, SubmitDatabaseFormRowResponse};
+use shared_entity::response::{AppResponse, JsonAppResponse};
+
+use crate::biz::collab::database_form::submit_database_form_row;
+use crate::state::AppState;
+
+/// Endpoints called without an account, the requests are authorized by the token in the path.
+pub fn public_scope() -> Scope {
+  web::scope("/api/public").service(
+    web::resource("/database/{token}/rows").route(web::post().to(post_database_form_row_handler)),
+  )
+}
+
+async fn post_database_form_row_handler(
+  token: web::Path<String>,
+  state: Data<AppState>,
+  payload: Json<SubmitDatabaseFormRow>,
+  req: HttpRequest,
+) -> Result<JsonAppResponse<SubmitDatabaseFormRowResponse>> {
+  let client_addr = client_addr(&req, &state.config.database_form.trusted_proxies);
+  let row_id = submit_database_form_row(
+    state.collab_access_control_storage.clone(),
+    &state.pg_pool,
+    &state.redis_connection_manager,
+    &state.workspace_read_only_cache,
+    &state.config.database_form,
+    &token.into_inner(),
+    &client_addr,
+    payload.into_inner(),
+  )
+  .await?;
+  Ok(Json(
+    AppResponse::Ok().with_data(SubmitDatabaseFormRowResponse { row_id }),
+  ))
+}
+
+/// The address of the client. The `X-Forwarded-For` header is only read when the request comes
+/// from a trusted reverse proxy, the client can write anything in it otherwise. The proxies append
+/// the address of their peer, so the last address that is not a trusted proxy is the client, the
+/// addresses before it may have been written by the client.
+fn client_addr(req: &HttpRequest, trusted_proxies: &[IpNet]) -> String {
+  let Some(peer_ip) = req.peer_addr().map(|addr| addr.ip()) else {
+    return String::new();
+  };
+  let forwarded_for = req
+    .headers()
+    .get_all("x-forwarded-for")
+    .filter_map(|value| value.to_str().ok())
+    .collect::<Vec<_>>()
+    .join(",");
+  forwarded_client_ip(peer_ip, &forwarded_for, trusted_proxies).to_string()
+}
+
+fn forwarded_client_ip(peer_ip: IpAddr, forwarded_for: &str, trusted_proxies: &[IpNet]) -> IpAddr {
+  let mut client_ip = peer_ip;
+  let mut forwarded = forwarded_for.rsplit(',').map(str::trim);
+  while trusted_proxies
+    .iter()
+    .any(|proxy| proxy.contains(&client_ip))
+  {
+    match forwarded
+      .next()
+      .and_then(|addr| addr.parse::<IpAddr>().ok())
+    {
+      Some(ip) => client_ip = ip,
+      None => break,
+    }
+  }
+  client_ip
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn forwarded_address_is_only_trusted_from_proxies() {
+    let proxies = vec!["172.16.0.0/12".parse::<IpNet>().unwrap()];
+    let proxy: IpAddr = "172.18.0.5".parse().unwrap();
+    let client: IpAddr = "203.0.113.7".parse().unwrap();
+
+    // not from a proxy, the header is written by the client
+    assert_eq!(
+      forwarded_client_ip(client, "198.51.100.1", &proxies),
+      client
+    );
+    assert_eq!(forwarded_client_ip(client, "198.51.100.1", &[]), client);
+    // the client can prepend any address, the one appended by the proxy is used
+    assert_eq!(
+      forwarded_client_ip(proxy, "198.51.100.1, 203.0.113.7", &proxies),
+      client
+    );
+    assert_eq!(forwarded_client_ip(proxy, "", &proxies), proxy);
+  }
+}
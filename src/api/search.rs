@@ -1,19 +1,33 @@
-use access_control::act::Action;
 use actix_web::web::{Data, Query};
 use actix_web::{web, Scope};
 use uuid::Uuid;
 
 use authentication::jwt::Authorization;
 use shared_entity::dto::search_dto::{SearchDocumentRequest, SearchDocumentResponseItem};
-use shared_entity::response::{AppResponse, JsonAppResponse};
+use shared_entity::response::{AppResponse, AppResponseError, JsonAppResponse};
 
 use crate::biz::search::search_document;
+use crate::biz::workspace::collab_guest::enforce_read_or_guest_access;
 use crate::state::AppState;
 
 pub fn search_scope() -> Scope {
   web::scope("/api/search/{workspace_id}")
     .service(web::resource("").route(web::get().to(document_search)))
 }
+#[utoipa::path(
+  get,
+  path = "/api/search/{workspace_id}",
+  tag = "search",
+  params(
+    ("workspace_id" = Uuid, Path, description = "Workspace to search in"),
+    SearchDocumentRequest,
+  ),
+  responses(
+    (status = 200, description = "Matching documents, best match first", body = Vec<SearchDocumentResponseItem>),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
 #[tracing::instrument(skip(state, auth, payload), err)]
 async fn document_search(
   auth: Authorization,
@@ -25,17 +39,22 @@ async fn document_search(
   let request = payload.into_inner();
   let user_uuid = auth.uuid()?;
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
-  state
-    .workspace_access_control
-    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
-    .await?;
+  let guest_access = enforce_read_or_guest_access(
+    &state.workspace_access_control,
+    &state.collab_guest_cache,
+    uid,
+    &workspace_id,
+  )
+  .await?;
   let metrics = &*state.metrics.request_metrics;
   let resp = search_document(
     &state.pg_pool,
     &state.collab_access_control_storage,
+    &state.restricted_view_cache,
     &state.indexer_scheduler,
     uid,
     workspace_id,
+    guest_access.as_deref(),
     request,
     metrics,
   )
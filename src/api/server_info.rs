@@ -1,7 +1,7 @@
 use actix_web::web::Data;
 use actix_web::{web, Scope};
 use shared_entity::dto::server_info_dto::ServerInfoResponseItem;
-use shared_entity::response::{AppResponse, JsonAppResponse};
+use shared_entity::response::{AppResponse, AppResponseError, JsonAppResponse};
 
 use crate::state::AppState;
 
@@ -9,6 +9,15 @@ pub fn server_info_scope() -> Scope {
   web::scope("/api/server").service(web::resource("").route(web::get().to(server_info_handler)))
 }
 
+#[utoipa::path(
+  get,
+  path = "/api/server",
+  tag = "server",
+  responses(
+    (status = 200, description = "Server capabilities", body = ServerInfoResponseItem),
+    (status = "default", description = "Error response", body = AppResponseError),
+  )
+)]
 async fn server_info_handler(
   state: Data<AppState>,
 ) -> actix_web::Result<JsonAppResponse<ServerInfoResponseItem>> {
@@ -18,6 +27,8 @@ async fn server_info_handler(
         supported_client_features: vec![],
         minimum_supported_client_version: None,
         appflowy_web_url: state.config.appflowy_web_url.clone(),
+        region: state.config.region.current.clone(),
+        regions: state.config.region.regions.clone(),
       })
       .into(),
   )
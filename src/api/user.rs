@@ -1,3 +1,4 @@
+use crate::biz::user::user_auth::{change_email, send_magic_link, send_password_recovery};
 use crate::biz::user::user_delete::delete_user;
 use crate::biz::user::user_info::{get_profile, get_user_workspace_info, update_user};
 use crate::biz::user::user_verify::verify_token;
@@ -5,9 +6,20 @@ use crate::state::AppState;
 use actix_web::web::{Data, Json};
 use actix_web::Result;
 use actix_web::{web, Scope};
+use app_error::AppError;
 use authentication::jwt::{Authorization, UserUuid};
+use database::notification_preferences::{
+  select_notification_preferences, upsert_notification_preferences,
+};
+use database::push_device::{delete_push_device, upsert_push_device};
 use database_entity::dto::{AFUserProfile, AFUserWorkspaceInfo};
-use shared_entity::dto::auth_dto::{DeleteUserQuery, SignInTokenResponse, UpdateUserParams};
+use shared_entity::dto::auth_dto::{
+  ChangeEmailParams, DeleteUserQuery, EmailLinkParams, SignInTokenResponse, UpdateUserParams,
+};
+use shared_entity::dto::notification_dto::{
+  NotificationPreferences, RegisterPushDeviceParams, UnregisterPushDeviceParams,
+  UpdateNotificationPreferencesParams,
+};
 use shared_entity::response::AppResponseError;
 use shared_entity::response::{AppResponse, JsonAppResponse};
 
@@ -15,11 +27,34 @@ pub fn user_scope() -> Scope {
   web::scope("/api/user")
     .service(web::resource("/verify/{access_token}").route(web::get().to(verify_user_handler)))
     .service(web::resource("/update").route(web::post().to(update_user_handler)))
+    .service(web::resource("/email").route(web::post().to(change_email_handler)))
+    .service(web::resource("/recover").route(web::post().to(recover_password_handler)))
+    .service(web::resource("/magic-link").route(web::post().to(magic_link_handler)))
     .service(web::resource("/profile").route(web::get().to(get_user_profile_handler)))
     .service(web::resource("/workspace").route(web::get().to(get_user_workspace_info_handler)))
+    .service(
+      web::resource("/notification-preferences")
+        .route(web::get().to(get_notification_preferences_handler))
+        .route(web::put().to(put_notification_preferences_handler)),
+    )
+    .service(
+      web::resource("/push-device")
+        .route(web::post().to(register_push_device_handler))
+        .route(web::delete().to(unregister_push_device_handler)),
+    )
     .service(web::resource("").route(web::delete().to(delete_user_handler)))
 }
 
+#[utoipa::path(
+  get,
+  path = "/api/user/verify/{access_token}",
+  tag = "user",
+  params(("access_token" = String, Path, description = "GoTrue access token")),
+  responses(
+    (status = 200, description = "Whether the user signed in for the first time", body = SignInTokenResponse),
+    (status = "default", description = "Error response", body = AppResponseError),
+  )
+)]
 #[tracing::instrument(skip(state, path), err)]
 async fn verify_user_handler(
   path: web::Path<String>,
@@ -33,6 +68,16 @@ async fn verify_user_handler(
   Ok(AppResponse::Ok().with_data(resp).into())
 }
 
+#[utoipa::path(
+  get,
+  path = "/api/user/profile",
+  tag = "user",
+  responses(
+    (status = 200, description = "Profile of the current user", body = AFUserProfile),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
 #[tracing::instrument(skip(state), err)]
 async fn get_user_profile_handler(
   uuid: UserUuid,
@@ -44,6 +89,16 @@ async fn get_user_profile_handler(
   Ok(AppResponse::Ok().with_data(profile).into())
 }
 
+#[utoipa::path(
+  get,
+  path = "/api/user/workspace",
+  tag = "user",
+  responses(
+    (status = 200, description = "Profile and workspaces of the current user", body = AFUserWorkspaceInfo),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
 #[tracing::instrument(skip(state), err)]
 async fn get_user_workspace_info_handler(
   uuid: UserUuid,
@@ -53,6 +108,118 @@ async fn get_user_workspace_info_handler(
   Ok(AppResponse::Ok().with_data(info).into())
 }
 
+#[utoipa::path(
+  get,
+  path = "/api/user/notification-preferences",
+  tag = "user",
+  responses(
+    (status = 200, description = "Notification preferences of the current user", body = NotificationPreferences),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+#[tracing::instrument(skip(state), err)]
+async fn get_notification_preferences_handler(
+  uuid: UserUuid,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<NotificationPreferences>> {
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  let preferences = select_notification_preferences(&state.pg_pool, uid, None).await?;
+  Ok(AppResponse::Ok().with_data(preferences).into())
+}
+
+#[utoipa::path(
+  put,
+  path = "/api/user/notification-preferences",
+  tag = "user",
+  request_body = UpdateNotificationPreferencesParams,
+  responses(
+    (status = 200, description = "Notification preferences after the update", body = NotificationPreferences),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+#[tracing::instrument(skip(state, payload), err)]
+async fn put_notification_preferences_handler(
+  uuid: UserUuid,
+  payload: Json<UpdateNotificationPreferencesParams>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<NotificationPreferences>> {
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  upsert_notification_preferences(&state.pg_pool, uid, None, &payload).await?;
+  let preferences = select_notification_preferences(&state.pg_pool, uid, None).await?;
+  Ok(AppResponse::Ok().with_data(preferences).into())
+}
+
+/// The tokens of APNs and FCM are much shorter, longer ones are rejected.
+const MAX_PUSH_TOKEN_LEN: usize = 4096;
+
+#[utoipa::path(
+  post,
+  path = "/api/user/push-device",
+  tag = "user",
+  request_body = RegisterPushDeviceParams,
+  responses(
+    (status = 200, description = "Push notifications are sent to the device"),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+#[tracing::instrument(skip(state, payload), err)]
+async fn register_push_device_handler(
+  uuid: UserUuid,
+  payload: Json<RegisterPushDeviceParams>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<()>> {
+  let params = payload.into_inner();
+  if params.token.is_empty() || params.token.len() > MAX_PUSH_TOKEN_LEN {
+    return Err(AppError::InvalidRequest("invalid push token".to_string()).into());
+  }
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  upsert_push_device(
+    &state.pg_pool,
+    uid,
+    &params.token,
+    params.platform,
+    params.device_id.as_deref(),
+  )
+  .await?;
+  Ok(AppResponse::Ok().into())
+}
+
+#[utoipa::path(
+  delete,
+  path = "/api/user/push-device",
+  tag = "user",
+  request_body = UnregisterPushDeviceParams,
+  responses(
+    (status = 200, description = "Push notifications are no longer sent to the device"),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+#[tracing::instrument(skip(state, payload), err)]
+async fn unregister_push_device_handler(
+  uuid: UserUuid,
+  payload: Json<UnregisterPushDeviceParams>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<()>> {
+  let uid = state.user_cache.get_user_uid(&uuid).await?;
+  delete_push_device(&state.pg_pool, uid, &payload.token).await?;
+  Ok(AppResponse::Ok().into())
+}
+
+#[utoipa::path(
+  post,
+  path = "/api/user/update",
+  tag = "user",
+  request_body = UpdateUserParams,
+  responses(
+    (status = 200, description = "User updated"),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
 #[tracing::instrument(skip(state, auth, payload), err)]
 async fn update_user_handler(
   auth: Authorization,
@@ -64,6 +231,98 @@ async fn update_user_handler(
   Ok(AppResponse::Ok().into())
 }
 
+#[utoipa::path(
+  post,
+  path = "/api/user/email",
+  tag = "user",
+  request_body = ChangeEmailParams,
+  responses(
+    (status = 200, description = "A confirmation link was sent to the new email"),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+#[tracing::instrument(skip(state, auth, payload), err)]
+async fn change_email_handler(
+  auth: Authorization,
+  payload: Json<ChangeEmailParams>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<()>> {
+  let user_uuid = auth.uuid()?;
+  change_email(
+    &state.pg_pool,
+    &state.gotrue_client,
+    &auth.token,
+    user_uuid,
+    payload.into_inner().new_email,
+  )
+  .await?;
+  Ok(AppResponse::Ok().into())
+}
+
+#[utoipa::path(
+  post,
+  path = "/api/user/recover",
+  tag = "user",
+  request_body = EmailLinkParams,
+  responses(
+    (status = 200, description = "A password recovery link was sent to the email"),
+    (status = "default", description = "Error response", body = AppResponseError),
+  )
+)]
+#[tracing::instrument(skip(state, payload), err)]
+async fn recover_password_handler(
+  payload: Json<EmailLinkParams>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<()>> {
+  let params = payload.into_inner();
+  send_password_recovery(
+    &state.pg_pool,
+    &state.gotrue_client,
+    params.email,
+    params.redirect_to,
+  )
+  .await?;
+  Ok(AppResponse::Ok().into())
+}
+
+#[utoipa::path(
+  post,
+  path = "/api/user/magic-link",
+  tag = "user",
+  request_body = EmailLinkParams,
+  responses(
+    (status = 200, description = "A link to sign in was sent to the email"),
+    (status = "default", description = "Error response", body = AppResponseError),
+  )
+)]
+#[tracing::instrument(skip(state, payload), err)]
+async fn magic_link_handler(
+  payload: Json<EmailLinkParams>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<()>> {
+  let params = payload.into_inner();
+  send_magic_link(
+    &state.pg_pool,
+    &state.gotrue_client,
+    params.email,
+    params.redirect_to,
+  )
+  .await?;
+  Ok(AppResponse::Ok().into())
+}
+
+#[utoipa::path(
+  delete,
+  path = "/api/user",
+  tag = "user",
+  params(DeleteUserQuery),
+  responses(
+    (status = 200, description = "User deleted"),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
 #[tracing::instrument(skip(state), err)]
 async fn delete_user_handler(
   auth: Authorization,
@@ -77,7 +336,7 @@ async fn delete_user_handler(
   } = query.into_inner();
   delete_user(
     &state.pg_pool,
-    &state.bucket_storage,
+    &state.redis_connection_manager,
     &state.gotrue_client,
     &state.gotrue_admin,
     &state.config.apple_oauth,
@@ -1,7 +1,10 @@
 use crate::domain::compression::{CompressionType, X_COMPRESSION_BUFFER_SIZE, X_COMPRESSION_TYPE};
 use actix_http::header::HeaderMap;
-use actix_web::web::Payload;
+use actix_web::error::{InternalError, JsonPayloadError};
+use actix_web::web::{JsonConfig, Payload};
+use actix_web::{HttpResponse, ResponseError};
 use app_error::AppError;
+use shared_entity::response::AppResponseError;
 
 use actix_web::HttpRequest;
 use async_trait::async_trait;
@@ -124,6 +127,24 @@ impl CollabValidator for CollabParams {
   }
 }
 
+/// Limits the body of the [actix_web::web::Json] extractor. Oversized bodies are rejected with
+/// 413 and a [AppError::PayloadTooLarge] response, before the body is read when the Content-Length
+/// is known.
+pub fn json_config(limit: usize) -> JsonConfig {
+  JsonConfig::default()
+    .limit(limit)
+    .error_handler(|err, _req| {
+      let app_err = match &err {
+        JsonPayloadError::OverflowKnownLength { .. } | JsonPayloadError::Overflow { .. } => {
+          AppError::PayloadTooLarge(err.to_string())
+        },
+        _ => AppError::InvalidRequest(err.to_string()),
+      };
+      let resp = HttpResponse::build(err.status_code()).json(AppResponseError::from(app_err));
+      InternalError::from_response(err, resp).into()
+    })
+}
+
 pub struct PayloadReader {
   payload: Payload,
   buffer: Vec<u8>,
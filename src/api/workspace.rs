@@ -2,13 +2,52 @@ use crate::api::util::{client_version_from_headers, realtime_user_for_web_reques
 use crate::api::util::{compress_type_from_header_value, device_id_from_headers};
 use crate::api::ws::RealtimeServerAddr;
 use crate::biz;
+use crate::biz::billing::ops::{
+  check_member_limit, get_workspace_billing, refresh_workspace_read_only_after_change,
+};
+use crate::biz::collab::archive::get_collab_archive_status;
+use crate::biz::collab::changes::get_changed_collabs;
+use crate::biz::collab::checkpoint::{
+  create_collab_checkpoint, get_collab_history, revert_collab_to_checkpoint,
+};
+use crate::biz::collab::database_form::{
+  create_database_form_token, delete_database_form_token, list_database_form_tokens,
+};
+use crate::biz::collab::database_row::{
+  create_database_row_comment, get_database_row_activity, get_database_row_comments,
+  remove_database_row_comment,
+};
+use crate::biz::collab::export::{
+  create_pdf_export_task, create_workspace_export_task, get_export_task,
+};
+use crate::biz::collab::external_id::{
+  bulk_upsert_database_rows, get_database_external_id_field, set_database_external_id_field,
+};
+use crate::biz::collab::lock::{lock_collab, unlock_collab};
 use crate::biz::collab::ops::{
   get_user_favorite_folder_views, get_user_recent_folder_views, get_user_trash_folder_views,
+  stream_batch_get_collab,
 };
+use crate::biz::collab::render::render_document;
+use crate::biz::collab::stats::get_collab_edit_stats;
 use crate::biz::collab::utils::collab_from_doc_state;
+use crate::biz::collab::workspace_collabs::list_workspace_collabs;
 use crate::biz::user::user_verify::verify_token;
 use crate::biz::workspace;
+use crate::biz::workspace::clone::{clone_workspace, get_workspace_clone_task};
+use crate::biz::workspace::collab_guest::{
+  convert_guest_to_member, enforce_read_or_guest_access, invite_collab_guest, list_collab_guests,
+  list_collab_members, remove_collab_guest,
+};
 use crate::biz::workspace::duplicate::duplicate_view_tree_and_collab;
+use crate::biz::workspace::folder_rebuild::rebuild_workspace_folder;
+use crate::biz::workspace::history_compaction::{
+  get_workspace_history_compaction, run_workspace_history_compaction,
+  update_workspace_history_compaction,
+};
+use crate::biz::workspace::image_asset::{
+  upload_view_image, upload_workspace_icon, ImageAssetKind,
+};
 use crate::biz::workspace::ops::{
   create_comment_on_published_view, create_reaction_on_comment, get_comments_on_published_view,
   get_reactions_on_published_view, remove_comment_on_published_view, remove_reaction_on_comment,
@@ -23,11 +62,20 @@ use crate::biz::workspace::publish::get_workspace_default_publish_view_info_meta
 use crate::biz::workspace::quick_note::{
   create_quick_note, delete_quick_note, list_quick_notes, update_quick_note,
 };
+use crate::biz::workspace::restricted_view::{
+  list_restricted_views, restrict_view, unrestrict_view,
+};
+use crate::biz::workspace::retention::{
+  dry_run_workspace_retention_policy, get_workspace_retention_policy,
+  update_workspace_retention_policy,
+};
+use crate::config::config::PayloadLimitSetting;
 use crate::domain::compression::{
   blocking_decompress, decompress, CompressionType, X_COMPRESSION_TYPE,
 };
 use crate::state::AppState;
 use access_control::act::Action;
+use actix_multipart::form::{bytes::Bytes as MPBytes, MultipartForm, MultipartFormConfig};
 use actix_web::web::{Bytes, Path, Payload};
 use actix_web::web::{Data, Json, PayloadConfig};
 use actix_web::{web, HttpResponse, ResponseError, Scope};
@@ -43,7 +91,6 @@ use collab::core::origin::CollabOrigin;
 use collab::entity::EncodedCollab;
 use collab::preclude::Collab;
 use collab_database::entity::FieldType;
-use collab_document::document::Document;
 use collab_entity::CollabType;
 use collab_folder::timestamp;
 use collab_rt_entity::collab_proto::{CollabDocStateParams, PayloadCompressionType};
@@ -52,16 +99,27 @@ use collab_rt_entity::user::RealtimeUser;
 use collab_rt_entity::RealtimeMessage;
 use collab_rt_protocol::collab_from_encode_collab;
 use database::collab::{CollabStorage, GetCollabOrigin};
+use database::file::{BucketClient, ResponseBlob};
+use database::notification_preferences::{
+  delete_workspace_notification_preferences, select_notification_preferences,
+  upsert_notification_preferences,
+};
 use database::user::select_uid_from_email;
 use database_entity::dto::PublishCollabItem;
 use database_entity::dto::PublishInfo;
 use database_entity::dto::*;
 use indexer::scheduler::{UnindexedCollabTask, UnindexedData};
+use infra::env_util::get_env_var;
 use itertools::Itertools;
 use prost::Message as ProstMessage;
 use rayon::prelude::*;
 use sha2::{Digest, Sha256};
-use shared_entity::dto::publish_dto::DuplicatePublishedPageResponse;
+use shared_entity::dto::billing_dto::WorkspaceBilling;
+use shared_entity::dto::export_dto::ExportTaskDetail;
+use shared_entity::dto::notification_dto::{
+  NotificationPreferences, UpdateNotificationPreferencesParams,
+};
+use shared_entity::dto::publish_dto::{DuplicatePublishedPageResponse, ReportPublishedViewParams};
 use shared_entity::dto::workspace_dto::*;
 use shared_entity::response::AppResponseError;
 use shared_entity::response::{AppResponse, JsonAppResponse};
@@ -87,7 +145,7 @@ pub const WORKSPACE_PUBLISH_PATTERN: &str = "/api/workspace/{workspace_id}/publi
 pub const WORKSPACE_PUBLISH_NAMESPACE_PATTERN: &str =
   "/api/workspace/{workspace_id}/publish-namespace";
 
-pub fn workspace_scope() -> Scope {
+pub fn workspace_scope(payload_limits: &PayloadLimitSetting) -> Scope {
   web::scope("/api/workspace")
     .service(
       web::resource("")
@@ -114,8 +172,31 @@ pub fn workspace_scope() -> Scope {
         .route(web::get().to(get_workspace_settings_handler))
         .route(web::post().to(post_workspace_settings_handler)),
     )
+    .service(
+      web::resource("/{workspace_id}/retention")
+        .route(web::get().to(get_workspace_retention_policy_handler))
+        .route(web::put().to(update_workspace_retention_policy_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/retention/dry-run")
+        .route(web::get().to(dry_run_workspace_retention_policy_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/history-compaction")
+        .route(web::get().to(get_workspace_history_compaction_handler))
+        .route(web::put().to(update_workspace_history_compaction_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/history-compaction/run")
+        .route(web::post().to(run_workspace_history_compaction_handler)),
+    )
     .service(web::resource("/{workspace_id}/open").route(web::put().to(open_workspace_handler)))
     .service(web::resource("/{workspace_id}/leave").route(web::post().to(leave_workspace_handler)))
+    .service(web::resource("/{workspace_id}/clone").route(web::post().to(clone_workspace_handler)))
+    .service(
+      web::resource("/{workspace_id}/clone/{task_id}")
+        .route(web::get().to(get_workspace_clone_task_handler)),
+    )
     .service(
       web::resource("/{workspace_id}/member")
         .route(web::get().to(get_workspace_members_handler))
@@ -131,20 +212,54 @@ pub fn workspace_scope() -> Scope {
       web::resource("v1/{workspace_id}/member/user/{user_id}")
         .route(web::get().to(get_workspace_member_v1_handler)),
       )
+    .service(
+      web::resource("/{workspace_id}/collab/changed")
+        .route(web::get().to(get_changed_collabs_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/collabs")
+        .route(web::get().to(list_workspace_collabs_handler)),
+    )
     .service(
       web::resource("/{workspace_id}/collab/{object_id}")
         .app_data(
-          PayloadConfig::new(5 * 1024 * 1024), // 5 MB
+          PayloadConfig::new(payload_limits.collab),
         )
         .route(web::post().to(create_collab_handler))
         .route(web::get().to(get_collab_handler))
         .route(web::put().to(update_collab_handler))
         .route(web::delete().to(delete_collab_handler)),
     )
+    .service(
+      web::resource("/{workspace_id}/collab/{object_id}/upload")
+        .route(web::post().to(create_collab_upload_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/collab/{object_id}/upload/{upload_id}/complete")
+        .route(web::post().to(complete_collab_upload_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/collab/{object_id}/lock")
+        .route(web::get().to(get_collab_lock_handler))
+        .route(web::post().to(lock_collab_handler))
+        .route(web::delete().to(unlock_collab_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/collab/{object_id}/stats")
+        .route(web::get().to(get_collab_edit_stats_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/collab/{object_id}/archive")
+        .route(web::get().to(get_collab_archive_status_handler)),
+    )
     .service(
       web::resource("/v1/{workspace_id}/collab/{object_id}")
         .route(web::get().to(v1_get_collab_handler)),
     )
+    .service(
+      web::resource("/v1/{workspace_id}/collab_list")
+        .route(web::post().to(stream_batch_get_collab_handler)),
+    )
     .service(
       web::resource("/v1/{workspace_id}/collab/{object_id}/json")
         .route(web::get().to(get_collab_json_handler)),
@@ -157,6 +272,23 @@ pub fn workspace_scope() -> Scope {
       web::resource("/v1/{workspace_id}/collab/{object_id}/web-update")
         .route(web::post().to(post_web_update_handler)),
     )
+    .service(
+      web::resource("/{workspace_id}/collab/{object_id}/render")
+        .route(web::get().to(render_collab_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/collab/{object_id}/export/pdf")
+        .route(web::post().to(export_pdf_handler)),
+    )
+    .service(
+      // registered before the export tasks, whose path would match it too
+      web::resource("/{workspace_id}/export/archive")
+        .route(web::post().to(export_workspace_archive_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/export/{task_id}")
+        .route(web::get().to(get_export_task_handler)),
+    )
     .service(
       web::resource("/{workspace_id}/collab/{object_id}/embed-info")
         .route(web::get().to(get_collab_embed_info_handler)),
@@ -169,6 +301,11 @@ pub fn workspace_scope() -> Scope {
     .service(
       web::resource("/{workspace_id}/space/{view_id}").route(web::patch().to(update_space_handler)),
     )
+    .service(
+      web::resource("/{workspace_id}/icon")
+        .app_data(image_asset_form_config(payload_limits))
+        .route(web::put().to(put_workspace_icon_handler)),
+    )
     .service(
       web::resource("/{workspace_id}/page-view").route(web::post().to(post_page_view_handler)),
     )
@@ -177,6 +314,16 @@ pub fn workspace_scope() -> Scope {
         .route(web::get().to(get_page_view_handler))
         .route(web::patch().to(update_page_view_handler)),
     )
+    .service(
+      web::resource("/{workspace_id}/page-view/{view_id}/icon")
+        .app_data(image_asset_form_config(payload_limits))
+        .route(web::put().to(put_page_icon_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/page-view/{view_id}/cover")
+        .app_data(image_asset_form_config(payload_limits))
+        .route(web::put().to(put_page_cover_handler)),
+    )
     .service(
       web::resource("/{workspace_id}/page-view/{view_id}/append-block")
         .route(web::post().to(append_block_to_page_handler)),
@@ -224,6 +371,24 @@ pub fn workspace_scope() -> Scope {
     .service(
       web::resource("/{workspace_id}/usage").route(web::get().to(get_workspace_usage_handler)),
     )
+    .service(
+      web::resource("/{workspace_id}/billing").route(web::get().to(get_workspace_billing_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/notification-preferences")
+        .route(web::get().to(get_workspace_notification_preferences_handler))
+        .route(web::put().to(put_workspace_notification_preferences_handler))
+        .route(web::delete().to(delete_workspace_notification_preferences_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/files")
+        .route(web::get().to(list_workspace_files_handler))
+        .route(web::delete().to(delete_workspace_files_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/files/approve")
+        .route(web::post().to(approve_workspace_files_handler)),
+    )
     .service(
       web::resource("/{workspace_id}/{object_id}/snapshot")
         .route(web::get().to(get_collab_snapshot_handler))
@@ -233,6 +398,18 @@ pub fn workspace_scope() -> Scope {
       web::resource("/{workspace_id}/{object_id}/snapshot/list")
         .route(web::get().to(get_all_collab_snapshot_list_handler)),
     )
+    .service(
+      web::resource("/{workspace_id}/{object_id}/checkpoint")
+        .route(web::post().to(create_collab_checkpoint_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/{object_id}/checkpoint/{checkpoint_id}/revert")
+        .route(web::post().to(revert_collab_checkpoint_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/{object_id}/history")
+        .route(web::get().to(get_collab_history_handler)),
+    )
     .service(
       web::resource("/published/{publish_namespace}")
         .route(web::get().to(get_default_published_collab_info_meta_handler)),
@@ -241,6 +418,10 @@ pub fn workspace_scope() -> Scope {
       web::resource("/v1/published/{publish_namespace}/{publish_name}")
         .route(web::get().to(get_v1_published_collab_handler)),
     )
+    .service(
+      web::resource("/published/{publish_namespace}/{file_name:(sitemap|rss|atom)\\.xml}")
+        .route(web::get().to(get_publish_feed_handler)),
+    )
     .service(
       web::resource("/published/{publish_namespace}/{publish_name}/blob")
         .route(web::get().to(get_published_collab_blob_handler)),
@@ -268,6 +449,10 @@ pub fn workspace_scope() -> Scope {
         .route(web::post().to(post_published_collab_comment_handler))
         .route(web::delete().to(delete_published_collab_comment_handler)),
     )
+    .service(
+      web::resource("/published-info/{view_id}/report")
+        .route(web::post().to(post_published_collab_report_handler)),
+    )
     .service(
       web::resource("/published-info/{view_id}/reaction")
         .route(web::get().to(get_published_collab_reaction_handler))
@@ -279,12 +464,46 @@ pub fn workspace_scope() -> Scope {
         .route(web::put().to(put_publish_namespace_handler))
         .route(web::get().to(get_publish_namespace_handler)),
     )
+    .service(
+      web::resource("/{workspace_id}/publish-domain")
+        .route(web::get().to(list_publish_domains_handler))
+        .route(web::post().to(post_publish_domain_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/publish-domain/{domain}")
+        .route(web::delete().to(delete_publish_domain_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/publish-domain/{domain}/verify")
+        .route(web::post().to(verify_publish_domain_handler)),
+    )
+    .service(
+      web::resource("/published-domain/namespace")
+        .route(web::get().to(get_publish_namespace_by_host_handler)),
+    )
+    .service(
+      // Called by the reverse proxy before issuing a TLS certificate on demand
+      web::resource("/published-domain/tls-check")
+        .route(web::get().to(get_publish_domain_tls_check_handler)),
+    )
+    .service(
+      web::resource("/v1/published-domain/{publish_name}")
+        .route(web::get().to(get_v1_published_collab_by_host_handler)),
+    )
+    .service(
+      web::resource("/published-domain/{publish_name}/blob")
+        .route(web::get().to(get_published_collab_blob_by_host_handler)),
+    )
     .service(
       web::resource("/{workspace_id}/publish-default")
         .route(web::put().to(put_workspace_default_published_view_handler))
         .route(web::delete().to(delete_workspace_default_published_view_handler))
         .route(web::get().to(get_workspace_published_default_info_handler)),
     )
+    .service(
+      web::resource("/{workspace_id}/publish/analytics")
+        .route(web::get().to(get_publish_analytics_handler)),
+    )
     .service(
       web::resource("/{workspace_id}/publish")
         .route(web::post().to(post_publish_collabs_handler))
@@ -294,6 +513,10 @@ pub fn workspace_scope() -> Scope {
     .service(
       web::resource("/{workspace_id}/folder").route(web::get().to(get_workspace_folder_handler)),
     )
+    .service(
+      web::resource("/{workspace_id}/folder/rebuild")
+        .route(web::post().to(rebuild_workspace_folder_handler)),
+    )
     .service(web::resource("/{workspace_id}/recent").route(web::get().to(get_recent_views_handler)))
     .service(
       web::resource("/{workspace_id}/favorite").route(web::get().to(get_favorite_views_handler)),
@@ -313,6 +536,10 @@ pub fn workspace_scope() -> Scope {
       // Web browser can't carry payload when using GET method, so for browser compatibility, we use POST method
       .route(web::post().to(batch_get_collab_handler)),
     )
+    .service(
+      web::resource("/{workspace_id}/collab_warm_up")
+        .route(web::post().to(warm_up_collab_handler)),
+    )
     .service(web::resource("/{workspace_id}/database").route(web::get().to(list_database_handler)))
     .service(
       web::resource("/{workspace_id}/database/{database_id}/row")
@@ -325,6 +552,19 @@ pub fn workspace_scope() -> Scope {
         .route(web::get().to(get_database_fields_handler))
         .route(web::post().to(post_database_fields_handler)),
     )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/view")
+        .route(web::get().to(list_database_views_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/view/{view_id}")
+        .route(web::patch().to(patch_database_view_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/fields/{field_id}")
+        .route(web::put().to(put_database_field_handler))
+        .route(web::delete().to(delete_database_field_handler)),
+    )
     .service(
       web::resource("/{workspace_id}/database/{database_id}/row/updated")
         .route(web::get().to(list_database_row_id_updated_handler)),
@@ -333,6 +573,61 @@ pub fn workspace_scope() -> Scope {
       web::resource("/{workspace_id}/database/{database_id}/row/detail")
         .route(web::get().to(list_database_row_details_handler)),
     )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/row/{row_id}/comment")
+        .route(web::get().to(get_database_row_comment_handler))
+        .route(web::post().to(post_database_row_comment_handler))
+        .route(web::delete().to(delete_database_row_comment_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/row/{row_id}/activity")
+        .route(web::get().to(get_database_row_activity_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/row/{row_id}/document")
+        .route(web::post().to(post_database_row_document_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/external-id-field")
+        .route(web::get().to(get_database_external_id_field_handler))
+        .route(web::put().to(put_database_external_id_field_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/rows:bulk_upsert")
+        .route(web::put().to(bulk_upsert_database_rows_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/form-token")
+        .route(web::get().to(list_database_form_tokens_handler))
+        .route(web::post().to(post_database_form_token_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/database/{database_id}/form-token/{token_id}")
+        .route(web::delete().to(delete_database_form_token_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/restricted-view")
+        .route(web::get().to(list_restricted_views_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/restricted-view/{view_id}")
+        .route(web::put().to(put_restricted_view_handler))
+        .route(web::delete().to(delete_restricted_view_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/collab/{object_id}/guest")
+        .route(web::get().to(list_collab_guests_handler))
+        .route(web::post().to(post_collab_guest_handler))
+        .route(web::delete().to(delete_collab_guest_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/collab/{object_id}/members")
+        .route(web::get().to(list_collab_members_handler)),
+    )
+    .service(
+      web::resource("/{workspace_id}/guest/{uid}/convert-to-member")
+        .route(web::post().to(convert_guest_to_member_handler)),
+    )
     .service(
       web::resource("/{workspace_id}/quick-note")
         .route(web::get().to(list_quick_notes_handler))
@@ -345,12 +640,10 @@ pub fn workspace_scope() -> Scope {
     )
 }
 
-pub fn collab_scope() -> Scope {
+pub fn collab_scope(payload_limits: &PayloadLimitSetting) -> Scope {
   web::scope("/api/realtime").service(
     web::resource("post/stream")
-      .app_data(
-        PayloadConfig::new(10 * 1024 * 1024), // 10 MB
-      )
+      .app_data(PayloadConfig::new(payload_limits.realtime_stream))
       .route(web::post().to(post_realtime_message_stream_handler)),
   )
 }
@@ -415,9 +708,10 @@ async fn delete_workspace_handler(
     .enforce_action(&uid, &workspace_id.to_string(), Action::Delete)
     .await?;
   workspace::ops::delete_workspace_for_user(
-    state.pg_pool.clone(),
+    &state.pg_pool,
+    &state.redis_connection_manager,
+    uid,
     *workspace_id,
-    state.bucket_storage.clone(),
   )
   .await?;
   Ok(AppResponse::Ok().into())
@@ -459,8 +753,16 @@ async fn post_workspace_invite_handler(
     .await?;
 
   let invitations = payload.into_inner();
+  check_member_limit(
+    &state.pg_pool,
+    &state.config.billing,
+    &workspace_id,
+    invitations.len() as i64,
+  )
+  .await?;
   workspace::ops::invite_workspace_members(
     &state.mailer,
+    &state.notification_center,
     &state.gotrue_admin,
     &state.pg_pool,
     &state.gotrue_client,
@@ -509,6 +811,7 @@ async fn post_accept_workspace_invite_handler(
   let invite_id = invite_id.into_inner();
   workspace::ops::accept_workspace_invite(
     &state.pg_pool,
+    &state.config.billing,
     state.workspace_access_control.clone(),
     user_uid,
     &user_uuid,
@@ -547,11 +850,119 @@ async fn post_workspace_settings_handler(
     .workspace_access_control
     .enforce_action(&uid, &workspace_id.to_string(), Action::Write)
     .await?;
-  let settings =
-    workspace::ops::update_workspace_settings(&state.pg_pool, &workspace_id, data).await?;
+  if data.ai_features.is_some() || data.region.is_some() {
+    state
+      .workspace_access_control
+      .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+      .await?;
+  }
+  let settings = workspace::ops::update_workspace_settings(
+    &state.pg_pool,
+    &workspace_id,
+    &state.config.region,
+    data,
+  )
+  .await?;
   Ok(AppResponse::Ok().with_data(settings).into())
 }
 
+#[instrument(skip_all, err, fields(user_uuid))]
+async fn get_workspace_retention_policy_handler(
+  user_uuid: UserUuid,
+  state: Data<AppState>,
+  workspace_id: web::Path<Uuid>,
+) -> Result<JsonAppResponse<WorkspaceRetentionPolicy>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
+    .await?;
+  let policy = get_workspace_retention_policy(&state.pg_pool, &workspace_id).await?;
+  Ok(AppResponse::Ok().with_data(policy).into())
+}
+
+#[instrument(level = "info", skip_all, err, fields(user_uuid))]
+async fn update_workspace_retention_policy_handler(
+  user_uuid: UserUuid,
+  state: Data<AppState>,
+  workspace_id: web::Path<Uuid>,
+  payload: Json<UpdateWorkspaceRetentionPolicyParams>,
+) -> Result<JsonAppResponse<WorkspaceRetentionPolicy>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  let policy =
+    update_workspace_retention_policy(&state.pg_pool, &workspace_id, payload.into_inner()).await?;
+  Ok(AppResponse::Ok().with_data(policy).into())
+}
+
+#[instrument(skip_all, err, fields(user_uuid))]
+async fn dry_run_workspace_retention_policy_handler(
+  user_uuid: UserUuid,
+  state: Data<AppState>,
+  workspace_id: web::Path<Uuid>,
+  query: web::Query<RetentionDryRunQuery>,
+) -> Result<JsonAppResponse<RetentionDryRunReport>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  let report =
+    dry_run_workspace_retention_policy(&state.pg_pool, &workspace_id, query.into_inner()).await?;
+  Ok(AppResponse::Ok().with_data(report).into())
+}
+
+#[instrument(skip_all, err, fields(user_uuid))]
+async fn get_workspace_history_compaction_handler(
+  user_uuid: UserUuid,
+  state: Data<AppState>,
+  workspace_id: web::Path<Uuid>,
+) -> Result<JsonAppResponse<WorkspaceHistoryCompaction>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
+    .await?;
+  let setting = get_workspace_history_compaction(&state.pg_pool, &workspace_id).await?;
+  Ok(AppResponse::Ok().with_data(setting).into())
+}
+
+#[instrument(level = "info", skip_all, err, fields(user_uuid))]
+async fn update_workspace_history_compaction_handler(
+  user_uuid: UserUuid,
+  state: Data<AppState>,
+  workspace_id: web::Path<Uuid>,
+  payload: Json<UpdateWorkspaceHistoryCompactionParams>,
+) -> Result<JsonAppResponse<WorkspaceHistoryCompaction>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  let setting =
+    update_workspace_history_compaction(&state.pg_pool, &workspace_id, payload.into_inner())
+      .await?;
+  Ok(AppResponse::Ok().with_data(setting).into())
+}
+
+#[instrument(level = "info", skip_all, err, fields(user_uuid))]
+async fn run_workspace_history_compaction_handler(
+  user_uuid: UserUuid,
+  state: Data<AppState>,
+  workspace_id: web::Path<Uuid>,
+) -> Result<JsonAppResponse<HistoryCompactionReport>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  let report = run_workspace_history_compaction(&state.pg_pool, &workspace_id).await?;
+  Ok(AppResponse::Ok().with_data(report).into())
+}
+
 #[instrument(skip_all, err)]
 async fn get_workspace_members_handler(
   user_uuid: UserUuid,
@@ -603,6 +1014,13 @@ async fn remove_workspace_member_handler(
     state.workspace_access_control.clone(),
   )
   .await?;
+  refresh_workspace_read_only_after_change(
+    &state.pg_pool,
+    &state.config.billing,
+    &state.workspace_read_only_cache,
+    &workspace_id,
+  )
+  .await;
 
   Ok(AppResponse::Ok().into())
 }
@@ -693,6 +1111,46 @@ async fn open_workspace_handler(
   Ok(AppResponse::Ok().with_data(workspace).into())
 }
 
+#[instrument(level = "debug", skip(state, payload), err)]
+async fn clone_workspace_handler(
+  user_uuid: UserUuid,
+  path: web::Path<Uuid>,
+  payload: Json<CloneWorkspaceParams>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<WorkspaceCloneTask>> {
+  let workspace_id = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  let task = clone_workspace(
+    &state.pg_pool,
+    state.workspace_access_control.clone(),
+    &state.collab_access_control_storage,
+    &state.redis_connection_manager,
+    state.config.collab.workspace_clone_max_size,
+    uid,
+    &user_uuid,
+    workspace_id,
+    payload.into_inner(),
+  )
+  .await?;
+  Ok(AppResponse::Ok().with_data(task).into())
+}
+
+#[instrument(level = "debug", skip(state), err)]
+async fn get_workspace_clone_task_handler(
+  user_uuid: UserUuid,
+  path: web::Path<(Uuid, Uuid)>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<WorkspaceCloneTask>> {
+  let (workspace_id, task_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let task = get_workspace_clone_task(&state.pg_pool, uid, &workspace_id, &task_id).await?;
+  Ok(AppResponse::Ok().with_data(task).into())
+}
+
 #[instrument(level = "debug", skip_all, err)]
 async fn leave_workspace_handler(
   user_uuid: UserUuid,
@@ -707,6 +1165,13 @@ async fn leave_workspace_handler(
     state.workspace_access_control.clone(),
   )
   .await?;
+  refresh_workspace_read_only_after_change(
+    &state.pg_pool,
+    &state.config.billing,
+    &state.workspace_read_only_cache,
+    &workspace_id,
+  )
+  .await;
   Ok(AppResponse::Ok().into())
 }
 
@@ -751,35 +1216,136 @@ async fn create_collab_handler(
   req: HttpRequest,
 ) -> Result<Json<AppResponse<()>>> {
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
-  let params = match req.headers().get(X_COMPRESSION_TYPE) {
-    None => serde_json::from_slice::<CreateCollabParams>(&payload).map_err(|err| {
-      AppError::InvalidRequest(format!(
-        "Failed to parse CreateCollabParams from JSON: {}",
-        err
-      ))
-    })?,
-    Some(_) => match compress_type_from_header_value(req.headers())? {
-      CompressionType::Brotli { buffer_size } => {
-        let decompress_data = blocking_decompress(payload.to_vec(), buffer_size).await?;
-        CreateCollabParams::from_bytes(&decompress_data).map_err(|err| {
-          AppError::InvalidRequest(format!(
-            "Failed to parse CreateCollabParams with brotli decompression data: {}",
-            err
-          ))
-        })?
-      },
-    },
-  };
-
-  let (params, workspace_id) = params.split();
+  let params = create_collab_params_from_payload(&payload, &req).await?;
+  create_collab(&state, uid, params).await?;
+  Ok(Json(AppResponse::Ok()))
+}
 
-  if params.object_id == workspace_id {
-    // Only the object with [CollabType::Folder] can have the same object_id as workspace_id. But
-    // it should use create workspace API
+/// Returns a presigned url to upload a collab that exceeds the payload limit of
+/// [create_collab_handler]. The uploaded body is the same as the one of [create_collab_handler]
+/// and the collab is created by [complete_collab_upload_handler].
+#[instrument(skip(state), err)]
+async fn create_collab_upload_handler(
+  user_uuid: UserUuid,
+  path: web::Path<(Uuid, Uuid)>,
+  payload: Json<CreateCollabUploadRequest>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<CreateCollabUploadResponse>>> {
+  let (workspace_id, object_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Write)
+    .await?;
+
+  let limit = state.config.application.payload_limits.collab_upload;
+  if payload.content_length as usize > limit {
     return Err(
-      AppError::InvalidRequest("object_id cannot be the same as workspace_id".to_string()).into(),
+      AppError::PayloadTooLarge(format!(
+        "collab size {} exceeds the limit {}",
+        payload.content_length, limit
+      ))
+      .into(),
     );
   }
+  let upload_id = Uuid::new_v4();
+  let presigned_url = state
+    .bucket_client
+    .gen_presigned_put_url(
+      &collab_upload_key(&workspace_id, &object_id, &upload_id),
+      "application/octet-stream",
+      payload.content_length,
+      COLLAB_UPLOAD_EXPIRES_SECS,
+    )
+    .await?;
+  Ok(Json(AppResponse::Ok().with_data(
+    CreateCollabUploadResponse {
+      upload_id: upload_id.to_string(),
+      presigned_url,
+    },
+  )))
+}
+
+#[instrument(skip(state, req), err)]
+async fn complete_collab_upload_handler(
+  user_uuid: UserUuid,
+  path: web::Path<(Uuid, Uuid, Uuid)>,
+  state: Data<AppState>,
+  req: HttpRequest,
+) -> Result<Json<AppResponse<()>>> {
+  let (workspace_id, object_id, upload_id) = path.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Write)
+    .await?;
+
+  let key = collab_upload_key(&workspace_id, &object_id, &upload_id);
+  let payload = state.bucket_client.get_blob(&key).await?.to_blob();
+  let result = async {
+    let params = create_collab_params_from_payload(&payload, &req).await?;
+    if params.workspace_id != workspace_id.to_string() || params.object_id != object_id.to_string()
+    {
+      return Err(AppError::InvalidRequest(
+        "the uploaded collab does not match the workspace_id or object_id".to_string(),
+      ));
+    }
+    create_collab(&state, uid, params).await
+  }
+  .await;
+  if let Err(err) = state.bucket_client.delete_blob(&key).await {
+    error!("Failed to delete uploaded collab {}: {}", key, err);
+  }
+  result?;
+  Ok(Json(AppResponse::Ok()))
+}
+
+const COLLAB_UPLOAD_EXPIRES_SECS: u64 = 10 * 60;
+
+fn collab_upload_key(workspace_id: &Uuid, object_id: &Uuid, upload_id: &Uuid) -> String {
+  format!("collab_upload/{}/{}/{}", workspace_id, object_id, upload_id)
+}
+
+async fn create_collab_params_from_payload(
+  payload: &[u8],
+  req: &HttpRequest,
+) -> Result<CreateCollabParams, AppError> {
+  let params = match req.headers().get(X_COMPRESSION_TYPE) {
+    None => serde_json::from_slice::<CreateCollabParams>(payload).map_err(|err| {
+      AppError::InvalidRequest(format!(
+        "Failed to parse CreateCollabParams from JSON: {}",
+        err
+      ))
+    })?,
+    Some(_) => match compress_type_from_header_value(req.headers())? {
+      CompressionType::Brotli { buffer_size } => {
+        let decompress_data = blocking_decompress(payload.to_vec(), buffer_size).await?;
+        CreateCollabParams::from_bytes(&decompress_data).map_err(|err| {
+          AppError::InvalidRequest(format!(
+            "Failed to parse CreateCollabParams with brotli decompression data: {}",
+            err
+          ))
+        })?
+      },
+    },
+  };
+  Ok(params)
+}
+
+async fn create_collab(
+  state: &AppState,
+  uid: i64,
+  params: CreateCollabParams,
+) -> Result<(), AppError> {
+  let (params, workspace_id) = params.split();
+
+  if params.object_id == workspace_id {
+    // Only the object with [CollabType::Folder] can have the same object_id as workspace_id. But
+    // it should use create workspace API
+    return Err(AppError::InvalidRequest(
+      "object_id cannot be the same as workspace_id".to_string(),
+    ));
+  }
 
   let collab = collab_from_encode_collab(&params.object_id, &params.encoded_collab_v1)
     .await
@@ -791,13 +1357,10 @@ async fn create_collab_handler(
     })?;
 
   if let Err(err) = params.collab_type.validate_require_data(&collab) {
-    return Err(
-      AppError::NoRequiredData(format!(
-        "collab doc state is not correct:{},{}",
-        params.object_id, err
-      ))
-      .into(),
-    );
+    return Err(AppError::NoRequiredData(format!(
+      "collab doc state is not correct:{},{}",
+      params.object_id, err
+    )));
   }
 
   if state
@@ -805,7 +1368,10 @@ async fn create_collab_handler(
     .can_index_workspace(&workspace_id)
     .await?
   {
-    if let Ok(text) = Document::open(collab).and_then(|doc| doc.to_plain_text(false, true)) {
+    if let Some(text) = state
+      .indexer_scheduler
+      .extract_text(&params.collab_type, &collab)
+    {
       let workspace_id_uuid =
         Uuid::parse_str(&workspace_id).map_err(|err| AppError::Internal(err.into()))?;
       let pending = UnindexedCollabTask::new(
@@ -841,7 +1407,7 @@ async fn create_collab_handler(
     .map_err(AppError::from)?;
   state.metrics.collab_metrics.observe_pg_tx(start.elapsed());
 
-  Ok(Json(AppResponse::Ok()))
+  Ok(())
 }
 
 #[instrument(skip(state, payload), err)]
@@ -886,6 +1452,7 @@ async fn batch_create_collab_handler(
     }
   }
   // Perform decompression and processing in a Rayon thread pool
+  let indexer_scheduler = state.indexer_scheduler.clone();
   let mut collab_params_list = tokio::task::spawn_blocking(move || match compress_type {
     CompressionType::Brotli { buffer_size } => offset_len_list
       .into_par_iter()
@@ -908,17 +1475,8 @@ async fn batch_create_collab_handler(
 
               match params.collab_type.validate_require_data(&collab) {
                 Ok(_) => {
-                  match params.collab_type {
-                    CollabType::Document => {
-                      let index_text =
-                        Document::open(collab).and_then(|doc| doc.to_plain_text(false, true));
-                      Some((Some(index_text), params))
-                    },
-                    _ => {
-                      // TODO(nathan): support other types
-                      Some((None, params))
-                    },
-                  }
+                  let index_text = indexer_scheduler.extract_text(&params.collab_type, &collab);
+                  Some((index_text, params))
                 },
                 Err(_) => None,
               }
@@ -963,18 +1521,15 @@ async fn batch_create_collab_handler(
           .indexer_scheduler
           .is_indexing_enabled(&p.1.collab_type)
       })
-      .flat_map(|value| match std::mem::take(&mut value.0) {
-        None => None,
-        Some(text) => text
-          .map(|text| {
-            UnindexedCollabTask::new(
-              workspace_id_uuid,
-              value.1.object_id.clone(),
-              value.1.collab_type.clone(),
-              UnindexedData::Text(text),
-            )
-          })
-          .ok(),
+      .flat_map(|value| {
+        std::mem::take(&mut value.0).map(|text| {
+          UnindexedCollabTask::new(
+            workspace_id_uuid,
+            value.1.object_id.clone(),
+            value.1.collab_type.clone(),
+            UnindexedData::Text(text),
+          )
+        })
       })
       .collect::<Vec<_>>();
   }
@@ -1130,6 +1685,10 @@ async fn post_web_update_handler(
       Action::Write,
     )
     .await?;
+  state
+    .collab_lock_cache
+    .enforce_unlocked(&workspace_id, &object_id.to_string())
+    .await?;
   let user = realtime_user_for_web_request(req.headers(), uid)?;
   trace!("create onetime web realtime user: {}", user);
 
@@ -1525,6 +2084,7 @@ async fn post_page_database_view_handler(
     &view_id,
     &payload.layout,
     payload.name.as_deref(),
+    payload.settings.clone(),
   )
   .await?;
   Ok(Json(AppResponse::Ok()))
@@ -1563,6 +2123,116 @@ async fn update_page_view_handler(
   Ok(Json(AppResponse::Ok()))
 }
 
+fn image_asset_form_config(payload_limits: &PayloadLimitSetting) -> MultipartFormConfig {
+  MultipartFormConfig::default()
+    .total_limit(payload_limits.image_asset)
+    .memory_limit(payload_limits.image_asset)
+}
+
+#[derive(MultipartForm)]
+#[multipart(duplicate_field = "deny")]
+struct ImageAssetForm {
+  file: MPBytes,
+}
+
+async fn put_workspace_icon_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  MultipartForm(form): MultipartForm<ImageAssetForm>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<ImageAsset>> {
+  let workspace_id = workspace_id.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Write)
+    .await?;
+  let asset = upload_workspace_icon(
+    &state.pg_pool,
+    &state.bucket_storage,
+    &state.redis_connection_manager,
+    &state.config.billing,
+    uid,
+    &workspace_id,
+    form.file.data.to_vec(),
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(asset)))
+}
+
+async fn put_page_icon_handler(
+  user_uuid: UserUuid,
+  path: web::Path<(Uuid, String)>,
+  MultipartForm(form): MultipartForm<ImageAssetForm>,
+  state: Data<AppState>,
+  server: Data<RealtimeServerAddr>,
+  req: HttpRequest,
+) -> Result<JsonAppResponse<ImageAsset>> {
+  put_page_image(
+    user_uuid,
+    path.into_inner(),
+    ImageAssetKind::Icon,
+    form,
+    state,
+    server,
+    req,
+  )
+  .await
+}
+
+async fn put_page_cover_handler(
+  user_uuid: UserUuid,
+  path: web::Path<(Uuid, String)>,
+  MultipartForm(form): MultipartForm<ImageAssetForm>,
+  state: Data<AppState>,
+  server: Data<RealtimeServerAddr>,
+  req: HttpRequest,
+) -> Result<JsonAppResponse<ImageAsset>> {
+  put_page_image(
+    user_uuid,
+    path.into_inner(),
+    ImageAssetKind::Cover,
+    form,
+    state,
+    server,
+    req,
+  )
+  .await
+}
+
+async fn put_page_image(
+  user_uuid: UserUuid,
+  (workspace_id, view_id): (Uuid, String),
+  kind: ImageAssetKind,
+  form: ImageAssetForm,
+  state: Data<AppState>,
+  server: Data<RealtimeServerAddr>,
+  req: HttpRequest,
+) -> Result<JsonAppResponse<ImageAsset>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .collab_access_control
+    .enforce_action(&workspace_id.to_string(), &uid, &view_id, Action::Write)
+    .await?;
+  let user = realtime_user_for_web_request(req.headers(), uid)?;
+  let asset = upload_view_image(
+    &state.metrics.appflowy_web_metrics,
+    server,
+    user,
+    &state.collab_access_control_storage,
+    &state.pg_pool,
+    &state.bucket_storage,
+    &state.redis_connection_manager,
+    &state.config.billing,
+    workspace_id,
+    &view_id,
+    kind,
+    form.file.data.to_vec(),
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(asset)))
+}
+
 async fn get_page_view_handler(
   user_uuid: UserUuid,
   path: web::Path<(Uuid, String)>,
@@ -1578,6 +2248,7 @@ async fn get_page_view_handler(
   let page_collab = get_page_view_collab(
     &state.pg_pool,
     &state.collab_access_control_storage,
+    &state.restricted_view_cache,
     uid,
     workspace_uuid,
     &view_id,
@@ -1654,905 +2325,2309 @@ async fn get_all_collab_snapshot_list_handler(
   Ok(Json(AppResponse::Ok().with_data(data)))
 }
 
-#[instrument(level = "debug", skip(payload, state), err)]
-async fn batch_get_collab_handler(
+#[instrument(level = "debug", skip(state), err)]
+async fn get_collab_lock_handler(
   user_uuid: UserUuid,
-  path: Path<String>,
+  path: web::Path<(Uuid, Uuid)>,
   state: Data<AppState>,
-  payload: Json<BatchQueryCollabParams>,
-) -> Result<Json<AppResponse<BatchQueryCollabResult>>> {
-  let workspace_id = path.into_inner();
-  let uid = state
-    .user_cache
-    .get_user_uid(&user_uuid)
-    .await
-    .map_err(AppResponseError::from)?;
-  let result = BatchQueryCollabResult(
-    state
-      .collab_access_control_storage
-      .batch_get_collab(&uid, &workspace_id, payload.into_inner().0, false)
-      .await,
-  );
-  Ok(Json(AppResponse::Ok().with_data(result)))
+) -> Result<Json<AppResponse<Option<AFCollabLock>>>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let (workspace_id, object_id) = path.into_inner();
+  let object_id = object_id.to_string();
+  state
+    .collab_access_control
+    .enforce_action(&workspace_id.to_string(), &uid, &object_id, Action::Read)
+    .await?;
+  let lock = state
+    .collab_lock_cache
+    .get_lock(&workspace_id, &object_id)
+    .await?;
+  Ok(Json(AppResponse::Ok().with_data(lock)))
 }
 
-#[instrument(skip(state, payload), err)]
-async fn update_collab_handler(
+#[instrument(level = "debug", skip(state), err)]
+async fn get_collab_edit_stats_handler(
   user_uuid: UserUuid,
-  payload: Json<CreateCollabParams>,
+  path: web::Path<(Uuid, Uuid)>,
+  query: web::Query<CollabEditStatsQuery>,
   state: Data<AppState>,
-) -> Result<Json<AppResponse<()>>> {
-  let (params, workspace_id) = payload.into_inner().split();
+) -> Result<Json<AppResponse<Option<AFCollabEditStats>>>> {
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
-
-  let create_params = CreateCollabParams::from((workspace_id.to_string(), params));
-  let (params, workspace_id) = create_params.split();
-  if state
-    .indexer_scheduler
-    .can_index_workspace(&workspace_id)
-    .await?
-  {
-    let workspace_id_uuid =
-      Uuid::parse_str(&workspace_id).map_err(|err| AppError::Internal(err.into()))?;
-
-    match params.collab_type {
-      CollabType::Document => {
-        let collab = collab_from_encode_collab(&params.object_id, &params.encoded_collab_v1)
-          .await
-          .map_err(|err| {
-            AppError::InvalidRequest(format!(
-              "Failed to create collab from encoded collab: {}",
-              err
-            ))
-          })?;
-        params
-          .collab_type
-          .validate_require_data(&collab)
-          .map_err(|err| {
-            AppError::NoRequiredData(format!(
-              "collab doc state is not correct:{},{}",
-              params.object_id, err
-            ))
-          })?;
-
-        if let Ok(text) = Document::open(collab).and_then(|doc| doc.to_plain_text(false, true)) {
-          let pending = UnindexedCollabTask::new(
-            workspace_id_uuid,
-            params.object_id.clone(),
-            params.collab_type.clone(),
-            UnindexedData::Text(text),
-          );
-          state
-            .indexer_scheduler
-            .index_pending_collab_one(pending, true)?;
-        }
-      },
-      _ => {
-        // TODO(nathan): support other collab type
-      },
-    }
-  }
-
+  let (workspace_id, object_id) = path.into_inner();
   state
-    .collab_access_control_storage
-    .queue_insert_or_update_collab(&workspace_id, &uid, params, false)
+    .collab_access_control
+    .enforce_action(
+      &workspace_id.to_string(),
+      &uid,
+      &object_id.to_string(),
+      Action::Read,
+    )
     .await?;
-  Ok(AppResponse::Ok().into())
+  let stats = get_collab_edit_stats(
+    &state.pg_pool,
+    &workspace_id,
+    &object_id,
+    query.into_inner().days,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(stats)))
 }
 
-#[instrument(level = "info", skip(state, payload), err)]
-async fn delete_collab_handler(
+#[instrument(level = "debug", skip(state), err)]
+async fn get_collab_archive_status_handler(
   user_uuid: UserUuid,
-  payload: Json<DeleteCollabParams>,
+  path: web::Path<(Uuid, Uuid)>,
   state: Data<AppState>,
-) -> Result<Json<AppResponse<()>>> {
-  let payload = payload.into_inner();
-  payload.validate().map_err(AppError::from)?;
-
-  let uid = state
-    .user_cache
-    .get_user_uid(&user_uuid)
-    .await
-    .map_err(AppResponseError::from)?;
-
+) -> Result<Json<AppResponse<AFCollabArchiveStatus>>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let (workspace_id, object_id) = path.into_inner();
   state
-    .collab_access_control_storage
-    .delete_collab(&payload.workspace_id, &uid, &payload.object_id)
-    .await
-    .map_err(AppResponseError::from)?;
-
-  Ok(AppResponse::Ok().into())
+    .collab_access_control
+    .enforce_action(
+      &workspace_id.to_string(),
+      &uid,
+      &object_id.to_string(),
+      Action::Read,
+    )
+    .await?;
+  let status = get_collab_archive_status(&state.pg_pool, &workspace_id, &object_id).await?;
+  Ok(Json(AppResponse::Ok().with_data(status)))
 }
 
-async fn put_workspace_default_published_view_handler(
+#[instrument(level = "debug", skip(state), err)]
+async fn get_changed_collabs_handler(
   user_uuid: UserUuid,
   workspace_id: web::Path<Uuid>,
-  payload: Json<UpdateDefaultPublishView>,
+  query: web::Query<ChangedCollabQuery>,
   state: Data<AppState>,
-) -> Result<Json<AppResponse<()>>> {
+) -> Result<Json<AppResponse<AFChangedCollabs>>> {
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let workspace_id = workspace_id.into_inner();
   state
     .workspace_access_control
-    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
     .await?;
-  let new_default_pub_view_id = payload.into_inner().view_id;
-  biz::workspace::publish::set_workspace_default_publish_view(
-    &state.pg_pool,
-    &workspace_id,
-    &new_default_pub_view_id,
-  )
-  .await?;
-  Ok(Json(AppResponse::Ok()))
+  let changes = get_changed_collabs(&state.pg_pool, &workspace_id, query.into_inner()).await?;
+  Ok(Json(AppResponse::Ok().with_data(changes)))
 }
 
-async fn delete_workspace_default_published_view_handler(
+#[instrument(level = "debug", skip(state), err)]
+async fn list_workspace_collabs_handler(
   user_uuid: UserUuid,
   workspace_id: web::Path<Uuid>,
+  query: web::Query<WorkspaceCollabsQuery>,
   state: Data<AppState>,
-) -> Result<Json<AppResponse<()>>> {
+) -> Result<Json<AppResponse<AFWorkspaceCollabs>>> {
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let workspace_id = workspace_id.into_inner();
   state
     .workspace_access_control
-    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
-    .await?;
-  biz::workspace::publish::unset_workspace_default_publish_view(&state.pg_pool, &workspace_id)
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
     .await?;
-  Ok(Json(AppResponse::Ok()))
+  let collabs = list_workspace_collabs(
+    &state.pg_pool,
+    &state.collab_access_control_storage,
+    &state.restricted_view_cache,
+    uid,
+    workspace_id,
+    query.into_inner(),
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(collabs)))
+}
+
+#[instrument(level = "debug", skip(state), err)]
+async fn render_collab_handler(
+  user_uuid: UserUuid,
+  path: web::Path<(Uuid, Uuid)>,
+  query: web::Query<RenderCollabQuery>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<String>>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let (workspace_id, object_id) = path.into_inner();
+  let (workspace_id, object_id) = (workspace_id.to_string(), object_id.to_string());
+  state
+    .collab_access_control
+    .enforce_action(&workspace_id, &uid, &object_id, Action::Read)
+    .await?;
+  let content = render_document(
+    &state.collab_access_control_storage,
+    uid,
+    &workspace_id,
+    &object_id,
+    query.into_inner().format,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(content)))
+}
+
+#[instrument(level = "debug", skip(state), err)]
+async fn export_pdf_handler(
+  user_uuid: UserUuid,
+  path: web::Path<(Uuid, Uuid)>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<ExportTaskDetail>>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let (workspace_id, object_id) = path.into_inner();
+  state
+    .collab_access_control
+    .enforce_action(
+      &workspace_id.to_string(),
+      &uid,
+      &object_id.to_string(),
+      Action::Read,
+    )
+    .await?;
+  let task = create_pdf_export_task(
+    &state.collab_access_control_storage,
+    &state.bucket_client,
+    &state.redis_connection_manager,
+    &state.pg_pool,
+    uid,
+    &user_uuid,
+    workspace_id,
+    object_id,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(task)))
+}
+
+#[instrument(level = "debug", skip(state), err)]
+async fn export_workspace_archive_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<ExportTaskDetail>>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let workspace_id = workspace_id.into_inner();
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  let task = create_workspace_export_task(
+    &state.bucket_client,
+    &state.redis_connection_manager,
+    &state.pg_pool,
+    uid,
+    &user_uuid,
+    workspace_id,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(task)))
+}
+
+#[instrument(level = "debug", skip(state), err)]
+async fn get_export_task_handler(
+  user_uuid: UserUuid,
+  path: web::Path<(Uuid, Uuid)>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<ExportTaskDetail>>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let (workspace_id, task_id) = path.into_inner();
+  let task = get_export_task(
+    &state.bucket_client,
+    &state.pg_pool,
+    uid,
+    &workspace_id,
+    &task_id,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(task)))
+}
+
+#[instrument(level = "debug", skip(state, payload, server), err)]
+async fn lock_collab_handler(
+  user_uuid: UserUuid,
+  path: web::Path<(Uuid, Uuid)>,
+  payload: Json<LockCollabParams>,
+  state: Data<AppState>,
+  server: Data<RealtimeServerAddr>,
+) -> Result<Json<AppResponse<AFCollabLock>>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let (workspace_id, object_id) = path.into_inner();
+  state
+    .collab_access_control
+    .enforce_access_level(
+      &workspace_id.to_string(),
+      &uid,
+      &object_id.to_string(),
+      AFAccessLevel::FullAccess,
+    )
+    .await?;
+  let lock = lock_collab(
+    &state.collab_lock_cache,
+    server,
+    uid,
+    workspace_id,
+    object_id,
+    payload.into_inner().reason,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(lock)))
+}
+
+#[instrument(level = "debug", skip(state, server), err)]
+async fn unlock_collab_handler(
+  user_uuid: UserUuid,
+  path: web::Path<(Uuid, Uuid)>,
+  state: Data<AppState>,
+  server: Data<RealtimeServerAddr>,
+) -> Result<Json<AppResponse<()>>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let (workspace_id, object_id) = path.into_inner();
+  state
+    .collab_access_control
+    .enforce_access_level(
+      &workspace_id.to_string(),
+      &uid,
+      &object_id.to_string(),
+      AFAccessLevel::FullAccess,
+    )
+    .await?;
+  unlock_collab(
+    &state.collab_lock_cache,
+    server,
+    uid,
+    workspace_id,
+    object_id,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok()))
+}
+
+#[instrument(level = "debug", skip(state, payload), err)]
+async fn create_collab_checkpoint_handler(
+  user_uuid: UserUuid,
+  path: web::Path<(Uuid, Uuid)>,
+  payload: Json<CreateCollabCheckpointParams>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<AFCollabCheckpoint>>> {
+  let uid = state
+    .user_cache
+    .get_user_uid(&user_uuid)
+    .await
+    .map_err(AppResponseError::from)?;
+  let (workspace_id, object_id) = path.into_inner();
+  let (workspace_id, object_id) = (workspace_id.to_string(), object_id.to_string());
+  state
+    .collab_access_control
+    .enforce_action(&workspace_id, &uid, &object_id, Action::Write)
+    .await?;
+  let params = payload.into_inner();
+  let checkpoint = create_collab_checkpoint(
+    &state.collab_access_control_storage,
+    uid,
+    &workspace_id,
+    &object_id,
+    params.label,
+    params.collab_type,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(checkpoint)))
+}
+
+#[instrument(level = "debug", skip_all, err)]
+async fn revert_collab_checkpoint_handler(
+  user_uuid: UserUuid,
+  path: web::Path<(Uuid, Uuid, i64)>,
+  payload: Json<RevertCollabCheckpointParams>,
+  state: Data<AppState>,
+  server: Data<RealtimeServerAddr>,
+  req: HttpRequest,
+) -> Result<Json<AppResponse<AFCollabCheckpoint>>> {
+  let uid = state
+    .user_cache
+    .get_user_uid(&user_uuid)
+    .await
+    .map_err(AppResponseError::from)?;
+  let (workspace_id, object_id, checkpoint_id) = path.into_inner();
+  state
+    .collab_access_control
+    .enforce_action(
+      &workspace_id.to_string(),
+      &uid,
+      &object_id.to_string(),
+      Action::Write,
+    )
+    .await?;
+  state
+    .collab_lock_cache
+    .enforce_unlocked(&workspace_id, &object_id.to_string())
+    .await?;
+  let user = realtime_user_for_web_request(req.headers(), uid)?;
+  let backup = revert_collab_to_checkpoint(
+    &state.metrics.appflowy_web_metrics,
+    server,
+    user,
+    &state.collab_access_control_storage,
+    workspace_id,
+    object_id,
+    checkpoint_id,
+    payload.into_inner().collab_type,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(backup)))
+}
+
+#[instrument(level = "debug", skip(state), err)]
+async fn get_collab_history_handler(
+  user_uuid: UserUuid,
+  path: web::Path<(Uuid, Uuid)>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<AFCollabHistory>>> {
+  let uid = state
+    .user_cache
+    .get_user_uid(&user_uuid)
+    .await
+    .map_err(AppResponseError::from)?;
+  let (workspace_id, object_id) = path.into_inner();
+  let (workspace_id, object_id) = (workspace_id.to_string(), object_id.to_string());
+  state
+    .collab_access_control
+    .enforce_action(&workspace_id, &uid, &object_id, Action::Read)
+    .await?;
+  let history = get_collab_history(
+    &state.collab_access_control_storage,
+    &workspace_id,
+    &object_id,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(history)))
+}
+
+#[instrument(level = "debug", skip(payload, state), err)]
+async fn batch_get_collab_handler(
+  user_uuid: UserUuid,
+  path: Path<String>,
+  state: Data<AppState>,
+  payload: Json<BatchQueryCollabParams>,
+) -> Result<Json<AppResponse<BatchQueryCollabResult>>> {
+  let workspace_id = path.into_inner();
+  let uid = state
+    .user_cache
+    .get_user_uid(&user_uuid)
+    .await
+    .map_err(AppResponseError::from)?;
+  let result = BatchQueryCollabResult(
+    state
+      .collab_access_control_storage
+      .batch_get_collab(&uid, &workspace_id, payload.into_inner().0, false)
+      .await,
+  );
+  Ok(Json(AppResponse::Ok().with_data(result)))
+}
+
+/// Same as [batch_get_collab_handler], but the results are streamed as newline delimited JSON, one
+/// `QueryCollabStreamItem` per object, instead of being buffered into a single response.
+#[instrument(level = "debug", skip(payload, state), err)]
+async fn stream_batch_get_collab_handler(
+  user_uuid: UserUuid,
+  path: Path<String>,
+  state: Data<AppState>,
+  payload: Json<BatchQueryCollabParams>,
+) -> Result<HttpResponse> {
+  let workspace_id = path.into_inner();
+  let uid = state
+    .user_cache
+    .get_user_uid(&user_uuid)
+    .await
+    .map_err(AppResponseError::from)?;
+  let stream = stream_batch_get_collab(
+    state.collab_access_control_storage.clone(),
+    uid,
+    workspace_id,
+    payload.into_inner().0,
+  );
+  Ok(
+    HttpResponse::Ok()
+      .content_type("application/x-ndjson")
+      .streaming(stream),
+  )
+}
+
+/// Loads the given collabs into the collab memory cache ahead of editing. Clients can call it when
+/// a folder is opened, so that the first editor of a large document doesn't pay the full load cost.
+#[instrument(level = "debug", skip(state, payload), err)]
+async fn warm_up_collab_handler(
+  user_uuid: UserUuid,
+  path: Path<Uuid>,
+  state: Data<AppState>,
+  payload: Json<WarmUpCollabParams>,
+) -> Result<Json<AppResponse<WarmUpCollabResult>>> {
+  let workspace_id = path.into_inner().to_string();
+  let params = payload.into_inner();
+  params.validate().map_err(AppError::from)?;
+
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Read)
+    .await?;
+
+  let budget_bytes = get_env_var("APPFLOWY_COLLAB_WARM_UP_BUDGET_BYTES", "67108864")
+    .parse::<usize>()
+    .unwrap_or(64 * 1024 * 1024);
+  let result = state
+    .collab_cache
+    .warm_up(&workspace_id, params.collabs, budget_bytes)
+    .await;
+  Ok(Json(AppResponse::Ok().with_data(result)))
+}
+
+#[instrument(skip(state, payload), err)]
+async fn update_collab_handler(
+  user_uuid: UserUuid,
+  payload: Json<CreateCollabParams>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<()>>> {
+  let (params, workspace_id) = payload.into_inner().split();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+
+  let create_params = CreateCollabParams::from((workspace_id.to_string(), params));
+  let (params, workspace_id) = create_params.split();
+  let workspace_id_uuid =
+    Uuid::parse_str(&workspace_id).map_err(|err| AppError::Internal(err.into()))?;
+  // the write is checked again by the storage, but the collab must not be indexed before
+  state
+    .collab_lock_cache
+    .enforce_unlocked(&workspace_id_uuid, &params.object_id)
+    .await?;
+  if state
+    .indexer_scheduler
+    .can_index_workspace(&workspace_id)
+    .await?
+  {
+    if state
+      .indexer_scheduler
+      .is_indexing_enabled(&params.collab_type)
+    {
+      let collab = collab_from_encode_collab(&params.object_id, &params.encoded_collab_v1)
+        .await
+        .map_err(|err| {
+          AppError::InvalidRequest(format!(
+            "Failed to create collab from encoded collab: {}",
+            err
+          ))
+        })?;
+      params
+        .collab_type
+        .validate_require_data(&collab)
+        .map_err(|err| {
+          AppError::NoRequiredData(format!(
+            "collab doc state is not correct:{},{}",
+            params.object_id, err
+          ))
+        })?;
+
+      if let Some(text) = state
+        .indexer_scheduler
+        .extract_text(&params.collab_type, &collab)
+      {
+        let pending = UnindexedCollabTask::new(
+          workspace_id_uuid,
+          params.object_id.clone(),
+          params.collab_type.clone(),
+          UnindexedData::Text(text),
+        );
+        state
+          .indexer_scheduler
+          .index_pending_collab_one(pending, true)?;
+      }
+    }
+  }
+
+  state
+    .collab_access_control_storage
+    .queue_insert_or_update_collab(&workspace_id, &uid, params, false)
+    .await?;
+  Ok(AppResponse::Ok().into())
+}
+
+#[instrument(level = "info", skip(state, payload), err)]
+async fn delete_collab_handler(
+  user_uuid: UserUuid,
+  payload: Json<DeleteCollabParams>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<()>>> {
+  let payload = payload.into_inner();
+  payload.validate().map_err(AppError::from)?;
+
+  let uid = state
+    .user_cache
+    .get_user_uid(&user_uuid)
+    .await
+    .map_err(AppResponseError::from)?;
+
+  state
+    .collab_access_control_storage
+    .delete_collab(&payload.workspace_id, &uid, &payload.object_id)
+    .await
+    .map_err(AppResponseError::from)?;
+
+  Ok(AppResponse::Ok().into())
+}
+
+async fn put_workspace_default_published_view_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  payload: Json<UpdateDefaultPublishView>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<()>>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  let new_default_pub_view_id = payload.into_inner().view_id;
+  biz::workspace::publish::set_workspace_default_publish_view(
+    &state.pg_pool,
+    &workspace_id,
+    &new_default_pub_view_id,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok()))
+}
+
+async fn delete_workspace_default_published_view_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<()>>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  biz::workspace::publish::unset_workspace_default_publish_view(&state.pg_pool, &workspace_id)
+    .await?;
+  Ok(Json(AppResponse::Ok()))
 }
 
 async fn get_workspace_published_default_info_handler(
   workspace_id: web::Path<Uuid>,
   state: Data<AppState>,
-) -> Result<Json<AppResponse<PublishInfo>>> {
+) -> Result<Json<AppResponse<PublishInfo>>> {
+  let workspace_id = workspace_id.into_inner();
+  let info =
+    biz::workspace::publish::get_workspace_default_publish_view_info(&state.pg_pool, &workspace_id)
+      .await?;
+  Ok(Json(AppResponse::Ok().with_data(info)))
+}
+
+async fn put_publish_namespace_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  payload: Json<UpdatePublishNamespace>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<()>>> {
+  let workspace_id = workspace_id.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  let UpdatePublishNamespace {
+    old_namespace,
+    new_namespace,
+  } = payload.into_inner();
+  biz::workspace::publish::set_workspace_namespace(
+    &state.pg_pool,
+    &workspace_id,
+    &old_namespace,
+    &new_namespace,
+  )
+  .await?;
+  state
+    .published_collab_store
+    .invalidate_workspace_cache(&workspace_id)
+    .await;
+  Ok(Json(AppResponse::Ok()))
+}
+
+async fn list_publish_domains_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<Vec<PublishDomainInfo>>>> {
+  let workspace_id = workspace_id.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  let domains = biz::workspace::publish_domain::list_publish_domains(
+    &state.pg_pool,
+    &state.config.published_collab,
+    &workspace_id,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(domains)))
+}
+
+async fn post_publish_domain_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  payload: Json<AddPublishDomain>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<PublishDomainInfo>>> {
+  let workspace_id = workspace_id.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  let domain = biz::workspace::publish_domain::add_publish_domain(
+    &state.pg_pool,
+    &state.config.published_collab,
+    &workspace_id,
+    &payload.domain,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(domain)))
+}
+
+async fn verify_publish_domain_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(Uuid, String)>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<PublishDomainInfo>>> {
+  let (workspace_id, domain) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  let domain = biz::workspace::publish_domain::verify_publish_domain(
+    &state.pg_pool,
+    &state.config.published_collab,
+    &workspace_id,
+    &domain,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(domain)))
+}
+
+async fn delete_publish_domain_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(Uuid, String)>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<()>>> {
+  let (workspace_id, domain) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  biz::workspace::publish_domain::remove_publish_domain(&state.pg_pool, &workspace_id, &domain)
+    .await?;
+  Ok(Json(AppResponse::Ok()))
+}
+
+async fn get_publish_namespace_by_host_handler(
+  req: HttpRequest,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<String>>> {
+  let host = req.connection_info().host().to_string();
+  let namespace =
+    biz::workspace::publish_domain::get_publish_namespace_by_host(&state.pg_pool, &host).await?;
+  Ok(Json(AppResponse::Ok().with_data(namespace)))
+}
+
+async fn get_publish_domain_tls_check_handler(
+  query: web::Query<PublishDomainQuery>,
+  state: Data<AppState>,
+) -> Result<HttpResponse> {
+  let verified =
+    biz::workspace::publish_domain::is_publish_domain_verified(&state.pg_pool, &query.domain)
+      .await?;
+  if verified {
+    Ok(HttpResponse::Ok().finish())
+  } else {
+    Ok(HttpResponse::NotFound().finish())
+  }
+}
+
+async fn get_v1_published_collab_by_host_handler(
+  publish_name: web::Path<String>,
+  req: HttpRequest,
+  state: Data<AppState>,
+) -> Result<HttpResponse> {
+  let host = req.connection_info().host().to_string();
+  let namespace =
+    biz::workspace::publish_domain::get_publish_namespace_by_host(&state.pg_pool, &host).await?;
+  get_v1_published_collab_handler(
+    web::Path::from((namespace, publish_name.into_inner())),
+    state,
+    req,
+  )
+  .await
+}
+
+async fn get_published_collab_blob_by_host_handler(
+  publish_name: web::Path<String>,
+  req: HttpRequest,
+  state: Data<AppState>,
+) -> Result<HttpResponse> {
+  let host = req.connection_info().host().to_string();
+  let namespace =
+    biz::workspace::publish_domain::get_publish_namespace_by_host(&state.pg_pool, &host).await?;
+  get_published_collab_blob_handler(
+    web::Path::from((namespace, publish_name.into_inner())),
+    state,
+    req,
+  )
+  .await
+}
+
+async fn get_publish_namespace_handler(
+  workspace_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<String>>> {
+  let workspace_id = workspace_id.into_inner();
+  let namespace =
+    biz::workspace::publish::get_workspace_publish_namespace(&state.pg_pool, &workspace_id).await?;
+  Ok(Json(AppResponse::Ok().with_data(namespace)))
+}
+
+async fn get_default_published_collab_info_meta_handler(
+  publish_namespace: web::Path<String>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<PublishInfoMeta<serde_json::Value>>>> {
+  let publish_namespace = publish_namespace.into_inner();
+  let (info, meta) =
+    get_workspace_default_publish_view_info_meta(&state.pg_pool, &publish_namespace).await?;
+  Ok(Json(
+    AppResponse::Ok().with_data(PublishInfoMeta { info, meta }),
+  ))
+}
+
+async fn get_v1_published_collab_handler(
+  path_param: web::Path<(String, String)>,
+  state: Data<AppState>,
+  req: HttpRequest,
+) -> Result<HttpResponse> {
+  let (workspace_namespace, publish_name) = path_param.into_inner();
+  let metadata = state
+    .published_collab_store
+    .get_collab_metadata(&workspace_namespace, &publish_name)
+    .await?;
+  state.publish_analytics.record(
+    req.headers(),
+    req.connection_info().host(),
+    &workspace_namespace,
+    &publish_name,
+  );
+  let body = serde_json::to_vec(&AppResponse::Ok().with_data(metadata))
+    .map_err(|err| AppError::Internal(err.into()))?;
+  Ok(published_collab_response(
+    &req,
+    &state,
+    body,
+    "application/json",
+  ))
+}
+
+async fn get_published_collab_blob_handler(
+  path_param: web::Path<(String, String)>,
+  state: Data<AppState>,
+  req: HttpRequest,
+) -> Result<HttpResponse> {
+  let (publish_namespace, publish_name) = path_param.into_inner();
+  let collab_data = state
+    .published_collab_store
+    .get_collab_blob_by_publish_namespace(&publish_namespace, &publish_name)
+    .await?;
+  Ok(published_collab_response(
+    &req,
+    &state,
+    collab_data,
+    "application/octet-stream",
+  ))
+}
+
+/// Published collabs are public, so they can be cached by the browsers and the CDN in front of
+/// the server. The ETag lets the clients revalidate a stale copy without downloading it again.
+fn published_collab_response(
+  req: &HttpRequest,
+  state: &AppState,
+  body: Vec<u8>,
+  content_type: &str,
+) -> HttpResponse {
+  let max_age = state.config.published_collab.cache_control_max_age_secs;
+  let cache_control = format!(
+    "public, max-age={}, stale-while-revalidate={}",
+    max_age,
+    max_age * 10
+  );
+  let etag = format!("\"{:x}\"", Sha256::digest(&body));
+  let not_modified = req
+    .headers()
+    .get(actix_web::http::header::IF_NONE_MATCH)
+    .and_then(|value| value.to_str().ok())
+    .map_or(false, |value| {
+      value
+        .split(',')
+        .any(|tag| tag.trim() == etag || tag.trim() == "*")
+    });
+  let mut builder = if not_modified {
+    HttpResponse::NotModified()
+  } else {
+    HttpResponse::Ok()
+  };
+  builder
+    .insert_header((actix_web::http::header::CACHE_CONTROL, cache_control))
+    .insert_header((actix_web::http::header::ETAG, etag));
+  if not_modified {
+    builder.finish()
+  } else {
+    builder.content_type(content_type).body(body)
+  }
+}
+
+async fn get_publish_feed_handler(
+  path_param: web::Path<(String, String)>,
+  state: Data<AppState>,
+) -> Result<HttpResponse> {
+  let (publish_namespace, file_name) = path_param.into_inner();
+  let file = biz::workspace::publish_feed::PublishFeedFile::try_from(file_name.as_str())?;
+  let data = biz::workspace::publish_feed::get_publish_feed(
+    &state.pg_pool,
+    &state.bucket_client,
+    &state.redis_connection_manager,
+    &publish_namespace,
+    file,
+  )
+  .await?;
+  let max_age = state.config.published_collab.cache_control_max_age_secs;
+  Ok(
+    HttpResponse::Ok()
+      .content_type(file.content_type())
+      .insert_header((
+        actix_web::http::header::CACHE_CONTROL,
+        format!("public, max-age={}", max_age),
+      ))
+      .body(data),
+  )
+}
+
+async fn post_published_duplicate_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<String>,
+  state: Data<AppState>,
+  params: Json<PublishedDuplicate>,
+) -> Result<Json<AppResponse<DuplicatePublishedPageResponse>>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Write)
+    .await?;
+  let params = params.into_inner();
+  let root_view_id_for_duplicate =
+    biz::workspace::publish_dup::duplicate_published_collab_to_workspace(
+      &state.pg_pool,
+      state.bucket_client.clone(),
+      state.collab_access_control_storage.clone(),
+      uid,
+      params.published_view_id,
+      workspace_id.into_inner(),
+      params.dest_view_id,
+    )
+    .await?;
+
+  Ok(Json(AppResponse::Ok().with_data(
+    DuplicatePublishedPageResponse {
+      view_id: root_view_id_for_duplicate,
+    },
+  )))
+}
+
+async fn list_published_collab_info_handler(
+  workspace_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<Vec<PublishInfoView>>>> {
+  let publish_infos = biz::workspace::publish::list_collab_publish_info(
+    state.published_collab_store.as_ref(),
+    &state.collab_access_control_storage,
+    &workspace_id.into_inner(),
+  )
+  .await?;
+
+  Ok(Json(AppResponse::Ok().with_data(publish_infos)))
+}
+
+// Deprecated since 0.7.4
+async fn get_published_collab_info_handler(
+  view_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<PublishInfo>>> {
+  let view_id = view_id.into_inner();
+  let collab_data = state
+    .published_collab_store
+    .get_collab_publish_info(&view_id)
+    .await?;
+  if collab_data.unpublished_timestamp.is_some() {
+    return Err(AppError::RecordNotFound("Collab is unpublished".to_string()).into());
+  }
+  Ok(Json(AppResponse::Ok().with_data(collab_data)))
+}
+
+async fn get_v1_published_collab_info_handler(
+  view_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<PublishInfo>>> {
+  let view_id = view_id.into_inner();
+  let collab_data = state
+    .published_collab_store
+    .get_collab_publish_info(&view_id)
+    .await?;
+  Ok(Json(AppResponse::Ok().with_data(collab_data)))
+}
+
+async fn get_published_collab_comment_handler(
+  view_id: web::Path<Uuid>,
+  optional_user_uuid: OptionalUserUuid,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<GlobalComments>> {
+  let view_id = view_id.into_inner();
+  let comments =
+    get_comments_on_published_view(&state.pg_pool, &view_id, &optional_user_uuid).await?;
+  let resp = GlobalComments { comments };
+  Ok(Json(AppResponse::Ok().with_data(resp)))
+}
+
+async fn post_published_collab_comment_handler(
+  user_uuid: UserUuid,
+  view_id: web::Path<Uuid>,
+  state: Data<AppState>,
+  data: Json<CreateGlobalCommentParams>,
+) -> Result<JsonAppResponse<()>> {
+  let view_id = view_id.into_inner();
+  create_comment_on_published_view(
+    &state.pg_pool,
+    &view_id,
+    &data.reply_comment_id,
+    &data.content,
+    &user_uuid,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok()))
+}
+
+async fn delete_published_collab_comment_handler(
+  user_uuid: UserUuid,
+  view_id: web::Path<Uuid>,
+  state: Data<AppState>,
+  data: Json<DeleteGlobalCommentParams>,
+) -> Result<JsonAppResponse<()>> {
+  let view_id = view_id.into_inner();
+  remove_comment_on_published_view(&state.pg_pool, &view_id, &data.comment_id, &user_uuid).await?;
+  Ok(Json(AppResponse::Ok()))
+}
+
+async fn post_published_collab_report_handler(
+  optional_user_uuid: OptionalUserUuid,
+  view_id: web::Path<Uuid>,
+  state: Data<AppState>,
+  data: Json<ReportPublishedViewParams>,
+) -> Result<JsonAppResponse<()>> {
+  biz::workspace::publish_moderation::report_published_view(
+    &state.pg_pool,
+    &state.metrics.published_collab_metrics,
+    &view_id.into_inner(),
+    optional_user_uuid.as_uuid(),
+    data.into_inner(),
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok()))
+}
+
+async fn get_published_collab_reaction_handler(
+  view_id: web::Path<Uuid>,
+  query: web::Query<GetReactionQueryParams>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<Reactions>> {
+  let view_id = view_id.into_inner();
+  let reactions =
+    get_reactions_on_published_view(&state.pg_pool, &view_id, &query.comment_id).await?;
+  let resp = Reactions { reactions };
+  Ok(Json(AppResponse::Ok().with_data(resp)))
+}
+
+async fn post_published_collab_reaction_handler(
+  user_uuid: UserUuid,
+  view_id: web::Path<Uuid>,
+  data: Json<CreateReactionParams>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<()>> {
+  let view_id = view_id.into_inner();
+  create_reaction_on_comment(
+    &state.pg_pool,
+    &data.comment_id,
+    &view_id,
+    &data.reaction_type,
+    &user_uuid,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok()))
+}
+
+async fn delete_published_collab_reaction_handler(
+  user_uuid: UserUuid,
+  data: Json<DeleteReactionParams>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<()>> {
+  remove_reaction_on_comment(
+    &state.pg_pool,
+    &data.comment_id,
+    &data.reaction_type,
+    &user_uuid,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok()))
+}
+
+// FIXME: This endpoint currently has a different behaviour from the publish page endpoint,
+// as it doesn't accept parameters. We will need to deprecate this endpoint and use a new
+// one that accepts parameters.
+async fn post_publish_collabs_handler(
+  workspace_id: web::Path<Uuid>,
+  user_uuid: UserUuid,
+  payload: Payload,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<()>>> {
+  let workspace_id = workspace_id.into_inner();
+
+  let mut accumulator = Vec::<PublishCollabItem<serde_json::Value, Vec<u8>>>::new();
+  let mut payload_reader: PayloadReader = PayloadReader::new(payload);
+
+  loop {
+    let meta: PublishCollabMetadata<serde_json::Value> = {
+      let meta_len = payload_reader.read_u32_little_endian().await?;
+      if meta_len > 4 * 1024 * 1024 {
+        // 4MiB Limit for metadata
+        return Err(AppError::InvalidRequest(String::from("metadata too large")).into());
+      }
+      if meta_len == 0 {
+        break;
+      }
+
+      let mut meta_buffer = vec![0; meta_len as usize];
+      payload_reader.read_exact(&mut meta_buffer).await?;
+      serde_json::from_slice(&meta_buffer)?
+    };
+
+    let data = {
+      let data_len = payload_reader.read_u32_little_endian().await?;
+      if data_len > 32 * 1024 * 1024 {
+        // 32MiB Limit for data
+        return Err(AppError::InvalidRequest(String::from("data too large")).into());
+      }
+      let mut data_buffer = vec![0; data_len as usize];
+      payload_reader.read_exact(&mut data_buffer).await?;
+      data_buffer
+    };
+
+    // Set comments_enabled and duplicate_enabled to true by default, as this is the default
+    // behaviour for the older web version.
+    accumulator.push(PublishCollabItem {
+      meta,
+      data,
+      comments_enabled: true,
+      duplicate_enabled: true,
+    });
+  }
+
+  if accumulator.is_empty() {
+    return Err(
+      AppError::InvalidRequest(String::from("did not receive any data to publish")).into(),
+    );
+  }
+  state
+    .published_collab_store
+    .publish_collabs(accumulator, &workspace_id, &user_uuid)
+    .await?;
+  Ok(Json(AppResponse::Ok()))
+}
+
+async fn get_publish_analytics_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  query: web::Query<PublishAnalyticsQuery>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<PublishAnalytics>>> {
+  let workspace_id = workspace_id.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
+    .await?;
+  let analytics = biz::workspace::publish_analytics::get_publish_analytics(
+    &state.pg_pool,
+    &workspace_id,
+    query.into_inner(),
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(analytics)))
+}
+
+async fn patch_published_collabs_handler(
+  workspace_id: web::Path<Uuid>,
+  user_uuid: UserUuid,
+  state: Data<AppState>,
+  patches: Json<Vec<PatchPublishedCollab>>,
+) -> Result<Json<AppResponse<()>>> {
+  let workspace_id = workspace_id.into_inner();
+  if patches.is_empty() {
+    return Err(AppError::InvalidRequest("No patches provided".to_string()).into());
+  }
+  state
+    .published_collab_store
+    .patch_collabs(&workspace_id, &user_uuid, &patches)
+    .await?;
+  Ok(Json(AppResponse::Ok()))
+}
+
+async fn delete_published_collabs_handler(
+  workspace_id: web::Path<Uuid>,
+  user_uuid: UserUuid,
+  state: Data<AppState>,
+  view_ids: Json<Vec<Uuid>>,
+) -> Result<Json<AppResponse<()>>> {
+  let workspace_id = workspace_id.into_inner();
+  let view_ids = view_ids.into_inner();
+  if view_ids.is_empty() {
+    return Err(AppError::InvalidRequest("No view_ids provided".to_string()).into());
+  }
+  state
+    .published_collab_store
+    .unpublish_collabs(&workspace_id, &view_ids, &user_uuid)
+    .await?;
+  Ok(Json(AppResponse::Ok()))
+}
+
+#[instrument(level = "info", skip_all, err)]
+async fn post_realtime_message_stream_handler(
+  user_uuid: UserUuid,
+  mut payload: Payload,
+  server: Data<RealtimeServerAddr>,
+  state: Data<AppState>,
+  req: HttpRequest,
+) -> Result<Json<AppResponse<()>>> {
+  let device_id = device_id_from_headers(req.headers())
+    .map(|s| s.to_string())
+    .unwrap_or_else(|_| "".to_string());
+  let uid = state
+    .user_cache
+    .get_user_uid(&user_uuid)
+    .await
+    .map_err(AppResponseError::from)?;
+
+  let mut bytes = BytesMut::new();
+  while let Some(item) = payload.next().await {
+    bytes.extend_from_slice(&item?);
+  }
+
+  let device_id = device_id.to_string();
+
+  let message = parser_realtime_msg(bytes.freeze(), req.clone()).await?;
+  let stream_message = ClientHttpStreamMessage {
+    uid,
+    device_id,
+    message,
+  };
+
+  // When the server is under heavy load, try_send may fail. In client side, it will retry to send
+  // the message later.
+  match server.try_send(stream_message) {
+    Ok(_) => return Ok(Json(AppResponse::Ok())),
+    Err(err) => Err(
+      AppError::ServiceTemporaryUnavailable(format!(
+        "Failed to send message to websocket server, error:{}",
+        err
+      ))
+      .into(),
+    ),
+  }
+}
+
+async fn get_workspace_usage_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<WorkspaceUsage>>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  let res =
+    biz::workspace::ops::get_workspace_document_total_bytes(&state.pg_pool, &workspace_id).await?;
+  Ok(Json(AppResponse::Ok().with_data(res)))
+}
+
+#[utoipa::path(
+  get,
+  path = "/api/workspace/{workspace_id}/billing",
+  tag = "billing",
+  params(("workspace_id" = Uuid, Path, description = "Workspace id")),
+  responses(
+    (status = 200, description = "The plan, usage, limits and renewal date of the workspace", body = WorkspaceBilling),
+    (status = "default", description = "Error response", body = AppResponseError),
+  ),
+  security(("bearer_auth" = []))
+)]
+async fn get_workspace_billing_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<WorkspaceBilling>>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let workspace_id = workspace_id.into_inner();
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
+    .await?;
+  let billing = get_workspace_billing(
+    &state.pg_pool,
+    &state.config.billing,
+    &state.workspace_read_only_cache,
+    &workspace_id,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(billing)))
+}
+
+/// Returns the preferences that apply to the user in the workspace.
+async fn get_workspace_notification_preferences_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<NotificationPreferences>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let workspace_id = workspace_id.into_inner();
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
+    .await?;
+  let preferences =
+    select_notification_preferences(&state.pg_pool, uid, Some(&workspace_id)).await?;
+  Ok(Json(AppResponse::Ok().with_data(preferences)))
+}
+
+async fn put_workspace_notification_preferences_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  payload: Json<UpdateNotificationPreferencesParams>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<NotificationPreferences>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let workspace_id = workspace_id.into_inner();
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
+    .await?;
+  upsert_notification_preferences(&state.pg_pool, uid, Some(&workspace_id), &payload).await?;
+  let preferences =
+    select_notification_preferences(&state.pg_pool, uid, Some(&workspace_id)).await?;
+  Ok(Json(AppResponse::Ok().with_data(preferences)))
+}
+
+async fn delete_workspace_notification_preferences_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<()>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  delete_workspace_notification_preferences(&state.pg_pool, uid, &workspace_id).await?;
+  Ok(Json(AppResponse::Ok()))
+}
+
+async fn list_workspace_files_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  query: web::Query<ListWorkspaceFilesQuery>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<WorkspaceFiles>>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
+    .await?;
+  let files =
+    biz::workspace::files::list_workspace_files(&state.pg_pool, &workspace_id, query.into_inner())
+      .await?;
+  Ok(Json(AppResponse::Ok().with_data(files)))
+}
+
+/// Deletes the files of the workspace in bulk, so only the owner, who is responsible for the
+/// storage quota, is allowed to do it.
+async fn delete_workspace_files_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  payload: Json<DeleteWorkspaceFilesParams>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<DeletedWorkspaceFiles>>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  let deleted = biz::workspace::files::delete_workspace_files(
+    &state.bucket_storage,
+    &workspace_id,
+    &payload.file_ids,
+  )
+  .await?;
+  refresh_workspace_read_only_after_change(
+    &state.pg_pool,
+    &state.config.billing,
+    &state.workspace_read_only_cache,
+    &workspace_id,
+  )
+  .await;
+  Ok(Json(AppResponse::Ok().with_data(deleted)))
+}
+
+/// Releases the files uploaded by guests from the quarantine, so that they can be downloaded.
+async fn approve_workspace_files_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  payload: Json<ApproveWorkspaceFilesParams>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<ApprovedWorkspaceFiles>>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Write)
+    .await?;
+  let approved = biz::workspace::files::approve_workspace_files(
+    &state.pg_pool,
+    &workspace_id,
+    &payload.file_ids,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(approved)))
+}
+
+async fn get_workspace_folder_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  state: Data<AppState>,
+  server: Data<RealtimeServerAddr>,
+  query: web::Query<QueryWorkspaceFolder>,
+  req: HttpRequest,
+) -> Result<Json<AppResponse<FolderView>>> {
+  let depth = query.depth.unwrap_or(1);
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let user = realtime_user_for_web_request(req.headers(), uid)?;
   let workspace_id = workspace_id.into_inner();
-  let info =
-    biz::workspace::publish::get_workspace_default_publish_view_info(&state.pg_pool, &workspace_id)
-      .await?;
-  Ok(Json(AppResponse::Ok().with_data(info)))
+  let guest_access = enforce_read_or_guest_access(
+    &state.workspace_access_control,
+    &state.collab_guest_cache,
+    uid,
+    &workspace_id,
+  )
+  .await?;
+  if let Some(guest_access) = guest_access {
+    // The guests only see the pages shared with them, whatever the requested root and depth.
+    let folder_view = biz::collab::ops::get_guest_workspace_structure(
+      &state.collab_access_control_storage,
+      &state.pg_pool,
+      &state.restricted_view_cache,
+      &guest_access,
+      uid,
+      workspace_id,
+    )
+    .await?;
+    return Ok(Json(AppResponse::Ok().with_data(folder_view)));
+  }
+  let root_view_id = if let Some(root_view_id) = query.root_view_id.as_ref() {
+    root_view_id.to_string()
+  } else {
+    workspace_id.to_string()
+  };
+  let folder_view = biz::collab::ops::get_user_workspace_structure(
+    &state.metrics.appflowy_web_metrics,
+    server,
+    &state.collab_access_control_storage,
+    &state.pg_pool,
+    &state.restricted_view_cache,
+    user,
+    workspace_id,
+    depth,
+    &root_view_id,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(folder_view)))
+}
+
+#[instrument(level = "info", skip_all, err, fields(user_uuid))]
+async fn rebuild_workspace_folder_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<RebuiltWorkspaceFolder>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let workspace_id = workspace_id.into_inner();
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  let rebuilt = rebuild_workspace_folder(
+    &state.pg_pool,
+    &state.collab_access_control_storage,
+    uid,
+    workspace_id,
+  )
+  .await?;
+  Ok(AppResponse::Ok().with_data(rebuilt).into())
+}
+
+async fn get_recent_views_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<RecentSectionItems>>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let workspace_id = workspace_id.into_inner();
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
+    .await?;
+  let folder_views = get_user_recent_folder_views(
+    &state.collab_access_control_storage,
+    &state.pg_pool,
+    uid,
+    workspace_id,
+  )
+  .await?;
+  let section_items = RecentSectionItems {
+    views: folder_views,
+  };
+  Ok(Json(AppResponse::Ok().with_data(section_items)))
+}
+
+async fn get_favorite_views_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<FavoriteSectionItems>>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let workspace_id = workspace_id.into_inner();
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
+    .await?;
+  let folder_views = get_user_favorite_folder_views(
+    &state.collab_access_control_storage,
+    &state.pg_pool,
+    uid,
+    workspace_id,
+  )
+  .await?;
+  let section_items = FavoriteSectionItems {
+    views: folder_views,
+  };
+  Ok(Json(AppResponse::Ok().with_data(section_items)))
+}
+
+async fn get_trash_views_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<Uuid>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<TrashSectionItems>>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let workspace_id = workspace_id.into_inner();
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
+    .await?;
+  let folder_views =
+    get_user_trash_folder_views(&state.collab_access_control_storage, uid, workspace_id).await?;
+  let section_items = TrashSectionItems {
+    views: folder_views,
+  };
+  Ok(Json(AppResponse::Ok().with_data(section_items)))
+}
+
+async fn get_workspace_publish_outline_handler(
+  publish_namespace: web::Path<String>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<PublishedView>>> {
+  let published_view = biz::collab::ops::get_published_view(
+    &state.collab_access_control_storage,
+    publish_namespace.into_inner(),
+    &state.pg_pool,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(published_view)))
+}
+
+async fn list_database_handler(
+  user_uuid: UserUuid,
+  workspace_id: web::Path<String>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<Vec<AFDatabase>>>> {
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let workspace_id = workspace_id.into_inner();
+  let dbs = biz::collab::ops::list_database(
+    &state.pg_pool,
+    &state.collab_access_control_storage,
+    uid,
+    workspace_id,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(dbs)))
+}
+
+async fn list_database_row_id_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String)>,
+  state: Data<AppState>,
+) -> Result<Json<AppResponse<Vec<AFDatabaseRow>>>> {
+  let (workspace_id, db_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Read)
+    .await?;
+
+  let db_rows = biz::collab::ops::list_database_row_ids(
+    &state.collab_access_control_storage,
+    &workspace_id,
+    &db_id,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(db_rows)))
+}
+
+async fn post_database_row_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String)>,
+  state: Data<AppState>,
+  add_database_row: Json<AddDatatabaseRow>,
+) -> Result<Json<AppResponse<String>>> {
+  let (workspace_id, db_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Write)
+    .await?;
+
+  let AddDatatabaseRow { cells, document } = add_database_row.into_inner();
+
+  let new_db_row_id = biz::collab::ops::insert_database_row(
+    state.collab_access_control_storage.clone(),
+    &state.pg_pool,
+    &workspace_id,
+    &db_id,
+    uid,
+    None,
+    cells,
+    document,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(new_db_row_id)))
+}
+
+async fn put_database_row_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String)>,
+  state: Data<AppState>,
+  upsert_db_row: Json<UpsertDatatabaseRow>,
+) -> Result<Json<AppResponse<String>>> {
+  let (workspace_id, db_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Write)
+    .await?;
+
+  let UpsertDatatabaseRow {
+    pre_hash,
+    cells,
+    document,
+  } = upsert_db_row.into_inner();
+
+  let row_id = {
+    let mut hasher = Sha256::new();
+    hasher.update(&workspace_id);
+    hasher.update(&db_id);
+    hasher.update(pre_hash);
+    let hash = hasher.finalize();
+    Uuid::from_bytes([
+      // take 16 out of 32 bytes
+      hash[0], hash[1], hash[2], hash[3], hash[4], hash[5], hash[6], hash[7], hash[8], hash[9],
+      hash[10], hash[11], hash[12], hash[13], hash[14], hash[15],
+    ])
+  };
+  let row_id_str = row_id.to_string();
+
+  biz::collab::ops::upsert_database_row(
+    state.collab_access_control_storage.clone(),
+    &state.pg_pool,
+    &workspace_id,
+    &db_id,
+    uid,
+    &row_id_str,
+    cells,
+    document,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(row_id_str)))
 }
 
-async fn put_publish_namespace_handler(
+async fn get_database_fields_handler(
   user_uuid: UserUuid,
-  workspace_id: web::Path<Uuid>,
-  payload: Json<UpdatePublishNamespace>,
+  path_param: web::Path<(String, String)>,
   state: Data<AppState>,
-) -> Result<Json<AppResponse<()>>> {
-  let workspace_id = workspace_id.into_inner();
+) -> Result<Json<AppResponse<Vec<AFDatabaseField>>>> {
+  let (workspace_id, db_id) = path_param.into_inner();
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   state
     .workspace_access_control
-    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .enforce_action(&uid, &workspace_id, Action::Read)
     .await?;
-  let UpdatePublishNamespace {
-    old_namespace,
-    new_namespace,
-  } = payload.into_inner();
-  biz::workspace::publish::set_workspace_namespace(
-    &state.pg_pool,
+
+  let db_fields = biz::collab::ops::get_database_fields(
+    &state.collab_access_control_storage,
     &workspace_id,
-    &old_namespace,
-    &new_namespace,
+    &db_id,
   )
   .await?;
-  Ok(Json(AppResponse::Ok()))
-}
-
-async fn get_publish_namespace_handler(
-  workspace_id: web::Path<Uuid>,
-  state: Data<AppState>,
-) -> Result<Json<AppResponse<String>>> {
-  let workspace_id = workspace_id.into_inner();
-  let namespace =
-    biz::workspace::publish::get_workspace_publish_namespace(&state.pg_pool, &workspace_id).await?;
-  Ok(Json(AppResponse::Ok().with_data(namespace)))
-}
 
-async fn get_default_published_collab_info_meta_handler(
-  publish_namespace: web::Path<String>,
-  state: Data<AppState>,
-) -> Result<Json<AppResponse<PublishInfoMeta<serde_json::Value>>>> {
-  let publish_namespace = publish_namespace.into_inner();
-  let (info, meta) =
-    get_workspace_default_publish_view_info_meta(&state.pg_pool, &publish_namespace).await?;
-  Ok(Json(
-    AppResponse::Ok().with_data(PublishInfoMeta { info, meta }),
-  ))
+  Ok(Json(AppResponse::Ok().with_data(db_fields)))
 }
 
-async fn get_v1_published_collab_handler(
+async fn post_database_fields_handler(
+  user_uuid: UserUuid,
   path_param: web::Path<(String, String)>,
   state: Data<AppState>,
-) -> Result<Json<AppResponse<serde_json::Value>>> {
-  let (workspace_namespace, publish_name) = path_param.into_inner();
-  let metadata = state
-    .published_collab_store
-    .get_collab_metadata(&workspace_namespace, &publish_name)
+  field: Json<AFInsertDatabaseField>,
+) -> Result<Json<AppResponse<String>>> {
+  let (workspace_id, db_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Write)
     .await?;
-  Ok(Json(AppResponse::Ok().with_data(metadata)))
-}
 
-async fn get_published_collab_blob_handler(
-  path_param: web::Path<(String, String)>,
-  state: Data<AppState>,
-) -> Result<Vec<u8>> {
-  let (publish_namespace, publish_name) = path_param.into_inner();
-  let collab_data = state
-    .published_collab_store
-    .get_collab_blob_by_publish_namespace(&publish_namespace, &publish_name)
-    .await?;
-  Ok(collab_data)
+  let field_id = biz::collab::ops::add_database_field(
+    uid,
+    state.collab_access_control_storage.clone(),
+    &state.pg_pool,
+    &workspace_id,
+    &db_id,
+    field.into_inner(),
+  )
+  .await?;
+
+  Ok(Json(AppResponse::Ok().with_data(field_id)))
 }
 
-async fn post_published_duplicate_handler(
+async fn list_database_views_handler(
   user_uuid: UserUuid,
-  workspace_id: web::Path<String>,
+  path_param: web::Path<(String, String)>,
   state: Data<AppState>,
-  params: Json<PublishedDuplicate>,
-) -> Result<Json<AppResponse<DuplicatePublishedPageResponse>>> {
+) -> Result<Json<AppResponse<Vec<AFDatabaseView>>>> {
+  let (workspace_id, db_id) = path_param.into_inner();
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   state
     .workspace_access_control
-    .enforce_action(&uid, &workspace_id.to_string(), Action::Write)
-    .await?;
-  let params = params.into_inner();
-  let root_view_id_for_duplicate =
-    biz::workspace::publish_dup::duplicate_published_collab_to_workspace(
-      &state.pg_pool,
-      state.bucket_client.clone(),
-      state.collab_access_control_storage.clone(),
-      uid,
-      params.published_view_id,
-      workspace_id.into_inner(),
-      params.dest_view_id,
-    )
+    .enforce_action(&uid, &workspace_id, Action::Read)
     .await?;
 
-  Ok(Json(AppResponse::Ok().with_data(
-    DuplicatePublishedPageResponse {
-      view_id: root_view_id_for_duplicate,
-    },
-  )))
-}
-
-async fn list_published_collab_info_handler(
-  workspace_id: web::Path<Uuid>,
-  state: Data<AppState>,
-) -> Result<Json<AppResponse<Vec<PublishInfoView>>>> {
-  let publish_infos = biz::workspace::publish::list_collab_publish_info(
-    state.published_collab_store.as_ref(),
+  let views = biz::collab::ops::list_database_views(
     &state.collab_access_control_storage,
-    &workspace_id.into_inner(),
+    &workspace_id,
+    &db_id,
   )
   .await?;
-
-  Ok(Json(AppResponse::Ok().with_data(publish_infos)))
-}
-
-// Deprecated since 0.7.4
-async fn get_published_collab_info_handler(
-  view_id: web::Path<Uuid>,
-  state: Data<AppState>,
-) -> Result<Json<AppResponse<PublishInfo>>> {
-  let view_id = view_id.into_inner();
-  let collab_data = state
-    .published_collab_store
-    .get_collab_publish_info(&view_id)
-    .await?;
-  if collab_data.unpublished_timestamp.is_some() {
-    return Err(AppError::RecordNotFound("Collab is unpublished".to_string()).into());
-  }
-  Ok(Json(AppResponse::Ok().with_data(collab_data)))
+  Ok(Json(AppResponse::Ok().with_data(views)))
 }
 
-async fn get_v1_published_collab_info_handler(
-  view_id: web::Path<Uuid>,
+async fn patch_database_view_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(String, String, String)>,
   state: Data<AppState>,
-) -> Result<Json<AppResponse<PublishInfo>>> {
-  let view_id = view_id.into_inner();
-  let collab_data = state
-    .published_collab_store
-    .get_collab_publish_info(&view_id)
+  settings: Json<AFDatabaseViewSettings>,
+) -> Result<Json<AppResponse<AFDatabaseView>>> {
+  let (workspace_id, db_id, view_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Write)
     .await?;
-  Ok(Json(AppResponse::Ok().with_data(collab_data)))
-}
-
-async fn get_published_collab_comment_handler(
-  view_id: web::Path<Uuid>,
-  optional_user_uuid: OptionalUserUuid,
-  state: Data<AppState>,
-) -> Result<JsonAppResponse<GlobalComments>> {
-  let view_id = view_id.into_inner();
-  let comments =
-    get_comments_on_published_view(&state.pg_pool, &view_id, &optional_user_uuid).await?;
-  let resp = GlobalComments { comments };
-  Ok(Json(AppResponse::Ok().with_data(resp)))
-}
 
-async fn post_published_collab_comment_handler(
-  user_uuid: UserUuid,
-  view_id: web::Path<Uuid>,
-  state: Data<AppState>,
-  data: Json<CreateGlobalCommentParams>,
-) -> Result<JsonAppResponse<()>> {
-  let view_id = view_id.into_inner();
-  create_comment_on_published_view(
+  let view = biz::collab::ops::update_database_view_settings(
+    uid,
+    state.collab_access_control_storage.clone(),
     &state.pg_pool,
+    &workspace_id,
+    &db_id,
     &view_id,
-    &data.reply_comment_id,
-    &data.content,
-    &user_uuid,
+    settings.into_inner(),
   )
   .await?;
-  Ok(Json(AppResponse::Ok()))
+  Ok(Json(AppResponse::Ok().with_data(view)))
 }
 
-async fn delete_published_collab_comment_handler(
+async fn put_database_field_handler(
   user_uuid: UserUuid,
-  view_id: web::Path<Uuid>,
+  path_param: web::Path<(String, String, String)>,
   state: Data<AppState>,
-  data: Json<DeleteGlobalCommentParams>,
-) -> Result<JsonAppResponse<()>> {
-  let view_id = view_id.into_inner();
-  remove_comment_on_published_view(&state.pg_pool, &view_id, &data.comment_id, &user_uuid).await?;
-  Ok(Json(AppResponse::Ok()))
-}
+  field: Json<AFUpdateDatabaseField>,
+) -> Result<Json<AppResponse<AFDatabaseField>>> {
+  let (workspace_id, db_id, field_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Write)
+    .await?;
 
-async fn get_published_collab_reaction_handler(
-  view_id: web::Path<Uuid>,
-  query: web::Query<GetReactionQueryParams>,
-  state: Data<AppState>,
-) -> Result<JsonAppResponse<Reactions>> {
-  let view_id = view_id.into_inner();
-  let reactions =
-    get_reactions_on_published_view(&state.pg_pool, &view_id, &query.comment_id).await?;
-  let resp = Reactions { reactions };
-  Ok(Json(AppResponse::Ok().with_data(resp)))
+  let field = biz::collab::ops::update_database_field(
+    uid,
+    state.collab_access_control_storage.clone(),
+    &state.pg_pool,
+    &workspace_id,
+    &db_id,
+    &field_id,
+    field.into_inner(),
+  )
+  .await?;
+
+  Ok(Json(AppResponse::Ok().with_data(field)))
 }
 
-async fn post_published_collab_reaction_handler(
+async fn delete_database_field_handler(
   user_uuid: UserUuid,
-  view_id: web::Path<Uuid>,
-  data: Json<CreateReactionParams>,
+  path_param: web::Path<(String, String, String)>,
   state: Data<AppState>,
-) -> Result<JsonAppResponse<()>> {
-  let view_id = view_id.into_inner();
-  create_reaction_on_comment(
+) -> Result<Json<AppResponse<()>>> {
+  let (workspace_id, db_id, field_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Write)
+    .await?;
+
+  biz::collab::ops::delete_database_field(
+    uid,
+    state.collab_access_control_storage.clone(),
     &state.pg_pool,
-    &data.comment_id,
-    &view_id,
-    &data.reaction_type,
-    &user_uuid,
+    &workspace_id,
+    &db_id,
+    &field_id,
   )
   .await?;
+
   Ok(Json(AppResponse::Ok()))
 }
 
-async fn delete_published_collab_reaction_handler(
+async fn list_database_row_id_updated_handler(
   user_uuid: UserUuid,
-  data: Json<DeleteReactionParams>,
+  path_param: web::Path<(String, String)>,
   state: Data<AppState>,
-) -> Result<JsonAppResponse<()>> {
-  remove_reaction_on_comment(
+  param: web::Query<ListDatabaseRowUpdatedParam>,
+) -> Result<Json<AppResponse<Vec<DatabaseRowUpdatedItem>>>> {
+  let (workspace_id, db_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Read)
+    .await?;
+
+  // Default to 1 hour ago
+  let after: DateTime<Utc> = param
+    .after
+    .unwrap_or_else(|| Utc::now() - Duration::hours(1));
+
+  let db_rows = biz::collab::ops::list_database_row_ids_updated(
+    &state.collab_access_control_storage,
     &state.pg_pool,
-    &data.comment_id,
-    &data.reaction_type,
-    &user_uuid,
+    &workspace_id,
+    &db_id,
+    &after,
   )
   .await?;
-  Ok(Json(AppResponse::Ok()))
+  Ok(Json(AppResponse::Ok().with_data(db_rows)))
 }
 
-// FIXME: This endpoint currently has a different behaviour from the publish page endpoint,
-// as it doesn't accept parameters. We will need to deprecate this endpoint and use a new
-// one that accepts parameters.
-async fn post_publish_collabs_handler(
-  workspace_id: web::Path<Uuid>,
+async fn list_database_row_details_handler(
   user_uuid: UserUuid,
-  payload: Payload,
+  path_param: web::Path<(String, String)>,
   state: Data<AppState>,
-) -> Result<Json<AppResponse<()>>> {
-  let workspace_id = workspace_id.into_inner();
-
-  let mut accumulator = Vec::<PublishCollabItem<serde_json::Value, Vec<u8>>>::new();
-  let mut payload_reader: PayloadReader = PayloadReader::new(payload);
-
-  loop {
-    let meta: PublishCollabMetadata<serde_json::Value> = {
-      let meta_len = payload_reader.read_u32_little_endian().await?;
-      if meta_len > 4 * 1024 * 1024 {
-        // 4MiB Limit for metadata
-        return Err(AppError::InvalidRequest(String::from("metadata too large")).into());
-      }
-      if meta_len == 0 {
-        break;
-      }
-
-      let mut meta_buffer = vec![0; meta_len as usize];
-      payload_reader.read_exact(&mut meta_buffer).await?;
-      serde_json::from_slice(&meta_buffer)?
-    };
-
-    let data = {
-      let data_len = payload_reader.read_u32_little_endian().await?;
-      if data_len > 32 * 1024 * 1024 {
-        // 32MiB Limit for data
-        return Err(AppError::InvalidRequest(String::from("data too large")).into());
-      }
-      let mut data_buffer = vec![0; data_len as usize];
-      payload_reader.read_exact(&mut data_buffer).await?;
-      data_buffer
-    };
-
-    // Set comments_enabled and duplicate_enabled to true by default, as this is the default
-    // behaviour for the older web version.
-    accumulator.push(PublishCollabItem {
-      meta,
-      data,
-      comments_enabled: true,
-      duplicate_enabled: true,
-    });
-  }
+  param: web::Query<ListDatabaseRowDetailParam>,
+) -> Result<Json<AppResponse<Vec<AFDatabaseRowDetail>>>> {
+  let (workspace_id, db_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  let list_db_row_query = param.into_inner();
+  let with_doc = list_db_row_query.with_doc.unwrap_or_default();
+  let expand_relations = list_db_row_query.expand_relations();
+  let row_ids = list_db_row_query.into_ids();
 
-  if accumulator.is_empty() {
+  if let Err(e) = Uuid::parse_str(&workspace_id) {
     return Err(
-      AppError::InvalidRequest(String::from("did not receive any data to publish")).into(),
+      AppError::InvalidRequest(format!("invalid workspace id `{}`: {}", db_id, e)).into(),
     );
   }
+  if let Err(e) = Uuid::parse_str(&db_id) {
+    return Err(AppError::InvalidRequest(format!("invalid database id `{}`: {}", db_id, e)).into());
+  }
+
+  for id in row_ids.iter() {
+    if let Err(e) = Uuid::parse_str(id) {
+      return Err(AppError::InvalidRequest(format!("invalid row id `{}`: {}", id, e)).into());
+    }
+  }
+
   state
-    .published_collab_store
-    .publish_collabs(accumulator, &workspace_id, &user_uuid)
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id, Action::Read)
     .await?;
-  Ok(Json(AppResponse::Ok()))
+
+  static UNSUPPORTED_FIELD_TYPES: &[FieldType] = &[FieldType::Relation];
+  let unsupported_field_types = if expand_relations {
+    &[]
+  } else {
+    UNSUPPORTED_FIELD_TYPES
+  };
+
+  let db_rows = biz::collab::ops::list_database_row_details(
+    &state.collab_access_control_storage,
+    uid,
+    workspace_id,
+    db_id,
+    &row_ids,
+    unsupported_field_types,
+    with_doc,
+    expand_relations,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok().with_data(db_rows)))
 }
 
-async fn patch_published_collabs_handler(
-  workspace_id: web::Path<Uuid>,
+async fn get_database_row_comment_handler(
   user_uuid: UserUuid,
+  path_param: web::Path<(Uuid, Uuid, Uuid)>,
   state: Data<AppState>,
-  patches: Json<Vec<PatchPublishedCollab>>,
-) -> Result<Json<AppResponse<()>>> {
-  let workspace_id = workspace_id.into_inner();
-  if patches.is_empty() {
-    return Err(AppError::InvalidRequest("No patches provided".to_string()).into());
-  }
+) -> Result<JsonAppResponse<GlobalComments>> {
+  let (workspace_id, _database_id, row_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   state
-    .published_collab_store
-    .patch_collabs(&workspace_id, &user_uuid, &patches)
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
     .await?;
-  Ok(Json(AppResponse::Ok()))
+  let comments =
+    get_database_row_comments(&state.pg_pool, &workspace_id, &row_id, &user_uuid).await?;
+  Ok(Json(
+    AppResponse::Ok().with_data(GlobalComments { comments }),
+  ))
 }
 
-async fn delete_published_collabs_handler(
-  workspace_id: web::Path<Uuid>,
+async fn post_database_row_comment_handler(
   user_uuid: UserUuid,
+  path_param: web::Path<(Uuid, Uuid, Uuid)>,
   state: Data<AppState>,
-  view_ids: Json<Vec<Uuid>>,
-) -> Result<Json<AppResponse<()>>> {
-  let workspace_id = workspace_id.into_inner();
-  let view_ids = view_ids.into_inner();
-  if view_ids.is_empty() {
-    return Err(AppError::InvalidRequest("No view_ids provided".to_string()).into());
-  }
+  data: Json<CreateGlobalCommentParams>,
+) -> Result<JsonAppResponse<()>> {
+  let (workspace_id, _database_id, row_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   state
-    .published_collab_store
-    .unpublish_collabs(&workspace_id, &view_ids, &user_uuid)
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
     .await?;
+  create_database_row_comment(
+    &state.pg_pool,
+    &workspace_id,
+    &row_id,
+    &data.reply_comment_id,
+    &data.content,
+    &user_uuid,
+  )
+  .await?;
   Ok(Json(AppResponse::Ok()))
 }
 
-#[instrument(level = "info", skip_all, err)]
-async fn post_realtime_message_stream_handler(
+async fn delete_database_row_comment_handler(
   user_uuid: UserUuid,
-  mut payload: Payload,
-  server: Data<RealtimeServerAddr>,
+  path_param: web::Path<(Uuid, Uuid, Uuid)>,
   state: Data<AppState>,
-  req: HttpRequest,
-) -> Result<Json<AppResponse<()>>> {
-  let device_id = device_id_from_headers(req.headers())
-    .map(|s| s.to_string())
-    .unwrap_or_else(|_| "".to_string());
-  let uid = state
-    .user_cache
-    .get_user_uid(&user_uuid)
-    .await
-    .map_err(AppResponseError::from)?;
-
-  let mut bytes = BytesMut::new();
-  while let Some(item) = payload.next().await {
-    bytes.extend_from_slice(&item?);
-  }
-
-  let device_id = device_id.to_string();
-
-  let message = parser_realtime_msg(bytes.freeze(), req.clone()).await?;
-  let stream_message = ClientHttpStreamMessage {
-    uid,
-    device_id,
-    message,
-  };
-
-  // When the server is under heavy load, try_send may fail. In client side, it will retry to send
-  // the message later.
-  match server.try_send(stream_message) {
-    Ok(_) => return Ok(Json(AppResponse::Ok())),
-    Err(err) => Err(
-      AppError::Internal(anyhow!(
-        "Failed to send message to websocket server, error:{}",
-        err
-      ))
-      .into(),
-    ),
-  }
+  data: Json<DeleteGlobalCommentParams>,
+) -> Result<JsonAppResponse<()>> {
+  let (workspace_id, _database_id, row_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
+    .await?;
+  remove_database_row_comment(
+    &state.pg_pool,
+    &workspace_id,
+    &row_id,
+    &data.comment_id,
+    &user_uuid,
+  )
+  .await?;
+  Ok(Json(AppResponse::Ok()))
 }
 
-async fn get_workspace_usage_handler(
+async fn get_database_row_activity_handler(
   user_uuid: UserUuid,
-  workspace_id: web::Path<Uuid>,
+  path_param: web::Path<(Uuid, Uuid, Uuid)>,
+  query: web::Query<DatabaseRowActivityQuery>,
   state: Data<AppState>,
-) -> Result<Json<AppResponse<WorkspaceUsage>>> {
+) -> Result<JsonAppResponse<AFDatabaseRowActivities>> {
+  let (workspace_id, _database_id, row_id) = path_param.into_inner();
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   state
     .workspace_access_control
-    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
     .await?;
-  let res =
-    biz::workspace::ops::get_workspace_document_total_bytes(&state.pg_pool, &workspace_id).await?;
-  Ok(Json(AppResponse::Ok().with_data(res)))
+  let activities =
+    get_database_row_activity(&state.pg_pool, &workspace_id, &row_id, &query.into_inner()).await?;
+  Ok(Json(AppResponse::Ok().with_data(activities)))
 }
 
-async fn get_workspace_folder_handler(
+async fn post_database_row_document_handler(
   user_uuid: UserUuid,
-  workspace_id: web::Path<Uuid>,
+  path_param: web::Path<(Uuid, Uuid, Uuid)>,
   state: Data<AppState>,
-  server: Data<RealtimeServerAddr>,
-  query: web::Query<QueryWorkspaceFolder>,
-  req: HttpRequest,
-) -> Result<Json<AppResponse<FolderView>>> {
-  let depth = query.depth.unwrap_or(1);
+) -> Result<JsonAppResponse<AFDatabaseRowDocument>> {
+  let (workspace_id, _database_id, row_id) = path_param.into_inner();
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
-  let user = realtime_user_for_web_request(req.headers(), uid)?;
-  let workspace_id = workspace_id.into_inner();
   state
     .workspace_access_control
-    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Write)
     .await?;
-  let root_view_id = if let Some(root_view_id) = query.root_view_id.as_ref() {
-    root_view_id.to_string()
-  } else {
-    workspace_id.to_string()
-  };
-  let folder_view = biz::collab::ops::get_user_workspace_structure(
-    &state.metrics.appflowy_web_metrics,
-    server,
-    &state.collab_access_control_storage,
+  let document = biz::collab::ops::get_or_create_database_row_document(
+    state.collab_access_control_storage.clone(),
     &state.pg_pool,
-    user,
-    workspace_id,
-    depth,
-    &root_view_id,
+    &workspace_id.to_string(),
+    uid,
+    &row_id.to_string(),
   )
   .await?;
-  Ok(Json(AppResponse::Ok().with_data(folder_view)))
+  Ok(Json(AppResponse::Ok().with_data(document)))
 }
 
-async fn get_recent_views_handler(
+async fn get_database_external_id_field_handler(
   user_uuid: UserUuid,
-  workspace_id: web::Path<Uuid>,
+  path_param: web::Path<(Uuid, Uuid)>,
   state: Data<AppState>,
-) -> Result<Json<AppResponse<RecentSectionItems>>> {
+) -> Result<JsonAppResponse<AFDatabaseExternalIdField>> {
+  let (workspace_id, database_id) = path_param.into_inner();
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
-  let workspace_id = workspace_id.into_inner();
   state
     .workspace_access_control
     .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
     .await?;
-  let folder_views = get_user_recent_folder_views(
+  let field =
+    get_database_external_id_field(&state.pg_pool, &workspace_id, &database_id.to_string()).await?;
+  Ok(Json(AppResponse::Ok().with_data(field)))
+}
+
+async fn put_database_external_id_field_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(Uuid, Uuid)>,
+  state: Data<AppState>,
+  payload: Json<AFDatabaseExternalIdField>,
+) -> Result<JsonAppResponse<()>> {
+  let (workspace_id, database_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Write)
+    .await?;
+  set_database_external_id_field(
     &state.collab_access_control_storage,
     &state.pg_pool,
+    &workspace_id,
+    &database_id.to_string(),
     uid,
-    workspace_id,
+    &payload.field_id,
   )
   .await?;
-  let section_items = RecentSectionItems {
-    views: folder_views,
-  };
-  Ok(Json(AppResponse::Ok().with_data(section_items)))
+  Ok(Json(AppResponse::Ok()))
 }
 
-async fn get_favorite_views_handler(
+async fn bulk_upsert_database_rows_handler(
   user_uuid: UserUuid,
-  workspace_id: web::Path<Uuid>,
+  path_param: web::Path<(Uuid, Uuid)>,
   state: Data<AppState>,
-) -> Result<Json<AppResponse<FavoriteSectionItems>>> {
+  payload: Json<BulkUpsertDatabaseRows>,
+) -> Result<JsonAppResponse<BulkUpsertDatabaseRowsResponse>> {
+  let (workspace_id, database_id) = path_param.into_inner();
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
-  let workspace_id = workspace_id.into_inner();
   state
     .workspace_access_control
-    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Write)
     .await?;
-  let folder_views = get_user_favorite_folder_views(
-    &state.collab_access_control_storage,
+  let rows = bulk_upsert_database_rows(
+    state.collab_access_control_storage.clone(),
     &state.pg_pool,
+    &workspace_id,
+    &database_id.to_string(),
     uid,
-    workspace_id,
+    payload.into_inner().rows,
   )
   .await?;
-  let section_items = FavoriteSectionItems {
-    views: folder_views,
-  };
-  Ok(Json(AppResponse::Ok().with_data(section_items)))
+  Ok(Json(
+    AppResponse::Ok().with_data(BulkUpsertDatabaseRowsResponse { rows }),
+  ))
 }
 
-async fn get_trash_views_handler(
+async fn list_database_form_tokens_handler(
   user_uuid: UserUuid,
-  workspace_id: web::Path<Uuid>,
+  path_param: web::Path<(Uuid, Uuid)>,
   state: Data<AppState>,
-) -> Result<Json<AppResponse<TrashSectionItems>>> {
+) -> Result<JsonAppResponse<Vec<AFDatabaseFormToken>>> {
+  let (workspace_id, database_id) = path_param.into_inner();
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
-  let workspace_id = workspace_id.into_inner();
   state
     .workspace_access_control
-    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
     .await?;
-  let folder_views =
-    get_user_trash_folder_views(&state.collab_access_control_storage, uid, workspace_id).await?;
-  let section_items = TrashSectionItems {
-    views: folder_views,
-  };
-  Ok(Json(AppResponse::Ok().with_data(section_items)))
+  let tokens =
+    list_database_form_tokens(&state.pg_pool, &workspace_id, &database_id.to_string()).await?;
+  Ok(Json(AppResponse::Ok().with_data(tokens)))
 }
 
-async fn get_workspace_publish_outline_handler(
-  publish_namespace: web::Path<String>,
+async fn post_database_form_token_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(Uuid, Uuid)>,
   state: Data<AppState>,
-) -> Result<Json<AppResponse<PublishedView>>> {
-  let published_view = biz::collab::ops::get_published_view(
+  payload: Json<CreateDatabaseFormTokenParams>,
+) -> Result<JsonAppResponse<AFDatabaseFormToken>> {
+  let (workspace_id, database_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  let token = create_database_form_token(
     &state.collab_access_control_storage,
-    publish_namespace.into_inner(),
     &state.pg_pool,
+    &workspace_id,
+    &database_id.to_string(),
+    uid,
+    payload.into_inner(),
   )
   .await?;
-  Ok(Json(AppResponse::Ok().with_data(published_view)))
+  Ok(Json(AppResponse::Ok().with_data(token)))
 }
 
-async fn list_database_handler(
+async fn delete_database_form_token_handler(
   user_uuid: UserUuid,
-  workspace_id: web::Path<String>,
+  path_param: web::Path<(Uuid, Uuid, Uuid)>,
   state: Data<AppState>,
-) -> Result<Json<AppResponse<Vec<AFDatabase>>>> {
+) -> Result<JsonAppResponse<()>> {
+  let (workspace_id, database_id, token_id) = path_param.into_inner();
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
-  let workspace_id = workspace_id.into_inner();
-  let dbs = biz::collab::ops::list_database(
+  state
+    .workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  delete_database_form_token(
     &state.pg_pool,
-    &state.collab_access_control_storage,
-    uid,
-    workspace_id,
+    &workspace_id,
+    &database_id.to_string(),
+    &token_id,
   )
   .await?;
-  Ok(Json(AppResponse::Ok().with_data(dbs)))
+  Ok(Json(AppResponse::Ok()))
 }
 
-async fn list_database_row_id_handler(
+async fn list_restricted_views_handler(
   user_uuid: UserUuid,
-  path_param: web::Path<(String, String)>,
+  workspace_id: web::Path<Uuid>,
   state: Data<AppState>,
-) -> Result<Json<AppResponse<Vec<AFDatabaseRow>>>> {
-  let (workspace_id, db_id) = path_param.into_inner();
+) -> Result<JsonAppResponse<Vec<AFRestrictedView>>> {
+  let workspace_id = workspace_id.into_inner();
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
-
   state
     .workspace_access_control
-    .enforce_action(&uid, &workspace_id, Action::Read)
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
     .await?;
-
-  let db_rows = biz::collab::ops::list_database_row_ids(
-    &state.collab_access_control_storage,
-    &workspace_id,
-    &db_id,
-  )
-  .await?;
-  Ok(Json(AppResponse::Ok().with_data(db_rows)))
+  let views = list_restricted_views(&state.pg_pool, &workspace_id).await?;
+  Ok(Json(AppResponse::Ok().with_data(views)))
 }
 
-async fn post_database_row_handler(
+async fn put_restricted_view_handler(
   user_uuid: UserUuid,
-  path_param: web::Path<(String, String)>,
+  path_param: web::Path<(Uuid, String)>,
   state: Data<AppState>,
-  add_database_row: Json<AddDatatabaseRow>,
-) -> Result<Json<AppResponse<String>>> {
-  let (workspace_id, db_id) = path_param.into_inner();
+  payload: Json<RestrictViewParams>,
+) -> Result<JsonAppResponse<AFRestrictedView>> {
+  let (workspace_id, view_id) = path_param.into_inner();
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   state
     .workspace_access_control
-    .enforce_action(&uid, &workspace_id, Action::Write)
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
     .await?;
-
-  let AddDatatabaseRow { cells, document } = add_database_row.into_inner();
-
-  let new_db_row_id = biz::collab::ops::insert_database_row(
-    state.collab_access_control_storage.clone(),
+  let view = restrict_view(
+    &state.collab_access_control_storage,
     &state.pg_pool,
+    &state.restricted_view_cache,
     &workspace_id,
-    &db_id,
+    &view_id,
     uid,
-    None,
-    cells,
-    document,
+    payload.into_inner(),
   )
   .await?;
-  Ok(Json(AppResponse::Ok().with_data(new_db_row_id)))
+  Ok(Json(AppResponse::Ok().with_data(view)))
 }
 
-async fn put_database_row_handler(
+async fn delete_restricted_view_handler(
   user_uuid: UserUuid,
-  path_param: web::Path<(String, String)>,
+  path_param: web::Path<(Uuid, String)>,
   state: Data<AppState>,
-  upsert_db_row: Json<UpsertDatatabaseRow>,
-) -> Result<Json<AppResponse<String>>> {
-  let (workspace_id, db_id) = path_param.into_inner();
+) -> Result<JsonAppResponse<()>> {
+  let (workspace_id, view_id) = path_param.into_inner();
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   state
     .workspace_access_control
-    .enforce_action(&uid, &workspace_id, Action::Write)
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
     .await?;
-
-  let UpsertDatatabaseRow {
-    pre_hash,
-    cells,
-    document,
-  } = upsert_db_row.into_inner();
-
-  let row_id = {
-    let mut hasher = Sha256::new();
-    hasher.update(&workspace_id);
-    hasher.update(&db_id);
-    hasher.update(pre_hash);
-    let hash = hasher.finalize();
-    Uuid::from_bytes([
-      // take 16 out of 32 bytes
-      hash[0], hash[1], hash[2], hash[3], hash[4], hash[5], hash[6], hash[7], hash[8], hash[9],
-      hash[10], hash[11], hash[12], hash[13], hash[14], hash[15],
-    ])
-  };
-  let row_id_str = row_id.to_string();
-
-  biz::collab::ops::upsert_database_row(
-    state.collab_access_control_storage.clone(),
-    &state.pg_pool,
-    &workspace_id,
-    &db_id,
-    uid,
-    &row_id_str,
-    cells,
-    document,
-  )
-  .await?;
-  Ok(Json(AppResponse::Ok().with_data(row_id_str)))
+  unrestrict_view(&state.restricted_view_cache, &workspace_id, &view_id).await?;
+  Ok(Json(AppResponse::Ok()))
 }
 
-async fn get_database_fields_handler(
+async fn list_collab_guests_handler(
   user_uuid: UserUuid,
-  path_param: web::Path<(String, String)>,
+  path_param: web::Path<(Uuid, String)>,
   state: Data<AppState>,
-) -> Result<Json<AppResponse<Vec<AFDatabaseField>>>> {
-  let (workspace_id, db_id) = path_param.into_inner();
+) -> Result<JsonAppResponse<Vec<AFCollabGuest>>> {
+  let (workspace_id, object_id) = path_param.into_inner();
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
   state
-    .workspace_access_control
-    .enforce_action(&uid, &workspace_id, Action::Read)
+    .collab_access_control
+    .enforce_access_level(
+      &workspace_id.to_string(),
+      &uid,
+      &object_id,
+      AFAccessLevel::FullAccess,
+    )
     .await?;
+  let guests = list_collab_guests(&state.pg_pool, &workspace_id, &object_id).await?;
+  Ok(Json(AppResponse::Ok().with_data(guests)))
+}
 
-  let db_fields = biz::collab::ops::get_database_fields(
+async fn list_collab_members_handler(
+  user_uuid: UserUuid,
+  path_param: web::Path<(Uuid, String)>,
+  state: Data<AppState>,
+) -> Result<JsonAppResponse<Vec<AFCollabMemberInfo>>> {
+  let (workspace_id, object_id) = path_param.into_inner();
+  let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  state
+    .collab_access_control
+    .enforce_access_level(
+      &workspace_id.to_string(),
+      &uid,
+      &object_id,
+      AFAccessLevel::ReadOnly,
+    )
+    .await?;
+  let members = list_collab_members(
+    &state.pg_pool,
     &state.collab_access_control_storage,
+    &state.restricted_view_cache,
+    uid,
     &workspace_id,
-    &db_id,
+    &object_id,
   )
   .await?;
-
-  Ok(Json(AppResponse::Ok().with_data(db_fields)))
+  Ok(Json(AppResponse::Ok().with_data(members)))
 }
 
-async fn post_database_fields_handler(
+async fn post_collab_guest_handler(
   user_uuid: UserUuid,
-  path_param: web::Path<(String, String)>,
+  path_param: web::Path<(Uuid, String)>,
   state: Data<AppState>,
-  field: Json<AFInsertDatabaseField>,
-) -> Result<Json<AppResponse<String>>> {
-  let (workspace_id, db_id) = path_param.into_inner();
+  payload: Json<InviteCollabGuestParams>,
+) -> Result<JsonAppResponse<()>> {
+  let (workspace_id, object_id) = path_param.into_inner();
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
+  // Only the users with full access can share the page with others.
   state
-    .workspace_access_control
-    .enforce_action(&uid, &workspace_id, Action::Write)
+    .collab_access_control
+    .enforce_access_level(
+      &workspace_id.to_string(),
+      &uid,
+      &object_id,
+      AFAccessLevel::FullAccess,
+    )
     .await?;
-
-  let field_id = biz::collab::ops::add_database_field(
-    uid,
-    state.collab_access_control_storage.clone(),
+  invite_collab_guest(
+    &state.mailer,
+    &state.notification_center,
+    &state.gotrue_admin,
+    &state.gotrue_client,
+    &state.collab_access_control_storage,
     &state.pg_pool,
+    &state.collab_guest_cache,
+    state.config.appflowy_web_url.as_deref(),
+    &user_uuid,
     &workspace_id,
-    &db_id,
-    field.into_inner(),
+    &object_id,
+    payload.into_inner(),
   )
   .await?;
-
-  Ok(Json(AppResponse::Ok().with_data(field_id)))
+  Ok(Json(AppResponse::Ok()))
 }
 
-async fn list_database_row_id_updated_handler(
+async fn delete_collab_guest_handler(
   user_uuid: UserUuid,
-  path_param: web::Path<(String, String)>,
+  path_param: web::Path<(Uuid, String)>,
   state: Data<AppState>,
-  param: web::Query<ListDatabaseRowUpdatedParam>,
-) -> Result<Json<AppResponse<Vec<DatabaseRowUpdatedItem>>>> {
-  let (workspace_id, db_id) = path_param.into_inner();
+  payload: Json<RemoveCollabGuestParams>,
+) -> Result<JsonAppResponse<()>> {
+  let (workspace_id, object_id) = path_param.into_inner();
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
-
   state
-    .workspace_access_control
-    .enforce_action(&uid, &workspace_id, Action::Read)
+    .collab_access_control
+    .enforce_access_level(
+      &workspace_id.to_string(),
+      &uid,
+      &object_id,
+      AFAccessLevel::FullAccess,
+    )
     .await?;
-
-  // Default to 1 hour ago
-  let after: DateTime<Utc> = param
-    .after
-    .unwrap_or_else(|| Utc::now() - Duration::hours(1));
-
-  let db_rows = biz::collab::ops::list_database_row_ids_updated(
-    &state.collab_access_control_storage,
+  remove_collab_guest(
     &state.pg_pool,
+    &state.collab_guest_cache,
     &workspace_id,
-    &db_id,
-    &after,
+    &object_id,
+    &payload.email,
   )
   .await?;
-  Ok(Json(AppResponse::Ok().with_data(db_rows)))
+  Ok(Json(AppResponse::Ok()))
 }
 
-async fn list_database_row_details_handler(
+async fn convert_guest_to_member_handler(
   user_uuid: UserUuid,
-  path_param: web::Path<(String, String)>,
+  path_param: web::Path<(Uuid, i64)>,
   state: Data<AppState>,
-  param: web::Query<ListDatabaseRowDetailParam>,
-) -> Result<Json<AppResponse<Vec<AFDatabaseRowDetail>>>> {
-  let (workspace_id, db_id) = path_param.into_inner();
+) -> Result<JsonAppResponse<()>> {
+  let (workspace_id, guest_uid) = path_param.into_inner();
   let uid = state.user_cache.get_user_uid(&user_uuid).await?;
-  let list_db_row_query = param.into_inner();
-  let with_doc = list_db_row_query.with_doc.unwrap_or_default();
-  let row_ids = list_db_row_query.into_ids();
-
-  if let Err(e) = Uuid::parse_str(&workspace_id) {
-    return Err(
-      AppError::InvalidRequest(format!("invalid workspace id `{}`: {}", db_id, e)).into(),
-    );
-  }
-  if let Err(e) = Uuid::parse_str(&db_id) {
-    return Err(AppError::InvalidRequest(format!("invalid database id `{}`: {}", db_id, e)).into());
-  }
-
-  for id in row_ids.iter() {
-    if let Err(e) = Uuid::parse_str(id) {
-      return Err(AppError::InvalidRequest(format!("invalid row id `{}`: {}", id, e)).into());
-    }
-  }
-
   state
     .workspace_access_control
-    .enforce_action(&uid, &workspace_id, Action::Read)
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
     .await?;
-
-  static UNSUPPORTED_FIELD_TYPES: &[FieldType] = &[FieldType::Relation];
-
-  let db_rows = biz::collab::ops::list_database_row_details(
-    &state.collab_access_control_storage,
-    uid,
-    workspace_id,
-    db_id,
-    &row_ids,
-    UNSUPPORTED_FIELD_TYPES,
-    with_doc,
+  convert_guest_to_member(
+    &state.pg_pool,
+    &state.config.billing,
+    &state.workspace_access_control,
+    &state.collab_guest_cache,
+    &workspace_id,
+    guest_uid,
   )
   .await?;
-  Ok(Json(AppResponse::Ok().with_data(db_rows)))
+  Ok(Json(AppResponse::Ok()))
 }
 
 #[inline]
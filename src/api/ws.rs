@@ -2,7 +2,7 @@ use std::collections::HashMap;
 use std::time::Duration;
 
 use actix::Addr;
-use actix_http::header::AUTHORIZATION;
+use actix_http::header::{HeaderName, HeaderValue, AUTHORIZATION};
 use actix_web::web::{Data, Path, Payload};
 use actix_web::{get, web, HttpRequest, HttpResponse, Result, Scope};
 use actix_web_actors::ws;
@@ -13,14 +13,21 @@ use tokio::sync::mpsc::Sender;
 use tracing::{debug, error, instrument, trace};
 
 use app_error::AppError;
-use appflowy_collaborate::actix_ws::client::rt_client::RealtimeClient;
+use appflowy_collaborate::actix_ws::client::rt_client::{RealtimeClient, SessionLimit};
 use appflowy_collaborate::actix_ws::server::RealtimeServerActor;
 use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
 use authentication::jwt::{authorization_from_token, UserUuid};
 use collab_rt_entity::user::{AFUserChange, RealtimeUser, UserMessage};
-use collab_rt_entity::RealtimeMessage;
+use collab_rt_entity::{
+  RealtimeMessage, RealtimeProtocol, CAPABILITIES_HEADER, PROTOCOL_VERSION_HEADER,
+};
+use shared_entity::dto::server_info_dto::{
+  parse_region_latencies, RegionInfo, PREFERRED_REGION_ENDPOINT_HEADER, PREFERRED_REGION_HEADER,
+  REGION_LATENCY_HEADER, REGION_WORKSPACE_HEADER,
+};
 use shared_entity::response::AppResponseError;
 
+use crate::biz::region::preferred_region;
 use crate::state::AppState;
 
 pub fn ws_scope() -> Scope {
@@ -56,6 +63,7 @@ pub async fn establish_ws_connection(
     device_id,
     client_version,
     connect_at,
+    RealtimeProtocol::legacy(),
   )
   .await
 }
@@ -76,6 +84,9 @@ pub async fn establish_ws_connection_v1(
     client_version,
     device_id,
     connect_at,
+    workspace_id,
+    region_latencies,
+    client_protocol,
   } = match ConnectInfo::parse_from(&request) {
     Ok(info) => info,
     Err(_) => {
@@ -88,7 +99,15 @@ pub async fn establish_ws_connection_v1(
     return Err(AppError::Connect("Client version is too low".to_string()).into());
   }
 
-  start_connect(
+  let protocol = RealtimeProtocol::current().negotiate(&client_protocol);
+  let region = preferred_region(
+    &state.pg_pool,
+    &state.config.region,
+    workspace_id.as_ref(),
+    &region_latencies,
+  )
+  .await;
+  let mut response = start_connect(
     &request,
     payload,
     &state,
@@ -98,8 +117,46 @@ pub async fn establish_ws_connection_v1(
     device_id,
     client_version,
     connect_at,
+    protocol.clone(),
   )
-  .await
+  .await?;
+  insert_protocol_headers(&mut response, &protocol);
+  if let Some(region) = region {
+    insert_region_headers(&mut response, &region);
+  }
+  Ok(response)
+}
+
+/// Hints the client to reconnect to the given region. The connection is accepted, the client
+/// decides whether to follow the hint.
+fn insert_region_headers(response: &mut HttpResponse, region: &RegionInfo) {
+  let headers = [
+    (PREFERRED_REGION_HEADER, &region.name),
+    (PREFERRED_REGION_ENDPOINT_HEADER, &region.ws_url),
+  ];
+  for (name, value) in headers {
+    if let Ok(value) = HeaderValue::from_str(value) {
+      response
+        .headers_mut()
+        .insert(HeaderName::from_static(name), value);
+    }
+  }
+}
+
+/// Tells the client the protocol version and the capabilities enabled for the connection. The
+/// clients that don't read the headers keep the legacy protocol.
+fn insert_protocol_headers(response: &mut HttpResponse, protocol: &RealtimeProtocol) {
+  let headers = [
+    (PROTOCOL_VERSION_HEADER, protocol.version.to_string()),
+    (CAPABILITIES_HEADER, protocol.capabilities_header()),
+  ];
+  for (name, value) in headers {
+    if let Ok(value) = HeaderValue::from_str(&value) {
+      response
+        .headers_mut()
+        .insert(HeaderName::from_static(name), value);
+    }
+  }
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -114,16 +171,22 @@ async fn start_connect(
   device_id: String,
   client_app_version: Version,
   connect_at: i64,
+  protocol: RealtimeProtocol,
 ) -> Result<HttpResponse> {
   let auth = authorization_from_token(access_token.as_str(), jwt_secret)?;
+  let auth_session_id = auth
+    .claims
+    .session_id
+    .as_deref()
+    .and_then(|session_id| uuid::Uuid::parse_str(session_id).ok());
   let user_uuid = UserUuid::from_auth(auth)?;
   let result = state.user_cache.get_user_uid(&user_uuid).await;
 
   match result {
     Ok(uid) => {
       debug!(
-        "🚀new websocket connect: uid={}, device_id={}, client_version:{}",
-        uid, device_id, client_app_version
+        "🚀new websocket connect: uid={}, device_id={}, client_version:{}, protocol:{}",
+        uid, device_id, client_app_version, protocol
       );
 
       let session_id = uuid::Uuid::new_v4().to_string();
@@ -134,6 +197,11 @@ async fn start_connect(
         connect_at,
         client_app_version.to_string(),
       );
+      let session_limit = SessionLimit {
+        max_sessions: state.session_policy.max_sessions(uid).await,
+        policy: state.session_policy.clone(),
+        auth_session_id,
+      };
       let (tx, external_source) = mpsc::channel(100);
       let client = RealtimeClient::new(
         realtime_user,
@@ -143,7 +211,9 @@ async fn start_connect(
         client_app_version,
         external_source,
         10,
-      );
+      )
+      .with_session_limit(session_limit)
+      .with_protocol(protocol);
 
       // Receive user change notifications and send them to the client.
       listen_on_user_change(state, uid, tx);
@@ -199,6 +269,12 @@ struct ConnectInfo {
   client_version: Version,
   device_id: String,
   connect_at: i64,
+  /// The workspace opened by the client, and the latencies it measured to the regions, used to
+  /// hint the region to connect to.
+  workspace_id: Option<uuid::Uuid>,
+  region_latencies: HashMap<String, u32>,
+  /// The legacy protocol for the clients that don't send their protocol version.
+  client_protocol: RealtimeProtocol,
 }
 
 const CLIENT_VERSION: &str = "client-version";
@@ -253,12 +329,30 @@ impl ConnectInfo {
         .unwrap_or_else(|_| chrono::Utc::now().timestamp()),
       Err(_) => chrono::Utc::now().timestamp(),
     };
+    let workspace_id = source
+      .extract_param(REGION_WORKSPACE_HEADER)
+      .ok()
+      .and_then(|workspace_id| uuid::Uuid::parse_str(&workspace_id).ok());
+    let region_latencies = source
+      .extract_param(REGION_LATENCY_HEADER)
+      .map(|value| parse_region_latencies(&value))
+      .unwrap_or_default();
+    let client_protocol = RealtimeProtocol::from_headers(
+      source
+        .extract_param(PROTOCOL_VERSION_HEADER)
+        .ok()
+        .as_deref(),
+      source.extract_param(CAPABILITIES_HEADER).ok().as_deref(),
+    );
 
     Ok(Self {
       access_token,
       client_version,
       device_id,
       connect_at,
+      workspace_id,
+      region_latencies,
+      client_protocol,
     })
   }
 }
@@ -6,19 +6,27 @@ use access_control::casbin::access::AccessControl;
 use access_control::casbin::collab::{CollabAccessControlImpl, RealtimeCollabAccessControlImpl};
 use access_control::casbin::workspace::WorkspaceAccessControlImpl;
 use access_control::collab::{CollabAccessControl, RealtimeAccessControl};
+use access_control::collab_guest::CollabGuestCache;
+use access_control::collab_lock::{CollabLockCache, CollabLockRealtimeAccessControl};
 use access_control::noops::collab::{
   CollabAccessControlImpl as NoOpsCollabAccessControlImpl,
   RealtimeCollabAccessControlImpl as NoOpsRealtimeCollabAccessControlImpl,
 };
 use access_control::noops::workspace::WorkspaceAccessControlImpl as NoOpsWorkspaceAccessControlImpl;
+use access_control::restricted_view::RestrictedViewCache;
+use access_control::session_policy::SessionPolicy;
 use access_control::workspace::WorkspaceAccessControl;
+use access_control::workspace_read_only::WorkspaceReadOnlyCache;
 use actix::Supervisor;
 use actix_identity::IdentityMiddleware;
 use actix_session::storage::RedisSessionStore;
 use actix_session::SessionMiddleware;
 use actix_web::cookie::Key;
 use actix_web::middleware::NormalizePath;
-use actix_web::{dev::Server, web, web::Data, App, HttpResponse, HttpServer, Responder};
+use actix_web::web::PayloadConfig;
+use actix_web::{
+  dev::Server, dev::ServerHandle, web, web::Data, App, HttpResponse, HttpServer, Responder,
+};
 use anyhow::{Context, Error};
 use appflowy_collaborate::collab::access_control::CollabStorageAccessControlImpl;
 use aws_sdk_s3::config::{Credentials, Region, SharedCredentialsProvider};
@@ -29,19 +37,32 @@ use aws_sdk_s3::types::{
 use mailer::config::MailerSetting;
 use secrecy::{ExposeSecret, Secret};
 use sqlx::{postgres::PgPoolOptions, PgPool};
-use tokio::sync::RwLock;
+use tokio::sync::{watch, RwLock};
 use tracing::{error, info};
 
 use appflowy_ai_client::client::AppFlowyAIClient;
 use appflowy_collaborate::actix_ws::server::RealtimeServerActor;
 use appflowy_collaborate::collab::cache::CollabCache;
 use appflowy_collaborate::collab::storage::CollabStorageImpl;
-use appflowy_collaborate::command::{CLCommandReceiver, CLCommandSender};
+use appflowy_collaborate::collab::view_tree::CollabCacheViewTreeLoader;
+use appflowy_collaborate::command::{
+  spawn_workspace_member_revocation, CLCommandReceiver, CLCommandSender,
+};
+use appflowy_collaborate::config::ReloadableSetting;
 use appflowy_collaborate::snapshot::SnapshotControl;
 use appflowy_collaborate::CollaborationServer;
 use collab_stream::metrics::CollabStreamMetrics;
 use collab_stream::stream_router::{StreamRouter, StreamRouterOptions};
-use database::file::s3_client_impl::{AwsS3BucketClientImpl, S3BucketStorage};
+use database::feature_flag::FeatureFlags;
+use database::file::azure_client_impl::AzureBlobClientImpl;
+use database::file::fs_client_impl::LocalFsBucketClientImpl;
+use database::file::gcs_client_impl::GcsBucketClientImpl;
+use database::file::memory_client_impl::InMemoryBucketClientImpl;
+use database::file::s3_client_impl::AwsS3BucketClientImpl;
+use database::file::{
+  BlobBucketStorage, BlobStorageBackend, BlobStorageClient, WorkspaceStorageCipher,
+  WorkspaceStorageRouter,
+};
 use indexer::collab_indexer::IndexerProvider;
 use indexer::scheduler::{IndexerConfiguration, IndexerScheduler};
 use infra::env_util::get_env_var;
@@ -49,26 +70,45 @@ use mailer::sender::Mailer;
 use snowflake::Snowflake;
 
 use crate::api::access_request::access_request_scope;
+use crate::api::admin_publish::admin_publish_report_scope;
+use crate::api::admin_user::admin_user_scope;
 use crate::api::ai::ai_completion_scope;
-use crate::api::chat::chat_scope;
+use crate::api::billing::billing_scope;
+use crate::api::chat::{chat_admin_scope, chat_scope};
 use crate::api::data_import::data_import_scope;
-use crate::api::file_storage::file_storage_scope;
+use crate::api::feature_flag::feature_flag_scope;
+use crate::api::file_storage::{file_storage_admin_scope, file_storage_scope};
 use crate::api::metrics::metrics_scope;
+use crate::api::openapi::openapi_scope;
+use crate::api::organization::organization_scope;
+use crate::api::public::public_scope;
 use crate::api::search::search_scope;
 use crate::api::server_info::server_info_scope;
 use crate::api::template::template_scope;
 use crate::api::user::user_scope;
+use crate::api::util::json_config;
 use crate::api::workspace::{collab_scope, workspace_scope};
 use crate::api::ws::ws_scope;
+use crate::biz::ai::language_check::LanguageChecker;
+use crate::biz::billing::ops::run_read_only_refresher;
+use crate::biz::notification::push::PushDispatcher;
+use crate::biz::notification::NotificationCenter;
 use crate::biz::pg_listener::PgListeners;
 use crate::biz::workspace::publish::{
   PublishedCollabPostgresStore, PublishedCollabS3StoreWithPostgresFallback, PublishedCollabStore,
 };
+use crate::biz::workspace::publish_analytics::PublishAnalyticsRecorder;
+use crate::biz::workspace::publish_cache::PublishedCollabCachedStore;
+use crate::biz::workspace::publish_domain::run_publish_domain_checker;
+use crate::biz::workspace::publish_feed::PublishedCollabFeedStore;
+use crate::biz::workspace::publish_moderation::{PublishModeration, PublishedCollabModeratedStore};
 use crate::config::config::{
   Config, DatabaseSetting, GoTrueSetting, PublishedCollabStorageBackend, S3Setting,
 };
 use crate::mailer::AFCloudMailer;
+use crate::middleware::cors_mw::CorsMiddleware;
 use crate::middleware::metrics_mw::MetricsMiddleware;
+use crate::middleware::read_only_mw::WorkspaceReadOnlyMiddleware;
 use crate::middleware::request_id::RequestIdMiddleware;
 use crate::state::{AppMetrics, AppState, GoTrueAdmin, UserCache};
 
@@ -99,6 +139,11 @@ impl Application {
   pub fn port(&self) -> u16 {
     self.port
   }
+
+  /// Handle stopping the server once it runs.
+  pub fn handle(&self) -> ServerHandle {
+    self.actix_server.handle()
+  }
 }
 
 pub async fn run_actix_server(
@@ -123,21 +168,33 @@ pub async fn run_actix_server(
   let realtime_server = CollaborationServer::<_>::new(
     storage.clone(),
     state.realtime_access_control.clone(),
+    state.restricted_view_cache.clone(),
     state.metrics.realtime_metrics.clone(),
     rt_cmd_recv,
+    state.redis_client.clone(),
     state.redis_stream_router.clone(),
     state.redis_connection_manager.clone(),
-    Duration::from_secs(config.collab.group_persistence_interval_secs),
-    Duration::from_secs(config.collab.group_prune_grace_period_secs),
+    // the settings are not reloaded when the collaboration server is embedded in appflowy cloud
+    watch::channel(ReloadableSetting {
+      group_persistence_interval: Duration::from_secs(
+        config.collab.group_persistence_interval_secs,
+      ),
+      group_prune_grace_period: Duration::from_secs(config.collab.group_prune_grace_period_secs),
+      websocket_rate_limit_per_sec: 10,
+    })
+    .1,
     state.indexer_scheduler.clone(),
   )
   .await
   .unwrap();
 
   let realtime_server_actor = Supervisor::start(|_| RealtimeServerActor(realtime_server));
+  let cors_setting = config.cors.clone();
+  let payload_limits = config.application.payload_limits.clone();
   let mut server = HttpServer::new(move || {
     App::new()
       .wrap(NormalizePath::trim())
+      .wrap(WorkspaceReadOnlyMiddleware)
        // Middleware is registered for each App, scope, or Resource and executed in opposite order as registration
       .wrap(MetricsMiddleware)
       .wrap(IdentityMiddleware::default())
@@ -146,20 +203,32 @@ pub async fn run_actix_server(
           .build(),
       )
       .wrap(RequestIdMiddleware)
+      .wrap(CorsMiddleware::new(cors_setting.clone()))
       .service(server_info_scope())
+      .service(openapi_scope())
       .service(user_scope())
-      .service(workspace_scope())
-      .service(collab_scope())
+      .service(workspace_scope(&payload_limits))
+      .service(collab_scope(&payload_limits))
       .service(ws_scope())
       .service(file_storage_scope())
-      .service(chat_scope())
+      .service(chat_scope(&payload_limits))
+      .service(chat_admin_scope())
+      .service(feature_flag_scope())
+      .service(admin_user_scope())
+      .service(admin_publish_report_scope())
+      .service(file_storage_admin_scope())
       .service(ai_completion_scope())
       .service(metrics_scope())
       .service(search_scope())
       .service(template_scope())
       .service(data_import_scope())
       .service(access_request_scope())
+      .service(organization_scope())
+      .service(public_scope())
+      .service(billing_scope())
       .route("/health", web::get().to(health_check))
+      .app_data(PayloadConfig::new(payload_limits.default))
+      .app_data(json_config(payload_limits.json))
       .app_data(Data::new(state.metrics.registry.clone()))
       .app_data(Data::new(state.metrics.request_metrics.clone()))
       .app_data(Data::new(state.metrics.realtime_metrics.clone()))
@@ -176,6 +245,24 @@ pub async fn run_actix_server(
   Ok(server.run())
 }
 
+/// Runs the workers of appflowy_worker in this process, with the Postgres pool and the Redis
+/// connection of the server. The realtime server already runs in this process and receives its
+/// commands over in-process channels, so only the background tasks go through Redis: they keep
+/// using the Redis streams, which survive a restart of the process and are still shared with any
+/// appflowy_worker instance added later.
+#[cfg(feature = "single-binary")]
+pub async fn run_embedded_workers(state: &AppState) -> Result<(), Error> {
+  let config = appflowy_worker::config::Config::from_env()?;
+  info!("Running the workers in the appflowy_cloud process");
+  appflowy_worker::application::run_workers(
+    &config,
+    state.pg_pool.clone(),
+    state.redis_connection_manager.clone(),
+    None,
+  )
+  .await
+}
+
 pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<AppState, Error> {
   // Print the feature flags
 
@@ -188,16 +275,17 @@ pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<A
 
   // Bucket storage
   info!("Setting up S3 bucket...");
-  let s3_client = AwsS3BucketClientImpl::new(
-    get_aws_s3_client(&config.s3).await?,
-    config.s3.bucket.clone(),
-    config.s3.minio_url.clone(),
-    config.s3.presigned_url_endpoint.clone(),
-  );
-  let bucket_storage = Arc::new(S3BucketStorage::from_bucket_impl(
-    s3_client.clone(),
-    pg_pool.clone(),
-  ));
+  let workspace_storage_router = get_workspace_storage_router(config, &pg_pool)?;
+  let s3_client = match &workspace_storage_router {
+    Some(router) => {
+      info!("Routing the objects of the workspaces with their own storage ...");
+      get_blob_storage_client(config)
+        .await?
+        .with_workspace_storage(router.clone())
+    },
+    None => get_blob_storage_client(config).await?,
+  };
+  let bucket_storage = Arc::new(BlobBucketStorage::new(s3_client.clone(), pg_pool.clone()));
 
   // Published Collab Storage
   info!("Setting up Published Collab storage...");
@@ -227,19 +315,63 @@ pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<A
 
   // Redis
   info!("Connecting to Redis...");
-  let (redis_conn_manager, redis_stream_router) = get_redis_client(
+  let (redis_client, redis_conn_manager, redis_stream_router) = get_redis_client(
     config.redis_uri.expose_secret(),
     config.redis_worker_count,
     metrics.collab_stream_metrics.clone(),
   )
   .await?;
 
+  let published_collab_store: Arc<dyn PublishedCollabStore> =
+    if config.published_collab.cache_enabled {
+      info!("Caching published collabs in Redis and the blob storage ...");
+      Arc::new(PublishedCollabCachedStore::new(
+        published_collab_store,
+        pg_pool.clone(),
+        redis_conn_manager.clone(),
+        s3_client.clone(),
+        config.published_collab.cache_ttl_secs,
+      ))
+    } else {
+      published_collab_store
+    };
+
+  let published_collab_store: Arc<dyn PublishedCollabStore> = Arc::new(
+    PublishedCollabFeedStore::new(published_collab_store, redis_conn_manager.clone()),
+  );
+
+  // The moderation is the outermost layer, so rejected content never reaches the cache.
+  let moderation = PublishModeration::new(&config.published_collab);
+  let published_collab_store: Arc<dyn PublishedCollabStore> = if moderation.is_enabled() {
+    info!("Checking the published collabs against the moderation denylists ...");
+    Arc::new(PublishedCollabModeratedStore::new(
+      published_collab_store,
+      moderation,
+      metrics.published_collab_metrics.clone(),
+    ))
+  } else {
+    published_collab_store
+  };
+
   info!("Setup AppFlowy AI: {}", config.appflowy_ai.url());
   let appflowy_ai_client = AppFlowyAIClient::new(&config.appflowy_ai.url());
+  if let Some(url) = &config.appflowy_ai.language_check_url {
+    info!("Checking the spelling and the grammar with {}", url);
+  }
+  let language_checker = Arc::new(LanguageChecker::new(
+    appflowy_ai_client.clone(),
+    config.appflowy_ai.language_check_url.clone(),
+    redis_conn_manager.clone(),
+    config.appflowy_ai.language_check_cache_ttl_secs,
+  ));
   // Pg listeners
   info!("Setting up Pg listeners...");
   let pg_listeners = Arc::new(PgListeners::new(&pg_pool).await?);
   // let collab_member_listener = pg_listeners.subscribe_collab_member_change();
+  spawn_workspace_member_revocation(
+    pg_listeners.subscribe_workspace_member_change(),
+    rt_cmd_tx.clone(),
+  );
 
   info!(
     "Setting up access controls, is_enable: {}",
@@ -249,9 +381,36 @@ pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<A
     AccessControl::new(pg_pool.clone(), metrics.access_control_metrics.clone()).await?;
 
   let user_cache = UserCache::new(pg_pool.clone()).await;
+  let collab_lock_cache = CollabLockCache::new(pg_pool.clone());
+  let workspace_read_only_cache = WorkspaceReadOnlyCache::new(pg_pool.clone());
+  let collab_cache = CollabCache::new(
+    redis_conn_manager.clone(),
+    pg_pool.clone(),
+    s3_client.clone(),
+    metrics.collab_metrics.clone(),
+    config.collab.s3_collab_threshold as usize,
+  );
+  let restricted_view_cache =
+    RestrictedViewCache::new(pg_pool.clone()).with_view_tree_loader(Arc::new(
+      CollabCacheViewTreeLoader::new(collab_cache.clone(), pg_pool.clone()),
+    ));
+  let collab_guest_cache = CollabGuestCache::new(pg_pool.clone());
+  tokio::spawn(run_read_only_refresher(
+    pg_pool.clone(),
+    config.billing.clone(),
+    workspace_read_only_cache.clone(),
+  ));
+  tokio::spawn(run_publish_domain_checker(
+    pg_pool.clone(),
+    config.published_collab.clone(),
+  ));
   let collab_access_control: Arc<dyn CollabAccessControl> =
     if config.access_control.is_enabled && config.access_control.enable_collab_access_control {
-      Arc::new(CollabAccessControlImpl::new(access_control.clone()))
+      Arc::new(CollabAccessControlImpl::new(
+        access_control.clone(),
+        collab_guest_cache.clone(),
+        restricted_view_cache.clone(),
+      ))
     } else {
       Arc::new(NoOpsCollabAccessControlImpl::new())
     };
@@ -263,27 +422,30 @@ pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<A
     };
   let realtime_access_control: Arc<dyn RealtimeAccessControl> =
     if config.access_control.is_enabled && config.access_control.enable_realtime_access_control {
-      Arc::new(RealtimeCollabAccessControlImpl::new(access_control))
+      Arc::new(RealtimeCollabAccessControlImpl::new(
+        access_control,
+        workspace_read_only_cache.clone(),
+        collab_guest_cache.clone(),
+        restricted_view_cache.clone(),
+      ))
     } else {
       Arc::new(NoOpsRealtimeCollabAccessControlImpl::new())
     };
-  let collab_cache = CollabCache::new(
-    redis_conn_manager.clone(),
-    pg_pool.clone(),
-    s3_client.clone(),
-    metrics.collab_metrics.clone(),
-    config.collab.s3_collab_threshold as usize,
+  let realtime_access_control: Arc<dyn RealtimeAccessControl> = Arc::new(
+    CollabLockRealtimeAccessControl::new(realtime_access_control, collab_lock_cache.clone()),
   );
 
   let collab_storage_access_control = CollabStorageAccessControlImpl {
     collab_access_control: collab_access_control.clone(),
     workspace_access_control: workspace_access_control.clone(),
+    collab_lock: collab_lock_cache.clone(),
     cache: collab_cache.clone(),
   };
   let snapshot_control = SnapshotControl::new(
     pg_pool.clone(),
     s3_client.clone(),
     metrics.collab_metrics.clone(),
+    config.collab.snapshot_offload_threshold,
   )
   .await;
   let collab_access_control_storage = Arc::new(CollabStorageImpl::new(
@@ -294,6 +456,11 @@ pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<A
   ));
 
   let mailer = get_mailer(&config.mailer).await?;
+  let notification_center = NotificationCenter::new(PushDispatcher::new(
+    &config.push,
+    pg_pool.clone(),
+    metrics.push_metrics.clone(),
+  )?);
 
   info!("Setting up Indexer scheduler...");
   let embedder_config = IndexerConfiguration {
@@ -317,6 +484,10 @@ pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<A
     redis_conn_manager.clone(),
   );
 
+  let publish_analytics = PublishAnalyticsRecorder::new(pg_pool.clone(), &config.published_collab);
+  let feature_flags = FeatureFlags::new(pg_pool.clone());
+  let session_policy = SessionPolicy::new(pg_pool.clone(), config.websocket.max_sessions_per_user);
+
   info!("Application state initialized");
   Ok(AppState {
     pg_pool,
@@ -324,6 +495,7 @@ pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<A
     user_cache,
     id_gen: Arc::new(RwLock::new(Snowflake::new(1))),
     gotrue_client,
+    redis_client,
     redis_stream_router,
     redis_connection_manager: redis_conn_manager,
     collab_cache,
@@ -331,19 +503,29 @@ pub async fn init_state(config: &Config, rt_cmd_tx: CLCommandSender) -> Result<A
     collab_access_control,
     workspace_access_control,
     realtime_access_control,
+    collab_lock_cache,
+    workspace_read_only_cache,
+    restricted_view_cache,
+    collab_guest_cache,
+    session_policy,
     bucket_storage,
     published_collab_store,
+    publish_analytics,
     bucket_client: s3_client,
+    workspace_storage_router,
     pg_listeners,
     metrics,
     gotrue_admin,
     mailer,
     ai_client: appflowy_ai_client,
+    language_checker,
+    notification_center,
     indexer_scheduler,
+    feature_flags,
   })
 }
 
-fn get_admin_client(
+pub fn get_admin_client(
   gotrue_client: gotrue::api::Client,
   gotrue_setting: &GoTrueSetting,
 ) -> GoTrueAdmin {
@@ -360,7 +542,14 @@ async fn get_redis_client(
   redis_uri: &str,
   worker_count: usize,
   metrics: Arc<CollabStreamMetrics>,
-) -> Result<(redis::aio::ConnectionManager, Arc<StreamRouter>), Error> {
+) -> Result<
+  (
+    redis::Client,
+    redis::aio::ConnectionManager,
+    Arc<StreamRouter>,
+  ),
+  Error,
+> {
   info!("Connecting to redis with uri: {}", redis_uri);
   let client = redis::Client::open(redis_uri).context("failed to connect to redis")?;
 
@@ -379,7 +568,89 @@ async fn get_redis_client(
     .get_connection_manager()
     .await
     .context("failed to get the connection manager")?;
-  Ok((manager, router.into()))
+  Ok((client, manager, router.into()))
+}
+
+pub async fn get_blob_storage_client(config: &Config) -> Result<BlobStorageClient, Error> {
+  let s3_setting = &config.s3;
+  let client = match config.blob_storage.backend {
+    BlobStorageBackend::S3 => BlobStorageClient::S3(AwsS3BucketClientImpl::new(
+      get_aws_s3_client(s3_setting).await?,
+      s3_setting.bucket.clone(),
+      s3_setting.minio_url.clone(),
+      s3_setting.presigned_url_endpoint.clone(),
+    )),
+    BlobStorageBackend::Gcs => {
+      info!("Using Google Cloud Storage as the blob storage backend ...");
+      let gcs_setting = S3Setting {
+        // Buckets are expected to be created in the Google Cloud console.
+        create_bucket: false,
+        use_minio: true,
+        minio_url: config.blob_storage.gcs_endpoint.clone(),
+        region: if s3_setting.region.is_empty() {
+          "auto".to_string()
+        } else {
+          s3_setting.region.clone()
+        },
+        ..s3_setting.clone()
+      };
+      BlobStorageClient::Gcs(GcsBucketClientImpl::new(AwsS3BucketClientImpl::new(
+        get_aws_s3_client(&gcs_setting).await?,
+        gcs_setting.bucket.clone(),
+        gcs_setting.minio_url.clone(),
+        gcs_setting.presigned_url_endpoint.clone(),
+      )))
+    },
+    BlobStorageBackend::Azure => {
+      info!("Using Azure Blob Storage as the blob storage backend ...");
+      let azure = &config.blob_storage.azure;
+      BlobStorageClient::Azure(AzureBlobClientImpl::new(
+        azure.account_name.clone(),
+        azure.account_key.expose_secret(),
+        azure.container.clone(),
+        azure.endpoint.clone(),
+      )?)
+    },
+    BlobStorageBackend::LocalFs => {
+      let local = &config.blob_storage.local;
+      info!(
+        "Using local directory {} as the blob storage backend ...",
+        local.root
+      );
+      BlobStorageClient::LocalFs(LocalFsBucketClientImpl::new(
+        local.root.clone().into(),
+        local.signing_key.expose_secret(),
+        local.public_url.clone(),
+      )?)
+    },
+    BlobStorageBackend::Memory => BlobStorageClient::Memory(InMemoryBucketClientImpl::new()),
+  };
+  Ok(client)
+}
+
+/// Returns the router of the workspaces that use their own bucket, None when the per-workspace
+/// storage is not configured.
+pub fn get_workspace_storage_router(
+  config: &Config,
+  pg_pool: &PgPool,
+) -> Result<Option<Arc<WorkspaceStorageRouter>>, Error> {
+  let Some(key) = &config.blob_storage.workspace_storage_key else {
+    return Ok(None);
+  };
+  let cipher = WorkspaceStorageCipher::from_base64(key.expose_secret())?;
+  let credentials = Credentials::new(
+    config.s3.access_key.clone(),
+    config.s3.secret_key.expose_secret().clone(),
+    None,
+    None,
+    "custom",
+  );
+  Ok(Some(Arc::new(WorkspaceStorageRouter::new(
+    pg_pool.clone(),
+    cipher,
+    credentials,
+    Duration::from_secs(config.blob_storage.workspace_storage_cache_ttl_secs),
+  ))))
 }
 
 pub async fn get_aws_s3_client(s3_setting: &S3Setting) -> Result<aws_sdk_s3::Client, Error> {
@@ -476,7 +747,7 @@ async fn get_mailer(mailer: &MailerSetting) -> Result<AFCloudMailer, Error> {
   AFCloudMailer::new(mailer).await
 }
 
-async fn get_connection_pool(setting: &DatabaseSetting) -> Result<PgPool, Error> {
+pub async fn get_connection_pool(setting: &DatabaseSetting) -> Result<PgPool, Error> {
   info!("Connecting to postgres database with setting: {}", setting);
   PgPoolOptions::new()
     .max_connections(setting.max_connections)
@@ -496,7 +767,7 @@ async fn migrate(pool: &PgPool) -> Result<(), Error> {
     .map_err(|e| anyhow::anyhow!("Failed to run migrations: {}", e))
 }
 
-async fn get_gotrue_client(setting: &GoTrueSetting) -> Result<gotrue::api::Client, Error> {
+pub async fn get_gotrue_client(setting: &GoTrueSetting) -> Result<gotrue::api::Client, Error> {
   info!("Connecting to GoTrue with setting: {:?}", setting);
   let gotrue_client = gotrue::api::Client::new(reqwest::Client::new(), &setting.base_url);
   let _ = gotrue_client
@@ -1,7 +1,12 @@
+use std::collections::HashMap;
 use std::ops::DerefMut;
 use std::sync::Arc;
 
+use crate::biz::billing::ops::check_member_limit;
 use crate::biz::collab::utils::get_latest_collab_folder;
+use crate::biz::notification::push::PushMessage;
+use crate::biz::notification::{NotificationCenter, NotificationPriority};
+use crate::config::config::BillingSetting;
 use crate::mailer::AFCloudMailer;
 use crate::{
   biz::collab::folder_view::{to_dto_view_icon, to_dto_view_layout},
@@ -116,8 +121,10 @@ pub async fn get_access_request(
 #[allow(clippy::too_many_arguments)]
 pub async fn approve_or_reject_access_request(
   pg_pool: &PgPool,
+  billing: &BillingSetting,
   workspace_access_control: Arc<dyn WorkspaceAccessControl>,
   mailer: AFCloudMailer,
+  notification_center: &NotificationCenter,
   appflowy_web_url: &str,
   request_id: Uuid,
   uid: i64,
@@ -132,6 +139,9 @@ pub async fn approve_or_reject_access_request(
     )
     .await?;
 
+  if is_approved {
+    check_member_limit(pg_pool, billing, &access_request.workspace.workspace_id, 1).await?;
+  }
   let mut txn = pg_pool.begin().await.context("approving request")?;
   let role = AFRole::Member;
   if is_approved {
@@ -149,6 +159,21 @@ pub async fn approve_or_reject_access_request(
         role.clone(),
       )
       .await?;
+    notification_center.notify(
+      access_request.requester.uid,
+      NotificationPriority::High,
+      PushMessage {
+        title: "Access request approved".to_string(),
+        body: format!(
+          "You can now open {}",
+          access_request.workspace.workspace_name
+        ),
+        data: HashMap::from([(
+          "workspace_id".to_string(),
+          access_request.workspace.workspace_id.to_string(),
+        )]),
+      },
+    );
     let cloned_mailer = mailer.clone();
     let launch_workspace_url = format!(
       "{}/app/{}",
@@ -0,0 +1,258 @@
+use app_error::AppError;
+use appflowy_ai_client::client::AppFlowyAIClient;
+use appflowy_ai_client::dto::{LanguageCheckMatch, LanguageCheckResponse};
+use futures_util::{stream, StreamExt, TryStreamExt};
+use redis::AsyncCommands;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tracing::{trace, warn};
+
+use crate::biz::chat::metrics::AIMetrics;
+use crate::state::RedisConnectionManager;
+
+/// Longest text, in characters, that can be checked in one request.
+pub const MAX_LANGUAGE_CHECK_TEXT_LEN: usize = 100_000;
+/// The text is checked in chunks of at most this many characters, the backends are slow with
+/// long texts and a chunk that did not change is found in the cache.
+const MAX_CHUNK_LEN: usize = 4_000;
+const CONCURRENT_CHUNK_CHECKS: usize = 4;
+
+enum LanguageCheckBackend {
+  AI(AppFlowyAIClient),
+  LanguageTool {
+    client: reqwest::Client,
+    url: String,
+  },
+}
+
+/// Checks the spelling and the grammar of texts with the AI service, or with a LanguageTool
+/// compatible server when one is configured. The results are cached in Redis by the hash of the
+/// checked chunk, errors of the cache are logged and the chunk is checked again.
+pub struct LanguageChecker {
+  backend: LanguageCheckBackend,
+  redis_client: RedisConnectionManager,
+  cache_ttl_secs: u64,
+}
+
+impl LanguageChecker {
+  pub fn new(
+    ai_client: AppFlowyAIClient,
+    language_tool_url: Option<String>,
+    redis_client: RedisConnectionManager,
+    cache_ttl_secs: u64,
+  ) -> Self {
+    let backend = match language_tool_url {
+      Some(url) => LanguageCheckBackend::LanguageTool {
+        client: reqwest::Client::new(),
+        url: url.trim_end_matches('/').to_string(),
+      },
+      None => LanguageCheckBackend::AI(ai_client),
+    };
+    Self {
+      backend,
+      redis_client,
+      cache_ttl_secs,
+    }
+  }
+
+  pub async fn check(
+    &self,
+    metrics: &AIMetrics,
+    text: &str,
+    language: Option<&str>,
+    ai_model: &str,
+  ) -> Result<LanguageCheckResponse, AppError> {
+    if text.chars().count() > MAX_LANGUAGE_CHECK_TEXT_LEN {
+      return Err(AppError::StringLengthLimitReached(format!(
+        "at most {} characters can be checked at once",
+        MAX_LANGUAGE_CHECK_TEXT_LEN
+      )));
+    }
+    metrics.record_total_language_check_count(1);
+    let chunks = split_text(text, MAX_CHUNK_LEN)
+      .into_iter()
+      .filter(|(_, chunk)| !chunk.trim().is_empty())
+      .collect::<Vec<_>>();
+    let results = stream::iter(chunks.into_iter().map(|(offset, chunk)| async move {
+      let matches = self.check_chunk(metrics, chunk, language, ai_model).await?;
+      Ok::<_, AppError>(matches.into_iter().map(move |mut m| {
+        m.offset += offset;
+        m
+      }))
+    }))
+    .buffered(CONCURRENT_CHUNK_CHECKS)
+    .try_collect::<Vec<_>>()
+    .await?;
+    Ok(LanguageCheckResponse {
+      matches: results.into_iter().flatten().collect(),
+    })
+  }
+
+  async fn check_chunk(
+    &self,
+    metrics: &AIMetrics,
+    chunk: &str,
+    language: Option<&str>,
+    ai_model: &str,
+  ) -> Result<Vec<LanguageCheckMatch>, AppError> {
+    let key = cache_key(chunk, language);
+    let cached: Result<Option<String>, _> = self.redis_client.clone().get(&key).await;
+    match cached {
+      Ok(Some(value)) => match serde_json::from_str(&value) {
+        Ok(matches) => {
+          metrics.record_language_check_cache_hit_count(1);
+          return Ok(matches);
+        },
+        Err(err) => warn!("invalid cached language check {}: {}", key, err),
+      },
+      Ok(None) => {},
+      Err(err) => warn!("failed to read language check cache: {}", err),
+    }
+
+    let matches = match &self.backend {
+      LanguageCheckBackend::AI(ai_client) => {
+        ai_client
+          .language_check(chunk, language, ai_model)
+          .await?
+          .matches
+      },
+      LanguageCheckBackend::LanguageTool { client, url } => {
+        language_tool_check(client, url, chunk, language).await?
+      },
+    };
+    trace!("language check found {} matches", matches.len());
+    if let Ok(value) = serde_json::to_string(&matches) {
+      let result: Result<(), _> = self
+        .redis_client
+        .clone()
+        .set_ex(&key, value, self.cache_ttl_secs)
+        .await;
+      if let Err(err) = result {
+        warn!("failed to write language check cache: {}", err);
+      }
+    }
+    Ok(matches)
+  }
+}
+
+fn cache_key(chunk: &str, language: Option<&str>) -> String {
+  let mut hasher = Sha256::new();
+  hasher.update(language.unwrap_or("auto").as_bytes());
+  hasher.update([0]);
+  hasher.update(chunk.as_bytes());
+  format!("af_language_check:{:x}", hasher.finalize())
+}
+
+#[derive(Deserialize)]
+struct LanguageToolResponse {
+  matches: Vec<LanguageToolMatch>,
+}
+
+#[derive(Deserialize)]
+struct LanguageToolMatch {
+  message: String,
+  offset: usize,
+  length: usize,
+  #[serde(default)]
+  replacements: Vec<LanguageToolReplacement>,
+  rule: Option<LanguageToolRule>,
+}
+
+#[derive(Deserialize)]
+struct LanguageToolReplacement {
+  value: String,
+}
+
+#[derive(Deserialize)]
+struct LanguageToolRule {
+  id: String,
+}
+
+/// The offsets returned by LanguageTool are in UTF-16 code units, the same as
+/// [LanguageCheckMatch].
+async fn language_tool_check(
+  client: &reqwest::Client,
+  url: &str,
+  text: &str,
+  language: Option<&str>,
+) -> Result<Vec<LanguageCheckMatch>, AppError> {
+  let resp = client
+    .post(format!("{}/v2/check", url))
+    .form(&[("text", text), ("language", language.unwrap_or("auto"))])
+    .send()
+    .await
+    .and_then(|resp| resp.error_for_status())
+    .map_err(|err| AppError::AIServiceUnavailable(err.to_string()))?;
+  let resp = resp
+    .json::<LanguageToolResponse>()
+    .await
+    .map_err(|err| AppError::AIServiceUnavailable(err.to_string()))?;
+  Ok(
+    resp
+      .matches
+      .into_iter()
+      .map(|m| LanguageCheckMatch {
+        offset: m.offset,
+        length: m.length,
+        message: m.message,
+        replacements: m.replacements.into_iter().map(|r| r.value).collect(),
+        rule_id: m.rule.map(|rule| rule.id),
+      })
+      .collect(),
+  )
+}
+
+/// Splits the text into chunks of at most `max_len` characters, after a line break when there is
+/// one, otherwise after a whitespace. Returns the chunks with their offset in the text, in UTF-16
+/// code units.
+fn split_text(text: &str, max_len: usize) -> Vec<(usize, &str)> {
+  let mut chunks = vec![];
+  let mut offset = 0;
+  let mut rest = text;
+  while !rest.is_empty() {
+    let end = match rest.char_indices().nth(max_len) {
+      None => rest.len(),
+      Some((end, _)) => {
+        let head = &rest[..end];
+        head
+          .rfind('\n')
+          .or_else(|| head.rfind(char::is_whitespace))
+          .map(|index| index + head[index..].chars().next().map_or(1, char::len_utf8))
+          .unwrap_or(end)
+      },
+    };
+    let (chunk, tail) = rest.split_at(end);
+    chunks.push((offset, chunk));
+    offset += chunk.encode_utf16().count();
+    rest = tail;
+  }
+  chunks
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn split_text_at_line_breaks() {
+    assert_eq!(split_text("", 10), vec![]);
+    assert_eq!(split_text("short", 10), vec![(0, "short")]);
+    assert_eq!(
+      split_text("first line\nsecond line", 15),
+      vec![(0, "first line\n"), (11, "second line")]
+    );
+    assert_eq!(
+      split_text("one two three", 9),
+      vec![(0, "one two "), (8, "three")]
+    );
+    assert_eq!(
+      split_text("abcdefgh", 3),
+      vec![(0, "abc"), (3, "def"), (6, "gh")]
+    );
+    // the offsets count the emoji as two UTF-16 code units
+    assert_eq!(
+      split_text("😀 abc def", 6),
+      vec![(0, "😀 abc "), (7, "def")]
+    );
+  }
+}
@@ -0,0 +1,449 @@
+use std::time::Duration;
+
+use access_control::workspace_read_only::WorkspaceReadOnlyCache;
+use app_error::AppError;
+use chrono::{Datelike, Utc};
+use database::billing::{
+  increment_workspace_ai_responses, select_workspace_ai_responses_since,
+  select_workspace_ids_with_plan_state, select_workspace_subscriptions,
+};
+use database::organization::select_organization_workspace_ids;
+use database::pg_row::AFSubscriptionRow;
+use database::resource_usage::get_workspace_usage_size;
+use database::workspace::select_workspace_member_count_from_workspace_id;
+use shared_entity::dto::billing_dto::{
+  RecurringInterval, SubscriptionPlan, SubscriptionStatus, WorkspaceBilling, WorkspacePlanLimits,
+  WorkspacePlanUsage,
+};
+use sqlx::PgPool;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{trace, warn};
+use uuid::Uuid;
+
+use crate::config::config::BillingSetting;
+
+/// The plan of a workspace, derived from the subscriptions that grant a plan.
+struct PlanState {
+  plan: SubscriptionPlan,
+  add_ons: Vec<SubscriptionPlan>,
+  /// The subscription of the plan, None on the free plan.
+  subscription: Option<AFSubscriptionRow>,
+  limits: WorkspacePlanLimits,
+}
+
+fn plan_rank(plan: &SubscriptionPlan) -> u8 {
+  match plan {
+    SubscriptionPlan::Team => 2,
+    SubscriptionPlan::Pro => 1,
+    _ => 0,
+  }
+}
+
+/// The subscriptions are ordered by descending end of period, so the most recent subscription
+/// wins when several grant the same plan.
+fn plan_state(setting: &BillingSetting, subscriptions: Vec<AFSubscriptionRow>) -> PlanState {
+  let mut plan = SubscriptionPlan::Free;
+  let mut add_ons = vec![];
+  let mut plan_subscription = None;
+  for subscription in subscriptions {
+    let status = SubscriptionStatus::try_from(subscription.status.as_str());
+    let sub_plan = SubscriptionPlan::try_from(subscription.plan);
+    let (status, sub_plan) = match (status, sub_plan) {
+      (Ok(status), Ok(sub_plan)) => (status, sub_plan),
+      (Err(err), _) | (_, Err(err)) => {
+        warn!(
+          "skip subscription {}: {}",
+          subscription.subscription_id, err
+        );
+        continue;
+      },
+    };
+    if !status.grants_plan() {
+      continue;
+    }
+    match sub_plan {
+      SubscriptionPlan::AiMax | SubscriptionPlan::AiLocal => {
+        if !add_ons.contains(&sub_plan) {
+          add_ons.push(sub_plan);
+        }
+      },
+      SubscriptionPlan::Pro | SubscriptionPlan::Team => {
+        if plan_rank(&sub_plan) > plan_rank(&plan) {
+          plan = sub_plan;
+          plan_subscription = Some(subscription);
+        }
+      },
+      SubscriptionPlan::Free => {},
+    }
+  }
+
+  let limits = if setting.enabled {
+    WorkspacePlanLimits {
+      member_count: Some(
+        plan_subscription
+          .as_ref()
+          .map(|subscription| subscription.quantity.max(1) as i64)
+          .unwrap_or(setting.free_member_limit),
+      ),
+      storage_bytes: match plan_subscription {
+        Some(_) => None,
+        None => Some(setting.free_storage_limit),
+      },
+      ai_responses_per_month: if add_ons.is_empty() {
+        Some(setting.ai_response_limit)
+      } else {
+        None
+      },
+    }
+  } else {
+    WorkspacePlanLimits::default()
+  };
+  PlanState {
+    plan,
+    add_ons,
+    subscription: plan_subscription,
+    limits,
+  }
+}
+
+async fn workspace_limits(
+  pg_pool: &PgPool,
+  setting: &BillingSetting,
+  workspace_id: &Uuid,
+) -> Result<WorkspacePlanLimits, AppError> {
+  if !setting.enabled {
+    return Ok(WorkspacePlanLimits::default());
+  }
+  let subscriptions = select_workspace_subscriptions(pg_pool, workspace_id).await?;
+  Ok(plan_state(setting, subscriptions).limits)
+}
+
+async fn ai_responses_this_month(pg_pool: &PgPool, workspace_id: &Uuid) -> Result<i64, AppError> {
+  let today = Utc::now().date_naive();
+  let month_start = today.with_day(1).unwrap_or(today);
+  select_workspace_ai_responses_since(pg_pool, workspace_id, month_start).await
+}
+
+fn check_limit(resource: &str, limit: Option<i64>, usage: i64) -> Result<(), AppError> {
+  match limit {
+    Some(limit) if usage > limit => Err(AppError::QuotaExceeded {
+      resource: resource.to_string(),
+      limit,
+      usage,
+    }),
+    _ => Ok(()),
+  }
+}
+
+pub async fn get_workspace_billing(
+  pg_pool: &PgPool,
+  setting: &BillingSetting,
+  read_only_cache: &WorkspaceReadOnlyCache,
+  workspace_id: &Uuid,
+) -> Result<WorkspaceBilling, AppError> {
+  let subscriptions = select_workspace_subscriptions(pg_pool, workspace_id).await?;
+  let state = plan_state(setting, subscriptions);
+  let usage = WorkspacePlanUsage {
+    member_count: select_workspace_member_count_from_workspace_id(pg_pool, workspace_id)
+      .await?
+      .unwrap_or_default(),
+    storage_bytes: get_workspace_usage_size(pg_pool, workspace_id).await? as i64,
+    ai_responses_this_month: ai_responses_this_month(pg_pool, workspace_id).await?,
+  };
+  let subscription = state.subscription.as_ref();
+  Ok(WorkspaceBilling {
+    workspace_id: *workspace_id,
+    plan: state.plan,
+    add_ons: state.add_ons,
+    status: subscription.and_then(|s| SubscriptionStatus::try_from(s.status.as_str()).ok()),
+    recurring_interval: subscription
+      .and_then(|s| RecurringInterval::try_from(s.recurring_interval).ok()),
+    org_id: subscription.and_then(|s| s.org_id),
+    renewal_date: subscription.map(|s| s.current_period_end),
+    cancel_at: subscription.and_then(|s| s.cancel_at),
+    usage,
+    limits: state.limits,
+    read_only_reason: read_only_cache
+      .get(workspace_id)
+      .await?
+      .map(|read_only| read_only.reason),
+  })
+}
+
+/// Checks that the workspace can have `new_members` more members.
+pub async fn check_member_limit(
+  pg_pool: &PgPool,
+  setting: &BillingSetting,
+  workspace_id: &Uuid,
+  new_members: i64,
+) -> Result<(), AppError> {
+  let limits = workspace_limits(pg_pool, setting, workspace_id).await?;
+  if limits.member_count.is_none() {
+    return Ok(());
+  }
+  let member_count = select_workspace_member_count_from_workspace_id(pg_pool, workspace_id)
+    .await?
+    .unwrap_or_default();
+  check_limit(
+    "workspace_members",
+    limits.member_count,
+    member_count + new_members,
+  )
+}
+
+/// Checks that `new_bytes` more bytes of files can be stored in the workspace.
+pub async fn check_storage_limit(
+  pg_pool: &PgPool,
+  setting: &BillingSetting,
+  workspace_id: &Uuid,
+  new_bytes: u64,
+) -> Result<(), AppError> {
+  let limits = workspace_limits(pg_pool, setting, workspace_id).await?;
+  if limits.storage_bytes.is_none() {
+    return Ok(());
+  }
+  let storage_bytes = get_workspace_usage_size(pg_pool, workspace_id).await? + new_bytes;
+  check_limit("storage_bytes", limits.storage_bytes, storage_bytes as i64)
+}
+
+/// Counts an AI response of the workspace, once checked that the monthly limit of the plan is not
+/// reached. The responses are counted even when billing is not enforced.
+pub async fn consume_ai_response(
+  pg_pool: &PgPool,
+  setting: &BillingSetting,
+  workspace_id: &Uuid,
+) -> Result<(), AppError> {
+  let limits = workspace_limits(pg_pool, setting, workspace_id).await?;
+  if limits.ai_responses_per_month.is_some() {
+    let count = ai_responses_this_month(pg_pool, workspace_id).await?;
+    check_limit("ai_responses", limits.ai_responses_per_month, count + 1)?;
+  }
+  increment_workspace_ai_responses(pg_pool, workspace_id).await
+}
+
+/// Returns why the workspace has to be read-only, None if its usage is within the limits of its
+/// plan. The AI responses are not considered, they are limited per request.
+fn read_only_reason(limits: &WorkspacePlanLimits, usage: &WorkspacePlanUsage) -> Option<String> {
+  if let Some(limit) = limits.member_count {
+    if usage.member_count > limit {
+      return Some(format!(
+        "the workspace has {} members, its plan allows {}",
+        usage.member_count, limit
+      ));
+    }
+  }
+  if let Some(limit) = limits.storage_bytes {
+    if usage.storage_bytes > limit {
+      return Some(format!(
+        "the workspace stores {} bytes, its plan allows {}",
+        usage.storage_bytes, limit
+      ));
+    }
+  }
+  None
+}
+
+/// Puts the workspace in read-only mode when its usage is over the limits of its plan, which
+/// happens when the subscription lapses or loses seats, and restores it once the usage is back
+/// within the limits. A past due subscription keeps its plan, so the workspace stays writable
+/// while the payment is retried.
+pub async fn refresh_workspace_read_only(
+  pg_pool: &PgPool,
+  setting: &BillingSetting,
+  read_only_cache: &WorkspaceReadOnlyCache,
+  workspace_id: &Uuid,
+) -> Result<(), AppError> {
+  let reason = if setting.enabled {
+    let limits = workspace_limits(pg_pool, setting, workspace_id).await?;
+    let usage = WorkspacePlanUsage {
+      member_count: select_workspace_member_count_from_workspace_id(pg_pool, workspace_id)
+        .await?
+        .unwrap_or_default(),
+      storage_bytes: get_workspace_usage_size(pg_pool, workspace_id).await? as i64,
+      ai_responses_this_month: 0,
+    };
+    read_only_reason(&limits, &usage)
+  } else {
+    None
+  };
+  if reason.is_none() && !read_only_cache.is_read_only(workspace_id).await? {
+    return Ok(());
+  }
+  read_only_cache.set(workspace_id, reason.as_deref()).await
+}
+
+/// Same as [refresh_workspace_read_only], for the usage changes that can lift the read-only mode,
+/// like deleting files or removing members. A failure is only logged, the workspace is refreshed
+/// again by [run_read_only_refresher].
+pub async fn refresh_workspace_read_only_after_change(
+  pg_pool: &PgPool,
+  setting: &BillingSetting,
+  read_only_cache: &WorkspaceReadOnlyCache,
+  workspace_id: &Uuid,
+) {
+  if let Err(err) =
+    refresh_workspace_read_only(pg_pool, setting, read_only_cache, workspace_id).await
+  {
+    warn!(
+      "failed to refresh the read-only state of workspace {}: {}",
+      workspace_id, err
+    );
+  }
+}
+
+/// Refreshes the workspaces a subscription applies to: its workspace, or all the workspaces of its
+/// organization.
+pub async fn refresh_subscription_read_only(
+  pg_pool: &PgPool,
+  setting: &BillingSetting,
+  read_only_cache: &WorkspaceReadOnlyCache,
+  subscription: &AFSubscriptionRow,
+) -> Result<(), AppError> {
+  let workspace_ids = match (subscription.workspace_id, subscription.org_id) {
+    (Some(workspace_id), _) => vec![workspace_id],
+    (None, Some(org_id)) => select_organization_workspace_ids(pg_pool, &org_id).await?,
+    (None, None) => vec![],
+  };
+  for workspace_id in workspace_ids {
+    refresh_workspace_read_only(pg_pool, setting, read_only_cache, &workspace_id).await?;
+  }
+  Ok(())
+}
+
+/// Periodically refreshes the workspaces whose state can change without any request: a
+/// subscription can lapse or renew on its own, and a read-only workspace is restored once billing
+/// is disabled.
+pub async fn run_read_only_refresher(
+  pg_pool: PgPool,
+  setting: BillingSetting,
+  read_only_cache: WorkspaceReadOnlyCache,
+) {
+  let mut ticker = interval(Duration::from_secs(
+    setting.read_only_refresh_interval_secs.max(1),
+  ));
+  ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+  loop {
+    ticker.tick().await;
+    let workspace_ids = match select_workspace_ids_with_plan_state(&pg_pool).await {
+      Ok(workspace_ids) => workspace_ids,
+      Err(err) => {
+        warn!("failed to select the workspaces to refresh: {}", err);
+        continue;
+      },
+    };
+    trace!(
+      "refreshing the read-only state of {} workspaces",
+      workspace_ids.len()
+    );
+    for workspace_id in workspace_ids {
+      refresh_workspace_read_only_after_change(&pg_pool, &setting, &read_only_cache, &workspace_id)
+        .await;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::Duration;
+
+  fn subscription(plan: SubscriptionPlan, status: &str, quantity: i32) -> AFSubscriptionRow {
+    AFSubscriptionRow {
+      subscription_id: format!("sub_{}", plan.as_ref()),
+      customer_id: "cus_1".to_string(),
+      workspace_id: Some(Uuid::new_v4()),
+      org_id: None,
+      plan: plan as i16,
+      status: status.to_string(),
+      recurring_interval: 0,
+      quantity,
+      current_period_end: Utc::now() + Duration::days(30),
+      cancel_at: None,
+      event_created_at: Utc::now(),
+    }
+  }
+
+  fn setting(enabled: bool) -> BillingSetting {
+    BillingSetting {
+      enabled,
+      webhook_secret: String::new().into(),
+      free_member_limit: 2,
+      free_storage_limit: 1024,
+      ai_response_limit: 100,
+      read_only_refresh_interval_secs: 300,
+    }
+  }
+
+  #[test]
+  fn plan_limits_from_subscriptions() {
+    let state = plan_state(&setting(true), vec![]);
+    assert_eq!(state.plan, SubscriptionPlan::Free);
+    assert_eq!(
+      state.limits,
+      WorkspacePlanLimits {
+        member_count: Some(2),
+        storage_bytes: Some(1024),
+        ai_responses_per_month: Some(100),
+      }
+    );
+
+    let state = plan_state(
+      &setting(true),
+      vec![
+        subscription(SubscriptionPlan::Pro, "active", 3),
+        subscription(SubscriptionPlan::Team, "past_due", 8),
+        subscription(SubscriptionPlan::AiMax, "trialing", 1),
+      ],
+    );
+    assert_eq!(state.plan, SubscriptionPlan::Team);
+    assert_eq!(state.add_ons, vec![SubscriptionPlan::AiMax]);
+    assert_eq!(
+      state.limits,
+      WorkspacePlanLimits {
+        member_count: Some(8),
+        storage_bytes: None,
+        ai_responses_per_month: None,
+      }
+    );
+
+    let state = plan_state(
+      &setting(true),
+      vec![subscription(SubscriptionPlan::Pro, "canceled", 3)],
+    );
+    assert_eq!(state.plan, SubscriptionPlan::Free);
+    assert!(state.subscription.is_none());
+
+    let state = plan_state(&setting(false), vec![]);
+    assert_eq!(state.limits, WorkspacePlanLimits::default());
+  }
+
+  #[test]
+  fn read_only_when_over_plan_limits() {
+    let limits = plan_state(&setting(true), vec![]).limits;
+    let usage = |member_count, storage_bytes| WorkspacePlanUsage {
+      member_count,
+      storage_bytes,
+      ai_responses_this_month: 1000,
+    };
+    assert!(read_only_reason(&limits, &usage(2, 1024)).is_none());
+    assert!(read_only_reason(&limits, &usage(3, 0)).is_some());
+    assert!(read_only_reason(&limits, &usage(1, 1025)).is_some());
+
+    // Once the subscription lapses, the workspace falls back to the limits of the free plan.
+    let limits = plan_state(
+      &setting(true),
+      vec![subscription(SubscriptionPlan::Pro, "active", 5)],
+    )
+    .limits;
+    assert!(read_only_reason(&limits, &usage(5, 1 << 40)).is_none());
+    let limits = plan_state(
+      &setting(true),
+      vec![subscription(SubscriptionPlan::Pro, "unpaid", 5)],
+    )
+    .limits;
+    assert!(read_only_reason(&limits, &usage(5, 0)).is_some());
+
+    let limits = plan_state(&setting(false), vec![]).limits;
+    assert!(read_only_reason(&limits, &usage(100, 1 << 40)).is_none());
+  }
+}
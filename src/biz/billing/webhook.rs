@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use std::ops::DerefMut;
+
+use access_control::workspace_read_only::WorkspaceReadOnlyCache;
+use anyhow::Context;
+use app_error::AppError;
+use chrono::{DateTime, Utc};
+use database::billing::{insert_billing_event, upsert_subscription};
+use database::pg_row::AFSubscriptionRow;
+use hmac::{Hmac, Mac};
+use secrecy::ExposeSecret;
+use serde::Deserialize;
+use sha2::Sha256;
+use shared_entity::dto::billing_dto::{RecurringInterval, SubscriptionPlan, SubscriptionStatus};
+use sqlx::PgPool;
+use tracing::{info, trace, warn};
+use uuid::Uuid;
+
+use super::ops::refresh_subscription_read_only;
+use crate::config::config::BillingSetting;
+
+/// Header carrying the signature of the payload, `t=<timestamp>,v1=<hex hmac sha256>`.
+pub const SIGNATURE_HEADER: &str = "Stripe-Signature";
+/// Signed payloads older than that are rejected, so a captured request can't be replayed later.
+const SIGNATURE_TOLERANCE_SECS: i64 = 300;
+const SUBSCRIPTION_EVENT_PREFIX: &str = "customer.subscription.";
+const SUBSCRIPTION_DELETED_EVENT: &str = "customer.subscription.deleted";
+
+#[derive(Debug, Deserialize)]
+struct WebhookEvent {
+  id: String,
+  #[serde(rename = "type")]
+  event_type: String,
+  created: i64,
+  data: WebhookEventData,
+}
+
+#[derive(Debug, Deserialize)]
+struct WebhookEventData {
+  object: serde_json::Value,
+}
+
+/// The fields of the subscription object of the payment provider the server relies on. The
+/// workspace or the organization, and the plan, are set in the metadata of the subscription.
+#[derive(Debug, Deserialize)]
+struct SubscriptionObject {
+  id: String,
+  customer: String,
+  status: String,
+  current_period_end: i64,
+  cancel_at: Option<i64>,
+  #[serde(default)]
+  metadata: HashMap<String, String>,
+  items: SubscriptionItems,
+}
+
+#[derive(Debug, Deserialize)]
+struct SubscriptionItems {
+  data: Vec<SubscriptionItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SubscriptionItem {
+  quantity: Option<i32>,
+  price: SubscriptionPrice,
+}
+
+#[derive(Debug, Deserialize)]
+struct SubscriptionPrice {
+  recurring: Option<SubscriptionRecurring>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SubscriptionRecurring {
+  interval: String,
+}
+
+/// Checks the signature of the payload. One of the `v1` signatures of the header must be the
+/// HMAC-SHA256 of `<timestamp>.<payload>` with the webhook secret.
+fn verify_signature(secret: &str, header: &str, payload: &[u8], now: i64) -> Result<(), AppError> {
+  let invalid = |reason: &str| AppError::UserUnAuthorized(format!("invalid signature: {}", reason));
+  let mut timestamp = None;
+  let mut signatures = vec![];
+  for part in header.split(',') {
+    match part.trim().split_once('=') {
+      Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+      Some(("v1", value)) => signatures.extend(hex::decode(value).ok()),
+      _ => {},
+    }
+  }
+  let timestamp = timestamp.ok_or_else(|| invalid("missing timestamp"))?;
+  if (now - timestamp).abs() > SIGNATURE_TOLERANCE_SECS {
+    return Err(invalid("timestamp out of tolerance"));
+  }
+
+  let mut mac =
+    Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take a key of any size");
+  mac.update(timestamp.to_string().as_bytes());
+  mac.update(b".");
+  mac.update(payload);
+  let matched = signatures
+    .iter()
+    .any(|signature| mac.clone().verify_slice(signature).is_ok());
+  if matched {
+    Ok(())
+  } else {
+    Err(invalid("no matching signature"))
+  }
+}
+
+fn timestamp_to_datetime(secs: i64) -> Result<DateTime<Utc>, AppError> {
+  DateTime::from_timestamp(secs, 0)
+    .ok_or_else(|| AppError::InvalidRequest(format!("invalid timestamp {}", secs)))
+}
+
+fn subscription_row(
+  event: &WebhookEvent,
+  subscription: SubscriptionObject,
+) -> Result<AFSubscriptionRow, AppError> {
+  let parse_id = |key: &str| {
+    subscription
+      .metadata
+      .get(key)
+      .map(|value| Uuid::parse_str(value))
+      .transpose()
+  };
+  let workspace_id = parse_id("workspace_id")?;
+  let org_id = parse_id("org_id")?;
+  if workspace_id.is_some() == org_id.is_some() {
+    return Err(AppError::InvalidRequest(format!(
+      "subscription {} must have either a workspace_id or an org_id in its metadata",
+      subscription.id
+    )));
+  }
+  let plan = subscription
+    .metadata
+    .get("plan")
+    .map(|plan| SubscriptionPlan::try_from(plan.as_str()))
+    .unwrap_or_else(|| Err(format!("subscription {} has no plan", subscription.id)))
+    .map_err(AppError::InvalidRequest)?;
+  let status = if event.event_type == SUBSCRIPTION_DELETED_EVENT {
+    SubscriptionStatus::Canceled
+  } else {
+    SubscriptionStatus::try_from(subscription.status.as_str()).map_err(AppError::InvalidRequest)?
+  };
+  let item = subscription.items.data.first();
+  let recurring_interval = match item
+    .and_then(|item| item.price.recurring.as_ref())
+    .map(|recurring| recurring.interval.as_str())
+  {
+    Some("year") => RecurringInterval::Year,
+    _ => RecurringInterval::Month,
+  };
+
+  Ok(AFSubscriptionRow {
+    subscription_id: subscription.id,
+    customer_id: subscription.customer,
+    workspace_id,
+    org_id,
+    plan: plan as i16,
+    status: status.as_str().to_string(),
+    recurring_interval: recurring_interval as i16,
+    quantity: item.and_then(|item| item.quantity).unwrap_or(1),
+    current_period_end: timestamp_to_datetime(subscription.current_period_end)?,
+    cancel_at: subscription
+      .cancel_at
+      .map(timestamp_to_datetime)
+      .transpose()?,
+    event_created_at: timestamp_to_datetime(event.created)?,
+  })
+}
+
+/// Applies a webhook event of the payment provider. The events other than the subscription ones
+/// are ignored, and each event is applied once even when it is delivered several times.
+pub async fn handle_billing_webhook(
+  pg_pool: &PgPool,
+  setting: &BillingSetting,
+  read_only_cache: &WorkspaceReadOnlyCache,
+  signature: Option<&str>,
+  payload: &[u8],
+) -> Result<(), AppError> {
+  let secret = setting.webhook_secret.expose_secret();
+  if secret.is_empty() {
+    return Err(AppError::FeatureDisabled("billing webhook".to_string()));
+  }
+  let signature =
+    signature.ok_or_else(|| AppError::UserUnAuthorized("missing signature".to_string()))?;
+  verify_signature(secret, signature, payload, Utc::now().timestamp())?;
+
+  let event: WebhookEvent = serde_json::from_slice(payload)?;
+  if !event.event_type.starts_with(SUBSCRIPTION_EVENT_PREFIX) {
+    trace!(
+      "ignore billing event {} of type {}",
+      event.id,
+      event.event_type
+    );
+    return Ok(());
+  }
+  let subscription: SubscriptionObject = serde_json::from_value(event.data.object.clone())?;
+  let row = subscription_row(&event, subscription)?;
+
+  let mut txn = pg_pool
+    .begin()
+    .await
+    .context("Begin transaction to apply billing event")?;
+  if !insert_billing_event(txn.deref_mut(), &event.id, &event.event_type).await? {
+    trace!("billing event {} was already applied", event.id);
+    return Ok(());
+  }
+  let applied = upsert_subscription(txn.deref_mut(), &row).await?;
+  txn
+    .commit()
+    .await
+    .context("Commit transaction to apply billing event")?;
+  info!(
+    "billing event {}: subscription {} is {} on plan {} ({})",
+    event.id,
+    row.subscription_id,
+    row.status,
+    row.plan,
+    if applied { "applied" } else { "outdated" }
+  );
+  // The event is recorded, a failure is fixed by the periodic refresh of the read-only state.
+  if applied {
+    if let Err(err) = refresh_subscription_read_only(pg_pool, setting, read_only_cache, &row).await
+    {
+      warn!(
+        "failed to refresh the workspaces of subscription {}: {}",
+        row.subscription_id, err
+      );
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn sign(secret: &str, timestamp: i64, payload: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(format!("{}.", timestamp).as_bytes());
+    mac.update(payload);
+    format!(
+      "t={},v1={}",
+      timestamp,
+      hex::encode(mac.finalize().into_bytes())
+    )
+  }
+
+  #[test]
+  fn webhook_signature() {
+    let payload = br#"{"id":"evt_1"}"#;
+    let now = 1_700_000_000;
+    let header = sign("whsec", now, payload);
+    assert!(verify_signature("whsec", &header, payload, now + 10).is_ok());
+    assert!(verify_signature("other", &header, payload, now).is_err());
+    assert!(verify_signature("whsec", &header, b"{}", now).is_err());
+    assert!(verify_signature("whsec", &header, payload, now + 301).is_err());
+    assert!(verify_signature("whsec", "v1=00", payload, now).is_err());
+  }
+
+  #[test]
+  fn subscription_from_event() {
+    let workspace_id = Uuid::new_v4();
+    let event: WebhookEvent = serde_json::from_value(serde_json::json!({
+      "id": "evt_1",
+      "type": "customer.subscription.deleted",
+      "created": 1_700_000_000,
+      "data": { "object": {
+        "id": "sub_1",
+        "customer": "cus_1",
+        "status": "active",
+        "current_period_end": 1_702_592_000,
+        "cancel_at": null,
+        "metadata": { "workspace_id": workspace_id.to_string(), "plan": "team" },
+        "items": { "data": [{ "quantity": 5, "price": { "recurring": { "interval": "year" } } }] }
+      }}
+    }))
+    .unwrap();
+    let subscription = serde_json::from_value(event.data.object.clone()).unwrap();
+    let row = subscription_row(&event, subscription).unwrap();
+    assert_eq!(row.workspace_id, Some(workspace_id));
+    assert_eq!(row.plan, SubscriptionPlan::Team as i16);
+    assert_eq!(row.status, "canceled");
+    assert_eq!(row.recurring_interval, RecurringInterval::Year as i16);
+    assert_eq!(row.quantity, 5);
+  }
+}
@@ -0,0 +1,273 @@
+use std::path::Path;
+
+use app_error::AppError;
+use appflowy_ai_client::client::AppFlowyAIClient;
+use appflowy_ai_client::dto::CreateChatContext;
+use aws_sdk_s3::primitives::ByteStream;
+use database::chat::chat_attachment::{
+  chat_attachment_object_key, insert_chat_attachment, select_chat_attachments,
+  update_chat_attachment_status, ChatAttachmentState,
+};
+use database::chat::chat_ops::select_chat;
+use database::file::{BlobStorageClient, BucketClient};
+use database::pg_row::AFChatAttachmentRow;
+use redis::AsyncCommands;
+use serde_json::json;
+use shared_entity::dto::chat_dto::{
+  ChatAttachment, ChatAttachmentStatus, ChatMetadataDescription, ContextLoader,
+};
+use sqlx::PgPool;
+use tracing::{error, trace};
+use uuid::Uuid;
+
+use crate::state::RedisConnectionManager;
+
+const CHAT_ATTACHMENT_STREAM: &str = "chat_attachment_task_stream";
+const ATTACHMENT_SOURCE: &str = "appflowy_chat_attachment";
+
+/// How the text of an attachment is extracted. Plain text and markdown are read by the server,
+/// PDF and images are handed to the worker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AttachmentKind {
+  Text,
+  Markdown,
+  Pdf,
+  Image,
+}
+
+impl AttachmentKind {
+  fn from_file(file_name: &str, content_type: Option<&str>) -> Option<Self> {
+    let extension = Path::new(file_name)
+      .extension()
+      .and_then(|ext| ext.to_str())
+      .map(|ext| ext.to_ascii_lowercase());
+    match extension.as_deref() {
+      Some("txt") => return Some(AttachmentKind::Text),
+      Some("md") | Some("markdown") => return Some(AttachmentKind::Markdown),
+      Some("pdf") => return Some(AttachmentKind::Pdf),
+      Some("png") | Some("jpg") | Some("jpeg") | Some("webp") | Some("gif") | Some("bmp")
+      | Some("tiff") => return Some(AttachmentKind::Image),
+      _ => {},
+    }
+
+    let content_type = content_type?.to_ascii_lowercase();
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    match mime {
+      "text/markdown" => Some(AttachmentKind::Markdown),
+      "application/pdf" => Some(AttachmentKind::Pdf),
+      _ if mime.starts_with("text/") => Some(AttachmentKind::Text),
+      _ if mime.starts_with("image/") => Some(AttachmentKind::Image),
+      _ => None,
+    }
+  }
+
+  fn default_content_type(&self) -> &'static str {
+    match self {
+      AttachmentKind::Text => "text/plain",
+      AttachmentKind::Markdown => "text/markdown",
+      AttachmentKind::Pdf => "application/pdf",
+      AttachmentKind::Image => "application/octet-stream",
+    }
+  }
+
+  fn task_kind(&self) -> &'static str {
+    match self {
+      AttachmentKind::Text => "text",
+      AttachmentKind::Markdown => "markdown",
+      AttachmentKind::Pdf => "pdf",
+      AttachmentKind::Image => "image",
+    }
+  }
+}
+
+/// Stores the file uploaded as the context of the chat and embeds its text. Text and markdown
+/// files are embedded before returning, the other formats are queued for the worker and the
+/// returned attachment stays in [ChatAttachmentStatus::Processing] until the worker is done.
+#[allow(clippy::too_many_arguments)]
+pub async fn upload_chat_attachment(
+  pg_pool: &PgPool,
+  bucket_client: &BlobStorageClient,
+  redis_client: &RedisConnectionManager,
+  ai_client: &AppFlowyAIClient,
+  uid: i64,
+  workspace_id: &Uuid,
+  chat_id: &Uuid,
+  file_name: &str,
+  content_type: Option<&str>,
+  data: Vec<u8>,
+) -> Result<ChatAttachment, AppError> {
+  let file_name = file_name.trim();
+  if file_name.is_empty() {
+    return Err(AppError::InvalidRequest(
+      "file name of the attachment is missing".to_string(),
+    ));
+  }
+  if data.is_empty() {
+    return Err(AppError::InvalidRequest("attachment is empty".to_string()));
+  }
+  let kind = AttachmentKind::from_file(file_name, content_type).ok_or_else(|| {
+    AppError::InvalidRequest(format!(
+      "unsupported attachment: {}, only text, markdown, PDF and image files are supported",
+      file_name
+    ))
+  })?;
+  let chat = select_chat(pg_pool, &chat_id.to_string()).await?;
+  if &chat.workspace_id != workspace_id {
+    return Err(AppError::RecordNotFound(format!(
+      "chat {} is not found in workspace {}",
+      chat_id, workspace_id
+    )));
+  }
+
+  let attachment_id = Uuid::new_v4();
+  let file_key = chat_attachment_object_key(workspace_id, chat_id, &attachment_id);
+  let content_type = content_type
+    .map(|content_type| content_type.to_string())
+    .unwrap_or_else(|| kind.default_content_type().to_string());
+  let text = match kind {
+    AttachmentKind::Text | AttachmentKind::Markdown => {
+      Some(String::from_utf8(data.clone()).map_err(|_| {
+        AppError::InvalidRequest(format!("{} is not a valid UTF-8 text file", file_name))
+      })?)
+    },
+    AttachmentKind::Pdf | AttachmentKind::Image => None,
+  };
+
+  bucket_client
+    .put_blob_with_content_type(&file_key, ByteStream::from(data.clone()), &content_type)
+    .await?;
+  let mut row = insert_chat_attachment(
+    pg_pool,
+    &attachment_id,
+    chat_id,
+    workspace_id,
+    uid,
+    file_name,
+    &content_type,
+    data.len() as i64,
+    &file_key,
+  )
+  .await?;
+
+  match text {
+    Some(text) => {
+      let loader = if kind == AttachmentKind::Markdown {
+        ContextLoader::Markdown
+      } else {
+        ContextLoader::Text
+      };
+      let context = CreateChatContext::new(chat_id.to_string(), loader.to_string(), text)
+        .with_metadata(attachment_metadata(&attachment_id, file_name));
+      trace!("create context for chat attachment: {}", context);
+      let (state, error) = match ai_client.create_chat_text_context(context).await {
+        Ok(_) => (ChatAttachmentState::Ready, None),
+        Err(err) => {
+          error!("Failed to embed chat attachment {}: {}", attachment_id, err);
+          (ChatAttachmentState::Failed, Some(err.to_string()))
+        },
+      };
+      update_chat_attachment_status(pg_pool, &attachment_id, state, error.as_deref()).await?;
+      row.status = state as i16;
+      row.error = error;
+    },
+    None => {
+      let task = json!({
+        "attachment_id": attachment_id,
+        "workspace_id": workspace_id,
+        "chat_id": chat_id,
+        "file_name": file_name,
+        "file_key": file_key,
+        "kind": kind.task_kind(),
+      });
+      let result: Result<(), _> = redis_client
+        .clone()
+        .xadd(CHAT_ATTACHMENT_STREAM, "*", &[("task", task.to_string())])
+        .await;
+      if let Err(err) = result {
+        let error = format!("failed to queue the text extraction: {}", err);
+        update_chat_attachment_status(
+          pg_pool,
+          &attachment_id,
+          ChatAttachmentState::Failed,
+          Some(&error),
+        )
+        .await?;
+        return Err(AppError::Internal(anyhow::anyhow!(error)));
+      }
+    },
+  }
+
+  Ok(chat_attachment_from_row(row))
+}
+
+pub async fn list_chat_attachments(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  chat_id: &Uuid,
+) -> Result<Vec<ChatAttachment>, AppError> {
+  let rows = select_chat_attachments(pg_pool, chat_id).await?;
+  Ok(
+    rows
+      .into_iter()
+      .filter(|row| &row.workspace_id == workspace_id)
+      .map(chat_attachment_from_row)
+      .collect(),
+  )
+}
+
+/// Metadata of the context, returned by the AI service along with the answers that use it.
+fn attachment_metadata(attachment_id: &Uuid, file_name: &str) -> ChatMetadataDescription {
+  ChatMetadataDescription {
+    id: attachment_id.to_string(),
+    name: file_name.to_string(),
+    source: ATTACHMENT_SOURCE.to_string(),
+    extra: None,
+  }
+}
+
+fn chat_attachment_from_row(row: AFChatAttachmentRow) -> ChatAttachment {
+  let status = match ChatAttachmentState::from(row.status) {
+    ChatAttachmentState::Processing => ChatAttachmentStatus::Processing,
+    ChatAttachmentState::Ready => ChatAttachmentStatus::Ready,
+    ChatAttachmentState::Failed => ChatAttachmentStatus::Failed,
+  };
+  ChatAttachment {
+    attachment_id: row.attachment_id,
+    chat_id: row.chat_id,
+    file_name: row.file_name,
+    content_type: row.content_type,
+    file_size: row.file_size,
+    status,
+    error: row.error,
+    created_at: row.created_at,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn attachment_kind_from_file() {
+    assert_eq!(
+      AttachmentKind::from_file("notes.MD", None),
+      Some(AttachmentKind::Markdown)
+    );
+    assert_eq!(
+      AttachmentKind::from_file("report.pdf", Some("application/octet-stream")),
+      Some(AttachmentKind::Pdf)
+    );
+    assert_eq!(
+      AttachmentKind::from_file("scan", Some("image/png")),
+      Some(AttachmentKind::Image)
+    );
+    assert_eq!(
+      AttachmentKind::from_file("log", Some("text/plain; charset=utf-8")),
+      Some(AttachmentKind::Text)
+    );
+    assert_eq!(
+      AttachmentKind::from_file("archive.zip", Some("application/zip")),
+      None
+    );
+  }
+}
@@ -0,0 +1,129 @@
+use app_error::AppError;
+use database::chat::chat_feedback::{
+  select_chat_message_author, select_chat_message_feedback, upsert_chat_message_feedback,
+};
+use database::chat::chat_ops::select_chat;
+use database::pg_row::AFChatMessageFeedbackRow;
+use shared_entity::dto::chat_dto::{
+  ChatAuthor, ChatAuthorType, ChatMessageFeedback, ChatMessageFeedbackParams,
+  ChatMessageFeedbackQuery, ChatMessageFeedbackRating,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::Validate;
+
+use super::metrics::AIMetrics;
+
+const DEFAULT_EXPORT_LIMIT: i64 = 1000;
+const MAX_EXPORT_LIMIT: i64 = 10_000;
+
+/// Stores the rating of the user for an answer of the AI. Rating the same answer again replaces
+/// the previous feedback.
+pub async fn create_chat_message_feedback(
+  pg_pool: &PgPool,
+  metrics: &AIMetrics,
+  uid: i64,
+  workspace_id: &Uuid,
+  chat_id: &Uuid,
+  message_id: i64,
+  params: ChatMessageFeedbackParams,
+) -> Result<(), AppError> {
+  params.validate()?;
+  let chat = select_chat(pg_pool, &chat_id.to_string()).await?;
+  if &chat.workspace_id != workspace_id {
+    return Err(AppError::RecordNotFound(format!(
+      "chat {} is not found in workspace {}",
+      chat_id, workspace_id
+    )));
+  }
+  let author = select_chat_message_author(pg_pool, chat_id, message_id)
+    .await?
+    .ok_or_else(|| {
+      AppError::RecordNotFound(format!(
+        "message {} is not found in chat {}",
+        message_id, chat_id
+      ))
+    })?;
+  let is_answer = serde_json::from_value::<ChatAuthor>(author)
+    .map(|author| matches!(author.author_type, ChatAuthorType::AI))
+    .unwrap_or(false);
+  if !is_answer {
+    return Err(AppError::InvalidRequest(
+      "only the answers of the AI can be rated".to_string(),
+    ));
+  }
+
+  let comment = params
+    .comment
+    .as_deref()
+    .map(str::trim)
+    .filter(|comment| !comment.is_empty());
+  let rating = rating_to_i16(params.rating);
+  let previous = upsert_chat_message_feedback(
+    pg_pool,
+    message_id,
+    uid,
+    chat_id,
+    workspace_id,
+    rating,
+    comment,
+  )
+  .await?;
+  if previous != Some(rating) {
+    metrics.record_message_feedback(params.rating);
+  }
+  Ok(())
+}
+
+/// Exports the feedback of all the workspaces, for the administrators of the instance.
+pub async fn export_chat_message_feedback(
+  pg_pool: &PgPool,
+  query: ChatMessageFeedbackQuery,
+) -> Result<Vec<ChatMessageFeedback>, AppError> {
+  let limit = query
+    .limit
+    .unwrap_or(DEFAULT_EXPORT_LIMIT)
+    .clamp(1, MAX_EXPORT_LIMIT);
+  let offset = query.offset.unwrap_or(0).max(0);
+  let rows = select_chat_message_feedback(
+    pg_pool,
+    query.from,
+    query.to,
+    query.workspace_id.as_ref(),
+    offset,
+    limit,
+  )
+  .await?;
+  Ok(
+    rows
+      .into_iter()
+      .map(chat_message_feedback_from_row)
+      .collect(),
+  )
+}
+
+fn rating_to_i16(rating: ChatMessageFeedbackRating) -> i16 {
+  match rating {
+    ChatMessageFeedbackRating::ThumbsUp => 1,
+    ChatMessageFeedbackRating::ThumbsDown => -1,
+  }
+}
+
+fn chat_message_feedback_from_row(row: AFChatMessageFeedbackRow) -> ChatMessageFeedback {
+  let rating = if row.rating > 0 {
+    ChatMessageFeedbackRating::ThumbsUp
+  } else {
+    ChatMessageFeedbackRating::ThumbsDown
+  };
+  ChatMessageFeedback {
+    message_id: row.message_id,
+    uid: row.uid,
+    chat_id: row.chat_id,
+    workspace_id: row.workspace_id,
+    rating,
+    comment: row.comment,
+    answer: row.answer,
+    created_at: row.created_at,
+    updated_at: row.updated_at,
+  }
+}
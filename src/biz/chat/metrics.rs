@@ -1,4 +1,5 @@
 use prometheus_client::metrics::counter::Counter;
+use shared_entity::dto::chat_dto::ChatMessageFeedbackRating;
 
 #[derive(Default, Clone)]
 pub struct AIMetrics {
@@ -8,6 +9,10 @@ pub struct AIMetrics {
   total_completion_count: Counter,
   total_summary_row_count: Counter,
   total_translate_row_count: Counter,
+  total_language_check_count: Counter,
+  language_check_cache_hit_count: Counter,
+  thumbs_up_feedback_count: Counter,
+  thumbs_down_feedback_count: Counter,
 }
 
 impl AIMetrics {
@@ -46,6 +51,26 @@ impl AIMetrics {
       "Total count of translation rows processed",
       metrics.total_translate_row_count.clone(),
     );
+    realtime_registry.register(
+      "total_language_check_count",
+      "Total count of language checks processed",
+      metrics.total_language_check_count.clone(),
+    );
+    realtime_registry.register(
+      "language_check_cache_hit_count",
+      "Total count of checked chunks of text found in the cache",
+      metrics.language_check_cache_hit_count.clone(),
+    );
+    realtime_registry.register(
+      "thumbs_up_feedback_count",
+      "Total count of answers rated with a thumbs up",
+      metrics.thumbs_up_feedback_count.clone(),
+    );
+    realtime_registry.register(
+      "thumbs_down_feedback_count",
+      "Total count of answers rated with a thumbs down",
+      metrics.thumbs_down_feedback_count.clone(),
+    );
 
     metrics
   }
@@ -73,4 +98,19 @@ impl AIMetrics {
   pub fn record_total_translate_row_count(&self, count: u64) {
     self.total_translate_row_count.inc_by(count);
   }
+
+  pub fn record_total_language_check_count(&self, count: u64) {
+    self.total_language_check_count.inc_by(count);
+  }
+
+  pub fn record_language_check_cache_hit_count(&self, count: u64) {
+    self.language_check_cache_hit_count.inc_by(count);
+  }
+
+  pub fn record_message_feedback(&self, rating: ChatMessageFeedbackRating) {
+    match rating {
+      ChatMessageFeedbackRating::ThumbsUp => self.thumbs_up_feedback_count.inc(),
+      ChatMessageFeedbackRating::ThumbsDown => self.thumbs_down_feedback_count.inc(),
+    };
+  }
 }
@@ -1,2 +1,5 @@
+pub mod attachment;
+pub mod feedback;
 pub mod metrics;
 pub mod ops;
+pub mod thread;
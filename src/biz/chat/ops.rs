@@ -9,14 +9,15 @@ use database::chat::chat_ops::{
   delete_answer_message_by_question_message_id, insert_answer_message,
   insert_answer_message_with_transaction, insert_chat, insert_question_message,
   select_chat_message_matching_reply_message_id, select_chat_messages,
-  select_chat_messages_with_author_uuid,
+  select_chat_messages_matching_query, select_chat_messages_with_author_uuid,
 };
 use futures::stream::Stream;
 use serde_json::json;
 use shared_entity::dto::chat_dto::{
-  ChatAuthor, ChatAuthorType, ChatAuthorWithUuid, ChatMessage, ChatMessageType,
-  ChatMessageWithAuthorUuid, CreateChatMessageParams, CreateChatParams, GetChatMessageParams,
-  RepeatedChatMessage, RepeatedChatMessageWithAuthorUuid, UpdateChatMessageContentParams,
+  ChatAuthor, ChatAuthorType, ChatAuthorWithUuid, ChatMessage, ChatMessageSearchResult,
+  ChatMessageType, ChatMessageWithAuthorUuid, CreateChatMessageParams, CreateChatParams,
+  GetChatMessageParams, RepeatedChatMessage, RepeatedChatMessageWithAuthorUuid,
+  SearchChatMessageParams, UpdateChatMessageContentParams,
 };
 use sqlx::PgPool;
 use tracing::{error, info, trace};
@@ -259,6 +260,22 @@ pub async fn get_chat_messages_with_author_uuid(
   Ok(messages)
 }
 
+pub async fn search_chat_messages(
+  pg_pool: &PgPool,
+  chat_id: &str,
+  params: SearchChatMessageParams,
+) -> Result<Vec<ChatMessageSearchResult>, AppError> {
+  params.validate()?;
+  let results =
+    select_chat_messages_matching_query(pg_pool, chat_id, &params.q, params.limit as i64).await?;
+  Ok(
+    results
+      .into_iter()
+      .map(|(message, snippet)| ChatMessageSearchResult { message, snippet })
+      .collect(),
+  )
+}
+
 pub async fn get_question_message(
   pg_pool: &PgPool,
   chat_id: &str,
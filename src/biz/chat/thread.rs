@@ -0,0 +1,133 @@
+use app_error::AppError;
+use chrono::{DateTime, Duration, Utc};
+use database::chat::chat_ops::get_all_chat_messages;
+use shared_entity::dto::chat_dto::{
+  ChatMessage, ChatThread, GetChatThreadParams, RepeatedChatThread,
+};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use validator::Validate;
+
+/// A question asked within this delay after the previous message continues the same thread.
+const FOLLOW_UP_WINDOW_SECS: i64 = 10 * 60;
+
+pub async fn get_chat_threads(
+  pg_pool: &PgPool,
+  chat_id: &str,
+  params: GetChatThreadParams,
+) -> Result<RepeatedChatThread, AppError> {
+  params.validate()?;
+  let messages = get_all_chat_messages(pg_pool, chat_id).await?;
+  let threads = group_chat_threads(messages, Duration::seconds(FOLLOW_UP_WINDOW_SECS));
+  let total = threads.len() as i64;
+  let mut threads = threads
+    .into_iter()
+    .rev()
+    .filter(|thread| {
+      params
+        .before
+        .map_or(true, |before| thread.thread_id < before)
+    })
+    .take(params.limit as usize + 1)
+    .collect::<Vec<_>>();
+  let has_more = threads.len() > params.limit as usize;
+  threads.truncate(params.limit as usize);
+  Ok(RepeatedChatThread {
+    threads,
+    has_more,
+    total,
+  })
+}
+
+/// Groups the messages, sorted by creation, into threads. An answer joins the thread of its
+/// question, the question being the message whose `reply_message_id` is the answer. Any other
+/// message starts a new thread, unless it was created within `follow_up_window` of the last
+/// message of the current thread.
+fn group_chat_threads(messages: Vec<ChatMessage>, follow_up_window: Duration) -> Vec<ChatThread> {
+  let question_of_answer = messages
+    .iter()
+    .filter_map(|message| {
+      message
+        .reply_message_id
+        .map(|answer_id| (answer_id, message.message_id))
+    })
+    .collect::<HashMap<_, _>>();
+
+  let mut threads: Vec<ChatThread> = vec![];
+  let mut thread_of_message = HashMap::new();
+  let mut last_activity: Option<DateTime<Utc>> = None;
+  for message in messages {
+    let answered_thread = question_of_answer
+      .get(&message.message_id)
+      .and_then(|question_id| thread_of_message.get(question_id))
+      .copied();
+    let index = match answered_thread {
+      Some(index) => index,
+      None => {
+        let is_follow_up =
+          last_activity.is_some_and(|at| message.created_at - at <= follow_up_window);
+        if !is_follow_up {
+          threads.push(ChatThread {
+            thread_id: message.message_id,
+            messages: vec![],
+          });
+        }
+        threads.len() - 1
+      },
+    };
+    if index == threads.len() - 1 {
+      last_activity = last_activity.max(Some(message.created_at));
+    }
+    thread_of_message.insert(message.message_id, index);
+    threads[index].messages.push(message);
+  }
+  threads
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use shared_entity::dto::chat_dto::{ChatAuthor, ChatAuthorType};
+
+  fn message(message_id: i64, minute: i64, reply_message_id: Option<i64>) -> ChatMessage {
+    ChatMessage {
+      author: ChatAuthor::new(1, ChatAuthorType::Human),
+      message_id,
+      content: format!("message {}", message_id),
+      created_at: DateTime::from_timestamp(minute * 60, 0).unwrap(),
+      meta_data: serde_json::Value::Null,
+      reply_message_id,
+    }
+  }
+
+  #[test]
+  fn group_questions_answers_and_follow_ups() {
+    let messages = vec![
+      // question, answered by 2, and a follow-up asked right after
+      message(1, 0, Some(2)),
+      message(2, 1, None),
+      message(3, 5, Some(4)),
+      message(4, 6, None),
+      // a new question, a day later
+      message(5, 24 * 60, Some(7)),
+      message(6, 24 * 60 + 1, None),
+      // the answer to 5 was regenerated after 6 was written
+      message(7, 24 * 60 + 30, None),
+    ];
+    let threads = group_chat_threads(messages, Duration::minutes(10));
+    let ids = threads
+      .iter()
+      .map(|thread| {
+        (
+          thread.thread_id,
+          thread
+            .messages
+            .iter()
+            .map(|message| message.message_id)
+            .collect::<Vec<_>>(),
+        )
+      })
+      .collect::<Vec<_>>();
+    assert_eq!(ids, vec![(1, vec![1, 2, 3, 4]), (5, vec![5, 6, 7])]);
+  }
+}
@@ -0,0 +1,26 @@
+use app_error::AppError;
+use collab_entity::CollabType;
+use database::collab::select_collab_archive;
+use database_entity::dto::AFCollabArchiveStatus;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Only documents are archived, the other collabs are always reported as not archived.
+pub async fn get_collab_archive_status(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  object_id: &Uuid,
+) -> Result<AFCollabArchiveStatus, AppError> {
+  let object_id = object_id.to_string();
+  let archive = select_collab_archive(pg_pool, &object_id, &CollabType::Document)
+    .await?
+    .filter(|archive| &archive.workspace_id == workspace_id);
+  Ok(AFCollabArchiveStatus {
+    object_id,
+    archived: archive.is_some(),
+    storage_class: archive
+      .as_ref()
+      .map(|archive| archive.storage_class.clone()),
+    archived_at: archive.map(|archive| archive.archived_at),
+  })
+}
@@ -0,0 +1,46 @@
+use app_error::AppError;
+use database::collab::select_changed_collabs;
+use database_entity::dto::{AFChangedCollabs, ChangedCollabQuery};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const DEFAULT_CHANGED_COLLAB_LIMIT: i64 = 500;
+const MAX_CHANGED_COLLAB_LIMIT: i64 = 1000;
+/// The changes of the last seconds are not listed yet, see [select_changed_collabs].
+const CHANGE_SETTLE_SECS: f64 = 2.0;
+
+/// Lists the collabs of the workspace created, updated or deleted since the given time, so the
+/// backup clients can mirror a workspace incrementally. The collabs deleted with their workspace
+/// are not listed, the whole workspace is gone then.
+pub async fn get_changed_collabs(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  query: ChangedCollabQuery,
+) -> Result<AFChangedCollabs, AppError> {
+  let limit = query
+    .limit
+    .unwrap_or(DEFAULT_CHANGED_COLLAB_LIMIT)
+    .clamp(1, MAX_CHANGED_COLLAB_LIMIT);
+  let after = query.after.unwrap_or_default();
+  let changes = select_changed_collabs(
+    pg_pool,
+    workspace_id,
+    &query.since,
+    &after,
+    limit,
+    CHANGE_SETTLE_SECS,
+  )
+  .await?;
+
+  let has_more = changes.len() as i64 == limit;
+  let (next_since, next_after) = match changes.last() {
+    Some(last) => (last.updated_at, Some(last.object_id.clone())),
+    None => (query.since, (!after.is_empty()).then_some(after)),
+  };
+  Ok(AFChangedCollabs {
+    changes,
+    next_since,
+    next_after,
+    has_more,
+  })
+}
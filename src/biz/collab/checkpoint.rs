@@ -0,0 +1,313 @@
+use std::collections::HashSet;
+
+use actix_web::web::Data;
+use app_error::AppError;
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use collab::preclude::Collab;
+use collab_entity::CollabType;
+use collab_rt_entity::user::RealtimeUser;
+use database::collab::{CollabStorage, GetCollabOrigin};
+use database_entity::dto::{AFCollabCheckpoint, AFCollabHistory, InsertCheckpointParams};
+use tracing::instrument;
+use uuid::Uuid;
+use yrs::types::text::{Diff, YChange};
+use yrs::types::{Attrs, Delta};
+use yrs::{Any, Array, In, Map, MapRef, Out, ReadTxn, Text, TransactionMut};
+
+use super::utils::{collab_from_doc_state, get_latest_collab, get_latest_collab_encoded};
+use crate::api::metrics::AppFlowyWebMetrics;
+use crate::api::ws::RealtimeServerAddr;
+use crate::biz::workspace::page_view::update_page_collab_data;
+
+/// Stores the current state of the collab as a named checkpoint.
+pub async fn create_collab_checkpoint(
+  collab_storage: &CollabAccessControlStorage,
+  uid: i64,
+  workspace_id: &str,
+  object_id: &str,
+  label: String,
+  collab_type: CollabType,
+) -> Result<AFCollabCheckpoint, AppError> {
+  let encoded_collab = get_latest_collab_encoded(
+    collab_storage,
+    GetCollabOrigin::User { uid },
+    workspace_id,
+    object_id,
+    collab_type.clone(),
+  )
+  .await?;
+  collab_storage
+    .create_checkpoint(InsertCheckpointParams {
+      object_id: object_id.to_string(),
+      workspace_id: workspace_id.to_string(),
+      label,
+      created_by: Some(uid),
+      doc_state: encoded_collab.doc_state,
+      collab_type,
+    })
+    .await
+}
+
+pub async fn get_collab_history(
+  collab_storage: &CollabAccessControlStorage,
+  workspace_id: &str,
+  object_id: &str,
+) -> Result<AFCollabHistory, AppError> {
+  collab_storage
+    .get_collab_history(workspace_id, object_id)
+    .await
+}
+
+/// Reverts the collab to the content it had when the checkpoint was created.
+///
+/// Replacing the stored doc state is not an option: connected clients would merge their local
+/// state back into it. Instead, a regular update that turns the current content into the
+/// checkpoint content is computed and applied through the realtime group, so that it reaches
+/// every client like any other edit. The state before the revert is saved as a checkpoint too,
+/// which makes the revert itself undoable.
+///
+/// Returns the checkpoint that was created for the state before the revert.
+#[allow(clippy::too_many_arguments)]
+#[instrument(level = "debug", skip_all, err)]
+pub async fn revert_collab_to_checkpoint(
+  appflowy_web_metrics: &AppFlowyWebMetrics,
+  server: Data<RealtimeServerAddr>,
+  user: RealtimeUser,
+  collab_storage: &CollabAccessControlStorage,
+  workspace_id: Uuid,
+  object_id: Uuid,
+  checkpoint_id: i64,
+  collab_type: CollabType,
+) -> Result<AFCollabCheckpoint, AppError> {
+  let uid = user.uid;
+  let workspace_id_str = workspace_id.to_string();
+  let object_id_str = object_id.to_string();
+  let (checkpoint, checkpoint_doc_state) = collab_storage
+    .get_collab_checkpoint(&workspace_id_str, &object_id_str, checkpoint_id)
+    .await?;
+  let checkpoint_collab = collab_from_doc_state(checkpoint_doc_state, &object_id_str)?;
+
+  let mut current_collab = get_latest_collab(
+    collab_storage,
+    GetCollabOrigin::User { uid },
+    &workspace_id_str,
+    &object_id_str,
+    collab_type.clone(),
+  )
+  .await?;
+  let backup = create_collab_checkpoint(
+    collab_storage,
+    uid,
+    &workspace_id_str,
+    &object_id_str,
+    format!("Before reverting to {}", checkpoint.label),
+    collab_type.clone(),
+  )
+  .await?;
+
+  let update = revert_update(&mut current_collab, &checkpoint_collab);
+  update_page_collab_data(
+    appflowy_web_metrics,
+    server,
+    user,
+    workspace_id,
+    object_id,
+    collab_type,
+    update,
+  )
+  .await?;
+  Ok(backup)
+}
+
+/// Computes the update that makes the content of `current` equal to the content of `target`.
+/// Shared types that exist in both collabs are updated in place rather than replaced, so that
+/// observers on the client side keep working after the revert.
+fn revert_update(current: &mut Collab, target: &Collab) -> Vec<u8> {
+  let target_data = target.data.clone();
+  let current_data = current.data.clone();
+  let target_txn = target.transact();
+  let mut txn = current.transact_mut();
+  sync_map(&mut txn, &current_data, &target_txn, &target_data);
+  txn.encode_update_v1()
+}
+
+fn sync_map<T: ReadTxn>(
+  txn: &mut TransactionMut,
+  current: &MapRef,
+  target_txn: &T,
+  target: &MapRef,
+) {
+  let target_keys: HashSet<String> = target.keys(target_txn).map(|k| k.to_string()).collect();
+  let stale_keys: Vec<String> = current
+    .keys(txn)
+    .filter(|k| !target_keys.contains(*k))
+    .map(|k| k.to_string())
+    .collect();
+  for key in stale_keys {
+    current.remove(txn, &key);
+  }
+
+  for (key, target_value) in target.iter(target_txn) {
+    match (current.get(txn, key), target_value) {
+      (Some(Out::YMap(current_map)), Out::YMap(target_map)) => {
+        sync_map(txn, &current_map, target_txn, &target_map);
+      },
+      (Some(Out::YText(current_text)), Out::YText(target_text)) => {
+        let target_diff = target_text.diff(target_txn, YChange::identity);
+        let current_diff = current_text.diff(txn, YChange::identity);
+        if text_chunks(txn, current_diff) == text_chunks(target_txn, target_diff.clone()) {
+          continue;
+        }
+
+        let len = current_text.len(txn);
+        current_text.remove_range(txn, 0, len);
+        for diff in target_diff {
+          let index = current_text.len(txn);
+          let attrs = diff.attributes.map(|attrs| *attrs).unwrap_or_default();
+          match diff.insert {
+            Out::Any(Any::String(chunk)) => {
+              current_text.insert_with_attributes(txn, index, &chunk, attrs);
+            },
+            embed => {
+              let embed = deep_copy(target_txn, embed);
+              current_text.insert_embed_with_attributes(txn, index, embed, attrs);
+            },
+          }
+        }
+      },
+      (Some(Out::YArray(current_array)), Out::YArray(target_array)) => {
+        if current_array.to_json(txn) != target_array.to_json(target_txn) {
+          let len = current_array.len(txn);
+          current_array.remove_range(txn, 0, len);
+          for value in target_array.iter(target_txn) {
+            current_array.push_back(txn, deep_copy(target_txn, value));
+          }
+        }
+      },
+      (Some(Out::Any(current_any)), Out::Any(target_any)) if current_any == target_any => {},
+      (_, target_value) => {
+        current.insert(txn, key, deep_copy(target_txn, target_value));
+      },
+    }
+  }
+}
+
+/// Text content as comparable (content, formatting) pairs.
+fn text_chunks<T: ReadTxn>(txn: &T, diff: Vec<Diff<YChange>>) -> Vec<(Any, Option<Attrs>)> {
+  diff
+    .into_iter()
+    .map(|diff| {
+      (
+        diff.insert.to_json(txn),
+        diff.attributes.map(|attrs| *attrs),
+      )
+    })
+    .collect()
+}
+
+/// Converts a value read from one document into a prelim that can be inserted into another.
+fn deep_copy<T: ReadTxn>(txn: &T, value: Out) -> In {
+  match value {
+    Out::Any(any) => In::Any(any),
+    Out::YText(text) => In::Text(
+      text
+        .diff(txn, YChange::identity)
+        .into_iter()
+        .map(|diff| Delta::Inserted(deep_copy(txn, diff.insert), diff.attributes))
+        .collect(),
+    ),
+    Out::YArray(array) => In::Array(array.iter(txn).map(|v| deep_copy(txn, v)).collect()),
+    Out::YMap(map) => In::Map(
+      map
+        .iter(txn)
+        .map(|(k, v)| (k.to_string(), deep_copy(txn, v)))
+        .collect(),
+    ),
+    other => In::Any(other.to_json(txn)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use collab::core::origin::CollabOrigin;
+  use serde_json::json;
+  use yrs::updates::decoder::Decode;
+  use yrs::{StateVector, Update};
+
+  use super::*;
+
+  fn text(content: &str) -> In {
+    In::Text(vec![Delta::Inserted(In::Any(Any::from(content)), None)])
+  }
+
+  fn map(entries: Vec<(&str, In)>) -> In {
+    In::Map(
+      entries
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect(),
+    )
+  }
+
+  fn collab(entries: Vec<(&str, In)>) -> Collab {
+    let mut collab = Collab::new_with_origin(CollabOrigin::Empty, "object", vec![], false);
+    for (key, value) in entries {
+      collab.insert(key, value);
+    }
+    collab
+  }
+
+  #[test]
+  fn revert_update_restores_the_checkpoint_content() {
+    let target = collab(vec![
+      ("title", In::Any(Any::from("t1"))),
+      ("body", text("hello")),
+      ("meta", map(vec![("a", In::Any(Any::from(1)))])),
+    ]);
+    let mut current = collab(vec![
+      ("title", In::Any(Any::from("t2"))),
+      ("body", text("hello world")),
+      (
+        "meta",
+        map(vec![("a", In::Any(Any::from(2))), ("b", text("new"))]),
+      ),
+      ("added", In::Any(Any::from(true))),
+    ]);
+    let current_state = current
+      .transact()
+      .encode_state_as_update_v1(&StateVector::default());
+    let mut replica = collab_from_doc_state(current_state, "object").unwrap();
+
+    let update = revert_update(&mut current, &target);
+    assert_eq!(current.to_json_value(), target.to_json_value());
+    assert_eq!(
+      current.to_json_value(),
+      json!({"title": "t1", "body": "hello", "meta": {"a": 1}})
+    );
+
+    // the update brings the other replicas of the collab to the checkpoint content too
+    replica
+      .transact_mut()
+      .apply_update(Update::decode_v1(&update).unwrap())
+      .unwrap();
+    assert_eq!(replica.to_json_value(), target.to_json_value());
+  }
+
+  #[test]
+  fn revert_update_keeps_shared_types_in_place() {
+    let target = collab(vec![("meta", map(vec![("a", In::Any(Any::from(1)))]))]);
+    let mut current = collab(vec![("meta", map(vec![("a", In::Any(Any::from(2)))]))]);
+    let meta_before = match current.data.get(&current.transact(), "meta") {
+      Some(Out::YMap(meta)) => meta,
+      other => panic!("expected a map, got {:?}", other),
+    };
+
+    revert_update(&mut current, &target);
+    let meta_after = match current.data.get(&current.transact(), "meta") {
+      Some(Out::YMap(meta)) => meta,
+      other => panic!("expected a map, got {:?}", other),
+    };
+    assert_eq!(meta_before, meta_after);
+    assert_eq!(current.to_json_value(), json!({"meta": {"a": 1}}));
+  }
+}
@@ -5,7 +5,7 @@ use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
 use async_trait::async_trait;
 use collab::preclude::Collab;
 use collab_database::{
-  database::{gen_database_group_id, gen_field_id},
+  database::{gen_database_filter_id, gen_database_group_id, gen_database_sort_id, gen_field_id},
   entity::FieldType,
   error::DatabaseError,
   fields::{
@@ -13,8 +13,8 @@ use collab_database::{
     select_type_option::SingleSelectTypeOption, Field, TypeOptionData,
   },
   views::{
-    BoardLayoutSetting, CalendarLayoutSetting, DatabaseLayout, FieldSettingsByFieldIdMap, Group,
-    GroupSetting, GroupSettingMap, LayoutSettings,
+    BoardLayoutSetting, CalendarLayoutSetting, DatabaseLayout, FieldSettingsByFieldIdMap,
+    FilterMap, Group, GroupSetting, GroupSettingMap, LayoutSettings, SortMap,
   },
   workspace_database::{
     DatabaseCollabPersistenceService, DatabaseCollabService, EncodeCollabByOid,
@@ -23,6 +23,7 @@ use collab_database::{
 use collab_entity::{CollabType, EncodedCollab};
 use collab_folder::CollabOrigin;
 use database::collab::GetCollabOrigin;
+use shared_entity::dto::workspace_dto::{AFDatabaseViewFilter, AFDatabaseViewSort};
 use uuid::Uuid;
 
 use super::utils::{batch_get_latest_collab_encoded, get_latest_collab_encoded};
@@ -37,10 +38,34 @@ pub struct LinkedViewDependencies {
 pub fn resolve_dependencies_when_create_database_linked_view(
   database_layout: DatabaseLayout,
   fields: &[Field],
+) -> Result<LinkedViewDependencies, AppError> {
+  resolve_dependencies_with_group_field(database_layout, fields, None)
+}
+
+/// Same as [resolve_dependencies_when_create_database_linked_view], but board views are grouped
+/// by `group_field_id` instead of the first field that can be grouped.
+pub fn resolve_dependencies_with_group_field(
+  database_layout: DatabaseLayout,
+  fields: &[Field],
+  group_field_id: Option<&str>,
 ) -> Result<LinkedViewDependencies, AppError> {
   match database_layout {
     DatabaseLayout::Grid => resolve_grid_dependencies(fields),
-    DatabaseLayout::Board => resolve_board_dependencies(fields),
+    DatabaseLayout::Board => match group_field_id {
+      Some(group_field_id) => {
+        let group_field = find_group_field(fields, group_field_id)?;
+        let group_settings = vec![group_setting_for_field(group_field)?];
+        let mut layout_settings = LayoutSettings::default();
+        layout_settings.insert(database_layout, BoardLayoutSetting::new().into());
+        Ok(LinkedViewDependencies {
+          layout_settings,
+          field_settings: default_field_settings_for_fields(fields, database_layout),
+          group_settings,
+          deps_fields: vec![],
+        })
+      },
+      None => resolve_board_dependencies(fields),
+    },
     DatabaseLayout::Calendar => resolve_calendar_dependencies(fields),
   }
 }
@@ -71,6 +96,33 @@ fn resolve_board_dependencies(
     },
   };
   let field_settings = default_field_settings_for_fields(&all_fields, database_layout);
+  let group_settings = vec![group_setting_for_field(&group_field)?];
+
+  let mut layout_settings = LayoutSettings::default();
+  layout_settings.insert(database_layout, BoardLayoutSetting::new().into());
+  Ok(LinkedViewDependencies {
+    layout_settings,
+    field_settings,
+    group_settings,
+    deps_fields,
+  })
+}
+
+pub fn find_group_field<'a>(fields: &'a [Field], field_id: &str) -> Result<&'a Field, AppError> {
+  let field = fields
+    .iter()
+    .find(|f| f.id == field_id)
+    .ok_or_else(|| AppError::RecordNotFound(format!("field {} not found", field_id)))?;
+  if !FieldType::from(field.field_type).can_be_group() {
+    return Err(AppError::InvalidRequest(format!(
+      "rows can not be grouped by field {}",
+      field_id
+    )));
+  }
+  Ok(field)
+}
+
+pub fn group_setting_for_field(group_field: &Field) -> Result<GroupSettingMap, AppError> {
   let group_ids = match FieldType::from(group_field.field_type) {
     FieldType::SingleSelect => {
       let mut group_ids = vec![group_field.id.clone()];
@@ -79,29 +131,120 @@ fn resolve_board_dependencies(
       Ok(group_ids)
     },
     FieldType::Checkbox => Ok(vec!["Yes".to_string(), "No".to_string()]),
-    field_type => Err(AppError::Internal(anyhow::anyhow!(format!(
+    field_type => Err(AppError::InvalidRequest(format!(
       "invalid dep field type({}) for board layout",
       field_type
-    )))),
+    ))),
   }?;
 
   let groups = group_ids.iter().map(|id| Group::new(id.clone())).collect();
-  let group_settings: Vec<GroupSettingMap> = vec![GroupSetting {
-    id: gen_database_group_id(),
-    field_id: group_field.id.clone(),
-    field_type: group_field.field_type,
-    groups,
-    content: Default::default(),
+  Ok(
+    GroupSetting {
+      id: gen_database_group_id(),
+      field_id: group_field.id.clone(),
+      field_type: group_field.field_type,
+      groups,
+      content: Default::default(),
+    }
+    .into(),
+  )
+}
+
+pub fn group_field_id_from_settings(group_settings: &[GroupSettingMap]) -> Option<String> {
+  group_settings
+    .iter()
+    .find_map(|map| GroupSetting::try_from(map.clone()).ok())
+    .map(|setting| setting.field_id)
+}
+
+// Keys used by the AppFlowy client to store filters and sorts in the database view.
+const FILTER_ID: &str = "id";
+const FILTER_TYPE: &str = "filter_type";
+const FILTER_FIELD_ID: &str = "field_id";
+const FILTER_FIELD_TYPE: &str = "ty";
+const FILTER_CONDITION: &str = "condition";
+const FILTER_CONTENT: &str = "content";
+/// Filter on a single field, as opposed to the `and`/`or` filters that combine other filters.
+const FILTER_TYPE_DATA: i64 = 2;
+const SORT_ID: &str = "id";
+const SORT_FIELD_ID: &str = "field_id";
+const SORT_CONDITION: &str = "condition";
+const SORT_CONDITION_DESCENDING: i64 = 1;
+
+pub fn filter_map_from_dto(
+  fields: &[Field],
+  filter: AFDatabaseViewFilter,
+) -> Result<FilterMap, AppError> {
+  let field = fields
+    .iter()
+    .find(|f| f.id == filter.field_id)
+    .ok_or_else(|| AppError::RecordNotFound(format!("field {} not found", filter.field_id)))?;
+  let id = if filter.id.is_empty() {
+    gen_database_filter_id()
+  } else {
+    filter.id
+  };
+  let value = serde_json::json!({
+    FILTER_ID: id,
+    FILTER_TYPE: FILTER_TYPE_DATA,
+    FILTER_FIELD_ID: field.id,
+    FILTER_FIELD_TYPE: field.field_type,
+    FILTER_CONDITION: filter.condition,
+    FILTER_CONTENT: filter.content,
+  });
+  serde_json::from_value(value).map_err(|err| AppError::Internal(err.into()))
+}
+
+/// Returns `None` for the filters that do not apply to a single field.
+pub fn filter_dto_from_map(map: &FilterMap) -> Option<AFDatabaseViewFilter> {
+  let value = serde_json::to_value(map).ok()?;
+  if value.get(FILTER_TYPE)?.as_i64()? != FILTER_TYPE_DATA {
+    return None;
   }
-  .into()];
+  Some(AFDatabaseViewFilter {
+    id: value.get(FILTER_ID)?.as_str()?.to_string(),
+    field_id: value.get(FILTER_FIELD_ID)?.as_str()?.to_string(),
+    condition: value.get(FILTER_CONDITION)?.as_i64()?,
+    content: value
+      .get(FILTER_CONTENT)
+      .and_then(|v| v.as_str())
+      .unwrap_or_default()
+      .to_string(),
+  })
+}
 
-  let mut layout_settings = LayoutSettings::default();
-  layout_settings.insert(database_layout, BoardLayoutSetting::new().into());
-  Ok(LinkedViewDependencies {
-    layout_settings,
-    field_settings,
-    group_settings,
-    deps_fields,
+pub fn sort_map_from_dto(fields: &[Field], sort: AFDatabaseViewSort) -> Result<SortMap, AppError> {
+  if !fields.iter().any(|f| f.id == sort.field_id) {
+    return Err(AppError::RecordNotFound(format!(
+      "field {} not found",
+      sort.field_id
+    )));
+  }
+  let id = if sort.id.is_empty() {
+    gen_database_sort_id()
+  } else {
+    sort.id
+  };
+  let condition = if sort.descending {
+    SORT_CONDITION_DESCENDING
+  } else {
+    0
+  };
+  let value = serde_json::json!({
+    SORT_ID: id,
+    SORT_FIELD_ID: sort.field_id,
+    SORT_CONDITION: condition,
+  });
+  serde_json::from_value(value).map_err(|err| AppError::Internal(err.into()))
+}
+
+pub fn sort_dto_from_map(map: &SortMap) -> Option<AFDatabaseViewSort> {
+  let value = serde_json::to_value(map).ok()?;
+  Some(AFDatabaseViewSort {
+    id: value.get(SORT_ID)?.as_str()?.to_string(),
+    field_id: value.get(SORT_FIELD_ID)?.as_str()?.to_string(),
+    descending: value.get(SORT_CONDITION).and_then(|v| v.as_i64())
+      == Some(SORT_CONDITION_DESCENDING),
   })
 }
 
@@ -118,9 +261,9 @@ fn single_select_type_option_ids_from_field(field: &Field) -> Result<Vec<String>
         .collect();
       Ok(single_select_type_option_ids)
     },
-    None => Err(AppError::Internal(anyhow::anyhow!(
-      "invalid field for single select type options",
-    ))),
+    None => Err(AppError::InvalidRequest(
+      "invalid field for single select type options".to_string(),
+    )),
   }
 }
 
@@ -307,6 +450,38 @@ mod tests {
     assert_eq!(group_setting.groups[1].id, card_status_option_ids[0]);
   }
 
+  #[test]
+  fn test_view_filter_and_sort_round_trip() {
+    let field = Field::from_field_type("name", FieldType::RichText, true);
+    let fields = vec![field.clone()];
+    let filter = AFDatabaseViewFilter {
+      id: "".to_string(),
+      field_id: field.id.clone(),
+      condition: 2,
+      content: "hello".to_string(),
+    };
+    let filter_map = filter_map_from_dto(&fields, filter.clone()).unwrap();
+    let decoded = filter_dto_from_map(&filter_map).unwrap();
+    assert!(!decoded.id.is_empty());
+    assert_eq!(decoded.field_id, filter.field_id);
+    assert_eq!(decoded.condition, 2);
+    assert_eq!(decoded.content, "hello");
+
+    let sort = AFDatabaseViewSort {
+      id: "sort_id".to_string(),
+      field_id: field.id.clone(),
+      descending: true,
+    };
+    let sort_map = sort_map_from_dto(&fields, sort.clone()).unwrap();
+    assert_eq!(sort_dto_from_map(&sort_map).unwrap(), sort);
+
+    let unknown = AFDatabaseViewSort {
+      field_id: "unknown".to_string(),
+      ..Default::default()
+    };
+    assert!(sort_map_from_dto(&fields, unknown).is_err());
+  }
+
   #[test]
   fn test_resolve_dependencies_when_create_database_linked_view_calendar() {
     let database_layout = DatabaseLayout::Calendar;
@@ -0,0 +1,308 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use access_control::workspace_read_only::WorkspaceReadOnlyCache;
+use app_error::AppError;
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use chrono::Utc;
+use collab_database::views::DatabaseView;
+use database::database_form::{
+  delete_database_form_token as delete_form_token_row, insert_database_form_token,
+  select_database_form_token_by_hash, select_database_form_tokens,
+  update_database_form_token_last_used, AFDatabaseFormTokenRow,
+};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use redis::AsyncCommands;
+use secrecy::ExposeSecret;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use shared_entity::dto::workspace_dto::{
+  AFDatabaseFormToken, CreateDatabaseFormTokenParams, SubmitDatabaseFormRow,
+};
+use sqlx::PgPool;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::ops::insert_database_row;
+use super::utils::{field_by_name_uniq, get_latest_collab_database_body};
+use crate::config::config::{CaptchaSetting, DatabaseFormSetting};
+use crate::state::RedisConnectionManager;
+
+const FORM_TOKEN_PREFIX: &str = "afform_";
+const FORM_TOKEN_RANDOM_LEN: usize = 40;
+const MAX_FORM_TOKEN_NAME_LEN: usize = 100;
+/// Key of the visibility in the field settings of a view, a hidden field has the value 2.
+const FIELD_VISIBILITY_KEY: &str = "visibility";
+const FIELD_VISIBILITY_ALWAYS_HIDDEN: i64 = 2;
+
+/// Creates a token adding rows to the database. The token itself is only returned here, the
+/// server keeps its hash.
+pub async fn create_database_form_token(
+  collab_storage: &CollabAccessControlStorage,
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  database_id: &str,
+  uid: i64,
+  params: CreateDatabaseFormTokenParams,
+) -> Result<AFDatabaseFormToken, AppError> {
+  let name = params.name.trim().to_string();
+  if name.chars().count() > MAX_FORM_TOKEN_NAME_LEN {
+    return Err(AppError::StringLengthLimitReached(format!(
+      "the name of a form token can not be longer than {} characters",
+      MAX_FORM_TOKEN_NAME_LEN
+    )));
+  }
+  let (db_collab, db_body) =
+    get_latest_collab_database_body(collab_storage, &workspace_id.to_string(), database_id).await?;
+  if let Some(view_id) = &params.view_id {
+    let txn = db_collab.transact();
+    if db_body.views.get_view(&txn, view_id).is_none() {
+      return Err(AppError::InvalidRequest(format!(
+        "view {} is not a view of database {}",
+        view_id, database_id
+      )));
+    }
+  }
+
+  let token = format!(
+    "{}{}",
+    FORM_TOKEN_PREFIX,
+    rand::thread_rng()
+      .sample_iter(&Alphanumeric)
+      .take(FORM_TOKEN_RANDOM_LEN)
+      .map(char::from)
+      .collect::<String>()
+  );
+  let row = insert_database_form_token(
+    pg_pool,
+    workspace_id,
+    database_id,
+    params.view_id.as_deref(),
+    &name,
+    &hash_form_token(&token),
+    uid,
+  )
+  .await?;
+  let mut form_token = form_token_from_row(row);
+  form_token.token = Some(token);
+  Ok(form_token)
+}
+
+pub async fn list_database_form_tokens(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  database_id: &str,
+) -> Result<Vec<AFDatabaseFormToken>, AppError> {
+  let rows = select_database_form_tokens(pg_pool, workspace_id, database_id).await?;
+  Ok(rows.into_iter().map(form_token_from_row).collect())
+}
+
+pub async fn delete_database_form_token(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  database_id: &str,
+  token_id: &Uuid,
+) -> Result<(), AppError> {
+  if !delete_form_token_row(pg_pool, workspace_id, database_id, token_id).await? {
+    return Err(AppError::RecordNotFound(format!(
+      "form token {} not found",
+      token_id
+    )));
+  }
+  Ok(())
+}
+
+/// Adds a row to the database of the token, on behalf of the user who created the token.
+/// Returns the id of the new row.
+#[allow(clippy::too_many_arguments)]
+pub async fn submit_database_form_row(
+  collab_storage: Arc<CollabAccessControlStorage>,
+  pg_pool: &PgPool,
+  redis_client: &RedisConnectionManager,
+  workspace_read_only_cache: &WorkspaceReadOnlyCache,
+  setting: &DatabaseFormSetting,
+  token: &str,
+  client_addr: &str,
+  params: SubmitDatabaseFormRow,
+) -> Result<String, AppError> {
+  let form_token = select_database_form_token_by_hash(pg_pool, &hash_form_token(token))
+    .await?
+    .ok_or_else(|| AppError::RecordNotFound("form token not found".to_string()))?;
+  workspace_read_only_cache
+    .enforce_writable(&form_token.workspace_id)
+    .await?;
+  check_rate_limit(
+    redis_client,
+    &form_token.token_id,
+    client_addr,
+    setting.rate_limit_per_minute,
+    setting.token_rate_limit_per_minute,
+  )
+  .await?;
+  if let Some(captcha) = &setting.captcha {
+    verify_captcha(captcha, params.captcha_response.as_deref(), client_addr).await?;
+  }
+  if params.cells.is_empty() {
+    return Err(AppError::InvalidRequest(
+      "the submitted row has no cell".to_string(),
+    ));
+  }
+
+  let workspace_id = form_token.workspace_id.to_string();
+  if let Some(view_id) = &form_token.view_id {
+    let (db_collab, db_body) =
+      get_latest_collab_database_body(&collab_storage, &workspace_id, &form_token.database_id)
+        .await?;
+    let txn = db_collab.transact();
+    let view = db_body.views.get_view(&txn, view_id).ok_or_else(|| {
+      AppError::RecordNotFound(format!("view {} of the form was deleted", view_id))
+    })?;
+    let hidden_field_ids = hidden_field_ids(&view);
+    let fields = db_body.fields.get_all_fields(&txn);
+    let field_by_name = field_by_name_uniq(fields.clone());
+    for key in params.cells.keys() {
+      let field_id = fields
+        .iter()
+        .find(|field| &field.id == key)
+        .or_else(|| field_by_name.get(key))
+        .map(|field| field.id.as_str());
+      match field_id {
+        Some(field_id) if !hidden_field_ids.contains(field_id) => {},
+        _ => {
+          return Err(AppError::InvalidRequest(format!(
+            "field {} is not part of the form",
+            key
+          )))
+        },
+      }
+    }
+  }
+
+  let row_id = insert_database_row(
+    collab_storage,
+    pg_pool,
+    &workspace_id,
+    &form_token.database_id,
+    form_token.created_by,
+    None,
+    params.cells,
+    None,
+  )
+  .await?;
+  if let Err(err) = update_database_form_token_last_used(pg_pool, &form_token.token_id).await {
+    warn!(
+      "failed to update the last use of form token {}: {}",
+      form_token.token_id, err
+    );
+  }
+  Ok(row_id)
+}
+
+fn hash_form_token(token: &str) -> String {
+  format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+fn hidden_field_ids(view: &DatabaseView) -> HashSet<String> {
+  view
+    .field_settings
+    .iter()
+    .filter(|(_, settings)| match settings.get(FIELD_VISIBILITY_KEY) {
+      Some(yrs::Any::BigInt(visibility)) => *visibility == FIELD_VISIBILITY_ALWAYS_HIDDEN,
+      Some(yrs::Any::Number(visibility)) => *visibility as i64 == FIELD_VISIBILITY_ALWAYS_HIDDEN,
+      _ => false,
+    })
+    .map(|(field_id, _)| field_id.clone())
+    .collect()
+}
+
+/// Counts the rows submitted with the token in the current minute, from the client address and
+/// from all the addresses: a form can't be flooded from many addresses either.
+async fn check_rate_limit(
+  redis_client: &RedisConnectionManager,
+  token_id: &Uuid,
+  client_addr: &str,
+  limit_per_minute: u32,
+  token_limit_per_minute: u32,
+) -> Result<(), AppError> {
+  let window = Utc::now().timestamp() / 60;
+  let token_key = format!("af:database_form:{}:{}", token_id, window);
+  if incr_window_count(redis_client, &token_key).await? > token_limit_per_minute {
+    return Err(AppError::TooManyRequests(format!(
+      "more than {} rows were submitted to the form in a minute",
+      token_limit_per_minute
+    )));
+  }
+  let key = format!("af:database_form:{}:{}:{}", token_id, client_addr, window);
+  if incr_window_count(redis_client, &key).await? > limit_per_minute {
+    return Err(AppError::TooManyRequests(format!(
+      "more than {} rows were submitted in a minute",
+      limit_per_minute
+    )));
+  }
+  Ok(())
+}
+
+async fn incr_window_count(
+  redis_client: &RedisConnectionManager,
+  key: &str,
+) -> Result<u32, AppError> {
+  let mut conn = redis_client.clone();
+  let count: u32 = conn
+    .incr(key, 1)
+    .await
+    .map_err(|err| AppError::Internal(err.into()))?;
+  if count == 1 {
+    let result: Result<(), _> = conn.expire(key, 60).await;
+    if let Err(err) = result {
+      warn!("failed to set the expiration of {}: {}", key, err);
+    }
+  }
+  Ok(count)
+}
+
+#[derive(Deserialize)]
+struct CaptchaVerifyResponse {
+  success: bool,
+}
+
+async fn verify_captcha(
+  setting: &CaptchaSetting,
+  captcha_response: Option<&str>,
+  client_addr: &str,
+) -> Result<(), AppError> {
+  let captcha_response = captcha_response
+    .filter(|response| !response.is_empty())
+    .ok_or_else(|| AppError::InvalidRequest("the captcha response is missing".to_string()))?;
+  let resp = reqwest::Client::new()
+    .post(&setting.verify_url)
+    .form(&[
+      ("secret", setting.secret.expose_secret().as_str()),
+      ("response", captcha_response),
+      ("remoteip", client_addr),
+    ])
+    .send()
+    .await
+    .map_err(|err| AppError::Internal(anyhow::anyhow!("failed to verify captcha: {}", err)))?
+    .json::<CaptchaVerifyResponse>()
+    .await
+    .map_err(|err| AppError::Internal(anyhow::anyhow!("invalid captcha verification: {}", err)))?;
+  if !resp.success {
+    return Err(AppError::InvalidRequest(
+      "the captcha verification failed".to_string(),
+    ));
+  }
+  Ok(())
+}
+
+fn form_token_from_row(row: AFDatabaseFormTokenRow) -> AFDatabaseFormToken {
+  AFDatabaseFormToken {
+    token_id: row.token_id,
+    name: row.name,
+    database_id: row.database_id,
+    view_id: row.view_id,
+    created_at: row.created_at,
+    last_used_at: row.last_used_at,
+    token: None,
+  }
+}
@@ -0,0 +1,124 @@
+use app_error::AppError;
+use collab_database::rows::{meta_id_from_row_id, RowMetaKey};
+use collab_entity::CollabType;
+use database::collab::{select_collab_meta_from_af_collab, select_database_row_activity};
+use database::workspace::{
+  insert_comment_to_published_view, select_comments_for_published_view_ordered_by_recency,
+  select_user_is_workspace_owner, update_comment_deletion_status,
+};
+use database_entity::dto::{AFDatabaseRowActivities, DatabaseRowActivityQuery, GlobalComment};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::biz::workspace::ops::MAX_COMMENT_LENGTH;
+
+const DEFAULT_ACTIVITY_LIMIT: u32 = 50;
+const MAX_ACTIVITY_LIMIT: u32 = 200;
+
+/// The comments of a row are stored along with the comments of the published views, keyed by the
+/// id of the row document. Returns an error if the row doesn't belong to the workspace.
+async fn row_comment_key(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  row_id: &Uuid,
+) -> Result<Uuid, AppError> {
+  let row_meta =
+    select_collab_meta_from_af_collab(pg_pool, &row_id.to_string(), &CollabType::DatabaseRow)
+      .await?;
+  if !row_meta.is_some_and(|meta| meta.workspace_id == *workspace_id) {
+    return Err(AppError::RecordNotFound(format!(
+      "database row {} not found",
+      row_id
+    )));
+  }
+  let document_id = meta_id_from_row_id(row_id, RowMetaKey::DocumentId);
+  Uuid::parse_str(&document_id)
+    .map_err(|err| AppError::Internal(anyhow::anyhow!("invalid row document id: {}", err)))
+}
+
+pub async fn get_database_row_comments(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  row_id: &Uuid,
+  user_uuid: &Uuid,
+) -> Result<Vec<GlobalComment>, AppError> {
+  let key = row_comment_key(pg_pool, workspace_id, row_id).await?;
+  // the workspace owner can delete any comment of the rows, like the owner of a published page
+  let owner_uuid = if select_user_is_workspace_owner(pg_pool, user_uuid, workspace_id).await? {
+    *user_uuid
+  } else {
+    Uuid::nil()
+  };
+  select_comments_for_published_view_ordered_by_recency(
+    pg_pool,
+    &key,
+    &Some(*user_uuid),
+    &owner_uuid,
+  )
+  .await
+}
+
+pub async fn create_database_row_comment(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  row_id: &Uuid,
+  reply_comment_id: &Option<Uuid>,
+  content: &str,
+  user_uuid: &Uuid,
+) -> Result<(), AppError> {
+  if content.len() > MAX_COMMENT_LENGTH {
+    return Err(AppError::StringLengthLimitReached(
+      "comment content exceed limit".to_string(),
+    ));
+  }
+  let key = row_comment_key(pg_pool, workspace_id, row_id).await?;
+  insert_comment_to_published_view(pg_pool, &key, user_uuid, content, reply_comment_id).await
+}
+
+pub async fn remove_database_row_comment(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  row_id: &Uuid,
+  comment_id: &Uuid,
+  user_uuid: &Uuid,
+) -> Result<(), AppError> {
+  let comments = get_database_row_comments(pg_pool, workspace_id, row_id, user_uuid).await?;
+  let comment = comments
+    .iter()
+    .find(|comment| comment.comment_id == *comment_id)
+    .ok_or_else(|| AppError::RecordNotFound(format!("comment {} not found", comment_id)))?;
+  // only the author and the workspace owner can delete a comment
+  if !comment.can_be_deleted {
+    return Err(AppError::UserUnAuthorized(
+      "User is not allowed to delete this comment".to_string(),
+    ));
+  }
+  update_comment_deletion_status(pg_pool, comment_id).await
+}
+
+pub async fn get_database_row_activity(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  row_id: &Uuid,
+  query: &DatabaseRowActivityQuery,
+) -> Result<AFDatabaseRowActivities, AppError> {
+  let limit = query
+    .limit
+    .unwrap_or(DEFAULT_ACTIVITY_LIMIT)
+    .clamp(1, MAX_ACTIVITY_LIMIT) as usize;
+  // fetch one more to know if there are older activities
+  let mut activities = select_database_row_activity(
+    pg_pool,
+    workspace_id,
+    &row_id.to_string(),
+    query.before,
+    limit as i64 + 1,
+  )
+  .await?;
+  let has_more = activities.len() > limit;
+  activities.truncate(limit);
+  Ok(AFDatabaseRowActivities {
+    activities,
+    has_more,
+  })
+}
@@ -0,0 +1,150 @@
+use app_error::AppError;
+use chrono::{DateTime, LocalResult, NaiveDate, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+use collab_database::entity::FieldType;
+use collab_database::fields::Field;
+use database::workspace::select_workspace_settings;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Key of the time zone in the type option of the DateTime fields.
+const TIMEZONE_ID_KEY: &str = "timezone_id";
+/// Numeric timestamps above this are in milliseconds, as seconds they would be past year 5000.
+const MILLIS_TIMESTAMP_THRESHOLD: i64 = 100_000_000_000;
+const NAIVE_DATETIME_FORMATS: [&str; 4] = [
+  "%Y-%m-%dT%H:%M:%S%.f",
+  "%Y-%m-%d %H:%M:%S%.f",
+  "%Y-%m-%dT%H:%M",
+  "%Y-%m-%d %H:%M",
+];
+
+pub fn parse_timezone(timezone: &str) -> Result<Tz, AppError> {
+  timezone
+    .parse::<Tz>()
+    .map_err(|_| AppError::InvalidRequest(format!("unknown time zone: {}", timezone)))
+}
+
+pub async fn get_workspace_default_timezone(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+) -> Result<String, AppError> {
+  let settings = select_workspace_settings(pg_pool, workspace_id).await?;
+  Ok(settings.map(|s| s.default_timezone).unwrap_or_default())
+}
+
+/// Returns the time zone the dates without an offset given to the field are in: the time zone of
+/// the field, then the default time zone of the workspace, then UTC. Unknown time zones are
+/// skipped, they may have been set by an older client.
+pub fn field_timezone(field: &Field, default_timezone: &str) -> Tz {
+  let field_timezone = field
+    .get_any_type_option(FieldType::DateTime.type_id())
+    .and_then(|type_option| match type_option.get(TIMEZONE_ID_KEY) {
+      Some(yrs::Any::String(timezone)) => Some(timezone.to_string()),
+      _ => None,
+    });
+  [field_timezone.as_deref(), Some(default_timezone)]
+    .into_iter()
+    .flatten()
+    .filter(|timezone| !timezone.is_empty())
+    .find_map(|timezone| timezone.parse::<Tz>().ok())
+    .unwrap_or(Tz::UTC)
+}
+
+/// Converts the value given to a DateTime cell into a unix timestamp in seconds. Accepts
+/// timestamps in seconds or milliseconds, RFC 3339 date times, and dates or date times without an
+/// offset, which are read in `timezone`. Null, empty strings and objects are returned unchanged.
+pub fn normalize_date_cell_value(
+  value: serde_json::Value,
+  timezone: Tz,
+) -> Result<serde_json::Value, AppError> {
+  let timestamp = match &value {
+    serde_json::Value::Number(number) => match number.as_i64() {
+      Some(timestamp) => timestamp,
+      None => number
+        .as_f64()
+        .map(|timestamp| timestamp as i64)
+        .ok_or_else(|| AppError::InvalidRequest(format!("invalid timestamp: {}", number)))?,
+    },
+    serde_json::Value::String(s) if !s.trim().is_empty() => parse_date_str(s.trim(), timezone)?,
+    _ => return Ok(value),
+  };
+  let timestamp = if timestamp.abs() >= MILLIS_TIMESTAMP_THRESHOLD {
+    timestamp / 1000
+  } else {
+    timestamp
+  };
+  Ok(serde_json::Value::from(timestamp))
+}
+
+fn parse_date_str(s: &str, timezone: Tz) -> Result<i64, AppError> {
+  if let Ok(timestamp) = s.parse::<i64>() {
+    return Ok(timestamp);
+  }
+  if let Ok(datetime) = DateTime::parse_from_rfc3339(s) {
+    return Ok(datetime.timestamp());
+  }
+  let naive = NAIVE_DATETIME_FORMATS
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+    .or_else(|| {
+      NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+    })
+    .ok_or_else(|| AppError::InvalidRequest(format!("invalid date: {}", s)))?;
+  match timezone.from_local_datetime(&naive) {
+    LocalResult::Single(datetime) => Ok(datetime.timestamp()),
+    // the clocks were turned back, the first of the two instants is used
+    LocalResult::Ambiguous(earliest, _) => Ok(earliest.timestamp()),
+    LocalResult::None => Err(AppError::InvalidRequest(format!(
+      "{} does not exist in time zone {}",
+      s, timezone
+    ))),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  fn normalize(value: serde_json::Value, timezone: &str) -> Result<serde_json::Value, AppError> {
+    normalize_date_cell_value(value, parse_timezone(timezone).unwrap())
+  }
+
+  #[test]
+  fn timestamps_are_kept_in_seconds() {
+    assert_eq!(normalize(json!(1733210221), "UTC").unwrap(), 1733210221);
+    assert_eq!(
+      normalize(json!(1733210221000i64), "UTC").unwrap(),
+      1733210221
+    );
+    assert_eq!(normalize(json!("1733210221"), "UTC").unwrap(), 1733210221);
+    assert_eq!(normalize(json!(null), "UTC").unwrap(), json!(null));
+  }
+
+  #[test]
+  fn dates_without_offset_are_read_in_timezone() {
+    // 2024-12-03 07:17:01 UTC
+    assert_eq!(
+      normalize(json!("2024-12-03T07:17:01+00:00"), "Asia/Tokyo").unwrap(),
+      1733210221
+    );
+    assert_eq!(
+      normalize(json!("2024-12-03 16:17:01"), "Asia/Tokyo").unwrap(),
+      1733210221
+    );
+    assert_eq!(
+      normalize(json!("2024-12-03"), "America/New_York").unwrap(),
+      1733202000
+    );
+  }
+
+  #[test]
+  fn invalid_dates_are_rejected() {
+    assert!(normalize(json!("yesterday"), "UTC").is_err());
+    // skipped when the clocks were turned forward
+    assert!(normalize(json!("2024-03-10 02:30"), "America/New_York").is_err());
+    assert!(parse_timezone("Mars/Olympus_Mons").is_err());
+  }
+}
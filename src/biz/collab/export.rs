@@ -0,0 +1,268 @@
+use anyhow::anyhow;
+use app_error::AppError;
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use aws_sdk_s3::primitives::ByteStream;
+use database::collab::GetCollabOrigin;
+use database::export::{
+  insert_export_task, select_export_task, ExportTaskState, EXPORT_FORMAT_APPFLOWY,
+  EXPORT_FORMAT_PDF,
+};
+use database::file::BlobStorageClient;
+use database::file::BucketClient;
+use database::pg_row::AFExportTaskRow;
+use database::user::select_name_and_email_from_uuid;
+use database::workspace::select_workspace_name_from_workspace_id;
+use redis::AsyncCommands;
+use serde_json::json;
+use shared_entity::dto::export_dto::ExportTaskDetail;
+use shared_entity::dto::workspace_dto::RenderFormat;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::render::render_document;
+use super::utils::get_latest_collab_folder;
+use crate::state::RedisConnectionManager;
+
+const EXPORT_TASK_STREAM: &str = "export_task_stream";
+/// The download url sent in the completion email is valid for a day.
+const NOTIFICATION_URL_EXPIRES_SECS: u64 = 24 * 60 * 60;
+const DOWNLOAD_URL_EXPIRES_SECS: u64 = 60 * 60;
+
+/// Renders the document to HTML and queues a task for the worker to turn it into a PDF. The user
+/// is notified by email once the PDF is available, the status can also be polled with
+/// [get_export_task].
+#[allow(clippy::too_many_arguments)]
+pub async fn create_pdf_export_task(
+  collab_storage: &CollabAccessControlStorage,
+  bucket_client: &BlobStorageClient,
+  redis_client: &RedisConnectionManager,
+  pg_pool: &PgPool,
+  uid: i64,
+  user_uuid: &Uuid,
+  workspace_id: Uuid,
+  object_id: Uuid,
+) -> Result<ExportTaskDetail, AppError> {
+  let workspace_id_str = workspace_id.to_string();
+  let object_id_str = object_id.to_string();
+  let body = render_document(
+    collab_storage,
+    uid,
+    &workspace_id_str,
+    &object_id_str,
+    RenderFormat::Html,
+  )
+  .await?;
+  let file_name = get_latest_collab_folder(
+    collab_storage,
+    GetCollabOrigin::User { uid },
+    &workspace_id_str,
+  )
+  .await
+  .ok()
+  .and_then(|folder| folder.get_view(&object_id_str))
+  .map(|view| view.name.clone())
+  .filter(|name| !name.is_empty())
+  .unwrap_or_else(|| "Untitled".to_string());
+
+  let task_id = Uuid::new_v4();
+  let html_key = format!("export/{}/{}/document.html", workspace_id, task_id);
+  let pdf_key = format!("export/{}/{}/document.pdf", workspace_id, task_id);
+  bucket_client
+    .put_blob_with_content_type(
+      &html_key,
+      ByteStream::from(html_page(&file_name, &body).into_bytes()),
+      "text/html; charset=utf-8",
+    )
+    .await?;
+  let download_url = bucket_client
+    .gen_presigned_get_url(&pdf_key, NOTIFICATION_URL_EXPIRES_SECS)
+    .await?;
+
+  let (user_name, user_email) = select_name_and_email_from_uuid(pg_pool, user_uuid).await?;
+  insert_export_task(
+    pg_pool,
+    &task_id,
+    &workspace_id,
+    &object_id_str,
+    uid,
+    EXPORT_FORMAT_PDF,
+  )
+  .await?;
+  let task = json!({
+    "task_id": task_id,
+    "uid": uid,
+    "user_name": user_name,
+    "user_email": user_email,
+    "workspace_id": workspace_id_str,
+    "object_id": object_id_str,
+    "file_name": file_name,
+    "format": EXPORT_FORMAT_PDF,
+    "html_key": html_key,
+    "file_key": pdf_key,
+    "download_url": download_url,
+  });
+  push_export_task(redis_client, task).await?;
+  Ok(pending_export_task(
+    task_id,
+    object_id_str,
+    EXPORT_FORMAT_PDF,
+  ))
+}
+
+/// Queues a task for the worker to pack the collabs and the files of the workspace into an
+/// archive, which can be imported into a new workspace of this instance or of another one. The
+/// user is notified by email once the archive is available.
+pub async fn create_workspace_export_task(
+  bucket_client: &BlobStorageClient,
+  redis_client: &RedisConnectionManager,
+  pg_pool: &PgPool,
+  uid: i64,
+  user_uuid: &Uuid,
+  workspace_id: Uuid,
+) -> Result<ExportTaskDetail, AppError> {
+  let workspace_id_str = workspace_id.to_string();
+  let file_name = select_workspace_name_from_workspace_id(pg_pool, &workspace_id)
+    .await?
+    .filter(|name| !name.is_empty())
+    .unwrap_or_else(|| "Untitled".to_string());
+
+  let task_id = Uuid::new_v4();
+  let archive_key = format!("export/{}/{}/workspace.zip", workspace_id, task_id);
+  let download_url = bucket_client
+    .gen_presigned_get_url(&archive_key, NOTIFICATION_URL_EXPIRES_SECS)
+    .await?;
+
+  let (user_name, user_email) = select_name_and_email_from_uuid(pg_pool, user_uuid).await?;
+  insert_export_task(
+    pg_pool,
+    &task_id,
+    &workspace_id,
+    &workspace_id_str,
+    uid,
+    EXPORT_FORMAT_APPFLOWY,
+  )
+  .await?;
+  let task = json!({
+    "task_id": task_id,
+    "uid": uid,
+    "user_name": user_name,
+    "user_email": user_email,
+    "workspace_id": workspace_id_str,
+    "object_id": workspace_id_str,
+    "file_name": file_name,
+    "format": EXPORT_FORMAT_APPFLOWY,
+    "file_key": archive_key,
+    "download_url": download_url,
+  });
+  push_export_task(redis_client, task).await?;
+  Ok(pending_export_task(
+    task_id,
+    workspace_id_str,
+    EXPORT_FORMAT_APPFLOWY,
+  ))
+}
+
+async fn push_export_task(
+  redis_client: &RedisConnectionManager,
+  task: serde_json::Value,
+) -> Result<(), AppError> {
+  let _: () = redis_client
+    .clone()
+    .xadd(EXPORT_TASK_STREAM, "*", &[("task", task.to_string())])
+    .await
+    .map_err(|err| AppError::Internal(anyhow!("Failed to push task to Redis stream: {}", err)))?;
+  Ok(())
+}
+
+fn pending_export_task(task_id: Uuid, object_id: String, format: &str) -> ExportTaskDetail {
+  ExportTaskDetail {
+    task_id: task_id.to_string(),
+    object_id,
+    format: format.to_string(),
+    status: ExportTaskState::Pending as i16,
+    download_url: None,
+    error: None,
+    created_at: chrono::Utc::now().timestamp(),
+  }
+}
+
+/// Returns the export task of the user, with a fresh download url once the task is completed.
+pub async fn get_export_task(
+  bucket_client: &BlobStorageClient,
+  pg_pool: &PgPool,
+  uid: i64,
+  workspace_id: &Uuid,
+  task_id: &Uuid,
+) -> Result<ExportTaskDetail, AppError> {
+  let task = select_export_task(pg_pool, task_id).await?;
+  if task.uid != uid || &task.workspace_id != workspace_id {
+    return Err(AppError::RecordNotFound(format!(
+      "export task {} not found",
+      task_id
+    )));
+  }
+  let download_url = match (&task.file_key, ExportTaskState::from(task.status)) {
+    (Some(file_key), ExportTaskState::Completed) => Some(
+      bucket_client
+        .gen_presigned_get_url(file_key, DOWNLOAD_URL_EXPIRES_SECS)
+        .await?,
+    ),
+    _ => None,
+  };
+  Ok(export_task_detail(task, download_url))
+}
+
+fn export_task_detail(task: AFExportTaskRow, download_url: Option<String>) -> ExportTaskDetail {
+  ExportTaskDetail {
+    task_id: task.task_id.to_string(),
+    object_id: task.oid,
+    format: task.format,
+    status: task.status,
+    download_url,
+    error: task.error,
+    created_at: task.created_at.timestamp(),
+  }
+}
+
+/// Wraps the rendered document into a standalone page with print friendly styles. The page is
+/// rendered by a browser on the worker, the content security policy keeps the document from
+/// making it load anything, only the images embedded as data urls are shown.
+fn html_page(title: &str, body: &str) -> String {
+  let title = title
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;");
+  format!(
+    r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta http-equiv="Content-Security-Policy" content="default-src 'none'; img-src data:; style-src 'unsafe-inline'">
+<title>{title}</title>
+<style>
+body {{ font-family: -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; line-height: 1.6; margin: 2cm; color: #1f2329; }}
+h1.title {{ font-size: 2em; margin-bottom: 1em; }}
+pre {{ background: #f5f5f5; padding: 12px; border-radius: 4px; white-space: pre-wrap; }}
+blockquote, aside {{ border-left: 4px solid #d0d7de; margin: 0; padding-left: 12px; color: #57606a; }}
+img {{ max-width: 100%; }}
+</style>
+</head>
+<body>
+<h1 class="title">{title}</h1>
+{body}
+</body>
+</html>"#
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn html_page_forbids_network_access() {
+    let page = html_page("<b>Plan</b>", "<img src=\"http://169.254.169.254/\">");
+    assert!(page.contains("content=\"default-src 'none'; img-src data:;"));
+    assert!(page.contains("<title>&lt;b&gt;Plan&lt;/b&gt;</title>"));
+  }
+}
@@ -0,0 +1,357 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::DerefMut;
+use std::sync::Arc;
+
+use app_error::AppError;
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use chrono::Utc;
+use collab::preclude::Collab;
+use collab_database::database::gen_row_id;
+use collab_database::entity::FieldType;
+use collab_database::rows::{CreateRowParams, DatabaseRowBody, Row, RowDetail, RowId};
+use collab_database::views::OrderObjectPosition;
+use collab_entity::CollabType;
+use collab_folder::CollabOrigin;
+use database::collab::{
+  database_row_cells, insert_database_row_activity, select_database_external_id_field,
+  upsert_database_external_id_field, GetCollabOrigin,
+};
+use database_entity::dto::{CollabParams, DatabaseRowCellChange};
+use shared_entity::dto::workspace_dto::{
+  AFDatabaseExternalIdField, AFUpsertedDatabaseRow, BulkUpsertDatabaseRow,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::date_cell::get_workspace_default_timezone;
+use super::utils::{
+  batch_get_latest_collab_encoded, collab_to_bin, get_latest_collab_database_body,
+  type_option_reader_by_id, write_to_database_row,
+};
+use crate::biz::workspace::ops::broadcast_update_with_timeout;
+
+/// Most rows that can be upserted in one request, all of them are written in a single transaction.
+pub const MAX_BULK_UPSERT_ROWS: usize = 500;
+
+pub async fn get_database_external_id_field(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  database_id: &str,
+) -> Result<AFDatabaseExternalIdField, AppError> {
+  let field_id = select_database_external_id_field(pg_pool, workspace_id, database_id)
+    .await?
+    .ok_or_else(|| {
+      AppError::RecordNotFound(format!("database {} has no external id field", database_id))
+    })?;
+  Ok(AFDatabaseExternalIdField { field_id })
+}
+
+/// The external ids are compared as text, so only the text fields can hold them.
+pub async fn set_database_external_id_field(
+  collab_storage: &CollabAccessControlStorage,
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  database_id: &str,
+  uid: i64,
+  field_id: &str,
+) -> Result<(), AppError> {
+  let (db_collab, db_body) =
+    get_latest_collab_database_body(collab_storage, &workspace_id.to_string(), database_id).await?;
+  let field = db_body
+    .fields
+    .get_field(&db_collab.transact(), field_id)
+    .ok_or_else(|| AppError::RecordNotFound(format!("field {} not found", field_id)))?;
+  if FieldType::from(field.field_type) != FieldType::RichText {
+    return Err(AppError::InvalidRequest(format!(
+      "field {} is not a text field",
+      field_id
+    )));
+  }
+  upsert_database_external_id_field(pg_pool, workspace_id, database_id, field_id, uid).await
+}
+
+struct UpdatedRow {
+  row_id: String,
+  encoded_collab: Vec<u8>,
+  update: Vec<u8>,
+  changes: Vec<DatabaseRowCellChange>,
+}
+
+/// Creates the rows whose external id is not found in the database and updates the cells of the
+/// others. The rows and the database are written in one transaction, and the row orders of all
+/// the views are updated once for all the created rows.
+pub async fn bulk_upsert_database_rows(
+  collab_storage: Arc<CollabAccessControlStorage>,
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  database_id: &str,
+  uid: i64,
+  rows: Vec<BulkUpsertDatabaseRow>,
+) -> Result<Vec<AFUpsertedDatabaseRow>, AppError> {
+  if rows.len() > MAX_BULK_UPSERT_ROWS {
+    return Err(AppError::InvalidRequest(format!(
+      "at most {} rows can be upserted at once",
+      MAX_BULK_UPSERT_ROWS
+    )));
+  }
+  let mut external_ids = HashSet::with_capacity(rows.len());
+  for row in &rows {
+    if row.external_id.is_empty() {
+      return Err(AppError::InvalidRequest(
+        "the external id of a row can't be empty".to_string(),
+      ));
+    }
+    if !external_ids.insert(row.external_id.as_str()) {
+      return Err(AppError::InvalidRequest(format!(
+        "external id {} is given more than once",
+        row.external_id
+      )));
+    }
+  }
+
+  let field_id = select_database_external_id_field(pg_pool, workspace_id, database_id)
+    .await?
+    .ok_or_else(|| {
+      AppError::InvalidRequest(format!("database {} has no external id field", database_id))
+    })?;
+  let workspace_uuid_str = workspace_id.to_string();
+  let default_timezone = get_workspace_default_timezone(pg_pool, workspace_id).await?;
+  let (mut db_collab, db_body) =
+    get_latest_collab_database_body(&collab_storage, &workspace_uuid_str, database_id).await?;
+  let (field, row_ids) = {
+    let txn = db_collab.transact();
+    let field = db_body.fields.get_field(&txn, &field_id).ok_or_else(|| {
+      AppError::InvalidRequest(format!(
+        "external id field {} was deleted from the database",
+        field_id
+      ))
+    })?;
+    let inline_view_id = db_body.get_inline_view_id(&txn);
+    let row_ids = db_body
+      .views
+      .get_view(&txn, &inline_view_id)
+      .map(|view| {
+        view
+          .row_orders
+          .into_iter()
+          .map(|row_order| row_order.id.to_string())
+          .collect::<Vec<_>>()
+      })
+      .unwrap_or_default();
+    (field, row_ids)
+  };
+
+  // The existing rows are indexed by the value of their external id cell.
+  let external_id_reader = type_option_reader_by_id(std::slice::from_ref(&field))
+    .remove(&field_id)
+    .ok_or_else(|| AppError::Internal(anyhow::anyhow!("no reader for field {}", field_id)))?;
+  let encoded_rows = batch_get_latest_collab_encoded(
+    &collab_storage,
+    GetCollabOrigin::Server,
+    &workspace_uuid_str,
+    &row_ids,
+    CollabType::DatabaseRow,
+  )
+  .await?;
+  let mut existing_rows = HashMap::with_capacity(encoded_rows.len());
+  for (row_id, encoded_row) in encoded_rows {
+    let collab = match Collab::new_with_source(
+      CollabOrigin::Server,
+      &row_id,
+      encoded_row.into(),
+      vec![],
+      false,
+    ) {
+      Ok(collab) => collab,
+      Err(err) => {
+        tracing::error!("Failed to open database row {}: {:?}", row_id, err);
+        continue;
+      },
+    };
+    let external_id = RowDetail::from_collab(&collab)
+      .and_then(|row_detail| row_detail.row.cells.get(&field_id).cloned())
+      .and_then(|cell| match external_id_reader.json_cell(&cell) {
+        serde_json::Value::String(external_id) if !external_id.is_empty() => Some(external_id),
+        _ => None,
+      });
+    if let Some(external_id) = external_id {
+      if external_ids.contains(external_id.as_str()) {
+        existing_rows.insert(external_id, (row_id, collab));
+      }
+    }
+  }
+
+  let now = Utc::now();
+  let mut upserted_rows = Vec::with_capacity(rows.len());
+  let mut created_rows = vec![];
+  let mut updated_rows = vec![];
+  let mut row_orders = vec![];
+  for BulkUpsertDatabaseRow {
+    external_id,
+    mut cells,
+  } in rows
+  {
+    cells.insert(
+      field_id.clone(),
+      serde_json::Value::String(external_id.clone()),
+    );
+    match existing_rows.remove(&external_id) {
+      Some((row_id, mut row_collab)) => {
+        let row_body = DatabaseRowBody::open(RowId::from(row_id.clone()), &mut row_collab)
+          .map_err(|err| {
+            AppError::Internal(anyhow::anyhow!(
+              "Failed to open database row {}: {}",
+              row_id,
+              err
+            ))
+          })?;
+        let mut row_txn = row_collab.transact_mut();
+        let cells_before = database_row_cells(&row_body.get_data(), &row_txn);
+        write_to_database_row(
+          &db_body,
+          &mut row_txn,
+          &row_body,
+          cells,
+          &default_timezone,
+          now.timestamp(),
+        )
+        .await?;
+        let cells_after = database_row_cells(&row_body.get_data(), &row_txn);
+        let changes = DatabaseRowCellChange::diff(&cells_before, &cells_after, Some(uid), now);
+        let update = row_txn.encode_update_v1();
+        drop(row_txn);
+        updated_rows.push(UpdatedRow {
+          row_id: row_id.clone(),
+          encoded_collab: collab_to_bin(row_collab, CollabType::DatabaseRow).await?,
+          update,
+          changes,
+        });
+        upserted_rows.push(AFUpsertedDatabaseRow {
+          external_id,
+          row_id,
+          created: false,
+        });
+      },
+      None => {
+        let row_id = gen_row_id();
+        let mut row_collab =
+          Collab::new_with_origin(CollabOrigin::Empty, row_id.clone(), vec![], false);
+        let row_body = DatabaseRowBody::create(
+          row_id.clone(),
+          &mut row_collab,
+          Row::empty(row_id.clone(), database_id),
+        );
+        row_body.update(&mut row_collab.transact_mut(), |row_update| {
+          row_update.set_created_at(now.timestamp());
+        });
+        write_to_database_row(
+          &db_body,
+          &mut row_collab.transact_mut(),
+          &row_body,
+          cells,
+          &default_timezone,
+          now.timestamp(),
+        )
+        .await?;
+        let row_order = db_body
+          .create_row(CreateRowParams {
+            id: row_id.clone(),
+            database_id: database_id.to_string(),
+            cells: row_body.cells(&row_collab.transact()).unwrap_or_default(),
+            height: 30,
+            visibility: true,
+            row_position: OrderObjectPosition::End,
+            created_at: now.timestamp(),
+            modified_at: now.timestamp(),
+          })
+          .await
+          .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to create row: {:?}", e)))?;
+        row_orders.push(row_order);
+        created_rows.push((
+          row_id.to_string(),
+          collab_to_bin(row_collab, CollabType::DatabaseRow).await?,
+        ));
+        upserted_rows.push(AFUpsertedDatabaseRow {
+          external_id,
+          row_id: row_id.to_string(),
+          created: true,
+        });
+      },
+    }
+  }
+
+  // The created rows are appended to every view of the database at once.
+  let db_collab_update = if row_orders.is_empty() {
+    None
+  } else {
+    let mut txn = db_collab.transact_mut();
+    let mut db_views = db_body.views.get_all_views(&txn);
+    for db_view in db_views.iter_mut() {
+      db_view.row_orders.extend(row_orders.iter().cloned());
+    }
+    db_body.views.clear(&mut txn);
+    for view in db_views {
+      db_body.views.insert_view(&mut txn, view);
+    }
+    Some(txn.encode_update_v1())
+  };
+
+  let mut db_txn = pg_pool.begin().await?;
+  for (row_id, encoded_collab) in created_rows {
+    collab_storage
+      .upsert_new_collab_with_transaction(
+        &workspace_uuid_str,
+        &uid,
+        CollabParams {
+          object_id: row_id,
+          encoded_collab_v1: encoded_collab.into(),
+          collab_type: CollabType::DatabaseRow,
+        },
+        &mut db_txn,
+        "inserting new database row from bulk upsert",
+      )
+      .await?;
+  }
+  for row in &updated_rows {
+    collab_storage
+      .upsert_new_collab_with_transaction(
+        &workspace_uuid_str,
+        &uid,
+        CollabParams {
+          object_id: row.row_id.clone(),
+          encoded_collab_v1: row.encoded_collab.clone().into(),
+          collab_type: CollabType::DatabaseRow,
+        },
+        &mut db_txn,
+        "updating database row from bulk upsert",
+      )
+      .await?;
+    insert_database_row_activity(db_txn.deref_mut(), workspace_id, &row.row_id, &row.changes)
+      .await?;
+  }
+  if db_collab_update.is_some() {
+    let updated_db_collab = collab_to_bin(db_collab, CollabType::Database).await?;
+    collab_storage
+      .upsert_new_collab_with_transaction(
+        &workspace_uuid_str,
+        &uid,
+        CollabParams {
+          object_id: database_id.to_string(),
+          encoded_collab_v1: updated_db_collab.into(),
+          collab_type: CollabType::Database,
+        },
+        &mut db_txn,
+        "inserting updated database from bulk upsert",
+      )
+      .await?;
+  }
+  db_txn.commit().await?;
+
+  for row in updated_rows {
+    broadcast_update_with_timeout(collab_storage.clone(), row.row_id, row.update).await;
+  }
+  if let Some(db_collab_update) = db_collab_update {
+    broadcast_update_with_timeout(collab_storage, database_id.to_string(), db_collab_update).await;
+  }
+  Ok(upserted_rows)
+}
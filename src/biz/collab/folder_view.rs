@@ -15,6 +15,8 @@ pub struct PrivateSpaceAndTrashViews {
   pub my_private_space_ids: HashSet<String>,
   pub other_private_space_ids: HashSet<String>,
   pub view_ids_in_trash: HashSet<String>,
+  /// Restricted views the user is not a member of, hidden with their subtree.
+  pub restricted_view_ids: HashSet<String>,
 }
 
 pub fn private_space_and_trash_view_ids(folder: &Folder) -> PrivateSpaceAndTrashViews {
@@ -48,6 +50,7 @@ pub fn private_space_and_trash_view_ids(folder: &Folder) -> PrivateSpaceAndTrash
     my_private_space_ids,
     other_private_space_ids,
     view_ids_in_trash,
+    restricted_view_ids: HashSet::new(),
   }
 }
 
@@ -58,8 +61,15 @@ pub fn collab_folder_to_folder_view(
   folder: &Folder,
   max_depth: u32,
   pubished_view_ids: &HashSet<String>,
+  restricted_view_ids: HashSet<String>,
 ) -> Result<FolderView, AppError> {
-  let private_space_and_trash_view_ids = private_space_and_trash_view_ids(folder);
+  if check_if_view_ancestors_fulfil_condition(root_view_id, folder, |view| {
+    restricted_view_ids.contains(&view.id)
+  }) {
+    return Err(AppError::NotEnoughPermissions);
+  }
+  let mut private_space_and_trash_view_ids = private_space_and_trash_view_ids(folder);
+  private_space_and_trash_view_ids.restricted_view_ids = restricted_view_ids;
 
   to_folder_view(
     workspace_id,
@@ -78,6 +88,63 @@ pub fn collab_folder_to_folder_view(
   )))
 }
 
+/// The folder seen by a guest of the workspace: the workspace root with the views shared with the
+/// guest as children, without the views below them.
+pub fn collab_folder_to_guest_folder_view(
+  workspace_id: Uuid,
+  folder: &Folder,
+  shared_view_ids: &HashSet<String>,
+  pubished_view_ids: &HashSet<String>,
+  restricted_view_ids: HashSet<String>,
+) -> Result<FolderView, AppError> {
+  let mut private_space_and_trash_view_ids = private_space_and_trash_view_ids(folder);
+  // a view shared with the guest is visible even when it is in a private space
+  private_space_and_trash_view_ids
+    .other_private_space_ids
+    .clear();
+  let workspace_view_id = workspace_id.to_string();
+  let mut root = to_folder_view(
+    workspace_id,
+    "",
+    &workspace_view_id,
+    folder,
+    &private_space_and_trash_view_ids,
+    pubished_view_ids,
+    false,
+    0,
+    0,
+  )
+  .ok_or(AppError::InvalidFolderView(format!(
+    "There is no valid folder view belonging to the workspace: {}",
+    workspace_id
+  )))?;
+
+  let mut children: Vec<FolderView> = shared_view_ids
+    .iter()
+    .filter(|view_id| {
+      !check_if_view_ancestors_fulfil_condition(view_id, folder, |view| {
+        restricted_view_ids.contains(&view.id)
+      })
+    })
+    .filter_map(|view_id| {
+      to_folder_view(
+        workspace_id,
+        "",
+        view_id,
+        folder,
+        &private_space_and_trash_view_ids,
+        pubished_view_ids,
+        false,
+        0,
+        0,
+      )
+    })
+    .collect();
+  children.sort_by(|a, b| a.name.cmp(&b.name));
+  root.children = children;
+  Ok(root)
+}
+
 #[allow(clippy::too_many_arguments)]
 fn to_folder_view(
   workspace_id: Uuid,
@@ -99,8 +166,11 @@ fn to_folder_view(
   let is_other_private_space = private_space_and_trash_views
     .other_private_space_ids
     .contains(view_id);
+  let is_restricted = private_space_and_trash_views
+    .restricted_view_ids
+    .contains(view_id);
 
-  if depth > max_depth || is_other_private_space || is_trash {
+  if depth > max_depth || is_other_private_space || is_trash || is_restricted {
     return None;
   }
 
@@ -283,6 +353,9 @@ fn get_view_and_children_recursive(
   if private_space_and_trash_views
     .view_ids_in_trash
     .contains(view_id)
+    || private_space_and_trash_views
+      .restricted_view_ids
+      .contains(view_id)
   {
     return None;
   }
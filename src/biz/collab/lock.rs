@@ -0,0 +1,51 @@
+use access_control::collab_lock::CollabLockCache;
+use actix_web::web::Data;
+use anyhow::anyhow;
+use app_error::AppError;
+use appflowy_collaborate::actix_ws::entities::CollabLockChangedMessage;
+use collab_rt_entity::CollabLockChanged;
+use database_entity::dto::AFCollabLock;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::api::ws::RealtimeServerAddr;
+
+/// Freezes the collab and informs the clients that have it open.
+#[instrument(level = "debug", skip(collab_lock_cache, server), err)]
+pub async fn lock_collab(
+  collab_lock_cache: &CollabLockCache,
+  server: Data<RealtimeServerAddr>,
+  uid: i64,
+  workspace_id: Uuid,
+  object_id: Uuid,
+  reason: Option<String>,
+) -> Result<AFCollabLock, AppError> {
+  let object_id = object_id.to_string();
+  let lock = collab_lock_cache
+    .lock(&workspace_id, &object_id, uid, reason.as_deref())
+    .await?;
+  broadcast_lock_changed(server, CollabLockChanged::new(object_id, true, uid, reason))?;
+  Ok(lock)
+}
+
+#[instrument(level = "debug", skip(collab_lock_cache, server), err)]
+pub async fn unlock_collab(
+  collab_lock_cache: &CollabLockCache,
+  server: Data<RealtimeServerAddr>,
+  uid: i64,
+  workspace_id: Uuid,
+  object_id: Uuid,
+) -> Result<(), AppError> {
+  let object_id = object_id.to_string();
+  collab_lock_cache.unlock(&workspace_id, &object_id).await?;
+  broadcast_lock_changed(server, CollabLockChanged::new(object_id, false, uid, None))
+}
+
+fn broadcast_lock_changed(
+  server: Data<RealtimeServerAddr>,
+  message: CollabLockChanged,
+) -> Result<(), AppError> {
+  server
+    .try_send(CollabLockChangedMessage { message })
+    .map_err(|err| AppError::Internal(anyhow!("Failed to send message to server: {}", err)))
+}
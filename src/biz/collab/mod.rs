@@ -1,5 +1,17 @@
+pub mod archive;
+pub mod changes;
+pub mod checkpoint;
 pub mod database;
+pub mod database_form;
+pub mod database_row;
+pub mod date_cell;
+pub mod export;
+pub mod external_id;
 pub mod folder_view;
+pub mod lock;
 pub mod ops;
 pub mod publish_outline;
+pub mod render;
+pub mod stats;
 pub mod utils;
+pub mod workspace_collabs;
@@ -1,24 +1,34 @@
 use std::collections::HashMap;
+use std::ops::DerefMut;
 use std::sync::Arc;
 
+use access_control::collab_guest::GuestAccess;
+use access_control::restricted_view::RestrictedViewCache;
 use actix_web::web::Data;
 use app_error::AppError;
 use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use async_stream::stream;
+use bytes::Bytes;
 use chrono::DateTime;
 use chrono::Utc;
 use collab::preclude::Collab;
 use collab_database::database::gen_field_id;
 use collab_database::database::gen_row_id;
+use collab_database::database::timestamp;
 use collab_database::entity::FieldType;
 use collab_database::fields::Field;
 use collab_database::fields::TypeOptions;
 use collab_database::rows::meta_id_from_row_id;
+use collab_database::rows::Cell;
 use collab_database::rows::CreateRowParams;
 use collab_database::rows::DatabaseRowBody;
 use collab_database::rows::Row;
 use collab_database::rows::RowDetail;
 use collab_database::rows::RowId;
 use collab_database::rows::RowMetaKey;
+use collab_database::template::entity::CELL_DATA;
+use collab_database::views::DatabaseLayout;
+use collab_database::views::DatabaseView;
 use collab_database::views::OrderObjectPosition;
 use collab_database::workspace_database::WorkspaceDatabase;
 use collab_database::workspace_database::WorkspaceDatabaseBody;
@@ -30,6 +40,8 @@ use collab_folder::Folder;
 use collab_folder::SectionItem;
 use collab_folder::{CollabOrigin, SpaceInfo};
 use collab_rt_entity::user::RealtimeUser;
+use database::collab::database_row_cells;
+use database::collab::insert_database_row_activity;
 use database::collab::select_last_updated_database_row_ids;
 use database::collab::select_workspace_database_oid;
 use database::collab::{CollabStorage, GetCollabOrigin};
@@ -37,20 +49,26 @@ use database::publish::select_published_view_ids_for_workspace;
 use database::publish::select_published_view_ids_with_publish_info_for_workspace;
 use database::publish::select_workspace_id_for_publish_namespace;
 use database_entity::dto::CollabParams;
+use database_entity::dto::DatabaseRowCellChange;
 use database_entity::dto::QueryCollab;
 use database_entity::dto::QueryCollabResult;
+use database_entity::dto::QueryCollabStreamItem;
 
+use futures::Stream;
 use shared_entity::dto::workspace_dto::AFDatabase;
 use shared_entity::dto::workspace_dto::AFDatabaseField;
 use shared_entity::dto::workspace_dto::AFDatabaseRow;
 use shared_entity::dto::workspace_dto::AFDatabaseRowDetail;
+use shared_entity::dto::workspace_dto::AFDatabaseRowDocument;
 use shared_entity::dto::workspace_dto::AFInsertDatabaseField;
+use shared_entity::dto::workspace_dto::AFUpdateDatabaseField;
 use shared_entity::dto::workspace_dto::DatabaseRowUpdatedItem;
 use shared_entity::dto::workspace_dto::FavoriteFolderView;
 use shared_entity::dto::workspace_dto::FolderViewMinimal;
 use shared_entity::dto::workspace_dto::PublishedViewInfo;
 use shared_entity::dto::workspace_dto::RecentFolderView;
 use shared_entity::dto::workspace_dto::TrashFolderView;
+use shared_entity::dto::workspace_dto::{AFDatabaseView, AFDatabaseViewSettings};
 use sqlx::PgPool;
 use yrs::Map;
 
@@ -64,20 +82,32 @@ use shared_entity::dto::workspace_dto::{FolderView, PublishedView};
 use sqlx::types::Uuid;
 use std::collections::HashSet;
 
+use super::database::filter_dto_from_map;
+use super::database::filter_map_from_dto;
+use super::database::find_group_field;
+use super::database::group_field_id_from_settings;
+use super::database::group_setting_for_field;
+use super::database::sort_dto_from_map;
+use super::database::sort_map_from_dto;
+use super::date_cell::get_workspace_default_timezone;
 use super::folder_view::collab_folder_to_folder_view;
+use super::folder_view::collab_folder_to_guest_folder_view;
 use super::folder_view::section_items_to_favorite_folder_view;
 use super::folder_view::section_items_to_recent_folder_view;
 use super::folder_view::section_items_to_trash_folder_view;
 use super::folder_view::to_dto_folder_view_miminal;
 use super::publish_outline::collab_folder_to_published_outline;
 use super::utils::collab_to_bin;
+use super::utils::create_empty_row_document;
 use super::utils::create_row_document;
 use super::utils::field_by_id_name_uniq;
 use super::utils::get_latest_collab;
 use super::utils::get_latest_collab_database_body;
 use super::utils::get_latest_collab_database_row_body;
+use super::utils::get_latest_collab_document;
 use super::utils::get_latest_collab_folder;
 use super::utils::get_row_details_serde;
+use super::utils::type_option_data_from_serde;
 use super::utils::type_option_reader_by_id;
 use super::utils::type_options_serde;
 use super::utils::write_to_database_row;
@@ -257,6 +287,7 @@ pub async fn get_user_workspace_structure(
   server: Data<RealtimeServerAddr>,
   collab_storage: &CollabAccessControlStorage,
   pg_pool: &PgPool,
+  restricted_view_cache: &RestrictedViewCache,
   user: RealtimeUser,
   workspace_id: Uuid,
   depth: u32,
@@ -269,6 +300,10 @@ pub async fn get_user_workspace_structure(
       depth, depth_limit
     )));
   }
+  let restricted_view_ids = restricted_view_cache
+    .get(&workspace_id)
+    .await?
+    .hidden_view_ids(user.uid);
   let folder = get_latest_collab_folder(
     collab_storage,
     GetCollabOrigin::User { uid: user.uid },
@@ -289,6 +324,43 @@ pub async fn get_user_workspace_structure(
     &patched_folder,
     depth,
     &publish_view_ids,
+    restricted_view_ids,
+  )
+}
+
+/// The structure of the workspace for a user who is not a member of it, limited to the views
+/// shared with the user.
+pub async fn get_guest_workspace_structure(
+  collab_storage: &CollabAccessControlStorage,
+  pg_pool: &PgPool,
+  restricted_view_cache: &RestrictedViewCache,
+  guest_access: &GuestAccess,
+  uid: i64,
+  workspace_id: Uuid,
+) -> Result<FolderView, AppError> {
+  let restricted_view_ids = restricted_view_cache
+    .get(&workspace_id)
+    .await?
+    .hidden_view_ids(uid);
+  // The folder itself is not shared with the guest.
+  let folder = get_latest_collab_folder(
+    collab_storage,
+    GetCollabOrigin::Server,
+    &workspace_id.to_string(),
+  )
+  .await?;
+  let publish_view_ids = select_published_view_ids_for_workspace(pg_pool, workspace_id).await?;
+  let publish_view_ids: HashSet<String> = publish_view_ids
+    .into_iter()
+    .map(|id| id.to_string())
+    .collect();
+  let shared_view_ids: HashSet<String> = guest_access.object_ids().cloned().collect();
+  collab_folder_to_guest_folder_view(
+    workspace_id,
+    &folder,
+    &shared_view_ids,
+    &publish_view_ids,
+    restricted_view_ids,
   )
 }
 
@@ -494,6 +566,8 @@ pub async fn insert_database_row(
     _ => None,
   };
 
+  let default_timezone =
+    get_workspace_default_timezone(pg_pool, &Uuid::parse_str(workspace_uuid_str)?).await?;
   let (mut db_collab, db_body) =
     get_latest_collab_database_body(&collab_storage, workspace_uuid_str, database_uuid_str).await?;
   write_to_database_row(
@@ -501,6 +575,7 @@ pub async fn insert_database_row(
     &mut new_db_row_collab.transact_mut(),
     &new_db_row_body,
     cell_value_by_id,
+    &default_timezone,
     creation_time.timestamp(),
   )
   .await?;
@@ -657,17 +732,24 @@ pub async fn upsert_database_row(
 
   // At this point, db row exists,
   // so we modify it, put into storage and broadcast change
+  let default_timezone =
+    get_workspace_default_timezone(pg_pool, &Uuid::parse_str(workspace_uuid_str)?).await?;
   let (_db_collab, db_body) =
     get_latest_collab_database_body(&collab_storage, workspace_uuid_str, database_uuid_str).await?;
   let mut db_row_txn = db_row_collab.transact_mut();
+  let cells_before = database_row_cells(&db_row_body.get_data(), &db_row_txn);
+  let now = Utc::now();
   write_to_database_row(
     &db_body,
     &mut db_row_txn,
     &db_row_body,
     cell_value_by_id,
-    Utc::now().timestamp(),
+    &default_timezone,
+    now.timestamp(),
   )
   .await?;
+  let cells_after = database_row_cells(&db_row_body.get_data(), &db_row_txn);
+  let row_changes = DatabaseRowCellChange::diff(&cells_before, &cells_after, Some(uid), now);
 
   // determine if there are any document changes
   let doc_changes: Option<(String, DocChanges)> = get_database_row_doc_changes(
@@ -701,6 +783,8 @@ pub async fn upsert_database_row(
       "inserting new database row from server",
     )
     .await?;
+  let workspace_uuid = Uuid::parse_str(workspace_uuid_str)?;
+  insert_database_row_activity(db_txn.deref_mut(), &workspace_uuid, row_id, &row_changes).await?;
   broadcast_update_with_timeout(
     collab_storage.clone(),
     row_id.to_string(),
@@ -777,6 +861,85 @@ pub async fn upsert_database_row(
   Ok(())
 }
 
+/// Returns the id of the document of the database row, creating the document first if the row
+/// doesn't have one yet. The new document is registered in the folder as an orphan view, like the
+/// documents the clients create when a row is opened.
+pub async fn get_or_create_database_row_document(
+  collab_storage: Arc<CollabAccessControlStorage>,
+  pg_pool: &PgPool,
+  workspace_uuid_str: &str,
+  uid: i64,
+  row_id: &str,
+) -> Result<AFDatabaseRowDocument, AppError> {
+  // make sure the row exists in the workspace
+  get_latest_collab_database_row_body(&collab_storage, workspace_uuid_str, row_id).await?;
+  let row_uuid = Uuid::parse_str(row_id)?;
+  let document_id = meta_id_from_row_id(&row_uuid, RowMetaKey::DocumentId);
+  match get_latest_collab_document(
+    &collab_storage,
+    GetCollabOrigin::Server,
+    workspace_uuid_str,
+    &document_id,
+  )
+  .await
+  {
+    Ok(_) => {
+      return Ok(AFDatabaseRowDocument {
+        document_id,
+        created: false,
+      })
+    },
+    Err(AppError::RecordNotFound(_)) => {},
+    Err(err) => return Err(err),
+  }
+
+  let CreatedRowDocument {
+    updated_folder,
+    folder_updates,
+    doc_ec_bytes,
+  } = create_empty_row_document(workspace_uuid_str, uid, &document_id, &collab_storage).await?;
+
+  let mut db_txn = pg_pool.begin().await?;
+  collab_storage
+    .upsert_new_collab_with_transaction(
+      workspace_uuid_str,
+      &uid,
+      CollabParams {
+        object_id: document_id.clone(),
+        encoded_collab_v1: doc_ec_bytes.into(),
+        collab_type: CollabType::Document,
+      },
+      &mut db_txn,
+      "inserting new database row document from server",
+    )
+    .await?;
+  collab_storage
+    .upsert_new_collab_with_transaction(
+      workspace_uuid_str,
+      &uid,
+      CollabParams {
+        object_id: workspace_uuid_str.to_string(),
+        encoded_collab_v1: updated_folder.into(),
+        collab_type: CollabType::Folder,
+      },
+      &mut db_txn,
+      "inserting updated folder from server",
+    )
+    .await?;
+  db_txn.commit().await?;
+  broadcast_update_with_timeout(
+    collab_storage,
+    workspace_uuid_str.to_string(),
+    folder_updates,
+  )
+  .await;
+
+  Ok(AFDatabaseRowDocument {
+    document_id,
+    created: true,
+  })
+}
+
 pub async fn get_database_fields(
   collab_storage: &CollabAccessControlStorage,
   workspace_uuid_str: &str,
@@ -815,18 +978,13 @@ pub async fn add_database_field(
 
   let new_id = gen_field_id();
   let mut type_options = TypeOptions::new();
-  let type_option_data = insert_field
-    .type_option_data
-    .unwrap_or(serde_json::json!({}));
-  match serde_json::from_value(type_option_data) {
-    Ok(tod) => type_options.insert(insert_field.field_type.to_string(), tod),
-    Err(err) => {
-      return Err(AppError::InvalidRequest(format!(
-        "Failed to parse type option: {:?}",
-        err
-      )));
-    },
-  };
+  let type_option_data = type_option_data_from_serde(
+    &FieldType::from(insert_field.field_type),
+    insert_field
+      .type_option_data
+      .unwrap_or(serde_json::json!({})),
+  )?;
+  type_options.insert(insert_field.field_type.to_string(), type_option_data);
 
   let new_field = Field {
     id: new_id.clone(),
@@ -847,6 +1005,265 @@ pub async fn add_database_field(
     );
     yrs_txn.encode_update_v1()
   };
+  save_database_collab(
+    uid,
+    collab_storage,
+    pg_pool,
+    workspace_id,
+    database_id,
+    db_collab,
+    db_collab_update,
+  )
+  .await?;
+  Ok(new_id)
+}
+
+/// Updates the name, the type and/or the type option of a database field.
+pub async fn update_database_field(
+  uid: i64,
+  collab_storage: Arc<CollabAccessControlStorage>,
+  pg_pool: &PgPool,
+  workspace_id: &str,
+  database_id: &str,
+  field_id: &str,
+  update_field: AFUpdateDatabaseField,
+) -> Result<AFDatabaseField, AppError> {
+  let (mut db_collab, db_body) =
+    get_latest_collab_database_body(&collab_storage, workspace_id, database_id).await?;
+  let field = db_body
+    .fields
+    .get_field(&db_collab.transact(), field_id)
+    .ok_or_else(|| AppError::RecordNotFound(format!("field {} not found", field_id)))?;
+  if field.is_primary
+    && update_field
+      .field_type
+      .is_some_and(|t| t != field.field_type)
+  {
+    return Err(AppError::InvalidRequest(
+      "the type of the primary field can not be changed".to_string(),
+    ));
+  }
+
+  let field_type = update_field.field_type.unwrap_or(field.field_type);
+  let type_option_data = update_field
+    .type_option_data
+    .map(|data| type_option_data_from_serde(&FieldType::from(field_type), data))
+    .transpose()?;
+
+  let db_collab_update = {
+    let mut yrs_txn = db_collab.transact_mut();
+    db_body
+      .fields
+      .update_field(&mut yrs_txn, field_id, |update| {
+        let mut update = update
+          .set_name_if_not_none(update_field.name)
+          .set_field_type(field_type);
+        if let Some(type_option_data) = type_option_data {
+          update = update.set_type_option(field_type, Some(type_option_data));
+        }
+        update.set_last_modified(timestamp());
+      });
+    yrs_txn.encode_update_v1()
+  };
+
+  let field = db_body
+    .fields
+    .get_field(&db_collab.transact(), field_id)
+    .ok_or_else(|| AppError::RecordNotFound(format!("field {} not found", field_id)))?;
+  save_database_collab(
+    uid,
+    collab_storage,
+    pg_pool,
+    workspace_id,
+    database_id,
+    db_collab,
+    db_collab_update,
+  )
+  .await?;
+
+  let field_type = FieldType::from(field.field_type);
+  Ok(AFDatabaseField {
+    id: field.id,
+    name: field.name,
+    field_type: format!("{:?}", field_type),
+    type_option: type_options_serde(&field.type_options, &field_type),
+    is_primary: field.is_primary,
+  })
+}
+
+/// Deletes a database field and removes it from all the views of the database. The primary
+/// field can not be deleted.
+pub async fn delete_database_field(
+  uid: i64,
+  collab_storage: Arc<CollabAccessControlStorage>,
+  pg_pool: &PgPool,
+  workspace_id: &str,
+  database_id: &str,
+  field_id: &str,
+) -> Result<(), AppError> {
+  let (mut db_collab, db_body) =
+    get_latest_collab_database_body(&collab_storage, workspace_id, database_id).await?;
+  let field = db_body
+    .fields
+    .get_field(&db_collab.transact(), field_id)
+    .ok_or_else(|| AppError::RecordNotFound(format!("field {} not found", field_id)))?;
+  if field.is_primary {
+    return Err(AppError::InvalidRequest(
+      "the primary field can not be deleted".to_string(),
+    ));
+  }
+
+  let db_collab_update = {
+    let mut yrs_txn = db_collab.transact_mut();
+    db_body
+      .views
+      .update_all_views(&mut yrs_txn, |_view_id, update| {
+        update
+          .remove_field_order(field_id)
+          .remove_field_setting(field_id);
+      });
+    db_body.fields.delete_field(&mut yrs_txn, field_id);
+    yrs_txn.encode_update_v1()
+  };
+
+  save_database_collab(
+    uid,
+    collab_storage,
+    pg_pool,
+    workspace_id,
+    database_id,
+    db_collab,
+    db_collab_update,
+  )
+  .await
+}
+
+pub async fn list_database_views(
+  collab_storage: &CollabAccessControlStorage,
+  workspace_id: &str,
+  database_id: &str,
+) -> Result<Vec<AFDatabaseView>, AppError> {
+  let (db_collab, db_body) =
+    get_latest_collab_database_body(collab_storage, workspace_id, database_id).await?;
+  let views = db_body.views.get_all_views(&db_collab.transact());
+  Ok(views.into_iter().map(database_view_to_dto).collect())
+}
+
+/// Replaces the group-by field, the filters and/or the sorts of a database view.
+pub async fn update_database_view_settings(
+  uid: i64,
+  collab_storage: Arc<CollabAccessControlStorage>,
+  pg_pool: &PgPool,
+  workspace_id: &str,
+  database_id: &str,
+  view_id: &str,
+  settings: AFDatabaseViewSettings,
+) -> Result<AFDatabaseView, AppError> {
+  let (mut db_collab, db_body) =
+    get_latest_collab_database_body(&collab_storage, workspace_id, database_id).await?;
+  let (view, fields) = {
+    let txn = db_collab.transact();
+    let view = db_body
+      .views
+      .get_view(&txn, view_id)
+      .ok_or_else(|| AppError::RecordNotFound(format!("database view {} not found", view_id)))?;
+    (view, db_body.fields.get_all_fields(&txn))
+  };
+
+  let group_settings = match settings.group_field_id {
+    Some(group_field_id) => {
+      if view.layout != DatabaseLayout::Board {
+        return Err(AppError::InvalidRequest(
+          "only board views can be grouped".to_string(),
+        ));
+      }
+      let group_field = find_group_field(&fields, &group_field_id)?;
+      Some(vec![group_setting_for_field(group_field)?])
+    },
+    None => None,
+  };
+  let filters = settings
+    .filters
+    .map(|filters| {
+      filters
+        .into_iter()
+        .map(|filter| filter_map_from_dto(&fields, filter))
+        .collect::<Result<Vec<_>, _>>()
+    })
+    .transpose()?;
+  let sorts = settings
+    .sorts
+    .map(|sorts| {
+      sorts
+        .into_iter()
+        .map(|sort| sort_map_from_dto(&fields, sort))
+        .collect::<Result<Vec<_>, _>>()
+    })
+    .transpose()?;
+
+  let db_collab_update = {
+    let mut yrs_txn = db_collab.transact_mut();
+    db_body
+      .views
+      .update_database_view(&mut yrs_txn, view_id, |mut update| {
+        if let Some(group_settings) = group_settings {
+          update = update.set_groups(group_settings);
+        }
+        if let Some(filters) = filters {
+          update = update.set_filters(filters);
+        }
+        if let Some(sorts) = sorts {
+          update = update.set_sorts(sorts);
+        }
+        update.set_modified_at(timestamp());
+      });
+    yrs_txn.encode_update_v1()
+  };
+
+  let view = db_body
+    .views
+    .get_view(&db_collab.transact(), view_id)
+    .ok_or_else(|| AppError::RecordNotFound(format!("database view {} not found", view_id)))?;
+  save_database_collab(
+    uid,
+    collab_storage,
+    pg_pool,
+    workspace_id,
+    database_id,
+    db_collab,
+    db_collab_update,
+  )
+  .await?;
+  Ok(database_view_to_dto(view))
+}
+
+fn database_view_to_dto(view: DatabaseView) -> AFDatabaseView {
+  AFDatabaseView {
+    group_field_id: group_field_id_from_settings(&view.group_settings),
+    filters: view
+      .filters
+      .iter()
+      .filter_map(filter_dto_from_map)
+      .collect(),
+    sorts: view.sorts.iter().filter_map(sort_dto_from_map).collect(),
+    id: view.id,
+    name: view.name,
+    layout: format!("{:?}", view.layout),
+    is_inline: view.is_inline,
+  }
+}
+
+/// Persists the database collab after a server side change and broadcasts the change to the
+/// clients that have the database open.
+async fn save_database_collab(
+  uid: i64,
+  collab_storage: Arc<CollabAccessControlStorage>,
+  pg_pool: &PgPool,
+  workspace_id: &str,
+  database_id: &str,
+  db_collab: Collab,
+  db_collab_update: Vec<u8>,
+) -> Result<(), AppError> {
   let updated_db_collab = collab_to_bin(db_collab, CollabType::Database).await?;
 
   let mut pg_txn = pg_pool.begin().await?;
@@ -866,8 +1283,7 @@ pub async fn add_database_field(
 
   pg_txn.commit().await?;
   broadcast_update_with_timeout(collab_storage, database_id.to_string(), db_collab_update).await;
-
-  Ok(new_id)
+  Ok(())
 }
 
 pub async fn list_database_row_ids_updated(
@@ -897,6 +1313,7 @@ pub async fn list_database_row_details(
   row_ids: &[&str],
   unsupported_field_types: &[FieldType],
   with_doc: bool,
+  expand_relations: bool,
 ) -> Result<Vec<AFDatabaseRowDetail>, AppError> {
   let (database_collab, db_body) =
     get_latest_collab_database_body(collab_storage, &workspace_uuid_str, &database_uuid_str)
@@ -914,6 +1331,16 @@ pub async fn list_database_row_details(
 
   let type_option_reader_by_id = type_option_reader_by_id(&all_fields);
   let field_by_id = field_by_id_name_uniq(all_fields);
+  let relation_fields: Vec<&Field> = if expand_relations {
+    field_by_id
+      .values()
+      .filter(|field| FieldType::from(field.field_type) == FieldType::Relation)
+      .collect()
+  } else {
+    vec![]
+  };
+  // (row id, relation field name, related row ids)
+  let mut relation_cells: Vec<(String, String, Vec<String>)> = vec![];
   let query_collabs: Vec<QueryCollab> = row_ids
     .iter()
     .map(|id| QueryCollab {
@@ -927,28 +1354,16 @@ pub async fn list_database_row_details(
     .into_iter()
     .flat_map(|(id, result)| match result {
       QueryCollabResult::Success { encode_collab_v1 } => {
-        let ec = match EncodedCollab::decode_from_bytes(&encode_collab_v1) {
-          Ok(ec) => ec,
-          Err(err) => {
-            tracing::error!("Failed to decode encoded collab: {:?}", err);
-            return None;
-          },
-        };
-        let collab =
-          match Collab::new_with_source(CollabOrigin::Server, &id, ec.into(), vec![], false) {
-            Ok(collab) => collab,
-            Err(err) => {
-              tracing::error!("Failed to create collab: {:?}", err);
-              return None;
-            },
-          };
-        let row_detail = match RowDetail::from_collab(&collab) {
-          Some(row_detail) => row_detail,
-          None => {
-            tracing::error!("Failed to get row detail from collab: {:?}", collab);
-            return None;
-          },
-        };
+        let row_detail = decode_row_detail(&id, &encode_collab_v1)?;
+        for field in relation_fields.iter() {
+          let related_row_ids = row_detail
+            .row
+            .cells
+            .get(&field.id)
+            .map(relation_row_ids_from_cell)
+            .unwrap_or_default();
+          relation_cells.push((id.clone(), field.name.clone(), related_row_ids));
+        }
 
         let has_doc = !row_detail.meta.is_document_empty;
         let cells = get_row_details_serde(row_detail, &field_by_id, &type_option_reader_by_id);
@@ -966,6 +1381,24 @@ pub async fn list_database_row_details(
     })
     .collect::<Vec<AFDatabaseRowDetail>>();
 
+  if !relation_fields.is_empty() {
+    let related_database_ids = relation_fields
+      .iter()
+      .filter_map(|field| {
+        related_database_id(field).map(|database_id| (field.name.clone(), database_id))
+      })
+      .collect::<HashMap<_, _>>();
+    expand_relation_cells(
+      collab_storage,
+      uid,
+      &workspace_uuid_str,
+      &related_database_ids,
+      relation_cells,
+      &mut db_row_details,
+    )
+    .await?;
+  }
+
   // Fill in the document content if requested and exists
   if with_doc {
     let doc_id_by_row_id = db_row_details
@@ -1000,6 +1433,166 @@ pub async fn list_database_row_details(
   Ok(db_row_details)
 }
 
+/// Maximum number of related rows resolved for a single request. Relation cells that point to
+/// more rows only contain the ids of the rows that were not resolved.
+const MAX_EXPANDED_RELATED_ROWS: usize = 1000;
+
+/// Replaces the related row ids of the relation cells with `{ "id": .., "name": .. }` objects,
+/// where `name` is the primary field value of the related row. Related rows are fetched with one
+/// batch per related database, and are not expanded any further: relations of related rows are
+/// never resolved, which bounds the work done for a request to a single level.
+async fn expand_relation_cells(
+  collab_storage: &CollabAccessControlStorage,
+  uid: i64,
+  workspace_id: &str,
+  related_database_id_by_field_name: &HashMap<String, String>,
+  relation_cells: Vec<(String, String, Vec<String>)>,
+  db_row_details: &mut [AFDatabaseRowDetail],
+) -> Result<(), AppError> {
+  // related database id -> related row ids
+  let mut row_ids_by_database: HashMap<&str, HashSet<&str>> = HashMap::new();
+  let mut num_rows = 0;
+  for (_, field_name, related_row_ids) in relation_cells.iter() {
+    let Some(database_id) = related_database_id_by_field_name.get(field_name) else {
+      continue;
+    };
+    let row_ids = row_ids_by_database.entry(database_id).or_default();
+    for row_id in related_row_ids {
+      if num_rows >= MAX_EXPANDED_RELATED_ROWS {
+        break;
+      }
+      if row_ids.insert(row_id) {
+        num_rows += 1;
+      }
+    }
+  }
+
+  // related row id -> primary field value
+  let mut primary_value_by_row_id: HashMap<String, serde_json::Value> = HashMap::new();
+  for (database_id, row_ids) in row_ids_by_database {
+    let primary_field =
+      match get_latest_collab_database_body(collab_storage, workspace_id, database_id).await {
+        Ok((db_collab, db_body)) => db_body
+          .fields
+          .get_all_fields(&db_collab.transact())
+          .into_iter()
+          .find(|field| field.is_primary),
+        Err(err) => {
+          tracing::warn!("Failed to get related database {}: {:?}", database_id, err);
+          None
+        },
+      };
+    let Some(primary_field) = primary_field else {
+      continue;
+    };
+    let reader_by_id = type_option_reader_by_id(std::slice::from_ref(&primary_field));
+    let Some(primary_reader) = reader_by_id.get(&primary_field.id) else {
+      continue;
+    };
+
+    let query_collabs = row_ids
+      .into_iter()
+      .map(|row_id| QueryCollab {
+        object_id: row_id.to_string(),
+        collab_type: CollabType::DatabaseRow,
+      })
+      .collect();
+    let results = collab_storage
+      .batch_get_collab(&uid, workspace_id, query_collabs, true)
+      .await;
+    for (row_id, result) in results {
+      if let QueryCollabResult::Success { encode_collab_v1 } = result {
+        if let Some(row_detail) = decode_row_detail(&row_id, &encode_collab_v1) {
+          let value = row_detail
+            .row
+            .cells
+            .get(&primary_field.id)
+            .map(|cell| primary_reader.json_cell(cell))
+            .unwrap_or(serde_json::Value::Null);
+          primary_value_by_row_id.insert(row_id, value);
+        }
+      }
+    }
+  }
+
+  let mut cells_by_row_id: HashMap<String, Vec<(String, Vec<String>)>> = HashMap::new();
+  for (row_id, field_name, related_row_ids) in relation_cells {
+    cells_by_row_id
+      .entry(row_id)
+      .or_default()
+      .push((field_name, related_row_ids));
+  }
+  for row_detail in db_row_details.iter_mut() {
+    let Some(cells) = cells_by_row_id.remove(&row_detail.id) else {
+      continue;
+    };
+    for (field_name, related_row_ids) in cells {
+      let related_rows = related_row_ids
+        .into_iter()
+        .map(|row_id| {
+          let name = primary_value_by_row_id
+            .get(&row_id)
+            .cloned()
+            .unwrap_or(serde_json::Value::Null);
+          serde_json::json!({ "id": row_id, "name": name })
+        })
+        .collect();
+      row_detail
+        .cells
+        .insert(field_name, serde_json::Value::Array(related_rows));
+    }
+  }
+  Ok(())
+}
+
+fn related_database_id(relation_field: &Field) -> Option<String> {
+  let type_option = relation_field
+    .type_options
+    .get(&FieldType::Relation.type_id())?;
+  match type_option.get("database_id")? {
+    yrs::Any::String(database_id) => Some(database_id.to_string()),
+    _ => None,
+  }
+}
+
+fn relation_row_ids_from_cell(cell: &Cell) -> Vec<String> {
+  match cell.get(CELL_DATA) {
+    Some(yrs::Any::Array(row_ids)) => row_ids
+      .iter()
+      .filter_map(|row_id| match row_id {
+        yrs::Any::String(row_id) => Some(row_id.to_string()),
+        _ => None,
+      })
+      .collect(),
+    _ => vec![],
+  }
+}
+
+fn decode_row_detail(row_id: &str, encode_collab_v1: &[u8]) -> Option<RowDetail> {
+  let ec = match EncodedCollab::decode_from_bytes(encode_collab_v1) {
+    Ok(ec) => ec,
+    Err(err) => {
+      tracing::error!("Failed to decode encoded collab: {:?}", err);
+      return None;
+    },
+  };
+  let collab = match Collab::new_with_source(CollabOrigin::Server, row_id, ec.into(), vec![], false)
+  {
+    Ok(collab) => collab,
+    Err(err) => {
+      tracing::error!("Failed to create collab: {:?}", err);
+      return None;
+    },
+  };
+  match RowDetail::from_collab(&collab) {
+    Some(row_detail) => Some(row_detail),
+    None => {
+      tracing::error!("Failed to get row detail from collab: {:?}", collab);
+      None
+    },
+  }
+}
+
 fn fill_in_db_row_doc(
   row_detail: &mut AFDatabaseRowDetail,
   doc_id_by_row_id: &HashMap<String, String>,
@@ -1041,3 +1634,84 @@ fn fill_in_db_row_doc(
   row_detail.doc = Some(plain_text);
   Ok(())
 }
+
+/// Number of collabs fetched at once by [stream_batch_get_collab]. Only the collabs of the current
+/// chunk are held in memory.
+const BATCH_GET_COLLAB_STREAM_CHUNK_SIZE: usize = 20;
+
+/// Fetches the given collabs chunk by chunk and yields one newline delimited JSON
+/// [QueryCollabStreamItem] per object, so the response doesn't have to be buffered.
+pub fn stream_batch_get_collab(
+  collab_storage: Arc<CollabAccessControlStorage>,
+  uid: i64,
+  workspace_id: String,
+  queries: Vec<QueryCollab>,
+) -> impl Stream<Item = Result<Bytes, AppError>> {
+  stream! {
+    for chunk in queries.chunks(BATCH_GET_COLLAB_STREAM_CHUNK_SIZE) {
+      let results = collab_storage
+        .batch_get_collab(&uid, &workspace_id, chunk.to_vec(), false)
+        .await;
+      for (object_id, result) in results {
+        let item = QueryCollabStreamItem { object_id, result };
+        match serde_json::to_vec(&item) {
+          Ok(mut line) => {
+            line.push(b'\n');
+            yield Ok::<Bytes, AppError>(Bytes::from(line));
+          },
+          Err(err) => {
+            yield Err(AppError::from(err));
+            return;
+          },
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use collab_database::fields::TypeOptionData;
+  use yrs::Any;
+
+  #[test]
+  fn related_row_ids_are_read_from_relation_cells() {
+    let mut cell = Cell::new();
+    cell.insert(
+      CELL_DATA.to_string(),
+      Any::Array(
+        vec![
+          Any::String("row_1".into()),
+          Any::BigInt(2),
+          Any::String("row_3".into()),
+        ]
+        .into(),
+      ),
+    );
+    assert_eq!(relation_row_ids_from_cell(&cell), vec!["row_1", "row_3"]);
+
+    let mut cell = Cell::new();
+    cell.insert(CELL_DATA.to_string(), Any::String("row_1".into()));
+    assert!(relation_row_ids_from_cell(&cell).is_empty());
+    assert!(relation_row_ids_from_cell(&Cell::new()).is_empty());
+  }
+
+  #[test]
+  fn related_database_is_read_from_relation_type_option() {
+    let mut field = Field::new(
+      "relation".to_string(),
+      "Relation".to_string(),
+      FieldType::Relation.into(),
+      false,
+    );
+    assert!(related_database_id(&field).is_none());
+
+    let mut type_option = TypeOptionData::new();
+    type_option.insert("database_id".to_string(), Any::String("db_1".into()));
+    field
+      .type_options
+      .insert(FieldType::Relation.type_id(), type_option);
+    assert_eq!(related_database_id(&field).as_deref(), Some("db_1"));
+  }
+}
@@ -0,0 +1,551 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+
+use app_error::AppError;
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use collab_document::blocks::{Block, DocumentData};
+use collab_document::document::Document;
+use collab_entity::CollabType;
+use database::collab::GetCollabOrigin;
+use serde_json::Value;
+use shared_entity::dto::workspace_dto::RenderFormat;
+
+use super::utils::get_latest_collab;
+
+/// Blocks nested deeper than this are not rendered, the recursion would otherwise be bounded
+/// only by the document sent by the user.
+const MAX_RENDER_DEPTH: usize = 64;
+
+/// Renders the latest state of a document collab in the requested format.
+pub async fn render_document(
+  collab_storage: &CollabAccessControlStorage,
+  uid: i64,
+  workspace_id: &str,
+  object_id: &str,
+  format: RenderFormat,
+) -> Result<String, AppError> {
+  let collab = get_latest_collab(
+    collab_storage,
+    GetCollabOrigin::User { uid },
+    workspace_id,
+    object_id,
+    CollabType::Document,
+  )
+  .await?;
+  let document = Document::open(collab)
+    .map_err(|err| AppError::InvalidRequest(format!("{} is not a document: {}", object_id, err)))?;
+  let data = document
+    .get_document_data()
+    .map_err(|err| AppError::Internal(err.into()))?;
+  Ok(render_document_data(&data, format))
+}
+
+pub fn render_document_data(data: &DocumentData, format: RenderFormat) -> String {
+  match format {
+    RenderFormat::Markdown => document_to_markdown(data),
+    RenderFormat::Html => document_to_html(data),
+  }
+}
+
+/// A text delta, as stored in the text map of the document.
+struct TextOp {
+  insert: String,
+  attributes: serde_json::Map<String, Value>,
+}
+
+impl TextOp {
+  fn attr_bool(&self, key: &str) -> bool {
+    self.attributes.get(key).and_then(Value::as_bool) == Some(true)
+  }
+
+  fn attr_str(&self, key: &str) -> Option<&str> {
+    self.attributes.get(key).and_then(Value::as_str)
+  }
+}
+
+struct DocumentReader<'a> {
+  data: &'a DocumentData,
+  /// The blocks already rendered. A block listed as the child of several blocks, or of one of its
+  /// descendants, is only rendered once.
+  visited: RefCell<HashSet<&'a str>>,
+}
+
+impl<'a> DocumentReader<'a> {
+  fn new(data: &'a DocumentData) -> Self {
+    let visited = RefCell::new(HashSet::from([data.page_id.as_str()]));
+    Self { data, visited }
+  }
+
+  /// The children of the block that were not rendered yet, none below [MAX_RENDER_DEPTH].
+  fn children(&self, block: &Block, nesting: usize) -> Vec<&'a Block> {
+    if nesting >= MAX_RENDER_DEPTH {
+      return vec![];
+    }
+    let Some(ids) = self.data.meta.children_map.get(&block.children) else {
+      return vec![];
+    };
+    let mut visited = self.visited.borrow_mut();
+    ids
+      .iter()
+      .filter_map(|id| self.data.blocks.get_key_value(id))
+      .filter(|(id, _)| visited.insert(id.as_str()))
+      .map(|(_, block)| block)
+      .collect()
+  }
+
+  fn root(&self) -> Option<&'a Block> {
+    self.data.blocks.get(&self.data.page_id)
+  }
+
+  fn text(&self, block: &Block) -> Vec<TextOp> {
+    let delta = block
+      .external_id
+      .as_ref()
+      .and_then(|text_id| self.data.meta.text_map.as_ref()?.get(text_id))
+      .and_then(|delta| serde_json::from_str::<Vec<Value>>(delta).ok())
+      .unwrap_or_default();
+    delta
+      .into_iter()
+      .filter_map(|op| {
+        let insert = op.get("insert")?.as_str()?.to_string();
+        let attributes = op
+          .get("attributes")
+          .and_then(Value::as_object)
+          .cloned()
+          .unwrap_or_default();
+        Some(TextOp { insert, attributes })
+      })
+      .collect()
+  }
+}
+
+fn data_str<'b>(block: &'b Block, key: &str) -> Option<&'b str> {
+  block.data.get(key).and_then(Value::as_str)
+}
+
+/// Only links that can't run scripts are kept in the output.
+fn is_safe_url(url: &str) -> bool {
+  let url = url.trim().to_ascii_lowercase();
+  url.starts_with("http://")
+    || url.starts_with("https://")
+    || url.starts_with("mailto:")
+    || url.starts_with('/')
+    || url.starts_with('#')
+}
+
+pub fn document_to_markdown(data: &DocumentData) -> String {
+  let reader = DocumentReader::new(data);
+  let mut out = String::new();
+  if let Some(root) = reader.root() {
+    let mut number = 0;
+    for child in reader.children(root, 0) {
+      markdown_block(&reader, child, 0, 1, &mut number, &mut out);
+    }
+  }
+  out.trim_end().to_string()
+}
+
+/// `depth` is the indentation of the block in the lists, `nesting` its depth in the document.
+fn markdown_block(
+  reader: &DocumentReader,
+  block: &Block,
+  depth: usize,
+  nesting: usize,
+  number: &mut usize,
+  out: &mut String,
+) {
+  let indent = "  ".repeat(depth);
+  let text = markdown_text(&reader.text(block));
+  if block.ty == "numbered_list" {
+    *number += 1;
+  } else {
+    *number = 0;
+  }
+
+  match block.ty.as_str() {
+    "heading" => {
+      let level = block
+        .data
+        .get("level")
+        .and_then(Value::as_u64)
+        .unwrap_or(1)
+        .clamp(1, 6) as usize;
+      out.push_str(&format!("{}{} {}\n\n", indent, "#".repeat(level), text));
+    },
+    "bulleted_list" => out.push_str(&format!("{}- {}\n", indent, text)),
+    "numbered_list" => out.push_str(&format!("{}{}. {}\n", indent, number, text)),
+    "todo_list" => {
+      let checked = block.data.get("checked").and_then(Value::as_bool) == Some(true);
+      let mark = if checked { "x" } else { " " };
+      out.push_str(&format!("{}- [{}] {}\n", indent, mark, text));
+    },
+    "toggle_list" => out.push_str(&format!("{}- {}\n", indent, text)),
+    "quote" => out.push_str(&format!("{}> {}\n\n", indent, text)),
+    "callout" => {
+      let icon = data_str(block, "icon").unwrap_or_default();
+      out.push_str(&format!("{}> {} {}\n\n", indent, icon, text));
+    },
+    "code" => {
+      let language = data_str(block, "language").unwrap_or_default();
+      let code: String = reader.text(block).into_iter().map(|op| op.insert).collect();
+      out.push_str(&format!(
+        "{}```{}\n{}\n{}```\n\n",
+        indent, language, code, indent
+      ));
+    },
+    "math_equation" => {
+      let formula = data_str(block, "formula").unwrap_or_default();
+      out.push_str(&format!("{}$$\n{}\n$$\n\n", indent, formula));
+    },
+    "divider" => out.push_str(&format!("{}---\n\n", indent)),
+    "image" => {
+      if let Some(url) = data_str(block, "url").filter(|url| is_safe_url(url)) {
+        out.push_str(&format!("{}![]({})\n\n", indent, url));
+      }
+    },
+    _ => {
+      if !text.is_empty() {
+        out.push_str(&format!("{}{}\n\n", indent, text));
+      }
+    },
+  }
+
+  let is_list = matches!(
+    block.ty.as_str(),
+    "bulleted_list" | "numbered_list" | "todo_list" | "toggle_list"
+  );
+  let child_depth = if is_list { depth + 1 } else { depth };
+  let children = reader.children(block, nesting);
+  let mut child_number = 0;
+  for child in &children {
+    markdown_block(
+      reader,
+      child,
+      child_depth,
+      nesting + 1,
+      &mut child_number,
+      out,
+    );
+  }
+  if is_list && !children.is_empty() {
+    out.push('\n');
+  }
+}
+
+fn markdown_text(ops: &[TextOp]) -> String {
+  let mut out = String::new();
+  for op in ops {
+    if let Some(formula) = op.attr_str("formula") {
+      out.push_str(&format!("${}$", formula));
+      continue;
+    }
+    let mut text = if op.attr_bool("code") {
+      format!("`{}`", op.insert)
+    } else {
+      escape_markdown_html(&op.insert)
+    };
+    if op.attr_bool("bold") {
+      text = format!("**{}**", text);
+    }
+    if op.attr_bool("italic") {
+      text = format!("_{}_", text);
+    }
+    if op.attr_bool("strikethrough") {
+      text = format!("~~{}~~", text);
+    }
+    if let Some(href) = op.attr_str("href").filter(|href| is_safe_url(href)) {
+      text = format!("[{}]({})", text, href);
+    }
+    out.push_str(&text);
+  }
+  out
+}
+
+pub fn document_to_html(data: &DocumentData) -> String {
+  let reader = DocumentReader::new(data);
+  let mut out = String::new();
+  if let Some(root) = reader.root() {
+    html_blocks(&reader, &reader.children(root, 0), 1, &mut out);
+  }
+  out
+}
+
+/// Renders sibling blocks, wrapping consecutive list items into a single list element.
+fn html_blocks(reader: &DocumentReader, blocks: &[&Block], nesting: usize, out: &mut String) {
+  let mut open_list: Option<&'static str> = None;
+  for block in blocks {
+    let list_tag = match block.ty.as_str() {
+      "bulleted_list" | "todo_list" | "toggle_list" => Some("ul"),
+      "numbered_list" => Some("ol"),
+      _ => None,
+    };
+    if open_list != list_tag {
+      if let Some(tag) = open_list {
+        out.push_str(&format!("</{}>", tag));
+      }
+      if let Some(tag) = list_tag {
+        out.push_str(&format!("<{}>", tag));
+      }
+      open_list = list_tag;
+    }
+    html_block(reader, block, nesting, out);
+  }
+  if let Some(tag) = open_list {
+    out.push_str(&format!("</{}>", tag));
+  }
+}
+
+fn html_block(reader: &DocumentReader, block: &Block, nesting: usize, out: &mut String) {
+  let text = html_text(&reader.text(block));
+  let children = reader.children(block, nesting);
+  let mut children_html = String::new();
+  html_blocks(reader, &children, nesting + 1, &mut children_html);
+
+  match block.ty.as_str() {
+    "heading" => {
+      let level = block
+        .data
+        .get("level")
+        .and_then(Value::as_u64)
+        .unwrap_or(1)
+        .clamp(1, 6);
+      out.push_str(&format!("<h{0}>{1}</h{0}>{2}", level, text, children_html));
+    },
+    "bulleted_list" | "numbered_list" | "toggle_list" => {
+      out.push_str(&format!("<li>{}{}</li>", text, children_html));
+    },
+    "todo_list" => {
+      let checked = block.data.get("checked").and_then(Value::as_bool) == Some(true);
+      let checkbox = if checked {
+        "<input type=\"checkbox\" checked disabled>"
+      } else {
+        "<input type=\"checkbox\" disabled>"
+      };
+      out.push_str(&format!("<li>{} {}{}</li>", checkbox, text, children_html));
+    },
+    "quote" => out.push_str(&format!(
+      "<blockquote>{}{}</blockquote>",
+      text, children_html
+    )),
+    "callout" => {
+      let icon = escape_html(data_str(block, "icon").unwrap_or_default());
+      out.push_str(&format!(
+        "<aside>{} {}{}</aside>",
+        icon, text, children_html
+      ));
+    },
+    "code" => {
+      let code: String = reader.text(block).into_iter().map(|op| op.insert).collect();
+      let language = escape_html(data_str(block, "language").unwrap_or_default());
+      out.push_str(&format!(
+        "<pre><code class=\"language-{}\">{}</code></pre>",
+        language,
+        escape_html(&code)
+      ));
+    },
+    "math_equation" => {
+      let formula = escape_html(data_str(block, "formula").unwrap_or_default());
+      out.push_str(&format!("<pre class=\"math\">{}</pre>", formula));
+    },
+    "divider" => out.push_str("<hr>"),
+    "image" => {
+      if let Some(url) = data_str(block, "url").filter(|url| is_safe_url(url)) {
+        out.push_str(&format!("<img src=\"{}\">", escape_html(url)));
+      }
+    },
+    _ => {
+      if !text.is_empty() {
+        out.push_str(&format!("<p>{}</p>", text));
+      }
+      out.push_str(&children_html);
+    },
+  }
+}
+
+fn html_text(ops: &[TextOp]) -> String {
+  let mut out = String::new();
+  for op in ops {
+    if let Some(formula) = op.attr_str("formula") {
+      out.push_str(&format!(
+        "<span class=\"math\">{}</span>",
+        escape_html(formula)
+      ));
+      continue;
+    }
+    let mut text = escape_html(&op.insert);
+    if op.attr_bool("code") {
+      text = format!("<code>{}</code>", text);
+    }
+    if op.attr_bool("bold") {
+      text = format!("<strong>{}</strong>", text);
+    }
+    if op.attr_bool("italic") {
+      text = format!("<em>{}</em>", text);
+    }
+    if op.attr_bool("strikethrough") {
+      text = format!("<s>{}</s>", text);
+    }
+    if op.attr_bool("underline") {
+      text = format!("<u>{}</u>", text);
+    }
+    if let Some(href) = op.attr_str("href").filter(|href| is_safe_url(href)) {
+      text = format!(
+        "<a href=\"{}\" rel=\"noopener noreferrer nofollow\">{}</a>",
+        escape_html(href),
+        text
+      );
+    }
+    out.push_str(&text);
+  }
+  out
+}
+
+fn escape_html(text: &str) -> String {
+  let mut out = String::with_capacity(text.len());
+  for c in text.chars() {
+    match c {
+      '&' => out.push_str("&amp;"),
+      '<' => out.push_str("&lt;"),
+      '>' => out.push_str("&gt;"),
+      '"' => out.push_str("&quot;"),
+      '\'' => out.push_str("&#39;"),
+      '\n' => out.push_str("<br>"),
+      c => out.push(c),
+    }
+  }
+  out
+}
+
+/// Markdown renderers pass the html of the text through, the text is written as is otherwise.
+fn escape_markdown_html(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use collab_document::blocks::DocumentMeta;
+  use serde_json::json;
+
+  use super::*;
+
+  fn block(id: &str, ty: &str, data: Value) -> Block {
+    Block {
+      id: id.to_string(),
+      ty: ty.to_string(),
+      parent: "page".to_string(),
+      children: format!("{}_children", id),
+      external_id: Some(format!("{}_text", id)),
+      external_type: Some("text".to_string()),
+      data: serde_json::from_value(data).unwrap(),
+    }
+  }
+
+  fn document(blocks: Vec<(Block, Value)>) -> DocumentData {
+    let mut text_map = HashMap::new();
+    let mut children_map = HashMap::new();
+    children_map.insert(
+      "page_children".to_string(),
+      blocks.iter().map(|(b, _)| b.id.clone()).collect(),
+    );
+    let mut all_blocks = HashMap::new();
+    all_blocks.insert("page".to_string(), block("page", "page", json!({})));
+    for (block, delta) in blocks {
+      text_map.insert(block.external_id.clone().unwrap(), delta.to_string());
+      all_blocks.insert(block.id.clone(), block);
+    }
+    DocumentData {
+      page_id: "page".to_string(),
+      blocks: all_blocks,
+      meta: DocumentMeta {
+        children_map,
+        text_map: Some(text_map),
+      },
+    }
+  }
+
+  #[test]
+  fn render_markdown_and_html() {
+    let data = document(vec![
+      (
+        block("h", "heading", json!({ "level": 2 })),
+        json!([{ "insert": "Title" }]),
+      ),
+      (
+        block("p", "paragraph", json!({})),
+        json!([
+          { "insert": "bold", "attributes": { "bold": true } },
+          { "insert": " <script>" },
+          { "insert": "link", "attributes": { "href": "javascript:alert(1)" } },
+        ]),
+      ),
+      (
+        block("a", "bulleted_list", json!({})),
+        json!([{ "insert": "one" }]),
+      ),
+      (
+        block("b", "bulleted_list", json!({})),
+        json!([{ "insert": "two" }]),
+      ),
+      (
+        block("t", "todo_list", json!({ "checked": true })),
+        json!([{ "insert": "done" }]),
+      ),
+    ]);
+
+    let markdown = document_to_markdown(&data);
+    assert_eq!(
+      markdown,
+      "## Title\n\n**bold** &lt;script&gt;link\n\n- one\n- two\n- [x] done"
+    );
+
+    let html = document_to_html(&data);
+    assert_eq!(
+      html,
+      "<h2>Title</h2><p><strong>bold</strong> &lt;script&gt;link</p>\
+       <ul><li>one</li><li>two</li><li><input type=\"checkbox\" checked disabled> done</li></ul>"
+    );
+  }
+
+  #[test]
+  fn cyclic_and_deep_documents_are_bounded() {
+    let mut data = document(vec![(
+      block("a", "paragraph", json!({})),
+      json!([{ "insert": "loop" }]),
+    )]);
+    // the block is its own child, and the page a child of the block
+    data.meta.children_map.insert(
+      "a_children".to_string(),
+      vec!["a".to_string(), "page".to_string()],
+    );
+    assert_eq!(document_to_markdown(&data), "loop");
+    assert_eq!(document_to_html(&data), "<p>loop</p>");
+
+    let mut data = document(vec![(
+      block("b0", "paragraph", json!({})),
+      json!([{ "insert": "b" }]),
+    )]);
+    for i in 1..10_000 {
+      let id = format!("b{}", i);
+      data
+        .meta
+        .children_map
+        .insert(format!("b{}_children", i - 1), vec![id.clone()]);
+      let mut child = block(&id, "paragraph", json!({}));
+      child.external_id = Some("b0_text".to_string());
+      data.blocks.insert(id, child);
+    }
+    assert_eq!(
+      document_to_markdown(&data).matches('b').count(),
+      MAX_RENDER_DEPTH
+    );
+    assert_eq!(
+      document_to_html(&data).matches("<p>").count(),
+      MAX_RENDER_DEPTH
+    );
+  }
+}
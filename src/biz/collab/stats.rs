@@ -0,0 +1,25 @@
+use app_error::AppError;
+use chrono::{Days, Utc};
+use database::collab::select_collab_edit_stats;
+use database_entity::dto::AFCollabEditStats;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const DEFAULT_STATS_DAYS: u32 = 30;
+const MAX_STATS_DAYS: u32 = 365;
+
+/// Returns the edit statistics of the collab with the daily edit counts of the last `days` days,
+/// or `None` if it has never been edited through the collaboration server.
+pub async fn get_collab_edit_stats(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  object_id: &Uuid,
+  days: Option<u32>,
+) -> Result<Option<AFCollabEditStats>, AppError> {
+  let days = days.unwrap_or(DEFAULT_STATS_DAYS).clamp(1, MAX_STATS_DAYS);
+  let today = Utc::now().date_naive();
+  let since = today
+    .checked_sub_days(Days::new(u64::from(days - 1)))
+    .unwrap_or(today);
+  select_collab_edit_stats(pg_pool, workspace_id, &object_id.to_string(), since).await
+}
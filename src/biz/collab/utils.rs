@@ -4,6 +4,7 @@ use collab::core::collab::DataSource;
 use collab::preclude::Collab;
 use collab_database::database::DatabaseBody;
 use collab_database::entity::FieldType;
+use collab_database::fields::select_type_option::SelectTypeOption;
 use collab_database::fields::type_option_cell_reader;
 use collab_database::fields::type_option_cell_writer;
 use collab_database::fields::Field;
@@ -21,6 +22,7 @@ use collab_database::template::timestamp_parse::TimestampCellData;
 use collab_database::workspace_database::NoPersistenceDatabaseCollabService;
 use collab_database::workspace_database::WorkspaceDatabaseBody;
 use collab_document::document::Document;
+use collab_document::document_data::default_document_data;
 use collab_document::importer::md_importer::MDImporter;
 use collab_entity::CollabType;
 use collab_entity::EncodedCollab;
@@ -41,6 +43,8 @@ use std::sync::Arc;
 use uuid::Uuid;
 use yrs::Map;
 
+use super::date_cell::{field_timezone, normalize_date_cell_value};
+
 pub const DEFAULT_SPACE_ICON: &str = "interface_essential/home-3";
 pub const DEFAULT_SPACE_ICON_COLOR: &str = "0xFFA34AFD";
 
@@ -194,6 +198,53 @@ pub fn type_options_serde(
   result
 }
 
+/// Inverse of [type_options_serde]: converts the type option given by an API client into the
+/// representation stored in the database collab. Type options that are stored as stringified
+/// JSON are validated against their schema before being stringified.
+pub fn type_option_data_from_serde(
+  field_type: &FieldType,
+  type_option: serde_json::Value,
+) -> Result<TypeOptionData, AppError> {
+  let type_option = match type_option {
+    serde_json::Value::Null => return Ok(TypeOptionData::new()),
+    serde_json::Value::Object(map) => map,
+    other => {
+      return Err(AppError::InvalidRequest(format!(
+        "type option of {:?} field must be an object, got: {}",
+        field_type, other
+      )))
+    },
+  };
+
+  let mut result = TypeOptionData::with_capacity(type_option.len());
+  for (key, value) in type_option {
+    let any = match field_type {
+      FieldType::SingleSelect | FieldType::MultiSelect | FieldType::Media => {
+        let value = match value {
+          // already stringified by the client
+          serde_json::Value::String(s) => serde_json::from_str(&s).map_err(|err| {
+            AppError::InvalidRequest(format!("invalid type option `{}`: {}", key, err))
+          })?,
+          value => value,
+        };
+        if key == "content"
+          && matches!(field_type, FieldType::SingleSelect | FieldType::MultiSelect)
+        {
+          serde_json::from_value::<SelectTypeOption>(value.clone()).map_err(|err| {
+            AppError::InvalidRequest(format!("invalid select type option: {}", err))
+          })?;
+        }
+        yrs::Any::String(value.to_string().into())
+      },
+      _ => serde_json::from_value::<yrs::Any>(value).map_err(|err| {
+        AppError::InvalidRequest(format!("invalid type option `{}`: {}", key, err))
+      })?,
+    };
+    result.insert(key, any);
+  }
+  Ok(result)
+}
+
 pub async fn get_latest_collab_database_row_body(
   collab_storage: &CollabAccessControlStorage,
   workspace_uuid_str: &str,
@@ -464,12 +515,15 @@ pub fn collab_from_doc_state(doc_state: Vec<u8>, object_id: &str) -> Result<Coll
 }
 
 /// Base on values given by [cell_value_by_id], write to fields of DatabaseRowBody.
+/// The values of the DateTime fields are normalized first, dates without an offset are read in
+/// the time zone of the field, or [default_timezone] if the field has none.
 /// Returns encoded collab updates to the database row
 pub async fn write_to_database_row(
   db_body: &DatabaseBody,
   db_row_txn: &mut yrs::TransactionMut<'_>,
   db_row_body: &DatabaseRowBody,
   cell_value_by_id: HashMap<String, serde_json::Value>,
+  default_timezone: &str,
   modified_ts: i64,
 ) -> Result<(), AppError> {
   let all_fields = db_body.fields.get_all_fields(db_row_txn);
@@ -480,12 +534,8 @@ pub async fn write_to_database_row(
   let type_option_reader_by_id = type_option_writer_by_id(&all_fields);
   let field_by_name = field_by_name_uniq(all_fields);
 
-  // set last_modified
-  db_row_body.update(db_row_txn, |row_update| {
-    row_update.set_last_modified(modified_ts);
-  });
-
-  // for each field given by user input, overwrite existing data
+  // validate all the values before the row is modified
+  let mut cell_values = Vec::with_capacity(cell_value_by_id.len());
   for (id, serde_val) in cell_value_by_id {
     let field = match field_by_id.get(&id) {
       Some(f) => f,
@@ -498,6 +548,22 @@ pub async fn write_to_database_row(
         },
       },
     };
+    let serde_val = match FieldType::from(field.field_type) {
+      FieldType::DateTime => {
+        normalize_date_cell_value(serde_val, field_timezone(field, default_timezone))?
+      },
+      _ => serde_val,
+    };
+    cell_values.push((field, serde_val));
+  }
+
+  // set last_modified
+  db_row_body.update(db_row_txn, |row_update| {
+    row_update.set_last_modified(modified_ts);
+  });
+
+  // for each field given by user input, overwrite existing data
+  for (field, serde_val) in cell_values {
     let cell_writer = match type_option_reader_by_id.get(&field.id) {
       Some(cell_writer) => cell_writer,
       None => {
@@ -528,6 +594,31 @@ pub async fn create_row_document(
     .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to import markdown: {:?}", e)))?;
   let doc = Document::create(new_doc_id, doc_data)
     .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to create document: {:?}", e)))?;
+  register_row_document(workspace_id, uid, new_doc_id, collab_storage, doc).await
+}
+
+/// Creates the empty document of a database row, for the rows whose document is created when the
+/// row is opened for the first time.
+pub async fn create_empty_row_document(
+  workspace_id: &str,
+  uid: i64,
+  new_doc_id: &str,
+  collab_storage: &CollabAccessControlStorage,
+) -> Result<CreatedRowDocument, AppError> {
+  let doc = Document::create(new_doc_id, default_document_data(new_doc_id))
+    .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to create document: {:?}", e)))?;
+  register_row_document(workspace_id, uid, new_doc_id, collab_storage, doc).await
+}
+
+/// Adds the row document to the folder as an orphan view, which is how the clients find the
+/// documents that are not part of the view tree.
+async fn register_row_document(
+  workspace_id: &str,
+  uid: i64,
+  new_doc_id: &str,
+  collab_storage: &CollabAccessControlStorage,
+  doc: Document,
+) -> Result<CreatedRowDocument, AppError> {
   let doc_ec = doc.encode_collab().map_err(|e| {
     AppError::Internal(anyhow::anyhow!("Failed to encode document collab: {:?}", e))
   })?;
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+use access_control::restricted_view::RestrictedViewCache;
+use app_error::AppError;
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use collab_entity::CollabType;
+use collab_folder::Folder;
+use database::collab::{select_workspace_collabs_by_type, GetCollabOrigin};
+use database_entity::dto::{AFWorkspaceCollab, AFWorkspaceCollabs, WorkspaceCollabsQuery};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::biz::collab::ops::get_latest_workspace_database;
+use crate::biz::collab::utils::get_latest_collab_folder;
+
+const DEFAULT_WORKSPACE_COLLAB_LIMIT: i64 = 500;
+const MAX_WORKSPACE_COLLAB_LIMIT: i64 = 1000;
+
+/// Lists the collabs of the given type in the workspace, with the names of their views, so that
+/// the databases or the documents of a workspace can be enumerated without reading its folder.
+/// The collabs whose views are in a restricted view the user is not a member of are left out.
+pub async fn list_workspace_collabs(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  restricted_view_cache: &RestrictedViewCache,
+  uid: i64,
+  workspace_id: Uuid,
+  query: WorkspaceCollabsQuery,
+) -> Result<AFWorkspaceCollabs, AppError> {
+  let collab_type = collab_type_from_name(&query.collab_type)?;
+  let limit = query
+    .limit
+    .unwrap_or(DEFAULT_WORKSPACE_COLLAB_LIMIT)
+    .clamp(1, MAX_WORKSPACE_COLLAB_LIMIT);
+  let rows = select_workspace_collabs_by_type(
+    pg_pool,
+    &workspace_id,
+    &collab_type,
+    query.after.as_deref().unwrap_or_default(),
+    limit,
+  )
+  .await?;
+  let has_more = rows.len() as i64 == limit;
+  let next_after = rows.last().map(|(object_id, _, _)| object_id.clone());
+
+  // the views of the collabs, which are the views of the databases for the database collabs
+  let view_ids_by_object_id: HashMap<String, Vec<String>> = match collab_type {
+    CollabType::Document => rows
+      .iter()
+      .map(|(object_id, _, _)| (object_id.clone(), vec![object_id.clone()]))
+      .collect(),
+    CollabType::Database => {
+      let (_, workspace_database) = get_latest_workspace_database(
+        collab_storage,
+        pg_pool,
+        GetCollabOrigin::Server,
+        workspace_id,
+      )
+      .await?;
+      workspace_database
+        .get_all_database_meta()
+        .into_iter()
+        .map(|meta| (meta.database_id, meta.linked_views))
+        .collect()
+    },
+    _ => HashMap::new(),
+  };
+  let folder = match view_ids_by_object_id.is_empty() {
+    true => None,
+    false => Some(
+      get_latest_collab_folder(
+        collab_storage,
+        GetCollabOrigin::User { uid },
+        &workspace_id.to_string(),
+      )
+      .await?,
+    ),
+  };
+  let restricted_views = restricted_view_cache.get(&workspace_id).await?;
+
+  let mut collabs = Vec::with_capacity(rows.len());
+  for (object_id, size, updated_at) in rows {
+    let view_ids = view_ids_by_object_id
+      .get(&object_id)
+      .map(Vec::as_slice)
+      .unwrap_or_default();
+    let (mut visible_names, mut has_view) = (vec![], false);
+    if let Some(folder) = &folder {
+      for view_id in view_ids {
+        let Some(view) = folder.get_view(view_id) else {
+          continue;
+        };
+        has_view = true;
+        if restricted_views.can_access_view(uid, view_id, |view_id| parent_of(folder, view_id)) {
+          visible_names.push(view.name.clone());
+        }
+      }
+    }
+    // all the views of the collab are hidden from the user
+    if has_view && visible_names.is_empty() {
+      continue;
+    }
+    collabs.push(AFWorkspaceCollab {
+      object_id,
+      name: visible_names.into_iter().next(),
+      size,
+      updated_at,
+    });
+  }
+  Ok(AFWorkspaceCollabs {
+    collabs,
+    next_after,
+    has_more,
+  })
+}
+
+fn parent_of(folder: &Folder, view_id: &str) -> Option<String> {
+  folder
+    .get_view(view_id)
+    .map(|view| view.parent_view_id.clone())
+}
+
+/// Parses the name of a collab type, case insensitive.
+fn collab_type_from_name(name: &str) -> Result<CollabType, AppError> {
+  let collab_type = match name.to_ascii_lowercase().as_str() {
+    "document" => CollabType::Document,
+    "database" => CollabType::Database,
+    "workspacedatabase" => CollabType::WorkspaceDatabase,
+    "folder" => CollabType::Folder,
+    "databaserow" => CollabType::DatabaseRow,
+    "userawareness" => CollabType::UserAwareness,
+    _ => {
+      return Err(AppError::InvalidRequest(format!(
+        "unknown collab type {}, expected one of Document, Database, WorkspaceDatabase, Folder, \
+         DatabaseRow or UserAwareness",
+        name
+      )))
+    },
+  };
+  Ok(collab_type)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse_collab_type_names() {
+    assert_eq!(
+      collab_type_from_name("Database").unwrap(),
+      CollabType::Database
+    );
+    assert_eq!(
+      collab_type_from_name("databaseRow").unwrap(),
+      CollabType::DatabaseRow
+    );
+    assert!(collab_type_from_name("Unknown").is_err());
+    assert!(collab_type_from_name("1").is_err());
+  }
+}
@@ -1,8 +1,13 @@
 pub mod access_request;
+pub mod ai;
+pub mod billing;
 pub mod chat;
 pub mod collab;
 pub mod data_import;
+pub mod notification;
+pub mod organization;
 pub mod pg_listener;
+pub mod region;
 pub mod search;
 pub mod template;
 pub mod user;
@@ -0,0 +1,55 @@
+use prometheus_client::metrics::counter::Counter;
+
+#[derive(Default, Clone)]
+pub struct PushMetrics {
+  sent_count: Counter,
+  failed_count: Counter,
+  retry_count: Counter,
+  invalidated_token_count: Counter,
+}
+
+impl PushMetrics {
+  pub fn register(registry: &mut prometheus_client::registry::Registry) -> Self {
+    let metrics = Self::default();
+    let push_registry = registry.sub_registry_with_prefix("push");
+
+    push_registry.register(
+      "sent_count",
+      "Total count of push notifications accepted by the push services",
+      metrics.sent_count.clone(),
+    );
+    push_registry.register(
+      "failed_count",
+      "Total count of push notifications that could not be delivered",
+      metrics.failed_count.clone(),
+    );
+    push_registry.register(
+      "retry_count",
+      "Total count of push notifications sent again after a transient error",
+      metrics.retry_count.clone(),
+    );
+    push_registry.register(
+      "invalidated_token_count",
+      "Total count of device tokens removed after the push services rejected them",
+      metrics.invalidated_token_count.clone(),
+    );
+
+    metrics
+  }
+
+  pub fn record_sent_count(&self, count: u64) {
+    self.sent_count.inc_by(count);
+  }
+
+  pub fn record_failed_count(&self, count: u64) {
+    self.failed_count.inc_by(count);
+  }
+
+  pub fn record_retry_count(&self, count: u64) {
+    self.retry_count.inc_by(count);
+  }
+
+  pub fn record_invalidated_token_count(&self, count: u64) {
+    self.invalidated_token_count.inc_by(count);
+  }
+}
@@ -0,0 +1,39 @@
+pub mod metrics;
+pub mod push;
+
+use std::sync::Arc;
+
+use push::{PushDispatcher, PushMessage};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationPriority {
+  Normal,
+  High,
+}
+
+/// Delivers the notifications of the events to the users. The high priority notifications are
+/// also pushed to the mobile devices of the user, so they reach the apps that are closed.
+#[derive(Clone, Default)]
+pub struct NotificationCenter {
+  push: Option<Arc<PushDispatcher>>,
+}
+
+impl NotificationCenter {
+  pub fn new(push: Option<PushDispatcher>) -> Self {
+    Self {
+      push: push.map(Arc::new),
+    }
+  }
+
+  /// Returns right away, the notification is delivered in the background.
+  pub fn notify(&self, uid: i64, priority: NotificationPriority, message: PushMessage) {
+    if priority != NotificationPriority::High {
+      return;
+    }
+    if let Some(push) = self.push.clone() {
+      tokio::spawn(async move {
+        push.send_to_user(uid, &message).await;
+      });
+    }
+  }
+}
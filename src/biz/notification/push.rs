@@ -0,0 +1,365 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Context;
+use app_error::AppError;
+use chrono::Utc;
+use database::push_device::{delete_push_devices_by_token, select_push_devices, AFPushDevice};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use reqwest::StatusCode;
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use shared_entity::dto::notification_dto::PushPlatform;
+use sqlx::PgPool;
+use tracing::{info, trace, warn};
+
+use super::metrics::PushMetrics;
+use crate::config::config::{ApnsSetting, FcmSetting, PushSetting};
+
+const APNS_URL: &str = "https://api.push.apple.com";
+const APNS_SANDBOX_URL: &str = "https://api.sandbox.push.apple.com";
+/// Apple rejects the provider tokens older than an hour, and the ones renewed more than every 20
+/// minutes.
+const APNS_TOKEN_TTL_SECS: i64 = 50 * 60;
+const GOOGLE_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const FCM_SCOPE: &str = "https://www.googleapis.com/auth/firebase.messaging";
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone)]
+pub struct PushMessage {
+  pub title: String,
+  pub body: String,
+  /// Given to the app when the notification is opened.
+  pub data: HashMap<String, String>,
+}
+
+#[derive(Debug)]
+enum PushError {
+  /// The token is not valid anymore, the app was uninstalled or the token expired.
+  InvalidToken,
+  Transient(String),
+  Failed(String),
+}
+
+/// Sends the push notifications to the devices registered by the users through APNs or FCM. A
+/// notification is sent again when the push service is unavailable, and the tokens rejected by
+/// the push services are removed.
+pub struct PushDispatcher {
+  client: reqwest::Client,
+  pg_pool: PgPool,
+  apns: Option<ApnsClient>,
+  fcm: Option<FcmClient>,
+  max_attempts: u32,
+  metrics: Arc<PushMetrics>,
+}
+
+impl PushDispatcher {
+  /// Returns None when no push service is configured.
+  pub fn new(
+    setting: &PushSetting,
+    pg_pool: PgPool,
+    metrics: Arc<PushMetrics>,
+  ) -> Result<Option<Self>, AppError> {
+    let apns = setting.apns.as_ref().map(ApnsClient::new).transpose()?;
+    let fcm = setting.fcm.as_ref().map(FcmClient::new).transpose()?;
+    if apns.is_none() && fcm.is_none() {
+      return Ok(None);
+    }
+    info!(
+      "Sending push notifications with APNs: {}, FCM: {}",
+      apns.is_some(),
+      fcm.is_some()
+    );
+    Ok(Some(Self {
+      client: reqwest::Client::new(),
+      pg_pool,
+      apns,
+      fcm,
+      max_attempts: setting.max_attempts.max(1),
+      metrics,
+    }))
+  }
+
+  pub async fn send_to_user(&self, uid: i64, message: &PushMessage) {
+    let devices = match select_push_devices(&self.pg_pool, uid).await {
+      Ok(devices) => devices,
+      Err(err) => {
+        warn!("failed to get the push devices of user {}: {}", uid, err);
+        return;
+      },
+    };
+    let mut invalid_tokens = vec![];
+    for device in devices {
+      match self.send_with_retry(&device, message).await {
+        Ok(true) => self.metrics.record_sent_count(1),
+        Ok(false) => trace!("no push service configured for {:?}", device.platform),
+        Err(PushError::InvalidToken) => invalid_tokens.push(device.token),
+        Err(err) => {
+          warn!("failed to push notification to user {}: {:?}", uid, err);
+          self.metrics.record_failed_count(1);
+        },
+      }
+    }
+    if !invalid_tokens.is_empty() {
+      self
+        .metrics
+        .record_failed_count(invalid_tokens.len() as u64);
+      match delete_push_devices_by_token(&self.pg_pool, &invalid_tokens).await {
+        Ok(count) => self.metrics.record_invalidated_token_count(count),
+        Err(err) => warn!("failed to remove invalid push tokens: {}", err),
+      }
+    }
+  }
+
+  /// Returns false when the platform of the device has no push service configured.
+  async fn send_with_retry(
+    &self,
+    device: &AFPushDevice,
+    message: &PushMessage,
+  ) -> Result<bool, PushError> {
+    let mut attempt = 1;
+    loop {
+      let result = match (device.platform, &self.apns, &self.fcm) {
+        (PushPlatform::Apns, Some(apns), _) => {
+          apns.send(&self.client, &device.token, message).await
+        },
+        (PushPlatform::Fcm, _, Some(fcm)) => fcm.send(&self.client, &device.token, message).await,
+        _ => return Ok(false),
+      };
+      match result {
+        Ok(()) => return Ok(true),
+        Err(PushError::Transient(err)) if attempt < self.max_attempts => {
+          trace!("retrying push notification after: {}", err);
+          self.metrics.record_retry_count(1);
+          tokio::time::sleep(RETRY_BASE_DELAY * 2u32.pow(attempt - 1)).await;
+          attempt += 1;
+        },
+        Err(err) => return Err(err),
+      }
+    }
+  }
+}
+
+/// Classifies the error responses shared by both push services.
+fn error_from_status(status: StatusCode, body: String) -> PushError {
+  if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+    PushError::Transient(format!("{}: {}", status, body))
+  } else {
+    PushError::Failed(format!("{}: {}", status, body))
+  }
+}
+
+#[derive(Serialize)]
+struct ApnsClaims<'a> {
+  iss: &'a str,
+  iat: i64,
+}
+
+struct ApnsClient {
+  url: &'static str,
+  team_id: String,
+  key_id: String,
+  topic: String,
+  key: EncodingKey,
+  /// The provider token and when it was issued.
+  token: Mutex<Option<(String, i64)>>,
+}
+
+impl ApnsClient {
+  fn new(setting: &ApnsSetting) -> Result<Self, AppError> {
+    let key = EncodingKey::from_ec_pem(setting.private_key.expose_secret().as_bytes())
+      .context("invalid APNs private key")?;
+    Ok(Self {
+      url: if setting.use_sandbox {
+        APNS_SANDBOX_URL
+      } else {
+        APNS_URL
+      },
+      team_id: setting.team_id.clone(),
+      key_id: setting.key_id.clone(),
+      topic: setting.topic.clone(),
+      key,
+      token: Mutex::new(None),
+    })
+  }
+
+  fn provider_token(&self) -> Result<String, PushError> {
+    let now = Utc::now().timestamp();
+    let mut cached = self.token.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some((token, issued_at)) = cached.as_ref() {
+      if now - issued_at < APNS_TOKEN_TTL_SECS {
+        return Ok(token.clone());
+      }
+    }
+    let mut header = Header::new(Algorithm::ES256);
+    header.kid = Some(self.key_id.clone());
+    let claims = ApnsClaims {
+      iss: &self.team_id,
+      iat: now,
+    };
+    let token = encode(&header, &claims, &self.key)
+      .map_err(|err| PushError::Failed(format!("failed to sign APNs token: {}", err)))?;
+    *cached = Some((token.clone(), now));
+    Ok(token)
+  }
+
+  async fn send(
+    &self,
+    client: &reqwest::Client,
+    device_token: &str,
+    message: &PushMessage,
+  ) -> Result<(), PushError> {
+    let mut payload = json!({
+      "aps": {
+        "alert": { "title": message.title, "body": message.body },
+        "sound": "default",
+      },
+    });
+    for (key, value) in &message.data {
+      payload[key] = json!(value);
+    }
+    let resp = client
+      .post(format!("{}/3/device/{}", self.url, device_token))
+      .bearer_auth(self.provider_token()?)
+      .header("apns-topic", &self.topic)
+      .header("apns-push-type", "alert")
+      .header("apns-priority", "10")
+      .json(&payload)
+      .send()
+      .await
+      .map_err(|err| PushError::Transient(err.to_string()))?;
+    let status = resp.status();
+    if status.is_success() {
+      return Ok(());
+    }
+    let body = resp.text().await.unwrap_or_default();
+    if status == StatusCode::GONE
+      || body.contains("BadDeviceToken")
+      || body.contains("DeviceTokenNotForTopic")
+    {
+      return Err(PushError::InvalidToken);
+    }
+    if body.contains("ExpiredProviderToken") {
+      *self.token.lock().unwrap_or_else(|err| err.into_inner()) = None;
+      return Err(PushError::Transient(body));
+    }
+    Err(error_from_status(status, body))
+  }
+}
+
+#[derive(Serialize)]
+struct GoogleClaims<'a> {
+  iss: &'a str,
+  scope: &'a str,
+  aud: &'a str,
+  iat: i64,
+  exp: i64,
+}
+
+#[derive(Deserialize)]
+struct GoogleTokenResponse {
+  access_token: String,
+  expires_in: i64,
+}
+
+struct FcmClient {
+  send_url: String,
+  client_email: String,
+  key: EncodingKey,
+  /// The OAuth access token and when it expires. The lock is held while the token is renewed, so
+  /// it's renewed once.
+  access_token: tokio::sync::Mutex<Option<(String, i64)>>,
+}
+
+impl FcmClient {
+  fn new(setting: &FcmSetting) -> Result<Self, AppError> {
+    let key = EncodingKey::from_rsa_pem(setting.private_key.expose_secret().as_bytes())
+      .context("invalid FCM private key")?;
+    Ok(Self {
+      send_url: format!(
+        "https://fcm.googleapis.com/v1/projects/{}/messages:send",
+        setting.project_id
+      ),
+      client_email: setting.client_email.clone(),
+      key,
+      access_token: tokio::sync::Mutex::new(None),
+    })
+  }
+
+  async fn access_token(&self, client: &reqwest::Client) -> Result<String, PushError> {
+    let now = Utc::now().timestamp();
+    let mut cached = self.access_token.lock().await;
+    if let Some((token, expires_at)) = cached.as_ref() {
+      if now + 60 < *expires_at {
+        return Ok(token.clone());
+      }
+    }
+    let claims = GoogleClaims {
+      iss: &self.client_email,
+      scope: FCM_SCOPE,
+      aud: GOOGLE_TOKEN_URL,
+      iat: now,
+      exp: now + 3600,
+    };
+    let assertion = encode(&Header::new(Algorithm::RS256), &claims, &self.key)
+      .map_err(|err| PushError::Failed(format!("failed to sign FCM assertion: {}", err)))?;
+    let resp = client
+      .post(GOOGLE_TOKEN_URL)
+      .form(&[
+        ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+        ("assertion", assertion.as_str()),
+      ])
+      .send()
+      .await
+      .map_err(|err| PushError::Transient(err.to_string()))?;
+    let status = resp.status();
+    if !status.is_success() {
+      let body = resp.text().await.unwrap_or_default();
+      return Err(error_from_status(status, body));
+    }
+    let token = resp
+      .json::<GoogleTokenResponse>()
+      .await
+      .map_err(|err| PushError::Failed(format!("invalid FCM access token: {}", err)))?;
+    *cached = Some((token.access_token.clone(), now + token.expires_in));
+    Ok(token.access_token)
+  }
+
+  async fn send(
+    &self,
+    client: &reqwest::Client,
+    device_token: &str,
+    message: &PushMessage,
+  ) -> Result<(), PushError> {
+    let payload = json!({
+      "message": {
+        "token": device_token,
+        "notification": { "title": message.title, "body": message.body },
+        "data": message.data,
+        "android": { "priority": "HIGH" },
+      },
+    });
+    let resp = client
+      .post(&self.send_url)
+      .bearer_auth(self.access_token(client).await?)
+      .json(&payload)
+      .send()
+      .await
+      .map_err(|err| PushError::Transient(err.to_string()))?;
+    let status = resp.status();
+    if status.is_success() {
+      return Ok(());
+    }
+    let body = resp.text().await.unwrap_or_default();
+    if status == StatusCode::NOT_FOUND || body.contains("UNREGISTERED") {
+      return Err(PushError::InvalidToken);
+    }
+    if status == StatusCode::UNAUTHORIZED {
+      *self.access_token.lock().await = None;
+      return Err(PushError::Transient(body));
+    }
+    Err(error_from_status(status, body))
+  }
+}
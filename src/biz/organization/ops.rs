@@ -0,0 +1,311 @@
+use std::collections::BTreeMap;
+use std::ops::DerefMut;
+use std::sync::Arc;
+
+use access_control::workspace::WorkspaceAccessControl;
+use anyhow::Context;
+use app_error::AppError;
+use database::organization::{
+  delete_organization_members, delete_workspace_organization, insert_organization,
+  select_organization_members, select_organization_of_user, select_organization_role,
+  select_organization_workspace_ids, select_organization_workspace_member_count,
+  select_organization_workspace_usage, select_organizations_of_user,
+  update_organization_max_sessions_per_user, update_workspace_organization,
+  upsert_organization_member,
+};
+use database::user::select_uid_from_email;
+use database::workspace::{select_user_role, upsert_workspace_member_with_txn};
+use database_entity::dto::AFRole;
+use shared_entity::dto::org_dto::{
+  AddOrganizationMembersParams, CreateOrganizationParams, OrgRole, Organization,
+  OrganizationMember, OrganizationMemberWorkspace, OrganizationUsage, OrganizationWorkspaceUsage,
+  RemoveOrganizationMembersParams, UpdateOrganizationSessionPolicyParams,
+  MAX_ORG_MEMBERS_PER_REQUEST,
+};
+use sqlx::PgPool;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::biz::billing::ops::check_member_limit;
+use crate::config::config::BillingSetting;
+
+const MAX_ORG_NAME_LEN: usize = 100;
+
+pub async fn create_organization(
+  pg_pool: &PgPool,
+  uid: i64,
+  params: CreateOrganizationParams,
+) -> Result<Organization, AppError> {
+  let name = params.name.trim();
+  if name.is_empty() || name.chars().count() > MAX_ORG_NAME_LEN {
+    return Err(AppError::InvalidRequest(format!(
+      "the name of an organization must have between 1 and {} characters",
+      MAX_ORG_NAME_LEN
+    )));
+  }
+  let billing_email = params
+    .billing_email
+    .as_deref()
+    .map(str::trim)
+    .filter(|email| !email.is_empty());
+
+  let mut txn = pg_pool
+    .begin()
+    .await
+    .context("Begin transaction to create organization")?;
+  let org_id = insert_organization(txn.deref_mut(), name, billing_email, uid).await?;
+  upsert_organization_member(txn.deref_mut(), &org_id, uid, OrgRole::Admin).await?;
+  txn
+    .commit()
+    .await
+    .context("Commit transaction to create organization")?;
+
+  info!("user {} created organization {}", uid, org_id);
+  get_organization(pg_pool, uid, &org_id).await
+}
+
+pub async fn list_organizations(pg_pool: &PgPool, uid: i64) -> Result<Vec<Organization>, AppError> {
+  let rows = select_organizations_of_user(pg_pool, uid).await?;
+  Ok(rows.into_iter().map(Organization::from).collect())
+}
+
+pub async fn get_organization(
+  pg_pool: &PgPool,
+  uid: i64,
+  org_id: &Uuid,
+) -> Result<Organization, AppError> {
+  select_organization_of_user(pg_pool, org_id, uid)
+    .await?
+    .map(Organization::from)
+    .ok_or_else(|| organization_not_found(org_id))
+}
+
+/// Moves a workspace owned by the user to the organization.
+pub async fn attach_workspace(
+  pg_pool: &PgPool,
+  workspace_access_control: &Arc<dyn WorkspaceAccessControl>,
+  uid: i64,
+  org_id: &Uuid,
+  workspace_id: &Uuid,
+) -> Result<(), AppError> {
+  ensure_org_admin(pg_pool, uid, org_id).await?;
+  workspace_access_control
+    .enforce_role(&uid, &workspace_id.to_string(), AFRole::Owner)
+    .await?;
+  if !update_workspace_organization(pg_pool, workspace_id, org_id).await? {
+    return Err(AppError::InvalidRequest(format!(
+      "workspace {} belongs to another organization",
+      workspace_id
+    )));
+  }
+  info!(
+    "user {} attached workspace {} to organization {}",
+    uid, workspace_id, org_id
+  );
+  Ok(())
+}
+
+pub async fn detach_workspace(
+  pg_pool: &PgPool,
+  uid: i64,
+  org_id: &Uuid,
+  workspace_id: &Uuid,
+) -> Result<(), AppError> {
+  ensure_org_admin(pg_pool, uid, org_id).await?;
+  if !delete_workspace_organization(pg_pool, workspace_id, org_id).await? {
+    return Err(AppError::RecordNotFound(format!(
+      "workspace {} does not belong to organization {}",
+      workspace_id, org_id
+    )));
+  }
+  info!(
+    "user {} detached workspace {} from organization {}",
+    uid, workspace_id, org_id
+  );
+  Ok(())
+}
+
+/// Adds existing users to the organization and, unless told otherwise, to every workspace of the
+/// organization as members. The users who already belong to a workspace keep their role in it.
+/// The member limit of each workspace is checked as if all the users were new to it.
+pub async fn add_organization_members(
+  pg_pool: &PgPool,
+  billing: &BillingSetting,
+  workspace_access_control: &Arc<dyn WorkspaceAccessControl>,
+  uid: i64,
+  org_id: &Uuid,
+  params: AddOrganizationMembersParams,
+) -> Result<(), AppError> {
+  ensure_org_admin(pg_pool, uid, org_id).await?;
+  check_member_emails(&params.emails)?;
+  let workspace_ids = if params.add_to_workspaces {
+    select_organization_workspace_ids(pg_pool, org_id).await?
+  } else {
+    vec![]
+  };
+  for workspace_id in &workspace_ids {
+    check_member_limit(pg_pool, billing, workspace_id, params.emails.len() as i64).await?;
+  }
+
+  let mut txn = pg_pool
+    .begin()
+    .await
+    .context("Begin transaction to add organization members")?;
+  let mut member_uids = Vec::with_capacity(params.emails.len());
+  for email in &params.emails {
+    let member_uid = select_uid_from_email(txn.deref_mut(), email).await?;
+    upsert_organization_member(txn.deref_mut(), org_id, member_uid, params.role).await?;
+    for workspace_id in &workspace_ids {
+      upsert_workspace_member_with_txn(&mut txn, workspace_id, email, AFRole::Member).await?;
+    }
+    member_uids.push(member_uid);
+  }
+  txn
+    .commit()
+    .await
+    .context("Commit transaction to add organization members")?;
+
+  for member_uid in member_uids {
+    for workspace_id in &workspace_ids {
+      let role = select_user_role(pg_pool, &member_uid, workspace_id).await?;
+      workspace_access_control
+        .insert_role(&member_uid, workspace_id, role)
+        .await?;
+    }
+  }
+  info!(
+    "user {} added {} members to organization {}",
+    uid,
+    params.emails.len(),
+    org_id
+  );
+  Ok(())
+}
+
+/// Removes users from the organization. The owner of the organization can't be removed, and the
+/// users keep their workspace memberships.
+pub async fn remove_organization_members(
+  pg_pool: &PgPool,
+  uid: i64,
+  org_id: &Uuid,
+  params: RemoveOrganizationMembersParams,
+) -> Result<(), AppError> {
+  ensure_org_admin(pg_pool, uid, org_id).await?;
+  check_member_emails(&params.emails)?;
+  let removed = delete_organization_members(pg_pool, org_id, &params.emails).await?;
+  info!(
+    "user {} removed {} members from organization {}",
+    uid, removed, org_id
+  );
+  Ok(())
+}
+
+/// Lists the members of the organization and of its workspaces, with the workspaces each of them
+/// belongs to.
+pub async fn list_organization_members(
+  pg_pool: &PgPool,
+  uid: i64,
+  org_id: &Uuid,
+) -> Result<Vec<OrganizationMember>, AppError> {
+  ensure_org_admin(pg_pool, uid, org_id).await?;
+  let rows = select_organization_members(pg_pool, org_id).await?;
+  let mut members: BTreeMap<(String, Uuid), OrganizationMember> = BTreeMap::new();
+  for row in rows {
+    let member = members
+      .entry((row.email.clone(), row.uuid))
+      .or_insert_with(|| OrganizationMember {
+        uuid: row.uuid,
+        email: row.email,
+        name: row.name,
+        org_role: row.org_role.map(OrgRole::from),
+        workspaces: vec![],
+      });
+    if let (Some(workspace_id), Some(role)) = (row.workspace_id, row.workspace_role) {
+      member.workspaces.push(OrganizationMemberWorkspace {
+        workspace_id,
+        role: AFRole::from(role),
+      });
+    }
+  }
+  Ok(members.into_values().collect())
+}
+
+pub async fn get_organization_usage(
+  pg_pool: &PgPool,
+  uid: i64,
+  org_id: &Uuid,
+) -> Result<OrganizationUsage, AppError> {
+  ensure_org_admin(pg_pool, uid, org_id).await?;
+  let workspaces = select_organization_workspace_usage(pg_pool, org_id)
+    .await?
+    .into_iter()
+    .map(|row| OrganizationWorkspaceUsage {
+      workspace_id: row.workspace_id,
+      workspace_name: row.workspace_name,
+      member_count: row.member_count,
+      total_document_size: row.total_document_size,
+      total_file_size: row.total_file_size,
+    })
+    .collect::<Vec<_>>();
+  let member_count = select_organization_workspace_member_count(pg_pool, org_id).await?;
+  Ok(OrganizationUsage {
+    org_id: *org_id,
+    workspace_count: workspaces.len() as i64,
+    member_count,
+    total_document_size: workspaces
+      .iter()
+      .map(|usage| usage.total_document_size)
+      .sum(),
+    total_file_size: workspaces.iter().map(|usage| usage.total_file_size).sum(),
+    workspaces,
+  })
+}
+
+/// Sets how many realtime sessions each user of the organization can keep open. The limit applies
+/// to the next connection of the users, the sessions above it are then signed out.
+pub async fn update_session_policy(
+  pg_pool: &PgPool,
+  uid: i64,
+  org_id: &Uuid,
+  params: UpdateOrganizationSessionPolicyParams,
+) -> Result<Organization, AppError> {
+  ensure_org_admin(pg_pool, uid, org_id).await?;
+  if params.max_sessions_per_user.is_some_and(|max| max < 1) {
+    return Err(AppError::InvalidRequest(
+      "the maximum number of sessions per user must be at least 1".to_string(),
+    ));
+  }
+  update_organization_max_sessions_per_user(pg_pool, org_id, params.max_sessions_per_user).await?;
+  info!(
+    "user {} set the session limit of organization {} to {:?}",
+    uid, org_id, params.max_sessions_per_user
+  );
+  get_organization(pg_pool, uid, org_id).await
+}
+
+/// The members of an organization can't tell whether another organization exists, so a missing
+/// organization and an organization the user doesn't belong to give the same error.
+async fn ensure_org_admin(pg_pool: &PgPool, uid: i64, org_id: &Uuid) -> Result<(), AppError> {
+  match select_organization_role(pg_pool, org_id, uid).await? {
+    Some(OrgRole::Admin) => Ok(()),
+    Some(OrgRole::Member) => Err(AppError::NotEnoughPermissions),
+    None => Err(organization_not_found(org_id)),
+  }
+}
+
+fn check_member_emails(emails: &[String]) -> Result<(), AppError> {
+  if emails.is_empty() {
+    return Err(AppError::InvalidRequest("No user selected".to_string()));
+  }
+  if emails.len() > MAX_ORG_MEMBERS_PER_REQUEST {
+    return Err(AppError::InvalidRequest(format!(
+      "At most {} users can be updated at once",
+      MAX_ORG_MEMBERS_PER_REQUEST
+    )));
+  }
+  Ok(())
+}
+
+fn organization_not_found(org_id: &Uuid) -> AppError {
+  AppError::RecordNotFound(format!("organization {} not found", org_id))
+}
@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+
+use app_error::AppError;
+use database::workspace::select_workspace_settings;
+use shared_entity::dto::server_info_dto::RegionInfo;
+use sqlx::PgPool;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::config::config::RegionSetting;
+
+/// Returns the region a client connecting to this server should connect to instead, if any. The
+/// region the workspace opened by the client resides in comes first, then the region with the
+/// lowest latency reported by the client.
+pub async fn preferred_region(
+  pg_pool: &PgPool,
+  setting: &RegionSetting,
+  workspace_id: Option<&Uuid>,
+  latencies: &HashMap<String, u32>,
+) -> Option<RegionInfo> {
+  if setting.current.is_empty() || setting.regions.len() < 2 {
+    return None;
+  }
+  if let Some(workspace_id) = workspace_id {
+    match select_workspace_settings(pg_pool, workspace_id).await {
+      Ok(Some(settings)) if !settings.region.is_empty() => {
+        return find_region(setting, &settings.region)
+          .filter(|region| region.name != setting.current)
+          .cloned();
+      },
+      Ok(_) => {},
+      Err(err) => warn!(
+        "failed to get the region of workspace {}: {}",
+        workspace_id, err
+      ),
+    }
+  }
+  region_by_latency(setting, latencies).cloned()
+}
+
+/// Returns the region with the lowest latency, if it saves at least the minimum gain over the
+/// current region. Without latency to the current region, any known region is better.
+fn region_by_latency<'a>(
+  setting: &'a RegionSetting,
+  latencies: &HashMap<String, u32>,
+) -> Option<&'a RegionInfo> {
+  let (best, best_latency) = setting
+    .regions
+    .iter()
+    .filter_map(|region| {
+      latencies
+        .get(&region.name)
+        .map(|latency| (region, *latency))
+    })
+    .min_by_key(|(_, latency)| *latency)?;
+  if best.name == setting.current {
+    return None;
+  }
+  match latencies.get(&setting.current) {
+    Some(current_latency)
+      if current_latency.saturating_sub(best_latency) < setting.min_latency_gain_ms =>
+    {
+      None
+    },
+    _ => Some(best),
+  }
+}
+
+fn find_region<'a>(setting: &'a RegionSetting, name: &str) -> Option<&'a RegionInfo> {
+  setting.regions.iter().find(|region| region.name == name)
+}
+
+/// The region of a workspace must be one of the regions of the deployment, or empty.
+pub fn validate_workspace_region(setting: &RegionSetting, region: &str) -> Result<(), AppError> {
+  if region.is_empty() || find_region(setting, region).is_some() {
+    return Ok(());
+  }
+  Err(AppError::InvalidRequest(format!(
+    "unknown region: {}",
+    region
+  )))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn setting() -> RegionSetting {
+    let region = |name: &str| RegionInfo {
+      name: name.to_string(),
+      base_url: format!("https://{}.example.com", name),
+      ws_url: format!("wss://{}.example.com/ws/v1", name),
+    };
+    RegionSetting {
+      current: "us".to_string(),
+      regions: vec![region("us"), region("eu"), region("ap")],
+      min_latency_gain_ms: 50,
+    }
+  }
+
+  fn latencies(entries: &[(&str, u32)]) -> HashMap<String, u32> {
+    entries
+      .iter()
+      .map(|(region, latency)| (region.to_string(), *latency))
+      .collect()
+  }
+
+  #[test]
+  fn lowest_latency_region_is_preferred() {
+    let setting = setting();
+    let best = region_by_latency(
+      &setting,
+      &latencies(&[("us", 180), ("eu", 40), ("ap", 300)]),
+    );
+    assert_eq!(best.unwrap().name, "eu");
+    let best = region_by_latency(&setting, &latencies(&[("eu", 40)]));
+    assert_eq!(best.unwrap().name, "eu");
+  }
+
+  #[test]
+  fn small_gains_keep_the_current_region() {
+    let setting = setting();
+    assert!(region_by_latency(&setting, &latencies(&[("us", 80), ("eu", 40)])).is_none());
+    assert!(region_by_latency(&setting, &latencies(&[("us", 20), ("eu", 40)])).is_none());
+    assert!(region_by_latency(&setting, &latencies(&[("mars", 1)])).is_none());
+  }
+
+  #[test]
+  fn workspace_region_must_be_known() {
+    let setting = setting();
+    assert!(validate_workspace_region(&setting, "eu").is_ok());
+    assert!(validate_workspace_region(&setting, "").is_ok());
+    assert!(validate_workspace_region(&setting, "mars").is_err());
+  }
+}
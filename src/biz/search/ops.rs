@@ -3,6 +3,8 @@ use crate::biz::collab::utils::get_latest_collab_folder;
 use crate::{
   api::metrics::RequestMetrics, biz::collab::folder_view::private_space_and_trash_view_ids,
 };
+use access_control::collab_guest::GuestAccess;
+use access_control::restricted_view::RestrictedViewCache;
 use app_error::AppError;
 use appflowy_ai_client::dto::{
   EmbeddingEncodingFormat, EmbeddingInput, EmbeddingModel, EmbeddingOutput, EmbeddingRequest,
@@ -46,7 +48,10 @@ fn populate_searchable_view_ids(
   let is_trash = private_space_and_trash_views
     .view_ids_in_trash
     .contains(current_view_id);
-  if is_other_private_space || is_trash {
+  let is_restricted = private_space_and_trash_views
+    .restricted_view_ids
+    .contains(current_view_id);
+  if is_other_private_space || is_trash || is_restricted {
     return;
   }
   let view = match folder.get_view(current_view_id) {
@@ -70,12 +75,15 @@ fn populate_searchable_view_ids(
   }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn search_document(
   pg_pool: &PgPool,
   collab_storage: &CollabAccessControlStorage,
+  restricted_view_cache: &RestrictedViewCache,
   indexer_scheduler: &Arc<IndexerScheduler>,
   uid: i64,
   workspace_uuid: Uuid,
+  guest_access: Option<&GuestAccess>,
   request: SearchDocumentRequest,
   metrics: &RequestMetrics,
 ) -> Result<Vec<SearchDocumentResponseItem>, AppError> {
@@ -108,13 +116,24 @@ pub async fn search_document(
     },
   };
 
-  let folder = get_latest_collab_folder(
-    collab_storage,
-    GetCollabOrigin::User { uid },
-    &workspace_uuid.to_string(),
-  )
-  .await?;
-  let private_space_and_trash_views = private_space_and_trash_view_ids(&folder);
+  // The folder itself is not shared with the guests of the workspace.
+  let origin = match guest_access {
+    Some(_) => GetCollabOrigin::Server,
+    None => GetCollabOrigin::User { uid },
+  };
+  let folder =
+    get_latest_collab_folder(collab_storage, origin, &workspace_uuid.to_string()).await?;
+  let mut private_space_and_trash_views = private_space_and_trash_view_ids(&folder);
+  private_space_and_trash_views.restricted_view_ids = restricted_view_cache
+    .get(&workspace_uuid)
+    .await?
+    .hidden_view_ids(uid);
+  if guest_access.is_some() {
+    // a view shared with a guest is visible even when it is in a private space
+    private_space_and_trash_views
+      .other_private_space_ids
+      .clear();
+  }
   let mut searchable_view_ids = HashSet::new();
   populate_searchable_view_ids(
     &folder,
@@ -125,6 +144,9 @@ pub async fn search_document(
     0,
     MAX_SEARCH_DEPTH,
   );
+  if let Some(guest_access) = guest_access {
+    searchable_view_ids.retain(|view_id| guest_access.access_level(view_id).is_some());
+  }
   let results = search_documents(
     pg_pool,
     SearchDocumentParams {
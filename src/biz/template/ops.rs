@@ -9,7 +9,7 @@ use anyhow::Context;
 use app_error::ErrorCode;
 use aws_sdk_s3::primitives::ByteStream;
 use database::{
-  file::{s3_client_impl::AwsS3BucketClientImpl, BucketClient, ResponseBlob},
+  file::{BlobStorageClient, BucketClient, ResponseBlob},
   publish::{select_publish_info_for_view_ids, select_published_collab_info},
   template::*,
 };
@@ -395,7 +395,7 @@ pub struct AvatarContent {
 }
 
 pub async fn get_avatar(
-  client: AwsS3BucketClientImpl,
+  client: BlobStorageClient,
   file_id: String,
 ) -> Result<AvatarContent, AppResponseError> {
   let object_key = avatar_object_key(&file_id);
@@ -411,7 +411,7 @@ pub async fn get_avatar(
 }
 
 pub async fn upload_avatar(
-  client: AwsS3BucketClientImpl,
+  client: BlobStorageClient,
   avatar: &MPBytes,
 ) -> Result<String, AppResponseError> {
   let content_type = match &avatar.content_type {
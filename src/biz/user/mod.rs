@@ -1,3 +1,5 @@
+pub mod user_admin;
+pub mod user_auth;
 pub mod user_delete;
 pub mod user_info;
 pub mod user_init;
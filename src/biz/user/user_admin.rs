@@ -0,0 +1,281 @@
+use app_error::AppError;
+use database::admin_audit::{insert_admin_audit_log, select_admin_audit_logs};
+use database::user::{select_email_from_user_uuid, select_users_with_workspace_count};
+use gotrue::params::AdminUserParams;
+use shared_entity::dto::admin_dto::{
+  AdminAuditAction, AdminAuditLog, AdminBulkUserFailure, AdminBulkUserResult, MAX_ADMIN_BULK_USERS,
+};
+use shared_entity::response::AppResponseError;
+use sqlx::PgPool;
+use std::future::Future;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::biz::user::user_delete::delete_user_and_workspaces;
+use crate::state::{GoTrueAdmin, RedisConnectionManager};
+
+/// Gotrue has no permanent ban, so deactivated users are banned for about a hundred years.
+const DEACTIVATE_BAN_DURATION: &str = "876000h";
+const EXPORT_PAGE_SIZE: i64 = 500;
+const DEFAULT_AUDIT_LOG_LIMIT: i64 = 100;
+const MAX_AUDIT_LOG_LIMIT: i64 = 1000;
+
+pub async fn deactivate_users(
+  pg_pool: &PgPool,
+  gotrue_client: &gotrue::api::Client,
+  gotrue_admin: &GoTrueAdmin,
+  actor_uuid: Uuid,
+  user_uuids: Vec<Uuid>,
+) -> Result<AdminBulkUserResult, AppError> {
+  check_bulk_params(actor_uuid, &user_uuids)?;
+  let admin_token = gotrue_admin.token().await?;
+  let result = apply_to_users(
+    pg_pool,
+    actor_uuid,
+    AdminAuditAction::DeactivateUser,
+    user_uuids,
+    |user_uuid| {
+      let admin_token = admin_token.clone();
+      async move {
+        gotrue_client
+          .admin_update_user(
+            &admin_token,
+            &user_uuid.to_string(),
+            &AdminUserParams {
+              ban_duration: DEACTIVATE_BAN_DURATION.to_string(),
+              ..Default::default()
+            },
+          )
+          .await
+          .map_err(AppResponseError::from)?;
+        Ok::<_, AppResponseError>(())
+      }
+    },
+  )
+  .await;
+  Ok(result)
+}
+
+pub async fn delete_users(
+  pg_pool: &PgPool,
+  redis_client: &RedisConnectionManager,
+  gotrue_client: &gotrue::api::Client,
+  gotrue_admin: &GoTrueAdmin,
+  actor_uuid: Uuid,
+  user_uuids: Vec<Uuid>,
+) -> Result<AdminBulkUserResult, AppError> {
+  check_bulk_params(actor_uuid, &user_uuids)?;
+  let result = apply_to_users(
+    pg_pool,
+    actor_uuid,
+    AdminAuditAction::DeleteUser,
+    user_uuids,
+    |user_uuid| {
+      delete_user_and_workspaces(
+        pg_pool,
+        redis_client,
+        gotrue_client,
+        gotrue_admin,
+        user_uuid,
+      )
+    },
+  )
+  .await;
+  Ok(result)
+}
+
+pub async fn send_password_reset_emails(
+  pg_pool: &PgPool,
+  gotrue_client: &gotrue::api::Client,
+  actor_uuid: Uuid,
+  user_uuids: Vec<Uuid>,
+) -> Result<AdminBulkUserResult, AppError> {
+  check_bulk_params(actor_uuid, &user_uuids)?;
+  let result = apply_to_users(
+    pg_pool,
+    actor_uuid,
+    AdminAuditAction::SendPasswordReset,
+    user_uuids,
+    |user_uuid| async move {
+      let email = select_email_from_user_uuid(pg_pool, &user_uuid).await?;
+      gotrue_client
+        .recover(&email, None)
+        .await
+        .map_err(AppResponseError::from)?;
+      Ok::<_, AppResponseError>(())
+    },
+  )
+  .await;
+  Ok(result)
+}
+
+/// Exports all the users as CSV, with the number of workspaces each user owns and belongs to.
+pub async fn export_users_csv(pg_pool: &PgPool, actor_uuid: Uuid) -> Result<String, AppError> {
+  let mut csv =
+    String::from("uid,uuid,email,name,created_at,owned_workspace_count,member_workspace_count\n");
+  let mut after_uid = i64::MIN;
+  let mut user_count = 0;
+  loop {
+    let rows = select_users_with_workspace_count(pg_pool, after_uid, EXPORT_PAGE_SIZE).await?;
+    let Some(last) = rows.last() else {
+      break;
+    };
+    after_uid = last.uid;
+    user_count += rows.len();
+    for row in rows {
+      let created_at = row
+        .created_at
+        .map(|created_at| created_at.to_rfc3339())
+        .unwrap_or_default();
+      csv.push_str(&format!(
+        "{},{},{},{},{},{},{}\n",
+        row.uid,
+        row.uuid,
+        csv_field(&row.email),
+        csv_field(&row.name),
+        created_at,
+        row.owned_workspace_count,
+        row.member_workspace_count,
+      ));
+    }
+  }
+
+  info!("admin {} exported {} users", actor_uuid, user_count);
+  record_audit_log(
+    pg_pool,
+    actor_uuid,
+    AdminAuditAction::ExportUsers,
+    "*",
+    Ok(()),
+    Some(format!("{} users", user_count)),
+  )
+  .await;
+  Ok(csv)
+}
+
+pub async fn list_audit_logs(
+  pg_pool: &PgPool,
+  offset: Option<i64>,
+  limit: Option<i64>,
+) -> Result<Vec<AdminAuditLog>, AppError> {
+  let offset = offset.unwrap_or(0).max(0);
+  let limit = limit
+    .unwrap_or(DEFAULT_AUDIT_LOG_LIMIT)
+    .clamp(1, MAX_AUDIT_LOG_LIMIT);
+  let rows = select_admin_audit_logs(pg_pool, offset, limit).await?;
+  Ok(rows.into_iter().map(AdminAuditLog::from).collect())
+}
+
+fn check_bulk_params(actor_uuid: Uuid, user_uuids: &[Uuid]) -> Result<(), AppError> {
+  if user_uuids.is_empty() {
+    return Err(AppError::InvalidRequest("No user selected".to_string()));
+  }
+  if user_uuids.len() > MAX_ADMIN_BULK_USERS {
+    return Err(AppError::InvalidRequest(format!(
+      "At most {} users can be updated at once",
+      MAX_ADMIN_BULK_USERS
+    )));
+  }
+  if user_uuids.contains(&actor_uuid) {
+    return Err(AppError::InvalidRequest(
+      "Administrators can not apply bulk operations to themselves".to_string(),
+    ));
+  }
+  Ok(())
+}
+
+/// Applies `f` to the users one after the other. A failure does not stop the remaining users
+/// from being processed, and every attempt is recorded in the audit log.
+async fn apply_to_users<F, Fut>(
+  pg_pool: &PgPool,
+  actor_uuid: Uuid,
+  action: AdminAuditAction,
+  mut user_uuids: Vec<Uuid>,
+  f: F,
+) -> AdminBulkUserResult
+where
+  F: Fn(Uuid) -> Fut,
+  Fut: Future<Output = Result<(), AppResponseError>>,
+{
+  user_uuids.sort();
+  user_uuids.dedup();
+
+  let mut result = AdminBulkUserResult::default();
+  for user_uuid in user_uuids {
+    let outcome = f(user_uuid).await;
+    record_audit_log(
+      pg_pool,
+      actor_uuid,
+      action,
+      &user_uuid.to_string(),
+      outcome.as_ref().map(|_| ()).map_err(|err| err.to_string()),
+      None,
+    )
+    .await;
+    match outcome {
+      Ok(()) => result.succeeded.push(user_uuid),
+      Err(err) => {
+        warn!(
+          "admin {} failed to {} {}: {}",
+          actor_uuid,
+          action.as_str(),
+          user_uuid,
+          err
+        );
+        result.failed.push(AdminBulkUserFailure {
+          user_uuid,
+          error: err.message.to_string(),
+        });
+      },
+    }
+  }
+  result
+}
+
+/// The action already happened when it gets recorded, so a failure to write the audit log is
+/// only logged.
+pub(crate) async fn record_audit_log(
+  pg_pool: &PgPool,
+  actor_uuid: Uuid,
+  action: AdminAuditAction,
+  target: &str,
+  outcome: Result<(), String>,
+  detail: Option<String>,
+) {
+  let (succeeded, detail) = match outcome {
+    Ok(()) => (true, detail),
+    Err(err) => (false, Some(err)),
+  };
+  if let Err(err) = insert_admin_audit_log(
+    pg_pool,
+    &actor_uuid,
+    action.as_str(),
+    target,
+    succeeded,
+    detail.as_deref(),
+  )
+  .await
+  {
+    warn!(
+      "failed to record the {} audit log of admin {}: {}",
+      action.as_str(),
+      actor_uuid,
+      err
+    );
+  }
+}
+
+/// Quotes the field if it contains a character that has a meaning in CSV. Fields starting with a
+/// formula character are prefixed with a quote so spreadsheets do not evaluate them.
+fn csv_field(value: &str) -> String {
+  let value = if value.starts_with(['=', '+', '-', '@']) {
+    format!("'{}", value)
+  } else {
+    value.to_string()
+  };
+  if value.contains([',', '"', '\n', '\r']) {
+    format!("\"{}\"", value.replace('"', "\"\""))
+  } else {
+    value
+  }
+}
@@ -0,0 +1,220 @@
+use app_error::AppError;
+use chrono::{Duration, Utc};
+use database::auth_audit::{insert_user_auth_audit_log, select_last_user_auth_action_at};
+use database::user::{select_email_from_user_uuid, select_uuid_from_email};
+use gotrue::params::MagicLinkParams;
+use gotrue_entity::dto::UpdateGotrueUserParams;
+use gotrue_entity::error::GoTrueError;
+use shared_entity::dto::auth_dto::UserAuthAction;
+use sqlx::PgPool;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::domain::user_email::UserEmail;
+
+/// Minimum delay between two emails of the same kind sent to an address. GoTrue limits the emails
+/// per IP address, which is the address of this server for all the requests made through it.
+const EMAIL_COOLDOWN_SECS: i64 = 60;
+
+/// Asks GoTrue to change the email of the user. GoTrue sends a confirmation link to the new
+/// address, the email of the user is updated when the user signs in after opening it.
+pub async fn change_email(
+  pg_pool: &PgPool,
+  gotrue_client: &gotrue::api::Client,
+  access_token: &str,
+  user_uuid: Uuid,
+  new_email: String,
+) -> Result<(), AppError> {
+  let new_email = parse_email(new_email)?;
+  let current_email = select_email_from_user_uuid(pg_pool, &user_uuid).await?;
+  if current_email.eq_ignore_ascii_case(&new_email) {
+    return Err(AppError::InvalidRequest(
+      "The new email is the current email".to_string(),
+    ));
+  }
+  let result = async {
+    check_cooldown(pg_pool, &new_email, UserAuthAction::ChangeEmail).await?;
+    if select_uuid_from_email(pg_pool, &new_email).await?.is_some() {
+      return Err(AppError::UserAlreadyRegistered(format!(
+        "{} is already used by another user",
+        new_email
+      )));
+    }
+    let params = UpdateGotrueUserParams::new().with_opt_email(Some(&new_email));
+    gotrue_client.update_user(access_token, &params).await?;
+    Ok(())
+  }
+  .await;
+  record_auth_audit_log(
+    pg_pool,
+    Some(&user_uuid),
+    &new_email,
+    UserAuthAction::ChangeEmail,
+    &result,
+  )
+  .await;
+  if result.is_ok() {
+    info!("user {} requested to change the email", user_uuid);
+  }
+  result
+}
+
+pub async fn send_password_recovery(
+  pg_pool: &PgPool,
+  gotrue_client: &gotrue::api::Client,
+  email: String,
+  redirect_to: Option<String>,
+) -> Result<(), AppError> {
+  let email = parse_email(email)?;
+  let user_uuid = select_uuid_from_email(pg_pool, &email).await?;
+  let result = async {
+    check_cooldown(pg_pool, &email, UserAuthAction::RecoverPassword).await?;
+    gotrue_client
+      .recover(&email, redirect_to)
+      .await
+      .map_err(recover_error)?;
+    Ok(())
+  }
+  .await;
+  record_auth_audit_log(
+    pg_pool,
+    user_uuid.as_ref(),
+    &email,
+    UserAuthAction::RecoverPassword,
+    &result,
+  )
+  .await;
+  result
+}
+
+/// Sends a link to sign in. The user is created when the email is not registered yet.
+pub async fn send_magic_link(
+  pg_pool: &PgPool,
+  gotrue_client: &gotrue::api::Client,
+  email: String,
+  redirect_to: Option<String>,
+) -> Result<(), AppError> {
+  let email = parse_email(email)?;
+  let user_uuid = select_uuid_from_email(pg_pool, &email).await?;
+  let result = async {
+    check_cooldown(pg_pool, &email, UserAuthAction::MagicLink).await?;
+    gotrue_client
+      .magic_link(
+        &MagicLinkParams {
+          email: email.clone(),
+          ..Default::default()
+        },
+        redirect_to,
+      )
+      .await?;
+    Ok(())
+  }
+  .await;
+  record_auth_audit_log(
+    pg_pool,
+    user_uuid.as_ref(),
+    &email,
+    UserAuthAction::MagicLink,
+    &result,
+  )
+  .await;
+  result
+}
+
+/// GoTrue answers 404 to a password recovery for an email without a user. Other endpoints use 404
+/// for a missing route or a misconfigured GoTrue, which stay internal errors.
+fn recover_error(err: GoTrueError) -> AppError {
+  match err {
+    GoTrueError::Internal(err) if err.code == 404 => AppError::RecordNotFound(err.msg),
+    err => err.into(),
+  }
+}
+
+fn parse_email(email: String) -> Result<String, AppError> {
+  UserEmail::parse(email.trim().to_string())
+    .map(|email| email.0)
+    .map_err(AppError::InvalidEmail)
+}
+
+/// The emails are compared lowercased, as GoTrue does, so that changing the case of an address
+/// doesn't bypass the cooldown.
+async fn check_cooldown(
+  pg_pool: &PgPool,
+  email: &str,
+  action: UserAuthAction,
+) -> Result<(), AppError> {
+  let email = email.to_lowercase();
+  let last_sent_at = select_last_user_auth_action_at(pg_pool, &email, action.as_str()).await?;
+  if let Some(last_sent_at) = last_sent_at {
+    if Utc::now() - last_sent_at < Duration::seconds(EMAIL_COOLDOWN_SECS) {
+      return Err(AppError::TooManyRequests(format!(
+        "an email was sent to {} less than {} seconds ago",
+        email, EMAIL_COOLDOWN_SECS
+      )));
+    }
+  }
+  Ok(())
+}
+
+/// The email was sent, or not, when it gets recorded, so a failure to write the audit log is only
+/// logged.
+async fn record_auth_audit_log(
+  pg_pool: &PgPool,
+  user_uuid: Option<&Uuid>,
+  email: &str,
+  action: UserAuthAction,
+  result: &Result<(), AppError>,
+) {
+  let detail = result.as_ref().err().map(|err| err.to_string());
+  if let Err(err) = insert_user_auth_audit_log(
+    pg_pool,
+    user_uuid,
+    &email.to_lowercase(),
+    action.as_str(),
+    result.is_ok(),
+    detail.as_deref(),
+  )
+  .await
+  {
+    warn!(
+      "failed to record the {} audit log of {}: {}",
+      action.as_str(),
+      email,
+      err
+    );
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use gotrue_entity::error::GoTrueErrorSerde;
+
+  fn gotrue_error(code: i64) -> GoTrueError {
+    GoTrueError::Internal(GoTrueErrorSerde {
+      code,
+      msg: "User not found".to_string(),
+      error_id: None,
+    })
+  }
+
+  #[test]
+  fn recover_not_found_is_record_not_found() {
+    assert!(matches!(
+      recover_error(gotrue_error(404)),
+      AppError::RecordNotFound(_)
+    ));
+    assert!(matches!(
+      recover_error(gotrue_error(429)),
+      AppError::TooManyRequests(_)
+    ));
+  }
+
+  #[test]
+  fn gotrue_not_found_is_not_record_not_found_elsewhere() {
+    assert!(!matches!(
+      AppError::from(gotrue_error(404)),
+      AppError::RecordNotFound(_)
+    ));
+  }
+}
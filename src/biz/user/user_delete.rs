@@ -1,11 +1,9 @@
-use std::sync::Arc;
-
-use crate::state::GoTrueAdmin;
+use crate::state::{GoTrueAdmin, RedisConnectionManager};
 use crate::{biz::workspace::ops::delete_workspace_for_user, config::config::AppleOAuthSetting};
 use app_error::ErrorCode;
 use authentication::jwt::Authorization;
-use database::file::s3_client_impl::S3BucketStorage;
-use database::workspace::{insert_workspace_ids_to_deleted_table, select_user_owned_workspaces_id};
+use database::user::select_uid_from_uuid;
+use database::workspace::select_user_owned_workspaces_id;
 use gotrue::params::AdminDeleteUserParams;
 use secrecy::{ExposeSecret, Secret};
 use shared_entity::response::AppResponseError;
@@ -15,7 +13,7 @@ use uuid::Uuid;
 #[allow(clippy::too_many_arguments)]
 pub async fn delete_user(
   pg_pool: &sqlx::PgPool,
-  bucket_storage: &Arc<S3BucketStorage>,
+  redis_client: &RedisConnectionManager,
   gotrue_client: &gotrue::api::Client,
   gotrue_admin: &GoTrueAdmin,
   apple_oauth: &AppleOAuthSetting,
@@ -37,7 +35,29 @@ pub async fn delete_user(
     };
   }
 
+  delete_user_and_workspaces(
+    pg_pool,
+    redis_client,
+    gotrue_client,
+    gotrue_admin,
+    user_uuid,
+  )
+  .await
+}
+
+/// Deletes the user from gotrue and queues the deletion of all the workspaces the user owns.
+pub async fn delete_user_and_workspaces(
+  pg_pool: &sqlx::PgPool,
+  redis_client: &RedisConnectionManager,
+  gotrue_client: &gotrue::api::Client,
+  gotrue_admin: &GoTrueAdmin,
+  user_uuid: Uuid,
+) -> Result<(), AppResponseError> {
   info!("admin deleting user: {:?}", user_uuid);
+  // The workspaces are selected before the user is deleted, as the user row is gone afterwards.
+  let uid = select_uid_from_uuid(pg_pool, &user_uuid).await?;
+  let workspace_ids = select_user_owned_workspaces_id(pg_pool, &user_uuid).await?;
+
   let admin_token = gotrue_admin.token().await?;
   gotrue_client
     .admin_delete_user(
@@ -50,26 +70,9 @@ pub async fn delete_user(
     .await
     .map_err(AppResponseError::from)?;
 
-  // spawn tasks to delete all workspaces owned by the user
-  let workspace_ids = select_user_owned_workspaces_id(pg_pool, &user_uuid).await?;
-
-  info!(
-    "saving workspaces: {:?} to deleted workspace table",
-    workspace_ids
-  );
-  insert_workspace_ids_to_deleted_table(pg_pool, workspace_ids.clone()).await?;
-
-  let mut tasks = vec![];
+  info!("queueing the deletion of workspaces: {:?}", workspace_ids);
   for workspace_id in workspace_ids {
-    let cloned_pg_pool = pg_pool.clone();
-    tasks.push(tokio::spawn(delete_workspace_for_user(
-      cloned_pg_pool,
-      workspace_id,
-      bucket_storage.clone(),
-    )));
-  }
-  for task in tasks {
-    task.await??;
+    delete_workspace_for_user(pg_pool, redis_client, uid, workspace_id).await?;
   }
 
   Ok(())
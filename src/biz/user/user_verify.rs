@@ -5,7 +5,8 @@ use std::time::Instant;
 use tracing::{event, instrument, trace};
 
 use app_error::AppError;
-use database::user::{create_user, is_user_exist};
+use database::collab_guest::accept_collab_guest_invitations;
+use database::user::{create_user, is_user_exist, select_email_from_user_uuid, update_user};
 use database::workspace::select_workspace;
 use database_entity::dto::AFRole;
 use workspace_template::document::getting_started::GettingStartedTemplate;
@@ -23,54 +24,87 @@ pub async fn verify_token(access_token: &str, state: &AppState) -> Result<bool,
   let name = name_from_user_metadata(&user.user_metadata);
 
   // Create new user if it doesn't exist
+  let is_new = !is_user_exist(&state.pg_pool, &user_uuid).await?;
+  if is_new {
+    create_new_user(state, &user_uuid, &user.email, &name).await?;
+  } else {
+    trace!("user already exists:{},{}", user.id, user.email);
+    // The email of the user only changes in GoTrue once the user confirmed the email change.
+    let email = select_email_from_user_uuid(&state.pg_pool, &user_uuid).await?;
+    if !user.email.is_empty() && email != user.email {
+      update_user(
+        &state.pg_pool,
+        &user_uuid,
+        None,
+        Some(user.email.clone()),
+        None,
+      )
+      .await?;
+      event!(tracing::Level::INFO, "user {} changed the email", user_uuid);
+    }
+  }
+
+  Ok(is_new)
+}
+
+/// Creates the user and its first workspace, initialized with the GetStarted template.
+/// Returns the uid and the id of the workspace.
+pub async fn create_new_user(
+  state: &AppState,
+  user_uuid: &uuid::Uuid,
+  email: &str,
+  name: &str,
+) -> Result<(i64, uuid::Uuid), AppError> {
   let mut txn = state
     .pg_pool
     .begin()
     .await
-    .context("acquire transaction to verify token")?;
-
-  let is_new = !is_user_exist(txn.deref_mut(), &user_uuid).await?;
-  if is_new {
-    let new_uid = state.id_gen.write().await.next_id();
-    event!(tracing::Level::INFO, "create new user:{}", new_uid);
-    let workspace_id =
-      create_user(txn.deref_mut(), new_uid, &user_uuid, &user.email, &name).await?;
-    let workspace_row = select_workspace(txn.deref_mut(), &workspace_id).await?;
-
-    // It's essential to cache the user's role because subsequent actions will rely on this cached information.
-    state
-      .workspace_access_control
-      .insert_role(&new_uid, &workspace_id, AFRole::Owner)
-      .await?;
-    // Need to commit the transaction for the record in `af_user` to be inserted
-    // so that `initialize_workspace_for_user` will be able to find the user
-    txn
-      .commit()
-      .await
-      .context("fail to commit transaction to verify token")?;
-
-    // Create a workspace with the GetStarted template
-    let mut txn2 = state.pg_pool.begin().await?;
-    let start = Instant::now();
-    initialize_workspace_for_user(
+    .context("acquire transaction to create user")?;
+  let new_uid = state.id_gen.write().await.next_id();
+  event!(tracing::Level::INFO, "create new user:{}", new_uid);
+  let workspace_id = create_user(txn.deref_mut(), new_uid, user_uuid, email, name).await?;
+  let workspace_row = select_workspace(txn.deref_mut(), &workspace_id).await?;
+  // The pages shared with the email before it had an account become accessible to the user.
+  let shared_collabs = accept_collab_guest_invitations(txn.deref_mut(), new_uid, email).await?;
+  if shared_collabs > 0 {
+    event!(
+      tracing::Level::INFO,
+      "user {} is a guest of {} collabs",
       new_uid,
-      &user_uuid,
-      &workspace_row,
-      &mut txn2,
-      vec![GettingStartedTemplate],
-      &state.collab_access_control_storage,
-    )
-    .await?;
-    txn2
-      .commit()
-      .await
-      .context("fail to commit transaction to initialize workspace")?;
-    state.metrics.collab_metrics.observe_pg_tx(start.elapsed());
-  } else {
-    trace!("user already exists:{},{}", user.id, user.email);
+      shared_collabs
+    );
   }
 
-  Ok(is_new)
+  // It's essential to cache the user's role because subsequent actions will rely on this cached information.
+  state
+    .workspace_access_control
+    .insert_role(&new_uid, &workspace_id, AFRole::Owner)
+    .await?;
+  // Need to commit the transaction for the record in `af_user` to be inserted
+  // so that `initialize_workspace_for_user` will be able to find the user
+  txn
+    .commit()
+    .await
+    .context("fail to commit transaction to create user")?;
+
+  // Create a workspace with the GetStarted template
+  let mut txn2 = state.pg_pool.begin().await?;
+  let start = Instant::now();
+  initialize_workspace_for_user(
+    new_uid,
+    user_uuid,
+    &workspace_row,
+    &mut txn2,
+    vec![GettingStartedTemplate],
+    &state.collab_access_control_storage,
+  )
+  .await?;
+  txn2
+    .commit()
+    .await
+    .context("fail to commit transaction to initialize workspace")?;
+  state.metrics.collab_metrics.observe_pg_tx(start.elapsed());
+  Ok((new_uid, workspace_id))
 }
 
 // Best effort to get user's name after oauth
@@ -0,0 +1,165 @@
+use std::sync::Arc;
+
+use access_control::workspace::WorkspaceAccessControl;
+use anyhow::anyhow;
+use app_error::AppError;
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use database::pg_row::AFWorkspaceCloneTaskRow;
+use database::resource_usage::get_workspace_usage_size;
+use database::workspace::{
+  select_workspace_member_list, select_workspace_name_from_workspace_id,
+  select_workspace_total_collab_bytes, upsert_workspace_member,
+};
+use database::workspace_clone::{
+  insert_workspace_clone_task, is_workspace_clone_pending, select_workspace_clone_task,
+};
+use redis::AsyncCommands;
+use serde_json::json;
+use shared_entity::dto::workspace_dto::{CloneWorkspaceParams, WorkspaceCloneTask};
+use shared_entity::response::AppResponseError;
+use sqlx::PgPool;
+use tracing::info;
+use uuid::Uuid;
+
+use super::ops::create_empty_workspace;
+use crate::state::RedisConnectionManager;
+
+const WORKSPACE_CLONE_STREAM: &str = "workspace_clone_task_stream";
+
+/// Creates an empty workspace owned by the user and queues a task for the worker to copy the
+/// collabs and the files of the source workspace into it. The members of the source workspace
+/// are added right away when requested, the clone stays hidden until the worker is done. The
+/// progress can be polled with [get_workspace_clone_task].
+#[allow(clippy::too_many_arguments)]
+pub async fn clone_workspace(
+  pg_pool: &PgPool,
+  workspace_access_control: Arc<dyn WorkspaceAccessControl>,
+  collab_storage: &Arc<CollabAccessControlStorage>,
+  redis_client: &RedisConnectionManager,
+  max_size: u64,
+  uid: i64,
+  user_uuid: &Uuid,
+  workspace_id: Uuid,
+  params: CloneWorkspaceParams,
+) -> Result<WorkspaceCloneTask, AppResponseError> {
+  if is_workspace_clone_pending(pg_pool, &workspace_id).await? {
+    return Err(
+      AppError::Conflict(format!(
+        "a clone of the workspace {} is already in progress",
+        workspace_id
+      ))
+      .into(),
+    );
+  }
+  let collab_size = match select_workspace_total_collab_bytes(pg_pool, &workspace_id).await {
+    Ok(size) => size.max(0) as u64,
+    Err(AppError::RecordNotFound(_)) => 0,
+    Err(err) => return Err(err.into()),
+  };
+  let size = collab_size + get_workspace_usage_size(pg_pool, &workspace_id).await?;
+  if size > max_size {
+    return Err(
+      AppError::PayloadTooLarge(format!(
+        "the workspace is {} bytes, only workspaces up to {} bytes can be cloned",
+        size, max_size
+      ))
+      .into(),
+    );
+  }
+
+  let workspace_name = match params.workspace_name.map(|name| name.trim().to_string()) {
+    Some(name) if !name.is_empty() => name,
+    _ => {
+      let source_name = select_workspace_name_from_workspace_id(pg_pool, &workspace_id)
+        .await?
+        .unwrap_or_default();
+      format!("{} (Copy)", source_name)
+    },
+  };
+  let workspace = create_empty_workspace(
+    pg_pool,
+    workspace_access_control.clone(),
+    collab_storage,
+    user_uuid,
+    uid,
+    &workspace_name,
+  )
+  .await?;
+  let target_workspace_id = workspace.workspace_id;
+
+  if params.include_members {
+    let members = select_workspace_member_list(pg_pool, &workspace_id).await?;
+    for member in members.into_iter().filter(|member| member.uid != uid) {
+      upsert_workspace_member(
+        pg_pool,
+        &target_workspace_id,
+        &member.email,
+        member.role.clone(),
+      )
+      .await?;
+      workspace_access_control
+        .insert_role(&member.uid, &target_workspace_id, member.role)
+        .await?;
+    }
+  }
+
+  let task_id = Uuid::new_v4();
+  insert_workspace_clone_task(
+    pg_pool,
+    &task_id,
+    &workspace_id,
+    &target_workspace_id,
+    uid,
+    params.include_members,
+  )
+  .await?;
+  let task = json!({
+    "task_id": task_id,
+    "uid": uid,
+    "source_workspace_id": workspace_id,
+    "target_workspace_id": target_workspace_id,
+  });
+  let _: () = redis_client
+    .clone()
+    .xadd(WORKSPACE_CLONE_STREAM, "*", &[("task", task.to_string())])
+    .await
+    .map_err(|err| AppError::Internal(anyhow!("Failed to push task to Redis stream: {}", err)))?;
+  info!(
+    "User:{} clone workspace:{} into:{}",
+    uid, workspace_id, target_workspace_id
+  );
+
+  let task = select_workspace_clone_task(pg_pool, &task_id).await?;
+  Ok(workspace_clone_task(task))
+}
+
+pub async fn get_workspace_clone_task(
+  pg_pool: &PgPool,
+  uid: i64,
+  workspace_id: &Uuid,
+  task_id: &Uuid,
+) -> Result<WorkspaceCloneTask, AppError> {
+  let task = select_workspace_clone_task(pg_pool, task_id).await?;
+  if task.uid != uid || &task.source_workspace_id != workspace_id {
+    return Err(AppError::RecordNotFound(format!(
+      "workspace clone task {} not found",
+      task_id
+    )));
+  }
+  Ok(workspace_clone_task(task))
+}
+
+fn workspace_clone_task(task: AFWorkspaceCloneTaskRow) -> WorkspaceCloneTask {
+  WorkspaceCloneTask {
+    task_id: task.task_id,
+    source_workspace_id: task.source_workspace_id,
+    target_workspace_id: task.target_workspace_id,
+    status: task.status,
+    total_collabs: task.total_collabs,
+    cloned_collabs: task.cloned_collabs,
+    total_blobs: task.total_blobs,
+    cloned_blobs: task.cloned_blobs,
+    error: task.error,
+    created_at: task.created_at,
+  }
+}
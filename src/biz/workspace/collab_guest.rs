@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use access_control::act::Action;
+use access_control::collab_guest::{CollabGuestCache, GuestAccess};
+use access_control::restricted_view::RestrictedViewCache;
+use access_control::workspace::WorkspaceAccessControl;
+use app_error::AppError;
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use database::collab::GetCollabOrigin;
+use database::collab_guest::{
+  convert_collab_guest_to_member, delete_collab_guest_invitation, select_collab_guests,
+  select_collab_members, upsert_collab_guest_invitation, AFCollabGuestRow, AFCollabMemberRow,
+};
+use database::user::{select_name_from_uuid, select_uid_from_uuid, select_uuid_from_email};
+use database::workspace::{select_workspace_member_list, select_workspace_name_from_workspace_id};
+use database_entity::dto::{AFAccessLevel, AFRole};
+use gotrue::params::{GenerateLinkParams, GenerateLinkType};
+use shared_entity::dto::workspace_dto::{
+  AFCollabGuest, AFCollabMemberInfo, AFCollabMemberSource, InviteCollabGuestParams,
+};
+use sqlx::PgPool;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::biz::billing::ops::check_member_limit;
+use crate::biz::collab::utils::get_latest_collab_folder;
+use crate::biz::notification::push::PushMessage;
+use crate::biz::notification::{NotificationCenter, NotificationPriority};
+use crate::config::config::BillingSetting;
+use crate::mailer::{AFCloudMailer, CollabGuestInvitationMailerParam};
+use crate::state::GoTrueAdmin;
+
+/// Checks that the user can read the workspace. A user who is not a member of the workspace but
+/// is a guest of some of its collabs is let through: the access of the guest is returned, so that
+/// the caller only shows the collabs shared with the user.
+pub async fn enforce_read_or_guest_access(
+  workspace_access_control: &Arc<dyn WorkspaceAccessControl>,
+  collab_guest_cache: &CollabGuestCache,
+  uid: i64,
+  workspace_id: &Uuid,
+) -> Result<Option<Arc<GuestAccess>>, AppError> {
+  match workspace_access_control
+    .enforce_action(&uid, &workspace_id.to_string(), Action::Read)
+    .await
+  {
+    Ok(()) => Ok(None),
+    Err(err) if err.is_not_enough_permissions() => {
+      let guest_access = collab_guest_cache.get(uid, workspace_id).await?;
+      if guest_access.is_empty() {
+        Err(err)
+      } else {
+        Ok(Some(guest_access))
+      }
+    },
+    Err(err) => Err(err),
+  }
+}
+
+pub async fn list_collab_guests(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  object_id: &str,
+) -> Result<Vec<AFCollabGuest>, AppError> {
+  let rows = select_collab_guests(pg_pool, workspace_id, object_id).await?;
+  Ok(rows.into_iter().map(collab_guest_from_row).collect())
+}
+
+/// The users who can access the collab, with their access level and where it comes from. The
+/// members of the workspace who can't see the page because it, or one of its ancestors, is a
+/// restricted view are left out.
+pub async fn list_collab_members(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  restricted_view_cache: &RestrictedViewCache,
+  uid: i64,
+  workspace_id: &Uuid,
+  object_id: &str,
+) -> Result<Vec<AFCollabMemberInfo>, AppError> {
+  let folder = get_latest_collab_folder(
+    collab_storage,
+    GetCollabOrigin::Server,
+    &workspace_id.to_string(),
+  )
+  .await?;
+  let restricted_views = restricted_view_cache.get(workspace_id).await?;
+  let can_access_view = |uid: i64| {
+    restricted_views.can_access_view(uid, object_id, |view_id| {
+      folder
+        .get_view(view_id)
+        .map(|view| view.parent_view_id.clone())
+    })
+  };
+  let rows = select_collab_members(pg_pool, workspace_id, object_id).await?;
+  let members: Vec<AFCollabMemberInfo> = rows
+    .into_iter()
+    .filter_map(|row| {
+      let workspace_access_level = row
+        .role
+        .map(|role| AFAccessLevel::from(&AFRole::from(role)))
+        .filter(|_| can_access_view(row.uid));
+      collab_member_from_row(row, workspace_access_level)
+    })
+    .collect();
+  // the user can read the collab, unless it's hidden by a restricted view
+  if !members.iter().any(|member| member.uid == uid) {
+    return Err(AppError::NotEnoughPermissions);
+  }
+  Ok(members)
+}
+
+/// Shares the page with the email, without making it a member of the workspace. An email that
+/// has no account yet gets access once it signs up.
+#[allow(clippy::too_many_arguments)]
+pub async fn invite_collab_guest(
+  mailer: &AFCloudMailer,
+  notification_center: &NotificationCenter,
+  gotrue_admin: &GoTrueAdmin,
+  gotrue_client: &gotrue::api::Client,
+  collab_storage: &CollabAccessControlStorage,
+  pg_pool: &PgPool,
+  collab_guest_cache: &CollabGuestCache,
+  appflowy_web_url: Option<&str>,
+  inviter: &Uuid,
+  workspace_id: &Uuid,
+  object_id: &str,
+  params: InviteCollabGuestParams,
+) -> Result<(), AppError> {
+  let email = params.email.trim().to_lowercase();
+  if email.is_empty() {
+    return Err(AppError::InvalidRequest("email is empty".to_string()));
+  }
+  let is_member = select_workspace_member_list(pg_pool, workspace_id)
+    .await?
+    .iter()
+    .any(|member| member.email.to_lowercase() == email);
+  if is_member {
+    return Err(AppError::InvalidRequest(format!(
+      "User with email {} is already a member of the workspace",
+      email
+    )));
+  }
+  let folder = get_latest_collab_folder(
+    collab_storage,
+    GetCollabOrigin::Server,
+    &workspace_id.to_string(),
+  )
+  .await?;
+  let page_name = folder
+    .get_view(object_id)
+    .map(|view| view.name.clone())
+    .ok_or_else(|| AppError::RecordNotFound(format!("view {} not found", object_id)))?;
+
+  let inviter_uid = select_uid_from_uuid(pg_pool, inviter).await?;
+  let invitee_uid = match select_uuid_from_email(pg_pool, &email).await? {
+    Some(invitee_uuid) => Some(select_uid_from_uuid(pg_pool, &invitee_uuid).await?),
+    None => None,
+  };
+  match invitee_uid {
+    Some(invitee_uid) => {
+      collab_guest_cache
+        .share(workspace_id, object_id, invitee_uid, params.access_level)
+        .await?
+    },
+    None => {
+      upsert_collab_guest_invitation(
+        pg_pool,
+        workspace_id,
+        object_id,
+        &email,
+        params.access_level,
+        inviter_uid,
+      )
+      .await?;
+      info!(
+        "collab {} of workspace {} shared with {}, pending sign up",
+        object_id, workspace_id, email
+      );
+    },
+  }
+
+  let inviter_name = select_name_from_uuid(pg_pool, inviter).await?;
+  let workspace_name = select_workspace_name_from_workspace_id(pg_pool, workspace_id)
+    .await?
+    .unwrap_or_default();
+  if let Some(invitee_uid) = invitee_uid {
+    notification_center.notify(
+      invitee_uid,
+      NotificationPriority::High,
+      PushMessage {
+        title: "Page shared".to_string(),
+        body: format!("{} shared {} with you", inviter_name, page_name),
+        data: HashMap::from([
+          ("workspace_id".to_string(), workspace_id.to_string()),
+          ("view_id".to_string(), object_id.to_string()),
+        ]),
+      },
+    );
+  }
+  if params.skip_email_send {
+    return Ok(());
+  }
+
+  let open_url = match appflowy_web_url {
+    Some(appflowy_web_url) => format!("{}/app/{}/{}", appflowy_web_url, workspace_id, object_id),
+    None => {
+      let admin_token = gotrue_admin.token().await?;
+      gotrue_client
+        .admin_generate_link(
+          &admin_token,
+          &GenerateLinkParams {
+            type_: GenerateLinkType::MagicLink,
+            email: email.clone(),
+            ..Default::default()
+          },
+        )
+        .await?
+        .action_link
+    },
+  };
+  let mailer = mailer.clone();
+  let access_level = access_level_description(params.access_level);
+  tokio::spawn(async move {
+    let result = mailer
+      .send_collab_guest_invitation(
+        &email,
+        CollabGuestInvitationMailerParam {
+          username: inviter_name,
+          workspace_name,
+          page_name,
+          access_level,
+          open_url,
+        },
+      )
+      .await;
+    if let Err(err) = result {
+      error!(
+        "Failed to send guest invitation email to {}: {}",
+        email, err
+      );
+    }
+  });
+  Ok(())
+}
+
+/// Stops sharing the page with the email, whether it signed up or not.
+pub async fn remove_collab_guest(
+  pg_pool: &PgPool,
+  collab_guest_cache: &CollabGuestCache,
+  workspace_id: &Uuid,
+  object_id: &str,
+  email: &str,
+) -> Result<(), AppError> {
+  let mut removed = delete_collab_guest_invitation(pg_pool, workspace_id, object_id, email).await?;
+  if let Some(uuid) = select_uuid_from_email(pg_pool, email).await? {
+    let uid = select_uid_from_uuid(pg_pool, &uuid).await?;
+    removed |= collab_guest_cache
+      .unshare(workspace_id, object_id, uid)
+      .await?;
+  }
+  if !removed {
+    return Err(AppError::RecordNotFound(format!(
+      "{} is not a guest of {}",
+      email, object_id
+    )));
+  }
+  Ok(())
+}
+
+/// Makes a guest a member of the workspace. The pages shared with the guest are no longer needed,
+/// the member role gives access to the whole workspace.
+pub async fn convert_guest_to_member(
+  pg_pool: &PgPool,
+  billing: &BillingSetting,
+  workspace_access_control: &Arc<dyn WorkspaceAccessControl>,
+  collab_guest_cache: &CollabGuestCache,
+  workspace_id: &Uuid,
+  guest_uid: i64,
+) -> Result<(), AppError> {
+  check_member_limit(pg_pool, billing, workspace_id, 1).await?;
+  let mut txn = pg_pool.begin().await?;
+  if !convert_collab_guest_to_member(&mut txn, workspace_id, guest_uid, AFRole::Member).await? {
+    return Err(AppError::RecordNotFound(format!(
+      "user {} is not a guest of workspace {}",
+      guest_uid, workspace_id
+    )));
+  }
+  txn.commit().await?;
+  workspace_access_control
+    .insert_role(&guest_uid, workspace_id, AFRole::Member)
+    .await?;
+  collab_guest_cache.invalidate(guest_uid, workspace_id);
+  info!(
+    "guest {} of workspace {} converted to member",
+    guest_uid, workspace_id
+  );
+  Ok(())
+}
+
+fn access_level_description(access_level: AFAccessLevel) -> String {
+  match access_level {
+    AFAccessLevel::ReadOnly => "Can view",
+    AFAccessLevel::ReadAndComment => "Can comment",
+    AFAccessLevel::ReadAndWrite => "Can edit",
+    AFAccessLevel::FullAccess => "Full access",
+  }
+  .to_string()
+}
+
+/// Keeps the highest of the access level given by the role of the user in the workspace and the
+/// one the collab was shared with, the role wins a tie. None when the user has neither.
+fn collab_member_from_row(
+  row: AFCollabMemberRow,
+  workspace_access_level: Option<AFAccessLevel>,
+) -> Option<AFCollabMemberInfo> {
+  let direct_access_level = row.access_level.map(AFAccessLevel::from);
+  let (access_level, source) = match (workspace_access_level, direct_access_level) {
+    (Some(workspace), Some(direct)) if direct as i32 > workspace as i32 => {
+      (direct, AFCollabMemberSource::Direct)
+    },
+    (Some(workspace), _) => (workspace, AFCollabMemberSource::Workspace),
+    (None, Some(direct)) => (direct, AFCollabMemberSource::Direct),
+    (None, None) => return None,
+  };
+  Some(AFCollabMemberInfo {
+    uid: row.uid,
+    uuid: row.uuid,
+    name: row.name,
+    email: row.email,
+    avatar_url: row.avatar_url,
+    access_level,
+    source,
+  })
+}
+
+fn collab_guest_from_row(row: AFCollabGuestRow) -> AFCollabGuest {
+  AFCollabGuest {
+    uid: row.uid,
+    name: row.name,
+    email: row.email,
+    access_level: AFAccessLevel::from(row.access_level),
+    created_at: row.created_at,
+  }
+}
@@ -0,0 +1,172 @@
+use crate::mailer::{AFCloudMailer, UploadQuarantinedMailerParam};
+use access_control::act::Action;
+use access_control::workspace::WorkspaceAccessControl;
+use app_error::AppError;
+use database::file::{blob_parent_dir, BlobBucketStorage};
+use database::pg_row::AFBlobStatus;
+use database::resource_usage::{select_workspace_files, update_quarantined_blobs_to_ok};
+use database::user::select_name_from_uuid;
+use database::workspace::select_workspace;
+use shared_entity::dto::workspace_dto::{
+  ApprovedWorkspaceFiles, DeletedWorkspaceFiles, ListWorkspaceFilesQuery, WorkspaceFile,
+  WorkspaceFiles,
+};
+use sqlx::PgPool;
+use tracing::{error, warn};
+use uuid::Uuid;
+
+const DEFAULT_FILES_LIMIT: u32 = 100;
+const MAX_FILES_LIMIT: u32 = 1000;
+const MAX_DELETE_FILES: usize = 1000;
+const MAX_APPROVE_FILES: usize = 1000;
+
+pub async fn list_workspace_files(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  query: ListWorkspaceFilesQuery,
+) -> Result<WorkspaceFiles, AppError> {
+  let limit = query
+    .limit
+    .unwrap_or(DEFAULT_FILES_LIMIT)
+    .clamp(1, MAX_FILES_LIMIT);
+  let rows = select_workspace_files(
+    pg_pool,
+    workspace_id,
+    query.prefix.as_deref().unwrap_or_default(),
+    query.cursor.as_deref(),
+    query.quarantined.map(|quarantined| {
+      if quarantined {
+        AFBlobStatus::Quarantined
+      } else {
+        AFBlobStatus::Ok
+      }
+    }),
+    limit as i64,
+  )
+  .await?;
+  let next_cursor = if rows.len() == limit as usize {
+    rows.last().map(|row| row.file_id.clone())
+  } else {
+    None
+  };
+  let files = rows
+    .into_iter()
+    .map(|row| WorkspaceFile {
+      object_id: blob_parent_dir(&row.file_id).map(|dir| dir.to_string()),
+      file_id: row.file_id,
+      file_type: row.file_type,
+      file_size: row.file_size,
+      modified_at: row.modified_at,
+      uploaded_by: row.uploaded_by,
+      uploader_name: row.uploader_name,
+      quarantined: AFBlobStatus::from(row.status) == AFBlobStatus::Quarantined,
+    })
+    .collect();
+  Ok(WorkspaceFiles { files, next_cursor })
+}
+
+pub async fn delete_workspace_files(
+  bucket_storage: &BlobBucketStorage,
+  workspace_id: &Uuid,
+  file_ids: &[String],
+) -> Result<DeletedWorkspaceFiles, AppError> {
+  if file_ids.len() > MAX_DELETE_FILES {
+    return Err(AppError::InvalidRequest(format!(
+      "at most {} files can be deleted at once",
+      MAX_DELETE_FILES
+    )));
+  }
+  let deleted = bucket_storage.delete_blobs(workspace_id, file_ids).await?;
+  let freed_bytes = deleted.iter().map(|(_, size)| size).sum();
+  Ok(DeletedWorkspaceFiles {
+    file_ids: deleted.into_iter().map(|(file_id, _)| file_id).collect(),
+    freed_bytes,
+  })
+}
+
+/// Returns the status of the blobs uploaded by the user. The members that can write to the
+/// workspace upload blobs as usual, while the uploads of the guests, who can only read, are
+/// quarantined until a member approves them.
+pub async fn upload_blob_status(
+  workspace_access_control: &dyn WorkspaceAccessControl,
+  uid: &i64,
+  workspace_id: &Uuid,
+) -> Result<AFBlobStatus, AppError> {
+  let workspace_id = workspace_id.to_string();
+  match workspace_access_control
+    .enforce_action(uid, &workspace_id, Action::Write)
+    .await
+  {
+    Ok(()) => Ok(AFBlobStatus::Ok),
+    Err(err) if err.is_not_enough_permissions() => {
+      workspace_access_control
+        .enforce_action(uid, &workspace_id, Action::Read)
+        .await?;
+      Ok(AFBlobStatus::Quarantined)
+    },
+    Err(err) => Err(err),
+  }
+}
+
+/// Lets the owner of the workspace know that a file is waiting for review. The email is sent in
+/// the background and only logged on failure.
+pub fn notify_upload_quarantined(
+  pg_pool: PgPool,
+  mailer: AFCloudMailer,
+  appflowy_web_url: Option<String>,
+  workspace_id: Uuid,
+  uploader_uuid: Uuid,
+  file_id: String,
+  file_size: usize,
+) {
+  let Some(appflowy_web_url) = appflowy_web_url else {
+    warn!(
+      "AppFlowy web url has not been set, skip notifying the quarantined upload {} of workspace {}",
+      file_id, workspace_id
+    );
+    return;
+  };
+  tokio::spawn(async move {
+    let result = async {
+      let workspace = select_workspace(&pg_pool, &workspace_id).await?;
+      let username = select_name_from_uuid(&pg_pool, &uploader_uuid).await?;
+      let param = UploadQuarantinedMailerParam {
+        username,
+        workspace_name: workspace.workspace_name.unwrap_or_default(),
+        file_name: file_id,
+        file_size,
+        review_url: format!(
+          "{}/app/{}/files?quarantined=true",
+          appflowy_web_url, workspace_id
+        ),
+      };
+      mailer
+        .send_upload_quarantined_notification(
+          &workspace.owner_name.unwrap_or_default(),
+          &workspace.owner_email.unwrap_or_default(),
+          param,
+        )
+        .await
+        .map_err(AppError::Internal)
+    }
+    .await;
+    if let Err(err) = result {
+      error!("Failed to send quarantined upload email: {:?}", err);
+    }
+  });
+}
+
+pub async fn approve_workspace_files(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  file_ids: &[String],
+) -> Result<ApprovedWorkspaceFiles, AppError> {
+  if file_ids.len() > MAX_APPROVE_FILES {
+    return Err(AppError::InvalidRequest(format!(
+      "at most {} files can be approved at once",
+      MAX_APPROVE_FILES
+    )));
+  }
+  let file_ids = update_quarantined_blobs_to_ok(pg_pool, workspace_id, file_ids).await?;
+  Ok(ApprovedWorkspaceFiles { file_ids })
+}
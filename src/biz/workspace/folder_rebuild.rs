@@ -0,0 +1,416 @@
+use std::collections::{HashMap, HashSet};
+
+use app_error::AppError;
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use collab::preclude::Collab;
+use collab_database::rows::{meta_id_from_row_id, RowMetaKey};
+use collab_database::views::DatabaseLayout;
+use collab_entity::CollabType;
+use collab_folder::hierarchy_builder::NestedChildViewBuilder;
+use collab_folder::{
+  timestamp, CollabOrigin, Folder, FolderData, RepeatedViewIdentifier, SectionItem, SpaceInfo,
+  View, ViewIdentifier, ViewLayout, Workspace,
+};
+use database::collab::{select_workspace_collab_oids_by_type, CollabStorage, GetCollabOrigin};
+use database::workspace::select_workspace;
+use database_entity::dto::CollabParams;
+use shared_entity::dto::workspace_dto::RebuiltWorkspaceFolder;
+use sqlx::PgPool;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::biz::collab::checkpoint::create_collab_checkpoint;
+use crate::biz::collab::folder_view::check_if_view_is_space;
+use crate::biz::collab::ops::get_latest_workspace_database;
+use crate::biz::collab::utils::{
+  get_latest_collab_database_body, get_latest_collab_folder, DEFAULT_SPACE_ICON,
+  DEFAULT_SPACE_ICON_COLOR,
+};
+
+/// A view whose content is stored in its own collab.
+struct SourceView {
+  id: String,
+  name: String,
+  layout: ViewLayout,
+  /// The view the source view belongs to when it is not in the folder, e.g. the first view of
+  /// the database for the other views of the same database.
+  parent_id: Option<String>,
+}
+
+/// Rebuilds the folder of the workspace from the collabs of the workspace, for when the folder
+/// collab is corrupted and the workspace can't be opened anymore.
+///
+/// The documents of the workspace and the views of the databases registered in the workspace
+/// database are the source of truth. The views of the current folder are kept, with their names,
+/// icons and position, as long as it can still be decoded and their collab still exists. The
+/// views that are not reachable from the workspace anymore are added to a new space.
+///
+/// The state before the rebuild is saved as a checkpoint of the folder when it can still be read,
+/// and the new state replaces the stored one. Clients that have the workspace open have to reopen
+/// it to pick up the new folder.
+pub async fn rebuild_workspace_folder(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  uid: i64,
+  workspace_id: Uuid,
+) -> Result<RebuiltWorkspaceFolder, AppError> {
+  let workspace_id_str = workspace_id.to_string();
+  let backup_checkpoint_id = match create_collab_checkpoint(
+    collab_storage,
+    uid,
+    &workspace_id_str,
+    &workspace_id_str,
+    "Before rebuilding the folder".to_string(),
+    CollabType::Folder,
+  )
+  .await
+  {
+    Ok(checkpoint) => Some(checkpoint.checkpoint_id),
+    Err(err) => {
+      warn!(
+        "[Folder Rebuild] unable to back up the folder of workspace {}: {}",
+        workspace_id, err
+      );
+      None
+    },
+  };
+  let current_data = match get_latest_collab_folder(
+    collab_storage,
+    GetCollabOrigin::User { uid },
+    &workspace_id_str,
+  )
+  .await
+  {
+    Ok(folder) => folder.get_folder_data(&workspace_id_str),
+    Err(err) => {
+      warn!(
+        "[Folder Rebuild] unable to open the folder of workspace {}, rebuilding it from scratch: {}",
+        workspace_id, err
+      );
+      None
+    },
+  };
+
+  let source_views = list_source_views(pg_pool, collab_storage, workspace_id).await?;
+  let workspace_name = select_workspace(pg_pool, &workspace_id)
+    .await?
+    .workspace_name
+    .or_else(|| {
+      current_data
+        .as_ref()
+        .map(|data| data.workspace.name.clone())
+    })
+    .unwrap_or_else(|| "Workspace".to_string());
+  let (folder_data, recovered_views) = rebuild_folder_data(
+    uid,
+    &workspace_id_str,
+    workspace_name,
+    current_data,
+    source_views,
+  );
+  let views = folder_data.views.len();
+
+  let collab = Collab::new_with_origin(CollabOrigin::Empty, &workspace_id_str, vec![], false);
+  let folder = Folder::create(uid, collab, None, folder_data);
+  let encoded_collab_v1 = folder
+    .encode_collab_v1(|collab| CollabType::Folder.validate_require_data(collab))
+    .map_err(|err| AppError::Internal(err.into()))?
+    .encode_to_bytes()
+    .map_err(|err| AppError::Internal(anyhow::Error::from(err)))?;
+  let mut txn = pg_pool.begin().await?;
+  collab_storage
+    .upsert_new_collab_with_transaction(
+      &workspace_id_str,
+      &uid,
+      CollabParams {
+        object_id: workspace_id_str.clone(),
+        encoded_collab_v1: encoded_collab_v1.into(),
+        collab_type: CollabType::Folder,
+      },
+      &mut txn,
+      "rebuild workspace folder",
+    )
+    .await?;
+  txn.commit().await?;
+  info!(
+    "[Folder Rebuild] rebuilt the folder of workspace {}: {} views, {} recovered",
+    workspace_id, views, recovered_views
+  );
+
+  Ok(RebuiltWorkspaceFolder {
+    views,
+    recovered_views,
+    backup_checkpoint_id,
+  })
+}
+
+/// Lists the documents of the workspace, except for the documents of database rows, followed by
+/// the views of the databases of the workspace.
+async fn list_source_views(
+  pg_pool: &PgPool,
+  collab_storage: &CollabAccessControlStorage,
+  workspace_id: Uuid,
+) -> Result<Vec<SourceView>, AppError> {
+  let workspace_id_str = workspace_id.to_string();
+  let row_documents: HashSet<String> =
+    select_workspace_collab_oids_by_type(pg_pool, &workspace_id, &CollabType::DatabaseRow)
+      .await?
+      .iter()
+      .filter_map(|row_id| Uuid::parse_str(row_id).ok())
+      .map(|row_id| meta_id_from_row_id(&row_id, RowMetaKey::DocumentId))
+      .collect();
+  let mut source_views: Vec<SourceView> =
+    select_workspace_collab_oids_by_type(pg_pool, &workspace_id, &CollabType::Document)
+      .await?
+      .into_iter()
+      .filter(|oid| !row_documents.contains(oid))
+      .map(|oid| SourceView {
+        id: oid,
+        name: String::new(),
+        layout: ViewLayout::Document,
+        parent_id: None,
+      })
+      .collect();
+
+  let (_, workspace_database) = get_latest_workspace_database(
+    collab_storage,
+    pg_pool,
+    GetCollabOrigin::Server,
+    workspace_id,
+  )
+  .await?;
+  for meta in workspace_database.get_all_database_meta() {
+    let (db_collab, db_body) =
+      match get_latest_collab_database_body(collab_storage, &workspace_id_str, &meta.database_id)
+        .await
+      {
+        Ok(database) => database,
+        Err(err) => {
+          warn!(
+            "[Folder Rebuild] skip database {} of workspace {}: {}",
+            meta.database_id, workspace_id, err
+          );
+          continue;
+        },
+      };
+    let db_views = db_body.views.get_all_views(&db_collab.transact());
+    // the linked views are in the order they were added to the database, the first one is the
+    // view the database was created with
+    let mut view_ids = meta.linked_views;
+    for view in &db_views {
+      if !view_ids.contains(&view.id) {
+        view_ids.push(view.id.clone());
+      }
+    }
+    let mut first_view_id: Option<String> = None;
+    for view_id in view_ids {
+      let Some(view) = db_views.iter().find(|view| view.id == view_id) else {
+        continue;
+      };
+      source_views.push(SourceView {
+        id: view.id.clone(),
+        name: view.name.clone(),
+        layout: database_layout_to_view_layout(&view.layout),
+        parent_id: first_view_id.clone(),
+      });
+      first_view_id.get_or_insert_with(|| view.id.clone());
+    }
+  }
+  Ok(source_views)
+}
+
+/// Builds the new folder data out of the current folder data, if any, and the views backed by a
+/// collab. Returns the folder data along with the number of views that were not in the folder.
+fn rebuild_folder_data(
+  uid: i64,
+  workspace_id: &str,
+  workspace_name: String,
+  current_data: Option<FolderData>,
+  source_views: Vec<SourceView>,
+) -> (FolderData, usize) {
+  let (current_workspace, mut current_views, current_view, favorites, recent, trash, private) =
+    match current_data {
+      Some(data) => (
+        Some(data.workspace),
+        data.views,
+        data.current_view,
+        data.favorites,
+        data.recent,
+        data.trash,
+        data.private,
+      ),
+      None => Default::default(),
+    };
+
+  // views without a collab of their own, like spaces and chats, are kept as long as they are
+  // in the folder. The document and database views have to be backed by a collab.
+  let source_ids: HashSet<&str> = source_views.iter().map(|view| view.id.as_str()).collect();
+  current_views.retain(|view| {
+    check_if_view_is_space(view)
+      || !(view.layout.is_document() || view.layout.is_database())
+      || source_ids.contains(view.id.as_str())
+  });
+  // the views that can't be reached from the workspace anymore are dropped, the ones backed by
+  // a collab are added back as orphans
+  let reachable = reachable_view_ids(workspace_id, &current_views);
+  let mut views: HashMap<String, View> = current_views
+    .into_iter()
+    .filter(|view| reachable.contains(&view.id))
+    .map(|view| (view.id.clone(), view))
+    .collect();
+
+  let has_space = views
+    .values()
+    .any(|view| view.parent_view_id == workspace_id && check_if_view_is_space(view));
+  let recovered_space_id = Uuid::new_v4().to_string();
+  let mut recovered_views = 0;
+  for source in source_views {
+    if views.contains_key(&source.id) {
+      continue;
+    }
+    let parent_id = source
+      .parent_id
+      .filter(|parent_id| views.contains_key(parent_id))
+      .unwrap_or_else(|| recovered_space_id.clone());
+    let view = NestedChildViewBuilder::new(uid, parent_id)
+      .with_view_id(&source.id)
+      .with_name(&source.name)
+      .with_layout(source.layout)
+      .build()
+      .view;
+    views.insert(source.id, view);
+    recovered_views += 1;
+  }
+  let no_root_view = !views
+    .values()
+    .any(|view| view.parent_view_id == workspace_id);
+  if recovered_views > 0 || no_root_view {
+    let name = if has_space { "Recovered" } else { "General" };
+    let space_view = NestedChildViewBuilder::new(uid, workspace_id.to_string())
+      .with_view_id(&recovered_space_id)
+      .with_name(name)
+      .with_extra(|extra| {
+        extra
+          .with_space_info(SpaceInfo {
+            space_icon: Some(DEFAULT_SPACE_ICON.to_string()),
+            space_icon_color: Some(DEFAULT_SPACE_ICON_COLOR.to_string()),
+            ..Default::default()
+          })
+          .build()
+      })
+      .build()
+      .view;
+    views.insert(recovered_space_id, space_view);
+  }
+
+  // the children are derived from the parent of every view, keeping the previous order of the
+  // children that were already there
+  let mut children_by_parent: HashMap<String, Vec<(i64, String)>> = HashMap::new();
+  for view in views.values() {
+    children_by_parent
+      .entry(view.parent_view_id.clone())
+      .or_default()
+      .push((view.created_at, view.id.clone()));
+  }
+  let ordered_children = |previous: &RepeatedViewIdentifier, parent_id: &str| {
+    let mut children = children_by_parent
+      .get(parent_id)
+      .cloned()
+      .unwrap_or_default();
+    children.sort();
+    let mut ordered: Vec<ViewIdentifier> = previous
+      .items
+      .iter()
+      .filter(|child| children.iter().any(|(_, id)| *id == child.id))
+      .cloned()
+      .collect();
+    for (_, id) in children {
+      if !ordered.iter().any(|child| child.id == id) {
+        ordered.push(ViewIdentifier { id });
+      }
+    }
+    RepeatedViewIdentifier::new(ordered)
+  };
+  let mut views: Vec<View> = views.into_values().collect();
+  views.sort_by_key(|view| view.created_at);
+  for view in views.iter_mut() {
+    view.children = ordered_children(&view.children, &view.id);
+  }
+
+  let now = timestamp();
+  let workspace = match current_workspace {
+    Some(mut workspace) => {
+      workspace.name = workspace_name;
+      workspace.child_views = ordered_children(&workspace.child_views, workspace_id);
+      workspace.last_edited_time = now;
+      workspace.last_edited_by = Some(uid);
+      workspace
+    },
+    None => Workspace {
+      id: workspace_id.to_string(),
+      name: workspace_name,
+      child_views: ordered_children(&RepeatedViewIdentifier::new(vec![]), workspace_id),
+      created_at: now,
+      created_by: Some(uid),
+      last_edited_time: now,
+      last_edited_by: Some(uid),
+    },
+  };
+  let view_ids: HashSet<String> = views.iter().map(|view| view.id.clone()).collect();
+  let current_view = if view_ids.contains(&current_view) {
+    current_view
+  } else {
+    views
+      .iter()
+      .find(|view| !check_if_view_is_space(view))
+      .map(|view| view.id.clone())
+      .unwrap_or_default()
+  };
+  let retain_sections = |mut sections: HashMap<_, Vec<_>>| {
+    for items in sections.values_mut() {
+      items.retain(|item: &SectionItem| view_ids.contains(&item.id));
+    }
+    sections
+  };
+
+  let data = FolderData {
+    workspace,
+    current_view,
+    views,
+    favorites: retain_sections(favorites),
+    recent: retain_sections(recent),
+    trash: retain_sections(trash),
+    private: retain_sections(private),
+  };
+  (data, recovered_views)
+}
+
+/// Returns the ids of the views that can be reached from the workspace by following the parent of
+/// every view.
+fn reachable_view_ids(workspace_id: &str, views: &[View]) -> HashSet<String> {
+  let mut children_by_parent: HashMap<&str, Vec<&str>> = HashMap::new();
+  for view in views {
+    children_by_parent
+      .entry(view.parent_view_id.as_str())
+      .or_default()
+      .push(view.id.as_str());
+  }
+  let mut reachable = HashSet::new();
+  let mut queue = vec![workspace_id];
+  while let Some(parent_id) = queue.pop() {
+    for child_id in children_by_parent.get(parent_id).into_iter().flatten() {
+      if reachable.insert(child_id.to_string()) {
+        queue.push(child_id);
+      }
+    }
+  }
+  reachable
+}
+
+fn database_layout_to_view_layout(layout: &DatabaseLayout) -> ViewLayout {
+  match layout {
+    DatabaseLayout::Board => ViewLayout::Board,
+    DatabaseLayout::Calendar => ViewLayout::Calendar,
+    _ => ViewLayout::Grid,
+  }
+}
@@ -0,0 +1,82 @@
+use app_error::AppError;
+use chrono::Utc;
+use database::history::compaction::{
+  compact_workspace_history, select_workspace_history_compaction,
+  upsert_workspace_history_compaction,
+};
+use database::pg_row::AFWorkspaceHistoryCompactionRow;
+use shared_entity::dto::workspace_dto::{
+  HistoryCompactionReport, UpdateWorkspaceHistoryCompactionParams, WorkspaceHistoryCompaction,
+};
+use sqlx::PgPool;
+use tracing::info;
+use uuid::Uuid;
+
+const MAX_COMPACTION_DAYS: i32 = 3650;
+
+pub async fn get_workspace_history_compaction(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+) -> Result<WorkspaceHistoryCompaction, AppError> {
+  let setting = select_workspace_history_compaction(pg_pool, workspace_id)
+    .await?
+    .map(history_compaction_from_row)
+    .unwrap_or_default();
+  Ok(setting)
+}
+
+pub async fn update_workspace_history_compaction(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  params: UpdateWorkspaceHistoryCompactionParams,
+) -> Result<WorkspaceHistoryCompaction, AppError> {
+  let current = get_workspace_history_compaction(pg_pool, workspace_id).await?;
+  let enabled = params.enabled.unwrap_or(current.enabled);
+  let hourly_days = params.hourly_days.unwrap_or(current.hourly_days);
+  let daily_days = params.daily_days.unwrap_or(current.daily_days);
+  if hourly_days < 1 || daily_days < hourly_days || daily_days > MAX_COMPACTION_DAYS {
+    return Err(AppError::InvalidRequest(format!(
+      "hourly_days must be at least 1 and at most daily_days, which must not exceed {} days",
+      MAX_COMPACTION_DAYS
+    )));
+  }
+  let row =
+    upsert_workspace_history_compaction(pg_pool, workspace_id, enabled, hourly_days, daily_days)
+      .await?;
+  Ok(history_compaction_from_row(row))
+}
+
+/// Compacts the history of the workspace with its current settings, even when the periodic
+/// compaction is disabled for the workspace.
+pub async fn run_workspace_history_compaction(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+) -> Result<HistoryCompactionReport, AppError> {
+  let setting = get_workspace_history_compaction(pg_pool, workspace_id).await?;
+  let result = compact_workspace_history(
+    pg_pool,
+    workspace_id,
+    setting.hourly_days,
+    setting.daily_days,
+    Utc::now().timestamp(),
+  )
+  .await?;
+  info!(
+    "compacted history of workspace {}: {} versions, {} states, {} bytes reclaimed",
+    workspace_id, result.deleted_versions, result.deleted_states, result.reclaimed_bytes
+  );
+  Ok(HistoryCompactionReport {
+    deleted_versions: result.deleted_versions,
+    deleted_states: result.deleted_states,
+    reclaimed_bytes: result.reclaimed_bytes,
+  })
+}
+
+fn history_compaction_from_row(row: AFWorkspaceHistoryCompactionRow) -> WorkspaceHistoryCompaction {
+  WorkspaceHistoryCompaction {
+    enabled: row.enabled,
+    hourly_days: row.hourly_days,
+    daily_days: row.daily_days,
+    last_compacted_at: row.last_compacted_at,
+  }
+}
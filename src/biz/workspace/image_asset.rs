@@ -0,0 +1,326 @@
+use std::io::Cursor;
+
+use actix_web::web::Data;
+use app_error::AppError;
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use aws_sdk_s3::primitives::ByteStream;
+use collab_folder::{Folder, IconType, ViewIcon};
+use collab_rt_entity::user::RealtimeUser;
+use database::collab::GetCollabOrigin;
+use database::file::{BlobBucketStorage, BlobKey};
+use database::pg_row::AFBlobStatus;
+use database::workspace::change_workspace_icon;
+use image::{ImageFormat, ImageReader};
+use redis::AsyncCommands;
+use serde_json::json;
+use shared_entity::dto::workspace_dto::{ImageAsset, ImageAssetSize};
+use sqlx::PgPool;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::api::file_storage::BlobPathV1;
+use crate::api::metrics::AppFlowyWebMetrics;
+use crate::api::ws::RealtimeServerAddr;
+use crate::biz::billing::ops::check_storage_limit;
+use crate::biz::collab::utils::get_latest_collab_folder;
+use crate::biz::workspace::page_view::update_workspace_folder_data;
+use crate::config::config::BillingSetting;
+use crate::state::RedisConnectionManager;
+
+const IMAGE_ASSET_STREAM: &str = "image_asset_task_stream";
+const ORIGINAL_FILE_ID: &str = "original";
+/// Larger images are rejected before they are decoded, see the worker.
+const MAX_IMAGE_DIMENSION: u32 = 8192;
+
+/// The images uploaded with the dedicated endpoints. The other images of the documents go through
+/// the generic blob API and are stored as is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageAssetKind {
+  Icon,
+  Cover,
+}
+
+impl ImageAssetKind {
+  /// The smallest width and height accepted.
+  fn min_dimensions(&self) -> (u32, u32) {
+    match self {
+      ImageAssetKind::Icon => (16, 16),
+      ImageAssetKind::Cover => (320, 100),
+    }
+  }
+
+  /// The bounds of the generated webp sizes, largest first.
+  fn sizes(&self) -> &'static [(u32, u32)] {
+    match self {
+      ImageAssetKind::Icon => &[(256, 256), (64, 64)],
+      ImageAssetKind::Cover => &[(1600, 1600), (640, 640)],
+    }
+  }
+}
+
+/// Validates the image and returns its content type.
+fn validate_image(kind: ImageAssetKind, data: &[u8]) -> Result<&'static str, AppError> {
+  let reader = ImageReader::new(Cursor::new(data))
+    .with_guessed_format()
+    .map_err(|err| AppError::InvalidRequest(format!("failed to read the image: {}", err)))?;
+  let format = match reader.format() {
+    Some(
+      format @ (ImageFormat::Png
+      | ImageFormat::Jpeg
+      | ImageFormat::Gif
+      | ImageFormat::WebP
+      | ImageFormat::Bmp),
+    ) => format,
+    _ => {
+      return Err(AppError::InvalidRequest(
+        "unsupported image, only PNG, JPEG, GIF, WebP and BMP images are supported".to_string(),
+      ))
+    },
+  };
+  let (width, height) = reader
+    .into_dimensions()
+    .map_err(|err| AppError::InvalidRequest(format!("invalid image: {}", err)))?;
+  let (min_width, min_height) = kind.min_dimensions();
+  if width < min_width || height < min_height {
+    return Err(AppError::InvalidRequest(format!(
+      "image of {}x{} is too small, the minimum is {}x{}",
+      width, height, min_width, min_height
+    )));
+  }
+  if width > MAX_IMAGE_DIMENSION || height > MAX_IMAGE_DIMENSION {
+    return Err(AppError::InvalidRequest(format!(
+      "image of {}x{} is too large, the maximum is {}x{}",
+      width, height, MAX_IMAGE_DIMENSION, MAX_IMAGE_DIMENSION
+    )));
+  }
+  Ok(format.to_mime_type())
+}
+
+/// Stores the original image and queues the generation of its webp sizes. The keys of the sizes
+/// are known upfront, so the caller can store them in the metadata right away.
+#[allow(clippy::too_many_arguments)]
+async fn store_image_asset(
+  pg_pool: &PgPool,
+  bucket_storage: &BlobBucketStorage,
+  redis_client: &RedisConnectionManager,
+  billing: &BillingSetting,
+  uid: i64,
+  workspace_id: &Uuid,
+  kind: ImageAssetKind,
+  data: Vec<u8>,
+) -> Result<ImageAsset, AppError> {
+  if data.is_empty() {
+    return Err(AppError::InvalidRequest("image is empty".to_string()));
+  }
+  let content_type = validate_image(kind, &data)?;
+  check_storage_limit(pg_pool, billing, workspace_id, data.len() as u64).await?;
+
+  let asset_id = Uuid::new_v4();
+  let blob_path = |file_id: &str| BlobPathV1 {
+    workspace_id: *workspace_id,
+    parent_dir: asset_id.to_string(),
+    file_id: file_id.to_string(),
+  };
+  let file_size = data.len();
+  bucket_storage
+    .put_blob_with_content_type(
+      blob_path(ORIGINAL_FILE_ID),
+      ByteStream::from(data),
+      content_type.to_string(),
+      file_size,
+      uid,
+      AFBlobStatus::Ok,
+    )
+    .await?;
+
+  let variants = kind
+    .sizes()
+    .iter()
+    .map(|(max_width, max_height)| {
+      json!({
+        "file_id": format!("{}.webp", max_width),
+        "max_width": max_width,
+        "max_height": max_height,
+      })
+    })
+    .collect::<Vec<_>>();
+  let original_key = blob_path(ORIGINAL_FILE_ID).scoped_object_key()?;
+  let task = json!({
+    "asset_id": asset_id,
+    "workspace_id": workspace_id,
+    "uid": uid,
+    "original_key": original_key,
+    "variants": variants,
+  });
+  let result: Result<(), _> = redis_client
+    .clone()
+    .xadd(IMAGE_ASSET_STREAM, "*", &[("task", task.to_string())])
+    .await;
+  if let Err(err) = result {
+    // the original is still usable, only the smaller sizes are missing
+    error!(
+      "Failed to queue the sizes of image asset {} of workspace {}: {}",
+      asset_id, workspace_id, err
+    );
+  }
+
+  let sizes = kind
+    .sizes()
+    .iter()
+    .map(|(max_width, max_height)| {
+      Ok(ImageAssetSize {
+        key: blob_path(&format!("{}.webp", max_width)).scoped_object_key()?,
+        max_width: *max_width,
+        max_height: *max_height,
+      })
+    })
+    .collect::<Result<Vec<_>, AppError>>()?;
+  Ok(ImageAsset {
+    asset_id,
+    original_key,
+    sizes,
+  })
+}
+
+/// Uploads the icon of the workspace, the largest size becomes the icon of the workspace.
+pub async fn upload_workspace_icon(
+  pg_pool: &PgPool,
+  bucket_storage: &BlobBucketStorage,
+  redis_client: &RedisConnectionManager,
+  billing: &BillingSetting,
+  uid: i64,
+  workspace_id: &Uuid,
+  data: Vec<u8>,
+) -> Result<ImageAsset, AppError> {
+  let asset = store_image_asset(
+    pg_pool,
+    bucket_storage,
+    redis_client,
+    billing,
+    uid,
+    workspace_id,
+    ImageAssetKind::Icon,
+    data,
+  )
+  .await?;
+  let mut txn = pg_pool.begin().await?;
+  change_workspace_icon(&mut txn, workspace_id, &asset.sizes[0].key).await?;
+  txn.commit().await?;
+  info!(
+    "icon of workspace {} set to image asset {}",
+    workspace_id, asset.asset_id
+  );
+  Ok(asset)
+}
+
+/// Uploads the icon or the cover image of the page. The largest size is stored as the url icon
+/// of the view, or as the custom cover in the extra of the view.
+#[allow(clippy::too_many_arguments)]
+pub async fn upload_view_image(
+  appflowy_web_metrics: &AppFlowyWebMetrics,
+  server: Data<RealtimeServerAddr>,
+  user: RealtimeUser,
+  collab_storage: &CollabAccessControlStorage,
+  pg_pool: &PgPool,
+  bucket_storage: &BlobBucketStorage,
+  redis_client: &RedisConnectionManager,
+  billing: &BillingSetting,
+  workspace_id: Uuid,
+  view_id: &str,
+  kind: ImageAssetKind,
+  data: Vec<u8>,
+) -> Result<ImageAsset, AppError> {
+  let collab_origin = GetCollabOrigin::User { uid: user.uid };
+  let mut folder =
+    get_latest_collab_folder(collab_storage, collab_origin, &workspace_id.to_string()).await?;
+  if folder.get_view(view_id).is_none() {
+    return Err(AppError::RecordNotFound(format!(
+      "view {} not found in workspace {}",
+      view_id, workspace_id
+    )));
+  }
+  let asset = store_image_asset(
+    pg_pool,
+    bucket_storage,
+    redis_client,
+    billing,
+    user.uid,
+    &workspace_id,
+    kind,
+    data,
+  )
+  .await?;
+  let folder_update = set_view_image(&mut folder, view_id, kind, &asset.sizes[0].key)?;
+  update_workspace_folder_data(
+    appflowy_web_metrics,
+    server,
+    user,
+    workspace_id,
+    folder_update,
+  )
+  .await?;
+  Ok(asset)
+}
+
+fn set_view_image(
+  folder: &mut Folder,
+  view_id: &str,
+  kind: ImageAssetKind,
+  key: &str,
+) -> Result<Vec<u8>, AppError> {
+  let extra = match kind {
+    ImageAssetKind::Icon => None,
+    ImageAssetKind::Cover => {
+      let mut extra = folder
+        .get_view(view_id)
+        .and_then(|view| view.extra.clone())
+        .and_then(|extra| serde_json::from_str::<serde_json::Value>(&extra).ok())
+        .filter(|extra| extra.is_object())
+        .unwrap_or_else(|| json!({}));
+      extra["cover"] = json!({ "type": "custom", "value": key });
+      Some(extra.to_string())
+    },
+  };
+  let icon = match kind {
+    ImageAssetKind::Icon => Some(ViewIcon {
+      ty: IconType::Url,
+      value: key.to_string(),
+    }),
+    ImageAssetKind::Cover => None,
+  };
+  let mut txn = folder.collab.transact_mut();
+  folder.body.views.update_view(&mut txn, view_id, |update| {
+    let update = match icon {
+      Some(icon) => update.set_icon(Some(icon)),
+      None => update,
+    };
+    update.set_extra_if_not_none(extra).done()
+  });
+  Ok(txn.encode_update_v1())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use image::{DynamicImage, RgbImage};
+
+  fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut data = Vec::new();
+    DynamicImage::ImageRgb8(RgbImage::new(width, height))
+      .write_to(&mut Cursor::new(&mut data), ImageFormat::Png)
+      .unwrap();
+    data
+  }
+
+  #[test]
+  fn validate_image_dimensions() {
+    assert_eq!(
+      validate_image(ImageAssetKind::Icon, &png(32, 32)).unwrap(),
+      "image/png"
+    );
+    assert!(validate_image(ImageAssetKind::Icon, &png(8, 8)).is_err());
+    assert!(validate_image(ImageAssetKind::Cover, &png(32, 32)).is_err());
+    assert!(validate_image(ImageAssetKind::Cover, &png(1200, 300)).is_ok());
+    assert!(validate_image(ImageAssetKind::Icon, b"not an image").is_err());
+  }
+}
@@ -1,6 +1,21 @@
+pub mod clone;
+pub mod collab_guest;
 pub mod duplicate;
+pub mod files;
+pub mod folder_rebuild;
+pub mod history_compaction;
+pub mod image_asset;
 pub mod ops;
 pub mod page_view;
 pub mod publish;
+pub mod publish_analytics;
+pub mod publish_cache;
+pub mod publish_domain;
 pub mod publish_dup;
+pub mod publish_feed;
+pub mod publish_moderation;
 pub mod quick_note;
+pub mod restricted_view;
+pub mod retention;
+pub mod storage_audit;
+pub mod storage_location;
@@ -19,14 +19,15 @@ use yrs::updates::encoder::Encode;
 use access_control::workspace::WorkspaceAccessControl;
 use app_error::AppError;
 use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
-use database::file::s3_client_impl::S3BucketStorage;
 use database::pg_row::AFWorkspaceMemberRow;
 
-use database::user::select_uid_from_email;
+use database::collab_guest::select_guest_workspace_ids;
+use database::user::{select_uid_from_email, select_uid_from_uuid};
 use database::workspace::*;
+use database::workspace_delete::{insert_workspace_delete_task, is_workspace_delete_pending};
 use database_entity::dto::{
   AFRole, AFWorkspace, AFWorkspaceInvitation, AFWorkspaceInvitationStatus, AFWorkspaceSettings,
-  GlobalComment, Reaction, WorkspaceUsage,
+  AIFeature, GlobalComment, Reaction, WorkspaceUsage, IMPORT_START_AFTER_KEY,
 };
 use gotrue::params::{GenerateLinkParams, GenerateLinkType};
 
@@ -36,31 +37,66 @@ use shared_entity::dto::workspace_dto::{
 use shared_entity::response::AppResponseError;
 use workspace_template::document::getting_started::GettingStartedTemplate;
 
+use crate::biz::billing::ops::check_member_limit;
+use crate::biz::collab::date_cell::parse_timezone;
+use crate::biz::notification::push::PushMessage;
+use crate::biz::notification::{NotificationCenter, NotificationPriority};
+use crate::biz::region::validate_workspace_region;
 use crate::biz::user::user_init::{
   create_user_awareness, create_workspace_collab, create_workspace_database_collab,
   initialize_workspace_for_user,
 };
+use crate::biz::workspace::publish_feed::queue_publish_feed_regeneration;
+use crate::config::config::{BillingSetting, RegionSetting};
 use crate::mailer::{AFCloudMailer, WorkspaceInviteMailerParam};
 use crate::state::{GoTrueAdmin, RedisConnectionManager};
 
-const MAX_COMMENT_LENGTH: usize = 5000;
+pub(crate) const MAX_COMMENT_LENGTH: usize = 5000;
 
+const WORKSPACE_DELETE_STREAM: &str = "workspace_delete_task_stream";
+
+/// Hides the workspace from its members and queues a task for the worker to delete its collabs,
+/// snapshots, files and cached collabs in batches. The workspace row is deleted by the worker
+/// once everything else is gone. Does nothing if the deletion is already queued.
 pub async fn delete_workspace_for_user(
-  pg_pool: PgPool,
+  pg_pool: &PgPool,
+  redis_client: &RedisConnectionManager,
+  uid: i64,
   workspace_id: Uuid,
-  bucket_storage: Arc<S3BucketStorage>,
 ) -> Result<(), AppResponseError> {
-  // remove files from s3
-  bucket_storage
-    .remove_dir(workspace_id.to_string().as_str())
-    .await?;
-
-  // remove from postgres
-  delete_from_workspace(&pg_pool, &workspace_id).await?;
+  if is_workspace_delete_pending(pg_pool, &workspace_id).await? {
+    return Ok(());
+  }
 
-  // TODO: There can be a rare case where user uploads while workspace is being deleted.
-  // We need some routine job to clean up these orphaned files.
+  let task_id = Uuid::new_v4();
+  let mut txn = pg_pool
+    .begin()
+    .await
+    .context("Begin transaction to delete workspace")?;
+  update_workspace_status(txn.deref_mut(), &workspace_id, false).await?;
+  insert_workspace_ids_to_deleted_table(txn.deref_mut(), vec![workspace_id]).await?;
+  insert_workspace_delete_task(txn.deref_mut(), &task_id, &workspace_id, uid).await?;
+  txn
+    .commit()
+    .await
+    .context("Commit transaction to delete workspace")?;
 
+  let task = json!({
+    "task_id": task_id,
+    "workspace_id": workspace_id,
+  });
+  let _: () = redis_client
+    .clone()
+    .xadd(WORKSPACE_DELETE_STREAM, "*", &[("task", task.to_string())])
+    .await
+    .map_err(|err| AppError::Internal(anyhow!("Failed to push task to Redis stream: {}", err)))?;
+  // the sitemap and feeds of the published pages are removed without waiting for the deletion
+  queue_publish_feed_regeneration(redis_client, &workspace_id).await;
+  tracing::info!(
+    "User:{} queued the deletion of workspace:{}",
+    uid,
+    workspace_id
+  );
   Ok(())
 }
 
@@ -296,6 +332,20 @@ pub async fn get_all_user_workspaces(
     }
   }
 
+  // The workspaces in which some pages are shared with the user are listed without their member
+  // count and role, the user is not a member of them.
+  let uid = select_uid_from_uuid(pg_pool, user_uuid).await?;
+  for workspace_id in select_guest_workspace_ids(pg_pool, uid).await? {
+    let row = select_workspace(pg_pool, &workspace_id).await?;
+    match AFWorkspace::try_from(row) {
+      Ok(mut workspace) => {
+        workspace.is_collab_guest = true;
+        workspaces.push(workspace);
+      },
+      Err(err) => tracing::error!("Failed to convert workspace row to AFWorkspace: {:?}", err),
+    }
+  }
+
   Ok(workspaces)
 }
 
@@ -323,6 +373,7 @@ pub async fn open_workspace(
 
 pub async fn accept_workspace_invite(
   pg_pool: &PgPool,
+  billing: &BillingSetting,
   workspace_access_control: Arc<dyn WorkspaceAccessControl>,
   user_uid: i64,
   user_uuid: &Uuid,
@@ -338,6 +389,7 @@ pub async fn accept_workspace_invite(
       )));
     }
   }
+  check_member_limit(pg_pool, billing, &inv.workspace_id, 1).await?;
   update_workspace_invitation_set_status_accepted(&mut txn, user_uuid, invite_id).await?;
   let invited_uid = inv
     .invitee_uid
@@ -353,6 +405,7 @@ pub async fn accept_workspace_invite(
 #[allow(clippy::too_many_arguments)]
 pub async fn invite_workspace_members(
   mailer: &AFCloudMailer,
+  notification_center: &NotificationCenter,
   gotrue_admin: &GoTrueAdmin,
   pg_pool: &PgPool,
   gotrue_client: &gotrue::api::Client,
@@ -462,6 +515,19 @@ pub async fn invite_workspace_members(
       }
     };
 
+    // The invitees who already have an account are also notified on their devices.
+    if let Ok(invitee_uid) = select_uid_from_email(pg_pool, &invitation.email).await {
+      notification_center.notify(
+        invitee_uid,
+        NotificationPriority::High,
+        PushMessage {
+          title: "Workspace invitation".to_string(),
+          body: format!("{} invited you to {}", inviter_name, workspace_name),
+          data: HashMap::from([("invite_id".to_string(), invite_id.to_string())]),
+        },
+      );
+    }
+
     if !invitation.skip_email_send {
       let cloned_mailer = mailer.clone();
       let email_sending = tokio::spawn(async move {
@@ -650,6 +716,7 @@ pub async fn get_workspace_settings(
 pub async fn update_workspace_settings(
   pg_pool: &PgPool,
   workspace_id: &Uuid,
+  region_setting: &RegionSetting,
   change: AFWorkspaceSettingsChange,
 ) -> Result<AFWorkspaceSettings, AppResponseError> {
   let mut tx = pg_pool.begin().await?;
@@ -664,12 +731,53 @@ pub async fn update_workspace_settings(
     setting.ai_model = ai_model;
   }
 
+  if let Some(ai_features) = change.ai_features {
+    let features = &mut setting.ai_features;
+    features.ai_chat = ai_features.ai_chat.unwrap_or(features.ai_chat);
+    features.ai_complete = ai_features.ai_complete.unwrap_or(features.ai_complete);
+    features.ai_summarize_row = ai_features
+      .ai_summarize_row
+      .unwrap_or(features.ai_summarize_row);
+    features.ai_translate = ai_features.ai_translate.unwrap_or(features.ai_translate);
+    features.ai_language_check = ai_features
+      .ai_language_check
+      .unwrap_or(features.ai_language_check);
+  }
+
+  if let Some(default_timezone) = change.default_timezone {
+    if !default_timezone.is_empty() {
+      parse_timezone(&default_timezone)?;
+    }
+    setting.default_timezone = default_timezone;
+  }
+
+  if let Some(region) = change.region {
+    validate_workspace_region(region_setting, &region)?;
+    setting.region = region;
+  }
+
   // Update the workspace settings in the database
   upsert_workspace_settings(&mut tx, workspace_id, &setting).await?;
   tx.commit().await?;
   Ok(setting)
 }
 
+/// Returns [AppError::FeatureDisabled] if the owner of the workspace turned the AI feature off.
+pub async fn ensure_ai_feature_enabled(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  feature: AIFeature,
+) -> Result<(), AppError> {
+  let settings = select_workspace_settings(pg_pool, workspace_id)
+    .await?
+    .unwrap_or_default();
+  if settings.ai_features.is_enabled(feature) {
+    Ok(())
+  } else {
+    Err(AppError::FeatureDisabled(feature.to_string()))
+  }
+}
+
 async fn check_if_user_is_allowed_to_delete_comment(
   pg_pool: &PgPool,
   user_uuid: &Uuid,
@@ -695,9 +803,20 @@ pub async fn create_upload_task(
   workspace_id: &str,
   file_size: usize,
   presigned_url: Option<String>,
+  start_after: Option<i64>,
   redis_client: &RedisConnectionManager,
   pg_pool: &PgPool,
 ) -> Result<(), AppError> {
+  // The worker keeps the scheduled tasks in the queue until they are due
+  let status = match start_after {
+    Some(start_after) if start_after > chrono::Utc::now().timestamp() => ImportTaskState::Scheduled,
+    _ => ImportTaskState::Pending,
+  };
+  let mut metadata = json!({"host": host});
+  if let Some(start_after) = start_after {
+    metadata[IMPORT_START_AFTER_KEY] = json!(start_after);
+  }
+
   // Insert the task into the database
   insert_import_task(
     uid,
@@ -705,34 +824,51 @@ pub async fn create_upload_task(
     file_size as i64,
     workspace_id.to_string(),
     uid,
-    Some(json!({"host": host})),
+    status,
+    Some(metadata),
     presigned_url,
     pg_pool,
   )
   .await?;
 
-  let _: () = redis_client
+  // The row is removed when the task can't be queued, so that it isn't left pending forever and
+  // doesn't count against the pending tasks of the user.
+  let result: Result<(), _> = redis_client
     .clone()
     .xadd("import_task_stream", "*", &[("task", task.to_string())])
-    .await
-    .map_err(|err| AppError::Internal(anyhow!("Failed to push task to Redis stream: {}", err)))?;
+    .await;
+  if let Err(err) = result {
+    if let Err(delete_err) = delete_import_task(pg_pool, &task_id).await {
+      tracing::error!(
+        "Failed to remove import task:{} that could not be queued: {}",
+        task_id,
+        delete_err
+      );
+    }
+    return Err(AppError::Internal(anyhow!(
+      "Failed to push task to Redis stream: {}",
+      err
+    )));
+  }
 
   Ok(())
 }
 
 pub async fn num_pending_task(uid: i64, pg_pool: &PgPool) -> Result<i64, AppError> {
-  // Query to check for pending tasks for the given user ID
+  // Query to check for pending and scheduled tasks for the given user ID
   let pending = ImportTaskState::Pending as i16;
+  let scheduled = ImportTaskState::Scheduled as i16;
   let query = "
         SELECT COUNT(*)
         FROM af_import_task
-        WHERE uid = $1 AND status = $2
+        WHERE uid = $1 AND status IN ($2, $3)
     ";
 
   // Execute the query and fetch the count
   let (count,): (i64,) = sqlx::query_as(query)
     .bind(uid)
     .bind(pending)
+    .bind(scheduled)
     .fetch_one(pg_pool)
     .await
     .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to query pending tasks: {:?}", e)))?;
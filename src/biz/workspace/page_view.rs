@@ -2,7 +2,8 @@ use crate::api::metrics::AppFlowyWebMetrics;
 use crate::api::ws::RealtimeServerAddr;
 use crate::biz::chat::ops::create_chat;
 use crate::biz::collab::database::{
-  resolve_dependencies_when_create_database_linked_view, LinkedViewDependencies,
+  filter_map_from_dto, resolve_dependencies_with_group_field, sort_map_from_dto,
+  LinkedViewDependencies,
 };
 use crate::biz::collab::folder_view::{
   check_if_view_is_space, parse_extra_field_as_json, to_dto_view_icon, to_dto_view_layout,
@@ -13,6 +14,7 @@ use crate::biz::collab::utils::{
   batch_get_latest_collab_encoded, collab_from_doc_state, collab_to_doc_state,
   get_latest_collab_database_body, get_latest_collab_encoded, get_latest_collab_folder,
 };
+use access_control::restricted_view::RestrictedViewCache;
 use actix_web::web::Data;
 use anyhow::anyhow;
 use app_error::AppError;
@@ -58,7 +60,8 @@ use serde_json::json;
 use shared_entity::dto::chat_dto::CreateChatParams;
 use shared_entity::dto::publish_dto::{PublishDatabaseData, PublishViewInfo, PublishViewMetaData};
 use shared_entity::dto::workspace_dto::{
-  FolderView, Page, PageCollab, PageCollabData, Space, SpacePermission, ViewIcon, ViewLayout,
+  AFDatabaseViewSettings, FolderView, Page, PageCollab, PageCollabData, Space, SpacePermission,
+  ViewIcon, ViewLayout,
 };
 use sqlx::PgPool;
 use std::collections::{HashMap, HashSet};
@@ -1498,6 +1501,7 @@ pub async fn unpublish_page(
 pub async fn get_page_view_collab(
   pg_pool: &PgPool,
   collab_access_control_storage: &CollabAccessControlStorage,
+  restricted_view_cache: &RestrictedViewCache,
   uid: i64,
   workspace_id: Uuid,
   view_id: &str,
@@ -1508,6 +1512,14 @@ pub async fn get_page_view_collab(
     &workspace_id.to_string(),
   )
   .await?;
+  let restricted_views = restricted_view_cache.get(&workspace_id).await?;
+  if !restricted_views.can_access_view(uid, view_id, |view_id| {
+    folder
+      .get_view(view_id)
+      .map(|view| view.parent_view_id.clone())
+  }) {
+    return Err(AppError::NotEnoughPermissions);
+  }
   let view = folder
     .get_view(view_id)
     .ok_or(AppError::InvalidFolderView(format!(
@@ -1769,7 +1781,9 @@ pub async fn create_database_view(
   database_view_id: &str,
   view_layout: &ViewLayout,
   name: Option<&str>,
+  settings: Option<AFDatabaseViewSettings>,
 ) -> Result<(), AppError> {
+  let settings = settings.unwrap_or_default();
   let database_layout = match view_layout {
     ViewLayout::Grid => DatabaseLayout::Grid,
     ViewLayout::Board => DatabaseLayout::Board,
@@ -1836,7 +1850,23 @@ pub async fn create_database_view(
     field_settings,
     group_settings,
     deps_fields,
-  } = resolve_dependencies_when_create_database_linked_view(database_layout, &fields)?;
+  } = resolve_dependencies_with_group_field(
+    database_layout,
+    &fields,
+    settings.group_field_id.as_deref(),
+  )?;
+  let filters = settings
+    .filters
+    .unwrap_or_default()
+    .into_iter()
+    .map(|filter| filter_map_from_dto(&fields, filter))
+    .collect::<Result<Vec<_>, _>>()?;
+  let sorts = settings
+    .sorts
+    .unwrap_or_default()
+    .into_iter()
+    .map(|sort| sort_map_from_dto(&fields, sort))
+    .collect::<Result<Vec<_>, _>>()?;
   let new_view_id = Uuid::new_v4().to_string();
   let database_encoded_update = {
     let mut txn = database_collab.transact_mut();
@@ -1847,9 +1877,9 @@ pub async fn create_database_view(
       name: name.unwrap_or_default().to_string(),
       layout: database_layout,
       layout_settings,
-      filters: vec![],
+      filters,
       group_settings,
-      sorts: vec![],
+      sorts,
       field_settings,
       created_at: timestamp,
       modified_at: timestamp,
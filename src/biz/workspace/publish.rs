@@ -26,7 +26,7 @@ use tracing::debug;
 use uuid::Uuid;
 
 use database::{
-  file::{s3_client_impl::AwsS3BucketClientImpl, BucketClient, ResponseBlob},
+  file::{BlobStorageClient, BucketClient, ResponseBlob},
   publish::{
     insert_or_replace_publish_collabs, select_publish_collab_meta, select_published_collab_blob,
     select_published_collab_info, select_published_collab_workspace_view_id,
@@ -296,12 +296,24 @@ pub trait PublishedCollabStore: Sync + Send + 'static {
     user_uuid: &Uuid,
   ) -> Result<(), AppError>;
 
+  /// Unpublishes the views without checking who is asking, for the administrators of the
+  /// instance acting on abuse reports.
+  async fn force_unpublish_collabs(
+    &self,
+    workspace_id: &Uuid,
+    view_ids: &[Uuid],
+  ) -> Result<(), AppError>;
+
   async fn patch_collabs(
     &self,
     workspace_id: &Uuid,
     user_uuid: &Uuid,
     patches: &[PatchPublishedCollab],
   ) -> Result<(), AppError>;
+
+  /// Drops the cached published pages of the workspace, for changes made outside of the store
+  /// like a new publish namespace.
+  async fn invalidate_workspace_cache(&self, _workspace_id: &Uuid) {}
 }
 
 pub struct PublishedCollabPostgresStore {
@@ -410,6 +422,14 @@ impl PublishedCollabStore for PublishedCollabPostgresStore {
     user_uuid: &Uuid,
   ) -> Result<(), AppError> {
     check_workspace_owner_or_publisher(&self.pg_pool, user_uuid, workspace_id, view_ids).await?;
+    self.force_unpublish_collabs(workspace_id, view_ids).await
+  }
+
+  async fn force_unpublish_collabs(
+    &self,
+    workspace_id: &Uuid,
+    view_ids: &[Uuid],
+  ) -> Result<(), AppError> {
     set_published_collabs_as_unpublished(&self.pg_pool, workspace_id, view_ids).await?;
     Ok(())
   }
@@ -427,14 +447,14 @@ impl PublishedCollabStore for PublishedCollabPostgresStore {
 pub struct PublishedCollabS3StoreWithPostgresFallback {
   metrics: Arc<PublishedCollabMetrics>,
   pg_pool: PgPool,
-  bucket_client: AwsS3BucketClientImpl,
+  bucket_client: BlobStorageClient,
 }
 
 impl PublishedCollabS3StoreWithPostgresFallback {
   pub fn new(
     metrics: Arc<PublishedCollabMetrics>,
     pg_pool: PgPool,
-    bucket_client: AwsS3BucketClientImpl,
+    bucket_client: BlobStorageClient,
   ) -> Self {
     Self {
       metrics,
@@ -596,6 +616,14 @@ impl PublishedCollabStore for PublishedCollabS3StoreWithPostgresFallback {
     user_uuid: &Uuid,
   ) -> Result<(), AppError> {
     check_workspace_owner_or_publisher(&self.pg_pool, user_uuid, workspace_id, view_ids).await?;
+    self.force_unpublish_collabs(workspace_id, view_ids).await
+  }
+
+  async fn force_unpublish_collabs(
+    &self,
+    workspace_id: &Uuid,
+    view_ids: &[Uuid],
+  ) -> Result<(), AppError> {
     let object_keys = view_ids
       .iter()
       .map(|view_id| get_collab_s3_key(workspace_id, view_id))
@@ -0,0 +1,239 @@
+use std::time::Duration;
+
+use actix_web::http::header::{HeaderMap, REFERER, USER_AGENT};
+use app_error::AppError;
+use chrono::{Datelike, Days, Months, Utc};
+use database::publish_analytics::{
+  create_published_view_event_partition, insert_published_view_events,
+  select_published_view_countries, select_published_view_daily_counts,
+  select_published_view_referrers, PublishedViewEvent,
+};
+use database_entity::dto::{PublishAnalytics, PublishAnalyticsQuery};
+use sqlx::PgPool;
+use tokio::sync::mpsc;
+use tokio::time::interval;
+use tracing::{error, trace, warn};
+use uuid::Uuid;
+
+use crate::config::config::PublishedCollabSetting;
+
+const EVENT_BUFFER_SIZE: usize = 10_000;
+const FLUSH_BATCH_SIZE: usize = 500;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+const PARTITION_CHECK_INTERVAL: Duration = Duration::from_secs(12 * 60 * 60);
+const DEFAULT_RANGE_DAYS: u64 = 30;
+const MAX_RANGE_DAYS: i64 = 366;
+const TOP_BUCKET_LIMIT: i64 = 20;
+const BOT_USER_AGENT_KEYWORDS: [&str; 4] = ["bot", "crawler", "spider", "preview"];
+
+/// Records the views of the published pages. The events are buffered and inserted in batches in
+/// the background, so serving a page never waits for the database. Events are dropped when the
+/// buffer is full.
+#[derive(Clone)]
+pub struct PublishAnalyticsRecorder {
+  sender: Option<mpsc::Sender<PublishedViewEvent>>,
+  country_header: String,
+}
+
+impl PublishAnalyticsRecorder {
+  pub fn new(pg_pool: PgPool, setting: &PublishedCollabSetting) -> Self {
+    if !setting.analytics_enabled {
+      return Self {
+        sender: None,
+        country_header: String::new(),
+      };
+    }
+
+    let (sender, receiver) = mpsc::channel(EVENT_BUFFER_SIZE);
+    tokio::spawn(run_partition_maintenance(pg_pool.clone()));
+    tokio::spawn(run_event_writer(pg_pool, receiver));
+    Self {
+      sender: Some(sender),
+      country_header: setting.analytics_country_header.to_ascii_lowercase(),
+    }
+  }
+
+  pub fn record(
+    &self,
+    headers: &HeaderMap,
+    host: &str,
+    publish_namespace: &str,
+    publish_name: &str,
+  ) {
+    let sender = match &self.sender {
+      Some(sender) => sender,
+      None => return,
+    };
+    let user_agent = headers
+      .get(USER_AGENT)
+      .and_then(|value| value.to_str().ok())
+      .unwrap_or_default();
+    if is_bot(user_agent) {
+      return;
+    }
+
+    let referrer = headers
+      .get(REFERER)
+      .and_then(|value| value.to_str().ok())
+      .and_then(referrer_host)
+      // Navigation inside the published site is not a referral
+      .filter(|referrer| {
+        let host = host.split(':').next().unwrap_or(host);
+        !host.eq_ignore_ascii_case(referrer)
+      });
+    let country = headers
+      .get(self.country_header.as_str())
+      .and_then(|value| value.to_str().ok())
+      .and_then(country_bucket);
+    let event = PublishedViewEvent {
+      publish_namespace: publish_namespace.to_string(),
+      publish_name: publish_name.to_string(),
+      referrer,
+      country,
+      created_at: Utc::now(),
+    };
+    if sender.try_send(event).is_err() {
+      trace!("publish analytics buffer is full, dropping event");
+    }
+  }
+}
+
+/// Returns the views of the published pages of the workspace aggregated per day, with the top
+/// referrers and countries of the range.
+pub async fn get_publish_analytics(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  query: PublishAnalyticsQuery,
+) -> Result<PublishAnalytics, AppError> {
+  let today = Utc::now().date_naive();
+  let to = query.to.unwrap_or(today);
+  let from = query.from.unwrap_or_else(|| {
+    to.checked_sub_days(Days::new(DEFAULT_RANGE_DAYS - 1))
+      .unwrap_or(to)
+  });
+  if from > to {
+    return Err(AppError::InvalidRequest(
+      "from must not be after to".to_string(),
+    ));
+  }
+  if (to - from).num_days() >= MAX_RANGE_DAYS {
+    return Err(AppError::InvalidRequest(format!(
+      "the range must not exceed {} days",
+      MAX_RANGE_DAYS
+    )));
+  }
+
+  let view_id = query.view_id.as_ref();
+  let daily_views =
+    select_published_view_daily_counts(pg_pool, workspace_id, from, to, view_id).await?;
+  let referrers =
+    select_published_view_referrers(pg_pool, workspace_id, from, to, view_id, TOP_BUCKET_LIMIT)
+      .await?;
+  let countries =
+    select_published_view_countries(pg_pool, workspace_id, from, to, view_id, TOP_BUCKET_LIMIT)
+      .await?;
+  Ok(PublishAnalytics {
+    from,
+    to,
+    daily_views,
+    referrers,
+    countries,
+  })
+}
+
+async fn run_event_writer(pg_pool: PgPool, mut receiver: mpsc::Receiver<PublishedViewEvent>) {
+  let mut buffer = Vec::with_capacity(FLUSH_BATCH_SIZE);
+  let mut ticker = interval(FLUSH_INTERVAL);
+  loop {
+    tokio::select! {
+      event = receiver.recv() => match event {
+        Some(event) => {
+          buffer.push(event);
+          if buffer.len() < FLUSH_BATCH_SIZE {
+            continue;
+          }
+        },
+        None => {
+          flush_events(&pg_pool, &mut buffer).await;
+          break;
+        },
+      },
+      _ = ticker.tick() => {},
+    }
+    flush_events(&pg_pool, &mut buffer).await;
+  }
+}
+
+async fn flush_events(pg_pool: &PgPool, buffer: &mut Vec<PublishedViewEvent>) {
+  if buffer.is_empty() {
+    return;
+  }
+  match insert_published_view_events(pg_pool, buffer).await {
+    Ok(count) => trace!("inserted {} published view events", count),
+    Err(err) => error!("failed to insert published view events: {}", err),
+  }
+  buffer.clear();
+}
+
+/// Creates the partitions of the current and the next month, so the events never land in the
+/// default partition.
+async fn run_partition_maintenance(pg_pool: PgPool) {
+  let mut ticker = interval(PARTITION_CHECK_INTERVAL);
+  loop {
+    ticker.tick().await;
+    let today = Utc::now().date_naive();
+    let this_month = today.with_day(1).unwrap_or(today);
+    let next_month = this_month
+      .checked_add_months(Months::new(1))
+      .unwrap_or(this_month);
+    for month in [this_month, next_month] {
+      if let Err(err) = create_published_view_event_partition(&pg_pool, month).await {
+        warn!(
+          "failed to create published view event partition for {}: {}",
+          month, err
+        );
+      }
+    }
+  }
+}
+
+fn is_bot(user_agent: &str) -> bool {
+  let user_agent = user_agent.to_ascii_lowercase();
+  BOT_USER_AGENT_KEYWORDS
+    .iter()
+    .any(|keyword| user_agent.contains(keyword))
+}
+
+/// Only the host of the referrer is kept, the path may contain personal data.
+fn referrer_host(referrer: &str) -> Option<String> {
+  reqwest::Url::parse(referrer)
+    .ok()
+    .and_then(|url| url.host_str().map(|host| host.to_ascii_lowercase()))
+}
+
+/// Two letter country code set by the reverse proxy or the CDN. Unknown values like `XX` or the
+/// Tor exit node code `T1` of Cloudflare are dropped.
+fn country_bucket(country: &str) -> Option<String> {
+  let country = country.trim().to_ascii_uppercase();
+  (country.len() == 2 && country.chars().all(|c| c.is_ascii_alphabetic()) && country != "XX")
+    .then_some(country)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn anonymize_view_event() {
+    assert_eq!(
+      referrer_host("https://Www.Google.com/search?q=secret").as_deref(),
+      Some("www.google.com")
+    );
+    assert_eq!(referrer_host("not a url"), None);
+    assert_eq!(country_bucket("de").as_deref(), Some("DE"));
+    assert_eq!(country_bucket("T1"), None);
+    assert_eq!(country_bucket("XX"), None);
+    assert!(is_bot("Mozilla/5.0 (compatible; Googlebot/2.1)"));
+    assert!(!is_bot("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7)"));
+  }
+}
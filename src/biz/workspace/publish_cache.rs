@@ -0,0 +1,316 @@
+use std::sync::Arc;
+
+use app_error::AppError;
+use async_trait::async_trait;
+use aws_sdk_s3::primitives::ByteStream;
+use database::file::{BlobStorageClient, BucketClient, ResponseBlob};
+use database::publish::select_workspace_id_for_publish_namespace;
+use database_entity::dto::{PatchPublishedCollab, PublishCollabItem, PublishInfo};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use shared_entity::dto::publish_dto::PublishViewMetaData;
+use sqlx::PgPool;
+use tracing::{trace, warn};
+use uuid::Uuid;
+
+use super::publish::PublishedCollabStore;
+use crate::state::RedisConnectionManager;
+
+/// Caches the published pages served to the public site.
+///
+/// Every workspace has a publish version stored in Redis, which is replaced whenever a view of
+/// the workspace is published, unpublished, patched or when the publish namespace changes. The
+/// metadata of a published page is kept in Redis and the collab blob is kept in the blob storage
+/// under the version it was read with, so an outdated entry is never served once the version has
+/// changed. Errors of the cache are logged and the request falls back to the inner store.
+pub struct PublishedCollabCachedStore {
+  inner: Arc<dyn PublishedCollabStore>,
+  pg_pool: PgPool,
+  redis_client: RedisConnectionManager,
+  bucket_client: BlobStorageClient,
+  ttl_secs: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedPublishEntry {
+  workspace_id: Uuid,
+  version: String,
+  metadata: serde_json::Value,
+}
+
+impl PublishedCollabCachedStore {
+  pub fn new(
+    inner: Arc<dyn PublishedCollabStore>,
+    pg_pool: PgPool,
+    redis_client: RedisConnectionManager,
+    bucket_client: BlobStorageClient,
+    ttl_secs: u64,
+  ) -> Self {
+    Self {
+      inner,
+      pg_pool,
+      redis_client,
+      bucket_client,
+      ttl_secs,
+    }
+  }
+
+  /// Returns the current publish version of the workspace, creating one if there is none yet.
+  async fn publish_version(&self, workspace_id: &Uuid) -> Result<String, AppError> {
+    let key = publish_version_key(workspace_id);
+    let mut conn = self.redis_client.clone();
+    let _: bool = conn
+      .set_nx(&key, Uuid::new_v4().to_string())
+      .await
+      .map_err(redis_error)?;
+    conn.get(&key).await.map_err(redis_error)
+  }
+
+  /// Replaces the publish version of the workspace and removes the artifacts cached for the
+  /// previous version.
+  async fn bump_publish_version(&self, workspace_id: &Uuid) -> Result<(), AppError> {
+    let key = publish_version_key(workspace_id);
+    let old_version: Option<String> = self
+      .redis_client
+      .clone()
+      .getset(&key, Uuid::new_v4().to_string())
+      .await
+      .map_err(redis_error)?;
+    if let Some(old_version) = old_version {
+      let bucket_client = self.bucket_client.clone();
+      let dir = format!("{}/", artifact_dir(workspace_id, &old_version));
+      tokio::spawn(async move {
+        if let Err(err) = bucket_client.remove_dir(&dir).await {
+          warn!(
+            "failed to remove outdated published collab artifacts: {}",
+            err
+          );
+        }
+      });
+    }
+    Ok(())
+  }
+
+  async fn invalidate(&self, workspace_id: &Uuid) {
+    if let Err(err) = self.bump_publish_version(workspace_id).await {
+      warn!(
+        "failed to invalidate published collab cache for workspace {}: {}",
+        workspace_id, err
+      );
+    }
+  }
+
+  async fn cached_entry(
+    &self,
+    publish_namespace: &str,
+    publish_name: &str,
+  ) -> Result<Option<CachedPublishEntry>, AppError> {
+    let value: Option<String> = self
+      .redis_client
+      .clone()
+      .get(entry_key(publish_namespace, publish_name))
+      .await
+      .map_err(redis_error)?;
+    let entry =
+      match value.and_then(|value| serde_json::from_str::<CachedPublishEntry>(&value).ok()) {
+        Some(entry) => entry,
+        None => return Ok(None),
+      };
+    if entry.version != self.publish_version(&entry.workspace_id).await? {
+      return Ok(None);
+    }
+    Ok(Some(entry))
+  }
+
+  /// Returns the cached entry of the published page, reading it from the inner store on a miss.
+  async fn entry(
+    &self,
+    publish_namespace: &str,
+    publish_name: &str,
+  ) -> Result<CachedPublishEntry, AppError> {
+    match self.cached_entry(publish_namespace, publish_name).await {
+      Ok(Some(entry)) => {
+        trace!(
+          "published collab cache hit: {}/{}",
+          publish_namespace,
+          publish_name
+        );
+        return Ok(entry);
+      },
+      Ok(None) => {},
+      Err(err) => warn!("failed to read published collab cache: {}", err),
+    }
+
+    let workspace_id =
+      select_workspace_id_for_publish_namespace(&self.pg_pool, publish_namespace).await?;
+    // The version is read before the data, so data published in between is stored under an
+    // outdated version and never served.
+    let version = self.publish_version(&workspace_id).await?;
+    let metadata = self
+      .inner
+      .get_collab_metadata(publish_namespace, publish_name)
+      .await?;
+    let entry = CachedPublishEntry {
+      workspace_id,
+      version,
+      metadata,
+    };
+    if let Ok(value) = serde_json::to_string(&entry) {
+      let result: Result<(), _> = self
+        .redis_client
+        .clone()
+        .set_ex(
+          entry_key(publish_namespace, publish_name),
+          value,
+          self.ttl_secs,
+        )
+        .await;
+      if let Err(err) = result {
+        warn!("failed to write published collab cache: {}", err);
+      }
+    }
+    Ok(entry)
+  }
+}
+
+#[async_trait]
+impl PublishedCollabStore for PublishedCollabCachedStore {
+  async fn publish_collabs(
+    &self,
+    published_items: Vec<PublishCollabItem<serde_json::Value, Vec<u8>>>,
+    workspace_id: &Uuid,
+    user_uuid: &Uuid,
+  ) -> Result<(), AppError> {
+    let result = self
+      .inner
+      .publish_collabs(published_items, workspace_id, user_uuid)
+      .await;
+    self.invalidate(workspace_id).await;
+    result
+  }
+
+  async fn get_collab_with_view_metadata_by_view_id(
+    &self,
+    view_id: &Uuid,
+  ) -> Result<Option<(PublishViewMetaData, Vec<u8>)>, AppError> {
+    self
+      .inner
+      .get_collab_with_view_metadata_by_view_id(view_id)
+      .await
+  }
+
+  async fn get_collab_metadata(
+    &self,
+    publish_namespace: &str,
+    publish_name: &str,
+  ) -> Result<serde_json::Value, AppError> {
+    let entry = self.entry(publish_namespace, publish_name).await?;
+    Ok(entry.metadata)
+  }
+
+  async fn list_collab_publish_info(
+    &self,
+    workspace_id: &Uuid,
+  ) -> Result<Vec<PublishInfo>, AppError> {
+    self.inner.list_collab_publish_info(workspace_id).await
+  }
+
+  async fn get_collab_publish_info(&self, view_id: &Uuid) -> Result<PublishInfo, AppError> {
+    self.inner.get_collab_publish_info(view_id).await
+  }
+
+  async fn get_collab_blob_by_publish_namespace(
+    &self,
+    publish_namespace: &str,
+    publish_name: &str,
+  ) -> Result<Vec<u8>, AppError> {
+    let entry = self.entry(publish_namespace, publish_name).await?;
+    let object_key = format!(
+      "{}/{}",
+      artifact_dir(&entry.workspace_id, &entry.version),
+      publish_name
+    );
+    match self.bucket_client.get_blob(&object_key).await {
+      Ok(resp) => return Ok(resp.to_blob()),
+      Err(AppError::RecordNotFound(_)) => {},
+      Err(err) => warn!("failed to read published collab artifact: {}", err),
+    }
+
+    let blob = self
+      .inner
+      .get_collab_blob_by_publish_namespace(publish_namespace, publish_name)
+      .await?;
+    let bucket_client = self.bucket_client.clone();
+    let data = blob.clone();
+    tokio::spawn(async move {
+      if let Err(err) = bucket_client
+        .put_blob(&object_key, ByteStream::from(data), None)
+        .await
+      {
+        warn!("failed to write published collab artifact: {}", err);
+      }
+    });
+    Ok(blob)
+  }
+
+  async fn unpublish_collabs(
+    &self,
+    workspace_id: &Uuid,
+    view_ids: &[Uuid],
+    user_uuid: &Uuid,
+  ) -> Result<(), AppError> {
+    let result = self
+      .inner
+      .unpublish_collabs(workspace_id, view_ids, user_uuid)
+      .await;
+    self.invalidate(workspace_id).await;
+    result
+  }
+
+  async fn force_unpublish_collabs(
+    &self,
+    workspace_id: &Uuid,
+    view_ids: &[Uuid],
+  ) -> Result<(), AppError> {
+    let result = self
+      .inner
+      .force_unpublish_collabs(workspace_id, view_ids)
+      .await;
+    self.invalidate(workspace_id).await;
+    result
+  }
+
+  async fn patch_collabs(
+    &self,
+    workspace_id: &Uuid,
+    user_uuid: &Uuid,
+    patches: &[PatchPublishedCollab],
+  ) -> Result<(), AppError> {
+    let result = self
+      .inner
+      .patch_collabs(workspace_id, user_uuid, patches)
+      .await;
+    self.invalidate(workspace_id).await;
+    result
+  }
+
+  async fn invalidate_workspace_cache(&self, workspace_id: &Uuid) {
+    self.invalidate(workspace_id).await;
+  }
+}
+
+fn publish_version_key(workspace_id: &Uuid) -> String {
+  format!("af:published:version:{}", workspace_id)
+}
+
+fn entry_key(publish_namespace: &str, publish_name: &str) -> String {
+  format!("af:published:{}:{}", publish_namespace, publish_name)
+}
+
+fn artifact_dir(workspace_id: &Uuid, version: &str) -> String {
+  format!("published_cache/{}/{}", workspace_id, version)
+}
+
+fn redis_error(err: redis::RedisError) -> AppError {
+  AppError::Internal(anyhow::anyhow!("published collab cache: {}", err))
+}
@@ -0,0 +1,409 @@
+use app_error::AppError;
+use chrono::{DateTime, Utc};
+use database::pg_row::AFWorkspaceCustomDomainRow;
+use database::publish::{
+  delete_expired_custom_domain_claims, delete_pending_custom_domain_claims,
+  delete_workspace_custom_domain, insert_workspace_custom_domain, select_custom_domains_to_recheck,
+  select_workspace_custom_domain, select_workspace_custom_domains,
+  select_workspace_id_for_verified_domain, update_custom_domain_checked,
+  update_custom_domain_verified,
+};
+use database_entity::dto::PublishDomainInfo;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use super::publish::get_workspace_publish_namespace;
+use crate::config::config::PublishedCollabSetting;
+
+const CHALLENGE_RECORD_PREFIX: &str = "_appflowy-challenge";
+const CHALLENGE_VALUE_PREFIX: &str = "appflowy-domain-verification=";
+/// TXT record type in the DNS JSON API.
+const DNS_TXT_TYPE: u16 = 16;
+/// Number of verified domains checked again per query.
+const RECHECK_BATCH_SIZE: i64 = 100;
+
+/// Attaches a custom domain to the publish namespace of the workspace. The domain is not served
+/// until [verify_publish_domain] finds the challenge TXT record in its DNS zone. Until then, other
+/// workspaces may claim the same domain, each with its own token, and the claim expires after
+/// [PublishedCollabSetting::custom_domain_claim_ttl_secs].
+pub async fn add_publish_domain(
+  pg_pool: &PgPool,
+  setting: &PublishedCollabSetting,
+  workspace_id: &Uuid,
+  domain: &str,
+) -> Result<PublishDomainInfo, AppError> {
+  let domain = normalize_domain(domain)?;
+  if let Some(existing) = select_workspace_custom_domain(pg_pool, workspace_id, &domain).await? {
+    if !is_claim_expired(&existing, setting.custom_domain_claim_ttl_secs, Utc::now()) {
+      return Ok(publish_domain_info(existing, setting));
+    }
+    // the claim is renewed with a new token
+    delete_workspace_custom_domain(pg_pool, workspace_id, &domain).await?;
+  }
+  ensure_not_verified_by_other(pg_pool, workspace_id, &domain).await?;
+
+  let token: String = rand::thread_rng()
+    .sample_iter(&Alphanumeric)
+    .take(32)
+    .map(char::from)
+    .collect();
+  let row = insert_workspace_custom_domain(pg_pool, workspace_id, &domain, &token).await?;
+  Ok(publish_domain_info(row, setting))
+}
+
+pub async fn list_publish_domains(
+  pg_pool: &PgPool,
+  setting: &PublishedCollabSetting,
+  workspace_id: &Uuid,
+) -> Result<Vec<PublishDomainInfo>, AppError> {
+  let rows = select_workspace_custom_domains(pg_pool, workspace_id).await?;
+  Ok(
+    rows
+      .into_iter()
+      .map(|row| publish_domain_info(row, setting))
+      .collect(),
+  )
+}
+
+/// Looks up the challenge TXT record of the domain and marks the domain as verified when the
+/// record contains the token of the workspace.
+pub async fn verify_publish_domain(
+  pg_pool: &PgPool,
+  setting: &PublishedCollabSetting,
+  workspace_id: &Uuid,
+  domain: &str,
+) -> Result<PublishDomainInfo, AppError> {
+  let domain = normalize_domain(domain)?;
+  let row = select_workspace_custom_domain(pg_pool, workspace_id, &domain)
+    .await?
+    .filter(|row| !is_claim_expired(row, setting.custom_domain_claim_ttl_secs, Utc::now()))
+    .ok_or_else(|| AppError::RecordNotFound(format!("domain {} not found", domain)))?;
+  if row.verified_at.is_some() {
+    return Ok(publish_domain_info(row, setting));
+  }
+  ensure_not_verified_by_other(pg_pool, workspace_id, &domain).await?;
+
+  let record_name = challenge_record_name(&domain);
+  let expected = challenge_record_value(&row.verification_token);
+  let records = lookup_txt_records(&setting.custom_domain_dns_resolver_url, &record_name).await?;
+  debug!("TXT records of {}: {:?}", record_name, records);
+  if !has_challenge_record(&records, &row.verification_token) {
+    return Err(AppError::InvalidRequest(format!(
+      "TXT record {} with value {} is not found",
+      record_name, expected
+    )));
+  }
+
+  // the workspace proved the control of the domain, the claims of the other workspaces are void
+  let mut txn = pg_pool.begin().await?;
+  let row = update_custom_domain_verified(txn.as_mut(), workspace_id, &domain).await?;
+  delete_pending_custom_domain_claims(txn.as_mut(), &domain).await?;
+  txn.commit().await?;
+  Ok(publish_domain_info(row, setting))
+}
+
+/// Releases the expired claims and checks the TXT record of the verified domains again, so that a
+/// domain stops being served once its owner removes the record, e.g. after a transfer.
+pub async fn run_publish_domain_checker(pg_pool: PgPool, setting: PublishedCollabSetting) {
+  let recheck_interval = Duration::from_secs(setting.custom_domain_recheck_interval_secs.max(1));
+  // the checks are spread over the interval rather than all done at once
+  let mut ticker = interval(recheck_interval.min(Duration::from_secs(3600)));
+  ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+  loop {
+    ticker.tick().await;
+    let now = Utc::now();
+    let claim_ttl = chrono::Duration::seconds(setting.custom_domain_claim_ttl_secs as i64);
+    match delete_expired_custom_domain_claims(&pg_pool, now - claim_ttl).await {
+      Ok(0) => {},
+      Ok(count) => debug!("released {} expired custom domain claims", count),
+      Err(err) => warn!(
+        "failed to release the expired custom domain claims: {}",
+        err
+      ),
+    }
+
+    let checked_before = now - chrono::Duration::seconds(recheck_interval.as_secs() as i64);
+    let rows =
+      match select_custom_domains_to_recheck(&pg_pool, checked_before, RECHECK_BATCH_SIZE).await {
+        Ok(rows) => rows,
+        Err(err) => {
+          warn!("failed to select the custom domains to check: {}", err);
+          continue;
+        },
+      };
+    for row in rows {
+      let record_name = challenge_record_name(&row.domain);
+      let records =
+        match lookup_txt_records(&setting.custom_domain_dns_resolver_url, &record_name).await {
+          Ok(records) => records,
+          // the domain stays verified when the resolver can't be reached, it's checked again at
+          // the next tick
+          Err(err) => {
+            warn!("failed to check the custom domain {}: {}", row.domain, err);
+            continue;
+          },
+        };
+      let record_found = has_challenge_record(&records, &row.verification_token);
+      if !record_found {
+        info!(
+          "TXT record of the custom domain {} of workspace {} is gone, the domain is no longer served",
+          row.domain, row.workspace_id
+        );
+      }
+      if let Err(err) =
+        update_custom_domain_checked(&pg_pool, &row.workspace_id, &row.domain, record_found).await
+      {
+        warn!("failed to update the custom domain {}: {}", row.domain, err);
+      }
+    }
+  }
+}
+
+async fn ensure_not_verified_by_other(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  domain: &str,
+) -> Result<(), AppError> {
+  match select_workspace_id_for_verified_domain(pg_pool, domain).await? {
+    Some(owner) if &owner != workspace_id => Err(AppError::RecordAlreadyExists(format!(
+      "domain {} is already verified by another workspace",
+      domain
+    ))),
+    _ => Ok(()),
+  }
+}
+
+/// A claim that hasn't been verified within the claim TTL no longer holds the domain.
+fn is_claim_expired(
+  row: &AFWorkspaceCustomDomainRow,
+  claim_ttl_secs: u64,
+  now: DateTime<Utc>,
+) -> bool {
+  row.verified_at.is_none()
+    && row.created_at + chrono::Duration::seconds(claim_ttl_secs as i64) <= now
+}
+
+fn has_challenge_record(records: &[String], token: &str) -> bool {
+  let expected = challenge_record_value(token);
+  records.iter().any(|record| record == &expected)
+}
+
+pub async fn remove_publish_domain(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  domain: &str,
+) -> Result<(), AppError> {
+  let domain = normalize_domain(domain)?;
+  if !delete_workspace_custom_domain(pg_pool, workspace_id, &domain).await? {
+    return Err(AppError::RecordNotFound(format!(
+      "domain {} not found",
+      domain
+    )));
+  }
+  Ok(())
+}
+
+/// Returns the publish namespace served on the host, which is the `Host` header of the request
+/// and may contain a port.
+pub async fn get_publish_namespace_by_host(
+  pg_pool: &PgPool,
+  host: &str,
+) -> Result<String, AppError> {
+  let domain = normalize_domain(strip_port(host))?;
+  let workspace_id = select_workspace_id_for_verified_domain(pg_pool, &domain)
+    .await?
+    .ok_or_else(|| AppError::RecordNotFound(format!("no workspace is published on {}", domain)))?;
+  get_workspace_publish_namespace(pg_pool, &workspace_id).await
+}
+
+/// Used by the reverse proxy before issuing a certificate for a domain on demand.
+pub async fn is_publish_domain_verified(pg_pool: &PgPool, domain: &str) -> Result<bool, AppError> {
+  let domain = match normalize_domain(domain) {
+    Ok(domain) => domain,
+    Err(_) => return Ok(false),
+  };
+  Ok(
+    select_workspace_id_for_verified_domain(pg_pool, &domain)
+      .await?
+      .is_some(),
+  )
+}
+
+fn publish_domain_info(
+  row: AFWorkspaceCustomDomainRow,
+  setting: &PublishedCollabSetting,
+) -> PublishDomainInfo {
+  PublishDomainInfo {
+    txt_record_name: challenge_record_name(&row.domain),
+    txt_record_value: challenge_record_value(&row.verification_token),
+    domain: row.domain,
+    verified: row.verified_at.is_some(),
+    cname_target: setting.custom_domain_cname_target.clone(),
+    created_at: row.created_at,
+    verified_at: row.verified_at,
+  }
+}
+
+fn challenge_record_name(domain: &str) -> String {
+  format!("{}.{}", CHALLENGE_RECORD_PREFIX, domain)
+}
+
+fn challenge_record_value(token: &str) -> String {
+  format!("{}{}", CHALLENGE_VALUE_PREFIX, token)
+}
+
+fn strip_port(host: &str) -> &str {
+  match host.rsplit_once(':') {
+    Some((domain, port)) if port.chars().all(|c| c.is_ascii_digit()) => domain,
+    _ => host,
+  }
+}
+
+/// Lowercases the domain and checks that it is a valid host name with at least two labels.
+fn normalize_domain(domain: &str) -> Result<String, AppError> {
+  let domain = domain.trim().trim_end_matches('.').to_ascii_lowercase();
+  let invalid = || AppError::InvalidRequest(format!("invalid domain: {}", domain));
+  if domain.is_empty() || domain.len() > 253 {
+    return Err(invalid());
+  }
+  let labels: Vec<&str> = domain.split('.').collect();
+  if labels.len() < 2 {
+    return Err(invalid());
+  }
+  for label in &labels {
+    if label.is_empty()
+      || label.len() > 63
+      || label.starts_with('-')
+      || label.ends_with('-')
+      || !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+      return Err(invalid());
+    }
+  }
+  // The top level domain is never numeric, which also rules out IPv4 addresses
+  if labels
+    .last()
+    .map_or(true, |tld| tld.chars().all(|c| c.is_ascii_digit()))
+  {
+    return Err(invalid());
+  }
+  Ok(domain)
+}
+
+#[derive(Deserialize)]
+struct DnsJsonResponse {
+  #[serde(rename = "Answer", default)]
+  answer: Vec<DnsJsonAnswer>,
+}
+
+#[derive(Deserialize)]
+struct DnsJsonAnswer {
+  #[serde(rename = "type")]
+  record_type: u16,
+  data: String,
+}
+
+/// Resolves the TXT records with a DNS over HTTPS JSON API, like the ones of Cloudflare or Google.
+async fn lookup_txt_records(resolver_url: &str, name: &str) -> Result<Vec<String>, AppError> {
+  let resp = reqwest::Client::new()
+    .get(resolver_url)
+    .query(&[("name", name), ("type", "TXT")])
+    .header("accept", "application/dns-json")
+    .send()
+    .await
+    .and_then(|resp| resp.error_for_status())
+    .map_err(|err| AppError::Internal(anyhow::anyhow!("failed to resolve {}: {}", name, err)))?;
+  let resp = resp
+    .json::<DnsJsonResponse>()
+    .await
+    .map_err(|err| AppError::Internal(anyhow::anyhow!("failed to resolve {}: {}", name, err)))?;
+  Ok(
+    resp
+      .answer
+      .into_iter()
+      .filter(|answer| answer.record_type == DNS_TXT_TYPE)
+      .map(|answer| parse_txt_data(&answer.data))
+      .collect(),
+  )
+}
+
+/// TXT data is returned as one or more quoted strings, which are joined as per RFC 7208.
+fn parse_txt_data(data: &str) -> String {
+  let data = data.trim();
+  if !data.starts_with('"') {
+    return data.to_string();
+  }
+  data
+    .split('"')
+    .enumerate()
+    .filter(|(i, _)| i % 2 == 1)
+    .map(|(_, part)| part)
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn normalize_custom_domain() {
+    assert_eq!(
+      normalize_domain(" Docs.Example.com. ").unwrap(),
+      "docs.example.com"
+    );
+    assert!(normalize_domain("localhost").is_err());
+    assert!(normalize_domain("127.0.0.1").is_err());
+    assert!(normalize_domain("-bad.example.com").is_err());
+    assert!(normalize_domain("bad_label.example.com").is_err());
+    assert!(normalize_domain("example..com").is_err());
+  }
+
+  #[test]
+  fn host_port_is_stripped() {
+    assert_eq!(strip_port("docs.example.com:8000"), "docs.example.com");
+    assert_eq!(strip_port("docs.example.com"), "docs.example.com");
+  }
+
+  #[test]
+  fn pending_claims_expire() {
+    let now = Utc::now();
+    let mut row = AFWorkspaceCustomDomainRow {
+      domain: "docs.example.com".to_string(),
+      workspace_id: Uuid::new_v4(),
+      verification_token: "token".to_string(),
+      verified_at: None,
+      checked_at: None,
+      created_at: now - chrono::Duration::minutes(30),
+    };
+    assert!(!is_claim_expired(&row, 3600, now));
+    row.created_at = now - chrono::Duration::hours(2);
+    assert!(is_claim_expired(&row, 3600, now));
+    // verified domains don't expire, they are checked again instead
+    row.verified_at = Some(now - chrono::Duration::hours(1));
+    assert!(!is_claim_expired(&row, 3600, now));
+  }
+
+  #[test]
+  fn challenge_record_must_match_token() {
+    let records = vec![
+      "v=spf1 -all".to_string(),
+      "appflowy-domain-verification=token".to_string(),
+    ];
+    assert!(has_challenge_record(&records, "token"));
+    assert!(!has_challenge_record(&records, "other"));
+    assert!(!has_challenge_record(&[], "token"));
+  }
+
+  #[test]
+  fn txt_data_parsing() {
+    assert_eq!(parse_txt_data("\"abc\""), "abc");
+    assert_eq!(parse_txt_data("\"abc\" \"def\""), "abcdef");
+    assert_eq!(parse_txt_data("abc"), "abc");
+  }
+}
@@ -19,7 +19,7 @@ use collab_entity::CollabType;
 use collab_folder::{CollabOrigin, Folder, RepeatedViewIdentifier, View};
 use database::collab::GetCollabOrigin;
 use database::collab::{select_workspace_database_oid, CollabStorage};
-use database::file::s3_client_impl::AwsS3BucketClientImpl;
+use database::file::BlobStorageClient;
 use database::file::BucketClient;
 use database::file::ResponseBlob;
 use database::publish::select_published_data_for_view_id;
@@ -52,7 +52,7 @@ use super::ops::broadcast_update_with_timeout;
 #[allow(clippy::too_many_arguments)]
 pub async fn duplicate_published_collab_to_workspace(
   pg_pool: &PgPool,
-  bucket_client: AwsS3BucketClientImpl,
+  bucket_client: BlobStorageClient,
   collab_storage: Arc<CollabAccessControlStorage>,
   dest_uid: i64,
   publish_view_id: String,
@@ -104,7 +104,7 @@ pub struct PublishCollabDuplicator {
   /// and writing them to dest workspace
   pg_pool: PgPool,
   /// for fetching published data from s3
-  bucket_client: AwsS3BucketClientImpl,
+  bucket_client: BlobStorageClient,
   /// user initiating the duplication
   duplicator_uid: i64,
   /// workspace to duplicate into
@@ -116,7 +116,7 @@ pub struct PublishCollabDuplicator {
 impl PublishCollabDuplicator {
   pub fn new(
     pg_pool: PgPool,
-    bucket_client: AwsS3BucketClientImpl,
+    bucket_client: BlobStorageClient,
     collab_storage: Arc<CollabAccessControlStorage>,
     dest_uid: i64,
     dest_workspace_id: String,
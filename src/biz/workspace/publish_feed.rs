@@ -0,0 +1,238 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use app_error::AppError;
+use async_trait::async_trait;
+use database::file::{BlobStorageClient, BucketClient, ResponseBlob};
+use database::publish::{published_feed_object_key, select_workspace_id_for_publish_namespace};
+use database::workspace_delete::is_workspace_delete_pending;
+use database_entity::dto::{PatchPublishedCollab, PublishCollabItem, PublishInfo};
+use redis::AsyncCommands;
+use serde_json::json;
+use shared_entity::dto::publish_dto::PublishViewMetaData;
+use sqlx::PgPool;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::publish::PublishedCollabStore;
+use crate::state::RedisConnectionManager;
+
+const PUBLISH_FEED_STREAM: &str = "publish_feed_task_stream";
+
+/// Files generated by the worker for every publish namespace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublishFeedFile {
+  Sitemap,
+  Rss,
+  Atom,
+}
+
+impl PublishFeedFile {
+  pub fn file_name(&self) -> &'static str {
+    match self {
+      PublishFeedFile::Sitemap => "sitemap.xml",
+      PublishFeedFile::Rss => "rss.xml",
+      PublishFeedFile::Atom => "atom.xml",
+    }
+  }
+
+  pub fn content_type(&self) -> &'static str {
+    match self {
+      PublishFeedFile::Sitemap => "application/xml; charset=utf-8",
+      PublishFeedFile::Rss => "application/rss+xml; charset=utf-8",
+      PublishFeedFile::Atom => "application/atom+xml; charset=utf-8",
+    }
+  }
+}
+
+impl TryFrom<&str> for PublishFeedFile {
+  type Error = AppError;
+
+  fn try_from(value: &str) -> Result<Self, Self::Error> {
+    match value {
+      "sitemap.xml" => Ok(PublishFeedFile::Sitemap),
+      "rss.xml" => Ok(PublishFeedFile::Rss),
+      "atom.xml" => Ok(PublishFeedFile::Atom),
+      _ => Err(AppError::RecordNotFound(format!("{} not found", value))),
+    }
+  }
+}
+
+/// Asks the worker to regenerate the sitemap and the feeds of the workspace. The published pages
+/// are already saved at this point, so a failure is only logged.
+pub async fn queue_publish_feed_regeneration(
+  redis_client: &RedisConnectionManager,
+  workspace_id: &Uuid,
+) {
+  let task = json!({ "workspace_id": workspace_id });
+  let result: Result<(), _> = redis_client
+    .clone()
+    .xadd(PUBLISH_FEED_STREAM, "*", &[("task", task.to_string())])
+    .await;
+  if let Err(err) = result {
+    warn!(
+      "failed to queue publish feed regeneration for workspace {}: {}",
+      workspace_id, err
+    );
+  }
+}
+
+/// Queues the regeneration of the sitemap and the feeds whenever the published pages of a
+/// workspace change, whichever path changed them: a publish, an unpublish by the user or by an
+/// administrator, a patch or a new publish namespace.
+pub struct PublishedCollabFeedStore {
+  inner: Arc<dyn PublishedCollabStore>,
+  redis_client: RedisConnectionManager,
+}
+
+impl PublishedCollabFeedStore {
+  pub fn new(inner: Arc<dyn PublishedCollabStore>, redis_client: RedisConnectionManager) -> Self {
+    Self {
+      inner,
+      redis_client,
+    }
+  }
+}
+
+#[async_trait]
+impl PublishedCollabStore for PublishedCollabFeedStore {
+  async fn publish_collabs(
+    &self,
+    published_items: Vec<PublishCollabItem<serde_json::Value, Vec<u8>>>,
+    workspace_id: &Uuid,
+    user_uuid: &Uuid,
+  ) -> Result<(), AppError> {
+    self
+      .inner
+      .publish_collabs(published_items, workspace_id, user_uuid)
+      .await?;
+    queue_publish_feed_regeneration(&self.redis_client, workspace_id).await;
+    Ok(())
+  }
+
+  async fn get_collab_with_view_metadata_by_view_id(
+    &self,
+    view_id: &Uuid,
+  ) -> Result<Option<(PublishViewMetaData, Vec<u8>)>, AppError> {
+    self
+      .inner
+      .get_collab_with_view_metadata_by_view_id(view_id)
+      .await
+  }
+
+  async fn get_collab_metadata(
+    &self,
+    publish_namespace: &str,
+    publish_name: &str,
+  ) -> Result<serde_json::Value, AppError> {
+    self
+      .inner
+      .get_collab_metadata(publish_namespace, publish_name)
+      .await
+  }
+
+  async fn list_collab_publish_info(
+    &self,
+    workspace_id: &Uuid,
+  ) -> Result<Vec<PublishInfo>, AppError> {
+    self.inner.list_collab_publish_info(workspace_id).await
+  }
+
+  async fn get_collab_publish_info(&self, view_id: &Uuid) -> Result<PublishInfo, AppError> {
+    self.inner.get_collab_publish_info(view_id).await
+  }
+
+  async fn get_collab_blob_by_publish_namespace(
+    &self,
+    publish_namespace: &str,
+    publish_name: &str,
+  ) -> Result<Vec<u8>, AppError> {
+    self
+      .inner
+      .get_collab_blob_by_publish_namespace(publish_namespace, publish_name)
+      .await
+  }
+
+  async fn unpublish_collabs(
+    &self,
+    workspace_id: &Uuid,
+    view_ids: &[Uuid],
+    user_uuid: &Uuid,
+  ) -> Result<(), AppError> {
+    self
+      .inner
+      .unpublish_collabs(workspace_id, view_ids, user_uuid)
+      .await?;
+    queue_publish_feed_regeneration(&self.redis_client, workspace_id).await;
+    Ok(())
+  }
+
+  async fn force_unpublish_collabs(
+    &self,
+    workspace_id: &Uuid,
+    view_ids: &[Uuid],
+  ) -> Result<(), AppError> {
+    self
+      .inner
+      .force_unpublish_collabs(workspace_id, view_ids)
+      .await?;
+    queue_publish_feed_regeneration(&self.redis_client, workspace_id).await;
+    Ok(())
+  }
+
+  async fn patch_collabs(
+    &self,
+    workspace_id: &Uuid,
+    user_uuid: &Uuid,
+    patches: &[PatchPublishedCollab],
+  ) -> Result<(), AppError> {
+    self
+      .inner
+      .patch_collabs(workspace_id, user_uuid, patches)
+      .await?;
+    queue_publish_feed_regeneration(&self.redis_client, workspace_id).await;
+    Ok(())
+  }
+
+  async fn invalidate_workspace_cache(&self, workspace_id: &Uuid) {
+    self.inner.invalidate_workspace_cache(workspace_id).await;
+    queue_publish_feed_regeneration(&self.redis_client, workspace_id).await;
+  }
+}
+
+/// Returns the generated file of the publish namespace. The generation is queued when the file
+/// doesn't exist yet, for the namespaces published before the feeds were introduced. Nothing is
+/// returned for a workspace being deleted.
+pub async fn get_publish_feed(
+  pg_pool: &PgPool,
+  bucket_client: &BlobStorageClient,
+  redis_client: &RedisConnectionManager,
+  publish_namespace: &str,
+  file: PublishFeedFile,
+) -> Result<Vec<u8>, AppError> {
+  let workspace_id = select_workspace_id_for_publish_namespace(pg_pool, publish_namespace).await?;
+  if is_workspace_delete_pending(pg_pool, &workspace_id).await? {
+    return Err(AppError::RecordNotFound(format!(
+      "{} of {} not found",
+      file.file_name(),
+      publish_namespace
+    )));
+  }
+  let object_key = published_feed_object_key(&workspace_id, file.file_name());
+  match bucket_client.get_blob(&object_key).await {
+    Ok(resp) => Ok(resp.to_blob()),
+    Err(AppError::RecordNotFound(_)) => {
+      queue_publish_feed_regeneration(redis_client, &workspace_id).await;
+      Err(AppError::RecordNotFound(format!(
+        "{} of {} is being generated",
+        file.file_name(),
+        publish_namespace
+      )))
+    },
+    Err(err) => Err(AppError::Internal(anyhow!(
+      "failed to get {}: {}",
+      object_key,
+      err
+    ))),
+  }
+}
@@ -0,0 +1,386 @@
+use std::sync::Arc;
+
+use app_error::AppError;
+use async_trait::async_trait;
+use database::publish_report::{
+  insert_published_view_report, select_published_view_report, select_published_view_reports,
+  select_workspace_id_of_published_view, update_open_published_view_reports_status,
+  update_published_view_report_status,
+};
+use database_entity::dto::{PatchPublishedCollab, PublishCollabItem, PublishInfo};
+use shared_entity::dto::admin_dto::{AdminAuditAction, PublishReportStatus, PublishedViewReport};
+use shared_entity::dto::publish_dto::{
+  PublishDatabaseData, PublishViewMetaData, ReportPublishedViewParams,
+  MAX_PUBLISH_REPORT_DETAILS_LEN,
+};
+use sqlx::PgPool;
+use tracing::info;
+use uuid::Uuid;
+
+use super::publish::PublishedCollabStore;
+use crate::api::metrics::PublishedCollabMetrics;
+use crate::biz::user::user_admin::record_audit_log;
+use crate::config::config::PublishedCollabSetting;
+
+const DEFAULT_REPORT_LIMIT: i64 = 100;
+const MAX_REPORT_LIMIT: i64 = 1000;
+
+/// Denylists checked against the content of the views when they are published.
+pub struct PublishModeration {
+  denied_keywords: Vec<String>,
+  denied_domains: Vec<String>,
+}
+
+impl PublishModeration {
+  pub fn new(setting: &PublishedCollabSetting) -> Self {
+    Self {
+      denied_keywords: setting.moderation_denied_keywords.clone(),
+      denied_domains: setting.moderation_denied_domains.clone(),
+    }
+  }
+
+  pub fn is_enabled(&self) -> bool {
+    !self.denied_keywords.is_empty() || !self.denied_domains.is_empty()
+  }
+
+  /// Returns the first denied keyword or domain found in the text.
+  fn find_denied(&self, text: &str) -> Option<&str> {
+    let text = text.to_lowercase();
+    self
+      .denied_keywords
+      .iter()
+      .find(|keyword| text.contains(keyword.as_str()))
+      .or_else(|| {
+        self
+          .denied_domains
+          .iter()
+          .find(|domain| contains_domain(&text, domain))
+      })
+      .map(|entry| entry.as_str())
+  }
+
+  fn check(&self, view_id: &Uuid, text: &str) -> Result<(), AppError> {
+    match self.find_denied(text) {
+      None => Ok(()),
+      Some(entry) => {
+        info!(
+          "publishing of view {} rejected, the content matches {}",
+          view_id, entry
+        );
+        Err(AppError::PublishContentRejected(format!(
+          "the content of view {} is not allowed to be published",
+          view_id
+        )))
+      },
+    }
+  }
+}
+
+/// Returns true if the text contains the domain, or one of its subdomains, as a host name.
+fn contains_domain(text: &str, domain: &str) -> bool {
+  let is_host_char = |c: char| c.is_alphanumeric() || c == '-';
+  text.match_indices(domain).any(|(start, _)| {
+    let before = text[..start].chars().next_back();
+    let mut after = text[start + domain.len()..].chars();
+    let after_ok = match after.next() {
+      None => true,
+      Some('.') => !after.next().is_some_and(is_host_char),
+      Some(c) => !is_host_char(c),
+    };
+    !before.is_some_and(is_host_char) && after_ok
+  })
+}
+
+/// The text the denylists are checked against: the publish name, the metadata and the strings of
+/// the encoded collabs. The strings of a collab are stored as utf-8 in its encoding, so they are
+/// found without decoding the collab. The data of a published database is the json encoding of
+/// [PublishDatabaseData].
+fn published_text(item: &PublishCollabItem<serde_json::Value, Vec<u8>>) -> String {
+  let mut text = format!("{}\n{}\n", item.meta.publish_name, item.meta.metadata);
+  match serde_json::from_slice::<PublishDatabaseData>(&item.data) {
+    Ok(data) => {
+      let collabs = std::iter::once(&data.database_collab)
+        .chain(data.database_row_collabs.values())
+        .chain(data.database_row_document_collabs.values());
+      for collab in collabs {
+        text.push_str(&String::from_utf8_lossy(collab));
+        text.push('\n');
+      }
+    },
+    Err(_) => text.push_str(&String::from_utf8_lossy(&item.data)),
+  }
+  text
+}
+
+/// Checks the published content against the moderation denylists before handing it to the inner
+/// store. The other operations are passed through.
+pub struct PublishedCollabModeratedStore {
+  inner: Arc<dyn PublishedCollabStore>,
+  moderation: PublishModeration,
+  metrics: Arc<PublishedCollabMetrics>,
+}
+
+impl PublishedCollabModeratedStore {
+  pub fn new(
+    inner: Arc<dyn PublishedCollabStore>,
+    moderation: PublishModeration,
+    metrics: Arc<PublishedCollabMetrics>,
+  ) -> Self {
+    Self {
+      inner,
+      moderation,
+      metrics,
+    }
+  }
+
+  fn check(&self, view_id: &Uuid, text: &str) -> Result<(), AppError> {
+    let result = self.moderation.check(view_id, text);
+    if result.is_err() {
+      self.metrics.incr_moderation_rejected_count(1);
+    }
+    result
+  }
+}
+
+#[async_trait]
+impl PublishedCollabStore for PublishedCollabModeratedStore {
+  async fn publish_collabs(
+    &self,
+    published_items: Vec<PublishCollabItem<serde_json::Value, Vec<u8>>>,
+    workspace_id: &Uuid,
+    user_uuid: &Uuid,
+  ) -> Result<(), AppError> {
+    for item in &published_items {
+      self.check(&item.meta.view_id, &published_text(item))?;
+    }
+    self
+      .inner
+      .publish_collabs(published_items, workspace_id, user_uuid)
+      .await
+  }
+
+  async fn get_collab_with_view_metadata_by_view_id(
+    &self,
+    view_id: &Uuid,
+  ) -> Result<Option<(PublishViewMetaData, Vec<u8>)>, AppError> {
+    self
+      .inner
+      .get_collab_with_view_metadata_by_view_id(view_id)
+      .await
+  }
+
+  async fn get_collab_metadata(
+    &self,
+    publish_namespace: &str,
+    publish_name: &str,
+  ) -> Result<serde_json::Value, AppError> {
+    self
+      .inner
+      .get_collab_metadata(publish_namespace, publish_name)
+      .await
+  }
+
+  async fn list_collab_publish_info(
+    &self,
+    workspace_id: &Uuid,
+  ) -> Result<Vec<PublishInfo>, AppError> {
+    self.inner.list_collab_publish_info(workspace_id).await
+  }
+
+  async fn get_collab_publish_info(&self, view_id: &Uuid) -> Result<PublishInfo, AppError> {
+    self.inner.get_collab_publish_info(view_id).await
+  }
+
+  async fn get_collab_blob_by_publish_namespace(
+    &self,
+    publish_namespace: &str,
+    publish_name: &str,
+  ) -> Result<Vec<u8>, AppError> {
+    self
+      .inner
+      .get_collab_blob_by_publish_namespace(publish_namespace, publish_name)
+      .await
+  }
+
+  async fn unpublish_collabs(
+    &self,
+    workspace_id: &Uuid,
+    view_ids: &[Uuid],
+    user_uuid: &Uuid,
+  ) -> Result<(), AppError> {
+    self
+      .inner
+      .unpublish_collabs(workspace_id, view_ids, user_uuid)
+      .await
+  }
+
+  async fn force_unpublish_collabs(
+    &self,
+    workspace_id: &Uuid,
+    view_ids: &[Uuid],
+  ) -> Result<(), AppError> {
+    self
+      .inner
+      .force_unpublish_collabs(workspace_id, view_ids)
+      .await
+  }
+
+  async fn patch_collabs(
+    &self,
+    workspace_id: &Uuid,
+    user_uuid: &Uuid,
+    patches: &[PatchPublishedCollab],
+  ) -> Result<(), AppError> {
+    for patch in patches {
+      if let Some(publish_name) = &patch.publish_name {
+        self.check(&patch.view_id, publish_name)?;
+      }
+    }
+    self
+      .inner
+      .patch_collabs(workspace_id, user_uuid, patches)
+      .await
+  }
+
+  async fn invalidate_workspace_cache(&self, workspace_id: &Uuid) {
+    self.inner.invalidate_workspace_cache(workspace_id).await
+  }
+}
+
+/// Records an abuse report on a published view. Visitors who are not signed in can report too.
+pub async fn report_published_view(
+  pg_pool: &PgPool,
+  metrics: &PublishedCollabMetrics,
+  view_id: &Uuid,
+  reporter_uuid: Option<Uuid>,
+  params: ReportPublishedViewParams,
+) -> Result<(), AppError> {
+  let details = params
+    .details
+    .map(|details| details.trim().to_string())
+    .filter(|details| !details.is_empty());
+  if let Some(details) = &details {
+    if details.chars().count() > MAX_PUBLISH_REPORT_DETAILS_LEN {
+      return Err(AppError::StringLengthLimitReached(format!(
+        "the details of a report can not be longer than {} characters",
+        MAX_PUBLISH_REPORT_DETAILS_LEN
+      )));
+    }
+  }
+  let workspace_id = select_workspace_id_of_published_view(pg_pool, view_id)
+    .await?
+    .ok_or_else(|| AppError::RecordNotFound(format!("view {} is not published", view_id)))?;
+  insert_published_view_report(
+    pg_pool,
+    view_id,
+    &workspace_id,
+    reporter_uuid.as_ref(),
+    params.reason.as_str(),
+    details.as_deref(),
+  )
+  .await?;
+  metrics.incr_abuse_report_count(1);
+  Ok(())
+}
+
+pub async fn list_publish_reports(
+  pg_pool: &PgPool,
+  status: Option<PublishReportStatus>,
+  offset: Option<i64>,
+  limit: Option<i64>,
+) -> Result<Vec<PublishedViewReport>, AppError> {
+  let offset = offset.unwrap_or(0).max(0);
+  let limit = limit
+    .unwrap_or(DEFAULT_REPORT_LIMIT)
+    .clamp(1, MAX_REPORT_LIMIT);
+  let rows = select_published_view_reports(
+    pg_pool,
+    status.unwrap_or(PublishReportStatus::Open),
+    offset,
+    limit,
+  )
+  .await?;
+  Ok(rows.into_iter().map(PublishedViewReport::from).collect())
+}
+
+/// Closes the report without acting on the view.
+pub async fn dismiss_publish_report(
+  pg_pool: &PgPool,
+  actor_uuid: Uuid,
+  report_id: Uuid,
+) -> Result<(), AppError> {
+  let result = async {
+    let updated = update_published_view_report_status(
+      pg_pool,
+      &report_id,
+      PublishReportStatus::Dismissed,
+      &actor_uuid,
+    )
+    .await?;
+    if !updated {
+      return Err(report_already_reviewed(&report_id));
+    }
+    Ok(())
+  }
+  .await;
+  record_audit_log(
+    pg_pool,
+    actor_uuid,
+    AdminAuditAction::DismissPublishReport,
+    &report_id.to_string(),
+    result.as_ref().map(|_| ()).map_err(|err| err.to_string()),
+    None,
+  )
+  .await;
+  result
+}
+
+/// Unpublishes the reported view, whoever published it, and closes all the open reports of the
+/// view.
+pub async fn unpublish_reported_view(
+  pg_pool: &PgPool,
+  published_collab_store: &Arc<dyn PublishedCollabStore>,
+  metrics: &PublishedCollabMetrics,
+  actor_uuid: Uuid,
+  report_id: Uuid,
+) -> Result<(), AppError> {
+  let report = select_published_view_report(pg_pool, &report_id).await?;
+  let result = async {
+    if PublishReportStatus::from(report.status) != PublishReportStatus::Open {
+      return Err(report_already_reviewed(&report_id));
+    }
+    published_collab_store
+      .force_unpublish_collabs(&report.workspace_id, &[report.view_id])
+      .await?;
+    update_open_published_view_reports_status(
+      pg_pool,
+      &report.view_id,
+      PublishReportStatus::Unpublished,
+      &actor_uuid,
+    )
+    .await?;
+    Ok(())
+  }
+  .await;
+  record_audit_log(
+    pg_pool,
+    actor_uuid,
+    AdminAuditAction::UnpublishReportedView,
+    &report_id.to_string(),
+    result.as_ref().map(|_| ()).map_err(|err| err.to_string()),
+    Some(format!("view {}", report.view_id)),
+  )
+  .await;
+  result?;
+
+  metrics.incr_moderation_unpublished_count(1);
+  info!(
+    "admin {} unpublished view {} after report {}",
+    actor_uuid, report.view_id, report_id
+  );
+  Ok(())
+}
+
+fn report_already_reviewed(report_id: &Uuid) -> AppError {
+  AppError::InvalidRequest(format!("report {} has already been reviewed", report_id))
+}
@@ -0,0 +1,106 @@
+use std::collections::HashSet;
+
+use access_control::restricted_view::RestrictedViewCache;
+use app_error::AppError;
+use appflowy_collaborate::collab::storage::CollabAccessControlStorage;
+use database::collab::GetCollabOrigin;
+use database::restricted_view::{select_restricted_views, AFRestrictedViewRow};
+use database::workspace::select_workspace_member_list;
+use shared_entity::dto::workspace_dto::{AFRestrictedView, RestrictViewParams};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::biz::collab::utils::get_latest_collab_folder;
+
+pub async fn list_restricted_views(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+) -> Result<Vec<AFRestrictedView>, AppError> {
+  let rows = select_restricted_views(pg_pool, workspace_id).await?;
+  Ok(rows.into_iter().map(restricted_view_from_row).collect())
+}
+
+/// Restricts the view and the views below it to the given members of the workspace. The members
+/// of a view that was already restricted are replaced.
+pub async fn restrict_view(
+  collab_storage: &CollabAccessControlStorage,
+  pg_pool: &PgPool,
+  restricted_view_cache: &RestrictedViewCache,
+  workspace_id: &Uuid,
+  view_id: &str,
+  uid: i64,
+  params: RestrictViewParams,
+) -> Result<AFRestrictedView, AppError> {
+  if view_id == workspace_id.to_string() {
+    return Err(AppError::InvalidRequest(
+      "the root of the workspace can not be restricted".to_string(),
+    ));
+  }
+  let folder = get_latest_collab_folder(
+    collab_storage,
+    GetCollabOrigin::User { uid },
+    &workspace_id.to_string(),
+  )
+  .await?;
+  if folder.get_view(view_id).is_none() {
+    return Err(AppError::RecordNotFound(format!(
+      "view {} not found",
+      view_id
+    )));
+  }
+
+  let workspace_members: HashSet<i64> = select_workspace_member_list(pg_pool, workspace_id)
+    .await?
+    .into_iter()
+    .map(|member| member.uid)
+    .collect();
+  let mut member_uids = params.member_uids;
+  member_uids.push(uid);
+  member_uids.sort_unstable();
+  member_uids.dedup();
+  if let Some(uid) = member_uids
+    .iter()
+    .find(|uid| !workspace_members.contains(uid))
+  {
+    return Err(AppError::InvalidRequest(format!(
+      "user {} is not a member of the workspace",
+      uid
+    )));
+  }
+
+  restricted_view_cache
+    .restrict(workspace_id, view_id, uid, &member_uids)
+    .await?;
+  select_restricted_views(pg_pool, workspace_id)
+    .await?
+    .into_iter()
+    .find(|row| row.view_id == view_id)
+    .map(restricted_view_from_row)
+    .ok_or_else(|| AppError::RecordNotFound(format!("view {} is not restricted", view_id)))
+}
+
+pub async fn unrestrict_view(
+  restricted_view_cache: &RestrictedViewCache,
+  workspace_id: &Uuid,
+  view_id: &str,
+) -> Result<(), AppError> {
+  if !restricted_view_cache
+    .unrestrict(workspace_id, view_id)
+    .await?
+  {
+    return Err(AppError::RecordNotFound(format!(
+      "view {} is not restricted",
+      view_id
+    )));
+  }
+  Ok(())
+}
+
+fn restricted_view_from_row(row: AFRestrictedViewRow) -> AFRestrictedView {
+  AFRestrictedView {
+    view_id: row.view_id,
+    member_uids: row.member_uids,
+    created_by: row.created_by,
+    created_at: row.created_at,
+  }
+}
@@ -0,0 +1,92 @@
+use app_error::AppError;
+use database::pg_row::AFWorkspaceRetentionPolicyRow;
+use database::retention::{
+  count_expired_chat_messages, count_expired_collab_snapshots, select_workspace_retention_policy,
+  upsert_workspace_retention_policy,
+};
+use shared_entity::dto::workspace_dto::{
+  RetentionDryRunQuery, RetentionDryRunReport, UpdateWorkspaceRetentionPolicyParams,
+  WorkspaceRetentionPolicy,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const MIN_RETENTION_DAYS: i32 = 1;
+const MAX_RETENTION_DAYS: i32 = 3650;
+
+pub async fn get_workspace_retention_policy(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+) -> Result<WorkspaceRetentionPolicy, AppError> {
+  let policy = select_workspace_retention_policy(pg_pool, workspace_id)
+    .await?
+    .map(retention_policy_from_row)
+    .unwrap_or_default();
+  Ok(policy)
+}
+
+pub async fn update_workspace_retention_policy(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  params: UpdateWorkspaceRetentionPolicyParams,
+) -> Result<WorkspaceRetentionPolicy, AppError> {
+  validate_retention_days("chat_history_days", params.chat_history_days)?;
+  validate_retention_days("snapshot_days", params.snapshot_days)?;
+  let row = upsert_workspace_retention_policy(
+    pg_pool,
+    workspace_id,
+    params.chat_history_days,
+    params.snapshot_days,
+  )
+  .await?;
+  Ok(retention_policy_from_row(row))
+}
+
+/// Counts the data that the cleanup would delete with the given policy, without deleting
+/// anything.
+pub async fn dry_run_workspace_retention_policy(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  query: RetentionDryRunQuery,
+) -> Result<RetentionDryRunReport, AppError> {
+  validate_retention_days("chat_history_days", query.chat_history_days)?;
+  validate_retention_days("snapshot_days", query.snapshot_days)?;
+  let current = get_workspace_retention_policy(pg_pool, workspace_id).await?;
+  let chat_history_days = query.chat_history_days.or(current.chat_history_days);
+  let snapshot_days = query.snapshot_days.or(current.snapshot_days);
+
+  let chat_messages = match chat_history_days {
+    Some(days) => count_expired_chat_messages(pg_pool, workspace_id, days).await?,
+    None => 0,
+  };
+  let snapshots = match snapshot_days {
+    Some(days) => count_expired_collab_snapshots(pg_pool, workspace_id, days).await?,
+    None => 0,
+  };
+  Ok(RetentionDryRunReport {
+    chat_history_days,
+    snapshot_days,
+    chat_messages,
+    snapshots,
+  })
+}
+
+fn validate_retention_days(field: &str, days: Option<i32>) -> Result<(), AppError> {
+  match days {
+    Some(days) if !(MIN_RETENTION_DAYS..=MAX_RETENTION_DAYS).contains(&days) => {
+      Err(AppError::InvalidRequest(format!(
+        "{} must be between {} and {} days",
+        field, MIN_RETENTION_DAYS, MAX_RETENTION_DAYS
+      )))
+    },
+    _ => Ok(()),
+  }
+}
+
+fn retention_policy_from_row(row: AFWorkspaceRetentionPolicyRow) -> WorkspaceRetentionPolicy {
+  WorkspaceRetentionPolicy {
+    chat_history_days: row.chat_history_days,
+    snapshot_days: row.snapshot_days,
+    updated_at: Some(row.updated_at),
+  }
+}
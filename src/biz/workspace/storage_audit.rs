@@ -0,0 +1,82 @@
+use app_error::AppError;
+use database::collab::select_workspace_collab_blobs;
+use database::file::{
+  find_cross_workspace_blob_references, verify_workspace_object_key, workspace_object_key_prefix,
+};
+use database::resource_usage::get_all_workspace_blob_ids;
+use shared_entity::dto::file_dto::{
+  CrossWorkspaceBlobReference, StoragePrefixAuditQuery, StoragePrefixAuditReport,
+};
+use sqlx::PgPool;
+use tracing::warn;
+use uuid::Uuid;
+
+const DEFAULT_AUDIT_COLLAB_LIMIT: u32 = 500;
+const MAX_AUDIT_COLLAB_LIMIT: u32 = 5000;
+
+/// Scans the blob metadata and the collabs of the workspace for object keys that escape the
+/// workspace prefix and for references to the blobs of other workspaces.
+pub async fn audit_workspace_storage_prefix(
+  pg_pool: &PgPool,
+  workspace_id: &Uuid,
+  query: StoragePrefixAuditQuery,
+) -> Result<StoragePrefixAuditReport, AppError> {
+  let invalid_blob_keys = if query.cursor.is_none() {
+    get_all_workspace_blob_ids(pg_pool, workspace_id)
+      .await?
+      .into_iter()
+      .filter(|file_id| {
+        let object_key = format!("{}{}", workspace_object_key_prefix(workspace_id), file_id);
+        verify_workspace_object_key(workspace_id, &object_key).is_err()
+      })
+      .collect()
+  } else {
+    vec![]
+  };
+
+  let limit = query
+    .limit
+    .unwrap_or(DEFAULT_AUDIT_COLLAB_LIMIT)
+    .clamp(1, MAX_AUDIT_COLLAB_LIMIT);
+  let collabs =
+    select_workspace_collab_blobs(pg_pool, workspace_id, query.cursor.as_deref(), limit as i64)
+      .await?;
+
+  let scanned_collabs = collabs.len();
+  let next_cursor = if scanned_collabs == limit as usize {
+    collabs.last().map(|(object_id, _)| object_id.clone())
+  } else {
+    None
+  };
+  let cross_workspace_references: Vec<_> = collabs
+    .into_iter()
+    .flat_map(|(object_id, blob)| {
+      find_cross_workspace_blob_references(workspace_id, &blob)
+        .into_iter()
+        .map(
+          move |(referenced_workspace_id, url_path)| CrossWorkspaceBlobReference {
+            object_id: object_id.clone(),
+            referenced_workspace_id: referenced_workspace_id.to_string(),
+            url_path,
+          },
+        )
+    })
+    .collect();
+
+  if !invalid_blob_keys.is_empty() || !cross_workspace_references.is_empty() {
+    warn!(
+      "storage prefix audit of workspace {}: {} invalid blob keys, {} cross workspace references",
+      workspace_id,
+      invalid_blob_keys.len(),
+      cross_workspace_references.len()
+    );
+  }
+
+  Ok(StoragePrefixAuditReport {
+    workspace_id: workspace_id.to_string(),
+    invalid_blob_keys,
+    scanned_collabs,
+    cross_workspace_references,
+    next_cursor,
+  })
+}
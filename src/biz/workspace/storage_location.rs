@@ -0,0 +1,168 @@
+use app_error::AppError;
+use database::file::{BlobStorageBackend, WorkspaceStorageRouter};
+use database::workspace::select_workspace;
+use database::workspace_storage::{queue_workspace_storage_relocation, select_workspace_storage};
+use shared_entity::dto::admin_dto::{
+  AdminAuditAction, UpdateWorkspaceStorageParams, WorkspaceStorageInfo,
+  WorkspaceStorageRelocationStatus, WorkspaceStorageSettings,
+};
+use sqlx::PgPool;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::biz::user::user_admin::record_audit_log;
+use crate::config::config::Config;
+
+/// Returns the storage of the workspace, without the keys of its bucket.
+pub async fn get_workspace_storage(
+  pg_pool: &PgPool,
+  router: Option<&WorkspaceStorageRouter>,
+  workspace_id: &Uuid,
+) -> Result<WorkspaceStorageInfo, AppError> {
+  let router = router.ok_or_else(workspace_storage_disabled)?;
+  let Some(row) = select_workspace_storage(pg_pool, workspace_id).await? else {
+    return Ok(WorkspaceStorageInfo {
+      workspace_id: *workspace_id,
+      location: None,
+      target: None,
+      relocation_status: WorkspaceStorageRelocationStatus::Idle,
+      relocated_objects: 0,
+      relocation_error: None,
+      updated_at: None,
+    });
+  };
+  let relocation_status = WorkspaceStorageRelocationStatus::from(row.relocation_status);
+  let location = match &row.settings {
+    Some(settings) => Some(router.cipher().decrypt(settings)?.location()),
+    None => None,
+  };
+  let target = match &row.target_settings {
+    Some(settings) => Some(router.cipher().decrypt(settings)?.location()),
+    None => None,
+  };
+  Ok(WorkspaceStorageInfo {
+    workspace_id: row.workspace_id,
+    location,
+    target,
+    relocation_status,
+    relocated_objects: row.relocated_objects,
+    relocation_error: row.relocation_error,
+    updated_at: Some(row.updated_at),
+  })
+}
+
+/// Queues the move of the objects of the workspace to another storage. The worker copies the
+/// objects, switches the workspace to the new storage, then empties the previous one.
+pub async fn relocate_workspace_storage(
+  pg_pool: &PgPool,
+  config: &Config,
+  router: Option<&WorkspaceStorageRouter>,
+  actor_uuid: Uuid,
+  workspace_id: &Uuid,
+  params: UpdateWorkspaceStorageParams,
+) -> Result<WorkspaceStorageInfo, AppError> {
+  let router = router.ok_or_else(workspace_storage_disabled)?;
+  let result = async {
+    if let Some(settings) = &params.settings {
+      validate_settings(config, settings)?;
+    }
+    select_workspace(pg_pool, workspace_id).await?;
+    let current = match select_workspace_storage(pg_pool, workspace_id).await? {
+      Some(row) => match &row.settings {
+        Some(settings) => Some(router.cipher().decrypt(settings)?),
+        None => None,
+      },
+      None => None,
+    };
+    match (&current, &params.settings) {
+      (None, None) => {
+        return Err(AppError::InvalidRequest(
+          "The workspace already uses the default storage".to_string(),
+        ))
+      },
+      (Some(current), Some(target)) if current.is_same_bucket(target) => {
+        return Err(AppError::InvalidRequest(format!(
+          "The workspace already uses the bucket {}",
+          target.bucket
+        )))
+      },
+      _ => {},
+    }
+
+    let target_settings = match &params.settings {
+      Some(settings) => Some(router.cipher().encrypt(settings)?),
+      None => None,
+    };
+    if !queue_workspace_storage_relocation(pg_pool, workspace_id, target_settings.as_deref())
+      .await?
+    {
+      return Err(AppError::Conflict(format!(
+        "The storage of workspace {} is already being relocated",
+        workspace_id
+      )));
+    }
+    Ok(())
+  }
+  .await;
+
+  let target = params
+    .settings
+    .as_ref()
+    .map(|settings| format!("to bucket {} in {}", settings.bucket, settings.region))
+    .unwrap_or_else(|| "to the default storage".to_string());
+  record_audit_log(
+    pg_pool,
+    actor_uuid,
+    AdminAuditAction::RelocateWorkspaceStorage,
+    &workspace_id.to_string(),
+    result.as_ref().map(|_| ()).map_err(|err| err.to_string()),
+    Some(target.clone()),
+  )
+  .await;
+  result?;
+
+  info!(
+    "admin {} queued the relocation of workspace {} {}",
+    actor_uuid, workspace_id, target
+  );
+  get_workspace_storage(pg_pool, Some(router), workspace_id).await
+}
+
+fn validate_settings(config: &Config, settings: &WorkspaceStorageSettings) -> Result<(), AppError> {
+  if settings.bucket.trim().is_empty() || settings.region.trim().is_empty() {
+    return Err(AppError::InvalidRequest(
+      "The bucket and the region of the storage are required".to_string(),
+    ));
+  }
+  if settings.access_key.is_some() != settings.secret_key.is_some() {
+    return Err(AppError::InvalidRequest(
+      "The access key and the secret key must be set together".to_string(),
+    ));
+  }
+  if let Some(endpoint) = &settings.endpoint {
+    let url = reqwest::Url::parse(endpoint)
+      .map_err(|err| AppError::InvalidRequest(format!("Invalid endpoint {}: {}", endpoint, err)))?;
+    if !matches!(url.scheme(), "http" | "https") {
+      return Err(AppError::InvalidRequest(format!(
+        "Invalid endpoint {}: the scheme must be http or https",
+        endpoint
+      )));
+    }
+  }
+  // Relocating the objects to the bucket they are already in would delete them once copied.
+  let uses_default_bucket = matches!(
+    config.blob_storage.backend,
+    BlobStorageBackend::S3 | BlobStorageBackend::Gcs
+  ) && settings.bucket == config.s3.bucket;
+  if uses_default_bucket {
+    return Err(AppError::InvalidRequest(format!(
+      "The bucket {} is the default storage of the instance",
+      settings.bucket
+    )));
+  }
+  Ok(())
+}
+
+fn workspace_storage_disabled() -> AppError {
+  AppError::InvalidRequest("Per-workspace storage is not enabled on this instance".to_string())
+}
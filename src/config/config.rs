@@ -1,14 +1,19 @@
 use std::fmt::Display;
+use std::net::IpAddr;
 use std::str::FromStr;
 
 use anyhow::Context;
+use ipnet::IpNet;
 use secrecy::{ExposeSecret, Secret};
 use semver::Version;
 use serde::Deserialize;
 use sqlx::postgres::{PgConnectOptions, PgSslMode};
 
+use database::file::gcs_client_impl::GCS_XML_API_ENDPOINT;
+use database::file::BlobStorageBackend;
 use infra::env_util::{get_env_var, get_env_var_opt};
 use mailer::config::MailerSetting;
+use shared_entity::dto::server_info_dto::RegionInfo;
 
 #[derive(Clone, Debug)]
 pub struct Config {
@@ -21,13 +26,19 @@ pub struct Config {
   pub redis_uri: Secret<String>,
   pub redis_worker_count: usize,
   pub s3: S3Setting,
+  pub blob_storage: BlobStorageSetting,
   pub appflowy_ai: AppFlowyAISetting,
   pub collab: CollabSetting,
   pub published_collab: PublishedCollabSetting,
+  pub billing: BillingSetting,
   pub mailer: MailerSetting,
   pub apple_oauth: AppleOAuthSetting,
   pub appflowy_web_url: Option<String>,
   pub admin_frontend_path_prefix: String,
+  pub cors: CorsSetting,
+  pub push: PushSetting,
+  pub database_form: DatabaseFormSetting,
+  pub region: RegionSetting,
 }
 
 #[derive(serde::Deserialize, Clone, Debug)]
@@ -62,6 +73,117 @@ pub struct S3Setting {
   pub presigned_url_endpoint: Option<String>,
 }
 
+/// Selects where blobs are stored. The [S3Setting] bucket and keys are used by the S3 and GCS
+/// backends, GCS expecting HMAC keys.
+#[derive(Clone, Debug)]
+pub struct BlobStorageSetting {
+  pub backend: BlobStorageBackend,
+  pub gcs_endpoint: String,
+  pub azure: AzureBlobSetting,
+  pub local: LocalFsBlobSetting,
+  /// Base64 encoded 32 bytes key encrypting the storage settings of the workspaces that use their
+  /// own bucket. Per-workspace storage is disabled when not set.
+  pub workspace_storage_key: Option<Secret<String>>,
+  pub workspace_storage_cache_ttl_secs: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct AzureBlobSetting {
+  pub account_name: String,
+  pub account_key: Secret<String>,
+  pub container: String,
+  /// Overrides the account endpoint, e.g. to use the Azurite emulator.
+  pub endpoint: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LocalFsBlobSetting {
+  /// Directory holding the blobs, shared with the appflowy_collaborate and appflowy_worker.
+  pub root: String,
+  /// Key used to sign the presigned urls.
+  pub signing_key: Secret<String>,
+  /// Base url of this server, used to build the presigned urls.
+  pub public_url: String,
+}
+
+/// Credentials of the push services, no push notification is sent to the devices of a platform
+/// without credentials.
+#[derive(Clone, Debug)]
+pub struct PushSetting {
+  pub apns: Option<ApnsSetting>,
+  pub fcm: Option<FcmSetting>,
+  /// Attempts to deliver a notification when the push service is unavailable or rate limits.
+  pub max_attempts: u32,
+}
+
+/// Token based authentication of the Apple Push Notification service.
+#[derive(Clone, Debug)]
+pub struct ApnsSetting {
+  pub team_id: String,
+  pub key_id: String,
+  /// Content of the `.p8` signing key.
+  pub private_key: Secret<String>,
+  /// Bundle id of the app.
+  pub topic: String,
+  pub use_sandbox: bool,
+}
+
+/// Service account of the Firebase project, used with the FCM HTTP v1 API.
+#[derive(Clone, Debug)]
+pub struct FcmSetting {
+  pub project_id: String,
+  pub client_email: String,
+  pub private_key: Secret<String>,
+}
+
+/// Protection of the rows submitted with the database form tokens.
+#[derive(Clone, Debug)]
+pub struct DatabaseFormSetting {
+  /// Rows accepted per minute for a token from the same client address.
+  pub rate_limit_per_minute: u32,
+  /// Rows accepted per minute for a token from all the client addresses.
+  pub token_rate_limit_per_minute: u32,
+  /// The reverse proxies whose forwarded client address is trusted. The address of the peer is
+  /// used for the requests that don't come from one of them.
+  pub trusted_proxies: Vec<IpNet>,
+  /// Every submission must carry a valid captcha response when set.
+  pub captcha: Option<CaptchaSetting>,
+}
+
+/// The regions of a deployment across regions. The realtime connections of the clients are hinted
+/// to the region the workspace they open resides in, or to the region they have the lowest latency
+/// to.
+#[derive(Clone, Debug)]
+pub struct RegionSetting {
+  /// Name of the region of this server, empty when the server is deployed in a single region.
+  pub current: String,
+  /// Every region of the deployment, including the current one.
+  pub regions: Vec<RegionInfo>,
+  /// A client is only hinted to a region with a lower latency than the current region if it saves
+  /// at least this many milliseconds.
+  pub min_latency_gain_ms: u32,
+}
+
+/// Verification of the captcha responses with a siteverify endpoint, as provided by Turnstile,
+/// hCaptcha and reCAPTCHA.
+#[derive(Clone, Debug)]
+pub struct CaptchaSetting {
+  pub verify_url: String,
+  pub secret: Secret<String>,
+}
+
+/// CORS and Content-Security-Policy headers, see [crate::middleware::cors_mw::CorsMiddleware].
+#[derive(Clone, Debug)]
+pub struct CorsSetting {
+  /// Exact origins, origins with a wildcard subdomain like `https://*.example.com`, or `*`.
+  pub allowed_origins: Vec<String>,
+  pub allowed_methods: String,
+  pub allowed_headers: String,
+  pub max_age_secs: u64,
+  pub allow_credentials: bool,
+  pub content_security_policy: Option<String>,
+}
+
 #[derive(serde::Deserialize, Clone, Debug)]
 pub struct GoTrueSetting {
   pub base_url: String,
@@ -75,6 +197,10 @@ pub struct GoTrueSetting {
 pub struct AppFlowyAISetting {
   pub port: Secret<String>,
   pub host: Secret<String>,
+  /// Url of a LanguageTool compatible server. The language checks go to the AI service when it's
+  /// not set.
+  pub language_check_url: Option<String>,
+  pub language_check_cache_ttl_secs: u64,
 }
 
 impl AppFlowyAISetting {
@@ -99,6 +225,27 @@ impl AppFlowyAISetting {
 pub struct ApplicationSetting {
   pub port: u16,
   pub host: String,
+  pub payload_limits: PayloadLimitSetting,
+}
+
+/// Maximum request body size, in bytes, of each class of endpoints. Requests exceeding the limit
+/// are rejected with 413 as soon as the Content-Length is known, or once the streamed body goes
+/// over the limit.
+#[derive(Clone, Debug)]
+pub struct PayloadLimitSetting {
+  /// Raw body of the endpoints without a specific limit.
+  pub default: usize,
+  /// JSON body of the endpoints without a specific limit.
+  pub json: usize,
+  /// Create or update a single collab. Larger collabs are uploaded to the blob storage first.
+  pub collab: usize,
+  /// Collab uploaded to the blob storage, see [PayloadLimitSetting::collab].
+  pub collab_upload: usize,
+  pub realtime_stream: usize,
+  /// File uploaded as the context of a chat.
+  pub chat_attachment: usize,
+  /// Icon or cover image uploaded to a workspace or a page.
+  pub image_asset: usize,
 }
 
 #[derive(Clone, Debug)]
@@ -110,6 +257,9 @@ pub struct DatabaseSetting {
   /// connections are reserved for system applications.
   /// When we exceed the limit of the database connection, then it shows an error message.
   pub max_connections: u32,
+  /// Number of prepared statements kept by each connection. The statements evicted from the cache
+  /// are parsed and planned again on their next execution.
+  pub statement_cache_capacity: usize,
 }
 
 impl Display for DatabaseSetting {
@@ -117,8 +267,8 @@ impl Display for DatabaseSetting {
     let masked_pg_conn_opts = self.pg_conn_opts.clone().password("********");
     write!(
       f,
-      "DatabaseSetting {{ pg_conn_opts: {:?}, require_ssl: {}, max_connections: {} }}",
-      masked_pg_conn_opts, self.require_ssl, self.max_connections
+      "DatabaseSetting {{ pg_conn_opts: {:?}, require_ssl: {}, max_connections: {}, statement_cache_capacity: {} }}",
+      masked_pg_conn_opts, self.require_ssl, self.max_connections, self.statement_cache_capacity
     )
   }
 }
@@ -131,7 +281,9 @@ impl DatabaseSetting {
       PgSslMode::Prefer
     };
     let options = self.pg_conn_opts.clone();
-    options.ssl_mode(ssl_mode)
+    options
+      .ssl_mode(ssl_mode)
+      .statement_cache_capacity(self.statement_cache_capacity)
   }
 }
 
@@ -142,6 +294,12 @@ pub struct CollabSetting {
   pub edit_state_max_count: u32,
   pub edit_state_max_secs: i64,
   pub s3_collab_threshold: u64,
+  /// When set, the snapshots are recorded in Postgres and only the ones larger than that many
+  /// bytes are written to the object storage. When not set, all snapshots are written to the
+  /// object storage.
+  pub snapshot_offload_threshold: Option<usize>,
+  /// Maximum size in bytes of the collabs and files of a workspace that can be cloned.
+  pub workspace_clone_max_size: u64,
 }
 
 #[derive(Clone, Debug)]
@@ -153,6 +311,43 @@ pub enum PublishedCollabStorageBackend {
 #[derive(Clone, Debug)]
 pub struct PublishedCollabSetting {
   pub storage_backend: PublishedCollabStorageBackend,
+  pub cache_enabled: bool,
+  pub cache_ttl_secs: u64,
+  /// `max-age` of the `Cache-Control` header returned with the published collabs.
+  pub cache_control_max_age_secs: u64,
+  /// DNS over HTTPS JSON endpoint used to look up the TXT challenge of the custom domains.
+  pub custom_domain_dns_resolver_url: String,
+  /// Host the custom domains should point to, shown to the workspace owners.
+  pub custom_domain_cname_target: Option<String>,
+  /// Custom domains that are not verified within this time are released.
+  pub custom_domain_claim_ttl_secs: u64,
+  /// Interval between two checks of the TXT record of a verified custom domain.
+  pub custom_domain_recheck_interval_secs: u64,
+  pub analytics_enabled: bool,
+  /// Header set by the reverse proxy or the CDN with the country code of the visitor.
+  pub analytics_country_header: String,
+  /// Publishing is rejected when the content contains one of these keywords, ignoring the case.
+  pub moderation_denied_keywords: Vec<String>,
+  /// Publishing is rejected when the content links to one of these domains or their subdomains.
+  pub moderation_denied_domains: Vec<String>,
+}
+
+/// Plan limits of the workspaces, fed by the subscription webhooks of the payment provider. The
+/// paid plans have as many members as seats and unlimited storage, the AI add-ons lift the AI
+/// response limit.
+#[derive(Clone, Debug)]
+pub struct BillingSetting {
+  /// When false, the subscriptions are still recorded but no plan limit is enforced.
+  pub enabled: bool,
+  /// Secret the webhook payloads are signed with. The webhook is rejected when it is empty.
+  pub webhook_secret: Secret<String>,
+  pub free_member_limit: i64,
+  pub free_storage_limit: i64,
+  /// Number of AI responses per month of the workspaces without an AI add-on.
+  pub ai_response_limit: i64,
+  /// How often the workspaces with a subscription, or in read-only mode, are checked against the
+  /// limits of their plan.
+  pub read_only_refresh_interval_secs: u64,
 }
 
 impl TryFrom<&str> for PublishedCollabStorageBackend {
@@ -198,6 +393,9 @@ pub fn get_configuration() -> Result<Config, anyhow::Error> {
       max_connections: get_env_var("APPFLOWY_DATABASE_MAX_CONNECTIONS", "40")
         .parse()
         .context("fail to get APPFLOWY_DATABASE_MAX_CONNECTIONS")?,
+      statement_cache_capacity: get_env_var("APPFLOWY_DATABASE_STATEMENT_CACHE_CAPACITY", "500")
+        .parse()
+        .context("fail to get APPFLOWY_DATABASE_STATEMENT_CACHE_CAPACITY")?,
     },
     gotrue: GoTrueSetting {
       base_url: get_env_var("APPFLOWY_GOTRUE_BASE_URL", "http://localhost:9999"),
@@ -209,11 +407,24 @@ pub fn get_configuration() -> Result<Config, anyhow::Error> {
     application: ApplicationSetting {
       port: get_env_var("APPFLOWY_APPLICATION_PORT", "8000").parse()?,
       host: get_env_var("APPFLOWY_APPLICATION_HOST", "0.0.0.0"),
+      payload_limits: PayloadLimitSetting {
+        default: get_env_var("APPFLOWY_PAYLOAD_LIMIT_DEFAULT", "262144").parse()?,
+        json: get_env_var("APPFLOWY_PAYLOAD_LIMIT_JSON", "2097152").parse()?,
+        collab: get_env_var("APPFLOWY_PAYLOAD_LIMIT_COLLAB", "5242880").parse()?,
+        collab_upload: get_env_var("APPFLOWY_PAYLOAD_LIMIT_COLLAB_UPLOAD", "209715200").parse()?,
+        realtime_stream: get_env_var("APPFLOWY_PAYLOAD_LIMIT_REALTIME_STREAM", "10485760")
+          .parse()?,
+        chat_attachment: get_env_var("APPFLOWY_PAYLOAD_LIMIT_CHAT_ATTACHMENT", "20971520")
+          .parse()?,
+        image_asset: get_env_var("APPFLOWY_PAYLOAD_LIMIT_IMAGE_ASSET", "10485760").parse()?,
+      },
     },
     websocket: WebsocketSetting {
       heartbeat_interval: get_env_var("APPFLOWY_WEBSOCKET_HEARTBEAT_INTERVAL", "6").parse()?,
       client_timeout: get_env_var("APPFLOWY_WEBSOCKET_CLIENT_TIMEOUT", "60").parse()?,
       min_client_version: get_env_var("APPFLOWY_WEBSOCKET_CLIENT_MIN_VERSION", "0.5.0").parse()?,
+      max_sessions_per_user: get_env_var("APPFLOWY_WEBSOCKET_MAX_SESSIONS_PER_USER", "10")
+        .parse()?,
     },
     redis_uri: get_env_var("APPFLOWY_REDIS_URI", "redis://localhost:6379").into(),
     redis_worker_count: get_env_var("APPFLOWY_REDIS_WORKERS", "60").parse()?,
@@ -231,9 +442,41 @@ pub fn get_configuration() -> Result<Config, anyhow::Error> {
       region: get_env_var("APPFLOWY_S3_REGION", ""),
       presigned_url_endpoint: get_env_var_opt("APPFLOWY_S3_PRESIGNED_URL_ENDPOINT"),
     },
+    blob_storage: BlobStorageSetting {
+      backend: get_env_var("APPFLOWY_BLOB_STORAGE_BACKEND", "s3")
+        .as_str()
+        .try_into()
+        .context("fail to get APPFLOWY_BLOB_STORAGE_BACKEND")?,
+      gcs_endpoint: get_env_var("APPFLOWY_GCS_ENDPOINT", GCS_XML_API_ENDPOINT),
+      azure: AzureBlobSetting {
+        account_name: get_env_var("APPFLOWY_AZURE_STORAGE_ACCOUNT", ""),
+        account_key: get_env_var("APPFLOWY_AZURE_STORAGE_KEY", "").into(),
+        container: get_env_var("APPFLOWY_AZURE_STORAGE_CONTAINER", "appflowy"),
+        endpoint: get_env_var_opt("APPFLOWY_AZURE_STORAGE_ENDPOINT"),
+      },
+      local: LocalFsBlobSetting {
+        root: get_env_var("APPFLOWY_LOCAL_STORAGE_ROOT", "./data/blobs"),
+        // Falls back to the GoTrue secret so the presigned urls are never signed by a well known key.
+        signing_key: get_env_var_opt("APPFLOWY_LOCAL_STORAGE_SIGNING_KEY")
+          .unwrap_or_else(|| get_env_var("APPFLOWY_GOTRUE_JWT_SECRET", "hello456"))
+          .into(),
+        public_url: get_env_var("APPFLOWY_LOCAL_STORAGE_PUBLIC_URL", "http://localhost:8000"),
+      },
+      workspace_storage_key: get_env_var_opt("APPFLOWY_WORKSPACE_STORAGE_KEY").map(Secret::new),
+      workspace_storage_cache_ttl_secs: get_env_var(
+        "APPFLOWY_WORKSPACE_STORAGE_CACHE_TTL_SECS",
+        "60",
+      )
+      .parse()
+      .context("fail to get APPFLOWY_WORKSPACE_STORAGE_CACHE_TTL_SECS")?,
+    },
     appflowy_ai: AppFlowyAISetting {
       port: get_env_var("AI_SERVER_PORT", "5001").into(),
       host: get_env_var("AI_SERVER_HOST", "localhost").into(),
+      language_check_url: get_env_var_opt("APPFLOWY_LANGUAGE_CHECK_URL"),
+      language_check_cache_ttl_secs: get_env_var("APPFLOWY_LANGUAGE_CHECK_CACHE_TTL_SECS", "86400")
+        .parse()
+        .context("fail to get APPFLOWY_LANGUAGE_CHECK_CACHE_TTL_SECS")?,
     },
     collab: CollabSetting {
       group_persistence_interval_secs: get_env_var(
@@ -246,11 +489,61 @@ pub fn get_configuration() -> Result<Config, anyhow::Error> {
       edit_state_max_count: get_env_var("APPFLOWY_COLLAB_EDIT_STATE_MAX_COUNT", "100").parse()?,
       edit_state_max_secs: get_env_var("APPFLOWY_COLLAB_EDIT_STATE_MAX_SECS", "60").parse()?,
       s3_collab_threshold: get_env_var("APPFLOWY_COLLAB_S3_THRESHOLD", "8000").parse()?,
+      snapshot_offload_threshold: get_env_var_opt("APPFLOWY_COLLAB_SNAPSHOT_OFFLOAD_THRESHOLD")
+        .map(|value| value.parse())
+        .transpose()?,
+      workspace_clone_max_size: get_env_var("APPFLOWY_WORKSPACE_CLONE_MAX_SIZE", "1073741824")
+        .parse()?,
     },
     published_collab: PublishedCollabSetting {
       storage_backend: get_env_var("APPFLOWY_PUBLISHED_COLLAB_STORAGE_BACKEND", "postgres")
         .as_str()
         .try_into()?,
+      cache_enabled: get_env_var("APPFLOWY_PUBLISHED_COLLAB_CACHE_ENABLED", "true").parse()?,
+      cache_ttl_secs: get_env_var("APPFLOWY_PUBLISHED_COLLAB_CACHE_TTL_SECS", "3600").parse()?,
+      cache_control_max_age_secs: get_env_var(
+        "APPFLOWY_PUBLISHED_COLLAB_CACHE_CONTROL_MAX_AGE",
+        "60",
+      )
+      .parse()?,
+      custom_domain_dns_resolver_url: get_env_var(
+        "APPFLOWY_CUSTOM_DOMAIN_DNS_RESOLVER_URL",
+        "https://cloudflare-dns.com/dns-query",
+      ),
+      custom_domain_cname_target: get_env_var_opt("APPFLOWY_CUSTOM_DOMAIN_CNAME_TARGET"),
+      custom_domain_claim_ttl_secs: get_env_var("APPFLOWY_CUSTOM_DOMAIN_CLAIM_TTL_SECS", "604800")
+        .parse()?,
+      custom_domain_recheck_interval_secs: get_env_var(
+        "APPFLOWY_CUSTOM_DOMAIN_RECHECK_INTERVAL_SECS",
+        "86400",
+      )
+      .parse()?,
+      analytics_enabled: get_env_var("APPFLOWY_PUBLISH_ANALYTICS_ENABLED", "true").parse()?,
+      analytics_country_header: get_env_var(
+        "APPFLOWY_PUBLISH_ANALYTICS_COUNTRY_HEADER",
+        "CF-IPCountry",
+      ),
+      moderation_denied_keywords: split_denylist(&get_env_var(
+        "APPFLOWY_PUBLISH_MODERATION_DENIED_KEYWORDS",
+        "",
+      )),
+      moderation_denied_domains: split_denylist(&get_env_var(
+        "APPFLOWY_PUBLISH_MODERATION_DENIED_DOMAINS",
+        "",
+      )),
+    },
+    billing: BillingSetting {
+      enabled: get_env_var("APPFLOWY_BILLING_ENABLED", "false").parse()?,
+      webhook_secret: get_env_var("APPFLOWY_BILLING_WEBHOOK_SECRET", "").into(),
+      free_member_limit: get_env_var("APPFLOWY_BILLING_FREE_MEMBER_LIMIT", "2").parse()?,
+      free_storage_limit: get_env_var("APPFLOWY_BILLING_FREE_STORAGE_LIMIT", "5368709120")
+        .parse()?,
+      ai_response_limit: get_env_var("APPFLOWY_BILLING_AI_RESPONSE_LIMIT", "100").parse()?,
+      read_only_refresh_interval_secs: get_env_var(
+        "APPFLOWY_BILLING_READ_ONLY_REFRESH_INTERVAL_SECS",
+        "300",
+      )
+      .parse()?,
     },
     mailer: MailerSetting {
       smtp_host: get_env_var("APPFLOWY_MAILER_SMTP_HOST", "smtp.gmail.com"),
@@ -266,10 +559,140 @@ pub fn get_configuration() -> Result<Config, anyhow::Error> {
     },
     appflowy_web_url: get_env_var_opt("APPFLOWY_WEB_URL"),
     admin_frontend_path_prefix: get_env_var("APPFLOWY_ADMIN_FRONTEND_PATH_PREFIX", ""),
+    cors: CorsSetting {
+      allowed_origins: get_env_var("APPFLOWY_CORS_ALLOWED_ORIGINS", "http://localhost:3000")
+        .split(',')
+        .map(|origin| origin.trim().trim_end_matches('/').to_string())
+        .filter(|origin| !origin.is_empty())
+        .collect(),
+      allowed_methods: get_env_var(
+        "APPFLOWY_CORS_ALLOWED_METHODS",
+        "GET, POST, PUT, DELETE, PATCH, OPTIONS",
+      ),
+      allowed_headers: get_env_var(
+        "APPFLOWY_CORS_ALLOWED_HEADERS",
+        "Content-Type, Authorization, Accept, Client-Version, Device-Id",
+      ),
+      max_age_secs: get_env_var("APPFLOWY_CORS_MAX_AGE", "3600")
+        .parse()
+        .context("fail to get APPFLOWY_CORS_MAX_AGE")?,
+      allow_credentials: get_env_var("APPFLOWY_CORS_ALLOW_CREDENTIALS", "false")
+        .parse()
+        .context("fail to get APPFLOWY_CORS_ALLOW_CREDENTIALS")?,
+      content_security_policy: get_env_var_opt("APPFLOWY_CONTENT_SECURITY_POLICY"),
+    },
+    push: PushSetting {
+      apns: get_env_var_opt("APPFLOWY_PUSH_APNS_PRIVATE_KEY").map(|private_key| ApnsSetting {
+        team_id: get_env_var("APPFLOWY_PUSH_APNS_TEAM_ID", ""),
+        key_id: get_env_var("APPFLOWY_PUSH_APNS_KEY_ID", ""),
+        private_key: pem_from_env(&private_key).into(),
+        topic: get_env_var("APPFLOWY_PUSH_APNS_TOPIC", "io.appflowy.appflowy"),
+        use_sandbox: get_env_var("APPFLOWY_PUSH_APNS_SANDBOX", "false")
+          .parse()
+          .unwrap_or(false),
+      }),
+      fcm: get_env_var_opt("APPFLOWY_PUSH_FCM_PRIVATE_KEY").map(|private_key| FcmSetting {
+        project_id: get_env_var("APPFLOWY_PUSH_FCM_PROJECT_ID", ""),
+        client_email: get_env_var("APPFLOWY_PUSH_FCM_CLIENT_EMAIL", ""),
+        private_key: pem_from_env(&private_key).into(),
+      }),
+      max_attempts: get_env_var("APPFLOWY_PUSH_MAX_ATTEMPTS", "3")
+        .parse()
+        .context("fail to get APPFLOWY_PUSH_MAX_ATTEMPTS")?,
+    },
+    database_form: DatabaseFormSetting {
+      rate_limit_per_minute: get_env_var("APPFLOWY_DATABASE_FORM_RATE_LIMIT_PER_MIN", "10")
+        .parse()
+        .context("fail to get APPFLOWY_DATABASE_FORM_RATE_LIMIT_PER_MIN")?,
+      token_rate_limit_per_minute: get_env_var(
+        "APPFLOWY_DATABASE_FORM_TOKEN_RATE_LIMIT_PER_MIN",
+        "100",
+      )
+      .parse()
+      .context("fail to get APPFLOWY_DATABASE_FORM_TOKEN_RATE_LIMIT_PER_MIN")?,
+      trusted_proxies: trusted_proxies_from_env(&get_env_var(
+        "APPFLOWY_DATABASE_FORM_TRUSTED_PROXIES",
+        "",
+      ))?,
+      captcha: get_env_var_opt("APPFLOWY_DATABASE_FORM_CAPTCHA_SECRET").map(|secret| {
+        CaptchaSetting {
+          verify_url: get_env_var(
+            "APPFLOWY_DATABASE_FORM_CAPTCHA_VERIFY_URL",
+            "https://challenges.cloudflare.com/turnstile/v0/siteverify",
+          ),
+          secret: secret.into(),
+        }
+      }),
+    },
+    region: RegionSetting {
+      current: get_env_var("APPFLOWY_REGION", ""),
+      regions: regions_from_env(&get_env_var("APPFLOWY_REGIONS", ""))
+        .context("fail to get APPFLOWY_REGIONS")?,
+      min_latency_gain_ms: get_env_var("APPFLOWY_REGION_MIN_LATENCY_GAIN_MS", "50")
+        .parse()
+        .context("fail to get APPFLOWY_REGION_MIN_LATENCY_GAIN_MS")?,
+    },
   };
   Ok(config)
 }
 
+/// Splits a comma separated denylist, the entries are lowercased to be matched ignoring the case.
+fn split_denylist(value: &str) -> Vec<String> {
+  value
+    .split(',')
+    .map(|entry| entry.trim().to_lowercase())
+    .filter(|entry| !entry.is_empty())
+    .collect()
+}
+
+/// Parses the regions given as `name=base_url` pairs separated by commas. The websocket endpoint
+/// of a region is derived from its base url.
+/// Comma separated addresses or ranges, like `10.0.0.2,172.16.0.0/12`.
+fn trusted_proxies_from_env(value: &str) -> Result<Vec<IpNet>, anyhow::Error> {
+  value
+    .split(',')
+    .map(str::trim)
+    .filter(|entry| !entry.is_empty())
+    .map(|entry| {
+      entry
+        .parse::<IpNet>()
+        .or_else(|_| entry.parse::<IpAddr>().map(IpNet::from))
+        .with_context(|| format!("invalid trusted proxy `{}`", entry))
+    })
+    .collect()
+}
+
+fn regions_from_env(value: &str) -> Result<Vec<RegionInfo>, anyhow::Error> {
+  value
+    .split(',')
+    .map(str::trim)
+    .filter(|entry| !entry.is_empty())
+    .map(|entry| {
+      let (name, base_url) = entry
+        .split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("invalid region `{}`, expected name=base_url", entry))?;
+      let base_url = base_url.trim().trim_end_matches('/').to_string();
+      let ws_url = if let Some(host) = base_url.strip_prefix("https://") {
+        format!("wss://{}/ws/v1", host)
+      } else if let Some(host) = base_url.strip_prefix("http://") {
+        format!("ws://{}/ws/v1", host)
+      } else {
+        anyhow::bail!("invalid base url of region `{}`: {}", name, base_url);
+      };
+      Ok(RegionInfo {
+        name: name.trim().to_string(),
+        base_url,
+        ws_url,
+      })
+    })
+    .collect()
+}
+
+/// The keys are often given on a single line in the env files, with escaped line breaks.
+fn pem_from_env(value: &str) -> String {
+  value.replace("\\n", "\n")
+}
+
 /// The possible runtime environment for our application.
 #[derive(Clone, Debug, Deserialize)]
 pub enum Environment {
@@ -306,4 +729,7 @@ pub struct WebsocketSetting {
   pub heartbeat_interval: u8,
   pub client_timeout: u8,
   pub min_client_version: Version,
+  /// Maximum number of realtime sessions a user can keep open, 0 means unlimited. Organizations
+  /// can set a stricter limit for their users.
+  pub max_sessions_per_user: usize,
 }
@@ -7,3 +7,5 @@ pub mod mailer;
 pub mod middleware;
 pub mod state;
 pub mod telemetry;
+#[cfg(feature = "test-harness")]
+pub mod test_harness;
@@ -5,6 +5,8 @@ pub const WORKSPACE_INVITE_TEMPLATE_NAME: &str = "workspace_invite";
 pub const WORKSPACE_ACCESS_REQUEST_TEMPLATE_NAME: &str = "workspace_access_request";
 pub const WORKSPACE_ACCESS_REQUEST_APPROVED_NOTIFICATION_TEMPLATE_NAME: &str =
   "workspace_access_request_approved_notification";
+pub const UPLOAD_QUARANTINED_TEMPLATE_NAME: &str = "upload_quarantined";
+pub const COLLAB_GUEST_INVITATION_TEMPLATE_NAME: &str = "collab_guest_invitation";
 
 #[derive(Clone)]
 pub struct AFCloudMailer(Mailer);
@@ -84,6 +86,49 @@ impl AFCloudMailer {
       )
       .await
   }
+
+  pub async fn send_upload_quarantined_notification(
+    &self,
+    recipient_name: &str,
+    email: &str,
+    param: UploadQuarantinedMailerParam,
+  ) -> Result<(), anyhow::Error> {
+    let subject = format!(
+      "{} uploaded a file to {} that needs review",
+      param.username, param.workspace_name
+    );
+    self
+      .0
+      .send_email_template(
+        Some(recipient_name.to_string()),
+        email,
+        UPLOAD_QUARANTINED_TEMPLATE_NAME,
+        param,
+        &subject,
+      )
+      .await
+  }
+
+  pub async fn send_collab_guest_invitation(
+    &self,
+    email: &str,
+    param: CollabGuestInvitationMailerParam,
+  ) -> Result<(), anyhow::Error> {
+    let subject = format!(
+      "{} shared {} with you in AppFlowy",
+      param.username, param.page_name
+    );
+    self
+      .0
+      .send_email_template(
+        None,
+        email,
+        COLLAB_GUEST_INVITATION_TEMPLATE_NAME,
+        param,
+        &subject,
+      )
+      .await
+  }
 }
 
 async fn register_mailer(mailer: &mut Mailer) -> Result<(), anyhow::Error> {
@@ -94,6 +139,10 @@ async fn register_mailer(mailer: &mut Mailer) -> Result<(), anyhow::Error> {
   let access_request_approved_notification_template = include_str!(
     "../assets/mailer_templates/build_production/access_request_approved_notification.html"
   );
+  let upload_quarantined_template =
+    include_str!("../assets/mailer_templates/build_production/upload_quarantined.html");
+  let collab_guest_invitation_template =
+    include_str!("../assets/mailer_templates/build_production/collab_guest_invitation.html");
   let template_strings = HashMap::from([
     (WORKSPACE_INVITE_TEMPLATE_NAME, workspace_invite_template),
     (
@@ -104,6 +153,14 @@ async fn register_mailer(mailer: &mut Mailer) -> Result<(), anyhow::Error> {
       WORKSPACE_ACCESS_REQUEST_APPROVED_NOTIFICATION_TEMPLATE_NAME,
       access_request_approved_notification_template,
     ),
+    (
+      UPLOAD_QUARANTINED_TEMPLATE_NAME,
+      upload_quarantined_template,
+    ),
+    (
+      COLLAB_GUEST_INVITATION_TEMPLATE_NAME,
+      collab_guest_invitation_template,
+    ),
   ]);
 
   for (template_name, template_string) in template_strings {
@@ -143,3 +200,21 @@ pub struct WorkspaceAccessRequestApprovedMailerParam {
   pub workspace_member_count: i64,
   pub launch_workspace_url: String,
 }
+
+#[derive(serde::Serialize)]
+pub struct UploadQuarantinedMailerParam {
+  pub username: String, // Uploader
+  pub workspace_name: String,
+  pub file_name: String,
+  pub file_size: usize,
+  pub review_url: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct CollabGuestInvitationMailerParam {
+  pub username: String, // Inviter
+  pub workspace_name: String,
+  pub page_name: String,
+  pub access_level: String,
+  pub open_url: String,
+}
@@ -2,8 +2,21 @@ use appflowy_cloud::application::{init_state, Application};
 use appflowy_cloud::config::config::get_configuration;
 use appflowy_cloud::telemetry::init_subscriber;
 
+/// Runs the workers in the appflowy_cloud process, see the single-binary feature.
+const SINGLE_BINARY_FLAG: &str = "--single-binary";
+
 #[actix_web::main]
 async fn main() -> anyhow::Result<()> {
+  let single_binary = std::env::args()
+    .skip(1)
+    .any(|arg| arg == SINGLE_BINARY_FLAG);
+  if single_binary && !cfg!(feature = "single-binary") {
+    anyhow::bail!(
+      "{} requires appflowy_cloud to be built with the single-binary feature",
+      SINGLE_BINARY_FLAG
+    );
+  }
+
   let level = std::env::var("RUST_LOG").unwrap_or("info".to_string());
   println!("AppFlowy Cloud with RUST_LOG={}", level);
   let mut filters = vec![];
@@ -19,6 +32,9 @@ async fn main() -> anyhow::Result<()> {
   filters.push(format!("appflowy_collaborate={}", level));
   filters.push(format!("appflowy_ai_client={}", level));
   filters.push(format!("indexer={}", level));
+  if single_binary {
+    filters.push(format!("appflowy_worker={}", level));
+  }
 
   // Load environment variables from .env file
   dotenvy::dotenv().ok();
@@ -32,7 +48,19 @@ async fn main() -> anyhow::Result<()> {
   let state = init_state(&conf, tx)
     .await
     .map_err(|e| anyhow::anyhow!("Failed to initialize application state: {}", e))?;
+  #[cfg(feature = "single-binary")]
+  let worker_state = single_binary.then(|| state.clone());
   let application = Application::build(conf, state, rx).await?;
+
+  #[cfg(feature = "single-binary")]
+  if let Some(worker_state) = worker_state {
+    tokio::select! {
+      result = application.run_until_stopped() => result?,
+      result = appflowy_cloud::application::run_embedded_workers(&worker_state) => result?,
+    }
+    return Ok(());
+  }
+
   application.run_until_stopped().await?;
 
   Ok(())
@@ -0,0 +1,228 @@
+use std::future::{ready, Ready};
+use std::rc::Rc;
+
+use actix_http::header::{HeaderName, HeaderValue};
+use actix_service::{forward_ready, Service, Transform};
+use actix_web::body::EitherBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{
+  ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+  ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_MAX_AGE, ACCESS_CONTROL_REQUEST_METHOD,
+  CONTENT_SECURITY_POLICY, ORIGIN, UPGRADE, VARY,
+};
+use actix_web::http::Method;
+use actix_web::HttpResponse;
+use futures_util::future::LocalBoxFuture;
+use tracing::debug;
+
+use crate::config::config::CorsSetting;
+
+/// Adds the CORS and Content-Security-Policy headers configured by [CorsSetting] to the responses
+/// and answers the preflight requests.
+///
+/// The websocket handshake is not covered by CORS in browsers, so a handshake coming from an
+/// origin that is neither allowed nor the server itself is rejected. Native clients don't send the
+/// `Origin` header and are not affected.
+#[derive(Clone)]
+pub struct CorsMiddleware {
+  setting: Rc<CorsSetting>,
+}
+
+impl CorsMiddleware {
+  pub fn new(setting: CorsSetting) -> Self {
+    Self {
+      setting: Rc::new(setting),
+    }
+  }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for CorsMiddleware
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+  S::Future: 'static,
+  B: 'static,
+{
+  type Response = ServiceResponse<EitherBody<B>>;
+  type Error = actix_web::Error;
+  type Transform = CorsMiddlewareService<S>;
+  type InitError = ();
+  type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+  fn new_transform(&self, service: S) -> Self::Future {
+    ready(Ok(CorsMiddlewareService {
+      service,
+      setting: self.setting.clone(),
+    }))
+  }
+}
+
+pub struct CorsMiddlewareService<S> {
+  service: S,
+  setting: Rc<CorsSetting>,
+}
+
+impl<S, B> Service<ServiceRequest> for CorsMiddlewareService<S>
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+  S::Future: 'static,
+  B: 'static,
+{
+  type Response = ServiceResponse<EitherBody<B>>;
+  type Error = actix_web::Error;
+  type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+  forward_ready!(service);
+
+  fn call(&self, req: ServiceRequest) -> Self::Future {
+    let setting = self.setting.clone();
+    let origin = req
+      .headers()
+      .get(ORIGIN)
+      .and_then(|value| value.to_str().ok())
+      .map(|value| value.to_string());
+    let allowed_origin = origin
+      .as_deref()
+      .filter(|origin| setting.is_origin_allowed(origin))
+      .map(|origin| origin.to_string());
+
+    if let Some(origin) = origin.as_deref() {
+      if is_websocket_handshake(&req)
+        && allowed_origin.is_none()
+        && !is_same_origin(origin, req.connection_info().host())
+      {
+        debug!("reject websocket handshake from origin: {}", origin);
+        let resp = HttpResponse::Forbidden().finish().map_into_right_body();
+        return Box::pin(async move { Ok(req.into_response(resp)) });
+      }
+
+      let is_preflight = req.method() == Method::OPTIONS
+        && req.headers().contains_key(ACCESS_CONTROL_REQUEST_METHOD);
+      if is_preflight {
+        let mut resp = HttpResponse::NoContent().finish();
+        if let Some(allowed_origin) = allowed_origin.as_deref() {
+          setting.insert_cors_headers(resp.headers_mut(), allowed_origin);
+        }
+        let resp = resp.map_into_right_body();
+        return Box::pin(async move { Ok(req.into_response(resp)) });
+      }
+    }
+
+    let fut = self.service.call(req);
+    Box::pin(async move {
+      let mut res = fut.await?;
+      let headers = res.headers_mut();
+      if let Some(allowed_origin) = allowed_origin.as_deref() {
+        setting.insert_cors_headers(headers, allowed_origin);
+      }
+      if let Some(csp) = setting
+        .content_security_policy
+        .as_deref()
+        .and_then(|csp| HeaderValue::from_str(csp).ok())
+      {
+        headers.insert(CONTENT_SECURITY_POLICY, csp);
+      }
+      Ok(res.map_into_left_body())
+    })
+  }
+}
+
+impl CorsSetting {
+  pub fn is_origin_allowed(&self, origin: &str) -> bool {
+    self
+      .allowed_origins
+      .iter()
+      .any(|allowed| origin_matches(allowed, origin))
+  }
+
+  fn insert_cors_headers(&self, headers: &mut actix_http::header::HeaderMap, origin: &str) {
+    let mut insert = |name: HeaderName, value: &str| {
+      if let Ok(value) = HeaderValue::from_str(value) {
+        headers.insert(name, value);
+      }
+    };
+    insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+    insert(ACCESS_CONTROL_ALLOW_METHODS, &self.allowed_methods);
+    insert(ACCESS_CONTROL_ALLOW_HEADERS, &self.allowed_headers);
+    insert(ACCESS_CONTROL_MAX_AGE, &self.max_age_secs.to_string());
+    if self.allow_credentials {
+      insert(ACCESS_CONTROL_ALLOW_CREDENTIALS, "true");
+    }
+    // The allowed origin depends on the request
+    insert(VARY, "Origin");
+  }
+}
+
+/// `allowed` is either `*`, an exact origin like `https://example.com`, or an origin with a
+/// wildcard subdomain like `https://*.example.com`.
+fn origin_matches(allowed: &str, origin: &str) -> bool {
+  if allowed == "*" || allowed.eq_ignore_ascii_case(origin) {
+    return true;
+  }
+  match allowed.split_once("://*.") {
+    Some((scheme, domain)) => origin
+      .strip_prefix(scheme)
+      .and_then(|rest| rest.strip_prefix("://"))
+      .and_then(|host| host.strip_suffix(domain))
+      .map_or(false, |subdomain| {
+        subdomain.ends_with('.') && subdomain.len() > 1
+      }),
+    None => false,
+  }
+}
+
+fn is_websocket_handshake(req: &ServiceRequest) -> bool {
+  req
+    .headers()
+    .get(UPGRADE)
+    .and_then(|value| value.to_str().ok())
+    .map_or(false, |value| value.eq_ignore_ascii_case("websocket"))
+}
+
+fn is_same_origin(origin: &str, host: &str) -> bool {
+  origin.split_once("://").map_or(false, |(_, origin_host)| {
+    origin_host.eq_ignore_ascii_case(host)
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn cors_origin_matches() {
+    assert!(origin_matches("*", "https://example.com"));
+    assert!(origin_matches(
+      "http://localhost:3000",
+      "http://localhost:3000"
+    ));
+    assert!(!origin_matches(
+      "http://localhost:3000",
+      "http://localhost:3001"
+    ));
+    assert!(origin_matches(
+      "https://*.example.com",
+      "https://docs.example.com"
+    ));
+    assert!(!origin_matches(
+      "https://*.example.com",
+      "https://example.com"
+    ));
+    assert!(!origin_matches(
+      "https://*.example.com",
+      "https://evilexample.com"
+    ));
+    assert!(!origin_matches(
+      "https://*.example.com",
+      "http://docs.example.com"
+    ));
+  }
+
+  #[test]
+  fn websocket_same_origin() {
+    assert!(is_same_origin(
+      "https://appflowy.example.com",
+      "appflowy.example.com"
+    ));
+    assert!(!is_same_origin("https://evil.com", "appflowy.example.com"));
+  }
+}
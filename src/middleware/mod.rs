@@ -1,2 +1,4 @@
+pub mod cors_mw;
 pub mod metrics_mw;
+pub mod read_only_mw;
 pub mod request_id;
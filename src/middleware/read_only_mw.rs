@@ -0,0 +1,193 @@
+use actix_service::{forward_ready, Service, Transform};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::Method;
+use actix_web::web::Data;
+use actix_web::Error;
+use futures_util::future::LocalBoxFuture;
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use uuid::Uuid;
+
+use crate::state::AppState;
+
+/// Scopes whose paths start with the workspace id. The realtime updates are checked by the
+/// realtime access control instead.
+const WORKSPACE_SCOPES: [&[&str]; 4] = [
+  &["api", "workspace"],
+  &["api", "workspace", "v1"],
+  &["api", "file_storage"],
+  &["api", "chat"],
+];
+
+/// Requests that do not change the content of the workspace, or that help the workspace get back
+/// within the limits of its plan, given as the segments after the workspace id. `*` matches any
+/// segment. Deletions are always allowed.
+const ALLOWED_WHEN_READ_ONLY: [&[&str]; 12] = [
+  &["open"],
+  &["leave"],
+  &["clone"],
+  &["collab_list"],
+  &["collab_warm_up"],
+  &["collab", "embed-info", "list"],
+  &["collab", "*", "export", "pdf"],
+  &["export", "archive"],
+  &["page-view", "*", "unpublish"],
+  &["delete-all-pages-from-trash"],
+  &["history-compaction", "run"],
+  &["notification-preferences"],
+];
+
+/// Rejects the requests that would change a workspace in read-only mode with
+/// [app_error::AppError::WorkspaceReadOnly]. The reads and the exports keep working.
+pub struct WorkspaceReadOnlyMiddleware;
+
+impl<S, B> Transform<S, ServiceRequest> for WorkspaceReadOnlyMiddleware
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  S::Future: 'static,
+  B: 'static,
+{
+  type Response = ServiceResponse<B>;
+  type Error = Error;
+  type Transform = WorkspaceReadOnlyMiddlewareService<S>;
+  type InitError = ();
+  type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+  fn new_transform(&self, service: S) -> Self::Future {
+    ready(Ok(WorkspaceReadOnlyMiddlewareService {
+      service: Rc::new(service),
+    }))
+  }
+}
+
+pub struct WorkspaceReadOnlyMiddlewareService<S> {
+  service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for WorkspaceReadOnlyMiddlewareService<S>
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  S::Future: 'static,
+  B: 'static,
+{
+  type Response = ServiceResponse<B>;
+  type Error = Error;
+  type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+  forward_ready!(service);
+
+  fn call(&self, req: ServiceRequest) -> Self::Future {
+    let workspace_id = match workspace_id_to_check(req.method(), req.path()) {
+      Some(workspace_id) => workspace_id,
+      None => return Box::pin(self.service.call(req)),
+    };
+    let state = match req.app_data::<Data<AppState>>() {
+      Some(state) => state.clone(),
+      None => {
+        tracing::error!("Failed to get app state from app_data");
+        return Box::pin(self.service.call(req));
+      },
+    };
+
+    let service = self.service.clone();
+    Box::pin(async move {
+      state
+        .workspace_read_only_cache
+        .enforce_writable(&workspace_id)
+        .await?;
+      service.call(req).await
+    })
+  }
+}
+
+/// Returns the workspace the request would change, None if the request does not have to be
+/// checked.
+fn workspace_id_to_check(method: &Method, path: &str) -> Option<Uuid> {
+  if !matches!(*method, Method::POST | Method::PUT | Method::PATCH) {
+    return None;
+  }
+  let segments = path
+    .split('/')
+    .filter(|segment| !segment.is_empty())
+    .collect::<Vec<_>>();
+  WORKSPACE_SCOPES.iter().find_map(|scope| {
+    let rest = segments.strip_prefix(*scope)?;
+    let (workspace_id, rest) = rest.split_first()?;
+    let workspace_id = Uuid::parse_str(workspace_id).ok()?;
+    let allowed = ALLOWED_WHEN_READ_ONLY.iter().any(|allowed| {
+      allowed.len() == rest.len()
+        && allowed
+          .iter()
+          .zip(rest)
+          .all(|(allowed, segment)| *allowed == "*" || allowed == segment)
+    });
+    (!allowed).then_some(workspace_id)
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn only_changes_to_a_workspace_are_checked() {
+    let workspace_id = Uuid::new_v4();
+    let check = |method: Method, path: String| workspace_id_to_check(&method, &path);
+
+    for path in [
+      format!("/api/workspace/{}/collab/abc", workspace_id),
+      format!("/api/workspace/v1/{}/collab/abc/full-sync", workspace_id),
+      format!("/api/file_storage/{}/create_upload/", workspace_id),
+      format!("/api/chat/{}/abc/message/question", workspace_id),
+    ] {
+      assert_eq!(check(Method::POST, path), Some(workspace_id));
+    }
+    assert_eq!(
+      check(
+        Method::PUT,
+        format!("/api/workspace/{}/quick-note/abc", workspace_id)
+      ),
+      Some(workspace_id)
+    );
+
+    assert_eq!(
+      check(
+        Method::GET,
+        format!("/api/workspace/{}/collab/abc", workspace_id)
+      ),
+      None
+    );
+    assert_eq!(
+      check(
+        Method::DELETE,
+        format!("/api/file_storage/{}/blob/abc", workspace_id)
+      ),
+      None
+    );
+    assert_eq!(
+      check(
+        Method::POST,
+        format!("/api/workspace/v1/{}/collab_list", workspace_id)
+      ),
+      None
+    );
+    assert_eq!(
+      check(
+        Method::POST,
+        format!("/api/workspace/{}/collab/abc/export/pdf", workspace_id)
+      ),
+      None
+    );
+    assert_eq!(
+      check(
+        Method::POST,
+        "/api/workspace/published-info/abc/report".to_string()
+      ),
+      None
+    );
+    assert_eq!(
+      check(Method::POST, format!("/api/ai/{}/complete", workspace_id)),
+      None
+    );
+  }
+}
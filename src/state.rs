@@ -1,7 +1,12 @@
 use std::sync::Arc;
 
 use access_control::collab::{CollabAccessControl, RealtimeAccessControl};
+use access_control::collab_guest::CollabGuestCache;
+use access_control::collab_lock::CollabLockCache;
+use access_control::restricted_view::RestrictedViewCache;
+use access_control::session_policy::SessionPolicy;
 use access_control::workspace::WorkspaceAccessControl;
+use access_control::workspace_read_only::WorkspaceReadOnlyCache;
 use dashmap::DashMap;
 use secrecy::{ExposeSecret, Secret};
 use sqlx::PgPool;
@@ -18,7 +23,8 @@ use appflowy_collaborate::metrics::CollabMetrics;
 use appflowy_collaborate::CollabRealtimeMetrics;
 use collab_stream::metrics::CollabStreamMetrics;
 use collab_stream::stream_router::StreamRouter;
-use database::file::s3_client_impl::{AwsS3BucketClientImpl, S3BucketStorage};
+use database::feature_flag::FeatureFlags;
+use database::file::{BlobBucketStorage, BlobStorageClient, WorkspaceStorageRouter};
 use database::user::{select_all_uid_uuid, select_uid_from_uuid};
 use gotrue::grant::{Grant, PasswordGrant};
 use indexer::metrics::EmbeddingMetrics;
@@ -26,9 +32,13 @@ use indexer::scheduler::IndexerScheduler;
 use snowflake::Snowflake;
 
 use crate::api::metrics::{AppFlowyWebMetrics, PublishedCollabMetrics, RequestMetrics};
+use crate::biz::ai::language_check::LanguageChecker;
 use crate::biz::chat::metrics::AIMetrics;
+use crate::biz::notification::metrics::PushMetrics;
+use crate::biz::notification::NotificationCenter;
 use crate::biz::pg_listener::PgListeners;
 use crate::biz::workspace::publish::PublishedCollabStore;
+use crate::biz::workspace::publish_analytics::PublishAnalyticsRecorder;
 use crate::config::config::Config;
 use crate::mailer::AFCloudMailer;
 
@@ -40,6 +50,7 @@ pub struct AppState {
   pub user_cache: UserCache,
   pub id_gen: Arc<RwLock<Snowflake>>,
   pub gotrue_client: gotrue::api::Client,
+  pub redis_client: redis::Client,
   pub redis_stream_router: Arc<StreamRouter>,
   pub redis_connection_manager: RedisConnectionManager,
   pub collab_cache: CollabCache,
@@ -47,15 +58,26 @@ pub struct AppState {
   pub collab_access_control: Arc<dyn CollabAccessControl>,
   pub workspace_access_control: Arc<dyn WorkspaceAccessControl>,
   pub realtime_access_control: Arc<dyn RealtimeAccessControl>,
-  pub bucket_storage: Arc<S3BucketStorage>,
+  pub collab_lock_cache: CollabLockCache,
+  pub workspace_read_only_cache: WorkspaceReadOnlyCache,
+  pub restricted_view_cache: RestrictedViewCache,
+  pub collab_guest_cache: CollabGuestCache,
+  pub session_policy: SessionPolicy,
+  pub bucket_storage: Arc<BlobBucketStorage>,
   pub published_collab_store: Arc<dyn PublishedCollabStore>,
-  pub bucket_client: AwsS3BucketClientImpl,
+  pub publish_analytics: PublishAnalyticsRecorder,
+  pub bucket_client: BlobStorageClient,
+  /// Set when the workspaces can use their own bucket.
+  pub workspace_storage_router: Option<Arc<WorkspaceStorageRouter>>,
   pub pg_listeners: Arc<PgListeners>,
   pub metrics: AppMetrics,
   pub gotrue_admin: GoTrueAdmin,
   pub mailer: AFCloudMailer,
   pub ai_client: AppFlowyAIClient,
+  pub language_checker: Arc<LanguageChecker>,
+  pub notification_center: NotificationCenter,
   pub indexer_scheduler: Arc<IndexerScheduler>,
+  pub feature_flags: FeatureFlags,
 }
 
 impl AppState {
@@ -129,6 +151,7 @@ pub struct AppMetrics {
   pub embedding_metrics: Arc<EmbeddingMetrics>,
   pub collab_stream_metrics: Arc<CollabStreamMetrics>,
   pub ai_metrics: Arc<AIMetrics>,
+  pub push_metrics: Arc<PushMetrics>,
 }
 
 impl Default for AppMetrics {
@@ -149,6 +172,7 @@ impl AppMetrics {
     let embedding_metrics = Arc::new(EmbeddingMetrics::register(&mut registry));
     let collab_stream_metrics = Arc::new(CollabStreamMetrics::register(&mut registry));
     let ai_metrics = Arc::new(AIMetrics::register(&mut registry));
+    let push_metrics = Arc::new(PushMetrics::register(&mut registry));
     Self {
       registry: Arc::new(registry),
       request_metrics,
@@ -160,6 +184,7 @@ impl AppMetrics {
       embedding_metrics,
       collab_stream_metrics,
       ai_metrics,
+      push_metrics,
     }
   }
 }
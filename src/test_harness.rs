@@ -0,0 +1,188 @@
+//! In-process harness for the tests of the server subsystems (import, permissions, publish...).
+//!
+//! [TestHarness::start] assembles an [AppState] the same way the server does, with:
+//! - a new Postgres database for each harness, created on the configured server and dropped by
+//!   [TestHarness::shutdown]. The tables owned by GoTrue are created by the harness, so GoTrue
+//!   doesn't need to run.
+//! - an [InMemoryBucketClientImpl] as the blob storage.
+//! - the http and realtime server listening on a random loopback port.
+//!
+//! Redis is still required. The harness must be started from an actix runtime, e.g. in an
+//! `#[actix_rt::test]`, since the realtime server runs as an actor.
+use actix_web::dev::ServerHandle;
+use anyhow::{Context, Error};
+use chrono::Utc;
+use database::file::memory_client_impl::InMemoryBucketClientImpl;
+use database::file::{BlobStorageBackend, BlobStorageClient};
+use gotrue_entity::gotrue_jwt::GoTrueJWTClaims;
+use jsonwebtoken::{encode, EncodingKey, Header};
+use secrecy::ExposeSecret;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::{Connection, PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::application::{init_state, Application};
+use crate::biz::user::user_verify::create_new_user;
+use crate::config::config::{get_configuration, Config};
+use crate::state::AppState;
+
+/// Lifetime of the access tokens minted by [TestHarness::access_token].
+const ACCESS_TOKEN_EXPIRES_IN_SECS: i64 = 3600;
+
+pub struct TestHarness {
+  pub state: AppState,
+  pub config: Config,
+  port: u16,
+  server: ServerHandle,
+  database_name: String,
+  /// Connected to the database the configuration pointed to, used to drop the test database.
+  admin_pool: PgPool,
+}
+
+pub struct TestHarnessUser {
+  pub uid: i64,
+  pub uuid: Uuid,
+  pub email: String,
+  pub workspace_id: Uuid,
+  /// Bearer token accepted by the server, signed with the GoTrue jwt secret.
+  pub access_token: String,
+}
+
+impl TestHarness {
+  /// Starts a harness with the configuration read from the environment.
+  pub async fn start() -> Result<Self, Error> {
+    Self::start_with(get_configuration()?).await
+  }
+
+  /// Starts a harness with the given configuration. The database, the blob storage and the
+  /// address of the server are replaced by the ones of the harness.
+  pub async fn start_with(mut config: Config) -> Result<Self, Error> {
+    let admin_pool = PgPoolOptions::new()
+      .max_connections(1)
+      .connect_with(config.db_settings.pg_connect_options())
+      .await
+      .context("fail to connect to postgres")?;
+    let database_name = format!("appflowy_test_{}", Uuid::new_v4().simple());
+    sqlx::query(&format!(r#"CREATE DATABASE "{}""#, database_name))
+      .execute(&admin_pool)
+      .await
+      .with_context(|| format!("fail to create database {}", database_name))?;
+    config.db_settings.pg_conn_opts = config.db_settings.pg_conn_opts.database(&database_name);
+    create_gotrue_tables(&config).await?;
+
+    config.blob_storage.backend = BlobStorageBackend::Memory;
+    config.blob_storage.workspace_storage_key = None;
+    config.application.host = "127.0.0.1".to_string();
+    config.application.port = 0;
+
+    let (rt_cmd_tx, rt_cmd_rx) = tokio::sync::mpsc::channel(1000);
+    let state = init_state(&config, rt_cmd_tx).await?;
+    let application = Application::build(config.clone(), state.clone(), rt_cmd_rx).await?;
+    let port = application.port();
+    let server = application.handle();
+    actix_web::rt::spawn(application.run_until_stopped());
+
+    Ok(Self {
+      state,
+      config,
+      port,
+      server,
+      database_name,
+      admin_pool,
+    })
+  }
+
+  pub fn base_url(&self) -> String {
+    format!("http://127.0.0.1:{}", self.port)
+  }
+
+  pub fn ws_url(&self) -> String {
+    format!("ws://127.0.0.1:{}/ws/v1", self.port)
+  }
+
+  /// The blob storage of the harness, to check the objects written by the subsystems.
+  pub fn blob_storage(&self) -> &InMemoryBucketClientImpl {
+    match self.state.bucket_client.default_client() {
+      BlobStorageClient::Memory(client) => client,
+      _ => unreachable!("the harness always uses the in-memory blob storage"),
+    }
+  }
+
+  /// Creates a user with its first workspace, as if the user signed in for the first time.
+  pub async fn create_user(&self) -> Result<TestHarnessUser, Error> {
+    let uuid = Uuid::new_v4();
+    let email = format!("{}@appflowy.test", uuid.simple());
+    sqlx::query("INSERT INTO auth.users (id) VALUES ($1)")
+      .bind(uuid)
+      .execute(&self.state.pg_pool)
+      .await?;
+    let (uid, workspace_id) = create_new_user(&self.state, &uuid, &email, "").await?;
+    let access_token = self.access_token(&uuid, &email)?;
+    Ok(TestHarnessUser {
+      uid,
+      uuid,
+      email,
+      workspace_id,
+      access_token,
+    })
+  }
+
+  /// Mints the access token GoTrue would give to the user.
+  pub fn access_token(&self, user_uuid: &Uuid, email: &str) -> Result<String, Error> {
+    let now = Utc::now().timestamp();
+    let claims = GoTrueJWTClaims {
+      aud: Some("authenticated".to_string()),
+      exp: Some(now + ACCESS_TOKEN_EXPIRES_IN_SECS),
+      jti: None,
+      iat: Some(now),
+      iss: None,
+      nbf: None,
+      sub: Some(user_uuid.to_string()),
+      email: email.to_string(),
+      phone: "".to_string(),
+      app_metadata: serde_json::json!({}),
+      user_metadata: serde_json::json!({}),
+      role: "authenticated".to_string(),
+      aal: None,
+      amr: None,
+      session_id: None,
+    };
+    let key = EncodingKey::from_secret(self.config.gotrue.jwt_secret.expose_secret().as_bytes());
+    Ok(encode(&Header::default(), &claims, &key)?)
+  }
+
+  /// Stops the server and drops the database of the harness.
+  pub async fn shutdown(self) -> Result<(), Error> {
+    self.server.stop(false).await;
+    self.state.pg_pool.close().await;
+    sqlx::query(&format!(
+      r#"DROP DATABASE IF EXISTS "{}" WITH (FORCE)"#,
+      self.database_name
+    ))
+    .execute(&self.admin_pool)
+    .await
+    .with_context(|| format!("fail to drop database {}", self.database_name))?;
+    Ok(())
+  }
+}
+
+/// The migrations reference the users table owned by GoTrue, which doesn't run in the tests.
+async fn create_gotrue_tables(config: &Config) -> Result<(), Error> {
+  let mut conn = PgConnection::connect_with(&config.db_settings.pg_connect_options()).await?;
+  sqlx::query("CREATE SCHEMA IF NOT EXISTS auth")
+    .execute(&mut conn)
+    .await?;
+  sqlx::query(
+    r#"
+      CREATE TABLE IF NOT EXISTS auth.users(
+        id uuid NOT NULL UNIQUE,
+        deleted_at timestamptz null,
+        CONSTRAINT users_pkey PRIMARY KEY (id)
+      )
+    "#,
+  )
+  .execute(&mut conn)
+  .await?;
+  conn.close().await?;
+  Ok(())
+}
@@ -1,17 +1,20 @@
 use crate::ai_test::util::{extract_image_url, read_text_from_asset};
 use std::time::Duration;
 
+use app_error::ErrorCode;
 use appflowy_ai_client::dto::{
   ChatQuestionQuery, OutputContent, OutputContentMetadata, OutputLayout, ResponseFormat,
 };
 use assert_json_diff::assert_json_include;
 use client_api::entity::{QuestionStream, QuestionStreamValue};
-use client_api_test::{ai_test_enabled, TestClient};
+use client_api_test::{admin_user_client, ai_test_enabled, TestClient};
 use futures_util::StreamExt;
 use serde_json::json;
 use shared_entity::dto::chat_dto::{
-  ChatMessageMetadata, ChatRAGData, CreateAnswerMessageParams, CreateChatMessageParams,
-  CreateChatParams, MessageCursor, UpdateChatParams,
+  ChatAttachmentStatus, ChatMessageFeedbackParams, ChatMessageFeedbackQuery,
+  ChatMessageFeedbackRating, ChatMessageMetadata, ChatRAGData, CreateAnswerMessageParams,
+  CreateChatMessageParams, CreateChatParams, GetChatThreadParams, MessageCursor,
+  SearchChatMessageParams, UpdateChatParams,
 };
 
 #[tokio::test]
@@ -189,6 +192,109 @@ async fn create_chat_and_create_messages_test() {
   assert_eq!(next_back.messages.len(), 10);
 }
 
+#[tokio::test]
+async fn search_chat_messages_and_threads_test() {
+  if !ai_test_enabled() {
+    return;
+  }
+
+  let test_client = TestClient::new_user_without_ws_conn().await;
+  let workspace_id = test_client.workspace_id().await;
+  let chat_id = uuid::Uuid::new_v4().to_string();
+  let params = CreateChatParams {
+    chat_id: chat_id.clone(),
+    name: "search chat".to_string(),
+    rag_ids: vec![],
+  };
+  test_client
+    .api_client
+    .create_chat(&workspace_id, params)
+    .await
+    .unwrap();
+
+  for (question, answer) in [
+    (
+      "What is on the roadmap?",
+      "Offline databases and a new editor",
+    ),
+    ("When is the editor released?", "In the next release"),
+  ] {
+    let question = test_client
+      .api_client
+      .create_question(
+        &workspace_id,
+        &chat_id,
+        CreateChatMessageParams::new_user(question),
+      )
+      .await
+      .unwrap();
+    test_client
+      .api_client
+      .save_answer(
+        &workspace_id,
+        &chat_id,
+        CreateAnswerMessageParams {
+          content: answer.to_string(),
+          metadata: None,
+          question_message_id: question.message_id,
+        },
+      )
+      .await
+      .unwrap();
+  }
+
+  let results = test_client
+    .api_client
+    .search_chat_messages(
+      &workspace_id,
+      &chat_id,
+      &SearchChatMessageParams {
+        q: "roadmap".to_string(),
+        limit: 10,
+      },
+    )
+    .await
+    .unwrap();
+  assert_eq!(results.len(), 1);
+  assert_eq!(results[0].message.content, "What is on the roadmap?");
+  assert!(results[0].snippet.contains("<b>roadmap</b>"));
+
+  let results = test_client
+    .api_client
+    .search_chat_messages(
+      &workspace_id,
+      &chat_id,
+      &SearchChatMessageParams {
+        q: "editor".to_string(),
+        limit: 10,
+      },
+    )
+    .await
+    .unwrap();
+  assert_eq!(results.len(), 2);
+
+  // the second question follows the first answer right away, so it continues the same thread
+  let threads = test_client
+    .api_client
+    .get_chat_threads(
+      &workspace_id,
+      &chat_id,
+      &GetChatThreadParams {
+        before: None,
+        limit: 10,
+      },
+    )
+    .await
+    .unwrap();
+  assert_eq!(threads.total, 1);
+  assert!(!threads.has_more);
+  assert_eq!(threads.threads[0].messages.len(), 4);
+  assert_eq!(
+    threads.threads[0].messages[0].content,
+    "What is on the roadmap?"
+  );
+}
+
 #[tokio::test]
 async fn chat_qa_test() {
   if !ai_test_enabled() {
@@ -246,6 +352,174 @@ async fn chat_qa_test() {
   println!("related questions: {:?}", related_questions.items);
 }
 
+#[tokio::test]
+async fn upload_chat_attachment_test() {
+  if !ai_test_enabled() {
+    return;
+  }
+  let test_client = TestClient::new_user_without_ws_conn().await;
+  let workspace_id = test_client.workspace_id().await;
+  let chat_id = uuid::Uuid::new_v4().to_string();
+  let params = CreateChatParams {
+    chat_id: chat_id.clone(),
+    name: "chat with attachment".to_string(),
+    rag_ids: vec![],
+  };
+  test_client
+    .api_client
+    .create_chat(&workspace_id, params)
+    .await
+    .unwrap();
+
+  let content = read_text_from_asset("my_profile.txt");
+  let attachment = test_client
+    .api_client
+    .upload_chat_attachment(
+      &workspace_id,
+      &chat_id,
+      "my_profile.txt",
+      "text/plain",
+      content.into_bytes(),
+    )
+    .await
+    .unwrap();
+  assert_eq!(attachment.status, ChatAttachmentStatus::Ready);
+
+  // Unsupported formats are rejected
+  let err = test_client
+    .api_client
+    .upload_chat_attachment(
+      &workspace_id,
+      &chat_id,
+      "archive.zip",
+      "application/zip",
+      vec![1, 2, 3],
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+
+  let attachments = test_client
+    .api_client
+    .list_chat_attachments(&workspace_id, &chat_id)
+    .await
+    .unwrap();
+  assert_eq!(attachments.len(), 1);
+  assert_eq!(attachments[0].attachment_id, attachment.attachment_id);
+
+  let params = CreateChatMessageParams::new_user("Where lucas live?");
+  let question = test_client
+    .api_client
+    .create_question(&workspace_id, &chat_id, params)
+    .await
+    .unwrap();
+  let answer = test_client
+    .api_client
+    .get_answer(&workspace_id, &chat_id, question.message_id)
+    .await
+    .unwrap();
+  assert!(!answer.content.is_empty());
+}
+
+#[tokio::test]
+async fn chat_message_feedback_test() {
+  if !ai_test_enabled() {
+    return;
+  }
+  let test_client = TestClient::new_user_without_ws_conn().await;
+  let workspace_id = test_client.workspace_id().await;
+  let chat_id = uuid::Uuid::new_v4().to_string();
+  let params = CreateChatParams {
+    chat_id: chat_id.clone(),
+    name: "chat with feedback".to_string(),
+    rag_ids: vec![],
+  };
+  test_client
+    .api_client
+    .create_chat(&workspace_id, params)
+    .await
+    .unwrap();
+  let question = test_client
+    .api_client
+    .create_question(
+      &workspace_id,
+      &chat_id,
+      CreateChatMessageParams::new_user("What is AppFlowy?"),
+    )
+    .await
+    .unwrap();
+  let answer = test_client
+    .api_client
+    .save_answer(
+      &workspace_id,
+      &chat_id,
+      CreateAnswerMessageParams {
+        content: "AppFlowy is an open source workspace".to_string(),
+        metadata: None,
+        question_message_id: question.message_id,
+      },
+    )
+    .await
+    .unwrap();
+
+  // Questions can't be rated
+  let err = test_client
+    .api_client
+    .create_chat_message_feedback(
+      &workspace_id,
+      &chat_id,
+      question.message_id,
+      ChatMessageFeedbackParams {
+        rating: ChatMessageFeedbackRating::ThumbsUp,
+        comment: None,
+      },
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+
+  for rating in [
+    ChatMessageFeedbackRating::ThumbsUp,
+    ChatMessageFeedbackRating::ThumbsDown,
+  ] {
+    test_client
+      .api_client
+      .create_chat_message_feedback(
+        &workspace_id,
+        &chat_id,
+        answer.message_id,
+        ChatMessageFeedbackParams {
+          rating,
+          comment: Some("too short".to_string()),
+        },
+      )
+      .await
+      .unwrap();
+  }
+
+  let query = ChatMessageFeedbackQuery {
+    workspace_id: Some(workspace_id.parse().unwrap()),
+    ..Default::default()
+  };
+  let err = test_client
+    .api_client
+    .export_chat_message_feedback(&query)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::NotEnoughPermissions);
+
+  // Rating the answer again replaces the previous feedback
+  let feedback = admin_user_client()
+    .await
+    .export_chat_message_feedback(&query)
+    .await
+    .unwrap();
+  assert_eq!(feedback.len(), 1);
+  assert_eq!(feedback[0].message_id, answer.message_id);
+  assert_eq!(feedback[0].rating, ChatMessageFeedbackRating::ThumbsDown);
+  assert_eq!(feedback[0].comment.as_deref(), Some("too short"));
+}
+
 #[tokio::test]
 async fn generate_chat_message_answer_test() {
   if !ai_test_enabled() {
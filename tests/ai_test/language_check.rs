@@ -0,0 +1,59 @@
+use app_error::ErrorCode;
+use client_api_test::{ai_test_enabled, TestClient};
+use database_entity::dto::{AFWorkspaceAIFeaturesChange, AFWorkspaceSettingsChange};
+use shared_entity::dto::ai_dto::LanguageCheckParams;
+
+#[tokio::test]
+async fn language_check_test() {
+  if !ai_test_enabled() {
+    return;
+  }
+  let test_client = TestClient::new_user().await;
+  let workspace_id = test_client.workspace_id().await;
+  let params = LanguageCheckParams {
+    workspace_id,
+    text: "I has a apple.\nThis sentence are wrong.".to_string(),
+    language: Some("en-US".to_string()),
+  };
+
+  let resp = test_client
+    .api_client
+    .language_check(params.clone())
+    .await
+    .unwrap();
+  assert!(!resp.matches.is_empty());
+  let text_len = params.text.encode_utf16().count();
+  for m in &resp.matches {
+    assert!(m.offset + m.length <= text_len);
+  }
+
+  // the second check is answered from the cache
+  let cached = test_client.api_client.language_check(params).await.unwrap();
+  assert_eq!(cached.matches, resp.matches);
+}
+
+#[tokio::test]
+async fn language_check_disabled_by_owner() {
+  let test_client = TestClient::new_user().await;
+  let workspace_id = test_client.workspace_id().await;
+  let change = AFWorkspaceSettingsChange::new().ai_features(AFWorkspaceAIFeaturesChange {
+    ai_language_check: Some(false),
+    ..Default::default()
+  });
+  test_client
+    .api_client
+    .update_workspace_settings(&workspace_id, &change)
+    .await
+    .unwrap();
+
+  let err = test_client
+    .api_client
+    .language_check(LanguageCheckParams {
+      workspace_id,
+      text: "I has a apple.".to_string(),
+      language: None,
+    })
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::FeatureDisabled);
+}
@@ -1,4 +1,5 @@
 mod chat_test;
+mod language_check;
 // mod local_ai_test;
 mod summarize_row;
 mod util;
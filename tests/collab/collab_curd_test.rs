@@ -4,8 +4,10 @@ use collab::entity::EncodedCollab;
 use collab_document::document_data::default_document_collab_data;
 use collab_entity::CollabType;
 use database_entity::dto::{
-  CollabParams, CreateCollabParams, QueryCollab, QueryCollabParams, QueryCollabResult,
+  ChangedCollabQuery, CollabChangeKind, CollabParams, CreateCollabParams, DeleteCollabParams,
+  QueryCollab, QueryCollabParams, QueryCollabResult,
 };
+use std::time::Duration;
 
 use reqwest::Method;
 use serde::Serialize;
@@ -13,6 +15,7 @@ use serde_json::json;
 
 use crate::collab::util::{empty_document_editor, generate_random_string, test_encode_collab_v1};
 use client_api_test::TestClient;
+use shared_entity::dto::workspace_dto::CreateWorkspaceParam;
 use shared_entity::response::AppResponse;
 use uuid::Uuid;
 
@@ -216,3 +219,131 @@ pub struct OldCreateCollabParams {
   inner: CollabParams,
   pub workspace_id: String,
 }
+
+#[tokio::test]
+async fn create_collab_with_object_id_of_another_workspace_test() {
+  let owner = TestClient::new_user().await;
+  let owner_workspace_id = owner.workspace_id().await;
+  let object_id = Uuid::new_v4().to_string();
+  let encoded_collab_v1 = test_encode_collab_v1(&object_id, "title", "hello world")
+    .encode_to_bytes()
+    .unwrap();
+  owner
+    .api_client
+    .create_collab(CreateCollabParams {
+      object_id: object_id.clone(),
+      encoded_collab_v1: encoded_collab_v1.clone(),
+      collab_type: CollabType::Unknown,
+      workspace_id: owner_workspace_id.clone(),
+    })
+    .await
+    .unwrap();
+
+  // the owner is told which workspace already has the object
+  let other_workspace_id = owner
+    .api_client
+    .create_workspace(CreateWorkspaceParam {
+      workspace_name: Some("other workspace".to_string()),
+    })
+    .await
+    .unwrap()
+    .workspace_id
+    .to_string();
+  let error = owner
+    .api_client
+    .create_collab(CreateCollabParams {
+      object_id: object_id.clone(),
+      encoded_collab_v1: encoded_collab_v1.clone(),
+      collab_type: CollabType::Unknown,
+      workspace_id: other_workspace_id,
+    })
+    .await
+    .unwrap_err();
+  assert!(error.is_collab_object_id_conflict());
+  assert_eq!(
+    error.conflicting_workspace_id(),
+    Some(owner_workspace_id.as_str())
+  );
+
+  // other users only learn that the object id is taken
+  let other = TestClient::new_user().await;
+  let error = other
+    .api_client
+    .create_collab(CreateCollabParams {
+      object_id,
+      encoded_collab_v1,
+      collab_type: CollabType::Unknown,
+      workspace_id: other.workspace_id().await,
+    })
+    .await
+    .unwrap_err();
+  assert_eq!(error.code, ErrorCode::CollabObjectIdConflict);
+  assert_eq!(error.conflicting_workspace_id(), None);
+}
+
+#[tokio::test]
+async fn list_changed_collabs_test() {
+  let test_client = TestClient::new_user().await;
+  let workspace_id = test_client.workspace_id().await;
+  let since = chrono::Utc::now();
+  let object_id = Uuid::new_v4().to_string();
+  let encoded_collab_v1 = test_encode_collab_v1(&object_id, "title", "hello world")
+    .encode_to_bytes()
+    .unwrap();
+  test_client
+    .api_client
+    .create_collab(CreateCollabParams {
+      object_id: object_id.clone(),
+      encoded_collab_v1,
+      collab_type: CollabType::Unknown,
+      workspace_id: workspace_id.clone(),
+    })
+    .await
+    .unwrap();
+
+  // the most recent changes are listed after a few seconds
+  tokio::time::sleep(Duration::from_secs(3)).await;
+  let changes = test_client
+    .api_client
+    .get_changed_collabs(
+      &workspace_id,
+      &ChangedCollabQuery {
+        since,
+        after: None,
+        limit: None,
+      },
+    )
+    .await
+    .unwrap();
+  let created = changes
+    .changes
+    .iter()
+    .find(|change| change.object_id == object_id)
+    .unwrap();
+  assert_eq!(created.change, CollabChangeKind::Created);
+
+  test_client
+    .api_client
+    .delete_collab(DeleteCollabParams {
+      object_id: object_id.clone(),
+      workspace_id: workspace_id.clone(),
+    })
+    .await
+    .unwrap();
+  tokio::time::sleep(Duration::from_secs(3)).await;
+  let changes = test_client
+    .api_client
+    .get_changed_collabs(
+      &workspace_id,
+      &ChangedCollabQuery {
+        since: changes.next_since,
+        after: changes.next_after,
+        limit: None,
+      },
+    )
+    .await
+    .unwrap();
+  assert_eq!(changes.changes.len(), 1);
+  assert_eq!(changes.changes[0].object_id, object_id);
+  assert_eq!(changes.changes[0].change, CollabChangeKind::Deleted);
+}
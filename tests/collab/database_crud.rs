@@ -1,9 +1,16 @@
 use std::collections::HashMap;
 
+use app_error::ErrorCode;
 use client_api_test::{generate_unique_registered_user_client, workspace_id_from_client};
 use collab_database::entity::FieldType;
+use collab_entity::CollabType;
+use database_entity::dto::{DatabaseRowActivityQuery, QueryCollabParams};
 use serde_json::json;
-use shared_entity::dto::workspace_dto::AFInsertDatabaseField;
+use shared_entity::dto::workspace_dto::{
+  AFDatabaseViewFilter, AFDatabaseViewSettings, AFDatabaseViewSort, AFInsertDatabaseField,
+  AFUpdateDatabaseField, BulkUpsertDatabaseRow, CreateDatabaseFormTokenParams,
+  ListDatabaseRowDetailParam, SubmitDatabaseFormRow,
+};
 
 #[tokio::test]
 async fn database_row_upsert_with_doc() {
@@ -140,6 +147,254 @@ async fn database_row_upsert() {
   }
 }
 
+#[tokio::test]
+async fn database_rows_bulk_upsert_by_external_id() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c).await;
+  let databases = c.list_databases(&workspace_id).await.unwrap();
+  let todo_db = &databases[0];
+
+  let bulk_row = |external_id: &str, description: &str| BulkUpsertDatabaseRow {
+    external_id: external_id.to_string(),
+    cells: HashMap::from([(String::from("Description"), json!(description))]),
+  };
+  // the external id field has to be set first
+  let err = c
+    .bulk_upsert_database_rows(&workspace_id, &todo_db.id, vec![bulk_row("a", "1")])
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+
+  let number_field_id = c
+    .add_database_field(
+      &workspace_id,
+      &todo_db.id,
+      &AFInsertDatabaseField {
+        name: "Number".to_string(),
+        field_type: FieldType::Number.into(),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+  let err = c
+    .set_database_external_id_field(&workspace_id, &todo_db.id, &number_field_id)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+
+  let external_id_field_id = c
+    .add_database_field(
+      &workspace_id,
+      &todo_db.id,
+      &AFInsertDatabaseField {
+        name: "ExternalId".to_string(),
+        field_type: FieldType::RichText.into(),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+  c.set_database_external_id_field(&workspace_id, &todo_db.id, &external_id_field_id)
+    .await
+    .unwrap();
+  assert_eq!(
+    c.get_database_external_id_field(&workspace_id, &todo_db.id)
+      .await
+      .unwrap()
+      .field_id,
+    external_id_field_id
+  );
+
+  let num_rows_before = c
+    .list_database_row_ids(&workspace_id, &todo_db.id)
+    .await
+    .unwrap()
+    .len();
+  let created = c
+    .bulk_upsert_database_rows(
+      &workspace_id,
+      &todo_db.id,
+      vec![bulk_row("a", "first a"), bulk_row("b", "first b")],
+    )
+    .await
+    .unwrap()
+    .rows;
+  assert_eq!(created.len(), 2);
+  assert!(created.iter().all(|row| row.created));
+
+  // sending the rows again updates them
+  let upserted = c
+    .bulk_upsert_database_rows(
+      &workspace_id,
+      &todo_db.id,
+      vec![bulk_row("b", "second b"), bulk_row("c", "first c")],
+    )
+    .await
+    .unwrap()
+    .rows;
+  assert_eq!(upserted[0].row_id, created[1].row_id);
+  assert!(!upserted[0].created);
+  assert!(upserted[1].created);
+  assert_eq!(
+    c.list_database_row_ids(&workspace_id, &todo_db.id)
+      .await
+      .unwrap()
+      .len(),
+    num_rows_before + 3
+  );
+
+  let row_detail = &c
+    .list_database_row_details(&workspace_id, &todo_db.id, &[&created[1].row_id], false)
+    .await
+    .unwrap()[0];
+  assert_eq!(row_detail.cells["Description"], "second b");
+  assert_eq!(row_detail.cells["ExternalId"], "b");
+
+  // the same external id can't be given twice
+  let err = c
+    .bulk_upsert_database_rows(
+      &workspace_id,
+      &todo_db.id,
+      vec![bulk_row("d", "1"), bulk_row("d", "2")],
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+}
+
+#[tokio::test]
+async fn database_row_comments_and_activity() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let user_uuid = c.get_profile().await.unwrap().uuid;
+  let workspace_id = workspace_id_from_client(&c).await;
+  let databases = c.list_databases(&workspace_id).await.unwrap();
+  let todo_db = &databases[0];
+
+  let pre_hash = String::from("row_with_activity");
+  let row_id = c
+    .upsert_database_item(
+      &workspace_id,
+      &todo_db.id,
+      pre_hash.clone(),
+      HashMap::from([(String::from("Description"), json!("description_1"))]),
+      None,
+    )
+    .await
+    .unwrap();
+  c.upsert_database_item(
+    &workspace_id,
+    &todo_db.id,
+    pre_hash,
+    HashMap::from([(String::from("Description"), json!("description_2"))]),
+    None,
+  )
+  .await
+  .unwrap();
+
+  // the edit of the existing row is recorded along with the user who made it
+  let activity = c
+    .get_database_row_activity(
+      &workspace_id,
+      &todo_db.id,
+      &row_id,
+      &DatabaseRowActivityQuery::default(),
+    )
+    .await
+    .unwrap();
+  assert!(!activity.has_more);
+  assert_eq!(activity.activities.len(), 1);
+  let change = &activity.activities[0];
+  assert_eq!(change.changed_by, Some(user_uuid));
+  assert!(change.old_value.is_some());
+  assert_ne!(change.old_value, change.new_value);
+
+  c.create_database_row_comment(&workspace_id, &todo_db.id, &row_id, "first", None)
+    .await
+    .unwrap();
+  let comments = c
+    .get_database_row_comments(&workspace_id, &todo_db.id, &row_id)
+    .await
+    .unwrap()
+    .comments;
+  assert_eq!(comments.len(), 1);
+  assert_eq!(comments[0].content, "first");
+  assert!(comments[0].can_be_deleted);
+
+  let reply_to = Some(comments[0].comment_id);
+  c.create_database_row_comment(&workspace_id, &todo_db.id, &row_id, "reply", reply_to)
+    .await
+    .unwrap();
+  c.delete_database_row_comment(&workspace_id, &todo_db.id, &row_id, &comments[0].comment_id)
+    .await
+    .unwrap();
+  let comments = c
+    .get_database_row_comments(&workspace_id, &todo_db.id, &row_id)
+    .await
+    .unwrap()
+    .comments;
+  assert_eq!(comments.len(), 2);
+  assert_eq!(comments[0].reply_comment_id, reply_to);
+  assert!(comments[1].is_deleted);
+
+  // comments of an unknown row are rejected
+  let err = c
+    .create_database_row_comment(
+      &workspace_id,
+      &todo_db.id,
+      &uuid::Uuid::new_v4().to_string(),
+      "lost",
+      None,
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::RecordNotFound);
+}
+
+#[tokio::test]
+async fn database_row_document_created_on_open() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c).await;
+  let databases = c.list_databases(&workspace_id).await.unwrap();
+  let todo_db = &databases[0];
+
+  let row_id = c
+    .add_database_item(&workspace_id, &todo_db.id, HashMap::new(), None)
+    .await
+    .unwrap();
+
+  // the first open creates the document, the next ones return the same document
+  let document = c
+    .open_database_row_document(&workspace_id, &todo_db.id, &row_id)
+    .await
+    .unwrap();
+  assert!(document.created);
+  let reopened = c
+    .open_database_row_document(&workspace_id, &todo_db.id, &row_id)
+    .await
+    .unwrap();
+  assert!(!reopened.created);
+  assert_eq!(reopened.document_id, document.document_id);
+
+  c.get_collab(QueryCollabParams::new(
+    &document.document_id,
+    CollabType::Document,
+    &workspace_id,
+  ))
+  .await
+  .unwrap();
+
+  let err = c
+    .open_database_row_document(
+      &workspace_id,
+      &todo_db.id,
+      &uuid::Uuid::new_v4().to_string(),
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::RecordNotFound);
+}
+
 #[tokio::test]
 async fn database_fields_crud() {
   let (c, _user) = generate_unique_registered_user_client().await;
@@ -251,6 +506,231 @@ async fn database_fields_crud() {
   }
 }
 
+#[tokio::test]
+async fn database_form_token_adds_rows() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c).await;
+  let databases = c.list_databases(&workspace_id).await.unwrap();
+  let todo_db = &databases[0];
+
+  let form_token = c
+    .create_database_form_token(
+      &workspace_id,
+      &todo_db.id,
+      &CreateDatabaseFormTokenParams {
+        name: "Feedback form".to_string(),
+        view_id: None,
+      },
+    )
+    .await
+    .unwrap();
+  let token = form_token.token.clone().unwrap();
+
+  let num_rows_before = c
+    .list_database_row_ids(&workspace_id, &todo_db.id)
+    .await
+    .unwrap()
+    .len();
+  let submitted = c
+    .submit_database_form_row(
+      &token,
+      &SubmitDatabaseFormRow {
+        cells: HashMap::from([(String::from("Description"), json!("from the form"))]),
+        captcha_response: None,
+      },
+    )
+    .await
+    .unwrap();
+  let row_detail = &c
+    .list_database_row_details(&workspace_id, &todo_db.id, &[&submitted.row_id], false)
+    .await
+    .unwrap()[0];
+  assert_eq!(row_detail.cells["Description"], "from the form");
+  assert_eq!(
+    c.list_database_row_ids(&workspace_id, &todo_db.id)
+      .await
+      .unwrap()
+      .len(),
+    num_rows_before + 1
+  );
+
+  // the token is never returned again
+  let tokens = c
+    .list_database_form_tokens(&workspace_id, &todo_db.id)
+    .await
+    .unwrap();
+  assert_eq!(tokens.len(), 1);
+  assert!(tokens[0].token.is_none());
+  assert!(tokens[0].last_used_at.is_some());
+
+  c.delete_database_form_token(&workspace_id, &todo_db.id, &form_token.token_id.to_string())
+    .await
+    .unwrap();
+  let err = c
+    .submit_database_form_row(
+      &token,
+      &SubmitDatabaseFormRow {
+        cells: HashMap::from([(String::from("Description"), json!("too late"))]),
+        captcha_response: None,
+      },
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::RecordNotFound);
+}
+
+#[tokio::test]
+async fn database_fields_update_and_delete() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c).await;
+  let databases = c.list_databases(&workspace_id).await.unwrap();
+  let todo_db = &databases[0];
+
+  let field_id = c
+    .add_database_field(
+      &workspace_id,
+      &todo_db.id,
+      &AFInsertDatabaseField {
+        name: "Priority".to_string(),
+        field_type: FieldType::RichText.into(),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+
+  // change to a single select with typed options
+  let field = c
+    .update_database_field(
+      &workspace_id,
+      &todo_db.id,
+      &field_id,
+      &AFUpdateDatabaseField {
+        name: Some("Priority Level".to_string()),
+        field_type: Some(FieldType::SingleSelect.into()),
+        type_option_data: Some(json!({
+          "content": {
+            "options": [
+              { "id": "high", "name": "High", "color": "Purple" },
+              { "id": "low", "name": "Low", "color": "Blue" },
+            ],
+            "disable_color": false,
+          }
+        })),
+      },
+    )
+    .await
+    .unwrap();
+  assert_eq!(field.name, "Priority Level");
+  assert_eq!(field.field_type, "SingleSelect");
+  assert_eq!(field.type_option["content"]["options"][0]["name"], "High");
+
+  // invalid select type option is rejected
+  let err = c
+    .update_database_field(
+      &workspace_id,
+      &todo_db.id,
+      &field_id,
+      &AFUpdateDatabaseField {
+        type_option_data: Some(json!({ "content": { "options": "not a list" } })),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+
+  // primary field can not be deleted
+  let fields = c
+    .get_database_fields(&workspace_id, &todo_db.id)
+    .await
+    .unwrap();
+  let primary = fields.iter().find(|f| f.is_primary).unwrap();
+  let err = c
+    .delete_database_field(&workspace_id, &todo_db.id, &primary.id)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+
+  c.delete_database_field(&workspace_id, &todo_db.id, &field_id)
+    .await
+    .unwrap();
+  let fields = c
+    .get_database_fields(&workspace_id, &todo_db.id)
+    .await
+    .unwrap();
+  assert!(fields.iter().all(|f| f.id != field_id));
+}
+
+#[tokio::test]
+async fn database_view_settings() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c).await;
+  let databases = c.list_databases(&workspace_id).await.unwrap();
+  let todo_db = &databases[0];
+
+  let views = c
+    .list_database_views(&workspace_id, &todo_db.id)
+    .await
+    .unwrap();
+  let grid_view = views.iter().find(|v| v.layout == "Grid").unwrap();
+  let fields = c
+    .get_database_fields(&workspace_id, &todo_db.id)
+    .await
+    .unwrap();
+  let primary = fields.iter().find(|f| f.is_primary).unwrap();
+
+  let view = c
+    .update_database_view_settings(
+      &workspace_id,
+      &todo_db.id,
+      &grid_view.id,
+      &AFDatabaseViewSettings {
+        filters: Some(vec![AFDatabaseViewFilter {
+          field_id: primary.id.clone(),
+          condition: 2,
+          content: "task".to_string(),
+          ..Default::default()
+        }]),
+        sorts: Some(vec![AFDatabaseViewSort {
+          field_id: primary.id.clone(),
+          descending: true,
+          ..Default::default()
+        }]),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+  assert_eq!(view.filters.len(), 1);
+  assert_eq!(view.filters[0].content, "task");
+  assert_eq!(view.sorts.len(), 1);
+  assert!(view.sorts[0].descending);
+
+  // only board views can be grouped
+  let err = c
+    .update_database_view_settings(
+      &workspace_id,
+      &todo_db.id,
+      &grid_view.id,
+      &AFDatabaseViewSettings {
+        group_field_id: Some(primary.id.clone()),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+
+  let views = c
+    .list_database_views(&workspace_id, &todo_db.id)
+    .await
+    .unwrap();
+  let grid_view = views.iter().find(|v| v.id == grid_view.id).unwrap();
+  assert_eq!(grid_view.filters, view.filters);
+  assert_eq!(grid_view.sorts, view.sorts);
+}
+
 #[tokio::test]
 async fn database_fields_unsupported_field_type() {
   let (c, _user) = generate_unique_registered_user_client().await;
@@ -297,9 +777,39 @@ async fn database_fields_unsupported_field_type() {
     assert_eq!(row_details.len(), 1);
     let new_row_detail = &row_details[0];
     assert!(!new_row_detail.cells.contains_key("MyRelationCol"));
+
+    // the relation cells are returned as the list of related rows when expanded
+    let row_details = c
+      .list_database_row_details_with_params(
+        &workspace_id,
+        &todo_db.id,
+        &ListDatabaseRowDetailParam::new(&[&new_row_id], false).with_expand_relations(),
+      )
+      .await
+      .unwrap();
+    assert_eq!(row_details.len(), 1);
+    assert!(row_details[0].cells["MyRelationCol"].is_array());
   }
 }
 
+#[test]
+fn list_row_details_expand_param() {
+  let param = ListDatabaseRowDetailParam::new(&["row_1"], false);
+  assert!(!param.expand_relations());
+  assert!(param.with_expand_relations().expand_relations());
+
+  let param = ListDatabaseRowDetailParam {
+    expand: Some("docs, relations".to_string()),
+    ..ListDatabaseRowDetailParam::new(&["row_1"], false)
+  };
+  assert!(param.expand_relations());
+  let param = ListDatabaseRowDetailParam {
+    expand: Some("relation".to_string()),
+    ..ListDatabaseRowDetailParam::new(&["row_1"], false)
+  };
+  assert!(!param.expand_relations());
+}
+
 #[tokio::test]
 async fn database_insert_row_with_doc() {
   let (c, _user) = generate_unique_registered_user_client().await;
@@ -4,6 +4,7 @@ mod collab_embedding_test;
 mod database_crud;
 mod missing_update_test;
 mod multi_devices_edit;
+mod observer_test;
 mod permission_test;
 mod single_device_edit;
 mod snapshot_test;
@@ -0,0 +1,84 @@
+use std::time::Duration;
+
+use client_api_test::TestClient;
+use collab_entity::CollabType;
+use collab_rt_entity::{AckCode, ServerCollabMessage};
+use database_entity::dto::AFRole;
+use futures_util::StreamExt;
+use tokio::time::timeout;
+
+#[tokio::test]
+async fn observer_receives_updates_without_joining_group_test() {
+  let mut owner = TestClient::new_user().await;
+  let observer = TestClient::new_user().await;
+  let stranger = TestClient::new_user().await;
+  let workspace_id = owner.workspace_id().await;
+  owner
+    .invite_and_accepted_workspace_member(&workspace_id, &observer, AFRole::Member)
+    .await
+    .unwrap();
+  let object_id = owner
+    .create_and_edit_collab(&workspace_id, CollabType::Unknown)
+    .await;
+  owner.wait_object_sync_complete(&object_id).await.unwrap();
+
+  // a user that is not a member of the workspace can't observe the collab
+  let channel = stranger
+    .ws_client
+    .subscribe_collab(object_id.clone())
+    .unwrap();
+  let mut stream = channel.stream();
+  stranger
+    .ws_client
+    .observe_collab(&workspace_id, &object_id, 1, true)
+    .unwrap();
+  let ack = wait_for_ack(&mut stream, 1).await;
+  assert_eq!(ack, AckCode::PermissionDenied);
+
+  let channel = observer
+    .ws_client
+    .subscribe_collab(object_id.clone())
+    .unwrap();
+  let mut stream = channel.stream();
+  observer
+    .ws_client
+    .observe_collab(&workspace_id, &object_id, 1, true)
+    .unwrap();
+  let ack = wait_for_ack(&mut stream, 1).await;
+  assert_eq!(ack, AckCode::Success);
+
+  owner.insert_into(&object_id, "title", "observed").await;
+  let broadcast = timeout(Duration::from_secs(30), async {
+    while let Some(Ok(message)) = stream.next().await {
+      if let ServerCollabMessage::ServerBroadcast(broadcast) = message {
+        return broadcast;
+      }
+    }
+    panic!("observer stream closed");
+  })
+  .await
+  .unwrap();
+  assert_eq!(broadcast.object_id, object_id);
+
+  // observers don't join the collab group, so they are not listed as connected users
+  let clients = owner.get_connect_users(&object_id).await;
+  assert_eq!(clients, vec![owner.uid().await]);
+}
+
+async fn wait_for_ack<S>(stream: &mut S, msg_id: u64) -> AckCode
+where
+  S: futures_util::Stream<Item = Result<ServerCollabMessage, anyhow::Error>> + Unpin,
+{
+  timeout(Duration::from_secs(30), async {
+    while let Some(Ok(message)) = stream.next().await {
+      if let ServerCollabMessage::ClientAck(ack) = message {
+        if ack.msg_id == msg_id {
+          return ack.get_code();
+        }
+      }
+    }
+    panic!("observer stream closed");
+  })
+  .await
+  .unwrap()
+}
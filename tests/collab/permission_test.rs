@@ -498,3 +498,51 @@ async fn multiple_user_with_read_only_permission_edit_same_collab_test() {
     .to_json_value();
   assert_json_eq!(json!({}), expected);
 }
+
+#[tokio::test]
+async fn removed_workspace_member_stops_receiving_updates_test() {
+  let collab_type = CollabType::Unknown;
+  let mut client_1 = TestClient::new_user().await;
+  let mut client_2 = TestClient::new_user().await;
+
+  let workspace_id = client_1.workspace_id().await;
+  let object_id = client_1
+    .create_and_edit_collab(&workspace_id, collab_type.clone())
+    .await;
+  client_1
+    .invite_and_accepted_workspace_member(&workspace_id, &client_2, AFRole::Member)
+    .await
+    .unwrap();
+  client_2
+    .open_collab(&workspace_id, &object_id, collab_type.clone())
+    .await;
+
+  client_1.insert_into(&object_id, "name", "AppFlowy").await;
+  client_1
+    .wait_object_sync_complete(&object_id)
+    .await
+    .unwrap();
+  assert_client_collab_within_secs(
+    &mut client_2,
+    &object_id,
+    "name",
+    json!({"name": "AppFlowy"}),
+    60,
+  )
+  .await;
+
+  // Once removed from the workspace, client 2 doesn't receive the updates of the collab it
+  // already opened.
+  client_1
+    .try_remove_workspace_member(&workspace_id, &client_2)
+    .await
+    .unwrap();
+  sleep(Duration::from_secs(2)).await;
+  client_1.insert_into(&object_id, "title", "Removed").await;
+  client_1
+    .wait_object_sync_complete(&object_id)
+    .await
+    .unwrap();
+  sleep(Duration::from_secs(3)).await;
+  assert_client_collab_within_secs(&mut client_2, &object_id, "title", json!({}), 10).await;
+}
@@ -3,6 +3,7 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use assert_json_diff::assert_json_eq;
+use client_api::collab_sync::SyncStateChange;
 use client_api::entity::AFRole;
 use collab::core::origin::CollabOrigin;
 use collab_entity::CollabType;
@@ -56,6 +57,47 @@ async fn realtime_write_single_collab_test() {
   .await
   .unwrap();
 }
+
+#[tokio::test]
+async fn sync_state_is_saved_after_edit_test() {
+  let collab_type = CollabType::Unknown;
+  let mut test_client = TestClient::new_user().await;
+  let workspace_id = test_client.workspace_id().await;
+  let object_id = test_client
+    .create_and_edit_collab(&workspace_id, collab_type.clone())
+    .await;
+  let mut changes = test_client.sync_state_tracker.subscribe();
+
+  test_client.insert_into(&object_id, "1", "a").await;
+  test_client
+    .wait_object_sync_complete(&object_id)
+    .await
+    .unwrap();
+
+  let state = test_client
+    .sync_state_tracker
+    .sync_state(&object_id)
+    .unwrap();
+  assert!(state.is_saved());
+  assert!(state.last_ack_msg_id.is_some());
+  assert!(!state.is_paused);
+
+  // the edit was reported as being sent before it was acknowledged
+  let mut saw_syncing = false;
+  while let Ok(change) = changes.try_recv() {
+    if let SyncStateChange::Updated {
+      object_id: id,
+      state,
+    } = change
+    {
+      if id == object_id && state.sink_state.is_syncing() {
+        saw_syncing = true;
+      }
+    }
+  }
+  assert!(saw_syncing);
+}
+
 #[tokio::test]
 async fn collab_write_small_chunk_of_data_test() {
   let collab_type = CollabType::Unknown;
@@ -769,3 +811,63 @@ async fn insert_text_through_http_post_request() {
   .await
   .unwrap();
 }
+
+#[tokio::test]
+async fn collab_edit_stats_test() {
+  let collab_type = CollabType::Unknown;
+  let mut test_client = TestClient::new_user().await;
+  let uid = test_client.uid().await;
+  let workspace_id = test_client.workspace_id().await;
+  let object_id = test_client
+    .create_and_edit_collab(&workspace_id, collab_type.clone())
+    .await;
+  test_client
+    .open_collab(&workspace_id, &object_id, collab_type.clone())
+    .await;
+  for i in 0..5 {
+    test_client
+      .insert_into(&object_id, &i.to_string(), i.to_string())
+      .await;
+  }
+  test_client
+    .wait_object_sync_complete(&object_id)
+    .await
+    .unwrap();
+
+  // the edits are recorded along with the periodic persistence of the collab
+  let mut stats = None;
+  for _ in 0..30 {
+    stats = test_client
+      .api_client
+      .get_collab_edit_stats(&workspace_id, &object_id, Some(7))
+      .await
+      .unwrap();
+    if stats.is_some() {
+      break;
+    }
+    sleep(Duration::from_secs(3)).await;
+  }
+  let stats = stats.expect("edit stats should be recorded");
+  assert_eq!(stats.object_id, object_id);
+  assert!(stats.total_updates > 0);
+  assert_eq!(stats.last_edited_by, Some(uid));
+  assert_eq!(stats.daily.len(), 1);
+  assert_eq!(stats.daily[0].edit_count, stats.total_updates);
+}
+
+#[tokio::test]
+async fn recently_edited_collab_is_not_archived_test() {
+  let mut test_client = TestClient::new_user().await;
+  let workspace_id = test_client.workspace_id().await;
+  let object_id = test_client
+    .create_and_edit_collab(&workspace_id, CollabType::Document)
+    .await;
+  let status = test_client
+    .api_client
+    .get_collab_archive_status(&workspace_id, &object_id)
+    .await
+    .unwrap();
+  assert_eq!(status.object_id, object_id);
+  assert!(!status.archived);
+  assert!(status.archived_at.is_none());
+}
@@ -1,9 +1,11 @@
+use app_error::ErrorCode;
 use client_api_test::{assert_server_collab, TestClient};
 use collab::core::collab::DataSource;
 use collab::core::origin::CollabOrigin;
 use collab::entity::EncodedCollab;
 use collab::preclude::{Collab, JsonValue};
 use collab_entity::CollabType;
+use database_entity::dto::CreateCollabCheckpointParams;
 use serde_json::json;
 
 #[tokio::test]
@@ -83,3 +85,93 @@ async fn verify_snapshot_state(
   let actual = collab.to_json_value();
   assert_eq!(actual, expected);
 }
+
+#[tokio::test]
+async fn create_and_revert_checkpoint() {
+  let mut c = TestClient::new_user().await;
+
+  let wid = c.workspace_id().await;
+  let oid = c.create_and_edit_collab(&wid, CollabType::Unknown).await;
+  c.open_collab(&wid, &oid, CollabType::Unknown).await;
+  c.insert_into(&oid, "title", "t1").await;
+  c.wait_object_sync_complete(&oid).await.unwrap();
+  assert_server_collab(
+    &wid,
+    &mut c.api_client,
+    &oid,
+    &CollabType::Unknown,
+    10,
+    json!({"title": "t1"}),
+  )
+  .await
+  .unwrap();
+  let checkpoint = c
+    .api_client
+    .create_checkpoint(
+      &wid,
+      &oid,
+      CreateCollabCheckpointParams {
+        label: "v1".to_string(),
+        collab_type: CollabType::Unknown,
+      },
+    )
+    .await
+    .unwrap();
+  assert_eq!(checkpoint.label, "v1");
+  assert_eq!(checkpoint.object_id, oid);
+
+  c.insert_into(&oid, "title", "t2").await;
+  c.wait_object_sync_complete(&oid).await.unwrap();
+  assert_server_collab(
+    &wid,
+    &mut c.api_client,
+    &oid,
+    &CollabType::Unknown,
+    10,
+    json!({"title": "t2"}),
+  )
+  .await
+  .unwrap();
+
+  // the revert saves the current state as a checkpoint before applying the checkpoint content
+  let backup = c
+    .api_client
+    .revert_to_checkpoint(&wid, &oid, checkpoint.checkpoint_id, CollabType::Unknown)
+    .await
+    .unwrap();
+  assert_eq!(backup.label, "Before reverting to v1");
+  assert_server_collab(
+    &wid,
+    &mut c.api_client,
+    &oid,
+    &CollabType::Unknown,
+    10,
+    json!({"title": "t1"}),
+  )
+  .await
+  .unwrap();
+
+  let history = c.api_client.get_collab_history(&wid, &oid).await.unwrap();
+  let checkpoint_ids: Vec<i64> = history
+    .checkpoints
+    .iter()
+    .map(|checkpoint| checkpoint.checkpoint_id)
+    .collect();
+  assert_eq!(
+    checkpoint_ids,
+    vec![backup.checkpoint_id, checkpoint.checkpoint_id]
+  );
+}
+
+#[tokio::test]
+async fn revert_to_unknown_checkpoint() {
+  let mut c = TestClient::new_user().await;
+  let wid = c.workspace_id().await;
+  let oid = c.create_and_edit_collab(&wid, CollabType::Unknown).await;
+  let error = c
+    .api_client
+    .revert_to_checkpoint(&wid, &oid, i64::MAX, CollabType::Unknown)
+    .await
+    .unwrap_err();
+  assert_eq!(error.code, ErrorCode::RecordNotFound);
+}
@@ -10,12 +10,15 @@ use database::collab::CollabMetadata;
 use database_entity::dto::{
   CreateCollabParams, DeleteCollabParams, QueryCollab, QueryCollabParams, QueryCollabResult,
 };
+use futures::TryStreamExt;
 use sqlx::types::Uuid;
 use std::collections::HashMap;
 use workspace_template::document::getting_started::GettingStartedTemplate;
 use workspace_template::WorkspaceTemplateBuilder;
 
-use crate::collab::util::{redis_connection_manager, test_encode_collab_v1};
+use crate::collab::util::{
+  generate_random_string, redis_connection_manager, test_encode_collab_v1,
+};
 
 #[tokio::test]
 async fn success_insert_collab_test() {
@@ -46,6 +49,37 @@ async fn success_insert_collab_test() {
   assert_eq!(doc_state, encode_collab.doc_state);
 }
 
+#[tokio::test]
+async fn insert_collab_larger_than_payload_limit_test() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c).await;
+  let object_id = Uuid::new_v4().to_string();
+  // Random text doesn't compress well, so the body goes over the 5MB limit of the create api
+  // and is uploaded to the blob storage instead.
+  let big_text = generate_random_string(10 * 1024 * 1024);
+  let encode_collab = test_encode_collab_v1(&object_id, "text", &big_text);
+  c.create_collab(CreateCollabParams {
+    object_id: object_id.clone(),
+    collab_type: CollabType::Unknown,
+    workspace_id: workspace_id.clone(),
+    encoded_collab_v1: encode_collab.encode_to_bytes().unwrap(),
+  })
+  .await
+  .unwrap();
+
+  let doc_state = c
+    .get_collab(QueryCollabParams::new(
+      &object_id,
+      CollabType::Unknown,
+      &workspace_id,
+    ))
+    .await
+    .unwrap()
+    .encode_collab
+    .doc_state;
+  assert_eq!(doc_state, encode_collab.doc_state);
+}
+
 #[tokio::test]
 async fn success_batch_get_collab_test() {
   let (c, _user) = generate_unique_registered_user_client().await;
@@ -154,6 +188,60 @@ async fn success_part_batch_get_collab_test() {
   }
 }
 
+#[tokio::test]
+async fn success_stream_batch_get_collab_test() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c).await;
+  // more collabs than the server fetches at once, so the results span several chunks
+  let queries: Vec<_> = (0..25)
+    .map(|_| QueryCollab {
+      object_id: Uuid::new_v4().to_string(),
+      collab_type: CollabType::Unknown,
+    })
+    .collect();
+
+  let mut expected_results = HashMap::new();
+  for (index, query) in queries.iter().enumerate() {
+    let object_id = query.object_id.clone();
+    if index == 3 {
+      expected_results.insert(
+        object_id,
+        QueryCollabResult::Failed {
+          error: "Record not found".to_string(),
+        },
+      );
+      continue;
+    }
+    let encode_collab = test_encode_collab_v1(&object_id, "title", "hello world")
+      .encode_to_bytes()
+      .unwrap();
+    c.create_collab(CreateCollabParams {
+      object_id: object_id.clone(),
+      encoded_collab_v1: encode_collab.clone(),
+      collab_type: query.collab_type.clone(),
+      workspace_id: workspace_id.clone(),
+    })
+    .await
+    .unwrap();
+    expected_results.insert(
+      object_id,
+      QueryCollabResult::Success {
+        encode_collab_v1: encode_collab,
+      },
+    );
+  }
+
+  let results: HashMap<_, _> = c
+    .stream_batch_get_collab(&workspace_id, queries)
+    .await
+    .unwrap()
+    .map_ok(|item| (item.object_id, item.result))
+    .try_collect()
+    .await
+    .unwrap();
+  assert_eq!(results, expected_results);
+}
+
 #[tokio::test]
 async fn success_delete_collab_test() {
   let (c, _user) = generate_unique_registered_user_client().await;
@@ -424,3 +512,70 @@ async fn insert_folder_data_success_test() {
     test_client.api_client.create_collab(params).await.unwrap();
   }
 }
+
+#[tokio::test]
+async fn warm_up_collab_test() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c).await;
+  let mut queries = vec![];
+  for _ in 0..2 {
+    let object_id = Uuid::new_v4().to_string();
+    let encode_collab = test_encode_collab_v1(&object_id, "title", "hello world")
+      .encode_to_bytes()
+      .unwrap();
+    c.create_collab(CreateCollabParams {
+      object_id: object_id.clone(),
+      encoded_collab_v1: encode_collab,
+      collab_type: CollabType::Unknown,
+      workspace_id: workspace_id.clone(),
+    })
+    .await
+    .unwrap();
+    queries.push(QueryCollab::new(&object_id, CollabType::Unknown));
+  }
+  let missing_object_id = Uuid::new_v4().to_string();
+  queries.push(QueryCollab::new(&missing_object_id, CollabType::Unknown));
+
+  let result = c
+    .warm_up_collabs(&workspace_id, queries.clone())
+    .await
+    .unwrap();
+  let mut loaded = result.warmed.clone();
+  loaded.extend(result.cached.clone());
+  loaded.sort();
+  let mut expected = vec![queries[0].object_id.clone(), queries[1].object_id.clone()];
+  expected.sort();
+  assert_eq!(loaded, expected);
+  assert_eq!(result.skipped, vec![missing_object_id.clone()]);
+
+  // the collabs are in the memory cache after the first warm-up
+  let result = c.warm_up_collabs(&workspace_id, queries).await.unwrap();
+  assert!(result.warmed.is_empty());
+  let mut cached = result.cached;
+  cached.sort();
+  assert_eq!(cached, expected);
+  assert_eq!(result.skipped, vec![missing_object_id]);
+}
+
+#[tokio::test]
+async fn fail_warm_up_collab_with_empty_list_test() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c).await;
+  let error = c.warm_up_collabs(&workspace_id, vec![]).await.unwrap_err();
+  assert_eq!(error.code, ErrorCode::InvalidRequest);
+}
+
+#[tokio::test]
+async fn fail_warm_up_collab_of_other_workspace_test() {
+  let (owner, _user) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&owner).await;
+  let (other, _user) = generate_unique_registered_user_client().await;
+  let error = other
+    .warm_up_collabs(
+      &workspace_id,
+      vec![QueryCollab::new(&workspace_id, CollabType::Folder)],
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(error.code, ErrorCode::NotEnoughPermissions);
+}
@@ -0,0 +1,49 @@
+use app_error::ErrorCode;
+use client_api_test::{admin_user_client, generate_unique_registered_user_client};
+use shared_entity::dto::feature_flag_dto::UpsertFeatureFlagParams;
+
+#[tokio::test]
+async fn admin_upsert_and_delete_feature_flag() {
+  let admin_client = admin_user_client().await;
+  let name = format!("test_flag_{}", uuid::Uuid::new_v4().simple());
+  let flag = admin_client
+    .upsert_feature_flag(
+      &name,
+      &UpsertFeatureFlagParams {
+        enabled: true,
+        rollout_percentage: 25,
+        description: "test flag".to_string(),
+      },
+    )
+    .await
+    .unwrap();
+  assert!(flag.enabled);
+  assert_eq!(flag.rollout_percentage, 25);
+
+  let flags = admin_client.list_feature_flags().await.unwrap();
+  assert!(flags.iter().any(|flag| flag.name == name));
+
+  let err = admin_client
+    .upsert_feature_flag(
+      &name,
+      &UpsertFeatureFlagParams {
+        enabled: true,
+        rollout_percentage: 101,
+        description: String::new(),
+      },
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+
+  admin_client.delete_feature_flag(&name).await.unwrap();
+  let flags = admin_client.list_feature_flags().await.unwrap();
+  assert!(!flags.iter().any(|flag| flag.name == name));
+}
+
+#[tokio::test]
+async fn non_admin_cannot_list_feature_flags() {
+  let (client, _) = generate_unique_registered_user_client().await;
+  let err = client.list_feature_flags().await.unwrap_err();
+  assert_eq!(err.code, ErrorCode::NotEnoughPermissions);
+}
@@ -0,0 +1 @@
+mod admin_test;
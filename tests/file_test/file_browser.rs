@@ -0,0 +1,138 @@
+use client_api_test::{
+  generate_unique_registered_user_client, workspace_id_from_client, TestClient,
+};
+use database_entity::dto::AFRole;
+use shared_entity::dto::workspace_dto::ListWorkspaceFilesQuery;
+
+#[tokio::test]
+async fn list_and_bulk_delete_workspace_files_test() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let user_uuid = c.get_profile().await.unwrap().uuid;
+  let workspace_id = workspace_id_from_client(&c).await;
+  let parent_dir = uuid::Uuid::new_v4().to_string();
+  let mime = mime::TEXT_PLAIN_UTF_8;
+  let mut file_ids = vec![];
+  for data in ["1", "22", "333"] {
+    let resp = c
+      .put_blob_v1(&workspace_id, &parent_dir, data, &mime)
+      .await
+      .unwrap();
+    file_ids.push(format!("{}_{}", parent_dir, resp.file_id));
+  }
+  file_ids.sort();
+
+  // list the files of the object page by page
+  let mut listed = vec![];
+  let mut query = ListWorkspaceFilesQuery {
+    prefix: Some(parent_dir.clone()),
+    cursor: None,
+    limit: Some(2),
+    quarantined: None,
+  };
+  loop {
+    let page = c.list_workspace_files(&workspace_id, &query).await.unwrap();
+    listed.extend(page.files);
+    match page.next_cursor {
+      Some(cursor) => query.cursor = Some(cursor),
+      None => break,
+    }
+  }
+  assert_eq!(
+    listed.iter().map(|f| f.file_id.clone()).collect::<Vec<_>>(),
+    file_ids
+  );
+  for file in &listed {
+    assert_eq!(file.uploaded_by, Some(user_uuid));
+    assert_eq!(file.object_id.as_deref(), Some(parent_dir.as_str()));
+    assert_eq!(file.file_type, mime.to_string());
+  }
+
+  let deleted = c
+    .delete_workspace_files(
+      &workspace_id,
+      vec![
+        file_ids[0].clone(),
+        file_ids[1].clone(),
+        "unknown".to_string(),
+      ],
+    )
+    .await
+    .unwrap();
+  assert_eq!(deleted.file_ids.len(), 2);
+  let remaining = c
+    .list_workspace_files(
+      &workspace_id,
+      &ListWorkspaceFilesQuery {
+        prefix: Some(parent_dir.clone()),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+  assert_eq!(remaining.files.len(), 1);
+  assert_eq!(remaining.files[0].file_id, file_ids[2]);
+  assert_eq!(
+    deleted.freed_bytes + remaining.files[0].file_size,
+    listed.iter().map(|f| f.file_size).sum::<i64>()
+  );
+}
+
+#[tokio::test]
+async fn guest_upload_is_quarantined_until_approved_test() {
+  let owner = TestClient::new_user_without_ws_conn().await;
+  let guest = TestClient::new_user_without_ws_conn().await;
+  let workspace_id = owner.workspace_id().await;
+  owner
+    .invite_and_accepted_workspace_member(&workspace_id, &guest, AFRole::Guest)
+    .await
+    .unwrap();
+
+  let parent_dir = uuid::Uuid::new_v4().to_string();
+  let resp = guest
+    .api_client
+    .put_blob_v1(&workspace_id, &parent_dir, "hello", &mime::TEXT_PLAIN_UTF_8)
+    .await
+    .unwrap();
+  let file_id = format!("{}_{}", parent_dir, resp.file_id);
+
+  // the file can't be downloaded before a member approves it
+  assert!(owner
+    .api_client
+    .get_blob_v1(&workspace_id, &parent_dir, &resp.file_id)
+    .await
+    .is_err());
+  let quarantined = owner
+    .api_client
+    .list_workspace_files(
+      &workspace_id,
+      &ListWorkspaceFilesQuery {
+        prefix: Some(parent_dir.clone()),
+        quarantined: Some(true),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+  assert_eq!(quarantined.files.len(), 1);
+  assert_eq!(quarantined.files[0].file_id, file_id);
+  assert!(quarantined.files[0].quarantined);
+
+  // guests can't approve the files
+  assert!(guest
+    .api_client
+    .approve_workspace_files(&workspace_id, vec![file_id.clone()])
+    .await
+    .is_err());
+  let approved = owner
+    .api_client
+    .approve_workspace_files(&workspace_id, vec![file_id.clone(), "unknown".to_string()])
+    .await
+    .unwrap();
+  assert_eq!(approved.file_ids, vec![file_id]);
+  let (_, data) = owner
+    .api_client
+    .get_blob_v1(&workspace_id, &parent_dir, &resp.file_id)
+    .await
+    .unwrap();
+  assert_eq!(data, b"hello");
+}
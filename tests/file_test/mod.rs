@@ -2,7 +2,9 @@ use std::borrow::Cow;
 use std::ops::Deref;
 
 mod delete_dir_test;
+mod file_browser;
 mod multiple_part_test;
+mod prefix_isolation;
 mod put_and_get;
 mod usage;
 
@@ -0,0 +1,80 @@
+use app_error::ErrorCode;
+use client_api_test::{admin_user_client, generate_unique_registered_user_client, TestClient};
+use collab_entity::CollabType;
+use database_entity::dto::CreateCollabParams;
+use shared_entity::dto::file_dto::StoragePrefixAuditQuery;
+use uuid::Uuid;
+
+use crate::collab::util::test_encode_collab_v1;
+
+#[tokio::test]
+async fn put_blob_with_parent_dir_escaping_workspace_prefix_test() {
+  let test_client = TestClient::new_user().await;
+  let workspace_id = test_client.workspace_id().await;
+  let err = test_client
+    .api_client
+    .put_blob_v1(
+      &workspace_id,
+      "dir%5C..",
+      "hello world",
+      &mime::TEXT_PLAIN_UTF_8,
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+}
+
+#[tokio::test]
+async fn audit_cross_workspace_blob_references_test() {
+  let test_client = TestClient::new_user().await;
+  let workspace_id = test_client.workspace_id().await;
+  let other_workspace_id = Uuid::new_v4();
+  let object_id = Uuid::new_v4().to_string();
+  let url = format!(
+    "https://appflowy.cloud/api/file_storage/{}/v1/blob/dir/file.png",
+    other_workspace_id
+  );
+  test_client
+    .api_client
+    .create_collab(CreateCollabParams {
+      object_id: object_id.clone(),
+      encoded_collab_v1: test_encode_collab_v1(&object_id, "image", &url)
+        .encode_to_bytes()
+        .unwrap(),
+      collab_type: CollabType::Unknown,
+      workspace_id: workspace_id.clone(),
+    })
+    .await
+    .unwrap();
+
+  let admin_client = admin_user_client().await;
+  let report = admin_client
+    .audit_workspace_storage_prefix(&workspace_id, &StoragePrefixAuditQuery::default())
+    .await
+    .unwrap();
+  assert!(report.invalid_blob_keys.is_empty());
+  let reference = report
+    .cross_workspace_references
+    .iter()
+    .find(|reference| reference.object_id == object_id)
+    .unwrap();
+  assert_eq!(
+    reference.referenced_workspace_id,
+    other_workspace_id.to_string()
+  );
+  assert_eq!(
+    reference.url_path,
+    format!(
+      "/api/file_storage/{}/v1/blob/dir/file.png",
+      other_workspace_id
+    )
+  );
+
+  // only the administrators can audit the storage
+  let (client, _) = generate_unique_registered_user_client().await;
+  let err = client
+    .audit_workspace_storage_prefix(&workspace_id, &StoragePrefixAuditQuery::default())
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::NotEnoughPermissions);
+}
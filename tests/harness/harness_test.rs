@@ -0,0 +1,31 @@
+use appflowy_cloud::test_harness::TestHarness;
+use aws_sdk_s3::primitives::ByteStream;
+use database::file::BucketClient;
+use reqwest::StatusCode;
+
+#[actix_rt::test]
+async fn harness_serves_created_user_test() {
+  let harness = TestHarness::start().await.unwrap();
+  let user = harness.create_user().await.unwrap();
+
+  let resp = reqwest::Client::new()
+    .get(format!("{}/api/workspace", harness.base_url()))
+    .bearer_auth(&user.access_token)
+    .send()
+    .await
+    .unwrap();
+  assert_eq!(resp.status(), StatusCode::OK);
+  let body = resp.text().await.unwrap();
+  assert!(body.contains(&user.workspace_id.to_string()));
+
+  let object_key = format!("{}/harness/blob", user.workspace_id);
+  harness
+    .state
+    .bucket_client
+    .put_blob(&object_key, ByteStream::from(b"blob".to_vec()), None)
+    .await
+    .unwrap();
+  assert!(harness.blob_storage().contains(&object_key));
+
+  harness.shutdown().await.unwrap();
+}
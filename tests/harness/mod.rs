@@ -0,0 +1 @@
+mod harness_test;
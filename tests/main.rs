@@ -1,8 +1,11 @@
 mod ai_test;
 mod collab;
 mod collab_history;
+mod feature_flag;
 mod file_test;
 mod gotrue;
+#[cfg(feature = "test-harness")]
+mod harness;
 mod search;
 mod server_info;
 mod sql_test;
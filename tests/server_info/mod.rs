@@ -1 +1,2 @@
 mod info;
+mod retry_policy;
@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use client_api::{Client, ClientConfiguration, RetryPolicy};
+use client_api_test::LOCALHOST_GOTRUE;
+use shared_entity::response::ErrorCode;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn circuit_breaker_opens_after_consecutive_failures() {
+  let retry_policy = RetryPolicy::default()
+    .with_max_retries(1)
+    .with_backoff(Duration::from_millis(10), Duration::from_millis(10))
+    .with_circuit_breaker(2, Duration::from_secs(60));
+  // Nothing listens on port 1
+  let client = Client::new(
+    "http://localhost:1",
+    "ws://localhost:1/ws/v2",
+    &LOCALHOST_GOTRUE,
+    &Uuid::new_v4().to_string(),
+    ClientConfiguration::default().with_retry_policy(retry_policy),
+    "0.7.0",
+  );
+
+  // The request and its retry fail to connect, which opens the circuit
+  let err = client
+    .get_published_collab_info(&Uuid::new_v4())
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::NetworkError, "{:?}", err);
+
+  // The next request fails without reaching the network
+  let err = client
+    .get_published_collab_info(&Uuid::new_v4())
+    .await
+    .unwrap_err();
+  assert_eq!(
+    err.code,
+    ErrorCode::ServiceTemporaryUnavailable,
+    "{:?}",
+    err
+  );
+}
@@ -0,0 +1,63 @@
+use crate::sql_test::util::{setup_db, test_create_user};
+use access_control::collab_lock::CollabLockCache;
+use app_error::AppError;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[sqlx::test(migrations = false)]
+async fn collab_lock_and_unlock_test(pool: PgPool) {
+  setup_db(&pool).await.unwrap();
+  let mut workspace_ids = vec![];
+  for _ in 0..2 {
+    let user_uuid = Uuid::new_v4();
+    let name = user_uuid.to_string();
+    let email = format!("{}@appflowy.io", name);
+    let user = test_create_user(&pool, user_uuid, &email, &name)
+      .await
+      .unwrap();
+    workspace_ids.push((user.uid, Uuid::parse_str(&user.workspace_id).unwrap()));
+  }
+  let (uid, workspace_id) = workspace_ids[0];
+  let (other_uid, other_workspace_id) = workspace_ids[1];
+  let object_id = Uuid::new_v4().to_string();
+
+  let cache = CollabLockCache::new(pool.clone());
+  let lock = cache
+    .lock(&workspace_id, &object_id, uid, Some("review"))
+    .await
+    .unwrap();
+  assert_eq!(lock.locked_by, uid);
+  assert_eq!(lock.reason.as_deref(), Some("review"));
+  assert!(matches!(
+    cache.enforce_unlocked(&workspace_id, &object_id).await,
+    Err(AppError::NotEnoughPermissions)
+  ));
+
+  // another server instance reads the lock from Postgres
+  let other_cache = CollabLockCache::new(pool.clone());
+  assert!(other_cache
+    .is_locked(&workspace_id, &object_id)
+    .await
+    .unwrap());
+  // the lock is scoped to its workspace
+  assert!(!other_cache
+    .is_locked(&other_workspace_id, &object_id)
+    .await
+    .unwrap());
+  assert!(cache
+    .lock(&other_workspace_id, &object_id, other_uid, None)
+    .await
+    .is_err());
+  cache.unlock(&other_workspace_id, &object_id).await.unwrap();
+  assert!(CollabLockCache::new(pool.clone())
+    .is_locked(&workspace_id, &object_id)
+    .await
+    .unwrap());
+
+  cache.unlock(&workspace_id, &object_id).await.unwrap();
+  assert!(!cache.is_locked(&workspace_id, &object_id).await.unwrap());
+  assert!(!CollabLockCache::new(pool.clone())
+    .is_locked(&workspace_id, &object_id)
+    .await
+    .unwrap());
+}
@@ -0,0 +1,161 @@
+use std::time::{Duration, Instant};
+
+use collab_entity::CollabType;
+use database::collab::{
+  batch_select_collab_blob, insert_into_af_collab_bulk_for_user, select_blob_from_af_collab,
+  select_blob_from_af_collab_in_workspace,
+};
+use database_entity::dto::{CollabParams, QueryCollab, QueryCollabResult};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use tokio::task::JoinSet;
+use uuid::Uuid;
+
+use crate::sql_test::util::{generate_random_bytes, setup_db, test_create_user};
+
+const WORKSPACE_COUNT: usize = 16;
+const COLLABS_PER_WORKSPACE: usize = 200;
+const READ_COUNT: usize = 2000;
+const CONCURRENCY: usize = 16;
+/// Bound of the p99 latency of a single collab read. Loose enough for a shared CI database, a
+/// read scanning every partition of af_collab instead of using the indexes exceeds it.
+const MAX_P99_READ_LATENCY: Duration = Duration::from_millis(500);
+
+/// Collabs spread over workspaces falling into the different hash partitions of af_collab.
+async fn insert_collabs(pool: &PgPool) -> Vec<(Uuid, String)> {
+  let mut collabs = vec![];
+  for i in 0..WORKSPACE_COUNT {
+    let user = test_create_user(
+      pool,
+      Uuid::new_v4(),
+      &format!("load_test_{}@appflowy.io", i),
+      "load_test_user",
+    )
+    .await
+    .unwrap();
+    let params_list = (0..COLLABS_PER_WORKSPACE)
+      .map(|_| CollabParams {
+        object_id: Uuid::new_v4().to_string(),
+        collab_type: CollabType::Document,
+        encoded_collab_v1: generate_random_bytes(2048).into(),
+      })
+      .collect::<Vec<_>>();
+    let mut txn = pool.begin().await.unwrap();
+    insert_into_af_collab_bulk_for_user(&mut txn, &user.uid, &user.workspace_id, &params_list)
+      .await
+      .unwrap();
+    txn.commit().await.unwrap();
+
+    let workspace_id = Uuid::parse_str(&user.workspace_id).unwrap();
+    collabs.extend(
+      params_list
+        .into_iter()
+        .map(|params| (workspace_id, params.object_id)),
+    );
+  }
+  sqlx::query("ANALYZE af_collab")
+    .execute(pool)
+    .await
+    .unwrap();
+  collabs
+}
+
+/// Runs `READ_COUNT` reads with `CONCURRENCY` concurrent readers and returns the p50 and p99
+/// latencies.
+async fn measure<F, Fut>(collabs: &[(Uuid, String)], read: F) -> (Duration, Duration)
+where
+  F: Fn(Uuid, String) -> Fut + Clone + Send + 'static,
+  Fut: std::future::Future<Output = ()> + Send,
+{
+  let mut latencies = Vec::with_capacity(READ_COUNT);
+  for chunk in (0..READ_COUNT).collect::<Vec<_>>().chunks(CONCURRENCY) {
+    let mut tasks = JoinSet::new();
+    for i in chunk {
+      let (workspace_id, object_id) = collabs[(i * 7919) % collabs.len()].clone();
+      let read = read.clone();
+      tasks.spawn(async move {
+        let start = Instant::now();
+        read(workspace_id, object_id).await;
+        start.elapsed()
+      });
+    }
+    while let Some(latency) = tasks.join_next().await {
+      latencies.push(latency.unwrap());
+    }
+  }
+  latencies.sort();
+  (
+    latencies[latencies.len() / 2],
+    latencies[latencies.len() * 99 / 100],
+  )
+}
+
+#[sqlx::test(migrations = false)]
+async fn collab_blob_read_latency_test(pool: PgPool) {
+  setup_db(&pool).await.unwrap();
+  let collabs = insert_collabs(&pool).await;
+
+  let all_partitions_pool = pool.clone();
+  let (all_partitions_p50, all_partitions_p99) =
+    measure(&collabs, move |_workspace_id, object_id| {
+      let pool = all_partitions_pool.clone();
+      async move {
+        select_blob_from_af_collab(&pool, &CollabType::Document, &object_id)
+          .await
+          .unwrap();
+      }
+    })
+    .await;
+
+  let workspace_pool = pool.clone();
+  let (workspace_p50, workspace_p99) = measure(&collabs, move |workspace_id, object_id| {
+    let pool = workspace_pool.clone();
+    async move {
+      select_blob_from_af_collab_in_workspace(
+        &pool,
+        &workspace_id,
+        &CollabType::Document,
+        &object_id,
+      )
+      .await
+      .unwrap();
+    }
+  })
+  .await;
+
+  assert!(
+    all_partitions_p99 < MAX_P99_READ_LATENCY,
+    "read collab by object id: p50 {:?}, p99 {:?}",
+    all_partitions_p50,
+    all_partitions_p99
+  );
+  assert!(
+    workspace_p99 < MAX_P99_READ_LATENCY,
+    "read collab by workspace and object id: p50 {:?}, p99 {:?}",
+    workspace_p50,
+    workspace_p99
+  );
+
+  // the batch read of a workspace only returns the collabs of that workspace
+  let (workspace_id, object_id) = collabs[0].clone();
+  let (_, other_object_id) = collabs[COLLABS_PER_WORKSPACE].clone();
+  let mut results = HashMap::new();
+  batch_select_collab_blob(
+    &pool,
+    &workspace_id,
+    vec![
+      QueryCollab::new(object_id.clone(), CollabType::Document),
+      QueryCollab::new(other_object_id.clone(), CollabType::Document),
+    ],
+    &mut results,
+  )
+  .await;
+  assert!(matches!(
+    results.get(&object_id),
+    Some(QueryCollabResult::Success { .. })
+  ));
+  assert!(matches!(
+    results.get(&other_object_id),
+    Some(QueryCollabResult::Failed { .. })
+  ));
+}
@@ -0,0 +1,107 @@
+use crate::sql_test::util::{setup_db, test_create_user};
+use collab_entity::CollabType;
+use database::history::compaction::compact_workspace_history;
+use database::history::ops::{get_snapshot_meta_list, insert_history};
+use sqlx::PgPool;
+use tonic_proto::history::SnapshotMetaPb;
+use uuid::Uuid;
+
+const DAY: i64 = 86400;
+
+#[sqlx::test(migrations = false)]
+async fn compact_history_keeps_one_version_per_tier_bucket_test(pool: PgPool) {
+  setup_db(&pool).await.unwrap();
+  let user_uuid = Uuid::new_v4();
+  let name = user_uuid.to_string();
+  let email = format!("{}@appflowy.io", name);
+  let user = test_create_user(&pool, user_uuid, &email, &name)
+    .await
+    .unwrap();
+  let workspace_id = Uuid::parse_str(&user.workspace_id).unwrap();
+  let object_id = Uuid::new_v4().to_string();
+  let collab_type = CollabType::Document;
+
+  let now = 1_700_006_400;
+  let week_start = (now - 60 * DAY) / (7 * DAY) * (7 * DAY);
+  let day_start = (now - 10 * DAY) / DAY * DAY;
+  let hour_start = (now - 7200) / 3600 * 3600;
+  let meta = |created_at: i64| SnapshotMetaPb {
+    oid: object_id.clone(),
+    snapshot: vec![1, 2, 3],
+    snapshot_version: 1,
+    created_at,
+  };
+
+  // two versions in the same week, restored from the state created right after them
+  insert_history(
+    &workspace_id,
+    &object_id,
+    vec![1; 10],
+    1,
+    None,
+    collab_type.clone(),
+    week_start + 30,
+    vec![meta(week_start + 10), meta(week_start + 20)],
+    pool.clone(),
+  )
+  .await
+  .unwrap();
+  // a state that no version needs
+  insert_history(
+    &workspace_id,
+    &object_id,
+    vec![2; 20],
+    1,
+    None,
+    collab_type.clone(),
+    now - 35 * DAY,
+    vec![],
+    pool.clone(),
+  )
+  .await
+  .unwrap();
+  // two versions in the same day and two in the same hour
+  insert_history(
+    &workspace_id,
+    &object_id,
+    vec![3; 30],
+    1,
+    None,
+    collab_type.clone(),
+    now,
+    vec![
+      meta(day_start + 10),
+      meta(day_start + 20),
+      meta(hour_start + 10),
+      meta(hour_start + 20),
+    ],
+    pool.clone(),
+  )
+  .await
+  .unwrap();
+
+  let result = compact_workspace_history(&pool, &workspace_id, 7, 30, now)
+    .await
+    .unwrap();
+  assert_eq!(result.deleted_versions, 3);
+  assert_eq!(result.deleted_states, 1);
+  assert_eq!(result.reclaimed_bytes, 3 * 3 + 20);
+
+  let versions = get_snapshot_meta_list(&object_id, &collab_type, &pool)
+    .await
+    .unwrap()
+    .into_iter()
+    .map(|meta| meta.created_at)
+    .collect::<Vec<_>>();
+  assert_eq!(
+    versions,
+    vec![hour_start + 20, day_start + 20, week_start + 20]
+  );
+
+  // compacting again doesn't delete anything
+  let result = compact_workspace_history(&pool, &workspace_id, 7, 30, now)
+    .await
+    .unwrap();
+  assert_eq!(result.deleted_versions, 0);
+  assert_eq!(result.deleted_states, 0);
+}
@@ -1,4 +1,9 @@
 mod chat_test;
+mod collab_lock_test;
+mod collab_read_load_test;
+mod history_compaction_test;
 mod history_test;
+mod publish_domain_test;
 pub(crate) mod util;
+mod workspace_storage_test;
 mod workspace_test;
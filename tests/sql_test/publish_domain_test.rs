@@ -0,0 +1,110 @@
+use crate::sql_test::util::{setup_db, test_create_user};
+use chrono::{Duration, Utc};
+use database::publish::{
+  delete_expired_custom_domain_claims, delete_pending_custom_domain_claims,
+  insert_workspace_custom_domain, select_custom_domains_to_recheck, select_workspace_custom_domain,
+  select_workspace_id_for_verified_domain, update_custom_domain_checked,
+  update_custom_domain_verified,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[sqlx::test(migrations = false)]
+async fn custom_domain_claims_test(pool: PgPool) {
+  setup_db(&pool).await.unwrap();
+  let mut workspace_ids = vec![];
+  for _ in 0..3 {
+    let user_uuid = Uuid::new_v4();
+    let name = user_uuid.to_string();
+    let email = format!("{}@appflowy.io", name);
+    let user = test_create_user(&pool, user_uuid, &email, &name)
+      .await
+      .unwrap();
+    workspace_ids.push(Uuid::parse_str(&user.workspace_id).unwrap());
+  }
+  let domain = format!("{}.example.com", Uuid::new_v4());
+
+  // the claims of several workspaces coexist until one is verified
+  for (i, workspace_id) in workspace_ids.iter().take(2).enumerate() {
+    insert_workspace_custom_domain(&pool, workspace_id, &domain, &format!("token{}", i))
+      .await
+      .unwrap();
+  }
+  assert!(select_workspace_id_for_verified_domain(&pool, &domain)
+    .await
+    .unwrap()
+    .is_none());
+
+  let mut txn = pool.begin().await.unwrap();
+  let row = update_custom_domain_verified(txn.as_mut(), &workspace_ids[1], &domain)
+    .await
+    .unwrap();
+  assert!(row.verified_at.is_some());
+  assert_eq!(
+    delete_pending_custom_domain_claims(txn.as_mut(), &domain)
+      .await
+      .unwrap(),
+    1
+  );
+  txn.commit().await.unwrap();
+  assert_eq!(
+    select_workspace_id_for_verified_domain(&pool, &domain)
+      .await
+      .unwrap(),
+    Some(workspace_ids[1])
+  );
+  assert!(
+    select_workspace_custom_domain(&pool, &workspace_ids[0], &domain)
+      .await
+      .unwrap()
+      .is_none()
+  );
+
+  // only one workspace can have the domain verified
+  insert_workspace_custom_domain(&pool, &workspace_ids[2], &domain, "token2")
+    .await
+    .unwrap();
+  assert!(
+    update_custom_domain_verified(&pool, &workspace_ids[2], &domain)
+      .await
+      .is_err()
+  );
+
+  // the verified domain is checked again, and released when its TXT record is gone
+  let rows = select_custom_domains_to_recheck(&pool, Utc::now() + Duration::seconds(1), 100)
+    .await
+    .unwrap();
+  assert!(rows.iter().any(|row| row.domain == domain));
+  assert!(
+    select_custom_domains_to_recheck(&pool, Utc::now() - Duration::hours(1), 100)
+      .await
+      .unwrap()
+      .iter()
+      .all(|row| row.domain != domain)
+  );
+  update_custom_domain_checked(&pool, &workspace_ids[1], &domain, true)
+    .await
+    .unwrap();
+  assert!(select_workspace_id_for_verified_domain(&pool, &domain)
+    .await
+    .unwrap()
+    .is_some());
+  update_custom_domain_checked(&pool, &workspace_ids[1], &domain, false)
+    .await
+    .unwrap();
+  assert!(select_workspace_id_for_verified_domain(&pool, &domain)
+    .await
+    .unwrap()
+    .is_none());
+
+  // the pending claims expire
+  delete_expired_custom_domain_claims(&pool, Utc::now() + Duration::seconds(1))
+    .await
+    .unwrap();
+  for workspace_id in &workspace_ids {
+    assert!(select_workspace_custom_domain(&pool, workspace_id, &domain)
+      .await
+      .unwrap()
+      .is_none());
+  }
+}
@@ -0,0 +1,86 @@
+use crate::sql_test::util::{setup_db, test_create_user};
+use chrono::Utc;
+use database::workspace_storage::{
+  complete_workspace_storage_relocation, queue_workspace_storage_relocation,
+  select_workspace_storage, select_workspace_storage_relocations, switch_workspace_storage,
+  update_workspace_storage_relocation_error, WorkspaceStorageRelocationStatus,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[sqlx::test(migrations = false)]
+async fn workspace_storage_relocation_test(pool: PgPool) {
+  setup_db(&pool).await.unwrap();
+  let user_uuid = Uuid::new_v4();
+  let name = user_uuid.to_string();
+  let email = format!("{}@appflowy.io", name);
+  let user = test_create_user(&pool, user_uuid, &email, &name)
+    .await
+    .unwrap();
+  let workspace_id = Uuid::parse_str(&user.workspace_id).unwrap();
+  let status = |pool: PgPool| async move {
+    let row = select_workspace_storage(&pool, &workspace_id)
+      .await
+      .unwrap()
+      .unwrap();
+    (
+      WorkspaceStorageRelocationStatus::from(row.relocation_status),
+      row,
+    )
+  };
+
+  assert!(
+    queue_workspace_storage_relocation(&pool, &workspace_id, Some(b"first"))
+      .await
+      .unwrap()
+  );
+  let (state, _) = status(pool.clone()).await;
+  assert_eq!(state, WorkspaceStorageRelocationStatus::Copying);
+  // only one relocation at a time
+  assert!(
+    !queue_workspace_storage_relocation(&pool, &workspace_id, Some(b"second"))
+      .await
+      .unwrap()
+  );
+
+  switch_workspace_storage(&pool, &workspace_id)
+    .await
+    .unwrap();
+  let (state, row) = status(pool.clone()).await;
+  assert_eq!(state, WorkspaceStorageRelocationStatus::Switched);
+  assert_eq!(row.settings.as_deref(), Some(&b"first"[..]));
+  assert_eq!(row.previous_settings, None);
+  let pending = select_workspace_storage_relocations(&pool, Utc::now(), 10)
+    .await
+    .unwrap();
+  assert!(pending.iter().any(|row| row.workspace_id == workspace_id));
+
+  // a failure once switched keeps the relocation going so the previous storage gets emptied
+  update_workspace_storage_relocation_error(&pool, &workspace_id, "unreachable")
+    .await
+    .unwrap();
+  let (state, row) = status(pool.clone()).await;
+  assert_eq!(state, WorkspaceStorageRelocationStatus::Switched);
+  assert_eq!(row.relocation_error.as_deref(), Some("unreachable"));
+
+  complete_workspace_storage_relocation(&pool, &workspace_id)
+    .await
+    .unwrap();
+  let (state, row) = status(pool.clone()).await;
+  assert_eq!(state, WorkspaceStorageRelocationStatus::Idle);
+  assert_eq!(row.settings.as_deref(), Some(&b"first"[..]));
+
+  // a failure while copying gives up, the workspace stays in its current storage
+  assert!(
+    queue_workspace_storage_relocation(&pool, &workspace_id, None)
+      .await
+      .unwrap()
+  );
+  update_workspace_storage_relocation_error(&pool, &workspace_id, "denied")
+    .await
+    .unwrap();
+  let (state, row) = status(pool.clone()).await;
+  assert_eq!(state, WorkspaceStorageRelocationStatus::Failed);
+  assert_eq!(row.settings.as_deref(), Some(&b"first"[..]));
+  assert_eq!(row.target_settings, None);
+}
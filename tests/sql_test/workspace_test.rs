@@ -2,8 +2,8 @@ use crate::sql_test::util::{generate_random_bytes, setup_db, test_create_user};
 
 use collab_entity::CollabType;
 use database::collab::{
-  insert_into_af_collab, insert_into_af_collab_bulk_for_user, select_blob_from_af_collab,
-  select_collab_meta_from_af_collab,
+  compress_stored_collab, insert_into_af_collab, insert_into_af_collab_bulk_for_user,
+  select_blob_from_af_collab, select_collab_meta_from_af_collab, select_uncompressed_collabs,
 };
 use database_entity::dto::CollabParams;
 use sqlx::PgPool;
@@ -189,6 +189,81 @@ async fn test_bulk_insert_duplicate_oid_partition_key(pool: PgPool) {
   assert_eq!(data, encoded_collab_v1); // should equal the data that insert first time
 }
 
+#[sqlx::test(migrations = false)]
+async fn compress_collab_blob_sql_test(pool: PgPool) {
+  setup_db(&pool).await.unwrap();
+
+  let user_uuid = uuid::Uuid::new_v4();
+  let user = test_create_user(&pool, user_uuid, "test@appflowy.io", "test_user")
+    .await
+    .unwrap();
+
+  let object_id = uuid::Uuid::new_v4().to_string();
+  let encoded_collab_v1 = b"compressible collab ".repeat(500);
+  let mut txn = pool.begin().await.unwrap();
+  insert_into_af_collab(
+    &mut txn,
+    &user.uid,
+    &user.workspace_id,
+    &CollabParams {
+      object_id: object_id.clone(),
+      collab_type: CollabType::Unknown,
+      encoded_collab_v1: encoded_collab_v1.clone().into(),
+    },
+  )
+  .await
+  .unwrap();
+  txn.commit().await.unwrap();
+
+  let (stored_len, len, compressed): (i32, i32, bool) =
+    sqlx::query_as("SELECT octet_length(blob), len, compressed FROM af_collab WHERE oid = $1")
+      .bind(&object_id)
+      .fetch_one(&pool)
+      .await
+      .unwrap();
+  assert!(compressed);
+  assert!((stored_len as usize) < encoded_collab_v1.len());
+  assert_eq!(len as usize, encoded_collab_v1.len());
+  let data = select_blob_from_af_collab(&pool, &CollabType::Unknown, &object_id)
+    .await
+    .unwrap();
+  assert_eq!(data, encoded_collab_v1);
+
+  // a blob written before the compression, compressed in the background
+  sqlx::query("UPDATE af_collab SET blob = $2, compressed = FALSE WHERE oid = $1")
+    .bind(&object_id)
+    .bind(&encoded_collab_v1)
+    .execute(&pool)
+    .await
+    .unwrap();
+  let updated_at: chrono::DateTime<chrono::Utc> =
+    sqlx::query_scalar("SELECT updated_at FROM af_collab WHERE oid = $1")
+      .bind(&object_id)
+      .fetch_one(&pool)
+      .await
+      .unwrap();
+  let collabs = select_uncompressed_collabs(&pool, None, 10).await.unwrap();
+  assert_eq!(collabs.len(), 1);
+  assert!(compress_stored_collab(&pool, &collabs[0]).await.unwrap());
+  assert!(select_uncompressed_collabs(&pool, None, 10)
+    .await
+    .unwrap()
+    .is_empty());
+
+  let (compressed, compressed_updated_at): (bool, chrono::DateTime<chrono::Utc>) =
+    sqlx::query_as("SELECT compressed, updated_at FROM af_collab WHERE oid = $1")
+      .bind(&object_id)
+      .fetch_one(&pool)
+      .await
+      .unwrap();
+  assert!(compressed);
+  assert_eq!(compressed_updated_at, updated_at);
+  let data = select_blob_from_af_collab(&pool, &CollabType::Unknown, &object_id)
+    .await
+    .unwrap();
+  assert_eq!(data, encoded_collab_v1);
+}
+
 #[sqlx::test(migrations = false)]
 async fn test_batch_insert_comparison(pool: PgPool) {
   setup_db(&pool).await.unwrap();
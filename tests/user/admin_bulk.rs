@@ -0,0 +1,64 @@
+use app_error::ErrorCode;
+use client_api_test::*;
+
+#[tokio::test]
+async fn admin_bulk_deactivate_and_delete_users() {
+  let admin_client = admin_user_client().await;
+  let (client, user) = generate_unique_registered_user_client().await;
+  let user_uuid = client.get_profile().await.unwrap().uuid;
+
+  let csv = admin_client.admin_export_users_csv().await.unwrap();
+  let line = csv
+    .lines()
+    .find(|line| line.contains(&user_uuid.to_string()))
+    .unwrap();
+  // the user owns the default workspace
+  assert!(line.ends_with(",1,1"), "unexpected csv line: {}", line);
+
+  let result = admin_client
+    .admin_deactivate_users(vec![user_uuid])
+    .await
+    .unwrap();
+  assert_eq!(result.succeeded, vec![user_uuid]);
+  assert!(result.failed.is_empty());
+  // a deactivated user can not sign in anymore
+  assert!(localhost_client()
+    .sign_in_password(&user.email, &user.password)
+    .await
+    .is_err());
+
+  let result = admin_client
+    .admin_delete_users(vec![user_uuid])
+    .await
+    .unwrap();
+  assert_eq!(result.succeeded, vec![user_uuid]);
+  let users = admin_client
+    .admin_list_users(Some(&user.email))
+    .await
+    .unwrap();
+  assert!(!users.iter().any(|u| u.email == user.email));
+
+  let logs = admin_client
+    .admin_list_audit_logs(None, Some(100))
+    .await
+    .unwrap();
+  let target = user_uuid.to_string();
+  assert!(logs
+    .iter()
+    .any(|log| log.action == "deactivate_user" && log.target == target && log.succeeded));
+  assert!(logs
+    .iter()
+    .any(|log| log.action == "delete_user" && log.target == target && log.succeeded));
+}
+
+#[tokio::test]
+async fn non_admin_cannot_bulk_delete_users() {
+  let (client, _) = generate_unique_registered_user_client().await;
+  let (other_client, _) = generate_unique_registered_user_client().await;
+  let other_uuid = other_client.get_profile().await.unwrap().uuid;
+  let err = client
+    .admin_delete_users(vec![other_uuid])
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::NotEnoughPermissions);
+}
@@ -0,0 +1,45 @@
+use app_error::ErrorCode;
+use client_api_test::*;
+
+#[tokio::test]
+async fn magic_link_is_not_sent_twice_in_a_row() {
+  let c = localhost_client();
+  let email = generate_unique_email();
+  c.sign_in_with_magic_link(&email, None).await.unwrap();
+  let err = c.sign_in_with_magic_link(&email, None).await.unwrap_err();
+  assert_eq!(err.code, ErrorCode::TooManyRequests, "{:?}", err);
+  // the case of the address doesn't matter
+  let err = c
+    .sign_in_with_magic_link(&email.to_uppercase(), None)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::TooManyRequests, "{:?}", err);
+
+  let err = c
+    .sign_in_with_magic_link("not an email", None)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidEmail, "{:?}", err);
+}
+
+#[tokio::test]
+async fn send_password_recovery_test() {
+  let (c, user) = generate_unique_registered_user_client().await;
+  c.send_password_recovery(&user.email, None).await.unwrap();
+}
+
+#[tokio::test]
+async fn change_email_to_email_of_another_user() {
+  let (c, user) = generate_unique_registered_user_client().await;
+  let (_, other_user) = generate_unique_registered_user_client().await;
+  let err = c.change_email(&other_user.email).await.unwrap_err();
+  assert_eq!(err.code, ErrorCode::UserAlreadyRegistered, "{:?}", err);
+
+  let err = c.change_email(&user.email).await.unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest, "{:?}", err);
+
+  c.change_email(&generate_unique_email()).await.unwrap();
+  // The email only changes once the confirmation link is opened.
+  let profile = c.get_profile().await.unwrap();
+  assert_eq!(profile.email.as_deref(), Some(user.email.as_str()));
+}
@@ -1,4 +1,7 @@
+mod admin_bulk;
 mod delete;
+mod email;
+mod notification_preferences;
 mod refresh;
 mod sign_in;
 mod sign_out;
@@ -0,0 +1,85 @@
+use app_error::ErrorCode;
+use client_api_test::*;
+use shared_entity::dto::notification_dto::{
+  DigestFrequency, NotificationPreferences, PushPlatform, RegisterPushDeviceParams,
+  UpdateNotificationPreferencesParams,
+};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn workspace_notification_preferences_override_user_preferences() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspace_id = workspace_id_from_client(&c).await;
+
+  let prefs = c.get_notification_preferences().await.unwrap();
+  assert_eq!(prefs, NotificationPreferences::default());
+
+  let prefs = c
+    .update_notification_preferences(&UpdateNotificationPreferencesParams {
+      email_on_comment: Some(false),
+      digest_frequency: Some(DigestFrequency::Weekly),
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+  assert!(prefs.email_on_mention);
+  assert!(!prefs.email_on_comment);
+  assert_eq!(prefs.digest_frequency, DigestFrequency::Weekly);
+
+  // the workspace only overrides the preferences it sets
+  let prefs = c
+    .update_workspace_notification_preferences(
+      &workspace_id,
+      &UpdateNotificationPreferencesParams {
+        email_on_import_completion: Some(false),
+        digest_frequency: Some(DigestFrequency::Daily),
+        ..Default::default()
+      },
+    )
+    .await
+    .unwrap();
+  assert!(!prefs.email_on_comment);
+  assert!(!prefs.email_on_import_completion);
+  assert_eq!(prefs.digest_frequency, DigestFrequency::Daily);
+  assert_eq!(
+    c.get_workspace_notification_preferences(&workspace_id)
+      .await
+      .unwrap(),
+    prefs
+  );
+
+  c.reset_workspace_notification_preferences(&workspace_id)
+    .await
+    .unwrap();
+  assert_eq!(
+    c.get_workspace_notification_preferences(&workspace_id)
+      .await
+      .unwrap(),
+    c.get_notification_preferences().await.unwrap()
+  );
+}
+
+#[tokio::test]
+async fn register_and_unregister_push_device() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let token = Uuid::new_v4().to_string();
+  let params = RegisterPushDeviceParams {
+    token: token.clone(),
+    platform: PushPlatform::Fcm,
+    device_id: Some("device".to_string()),
+  };
+  c.register_push_device(&params).await.unwrap();
+  // registering the same token again only updates it
+  c.register_push_device(&params).await.unwrap();
+  c.unregister_push_device(&token).await.unwrap();
+
+  let err = c
+    .register_push_device(&RegisterPushDeviceParams {
+      token: String::new(),
+      platform: PushPlatform::Apns,
+      device_id: None,
+    })
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+}
@@ -0,0 +1,21 @@
+use client_api::entity::billing_dto::SubscriptionPlan;
+use client_api_test::generate_unique_registered_user_client;
+
+#[tokio::test]
+async fn workspace_billing_on_free_plan_test() {
+  let (client, _) = generate_unique_registered_user_client().await;
+  let workspace_id = client.get_workspaces().await.unwrap()[0].workspace_id;
+  let billing = client.get_workspace_billing(&workspace_id).await.unwrap();
+  assert_eq!(billing.workspace_id, workspace_id);
+  assert_eq!(billing.plan, SubscriptionPlan::Free);
+  assert!(billing.add_ons.is_empty());
+  assert!(billing.renewal_date.is_none());
+  assert_eq!(billing.usage.member_count, 1);
+  assert!(billing.read_only_reason.is_none());
+
+  let (other_client, _) = generate_unique_registered_user_client().await;
+  assert!(other_client
+    .get_workspace_billing(&workspace_id)
+    .await
+    .is_err());
+}
@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+use client_api_test::TestClient;
+use shared_entity::dto::workspace_dto::CloneWorkspaceParams;
+
+#[tokio::test]
+async fn clone_workspace_test() {
+  let client = TestClient::new_user().await;
+  let workspace_id = client.workspace_id().await;
+  let source_folder = client.get_folder(&workspace_id).await;
+  let source_views = source_folder.get_views_belong_to(&workspace_id);
+
+  let mut task = client
+    .api_client
+    .clone_workspace(
+      &workspace_id,
+      CloneWorkspaceParams {
+        workspace_name: Some("Cloned".to_string()),
+        include_members: false,
+      },
+    )
+    .await
+    .unwrap();
+
+  for _ in 0..30 {
+    if task.status != 0 {
+      break;
+    }
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    task = client
+      .api_client
+      .get_workspace_clone_task(&workspace_id, &task.task_id.to_string())
+      .await
+      .unwrap();
+  }
+  assert_eq!(task.status, 1, "clone failed: {:?}", task.error);
+  assert_eq!(task.cloned_collabs, task.total_collabs);
+
+  let target_workspace_id = task.target_workspace_id.to_string();
+  let cloned_folder = client.get_folder(&target_workspace_id).await;
+  let cloned_views = cloned_folder.get_views_belong_to(&target_workspace_id);
+  assert_eq!(cloned_views.len(), source_views.len());
+  for view in cloned_views {
+    assert!(source_views.iter().all(|source| source.id != view.id));
+    assert!(source_views.iter().any(|source| source.name == view.name));
+  }
+}
@@ -1,4 +1,5 @@
 use anyhow::Error;
+use client_api::entity::ImportSource;
 use client_api_test::TestClient;
 use collab_document::importer::define::{BlockType, URL_FIELD};
 use collab_folder::ViewLayout;
@@ -78,6 +79,30 @@ async fn import_blog_post_test() {
     "expected URLs to be empty: {:?}",
     expected_urls
   );
+  // Step 9: The summary of the import is kept with the task
+  let tasks = client.api_client.get_import_list().await.unwrap().tasks;
+  let summary = tasks[0].summary.clone().unwrap();
+  assert!(summary.documents >= 1);
+  assert_eq!(summary.databases, 0);
+  assert!(summary.uploaded_files >= 3);
+  assert!(summary.error.is_none());
+
+  let task = client
+    .api_client
+    .get_import_task(&tasks[0].task_id)
+    .await
+    .unwrap();
+  // completed
+  assert_eq!(task.status, 1);
+  assert_eq!(task.summary.unwrap().documents, summary.documents);
+
+  // the task of another user is not found
+  let other_client = TestClient::new_user().await;
+  assert!(other_client
+    .api_client
+    .get_import_task(&tasks[0].task_id)
+    .await
+    .is_err());
 }
 
 #[tokio::test]
@@ -180,6 +205,96 @@ async fn imported_workspace_do_not_become_latest_visit_workspace_test() {
   );
 }
 
+#[tokio::test]
+async fn cancel_scheduled_import_test() {
+  let client = TestClient::new_user().await;
+  let file_path = PathBuf::from("tests/workspace/asset/blog_post.zip".to_string());
+  let start_after = chrono::Utc::now().timestamp() + 3600;
+  let resp = client
+    .api_client
+    .create_scheduled_import(&file_path, Some(start_after))
+    .await
+    .unwrap();
+  client
+    .api_client
+    .upload_import_file(&file_path, &resp.presigned_url)
+    .await
+    .unwrap();
+
+  let tasks = client.api_client.get_import_list().await.unwrap().tasks;
+  assert_eq!(tasks.len(), 1);
+  // scheduled
+  assert_eq!(tasks[0].status, 5);
+  assert_eq!(tasks[0].start_after, Some(start_after));
+
+  client
+    .api_client
+    .cancel_import(&resp.task_id)
+    .await
+    .unwrap();
+  let tasks = client.api_client.get_import_list().await.unwrap().tasks;
+  // cancelled
+  assert_eq!(tasks[0].status, 4);
+
+  // a cancelled task can't be cancelled again
+  assert!(client
+    .api_client
+    .cancel_import(&resp.task_id)
+    .await
+    .is_err());
+}
+
+#[tokio::test]
+async fn upload_and_import_in_one_request_test() {
+  let client = TestClient::new_user().await;
+  let file_path = PathBuf::from("tests/workspace/asset/blog_post.zip".to_string());
+  let resp = client
+    .api_client
+    .upload_and_import(&file_path, ImportSource::Notion)
+    .await
+    .unwrap();
+  assert_eq!(resp.file_size, std::fs::metadata(&file_path).unwrap().len());
+
+  let tasks = client.api_client.get_import_list().await.unwrap().tasks;
+  assert_eq!(tasks.len(), 1);
+  assert_eq!(tasks[0].task_id, resp.task_id);
+  wait_until_num_import_task_complete(&client, 1).await;
+
+  let workspaces = client.api_client.get_workspaces().await.unwrap();
+  assert!(workspaces
+    .iter()
+    .any(|workspace| workspace.workspace_id.to_string() == resp.workspace_id));
+}
+
+#[tokio::test]
+async fn import_split_export_test() {
+  let client = TestClient::new_user().await;
+  let file_path = PathBuf::from("tests/workspace/asset/blog_post.zip".to_string());
+  let part_path = PathBuf::from("tests/workspace/asset/project&task.zip".to_string());
+  let resp = client
+    .api_client
+    .create_split_import(&file_path, &[part_path.as_path()])
+    .await
+    .unwrap();
+  assert_eq!(resp.part_presigned_urls.len(), 1);
+
+  // the import waits for all the parts
+  client
+    .api_client
+    .upload_import_file(&file_path, &resp.presigned_url)
+    .await
+    .unwrap();
+  client
+    .api_client
+    .upload_import_file(&part_path, &resp.part_presigned_urls[0])
+    .await
+    .unwrap();
+  wait_until_num_import_task_complete(&client, 1).await;
+
+  let workspaces = client.api_client.get_workspaces().await.unwrap();
+  assert_eq!(workspaces.len(), 2);
+}
+
 #[allow(dead_code)]
 async fn upload_file(
   client: &TestClient,
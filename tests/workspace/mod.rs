@@ -1,9 +1,12 @@
 mod access_request;
+mod billing;
+mod clone_test;
 mod default_user_workspace;
 mod edit_workspace;
 mod import_test;
 mod invitation_crud;
 mod member_crud;
+mod organization;
 mod page_view;
 mod publish;
 mod published_data;
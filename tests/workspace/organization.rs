@@ -0,0 +1,134 @@
+use app_error::ErrorCode;
+use client_api::entity::org_dto::{
+  AddOrganizationMembersParams, CreateOrganizationParams, OrgRole, RemoveOrganizationMembersParams,
+  UpdateOrganizationSessionPolicyParams,
+};
+use client_api_test::generate_unique_registered_user_client;
+use database_entity::dto::AFRole;
+
+#[tokio::test]
+async fn organization_members_and_usage_test() {
+  let (admin_client, _) = generate_unique_registered_user_client().await;
+  let (member_client, member) = generate_unique_registered_user_client().await;
+  let workspace_id = admin_client.get_workspaces().await.unwrap()[0].workspace_id;
+
+  let org = admin_client
+    .create_organization(&CreateOrganizationParams {
+      name: "Acme".to_string(),
+      billing_email: None,
+    })
+    .await
+    .unwrap();
+  assert_eq!(org.role, OrgRole::Admin);
+  admin_client
+    .attach_workspace_to_organization(&org.org_id, &workspace_id)
+    .await
+    .unwrap();
+  admin_client
+    .add_organization_members(
+      &org.org_id,
+      &AddOrganizationMembersParams {
+        emails: vec![member.email.clone()],
+        role: OrgRole::Member,
+        add_to_workspaces: true,
+      },
+    )
+    .await
+    .unwrap();
+
+  let members = admin_client
+    .list_organization_members(&org.org_id)
+    .await
+    .unwrap();
+  assert_eq!(members.len(), 2);
+  let added = members.iter().find(|m| m.email == member.email).unwrap();
+  assert_eq!(added.org_role, Some(OrgRole::Member));
+  assert_eq!(added.workspaces.len(), 1);
+  assert_eq!(added.workspaces[0].workspace_id, workspace_id);
+  assert_eq!(added.workspaces[0].role, AFRole::Member);
+
+  let usage = admin_client
+    .get_organization_usage(&org.org_id)
+    .await
+    .unwrap();
+  assert_eq!(usage.workspace_count, 1);
+  assert_eq!(usage.member_count, 2);
+
+  // Only the admins of the organization can see its usage
+  let err = member_client
+    .get_organization_usage(&org.org_id)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::NotEnoughPermissions);
+
+  admin_client
+    .remove_organization_members(
+      &org.org_id,
+      &RemoveOrganizationMembersParams {
+        emails: vec![member.email.clone()],
+      },
+    )
+    .await
+    .unwrap();
+  let orgs = member_client.list_organizations().await.unwrap();
+  assert!(orgs.is_empty());
+}
+
+#[tokio::test]
+async fn organization_session_policy_test() {
+  let (admin_client, _) = generate_unique_registered_user_client().await;
+  let (member_client, member) = generate_unique_registered_user_client().await;
+  let org = admin_client
+    .create_organization(&CreateOrganizationParams {
+      name: "Acme".to_string(),
+      billing_email: None,
+    })
+    .await
+    .unwrap();
+  assert_eq!(org.max_sessions_per_user, None);
+  admin_client
+    .add_organization_members(
+      &org.org_id,
+      &AddOrganizationMembersParams {
+        emails: vec![member.email.clone()],
+        role: OrgRole::Member,
+        add_to_workspaces: false,
+      },
+    )
+    .await
+    .unwrap();
+
+  let org = admin_client
+    .update_organization_session_policy(
+      &org.org_id,
+      &UpdateOrganizationSessionPolicyParams {
+        max_sessions_per_user: Some(3),
+      },
+    )
+    .await
+    .unwrap();
+  assert_eq!(org.max_sessions_per_user, Some(3));
+
+  let err = admin_client
+    .update_organization_session_policy(
+      &org.org_id,
+      &UpdateOrganizationSessionPolicyParams {
+        max_sessions_per_user: Some(0),
+      },
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+
+  // Only the admins of the organization can change its session policy
+  let err = member_client
+    .update_organization_session_policy(
+      &org.org_id,
+      &UpdateOrganizationSessionPolicyParams {
+        max_sessions_per_user: None,
+      },
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::NotEnoughPermissions);
+}
@@ -1,5 +1,8 @@
+use std::io::Cursor;
 use std::{collections::HashSet, time::Duration};
 
+use app_error::ErrorCode;
+
 use client_api::entity::{QueryCollab, QueryCollabParams};
 use client_api_test::{
   generate_unique_registered_user, generate_unique_registered_user_client, TestClient,
@@ -7,6 +10,7 @@ use client_api_test::{
 use collab::core::origin::CollabClient;
 use collab_entity::CollabType;
 use collab_folder::{CollabOrigin, Folder};
+use image::{DynamicImage, ImageFormat, RgbImage};
 use serde_json::{json, Value};
 use shared_entity::dto::workspace_dto::{
   AppendBlockToPageParams, CreatePageDatabaseViewParams, CreatePageParams, CreateSpaceParams,
@@ -41,6 +45,14 @@ async fn get_latest_folder(test_client: &TestClient, workspace_id: &str) -> Fold
   .unwrap()
 }
 
+fn png(width: u32, height: u32) -> Vec<u8> {
+  let mut data = Vec::new();
+  DynamicImage::ImageRgb8(RgbImage::new(width, height))
+    .write_to(&mut Cursor::new(&mut data), ImageFormat::Png)
+    .unwrap();
+  data
+}
+
 #[tokio::test]
 async fn get_page_view() {
   let (c, _user) = generate_unique_registered_user_client().await;
@@ -773,6 +785,57 @@ async fn update_page() {
   assert_eq!(updated_view.is_locked, None);
 }
 
+#[tokio::test]
+async fn upload_page_cover() {
+  let registered_user = generate_unique_registered_user().await;
+  let mut app_client = TestClient::user_with_new_device(registered_user.clone()).await;
+  let web_client = TestClient::user_with_new_device(registered_user.clone()).await;
+  let workspace_id = app_client.workspace_id().await;
+  app_client.open_workspace_collab(&workspace_id).await;
+  app_client
+    .wait_object_sync_complete(&workspace_id)
+    .await
+    .unwrap();
+  let folder_view = web_client
+    .api_client
+    .get_workspace_folder(&workspace_id.to_string(), Some(2), None)
+    .await
+    .unwrap();
+  let view_id = folder_view.children[0].children[0].view_id.clone();
+  let workspace_uuid = Uuid::parse_str(&workspace_id).unwrap();
+
+  let too_small = web_client
+    .api_client
+    .upload_page_cover(workspace_uuid, &view_id, "cover.png", png(32, 32))
+    .await
+    .unwrap_err();
+  assert_eq!(too_small.code, ErrorCode::InvalidRequest);
+
+  let asset = web_client
+    .api_client
+    .upload_page_cover(workspace_uuid, &view_id, "cover.png", png(1200, 400))
+    .await
+    .unwrap();
+  assert_eq!(
+    asset.sizes[0].key,
+    format!("{}/{}/1600.webp", workspace_id, asset.asset_id)
+  );
+  let (_, original) = web_client
+    .api_client
+    .get_blob_v1(&workspace_id, &asset.asset_id.to_string(), "original")
+    .await
+    .unwrap();
+  assert_eq!(original, png(1200, 400));
+
+  let folder = get_latest_folder(&app_client, &workspace_id).await;
+  let extra: Value =
+    serde_json::from_str(&folder.get_view(&view_id).unwrap().extra.unwrap()).unwrap();
+  assert_eq!(
+    extra["cover"],
+    json!({"type": "custom", "value": asset.sizes[0].key})
+  );
+}
+
 #[tokio::test]
 async fn create_space() {
   let registered_user = generate_unique_registered_user().await;
@@ -1034,6 +1097,7 @@ async fn create_database_page_view() {
       &CreatePageDatabaseViewParams {
         layout: ViewLayout::Grid,
         name: Some("Grid View".to_string()),
+        settings: None,
       },
     )
     .await
@@ -2,11 +2,13 @@ use app_error::ErrorCode;
 use appflowy_cloud::biz::collab::folder_view::collab_folder_to_folder_view;
 use appflowy_cloud::biz::collab::utils::collab_from_doc_state;
 use client_api::entity::{
-  AFRole, GlobalComment, PatchPublishedCollab, PublishCollabItem, PublishCollabMetadata,
-  PublishInfoMeta,
+  AFRole, GlobalComment, PatchPublishedCollab, PublishAnalyticsQuery, PublishCollabItem,
+  PublishCollabMetadata, PublishInfoMeta,
 };
 use client_api_test::TestClient;
-use client_api_test::{generate_unique_registered_user_client, localhost_client};
+use client_api_test::{
+  admin_user_client, generate_unique_registered_user_client, localhost_client,
+};
 use collab::util::MapExt;
 use collab_database::database::DatabaseBody;
 use collab_database::entity::FieldType;
@@ -18,7 +20,10 @@ use collab_entity::CollabType;
 use collab_folder::{CollabOrigin, Folder, UserId};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
-use shared_entity::dto::publish_dto::PublishDatabaseData;
+use shared_entity::dto::admin_dto::PublishReportStatus;
+use shared_entity::dto::publish_dto::{
+  PublishDatabaseData, PublishReportReason, ReportPublishedViewParams,
+};
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::thread::sleep;
@@ -1858,3 +1863,260 @@ async fn test_republish_patch() {
   .await
   .unwrap();
 }
+
+#[tokio::test]
+async fn test_republish_invalidates_published_cache() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspace_id = get_first_workspace_string(&c).await;
+  let my_namespace = uuid::Uuid::new_v4().to_string();
+  c.set_workspace_publish_namespace(&workspace_id.to_string(), my_namespace.clone())
+    .await
+    .unwrap();
+
+  let publish_name = "my-publish-name";
+  let view_id = uuid::Uuid::new_v4();
+  let guest_client = localhost_client();
+  for i in 1..=2 {
+    c.publish_collabs::<MyCustomMetadata, &[u8]>(
+      &workspace_id,
+      vec![PublishCollabItem {
+        meta: PublishCollabMetadata {
+          view_id,
+          publish_name: publish_name.to_string(),
+          metadata: MyCustomMetadata {
+            title: format!("my_title_{}", i),
+          },
+        },
+        data: format!("yrs_encoded_data_{}", i).as_bytes(),
+        comments_enabled: true,
+        duplicate_enabled: true,
+      }],
+    )
+    .await
+    .unwrap();
+
+    // The second read is served from the cache
+    for _ in 0..2 {
+      let published_collab = guest_client
+        .get_published_collab::<MyCustomMetadata>(&my_namespace, publish_name)
+        .await
+        .unwrap();
+      assert_eq!(published_collab.title, format!("my_title_{}", i));
+      let blob = guest_client
+        .get_published_collab_blob(&my_namespace, publish_name)
+        .await
+        .unwrap();
+      assert_eq!(blob, format!("yrs_encoded_data_{}", i));
+    }
+  }
+
+  // The pages are no longer reachable with the old namespace
+  let new_namespace = uuid::Uuid::new_v4().to_string();
+  c.set_workspace_publish_namespace(&workspace_id.to_string(), new_namespace.clone())
+    .await
+    .unwrap();
+  let err = guest_client
+    .get_published_collab_blob(&my_namespace, publish_name)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::RecordNotFound, "{:?}", err);
+}
+
+#[tokio::test]
+async fn test_publish_custom_domain() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspace_id = get_first_workspace_string(&c).await;
+  let domain = format!("{}.example.com", uuid::Uuid::new_v4());
+
+  let info = c
+    .add_publish_domain(&workspace_id, &domain.to_uppercase())
+    .await
+    .unwrap();
+  assert_eq!(info.domain, domain);
+  assert!(!info.verified);
+  assert_eq!(
+    info.txt_record_name,
+    format!("_appflowy-challenge.{}", domain)
+  );
+
+  let domains = c.list_publish_domains(&workspace_id).await.unwrap();
+  assert_eq!(domains.len(), 1);
+  assert_eq!(domains[0].txt_record_value, info.txt_record_value);
+
+  // Another workspace can claim the domain until one of them verifies it, with its own token
+  let (c2, _user2) = generate_unique_registered_user_client().await;
+  let workspace_id_2 = get_first_workspace_string(&c2).await;
+  let info_2 = c2
+    .add_publish_domain(&workspace_id_2, &domain)
+    .await
+    .unwrap();
+  assert!(!info_2.verified);
+  assert_ne!(info_2.txt_record_value, info.txt_record_value);
+  assert_eq!(
+    c.list_publish_domains(&workspace_id).await.unwrap().len(),
+    1
+  );
+
+  let err = c
+    .add_publish_domain(&workspace_id, "localhost")
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest, "{:?}", err);
+
+  c.remove_publish_domain(&workspace_id, &domain)
+    .await
+    .unwrap();
+  assert!(c
+    .list_publish_domains(&workspace_id)
+    .await
+    .unwrap()
+    .is_empty());
+}
+
+#[tokio::test]
+async fn test_publish_analytics() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspace_id = get_first_workspace_string(&c).await;
+  let my_namespace = uuid::Uuid::new_v4().to_string();
+  c.set_workspace_publish_namespace(&workspace_id.to_string(), my_namespace.clone())
+    .await
+    .unwrap();
+
+  let publish_name = "my-publish-name";
+  let view_id = uuid::Uuid::new_v4();
+  c.publish_collabs::<MyCustomMetadata, &[u8]>(
+    &workspace_id,
+    vec![PublishCollabItem {
+      meta: PublishCollabMetadata {
+        view_id,
+        publish_name: publish_name.to_string(),
+        metadata: MyCustomMetadata {
+          title: "my_title".to_string(),
+        },
+      },
+      data: "yrs_encoded_data".as_bytes(),
+      comments_enabled: true,
+      duplicate_enabled: true,
+    }],
+  )
+  .await
+  .unwrap();
+
+  let guest_client = localhost_client();
+  for _ in 0..3 {
+    guest_client
+      .get_published_collab::<MyCustomMetadata>(&my_namespace, publish_name)
+      .await
+      .unwrap();
+  }
+
+  // The events are written in the background
+  let query = PublishAnalyticsQuery {
+    view_id: Some(view_id),
+    ..Default::default()
+  };
+  let mut views = 0;
+  for _ in 0..10 {
+    let analytics = c
+      .get_publish_analytics(&workspace_id, &query)
+      .await
+      .unwrap();
+    views = analytics.daily_views.iter().map(|day| day.views).sum();
+    if views == 3 {
+      break;
+    }
+    tokio::time::sleep(Duration::from_secs(2)).await;
+  }
+  assert_eq!(views, 3);
+}
+
+#[tokio::test]
+async fn test_report_and_unpublish_published_view() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspace_id = get_first_workspace_string(&c).await;
+  c.set_workspace_publish_namespace(&workspace_id.to_string(), uuid::Uuid::new_v4().to_string())
+    .await
+    .unwrap();
+
+  let publish_name = "reported-page";
+  let view_id = uuid::Uuid::new_v4();
+  c.publish_collabs::<MyCustomMetadata, &[u8]>(
+    &workspace_id,
+    vec![PublishCollabItem {
+      meta: PublishCollabMetadata {
+        view_id,
+        publish_name: publish_name.to_string(),
+        metadata: MyCustomMetadata {
+          title: "my_title".to_string(),
+        },
+      },
+      data: "yrs_encoded_data".as_bytes(),
+      comments_enabled: true,
+      duplicate_enabled: true,
+    }],
+  )
+  .await
+  .unwrap();
+
+  // Visitors can report without signing in
+  let guest_client = localhost_client();
+  guest_client
+    .report_published_view(
+      &view_id,
+      &ReportPublishedViewParams {
+        reason: PublishReportReason::Phishing,
+        details: Some("asks for passwords".to_string()),
+      },
+    )
+    .await
+    .unwrap();
+
+  let err = c
+    .admin_list_publish_reports(None, None, None)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::NotEnoughPermissions, "{:?}", err);
+
+  let admin_client = admin_user_client().await;
+  let reports = admin_client
+    .admin_list_publish_reports(None, None, Some(1000))
+    .await
+    .unwrap();
+  let report = reports
+    .into_iter()
+    .find(|report| report.view_id == view_id)
+    .unwrap();
+  assert_eq!(report.reason, PublishReportReason::Phishing);
+  assert_eq!(report.status, PublishReportStatus::Open);
+  assert!(report.reporter_uuid.is_none());
+
+  admin_client
+    .admin_unpublish_reported_view(&report.report_id)
+    .await
+    .unwrap();
+  let publish_info = c.get_published_collab_info(&view_id).await.unwrap();
+  assert!(
+    publish_info.unpublished_timestamp.is_some(),
+    "{:?}",
+    publish_info
+  );
+
+  let reports = admin_client
+    .admin_list_publish_reports(Some(PublishReportStatus::Unpublished), None, Some(1000))
+    .await
+    .unwrap();
+  assert!(reports.iter().any(|r| r.report_id == report.report_id));
+
+  // The view is not published anymore
+  let err = guest_client
+    .report_published_view(
+      &view_id,
+      &ReportPublishedViewParams {
+        reason: PublishReportReason::Spam,
+        details: None,
+      },
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::RecordNotFound, "{:?}", err);
+}
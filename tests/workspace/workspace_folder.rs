@@ -1,4 +1,10 @@
-use client_api_test::generate_unique_registered_user_client;
+use app_error::ErrorCode;
+use client_api::entity::workspace_dto::{
+  FolderView, InviteCollabGuestParams, RemoveCollabGuestParams, RestrictViewParams,
+};
+use client_api_test::{generate_unique_registered_user_client, TestClient};
+use database_entity::dto::{AFAccessLevel, AFRole};
+use uuid::Uuid;
 
 #[tokio::test]
 async fn get_workpace_folder() {
@@ -31,3 +37,168 @@ async fn get_workpace_folder() {
     .unwrap();
   assert_eq!(folder_view.children.len(), 2);
 }
+
+#[tokio::test]
+async fn rebuild_workspace_folder_keeps_existing_views() {
+  let (c, _user) = generate_unique_registered_user_client().await;
+  let workspaces = c.get_workspaces().await.unwrap();
+  let workspace_id = workspaces[0].workspace_id.to_string();
+  let before = c
+    .get_workspace_folder(&workspace_id, Some(2), None)
+    .await
+    .unwrap();
+
+  let rebuilt = c.rebuild_workspace_folder(&workspace_id).await.unwrap();
+  assert!(rebuilt.backup_checkpoint_id.is_some());
+
+  let after = c
+    .get_workspace_folder(&workspace_id, Some(2), None)
+    .await
+    .unwrap();
+  assert_eq!(after.children[0].view_id, before.children[0].view_id);
+  assert_eq!(after.children[0].name, "General");
+  let child_ids = |children: &[FolderView]| {
+    children
+      .iter()
+      .map(|child| child.view_id.clone())
+      .collect::<Vec<_>>()
+  };
+  assert_eq!(
+    child_ids(&after.children[0].children),
+    child_ids(&before.children[0].children)
+  );
+}
+
+#[tokio::test]
+async fn restricted_view_is_hidden_from_non_members() {
+  let owner = TestClient::new_user_without_ws_conn().await;
+  let member = TestClient::new_user_without_ws_conn().await;
+  let workspace_id = owner.workspace_id().await;
+  owner
+    .invite_and_accepted_workspace_member(&workspace_id, &member, AFRole::Member)
+    .await
+    .unwrap();
+  let folder_view = owner
+    .api_client
+    .get_workspace_folder(&workspace_id, Some(1), None)
+    .await
+    .unwrap();
+  let space_id = folder_view.children[0].view_id.clone();
+
+  let workspace_uuid = Uuid::parse_str(&workspace_id).unwrap();
+  let restricted = owner
+    .api_client
+    .restrict_view(
+      workspace_uuid,
+      &space_id,
+      &RestrictViewParams {
+        member_uids: vec![],
+      },
+    )
+    .await
+    .unwrap();
+  // the owner always keeps access to the views it restricts
+  assert_eq!(restricted.member_uids, vec![owner.uid().await]);
+
+  let folder_view = member
+    .api_client
+    .get_workspace_folder(&workspace_id, Some(1), None)
+    .await
+    .unwrap();
+  assert!(folder_view
+    .children
+    .iter()
+    .all(|child| child.view_id != space_id));
+  let err = member
+    .api_client
+    .get_workspace_folder(&workspace_id, Some(1), Some(space_id.clone()))
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::NotEnoughPermissions);
+
+  owner
+    .api_client
+    .unrestrict_view(workspace_uuid, &space_id)
+    .await
+    .unwrap();
+  let folder_view = member
+    .api_client
+    .get_workspace_folder(&workspace_id, Some(1), None)
+    .await
+    .unwrap();
+  assert_eq!(folder_view.children[0].view_id, space_id);
+}
+
+#[tokio::test]
+async fn collab_guest_only_sees_the_shared_page() {
+  let owner = TestClient::new_user_without_ws_conn().await;
+  let guest = TestClient::new_user_without_ws_conn().await;
+  let workspace_id = owner.workspace_id().await;
+  let workspace_uuid = Uuid::parse_str(&workspace_id).unwrap();
+  let folder_view = owner
+    .api_client
+    .get_workspace_folder(&workspace_id, Some(2), None)
+    .await
+    .unwrap();
+  let page = folder_view.children[0].children[0].clone();
+
+  let err = guest
+    .api_client
+    .get_workspace_folder(&workspace_id, Some(1), None)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::NotEnoughPermissions);
+
+  owner
+    .api_client
+    .invite_collab_guest(
+      workspace_uuid,
+      &page.view_id,
+      &InviteCollabGuestParams {
+        email: guest.email().await,
+        access_level: AFAccessLevel::ReadOnly,
+        skip_email_send: true,
+      },
+    )
+    .await
+    .unwrap();
+  let guests = owner
+    .api_client
+    .list_collab_guests(workspace_uuid, &page.view_id)
+    .await
+    .unwrap();
+  assert_eq!(guests.len(), 1);
+  assert_eq!(guests[0].uid, Some(guest.uid().await));
+
+  let workspaces = guest.api_client.get_workspaces().await.unwrap();
+  let shared_workspace = workspaces
+    .iter()
+    .find(|workspace| workspace.workspace_id == workspace_uuid)
+    .unwrap();
+  assert!(shared_workspace.is_collab_guest);
+  let folder_view = guest
+    .api_client
+    .get_workspace_folder(&workspace_id, Some(1), None)
+    .await
+    .unwrap();
+  assert_eq!(folder_view.children.len(), 1);
+  assert_eq!(folder_view.children[0].view_id, page.view_id);
+
+  owner
+    .api_client
+    .remove_collab_guest(
+      workspace_uuid,
+      &page.view_id,
+      &RemoveCollabGuestParams {
+        email: guest.email().await,
+      },
+    )
+    .await
+    .unwrap();
+  let err = guest
+    .api_client
+    .get_workspace_folder(&workspace_id, Some(1), None)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::NotEnoughPermissions);
+}
@@ -1,7 +1,14 @@
+use app_error::ErrorCode;
 use client_api::Client;
 use client_api_test::generate_unique_registered_user_client;
-use database_entity::dto::{AFRole, AFWorkspaceInvitationStatus, AFWorkspaceSettingsChange};
-use shared_entity::dto::workspace_dto::WorkspaceMemberInvitation;
+use database_entity::dto::{
+  AFRole, AFWorkspaceAIFeaturesChange, AFWorkspaceInvitationStatus, AFWorkspaceSettingsChange,
+};
+use shared_entity::dto::chat_dto::CreateChatParams;
+use shared_entity::dto::workspace_dto::{
+  RetentionDryRunQuery, UpdateWorkspaceHistoryCompactionParams,
+  UpdateWorkspaceRetentionPolicyParams, WorkspaceMemberInvitation,
+};
 use uuid::Uuid;
 
 #[tokio::test]
@@ -55,6 +62,164 @@ async fn get_and_set_workspace_by_non_owner() {
     .unwrap();
 }
 
+#[tokio::test]
+async fn disable_ai_chat_by_owner() {
+  let (alice_client, _alice) = generate_unique_registered_user_client().await;
+  let workspaces = alice_client.get_workspaces().await.unwrap();
+  let alice_workspace_id = workspaces.first().unwrap().workspace_id;
+  let (bob_client, bob) = generate_unique_registered_user_client().await;
+  invite_user_to_workspace(&alice_workspace_id, &alice_client, &bob_client, &bob.email).await;
+
+  let change = AFWorkspaceSettingsChange::new().ai_features(AFWorkspaceAIFeaturesChange {
+    ai_chat: Some(false),
+    ..Default::default()
+  });
+  let err = bob_client
+    .update_workspace_settings(&alice_workspace_id.to_string(), &change)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::NotEnoughPermissions);
+
+  let settings = alice_client
+    .update_workspace_settings(&alice_workspace_id.to_string(), &change)
+    .await
+    .unwrap();
+  assert!(!settings.ai_features.ai_chat);
+  assert!(settings.ai_features.ai_summarize_row);
+
+  let err = bob_client
+    .create_chat(
+      &alice_workspace_id.to_string(),
+      CreateChatParams {
+        chat_id: Uuid::new_v4().to_string(),
+        name: "my chat".to_string(),
+        rag_ids: vec![],
+      },
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::FeatureDisabled);
+}
+
+#[tokio::test]
+async fn set_workspace_retention_policy() {
+  let (alice_client, _alice) = generate_unique_registered_user_client().await;
+  let workspaces = alice_client.get_workspaces().await.unwrap();
+  let workspace_id = workspaces.first().unwrap().workspace_id;
+  let (bob_client, bob) = generate_unique_registered_user_client().await;
+  invite_user_to_workspace(&workspace_id, &alice_client, &bob_client, &bob.email).await;
+
+  let policy = alice_client
+    .get_workspace_retention_policy(workspace_id.to_string())
+    .await
+    .unwrap();
+  assert!(policy.chat_history_days.is_none());
+
+  let params = UpdateWorkspaceRetentionPolicyParams {
+    chat_history_days: Some(90),
+    snapshot_days: None,
+  };
+  let err = bob_client
+    .update_workspace_retention_policy(workspace_id.to_string(), &params)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::NotEnoughPermissions);
+  let policy = alice_client
+    .update_workspace_retention_policy(workspace_id.to_string(), &params)
+    .await
+    .unwrap();
+  assert_eq!(policy.chat_history_days, Some(90));
+
+  let report = alice_client
+    .dry_run_workspace_retention_policy(
+      workspace_id.to_string(),
+      &RetentionDryRunQuery {
+        chat_history_days: None,
+        snapshot_days: Some(1),
+      },
+    )
+    .await
+    .unwrap();
+  assert_eq!(report.chat_history_days, Some(90));
+  assert_eq!(report.snapshot_days, Some(1));
+  assert_eq!(report.chat_messages, 0);
+
+  let err = alice_client
+    .update_workspace_retention_policy(
+      workspace_id.to_string(),
+      &UpdateWorkspaceRetentionPolicyParams {
+        chat_history_days: Some(0),
+        snapshot_days: None,
+      },
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+}
+
+#[tokio::test]
+async fn set_workspace_history_compaction() {
+  let (alice_client, _alice) = generate_unique_registered_user_client().await;
+  let workspaces = alice_client.get_workspaces().await.unwrap();
+  let workspace_id = workspaces.first().unwrap().workspace_id;
+  let (bob_client, bob) = generate_unique_registered_user_client().await;
+  invite_user_to_workspace(&workspace_id, &alice_client, &bob_client, &bob.email).await;
+
+  let setting = alice_client
+    .get_workspace_history_compaction(workspace_id.to_string())
+    .await
+    .unwrap();
+  assert!(setting.enabled);
+  assert_eq!(setting.hourly_days, 7);
+  assert_eq!(setting.daily_days, 30);
+
+  let params = UpdateWorkspaceHistoryCompactionParams {
+    enabled: None,
+    hourly_days: Some(3),
+    daily_days: None,
+  };
+  let err = bob_client
+    .update_workspace_history_compaction(workspace_id.to_string(), &params)
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::NotEnoughPermissions);
+  let setting = alice_client
+    .update_workspace_history_compaction(workspace_id.to_string(), &params)
+    .await
+    .unwrap();
+  assert_eq!(setting.hourly_days, 3);
+  assert_eq!(setting.daily_days, 30);
+
+  let err = alice_client
+    .update_workspace_history_compaction(
+      workspace_id.to_string(),
+      &UpdateWorkspaceHistoryCompactionParams {
+        enabled: None,
+        hourly_days: Some(60),
+        daily_days: None,
+      },
+    )
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::InvalidRequest);
+
+  let err = bob_client
+    .compact_workspace_history(workspace_id.to_string())
+    .await
+    .unwrap_err();
+  assert_eq!(err.code, ErrorCode::NotEnoughPermissions);
+  let report = alice_client
+    .compact_workspace_history(workspace_id.to_string())
+    .await
+    .unwrap();
+  assert_eq!(report.deleted_versions, 0);
+  let setting = alice_client
+    .get_workspace_history_compaction(workspace_id.to_string())
+    .await
+    .unwrap();
+  assert!(setting.last_compacted_at.is_some());
+}
+
 async fn invite_user_to_workspace(
   workspace_id: &Uuid,
   owner: &Client,